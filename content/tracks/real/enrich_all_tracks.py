@@ -473,7 +473,8 @@ def process_track(track_file, output_dir):
     for i, node in enumerate(nodes):
         node['z'] = float(round(elevations[i], 3))
         if 'banking' not in node or node.get('banking') == 0:
-            node['banking'] = float(round(banking_values[i], 1))
+            # Corners are configured in degrees; track files store radians
+            node['banking'] = float(round(math.radians(banking_values[i]), 4))
     
     if 'raceline' in track_data and track_data['raceline']:
         print(f"   Updating raceline with {len(track_data['raceline'])} points...")
@@ -253,6 +253,75 @@ public partial class CarModelCache : Node
         }
     }
 
+    /// <summary>
+    /// Paint a loaded model's untextured panels in one of its car's liveries: the
+    /// skin texture when the livery has one, otherwise the primary team color,
+    /// plus a number plate.
+    /// </summary>
+    public void ApplyLivery(Node3D model, string carUuid, Livery livery)
+    {
+        Texture2D? skin = null;
+        if (!string.IsNullOrEmpty(livery.Texture) && _modelInfo.TryGetValue(carUuid, out var info))
+        {
+            var texturePath = System.IO.Path.Combine(System.IO.Path.GetDirectoryName(info.ModelPath) ?? "", livery.Texture);
+            var image = Image.LoadFromFile(texturePath);
+            if (image != null)
+            {
+                skin = ImageTexture.CreateFromImage(image);
+            }
+            else
+            {
+                GD.PrintErr($"  Livery texture not found: {texturePath}");
+            }
+        }
+
+        PaintMeshes(model, skin, Color.FromHtml(livery.PrimaryColor));
+
+        model.GetNodeOrNull("NumberPlate")?.Free();
+        if (livery.Number is ushort number)
+        {
+            model.AddChild(new Label3D
+            {
+                Name = "NumberPlate",
+                Text = number.ToString(),
+                Modulate = Color.FromHtml(livery.SecondaryColor),
+                Billboard = BaseMaterial3D.BillboardModeEnum.Enabled,
+                Position = new Vector3(0, 1.5f, 0),
+                PixelSize = 0.01f
+            });
+        }
+    }
+
+    private static void PaintMeshes(Node node, Texture2D? skin, Color primary)
+    {
+        if (node is MeshInstance3D meshInstance && meshInstance.Mesh != null)
+        {
+            for (int i = 0; i < meshInstance.Mesh.GetSurfaceCount(); i++)
+            {
+                if (meshInstance.Mesh.SurfaceGetMaterial(i) is not StandardMaterial3D original) continue;
+                // Only the plain-colored panels are painted, not textured parts like tires
+                if (original.AlbedoTexture != null) continue;
+
+                // Paint a copy so other instances of the model keep their own skin
+                var paint = (StandardMaterial3D)original.Duplicate();
+                if (skin != null)
+                {
+                    paint.AlbedoTexture = skin;
+                }
+                else
+                {
+                    paint.AlbedoColor = primary;
+                }
+                meshInstance.SetSurfaceOverrideMaterial(i, paint);
+            }
+        }
+
+        foreach (Node child in node.GetChildren())
+        {
+            PaintMeshes(child, skin, primary);
+        }
+    }
+
     /// <summary>
     /// Check if a model is registered
     /// </summary>
@@ -9,7 +9,7 @@ namespace ApexSim;
 public partial class CarSelectionAltDialog : Control
 {
     [Signal]
-    public delegate void CarSelectedEventHandler(string carId, string carName);
+    public delegate void CarSelectedEventHandler(string carId, string carName, int livery);
 
     private ItemList? _carList;
     private Label? _statusLabel; // reuse CarName as status
@@ -25,6 +25,7 @@ public partial class CarSelectionAltDialog : Control
     private Control? _gearGraphControl;
     private Button? _selectButton;
     private Button? _cancelButton;
+    private OptionButton? _liveryPicker;
 
     private List<CarConfigSummary> _allCars = new();
     private Dictionary<string, string> _tomlById = new(); // car id -> toml path
@@ -78,6 +79,14 @@ public partial class CarSelectionAltDialog : Control
         if (_selectButton != null) _selectButton.Pressed += OnSelectPressed;
         if (_cancelButton != null) _cancelButton.Pressed += OnCancelPressed;
 
+        // Filled with the shown car's liveries, hidden when it has only one
+        if (_selectButton != null)
+        {
+            _liveryPicker = new OptionButton { Name = "LiveryPicker", Visible = false };
+            _selectButton.GetParent().AddChild(_liveryPicker);
+            _selectButton.GetParent().MoveChild(_liveryPicker, _selectButton.GetIndex());
+        }
+
         // Try to load cars (and respect server lobby if present)
         RequestCarData();
     }
@@ -268,6 +277,10 @@ public partial class CarSelectionAltDialog : Control
                     var server = network.LastLobbyState.CarConfigs.FirstOrDefault(c => c.Id == l.Id);
                     if (server != null)
                     {
+                        if (!ContentCheck.Matches(server.ContentHash, l.ContentHash))
+                        {
+                            ContentCheck.WarnMismatch("car", server.Name);
+                        }
                         available.Add(new CarConfigSummary
                         {
                             Id = server.Id,
@@ -385,7 +398,8 @@ public partial class CarSelectionAltDialog : Control
             Name = name,
             ModelPath = modelPath,
             MassKg = massKg,
-            MaxEngineForceN = maxEngineForce
+            MaxEngineForceN = maxEngineForce,
+            ContentHash = ContentCheck.ForCar(tomlPath, model ?? "") ?? ""
         };
 
         return summary;
@@ -412,6 +426,16 @@ public partial class CarSelectionAltDialog : Control
         var car = _allCars[index];
         _carNameLabel!.Text = car.Name;
 
+        if (_liveryPicker != null)
+        {
+            _liveryPicker.Clear();
+            foreach (var livery in car.Liveries)
+            {
+                _liveryPicker.AddItem(livery.Number is ushort number ? $"{livery.Name} #{number}" : livery.Name);
+            }
+            _liveryPicker.Visible = car.Liveries.Length > 1;
+        }
+
         // Load toml for details
         if (_tomlById.TryGetValue(car.Id, out var tomlPath))
         {
@@ -617,7 +641,8 @@ public partial class CarSelectionAltDialog : Control
         if (idx >= 0 && idx < _allCars.Count)
         {
             var car = _allCars[idx];
-            EmitSignal(SignalName.CarSelected, car.Id, car.Name);
+            var livery = _liveryPicker != null && _liveryPicker.Selected > 0 ? _liveryPicker.Selected : 0;
+            EmitSignal(SignalName.CarSelected, car.Id, car.Name, livery);
             QueueFree();
         }
     }
@@ -8,13 +8,14 @@ namespace ApexSim;
 public partial class CarSelectionDialog : Control
 {
     [Signal]
-    public delegate void CarSelectedEventHandler(string carId, string carName);
+    public delegate void CarSelectedEventHandler(string carId, string carName, int livery);
 
     private GridContainer? _gridContainer;
     private LineEdit? _searchInput;
     private Label? _statusLabel;
     private Button? _selectButton;
     private Button? _cancelButton;
+    private OptionButton? _liveryPicker;
     private NetworkClient? _network;
 
     private List<CarConfigSummary> _allCars = new();
@@ -35,8 +36,14 @@ public partial class CarSelectionDialog : Control
         _cancelButton.Pressed += OnCancelPressed;
         _searchInput.TextChanged += OnSearchTextChanged;
 
+        // Shown once a car with more than one livery is picked
+        _liveryPicker = new OptionButton { Name = "LiveryPicker", Visible = false };
+        _selectButton.GetParent().AddChild(_liveryPicker);
+        _selectButton.GetParent().MoveChild(_liveryPicker, _selectButton.GetIndex());
+
         _network = GetNode<NetworkClient>("/root/Network");
         _network.LobbyStateReceived += OnLobbyStateReceived;
+        _network.ContentDownloaded += OnContentDownloaded;
 
         // Load the car card scene
         _carCardScene = GD.Load<PackedScene>("res://scenes/car_card.tscn");
@@ -104,6 +111,7 @@ public partial class CarSelectionDialog : Control
 
         // Filter local cars to only those available on the server
         var availableCars = new List<CarConfigSummary>();
+        var mismatchedCars = 0;
         foreach (var localCar in localCars)
         {
             var localId = localCar.Id.ToString();
@@ -116,6 +124,13 @@ public partial class CarSelectionDialog : Control
                 {
                     if (serverCar.Id.ToString() == localCar.Id.ToString())
                     {
+                        if (!ContentCheck.Matches(serverCar.ContentHash, localCar.ContentHash))
+                        {
+                            ContentCheck.WarnMismatch("car", serverCar.Name);
+                            mismatchedCars++;
+                            _ = _network!.DownloadContentAsync(ContentKind.Car, serverCar.Id);
+                        }
+
                         // Use server's authoritative ID and stats, with local model path
                         availableCars.Add(new CarConfigSummary
                         {
@@ -131,10 +146,27 @@ public partial class CarSelectionDialog : Control
             }
         }
 
+        // Fetch the server's cars that are not in the local content folder
+        var localCarIds = new HashSet<string>(localCars.Select(c => c.Id.ToString()));
+        var missingCars = 0;
+        foreach (var serverCar in lobbyState.CarConfigs)
+        {
+            if (localCarIds.Contains(serverCar.Id.ToString())) continue;
+            missingCars++;
+            _ = _network!.DownloadContentAsync(ContentKind.Car, serverCar.Id);
+        }
+
         // Sort by name
         availableCars.Sort((a, b) => string.Compare(a.Name, b.Name, System.StringComparison.Ordinal));
         _allCars.AddRange(availableCars);
 
+        if (_allCars.Count == 0 && missingCars > 0)
+        {
+            _statusLabel!.Text = $"Downloading {missingCars} car(s) from the server...";
+            _statusLabel.Modulate = Colors.Orange;
+            return;
+        }
+
         if (_allCars.Count == 0)
         {
             _statusLabel!.Text = "No cars available! Make sure cars exist in both local content/cars folder and on server.";
@@ -142,8 +174,16 @@ public partial class CarSelectionDialog : Control
             return;
         }
 
-        _statusLabel!.Text = $"{_allCars.Count} car(s) available";
-        _statusLabel.Modulate = Colors.Green;
+        if (mismatchedCars + missingCars > 0)
+        {
+            _statusLabel!.Text = $"{_allCars.Count} car(s) available, downloading {mismatchedCars + missingCars} missing or changed car(s) from the server";
+            _statusLabel.Modulate = Colors.Orange;
+        }
+        else
+        {
+            _statusLabel!.Text = $"{_allCars.Count} car(s) available";
+            _statusLabel.Modulate = Colors.Green;
+        }
 
         // Create car cards
         CreateCarCards();
@@ -152,6 +192,16 @@ public partial class CarSelectionDialog : Control
         _carsPopulated = true;
     }
 
+    private void OnContentDownloaded(int kind, string id)
+    {
+        if (!IsInsideTree() || (ContentKind)kind != ContentKind.Car) return;
+        if (_network!.LastLobbyState == null) return;
+
+        // Pick up the car that has been fetched
+        _carsPopulated = false;
+        PopulateCars(_network.LastLobbyState);
+    }
+
     private List<CarConfigSummary> LoadLocalCars()
     {
         var cars = new List<CarConfigSummary>();
@@ -287,7 +337,8 @@ public partial class CarSelectionDialog : Control
             Name = name,
             ModelPath = modelPath,
             MassKg = massKg,
-            MaxEngineForceN = maxEngineForce
+            MaxEngineForceN = maxEngineForce,
+            ContentHash = ContentCheck.ForCar(tomlPath, model!) ?? ""
         };
     }
 
@@ -344,8 +395,28 @@ public partial class CarSelectionDialog : Control
         _selectedCard = clickedCard;
         _selectedCard.IsSelected = true;
 
+        // With a choice of liveries, wait for the player to pick one and press Select
+        var liveries = _selectedCard.CarConfig?.Liveries ?? Array.Empty<Livery>();
+        if (liveries.Length > 1)
+        {
+            _liveryPicker!.Clear();
+            foreach (var livery in liveries)
+            {
+                _liveryPicker.AddItem(livery.Number is ushort number ? $"{livery.Name} #{number}" : livery.Name);
+            }
+            _liveryPicker.Selected = 0;
+            _liveryPicker.Visible = true;
+            _statusLabel!.Text = $"Pick a livery for {_selectedCard.CarConfig!.Name}";
+            return;
+        }
+
         // Emit signal and close immediately
-        EmitSignal(SignalName.CarSelected, _selectedCard?.CarConfig?.Id.ToString() ?? string.Empty, _selectedCard?.CarConfig?.Name ?? string.Empty);
+        EmitSelection(0);
+    }
+
+    private void EmitSelection(int livery)
+    {
+        EmitSignal(SignalName.CarSelected, _selectedCard?.CarConfig?.Id.ToString() ?? string.Empty, _selectedCard?.CarConfig?.Name ?? string.Empty, livery);
         QueueFree();
     }
 
@@ -356,10 +427,10 @@ public partial class CarSelectionDialog : Control
 
     private void OnSelectPressed()
     {
-        // This is now handled in OnCardClicked, but keep this for safety
+        // Cars with several liveries are confirmed here, the rest in OnCardClicked
         if (_selectedCard?.CarConfig != null)
         {
-            QueueFree();
+            EmitSelection(_liveryPicker!.Visible ? _liveryPicker.Selected : 0);
         }
     }
 
@@ -373,6 +444,7 @@ public partial class CarSelectionDialog : Control
         if (_network != null)
         {
             _network.LobbyStateReceived -= OnLobbyStateReceived;
+            _network.ContentDownloaded -= OnContentDownloaded;
         }
 
         // Clean up card event handlers
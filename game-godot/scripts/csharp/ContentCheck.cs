@@ -0,0 +1,51 @@
+using Godot;
+using System;
+using System.IO;
+using System.Security.Cryptography;
+
+namespace ApexSim;
+
+// Hashes local content the same way the server does, so cars and tracks that
+// differ from the server's copy can be flagged instead of rendered wrongly.
+public static class ContentCheck
+{
+    // SHA-256 of a car's car.toml followed by its model file, or null when car.toml is missing
+    public static string? ForCar(string carTomlPath, string modelFile)
+    {
+        if (!File.Exists(carTomlPath))
+        {
+            return null;
+        }
+
+        using var sha = IncrementalHash.CreateHash(HashAlgorithmName.SHA256);
+        sha.AppendData(File.ReadAllBytes(carTomlPath));
+        var modelPath = Path.Combine(Path.GetDirectoryName(carTomlPath) ?? "", modelFile);
+        if (!string.IsNullOrEmpty(modelFile) && File.Exists(modelPath))
+        {
+            sha.AppendData(File.ReadAllBytes(modelPath));
+        }
+        return Convert.ToHexString(sha.GetHashAndReset()).ToLowerInvariant();
+    }
+
+    // SHA-256 of a track file given relative to the content folder, or null when it is missing
+    public static string? ForTrack(string sourcePath)
+    {
+        var path = Path.Combine(ClientConfig.Instance.ContentDirectory, sourcePath);
+        if (!File.Exists(path))
+        {
+            return null;
+        }
+        return Convert.ToHexString(SHA256.HashData(File.ReadAllBytes(path))).ToLowerInvariant();
+    }
+
+    // Whether a local copy matches the server's; servers that send no hash are trusted
+    public static bool Matches(string serverHash, string? localHash)
+    {
+        return string.IsNullOrEmpty(serverHash) || string.Equals(serverHash, localHash, StringComparison.OrdinalIgnoreCase);
+    }
+
+    public static void WarnMismatch(string kind, string name)
+    {
+        GD.PushWarning($"Local {kind} '{name}' is missing or differs from the server's version; it may not render as the server simulates it. Update your content folder.");
+    }
+}
@@ -0,0 +1,167 @@
+using Godot;
+using System;
+using System.Collections.Generic;
+using System.IO;
+using System.Linq;
+using System.Security.Cryptography;
+using System.Threading.Tasks;
+
+namespace ApexSim;
+
+// Fetches a car's or track's files from the server into the content folder.
+// Files already there with the server's hash are kept. A file being downloaded
+// is written to "<file>.part" and only replaces the real file once its hash
+// checks out, so a broken-off download resumes from the bytes it already has.
+public class ContentDownloader
+{
+    private class PendingPack
+    {
+        public ContentKind Kind;
+        public string Id = "";
+        public HashSet<string> Remaining = new();
+    }
+
+    private readonly Func<ClientMessage, Task> _send;
+    private readonly List<PendingPack> _packs = new();
+    private readonly Dictionary<string, ContentFileInfo> _files = new();
+
+    public event Action<ContentKind, string>? Completed;
+    public event Action<ContentKind, string, string>? Failed;
+
+    public ContentDownloader(Func<ClientMessage, Task> send)
+    {
+        _send = send;
+    }
+
+    public bool IsDownloading(ContentKind kind, string id)
+    {
+        return _packs.Any(p => p.Kind == kind && p.Id == id);
+    }
+
+    public async Task RequestAsync(ContentKind kind, string id)
+    {
+        if (IsDownloading(kind, id)) return;
+        _packs.Add(new PendingPack { Kind = kind, Id = id });
+        await _send(new RequestContentMessage { Kind = kind, Id = id });
+    }
+
+    public async Task OnManifestAsync(ContentManifestMessage manifest)
+    {
+        var pack = _packs.FirstOrDefault(p => p.Kind == manifest.Kind && p.Id == manifest.Id);
+        if (pack == null) return;
+
+        var tooLarge = manifest.Files.FirstOrDefault(f => f.TooLarge);
+        if (tooLarge != null)
+        {
+            Fail(pack, $"{tooLarge.Path} is too large for the server to send");
+            return;
+        }
+
+        // Never write outside the content folder
+        var outside = manifest.Files.FirstOrDefault(f => Path.IsPathRooted(f.Path) || f.Path.Split('/', '\\').Contains(".."));
+        if (outside != null)
+        {
+            Fail(pack, $"{outside.Path} is outside the content folder");
+            return;
+        }
+
+        var requests = new List<RequestContentFileMessage>();
+        foreach (var file in manifest.Files)
+        {
+            var localPath = LocalPath(file.Path);
+            if (File.Exists(localPath) && HashFile(localPath) == file.Sha256) continue;
+
+            var partPath = localPath + ".part";
+            ulong offset = File.Exists(partPath) ? (ulong)new FileInfo(partPath).Length : 0;
+            if (offset > file.SizeBytes)
+            {
+                File.Delete(partPath);
+                offset = 0;
+            }
+            pack.Remaining.Add(file.Path);
+            _files[file.Path] = file;
+            requests.Add(new RequestContentFileMessage { Path = file.Path, Offset = offset });
+        }
+
+        if (pack.Remaining.Count == 0)
+        {
+            Finish(pack);
+            return;
+        }
+        GD.Print($"Downloading {pack.Remaining.Count} file(s) of {manifest.Kind} {manifest.Id}");
+        foreach (var request in requests)
+        {
+            await _send(request);
+        }
+    }
+
+    public void OnChunk(ContentChunkMessage chunk)
+    {
+        if (!_files.TryGetValue(chunk.Path, out var file)) return;
+        var pack = _packs.FirstOrDefault(p => p.Remaining.Contains(chunk.Path));
+        if (pack == null) return;
+
+        var localPath = LocalPath(chunk.Path);
+        var partPath = localPath + ".part";
+        try
+        {
+            Directory.CreateDirectory(Path.GetDirectoryName(partPath) ?? ".");
+            using (var stream = new FileStream(partPath, FileMode.OpenOrCreate, FileAccess.Write))
+            {
+                stream.Seek((long)chunk.Offset, SeekOrigin.Begin);
+                stream.Write(chunk.Data, 0, chunk.Data.Length);
+            }
+
+            if (chunk.Offset + (ulong)chunk.Data.Length < chunk.TotalBytes) return;
+
+            _files.Remove(chunk.Path);
+            if (HashFile(partPath) != file.Sha256)
+            {
+                File.Delete(partPath);
+                Fail(pack, $"{chunk.Path} did not match the server's hash");
+                return;
+            }
+            File.Move(partPath, localPath, true);
+        }
+        catch (Exception ex)
+        {
+            Fail(pack, $"Failed to write {chunk.Path}: {ex.Message}");
+            return;
+        }
+
+        pack.Remaining.Remove(chunk.Path);
+        if (pack.Remaining.Count == 0)
+        {
+            Finish(pack);
+        }
+    }
+
+    private void Finish(PendingPack pack)
+    {
+        _packs.Remove(pack);
+        GD.Print($"Content of {pack.Kind} {pack.Id} is up to date");
+        Completed?.Invoke(pack.Kind, pack.Id);
+    }
+
+    private void Fail(PendingPack pack, string reason)
+    {
+        _packs.Remove(pack);
+        foreach (var path in pack.Remaining)
+        {
+            _files.Remove(path);
+        }
+        GD.PrintErr($"Download of {pack.Kind} {pack.Id} failed: {reason}");
+        Failed?.Invoke(pack.Kind, pack.Id, reason);
+    }
+
+    private static string LocalPath(string contentPath)
+    {
+        return Path.Combine(ClientConfig.Instance.ContentDirectory, contentPath);
+    }
+
+    private static string HashFile(string path)
+    {
+        using var stream = File.OpenRead(path);
+        return Convert.ToHexString(SHA256.HashData(stream)).ToLowerInvariant();
+    }
+}
@@ -44,6 +44,15 @@ public partial class NetworkClient : Node
     [Signal]
     public delegate void TelemetryReceivedEventHandler();
 
+    [Signal]
+    public delegate void WeatherUpdatedEventHandler();
+
+    [Signal]
+    public delegate void ContentDownloadedEventHandler(int kind, string id);
+
+    [Signal]
+    public delegate void ContentDownloadFailedEventHandler(int kind, string id, string reason);
+
     private TcpClient? _tcpClient;
     private NetworkStream? _stream;
     private bool _isConnected = false;
@@ -57,6 +66,9 @@ public partial class NetworkClient : Node
     private const double HeartbeatInterval = 2.0; // Send heartbeat every 2 seconds
     private uint _clientTick = 0;
 
+    // Car and track files fetched from the server
+    private ContentDownloader? _contentDownloads;
+
     // Store latest lobby state for retrieval
     public LobbyStateMessage? LastLobbyState { get; private set; }
 
@@ -66,10 +78,21 @@ public partial class NetworkClient : Node
     // Store latest telemetry
     public TelemetryMessage? LastTelemetry { get; private set; }
 
+    // Store latest weather of the current session
+    public WeatherUpdateMessage? LastWeather { get; private set; }
+
+    // Setup the server last accepted for our car
+    public CarSetup? CurrentSetup { get; private set; }
+
+    // Driver aids the server last accepted for our car
+    public DriverAids? CurrentAids { get; private set; }
+
     public string ServerAddress { get; set; } = "127.0.0.1";
     public int ServerPort { get; set; } = 9000;
     public string PlayerName { get; set; } = "Player";
     public string AuthToken { get; set; } = "dev-token";
+    // Telemetry frames per second asked for at login; 0 for every server tick
+    public ushort TelemetryRateHz { get; set; }
     public new bool IsConnected => _isConnected;
     public string PlayerId => _playerId;
 
@@ -122,7 +145,8 @@ public partial class NetworkClient : Node
         await SendMessageAsync(new AuthenticateMessage
         {
             Token = token,
-            PlayerName = name
+            PlayerName = name,
+            TelemetryRateHz = TelemetryRateHz
         });
     }
 
@@ -131,12 +155,12 @@ public partial class NetworkClient : Node
         await SendMessageAsync(new RequestLobbyStateMessage());
     }
 
-    public async Task SelectCarAsync(string carId)
+    public async Task SelectCarAsync(string carId, byte livery = 0)
     {
-        await SendMessageAsync(new SelectCarMessage { CarConfigId = carId });
+        await SendMessageAsync(new SelectCarMessage { CarConfigId = carId, Livery = livery });
     }
 
-    public async Task CreateSessionAsync(string trackId, byte maxPlayers, byte aiCount, byte lapLimit, SessionKind sessionKind = SessionKind.Multiplayer)
+    public async Task CreateSessionAsync(string trackId, byte maxPlayers, byte aiCount, byte lapLimit, SessionKind sessionKind = SessionKind.Multiplayer, WeatherCondition weather = WeatherCondition.Dry, WeatherChange[]? forecast = null, SetupPolicy setupPolicy = SetupPolicy.Open, DriverAids? aidLimits = null, CollisionPolicy collisionPolicy = CollisionPolicy.Solid, TimeOfDay timeOfDay = TimeOfDay.Noon)
     {
         await SendMessageAsync(new CreateSessionMessage
         {
@@ -144,7 +168,13 @@ public partial class NetworkClient : Node
             MaxPlayers = maxPlayers,
             AiCount = aiCount,
             LapLimit = lapLimit,
-            SessionKind = sessionKind
+            SessionKind = sessionKind,
+            Weather = weather,
+            Forecast = forecast ?? Array.Empty<WeatherChange>(),
+            SetupPolicy = setupPolicy,
+            CollisionPolicy = collisionPolicy,
+            TimeOfDay = timeOfDay,
+            AidLimits = aidLimits
         });
     }
 
@@ -163,6 +193,22 @@ public partial class NetworkClient : Node
         await SendMessageAsync(new StartSessionMessage());
     }
 
+    public async Task ApplySetupAsync(CarSetup setup)
+    {
+        await SendMessageAsync(new ApplySetupMessage { Setup = setup });
+    }
+
+    public async Task SetDriverAidsAsync(DriverAids aids)
+    {
+        await SendMessageAsync(new SetDriverAidsMessage { Aids = aids });
+    }
+
+    public async Task SetTelemetryRateAsync(ushort rateHz)
+    {
+        TelemetryRateHz = rateHz;
+        await SendMessageAsync(new SetTelemetryRateMessage { RateHz = rateHz });
+    }
+
     public async Task SetGameModeAsync(GameMode mode)
     {
         await SendMessageAsync(new SetGameModeMessage { Mode = mode });
@@ -177,6 +223,28 @@ public partial class NetworkClient : Node
         });
     }
 
+    // Fetch the files of a car or track that is missing or differs locally
+    public async Task DownloadContentAsync(ContentKind kind, string id)
+    {
+        await ContentDownloads.RequestAsync(kind, id);
+    }
+
+    public bool IsDownloadingContent(ContentKind kind, string id) => ContentDownloads.IsDownloading(kind, id);
+
+    private ContentDownloader ContentDownloads
+    {
+        get
+        {
+            if (_contentDownloads == null)
+            {
+                _contentDownloads = new ContentDownloader(SendMessageAsync);
+                _contentDownloads.Completed += (kind, id) => EmitSignal(SignalName.ContentDownloaded, (int)kind, id);
+                _contentDownloads.Failed += (kind, id, reason) => EmitSignal(SignalName.ContentDownloadFailed, (int)kind, id, reason);
+            }
+            return _contentDownloads;
+        }
+    }
+
     public async Task SendHeartbeatAsync(uint clientTick)
     {
         await SendMessageAsync(new HeartbeatMessage { ClientTick = clientTick });
@@ -327,6 +395,14 @@ public partial class NetworkClient : Node
                 EmitSignal(SignalName.LobbyStateReceived);
                 break;
 
+            case LobbyUpdateMessage lobbyUpdate:
+                if (LastLobbyState != null)
+                {
+                    ApplyLobbyUpdate(LastLobbyState, lobbyUpdate);
+                    EmitSignal(SignalName.LobbyStateReceived);
+                }
+                break;
+
             case SessionJoinedMessage sessionJoined:
                 CurrentSessionId = sessionJoined.SessionId;
                 EmitSignal(SignalName.SessionJoined, sessionJoined.SessionId, sessionJoined.YourGridPosition);
@@ -334,6 +410,7 @@ public partial class NetworkClient : Node
 
             case SessionLeftMessage:
                 CurrentSessionId = null;
+                LastWeather = null;
                 EmitSignal(SignalName.SessionLeft);
                 break;
 
@@ -358,6 +435,27 @@ public partial class NetworkClient : Node
                 EmitSignal(SignalName.TelemetryReceived);
                 break;
 
+            case WeatherUpdateMessage weather:
+                LastWeather = weather;
+                EmitSignal(SignalName.WeatherUpdated);
+                break;
+
+            case SetupAppliedMessage setupApplied:
+                CurrentSetup = setupApplied.Setup;
+                break;
+
+            case DriverAidsAppliedMessage aidsApplied:
+                CurrentAids = aidsApplied.Aids;
+                break;
+
+            case ContentManifestMessage manifest:
+                _ = ContentDownloads.OnManifestAsync(manifest);
+                break;
+
+            case ContentChunkMessage chunk:
+                ContentDownloads.OnChunk(chunk);
+                break;
+
             default:
                 // Silently ignore unhandled messages
                 break;
@@ -382,7 +480,8 @@ public partial class NetworkClient : Node
                 payload = new Dictionary<string, object?>
                 {
                     ["token"] = auth.Token,
-                    ["player_name"] = auth.PlayerName
+                    ["player_name"] = auth.PlayerName,
+                    ["telemetry_rate_hz"] = auth.TelemetryRateHz
                 };
                 break;
             case HeartbeatMessage hb:
@@ -391,7 +490,11 @@ public partial class NetworkClient : Node
                 break;
             case SelectCarMessage selectCar:
                 type = "SelectCar";
-                payload = new Dictionary<string, object?> { ["car_config_id"] = selectCar.CarConfigId };
+                payload = new Dictionary<string, object?>
+                {
+                    ["car_config_id"] = selectCar.CarConfigId,
+                    ["livery"] = selectCar.Livery
+                };
                 break;
             case RequestLobbyStateMessage:
                 type = "RequestLobbyState";
@@ -404,7 +507,22 @@ public partial class NetworkClient : Node
                     ["max_players"] = createSession.MaxPlayers,
                     ["ai_count"] = createSession.AiCount,
                     ["lap_limit"] = createSession.LapLimit,
-                    ["session_kind"] = (byte)createSession.SessionKind
+                    ["session_kind"] = (byte)createSession.SessionKind,
+                    ["weather"] = new Dictionary<string, object?>
+                    {
+                        ["condition"] = (byte)createSession.Weather,
+                        ["forecast"] = createSession.Forecast
+                            .Select(c => new Dictionary<string, object?>
+                            {
+                                ["after_s"] = c.AfterS,
+                                ["condition"] = (byte)c.Condition
+                            })
+                            .ToArray()
+                    },
+                    ["setup_policy"] = (byte)createSession.SetupPolicy,
+                    ["collision_policy"] = (byte)createSession.CollisionPolicy,
+                    ["time_of_day"] = (byte)createSession.TimeOfDay,
+                    ["aid_limits"] = createSession.AidLimits == null ? null : AidsPayload(createSession.AidLimits)
                 };
                 break;
             case JoinSessionMessage join:
@@ -417,6 +535,30 @@ public partial class NetworkClient : Node
             case StartSessionMessage:
                 type = "StartSession";
                 break;
+            case SetDriverAidsMessage setAids:
+                type = "SetDriverAids";
+                payload = new Dictionary<string, object?> { ["aids"] = AidsPayload(setAids.Aids) };
+                break;
+            case ApplySetupMessage applySetup:
+                type = "ApplySetup";
+                payload = new Dictionary<string, object?>
+                {
+                    ["setup"] = new Dictionary<string, object?>
+                    {
+                        ["final_drive_ratio"] = applySetup.Setup.FinalDriveRatio,
+                        ["gear_ratios"] = applySetup.Setup.GearRatios,
+                        ["lift_coefficient_front"] = applySetup.Setup.LiftCoefficientFront,
+                        ["lift_coefficient_rear"] = applySetup.Setup.LiftCoefficientRear,
+                        ["tire_pressure_front_kpa"] = applySetup.Setup.TirePressureFrontKpa,
+                        ["tire_pressure_rear_kpa"] = applySetup.Setup.TirePressureRearKpa,
+                        ["brake_bias_front"] = applySetup.Setup.BrakeBiasFront
+                    }
+                };
+                break;
+            case SetTelemetryRateMessage setTelemetryRate:
+                type = "SetTelemetryRate";
+                payload = new Dictionary<string, object?> { ["rate_hz"] = setTelemetryRate.RateHz };
+                break;
             case SetGameModeMessage setGameMode:
                 type = "SetGameMode";
                 payload = new Dictionary<string, object?> { ["mode"] = (byte)setGameMode.Mode };
@@ -432,6 +574,22 @@ public partial class NetworkClient : Node
             case DisconnectMessage:
                 type = "Disconnect";
                 break;
+            case RequestContentMessage requestContent:
+                type = "RequestContent";
+                payload = new Dictionary<string, object?>
+                {
+                    ["kind"] = (byte)requestContent.Kind,
+                    ["id"] = requestContent.Id
+                };
+                break;
+            case RequestContentFileMessage requestFile:
+                type = "RequestContentFile";
+                payload = new Dictionary<string, object?>
+                {
+                    ["path"] = requestFile.Path,
+                    ["offset"] = requestFile.Offset
+                };
+                break;
             default:
                 throw new Exception($"Unsupported client message type: {message.GetType().Name}");
         }
@@ -465,6 +623,7 @@ public partial class NetworkClient : Node
                 "AuthFailure" => BuildAuthFailure(dataObj),
                 "HeartbeatAck" => BuildHeartbeatAck(dataObj),
                 "LobbyState" => BuildLobbyState(dataObj),
+                "LobbyUpdate" => BuildLobbyUpdate(dataObj),
                 "SessionJoined" => BuildSessionJoined(dataObj),
                 "SessionLeft" => new SessionLeftMessage(),
                 "SessionStarting" => BuildSessionStarting(dataObj),
@@ -472,6 +631,11 @@ public partial class NetworkClient : Node
                 "Error" => BuildError(dataObj),
                 "PlayerDisconnected" => BuildPlayerDisconnected(dataObj),
                 "Telemetry" => BuildTelemetry(dataObj),
+                "WeatherUpdate" => BuildWeatherUpdate(dataObj),
+                "SetupApplied" => new SetupAppliedMessage { Setup = BuildCarSetup(ToStringMap(dataObj)["setup"]) },
+                "DriverAidsApplied" => new DriverAidsAppliedMessage { Aids = BuildDriverAids(ToStringMap(dataObj)["aids"]) },
+                "ContentManifest" => BuildContentManifest(dataObj),
+                "ContentChunk" => BuildContentChunk(dataObj),
                 _ => throw new Exception($"Unknown server message type: {messageType}")
             };
         }
@@ -538,6 +702,70 @@ public partial class NetworkClient : Node
         };
     }
 
+    private static LobbyUpdateMessage BuildLobbyUpdate(object? data)
+    {
+        var map = ToStringMap(data);
+        return new LobbyUpdateMessage
+        {
+            Kind = (LobbyUpdateKind)ReadUInt(map, "Kind"),
+            Player = map.TryGetValue("Player", out var playerObj) && playerObj != null ? BuildLobbyPlayer(playerObj) : null,
+            Session = map.TryGetValue("Session", out var sessionObj) && sessionObj != null ? BuildSessionSummary(sessionObj) : null,
+            PlayerId = ReadOptionalUuid(map, "PlayerId"),
+            SessionId = ReadOptionalUuid(map, "SessionId")
+        };
+    }
+
+    // Apply an incremental lobby change to the last full lobby state
+    private static void ApplyLobbyUpdate(LobbyStateMessage state, LobbyUpdateMessage update)
+    {
+        switch (update.Kind)
+        {
+            case LobbyUpdateKind.PlayerJoined:
+            case LobbyUpdateKind.PlayerUpdated:
+                if (update.Player is { } player)
+                {
+                    state.PlayersInLobby = state.PlayersInLobby
+                        .Where(p => p.Id != player.Id)
+                        .Append(player)
+                        .ToArray();
+                }
+                break;
+
+            case LobbyUpdateKind.PlayerLeft:
+                state.PlayersInLobby = state.PlayersInLobby.Where(p => p.Id != update.PlayerId).ToArray();
+                break;
+
+            case LobbyUpdateKind.SessionCreated:
+            case LobbyUpdateKind.SessionUpdated:
+                if (update.Session is { } session)
+                {
+                    state.AvailableSessions = state.AvailableSessions
+                        .Where(s => s.Id != session.Id)
+                        .Append(session)
+                        .ToArray();
+                }
+                break;
+
+            case LobbyUpdateKind.SessionRemoved:
+                state.AvailableSessions = state.AvailableSessions.Where(s => s.Id != update.SessionId).ToArray();
+                break;
+        }
+    }
+
+    private static WeatherUpdateMessage BuildWeatherUpdate(object? data)
+    {
+        var map = ToStringMap(data);
+        var hasNext = map.TryGetValue("NextCondition", out var nextObj) && nextObj != null;
+        return new WeatherUpdateMessage
+        {
+            SessionId = ReadUuid(map, "SessionId"),
+            Condition = (WeatherCondition)ReadUInt(map, "Condition"),
+            TrackWetness = ReadFloat(map, "TrackWetness"),
+            NextCondition = hasNext ? (WeatherCondition)ReadUInt(map, "NextCondition") : null,
+            NextChangeInS = hasNext ? (uint)ReadUInt(map, "NextChangeInS") : null
+        };
+    }
+
     private static SessionJoinedMessage BuildSessionJoined(object? data)
     {
         var map = ToStringMap(data);
@@ -601,7 +829,24 @@ public partial class NetworkClient : Node
             CountdownMs = map.TryGetValue("countdown_ms", out var countdownObj) && countdownObj != null
                 ? (ushort)ReadUInt(map, "countdown_ms")
                 : null,
-            CarStates = carStates
+            CarStates = carStates,
+            RaceProgress = map.TryGetValue("race_progress", out var progressObj) && progressObj != null
+                ? BuildRaceProgress(progressObj)
+                : null
+        };
+    }
+
+    private static RaceProgress BuildRaceProgress(object? obj)
+    {
+        var map = ToStringMap(obj);
+        return new RaceProgress
+        {
+            LeaderLap = (ushort)ReadUInt(map, "LeaderLap"),
+            TotalLaps = (ushort)ReadUInt(map, "TotalLaps"),
+            CompletedFraction = ReadFloat(map, "CompletedFraction"),
+            EstimatedRemainingS = map.TryGetValue("EstimatedRemainingS", out var etaObj) && etaObj != null
+                ? (uint)ReadUInt(map, "EstimatedRemainingS")
+                : null
         };
     }
 
@@ -617,6 +862,7 @@ public partial class NetworkClient : Node
             YawRad = ReadFloat(map, "yaw_rad"),
             PitchRad = ReadFloat(map, "pitch_rad"),
             RollRad = ReadFloat(map, "roll_rad"),
+            Orientation = map.TryGetValue("orientation", out var orientationObj) && orientationObj != null ? BuildOrientation(orientationObj) : new CarOrientation(),
             SpeedMps = ReadFloat(map, "speed_mps"),
             Throttle = ReadFloat(map, "throttle"),
             Brake = ReadFloat(map, "brake"),
@@ -628,10 +874,59 @@ public partial class NetworkClient : Node
                 ? (byte)ReadUInt(map, "finish_position")
                 : null,
             IsOnTrack = map.TryGetValue("is_on_track", out var onTrackObj) && onTrackObj is bool onTrack && onTrack,
-            IsColliding = map.TryGetValue("is_colliding", out var collidingObj) && collidingObj is bool colliding && colliding
+            IsColliding = map.TryGetValue("is_colliding", out var collidingObj) && collidingObj is bool colliding && colliding,
+            IsGhost = map.TryGetValue("is_ghost", out var ghostObj) && ghostObj is bool ghost && ghost,
+            Aids = map.TryGetValue("aids", out var aidsObj) && aidsObj != null ? BuildDriverAids(aidsObj) : new DriverAids(),
+            AidsActive = map.TryGetValue("aids_active", out var activeObj) && activeObj != null ? BuildActiveAids(activeObj) : new ActiveAids(),
+            EngineStalled = map.TryGetValue("engine_stalled", out var stalledObj) && stalledObj is bool stalled && stalled,
+            IsAirborne = map.TryGetValue("is_airborne", out var airborneObj) && airborneObj is bool airborne && airborne,
+            IsOverturned = map.TryGetValue("is_overturned", out var overturnedObj) && overturnedObj is bool overturned && overturned,
+            Livery = map.ContainsKey("livery") ? (byte)ReadUInt(map, "livery") : (byte)0
         };
     }
 
+    private static DriverAids BuildDriverAids(object? obj)
+    {
+        var map = ToStringMap(obj);
+        return new DriverAids
+        {
+            Abs = (AidLevel)ReadUInt(map, "abs"),
+            TractionControl = (AidLevel)ReadUInt(map, "traction_control"),
+            StabilityControl = (AidLevel)ReadUInt(map, "stability_control")
+        };
+    }
+
+    private static CarOrientation BuildOrientation(object? obj)
+    {
+        var map = ToStringMap(obj);
+        return new CarOrientation
+        {
+            W = ReadFloat(map, "w"),
+            X = ReadFloat(map, "x"),
+            Y = ReadFloat(map, "y"),
+            Z = ReadFloat(map, "z")
+        };
+    }
+
+    private static ActiveAids BuildActiveAids(object? obj)
+    {
+        var map = ToStringMap(obj);
+        bool Flag(string key) => map.TryGetValue(key, out var flag) && flag is bool on && on;
+        return new ActiveAids
+        {
+            Abs = Flag("abs"),
+            TractionControl = Flag("traction_control"),
+            StabilityControl = Flag("stability_control")
+        };
+    }
+
+    private static Dictionary<string, object?> AidsPayload(DriverAids aids) => new()
+    {
+        ["abs"] = (byte)aids.Abs,
+        ["traction_control"] = (byte)aids.TractionControl,
+        ["stability_control"] = (byte)aids.StabilityControl
+    };
+
     private static LobbyPlayer BuildLobbyPlayer(object? obj)
     {
         var map = ToStringMap(obj);
@@ -641,6 +936,7 @@ public partial class NetworkClient : Node
             Id = ReadUuid(map, "Id"),
             Name = ReadString(map, "Name"),
             SelectedCar = ReadOptionalUuid(map, "SelectedCar"),
+            SelectedLivery = map.ContainsKey("SelectedLivery") ? (byte)ReadUInt(map, "SelectedLivery") : (byte)0,
             InSession = ReadOptionalUuid(map, "InSession")
         };
     }
@@ -656,7 +952,16 @@ public partial class NetworkClient : Node
             HostName = ReadString(map, "HostName"),
             PlayerCount = (byte)ReadUInt(map, "PlayerCount"),
             MaxPlayers = (byte)ReadUInt(map, "MaxPlayers"),
-            State = (SessionState)ReadUInt(map, "State")
+            State = (SessionState)ReadUInt(map, "State"),
+            RaceProgress = map.TryGetValue("RaceProgress", out var progressObj) && progressObj != null
+                ? BuildRaceProgress(progressObj)
+                : null,
+            SetupPolicy = map.ContainsKey("SetupPolicy") ? (SetupPolicy)ReadUInt(map, "SetupPolicy") : SetupPolicy.Open,
+            CollisionPolicy = map.ContainsKey("CollisionPolicy") ? (CollisionPolicy)ReadUInt(map, "CollisionPolicy") : CollisionPolicy.Solid,
+            TimeOfDay = map.ContainsKey("TimeOfDay") ? (TimeOfDay)ReadUInt(map, "TimeOfDay") : TimeOfDay.Noon,
+            AidLimits = map.TryGetValue("AidLimits", out var limitsObj) && limitsObj != null
+                ? BuildDriverAids(limitsObj)
+                : DriverAids.Unrestricted()
         };
     }
 
@@ -669,7 +974,99 @@ public partial class NetworkClient : Node
             Name = ReadString(map, "Name"),
             ModelPath = ReadString(map, "ModelPath"),
             MassKg = ReadFloat(map, "MassKg"),
-            MaxEngineForceN = ReadFloat(map, "MaxEngineForceN")
+            MaxEngineForceN = ReadFloat(map, "MaxEngineForceN"),
+            PeakTorqueNm = ReadFloat(map, "PeakTorqueNm"),
+            PeakPowerW = ReadFloat(map, "PeakPowerW"),
+            SetupRanges = map.TryGetValue("SetupRanges", out var rangesObj) && rangesObj != null
+                ? BuildSetupRanges(rangesObj)
+                : new SetupRanges(),
+            DefaultSetup = map.TryGetValue("DefaultSetup", out var setupObj) && setupObj != null
+                ? BuildCarSetup(setupObj)
+                : new CarSetup(),
+            ContentHash = ReadOptionalString(map, "ContentHash") ?? "",
+            Liveries = map.TryGetValue("Liveries", out var liveriesObj) && liveriesObj != null
+                ? ToList(liveriesObj).Select(BuildLivery).ToArray()
+                : Array.Empty<Livery>()
+        };
+    }
+
+    private static Livery BuildLivery(object? obj)
+    {
+        var map = ToStringMap(obj);
+        return new Livery
+        {
+            Name = ReadString(map, "name"),
+            Texture = ReadOptionalString(map, "texture"),
+            PrimaryColor = ReadString(map, "primary_color"),
+            SecondaryColor = ReadString(map, "secondary_color"),
+            Number = map.TryGetValue("number", out var numberObj) && numberObj != null ? (ushort)ReadUInt(map, "number") : null
+        };
+    }
+
+    private static CarSetup BuildCarSetup(object? obj)
+    {
+        var map = ToStringMap(obj);
+        return new CarSetup
+        {
+            FinalDriveRatio = ReadFloat(map, "final_drive_ratio"),
+            GearRatios = ToList(map["gear_ratios"]).Select(r => Convert.ToSingle(r)).ToArray(),
+            LiftCoefficientFront = ReadFloat(map, "lift_coefficient_front"),
+            LiftCoefficientRear = ReadFloat(map, "lift_coefficient_rear"),
+            TirePressureFrontKpa = ReadFloat(map, "tire_pressure_front_kpa"),
+            TirePressureRearKpa = ReadFloat(map, "tire_pressure_rear_kpa"),
+            BrakeBiasFront = ReadFloat(map, "brake_bias_front")
+        };
+    }
+
+    private static SetupRanges BuildSetupRanges(object? obj)
+    {
+        var map = ToStringMap(obj);
+        SetupRange Range(string key)
+        {
+            var range = ToStringMap(map[key]);
+            return new SetupRange { Min = ReadFloat(range, "min"), Max = ReadFloat(range, "max") };
+        }
+        return new SetupRanges
+        {
+            FinalDriveRatio = Range("final_drive_ratio"),
+            GearRatio = Range("gear_ratio"),
+            LiftCoefficientFront = Range("lift_coefficient_front"),
+            LiftCoefficientRear = Range("lift_coefficient_rear"),
+            TirePressureKpa = Range("tire_pressure_kpa"),
+            BrakeBiasFront = Range("brake_bias_front")
+        };
+    }
+
+    private static ContentManifestMessage BuildContentManifest(object? data)
+    {
+        var map = ToStringMap(data);
+        return new ContentManifestMessage
+        {
+            Kind = (ContentKind)ReadUInt(map, "Kind"),
+            Id = ReadUuid(map, "Id"),
+            Files = ToList(map["Files"]).Select(f =>
+            {
+                var file = ToStringMap(f);
+                return new ContentFileInfo
+                {
+                    Path = ReadString(file, "Path"),
+                    SizeBytes = ReadUInt(file, "SizeBytes"),
+                    Sha256 = ReadString(file, "Sha256"),
+                    TooLarge = file.TryGetValue("TooLarge", out var tooLargeObj) && tooLargeObj is bool tooLarge && tooLarge
+                };
+            }).ToArray()
+        };
+    }
+
+    private static ContentChunkMessage BuildContentChunk(object? data)
+    {
+        var map = ToStringMap(data);
+        return new ContentChunkMessage
+        {
+            Path = ReadString(map, "Path"),
+            Offset = ReadUInt(map, "Offset"),
+            TotalBytes = ReadUInt(map, "TotalBytes"),
+            Data = map.TryGetValue("Data", out var bytes) && bytes is byte[] chunk ? chunk : Array.Empty<byte>()
         };
     }
 
@@ -679,7 +1076,9 @@ public partial class NetworkClient : Node
         return new TrackConfigSummary
         {
             Id = ReadUuid(map, "Id"),
-            Name = ReadString(map, "Name")
+            Name = ReadString(map, "Name"),
+            SourcePath = ReadOptionalString(map, "SourcePath"),
+            ContentHash = ReadOptionalString(map, "ContentHash") ?? ""
         };
     }
 
@@ -737,6 +1136,15 @@ public partial class NetworkClient : Node
         return value.ToString() ?? string.Empty;
     }
 
+    private static string? ReadOptionalString(Dictionary<string, object?> map, string key)
+    {
+        if (!map.TryGetValue(key, out var value) || value == null)
+        {
+            return null;
+        }
+        return value.ToString();
+    }
+
     private static string ReadUuid(Dictionary<string, object?> map, string key)
     {
         if (!map.TryGetValue(key, out var value) || value == null)
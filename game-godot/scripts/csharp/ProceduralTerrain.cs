@@ -11,9 +11,14 @@ namespace ApexSim;
 public partial class ProceduralTerrain : Node3D
 {
 	private ProceduralWorldData? _worldData;
-	private MeshInstance3D? _terrainMesh;
+	private readonly List<MeshInstance3D> _terrainMeshes = new();
 	private const float SCALE_FACTOR = 50.0f; // Server units to Godot units
 
+	// Splatmap layer colors; grass takes the biome's ground color
+	private static readonly Color DirtColor = new(0.42f, 0.33f, 0.24f);
+	private static readonly Color RockColor = new(0.45f, 0.43f, 0.4f);
+	private static readonly Color SandColor = new(0.82f, 0.74f, 0.54f);
+
 	/// <summary>
 	/// Generate terrain mesh from procedural world data.
 	/// </summary>
@@ -31,38 +36,280 @@ public partial class ProceduralTerrain : Node3D
 		GD.Print($"Generating terrain mesh: {heightmap.Width}x{heightmap.Height} cells, " +
 				 $"cell size: {heightmap.CellSizeM}m, environment: {worldData.EnvironmentType}");
 
-		// Create mesh instance
-		_terrainMesh = new MeshInstance3D();
-		AddChild(_terrainMesh);
-
-		// Generate the terrain mesh
-		var mesh = GenerateTerrainMesh(heightmap, worldData.Preset);
-		_terrainMesh.Mesh = mesh;
-		_terrainMesh.CastShadow = GeometryInstance3D.ShadowCastingSetting.Off;
+		if (worldData.Lod is { tile_cells: > 0 } lod)
+		{
+			SpawnTerrainTiles(heightmap, lod, worldData.Preset, worldData.Splatmap);
+		}
+		else
+		{
+			// Caches from before LOD levels: one mesh of the whole grid
+			AddTerrainMesh(GenerateTerrainMesh(heightmap, worldData.Preset, worldData.Splatmap,
+				1, 0, 0, heightmap.Width - 1, heightmap.Height - 1, Vector3.Zero), Vector3.Zero, 0.0f, 0.0f);
+		}
 
 		GD.Print($"Terrain mesh generated successfully with {heightmap.Heights.Count} height values");
+
+		SpawnWater(worldData.Water);
+		SpawnObjects(worldData.Objects);
+	}
+
+	/// <summary>
+	/// Mesh every terrain tile at every level of detail. Each level takes
+	/// over from the finer one where that one ends, at twice its distance;
+	/// the coarsest reaches the horizon. Tiles sit at their centers, which
+	/// Godot measures their visibility range from.
+	/// </summary>
+	private void SpawnTerrainTiles(TerrainHeightmap heightmap, TerrainLod lod, EnvironmentPreset preset, Splatmap? splatmap)
+	{
+		float tileSize = lod.tile_cells * heightmap.CellSizeM * SCALE_FACTOR;
+		int columns = Math.Max(1, (heightmap.Width - 2) / lod.tile_cells + 1);
+		int rows = Math.Max(1, (heightmap.Height - 2) / lod.tile_cells + 1);
+		int coarsest = lod.levels.Count;
+
+		for (int level = 0; level <= coarsest; level++)
+		{
+			var grid = lod.Level(heightmap, level);
+			int cells = lod.tile_cells >> level;
+			float begin = level == 0 ? 0.0f : 2.0f * tileSize * (1 << (level - 1));
+			float end = level == coarsest ? 0.0f : 2.0f * tileSize * (1 << level);
+
+			for (int ty = 0; ty < rows; ty++)
+			{
+				for (int tx = 0; tx < columns; tx++)
+				{
+					int x0 = tx * cells;
+					int y0 = ty * cells;
+					int x1 = Math.Min(x0 + cells, grid.Width - 1);
+					int y1 = Math.Min(y0 + cells, grid.Height - 1);
+					if (x1 <= x0 || y1 <= y0)
+					{
+						continue;
+					}
+					var center = ServerToGodot(
+						grid.OriginX + (x0 + x1) * 0.5f * grid.CellSizeM,
+						grid.OriginY + (y0 + y1) * 0.5f * grid.CellSizeM,
+						0.0f);
+					var mesh = GenerateTerrainMesh(grid, preset, splatmap, 1 << level, x0, y0, x1, y1, center);
+					AddTerrainMesh(mesh, center, begin, end);
+				}
+			}
+		}
+		GD.Print($"Terrain split into {columns}x{rows} tiles at {coarsest + 1} levels of detail");
+	}
+
+	private void AddTerrainMesh(ArrayMesh mesh, Vector3 position, float visibleFrom, float visibleTo)
+	{
+		var instance = new MeshInstance3D
+		{
+			Mesh = mesh,
+			Position = position,
+			CastShadow = GeometryInstance3D.ShadowCastingSetting.Off,
+			VisibilityRangeBegin = visibleFrom,
+			VisibilityRangeEnd = visibleTo,
+		};
+		AddChild(instance);
+		_terrainMeshes.Add(instance);
+	}
+
+	/// <summary>
+	/// Draw lakes as flat quads at their level and rivers as ribbons along
+	/// their course; the terrain hides whatever reaches under the banks.
+	/// </summary>
+	private void SpawnWater(WaterFeatures water)
+	{
+		if (water.lakes.Count == 0 && water.rivers.Count == 0)
+		{
+			return;
+		}
+
+		var surfaceTool = new SurfaceTool();
+		surfaceTool.Begin(Mesh.PrimitiveType.Triangles);
+		surfaceTool.SetNormal(Vector3.Up);
+
+		foreach (var lake in water.lakes)
+		{
+			foreach (var rect in lake.rects)
+			{
+				AddWaterQuad(surfaceTool,
+					ServerToGodot(rect[0], rect[1], lake.level),
+					ServerToGodot(rect[2], rect[1], lake.level),
+					ServerToGodot(rect[0], rect[3], lake.level),
+					ServerToGodot(rect[2], rect[3], lake.level));
+			}
+		}
+
+		foreach (var river in water.rivers)
+		{
+			var points = river.points;
+			for (int i = 0; i + 1 < points.Count; i++)
+			{
+				var (left0, right0) = RiverBanks(points, i);
+				var (left1, right1) = RiverBanks(points, i + 1);
+				AddWaterQuad(surfaceTool, right0, right1, left0, left1);
+			}
+		}
+
+		var mesh = surfaceTool.Commit();
+		mesh.SurfaceSetMaterial(0, new StandardMaterial3D
+		{
+			AlbedoColor = new Color(0.15f, 0.3f, 0.4f),
+			Roughness = 0.1f,
+			Metallic = 0.3f,
+			CullMode = BaseMaterial3D.CullModeEnum.Disabled,
+		});
+		AddChild(new MeshInstance3D { Mesh = mesh, Name = "Water", CastShadow = GeometryInstance3D.ShadowCastingSetting.Off });
+
+		GD.Print($"Drew {water.lakes.Count} lakes and {water.rivers.Count} rivers");
+	}
+
+	/// <summary>
+	/// Left and right edges of a river's surface at one of its points, in Godot space.
+	/// </summary>
+	private (Vector3, Vector3) RiverBanks(List<RiverPoint> points, int i)
+	{
+		var before = points[Math.Max(i - 1, 0)];
+		var after = points[Math.Min(i + 1, points.Count - 1)];
+		var direction = new Vector2(after.x - before.x, after.y - before.y).Normalized();
+		// Left of the direction of flow, in server space
+		var left = new Vector2(-direction.Y, direction.X) * points[i].width / 2.0f;
+		var point = points[i];
+		return (
+			ServerToGodot(point.x + left.X, point.y + left.Y, point.level),
+			ServerToGodot(point.x - left.X, point.y - left.Y, point.level));
+	}
+
+	private static void AddWaterQuad(SurfaceTool surfaceTool, Vector3 a, Vector3 b, Vector3 c, Vector3 d)
+	{
+		surfaceTool.AddVertex(a);
+		surfaceTool.AddVertex(b);
+		surfaceTool.AddVertex(c);
+		surfaceTool.AddVertex(b);
+		surfaceTool.AddVertex(d);
+		surfaceTool.AddVertex(c);
+	}
+
+	/// <summary>
+	/// Light the track's own lamps, for sessions at dusk or at night.
+	/// </summary>
+	public void SpawnLights(List<LightSource> lights)
+	{
+		foreach (var light in lights)
+		{
+			AddChild(new OmniLight3D
+			{
+				Name = $"Light_{light.kind}",
+				Position = ServerToGodot(light.x, light.y, light.z),
+				LightColor = new Color(light.color[0], light.color[1], light.color[2]),
+				LightEnergy = light.energy,
+				OmniRange = light.range_m * SCALE_FACTOR,
+				ShadowEnabled = false,
+			});
+		}
+		GD.Print($"Lit {lights.Count} track lights");
+	}
+
+	/// <summary>
+	/// Draw the trackside objects, one multimesh per kind.
+	/// Stand-in primitives until models exist for each kind.
+	/// </summary>
+	private void SpawnObjects(List<TracksideObject> objects)
+	{
+		var byKind = new Dictionary<string, List<TracksideObject>>();
+		foreach (var obj in objects)
+		{
+			if (!byKind.TryGetValue(obj.kind, out var list))
+			{
+				list = new List<TracksideObject>();
+				byKind[obj.kind] = list;
+			}
+			list.Add(obj);
+		}
+
+		foreach (var (kind, list) in byKind)
+		{
+			var (mesh, color, height) = ObjectPrimitive(kind);
+			mesh.Material = new StandardMaterial3D { AlbedoColor = color, Roughness = 0.9f };
+
+			var multiMesh = new MultiMesh
+			{
+				TransformFormat = MultiMesh.TransformFormatEnum.Transform3D,
+				Mesh = mesh,
+				InstanceCount = list.Count,
+			};
+			for (int i = 0; i < list.Count; i++)
+			{
+				var obj = list[i];
+				// Heading is counter-clockwise from +X in server space, which
+				// is a rotation about +Y in Godot space
+				var basis = new Basis(Vector3.Up, obj.heading_rad).Scaled(Vector3.One * obj.scale * SCALE_FACTOR);
+				// Primitives are centred; lift them to rest on the ground
+				var origin = ServerToGodot(obj.x, obj.y, obj.z + height * obj.scale / 2.0f);
+				multiMesh.SetInstanceTransform(i, new Transform3D(basis, origin));
+			}
+			AddChild(new MultiMeshInstance3D { Multimesh = multiMesh, Name = $"Objects_{kind}" });
+		}
+
+		GD.Print($"Placed {objects.Count} trackside objects of {byKind.Count} kinds");
 	}
 
 	/// <summary>
-	/// Generate terrain mesh from heightmap data.
+	/// Primitive mesh, color and height standing in for an object kind, sized in meters.
 	/// </summary>
-	private ArrayMesh GenerateTerrainMesh(TerrainHeightmap heightmap, EnvironmentPreset preset)
+	private static (PrimitiveMesh, Color, float) ObjectPrimitive(string kind)
+	{
+		return kind switch
+		{
+			"grandstand" => (new BoxMesh { Size = new Vector3(12.0f, 8.0f, 30.0f) }, new Color(0.6f, 0.6f, 0.65f), 8.0f),
+			"marshal_post" => (new BoxMesh { Size = new Vector3(2.0f, 3.0f, 2.0f) }, new Color(1.0f, 0.5f, 0.0f), 3.0f),
+			"tire_wall" => (new CylinderMesh { TopRadius = 0.6f, BottomRadius = 0.6f, Height = 1.2f }, new Color(0.1f, 0.1f, 0.1f), 1.2f),
+			"barrier" => (new BoxMesh { Size = new Vector3(12.0f, 0.8f, 0.3f) }, new Color(0.8f, 0.8f, 0.8f), 0.8f),
+			_ when kind.StartsWith("tree") => (new CylinderMesh { TopRadius = 0.0f, BottomRadius = 2.5f, Height = 10.0f }, new Color(0.15f, 0.35f, 0.15f), 10.0f),
+			_ when kind.StartsWith("building") => (new BoxMesh { Size = new Vector3(15.0f, 12.0f, 15.0f) }, new Color(0.55f, 0.5f, 0.45f), 12.0f),
+			_ when kind.StartsWith("rock") => (new SphereMesh { Radius = 1.5f, Height = 2.0f }, new Color(0.45f, 0.43f, 0.4f), 2.0f),
+			_ => (new SphereMesh { Radius = 1.0f, Height = 1.5f }, new Color(0.3f, 0.45f, 0.2f), 1.5f),
+		};
+	}
+
+	/// <summary>
+	/// Generate terrain mesh from the cells of a heightmap between grid
+	/// points (x0, y0) and (x1, y1), with vertices relative to <c>offset</c>.
+	/// <c>splatStep</c> is how many full-resolution cells one cell of the
+	/// grid spans, to find its ground cover in the splatmap.
+	/// </summary>
+	private ArrayMesh GenerateTerrainMesh(TerrainHeightmap heightmap, EnvironmentPreset preset, Splatmap? splatmap,
+		int splatStep, int x0, int y0, int x1, int y1, Vector3 offset)
 	{
 		var surfaceTool = new SurfaceTool();
 		surfaceTool.Begin(Mesh.PrimitiveType.Triangles);
 
-		// Get ground color from preset
+		// Get ground color from preset; biomes fade to their peak color on high ground
 		var groundColor = new Color(
 			preset.GroundColor[0],
 			preset.GroundColor[1],
 			preset.GroundColor[2]
 		);
+		Color? peakColor = preset.PeakColor is { Length: 3 } peak
+			? new Color(peak[0], peak[1], peak[2])
+			: null;
+		// Blend the splatmap's layers; caches without one keep the flat ground color
+		Color CoverAt(int x, int y)
+		{
+			if (splatmap is not { width: > 0 } splat)
+			{
+				return groundColor;
+			}
+			var (grass, dirt, rock, sand) = splat.WeightsAt(x * splatStep, y * splatStep);
+			return groundColor * grass + DirtColor * dirt + RockColor * rock + SandColor * sand;
+		}
+		Color ColorAt(int x, int y, float h) => peakColor is Color top && preset.max_height > 0.0f
+			? CoverAt(x, y).Lerp(top, Mathf.SmoothStep(0.6f, 0.85f, h / preset.max_height))
+			: CoverAt(x, y);
 
 		// Generate quad grid from heightmap
-		// Skip the last row and column (we need pairs of vertices for quads)
-		for (int y = 0; y < heightmap.Height - 1; y++)
+		// Stop short of the last row and column (we need pairs of vertices for quads)
+		for (int y = y0; y < y1; y++)
 		{
-			for (int x = 0; x < heightmap.Width - 1; x++)
+			for (int x = x0; x < x1; x++)
 			{
 				// Get the 4 corner heights
 				float h00 = heightmap.GetHeight(x, y);
@@ -79,10 +326,10 @@ public partial class ProceduralTerrain : Node3D
 				// Convert to Godot coordinates
 				// Server: X, Y (horizontal), Z (elevation)
 				// Godot: X (same), Y (elevation), Z (-Y flipped)
-				var v00 = ServerToGodot(worldX0, worldY0, h00);
-				var v10 = ServerToGodot(worldX1, worldY0, h10);
-				var v01 = ServerToGodot(worldX0, worldY1, h01);
-				var v11 = ServerToGodot(worldX1, worldY1, h11);
+				var v00 = ServerToGodot(worldX0, worldY0, h00) - offset;
+				var v10 = ServerToGodot(worldX1, worldY0, h10) - offset;
+				var v01 = ServerToGodot(worldX0, worldY1, h01) - offset;
+				var v11 = ServerToGodot(worldX1, worldY1, h11) - offset;
 
 				// Calculate smooth normals for lighting
 				var normal00 = CalculateNormal(heightmap, x, y);
@@ -91,28 +338,28 @@ public partial class ProceduralTerrain : Node3D
 				var normal11 = CalculateNormal(heightmap, x + 1, y + 1);
 
 				// First triangle (v00, v10, v01)
-				surfaceTool.SetColor(groundColor);
+				surfaceTool.SetColor(ColorAt(x, y, h00));
 				surfaceTool.SetNormal(normal00);
 				surfaceTool.AddVertex(v00);
 
-				surfaceTool.SetColor(groundColor);
+				surfaceTool.SetColor(ColorAt(x + 1, y, h10));
 				surfaceTool.SetNormal(normal10);
 				surfaceTool.AddVertex(v10);
 
-				surfaceTool.SetColor(groundColor);
+				surfaceTool.SetColor(ColorAt(x, y + 1, h01));
 				surfaceTool.SetNormal(normal01);
 				surfaceTool.AddVertex(v01);
 
 				// Second triangle (v10, v11, v01)
-				surfaceTool.SetColor(groundColor);
+				surfaceTool.SetColor(ColorAt(x + 1, y, h10));
 				surfaceTool.SetNormal(normal10);
 				surfaceTool.AddVertex(v10);
 
-				surfaceTool.SetColor(groundColor);
+				surfaceTool.SetColor(ColorAt(x + 1, y + 1, h11));
 				surfaceTool.SetNormal(normal11);
 				surfaceTool.AddVertex(v11);
 
-				surfaceTool.SetColor(groundColor);
+				surfaceTool.SetColor(ColorAt(x, y + 1, h01));
 				surfaceTool.SetNormal(normal01);
 				surfaceTool.AddVertex(v01);
 			}
@@ -172,7 +419,11 @@ public partial class ProceduralTerrain : Node3D
 
 	public override void _ExitTree()
 	{
-		_terrainMesh?.QueueFree();
+		foreach (var mesh in _terrainMeshes)
+		{
+			mesh.QueueFree();
+		}
+		_terrainMeshes.Clear();
 		base._ExitTree();
 	}
 }
@@ -96,6 +96,67 @@ public class TerrainHeightmap
 	}
 }
 
+/// <summary>
+/// One layer of a biome's heightmap noise stack.
+/// Matches the Rust NoiseLayer structure.
+/// </summary>
+[MessagePackObject]
+public class NoiseLayer
+{
+	[Key(0)]
+	public float frequency { get; set; }
+	[Key(1)]
+	public float amplitude { get; set; }
+	[Key(2)]
+	public bool ridged { get; set; }
+}
+
+/// <summary>
+/// Sky and fog hints for a biome; colors are RGB in the 0-1 range.
+/// Matches the Rust SkyHint structure.
+/// </summary>
+[MessagePackObject]
+public class SkyHint
+{
+	[Key(0)]
+	public float[] top_color { get; set; } = { 0.385f, 0.454f, 0.55f };
+	[Key(1)]
+	public float[] horizon_color { get; set; } = { 0.646f, 0.656f, 0.67f };
+	[Key(2)]
+	public float sun_elevation_deg { get; set; } = 45.0f;
+	[Key(3)]
+	public float fog_density { get; set; }
+}
+
+/// <summary>
+/// Sun, sky and ambient light at one time of day; colors are RGB in the
+/// 0-1 range. At night the sun is the moon.
+/// Matches the Rust Lighting structure.
+/// </summary>
+[MessagePackObject]
+public class Lighting
+{
+	[Key(0)]
+	public TimeOfDay time_of_day { get; set; } = TimeOfDay.Noon;
+	[Key(1)]
+	public float sun_elevation_deg { get; set; } = 45.0f;
+	// Compass bearing, clockwise from north (server +Y)
+	[Key(2)]
+	public float sun_azimuth_deg { get; set; } = 180.0f;
+	[Key(3)]
+	public float[] sun_color { get; set; } = { 1.0f, 1.0f, 1.0f };
+	[Key(4)]
+	public float sun_energy { get; set; } = 1.0f;
+	[Key(5)]
+	public float[] ambient_color { get; set; } = { 1.0f, 1.0f, 1.0f };
+	[Key(6)]
+	public float ambient_energy { get; set; } = 0.5f;
+	[Key(7)]
+	public float[] sky_top_color { get; set; } = { 0.385f, 0.454f, 0.55f };
+	[Key(8)]
+	public float[] sky_horizon_color { get; set; } = { 0.646f, 0.656f, 0.67f };
+}
+
 /// <summary>
 /// Environment preset configuration for procedural generation.
 /// Matches the Rust EnvironmentPreset structure.
@@ -116,10 +177,173 @@ public class EnvironmentPreset
 	public List<string> allowed_objects { get; set; } = new();
 	[Key(5)]
 	public float[] ground_color { get; set; } = new float[3];
+	// Absent in terrain caches written before biomes existed
+	[Key(6)]
+	public List<NoiseLayer> noise_layers { get; set; } = new();
+	[Key(7)]
+	public float[]? peak_color { get; set; }
+	[Key(8)]
+	public SkyHint sky { get; set; } = new();
+	[Key(9)]
+	public float rainfall { get; set; }
+	[Key(10)]
+	public float sand_below { get; set; }
+	[Key(11)]
+	public List<Lighting> lighting { get; set; } = new();
 
 	// Convenience properties with PascalCase
 	[IgnoreMember]
 	public float[] GroundColor => ground_color;
+
+	[IgnoreMember]
+	public float[]? PeakColor => peak_color;
+
+	[IgnoreMember]
+	public SkyHint Sky => sky;
+}
+
+/// <summary>
+/// An object placed beside the track: a trackside kind such as "grandstand"
+/// or scenery from the biome. Matches the Rust TracksideObject structure.
+/// </summary>
+[MessagePackObject]
+public class TracksideObject
+{
+	[Key(0)]
+	public string kind { get; set; } = "";
+	[Key(1)]
+	public float x { get; set; }
+	[Key(2)]
+	public float y { get; set; }
+	[Key(3)]
+	public float z { get; set; }
+	[Key(4)]
+	public float heading_rad { get; set; }
+	[Key(5)]
+	public float scale { get; set; } = 1.0f;
+}
+
+/// <summary>
+/// A lake with a flat surface at <c>level</c>, drawn over rectangles
+/// [min_x, min_y, max_x, max_y] that reach under the shore.
+/// Matches the Rust Lake structure.
+/// </summary>
+[MessagePackObject]
+public class Lake
+{
+	[Key(0)]
+	public float level { get; set; }
+	[Key(1)]
+	public List<float[]> rects { get; set; } = new();
+}
+
+/// <summary>
+/// A point on a river's course, with the height and width of its water surface.
+/// Matches the Rust RiverPoint structure.
+/// </summary>
+[MessagePackObject]
+public class RiverPoint
+{
+	[Key(0)]
+	public float x { get; set; }
+	[Key(1)]
+	public float y { get; set; }
+	[Key(2)]
+	public float level { get; set; }
+	[Key(3)]
+	public float width { get; set; }
+}
+
+/// <summary>
+/// A river from its source downstream. Matches the Rust River structure.
+/// </summary>
+[MessagePackObject]
+public class River
+{
+	[Key(0)]
+	public List<RiverPoint> points { get; set; } = new();
+}
+
+/// <summary>
+/// Lakes and rivers of a world. Matches the Rust WaterFeatures structure.
+/// </summary>
+[MessagePackObject]
+public class WaterFeatures
+{
+	[Key(0)]
+	public List<Lake> lakes { get; set; } = new();
+	[Key(1)]
+	public List<River> rivers { get; set; } = new();
+}
+
+/// <summary>
+/// Ground cover of the terrain: weights of grass, dirt, rock and sand for
+/// each heightmap cell, four bytes per cell summing to 255 (row-major like
+/// the heights). Matches the Rust Splatmap structure.
+/// </summary>
+[MessagePackObject]
+public class Splatmap
+{
+	[Key(0)]
+	public int width { get; set; }
+	[Key(1)]
+	public int height { get; set; }
+	[Key(2)]
+	public byte[] weights { get; set; } = Array.Empty<byte>();
+
+	/// <summary>
+	/// Layer weights (0-1) of a cell, in the order grass, dirt, rock, sand.
+	/// </summary>
+	public (float Grass, float Dirt, float Rock, float Sand) WeightsAt(int x, int y)
+	{
+		x = Math.Clamp(x, 0, width - 1);
+		y = Math.Clamp(y, 0, height - 1);
+		int i = (y * width + x) * 4;
+		return (weights[i] / 255.0f, weights[i + 1] / 255.0f, weights[i + 2] / 255.0f, weights[i + 3] / 255.0f);
+	}
+}
+
+/// <summary>
+/// A lamp of the track's own, lit at dusk and at night. <c>kind</c> is
+/// "pit_lane" or "start_gantry". Matches the Rust LightSource structure.
+/// </summary>
+[MessagePackObject]
+public class LightSource
+{
+	[Key(0)]
+	public string kind { get; set; } = "";
+	[Key(1)]
+	public float x { get; set; }
+	[Key(2)]
+	public float y { get; set; }
+	[Key(3)]
+	public float z { get; set; }
+	[Key(4)]
+	public float[] color { get; set; } = { 1.0f, 1.0f, 1.0f };
+	[Key(5)]
+	public float range_m { get; set; }
+	[Key(6)]
+	public float energy { get; set; }
+}
+
+/// <summary>
+/// Coarser copies of the heightmap for distant terrain. Tiles cover the same
+/// ground at every level: <c>tile_cells</c> cells a side at full resolution,
+/// half as many at each coarser level. Matches the Rust TerrainLod structure.
+/// </summary>
+[MessagePackObject]
+public class TerrainLod
+{
+	[Key(0)]
+	public int tile_cells { get; set; }
+	// Levels 1 and up; level 0 is the heightmap itself
+	[Key(1)]
+	public List<TerrainHeightmap> levels { get; set; } = new();
+
+	/// <summary>
+	/// Grid of a level, where 0 is the full-resolution heightmap.
+	/// </summary>
+	public TerrainHeightmap Level(TerrainHeightmap heightmap, int level) => level == 0 ? heightmap : levels[level - 1];
 }
 
 /// <summary>
@@ -145,6 +369,20 @@ public class ProceduralWorldData
 	public string decal_profile { get; set; } = "";
 	[Key(6)]
 	public EnvironmentPreset preset { get; set; } = new();
+	[Key(7)]
+	public List<TracksideObject> objects { get; set; } = new();
+	[Key(8)]
+	public WaterFeatures water { get; set; } = new();
+	// Absent in terrain caches written before splatmaps existed
+	[Key(9)]
+	public Splatmap? splatmap { get; set; }
+	[Key(10)]
+	public TerrainLod? lod { get; set; }
+	// Every time of day; empty in caches written before lighting existed
+	[Key(11)]
+	public List<Lighting> lighting { get; set; } = new();
+	[Key(12)]
+	public List<LightSource> lights { get; set; } = new();
 
 	// Convenience properties with PascalCase
 	[IgnoreMember]
@@ -155,4 +393,25 @@ public class ProceduralWorldData
 
 	[IgnoreMember]
 	public EnvironmentPreset Preset => preset;
+
+	[IgnoreMember]
+	public List<TracksideObject> Objects => objects;
+
+	[IgnoreMember]
+	public WaterFeatures Water => water;
+
+	[IgnoreMember]
+	public Splatmap? Splatmap => splatmap;
+
+	[IgnoreMember]
+	public TerrainLod? Lod => lod;
+
+	[IgnoreMember]
+	public List<LightSource> Lights => lights;
+
+	/// <summary>
+	/// Lighting at a time of day, or null for caches from before lighting.
+	/// </summary>
+	public Lighting? LightingAt(TimeOfDay timeOfDay) =>
+		lighting.Find(l => l.time_of_day == timeOfDay) ?? preset.lighting.Find(l => l.time_of_day == timeOfDay);
 }
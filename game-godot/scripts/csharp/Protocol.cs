@@ -8,7 +8,8 @@ public enum SessionState : byte
     Lobby = 0,
     Countdown = 1,
     Racing = 2,
-    Finished = 3
+    Finished = 3,
+    Preparing = 4 // Track still loading; not joinable yet
 }
 
 // Session Types / Kinds
@@ -29,7 +30,104 @@ public enum GameMode : byte
     FreePractice = 4,
     Replay = 5,
     Qualification = 6,
-    Race = 7
+    Race = 7,
+    RollingStart = 8,
+    FormationLap = 9
+}
+
+// Lobby update kinds
+public enum LobbyUpdateKind : byte
+{
+    PlayerJoined = 0,
+    PlayerUpdated = 1,
+    PlayerLeft = 2,
+    SessionCreated = 3,
+    SessionUpdated = 4,
+    SessionRemoved = 5
+}
+
+// Content a client can download from the server
+public enum ContentKind : byte
+{
+    Car = 0,
+    Track = 1
+}
+
+// Weather conditions
+public enum WeatherCondition : byte
+{
+    Dry = 0,
+    Damp = 1,
+    Wet = 2
+}
+
+// Whether players may change their car setup in a session
+public enum SetupPolicy : byte
+{
+    Open = 0,
+    Fixed = 1
+}
+
+// Whether cars in a session's free practice touch each other
+public enum CollisionPolicy : byte
+{
+    Solid = 0,
+    Ghost = 1 // Cars pass through each other but still hit walls
+}
+
+// Time of day a session is set at, chosen when creating it
+public enum TimeOfDay : byte
+{
+    Morning = 0,
+    Noon = 1,
+    Afternoon = 2,
+    Dusk = 3, // Sun on the horizon
+    Night = 4 // Moonlight; the track's own lights do the rest
+}
+
+// How strongly a driver aid intervenes; sessions cap each aid at a level
+public enum AidLevel : byte
+{
+    Off = 0,
+    Low = 1,
+    High = 2
+}
+
+public class DriverAids
+{
+    public AidLevel Abs { get; set; } = AidLevel.Off;
+    public AidLevel TractionControl { get; set; } = AidLevel.Off;
+    public AidLevel StabilityControl { get; set; } = AidLevel.Off;
+
+    public static DriverAids Unrestricted() => new()
+    {
+        Abs = AidLevel.High,
+        TractionControl = AidLevel.High,
+        StabilityControl = AidLevel.High
+    };
+}
+
+// Aids that intervened on the last tick, for dashboard lights
+public class ActiveAids
+{
+    public bool Abs { get; set; }
+    public bool TractionControl { get; set; }
+    public bool StabilityControl { get; set; }
+}
+
+// Body orientation quaternion rotating the car's frame (x forward, y left, z up) into the server's world frame
+public class CarOrientation
+{
+    public float W { get; set; } = 1.0f;
+    public float X { get; set; }
+    public float Y { get; set; }
+    public float Z { get; set; }
+}
+
+public class WeatherChange
+{
+    public uint AfterS { get; set; }
+    public WeatherCondition Condition { get; set; }
 }
 
 // Client Messages
@@ -39,6 +137,8 @@ public class AuthenticateMessage : ClientMessage
 {
     public string Token { get; set; } = "";
     public string PlayerName { get; set; } = "";
+    // Telemetry frames per second; 0 for every server tick
+    public ushort TelemetryRateHz { get; set; }
 }
 
 public class HeartbeatMessage : ClientMessage
@@ -46,9 +146,15 @@ public class HeartbeatMessage : ClientMessage
     public uint ClientTick { get; set; }
 }
 
+public class StatsAckMessage : ClientMessage
+{
+    public uint PingId { get; set; }
+}
+
 public class SelectCarMessage : ClientMessage
 {
     public string CarConfigId { get; set; } = "";
+    public byte Livery { get; set; } // Index into the car's liveries
 }
 
 public class RequestLobbyStateMessage : ClientMessage { }
@@ -60,6 +166,12 @@ public class CreateSessionMessage : ClientMessage
     public byte AiCount { get; set; }
     public byte LapLimit { get; set; }
     public SessionKind SessionKind { get; set; } = SessionKind.Multiplayer;
+    public WeatherCondition Weather { get; set; } = WeatherCondition.Dry;
+    public WeatherChange[] Forecast { get; set; } = Array.Empty<WeatherChange>();
+    public SetupPolicy SetupPolicy { get; set; } = SetupPolicy.Open;
+    public CollisionPolicy CollisionPolicy { get; set; } = CollisionPolicy.Solid;
+    public TimeOfDay TimeOfDay { get; set; } = TimeOfDay.Noon;
+    public DriverAids? AidLimits { get; set; } // Null allows every aid at full strength
 }
 
 public class JoinSessionMessage : ClientMessage
@@ -71,6 +183,21 @@ public class LeaveSessionMessage : ClientMessage { }
 
 public class StartSessionMessage : ClientMessage { }
 
+public class ApplySetupMessage : ClientMessage
+{
+    public CarSetup Setup { get; set; } = new();
+}
+
+public class SetDriverAidsMessage : ClientMessage
+{
+    public DriverAids Aids { get; set; } = new();
+}
+
+public class SetTelemetryRateMessage : ClientMessage
+{
+    public ushort RateHz { get; set; }
+}
+
 public class SetGameModeMessage : ClientMessage
 {
     public GameMode Mode { get; set; }
@@ -84,6 +211,18 @@ public class StartCountdownMessage : ClientMessage
 
 public class DisconnectMessage : ClientMessage { }
 
+public class RequestContentMessage : ClientMessage
+{
+    public ContentKind Kind { get; set; }
+    public string Id { get; set; } = "";
+}
+
+public class RequestContentFileMessage : ClientMessage
+{
+    public string Path { get; set; } = "";
+    public ulong Offset { get; set; } // Bytes already downloaded, to resume
+}
+
 // Server Messages
 public abstract class ServerMessage { }
 
@@ -104,6 +243,19 @@ public class HeartbeatAckMessage : ServerMessage
     public uint ServerTick { get; set; }
 }
 
+public class ConnectionStatsMessage : ServerMessage
+{
+    public uint PingId { get; set; }
+    public ulong MessagesSent { get; set; }
+    public ulong MessagesReceived { get; set; }
+    public ulong BytesSent { get; set; }
+    public ulong BytesReceived { get; set; }
+    public float? RttMs { get; set; }
+    public ulong MessagesDropped { get; set; }
+    public float LossPercent { get; set; }
+    public ulong RateLimitHits { get; set; }
+}
+
 public class LobbyStateMessage : ServerMessage
 {
     public LobbyPlayer[] PlayersInLobby { get; set; } = Array.Empty<LobbyPlayer>();
@@ -112,10 +264,20 @@ public class LobbyStateMessage : ServerMessage
     public TrackConfigSummary[] TrackConfigs { get; set; } = Array.Empty<TrackConfigSummary>();
 }
 
+public class LobbyUpdateMessage : ServerMessage
+{
+    public LobbyUpdateKind Kind { get; set; }
+    public LobbyPlayer? Player { get; set; }
+    public SessionSummary? Session { get; set; }
+    public string? PlayerId { get; set; }
+    public string? SessionId { get; set; }
+}
+
 public class SessionJoinedMessage : ServerMessage
 {
     public string SessionId { get; set; } = "";
     public byte YourGridPosition { get; set; }
+    public uint DelaySeconds { get; set; }
 }
 
 public class SessionLeftMessage : ServerMessage
@@ -133,6 +295,48 @@ public class ErrorMessage : ServerMessage
     public string Message { get; set; } = "";
 }
 
+public class WeatherUpdateMessage : ServerMessage
+{
+    public string SessionId { get; set; } = "";
+    public WeatherCondition Condition { get; set; }
+    public float TrackWetness { get; set; }
+    public WeatherCondition? NextCondition { get; set; }
+    public uint? NextChangeInS { get; set; }
+}
+
+public class SetupAppliedMessage : ServerMessage
+{
+    public CarSetup Setup { get; set; } = new();
+}
+
+public class DriverAidsAppliedMessage : ServerMessage
+{
+    public DriverAids Aids { get; set; } = new();
+}
+
+public class ContentFileInfo
+{
+    public string Path { get; set; } = ""; // Relative to the content folder
+    public ulong SizeBytes { get; set; }
+    public string Sha256 { get; set; } = "";
+    public bool TooLarge { get; set; } // Over the server's size limit
+}
+
+public class ContentManifestMessage : ServerMessage
+{
+    public ContentKind Kind { get; set; }
+    public string Id { get; set; } = "";
+    public ContentFileInfo[] Files { get; set; } = Array.Empty<ContentFileInfo>();
+}
+
+public class ContentChunkMessage : ServerMessage
+{
+    public string Path { get; set; } = "";
+    public ulong Offset { get; set; }
+    public ulong TotalBytes { get; set; }
+    public byte[] Data { get; set; } = Array.Empty<byte>();
+}
+
 public class PlayerDisconnectedMessage : ServerMessage
 {
     public string PlayerId { get; set; } = "";
@@ -150,6 +354,16 @@ public class TelemetryMessage : ServerMessage
     public GameMode GameMode { get; set; }
     public ushort? CountdownMs { get; set; }
     public CarStateTelemetry[] CarStates { get; set; } = Array.Empty<CarStateTelemetry>();
+    public RaceProgress? RaceProgress { get; set; }
+}
+
+// Leader's progress through a lap-limited race, e.g. "12/20 laps, ~9 min left"
+public class RaceProgress
+{
+    public ushort LeaderLap { get; set; }
+    public ushort TotalLaps { get; set; }
+    public float CompletedFraction { get; set; }
+    public uint? EstimatedRemainingS { get; set; } // Null until the leader has completed a lap
 }
 
 public class CarStateTelemetry
@@ -163,6 +377,7 @@ public class CarStateTelemetry
     public float YawRad { get; set; }
     public float PitchRad { get; set; }
     public float RollRad { get; set; }
+    public CarOrientation Orientation { get; set; } = new();
     // Motion
     public float SpeedMps { get; set; }
     public float Throttle { get; set; }
@@ -179,6 +394,14 @@ public class CarStateTelemetry
     // Status
     public bool IsOnTrack { get; set; }
     public bool IsColliding { get; set; }
+    public bool IsGhost { get; set; } // Passes through other cars; draw see-through
+    // Driver aids
+    public DriverAids Aids { get; set; } = new();
+    public ActiveAids AidsActive { get; set; } = new();
+    public bool EngineStalled { get; set; }
+    public bool IsAirborne { get; set; }
+    public bool IsOverturned { get; set; } // Rolled onto its side and out of the session
+    public byte Livery { get; set; } // Index into the car's liveries
 }
 
 // Data structures
@@ -187,6 +410,7 @@ public class LobbyPlayer
     public string Id { get; set; } = "";
     public string Name { get; set; } = "";
     public string? SelectedCar { get; set; }
+    public byte SelectedLivery { get; set; }
     public string? InSession { get; set; }
 }
 
@@ -199,6 +423,40 @@ public class SessionSummary
     public byte PlayerCount { get; set; }
     public byte MaxPlayers { get; set; }
     public SessionState State { get; set; }
+    public RaceProgress? RaceProgress { get; set; }
+    public SetupPolicy SetupPolicy { get; set; } = SetupPolicy.Open;
+    public CollisionPolicy CollisionPolicy { get; set; } = CollisionPolicy.Solid;
+    public TimeOfDay TimeOfDay { get; set; } = TimeOfDay.Noon;
+    public DriverAids AidLimits { get; set; } = DriverAids.Unrestricted();
+}
+
+// Tunable car setup; gear ratios are forward gears only, pressures are cold
+public class CarSetup
+{
+    public float FinalDriveRatio { get; set; }
+    public float[] GearRatios { get; set; } = Array.Empty<float>();
+    public float LiftCoefficientFront { get; set; }
+    public float LiftCoefficientRear { get; set; }
+    public float TirePressureFrontKpa { get; set; }
+    public float TirePressureRearKpa { get; set; }
+    public float BrakeBiasFront { get; set; }
+}
+
+public class SetupRange
+{
+    public float Min { get; set; }
+    public float Max { get; set; }
+}
+
+// Allowed values for each setup field of a car
+public class SetupRanges
+{
+    public SetupRange FinalDriveRatio { get; set; } = new();
+    public SetupRange GearRatio { get; set; } = new();
+    public SetupRange LiftCoefficientFront { get; set; } = new();
+    public SetupRange LiftCoefficientRear { get; set; } = new();
+    public SetupRange TirePressureKpa { get; set; } = new();
+    public SetupRange BrakeBiasFront { get; set; } = new();
 }
 
 public class CarConfigSummary
@@ -208,10 +466,42 @@ public class CarConfigSummary
     public string ModelPath { get; set; } = "";
     public float MassKg { get; set; } = 0;
     public float MaxEngineForceN { get; set; } = 0;
+    public float PeakTorqueNm { get; set; } = 0;
+    public float PeakPowerW { get; set; } = 0;
+    public SetupRanges SetupRanges { get; set; } = new();
+    public CarSetup DefaultSetup { get; set; } = new();
+    public string ContentHash { get; set; } = ""; // SHA-256 of the server's car.toml and model file
+    public Livery[] Liveries { get; set; } = Array.Empty<Livery>(); // Empty when the car has only its model's paint
+}
+
+// A car's paint scheme
+public class Livery
+{
+    public string Name { get; set; } = "";
+    public string? Texture { get; set; } // Relative to the car's folder; null paints in the team colors
+    public string PrimaryColor { get; set; } = "#ffffff"; // "#rrggbb"
+    public string SecondaryColor { get; set; } = "#ffffff";
+    public ushort? Number { get; set; } // Race number for the number plate
 }
 
 public class TrackConfigSummary
 {
     public string Id { get; set; } = "";
     public string Name { get; set; } = "";
+    public string? SourcePath { get; set; } // Track file relative to the content folder
+    public string ContentHash { get; set; } = ""; // SHA-256 of the server's track file
+    public string? Country { get; set; }
+    public float LengthM { get; set; } = 0;
+    public uint Corners { get; set; } = 0;
+    public string[] Tags { get; set; } = Array.Empty<string>(); // e.g. "oval", "street"
+    public LapRecord? LapRecord { get; set; } // Null until a race lap is driven on the track
+}
+
+// Fastest lap driven on a track in a race
+public class LapRecord
+{
+    public uint LapTimeMs { get; set; }
+    public string DriverName { get; set; } = "";
+    public string CarName { get; set; } = "";
+    public ulong SetAt { get; set; } // Unix time (s)
 }
@@ -14,6 +14,7 @@ public partial class SessionBrowserDialog : Control
     private NetworkClient? _network;
     private List<SessionSummary> _sessions = new();
     private CarConfigSummary? _selectedCar = null;
+    private byte _selectedLivery = 0;
     private PackedScene? _carSelectionScene;
 
     public override void _Ready()
@@ -132,8 +133,10 @@ public partial class SessionBrowserDialog : Control
         if (inst is Node node) GetTree().Root.AddChild(node);
     }
 
-    private void OnCarSelected(string carId, string carName)
+    private void OnCarSelected(string carId, string carName, int livery)
     {
+        _selectedLivery = (byte)livery;
+
         // Find the car in the lobby state
         if (_network?.LastLobbyState != null)
         {
@@ -250,7 +253,12 @@ public partial class SessionBrowserDialog : Control
             }
             else
             {
-                disabledLabel.Text = session.State != SessionState.Lobby ? "In Progress" : "Full";
+                disabledLabel.Text = session.State switch
+                {
+                    SessionState.Preparing => "Preparing",
+                    SessionState.Lobby => "Full",
+                    _ => "In Progress"
+                };
             }
             disabledLabel.CustomMinimumSize = new Vector2(100, 40);
             disabledLabel.HorizontalAlignment = HorizontalAlignment.Center;
@@ -270,6 +278,7 @@ public partial class SessionBrowserDialog : Control
             SessionState.Countdown => Colors.Yellow,
             SessionState.Racing => Colors.Orange,
             SessionState.Finished => Colors.Gray,
+            SessionState.Preparing => Colors.LightBlue,
             _ => Colors.White
         };
     }
@@ -291,7 +300,7 @@ public partial class SessionBrowserDialog : Control
         _statusLabel!.Text = $"Joining {session.TrackName} with {_selectedCar.Name}...";
         _statusLabel.Modulate = Colors.Yellow;
 
-        await _network!.SelectCarAsync(_selectedCar.Id);
+        await _network!.SelectCarAsync(_selectedCar.Id, _selectedLivery);
         await _network!.JoinSessionAsync(session.Id);
         // Don't close yet - wait for SessionJoined event
     }
@@ -12,12 +12,14 @@ public partial class SessionCreationDialog : Control
     private SpinBox? _maxPlayersSpinBox;
     private SpinBox? _aiCountSpinBox;
     private SpinBox? _lapLimitSpinBox;
+    private OptionButton? _timeOfDaySelector;
     private Button? _createButton;
     private Button? _cancelButton;
     private Label? _statusLabel;
     private NetworkClient? _network;
     private TrackConfigSummary? _selectedTrack = null;
     private CarConfigSummary? _selectedCar = null;
+    private byte _selectedLivery = 0;
     private PackedScene? _trackSelectionScene;
     private PackedScene? _carSelectionScene;
 
@@ -29,6 +31,7 @@ public partial class SessionCreationDialog : Control
         _maxPlayersSpinBox = GetNode<SpinBox>("Panel/VBox/MaxPlayers/SpinBox");
         _aiCountSpinBox = GetNode<SpinBox>("Panel/VBox/AICount/SpinBox");
         _lapLimitSpinBox = GetNode<SpinBox>("Panel/VBox/LapLimit/SpinBox");
+        _timeOfDaySelector = GetNode<OptionButton>("Panel/VBox/TimeOfDay/TimeOfDayOptionButton");
         _createButton = GetNode<Button>("Panel/VBox/ButtonBar/CreateButton");
         _cancelButton = GetNode<Button>("Panel/VBox/ButtonBar/CancelButton");
         _statusLabel = GetNode<Label>("Panel/VBox/StatusLabel");
@@ -39,6 +42,12 @@ public partial class SessionCreationDialog : Control
         _sessionTypeSelector.AddItem("Sandbox", (int)SessionKind.Sandbox);
         _sessionTypeSelector.Selected = 0;
 
+        foreach (var timeOfDay in Enum.GetValues<TimeOfDay>())
+        {
+            _timeOfDaySelector.AddItem(timeOfDay.ToString(), (int)timeOfDay);
+        }
+        _timeOfDaySelector.Selected = _timeOfDaySelector.GetItemIndex((int)TimeOfDay.Noon);
+
         _createButton.Pressed += OnCreatePressed;
         _cancelButton.Pressed += OnCancelPressed;
         _trackSelectorButton.Pressed += OnTrackSelectorPressed;
@@ -196,8 +205,10 @@ public partial class SessionCreationDialog : Control
         }
     }
 
-    private void OnCarSelected(string carId, string carName)
+    private void OnCarSelected(string carId, string carName, int livery)
     {
+        _selectedLivery = (byte)livery;
+
         // Find the car in the lobby state
         if (_network?.LastLobbyState != null)
         {
@@ -271,13 +282,14 @@ public partial class SessionCreationDialog : Control
 
         var sessionTypeIndex = _sessionTypeSelector!.Selected;
         var sessionKind = (SessionKind)sessionTypeIndex;
+        var timeOfDay = (TimeOfDay)_timeOfDaySelector!.GetSelectedId();
 
         _statusLabel!.Text = $"Creating session on {track.Name} with {_selectedCar.Name}...";
         _statusLabel.Modulate = Colors.Yellow;
         _createButton!.Disabled = true;
 
-        await _network!.SelectCarAsync(_selectedCar.Id);
-        await _network!.CreateSessionAsync(track.Id, maxPlayers, aiCount, lapLimit, sessionKind);
+        await _network!.SelectCarAsync(_selectedCar.Id, _selectedLivery);
+        await _network!.CreateSessionAsync(track.Id, maxPlayers, aiCount, lapLimit, sessionKind, timeOfDay: timeOfDay);
     }
 
     private void OnSessionJoined(string sessionId, byte gridPosition)
@@ -366,16 +366,16 @@ public partial class SessionLobby : Control
         if (inst is Node node) GetTree().Root.AddChild(node);
     }
 
-    private async void OnCarSelected(string carId, string carName)
+    private async void OnCarSelected(string carId, string carName, int livery)
     {
-        GD.Print($"OnCarSelected: carId={carId}, carName={carName}");
+        GD.Print($"OnCarSelected: carId={carId}, carName={carName}, livery={livery}");
         _statusLabel!.Text = $"Selecting car: {carName}...";
         _statusLabel.Modulate = Colors.Yellow;
 
         if (_network != null)
         {
             GD.Print("OnCarSelected: Sending SelectCarAsync to server...");
-            await _network.SelectCarAsync(carId);
+            await _network.SelectCarAsync(carId, (byte)livery);
             GD.Print("OnCarSelected: SelectCarAsync completed");
             _statusLabel.Text = $"Car selected: {carName}";
             _statusLabel.Modulate = new Color(0, 1, 0.4f);
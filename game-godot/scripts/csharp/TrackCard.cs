@@ -15,6 +15,8 @@ public partial class TrackCard : PanelContainer
 
     private Label? _nameLabel;
     private Label? _lengthLabel;
+    private Label? _tagsLabel;
+    private Label? _lapRecordLabel;
     private TextureRect? _trackImage;
     private ColorRect? _selectionIndicator;
     private ColorRect? _selectionBorder;
@@ -39,6 +41,8 @@ public partial class TrackCard : PanelContainer
     {
         _nameLabel = GetNode<Label>("VBox/InfoSection/TrackName");
         _lengthLabel = GetNode<Label>("VBox/InfoSection/TrackInfo/Length");
+        _tagsLabel = GetNode<Label>("VBox/InfoSection/TrackInfo/Tags");
+        _lapRecordLabel = GetNode<Label>("VBox/InfoSection/TrackInfo/LapRecord");
         _trackImage = GetNode<TextureRect>("VBox/TrackImageContainer/TrackImage");
         _selectionIndicator = GetNode<ColorRect>("SelectionIndicator");
         _selectionBorder = GetNode<ColorRect>("SelectionBorder");
@@ -66,12 +70,37 @@ public partial class TrackCard : PanelContainer
             _nameLabel.Text = trackConfig.Name;
 
         if (_lengthLabel != null)
-            _lengthLabel.Text = $"Track ID: {trackConfig.Id.Substring(0, 8)}...";
+        {
+            var details = new List<string> { $"{trackConfig.LengthM / 1000f:0.00} km", $"{trackConfig.Corners} corners" };
+            if (!string.IsNullOrEmpty(trackConfig.Country))
+                details.Add(trackConfig.Country);
+            _lengthLabel.Text = string.Join(" · ", details);
+        }
+
+        if (_tagsLabel != null)
+        {
+            _tagsLabel.Text = string.Join(", ", trackConfig.Tags);
+            _tagsLabel.Visible = trackConfig.Tags.Length > 0;
+        }
+
+        if (_lapRecordLabel != null)
+        {
+            var record = trackConfig.LapRecord;
+            _lapRecordLabel.Text = record == null
+                ? "No lap record yet"
+                : $"Record {FormatLapTime(record.LapTimeMs)} · {record.DriverName} ({record.CarName})";
+        }
 
         // Load track preview image
         LoadTrackImage();
     }
 
+    private static string FormatLapTime(uint ms)
+    {
+        var time = TimeSpan.FromMilliseconds(ms);
+        return $"{(int)time.TotalMinutes}:{time.Seconds:00}.{time.Milliseconds:000}";
+    }
+
     private void LoadTrackImage()
     {
         if (_trackImage == null || _trackConfig == null) return;
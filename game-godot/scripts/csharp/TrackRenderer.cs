@@ -27,6 +27,7 @@ public partial class TrackRenderer : Node3D
 	// Car rendering for demo mode
 	private Node3D? _demoCarModel;
 	private string? _selectedCarId;
+	private int _demoCarLivery = -1;
 
 	// MessagePack options for terrain data deserialization
 	// Use StandardResolver for array-based MessagePack (matches Rust rmp_serde::to_vec)
@@ -579,6 +580,16 @@ public partial class TrackRenderer : Node3D
 		// Use the first AI car (there should only be one in demo mode)
 		var carState = aiCars[0];
 
+		if (carState.Livery != _demoCarLivery)
+		{
+			_demoCarLivery = carState.Livery;
+			var liveries = _network?.LastLobbyState?.CarConfigs.FirstOrDefault(c => c.Id == _selectedCarId)?.Liveries;
+			if (liveries != null && _demoCarLivery < liveries.Length)
+			{
+				CarModelCache.Instance.ApplyLivery(_demoCarModel, _selectedCarId!, liveries[_demoCarLivery]);
+			}
+		}
+
 		// Debug: Log player info to verify this is the AI
 		if (_telemetryCount <= 5)
 		{
@@ -1658,7 +1669,98 @@ public partial class TrackRenderer : Node3D
 
 		// Generate terrain mesh
 		_proceduralTerrain.GenerateTerrain(terrainData);
+		ApplySkyHint(terrainData.Preset.Sky);
+
+		var timeOfDay = SessionTimeOfDay();
+		if (terrainData.LightingAt(timeOfDay) is { } lighting)
+		{
+			ApplyLighting(lighting);
+		}
+		if (timeOfDay is TimeOfDay.Dusk or TimeOfDay.Night)
+		{
+			_proceduralTerrain.SpawnLights(terrainData.Lights);
+		}
 
 		GD.Print("✅ Procedural terrain rendered successfully");
 	}
+
+	/// <summary>
+	/// Time of day of the session being shown; noon outside a session.
+	/// </summary>
+	private TimeOfDay SessionTimeOfDay()
+	{
+		var sessions = _network?.LastLobbyState?.AvailableSessions;
+		var session = sessions == null ? null : Array.Find(sessions, s => s.Id == _currentSessionId);
+		return session?.TimeOfDay ?? TimeOfDay.Noon;
+	}
+
+	/// <summary>
+	/// Set the scene's sky, sun and ambient light for a time of day.
+	/// </summary>
+	private void ApplyLighting(Lighting lighting)
+	{
+		static Color ToColor(float[] rgb) => new(rgb[0], rgb[1], rgb[2]);
+
+		var environment = GetNodeOrNull<WorldEnvironment>("../WorldEnvironment")?.Environment;
+		if (environment?.Sky?.SkyMaterial is ProceduralSkyMaterial skyMaterial)
+		{
+			skyMaterial.SkyTopColor = ToColor(lighting.sky_top_color);
+			skyMaterial.SkyHorizonColor = ToColor(lighting.sky_horizon_color);
+		}
+		if (environment != null)
+		{
+			environment.AmbientLightSource = Godot.Environment.AmbientSource.Color;
+			environment.AmbientLightColor = ToColor(lighting.ambient_color);
+			environment.AmbientLightEnergy = lighting.ambient_energy;
+			if (environment.FogEnabled)
+			{
+				environment.FogLightColor = ToColor(lighting.sky_horizon_color);
+			}
+		}
+
+		var sun = GetNodeOrNull<DirectionalLight3D>("../DirectionalLight3D");
+		if (sun != null)
+		{
+			// The light shines away from the sun; bearings run clockwise from
+			// server +Y (Godot -Z), Godot's yaw counter-clockwise
+			sun.RotationDegrees = new Vector3(-lighting.sun_elevation_deg, 180.0f - lighting.sun_azimuth_deg, 0.0f);
+			sun.LightColor = ToColor(lighting.sun_color);
+			sun.LightEnergy = lighting.sun_energy;
+		}
+	}
+
+	/// <summary>
+	/// Set the scene's sky, fog and sun from the biome's sky hint.
+	/// </summary>
+	private void ApplySkyHint(SkyHint hint)
+	{
+		var environment = GetNodeOrNull<WorldEnvironment>("../WorldEnvironment")?.Environment;
+		if (environment?.Sky?.SkyMaterial is ProceduralSkyMaterial skyMaterial)
+		{
+			if (hint.top_color is { Length: 3 } top)
+			{
+				skyMaterial.SkyTopColor = new Color(top[0], top[1], top[2]);
+			}
+			if (hint.horizon_color is { Length: 3 } horizon)
+			{
+				skyMaterial.SkyHorizonColor = new Color(horizon[0], horizon[1], horizon[2]);
+			}
+		}
+
+		if (environment != null)
+		{
+			environment.FogEnabled = hint.fog_density > 0.0f;
+			// Server densities are per meter; the scene is scaled up by 50
+			environment.FogDensity = hint.fog_density / 50.0f;
+			environment.FogLightColor = environment.Sky?.SkyMaterial is ProceduralSkyMaterial sky
+				? sky.SkyHorizonColor
+				: environment.FogLightColor;
+		}
+
+		var sun = GetNodeOrNull<DirectionalLight3D>("../DirectionalLight3D");
+		if (sun != null)
+		{
+			sun.RotationDegrees = new Vector3(-hint.sun_elevation_deg, sun.RotationDegrees.Y, sun.RotationDegrees.Z);
+		}
+	}
 }
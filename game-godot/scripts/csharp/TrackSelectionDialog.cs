@@ -37,6 +37,7 @@ public partial class TrackSelectionDialog : Control
 
         _network = GetNode<NetworkClient>("/root/Network");
         _network.LobbyStateReceived += OnLobbyStateReceived;
+        _network.ContentDownloaded += OnContentDownloaded;
 
         // Load the track card scene
         _trackCardScene = GD.Load<PackedScene>("res://scenes/track_card.tscn");
@@ -85,6 +86,15 @@ public partial class TrackSelectionDialog : Control
         PopulateTracks(lobbyState);
     }
 
+    private void OnContentDownloaded(int kind, string id)
+    {
+        if (!IsInsideTree() || (ContentKind)kind != ContentKind.Track) return;
+        if (_network!.LastLobbyState == null) return;
+
+        // Check the tracks again now that one has been fetched
+        PopulateTracks(_network.LastLobbyState);
+    }
+
     private void PopulateTracks(LobbyStateMessage lobbyState)
     {
         if (!IsInsideTree()) return;
@@ -105,8 +115,29 @@ public partial class TrackSelectionDialog : Control
             return;
         }
 
-        _statusLabel!.Text = $"{_allTracks.Count} track(s) available";
-        _statusLabel.Modulate = Colors.Green;
+        // Flag tracks whose local file is missing or not the one the server loaded
+        var mismatchedTracks = 0;
+        foreach (var track in _allTracks)
+        {
+            if (track.SourcePath == null) continue;
+            if (!ContentCheck.Matches(track.ContentHash, ContentCheck.ForTrack(track.SourcePath)))
+            {
+                ContentCheck.WarnMismatch("track", track.Name);
+                mismatchedTracks++;
+                _ = _network!.DownloadContentAsync(ContentKind.Track, track.Id);
+            }
+        }
+
+        if (mismatchedTracks > 0)
+        {
+            _statusLabel!.Text = $"{_allTracks.Count} track(s) available, downloading {mismatchedTracks} missing or changed track(s) from the server";
+            _statusLabel.Modulate = Colors.Orange;
+        }
+        else
+        {
+            _statusLabel!.Text = $"{_allTracks.Count} track(s) available";
+            _statusLabel.Modulate = Colors.Green;
+        }
 
         // Create track list items
         CreateTrackCards();
@@ -200,6 +231,7 @@ public partial class TrackSelectionDialog : Control
         if (_network != null)
         {
             _network.LobbyStateReceived -= OnLobbyStateReceived;
+            _network.ContentDownloaded -= OnContentDownloaded;
         }
 
         // Clean up card event handlers
@@ -0,0 +1,10 @@
+// Generates the gRPC control plane from proto/control.proto. protox compiles the
+// proto in Rust, so building does not need protoc installed.
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=proto/control.proto");
+    let descriptors = protox::compile(["proto/control.proto"], ["proto"])?;
+    tonic_prost_build::configure()
+        .build_client(false)
+        .compile_fds(descriptors)?;
+    Ok(())
+}
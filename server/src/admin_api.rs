@@ -0,0 +1,489 @@
+//! Authenticated HTTP admin API, so ops tooling can manage the server without
+//! speaking the game protocol.
+//!
+//! Every request needs `Authorization: Bearer <token>` with one of the
+//! server's `admin_tokens`. The HTTP side only parses and authorizes; each
+//! request becomes an `AdminCommand` that the server carries out against its
+//! state and answers through the `AdminRequest`.
+//!
+//! - `GET /admin/players` - connected players
+//! - `POST /admin/players/{player_id}/kick` - disconnect a player, body `{"reason": "..."}` (optional)
+//! - `GET /admin/sessions` - running sessions
+//! - `POST /admin/sessions` - open a free practice session, body `{"track": "...", "max_players": 8}`
+//! - `GET /admin/sessions/{session_id}` - one session
+//! - `PATCH /admin/sessions/{session_id}` - change settings, body `{"lap_limit": 10, "max_players": 12}`
+//! - `DELETE /admin/sessions/{session_id}` - close a session
+//! - `POST /admin/sessions/{session_id}/transition` - force `{"transition": "start"}` or `"finish"`
+//! - `POST /admin/announcements` - message every player, body `{"message": "..."}`
+//! - `POST /admin/tracks` - generate a circuit and load it, body `{"name": "...", "params": {"length_m": 3500, "corners": 10}}`
+//! - `POST /admin/drain` - refuse new sessions and report not ready
+//! - `POST /admin/shutdown` - shut the server down
+//! - `GET /admin/stats` - player and session counts, dropped messages
+
+use crate::data::*;
+use crate::procgen::circuit::CircuitParams;
+use crate::tick_lag::TickLagSnapshot;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{body::Incoming, Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{error, info};
+
+/// Largest request body accepted
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// Requests queued for the server before callers get 503
+const ADMIN_REQUEST_CHANNEL_SIZE: usize = 32;
+
+/// What an admin asked the server to do
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdminCommand {
+    ListPlayers,
+    KickPlayer { player_id: PlayerId, reason: Option<String> },
+    ListSessions,
+    GetSession { session_id: SessionId },
+    CreateSession { create: SessionCreate },
+    UpdateSession { session_id: SessionId, update: SessionUpdate },
+    CloseSession { session_id: SessionId },
+    TransitionSession { session_id: SessionId, transition: SessionTransition },
+    Announce { message: String },
+    GenerateTrack { generate: TrackGenerate },
+    Drain,
+    Shutdown,
+    Stats,
+}
+
+/// A free practice session opened by `POST /admin/sessions`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SessionCreate {
+    /// Track ID or name
+    pub track: String,
+    pub max_players: u8,
+}
+
+/// A state change forced on a session, whatever its host does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionTransition {
+    /// Start the countdown of a session in the lobby
+    Start,
+    /// End a race now, classifying cars still running in their current order
+    Finish,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TransitionBody {
+    transition: SessionTransition,
+}
+
+/// Settings changed by `PATCH /admin/sessions/{session_id}`; absent fields stay
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SessionUpdate {
+    /// Only while the session is still in the lobby
+    pub lap_limit: Option<u8>,
+    /// Not below the drivers already in the session
+    pub max_players: Option<u8>,
+    /// Balance of Performance by car ID or name, only while the session is in
+    /// the lobby. Replaces what the car had in this session; `{}` clears it
+    pub balance: Option<HashMap<String, BalanceOfPerformance>>,
+}
+
+/// A circuit generated by `POST /admin/tracks`, saved and loaded at once
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TrackGenerate {
+    pub name: String,
+    /// Absent parameters take their defaults
+    #[serde(default)]
+    pub params: CircuitParams,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KickBody {
+    reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AnnouncementBody {
+    message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AdminPlayer {
+    pub player_id: PlayerId,
+    pub name: String,
+    pub address: Option<IpAddr>,
+    pub admin: bool,
+    /// Session the player drives or spectates in
+    pub session_id: Option<SessionId>,
+    pub spectating: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AdminSession {
+    pub session_id: SessionId,
+    pub track_name: String,
+    pub host_name: String,
+    pub session_kind: SessionKind,
+    pub state: SessionState,
+    pub game_mode: GameMode,
+    pub drivers: usize,
+    pub spectators: usize,
+    pub max_players: u8,
+    pub lap_limit: u8,
+    pub server_tick: u32,
+    /// Balance of Performance set for this session, by car name
+    pub balance: BTreeMap<String, BalanceOfPerformance>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AdminTrack {
+    pub track_id: TrackConfigId,
+    pub name: String,
+    pub length_m: f32,
+    /// Track file, relative to the content folder
+    pub source_path: Option<String>,
+    /// Seed the layout was generated from
+    pub seed: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AdminStats {
+    pub uptime_s: u64,
+    pub player_count: usize,
+    pub session_count: usize,
+    pub draining: bool,
+    pub tcp_messages_dropped: u64,
+    pub udp_messages_dropped: u64,
+    /// Whether the game loop keeps up with `tick_rate_hz`
+    pub tick_lag: TickLagSnapshot,
+    pub sessions: Vec<AdminSession>,
+}
+
+/// Successful answers, sent as JSON
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum AdminResponse {
+    Players(Vec<AdminPlayer>),
+    Sessions(Vec<AdminSession>),
+    Session(AdminSession),
+    Stats(AdminStats),
+    Track(AdminTrack),
+    Done { message: String },
+}
+
+/// A failed command, sent as `{"error": message}` with `status`
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdminError {
+    pub status: StatusCode,
+    pub message: String,
+}
+
+impl AdminError {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self { status, message: message.into() }
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::CONFLICT, message)
+    }
+}
+
+pub type AdminResult = Result<AdminResponse, AdminError>;
+
+/// A command waiting for the server, answered with `respond`
+#[derive(Debug)]
+pub struct AdminRequest {
+    pub command: AdminCommand,
+    reply: oneshot::Sender<AdminResult>,
+}
+
+impl AdminRequest {
+    pub fn respond(self, result: AdminResult) {
+        // The caller may have hung up; nothing left to tell
+        let _ = self.reply.send(result);
+    }
+}
+
+/// The HTTP side of the admin API
+#[derive(Clone)]
+pub struct AdminApi {
+    tokens: Arc<Vec<String>>,
+    requests: mpsc::Sender<AdminRequest>,
+}
+
+impl AdminApi {
+    /// An API accepting `tokens`, and the queue its commands arrive on
+    pub fn new(tokens: Vec<String>) -> (Self, mpsc::Receiver<AdminRequest>) {
+        let (requests, receiver) = mpsc::channel(ADMIN_REQUEST_CHANNEL_SIZE);
+        (Self { tokens: Arc::new(tokens), requests }, receiver)
+    }
+
+    /// Whether an `Authorization` header value carries one of the admin tokens
+    pub fn authorized(&self, authorization: Option<&str>) -> bool {
+        let Some(token) = authorization.and_then(|value| value.strip_prefix("Bearer ")) else {
+            return false;
+        };
+        !token.is_empty() && self.tokens.iter().any(|admin| constant_time_eq(admin.as_bytes(), token.as_bytes()))
+    }
+
+    /// Hand a command to the server and wait for its answer
+    pub async fn execute(&self, command: AdminCommand) -> AdminResult {
+        let (reply, answer) = oneshot::channel();
+        let unavailable = || AdminError::new(StatusCode::SERVICE_UNAVAILABLE, "Server is not accepting admin commands");
+        self.requests
+            .try_send(AdminRequest { command, reply })
+            .map_err(|_| unavailable())?;
+        answer.await.map_err(|_| unavailable())?
+    }
+}
+
+/// Compare tokens without leaking how much of a guess matched
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// The command for a request, or why it is not one
+pub fn route(method: &Method, path: &str, body: &[u8]) -> Result<AdminCommand, AdminError> {
+    let Some(rest) = path.strip_prefix("/admin") else {
+        return Err(AdminError::not_found("Not Found"));
+    };
+    let segments: Vec<&str> = rest.split('/').filter(|s| !s.is_empty()).collect();
+    let player_id = |id: &str| id.parse::<PlayerId>().map_err(|_| AdminError::not_found(format!("Invalid player id {}", id)));
+    let session_id = |id: &str| id.parse::<SessionId>().map_err(|_| AdminError::not_found(format!("Invalid session id {}", id)));
+
+    match (method, segments.as_slice()) {
+        (&Method::GET, ["players"]) => Ok(AdminCommand::ListPlayers),
+        (&Method::POST, ["players", id, "kick"]) => {
+            let reason = if body.is_empty() { None } else { parse_body::<KickBody>(body)?.reason };
+            Ok(AdminCommand::KickPlayer { player_id: player_id(id)?, reason })
+        }
+        (&Method::GET, ["sessions"]) => Ok(AdminCommand::ListSessions),
+        (&Method::POST, ["sessions"]) => Ok(AdminCommand::CreateSession { create: parse_body(body)? }),
+        (&Method::GET, ["sessions", id]) => Ok(AdminCommand::GetSession { session_id: session_id(id)? }),
+        (&Method::PATCH, ["sessions", id]) => Ok(AdminCommand::UpdateSession {
+            session_id: session_id(id)?,
+            update: parse_body(body)?,
+        }),
+        (&Method::DELETE, ["sessions", id]) => Ok(AdminCommand::CloseSession { session_id: session_id(id)? }),
+        (&Method::POST, ["sessions", id, "transition"]) => Ok(AdminCommand::TransitionSession {
+            session_id: session_id(id)?,
+            transition: parse_body::<TransitionBody>(body)?.transition,
+        }),
+        (&Method::POST, ["announcements"]) => {
+            let message = parse_body::<AnnouncementBody>(body)?.message;
+            if message.trim().is_empty() {
+                return Err(AdminError::bad_request("Announcement message is empty"));
+            }
+            Ok(AdminCommand::Announce { message })
+        }
+        (&Method::POST, ["tracks"]) => Ok(AdminCommand::GenerateTrack { generate: parse_body(body)? }),
+        (&Method::POST, ["drain"]) => Ok(AdminCommand::Drain),
+        (&Method::POST, ["shutdown"]) => Ok(AdminCommand::Shutdown),
+        (&Method::GET, ["stats"]) => Ok(AdminCommand::Stats),
+        (
+            _,
+            ["players"] | ["players", _, "kick"] | ["sessions"] | ["sessions", _] | ["sessions", _, "transition"]
+            | ["announcements"] | ["tracks"] | ["drain"] | ["shutdown"] | ["stats"],
+        ) => {
+            Err(AdminError::new(StatusCode::METHOD_NOT_ALLOWED, "Method Not Allowed"))
+        }
+        _ => Err(AdminError::not_found("Not Found")),
+    }
+}
+
+fn parse_body<T: for<'de> Deserialize<'de>>(body: &[u8]) -> Result<T, AdminError> {
+    serde_json::from_slice(body).map_err(|e| AdminError::bad_request(format!("Invalid request body: {}", e)))
+}
+
+async fn handle_admin(req: Request<Incoming>, api: AdminApi) -> Result<Response<Full<Bytes>>, hyper::Error> {
+    let authorization = req.headers().get(hyper::header::AUTHORIZATION).and_then(|value| value.to_str().ok());
+    if !api.authorized(authorization) {
+        return Ok(error_response(&AdminError::new(StatusCode::UNAUTHORIZED, "Admin token required")));
+    }
+
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    let body = match Limited::new(req.into_body(), MAX_BODY_BYTES).collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(_) => {
+            return Ok(error_response(&AdminError::new(StatusCode::PAYLOAD_TOO_LARGE, "Request body too large")));
+        }
+    };
+
+    let result = match route(&method, &path, &body) {
+        Ok(command) => {
+            info!("Admin API: {} {}", method, path);
+            api.execute(command).await
+        }
+        Err(e) => Err(e),
+    };
+    Ok(match result {
+        Ok(response) => json_response(StatusCode::OK, &response),
+        Err(e) => error_response(&e),
+    })
+}
+
+fn json_response(status: StatusCode, data: &impl Serialize) -> Response<Full<Bytes>> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Full::new(Bytes::from(serde_json::to_vec(data).unwrap_or_default())))
+        .unwrap()
+}
+
+fn error_response(error: &AdminError) -> Response<Full<Bytes>> {
+    json_response(error.status, &serde_json::json!({ "error": error.message }))
+}
+
+/// Serve the admin API on `bind_addr`
+pub async fn run_admin_api_server(bind_addr: String, api: AdminApi) -> Result<(), Box<dyn std::error::Error>> {
+    let listener = TcpListener::bind(&bind_addr).await?;
+    info!("Admin API listening on {}", bind_addr);
+
+    loop {
+        let (stream, _) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                error!("Failed to accept admin API connection: {}", e);
+                continue;
+            }
+        };
+
+        let io = TokioIo::new(stream);
+        let api = api.clone();
+
+        tokio::spawn(async move {
+            let service = service_fn(move |req| handle_admin(req, api.clone()));
+
+            if let Err(err) = http1::Builder::new().serve_connection(io, service).await {
+                error!("Error serving admin API connection: {}", err);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_routes() {
+        let player_id = PlayerId::new_v4();
+        let session_id = SessionId::new_v4();
+
+        assert_eq!(route(&Method::GET, "/admin/players", b""), Ok(AdminCommand::ListPlayers));
+        assert_eq!(
+            route(&Method::POST, &format!("/admin/players/{}/kick", player_id), b""),
+            Ok(AdminCommand::KickPlayer { player_id, reason: None })
+        );
+        assert_eq!(
+            route(&Method::POST, &format!("/admin/players/{}/kick", player_id), br#"{"reason":"AFK"}"#),
+            Ok(AdminCommand::KickPlayer { player_id, reason: Some("AFK".to_string()) })
+        );
+        assert_eq!(
+            route(&Method::PATCH, &format!("/admin/sessions/{}", session_id), br#"{"lap_limit":12}"#),
+            Ok(AdminCommand::UpdateSession {
+                session_id,
+                update: SessionUpdate { lap_limit: Some(12), ..Default::default() },
+            })
+        );
+        assert_eq!(
+            route(
+                &Method::PATCH,
+                &format!("/admin/sessions/{}", session_id),
+                br#"{"balance":{"Formula":{"ballast_kg":20,"power_multiplier":0.95}}}"#
+            ),
+            Ok(AdminCommand::UpdateSession {
+                session_id,
+                update: SessionUpdate {
+                    balance: Some(HashMap::from([(
+                        "Formula".to_string(),
+                        BalanceOfPerformance { ballast_kg: 20.0, power_multiplier: 0.95, fuel_capacity_liters: None },
+                    )])),
+                    ..Default::default()
+                },
+            })
+        );
+        assert_eq!(
+            route(&Method::POST, "/admin/sessions", br#"{"track":"Monza","max_players":12}"#),
+            Ok(AdminCommand::CreateSession {
+                create: SessionCreate { track: "Monza".to_string(), max_players: 12 },
+            })
+        );
+        assert_eq!(route(&Method::GET, "/admin/stats", b""), Ok(AdminCommand::Stats));
+        assert_eq!(
+            route(&Method::POST, "/admin/tracks", br#"{"name":"Test Ring","params":{"corners":8,"seed":42}}"#),
+            Ok(AdminCommand::GenerateTrack {
+                generate: TrackGenerate {
+                    name: "Test Ring".to_string(),
+                    params: CircuitParams { corners: 8, seed: Some(42), ..Default::default() },
+                },
+            })
+        );
+        assert_eq!(
+            route(&Method::POST, &format!("/admin/sessions/{}/transition", session_id), br#"{"transition":"finish"}"#),
+            Ok(AdminCommand::TransitionSession { session_id, transition: SessionTransition::Finish })
+        );
+        assert_eq!(
+            route(&Method::DELETE, &format!("/admin/sessions/{}/", session_id), b""),
+            Ok(AdminCommand::CloseSession { session_id })
+        );
+        assert_eq!(
+            route(&Method::POST, "/admin/announcements", br#"{"message":"Restart in 5 minutes"}"#),
+            Ok(AdminCommand::Announce { message: "Restart in 5 minutes".to_string() })
+        );
+
+        let status = |method: &Method, path: &str, body: &[u8]| route(method, path, body).unwrap_err().status;
+        assert_eq!(status(&Method::GET, "/admin/shutdown", b""), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(status(&Method::GET, "/admin/nothing", b""), StatusCode::NOT_FOUND);
+        assert_eq!(status(&Method::DELETE, "/admin/sessions/not-a-uuid", b""), StatusCode::NOT_FOUND);
+        assert_eq!(status(&Method::PATCH, &format!("/admin/sessions/{}", session_id), br#"{"laps":3}"#), StatusCode::BAD_REQUEST);
+        assert_eq!(status(&Method::POST, "/admin/announcements", br#"{"message":" "}"#), StatusCode::BAD_REQUEST);
+        assert_eq!(status(&Method::POST, "/admin/tracks", br#"{"name":"X","params":{"turns":8}}"#), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_authorization_and_execution() {
+        let (api, mut requests) = AdminApi::new(vec!["secret-token".to_string()]);
+        assert!(api.authorized(Some("Bearer secret-token")));
+        assert!(!api.authorized(Some("Bearer secret-tokem")));
+        assert!(!api.authorized(Some("secret-token")));
+        assert!(!api.authorized(None));
+        let (no_tokens, _) = AdminApi::new(Vec::new());
+        assert!(!no_tokens.authorized(Some("Bearer ")));
+
+        tokio::spawn(async move {
+            while let Some(request) = requests.recv().await {
+                let result = match request.command {
+                    AdminCommand::Drain => Ok(AdminResponse::Done { message: "Draining".to_string() }),
+                    _ => Err(AdminError::conflict("Not now")),
+                };
+                request.respond(result);
+            }
+        });
+        assert!(matches!(api.execute(AdminCommand::Drain).await, Ok(AdminResponse::Done { .. })));
+        assert_eq!(api.execute(AdminCommand::Shutdown).await.unwrap_err().status, StatusCode::CONFLICT);
+    }
+}
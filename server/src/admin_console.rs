@@ -0,0 +1,642 @@
+//! Interactive admin console for operators on the server's own machine.
+//!
+//! Reads commands from the server's terminal (`[admin_console] stdin`) or from
+//! a unix socket that `apexsim-server --console <path>` connects to. Commands
+//! become the same `AdminCommand`s as the HTTP admin API; access to the
+//! terminal or the owner-only socket stands in for an admin token.
+//!
+//! Socket protocol: one command per line, answered by output lines and an
+//! empty line. A line starting with a tab asks for the completions of the
+//! rest of it instead, one per line.
+
+use crate::admin_api::{
+    AdminApi, AdminCommand, AdminPlayer, AdminResponse, AdminSession, SessionCreate, SessionTransition, SessionUpdate,
+    TrackGenerate,
+};
+use crate::data::*;
+use crate::procgen::circuit::CircuitParams;
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use tracing::info;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+/// Handle to the server's log filter, for `log <filter>`
+pub type LogFilterHandle = reload::Handle<EnvFilter, Registry>;
+
+const PROMPT: &str = "apexsim> ";
+
+/// Console commands, their arguments and what they do
+const COMMANDS: &[(&str, &str, &str)] = &[
+    ("players", "", "list connected players"),
+    ("kick", "<player_id> [reason]", "disconnect a player"),
+    ("sessions", "", "list running sessions"),
+    ("session", "<session_id>", "show one session"),
+    ("create", "<track> <max_players>", "open a free practice session"),
+    ("close", "<session_id>", "close a session"),
+    ("start", "<session_id>", "start the countdown of a session in the lobby"),
+    ("finish", "<session_id>", "end a race now"),
+    (
+        "bop",
+        "<session_id> <car> [ballast=<kg>] [power=<x>] [fuel=<l>]",
+        "set a car's Balance of Performance in a lobby; none clears it",
+    ),
+    (
+        "gentrack",
+        "<name> [length=<m>] [corners=<n>] [straights=<0-0.9>] [elevation=<m>] [width=<m>] [seed=<n>] [env=<preset|none>]",
+        "generate a circuit and load it",
+    ),
+    ("say", "<message>", "message every player"),
+    ("drain", "", "refuse new sessions and report not ready"),
+    ("shutdown", "", "shut the server down"),
+    ("stats", "", "player and session counts, dropped messages"),
+    ("log", "[filter]", "show or set the log filter, e.g. `debug` or `info,apexsim_server::transport=trace`"),
+    ("help", "", "list commands"),
+    ("quit", "", "leave the console"),
+];
+
+const LOG_LEVELS: &[&str] = &["trace", "debug", "info", "warn", "error"];
+
+/// A parsed console line
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConsoleCommand {
+    Admin(AdminCommand),
+    /// Show the log filter, or replace it
+    Log(Option<String>),
+    Help,
+    Quit,
+}
+
+/// Parse a console line; `Ok(None)` for a blank one
+pub fn parse(line: &str) -> Result<Option<ConsoleCommand>, String> {
+    let line = line.trim();
+    let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let rest = rest.trim();
+    let args: Vec<&str> = rest.split_whitespace().collect();
+    let session_id = || match args.as_slice() {
+        [id] => id.parse::<SessionId>().map_err(|_| format!("Invalid session id {}", id)),
+        _ => Err(format!("usage: {} <session_id>", name)),
+    };
+    let no_args = |command: ConsoleCommand| {
+        if args.is_empty() {
+            Ok(command)
+        } else {
+            Err(format!("{} takes no arguments", name))
+        }
+    };
+    let command = match name {
+        "" => return Ok(None),
+        "players" => no_args(ConsoleCommand::Admin(AdminCommand::ListPlayers))?,
+        "kick" => {
+            let (id, reason) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            if id.is_empty() {
+                return Err("usage: kick <player_id> [reason]".to_string());
+            }
+            let player_id = id.parse::<PlayerId>().map_err(|_| format!("Invalid player id {}", id))?;
+            let reason = Some(reason.trim().to_string()).filter(|reason| !reason.is_empty());
+            ConsoleCommand::Admin(AdminCommand::KickPlayer { player_id, reason })
+        }
+        "sessions" => no_args(ConsoleCommand::Admin(AdminCommand::ListSessions))?,
+        "session" => ConsoleCommand::Admin(AdminCommand::GetSession { session_id: session_id()? }),
+        "create" => match args.as_slice() {
+            [track, max_players] => {
+                let max_players = max_players
+                    .parse()
+                    .map_err(|_| format!("Invalid max_players {}", max_players))?;
+                let create = SessionCreate { track: track.to_string(), max_players };
+                ConsoleCommand::Admin(AdminCommand::CreateSession { create })
+            }
+            _ => return Err("usage: create <track> <max_players>".to_string()),
+        },
+        "close" => ConsoleCommand::Admin(AdminCommand::CloseSession { session_id: session_id()? }),
+        "start" | "finish" => {
+            let transition = if name == "start" { SessionTransition::Start } else { SessionTransition::Finish };
+            ConsoleCommand::Admin(AdminCommand::TransitionSession { session_id: session_id()?, transition })
+        }
+        "bop" => {
+            let usage = || "usage: bop <session_id> <car> [ballast=<kg>] [power=<x>] [fuel=<l>]".to_string();
+            let [id, car, settings @ ..] = args.as_slice() else {
+                return Err(usage());
+            };
+            let session_id = id.parse::<SessionId>().map_err(|_| format!("Invalid session id {}", id))?;
+            let mut bop = BalanceOfPerformance::default();
+            for setting in settings {
+                let (key, value) = setting.split_once('=').ok_or_else(usage)?;
+                let value = value.parse::<f32>().map_err(|_| format!("Invalid {} {}", key, value))?;
+                match key {
+                    "ballast" => bop.ballast_kg = value,
+                    "power" => bop.power_multiplier = value,
+                    "fuel" => bop.fuel_capacity_liters = Some(value),
+                    _ => return Err(usage()),
+                }
+            }
+            let update = SessionUpdate {
+                balance: Some([(car.to_string(), bop)].into_iter().collect()),
+                ..Default::default()
+            };
+            ConsoleCommand::Admin(AdminCommand::UpdateSession { session_id, update })
+        }
+        "gentrack" => {
+            let usage = || {
+                "usage: gentrack <name> [length=<m>] [corners=<n>] [straights=<0-0.9>] [elevation=<m>] [width=<m>] \
+                 [seed=<n>] [env=<preset|none>]"
+                    .to_string()
+            };
+            let (settings, name): (Vec<&str>, Vec<&str>) = args.iter().partition(|arg| arg.contains('='));
+            if name.is_empty() {
+                return Err(usage());
+            }
+            let mut params = CircuitParams::default();
+            for setting in settings {
+                let (key, value) = setting.split_once('=').ok_or_else(usage)?;
+                let invalid = || format!("Invalid {} {}", key, value);
+                match key {
+                    "length" => params.length_m = value.parse().map_err(|_| invalid())?,
+                    "corners" => params.corners = value.parse().map_err(|_| invalid())?,
+                    "straights" => params.straights = value.parse().map_err(|_| invalid())?,
+                    "elevation" => params.elevation_variance_m = value.parse().map_err(|_| invalid())?,
+                    "width" => params.width_m = value.parse().map_err(|_| invalid())?,
+                    "seed" => params.seed = Some(value.parse().map_err(|_| invalid())?),
+                    "env" => params.environment = Some(value.to_string()).filter(|env| env != "none"),
+                    _ => return Err(usage()),
+                }
+            }
+            let generate = TrackGenerate { name: name.join(" "), params };
+            ConsoleCommand::Admin(AdminCommand::GenerateTrack { generate })
+        }
+        "say" => {
+            if rest.is_empty() {
+                return Err("usage: say <message>".to_string());
+            }
+            ConsoleCommand::Admin(AdminCommand::Announce { message: rest.to_string() })
+        }
+        "drain" => no_args(ConsoleCommand::Admin(AdminCommand::Drain))?,
+        "shutdown" => no_args(ConsoleCommand::Admin(AdminCommand::Shutdown))?,
+        "stats" => no_args(ConsoleCommand::Admin(AdminCommand::Stats))?,
+        "log" => ConsoleCommand::Log(Some(rest.to_string()).filter(|filter| !filter.is_empty())),
+        "help" => no_args(ConsoleCommand::Help)?,
+        "quit" | "exit" => no_args(ConsoleCommand::Quit)?,
+        _ => return Err(format!("Unknown command {}; try `help`", name)),
+    };
+    Ok(Some(command))
+}
+
+/// Completions for the last word of `line`, given the known session and player IDs
+pub fn completions(line: &str, session_ids: &[String], player_ids: &[String]) -> Vec<String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let (done, word) = if line.ends_with(char::is_whitespace) || line.is_empty() {
+        (words.as_slice(), "")
+    } else {
+        (&words[..words.len() - 1], words[words.len() - 1])
+    };
+    let candidates: Vec<String> = match done {
+        [] => COMMANDS.iter().map(|(name, _, _)| name.to_string()).collect(),
+        ["session" | "close" | "start" | "finish" | "bop"] => session_ids.to_vec(),
+        ["kick"] => player_ids.to_vec(),
+        ["log"] => LOG_LEVELS.iter().map(|level| level.to_string()).collect(),
+        _ => Vec::new(),
+    };
+    candidates.into_iter().filter(|candidate| candidate.starts_with(word)).collect()
+}
+
+/// Where completions are inserted: the start of the last word
+fn word_start(line: &str) -> usize {
+    line.len() - line.rsplit(char::is_whitespace).next().unwrap_or("").len()
+}
+
+/// Runs console lines against the admin API
+#[derive(Clone)]
+pub struct AdminConsole {
+    api: AdminApi,
+    log_filter: Option<LogFilterHandle>,
+}
+
+impl AdminConsole {
+    pub fn new(api: AdminApi, log_filter: Option<LogFilterHandle>) -> Self {
+        Self { api, log_filter }
+    }
+
+    /// Run one line and return its output; `None` when the user leaves
+    pub async fn execute(&self, line: &str) -> Option<String> {
+        let output = match parse(line) {
+            Ok(None) => String::new(),
+            Ok(Some(ConsoleCommand::Quit)) => return None,
+            Ok(Some(ConsoleCommand::Help)) => help(),
+            Ok(Some(ConsoleCommand::Log(filter))) => self.log(filter),
+            Ok(Some(ConsoleCommand::Admin(command))) => match self.api.execute(command).await {
+                Ok(response) => format_response(&response),
+                Err(e) => format!("error: {}", e.message),
+            },
+            Err(message) => format!("error: {}", message),
+        };
+        Some(output)
+    }
+
+    /// Completions for the last word of `line`
+    pub async fn complete(&self, line: &str) -> Vec<String> {
+        let session_ids = match self.api.execute(AdminCommand::ListSessions).await {
+            Ok(AdminResponse::Sessions(sessions)) => sessions.iter().map(|s| s.session_id.to_string()).collect(),
+            _ => Vec::new(),
+        };
+        let player_ids = match self.api.execute(AdminCommand::ListPlayers).await {
+            Ok(AdminResponse::Players(players)) => players.iter().map(|p| p.player_id.to_string()).collect(),
+            _ => Vec::new(),
+        };
+        completions(line, &session_ids, &player_ids)
+    }
+
+    fn log(&self, filter: Option<String>) -> String {
+        let Some(handle) = &self.log_filter else {
+            return "error: The log filter cannot be changed on this server".to_string();
+        };
+        let Some(filter) = filter else {
+            return handle
+                .with_current(|current| format!("log filter: {}", current))
+                .unwrap_or_else(|e| format!("error: {}", e));
+        };
+        let parsed = match EnvFilter::try_new(&filter) {
+            Ok(parsed) => parsed,
+            Err(e) => return format!("error: Invalid log filter {}: {}", filter, e),
+        };
+        match handle.reload(parsed) {
+            Ok(()) => {
+                info!("Log filter set to {} from the admin console", filter);
+                format!("log filter: {}", filter)
+            }
+            Err(e) => format!("error: {}", e),
+        }
+    }
+}
+
+fn help() -> String {
+    COMMANDS
+        .iter()
+        .map(|(name, args, about)| format!("  {:<32} {}", format!("{} {}", name, args).trim_end(), about))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn format_response(response: &AdminResponse) -> String {
+    match response {
+        AdminResponse::Players(players) if players.is_empty() => "No players connected".to_string(),
+        AdminResponse::Players(players) => players.iter().map(format_player).collect::<Vec<_>>().join("\n"),
+        AdminResponse::Sessions(sessions) if sessions.is_empty() => "No sessions".to_string(),
+        AdminResponse::Sessions(sessions) => sessions.iter().map(format_session).collect::<Vec<_>>().join("\n"),
+        AdminResponse::Session(session) => format_session(session),
+        AdminResponse::Stats(stats) => {
+            let lag = &stats.tick_lag;
+            let mut lines = vec![
+                format!(
+                    "up {}s, {} players, {} sessions{}, dropped {} tcp / {} udp messages",
+                    stats.uptime_s,
+                    stats.player_count,
+                    stats.session_count,
+                    if stats.draining { " (draining)" } else { "" },
+                    stats.tcp_messages_dropped,
+                    stats.udp_messages_dropped,
+                ),
+                format!(
+                    "{} ticks, {} over budget, {} caught up, {} skipped, slowest {} us, lag {} us",
+                    lag.ticks, lag.overruns, lag.catch_up_ticks, lag.skipped_ticks, lag.max_tick_us, lag.lag_us,
+                ),
+            ];
+            lines.extend(stats.sessions.iter().map(format_session));
+            lines.join("\n")
+        }
+        AdminResponse::Track(track) => format!(
+            "{}  {} generated, {:.0} m, seed {}, saved as {}",
+            track.track_id,
+            track.name,
+            track.length_m,
+            track.seed.map_or_else(|| "-".to_string(), |seed| seed.to_string()),
+            track.source_path.as_deref().unwrap_or("-"),
+        ),
+        AdminResponse::Done { message } => message.clone(),
+    }
+}
+
+fn format_player(player: &AdminPlayer) -> String {
+    let address = player.address.map_or_else(|| "-".to_string(), |address| address.to_string());
+    let session = player.session_id.map_or_else(|| "-".to_string(), |id| id.to_string());
+    format!(
+        "{}  {:<20} {:<15} session {}{}{}",
+        player.player_id,
+        player.name,
+        address,
+        session,
+        if player.spectating { " (spectating)" } else { "" },
+        if player.admin { " [admin]" } else { "" },
+    )
+}
+
+fn format_session(session: &AdminSession) -> String {
+    let mut line = format!(
+        "{}  {:<20} {:?} {:?}, {}/{} drivers, {} spectators, {} laps, host {}",
+        session.session_id,
+        session.track_name,
+        session.session_kind,
+        session.state,
+        session.drivers,
+        session.max_players,
+        session.spectators,
+        session.lap_limit,
+        session.host_name,
+    );
+    for (car, bop) in &session.balance {
+        line.push_str(&format!("\n    BoP {}: {:+} kg, power x{}", car, bop.ballast_kg, bop.power_multiplier));
+        if let Some(fuel) = bop.fuel_capacity_liters {
+            line.push_str(&format!(", {} l fuel", fuel));
+        }
+    }
+    line
+}
+
+/// Line editor helper completing with a caller-supplied function
+struct ConsoleHelper<F> {
+    complete: F,
+}
+
+impl<F: Fn(&str) -> Vec<String>> Completer for ConsoleHelper<F> {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        let line = &line[..pos];
+        Ok((word_start(line), (self.complete)(line)))
+    }
+}
+
+impl<F> Hinter for ConsoleHelper<F> {
+    type Hint = String;
+}
+
+impl<F> Highlighter for ConsoleHelper<F> {}
+
+impl<F> Validator for ConsoleHelper<F> {}
+
+impl<F: Fn(&str) -> Vec<String>> Helper for ConsoleHelper<F> {}
+
+/// Read lines with history and tab completion until `execute` returns `None`,
+/// end of input or Ctrl-C
+fn run_editor(
+    mut execute: impl FnMut(&str) -> Option<String>,
+    complete: impl Fn(&str) -> Vec<String>,
+) -> rustyline::Result<()> {
+    let mut editor = Editor::new()?;
+    editor.set_helper(Some(ConsoleHelper { complete }));
+    loop {
+        let line = match editor.readline(PROMPT) {
+            Ok(line) => line,
+            Err(ReadlineError::Eof | ReadlineError::Interrupted) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        if !line.trim().is_empty() {
+            editor.add_history_entry(line.as_str())?;
+        }
+        match execute(&line) {
+            Some(output) if output.is_empty() => {}
+            Some(output) => println!("{}", output),
+            None => return Ok(()),
+        }
+    }
+}
+
+/// Read console commands from the server's terminal on a thread of its own;
+/// leaving the console leaves the server running
+pub fn spawn_stdin_console(console: AdminConsole) {
+    let runtime = tokio::runtime::Handle::current();
+    std::thread::spawn(move || {
+        let result = run_editor(
+            |line| runtime.block_on(console.execute(line)),
+            |line| runtime.block_on(console.complete(line)),
+        );
+        match result {
+            Ok(()) => info!("Admin console on stdin closed"),
+            Err(e) => tracing::warn!("Admin console on stdin failed: {}", e),
+        }
+    });
+}
+
+/// Serve the console on a unix socket only the server's user may open
+#[cfg(unix)]
+pub async fn run_console_socket(path: String, console: AdminConsole) -> std::io::Result<()> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    // A socket left behind by an earlier run would make bind fail
+    if let Ok(metadata) = std::fs::symlink_metadata(&path) {
+        if !metadata.file_type().is_socket() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("{} exists and is not a socket", path),
+            ));
+        }
+        std::fs::remove_file(&path)?;
+    }
+    let listener = tokio::net::UnixListener::bind(&path)?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    info!("Admin console listening on {}", path);
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let console = console.clone();
+        tokio::spawn(async move {
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let output = match line.strip_prefix('\t') {
+                    Some(partial) => console.complete(partial).await.join("\n"),
+                    None => match console.execute(&line).await {
+                        Some(output) => output,
+                        None => break,
+                    },
+                };
+                let reply = if output.is_empty() { "\n".to_string() } else { format!("{}\n\n", output) };
+                if writer.write_all(reply.as_bytes()).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+/// Send one request over the console socket and read its answer;
+/// `None` once the server has closed the connection
+#[cfg(unix)]
+fn console_request(
+    stream: &mut std::os::unix::net::UnixStream,
+    reader: &mut impl std::io::BufRead,
+    request: &str,
+) -> std::io::Result<Option<Vec<String>>> {
+    use std::io::Write;
+
+    writeln!(stream, "{}", request)?;
+    let mut output = Vec::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            return Ok(Some(output));
+        }
+        output.push(line.to_string());
+    }
+}
+
+/// Connect to a running server's console socket (`--console <path>`)
+#[cfg(unix)]
+pub fn run_console_client(path: &str) -> std::io::Result<()> {
+    use std::io::BufReader;
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(path)?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    // Completion asks over a connection of its own, as the editor holds the first
+    let complete = |line: &str| {
+        let request = || -> std::io::Result<Vec<String>> {
+            let mut stream = UnixStream::connect(path)?;
+            let mut reader = BufReader::new(stream.try_clone()?);
+            Ok(console_request(&mut stream, &mut reader, &format!("\t{}", line))?.unwrap_or_default())
+        };
+        request().unwrap_or_default()
+    };
+    let mut failure = None;
+    let execute = |line: &str| match console_request(&mut stream, &mut reader, line) {
+        Ok(output) => output.map(|lines| lines.join("\n")),
+        Err(e) => {
+            failure = Some(e);
+            None
+        }
+    };
+    run_editor(execute, complete).map_err(std::io::Error::other)?;
+    failure.map_or(Ok(()), Err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        let session_id = SessionId::new_v4();
+        let player_id = PlayerId::new_v4();
+
+        assert_eq!(parse("   "), Ok(None));
+        assert_eq!(parse("players"), Ok(Some(ConsoleCommand::Admin(AdminCommand::ListPlayers))));
+        assert_eq!(
+            parse(&format!("kick {} too  fast", player_id)),
+            Ok(Some(ConsoleCommand::Admin(AdminCommand::KickPlayer {
+                player_id,
+                reason: Some("too  fast".to_string()),
+            })))
+        );
+        assert_eq!(
+            parse(&format!("kick {}", player_id)),
+            Ok(Some(ConsoleCommand::Admin(AdminCommand::KickPlayer { player_id, reason: None })))
+        );
+        assert_eq!(
+            parse(&format!("finish {}", session_id)),
+            Ok(Some(ConsoleCommand::Admin(AdminCommand::TransitionSession {
+                session_id,
+                transition: SessionTransition::Finish,
+            })))
+        );
+        assert_eq!(
+            parse("create Monza 12"),
+            Ok(Some(ConsoleCommand::Admin(AdminCommand::CreateSession {
+                create: SessionCreate { track: "Monza".to_string(), max_players: 12 },
+            })))
+        );
+        assert_eq!(
+            parse("say  Server restarts in 5 minutes "),
+            Ok(Some(ConsoleCommand::Admin(AdminCommand::Announce {
+                message: "Server restarts in 5 minutes".to_string(),
+            })))
+        );
+        assert_eq!(
+            parse(&format!("bop {} Formula ballast=25 power=0.95", session_id)),
+            Ok(Some(ConsoleCommand::Admin(AdminCommand::UpdateSession {
+                session_id,
+                update: SessionUpdate {
+                    balance: Some(
+                        [(
+                            "Formula".to_string(),
+                            BalanceOfPerformance { ballast_kg: 25.0, power_multiplier: 0.95, fuel_capacity_liters: None },
+                        )]
+                        .into_iter()
+                        .collect()
+                    ),
+                    ..Default::default()
+                },
+            })))
+        );
+        assert_eq!(
+            parse("gentrack Test Ring corners=8 seed=42 env=none"),
+            Ok(Some(ConsoleCommand::Admin(AdminCommand::GenerateTrack {
+                generate: TrackGenerate {
+                    name: "Test Ring".to_string(),
+                    params: CircuitParams { corners: 8, seed: Some(42), environment: None, ..Default::default() },
+                },
+            })))
+        );
+        assert_eq!(parse("log"), Ok(Some(ConsoleCommand::Log(None))));
+        assert_eq!(parse("log debug"), Ok(Some(ConsoleCommand::Log(Some("debug".to_string())))));
+        assert_eq!(parse("exit"), Ok(Some(ConsoleCommand::Quit)));
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_lines() {
+        assert!(parse("launch").is_err());
+        assert!(parse("session").is_err());
+        assert!(parse("session not-an-id").is_err());
+        assert!(parse("kick").is_err());
+        assert!(parse("create Monza many").is_err());
+        assert!(parse("say").is_err());
+        assert!(parse(&format!("bop {}", SessionId::new_v4())).is_err());
+        assert!(parse(&format!("bop {} Formula weight=10", SessionId::new_v4())).is_err());
+        assert!(parse("gentrack length=3000").is_err());
+        assert!(parse("gentrack Ring turns=8").is_err());
+        assert!(parse("shutdown now").is_err());
+    }
+
+    #[test]
+    fn test_completions() {
+        let sessions = vec!["aaaa-1".to_string(), "abcd-2".to_string()];
+        let players = vec!["ffff-3".to_string()];
+
+        assert_eq!(completions("st", &sessions, &players), vec!["start", "stats"]);
+        assert_eq!(completions("", &sessions, &players).len(), COMMANDS.len());
+        assert_eq!(completions("finish ab", &sessions, &players), vec!["abcd-2"]);
+        assert_eq!(completions("close ", &sessions, &players), sessions);
+        assert_eq!(completions("kick ", &sessions, &players), players);
+        assert_eq!(completions("log w", &sessions, &players), vec!["warn"]);
+        assert!(completions("say he", &sessions, &players).is_empty());
+        assert_eq!(word_start("finish ab"), 7);
+        assert_eq!(word_start("fin"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_console_runs_commands_through_admin_api() {
+        let (api, mut requests) = AdminApi::new(Vec::new());
+        tokio::spawn(async move {
+            while let Some(request) = requests.recv().await {
+                let response = match &request.command {
+                    AdminCommand::Announce { message } => AdminResponse::Done { message: format!("Announced {}", message) },
+                    _ => AdminResponse::Players(Vec::new()),
+                };
+                request.respond(Ok(response));
+            }
+        });
+        let console = AdminConsole::new(api, None);
+
+        assert_eq!(console.execute("say hello").await.as_deref(), Some("Announced hello"));
+        assert_eq!(console.execute("players").await.as_deref(), Some("No players connected"));
+        assert!(console.execute("log").await.unwrap().starts_with("error:"));
+        assert!(console.execute("bogus").await.unwrap().starts_with("error:"));
+        assert_eq!(console.execute("quit").await, None);
+    }
+}
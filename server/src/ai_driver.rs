@@ -14,8 +14,8 @@
 //! that defines their behavior characteristics.
 
 use crate::data::*;
+use crate::physics;
 use serde::{Deserialize, Serialize};
-use uuid::Uuid;
 
 /// Skill level bounds for AI drivers
 pub const MIN_SKILL_LEVEL: u8 = 70;
@@ -24,6 +24,47 @@ pub const MAX_SKILL_LEVEL: u8 = 110;
 /// Default skill level (average driver)
 pub const DEFAULT_SKILL_LEVEL: u8 = 90;
 
+/// How far ahead the tactical layer predicts traffic (seconds)
+const AVOIDANCE_HORIZON_S: f32 = 1.5;
+/// Time step of the traffic prediction (seconds)
+const AVOIDANCE_STEP_S: f32 = 0.1;
+/// Lateral distance below which two predicted cars would touch (meters)
+const AVOIDANCE_HALF_WIDTH_M: f32 = 2.2;
+/// Gap kept to a car ahead in the AI's path (meters)
+const AVOIDANCE_MIN_GAP_M: f32 = 6.0;
+/// How far the AI moves off its line to yield to a car ahead (meters)
+const AVOIDANCE_YIELD_M: f32 = 3.0;
+/// Distance kept from the track edge while yielding (meters)
+const AVOIDANCE_EDGE_MARGIN_M: f32 = 1.5;
+
+/// Deceleration assumed when braking for a hinted corner in the dry (m/s^2)
+const HINT_BRAKING_DECEL_MPS2: f32 = 8.0;
+
+/// Angle to the track direction beyond which a stopped car counts as facing backwards (radians)
+const RECOVERY_BACKWARDS_RAD: f32 = 2.0;
+/// Speed below which a car that is not held up by traffic counts as stuck (m/s)
+const RECOVERY_STUCK_SPEED_MPS: f32 = 1.0;
+/// How long a car must be stuck before it starts to recover (seconds)
+const RECOVERY_STUCK_TIME_S: f32 = 2.0;
+/// Longest time spent reversing before driving forwards again (seconds)
+const RECOVERY_REVERSE_TIME_S: f32 = 2.5;
+/// Shortest time spent reversing, so the car clears what it is stuck on (seconds)
+const RECOVERY_MIN_REVERSE_TIME_S: f32 = 1.0;
+/// Angle to the track direction at which reversing has turned the car far enough (radians)
+const RECOVERY_TURNED_RAD: f32 = 1.0;
+/// Angle to the track direction at which a rejoining car is back on its way (radians)
+const RECOVERY_ALIGNED_RAD: f32 = 0.5;
+/// Throttle used while reversing
+const RECOVERY_REVERSE_THROTTLE: f32 = 0.5;
+/// Speed cap while heading back onto the track (m/s)
+const RECOVERY_REJOIN_SPEED_MPS: f32 = 12.0;
+/// How far back along the track a rejoining car looks for traffic (meters)
+const RECOVERY_YIELD_DISTANCE_M: f32 = 80.0;
+/// A rejoining car waits for traffic arriving within this time (seconds)
+const RECOVERY_YIELD_TIME_S: f32 = 3.0;
+/// Time after which a car that has not recovered is put back on the track (seconds)
+pub const RECOVERY_RESET_TIMEOUT_S: f32 = 15.0;
+
 /// Profile for an AI-controlled driver.
 ///
 /// Each AI driver has their own profile that determines their driving behavior,
@@ -80,7 +121,7 @@ impl AiDriverProfile {
         let normalized_skill = (skill - MIN_SKILL_LEVEL) as f32 / (MAX_SKILL_LEVEL - MIN_SKILL_LEVEL) as f32;
 
         Self {
-            id: Uuid::new_v4(),
+            id: PlayerId::new_v4(),
             name: name.into(),
             skill_level: skill,
             // Derive attributes from skill level with sensible defaults
@@ -95,6 +136,7 @@ impl AiDriverProfile {
     }
     
     /// Create a profile with full customization.
+    #[allow(clippy::too_many_arguments)]
     pub fn with_attributes(
         name: impl Into<String>,
         skill_level: u8,
@@ -106,7 +148,7 @@ impl AiDriverProfile {
         consistency: f32,
     ) -> Self {
         Self {
-            id: Uuid::new_v4(),
+            id: PlayerId::new_v4(),
             name: name.into(),
             skill_level: skill_level.clamp(MIN_SKILL_LEVEL, MAX_SKILL_LEVEL),
             aggressiveness: aggressiveness.clamp(0.0, 1.0),
@@ -136,12 +178,69 @@ impl Default for AiDriverProfile {
 ///
 /// This implements the three-layer architecture:
 /// 1. Planning: Uses racing line data to determine target waypoints and speeds
-/// 2. Tactical: Reacts to dynamic conditions (not yet fully implemented)
+/// 2. Tactical: Caps the target speed and shifts the target point to avoid traffic,
+///    and recovers the car after spins and off-track excursions
 /// 3. Low-level control: Converts targets to throttle/brake/steering/gear inputs
 pub struct AiDriverController<'a> {
     profile: &'a AiDriverProfile,
     track_config: &'a TrackConfig,
     car_config: &'a CarConfig,
+    traffic: Vec<&'a CarState>,
+    field: Vec<&'a CarState>,
+}
+
+/// What an AI driver is doing to get back to racing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RecoveryPhase {
+    /// Following the line
+    #[default]
+    Racing,
+    /// Backing away from a barrier or turning round after a spin
+    Reversing,
+    /// Heading back onto the track at low speed, giving way to traffic
+    Rejoining,
+}
+
+/// Recovery state machine of one AI driver, kept between ticks.
+///
+/// A car that faces backwards or stops without traffic in the way reverses,
+/// turning its nose towards the track direction, then rejoins. A car that runs
+/// off the track rejoins directly. Rejoining cars wait for traffic coming up
+/// behind them before driving back onto the racing surface.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct AiRecovery {
+    pub phase: RecoveryPhase,
+    /// Time spent in the current phase (seconds)
+    phase_s: f32,
+    /// Time since the car left `Racing` (seconds)
+    recovering_s: f32,
+    /// Time the car has been stuck (seconds)
+    stuck_s: f32,
+}
+
+impl AiRecovery {
+    /// Whether the car has been recovering for too long and should be put back on the track
+    pub fn timed_out(&self) -> bool {
+        self.phase != RecoveryPhase::Racing && self.recovering_s >= RECOVERY_RESET_TIMEOUT_S
+    }
+
+    fn enter(&mut self, phase: RecoveryPhase) {
+        if self.phase == RecoveryPhase::Racing {
+            self.recovering_s = 0.0;
+        }
+        self.phase = phase;
+        self.phase_s = 0.0;
+        self.stuck_s = 0.0;
+    }
+}
+
+/// Adjustment the tactical layer makes to the planned targets
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Avoidance {
+    /// Highest speed that keeps a safe gap to the car ahead (m/s)
+    speed_cap: f32,
+    /// Shift of the target point off the line, positive to the left (meters)
+    lateral_offset: f32,
 }
 
 impl<'a> AiDriverController<'a> {
@@ -151,6 +250,91 @@ impl<'a> AiDriverController<'a> {
             profile,
             track_config,
             car_config,
+            traffic: Vec::new(),
+            field: Vec::new(),
+        }
+    }
+
+    /// Give the controller the cars it should avoid.
+    ///
+    /// Entries with the AI's own player id are ignored.
+    pub fn with_traffic(mut self, traffic: Vec<&'a CarState>) -> Self {
+        self.traffic = traffic;
+        self
+    }
+
+    /// Give the controller every car on track, which it gives way to when rejoining.
+    ///
+    /// Entries with the AI's own player id are ignored.
+    pub fn with_field(mut self, field: Vec<&'a CarState>) -> Self {
+        self.field = field;
+        self
+    }
+
+    /// Advance the recovery state machine by one tick.
+    pub fn update_recovery(&self, state: &CarState, recovery: &mut AiRecovery, dt: f32) {
+        recovery.phase_s += dt;
+        recovery.recovering_s += dt;
+
+        let heading_error = self.heading_error(state).abs();
+        let held_by_traffic = self
+            .avoid_traffic(state)
+            .is_some_and(|avoidance| avoidance.speed_cap <= RECOVERY_STUCK_SPEED_MPS);
+        if state.speed_mps < RECOVERY_STUCK_SPEED_MPS && !held_by_traffic && !self.must_yield(state) {
+            recovery.stuck_s += dt;
+        } else {
+            recovery.stuck_s = 0.0;
+        }
+        let stuck = recovery.stuck_s >= RECOVERY_STUCK_TIME_S;
+        // A car that spun to a halt facing the wrong way; one still moving can turn round itself
+        let spun = heading_error > RECOVERY_BACKWARDS_RAD && state.speed_mps < RECOVERY_STUCK_SPEED_MPS;
+
+        match recovery.phase {
+            RecoveryPhase::Racing => {
+                if state.is_airborne {
+                    // Wait for the landing before judging the car's heading
+                } else if stuck || spun {
+                    recovery.enter(RecoveryPhase::Reversing);
+                } else if !state.is_on_track {
+                    recovery.enter(RecoveryPhase::Rejoining);
+                }
+            }
+            RecoveryPhase::Reversing => {
+                let turned = heading_error < RECOVERY_TURNED_RAD && recovery.phase_s >= RECOVERY_MIN_REVERSE_TIME_S;
+                if turned || recovery.phase_s >= RECOVERY_REVERSE_TIME_S {
+                    recovery.enter(RecoveryPhase::Rejoining);
+                }
+            }
+            RecoveryPhase::Rejoining => {
+                if stuck || spun {
+                    recovery.enter(RecoveryPhase::Reversing);
+                } else if state.is_on_track && heading_error < RECOVERY_ALIGNED_RAD {
+                    recovery.phase = RecoveryPhase::Racing;
+                    recovery.phase_s = 0.0;
+                    recovery.stuck_s = 0.0;
+                }
+            }
+        }
+    }
+
+    /// Generate input for the AI driver in its current recovery phase.
+    pub fn generate_input_with_recovery(
+        &self,
+        state: &CarState,
+        recovery: &AiRecovery,
+        current_tick: u32,
+    ) -> PlayerInputData {
+        match recovery.phase {
+            RecoveryPhase::Racing => self.generate_input(state, current_tick),
+            RecoveryPhase::Reversing => self.reverse(state),
+            RecoveryPhase::Rejoining if self.must_yield(state) => PlayerInputData {
+                throttle: 0.0,
+                brake: 1.0,
+                steering: 0.0,
+                gear: Some(state.gear.max(1)),
+                clutch: None,
+            },
+            RecoveryPhase::Rejoining => self.drive(state, current_tick, RECOVERY_REJOIN_SPEED_MPS),
         }
     }
 
@@ -163,6 +347,11 @@ impl<'a> AiDriverController<'a> {
     /// - Throttle/brake balance (higher skill = better modulation)
     /// - Gear selection (higher skill = better shifting points)
     pub fn generate_input(&self, state: &CarState, current_tick: u32) -> PlayerInputData {
+        self.drive(state, current_tick, f32::INFINITY)
+    }
+
+    /// Follow the line at no more than `max_speed` (m/s).
+    fn drive(&self, state: &CarState, current_tick: u32, max_speed: f32) -> PlayerInputData {
         let track_length = self.get_track_length();
         
         // Skill-based parameters
@@ -176,34 +365,72 @@ impl<'a> AiDriverController<'a> {
         // Apply consistency variation (lower consistency = more speed variation)
         let consistency_noise = self.get_consistency_noise(current_tick);
         let target_speed = base_target_speed * (1.0 + consistency_noise * (1.0 - self.profile.consistency) * 0.15);
+
+        // Cornering speed goes with the square root of grip, so back off on a wet track
+        let wet_grip = physics::wet_grip_factor(SurfaceType::Asphalt, state.track_wetness);
+        let target_speed = (target_speed * wet_grip.sqrt())
+            .min(self.hinted_speed_cap(state, wet_grip))
+            .min(max_speed);
         
-        // Look-ahead distance scales with skill (better anticipation)
-        let look_ahead_distance = 15.0 + (skill_factor * 20.0);
+        // Look-ahead distance scales with skill (better anticipation), and grows
+        // in the wet where braking points come earlier
+        let look_ahead_distance = (15.0 + (skill_factor * 20.0)) / wet_grip;
         
-        // Find target point ahead on centerline
+        // Find target point ahead on centerline, or on the pit lane's while in it
         let target_progress = state.track_progress + look_ahead_distance;
         let wrapped_progress = target_progress % track_length;
-        
-        let target_point = self.find_nearest_centerline_point(wrapped_progress);
-        
+
+        let in_pit_lane = self.pit_lane_position(state);
+        let target_point = in_pit_lane
+            .and_then(|(pit_lane, distance)| {
+                let ahead = distance + look_ahead_distance;
+                (ahead < pit_lane.length_m()).then(|| pit_lane.point_at(ahead)).flatten()
+            })
+            .unwrap_or_else(|| self.find_nearest_centerline_point(wrapped_progress));
+
+        // Keep to the pit speed limit, reaching it before the limited stretch starts
+        let target_speed = match in_pit_lane {
+            Some((pit_lane, distance))
+                if pit_lane.is_speed_limited(distance) || pit_lane.is_speed_limited(distance + look_ahead_distance) =>
+            {
+                target_speed.min(pit_lane.speed_limit_mps)
+            }
+            _ => target_speed,
+        };
+
+        // Tactical layer: slow down behind, and move aside for, cars in the path.
+        // Outside the track's overtaking zones the AI stays on the line and follows
+        let avoidance = self.avoid_traffic(state);
+        let (target_x, target_y) = match avoidance {
+            Some(avoidance) if self.may_leave_line(state) => self.offset_target(target_point, avoidance.lateral_offset),
+            _ => (target_point.x, target_point.y),
+        };
+        let target_speed = match avoidance {
+            Some(avoidance) => target_speed.min(avoidance.speed_cap),
+            None => target_speed,
+        };
+
         // Calculate steering toward target
-        let steering = self.calculate_steering(state, target_point, skill_factor);
+        let steering = self.calculate_steering(state, target_x, target_y, skill_factor);
 
         // Calculate throttle and brake
-        let (throttle, brake) = self.calculate_throttle_brake(state, target_speed, skill_factor);
+        let (mut throttle, brake) = self.calculate_throttle_brake(state, target_speed, skill_factor);
+
+        // Never accelerate into a car the AI has to hold back for
+        if avoidance.is_some_and(|avoidance| avoidance.speed_cap <= state.speed_mps) {
+            throttle = 0.0;
+        }
 
         // Calculate gear selection
         let gear = self.calculate_gear(state, skill_factor);
 
-        // Calculate clutch (simple: always fully engaged for now)
-        let clutch = Some(1.0);
-
         PlayerInputData {
             throttle,
             brake,
             steering,
             gear: Some(gear),
-            clutch,
+            // The automatic clutch handles launches and keeps the engine from stalling
+            clutch: None,
         }
     }
     
@@ -216,7 +443,7 @@ impl<'a> AiDriverController<'a> {
     /// Generate consistency-based noise for the current tick.
     fn get_consistency_noise(&self, tick: u32) -> f32 {
         // Simple pseudo-random noise based on tick and driver ID
-        let seed = (tick as u64).wrapping_mul(self.profile.id.as_u128() as u64);
+        let seed = (tick as u64).wrapping_mul(self.profile.id.as_uuid().as_u128() as u64);
         let noise = ((seed % 1000) as f32 / 500.0) - 1.0; // -1.0 to 1.0
         noise
     }
@@ -232,10 +459,177 @@ impl<'a> AiDriverController<'a> {
             .unwrap_or(&self.track_config.centerline[0])
     }
     
+    /// Highest speed the track's AI hints allow here (m/s).
+    ///
+    /// Approaching a braking marker the cap follows a braking curve down to the
+    /// marker's speed, which is then held for the marker's `hold_m`. Inside a pit
+    /// entry caution zone the zone's speed applies.
+    fn hinted_speed_cap(&self, state: &CarState, wet_grip: f32) -> f32 {
+        let hints = &self.track_config.ai_hints;
+        let track_length = self.get_track_length();
+        let progress = state.track_progress;
+        let decel = HINT_BRAKING_DECEL_MPS2 * wet_grip;
+
+        let braking = hints.braking_markers.iter().map(|marker| {
+            let corner_speed = marker.speed_mps * wet_grip.sqrt();
+            let past = (progress - marker.distance_m).rem_euclid(track_length);
+            if past <= marker.hold_m {
+                return corner_speed;
+            }
+            let to_marker = (marker.distance_m - progress).rem_euclid(track_length);
+            (corner_speed.powi(2) + 2.0 * decel * to_marker).sqrt()
+        });
+        let caution = hints
+            .pit_entry_caution
+            .iter()
+            .filter(|zone| zone.contains(progress, track_length))
+            .map(|zone| zone.speed_mps);
+
+        braking.chain(caution).fold(f32::INFINITY, f32::min)
+    }
+
+    /// The track's pit lane and the distance along it, while the car is in it.
+    fn pit_lane_position(&self, state: &CarState) -> Option<(&PitLaneConfig, f32)> {
+        let pit_lane = self.track_config.pit_lane.as_ref()?;
+        Some((pit_lane, pit_lane.locate(state.pos_x, state.pos_y)?))
+    }
+
+    /// Whether the track's AI hints let the driver pull off the line here.
+    /// Nobody overtakes in the pit lane.
+    fn may_leave_line(&self, state: &CarState) -> bool {
+        if self.pit_lane_position(state).is_some() {
+            return false;
+        }
+        let hints = &self.track_config.ai_hints;
+        let track_length = self.get_track_length();
+        let progress = state.track_progress;
+
+        let in_caution = hints.pit_entry_caution.iter().any(|zone| zone.contains(progress, track_length));
+        let in_overtaking_zone = hints.overtaking_zones.is_empty()
+            || hints.overtaking_zones.iter().any(|zone| zone.contains(progress, track_length));
+        in_overtaking_zone && !in_caution
+    }
+
+    /// Back up, steering so the nose swings round towards the track direction.
+    fn reverse(&self, state: &CarState) -> PlayerInputData {
+        let (sin_yaw, cos_yaw) = state.yaw_rad.sin_cos();
+        let forward_speed = state.vel_x * cos_yaw + state.vel_y * sin_yaw;
+        if forward_speed > RECOVERY_STUCK_SPEED_MPS {
+            // Stop before selecting reverse
+            return PlayerInputData {
+                throttle: 0.0,
+                brake: 1.0,
+                steering: 0.0,
+                gear: Some(state.gear),
+                clutch: None,
+            };
+        }
+
+        // Steering works the other way round in reverse: steering towards the
+        // heading error swings the nose against it
+        let steering = (self.heading_error(state) * 2.0).clamp(-1.0, 1.0);
+        PlayerInputData {
+            throttle: RECOVERY_REVERSE_THROTTLE,
+            brake: 0.0,
+            steering,
+            gear: Some(-1),
+            clutch: None,
+        }
+    }
+
+    /// Whether a car coming up behind would arrive before the AI has rejoined.
+    fn must_yield(&self, state: &CarState) -> bool {
+        if state.is_on_track && self.heading_error(state).abs() < RECOVERY_ALIGNED_RAD {
+            return false;
+        }
+        let track_length = self.get_track_length();
+        self.field.iter().any(|other| {
+            if other.player_id == state.player_id || other.is_ghost || !other.is_on_track {
+                return false;
+            }
+            let behind = (state.track_progress - other.track_progress).rem_euclid(track_length);
+            behind > 0.0
+                && behind < RECOVERY_YIELD_DISTANCE_M
+                && other.speed_mps > RECOVERY_STUCK_SPEED_MPS
+                && behind / other.speed_mps < RECOVERY_YIELD_TIME_S
+        })
+    }
+
+    /// Car heading relative to the track direction at its position, -PI to PI.
+    fn heading_error(&self, state: &CarState) -> f32 {
+        let track_length = self.get_track_length();
+        let point = self.find_nearest_centerline_point(state.track_progress.rem_euclid(track_length));
+        self.normalize_angle(state.yaw_rad - point.heading_rad)
+    }
+
+    /// Predict the traffic a short horizon ahead and find the car the AI must avoid.
+    ///
+    /// Both cars are extrapolated along their current velocity. A car that would
+    /// come closer than the safe gap in the AI's path caps the AI's speed to keep a
+    /// safe gap, and the AI yields to the side away from it.
+    fn avoid_traffic(&self, state: &CarState) -> Option<Avoidance> {
+        // Ghost cars pass through each other, so there is nothing to avoid
+        if state.is_ghost {
+            return None;
+        }
+
+        let (sin_yaw, cos_yaw) = state.yaw_rad.sin_cos();
+        let steps = (AVOIDANCE_HORIZON_S / AVOIDANCE_STEP_S).round() as u32;
+        let mut nearest: Option<(f32, Avoidance)> = None;
+
+        for other in &self.traffic {
+            if other.player_id == state.player_id || other.is_ghost {
+                continue;
+            }
+
+            let dx = other.pos_x - state.pos_x;
+            let dy = other.pos_y - state.pos_y;
+            let dvx = other.vel_x - state.vel_x;
+            let dvy = other.vel_y - state.vel_y;
+
+            let conflict = (0..=steps).any(|step| {
+                let t = step as f32 * AVOIDANCE_STEP_S;
+                let rx = dx + dvx * t;
+                let ry = dy + dvy * t;
+                let ahead = rx * cos_yaw + ry * sin_yaw;
+                let left = -rx * sin_yaw + ry * cos_yaw;
+                ahead > 0.0 && ahead < AVOIDANCE_MIN_GAP_M && left.abs() < AVOIDANCE_HALF_WIDTH_M
+            });
+            if !conflict {
+                continue;
+            }
+
+            let gap = dx * cos_yaw + dy * sin_yaw;
+            if gap <= 0.0 {
+                continue;
+            }
+            let other_speed = other.vel_x * cos_yaw + other.vel_y * sin_yaw;
+            let speed_cap = (other_speed + (gap - AVOIDANCE_MIN_GAP_M) / AVOIDANCE_HORIZON_S).max(0.0);
+            let left = -dx * sin_yaw + dy * cos_yaw;
+            let lateral_offset = if left >= 0.0 { -AVOIDANCE_YIELD_M } else { AVOIDANCE_YIELD_M };
+
+            if nearest.is_none_or(|(nearest_gap, _)| gap < nearest_gap) {
+                nearest = Some((gap, Avoidance { speed_cap, lateral_offset }));
+            }
+        }
+
+        nearest.map(|(_, avoidance)| avoidance)
+    }
+
+    /// Shift a target point sideways, staying on the track.
+    fn offset_target(&self, target: &TrackPoint, lateral_offset: f32) -> (f32, f32) {
+        let offset = lateral_offset.clamp(
+            -(target.width_right_m - AVOIDANCE_EDGE_MARGIN_M).max(0.0),
+            (target.width_left_m - AVOIDANCE_EDGE_MARGIN_M).max(0.0),
+        );
+        let (sin_heading, cos_heading) = target.heading_rad.sin_cos();
+        (target.x - sin_heading * offset, target.y + cos_heading * offset)
+    }
+
     /// Calculate steering input based on target point and skill.
-    fn calculate_steering(&self, state: &CarState, target: &TrackPoint, skill_factor: f32) -> f32 {
-        let dx = target.x - state.pos_x;
-        let dy = target.y - state.pos_y;
+    fn calculate_steering(&self, state: &CarState, target_x: f32, target_y: f32, skill_factor: f32) -> f32 {
+        let dx = target_x - state.pos_x;
+        let dy = target_y - state.pos_y;
         let target_angle = dy.atan2(dx);
         let angle_diff = target_angle - state.yaw_rad;
         
@@ -309,8 +703,8 @@ impl<'a> AiDriverController<'a> {
             return current_gear - 1;
         }
 
-        // Start in first gear if in neutral
-        if current_gear == 0 {
+        // Start in first gear from neutral or reverse
+        if current_gear <= 0 {
             return 1;
         }
 
@@ -321,7 +715,7 @@ impl<'a> AiDriverController<'a> {
     /// Normalize an angle to the range -PI to PI.
     fn normalize_angle(&self, angle: f32) -> f32 {
         let pi = std::f32::consts::PI;
-        ((angle + pi) % (2.0 * pi)) - pi
+        (angle + pi).rem_euclid(2.0 * pi) - pi
     }
 
     /// Get the total track length.
@@ -408,8 +802,8 @@ mod tests {
         let controller = AiDriverController::new(&profile, &track, &car);
 
         let car_state = CarState::new(
-            Uuid::new_v4(),
-            Uuid::new_v4(),
+            PlayerId::new_v4(),
+            CarConfigId::new_v4(),
             &track.start_positions[0],
         );
 
@@ -420,6 +814,292 @@ mod tests {
         assert!(input.steering >= -1.0 && input.steering <= 1.0);
     }
 
+    #[test]
+    fn test_ai_backs_off_in_the_wet() {
+        let profile = AiDriverProfile::new("Test", 90);
+        let track = TrackConfig::default();
+        let car = CarConfig::default();
+        let controller = AiDriverController::new(&profile, &track, &car);
+
+        let mut car_state = CarState::new(
+            PlayerId::new_v4(),
+            CarConfigId::new_v4(),
+            &track.start_positions[0],
+        );
+        car_state.speed_mps = 45.0;
+
+        let dry = controller.generate_input(&car_state, 100);
+        car_state.track_wetness = 1.0;
+        let wet = controller.generate_input(&car_state, 100);
+
+        assert!(wet.throttle < dry.throttle || wet.brake > dry.brake);
+    }
+
+    /// A car on the first centerline point of the default track, driving along it
+    fn car_on_line(track: &TrackConfig, distance_ahead: f32, speed: f32) -> CarState {
+        let point = &track.centerline[0];
+        let (sin_heading, cos_heading) = point.heading_rad.sin_cos();
+        let mut state = CarState::new(PlayerId::new_v4(), CarConfigId::new_v4(), &track.start_positions[0]);
+        state.pos_x = point.x + cos_heading * distance_ahead;
+        state.pos_y = point.y + sin_heading * distance_ahead;
+        state.yaw_rad = point.heading_rad;
+        state.vel_x = cos_heading * speed;
+        state.vel_y = sin_heading * speed;
+        state.speed_mps = speed;
+        state.gear = 3;
+        state.engine_rpm = 5000.0;
+        state
+    }
+
+    #[test]
+    fn test_ai_brakes_for_a_car_stopped_in_its_path() {
+        let profile = AiDriverProfile::new("Test", 90);
+        let track = TrackConfig::default();
+        let car = CarConfig::default();
+
+        let ai_state = car_on_line(&track, 0.0, 30.0);
+        let stopped = car_on_line(&track, 15.0, 0.0);
+
+        let clear = AiDriverController::new(&profile, &track, &car).generate_input(&ai_state, 100);
+        let blocked = AiDriverController::new(&profile, &track, &car)
+            .with_traffic(vec![&stopped])
+            .generate_input(&ai_state, 100);
+
+        assert!(clear.throttle > 0.0);
+        assert_eq!(blocked.throttle, 0.0);
+        assert!(blocked.brake > 0.0);
+    }
+
+    #[test]
+    fn test_ai_holds_its_grid_slot_behind_a_car_that_has_not_launched() {
+        let profile = AiDriverProfile::new("Test", 90);
+        let track = TrackConfig::default();
+        let car = CarConfig::default();
+
+        let mut ai_state = car_on_line(&track, 0.0, 0.0);
+        ai_state.gear = 1;
+        let ahead = car_on_line(&track, 4.0, 0.0);
+
+        let input = AiDriverController::new(&profile, &track, &car)
+            .with_traffic(vec![&ahead])
+            .generate_input(&ai_state, 100);
+
+        assert_eq!(input.throttle, 0.0);
+    }
+
+    #[test]
+    fn test_ai_yields_away_from_a_slower_car() {
+        let profile = AiDriverProfile::new("Test", 90);
+        let track = TrackConfig::default();
+        let car = CarConfig::default();
+
+        let ai_state = car_on_line(&track, 0.0, 30.0);
+        let mut slower = car_on_line(&track, 12.0, 15.0);
+        // Slightly to the left of the AI's line
+        let (sin_heading, cos_heading) = ai_state.yaw_rad.sin_cos();
+        slower.pos_x -= sin_heading;
+        slower.pos_y += cos_heading;
+
+        let clear = AiDriverController::new(&profile, &track, &car).generate_input(&ai_state, 100);
+        let yielding = AiDriverController::new(&profile, &track, &car)
+            .with_traffic(vec![&slower])
+            .generate_input(&ai_state, 100);
+
+        // Positive steering turns left, so yielding to the right steers less
+        assert!(yielding.steering < clear.steering);
+    }
+
+    #[test]
+    fn test_ai_brakes_for_a_hinted_corner() {
+        let profile = AiDriverProfile::new("Test", 90);
+        let mut track = TrackConfig::default();
+        let car = CarConfig::default();
+        let ai_state = car_on_line(&track, 0.0, 40.0);
+
+        let clear = AiDriverController::new(&profile, &track, &car).generate_input(&ai_state, 100);
+
+        track.ai_hints.braking_markers.push(BrakingMarker {
+            distance_m: ai_state.track_progress + 30.0,
+            speed_mps: 15.0,
+            hold_m: 0.0,
+        });
+        let hinted = AiDriverController::new(&profile, &track, &car).generate_input(&ai_state, 100);
+
+        assert!(clear.throttle > 0.0);
+        assert_eq!(hinted.throttle, 0.0);
+        assert!(hinted.brake > 0.0);
+    }
+
+    #[test]
+    fn test_ai_keeps_to_the_pit_speed_limit() {
+        let profile = AiDriverProfile::new("Test", 90);
+        let mut track = TrackConfig::default();
+        let car = CarConfig::default();
+
+        // A straight pit lane well away from the oval
+        let centerline: Vec<TrackPoint> = (0..100)
+            .map(|i| TrackPoint { x: i as f32 * 2.0, y: 400.0, distance_from_start_m: i as f32 * 2.0, ..Default::default() })
+            .collect();
+        let mut ai_state = car_on_line(&track, 0.0, 25.0);
+        (ai_state.pos_x, ai_state.pos_y, ai_state.yaw_rad) = (20.0, 400.0, 0.0);
+        (ai_state.vel_x, ai_state.vel_y) = (25.0, 0.0);
+
+        track.pit_lane = Some(PitLaneConfig {
+            entry_point: track.centerline[30].clone(),
+            exit_point: track.centerline[5].clone(),
+            speed_limit_mps: 16.0,
+            pit_stalls: Vec::new(),
+            centerline,
+            speed_limit_start_m: 150.0,
+            speed_limit_end_m: 190.0,
+        });
+        let before_limit = AiDriverController::new(&profile, &track, &car).generate_input(&ai_state, 100);
+
+        track.pit_lane.as_mut().unwrap().speed_limit_start_m = 0.0;
+        let limited = AiDriverController::new(&profile, &track, &car).generate_input(&ai_state, 100);
+
+        assert!(before_limit.throttle > 0.0);
+        assert_eq!(limited.throttle, 0.0);
+        assert!(limited.brake > 0.0);
+        assert!(limited.steering.abs() < 0.05, "Follows the pit lane, not the oval");
+    }
+
+    #[test]
+    fn test_ai_stays_on_line_outside_overtaking_zones() {
+        let profile = AiDriverProfile::new("Test", 90);
+        let mut track = TrackConfig::default();
+        let car = CarConfig::default();
+
+        let ai_state = car_on_line(&track, 0.0, 30.0);
+        let mut slower = car_on_line(&track, 12.0, 15.0);
+        let (sin_heading, cos_heading) = ai_state.yaw_rad.sin_cos();
+        slower.pos_x -= sin_heading;
+        slower.pos_y += cos_heading;
+
+        let track_length = track.centerline.last().unwrap().distance_from_start_m;
+        let far_side = ai_state.track_progress + track_length / 2.0;
+        track.ai_hints.overtaking_zones.push(TrackStretch {
+            start_m: far_side % track_length,
+            end_m: (far_side + 100.0) % track_length,
+        });
+
+        let clear = AiDriverController::new(&profile, &track, &car).generate_input(&ai_state, 100);
+        let following = AiDriverController::new(&profile, &track, &car)
+            .with_traffic(vec![&slower])
+            .generate_input(&ai_state, 100);
+
+        assert_eq!(following.steering, clear.steering);
+        assert_eq!(following.throttle, 0.0);
+    }
+
+    #[test]
+    fn test_ai_ignores_cars_behind_and_ghosts() {
+        let profile = AiDriverProfile::new("Test", 90);
+        let track = TrackConfig::default();
+        let car = CarConfig::default();
+
+        let mut ai_state = car_on_line(&track, 0.0, 30.0);
+        let behind = car_on_line(&track, -10.0, 30.0);
+        let clear = AiDriverController::new(&profile, &track, &car).generate_input(&ai_state, 100);
+        let followed = AiDriverController::new(&profile, &track, &car)
+            .with_traffic(vec![&behind])
+            .generate_input(&ai_state, 100);
+        assert_eq!(followed.throttle, clear.throttle);
+        assert_eq!(followed.steering, clear.steering);
+
+        ai_state.is_ghost = true;
+        let stopped = car_on_line(&track, 15.0, 0.0);
+        let ghost = AiDriverController::new(&profile, &track, &car)
+            .with_traffic(vec![&stopped])
+            .generate_input(&ai_state, 100);
+        assert!(ghost.throttle > 0.0);
+    }
+
+    /// Step the recovery state machine for `seconds` at 60 Hz
+    fn run_recovery(controller: &AiDriverController, state: &CarState, recovery: &mut AiRecovery, seconds: f32) {
+        for _ in 0..(seconds * 60.0) as u32 {
+            controller.update_recovery(state, recovery, 1.0 / 60.0);
+        }
+    }
+
+    #[test]
+    fn test_spun_car_reverses_round_then_rejoins() {
+        let profile = AiDriverProfile::new("Test", 90);
+        let track = TrackConfig::default();
+        let car = CarConfig::default();
+        let controller = AiDriverController::new(&profile, &track, &car);
+
+        let mut spun = car_on_line(&track, 0.0, 0.0);
+        spun.yaw_rad += std::f32::consts::PI;
+        let mut recovery = AiRecovery::default();
+        controller.update_recovery(&spun, &mut recovery, 1.0 / 60.0);
+        assert_eq!(recovery.phase, RecoveryPhase::Reversing);
+
+        let input = controller.generate_input_with_recovery(&spun, &recovery, 100);
+        assert_eq!(input.gear, Some(-1));
+        assert!(input.throttle > 0.0);
+
+        // Once reversing has swung the nose round the car drives off again
+        let mut turned = car_on_line(&track, 0.0, 2.0);
+        turned.yaw_rad += 0.8;
+        run_recovery(&controller, &turned, &mut recovery, RECOVERY_MIN_REVERSE_TIME_S + 0.1);
+        assert_eq!(recovery.phase, RecoveryPhase::Rejoining);
+
+        let rejoined = car_on_line(&track, 0.0, 10.0);
+        controller.update_recovery(&rejoined, &mut recovery, 1.0 / 60.0);
+        assert_eq!(recovery.phase, RecoveryPhase::Racing);
+    }
+
+    #[test]
+    fn test_stopped_car_only_counts_as_stuck_without_traffic_ahead() {
+        let profile = AiDriverProfile::new("Test", 90);
+        let track = TrackConfig::default();
+        let car = CarConfig::default();
+        let stopped = car_on_line(&track, 0.0, 0.0);
+
+        let queued_behind = car_on_line(&track, 4.0, 0.0);
+        let waiting = AiDriverController::new(&profile, &track, &car).with_traffic(vec![&queued_behind]);
+        let mut recovery = AiRecovery::default();
+        run_recovery(&waiting, &stopped, &mut recovery, RECOVERY_STUCK_TIME_S * 2.0);
+        assert_eq!(recovery.phase, RecoveryPhase::Racing);
+
+        let alone = AiDriverController::new(&profile, &track, &car);
+        let mut recovery = AiRecovery::default();
+        run_recovery(&alone, &stopped, &mut recovery, RECOVERY_STUCK_TIME_S * 1.1);
+        assert_eq!(recovery.phase, RecoveryPhase::Reversing);
+    }
+
+    #[test]
+    fn test_off_track_car_gives_way_before_rejoining() {
+        let profile = AiDriverProfile::new("Test", 90);
+        let track = TrackConfig::default();
+        let car = CarConfig::default();
+
+        let mut off_track = car_on_line(&track, 0.0, 5.0);
+        off_track.is_on_track = false;
+        off_track.yaw_rad += 1.0;
+        off_track.track_progress = 100.0;
+        let mut recovery = AiRecovery::default();
+        AiDriverController::new(&profile, &track, &car).update_recovery(&off_track, &mut recovery, 1.0 / 60.0);
+        assert_eq!(recovery.phase, RecoveryPhase::Rejoining);
+
+        // Traffic on the track 40 m back at racing speed
+        let mut oncoming = car_on_line(&track, 0.0, 30.0);
+        oncoming.track_progress = 60.0;
+        let yielding = AiDriverController::new(&profile, &track, &car)
+            .with_field(vec![&oncoming])
+            .generate_input_with_recovery(&off_track, &recovery, 100);
+        assert_eq!(yielding.throttle, 0.0);
+        assert!(yielding.brake > 0.0);
+
+        // Traffic that has gone past is no reason to wait
+        oncoming.track_progress = 140.0;
+        let rejoining = AiDriverController::new(&profile, &track, &car)
+            .with_field(vec![&oncoming])
+            .generate_input_with_recovery(&off_track, &recovery, 100);
+        assert!(rejoining.throttle > 0.0);
+    }
+
     #[test]
     fn test_skill_affects_target_speed() {
         let slow_profile = AiDriverProfile::new("Slow", MIN_SKILL_LEVEL);
@@ -0,0 +1,255 @@
+//! Stable pseudonyms for public data feeds.
+//!
+//! With anonymization enabled, spectators without admin rights and exported
+//! replays see every player under a pseudonymous ID and name derived from a
+//! server secret. The same player always maps to the same pseudonym, so public
+//! timing and replays stay consistent without revealing who was driving.
+//! Racers and admins keep receiving real identities.
+
+use crate::data::PlayerId;
+use aws_lc_rs::hmac;
+use crate::game_session::SimulationState;
+use crate::network::{RaceEvent, ServerMessage, SessionResultsData, StandingsData, Telemetry};
+use serde_json::Value;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Maps player IDs to pseudonyms keyed by a server secret
+#[derive(Debug, Clone)]
+pub struct Pseudonymizer {
+    key: hmac::Key,
+}
+
+impl Pseudonymizer {
+    /// Pseudonyms are stable for as long as `secret` is; an empty secret gets a random one
+    pub fn new(secret: &str) -> Self {
+        let secret = if secret.is_empty() {
+            Uuid::new_v4().as_bytes().to_vec()
+        } else {
+            secret.as_bytes().to_vec()
+        };
+        Self { key: hmac::Key::new(hmac::HMAC_SHA256, &secret) }
+    }
+
+    /// Pseudonymous stand-in for a player's ID
+    pub fn player_id(&self, player_id: PlayerId) -> PlayerId {
+        if player_id.is_nil() {
+            return player_id;
+        }
+        // Mark it as a random (v4) UUID so it looks like any other player ID
+        let mut bytes: [u8; 16] = self.hash(player_id, 0);
+        bytes[6] = (bytes[6] & 0x0f) | 0x40;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+        PlayerId(Uuid::from_bytes(bytes))
+    }
+
+    /// Display name shown in place of a player's name
+    pub fn player_name(&self, player_id: PlayerId) -> String {
+        format!("Driver {:04X}", u16::from_be_bytes(self.hash(player_id, 1)))
+    }
+
+    /// Copy of a session broadcast with player identities replaced
+    pub fn message(&self, message: &ServerMessage) -> ServerMessage {
+        match message {
+            ServerMessage::Telemetry(telemetry) => ServerMessage::Telemetry(self.telemetry(telemetry)),
+            ServerMessage::Standings(standings) => ServerMessage::Standings(self.standings(standings)),
+            ServerMessage::RaceEvent(event) => ServerMessage::RaceEvent(self.race_event(event)),
+            ServerMessage::SessionResults(results) => ServerMessage::SessionResults(self.results(results)),
+            ServerMessage::ExtendedTelemetry(extended) => {
+                let mut extended = extended.clone();
+                for car in &mut extended.cars {
+                    car.player_id = self.player_id(car.player_id);
+                }
+                ServerMessage::ExtendedTelemetry(extended)
+            }
+            ServerMessage::RecentTelemetry(recent) => {
+                let mut recent = recent.clone();
+                recent.player_id = self.player_id(recent.player_id);
+                for sample in &mut recent.samples {
+                    sample.car_state.player_id = recent.player_id;
+                }
+                ServerMessage::RecentTelemetry(recent)
+            }
+            other => other.clone(),
+        }
+    }
+
+    pub fn telemetry(&self, telemetry: &Telemetry) -> Telemetry {
+        let mut telemetry = telemetry.clone();
+        for car in &mut telemetry.car_states {
+            car.player_id = self.player_id(car.player_id);
+        }
+        telemetry
+    }
+
+    pub fn standings(&self, standings: &StandingsData) -> StandingsData {
+        let mut standings = standings.clone();
+        for entry in &mut standings.entries {
+            entry.player_id = self.player_id(entry.player_id);
+        }
+        standings
+    }
+
+    pub fn race_event(&self, event: &RaceEvent) -> RaceEvent {
+        RaceEvent {
+            player_id: self.player_id(event.player_id),
+            other_player_id: event.other_player_id.map(|id| self.player_id(id)),
+            ..event.clone()
+        }
+    }
+
+    pub fn results(&self, results: &SessionResultsData) -> SessionResultsData {
+        let mut results = results.clone();
+        for entry in &mut results.entries {
+            entry.player_id = self.player_id(entry.player_id);
+        }
+        for entry in &mut results.timeline {
+            entry.player_id = entry.player_id.map(|id| self.player_id(id));
+            entry.other_player_id = entry.other_player_id.map(|id| self.player_id(id));
+        }
+        results
+    }
+
+    /// Copy of a session's simulation state with every player ID replaced.
+    ///
+    /// IDs also key the private state of rolling starts and formation laps, so
+    /// the state is rewritten as JSON rather than field by field.
+    pub fn simulation_state(&self, state: &SimulationState) -> Result<SimulationState, serde_json::Error> {
+        let session = &state.session;
+        let pseudonyms: HashMap<String, String> = session
+            .participants
+            .keys()
+            .chain(&session.ai_player_ids)
+            .chain(std::iter::once(&session.host_player_id))
+            .map(|id| (id.to_string(), self.player_id(*id).to_string()))
+            .collect();
+
+        let mut value = serde_json::to_value(state)?;
+        replace_strings(&mut value, &pseudonyms);
+        serde_json::from_value(value)
+    }
+
+    /// HMAC-SHA256 of a domain byte and the player ID, truncated to `N` bytes
+    fn hash<const N: usize>(&self, player_id: PlayerId, domain: u8) -> [u8; N] {
+        let mut message = [0u8; 17];
+        message[0] = domain;
+        message[1..].copy_from_slice(player_id.as_uuid().as_bytes());
+        let tag = hmac::sign(&self.key, &message);
+
+        let mut truncated = [0u8; N];
+        truncated.copy_from_slice(&tag.as_ref()[..N]);
+        truncated
+    }
+}
+
+/// Swap every string value and object key found in `replacements`
+fn replace_strings(value: &mut Value, replacements: &HashMap<String, String>) {
+    match value {
+        Value::String(s) => {
+            if let Some(replacement) = replacements.get(s.as_str()) {
+                *s = replacement.clone();
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                replace_strings(item, replacements);
+            }
+        }
+        Value::Object(map) => {
+            for (key, mut item) in std::mem::take(map) {
+                replace_strings(&mut item, replacements);
+                map.insert(replacements.get(&key).cloned().unwrap_or(key), item);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{
+        CarConfigId, CarState, GameMode, GridSlot, RaceEventKind, SessionId, SessionState, TimelineEventKind,
+    };
+    use crate::network::{CarStateTelemetry, TimelineEntry};
+
+    #[test]
+    fn test_pseudonyms_are_stable_per_secret() {
+        let player = PlayerId::new_v4();
+        let other = PlayerId::new_v4();
+        let server = Pseudonymizer::new("league-secret");
+
+        assert_eq!(server.player_id(player), Pseudonymizer::new("league-secret").player_id(player));
+        assert_eq!(server.player_name(player), server.player_name(player));
+        assert_ne!(server.player_id(player), player);
+        assert_ne!(server.player_id(player), server.player_id(other));
+        assert_ne!(server.player_id(player), Pseudonymizer::new("other-secret").player_id(player));
+        assert_eq!(server.player_id(player).as_uuid().get_version_num(), 4);
+        assert!(server.player_name(player).starts_with("Driver "));
+    }
+
+    #[test]
+    fn test_broadcasts_lose_real_player_ids() {
+        let server = Pseudonymizer::new("league-secret");
+        let player = PlayerId::new_v4();
+        let overtaken = PlayerId::new_v4();
+
+        let event = ServerMessage::RaceEvent(RaceEvent {
+            session_id: SessionId::new_v4(),
+            server_tick: 10,
+            kind: RaceEventKind::Overtake,
+            player_id: player,
+            other_player_id: Some(overtaken),
+            lap: 2,
+            position: Some(1),
+            lap_time_ms: None,
+            penalty_ms: None,
+            message: None,
+        });
+        let ServerMessage::RaceEvent(public) = server.message(&event) else {
+            panic!("Expected a race event");
+        };
+        assert_eq!(public.player_id, server.player_id(player));
+        assert_eq!(public.other_player_id, Some(server.player_id(overtaken)));
+        assert_eq!(public.lap, 2);
+
+        let results = ServerMessage::SessionResults(SessionResultsData {
+            session_id: SessionId::new_v4(),
+            server_tick: 20,
+            entries: Vec::new(),
+            timeline: vec![TimelineEntry {
+                server_tick: 10,
+                kind: TimelineEventKind::LeadChange,
+                player_id: Some(player),
+                other_player_id: Some(overtaken),
+                lap: 2,
+                position: Some(1),
+                penalty_ms: None,
+                message: None,
+            }],
+        });
+        let ServerMessage::SessionResults(public) = server.message(&results) else {
+            panic!("Expected results");
+        };
+        assert_eq!(public.timeline[0].player_id, Some(server.player_id(player)));
+        assert_eq!(public.timeline[0].other_player_id, Some(server.player_id(overtaken)));
+
+        let telemetry = ServerMessage::Telemetry(Telemetry {
+            server_tick: 10,
+            session_state: SessionState::Racing,
+            game_mode: GameMode::Race,
+            countdown_ms: None,
+            car_states: vec![CarStateTelemetry::from(&CarState::new(
+                player,
+                CarConfigId::new_v4(),
+                &GridSlot { position: 1, x: 0.0, y: 0.0, z: 0.0, yaw_rad: 0.0 },
+            ))],
+            pace_car: None,
+            race_progress: None,
+        });
+        let ServerMessage::Telemetry(public) = server.message(&telemetry) else {
+            panic!("Expected telemetry");
+        };
+        assert_eq!(public.car_states[0].player_id, server.player_id(player));
+    }
+}
@@ -0,0 +1,188 @@
+//! Persistent ban list.
+//!
+//! A ban matches a player's auth token, their IP address, or either when both
+//! are set. The list is written to a JSON file on every change so moderation
+//! decisions survive restarts; expired bans are dropped when it is loaded.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// One ban, by auth identity and/or IP
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BanEntry {
+    pub id: Uuid,
+    /// Auth token the player logged in with
+    #[serde(default)]
+    pub identity: Option<String>,
+    #[serde(default)]
+    pub ip: Option<IpAddr>,
+    /// Name the player used when banned, for admins reading the list
+    pub player_name: String,
+    pub reason: String,
+    /// Unix time the ban was issued (s)
+    pub banned_at: u64,
+    /// Unix time the ban lifts (s); None for a permanent ban
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+impl BanEntry {
+    pub fn is_active(&self, now: u64) -> bool {
+        self.expires_at.is_none_or(|expires_at| now < expires_at)
+    }
+
+    /// Message shown to the banned player
+    pub fn describe(&self, now: u64) -> String {
+        match self.expires_at {
+            Some(expires_at) => {
+                let minutes = expires_at.saturating_sub(now).div_ceil(60);
+                format!("Banned for {} more minute(s): {}", minutes, self.reason)
+            }
+            None => format!("Banned: {}", self.reason),
+        }
+    }
+
+    fn matches(&self, identity: Option<&str>, ip: Option<IpAddr>) -> bool {
+        let identity_match = matches!((&self.identity, identity), (Some(banned), Some(id)) if banned == id);
+        let ip_match = matches!((self.ip, ip), (Some(banned), Some(ip)) if banned == ip);
+        identity_match || ip_match
+    }
+}
+
+/// Ban entries, optionally backed by a file
+#[derive(Debug, Default)]
+pub struct BanList {
+    path: Option<PathBuf>,
+    entries: Vec<BanEntry>,
+}
+
+impl BanList {
+    /// In-memory list that is never written to disk
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the list stored at `path`; a missing file is an empty list
+    pub fn load<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut entries: Vec<BanEntry> = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+
+        let now = unix_now();
+        entries.retain(|entry| entry.is_active(now));
+
+        Ok(Self {
+            path: Some(path),
+            entries,
+        })
+    }
+
+    /// Active ban matching the identity or IP, if any
+    pub fn find(&self, identity: Option<&str>, ip: Option<IpAddr>, now: u64) -> Option<&BanEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.is_active(now) && entry.matches(identity, ip))
+    }
+
+    /// Bans still in force
+    pub fn active(&self, now: u64) -> impl Iterator<Item = &BanEntry> {
+        self.entries.iter().filter(move |entry| entry.is_active(now))
+    }
+
+    pub fn add(&mut self, entry: BanEntry) -> std::io::Result<()> {
+        self.entries.push(entry);
+        self.save()
+    }
+
+    /// Lift a ban, returning false if there is none with that ID
+    pub fn remove(&mut self, id: Uuid) -> std::io::Result<bool> {
+        let before = self.entries.len();
+        self.entries.retain(|entry| entry.id != id);
+        if self.entries.len() == before {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Write a sibling file and rename it over the list so a crash never leaves it half-written
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_string_pretty(&self.entries)?)?;
+        fs::rename(tmp_path, path)
+    }
+}
+
+/// Current Unix time in seconds, the clock ban expiry is measured against
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(identity: Option<&str>, ip: Option<&str>, expires_at: Option<u64>) -> BanEntry {
+        BanEntry {
+            id: Uuid::new_v4(),
+            identity: identity.map(str::to_string),
+            ip: ip.map(|ip| ip.parse().unwrap()),
+            player_name: "Griefer".to_string(),
+            reason: "Wrecking".to_string(),
+            banned_at: 1_000,
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn test_bans_match_identity_or_ip_until_expiry() {
+        let mut bans = BanList::new();
+        bans.add(entry(Some("token-a"), Some("10.0.0.1"), Some(2_000))).unwrap();
+        bans.add(entry(Some("token-b"), None, None)).unwrap();
+
+        let other_ip: IpAddr = "10.0.0.2".parse().unwrap();
+        assert!(bans.find(Some("token-a"), Some(other_ip), 1_500).is_some());
+        assert!(bans.find(Some("someone"), Some("10.0.0.1".parse().unwrap()), 1_500).is_some());
+        assert!(bans.find(Some("token-a"), None, 2_000).is_none(), "Ban expired");
+        assert!(bans.find(Some("token-b"), None, u64::MAX - 1).is_some(), "Permanent ban");
+        assert!(bans.find(Some("someone"), Some(other_ip), 1_500).is_none());
+        assert!(bans.find(None, None, 1_500).is_none());
+    }
+
+    #[test]
+    fn test_ban_list_survives_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("moderation").join("bans.json");
+
+        let mut bans = BanList::load(&path).unwrap();
+        let permanent = entry(Some("token-a"), None, None);
+        let expired = entry(Some("token-b"), None, Some(1));
+        let lifted = entry(None, Some("10.0.0.1"), None);
+        bans.add(permanent.clone()).unwrap();
+        bans.add(expired).unwrap();
+        bans.add(lifted.clone()).unwrap();
+        assert!(bans.remove(lifted.id).unwrap());
+        assert!(!bans.remove(lifted.id).unwrap());
+
+        let reloaded = BanList::load(&path).unwrap();
+        let now = unix_now();
+        assert_eq!(reloaded.active(now).cloned().collect::<Vec<_>>(), vec![permanent]);
+    }
+}
@@ -1,15 +1,15 @@
-/// Track Converter Tool
-/// 
-/// Converts race track data from the racetrack-database CSV format
-/// to ApexSim's YAML/JSON track format.
-/// 
-/// Usage:
-///   cargo run --bin convert_track -- --tracks-csv /path/to/tracks/Monaco.csv \
-///                                     --raceline-csv /path/to/racelines/Monaco.csv \
-///                                     --output /path/to/output/monaco.yaml \
-///                                     --name "Monaco Grand Prix" \
-///                                     --country Monaco \
-///                                     --category F1
+//! Track Converter Tool
+//! 
+//! Converts race track data from the racetrack-database CSV format
+//! to ApexSim's YAML/JSON track format.
+//! 
+//! Usage:
+//!   cargo run --bin convert_track -- --tracks-csv /path/to/tracks/Monaco.csv \
+//!                                     --raceline-csv /path/to/racelines/Monaco.csv \
+//!                                     --output /path/to/output/monaco.yaml \
+//!                                     --name "Monaco Grand Prix" \
+//!                                     --country Monaco \
+//!                                     --category F1
 
 use std::fs::File;
 use std::io::{BufRead, BufReader};
@@ -49,6 +49,10 @@ struct Args {
     #[arg(long)]
     category: Option<String>,
 
+    /// Comma-separated tags (e.g., street,historic)
+    #[arg(long, value_delimiter = ',')]
+    tags: Vec<String>,
+
     /// Year the track was built
     #[arg(long)]
     year_built: Option<u32>,
@@ -61,10 +65,6 @@ struct Args {
     #[arg(short = 'f', long)]
     format: Option<String>,
 
-    /// Elevation mode: flat (z=0) or auto-compute from data
-    #[arg(long, default_value = "flat")]
-    elevation: String,
-
     /// Default friction coefficient for all nodes
     #[arg(long, default_value = "1.0")]
     friction: f32,
@@ -231,13 +231,15 @@ fn convert_to_track_format(
         .map(|p| TrackNode {
             x: p.x,
             y: p.y,
-            z: if args.elevation == "flat" { 0.0 } else { 0.0 }, // Could compute from data
+            z: 0.0, // The CSV source has no elevation data
             width: None, // Use width_left/width_right instead
             width_left: Some(p.width_left),
             width_right: Some(p.width_right),
             banking: Some(0.0), // Could be computed from track geometry
+            camber: None,
             friction: Some(args.friction),
             surface_type: Some("Asphalt".to_string()),
+            runoff_surface: None,
         })
         .collect();
 
@@ -247,6 +249,7 @@ fn convert_to_track_format(
             x: p.x,
             y: p.y,
             z: 0.0,
+            target_speed_mps: 0.0,
         })
         .collect();
 
@@ -257,6 +260,8 @@ fn convert_to_track_format(
         description: args.description.clone(),
         year_built: args.year_built,
         category: args.category.clone(),
+        corners: None, // Counted when the track is loaded
+        tags: args.tags.clone(),
         // Procedural generation fields (not used for converted tracks)
         environment_type: None,
         terrain_seed: None,
@@ -265,6 +270,9 @@ fn convert_to_track_format(
         terrain_blend_width: None,
         object_density: None,
         decal_profile: None,
+        // TUMFTM layouts are not georeferenced
+        geo_origin: None,
+        terrain_dem: None,
     };
 
     // Calculate a reasonable default width from the nodes
@@ -287,6 +295,11 @@ fn convert_to_track_format(
         closed_loop: args.closed_loop,
         raceline,
         metadata: Some(metadata),
+        runoff_width: None,
+        boundary_walls: None,
+        walls: Vec::new(),
+        pit_lane: None, // The source data has no pit lanes
+        timing: None,
     }
 }
 
@@ -0,0 +1,176 @@
+//! GPS Track Importer
+//!
+//! Turns a recorded GPS lap (GPX or KML) into ApexSim's YAML/JSON track
+//! format: the trace is smoothed and resampled, a closed lap is found where
+//! it comes back on itself, and a waypoint named "Start" or "Finish" places
+//! the start/finish line.
+//!
+//! Usage:
+//!   cargo run --bin import_gps -- --input lap.gpx \
+//!                                  --output ../content/tracks/local/my-circuit.yaml \
+//!                                  --name "My Circuit" --width 10
+
+use apexsim_server::data::TrackMetadata;
+use apexsim_server::procgen::environment_presets::get_preset;
+use apexsim_server::track_import::{self, ElevationMode, ImportOptions};
+use apexsim_server::track_loader::TrackLoader;
+use clap::Parser;
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Recorded trace (.gpx or .kml)
+    #[arg(short = 'i', long)]
+    input: PathBuf,
+
+    /// Output file path (.yaml or .json)
+    #[arg(short = 'o', long)]
+    output: PathBuf,
+
+    /// Track name (default: the name in the trace, else the file name)
+    #[arg(short = 'n', long)]
+    name: Option<String>,
+
+    /// Track width (m)
+    #[arg(long, default_value = "12.0")]
+    width: f32,
+
+    /// Distance between track nodes (m)
+    #[arg(long, default_value = "5.0")]
+    spacing: f64,
+
+    /// Window the centerline is smoothed over (m); 0 keeps it as recorded
+    #[arg(long, default_value = "20.0")]
+    smoothing: f64,
+
+    /// Elevation mode: gps (recorded altitude, smoothed) or flat
+    #[arg(long, default_value = "gps")]
+    elevation: String,
+
+    /// Window the elevation is smoothed over (m)
+    #[arg(long, default_value = "100.0")]
+    elevation_smoothing: f64,
+
+    /// Closed loop: auto finds a lap, yes requires one, no keeps a road course
+    #[arg(long, default_value = "auto")]
+    closed_loop: String,
+
+    /// How close the trace must come back to itself to close a lap (m)
+    #[arg(long, default_value = "25.0")]
+    lap_closure: f64,
+
+    /// Biome to generate terrain around the track in (desert, forest, alpine, coastal, ...)
+    #[arg(long)]
+    environment: Option<String>,
+
+    /// SRTM tile directory or GeoTIFF to take that terrain from instead of noise
+    #[arg(long, requires = "environment")]
+    terrain_dem: Option<PathBuf>,
+
+    /// Country where the track is located
+    #[arg(long)]
+    country: Option<String>,
+
+    /// City where the track is located
+    #[arg(long)]
+    city: Option<String>,
+
+    /// Output format: yaml or json (auto-detected from extension if not specified)
+    #[arg(short = 'f', long)]
+    format: Option<String>,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+
+    if let Some(environment) = args.environment.as_deref().filter(|name| get_preset(name).is_none()) {
+        return Err(format!("Unknown environment: {}", environment).into());
+    }
+
+    let options = ImportOptions {
+        node_spacing_m: args.spacing,
+        smoothing_m: args.smoothing,
+        elevation_smoothing_m: args.elevation_smoothing,
+        elevation: match args.elevation.as_str() {
+            "gps" => ElevationMode::Recorded,
+            "flat" => ElevationMode::Flat,
+            other => return Err(format!("Unknown elevation mode: {}", other).into()),
+        },
+        width_m: args.width,
+        closed_loop: match args.closed_loop.as_str() {
+            "auto" => None,
+            "yes" | "true" => Some(true),
+            "no" | "false" => Some(false),
+            other => return Err(format!("Unknown closed loop setting: {}", other).into()),
+        },
+        lap_closure_m: args.lap_closure,
+    };
+
+    println!("Reading trace from: {}", args.input.display());
+    let trace = track_import::read_trace(&args.input)?;
+    println!("  Loaded {} points and {} waypoints", trace.points.len(), trace.markers.len());
+
+    let name = args
+        .name
+        .clone()
+        .or_else(|| trace.name.clone())
+        .or_else(|| args.input.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "Imported track".to_string());
+    println!("Importing track: {}", name);
+    let mut track_file = track_import::trace_to_track(&trace, &name, &options)?;
+    let length_m = track_file.metadata.as_ref().and_then(|metadata| metadata.length_m).unwrap_or(0.0);
+    let terrain_dem = match &args.terrain_dem {
+        Some(dem) => Some(track_import::path_from_track(&args.output, dem).map_err(|e| format!("{}: {}", dem.display(), e))?),
+        None => None,
+    };
+    // Keep the import's length and geo origin
+    let metadata = track_file.metadata.take().unwrap_or_default();
+    track_file.metadata = Some(TrackMetadata {
+        country: args.country.clone(),
+        city: args.city.clone(),
+        environment_type: args.environment.clone(),
+        terrain_dem,
+        ..metadata
+    });
+    println!(
+        "  {} nodes, {}, {:.2} km",
+        track_file.nodes.len(),
+        if track_file.closed_loop { "closed loop" } else { "open road course" },
+        length_m / 1000.0
+    );
+    let climb = track_file.nodes.iter().map(|node| node.z).fold(0.0_f32, f32::max);
+    println!("  Elevation range: {:.1} m", climb);
+
+    // Determine output format
+    let format = if let Some(ref fmt) = args.format {
+        fmt.to_lowercase()
+    } else {
+        args.output
+            .extension()
+            .and_then(|s| s.to_str())
+            .unwrap_or("yaml")
+            .to_lowercase()
+    };
+    let content = match format.as_str() {
+        "json" => serde_json::to_string_pretty(&track_file)?,
+        "yaml" | "yml" => serde_yaml::to_string(&track_file)?,
+        _ => return Err(format!("Unsupported output format: {}", format).into()),
+    };
+
+    // Report anything that would keep the server from loading the track
+    let problems = TrackLoader::validate(&content);
+    for problem in &problems {
+        eprintln!("Warning: {}", problem);
+    }
+
+    println!("Writing to: {}", args.output.display());
+    std::fs::write(&args.output, content)?;
+    if problems.is_empty() {
+        println!("✓ Import completed successfully!");
+    } else {
+        println!("Imported with {} problem(s) to fix before the server loads it", problems.len());
+    }
+
+    Ok(())
+}
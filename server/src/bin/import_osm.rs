@@ -0,0 +1,226 @@
+//! OpenStreetMap Track Importer
+//!
+//! Builds an ApexSim track from OpenStreetMap road data: a way, a relation
+//! whose ways make up the circuit, or the `highway=raceway` ways in a
+//! bounding box. Widths come from the ways' `width` or `lanes` tags and
+//! heights from SRTM elevation tiles.
+//!
+//! Usage:
+//!   cargo run --bin import_osm -- --relation 1234567 --print-query
+//!   curl -o circuit.osm "<printed URL>"
+//!   cargo run --bin import_osm -- --osm circuit.osm --relation 1234567 \
+//!                                  --dem ~/srtm --output ../content/tracks/local/circuit.yaml
+//!
+//! With `--environment forest --terrain-dem ~/srtm` the generated terrain
+//! around the track comes from the same tiles, so it sits in its real hills.
+
+use apexsim_server::data::TrackMetadata;
+use apexsim_server::procgen::environment_presets::get_preset;
+use apexsim_server::track_import::{self, ElevationMode, ElevationModel, ImportOptions, OsmSelection, SrtmTiles};
+use apexsim_server::track_loader::TrackLoader;
+use clap::Parser;
+use std::path::PathBuf;
+
+const OVERPASS_URL: &str = "https://overpass-api.de/api/interpreter";
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// OSM XML file holding the selected ways and their nodes
+    #[arg(long, required_unless_present = "print_query")]
+    osm: Option<PathBuf>,
+
+    /// Build the track from this way
+    #[arg(long, conflicts_with_all = ["relation", "bbox"])]
+    way: Option<i64>,
+
+    /// Build the track from the ways of this relation
+    #[arg(long, conflicts_with = "bbox")]
+    relation: Option<i64>,
+
+    /// Build the track from the raceway ways in south,west,north,east
+    #[arg(long, allow_hyphen_values = true)]
+    bbox: Option<String>,
+
+    /// Print the Overpass API URL that downloads the selection, and exit
+    #[arg(long)]
+    print_query: bool,
+
+    /// Output file path (.yaml or .json)
+    #[arg(short = 'o', long, required_unless_present = "print_query")]
+    output: Option<PathBuf>,
+
+    /// Track name (default: the way's or relation's name)
+    #[arg(short = 'n', long)]
+    name: Option<String>,
+
+    /// Directory of SRTM .hgt tiles for elevation; flat without one
+    #[arg(long)]
+    dem: Option<PathBuf>,
+
+    /// Width of ways without a width or lanes tag (m)
+    #[arg(long, default_value = "12.0")]
+    width: f32,
+
+    /// Distance between track nodes (m)
+    #[arg(long, default_value = "5.0")]
+    spacing: f64,
+
+    /// Window the centerline is smoothed over (m); 0 keeps it as mapped
+    #[arg(long, default_value = "20.0")]
+    smoothing: f64,
+
+    /// Window the elevation is smoothed over (m)
+    #[arg(long, default_value = "100.0")]
+    elevation_smoothing: f64,
+
+    /// Closed loop: auto follows the ways, yes requires them to close, no keeps a road course
+    #[arg(long, default_value = "auto")]
+    closed_loop: String,
+
+    /// Biome to generate terrain around the track in (desert, forest, alpine, coastal, ...)
+    #[arg(long)]
+    environment: Option<String>,
+
+    /// SRTM tile directory or GeoTIFF to take that terrain from instead of noise
+    #[arg(long, requires = "environment")]
+    terrain_dem: Option<PathBuf>,
+
+    /// Country where the track is located
+    #[arg(long)]
+    country: Option<String>,
+
+    /// City where the track is located
+    #[arg(long)]
+    city: Option<String>,
+
+    /// Output format: yaml or json (auto-detected from extension if not specified)
+    #[arg(short = 'f', long)]
+    format: Option<String>,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+
+    let selection = match (args.way, args.relation, &args.bbox) {
+        (Some(id), _, _) => OsmSelection::Way(id),
+        (_, Some(id), _) => OsmSelection::Relation(id),
+        (_, _, Some(bbox)) => {
+            let values = bbox.split(',').map(|v| v.trim().parse::<f64>()).collect::<Result<Vec<_>, _>>();
+            match values.as_deref() {
+                Ok(&[south, west, north, east]) if south < north && west < east => {
+                    OsmSelection::BoundingBox { south, west, north, east }
+                }
+                _ => return Err(format!("Invalid bounding box {}, expected south,west,north,east", bbox).into()),
+            }
+        }
+        _ => return Err("Select the track with --way, --relation or --bbox".into()),
+    };
+    if args.print_query {
+        println!("{}?data={}", OVERPASS_URL, percent_encode(&selection.overpass_query()));
+        return Ok(());
+    }
+    let (Some(osm), Some(output)) = (&args.osm, &args.output) else {
+        return Err("--osm and --output are required".into());
+    };
+
+    if let Some(environment) = args.environment.as_deref().filter(|name| get_preset(name).is_none()) {
+        return Err(format!("Unknown environment: {}", environment).into());
+    }
+
+    let options = ImportOptions {
+        node_spacing_m: args.spacing,
+        smoothing_m: args.smoothing,
+        elevation_smoothing_m: args.elevation_smoothing,
+        elevation: if args.dem.is_some() { ElevationMode::Recorded } else { ElevationMode::Flat },
+        width_m: args.width,
+        closed_loop: match args.closed_loop.as_str() {
+            "auto" => None,
+            "yes" | "true" => Some(true),
+            "no" | "false" => Some(false),
+            other => return Err(format!("Unknown closed loop setting: {}", other).into()),
+        },
+        ..Default::default()
+    };
+
+    println!("Reading OSM data from: {}", osm.display());
+    let data = track_import::read_osm(osm)?;
+    println!("  Loaded {} nodes, {} ways and {} relations", data.nodes.len(), data.ways.len(), data.relations.len());
+
+    let mut dem = args.dem.as_ref().map(|dir| {
+        println!("Reading elevation from: {}", dir.display());
+        SrtmTiles::new(dir)
+    });
+    let mut track_file = track_import::osm_to_track(
+        &data,
+        selection,
+        dem.as_mut().map(|dem| dem as &mut dyn ElevationModel),
+        args.name.as_deref(),
+        &options,
+    )?;
+    println!("Importing track: {}", track_file.name);
+    let length_m = track_file.metadata.as_ref().and_then(|metadata| metadata.length_m).unwrap_or(0.0);
+    let terrain_dem = match &args.terrain_dem {
+        Some(dem) => Some(track_import::path_from_track(output, dem).map_err(|e| format!("{}: {}", dem.display(), e))?),
+        None => None,
+    };
+    // Keep the import's length and geo origin
+    let metadata = track_file.metadata.take().unwrap_or_default();
+    track_file.metadata = Some(TrackMetadata {
+        country: args.country.clone(),
+        city: args.city.clone(),
+        environment_type: args.environment.clone(),
+        terrain_dem,
+        ..metadata
+    });
+    println!(
+        "  {} nodes, {}, {:.2} km",
+        track_file.nodes.len(),
+        if track_file.closed_loop { "closed loop" } else { "open road course" },
+        length_m / 1000.0
+    );
+    let climb = track_file.nodes.iter().map(|node| node.z).fold(0.0_f32, f32::max);
+    println!("  Elevation range: {:.1} m", climb);
+
+    // Determine output format
+    let format = if let Some(ref fmt) = args.format {
+        fmt.to_lowercase()
+    } else {
+        output
+            .extension()
+            .and_then(|s| s.to_str())
+            .unwrap_or("yaml")
+            .to_lowercase()
+    };
+    let content = match format.as_str() {
+        "json" => serde_json::to_string_pretty(&track_file)?,
+        "yaml" | "yml" => serde_yaml::to_string(&track_file)?,
+        _ => return Err(format!("Unsupported output format: {}", format).into()),
+    };
+
+    // Report anything that would keep the server from loading the track
+    let problems = TrackLoader::validate(&content);
+    for problem in &problems {
+        eprintln!("Warning: {}", problem);
+    }
+
+    println!("Writing to: {}", output.display());
+    std::fs::write(output, content)?;
+    if problems.is_empty() {
+        println!("✓ Import completed successfully!");
+    } else {
+        println!("Imported with {} problem(s) to fix before the server loads it", problems.len());
+    }
+
+    Ok(())
+}
+
+/// Percent-encode everything but unreserved URL characters
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
@@ -0,0 +1,88 @@
+//! Load Test Harness
+//!
+//! Drives a running server with simulated clients as described by a scenario
+//! TOML file and writes a JSON report of how it held up.
+//!
+//! Usage:
+//!   cargo run --release --bin apexsim-loadtest -- loadtest/baseline.toml \
+//!                                                 --report results/baseline.json
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use clap::Parser;
+use apexsim_server::loadtest::{self, Scenario};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Scenario TOML file
+    scenario: PathBuf,
+
+    /// Server TCP address, overriding the scenario's target
+    #[arg(short = 't', long)]
+    target: Option<String>,
+
+    /// Write the JSON report here instead of to stdout
+    #[arg(short = 'r', long)]
+    report: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let mut scenario = match Scenario::load(&args.scenario) {
+        Ok(scenario) => scenario,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+    if let Some(target) = args.target {
+        scenario.target = target;
+    }
+
+    eprintln!(
+        "Running '{}': {} clients against {} for {:.0}s",
+        scenario.name, scenario.clients, scenario.target, scenario.duration_s
+    );
+    let report = loadtest::run(&scenario).await;
+
+    if let Some(error) = &report.error {
+        eprintln!("Error: {}", error);
+    }
+    eprintln!(
+        "Sustained {:.1} Hz of {} Hz ({:.0}%), {} inputs sent, {} telemetry received, {}/{} clients with telemetry",
+        report.actual_tick_rate_hz,
+        scenario.tick_rate_hz,
+        report.tick_ratio * 100.0,
+        report.total_inputs_sent,
+        report.total_telemetry_received,
+        report.clients_with_telemetry,
+        scenario.clients
+    );
+    eprintln!("{}", if report.passed { "PASSED" } else { "FAILED" });
+
+    let json = match serde_json::to_string_pretty(&report) {
+        Ok(json) => json,
+        Err(e) => {
+            eprintln!("Error: Failed to serialize report: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+    match &args.report {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, json) {
+                eprintln!("Error: Failed to write {}: {}", path.display(), e);
+                return ExitCode::from(2);
+            }
+        }
+        None => println!("{}", json),
+    }
+
+    if report.passed {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
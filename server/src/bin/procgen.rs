@@ -0,0 +1,101 @@
+//! Procedural World Baker
+//!
+//! Generates the terrain cache (`<track>.terrain.msgpack`) and world
+//! manifest (`<track>.world.yaml`) of tracks with an `environment_type`,
+//! without starting the server. Takes track files, directories of them, or
+//! world manifests; a manifest bakes the track beside it from the
+//! parameters it holds, always, so a tweaked seed or preset can be tried
+//! without touching the track file.
+//!
+//! Usage:
+//!   cargo run --bin procgen -- ../content/tracks
+//!   cargo run --bin procgen -- ../content/tracks/real/Spa.yaml --force
+//!   cargo run --bin procgen -- ../content/tracks/real/Spa.world.yaml
+//!
+//! Caches that are newer than their track file and whose manifest matches
+//! are left alone unless `--force` is given.
+
+use apexsim_server::procgen::manifest::{self, WorldParams};
+use apexsim_server::procgen::terrain::{bake_track_world, track_files};
+use clap::Parser;
+use std::path::{Path, PathBuf};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Track files, world manifests (.world.yaml) or directories of tracks
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Rebuild worlds even when their cache is up to date
+    #[arg(long)]
+    force: bool,
+}
+
+/// What to bake: a track, from its metadata or from a manifest's parameters
+struct Job {
+    track_file: PathBuf,
+    params: Option<WorldParams>,
+    /// Bake even when the cache is up to date
+    force: bool,
+}
+
+fn jobs_for(path: &Path) -> Result<Vec<Job>, String> {
+    if path.is_dir() {
+        return Ok(track_files(path)?
+            .into_iter()
+            .map(|track_file| Job { track_file, params: None, force: false })
+            .collect());
+    }
+    if !path.is_file() {
+        return Err(format!("{} not found", path.display()));
+    }
+    if manifest::is_manifest_file(path) {
+        let track_file = manifest::track_for_manifest(path)
+            .ok_or_else(|| format!("No track file next to {}", path.display()))?;
+        let content = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let params: WorldParams =
+            serde_yaml::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+        // The manifest on disk is what the cache is checked against, so it
+        // always looks up to date
+        return Ok(vec![Job { track_file, params: Some(params), force: true }]);
+    }
+    Ok(vec![Job { track_file: path.to_path_buf(), params: None, force: false }])
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let (mut baked, mut skipped, mut failed) = (0, 0, 0);
+    for path in &args.paths {
+        let jobs = match jobs_for(path) {
+            Ok(jobs) => jobs,
+            Err(e) => {
+                eprintln!("❌ {}", e);
+                failed += 1;
+                continue;
+            }
+        };
+        for job in jobs {
+            match bake_track_world(&job.track_file, job.params, job.force || args.force) {
+                Ok(true) => {
+                    baked += 1;
+                    println!("✅ Baked {}", job.track_file.display());
+                }
+                Ok(false) => {
+                    skipped += 1;
+                    println!("⏭️  Skipped {} (up to date or no environment_type)", job.track_file.display());
+                }
+                Err(e) => {
+                    failed += 1;
+                    eprintln!("❌ {}: {}", job.track_file.display(), e);
+                }
+            }
+        }
+    }
+
+    println!("{} baked, {} skipped, {} failed", baked, skipped, failed);
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
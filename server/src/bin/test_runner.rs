@@ -20,7 +20,8 @@ struct TestCase {
     function_name: String,
     file: String,
     description: String,
-    requires_server: bool,
+    /// Long-running and `#[ignore]`d, so run with `--ignored`
+    slow: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -56,14 +57,12 @@ impl TestRunner {
             "...".to_string()
         } else {
             let mut result = String::new();
-            let mut char_count = 0;
-            for ch in s.chars() {
+            for (char_count, ch) in s.chars().enumerate() {
                 if char_count + 3 >= max_len {
                     result.push_str("...");
                     break;
                 }
                 result.push(ch);
-                char_count += 1;
             }
             result
         }
@@ -80,49 +79,49 @@ impl TestRunner {
                         function_name: "test_server_initialization".to_string(),
                         file: "integration_test".to_string(),
                         description: "Basic server startup test".to_string(),
-                        requires_server: false,
+                        slow: false,
                     },
                     TestCase {
                         name: "CLI Client Workflow".to_string(),
                         function_name: "test_cli_client_workflow".to_string(),
                         file: "integration_test".to_string(),
                         description: "Complete client workflow: auth, car select, race".to_string(),
-                        requires_server: true,
+                        slow: false,
                     },
                     TestCase {
                         name: "Multiplayer Race Session".to_string(),
                         function_name: "test_multiplayer_race_session".to_string(),
                         file: "integration_test".to_string(),
                         description: "4 clients racing together".to_string(),
-                        requires_server: true,
+                        slow: false,
                     },
                     TestCase {
                         name: "Telemetry Broadcast".to_string(),
                         function_name: "test_telemetry_broadcast".to_string(),
                         file: "integration_test".to_string(),
                         description: "Verify clients receive telemetry broadcasts".to_string(),
-                        requires_server: true,
+                        slow: false,
                     },
                     TestCase {
                         name: "Tick Rate Stress".to_string(),
                         function_name: "test_tick_rate_stress".to_string(),
                         file: "integration_test".to_string(),
-                        description: "Test various tick rates (120Hz-1440Hz)".to_string(),
-                        requires_server: true,
+                        description: "Test various tick rates (120Hz-960Hz)".to_string(),
+                        slow: true,
                     },
                     TestCase {
                         name: "Multi-Client Load".to_string(),
                         function_name: "test_multi_client_load".to_string(),
                         file: "integration_test".to_string(),
                         description: "16 concurrent clients load test".to_string(),
-                        requires_server: true,
+                        slow: true,
                     },
                     TestCase {
                         name: "Sandbox Session Workflow".to_string(),
                         function_name: "test_sandbox_session_workflow".to_string(),
                         file: "integration_test".to_string(),
                         description: "Sandbox mode session workflow".to_string(),
-                        requires_server: true,
+                        slow: false,
                     },
                 ],
             },
@@ -135,91 +134,91 @@ impl TestRunner {
                         function_name: "test_create_session".to_string(),
                         file: "lobby_integration_tests".to_string(),
                         description: "Session creation flow".to_string(),
-                        requires_server: true,
+                        slow: false,
                     },
                     TestCase {
                         name: "Join Session".to_string(),
                         function_name: "test_join_session".to_string(),
                         file: "lobby_integration_tests".to_string(),
                         description: "Join existing session".to_string(),
-                        requires_server: true,
+                        slow: false,
                     },
                     TestCase {
                         name: "Leave Session".to_string(),
                         function_name: "test_leave_session".to_string(),
                         file: "lobby_integration_tests".to_string(),
                         description: "Leave session flow".to_string(),
-                        requires_server: true,
+                        slow: false,
                     },
                     TestCase {
                         name: "Session Cleanup on Empty".to_string(),
                         function_name: "test_session_cleanup_on_empty".to_string(),
                         file: "lobby_integration_tests".to_string(),
                         description: "Automatic session cleanup".to_string(),
-                        requires_server: true,
+                        slow: false,
                     },
                     TestCase {
                         name: "Max Players Limit".to_string(),
                         function_name: "test_max_players_limit".to_string(),
                         file: "lobby_integration_tests".to_string(),
                         description: "Session player limit enforcement".to_string(),
-                        requires_server: true,
+                        slow: false,
                     },
                     TestCase {
                         name: "Rapid Join/Leave".to_string(),
                         function_name: "test_rapid_join_leave".to_string(),
                         file: "lobby_integration_tests".to_string(),
                         description: "Rapid join/leave operations".to_string(),
-                        requires_server: true,
+                        slow: false,
                     },
                     TestCase {
                         name: "Multiple Sessions".to_string(),
                         function_name: "test_multiple_sessions".to_string(),
                         file: "lobby_integration_tests".to_string(),
                         description: "Multiple concurrent sessions".to_string(),
-                        requires_server: true,
+                        slow: false,
                     },
                     TestCase {
                         name: "Join Nonexistent Session".to_string(),
                         function_name: "test_join_nonexistent_session".to_string(),
                         file: "lobby_integration_tests".to_string(),
                         description: "Error handling for invalid session".to_string(),
-                        requires_server: true,
+                        slow: false,
                     },
                     TestCase {
                         name: "Lobby State Broadcast".to_string(),
                         function_name: "test_lobby_state_broadcast".to_string(),
                         file: "lobby_integration_tests".to_string(),
                         description: "Lobby state updates to all clients".to_string(),
-                        requires_server: true,
+                        slow: false,
                     },
                     TestCase {
                         name: "Player Returns to Lobby".to_string(),
                         function_name: "test_player_returns_to_lobby".to_string(),
                         file: "lobby_integration_tests".to_string(),
                         description: "Return to lobby after race".to_string(),
-                        requires_server: true,
+                        slow: false,
                     },
                     TestCase {
                         name: "Session Kinds".to_string(),
                         function_name: "test_session_kinds".to_string(),
                         file: "lobby_integration_tests".to_string(),
                         description: "Different session types".to_string(),
-                        requires_server: true,
+                        slow: false,
                     },
                     TestCase {
                         name: "Disconnect Cleanup".to_string(),
                         function_name: "test_disconnect_cleanup".to_string(),
                         file: "lobby_integration_tests".to_string(),
                         description: "Cleanup on client disconnect".to_string(),
-                        requires_server: true,
+                        slow: false,
                     },
                     TestCase {
                         name: "Demo Mode Lap Timing".to_string(),
                         function_name: "test_demo_mode_lap_timing".to_string(),
                         file: "lobby_integration_tests".to_string(),
                         description: "Lap timing in demo mode".to_string(),
-                        requires_server: true,
+                        slow: true,
                     },
                 ],
             },
@@ -232,7 +231,7 @@ impl TestRunner {
                         function_name: "test_demo_lap_timing".to_string(),
                         file: "demo_lap_tests".to_string(),
                         description: "Demo lap timing accuracy".to_string(),
-                        requires_server: false,
+                        slow: true,
                     },
                 ],
             },
@@ -245,28 +244,28 @@ impl TestRunner {
                         function_name: "test_server_starts_without_tls_when_not_required".to_string(),
                         file: "tls_requirement_test".to_string(),
                         description: "Server starts without TLS when not required".to_string(),
-                        requires_server: false,
+                        slow: false,
                     },
                     TestCase {
                         name: "TLS Required (Fails Without Certs)".to_string(),
                         function_name: "test_server_fails_without_tls_when_required".to_string(),
                         file: "tls_requirement_test".to_string(),
                         description: "Server fails without TLS when required".to_string(),
-                        requires_server: false,
+                        slow: false,
                     },
                     TestCase {
                         name: "TLS Required (Starts With Certs)".to_string(),
                         function_name: "test_server_starts_with_tls_when_required_and_certs_exist".to_string(),
                         file: "tls_requirement_test".to_string(),
                         description: "Server starts with TLS when certs exist".to_string(),
-                        requires_server: false,
+                        slow: false,
                     },
                     TestCase {
                         name: "TLS State Logging".to_string(),
                         function_name: "test_tls_state_logging".to_string(),
                         file: "tls_requirement_test".to_string(),
                         description: "TLS state logging".to_string(),
-                        requires_server: false,
+                        slow: false,
                     },
                 ],
             },
@@ -279,28 +278,28 @@ impl TestRunner {
                         function_name: "test_bounded_channels_prevent_oom".to_string(),
                         file: "transport_backpressure_test".to_string(),
                         description: "Backpressure prevents memory issues".to_string(),
-                        requires_server: false,
+                        slow: false,
                     },
                     TestCase {
                         name: "Droppable Messages Dropped".to_string(),
                         function_name: "test_droppable_messages_are_dropped_when_queue_full".to_string(),
                         file: "transport_backpressure_test".to_string(),
                         description: "Message dropping when queue full".to_string(),
-                        requires_server: false,
+                        slow: false,
                     },
                     TestCase {
                         name: "Message Priority Classification".to_string(),
                         function_name: "test_message_priority_classification".to_string(),
                         file: "transport_backpressure_test".to_string(),
                         description: "Message priority handling".to_string(),
-                        requires_server: false,
+                        slow: false,
                     },
                     TestCase {
                         name: "Metrics Tracking".to_string(),
                         function_name: "test_metrics_tracking".to_string(),
                         file: "transport_backpressure_test".to_string(),
                         description: "Metrics collection".to_string(),
-                        requires_server: false,
+                        slow: false,
                     },
                 ],
             },
@@ -460,10 +459,10 @@ impl TestRunner {
         for (idx, test) in category.tests.iter().enumerate() {
             let is_selected = idx == self.selected_test;
 
-            let server_indicator = if test.requires_server { "[S]" } else { "   " };
+            let slow_indicator = if test.slow { "[L]" } else { "   " };
             let max_name_width = (width as usize).saturating_sub(12);
             let test_name = Self::truncate_str(&test.name, max_name_width);
-            let line = format!(" {} {:2}. {}", server_indicator, idx + 1, test_name);
+            let line = format!(" {} {:2}. {}", slow_indicator, idx + 1, test_name);
             let line = Self::truncate_str(&line, (width as usize).saturating_sub(1));
 
             execute!(stdout, MoveTo(0, current_row))?;
@@ -499,7 +498,7 @@ impl TestRunner {
         }
 
         // Footer
-        let footer = Self::truncate_str("  [S] = Requires running server on 127.0.0.1:9000", (width as usize).saturating_sub(1));
+        let footer = Self::truncate_str("  [L] = Long-running, skipped by plain cargo test", (width as usize).saturating_sub(1));
         execute!(
             stdout,
             MoveTo(0, height - 1),
@@ -557,11 +556,9 @@ impl TestRunner {
 
         let end_line = (start_line + available_lines).min(total_output_lines);
 
-        let mut current_row = header_lines;
-        for line in &self.test_output[start_line..end_line] {
+        for (current_row, line) in (header_lines..).zip(&self.test_output[start_line..end_line]) {
             let truncated = Self::truncate_str(line, (width as usize).saturating_sub(1));
             execute!(stdout, MoveTo(0, current_row), Print(&truncated))?;
-            current_row += 1;
         }
 
         // Footer
@@ -614,11 +611,9 @@ impl TestRunner {
         let start_line = self.output_scroll;
         let end_line = (start_line + available_lines).min(self.test_output.len());
 
-        let mut current_row = header_lines;
-        for line in &self.test_output[start_line..end_line] {
+        for (current_row, line) in (header_lines..).zip(&self.test_output[start_line..end_line]) {
             let truncated = Self::truncate_str(line, (width as usize).saturating_sub(1));
             execute!(stdout, MoveTo(0, current_row), Print(&truncated))?;
-            current_row += 1;
         }
 
         // Footer
@@ -663,8 +658,8 @@ impl TestRunner {
             .arg(&test.function_name)
             .arg("--");
 
-        // Only pass --ignored for tests marked as requiring server (which have #[ignore])
-        if test.requires_server {
+        // Only pass --ignored for the long-running tests (which have #[ignore])
+        if test.slow {
             cmd.arg("--ignored");
         }
 
@@ -672,7 +667,7 @@ impl TestRunner {
             .arg("--test-threads=1")
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
-            .current_dir("/home/guido/apexsim/server");
+            .current_dir(env!("CARGO_MANIFEST_DIR"));
 
         let process_handle = Arc::new(Mutex::new(None::<Child>));
         self.running_process = Some(process_handle.clone());
@@ -693,10 +688,8 @@ impl TestRunner {
 
             if let Some(stdout) = stdout {
                 let reader = std::io::BufReader::new(stdout);
-                for line in reader.lines() {
-                    if let Ok(line) = line {
-                        output_lines_clone.lock().unwrap().push(line);
-                    }
+                for line in reader.lines().map_while(Result::ok) {
+                    output_lines_clone.lock().unwrap().push(line);
                 }
             }
         });
@@ -707,10 +700,8 @@ impl TestRunner {
 
             if let Some(stderr) = stderr {
                 let reader = std::io::BufReader::new(stderr);
-                for line in reader.lines() {
-                    if let Ok(line) = line {
-                        output_lines_clone.lock().unwrap().push(line);
-                    }
+                for line in reader.lines().map_while(Result::ok) {
+                    output_lines_clone.lock().unwrap().push(line);
                 }
             }
         });
@@ -786,16 +777,14 @@ impl TestRunner {
 
     fn handle_category_input(&mut self, key: KeyEvent) -> io::Result<bool> {
         match key.code {
-            KeyCode::Up => {
-                if self.selected_category > 0 {
+            KeyCode::Up
+                if self.selected_category > 0 => {
                     self.selected_category -= 1;
                 }
-            }
-            KeyCode::Down => {
-                if self.selected_category < self.categories.len() - 1 {
+            KeyCode::Down
+                if self.selected_category < self.categories.len() - 1 => {
                     self.selected_category += 1;
                 }
-            }
             KeyCode::Enter => {
                 self.selected_test = 0;
                 self.mode = Mode::TestMenu;
@@ -812,16 +801,14 @@ impl TestRunner {
         let category = &self.categories[self.selected_category];
 
         match key.code {
-            KeyCode::Up => {
-                if self.selected_test > 0 {
+            KeyCode::Up
+                if self.selected_test > 0 => {
                     self.selected_test -= 1;
                 }
-            }
-            KeyCode::Down => {
-                if self.selected_test < category.tests.len() - 1 {
+            KeyCode::Down
+                if self.selected_test < category.tests.len() - 1 => {
                     self.selected_test += 1;
                 }
-            }
             KeyCode::Enter => {
                 self.run_test()?;
             }
@@ -841,11 +828,10 @@ impl TestRunner {
         let available_lines = (height as usize).saturating_sub(4);
 
         match key.code {
-            KeyCode::Up => {
-                if self.output_scroll > 0 {
+            KeyCode::Up
+                if self.output_scroll > 0 => {
                     self.output_scroll -= 1;
                 }
-            }
             KeyCode::Down => {
                 let max_scroll = self
                     .test_output
@@ -941,13 +927,13 @@ impl TestRunner {
                 .arg(&test.function_name)
                 .arg("--");
 
-            if test.requires_server {
+            if test.slow {
                 cmd.arg("--ignored");
             }
 
             cmd.arg("--nocapture")
                 .arg("--test-threads=1")
-                .current_dir("/home/guido/apexsim/server");
+                .current_dir(env!("CARGO_MANIFEST_DIR"));
 
             let output = cmd.output()?;
             let stdout = String::from_utf8_lossy(&output.stdout);
@@ -1004,12 +990,12 @@ fn list_categories(runner: &TestRunner) {
         println!("{}. {} ({} tests)", idx + 1, category.name, category.tests.len());
         println!("   {}", category.description);
         for (tidx, test) in category.tests.iter().enumerate() {
-            let server_marker = if test.requires_server { "[S]" } else { "   " };
-            println!("      {} {}.{} {}", server_marker, idx + 1, tidx + 1, test.name);
+            let slow_marker = if test.slow { "[L]" } else { "   " };
+            println!("      {} {}.{} {}", slow_marker, idx + 1, tidx + 1, test.name);
         }
         println!();
     }
-    println!("[S] = Requires running server on 127.0.0.1:9000");
+    println!("[L] = Long-running, skipped by plain cargo test");
 }
 
 fn main() -> io::Result<()> {
@@ -1,8 +1,12 @@
 use crate::data::*;
+use crate::content_hash;
 use serde::Deserialize;
 use std::path::Path;
 use uuid::Uuid;
 
+/// Clutch capacity as a multiple of peak engine torque when a car file gives none
+const DEFAULT_CLUTCH_CAPACITY_FACTOR: f32 = 1.5;
+
 #[derive(Debug, Deserialize)]
 struct CarToml {
     id: String,
@@ -26,6 +30,18 @@ struct CarToml {
     fuel: Option<FuelToml>,
     #[serde(default)]
     hybrid: Option<HybridToml>,
+    #[serde(default)]
+    tires: Option<TiresToml>,
+    #[serde(default)]
+    suspension: Option<SuspensionToml>,
+    #[serde(default)]
+    aero: Option<AeroToml>,
+    #[serde(default)]
+    setup: Option<SetupToml>,
+    #[serde(default)]
+    collision: Option<CollisionToml>,
+    #[serde(default)]
+    livery: Vec<LiveryToml>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -75,6 +91,18 @@ struct EngineToml {
 
     #[serde(default)]
     torque_curve: Vec<TorqueCurvePointToml>,
+
+    #[serde(default)]
+    turbo: Option<TurboToml>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TurboToml {
+    #[serde(default)]
+    boost_torque_share: Option<f32>,
+    boost_threshold_rpm: f32,
+    #[serde(default)]
+    spool_time_s: Option<f32>,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -89,6 +117,8 @@ struct TransmissionToml {
     shift_time_s: Option<f32>,
     #[serde(default)]
     efficiency: Option<f32>,
+    #[serde(default)]
+    clutch_max_torque_nm: Option<f32>,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -109,6 +139,191 @@ struct DifferentialToml {
     lock_coast: Option<f32>,
 }
 
+/// Optional tire model tuning; unset values use the `TireConfig` defaults
+#[derive(Debug, Deserialize, Default)]
+struct TiresToml {
+    #[serde(default)]
+    optimal_slip_ratio: Option<f32>,
+    #[serde(default)]
+    optimal_slip_angle_rad: Option<f32>,
+    #[serde(default)]
+    longitudinal_shape: Option<f32>,
+    #[serde(default)]
+    longitudinal_curvature: Option<f32>,
+    #[serde(default)]
+    lateral_shape: Option<f32>,
+    #[serde(default)]
+    lateral_curvature: Option<f32>,
+    #[serde(default)]
+    load_sensitivity: Option<f32>,
+    #[serde(default)]
+    nominal_load_n: Option<f32>,
+}
+
+/// Optional aerodynamic surfaces; drag stays in `[physics]`
+#[derive(Debug, Deserialize, Default)]
+struct AeroToml {
+    #[serde(default)]
+    frontal_area_m2: Option<f32>,
+    /// Negative values produce downforce
+    #[serde(default)]
+    lift_coefficient_front: Option<f32>,
+    #[serde(default)]
+    lift_coefficient_rear: Option<f32>,
+}
+
+/// Optional suspension tuning; unset values use the `SuspensionConfig` defaults
+#[derive(Debug, Deserialize, Default)]
+struct SuspensionToml {
+    #[serde(default)]
+    spring_rate_front_n_per_m: Option<f32>,
+    #[serde(default)]
+    spring_rate_rear_n_per_m: Option<f32>,
+    #[serde(default)]
+    damper_compression_front: Option<f32>,
+    #[serde(default)]
+    damper_compression_rear: Option<f32>,
+    #[serde(default)]
+    damper_rebound_front: Option<f32>,
+    #[serde(default)]
+    damper_rebound_rear: Option<f32>,
+    #[serde(default)]
+    anti_roll_bar_front: Option<f32>,
+    #[serde(default)]
+    anti_roll_bar_rear: Option<f32>,
+    #[serde(default)]
+    max_travel_m: Option<f32>,
+    #[serde(default)]
+    ride_height_front_m: Option<f32>,
+    #[serde(default)]
+    ride_height_rear_m: Option<f32>,
+}
+
+/// Optional `[min, max]` limits on player setups; unset ranges use the `SetupRanges` defaults
+#[derive(Debug, Deserialize, Default)]
+struct SetupToml {
+    #[serde(default)]
+    final_drive_ratio: Option<[f32; 2]>,
+    #[serde(default)]
+    gear_ratio: Option<[f32; 2]>,
+    #[serde(default)]
+    lift_coefficient_front: Option<[f32; 2]>,
+    #[serde(default)]
+    lift_coefficient_rear: Option<[f32; 2]>,
+    #[serde(default)]
+    tire_pressure_kpa: Option<[f32; 2]>,
+    #[serde(default)]
+    brake_bias_front: Option<[f32; 2]>,
+}
+
+/// Optional collision footprint, either a convex hull or a set of capsules.
+/// Points are `[forward, left]` in metres from the car's center; without either
+/// the car collides as a box of its length and width.
+#[derive(Debug, Deserialize, Default)]
+struct CollisionToml {
+    #[serde(default)]
+    hull: Option<Vec<[f32; 2]>>,
+    #[serde(default)]
+    capsules: Vec<CapsuleToml>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CapsuleToml {
+    from: [f32; 2],
+    to: [f32; 2],
+    radius_m: f32,
+}
+
+impl CollisionToml {
+    fn into_shape(self) -> Result<CollisionShape, Box<dyn std::error::Error>> {
+        match (self.hull, self.capsules.is_empty()) {
+            (Some(_), false) => Err("[collision] defines both a hull and capsules".into()),
+            (Some(points), true) => {
+                let hull = convex_hull(points);
+                if hull.len() < 3 {
+                    return Err("[collision] hull needs at least three points not on one line".into());
+                }
+                Ok(CollisionShape::Hull(hull))
+            }
+            (None, false) => {
+                if self.capsules.iter().any(|c| c.radius_m <= 0.0) {
+                    return Err("[collision] capsule radius must be positive".into());
+                }
+                Ok(CollisionShape::Capsules(
+                    self.capsules
+                        .into_iter()
+                        .map(|c| CollisionCapsule { from: c.from, to: c.to, radius_m: c.radius_m })
+                        .collect(),
+                ))
+            }
+            (None, true) => Ok(CollisionShape::Box),
+        }
+    }
+}
+
+/// Counter-clockwise convex hull of `points` (monotone chain), dropping collinear points
+fn convex_hull(mut points: Vec<[f32; 2]>) -> Vec<[f32; 2]> {
+    points.sort_by(|a, b| a[0].total_cmp(&b[0]).then(a[1].total_cmp(&b[1])));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+
+    let turn = |o: [f32; 2], a: [f32; 2], b: [f32; 2]| (a[0] - o[0]) * (b[1] - o[1]) - (a[1] - o[1]) * (b[0] - o[0]);
+    let mut hull: Vec<[f32; 2]> = Vec::with_capacity(points.len() * 2);
+    for pass in [points.clone(), points.into_iter().rev().collect()] {
+        let start = hull.len();
+        for p in pass {
+            while hull.len() >= start + 2 && turn(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0 {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        // The last point of each chain starts the next one
+        hull.pop();
+    }
+    hull
+}
+
+/// A `[[livery]]` table; the texture path is relative to the car's folder
+#[derive(Debug, Deserialize)]
+struct LiveryToml {
+    name: String,
+    #[serde(default)]
+    texture: Option<String>,
+    primary_color: String,
+    secondary_color: String,
+    #[serde(default)]
+    number: Option<u16>,
+}
+
+impl LiveryToml {
+    fn into_livery(self, car_folder: &Path) -> Result<Livery, Box<dyn std::error::Error>> {
+        for color in [&self.primary_color, &self.secondary_color] {
+            let hex = color.strip_prefix('#').unwrap_or("");
+            if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(format!("[[livery]] \"{}\": color \"{}\" is not #rrggbb", self.name, color).into());
+            }
+        }
+        if let Some(texture) = &self.texture {
+            let relative = Path::new(texture);
+            if relative.is_absolute() || relative.components().any(|c| matches!(c, std::path::Component::ParentDir)) {
+                return Err(format!("[[livery]] \"{}\": texture must be inside the car folder", self.name).into());
+            }
+            if !car_folder.join(relative).is_file() {
+                return Err(format!("[[livery]] \"{}\": texture {} not found", self.name, texture).into());
+            }
+        }
+        Ok(Livery {
+            name: self.name,
+            texture: self.texture,
+            primary_color: self.primary_color.to_ascii_lowercase(),
+            secondary_color: self.secondary_color.to_ascii_lowercase(),
+            number: self.number,
+        })
+    }
+}
+
 #[derive(Debug, Deserialize, Default)]
 struct FuelToml {
     #[serde(default)]
@@ -145,7 +360,7 @@ impl CarLoader {
         let car_toml: CarToml = toml::from_str(&content)?;
 
         // Parse UUID from the ID string
-        let id = Uuid::parse_str(&car_toml.id)?;
+        let id = CarConfigId(Uuid::parse_str(&car_toml.id)?);
 
         let engine_toml = car_toml.engine.unwrap_or_default();
         let transmission_toml = car_toml.transmission.unwrap_or_default();
@@ -153,19 +368,62 @@ impl CarLoader {
         let differential_toml = car_toml.differential.unwrap_or_default();
         let fuel_toml = car_toml.fuel.unwrap_or_default();
         let hybrid_toml = car_toml.hybrid.unwrap_or_default();
+        let tires_toml = car_toml.tires.unwrap_or_default();
+        let tire_defaults = TireConfig::default();
+        let suspension_toml = car_toml.suspension.unwrap_or_default();
+        let suspension_defaults = SuspensionConfig::default();
+        let aero_toml = car_toml.aero.unwrap_or_default();
+        let setup_toml = car_toml.setup.unwrap_or_default();
+        let collision_shape = car_toml.collision.unwrap_or_default().into_shape()?;
+        if car_toml.livery.len() > u8::MAX as usize + 1 {
+            return Err("a car can have at most 256 liveries".into());
+        }
+        let car_folder = path.parent().unwrap_or(Path::new("."));
+        let liveries = car_toml
+            .livery
+            .into_iter()
+            .map(|l| l.into_livery(car_folder))
+            .collect::<Result<Vec<_>, _>>()?;
+        let setup_defaults = SetupRanges::default();
+        let range = |limits: Option<[f32; 2]>, default: SetupRange| {
+            limits.map(|[min, max]| SetupRange::new(min, max)).unwrap_or(default)
+        };
 
-        // Convert engine force to power (legacy approximation: P = F * v, assuming ~100 m/s)
-        let max_engine_power_w = engine_toml
-            .max_power_w
-            .unwrap_or_else(|| car_toml.physics.max_engine_force_n * 100.0);
+        let idle_rpm = engine_toml.idle_rpm.unwrap_or(900.0);
+        let redline_rpm = engine_toml.redline_rpm.unwrap_or(7500.0);
+        let max_rpm = engine_toml.max_rpm.unwrap_or(8000.0);
+        let max_torque_nm = engine_toml.max_torque_nm.unwrap_or(450.0);
+        let mut torque_curve: Vec<TorqueCurvePoint> = engine_toml
+            .torque_curve
+            .iter()
+            .map(|p| TorqueCurvePoint {
+                rpm: p.rpm,
+                torque_nm: p.torque_nm,
+            })
+            .collect();
+
+        // Peak power from the torque curve when given, else the legacy
+        // approximation from engine force (P = F * v, assuming ~100 m/s)
+        let max_engine_power_w = engine_toml.max_power_w.unwrap_or_else(|| {
+            if torque_curve.is_empty() {
+                car_toml.physics.max_engine_force_n * 100.0
+            } else {
+                EngineConfig { torque_curve: torque_curve.clone(), ..EngineConfig::default() }.peak_power_w()
+            }
+        });
+        if torque_curve.is_empty() {
+            torque_curve = EngineConfig::synthesized_torque_curve(max_torque_nm, max_engine_power_w, idle_rpm, redline_rpm, max_rpm);
+        }
 
         println!("  Loaded {}: mass={}kg, engine_force={}N, power={}W",
             car_toml.name, car_toml.physics.mass_kg, car_toml.physics.max_engine_force_n, max_engine_power_w);
 
-        Ok(CarConfig {
+        let mut config = CarConfig {
             id,
             name: car_toml.name,
+            content_hash: content_hash::hash_car(path, &car_toml.model)?,
             model: car_toml.model,
+            source_path: None,
 
             // Physical dimensions
             mass_kg: car_toml.physics.mass_kg,
@@ -184,10 +442,10 @@ impl CarLoader {
 
             // Engine & drivetrain
             max_engine_power_w,
-            max_engine_torque_nm: engine_toml.max_torque_nm.unwrap_or(450.0),
-            max_engine_rpm: engine_toml.max_rpm.unwrap_or(8000.0),
-            idle_rpm: engine_toml.idle_rpm.unwrap_or(900.0),
-            redline_rpm: engine_toml.redline_rpm.unwrap_or(7500.0),
+            max_engine_torque_nm: max_torque_nm,
+            max_engine_rpm: max_rpm,
+            idle_rpm,
+            redline_rpm,
             gear_ratios: transmission_toml
                 .gear_ratios
                 .unwrap_or_else(|| vec![-3.5, 3.8, 2.4, 1.7, 1.3, 1.0, 0.8]),
@@ -200,18 +458,16 @@ impl CarLoader {
 
             engine: EngineConfig {
                 rev_limiter_rpm: engine_toml.rev_limiter_rpm.unwrap_or_else(|| engine_toml.max_rpm.unwrap_or(8000.0)),
-                torque_curve: engine_toml
-                    .torque_curve
-                    .into_iter()
-                    .map(|p| TorqueCurvePoint {
-                        rpm: p.rpm,
-                        torque_nm: p.torque_nm,
-                    })
-                    .collect(),
+                torque_curve,
                 inertia_kg_m2: engine_toml.inertia_kg_m2.unwrap_or(0.25),
                 friction_torque_nm: engine_toml.friction_torque_nm.unwrap_or(20.0),
                 engine_brake_torque_nm: engine_toml.engine_brake_torque_nm.unwrap_or(80.0),
                 idle_control_gain: engine_toml.idle_control_gain.unwrap_or(0.15),
+                turbo: engine_toml.turbo.map(|t| TurboConfig {
+                    boost_torque_share: t.boost_torque_share.unwrap_or(0.4),
+                    boost_threshold_rpm: t.boost_threshold_rpm,
+                    spool_time_s: t.spool_time_s.unwrap_or(0.8),
+                }),
             },
             transmission: TransmissionConfig {
                 transmission_type: match transmission_toml.transmission_type.as_deref() {
@@ -223,6 +479,9 @@ impl CarLoader {
                 },
                 shift_time_s: transmission_toml.shift_time_s.unwrap_or(0.12),
                 efficiency: transmission_toml.efficiency.unwrap_or(0.92),
+                clutch_max_torque_nm: transmission_toml
+                    .clutch_max_torque_nm
+                    .unwrap_or(engine_toml.max_torque_nm.unwrap_or(450.0) * DEFAULT_CLUTCH_CAPACITY_FACTOR),
             },
             differential: DifferentialConfig {
                 differential_type: match differential_toml.differential_type.as_deref() {
@@ -258,22 +517,200 @@ impl CarLoader {
 
             // Aerodynamics
             drag_coefficient: car_toml.physics.drag_coefficient,
-            frontal_area_m2: 2.2,
-            lift_coefficient_front: -0.15,
-            lift_coefficient_rear: -0.20,
+            frontal_area_m2: aero_toml.frontal_area_m2.unwrap_or(2.2),
+            lift_coefficient_front: aero_toml.lift_coefficient_front.unwrap_or(-0.15),
+            lift_coefficient_rear: aero_toml.lift_coefficient_rear.unwrap_or(-0.20),
 
             // Steering
             max_steering_angle_rad: car_toml.physics.max_steering_angle_rad,
             steering_ratio: 14.0,
 
             // Suspension
-            suspension: SuspensionConfig::default(),
+            suspension: SuspensionConfig {
+                spring_rate_front_n_per_m: suspension_toml.spring_rate_front_n_per_m.unwrap_or(suspension_defaults.spring_rate_front_n_per_m),
+                spring_rate_rear_n_per_m: suspension_toml.spring_rate_rear_n_per_m.unwrap_or(suspension_defaults.spring_rate_rear_n_per_m),
+                damper_compression_front: suspension_toml.damper_compression_front.unwrap_or(suspension_defaults.damper_compression_front),
+                damper_compression_rear: suspension_toml.damper_compression_rear.unwrap_or(suspension_defaults.damper_compression_rear),
+                damper_rebound_front: suspension_toml.damper_rebound_front.unwrap_or(suspension_defaults.damper_rebound_front),
+                damper_rebound_rear: suspension_toml.damper_rebound_rear.unwrap_or(suspension_defaults.damper_rebound_rear),
+                anti_roll_bar_front: suspension_toml.anti_roll_bar_front.unwrap_or(suspension_defaults.anti_roll_bar_front),
+                anti_roll_bar_rear: suspension_toml.anti_roll_bar_rear.unwrap_or(suspension_defaults.anti_roll_bar_rear),
+                max_travel_m: suspension_toml.max_travel_m.unwrap_or(suspension_defaults.max_travel_m),
+                ride_height_front_m: suspension_toml.ride_height_front_m.unwrap_or(suspension_defaults.ride_height_front_m),
+                ride_height_rear_m: suspension_toml.ride_height_rear_m.unwrap_or(suspension_defaults.ride_height_rear_m),
+            },
 
             // Tires
             tire_config: TireConfig {
                 grip_coefficient: car_toml.physics.grip_coefficient,
-                ..TireConfig::default()
+                optimal_slip_ratio: tires_toml.optimal_slip_ratio.unwrap_or(tire_defaults.optimal_slip_ratio),
+                optimal_slip_angle_rad: tires_toml.optimal_slip_angle_rad.unwrap_or(tire_defaults.optimal_slip_angle_rad),
+                longitudinal_shape: tires_toml.longitudinal_shape.unwrap_or(tire_defaults.longitudinal_shape),
+                longitudinal_curvature: tires_toml.longitudinal_curvature.unwrap_or(tire_defaults.longitudinal_curvature),
+                lateral_shape: tires_toml.lateral_shape.unwrap_or(tire_defaults.lateral_shape),
+                lateral_curvature: tires_toml.lateral_curvature.unwrap_or(tire_defaults.lateral_curvature),
+                load_sensitivity: tires_toml.load_sensitivity.unwrap_or(tire_defaults.load_sensitivity),
+                // Grip is quoted at the car's static corner load unless set
+                nominal_load_n: tires_toml.nominal_load_n.unwrap_or(car_toml.physics.mass_kg * 9.81 / 4.0),
+                ..tire_defaults
             },
-        })
+
+            setup_ranges: SetupRanges {
+                final_drive_ratio: range(setup_toml.final_drive_ratio, setup_defaults.final_drive_ratio),
+                gear_ratio: range(setup_toml.gear_ratio, setup_defaults.gear_ratio),
+                lift_coefficient_front: range(setup_toml.lift_coefficient_front, setup_defaults.lift_coefficient_front),
+                lift_coefficient_rear: range(setup_toml.lift_coefficient_rear, setup_defaults.lift_coefficient_rear),
+                tire_pressure_kpa: range(setup_toml.tire_pressure_kpa, setup_defaults.tire_pressure_kpa),
+                brake_bias_front: range(setup_toml.brake_bias_front, setup_defaults.brake_bias_front),
+            },
+
+            collision_shape,
+            liveries,
+        };
+
+        // The car as delivered is always a legal setup
+        let fatal: Vec<String> = Self::validate(&config).into_iter().filter(|d| d.fatal).map(|d| d.to_string()).collect();
+        if !fatal.is_empty() {
+            return Err(fatal.join("; ").into());
+        }
+
+        config.setup_ranges = config.setup_ranges.clone().including(&CarSetup::baseline(&config));
+        Ok(config)
+    }
+
+    /// Sanity checks on a loaded car. Values the physics cannot run with are
+    /// fatal; values outside what real cars have are warnings.
+    pub fn validate(config: &CarConfig) -> Vec<CarDiagnostic> {
+        let mut found = Vec::new();
+        let mut check = |ok: bool, fatal: bool, field: &str, message: String| {
+            if !ok {
+                found.push(CarDiagnostic { field: field.to_string(), message, fatal });
+            }
+        };
+
+        let positive = [
+            ("physics.mass_kg", config.mass_kg),
+            ("physics.wheelbase_m", config.wheelbase_m),
+            ("physics.length_m", config.length_m),
+            ("physics.width_m", config.width_m),
+            ("wheel_radius_m", config.wheel_radius_m),
+            ("final_drive_ratio", config.final_drive_ratio),
+            ("physics.grip_coefficient", config.tire_config.grip_coefficient),
+            ("physics.max_brake_force_n", config.max_brake_force_n),
+        ];
+        for (field, value) in positive {
+            check(value.is_finite() && value > 0.0, true, field, format!("must be a positive number, got {}", value));
+        }
+        check(config.drag_coefficient.is_finite() && config.drag_coefficient >= 0.0, true, "physics.drag_coefficient",
+            format!("must not be negative, got {}", config.drag_coefficient));
+        for (field, value) in [("weight_distribution_front", config.weight_distribution_front), ("brake_bias_front", config.brake_bias_front)] {
+            check((0.0..=1.0).contains(&value), true, field, format!("must be between 0 and 1, got {}", value));
+        }
+        let efficiency = config.transmission.efficiency;
+        check(efficiency > 0.0 && efficiency <= 1.0, true, "transmission.efficiency", format!("must be above 0 and at most 1, got {}", efficiency));
+
+        let forward: Vec<f32> = config.gear_ratios.iter().copied().filter(|&r| r > 0.0).collect();
+        check(!forward.is_empty(), true, "transmission.gear_ratios", "has no forward gears".to_string());
+        check(config.gear_ratios.iter().all(|r| r.is_finite()), true, "transmission.gear_ratios", "must all be numbers".to_string());
+        check(forward.windows(2).all(|pair| pair[1] < pair[0]), false, "transmission.gear_ratios",
+            format!("forward gears should get taller gear by gear, got {:?}", forward));
+
+        let curve = &config.engine.torque_curve;
+        check(curve.iter().all(|p| p.rpm.is_finite() && p.torque_nm.is_finite() && p.torque_nm >= 0.0), true, "engine.torque_curve",
+            "must have finite rpm and non-negative torque".to_string());
+        check(config.engine.peak_torque_nm() > 0.0, true, "engine.torque_curve", "has no torque".to_string());
+        check(config.engine.rev_limiter_rpm > config.idle_rpm, true, "engine.rev_limiter_rpm",
+            format!("{} is not above idle at {}", config.engine.rev_limiter_rpm, config.idle_rpm));
+
+        check((100.0..=4000.0).contains(&config.mass_kg), false, "physics.mass_kg", format!("{} kg is outside 100-4000 kg", config.mass_kg));
+        check((0.1..=0.6).contains(&config.wheel_radius_m), false, "wheel_radius_m", format!("{} m is outside 0.1-0.6 m", config.wheel_radius_m));
+        check((0.3..=2.5).contains(&config.tire_config.grip_coefficient), false, "physics.grip_coefficient",
+            format!("{} is outside 0.3-2.5", config.tire_config.grip_coefficient));
+        check((0.02..=0.3).contains(&config.tire_config.optimal_slip_ratio), false, "tires.optimal_slip_ratio",
+            format!("{} is outside 0.02-0.3", config.tire_config.optimal_slip_ratio));
+        check((0.02..=0.35).contains(&config.tire_config.optimal_slip_angle_rad), false, "tires.optimal_slip_angle_rad",
+            format!("{} rad is outside 0.02-0.35 rad", config.tire_config.optimal_slip_angle_rad));
+        let power_to_weight = config.power_to_weight_w_per_kg();
+        check(power_to_weight <= 1500.0, false, "engine", format!("{:.0} W/kg is more than any race car", power_to_weight));
+        let top_speed_kmh = config.theoretical_top_speed_mps() * 3.6;
+        check((20.0..=450.0).contains(&top_speed_kmh), false, "transmission.gear_ratios",
+            format!("theoretical top speed of {:.0} km/h is outside 20-450 km/h", top_speed_kmh));
+        found
+    }
+}
+
+/// Problem found in a loaded car
+#[derive(Debug, Clone, PartialEq)]
+pub struct CarDiagnostic {
+    /// Car file field responsible, such as `physics.mass_kg`
+    pub field: String,
+    pub message: String,
+    /// The physics cannot run the car, so it is not loaded
+    pub fatal: bool,
+}
+
+impl std::fmt::Display for CarDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn car_toml(mass_kg: f32, gear_ratios: &str) -> String {
+        format!(
+            r#"id = "0b6f1c3e-7a52-4a39-9d1e-3f0c2b8a4d11"
+name = "Test Car"
+version = "1.0.0"
+model = "test.glb"
+
+[physics]
+mass_kg = {mass_kg}
+max_engine_force_n = 6000.0
+max_brake_force_n = 12000.0
+drag_coefficient = 0.35
+grip_coefficient = 1.1
+max_steering_angle_rad = 0.6
+wheelbase_m = 2.6
+
+[transmission]
+gear_ratios = {gear_ratios}
+"#
+        )
+    }
+
+    fn load(content: &str) -> Result<CarConfig, String> {
+        let dir = std::env::temp_dir().join(format!("apexsim-car-loader-{}-{}", std::process::id(), Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("car.toml");
+        std::fs::write(&path, content).unwrap();
+        let result = CarLoader::load_from_file(&path).map_err(|e| e.to_string());
+        std::fs::remove_dir_all(&dir).unwrap();
+        result
+    }
+
+    #[test]
+    fn test_plausible_car_loads_without_warnings() {
+        let car = load(&car_toml(1300.0, "[-3.2, 3.4, 2.2, 1.6, 1.25, 1.0, 0.82]")).unwrap();
+        assert_eq!(CarLoader::validate(&car), []);
+        assert!(car.power_to_weight_w_per_kg() > 0.0);
+        let top_speed_kmh = car.theoretical_top_speed_mps() * 3.6;
+        assert!((150.0..350.0).contains(&top_speed_kmh), "top speed {} km/h", top_speed_kmh);
+    }
+
+    #[test]
+    fn test_malformed_car_is_refused_and_odd_gearing_is_a_warning() {
+        let error = load(&car_toml(0.0, "[-3.2, 3.4, 2.2]")).unwrap_err();
+        assert!(error.contains("physics.mass_kg"), "{}", error);
+
+        let error = load(&car_toml(1300.0, "[-3.2]")).unwrap_err();
+        assert!(error.contains("no forward gears"), "{}", error);
+
+        let car = load(&car_toml(1300.0, "[-3.2, 3.4, 1.0, 2.2]")).unwrap();
+        let warnings = CarLoader::validate(&car);
+        assert!(warnings.iter().all(|d| !d.fatal));
+        assert!(warnings.iter().any(|d| d.field == "transmission.gear_ratios"), "{:?}", warnings);
     }
 }
@@ -0,0 +1,399 @@
+//! Several simulation servers behind one lobby.
+//!
+//! Instances POST an `InstanceReport` to `{gateway_url}/instances` every
+//! report interval and DELETE `{gateway_url}/instances/{instance_id}` when
+//! they shut down. The gateway lists their sessions in its lobby beside its
+//! own and answers `JoinSession` for one of them with `InstanceRedirect`: the
+//! client connects to that instance and joins there. `CreateSession` goes to
+//! whichever server has the smallest share of its session slots in use, the
+//! gateway included unless `local_sessions` is off. Instances that stop
+//! reporting drop out of the lobby after `instance_timeout_ms`.
+//!
+//! Remote sessions travel in full `LobbyState` messages only; the lobby
+//! deltas a gateway broadcasts cover its own sessions.
+//!
+//! Every request to the gateway needs `Authorization: Bearer <token>` with
+//! the cluster's shared `token`, so only instances can add or remove entries.
+
+
+use crate::admin_api::constant_time_eq;
+use crate::data::SessionId;
+use crate::master_server::{http_base, http_request};
+use crate::network::SessionSummary;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{body::Incoming, Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+use tracing::{error, info};
+use uuid::Uuid;
+
+/// Longest wait for the gateway to answer a report
+const GATEWAY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Largest instance report the gateway reads
+const MAX_REPORT_BYTES: usize = 1024 * 1024;
+
+/// What an instance tells the gateway about itself
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstanceReport {
+    /// New on every start, so the gateway can tell restarts apart
+    pub instance_id: Uuid,
+    /// TCP address players connect to
+    pub address: String,
+    pub player_count: u32,
+    /// Sessions running or being built, listed or not
+    pub session_count: u32,
+    pub max_sessions: u8,
+    /// Sessions players can see in the lobby
+    pub sessions: Vec<SessionSummary>,
+}
+
+impl InstanceReport {
+    /// Share of the session slots in use
+    pub fn load(&self) -> f32 {
+        session_load(self.session_count as usize, self.max_sessions)
+    }
+
+    fn has_room(&self) -> bool {
+        self.session_count < self.max_sessions as u32
+    }
+}
+
+/// Share of `max_sessions` that `sessions` take
+pub fn session_load(sessions: usize, max_sessions: u8) -> f32 {
+    sessions as f32 / max_sessions.max(1) as f32
+}
+
+/// Talks to the gateway from an instance
+#[derive(Debug, Clone)]
+pub struct GatewayClient {
+    base: String,
+    token: String,
+}
+
+impl GatewayClient {
+    /// A client for the gateway at `url`, such as `http://gateway.example.com:9200`,
+    /// presenting the cluster `token`
+    pub fn new(url: &str, token: &str) -> io::Result<Self> {
+        Ok(Self { base: http_base(url, "gateway")?, token: token.to_string() })
+    }
+
+    /// Add or refresh this instance
+    pub async fn report(&self, report: &InstanceReport) -> io::Result<()> {
+        let body = serde_json::to_vec(report).map_err(io::Error::other)?;
+        self.request(Method::POST, "/instances", Some(body)).await.map(|_| ())
+    }
+
+    /// Take this instance out of the cluster
+    pub async fn leave(&self, instance_id: Uuid) -> io::Result<()> {
+        self.request(Method::DELETE, &format!("/instances/{}", instance_id), None).await.map(|_| ())
+    }
+
+    /// Every instance the gateway currently routes to
+    pub async fn instances(&self) -> io::Result<Vec<InstanceReport>> {
+        let body = self.request(Method::GET, "/instances", None).await?;
+        serde_json::from_slice(&body).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    async fn request(&self, method: Method, path: &str, body: Option<Vec<u8>>) -> io::Result<Bytes> {
+        tokio::time::timeout(GATEWAY_TIMEOUT, http_request(&self.base, method, path, body, Some(&self.token)))
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "gateway did not answer"))?
+    }
+}
+
+/// The gateway's view of its instances, shared between the report server and
+/// the client message handlers
+#[derive(Debug, Clone)]
+pub struct ClusterRegistry {
+    instances: Arc<Mutex<Instances>>,
+    timeout: Duration,
+}
+
+#[derive(Debug, Default)]
+struct Instances {
+    /// Latest report of each instance and when it came
+    live: HashMap<Uuid, (InstanceReport, Instant)>,
+    /// Instances that left; a report still on its way must not bring one back
+    departed: HashSet<Uuid>,
+}
+
+impl ClusterRegistry {
+    /// Instances are forgotten `timeout` after their last report
+    pub fn new(timeout: Duration) -> Self {
+        Self { instances: Arc::default(), timeout }
+    }
+
+    pub fn update(&self, report: InstanceReport) {
+        let mut instances = self.instances.lock().unwrap();
+        if instances.departed.contains(&report.instance_id) {
+            return;
+        }
+        if !instances.live.contains_key(&report.instance_id) {
+            info!("Instance {} joined the cluster at {}", report.instance_id, report.address);
+        }
+        instances.live.insert(report.instance_id, (report, Instant::now()));
+    }
+
+    pub fn remove(&self, instance_id: Uuid) {
+        let mut instances = self.instances.lock().unwrap();
+        instances.departed.insert(instance_id);
+        if let Some((report, _)) = instances.live.remove(&instance_id) {
+            info!("Instance {} at {} left the cluster", instance_id, report.address);
+        }
+    }
+
+    /// Instances that reported recently
+    pub fn instances(&self) -> Vec<InstanceReport> {
+        let mut instances = self.instances.lock().unwrap();
+        let timeout = self.timeout;
+        instances.live.retain(|instance_id, (report, seen)| {
+            let live = seen.elapsed() < timeout;
+            if !live {
+                info!("Instance {} at {} stopped reporting", instance_id, report.address);
+            }
+            live
+        });
+        instances.live.values().map(|(report, _)| report.clone()).collect()
+    }
+
+    /// Every session the instances list
+    pub fn sessions(&self) -> Vec<SessionSummary> {
+        self.instances().into_iter().flat_map(|report| report.sessions).collect()
+    }
+
+    /// Address of the instance hosting `session_id`
+    pub fn instance_hosting(&self, session_id: SessionId) -> Option<String> {
+        self.instances()
+            .into_iter()
+            .find(|report| report.sessions.iter().any(|session| session.id == session_id))
+            .map(|report| report.address)
+    }
+
+    /// Address of the instance a new session should go to: the least loaded
+    /// one with room, if it is less loaded than `local_load`, the gateway's
+    /// own load (`None` when the gateway cannot host it)
+    pub fn least_loaded(&self, local_load: Option<f32>) -> Option<String> {
+        self.instances()
+            .into_iter()
+            .filter(InstanceReport::has_room)
+            .filter(|report| local_load.is_none_or(|local| report.load() < local))
+            .min_by(|a, b| a.load().total_cmp(&b.load()).then(a.player_count.cmp(&b.player_count)))
+            .map(|report| report.address)
+    }
+}
+
+/// Take instance reports on `listener` until the server stops; requests
+/// without the cluster `token` are refused
+pub async fn run_gateway_server(listener: TcpListener, registry: ClusterRegistry, token: String) {
+    let token = Arc::new(token);
+    if let Ok(addr) = listener.local_addr() {
+        info!("Cluster gateway taking instance reports on {}", addr);
+    }
+    loop {
+        let (stream, _) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                error!("Failed to accept instance connection: {}", e);
+                continue;
+            }
+        };
+
+        let registry = registry.clone();
+        let token = Arc::clone(&token);
+        tokio::spawn(async move {
+            let service = service_fn(move |req| handle_instance_request(req, registry.clone(), Arc::clone(&token)));
+            if let Err(err) = http1::Builder::new().serve_connection(TokioIo::new(stream), service).await {
+                error!("Error serving instance connection: {}", err);
+            }
+        });
+    }
+}
+
+/// Whether an `Authorization` header value carries the cluster token; an
+/// unset token admits no one
+fn authorized(authorization: Option<&str>, token: &str) -> bool {
+    let Some(presented) = authorization.and_then(|value| value.strip_prefix("Bearer ")) else {
+        return false;
+    };
+    !token.is_empty() && constant_time_eq(token.as_bytes(), presented.as_bytes())
+}
+
+async fn handle_instance_request(
+    req: Request<Incoming>,
+    registry: ClusterRegistry,
+    token: Arc<String>,
+) -> Result<Response<Full<Bytes>>, hyper::Error> {
+    let authorization = req.headers().get(hyper::header::AUTHORIZATION).and_then(|value| value.to_str().ok());
+    if !authorized(authorization, &token) {
+        return Ok(empty_response(StatusCode::UNAUTHORIZED));
+    }
+
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+
+    let response = match (method, path.as_str()) {
+        (Method::GET, "/instances") => {
+            json_response(StatusCode::OK, serde_json::to_vec(&registry.instances()).unwrap_or_default())
+        }
+        (Method::POST, "/instances") => {
+            let body = match Limited::new(req.into_body(), MAX_REPORT_BYTES).collect().await {
+                Ok(body) => body.to_bytes(),
+                Err(_) => return Ok(empty_response(StatusCode::PAYLOAD_TOO_LARGE)),
+            };
+            match serde_json::from_slice::<InstanceReport>(&body) {
+                Ok(report) => {
+                    registry.update(report);
+                    empty_response(StatusCode::NO_CONTENT)
+                }
+                Err(_) => empty_response(StatusCode::BAD_REQUEST),
+            }
+        }
+        (Method::DELETE, path) => match path.strip_prefix("/instances/").and_then(|id| id.parse().ok()) {
+            Some(instance_id) => {
+                registry.remove(instance_id);
+                empty_response(StatusCode::NO_CONTENT)
+            }
+            None => empty_response(StatusCode::NOT_FOUND),
+        },
+        _ => empty_response(StatusCode::NOT_FOUND),
+    };
+    Ok(response)
+}
+
+fn json_response(status: StatusCode, body: Vec<u8>) -> Response<Full<Bytes>> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Full::new(Bytes::from(body)))
+        .unwrap()
+}
+
+fn empty_response(status: StatusCode) -> Response<Full<Bytes>> {
+    Response::builder().status(status).body(Full::new(Bytes::new())).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::*;
+
+    fn summary() -> SessionSummary {
+        SessionSummary {
+            id: SessionId::new_v4(),
+            track_name: "Test Oval".to_string(),
+            track_file: "tracks/test_oval.yaml".to_string(),
+            host_name: "Host".to_string(),
+            session_kind: SessionKind::Multiplayer,
+            player_count: 1,
+            max_players: 8,
+            state: SessionState::Lobby,
+            race_progress: None,
+            setup_policy: SetupPolicy::Open,
+            collision_policy: CollisionPolicy::Solid,
+            time_of_day: TimeOfDay::Noon,
+            aid_limits: DriverAids::unrestricted(),
+        }
+    }
+
+    fn report(address: &str, session_count: u32, sessions: Vec<SessionSummary>) -> InstanceReport {
+        InstanceReport {
+            instance_id: Uuid::new_v4(),
+            address: address.to_string(),
+            player_count: 0,
+            session_count,
+            max_sessions: 4,
+            sessions,
+        }
+    }
+
+    #[test]
+    fn test_new_sessions_go_to_the_least_loaded_server() {
+        let registry = ClusterRegistry::new(Duration::from_secs(5));
+        let session = summary();
+        let busy = report("10.0.0.1:9000", 3, vec![session.clone()]);
+        let full = report("10.0.0.2:9000", 4, Vec::new());
+        let quiet = report("10.0.0.3:9000", 1, Vec::new());
+        for instance in [busy.clone(), full, quiet] {
+            registry.update(instance);
+        }
+
+        assert_eq!(registry.least_loaded(None).as_deref(), Some("10.0.0.3:9000"));
+        assert_eq!(registry.least_loaded(Some(0.5)).as_deref(), Some("10.0.0.3:9000"));
+        assert_eq!(registry.least_loaded(Some(0.25)), None, "Ties stay on the gateway");
+        assert_eq!(registry.instance_hosting(session.id).as_deref(), Some("10.0.0.1:9000"));
+        assert_eq!(registry.sessions().len(), 1);
+
+        registry.remove(busy.instance_id);
+        assert_eq!(registry.instance_hosting(session.id), None);
+        registry.update(busy);
+        assert_eq!(registry.instance_hosting(session.id), None, "A late report does not bring it back");
+    }
+
+    #[test]
+    fn test_silent_instances_drop_out() {
+        let registry = ClusterRegistry::new(Duration::from_millis(20));
+        registry.update(report("10.0.0.1:9000", 0, vec![summary()]));
+        assert_eq!(registry.instances().len(), 1);
+        std::thread::sleep(Duration::from_millis(40));
+        assert!(registry.instances().is_empty());
+        assert!(registry.sessions().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_instances_report_over_http() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let registry = ClusterRegistry::new(Duration::from_secs(5));
+        let server = tokio::spawn(run_gateway_server(listener, registry.clone(), "cluster-token".to_string()));
+
+        let gateway = GatewayClient::new(&url, "cluster-token").unwrap();
+        let instance = report("10.0.0.1:9000", 1, vec![summary()]);
+        gateway.report(&instance).await.unwrap();
+        assert_eq!(registry.instances(), vec![instance.clone()]);
+        assert_eq!(gateway.instances().await.unwrap(), vec![instance.clone()]);
+
+        gateway.leave(instance.instance_id).await.unwrap();
+        assert!(registry.instances().is_empty());
+        assert!(GatewayClient::new("https://gateway:9200", "cluster-token").is_err());
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_gateway_refuses_unauthenticated_and_oversized_requests() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let registry = ClusterRegistry::new(Duration::from_secs(5));
+        let server = tokio::spawn(run_gateway_server(listener, registry.clone(), "cluster-token".to_string()));
+        let instance = report("10.0.0.1:9000", 1, vec![summary()]);
+
+        for token in ["", "wrong-token"] {
+            let gateway = GatewayClient::new(&url, token).unwrap();
+            let refused = gateway.report(&instance).await.unwrap_err();
+            assert!(refused.to_string().contains("401"), "{}", refused);
+        }
+        let body = serde_json::to_vec(&instance).unwrap();
+        assert!(http_request(&url, Method::POST, "/instances", Some(body), None).await.is_err());
+        assert!(registry.instances().is_empty());
+
+        let gateway = GatewayClient::new(&url, "cluster-token").unwrap();
+        gateway.report(&instance).await.unwrap();
+        assert!(http_request(&url, Method::DELETE, &format!("/instances/{}", instance.instance_id), None, None)
+            .await
+            .is_err());
+        assert_eq!(registry.instances().len(), 1, "An unauthenticated DELETE leaves the instance listed");
+
+        let oversized = vec![b' '; MAX_REPORT_BYTES + 1];
+        let refused = http_request(&url, Method::POST, "/instances", Some(oversized), Some("cluster-token")).await.unwrap_err();
+        assert!(refused.to_string().contains("413"), "{}", refused);
+        server.abort();
+    }
+}
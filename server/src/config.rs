@@ -1,4 +1,7 @@
+use crate::data::{BalanceOfPerformance, RealismPreset};
+use crate::physics::CollisionSettings;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
@@ -10,6 +13,42 @@ pub struct ServerConfig {
     pub logging: LoggingSettings,
     #[serde(default)]
     pub ai: AiSettings,
+    #[serde(default)]
+    pub physics: PhysicsSettings,
+    #[serde(default)]
+    pub replication: ReplicationSettings,
+    #[serde(default)]
+    pub open_practice: OpenPracticeSettings,
+    #[serde(default)]
+    pub privacy: PrivacySettings,
+    #[serde(default)]
+    pub spectating: SpectatingSettings,
+    #[serde(default)]
+    pub session_saves: SessionSaveSettings,
+    #[serde(default)]
+    pub journal: JournalSettings,
+    #[serde(default)]
+    pub live_timing: LiveTimingSettings,
+    #[serde(default)]
+    pub dashboard: DashboardSettings,
+    #[serde(default)]
+    pub admin_api: AdminApiSettings,
+    #[serde(default)]
+    pub grpc: GrpcSettings,
+    #[serde(default)]
+    pub admin_console: AdminConsoleSettings,
+    #[serde(default)]
+    pub master_server: MasterServerSettings,
+    #[serde(default)]
+    pub cluster: ClusterSettings,
+    #[serde(default)]
+    pub session_limits: SessionLimitSettings,
+    #[serde(default)]
+    pub content_transfer: ContentTransferSettings,
+    #[serde(default)]
+    pub results_export: ResultsExportSettings,
+    #[serde(default)]
+    pub balance_of_performance: BalanceSettings,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,6 +56,23 @@ pub struct ServerSettings {
     pub tick_rate_hz: u16,
     pub max_sessions: u8,
     pub session_timeout_seconds: u32,
+    /// Authentication tokens that grant league admin rights (moving players between sessions)
+    #[serde(default)]
+    pub admin_tokens: Vec<String>,
+    /// File the ban list is kept in
+    #[serde(default = "default_ban_list_path")]
+    pub ban_list_path: String,
+    /// File the lap record of each track is kept in
+    #[serde(default = "default_lap_records_path")]
+    pub lap_records_path: String,
+}
+
+fn default_ban_list_path() -> String {
+    "./bans.json".to_string()
+}
+
+fn default_lap_records_path() -> String {
+    "./lap_records.json".to_string()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,6 +85,20 @@ pub struct NetworkSettings {
     pub require_tls: bool,
     pub heartbeat_interval_ms: u64,
     pub heartbeat_timeout_ms: u64,
+    /// How often each client is sent its `ConnectionStats` (0 disables)
+    #[serde(default = "default_stats_interval_ms")]
+    pub stats_interval_ms: u64,
+    /// Inbound messages allowed per connection per second; extras are discarded (0 disables)
+    #[serde(default = "default_max_messages_per_second")]
+    pub max_messages_per_second: u32,
+}
+
+fn default_stats_interval_ms() -> u64 {
+    2000
+}
+
+fn default_max_messages_per_second() -> u32 {
+    crate::transport::DEFAULT_MAX_MESSAGES_PER_SECOND
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +111,58 @@ pub struct ContentSettings {
 pub struct LoggingSettings {
     pub level: String,
     pub console_enabled: bool,
+    #[serde(default)]
+    pub file: LogFileSettings,
+}
+
+/// How the log file is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum LogFileFormat {
+    #[default]
+    Plain,
+    /// One JSON object per line
+    Json,
+}
+
+/// When the log file is moved aside for a new one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum LogRotation {
+    /// One file that grows forever
+    Never,
+    Hourly,
+    #[default]
+    Daily,
+    /// Once the file reaches `max_size_mb`
+    Size,
+}
+
+/// Log file output, filtered apart from the console.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogFileSettings {
+    /// File to log to; empty logs to the console only
+    pub path: String,
+    pub format: LogFileFormat,
+    /// Filter for the file, e.g. `info,apexsim_server::transport=debug`
+    pub level: String,
+    pub rotation: LogRotation,
+    /// Size a file grows to before it is rotated, with `rotation = "Size"`
+    pub max_size_mb: u64,
+    /// Rotated files kept besides the current one; 0 keeps every one
+    pub max_files: usize,
+}
+
+impl Default for LogFileSettings {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            format: LogFileFormat::Plain,
+            level: "info".to_string(),
+            rotation: LogRotation::Daily,
+            max_size_mb: 100,
+            max_files: 14,
+        }
+    }
 }
 
 /// AI driver configuration settings.
@@ -75,6 +197,398 @@ impl Default for AiSettings {
     }
 }
 
+/// Physics simulation settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhysicsSettings {
+    /// Realism preset controlling cold tire and brake behavior
+    pub realism: RealismPreset,
+    /// Ambient temperature that cold tires and brakes start at (°C)
+    pub ambient_temp_c: f32,
+    /// Physics steps per server tick; 0 picks enough to keep each step at or under 1/240 s
+    #[serde(default)]
+    pub substeps: u8,
+    /// Car-to-car contact response
+    #[serde(default)]
+    pub collisions: CollisionSettings,
+}
+
+impl Default for PhysicsSettings {
+    fn default() -> Self {
+        Self {
+            realism: RealismPreset::default(),
+            ambient_temp_c: 20.0,
+            substeps: 0,
+            collisions: CollisionSettings::default(),
+        }
+    }
+}
+
+/// Role of this process in a hot standby pair
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ReplicationRole {
+    #[default]
+    Disabled,
+    /// Hosts sessions and streams them to the standby
+    Primary,
+    /// Receives sessions and takes over if the primary dies
+    Standby,
+}
+
+/// Hot standby replication settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplicationSettings {
+    pub role: ReplicationRole,
+    /// Replication link: the standby listens here and the primary connects to it
+    pub standby_addr: String,
+    /// Client-facing TCP address of the standby, sent to players for failover
+    pub redirect_addr: String,
+    /// How often full session snapshots are sent
+    pub snapshot_interval_ms: u64,
+    /// Primary silence after which the standby takes over
+    pub failover_timeout_ms: u64,
+    /// Secret the primary presents on the replication link; both sides must share it
+    pub shared_secret: String,
+}
+
+impl Default for ReplicationSettings {
+    fn default() -> Self {
+        Self {
+            role: ReplicationRole::Disabled,
+            standby_addr: "127.0.0.1:9100".to_string(),
+            redirect_addr: "127.0.0.1:9000".to_string(),
+            snapshot_interval_ms: 500,
+            failover_timeout_ms: 3000,
+            shared_secret: String::new(),
+        }
+    }
+}
+
+/// Dedicated open practice server: one always-on session per track.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OpenPracticeSettings {
+    pub enabled: bool,
+    pub max_players: u8,
+    /// Minutes between automatic session resets (0 disables resets)
+    pub reset_interval_minutes: u32,
+}
+
+impl Default for OpenPracticeSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_players: 16,
+            reset_interval_minutes: 60,
+        }
+    }
+}
+
+/// What player identities public data feeds may carry.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct PrivacySettings {
+    /// Show public spectators and exported replays stable pseudonyms instead of player IDs and names
+    pub anonymize_public_data: bool,
+    /// Secret the pseudonyms are derived from; keep it fixed to keep pseudonyms stable across restarts.
+    /// Empty picks a random secret at startup.
+    pub pseudonym_secret: String,
+}
+
+/// Delayed spectator feeds, re-simulated from the replay being recorded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpectatingSettings {
+    /// Delay forced on spectators who are not admins, so nobody can relay the live race to a driver
+    pub min_delay_seconds: u32,
+    /// Longest delay a spectator may ask for
+    pub max_delay_seconds: u32,
+}
+
+impl Default for SpectatingSettings {
+    fn default() -> Self {
+        Self {
+            min_delay_seconds: 0,
+            max_delay_seconds: 300,
+        }
+    }
+}
+
+impl SpectatingSettings {
+    /// Delay a spectator gets when asking for `requested_seconds`; admins may watch live
+    pub fn delay_seconds(&self, requested_seconds: u32, admin: bool) -> u32 {
+        let min = if admin { 0 } else { self.min_delay_seconds };
+        requested_seconds.max(min).min(self.max_delay_seconds.max(min))
+    }
+}
+
+/// Mid-race session saves, for resuming long races after a restart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionSaveSettings {
+    pub dir: String,
+    /// Seconds between automatic saves of every racing session (0 disables them)
+    pub auto_save_interval_seconds: u32,
+}
+
+impl Default for SessionSaveSettings {
+    fn default() -> Self {
+        Self {
+            dir: "./saves".to_string(),
+            auto_save_interval_seconds: 0,
+        }
+    }
+}
+
+/// Crash journal of the running sessions, restored after a crash.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JournalSettings {
+    pub enabled: bool,
+    pub path: String,
+    /// Seconds between journal writes
+    pub interval_seconds: u32,
+}
+
+impl Default for JournalSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: "./saves/journal.bin".to_string(),
+            interval_seconds: 5,
+        }
+    }
+}
+
+/// Live timing served on the health server's address.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LiveTimingSettings {
+    pub enabled: bool,
+    /// How often the timing of each public session is refreshed
+    pub interval_ms: u64,
+}
+
+impl Default for LiveTimingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_ms: 1000,
+        }
+    }
+}
+
+/// Finished-race results for league tools, written to a directory and/or
+/// POSTed to a webhook.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResultsExportSettings {
+    pub enabled: bool,
+    /// Empty to not write files
+    pub directory: String,
+    /// `http://` URL results are POSTed to; empty for none
+    pub webhook_url: String,
+    /// Team of each driver, by driver name
+    pub teams: HashMap<String, String>,
+}
+
+impl Default for ResultsExportSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: "./results".to_string(),
+            webhook_url: String::new(),
+            teams: HashMap::new(),
+        }
+    }
+}
+
+/// Fixed-layout UDP packets for dashboards and motion rigs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DashboardSettings {
+    pub enabled: bool,
+    /// Local address the packets are sent from
+    pub bind: String,
+    /// Highest rate a client may ask for
+    pub max_rate_hz: u16,
+}
+
+impl Default for DashboardSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: "0.0.0.0:0".to_string(),
+            max_rate_hz: 60,
+        }
+    }
+}
+
+/// HTTP admin API, authenticated with the server's `admin_tokens`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdminApiSettings {
+    pub enabled: bool,
+    pub bind: String,
+}
+
+impl Default for AdminApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: "127.0.0.1:9091".to_string(),
+        }
+    }
+}
+
+/// gRPC control plane mirroring the admin API, with the same `admin_tokens`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GrpcSettings {
+    pub enabled: bool,
+    pub bind: String,
+}
+
+impl Default for GrpcSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: "127.0.0.1:9092".to_string(),
+        }
+    }
+}
+
+/// Interactive admin console, for operators on the server's own machine.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct AdminConsoleSettings {
+    /// Read commands from the server's terminal
+    pub stdin: bool,
+    /// Unix socket `--console` connects to; empty for none
+    pub socket_path: String,
+}
+
+/// Listing on a master server list for the public server browser.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MasterServerSettings {
+    pub enabled: bool,
+    /// Master list to register with, also used by `--query-servers`
+    pub url: String,
+    /// Name shown in the server browser
+    pub name: String,
+    pub region: String,
+    /// Address players connect to; empty lets the master use the heartbeat's source address
+    pub public_address: String,
+    pub heartbeat_interval_seconds: u64,
+}
+
+impl Default for MasterServerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: String::new(),
+            name: "ApexSim Server".to_string(),
+            region: String::new(),
+            public_address: String::new(),
+            heartbeat_interval_seconds: 30,
+        }
+    }
+}
+
+/// Quotas on `CreateSession`, so one client cannot use up `max_sessions`.
+/// Admins are exempt; 0 turns a limit off.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct SessionLimitSettings {
+    /// Open sessions one player may have created
+    pub max_per_player: u8,
+    /// Open sessions players from one IP address may have created
+    pub max_per_address: u8,
+    /// Wait after creating a session before the same player or address creates another
+    pub creation_cooldown_seconds: u64,
+}
+
+/// Downloads of car and track files by clients that are missing them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContentTransferSettings {
+    pub enabled: bool,
+    /// Largest single file served; bigger files are listed but refused
+    pub max_file_mb: u64,
+}
+
+impl Default for ContentTransferSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_file_mb: 256,
+        }
+    }
+}
+
+impl ContentTransferSettings {
+    pub fn max_file_bytes(&self) -> u64 {
+        self.max_file_mb * 1024 * 1024
+    }
+}
+
+/// Server-wide Balance of Performance. Session adjustments made by admins
+/// apply on top of these.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BalanceSettings {
+    /// Handicaps by car ID or name
+    pub cars: HashMap<String, BalanceOfPerformance>,
+}
+
+/// Role of this process in a cluster of simulation servers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ClusterRole {
+    #[default]
+    Standalone,
+    /// Shows every instance's sessions in its lobby and routes players to them
+    Gateway,
+    /// Runs sessions and reports them to the gateway
+    Instance,
+}
+
+/// Several simulation processes behind one lobby.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClusterSettings {
+    pub role: ClusterRole,
+    /// Gateway: where instances send their reports
+    pub bind: String,
+    /// Instance: the gateway's `bind`, as `http://host:port`
+    pub gateway_url: String,
+    /// Instance: TCP address players are sent to for this instance
+    pub public_address: String,
+    /// Gateway: also host sessions here, rather than only routing to instances
+    pub local_sessions: bool,
+    /// Instance: how often the gateway is told about this instance's sessions
+    pub report_interval_ms: u64,
+    /// Gateway: instances silent for this long are taken out of the lobby
+    pub instance_timeout_ms: u64,
+    /// Shared by the gateway and its instances; the gateway refuses requests without it
+    pub token: String,
+}
+
+impl Default for ClusterSettings {
+    fn default() -> Self {
+        Self {
+            role: ClusterRole::Standalone,
+            bind: "127.0.0.1:9200".to_string(),
+            gateway_url: String::new(),
+            public_address: String::new(),
+            local_sessions: true,
+            report_interval_ms: 1000,
+            instance_timeout_ms: 5000,
+            token: String::new(),
+        }
+    }
+}
+
 impl Default for ServerConfig {
     fn default() -> Self {
         Self {
@@ -82,6 +596,9 @@ impl Default for ServerConfig {
                 tick_rate_hz: 240,
                 max_sessions: 8,
                 session_timeout_seconds: 300,
+                admin_tokens: Vec::new(),
+                ban_list_path: default_ban_list_path(),
+                lap_records_path: default_lap_records_path(),
             },
             network: NetworkSettings {
                 tcp_bind: "127.0.0.1:9000".to_string(),
@@ -92,6 +609,8 @@ impl Default for ServerConfig {
                 require_tls: false,
                 heartbeat_interval_ms: 1000,
                 heartbeat_timeout_ms: 5000,
+                stats_interval_ms: default_stats_interval_ms(),
+                max_messages_per_second: default_max_messages_per_second(),
             },
             content: ContentSettings {
                 cars_dir: "../content/cars".to_string(),
@@ -100,25 +619,296 @@ impl Default for ServerConfig {
             logging: LoggingSettings {
                 level: "info".to_string(),
                 console_enabled: true,
+                file: LogFileSettings::default(),
             },
             ai: AiSettings::default(),
+            physics: PhysicsSettings::default(),
+            replication: ReplicationSettings::default(),
+            open_practice: OpenPracticeSettings::default(),
+            privacy: PrivacySettings::default(),
+            spectating: SpectatingSettings::default(),
+            session_saves: SessionSaveSettings::default(),
+            journal: JournalSettings::default(),
+            live_timing: LiveTimingSettings::default(),
+            dashboard: DashboardSettings::default(),
+            admin_api: AdminApiSettings::default(),
+            grpc: GrpcSettings::default(),
+            admin_console: AdminConsoleSettings::default(),
+            results_export: ResultsExportSettings::default(),
+            master_server: MasterServerSettings::default(),
+            cluster: ClusterSettings::default(),
+            session_limits: SessionLimitSettings::default(),
+            content_transfer: ContentTransferSettings::default(),
+            balance_of_performance: BalanceSettings::default(),
         }
     }
 }
 
+/// Slowest tick rate the game loop runs at
+pub const MIN_TICK_RATE_HZ: u16 = 10;
+
+/// Fastest tick rate the game loop runs at
+pub const MAX_TICK_RATE_HZ: u16 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Protocol {
+    Tcp,
+    Udp,
+}
+
+fn is_wildcard(host: &str) -> bool {
+    matches!(host, "0.0.0.0" | "::" | "")
+}
+
+/// Environment variables starting with this override config values, one
+/// `__` per level: `APEXSIM__SERVER__TICK_RATE_HZ=120` sets `[server] tick_rate_hz`
+pub const ENV_OVERRIDE_PREFIX: &str = "APEXSIM__";
+
 impl ServerConfig {
+    /// Load `path` with the environment's overrides applied
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
         let contents = fs::read_to_string(path)?;
-        let config: ServerConfig = toml::from_str(&contents)?;
-        Ok(config)
+        let mut table: toml::Table = toml::from_str(&contents)?;
+        apply_env_overrides(&mut table, std::env::vars())?;
+        Ok(table.try_into()?)
+    }
+
+    /// Load `path`, or the defaults with the environment's overrides applied
+    /// when there is no such file. A file that cannot be read or parsed is an
+    /// error rather than a reason to run with the defaults.
+    pub fn load_or_default<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        if !path.as_ref().exists() {
+            eprintln!("Config file {} not found, using defaults", path.as_ref().display());
+            return Self::default_with_env_overrides();
+        }
+        Self::load(path)
+    }
+
+    /// Everything the server could not run with, as `section.field: problem`
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        let tick_rate_hz = self.server.tick_rate_hz;
+        if !(MIN_TICK_RATE_HZ..=MAX_TICK_RATE_HZ).contains(&tick_rate_hz) {
+            problems.push(format!(
+                "server.tick_rate_hz: {} is outside {}-{}",
+                tick_rate_hz, MIN_TICK_RATE_HZ, MAX_TICK_RATE_HZ
+            ));
+        }
+        if self.server.max_sessions == 0 {
+            problems.push("server.max_sessions: must be at least 1".to_string());
+        }
+        if self.network.heartbeat_timeout_ms <= self.network.heartbeat_interval_ms {
+            problems.push(format!(
+                "network.heartbeat_timeout_ms: {} must be longer than heartbeat_interval_ms ({})",
+                self.network.heartbeat_timeout_ms, self.network.heartbeat_interval_ms
+            ));
+        }
+        if tracing_subscriber::EnvFilter::try_new(&self.logging.level).is_err() {
+            problems.push(format!("logging.level: \"{}\" is not a log filter", self.logging.level));
+        }
+        let log_file = &self.logging.file;
+        if !log_file.path.is_empty() {
+            if tracing_subscriber::EnvFilter::try_new(&log_file.level).is_err() {
+                problems.push(format!("logging.file.level: \"{}\" is not a log filter", log_file.level));
+            }
+            if log_file.rotation == LogRotation::Size && log_file.max_size_mb == 0 {
+                problems.push("logging.file.max_size_mb: must be at least 1 with size rotation".to_string());
+            }
+        }
+
+        if self.network.require_tls {
+            for (field, path) in [
+                ("network.tls_cert_path", &self.network.tls_cert_path),
+                ("network.tls_key_path", &self.network.tls_key_path),
+            ] {
+                if !Path::new(path).is_file() {
+                    problems.push(format!("{}: {} does not exist, and require_tls is set", field, path));
+                }
+            }
+        }
+
+        for (field, dir) in [("content.cars_dir", &self.content.cars_dir), ("content.tracks_dir", &self.content.tracks_dir)] {
+            if !Path::new(dir).is_dir() {
+                problems.push(format!("{}: {} is not a directory", field, dir));
+            }
+        }
+
+        if self.replication.role != ReplicationRole::Disabled && self.replication.shared_secret.is_empty() {
+            problems.push("replication.shared_secret: the primary and standby need a shared secret".to_string());
+        }
+
+        match self.cluster.role {
+            ClusterRole::Standalone => {}
+            ClusterRole::Gateway => {
+                if self.cluster.instance_timeout_ms <= self.cluster.report_interval_ms {
+                    problems.push(format!(
+                        "cluster.instance_timeout_ms: {} must be longer than report_interval_ms ({})",
+                        self.cluster.instance_timeout_ms, self.cluster.report_interval_ms
+                    ));
+                }
+            }
+            ClusterRole::Instance => {
+                if let Err(e) = crate::cluster::GatewayClient::new(&self.cluster.gateway_url, &self.cluster.token) {
+                    problems.push(format!("cluster.gateway_url: {}", e));
+                }
+                if self.cluster.public_address.is_empty() {
+                    problems.push("cluster.public_address: instances need the address players reach them at".to_string());
+                }
+                if self.cluster.report_interval_ms == 0 {
+                    problems.push("cluster.report_interval_ms: must be at least 1".to_string());
+                }
+            }
+        }
+        if self.cluster.role != ClusterRole::Standalone && self.cluster.token.is_empty() {
+            problems.push("cluster.token: the gateway and its instances need a shared token".to_string());
+        }
+
+        for (car, balance) in &self.balance_of_performance.cars {
+            if let Err(e) = balance.validate() {
+                problems.push(format!("balance_of_performance.cars.\"{}\": {}", car, e));
+            }
+        }
+
+        problems.extend(self.bind_conflicts());
+        problems
     }
 
-    pub fn load_or_default<P: AsRef<Path>>(path: P) -> Self {
-        Self::load(path).unwrap_or_else(|e| {
-            eprintln!("Failed to load config: {}, using defaults", e);
-            Self::default()
-        })
+    /// Listen addresses that do not parse or that another listener already uses
+    fn bind_conflicts(&self) -> Vec<String> {
+        let mut binds = vec![
+            ("network.tcp_bind", Protocol::Tcp, &self.network.tcp_bind),
+            ("network.udp_bind", Protocol::Udp, &self.network.udp_bind),
+            ("network.health_bind", Protocol::Tcp, &self.network.health_bind),
+        ];
+        if self.admin_api.enabled {
+            binds.push(("admin_api.bind", Protocol::Tcp, &self.admin_api.bind));
+        }
+        if self.grpc.enabled {
+            binds.push(("grpc.bind", Protocol::Tcp, &self.grpc.bind));
+        }
+        if self.dashboard.enabled {
+            binds.push(("dashboard.bind", Protocol::Udp, &self.dashboard.bind));
+        }
+        if self.replication.role == ReplicationRole::Standby {
+            binds.push(("replication.standby_addr", Protocol::Tcp, &self.replication.standby_addr));
+        }
+        if self.cluster.role == ClusterRole::Gateway {
+            binds.push(("cluster.bind", Protocol::Tcp, &self.cluster.bind));
+        }
+
+        let mut problems = Vec::new();
+        let mut parsed: Vec<(&str, Protocol, &str, u16)> = Vec::new();
+        for (field, protocol, bind) in binds {
+            let Some((host, port)) = bind.rsplit_once(':').and_then(|(host, port)| Some((host, port.parse::<u16>().ok()?)))
+            else {
+                problems.push(format!("{}: \"{}\" is not a host:port address", field, bind));
+                continue;
+            };
+            let host = host.trim_start_matches('[').trim_end_matches(']');
+            let clash = parsed.iter().find(|(_, other_protocol, other_host, other_port)| {
+                *other_protocol == protocol
+                    && port != 0
+                    && *other_port == port
+                    && (*other_host == host || is_wildcard(other_host) || is_wildcard(host))
+            });
+            if let Some((other, _, _, _)) = clash {
+                problems.push(format!("{}: port {} is already used by {}", field, port, other));
+            }
+            parsed.push((field, protocol, host, port));
+        }
+        problems
     }
+
+    fn default_with_env_overrides() -> Result<Self, Box<dyn std::error::Error>> {
+        let mut table = toml::Table::try_from(Self::default())?;
+        apply_env_overrides(&mut table, std::env::vars())?;
+        Ok(table.try_into()?)
+    }
+
+    /// Take the settings that are safe to change while the server runs from
+    /// `new`. The rest keep their values until a restart.
+    pub fn apply_reload(&mut self, new: &ServerConfig) -> ConfigReload {
+        let mut reload = ConfigReload::default();
+        macro_rules! reloadable {
+            ($($section:ident . $field:ident),* $(,)?) => {
+                $(
+                    if self.$section.$field != new.$section.$field {
+                        self.$section.$field = new.$section.$field.clone();
+                        reload.applied.push(concat!(stringify!($section), ".", stringify!($field)));
+                    }
+                )*
+            };
+        }
+        reloadable!(
+            logging.level,
+            server.max_sessions,
+            server.session_timeout_seconds,
+            network.stats_interval_ms,
+            live_timing.interval_ms,
+            session_saves.auto_save_interval_seconds,
+            dashboard.max_rate_hz,
+            session_limits.max_per_player,
+            session_limits.max_per_address,
+            session_limits.creation_cooldown_seconds,
+        );
+
+        // Whatever still differs was not reloadable
+        if let (Ok(current), Ok(new)) = (toml::Table::try_from(&*self), toml::Table::try_from(new)) {
+            reload.needs_restart = new
+                .iter()
+                .filter(|(section, value)| current.get(*section) != Some(*value))
+                .map(|(section, _)| section.clone())
+                .collect();
+        }
+        reload
+    }
+}
+
+/// What a config reload changed
+#[derive(Debug, Default, PartialEq)]
+pub struct ConfigReload {
+    /// Settings now in effect, as `section.field`
+    pub applied: Vec<&'static str>,
+    /// Sections with other changes, which wait for a restart
+    pub needs_restart: Vec<String>,
+}
+
+/// Set the values named by `APEXSIM__SECTION__FIELD` variables in `table`.
+/// A value is read as TOML (numbers, booleans, arrays) when it parses as
+/// one, and as a string otherwise.
+pub fn apply_env_overrides(
+    table: &mut toml::Table,
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Result<(), String> {
+    for (name, raw) in vars {
+        let Some(path) = name.strip_prefix(ENV_OVERRIDE_PREFIX) else {
+            continue;
+        };
+        let keys: Vec<String> = path.split("__").map(|key| key.to_lowercase()).collect();
+        if keys.iter().any(|key| key.is_empty()) {
+            return Err(format!("{} does not name a config value", name));
+        }
+        let (field, sections) = keys.split_last().expect("split always yields a key");
+        let mut target = &mut *table;
+        for section in sections {
+            let entry = target
+                .entry(section.clone())
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+            target = entry
+                .as_table_mut()
+                .ok_or_else(|| format!("{}: {} is not a section", name, section))?;
+        }
+        target.insert(field.clone(), parse_env_value(&raw));
+    }
+    Ok(())
+}
+
+fn parse_env_value(raw: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|mut parsed| parsed.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
 }
 
 #[cfg(test)]
@@ -140,4 +930,153 @@ mod tests {
         assert!(toml_str.contains("tick_rate_hz"));
         assert!(toml_str.contains("tcp_bind"));
     }
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let mut table = toml::Table::try_from(ServerConfig::default()).unwrap();
+        apply_env_overrides(
+            &mut table,
+            vars(&[
+                ("APEXSIM__SERVER__TICK_RATE_HZ", "120"),
+                ("APEXSIM__NETWORK__TCP_BIND", "0.0.0.0:9100"),
+                ("APEXSIM__NETWORK__REQUIRE_TLS", "true"),
+                ("APEXSIM__SERVER__ADMIN_TOKENS", r#"["a", "b"]"#),
+                ("APEXSIM__RESULTS_EXPORT__TEAMS__ALICE", "Red Team"),
+                ("PATH", "/usr/bin"),
+            ]),
+        )
+        .unwrap();
+        let config: ServerConfig = table.try_into().unwrap();
+
+        assert_eq!(config.server.tick_rate_hz, 120);
+        assert_eq!(config.network.tcp_bind, "0.0.0.0:9100");
+        assert!(config.network.require_tls);
+        assert_eq!(config.server.admin_tokens, vec!["a", "b"]);
+        assert_eq!(config.results_export.teams["alice"], "Red Team");
+    }
+
+    #[test]
+    fn test_env_overrides_reject_bad_paths() {
+        let mut table = toml::Table::try_from(ServerConfig::default()).unwrap();
+        assert!(apply_env_overrides(&mut table, vars(&[("APEXSIM__SERVER__TICK_RATE_HZ__X", "1")])).is_err());
+        assert!(apply_env_overrides(&mut table, vars(&[("APEXSIM____X", "1")])).is_err());
+    }
+
+    #[test]
+    fn test_apply_reload_takes_only_reloadable_settings() {
+        let mut config = ServerConfig::default();
+        let mut new = config.clone();
+        new.logging.level = "debug".to_string();
+        new.server.max_sessions = 16;
+        new.live_timing.interval_ms = 250;
+        new.server.tick_rate_hz = 120;
+        new.network.tcp_bind = "0.0.0.0:9100".to_string();
+
+        let reload = config.apply_reload(&new);
+        assert_eq!(reload.applied, vec!["logging.level", "server.max_sessions", "live_timing.interval_ms"]);
+        let mut needs_restart = reload.needs_restart;
+        needs_restart.sort();
+        assert_eq!(needs_restart, vec!["network", "server"]);
+        assert_eq!((config.logging.level.as_str(), config.server.max_sessions), ("debug", 16));
+        assert_eq!((config.server.tick_rate_hz, config.network.tcp_bind.as_str()), (240, "127.0.0.1:9000"));
+
+        assert_eq!(config.apply_reload(&config.clone()), ConfigReload::default());
+    }
+
+    fn valid_config() -> ServerConfig {
+        let mut config = ServerConfig::default();
+        let dir = std::env::temp_dir().to_string_lossy().into_owned();
+        config.content.cars_dir = dir.clone();
+        config.content.tracks_dir = dir;
+        config
+    }
+
+    #[test]
+    fn test_validate_reports_every_problem() {
+        assert_eq!(valid_config().validate(), Vec::<String>::new());
+
+        let mut config = valid_config();
+        config.server.tick_rate_hz = 5;
+        config.network.require_tls = true;
+        config.network.tls_cert_path = "/nonexistent/cert.pem".to_string();
+        config.network.tls_key_path = "/nonexistent/key.pem".to_string();
+        config.content.tracks_dir = "/nonexistent/tracks".to_string();
+        config.network.health_bind = "localhost".to_string();
+        config.logging.file.path = "server.log".to_string();
+        config.logging.file.level = "info,=".to_string();
+
+        let problems = config.validate();
+        let fields: Vec<&str> = problems.iter().map(|p| p.split(':').next().unwrap()).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "server.tick_rate_hz",
+                "logging.file.level",
+                "network.tls_cert_path",
+                "network.tls_key_path",
+                "content.tracks_dir",
+                "network.health_bind"
+            ],
+            "{:?}",
+            problems
+        );
+    }
+
+    #[test]
+    fn test_validate_cluster_instance() {
+        let mut config = valid_config();
+        config.cluster.role = ClusterRole::Instance;
+        config.cluster.gateway_url = "https://gateway:9200".to_string();
+        let problems = config.validate();
+        let fields: Vec<&str> = problems.iter().map(|p| p.split(':').next().unwrap()).collect();
+        assert_eq!(fields, vec!["cluster.gateway_url", "cluster.public_address", "cluster.token"], "{:?}", problems);
+
+        config.cluster.gateway_url = "http://gateway:9200".to_string();
+        config.cluster.public_address = "10.0.0.2:9000".to_string();
+        config.cluster.token = "cluster-token".to_string();
+        assert_eq!(config.validate(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_validate_port_conflicts() {
+        let mut config = valid_config();
+        config.network.tcp_bind = "0.0.0.0:9000".to_string();
+        config.network.udp_bind = "0.0.0.0:9000".to_string();
+        config.network.health_bind = "127.0.0.1:9000".to_string();
+        assert_eq!(config.validate(), vec!["network.health_bind: port 9000 is already used by network.tcp_bind"]);
+
+        // Distinct hosts may share a port; port 0 never conflicts
+        config.network.tcp_bind = "127.0.0.1:0".to_string();
+        config.network.health_bind = "127.0.0.2:0".to_string();
+        config.admin_api.enabled = true;
+        config.admin_api.bind = "127.0.0.2:0".to_string();
+        assert_eq!(config.validate(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_load_or_default_only_falls_back_on_missing_file() {
+        let dir = std::env::temp_dir().join(format!("apexsim-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert!(ServerConfig::load_or_default(dir.join("missing.toml")).is_ok());
+
+        let broken = dir.join("broken.toml");
+        std::fs::write(&broken, "[server\n").unwrap();
+        assert!(ServerConfig::load_or_default(&broken).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_spectator_delay_limits() {
+        let spectating = SpectatingSettings { min_delay_seconds: 30, max_delay_seconds: 120 };
+        assert_eq!(spectating.delay_seconds(0, false), 30);
+        assert_eq!(spectating.delay_seconds(60, false), 60);
+        assert_eq!(spectating.delay_seconds(600, false), 120);
+        // Admins may watch live, but not further back than anyone else
+        assert_eq!(spectating.delay_seconds(0, true), 0);
+        assert_eq!(spectating.delay_seconds(600, true), 120);
+    }
 }
@@ -0,0 +1,56 @@
+//! Fingerprints of the content files clients render from.
+//!
+//! A track is hashed from its track file; a car from its `car.toml` followed by
+//! its model file. The hashes go out with `LobbyState`, so a client can tell when
+//! its local copy of a car or track is not the one the server simulates.
+
+use aws_lc_rs::digest::{Context, SHA256};
+use std::path::Path;
+
+/// SHA-256 of the given byte slices in order, as lowercase hex
+pub fn hash_parts(parts: &[&[u8]]) -> String {
+    let mut context = Context::new(&SHA256);
+    for part in parts {
+        context.update(part);
+    }
+    context.finish().as_ref().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Hash of a car folder: its `car.toml` and, when present, its model file
+pub fn hash_car(car_toml: &Path, model: &str) -> std::io::Result<String> {
+    let toml = std::fs::read(car_toml)?;
+    let model_path = car_toml.parent().map(|dir| dir.join(model));
+    let model_bytes = match model_path {
+        Some(path) if !model.is_empty() && path.is_file() => std::fs::read(path)?,
+        _ => Vec::new(),
+    };
+    Ok(hash_parts(&[&toml, &model_bytes]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_is_sha256_of_the_parts_in_order() {
+        assert_eq!(
+            hash_parts(&[b"ab", b"c"]),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(hash_parts(&[b"abc"]), hash_parts(&[b"a", b"", b"bc"]));
+        assert_ne!(hash_parts(&[b"abc"]), hash_parts(&[b"abd"]));
+    }
+
+    #[test]
+    fn test_car_hash_covers_the_model_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let toml = dir.path().join("car.toml");
+        std::fs::write(&toml, "name = \"Test\"\n").unwrap();
+        std::fs::write(dir.path().join("car.glb"), b"mesh").unwrap();
+
+        let before = hash_car(&toml, "car.glb").unwrap();
+        assert_eq!(before, hash_parts(&[b"name = \"Test\"\n", b"mesh"]));
+        std::fs::write(dir.path().join("car.glb"), b"other mesh").unwrap();
+        assert_ne!(hash_car(&toml, "car.glb").unwrap(), before);
+    }
+}
@@ -0,0 +1,177 @@
+//! Car and track files served to clients that are missing them.
+//!
+//! A client asks for the manifest of a loaded car or track, then downloads each
+//! file it lacks, or whose hash differs from its copy, as `ContentChunk`s. A
+//! download that breaks off resumes from the bytes the client already has.
+//! Files are named by their path relative to the content folder, and only the
+//! files of loaded content can be downloaded, so a request cannot reach
+//! anything else on disk.
+
+use crate::content_hash;
+use crate::data::{CarConfig, TrackConfig};
+use crate::network::{ContentFileInfo, ContentKind, ContentManifestData};
+use crate::procgen::terrain;
+use crate::track_loader::TrackLoader;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use uuid::Uuid;
+
+/// Size of each `ContentChunk` when a client downloads a content file
+pub const CONTENT_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Manifests and files one connection may be sent at the same time
+pub const MAX_CONTENT_TRANSFERS_PER_CONNECTION: usize = 4;
+
+/// Hash of a file as it was when last read
+#[derive(Clone)]
+struct CachedHash {
+    size_bytes: u64,
+    modified: Option<SystemTime>,
+    sha256: String,
+}
+
+/// The downloadable files of every loaded car and track
+#[derive(Default)]
+pub struct ContentLibrary {
+    /// Paths of each car's or track's files, relative to the content folder
+    packs: HashMap<(ContentKind, Uuid), Vec<String>>,
+    /// Relative path -> file on disk
+    files: HashMap<String, PathBuf>,
+    /// Hashes are computed on the first request, not at startup
+    hashes: Mutex<HashMap<PathBuf, CachedHash>>,
+}
+
+impl Clone for ContentLibrary {
+    fn clone(&self) -> Self {
+        Self {
+            packs: self.packs.clone(),
+            files: self.files.clone(),
+            hashes: Mutex::new(self.hashes.lock().unwrap().clone()),
+        }
+    }
+}
+
+impl ContentLibrary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serve a track's file, with its AI hints and terrain cache when they exist
+    pub fn add_track(&mut self, track: &TrackConfig, content_root: &Path) {
+        let Some(source_path) = &track.source_path else {
+            return;
+        };
+        let track_file = content_root.join(source_path);
+        let hints = TrackLoader::ai_hints_path(&track_file);
+        let terrain = terrain::get_terrain_cache_path(&track_file);
+        let files = [track_file, hints, terrain].into_iter().filter(|f| f.is_file()).collect();
+        self.add_pack(ContentKind::Track, track.id.0, content_root, files);
+    }
+
+    /// Serve every file in a car's folder: `car.toml`, the model and its textures
+    pub fn add_car(&mut self, car: &CarConfig, content_root: &Path) {
+        let Some(folder) = car.source_path.as_ref().and_then(|p| content_root.join(p).parent().map(Path::to_path_buf)) else {
+            return;
+        };
+        let mut files = Vec::new();
+        collect_files(&folder, &mut files);
+        files.sort();
+        self.add_pack(ContentKind::Car, car.id.0, content_root, files);
+    }
+
+    fn add_pack(&mut self, kind: ContentKind, id: Uuid, content_root: &Path, files: Vec<PathBuf>) {
+        let mut pack = Vec::with_capacity(files.len());
+        for file in files {
+            let relative = file.strip_prefix(content_root).unwrap_or(&file);
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            self.files.insert(relative.clone(), file);
+            pack.push(relative);
+        }
+        self.packs.insert((kind, id), pack);
+    }
+
+    /// The file on disk for a path from a manifest; `None` for anything else
+    pub fn file(&self, path: &str) -> Option<&Path> {
+        self.files.get(path).map(PathBuf::as_path)
+    }
+
+    /// Files of a car or track with their sizes and hashes, `None` when it is
+    /// not loaded. Files changed since they were last hashed are read again,
+    /// so call this off the game loop.
+    pub fn manifest(&self, kind: ContentKind, id: Uuid, max_file_bytes: u64) -> Option<std::io::Result<ContentManifestData>> {
+        let pack = self.packs.get(&(kind, id))?;
+        let files = pack
+            .iter()
+            .map(|path| {
+                let (size_bytes, sha256) = self.hash(&self.files[path])?;
+                Ok(ContentFileInfo {
+                    path: path.clone(),
+                    size_bytes,
+                    sha256,
+                    too_large: size_bytes > max_file_bytes,
+                })
+            })
+            .collect::<std::io::Result<Vec<_>>>();
+        Some(files.map(|files| ContentManifestData { kind, id, files }))
+    }
+
+    fn hash(&self, file: &Path) -> std::io::Result<(u64, String)> {
+        let metadata = std::fs::metadata(file)?;
+        let modified = metadata.modified().ok();
+        if let Some(cached) = self.hashes.lock().unwrap().get(file) {
+            if cached.size_bytes == metadata.len() && cached.modified == modified && modified.is_some() {
+                return Ok((cached.size_bytes, cached.sha256.clone()));
+            }
+        }
+
+        let bytes = std::fs::read(file)?;
+        let sha256 = content_hash::hash_parts(&[&bytes]);
+        let size_bytes = bytes.len() as u64;
+        self.hashes.lock().unwrap().insert(file.to_path_buf(), CachedHash { size_bytes, modified, sha256: sha256.clone() });
+        Ok((size_bytes, sha256))
+    }
+}
+
+fn collect_files(dir: &Path, found: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+        if path.is_dir() {
+            collect_files(&path, found);
+        } else if path.is_file() {
+            found.push(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_track_pack_has_its_hints_and_terrain_and_nothing_else() {
+        let root = tempfile::tempdir().unwrap();
+        let tracks = root.path().join("tracks");
+        std::fs::create_dir_all(&tracks).unwrap();
+        std::fs::write(tracks.join("Oval.yaml"), "name: Oval\n").unwrap();
+        std::fs::write(tracks.join("Oval.terrain.msgpack"), [1u8; 100]).unwrap();
+        std::fs::write(tracks.join("Other.yaml"), "name: Other\n").unwrap();
+
+        let track = TrackConfig { source_path: Some("tracks/Oval.yaml".to_string()), ..TrackConfig::default() };
+        let mut library = ContentLibrary::new();
+        library.add_track(&track, root.path());
+
+        let manifest = library.manifest(ContentKind::Track, track.id.0, 50).unwrap().unwrap();
+        let files: Vec<_> = manifest.files.iter().map(|f| (f.path.as_str(), f.size_bytes, f.too_large)).collect();
+        assert_eq!(files, [("tracks/Oval.yaml", 11, false), ("tracks/Oval.terrain.msgpack", 100, true)]);
+        assert_eq!(manifest.files[0].sha256, content_hash::hash_parts(&[b"name: Oval\n"]));
+
+        assert!(library.file("tracks/Oval.yaml").is_some());
+        assert!(library.file("tracks/Other.yaml").is_none());
+        assert!(library.file("tracks/../tracks/Oval.yaml").is_none());
+        assert!(library.manifest(ContentKind::Car, track.id.0, 50).is_none());
+    }
+}
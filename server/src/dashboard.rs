@@ -0,0 +1,332 @@
+//! Fixed-layout UDP telemetry for dashboards, motion rigs and overlay tools.
+//!
+//! A client asks for it with `SetDashboardOutput` and names a UDP port on its
+//! own address. The server then sends that port one `DashboardPacket` for the
+//! player's car at the chosen rate. The packet is a packed little-endian struct
+//! with a magic and version in front, so tools can read it without a
+//! MessagePack decoder or an ApexSim plugin. The layout is documented in
+//! docs/NETWORK_IMPLEMENTATION.md and only grows at the end within a version.
+
+use crate::data::*;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+
+/// First bytes of every packet
+pub const DASHBOARD_PACKET_MAGIC: [u8; 4] = *b"APXD";
+
+/// Layout version, bumped when a field moves or changes meaning
+pub const DASHBOARD_PACKET_VERSION: u16 = 1;
+
+/// Bytes in a version 1 packet
+pub const DASHBOARD_PACKET_SIZE: usize = 220;
+
+const FLAG_ON_TRACK: u8 = 1 << 0;
+const FLAG_ENGINE_STALLED: u8 = 1 << 1;
+const FLAG_FINISHED: u8 = 1 << 2;
+const FLAG_AIRBORNE: u8 = 1 << 3;
+const FLAG_COLLIDING: u8 = 1 << 4;
+
+/// One car's state in the dashboard layout. Per-wheel arrays are ordered
+/// FL, FR, RL, RR
+#[derive(Debug, Clone, PartialEq)]
+pub struct DashboardPacket {
+    pub server_tick: u32,
+    pub session_state: SessionState,
+    pub game_mode: GameMode,
+    pub gear: i8,
+    pub flags: u8,
+    pub speed_mps: f32,
+    pub engine_rpm: f32,
+    pub max_rpm: f32,
+    pub throttle: f32,
+    pub brake: f32,
+    pub steering: f32,
+    pub clutch: f32,
+    pub position: [f32; 3],
+    pub velocity: [f32; 3],
+    /// Yaw, pitch, roll
+    pub rotation_rad: [f32; 3],
+    /// Lateral, longitudinal, vertical
+    pub g_force: [f32; 3],
+    pub tire_temps_c: [f32; 4],
+    pub tire_pressures_kpa: [f32; 4],
+    pub tire_wear_percent: [f32; 4],
+    pub slip_ratio: [f32; 4],
+    pub suspension_travel_m: [f32; 4],
+    pub brake_temps_c: [f32; 4],
+    pub fuel_liters: f32,
+    pub fuel_capacity_liters: f32,
+    pub current_lap: u16,
+    /// 0 when the session has no lap limit
+    pub total_laps: u16,
+    /// Race position, 1 for the leader
+    pub race_position: u8,
+    pub car_count: u8,
+    pub current_lap_time_ms: u32,
+    /// 0 until a lap is completed
+    pub last_lap_time_ms: u32,
+    /// 0 until a lap is completed
+    pub best_lap_time_ms: u32,
+    pub track_progress_m: f32,
+}
+
+impl DashboardPacket {
+    /// Packet for `state`, driving in `session` at `race_position` of `car_count`
+    pub fn new(state: &CarState, session: &RaceSession, max_rpm: f32, race_position: u8, car_count: u8) -> Self {
+        let mut flags = 0;
+        for (set, flag) in [
+            (state.is_on_track, FLAG_ON_TRACK),
+            (state.engine_stalled, FLAG_ENGINE_STALLED),
+            (state.finish_position.is_some(), FLAG_FINISHED),
+            (state.is_airborne, FLAG_AIRBORNE),
+            (state.is_colliding, FLAG_COLLIDING),
+        ] {
+            if set {
+                flags |= flag;
+            }
+        }
+        let tires = [
+            &state.tires.front_left,
+            &state.tires.front_right,
+            &state.tires.rear_left,
+            &state.tires.rear_right,
+        ];
+
+        Self {
+            server_tick: session.current_tick,
+            session_state: session.state,
+            game_mode: session.game_mode,
+            gear: state.gear,
+            flags,
+            speed_mps: state.speed_mps,
+            engine_rpm: state.engine_rpm,
+            max_rpm,
+            throttle: state.throttle_input,
+            brake: state.brake_input,
+            steering: state.steering_input,
+            clutch: state.clutch_input,
+            position: [state.pos_x, state.pos_y, state.pos_z],
+            velocity: [state.vel_x, state.vel_y, state.vel_z],
+            rotation_rad: [state.yaw_rad, state.pitch_rad, state.roll_rad],
+            g_force: [state.g_forces.lateral_g, state.g_forces.longitudinal_g, state.g_forces.vertical_g],
+            tire_temps_c: tires.map(|tire| tire.temperature_c),
+            tire_pressures_kpa: tires.map(|tire| tire.pressure_kpa),
+            tire_wear_percent: tires.map(|tire| tire.wear_percent),
+            slip_ratio: tires.map(|tire| tire.slip_ratio),
+            suspension_travel_m: [
+                state.suspension.front_left_travel_m,
+                state.suspension.front_right_travel_m,
+                state.suspension.rear_left_travel_m,
+                state.suspension.rear_right_travel_m,
+            ],
+            brake_temps_c: [
+                state.brakes.front_left_temp_c,
+                state.brakes.front_right_temp_c,
+                state.brakes.rear_left_temp_c,
+                state.brakes.rear_right_temp_c,
+            ],
+            fuel_liters: state.fuel_liters,
+            fuel_capacity_liters: state.fuel_capacity_liters,
+            current_lap: state.current_lap,
+            total_laps: session.lap_limit as u16,
+            race_position,
+            car_count,
+            current_lap_time_ms: state.current_lap_time_ms,
+            last_lap_time_ms: state.last_lap_time_ms.unwrap_or(0),
+            best_lap_time_ms: state.best_lap_time_ms.unwrap_or(0),
+            track_progress_m: state.track_progress,
+        }
+    }
+
+    /// The packed little-endian wire form
+    pub fn to_bytes(&self) -> [u8; DASHBOARD_PACKET_SIZE] {
+        let mut out = [0u8; DASHBOARD_PACKET_SIZE];
+        let mut writer = PacketWriter { out: &mut out, at: 0 };
+
+        writer.put(&DASHBOARD_PACKET_MAGIC);
+        writer.put(&DASHBOARD_PACKET_VERSION.to_le_bytes());
+        writer.put(&(DASHBOARD_PACKET_SIZE as u16).to_le_bytes());
+        writer.put(&self.server_tick.to_le_bytes());
+        writer.put(&[self.session_state as u8, self.game_mode as u8, self.gear as u8, self.flags]);
+        for value in [
+            self.speed_mps,
+            self.engine_rpm,
+            self.max_rpm,
+            self.throttle,
+            self.brake,
+            self.steering,
+            self.clutch,
+        ] {
+            writer.put_f32(value);
+        }
+        for values in [self.position, self.velocity, self.rotation_rad, self.g_force] {
+            values.iter().for_each(|value| writer.put_f32(*value));
+        }
+        for values in [
+            self.tire_temps_c,
+            self.tire_pressures_kpa,
+            self.tire_wear_percent,
+            self.slip_ratio,
+            self.suspension_travel_m,
+            self.brake_temps_c,
+        ] {
+            values.iter().for_each(|value| writer.put_f32(*value));
+        }
+        writer.put_f32(self.fuel_liters);
+        writer.put_f32(self.fuel_capacity_liters);
+        writer.put(&self.current_lap.to_le_bytes());
+        writer.put(&self.total_laps.to_le_bytes());
+        writer.put(&[self.race_position, self.car_count, 0, 0]);
+        writer.put(&self.current_lap_time_ms.to_le_bytes());
+        writer.put(&self.last_lap_time_ms.to_le_bytes());
+        writer.put(&self.best_lap_time_ms.to_le_bytes());
+        writer.put_f32(self.track_progress_m);
+        debug_assert_eq!(writer.at, DASHBOARD_PACKET_SIZE);
+
+        out
+    }
+}
+
+struct PacketWriter<'a> {
+    out: &'a mut [u8],
+    at: usize,
+}
+
+impl PacketWriter<'_> {
+    fn put(&mut self, bytes: &[u8]) {
+        self.out[self.at..self.at + bytes.len()].copy_from_slice(bytes);
+        self.at += bytes.len();
+    }
+
+    fn put_f32(&mut self, value: f32) {
+        self.put(&value.to_le_bytes());
+    }
+}
+
+/// Where and how often a player's dashboard packets go
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DashboardTarget {
+    pub addr: SocketAddr,
+    pub rate_hz: u16,
+    interval_ticks: u32,
+}
+
+/// Dashboard outputs of every player who asked for one
+#[derive(Debug, Clone, Default)]
+pub struct DashboardOutputs {
+    targets: HashMap<PlayerId, DashboardTarget>,
+}
+
+impl DashboardOutputs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Send `player_id`'s car to `addr` at `rate_hz`, capped at `max_rate_hz` and
+    /// the tick rate. Returns the rate actually used
+    pub fn set(&mut self, player_id: PlayerId, addr: SocketAddr, rate_hz: u16, max_rate_hz: u16, tick_rate_hz: u16) -> u16 {
+        let rate_hz = rate_hz.clamp(1, max_rate_hz.clamp(1, tick_rate_hz.max(1)));
+        let interval_ticks = (tick_rate_hz as u32 / rate_hz as u32).max(1);
+        self.targets.insert(player_id, DashboardTarget { addr, rate_hz, interval_ticks });
+        rate_hz
+    }
+
+    pub fn remove(&mut self, player_id: &PlayerId) -> Option<DashboardTarget> {
+        self.targets.remove(player_id)
+    }
+
+    pub fn get(&self, player_id: &PlayerId) -> Option<&DashboardTarget> {
+        self.targets.get(player_id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// Players in `session` whose packet is due at its current tick, with their address
+    pub fn due(&self, session: &RaceSession) -> Vec<(PlayerId, SocketAddr)> {
+        session
+            .participants
+            .keys()
+            .filter_map(|player_id| {
+                let target = self.targets.get(player_id)?;
+                session
+                    .current_tick
+                    .is_multiple_of(target.interval_ticks)
+                    .then_some((*player_id, target.addr))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRID_SLOT: GridSlot = GridSlot { position: 1, x: 0.0, y: 0.0, z: 0.0, yaw_rad: 0.0 };
+
+    #[test]
+    fn test_packet_layout() {
+        let player_id = PlayerId::new_v4();
+        let mut session = RaceSession::new(PlayerId::new_v4(), TrackConfigId::new_v4(), SessionKind::Multiplayer, 8, 0, 5);
+        session.state = SessionState::Racing;
+        session.current_tick = 4800;
+        let mut state = CarState::new(player_id, CarConfigId::new_v4(), &GRID_SLOT);
+        state.speed_mps = 42.5;
+        state.gear = -1;
+        state.engine_stalled = true;
+        state.tires.rear_right.temperature_c = 91.0;
+        state.current_lap = 3;
+        state.best_lap_time_ms = Some(81_234);
+        state.track_progress = 1234.5;
+
+        let packet = DashboardPacket::new(&state, &session, 8000.0, 2, 6);
+        let bytes = packet.to_bytes();
+        let f32_at = |offset: usize| f32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        let u32_at = |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+
+        assert_eq!(&bytes[0..4], b"APXD");
+        assert_eq!(u16::from_le_bytes([bytes[4], bytes[5]]), DASHBOARD_PACKET_VERSION);
+        assert_eq!(u16::from_le_bytes([bytes[6], bytes[7]]) as usize, DASHBOARD_PACKET_SIZE);
+        assert_eq!(u32_at(8), 4800);
+        assert_eq!(bytes[12], SessionState::Racing as u8);
+        assert_eq!(bytes[14] as i8, -1);
+        assert_eq!(bytes[15] & FLAG_ENGINE_STALLED, FLAG_ENGINE_STALLED);
+        assert_eq!(f32_at(16), 42.5);
+        assert_eq!(f32_at(24), 8000.0);
+        assert_eq!(f32_at(92 + 12), 91.0);
+        assert_eq!(u16::from_le_bytes([bytes[196], bytes[197]]), 3);
+        assert_eq!(u16::from_le_bytes([bytes[198], bytes[199]]), 5);
+        assert_eq!((bytes[200], bytes[201]), (2, 6));
+        assert_eq!(u32_at(212), 81_234);
+        assert_eq!(f32_at(216), 1234.5);
+    }
+
+    #[test]
+    fn test_outputs_follow_rate() {
+        let player_id = PlayerId::new_v4();
+        let mut session = RaceSession::new(PlayerId::new_v4(), TrackConfigId::new_v4(), SessionKind::Multiplayer, 8, 0, 0);
+        session
+            .participants
+            .insert(player_id, CarState::new(player_id, CarConfigId::new_v4(), &GRID_SLOT));
+        let addr: SocketAddr = "127.0.0.1:20777".parse().unwrap();
+
+        let mut outputs = DashboardOutputs::new();
+        assert_eq!(outputs.set(player_id, addr, 500, 120, 240), 120);
+        assert_eq!(outputs.set(player_id, addr, 60, 120, 240), 60);
+
+        let due_ticks: Vec<u32> = (0..12)
+            .filter(|tick| {
+                session.current_tick = *tick;
+                !outputs.due(&session).is_empty()
+            })
+            .collect();
+        assert_eq!(due_ticks, vec![0, 4, 8]);
+        assert_eq!(outputs.due(&session), Vec::new());
+
+        session.current_tick = 8;
+        assert_eq!(outputs.due(&session), vec![(player_id, addr)]);
+        outputs.remove(&player_id);
+        assert!(outputs.due(&session).is_empty());
+    }
+}
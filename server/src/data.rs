@@ -4,10 +4,78 @@ use std::collections::HashMap;
 use uuid::Uuid;
 
 // --- Identifiers ---
-pub type PlayerId = Uuid;
-pub type SessionId = Uuid;
-pub type CarConfigId = Uuid;
-pub type TrackConfigId = Uuid;
+
+/// Declares a UUID-backed identifier that serializes exactly like the bare `Uuid`
+/// it wraps, so the wire and file formats are unchanged.
+macro_rules! uuid_id {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+        #[serde(transparent)]
+        pub struct $name(pub Uuid);
+
+        impl $name {
+            pub fn new_v4() -> Self {
+                Self(Uuid::new_v4())
+            }
+
+            pub const fn nil() -> Self {
+                Self(Uuid::nil())
+            }
+
+            pub fn is_nil(&self) -> bool {
+                self.0.is_nil()
+            }
+
+            pub fn as_uuid(&self) -> &Uuid {
+                &self.0
+            }
+        }
+
+        impl From<Uuid> for $name {
+            fn from(uuid: Uuid) -> Self {
+                Self(uuid)
+            }
+        }
+
+        impl From<$name> for Uuid {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = uuid::Error;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Uuid::parse_str(s).map(Self)
+            }
+        }
+    };
+}
+
+uuid_id!(
+    /// A connected player or AI driver
+    PlayerId
+);
+uuid_id!(
+    /// A race session
+    SessionId
+);
+uuid_id!(
+    /// A loaded car configuration
+    CarConfigId
+);
+uuid_id!(
+    /// A loaded track configuration
+    TrackConfigId
+);
 pub type ConnectionId = Uuid;
 
 // --- Player State ---
@@ -26,6 +94,12 @@ pub struct CarConfig {
     pub id: CarConfigId,
     pub name: String,
     pub model: String,
+    /// SHA-256 of the car's `car.toml` and model file, empty for built-in cars
+    #[serde(default)]
+    pub content_hash: String,
+    /// Path to the car's `car.toml`, relative to the content folder (e.g. "cars/golfcart/car.toml")
+    #[serde(default)]
+    pub source_path: Option<String>,
 
     // Physical dimensions
     pub mass_kg: f32,
@@ -53,7 +127,8 @@ pub struct CarConfig {
     pub drivetrain: Drivetrain,     // FWD, RWD, AWD
 
     // Engine simulation parameters (more detailed than the legacy fields above).
-    // These are optional/soft-configurable: physics falls back to legacy behavior if unset.
+    // Physics drives the engine from `engine.torque_curve`; the loader builds one
+    // from the peak torque and power when a car file has none.
     #[serde(default)]
     pub engine: EngineConfig,
     #[serde(default)]
@@ -85,45 +160,84 @@ pub struct CarConfig {
     
     // Tires
     pub tire_config: TireConfig,
+
+    /// Limits on what players may change in their setup
+    #[serde(default)]
+    pub setup_ranges: SetupRanges,
+
+    /// Footprint used for contacts with other cars and walls
+    #[serde(default)]
+    pub collision_shape: CollisionShape,
+
+    /// Paint schemes players pick from; index 0 is the default
+    #[serde(default)]
+    pub liveries: Vec<Livery>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// A paint scheme for a car
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Livery {
+    pub name: String,
+    /// Skin texture, relative to the car's folder; None paints the model in the team colors
+    #[serde(default)]
+    pub texture: Option<String>,
+    /// Team colors as "#rrggbb"
+    pub primary_color: String,
+    pub secondary_color: String,
+    /// Race number shown on the number plate
+    #[serde(default)]
+    pub number: Option<u16>,
+}
+
+/// Collision footprint in the car's own frame: x forward, y left, in metres
+/// from the geometric center
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub enum CollisionShape {
+    /// Box spanning the car's length and width
+    #[default]
+    Box,
+    /// Convex outline of the bodywork, counter-clockwise
+    Hull(Vec<[f32; 2]>),
+    /// Rounded segments, e.g. one across each axle so wheels touch before bodywork
+    Capsules(Vec<CollisionCapsule>),
+}
+
+/// A segment grown by a radius, so its ends and sides are rounded
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CollisionCapsule {
+    pub from: [f32; 2],
+    pub to: [f32; 2],
+    pub radius_m: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum Drivetrain {
     FWD,  // Front-wheel drive
+    #[default]
     RWD,  // Rear-wheel drive
     AWD,  // All-wheel drive
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
 pub enum TransmissionType {
     Manual,
     DCT,
+    #[default]
     Sequential,
     Automatic,
     CVT,
 }
 
-impl Default for TransmissionType {
-    fn default() -> Self {
-        TransmissionType::Sequential
-    }
-}
-
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
 pub enum DifferentialType {
     Open,
     Locked,
+    #[default]
     ClutchLSD,
     ViscousLSD,
     Torsen,
 }
 
-impl Default for DifferentialType {
-    fn default() -> Self {
-        DifferentialType::ClutchLSD
-    }
-}
-
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct TorqueCurvePoint {
     pub rpm: f32,
@@ -132,9 +246,9 @@ pub struct TorqueCurvePoint {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EngineConfig {
-    /// RPM at which the limiter starts cutting torque.
+    /// RPM at which the limiter cuts the fuel.
     pub rev_limiter_rpm: f32,
-    /// Optional torque curve used by physics when non-empty.
+    /// Full-throttle torque against RPM; the loader synthesizes one if a car gives none.
     pub torque_curve: Vec<TorqueCurvePoint>,
     /// Approx. engine rotational inertia.
     pub inertia_kg_m2: f32,
@@ -144,6 +258,55 @@ pub struct EngineConfig {
     pub engine_brake_torque_nm: f32,
     /// Idle controller strength (simple proportional gain).
     pub idle_control_gain: f32,
+    /// Turbocharger; without one the curve's torque is on tap at once.
+    #[serde(default)]
+    pub turbo: Option<TurboConfig>,
+}
+
+/// Turbocharger, whose boost lags the throttle; the engine's torque curve is
+/// its torque at full boost
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurboConfig {
+    /// Share of the full-boost torque that comes from boost (0-1)
+    pub boost_torque_share: f32,
+    /// Engine speed below which the turbo makes no boost
+    pub boost_threshold_rpm: f32,
+    /// Time constant of boost building up once on the throttle
+    pub spool_time_s: f32,
+}
+
+impl EngineConfig {
+    /// Torque curve for an engine known only by its peak torque and power:
+    /// torque builds to its peak at about 60% of the rev range, and above that
+    /// the engine makes no more than its peak power
+    pub fn synthesized_torque_curve(peak_torque_nm: f32, peak_power_w: f32, idle_rpm: f32, redline_rpm: f32, max_rpm: f32) -> Vec<TorqueCurvePoint> {
+        const POINTS: usize = 12;
+        let rev_band = (redline_rpm - idle_rpm).max(1.0);
+        let top_rpm = max_rpm.max(redline_rpm);
+        (0..POINTS)
+            .map(|i| {
+                let rpm = idle_rpm + (top_rpm - idle_rpm) * i as f32 / (POINTS - 1) as f32;
+                let normalized = (rpm - idle_rpm) / rev_band;
+                let shaped = peak_torque_nm * (1.0 - (normalized - 0.6).powi(2)).clamp(0.3, 1.0);
+                let omega = rpm * 2.0 * std::f32::consts::PI / 60.0;
+                let power_limited = if omega > 0.0 { peak_power_w / omega } else { shaped };
+                TorqueCurvePoint { rpm, torque_nm: shaped.min(power_limited) }
+            })
+            .collect()
+    }
+
+    /// Highest torque on the curve
+    pub fn peak_torque_nm(&self) -> f32 {
+        self.torque_curve.iter().map(|p| p.torque_nm).fold(0.0, f32::max)
+    }
+
+    /// Highest power on the curve (W)
+    pub fn peak_power_w(&self) -> f32 {
+        self.torque_curve
+            .iter()
+            .map(|p| p.torque_nm * p.rpm * 2.0 * std::f32::consts::PI / 60.0)
+            .fold(0.0, f32::max)
+    }
 }
 
 impl Default for EngineConfig {
@@ -155,6 +318,7 @@ impl Default for EngineConfig {
             friction_torque_nm: 20.0,
             engine_brake_torque_nm: 80.0,
             idle_control_gain: 0.15,
+            turbo: None,
         }
     }
 }
@@ -162,10 +326,12 @@ impl Default for EngineConfig {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransmissionConfig {
     pub transmission_type: TransmissionType,
-    /// Shift time used by automatic shifting / latency simulation.
+    /// Time the drive is cut while changing gear.
     pub shift_time_s: f32,
     /// Drivetrain efficiency multiplier (0-1). Applied to wheel torque.
     pub efficiency: f32,
+    /// Torque the fully engaged clutch carries before it slips.
+    pub clutch_max_torque_nm: f32,
 }
 
 impl Default for TransmissionConfig {
@@ -174,6 +340,7 @@ impl Default for TransmissionConfig {
             transmission_type: TransmissionType::Sequential,
             shift_time_s: 0.12,
             efficiency: 0.92,
+            clutch_max_torque_nm: 700.0,
         }
     }
 }
@@ -244,12 +411,6 @@ impl Default for HybridConfig {
     }
 }
 
-impl Default for Drivetrain {
-    fn default() -> Self {
-        Drivetrain::RWD
-    }
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SuspensionConfig {
     pub spring_rate_front_n_per_m: f32,
@@ -261,6 +422,19 @@ pub struct SuspensionConfig {
     pub anti_roll_bar_front: f32,
     pub anti_roll_bar_rear: f32,
     pub max_travel_m: f32,
+    /// Static ground clearance; compressing further than this bottoms the chassis out
+    #[serde(default = "default_ride_height_front_m")]
+    pub ride_height_front_m: f32,
+    #[serde(default = "default_ride_height_rear_m")]
+    pub ride_height_rear_m: f32,
+}
+
+fn default_ride_height_front_m() -> f32 {
+    0.08
+}
+
+fn default_ride_height_rear_m() -> f32 {
+    0.09
 }
 
 impl Default for SuspensionConfig {
@@ -275,10 +449,17 @@ impl Default for SuspensionConfig {
             anti_roll_bar_front: 15000.0,
             anti_roll_bar_rear: 12000.0,
             max_travel_m: 0.15,
+            ride_height_front_m: default_ride_height_front_m(),
+            ride_height_rear_m: default_ride_height_rear_m(),
         }
     }
 }
 
+/// Tire model parameters (Pacejka magic formula).
+///
+/// Each slip curve peaks at its optimal slip; the shape factor controls how
+/// quickly force falls off past the peak and the curvature factor how sharp
+/// the peak is.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TireConfig {
     pub grip_coefficient: f32,           // Base grip coefficient (0.8-1.2)
@@ -288,6 +469,54 @@ pub struct TireConfig {
     pub optimal_temperature_c: f32,      // Optimal tire temp for best grip
     pub temperature_grip_falloff: f32,   // Grip reduction per degree from optimal
     pub wear_rate: f32,                  // Wear rate multiplier
+    #[serde(default = "default_longitudinal_shape")]
+    pub longitudinal_shape: f32,         // Magic formula C for slip ratio (1.5-1.9)
+    #[serde(default)]
+    pub longitudinal_curvature: f32,     // Magic formula E for slip ratio (-1.0-0.9)
+    #[serde(default = "default_lateral_shape")]
+    pub lateral_shape: f32,              // Magic formula C for slip angle (1.2-1.5)
+    #[serde(default)]
+    pub lateral_curvature: f32,          // Magic formula E for slip angle (-1.0-0.9)
+    #[serde(default = "default_load_sensitivity")]
+    pub load_sensitivity: f32,           // Grip lost per unit of load above nominal (0.0-0.3)
+    #[serde(default = "default_nominal_load_n")]
+    pub nominal_load_n: f32,             // Wheel load the grip coefficient is quoted at
+    #[serde(default = "default_cold_pressure_kpa")]
+    pub cold_pressure_front_kpa: f32,    // Pressure set in the garage, at 20 °C
+    #[serde(default = "default_cold_pressure_kpa")]
+    pub cold_pressure_rear_kpa: f32,
+    #[serde(default = "default_optimal_pressure_kpa")]
+    pub optimal_pressure_kpa: f32,       // Hot pressure the tire grips best at
+    #[serde(default = "default_pressure_grip_falloff")]
+    pub pressure_grip_falloff: f32,      // Grip lost per kPa the hot pressure is off optimal
+}
+
+fn default_longitudinal_shape() -> f32 {
+    1.65
+}
+
+fn default_lateral_shape() -> f32 {
+    1.3
+}
+
+fn default_load_sensitivity() -> f32 {
+    0.1
+}
+
+fn default_nominal_load_n() -> f32 {
+    3500.0
+}
+
+fn default_cold_pressure_kpa() -> f32 {
+    210.0
+}
+
+fn default_optimal_pressure_kpa() -> f32 {
+    245.0
+}
+
+fn default_pressure_grip_falloff() -> f32 {
+    0.002
 }
 
 impl Default for TireConfig {
@@ -300,6 +529,16 @@ impl Default for TireConfig {
             optimal_temperature_c: 90.0,
             temperature_grip_falloff: 0.005,
             wear_rate: 1.0,
+            longitudinal_shape: default_longitudinal_shape(),
+            longitudinal_curvature: 0.0,
+            lateral_shape: default_lateral_shape(),
+            lateral_curvature: 0.0,
+            load_sensitivity: default_load_sensitivity(),
+            nominal_load_n: default_nominal_load_n(),
+            cold_pressure_front_kpa: default_cold_pressure_kpa(),
+            cold_pressure_rear_kpa: default_cold_pressure_kpa(),
+            optimal_pressure_kpa: default_optimal_pressure_kpa(),
+            pressure_grip_falloff: default_pressure_grip_falloff(),
         }
     }
 }
@@ -307,9 +546,11 @@ impl Default for TireConfig {
 impl Default for CarConfig {
     fn default() -> Self {
         Self {
-            id: Uuid::new_v4(),
+            id: CarConfigId::new_v4(),
             name: "Default Car".to_string(),
             model: "default.glb".to_string(),
+            content_hash: String::new(),
+            source_path: None,
 
             // Physical dimensions
             mass_kg: 1200.0,
@@ -338,6 +579,7 @@ impl Default for CarConfig {
 
             engine: EngineConfig {
                 rev_limiter_rpm: 8000.0,
+                torque_curve: EngineConfig::synthesized_torque_curve(450.0, 300000.0, 900.0, 7500.0, 8000.0),
                 ..EngineConfig::default()
             },
             transmission: TransmissionConfig::default(),
@@ -365,10 +607,253 @@ impl Default for CarConfig {
             
             // Tires
             tire_config: TireConfig::default(),
+
+            setup_ranges: SetupRanges::default(),
+
+            collision_shape: CollisionShape::Box,
+            liveries: Vec::new(),
         }
     }
 }
 
+impl CarConfig {
+    /// Force at the driven wheels at the torque curve's peak in first gear,
+    /// before the tires limit it
+    pub fn peak_drive_force_n(&self) -> f32 {
+        let first_gear = self.gear_ratios.get(1).copied().unwrap_or(0.0);
+        self.engine.peak_torque_nm() * first_gear * self.final_drive_ratio * self.transmission.efficiency
+            / self.wheel_radius_m.max(0.01)
+    }
+
+    /// Peak engine power per kilogram of car (W/kg)
+    pub fn power_to_weight_w_per_kg(&self) -> f32 {
+        self.engine.peak_power_w() / self.mass_kg.max(1.0)
+    }
+
+    /// Top speed on the flat: where drag takes all the power at the wheels, or
+    /// the rev limiter in top gear if that comes first
+    pub fn theoretical_top_speed_mps(&self) -> f32 {
+        let wheel_power_w = self.engine.peak_power_w() * self.transmission.efficiency;
+        let drag_area = 0.5 * crate::physics::AIR_DENSITY * self.drag_coefficient * self.frontal_area_m2;
+        let drag_limited = if drag_area > 0.0 { (wheel_power_w / drag_area).cbrt() } else { f32::INFINITY };
+
+        let top_gear = self.gear_ratios.iter().copied().filter(|&r| r > 0.0).fold(f32::INFINITY, f32::min);
+        let rev_limited = if top_gear.is_finite() && self.final_drive_ratio > 0.0 {
+            self.engine.rev_limiter_rpm * std::f32::consts::PI / 30.0 / (top_gear * self.final_drive_ratio) * self.wheel_radius_m
+        } else {
+            0.0
+        };
+        drag_limited.min(rev_limited)
+    }
+}
+
+/// Balance of Performance: handicaps put on a car on top of its own data, so
+/// different makes can race closely without editing car files
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BalanceOfPerformance {
+    /// Extra mass carried (kg)
+    pub ballast_kg: f32,
+    /// Scales engine torque, and so power, across the rev range
+    pub power_multiplier: f32,
+    /// Replaces the car's fuel tank size
+    pub fuel_capacity_liters: Option<f32>,
+}
+
+impl Default for BalanceOfPerformance {
+    fn default() -> Self {
+        Self {
+            ballast_kg: 0.0,
+            power_multiplier: 1.0,
+            fuel_capacity_liters: None,
+        }
+    }
+}
+
+impl BalanceOfPerformance {
+    /// Whether this changes nothing
+    pub fn is_neutral(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=500.0).contains(&self.ballast_kg) {
+            return Err(format!("ballast_kg {} is outside 0..=500", self.ballast_kg));
+        }
+        if !(0.5..=1.5).contains(&self.power_multiplier) {
+            return Err(format!("power_multiplier {} is outside 0.5..=1.5", self.power_multiplier));
+        }
+        match self.fuel_capacity_liters {
+            Some(liters) if !(liters.is_finite() && liters > 0.0) => Err(format!("fuel_capacity_liters {} must be positive", liters)),
+            _ => Ok(()),
+        }
+    }
+
+    /// The car config with the handicaps applied
+    pub fn apply(&self, config: &CarConfig) -> CarConfig {
+        let mut balanced = config.clone();
+        balanced.mass_kg += self.ballast_kg;
+        for point in &mut balanced.engine.torque_curve {
+            point.torque_nm *= self.power_multiplier;
+        }
+        balanced.max_engine_torque_nm *= self.power_multiplier;
+        balanced.max_engine_power_w *= self.power_multiplier;
+        if let Some(liters) = self.fuel_capacity_liters {
+            balanced.fuel.capacity_liters = liters;
+        }
+        balanced
+    }
+}
+
+/// Drag coefficient added per unit of extra wing lift coefficient
+const WING_DRAG_PER_LIFT: f32 = 0.15;
+
+/// Inclusive range a setup value may be chosen from
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SetupRange {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl SetupRange {
+    pub fn new(min: f32, max: f32) -> Self {
+        Self { min, max }
+    }
+
+    pub fn contains(&self, value: f32) -> bool {
+        value >= self.min && value <= self.max
+    }
+
+    /// The range widened just enough to take in `value`
+    pub fn including(self, value: f32) -> Self {
+        Self {
+            min: self.min.min(value),
+            max: self.max.max(value),
+        }
+    }
+}
+
+/// How far a car's setup may be changed from its baseline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SetupRanges {
+    pub final_drive_ratio: SetupRange,
+    /// Applies to every forward gear
+    pub gear_ratio: SetupRange,
+    /// Wing lift coefficients; more negative is more wing, more downforce and more drag
+    pub lift_coefficient_front: SetupRange,
+    pub lift_coefficient_rear: SetupRange,
+    /// Cold tire pressure
+    pub tire_pressure_kpa: SetupRange,
+    pub brake_bias_front: SetupRange,
+}
+
+impl SetupRanges {
+    /// The ranges widened to take in every value of `setup`
+    pub fn including(self, setup: &CarSetup) -> Self {
+        Self {
+            final_drive_ratio: self.final_drive_ratio.including(setup.final_drive_ratio),
+            gear_ratio: setup.gear_ratios.iter().fold(self.gear_ratio, |range, &ratio| range.including(ratio)),
+            lift_coefficient_front: self.lift_coefficient_front.including(setup.lift_coefficient_front),
+            lift_coefficient_rear: self.lift_coefficient_rear.including(setup.lift_coefficient_rear),
+            tire_pressure_kpa: self
+                .tire_pressure_kpa
+                .including(setup.tire_pressure_front_kpa)
+                .including(setup.tire_pressure_rear_kpa),
+            brake_bias_front: self.brake_bias_front.including(setup.brake_bias_front),
+        }
+    }
+}
+
+impl Default for SetupRanges {
+    fn default() -> Self {
+        Self {
+            final_drive_ratio: SetupRange::new(2.5, 6.0),
+            gear_ratio: SetupRange::new(0.5, 5.0),
+            lift_coefficient_front: SetupRange::new(-1.5, 0.0),
+            lift_coefficient_rear: SetupRange::new(-2.5, 0.0),
+            tire_pressure_kpa: SetupRange::new(150.0, 300.0),
+            brake_bias_front: SetupRange::new(0.45, 0.75),
+        }
+    }
+}
+
+/// A player's adjustments to their car for a session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CarSetup {
+    pub final_drive_ratio: f32,
+    /// Forward gears, first gear first
+    pub gear_ratios: Vec<f32>,
+    pub lift_coefficient_front: f32,
+    pub lift_coefficient_rear: f32,
+    /// Cold tire pressures
+    pub tire_pressure_front_kpa: f32,
+    pub tire_pressure_rear_kpa: f32,
+    pub brake_bias_front: f32,
+}
+
+impl CarSetup {
+    /// The setup the car comes with
+    pub fn baseline(config: &CarConfig) -> Self {
+        Self {
+            final_drive_ratio: config.final_drive_ratio,
+            gear_ratios: config.gear_ratios.iter().skip(1).copied().collect(),
+            lift_coefficient_front: config.lift_coefficient_front,
+            lift_coefficient_rear: config.lift_coefficient_rear,
+            tire_pressure_front_kpa: config.tire_config.cold_pressure_front_kpa,
+            tire_pressure_rear_kpa: config.tire_config.cold_pressure_rear_kpa,
+            brake_bias_front: config.brake_bias_front,
+        }
+    }
+
+    /// Check the setup fits the car and every value is within its setup ranges
+    pub fn validate(&self, config: &CarConfig) -> Result<(), String> {
+        let ranges = &config.setup_ranges;
+        let forward_gears = config.gear_ratios.len().saturating_sub(1);
+        if self.gear_ratios.len() != forward_gears {
+            return Err(format!("{} has {} forward gears, got {} ratios", config.name, forward_gears, self.gear_ratios.len()));
+        }
+        if self.gear_ratios.windows(2).any(|pair| pair[1] >= pair[0]) {
+            return Err("Each gear must be taller than the one before".to_string());
+        }
+
+        let check = |name: &str, value: f32, range: &SetupRange| {
+            if range.contains(value) {
+                Ok(())
+            } else {
+                Err(format!("{} {} is outside {}..={}", name, value, range.min, range.max))
+            }
+        };
+        check("Final drive ratio", self.final_drive_ratio, &ranges.final_drive_ratio)?;
+        for ratio in &self.gear_ratios {
+            check("Gear ratio", *ratio, &ranges.gear_ratio)?;
+        }
+        check("Front wing lift coefficient", self.lift_coefficient_front, &ranges.lift_coefficient_front)?;
+        check("Rear wing lift coefficient", self.lift_coefficient_rear, &ranges.lift_coefficient_rear)?;
+        check("Front tire pressure", self.tire_pressure_front_kpa, &ranges.tire_pressure_kpa)?;
+        check("Rear tire pressure", self.tire_pressure_rear_kpa, &ranges.tire_pressure_kpa)?;
+        check("Brake bias", self.brake_bias_front, &ranges.brake_bias_front)
+    }
+
+    /// The car config with this setup applied; changing the wings changes drag too
+    pub fn apply(&self, config: &CarConfig) -> CarConfig {
+        let mut tuned = config.clone();
+        tuned.final_drive_ratio = self.final_drive_ratio;
+        tuned.gear_ratios.truncate(1);
+        tuned.gear_ratios.extend(&self.gear_ratios);
+
+        let extra_wing = (config.lift_coefficient_front - self.lift_coefficient_front)
+            + (config.lift_coefficient_rear - self.lift_coefficient_rear);
+        tuned.drag_coefficient = (config.drag_coefficient + extra_wing * WING_DRAG_PER_LIFT).max(0.05);
+        tuned.lift_coefficient_front = self.lift_coefficient_front;
+        tuned.lift_coefficient_rear = self.lift_coefficient_rear;
+
+        tuned.tire_config.cold_pressure_front_kpa = self.tire_pressure_front_kpa;
+        tuned.tire_config.cold_pressure_rear_kpa = self.tire_pressure_rear_kpa;
+        tuned.brake_bias_front = self.brake_bias_front;
+        tuned
+    }
+}
+
 // --- Track Configuration (Static / Moddable) ---
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TrackConfig {
@@ -379,6 +864,9 @@ pub struct TrackConfig {
     /// Path to the source track file, relative to the content folder (e.g. "tracks/real/Austin.yaml")
     #[serde(default)]
     pub source_path: Option<String>,
+    /// SHA-256 of the source track file, empty for built-in tracks
+    #[serde(default)]
+    pub content_hash: String,
     pub start_positions: Vec<GridSlot>,
     pub track_surface: TrackSurface,
     pub pit_lane: Option<PitLaneConfig>,
@@ -391,6 +879,153 @@ pub struct TrackConfig {
     /// Optional procedural world data
     #[serde(default)]
     pub procedural_world: Option<crate::procgen::ProceduralWorldData>,
+    /// Barriers cars collide with: the generated track boundary plus any walls from the track file
+    #[serde(default)]
+    pub walls: Vec<WallSegment>,
+    /// Hand-tuned hints for the AI, from the optional `<track>.ai.yaml` next to the track file
+    #[serde(default)]
+    pub ai_hints: AiHints,
+    /// Sector splits and speed traps from the track file
+    #[serde(default)]
+    pub timing: TimingLines,
+}
+
+/// Timing lines of a track, as distances along the centerline from the
+/// start/finish line (m). The centerline starts at the start/finish line.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TimingLines {
+    /// Lines between sectors, in lap order; empty when the lap is one sector
+    #[serde(default)]
+    pub sector_splits_m: Vec<f32>,
+    #[serde(default)]
+    pub speed_traps: Vec<SpeedTrap>,
+    #[serde(default)]
+    pub drs_zones: Vec<DrsZone>,
+}
+
+impl TimingLines {
+    /// Sector, from 1, of a car `progress` m into the lap
+    pub fn sector_at(&self, progress: f32) -> u8 {
+        1 + self.sector_splits_m.iter().filter(|&&split| split <= progress).count() as u8
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeedTrap {
+    pub name: String,
+    pub distance_m: f32,
+}
+
+/// Stretch of the lap where DRS or a boost may be used; `end_m` is before
+/// `start_m` when the zone runs across the start/finish line
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DrsZone {
+    pub start_m: f32,
+    pub end_m: f32,
+}
+
+/// Runoff between the track edge and the generated boundary wall, unless the track file sets it
+pub const DEFAULT_RUNOFF_WIDTH_M: f32 = 15.0;
+
+/// Spacing of generated boundary wall points, much coarser than the interpolated centerline
+const BOUNDARY_WALL_SPACING_M: f32 = 5.0;
+
+/// Straight barrier section in the ground plane
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WallSegment {
+    pub x1: f32,
+    pub y1: f32,
+    pub x2: f32,
+    pub y2: f32,
+}
+
+impl WallSegment {
+    /// Walls along both edges of the track, `runoff_width_m` beyond the tarmac
+    ///
+    /// Wall points that would come closer to another part of the track than to
+    /// their own edge (inside tight hairpins, between neighbouring straights) are
+    /// dropped, so a generated wall never cuts across the circuit.
+    pub fn boundary(centerline: &[TrackPoint], runoff_width_m: f32, closed_loop: bool) -> Vec<WallSegment> {
+        // Every centerline point a wall point apart, plus the end of an open track
+        let mut points: Vec<&TrackPoint> = Vec::new();
+        for point in centerline {
+            if points
+                .last()
+                .is_none_or(|last| (point.x - last.x).hypot(point.y - last.y) >= BOUNDARY_WALL_SPACING_M)
+            {
+                points.push(point);
+            }
+        }
+        if let (Some(last), false) = (centerline.last(), closed_loop) {
+            if !std::ptr::eq(points[points.len() - 1], last) {
+                points.push(last);
+            }
+        }
+        if points.len() < 2 {
+            return Vec::new();
+        }
+
+        let offset = |p: &TrackPoint, left: bool| {
+            runoff_width_m + if left { p.width_left_m } else { p.width_right_m }
+        };
+
+        // Bucket centerline points so each clearance check only looks at neighbouring cells
+        let cell_size = points
+            .iter()
+            .map(|p| offset(p, true).max(offset(p, false)))
+            .fold(1.0, f32::max);
+        let cell = |x: f32, y: f32| ((x / cell_size).floor() as i32, (y / cell_size).floor() as i32);
+        let mut grid: HashMap<(i32, i32), Vec<&TrackPoint>> = HashMap::new();
+        for &point in &points {
+            grid.entry(cell(point.x, point.y)).or_default().push(point);
+        }
+        let is_clear = |x: f32, y: f32, clearance: f32| {
+            let (cx, cy) = cell(x, y);
+            (-1..=1).all(|dx| {
+                (-1..=1).all(|dy| {
+                    grid.get(&(cx + dx, cy + dy))
+                        .is_none_or(|points| points.iter().all(|p| (p.x - x).hypot(p.y - y) >= clearance * 0.9))
+                })
+            })
+        };
+
+        let mut walls = Vec::new();
+        for left in [true, false] {
+            let side = if left { 1.0 } else { -1.0 };
+            let vertices: Vec<Option<(f32, f32)>> = points
+                .iter()
+                .map(|p| {
+                    let distance = offset(p, left);
+                    let (sin, cos) = p.heading_rad.sin_cos();
+                    let x = p.x - sin * distance * side;
+                    let y = p.y + cos * distance * side;
+                    is_clear(x, y, distance).then_some((x, y))
+                })
+                .collect();
+
+            let pairs = vertices.windows(2).map(|pair| (pair[0], pair[1]));
+            let closing = closed_loop.then(|| (vertices[vertices.len() - 1], vertices[0]));
+            for (start, end) in pairs.chain(closing) {
+                if let (Some((x1, y1)), Some((x2, y2))) = (start, end) {
+                    if (x2 - x1).hypot(y2 - y1) > 1e-3 {
+                        walls.push(WallSegment { x1, y1, x2, y2 });
+                    }
+                }
+            }
+        }
+        walls
+    }
+
+    /// Segments joining consecutive `points`, back to the first if `closed`
+    pub fn polyline(points: &[(f32, f32)], closed: bool) -> Vec<WallSegment> {
+        let closing = (closed && points.len() > 2).then(|| [points[points.len() - 1], points[0]]);
+        points
+            .windows(2)
+            .map(|pair| [pair[0], pair[1]])
+            .chain(closing)
+            .map(|[(x1, y1), (x2, y2)]| WallSegment { x1, y1, x2, y2 })
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -398,6 +1033,70 @@ pub struct RacelinePoint {
     pub x: f32,
     pub y: f32,
     pub z: f32,
+    /// Speed the line is driven at here (m/s), filled in when the track loads
+    #[serde(default)]
+    pub target_speed_mps: f32,
+}
+
+/// Per-track AI tuning for corners the generic driving logic gets wrong.
+///
+/// Positions are distances along the centerline from the start line (m).
+/// Stretches with `end_m` before `start_m` wrap round the start line.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AiHints {
+    #[serde(default)]
+    pub braking_markers: Vec<BrakingMarker>,
+    /// Where AI drivers may pull off the line to pass; anywhere when empty
+    #[serde(default)]
+    pub overtaking_zones: Vec<TrackStretch>,
+    /// Stretches around the pit entry where AI drivers slow down and stay on the line
+    #[serde(default)]
+    pub pit_entry_caution: Vec<CautionZone>,
+}
+
+/// Speed an AI driver must be down to when it reaches a corner
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BrakingMarker {
+    pub distance_m: f32,
+    pub speed_mps: f32,
+    /// How far past the marker the speed is held, e.g. to the apex (m)
+    #[serde(default)]
+    pub hold_m: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TrackStretch {
+    pub start_m: f32,
+    pub end_m: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CautionZone {
+    pub start_m: f32,
+    pub end_m: f32,
+    pub speed_mps: f32,
+}
+
+impl TrackStretch {
+    /// Whether `distance_m` along a track of `track_length_m` falls in the stretch
+    pub fn contains(&self, distance_m: f32, track_length_m: f32) -> bool {
+        stretch_contains(self.start_m, self.end_m, distance_m, track_length_m)
+    }
+}
+
+impl CautionZone {
+    /// Whether `distance_m` along a track of `track_length_m` falls in the zone
+    pub fn contains(&self, distance_m: f32, track_length_m: f32) -> bool {
+        stretch_contains(self.start_m, self.end_m, distance_m, track_length_m)
+    }
+}
+
+fn stretch_contains(start_m: f32, end_m: f32, distance_m: f32, track_length_m: f32) -> bool {
+    if track_length_m <= 0.0 {
+        return false;
+    }
+    let length = (end_m - start_m).rem_euclid(track_length_m);
+    (distance_m - start_m).rem_euclid(track_length_m) <= length
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -408,10 +1107,14 @@ pub struct TrackMetadata {
     pub description: Option<String>,
     pub year_built: Option<u32>,
     pub category: Option<String>,  // e.g., "F1", "DTM", "IndyCar"
+    #[serde(default)]
+    pub corners: Option<u32>,      // Counted from the centerline when not given
+    #[serde(default)]
+    pub tags: Vec<String>,         // e.g., "oval", "street", "permanent", "historic"
 
     // Procedural generation parameters
     #[serde(default)]
-    pub environment_type: Option<String>,      // "desert" | "forest" | "city" | "mountains" | "plains"
+    pub environment_type: Option<String>,      // "desert" | "forest" | "alpine" | "coastal" | "city" | ...
     #[serde(default)]
     pub terrain_seed: Option<u32>,             // Deterministic seed (None = generate from track ID)
     #[serde(default)]
@@ -419,19 +1122,43 @@ pub struct TrackMetadata {
     #[serde(default)]
     pub terrain_detail: Option<f32>,           // Noise frequency multiplier (default: 0.5)
     #[serde(default)]
-    pub terrain_blend_width: Option<f32>,      // Track corridor blend meters (default: 20.0)
+    pub terrain_blend_width: Option<f32>,      // Blend meters past the shoulders (default: 20.0)
     #[serde(default)]
-    pub object_density: Option<f32>,           // 0-1 density multiplier (default: 0.8)
+    pub object_density: Option<f32>,           // 0-1 vegetation/object density (default: the biome's)
     #[serde(default)]
     pub decal_profile: Option<String>,         // Decal set identifier (default: "default")
+
+    // Real-world placement, for tracks imported from GPS or OSM
+    #[serde(default)]
+    pub geo_origin: Option<GeoOrigin>,         // Where x = 0, y = 0 lies on the globe
+    #[serde(default)]
+    pub terrain_dem: Option<String>,           // SRTM tile directory or GeoTIFF, relative to the track file
+}
+
+/// Latitude and longitude (WGS84 degrees) of a track's x = 0, y = 0; x
+/// points east and y north from there
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GeoOrigin {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+/// Fastest lap driven on a track in a race
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LapRecord {
+    pub lap_time_ms: u32,
+    pub driver_name: String,
+    pub car_name: String,
+    /// Unix time the lap was driven (s)
+    pub set_at: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TrackSurface {
     pub base_grip: f32,              // Base grip multiplier (1.0 = normal asphalt)
     pub curb_grip: f32,              // Grip on curbs
-    pub off_track_grip: f32,         // Grip off track (grass/gravel)
-    pub off_track_speed_penalty: f32, // Speed reduction factor off track
+    pub off_track_grip: f32,         // Grip on grass
+    pub off_track_speed_penalty: f32, // Fraction of speed lost per second on grass
 }
 
 impl Default for TrackSurface {
@@ -450,7 +1177,56 @@ pub struct PitLaneConfig {
     pub entry_point: TrackPoint,
     pub exit_point: TrackPoint,
     pub speed_limit_mps: f32,
+    /// Pit box for each grid slot; `position` is the slot
     pub pit_stalls: Vec<PitStall>,
+    /// Pit lane centerline from entry to exit. Its distances run along the
+    /// pit lane; empty when the track only gives entry and exit
+    #[serde(default)]
+    pub centerline: Vec<TrackPoint>,
+    /// Stretch of the pit lane the speed limit covers (m from the pit entry)
+    #[serde(default)]
+    pub speed_limit_start_m: f32,
+    #[serde(default)]
+    pub speed_limit_end_m: f32,
+}
+
+impl PitLaneConfig {
+    /// Distance along the pit lane of a position inside it
+    pub fn locate(&self, x: f32, y: f32) -> Option<f32> {
+        let nearest = self.centerline.iter().min_by(|a, b| {
+            let da = (a.x - x).powi(2) + (a.y - y).powi(2);
+            let db = (b.x - x).powi(2) + (b.y - y).powi(2);
+            da.total_cmp(&db)
+        })?;
+        let dx = x - nearest.x;
+        let dy = y - nearest.y;
+        // Lateral offset, positive to the left of the direction of travel
+        let lateral = -dx * nearest.heading_rad.sin() + dy * nearest.heading_rad.cos();
+        let half_width = if lateral >= 0.0 { nearest.width_left_m } else { nearest.width_right_m };
+        (lateral.abs() <= half_width).then_some(nearest.distance_from_start_m)
+    }
+
+    /// Centerline point nearest `distance` along the pit lane
+    pub fn point_at(&self, distance: f32) -> Option<&TrackPoint> {
+        self.centerline.iter().min_by(|a, b| {
+            (a.distance_from_start_m - distance).abs().total_cmp(&(b.distance_from_start_m - distance).abs())
+        })
+    }
+
+    /// Length of the pit lane centerline (m)
+    pub fn length_m(&self) -> f32 {
+        self.centerline.last().map_or(0.0, |p| p.distance_from_start_m)
+    }
+
+    /// Whether the speed limit applies `distance` along the pit lane
+    pub fn is_speed_limited(&self, distance: f32) -> bool {
+        (self.speed_limit_start_m..=self.speed_limit_end_m).contains(&distance)
+    }
+
+    /// Pit box of the car starting from `grid_position`
+    pub fn stall_for(&self, grid_position: u8) -> Option<&PitStall> {
+        self.pit_stalls.iter().find(|stall| stall.position == grid_position)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -459,6 +1235,9 @@ pub struct PitStall {
     pub x: f32,
     pub y: f32,
     pub z: f32,
+    /// Direction a car parks in
+    #[serde(default)]
+    pub yaw_rad: f32,
 }
 
 impl Default for TrackConfig {
@@ -511,6 +1290,7 @@ impl Default for TrackConfig {
                 heading_rad: heading,
                 surface_type: SurfaceType::Asphalt,
                 grip_modifier: 1.0,
+                runoff_surface: SurfaceType::Grass,
             });
         }
         
@@ -526,24 +1306,40 @@ impl Default for TrackConfig {
             });
         }
         
+        let walls = WallSegment::boundary(&centerline, DEFAULT_RUNOFF_WIDTH_M, true);
+
         Self {
-            id: Uuid::new_v4(),
+            id: TrackConfigId::new_v4(),
             name: "Default Oval".to_string(),
             centerline,
             width_m: 15.0,
             source_path: None,
+            content_hash: String::new(),
             start_positions,
             track_surface: TrackSurface::default(),
             pit_lane: None,
             raceline: Vec::new(),
             metadata: TrackMetadata::default(),
             procedural_world: None,
+            walls,
+            ai_hints: AiHints::default(),
+            timing: TimingLines::default(),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// How much of the simulation's harsher behavior is enabled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum RealismPreset {
+    Casual,      // Tires and brakes are always at working temperature
+    #[default]
+    Standard,    // Mild cold-start penalty that clears within an out lap
+    Simulation,  // Full cold-start penalty and slow warmup
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum SurfaceType {
+    #[default]
     Asphalt,
     Concrete,
     Curb,
@@ -553,12 +1349,6 @@ pub enum SurfaceType {
     Wet,
 }
 
-impl Default for SurfaceType {
-    fn default() -> Self {
-        SurfaceType::Asphalt
-    }
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TrackPoint {
     pub x: f32,
@@ -573,6 +1363,12 @@ pub struct TrackPoint {
     pub heading_rad: f32,            // Track direction at this point
     pub surface_type: SurfaceType,
     pub grip_modifier: f32,          // Local grip adjustment (1.0 = normal)
+    #[serde(default = "default_runoff_surface")]
+    pub runoff_surface: SurfaceType, // What a car running wide here lands on
+}
+
+fn default_runoff_surface() -> SurfaceType {
+    SurfaceType::Grass
 }
 
 impl Default for TrackPoint {
@@ -590,6 +1386,7 @@ impl Default for TrackPoint {
             heading_rad: 0.0,
             surface_type: SurfaceType::Asphalt,
             grip_modifier: 1.0,
+            runoff_surface: SurfaceType::Grass,
         }
     }
 }
@@ -621,6 +1418,42 @@ pub struct TireTelemetry {
     pub rear_right: TireData,
 }
 
+impl TireTelemetry {
+    /// All four tires at the same temperature
+    pub fn uniform(temperature_c: f32) -> Self {
+        let tire = TireData {
+            temperature_c,
+            ..Default::default()
+        };
+        Self {
+            front_left: tire,
+            front_right: tire,
+            rear_left: tire,
+            rear_right: tire,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct BrakeTelemetry {
+    pub front_left_temp_c: f32,
+    pub front_right_temp_c: f32,
+    pub rear_left_temp_c: f32,
+    pub rear_right_temp_c: f32,
+}
+
+impl BrakeTelemetry {
+    /// All four brake discs at the same temperature
+    pub fn uniform(temperature_c: f32) -> Self {
+        Self {
+            front_left_temp_c: temperature_c,
+            front_right_temp_c: temperature_c,
+            rear_left_temp_c: temperature_c,
+            rear_right_temp_c: temperature_c,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
 pub struct GForces {
     pub lateral_g: f32,      // Side-to-side (left negative, right positive)
@@ -636,6 +1469,85 @@ pub struct SuspensionTelemetry {
     pub rear_right_travel_m: f32,
 }
 
+/// Sprung body motion on the suspension, relative to static equilibrium
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct ChassisMotion {
+    pub heave_m: f32,         // Body drop (compression positive)
+    pub pitch_rad: f32,       // Nose down positive
+    pub roll_rad: f32,        // Right side down positive
+    pub heave_rate_mps: f32,
+    pub pitch_rate_rps: f32,
+    pub roll_rate_rps: f32,
+}
+
+/// Body orientation as a unit quaternion rotating the car's frame (x forward,
+/// y left, z up) into the world frame
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Orientation {
+    pub w: f32,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl Default for Orientation {
+    fn default() -> Self {
+        Self { w: 1.0, x: 0.0, y: 0.0, z: 0.0 }
+    }
+}
+
+impl Orientation {
+    /// From heading, nose-up pitch and left-side-down roll, as kept on `CarState`
+    pub fn from_euler(yaw_rad: f32, pitch_rad: f32, roll_rad: f32) -> Self {
+        let (sy, cy) = (yaw_rad / 2.0).sin_cos();
+        // Nose up and left side down are negative turns about the car's y and x axes
+        let (sp, cp) = (-pitch_rad / 2.0).sin_cos();
+        let (sr, cr) = (-roll_rad / 2.0).sin_cos();
+        Self {
+            w: cy * cp * cr + sy * sp * sr,
+            x: cy * cp * sr - sy * sp * cr,
+            y: cy * sp * cr + sy * cp * sr,
+            z: sy * cp * cr - cy * sp * sr,
+        }
+    }
+
+    /// Heading, nose-up pitch and left-side-down roll
+    pub fn to_euler(&self) -> (f32, f32, f32) {
+        let Self { w, x, y, z } = *self;
+        let yaw = (2.0 * (w * z + x * y)).atan2(1.0 - 2.0 * (y * y + z * z));
+        let pitch = (2.0 * (w * y - z * x)).clamp(-1.0, 1.0).asin();
+        let roll = (2.0 * (w * x + y * z)).atan2(1.0 - 2.0 * (x * x + y * y));
+        (yaw, -pitch, -roll)
+    }
+
+    /// Turn by body-frame angular rates (rad/s about the car's x, y and z axes) for `dt` seconds
+    pub fn integrate(&self, (rate_x, rate_y, rate_z): (f32, f32, f32), dt: f32) -> Self {
+        let Self { w, x, y, z } = *self;
+        let half_dt = dt / 2.0;
+        let turned = Self {
+            w: w - half_dt * (x * rate_x + y * rate_y + z * rate_z),
+            x: x + half_dt * (w * rate_x + y * rate_z - z * rate_y),
+            y: y + half_dt * (w * rate_y + z * rate_x - x * rate_z),
+            z: z + half_dt * (w * rate_z + x * rate_y - y * rate_x),
+        };
+        turned.normalized()
+    }
+
+    /// World-frame direction of the car's roof
+    pub fn up(&self) -> (f32, f32, f32) {
+        let Self { w, x, y, z } = *self;
+        (2.0 * (x * z + w * y), 2.0 * (y * z - w * x), 1.0 - 2.0 * (x * x + y * y))
+    }
+
+    fn normalized(self) -> Self {
+        let norm = (self.w * self.w + self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
+        if norm < f32::EPSILON {
+            return Self::default();
+        }
+        Self { w: self.w / norm, x: self.x / norm, y: self.y / norm, z: self.z / norm }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
 pub struct DamageState {
     pub front_damage_percent: f32,
@@ -652,16 +1564,21 @@ pub struct CarState {
     pub player_id: PlayerId,
     pub car_config_id: CarConfigId,
     pub grid_position: u8,
+    /// Index into the car's `liveries`
+    #[serde(default)]
+    pub livery: u8,
     
     // 3D Position
     pub pos_x: f32,
     pub pos_y: f32,
     pub pos_z: f32,                   // Elevation
     
-    // 3D Orientation (Euler angles)
+    // 3D Orientation (Euler angles, kept in step with `orientation`)
     pub yaw_rad: f32,                 // Heading (rotation around Z axis)
-    pub pitch_rad: f32,               // Nose up/down (rotation around Y axis)
-    pub roll_rad: f32,                // Body roll (rotation around X axis)
+    pub pitch_rad: f32,               // Nose up positive
+    pub roll_rad: f32,                // Left side down positive
+    #[serde(default)]
+    pub orientation: Orientation,
     
     // 3D Velocity
     pub vel_x: f32,
@@ -671,8 +1588,16 @@ pub struct CarState {
     
     // Angular velocities
     pub angular_vel_yaw: f32,         // Yaw rate (rad/s)
-    pub angular_vel_pitch: f32,       // Pitch rate (rad/s)
-    pub angular_vel_roll: f32,        // Roll rate (rad/s)
+    pub angular_vel_pitch: f32,       // Pitch rate (rad/s, nose up positive)
+    pub angular_vel_roll: f32,        // Roll rate (rad/s, left side down positive)
+
+    // Rigid body contact
+    #[serde(default)]
+    pub tip_rad: f32,                 // Roll onto one side's wheels, left side down positive; 0 with all four down
+    #[serde(default)]
+    pub is_airborne: bool,
+    #[serde(default)]
+    pub is_overturned: bool,          // Rolled onto its side; slides to a stop and is out
     
     // Inputs
     pub throttle_input: f32,
@@ -680,6 +1605,8 @@ pub struct CarState {
     pub steering_input: f32,
     pub gear: i8,                     // Current gear (-1 = reverse, 0 = neutral, 1-6+)
     pub clutch_input: f32,            // Clutch engagement (0 = disengaged, 1 = engaged)
+    #[serde(default)]
+    pub shift_timer_s: f32,           // Time left in a gear change; no drive until it ends
     
     // Track position
     pub track_progress: f32,
@@ -696,6 +1623,8 @@ pub struct CarState {
     pub collision_normal_x: f32,
     pub collision_normal_y: f32,
     pub collision_normal_z: f32,
+    #[serde(default)]
+    pub is_ghost: bool,               // Passes through other cars
     
     // Surface state
     pub current_surface: SurfaceType,
@@ -706,11 +1635,20 @@ pub struct CarState {
     pub tires: TireTelemetry,
     pub g_forces: GForces,
     pub suspension: SuspensionTelemetry,
+    #[serde(default)]
+    pub chassis: ChassisMotion,
     pub fuel_liters: f32,
     pub fuel_capacity_liters: f32,
     pub fuel_consumption_lps: f32,
     pub damage: DamageState,
+    pub brakes: BrakeTelemetry,
+    pub tire_grip_factor: f32,        // Grip multiplier from tire temperature (1.0 = in window)
+    pub brake_efficiency: f32,        // Stopping power multiplier from disc temperature
     pub engine_rpm: f32,
+    #[serde(default)]
+    pub engine_stalled: bool,         // Stalled until the clutch is pressed or neutral selected
+    #[serde(default)]
+    pub turbo_boost: f32,             // Turbo boost built up (0 = none, 1 = full)
     pub engine_temp_c: f32,
     pub oil_temp_c: f32,
     pub oil_pressure_kpa: f32,
@@ -726,6 +1664,21 @@ pub struct CarState {
     pub downforce_front_n: f32,
     pub downforce_rear_n: f32,
     pub drag_force_n: f32,
+    /// Slipstream strength from the car ahead (0 = clean air, 1 = full tow)
+    #[serde(default)]
+    pub draft_factor: f32,
+    /// Car whose wake this car is in
+    #[serde(default)]
+    pub drafting_behind: Option<PlayerId>,
+    /// Standing water on the track from the session weather (0 = dry, 1 = soaked)
+    #[serde(default)]
+    pub track_wetness: f32,
+    /// Driver aids the car ran with on the last tick
+    #[serde(default)]
+    pub aids: DriverAids,
+    /// Aids that cut the driver's throttle or brake on the last tick
+    #[serde(default)]
+    pub aids_active: ActiveAids,
 }
 
 impl CarState {
@@ -734,6 +1687,7 @@ impl CarState {
             player_id,
             car_config_id,
             grid_position: grid_slot.position,
+            livery: 0,
             
             // 3D Position
             pos_x: grid_slot.x,
@@ -744,6 +1698,7 @@ impl CarState {
             yaw_rad: grid_slot.yaw_rad,
             pitch_rad: 0.0,
             roll_rad: 0.0,
+            orientation: Orientation::from_euler(grid_slot.yaw_rad, 0.0, 0.0),
             
             // Velocity
             vel_x: 0.0,
@@ -755,6 +1710,9 @@ impl CarState {
             angular_vel_yaw: 0.0,
             angular_vel_pitch: 0.0,
             angular_vel_roll: 0.0,
+            tip_rad: 0.0,
+            is_airborne: false,
+            is_overturned: false,
             
             // Inputs
             throttle_input: 0.0,
@@ -762,6 +1720,7 @@ impl CarState {
             steering_input: 0.0,
             gear: 1,
             clutch_input: 1.0,
+            shift_timer_s: 0.0,
             
             // Track position
             track_progress: 0.0,
@@ -778,16 +1737,18 @@ impl CarState {
             collision_normal_x: 0.0,
             collision_normal_y: 0.0,
             collision_normal_z: 0.0,
+            is_ghost: false,
             
             // Surface
             current_surface: SurfaceType::Asphalt,
             is_on_track: true,
             surface_grip_modifier: 1.0,
             
-            // Telemetry
-            tires: TireTelemetry::default(),
+            // Telemetry (tires and brakes start warm; sessions apply cold starts)
+            tires: TireTelemetry::uniform(90.0),
             g_forces: GForces::default(),
             suspension: SuspensionTelemetry::default(),
+            chassis: ChassisMotion::default(),
             fuel_liters: 100.0,
             fuel_capacity_liters: 100.0,
             fuel_consumption_lps: 0.0,
@@ -795,7 +1756,12 @@ impl CarState {
                 is_drivable: true,
                 ..Default::default()
             },
+            brakes: BrakeTelemetry::uniform(300.0),
+            tire_grip_factor: 1.0,
+            brake_efficiency: 1.0,
             engine_rpm: 900.0,
+            engine_stalled: false,
+            turbo_boost: 0.0,
             engine_temp_c: 85.0,
             oil_temp_c: 90.0,
             oil_pressure_kpa: 350.0,
@@ -811,6 +1777,11 @@ impl CarState {
             downforce_front_n: 0.0,
             downforce_rear_n: 0.0,
             drag_force_n: 0.0,
+            draft_factor: 0.0,
+            drafting_behind: None,
+            track_wetness: 0.0,
+            aids: DriverAids::default(),
+            aids_active: ActiveAids::default(),
         }
     }
 }
@@ -823,27 +1794,200 @@ pub enum SessionState {
     Countdown = 1,
     Racing = 2,
     Finished = 3,
+    /// Listed in the lobby while its track and cars are loaded; not yet joinable
+    Preparing = 4,
+}
+
+/// Where a car is relative to the pit lane, as shown on the leaderboard
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+pub enum PitStatus {
+    OnTrack = 0,
+    InPitLane = 1,
+    /// Stopped in a pit stall
+    InPitBox = 2,
 }
 
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+pub enum RaceEventKind {
+    LapCompleted = 0,
+    Overtake = 1,
+    OffTrack = 2,
+    Penalty = 3,
+    PitEntry = 4,
+    PitExit = 5,
+    Finished = 6,
+    /// Missed the grid box after the formation lap
+    PitLaneStart = 7,
+    /// Hit a wall, or another car when `other_player_id` is set
+    Contact = 8,
+}
+
+/// What makes part of a replay a highlight
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+pub enum HighlightKind {
+    Overtake = 0,
+    Contact = 1,
+    /// The leader's last lap up to the winner crossing the line
+    FinalLap = 2,
+}
+
+/// What a `TimelineEntry` of a race's results records
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+pub enum TimelineEventKind {
+    RaceStart = 0,
+    /// A car took the lead
+    LeadChange = 1,
+    PitEntry = 2,
+    PitExit = 3,
+    /// Missed the grid box after the formation lap
+    PitLaneStart = 4,
+    Penalty = 5,
+    OffTrack = 6,
+    Finished = 7,
+    /// Hit a wall, or another car when `other_player_id` is set
+    Contact = 8,
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr, Default)]
 pub enum SessionKind {
+    #[default]
     Multiplayer = 0,
     Practice = 1,
     Sandbox = 2,
 }
 
-impl Default for SessionKind {
-    fn default() -> Self {
-        SessionKind::Multiplayer
+/// Whether players may change their car setup in a session
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr, Default)]
+pub enum SetupPolicy {
+    #[default]
+    Open = 0,
+    /// Every car runs its baseline setup
+    Fixed = 1,
+}
+
+/// Whether cars in a session's free practice touch each other
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr, Default)]
+pub enum CollisionPolicy {
+    #[default]
+    Solid = 0,
+    /// In free practice every car is a ghost: cars pass through each other but still hit walls
+    Ghost = 1,
+}
+
+/// How strongly a driver aid intervenes
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize_repr, Deserialize_repr, Default)]
+pub enum AidLevel {
+    #[default]
+    Off = 0,
+    /// Steps in only well past the limit of grip
+    Low = 1,
+    /// Holds the car close to the limit of grip
+    High = 2,
+}
+
+/// Driver aid levels, chosen by a player or allowed at most by a session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct DriverAids {
+    pub abs: AidLevel,
+    pub traction_control: AidLevel,
+    pub stability_control: AidLevel,
+}
+
+impl DriverAids {
+    /// Every aid at its highest level, for sessions that allow anything
+    pub fn unrestricted() -> Self {
+        Self {
+            abs: AidLevel::High,
+            traction_control: AidLevel::High,
+            stability_control: AidLevel::High,
+        }
+    }
+
+    /// Whether every aid is at or below the level `limits` allows
+    pub fn within(&self, limits: &DriverAids) -> bool {
+        self.abs <= limits.abs
+            && self.traction_control <= limits.traction_control
+            && self.stability_control <= limits.stability_control
+    }
+
+    /// These aids lowered to what `limits` allows
+    pub fn capped(&self, limits: &DriverAids) -> Self {
+        Self {
+            abs: self.abs.min(limits.abs),
+            traction_control: self.traction_control.min(limits.traction_control),
+            stability_control: self.stability_control.min(limits.stability_control),
+        }
     }
 }
 
+/// Which driver aids intervened on a tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct ActiveAids {
+    pub abs: bool,
+    pub traction_control: bool,
+    pub stability_control: bool,
+}
+
+/// Sky condition driving how wet the track gets
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr, Default)]
+pub enum WeatherCondition {
+    #[default]
+    Dry = 0,
+    /// Light drizzle, the track stays damp
+    Damp = 1,
+    /// Rain, the track soaks through
+    Wet = 2,
+}
+
+/// Forecast entry: switch to `condition` this long after the session starts
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WeatherChange {
+    pub after_s: u32,
+    pub condition: WeatherCondition,
+}
+
+/// Weather chosen when creating a session
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WeatherSettings {
+    pub condition: WeatherCondition,
+    /// Condition changes over the session, in any order
+    #[serde(default)]
+    pub forecast: Vec<WeatherChange>,
+}
+
+/// Time of day a session is set at, chosen when creating it
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize_repr, Deserialize_repr, Default)]
+pub enum TimeOfDay {
+    Morning = 0,
+    #[default]
+    Noon = 1,
+    Afternoon = 2,
+    /// Sun on the horizon
+    Dusk = 3,
+    /// Moonlight; the track's own lights do the rest
+    Night = 4,
+}
+
+impl TimeOfDay {
+    pub const ALL: [TimeOfDay; 5] = [Self::Morning, Self::Noon, Self::Afternoon, Self::Dusk, Self::Night];
+}
+
 /// Game modes determine the behavior and rules during a session
 #[repr(u8)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr, Default)]
 pub enum GameMode {
     /// Lobby state, no telemetry sent, players selecting cars
+    #[default]
     Lobby = 0,
     /// Nothing moves, no telemetry, camera exploration only
     Sandbox = 1,
@@ -859,12 +2003,10 @@ pub enum GameMode {
     Qualification = 6,
     /// Race mode (to be implemented)
     Race = 7,
-}
-
-impl Default for GameMode {
-    fn default() -> Self {
-        GameMode::Lobby
-    }
+    /// Formation lap behind the pace car, goes green when the leader crosses the line
+    RollingStart = 8,
+    /// Controlled lap back to the grid boxes before a standing start
+    FormationLap = 9,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -891,6 +2033,8 @@ pub struct RaceSession {
     pub ai_player_ids: Vec<PlayerId>,
     /// Demo lap state (used in DemoLap mode)
     pub demo_lap_progress: Option<f32>,
+    #[serde(default)]
+    pub time_of_day: TimeOfDay,
 }
 
 impl RaceSession {
@@ -903,7 +2047,7 @@ impl RaceSession {
         lap_limit: u8,
     ) -> Self {
         Self {
-            id: Uuid::new_v4(),
+            id: SessionId::new_v4(),
             track_config_id,
             host_player_id,
             session_kind,
@@ -919,6 +2063,7 @@ impl RaceSession {
             ai_player_ids: Vec::new(),
             demo_lap_progress: None,
             host_car_id: None,
+            time_of_day: TimeOfDay::default(),
         }
     }
 }
@@ -929,8 +2074,8 @@ pub struct PlayerInputData {
     pub throttle: f32,
     pub brake: f32,
     pub steering: f32,
-    pub gear: Option<i8>,      // Desired gear (-1 = reverse, 0 = neutral, 1-6+)
-    pub clutch: Option<f32>,   // Clutch input (0.0-1.0)
+    pub gear: Option<i8>,      // Desired gear (-1 = reverse, 0 = neutral, 1-6+); None shifts automatically
+    pub clutch: Option<f32>,   // Clutch engagement (0.0-1.0); None works the clutch automatically
 }
 
 impl PlayerInputData {
@@ -938,6 +2083,7 @@ impl PlayerInputData {
         self.throttle = self.throttle.clamp(0.0, 1.0);
         self.brake = self.brake.clamp(0.0, 1.0);
         self.steering = self.steering.clamp(-1.0, 1.0);
+        self.clutch = self.clutch.map(|clutch| clutch.clamp(0.0, 1.0));
     }
 }
 
@@ -948,7 +2094,7 @@ mod tests {
     #[test]
     fn test_player_creation() {
         let player = Player {
-            id: Uuid::new_v4(),
+            id: PlayerId::new_v4(),
             name: "TestPlayer".to_string(),
             connection_id: Uuid::new_v4(),
             selected_car_config_id: None,
@@ -959,6 +2105,17 @@ mod tests {
         assert!(!player.is_ai);
     }
 
+    #[test]
+    fn test_typed_id_serializes_like_uuid() {
+        let raw = Uuid::new_v4();
+        let id = PlayerId::from(raw);
+
+        assert_eq!(serde_json::to_string(&id).unwrap(), serde_json::to_string(&raw).unwrap());
+        assert_eq!(rmp_serde::to_vec(&id).unwrap(), rmp_serde::to_vec(&raw).unwrap());
+        assert_eq!(id.to_string().parse::<PlayerId>().unwrap(), id);
+        assert!(SessionId::nil().is_nil());
+    }
+
     #[test]
     fn test_car_config_default() {
         let car = CarConfig::default();
@@ -966,6 +2123,69 @@ mod tests {
         assert!(car.mass_kg > 0.0);
     }
 
+    #[test]
+    fn test_car_setup_must_stay_within_ranges() {
+        let car = CarConfig::default();
+        let baseline = CarSetup::baseline(&car);
+        assert_eq!(baseline.gear_ratios.len(), 6);
+        assert!(baseline.validate(&car).is_ok());
+
+        let short = CarSetup { final_drive_ratio: 4.5, ..baseline.clone() };
+        assert!(short.validate(&car).is_ok());
+        assert_eq!(short.apply(&car).final_drive_ratio, 4.5);
+
+        assert!(CarSetup { final_drive_ratio: 9.0, ..baseline.clone() }.validate(&car).is_err());
+        assert!(CarSetup { brake_bias_front: 0.95, ..baseline.clone() }.validate(&car).is_err());
+        assert!(CarSetup { gear_ratios: vec![3.8, 2.4], ..baseline.clone() }.validate(&car).is_err(), "Wrong gear count");
+        assert!(
+            CarSetup { gear_ratios: vec![3.8, 2.4, 2.6, 1.3, 1.0, 0.8], ..baseline.clone() }.validate(&car).is_err(),
+            "Third gear shorter than second"
+        );
+
+        // More wing buys downforce at the cost of drag
+        let high_downforce = CarSetup { lift_coefficient_rear: -0.6, ..baseline };
+        let tuned = high_downforce.apply(&car);
+        assert_eq!(tuned.lift_coefficient_rear, -0.6);
+        assert!(tuned.drag_coefficient > car.drag_coefficient);
+        assert_eq!(tuned.gear_ratios, car.gear_ratios);
+    }
+
+    #[test]
+    fn test_orientation_round_trips_euler_angles() {
+        let orientation = Orientation::from_euler(2.0, 0.3, -0.4);
+        let (yaw, pitch, roll) = orientation.to_euler();
+        assert!((yaw - 2.0).abs() < 1e-4 && (pitch - 0.3).abs() < 1e-4 && (roll + 0.4).abs() < 1e-4);
+
+        // Nose up points the roof backwards; left side down tips it to the left
+        let (up_x, _, _) = Orientation::from_euler(0.0, 0.3, 0.0).up();
+        assert!(up_x < -0.2);
+        let (_, up_y, _) = Orientation::from_euler(0.0, 0.0, 0.3).up();
+        assert!(up_y > 0.2);
+
+        // A second at a yaw rate of 1 rad/s turns the heading by a radian
+        let mut turning = Orientation::default();
+        for _ in 0..100 {
+            turning = turning.integrate((0.0, 0.0, 1.0), 0.01);
+        }
+        assert!((turning.to_euler().0 - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_synthesized_torque_curve_is_power_limited() {
+        let curve = EngineConfig::synthesized_torque_curve(450.0, 300000.0, 900.0, 7500.0, 8000.0);
+        let engine = EngineConfig { torque_curve: curve, ..EngineConfig::default() };
+
+        assert_eq!(engine.torque_curve.first().unwrap().rpm, 900.0);
+        assert_eq!(engine.torque_curve.last().unwrap().rpm, 8000.0);
+        assert!(engine.peak_torque_nm() <= 450.0);
+        assert!(engine.peak_torque_nm() > 400.0);
+        assert!(engine.peak_power_w() <= 300000.0 * 1.001);
+
+        // Torque falls away at the top end once power is the limit
+        let top = engine.torque_curve.last().unwrap();
+        assert!(top.torque_nm < engine.peak_torque_nm());
+    }
+
     #[test]
     fn test_track_config_default() {
         let track = TrackConfig::default();
@@ -974,10 +2194,22 @@ mod tests {
         assert!(!track.start_positions.is_empty());
     }
 
+    #[test]
+    fn test_track_stretch_wraps_round_the_start_line() {
+        let stretch = TrackStretch { start_m: 100.0, end_m: 200.0 };
+        assert!(stretch.contains(150.0, 1000.0));
+        assert!(!stretch.contains(250.0, 1000.0));
+
+        let wrapping = TrackStretch { start_m: 900.0, end_m: 50.0 };
+        assert!(wrapping.contains(950.0, 1000.0));
+        assert!(wrapping.contains(20.0, 1000.0));
+        assert!(!wrapping.contains(500.0, 1000.0));
+    }
+
     #[test]
     fn test_race_session_creation() {
-        let host_id = Uuid::new_v4();
-        let track_id = Uuid::new_v4();
+        let host_id = PlayerId::new_v4();
+        let track_id = TrackConfigId::new_v4();
         let session = RaceSession::new(host_id, track_id, SessionKind::Multiplayer, 8, 2, 5);
         
         assert_eq!(session.host_player_id, host_id);
@@ -1007,8 +2239,8 @@ mod tests {
 
     #[test]
     fn test_car_state_creation() {
-        let player_id = Uuid::new_v4();
-        let car_id = Uuid::new_v4();
+        let player_id = PlayerId::new_v4();
+        let car_id = CarConfigId::new_v4();
         let grid_slot = GridSlot {
             position: 1,
             x: 0.0,
@@ -0,0 +1,333 @@
+//! Formation lap before a standing start.
+//!
+//! Drivers complete one lap at a capped speed and stop in their grid box.
+//! A car is parked once it has covered most of the lap and comes to rest
+//! inside its box; it is then held in place until the rest of the field
+//! arrives. Cars still out when the time limit expires start from the pit
+//! lane.
+
+use crate::data::*;
+use crate::physics::TickTiming;
+use crate::rolling_start::{cap_speed, hold_lap, track_length, wrap_distance};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Speed cap during the formation lap (m/s, ~110 km/h)
+pub const FORMATION_SPEED_MPS: f32 = 30.0;
+
+/// Default time allowed to get round and back into the grid boxes
+pub const DEFAULT_TIME_LIMIT_S: u16 = 180;
+
+/// How close to the grid slot a car must stop to count as in its box
+const BOX_RADIUS_M: f32 = 3.0;
+
+/// Speed below which a car in its box is considered stopped
+const STOPPED_SPEED_MPS: f32 = 1.0;
+
+/// Fraction of the lap a car must cover before it can park
+const MIN_LAP_FRACTION: f32 = 0.5;
+
+/// Gap between cars queued at the pit exit
+const PIT_QUEUE_SPACING_M: f32 = 8.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FormationCar {
+    slot: Option<GridSlot>,
+    travelled_m: f32,
+    last_progress: f32,
+    parked: bool,
+}
+
+/// State of an in-progress formation lap
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormationLap {
+    cars: HashMap<PlayerId, FormationCar>,
+    track_length_m: f32,
+    ticks_remaining: u32,
+    timing: TickTiming,
+}
+
+impl FormationLap {
+    pub fn new(session: &RaceSession, track: &TrackConfig, time_limit_s: u16, timing: TickTiming) -> Self {
+        let cars = session
+            .participants
+            .values()
+            .map(|state| {
+                let slot = track
+                    .start_positions
+                    .iter()
+                    .find(|s| s.position == state.grid_position)
+                    .cloned();
+                (
+                    state.player_id,
+                    FormationCar {
+                        slot,
+                        travelled_m: 0.0,
+                        last_progress: state.track_progress,
+                        parked: false,
+                    },
+                )
+            })
+            .collect();
+
+        Self {
+            cars,
+            track_length_m: track_length(track),
+            ticks_remaining: timing.ticks(time_limit_s as u32),
+            timing,
+        }
+    }
+
+    pub fn time_remaining_ms(&self) -> u32 {
+        self.timing.ticks_to_ms(self.ticks_remaining)
+    }
+
+    /// Whether the car has parked in its grid box
+    pub fn is_parked(&self, player_id: &PlayerId) -> bool {
+        self.cars.get(player_id).is_some_and(|c| c.parked)
+    }
+
+    /// Every car has parked or the time limit has expired
+    pub fn is_finished(&self) -> bool {
+        self.ticks_remaining == 0 || self.cars.values().all(|c| c.parked)
+    }
+
+    /// Advance the formation lap after the cars have moved for this tick.
+    ///
+    /// Caps speeds, holds lap counting and parks cars that stop in their box.
+    pub fn update(&mut self, session: &mut RaceSession) {
+        self.ticks_remaining = self.ticks_remaining.saturating_sub(1);
+        self.cars.retain(|id, _| session.participants.contains_key(id));
+
+        for (id, car) in self.cars.iter_mut() {
+            let Some(state) = session.participants.get_mut(id) else {
+                continue;
+            };
+
+            hold_lap(state);
+
+            if car.parked {
+                if let Some(slot) = &car.slot {
+                    park(state, slot);
+                }
+                continue;
+            }
+
+            cap_speed(state, FORMATION_SPEED_MPS);
+            car.travelled_m += wrap_distance(state.track_progress - car.last_progress, self.track_length_m);
+            car.last_progress = state.track_progress;
+
+            let Some(slot) = &car.slot else {
+                continue;
+            };
+            let distance_to_slot = ((state.pos_x - slot.x).powi(2) + (state.pos_y - slot.y).powi(2)).sqrt();
+            if car.travelled_m >= self.track_length_m * MIN_LAP_FRACTION
+                && distance_to_slot <= BOX_RADIUS_M
+                && state.speed_mps < STOPPED_SPEED_MPS
+            {
+                car.parked = true;
+                park(state, slot);
+            }
+        }
+    }
+
+    /// Settle the field for the start: parked cars stay in their grid boxes and
+    /// everyone else is moved to their pit box, or queued at the pit exit when
+    /// the track has none for them. Returns the pit lane starters.
+    pub fn finish(&self, session: &mut RaceSession, track: &TrackConfig) -> Vec<PlayerId> {
+        let mut pit_lane_starters: Vec<PlayerId> = self
+            .cars
+            .iter()
+            .filter(|(_, car)| !car.parked)
+            .map(|(id, _)| *id)
+            .collect();
+        pit_lane_starters.sort_by_key(|id| session.participants.get(id).map(|s| s.grid_position));
+
+        let mut queue_idx = 0;
+        for id in &pit_lane_starters {
+            let Some(state) = session.participants.get_mut(id) else {
+                continue;
+            };
+            let pit_box = track.pit_lane.as_ref().and_then(|pit_lane| pit_lane.stall_for(state.grid_position));
+            let slot = match pit_box {
+                Some(stall) => GridSlot { position: 0, x: stall.x, y: stall.y, z: stall.z, yaw_rad: stall.yaw_rad },
+                None => {
+                    queue_idx += 1;
+                    pit_lane_slot(track, queue_idx - 1)
+                }
+            };
+            park(state, &slot);
+            state.track_progress = slot_progress(track, &slot);
+        }
+
+        pit_lane_starters
+    }
+}
+
+/// Hold a car stationary on a grid slot
+pub(crate) fn park(state: &mut CarState, slot: &GridSlot) {
+    state.pos_x = slot.x;
+    state.pos_y = slot.y;
+    state.pos_z = slot.z;
+    state.yaw_rad = slot.yaw_rad;
+    state.vel_x = 0.0;
+    state.vel_y = 0.0;
+    state.vel_z = 0.0;
+    state.speed_mps = 0.0;
+    state.angular_vel_yaw = 0.0;
+    state.angular_vel_pitch = 0.0;
+    state.angular_vel_roll = 0.0;
+    state.tip_rad = 0.0;
+    state.is_airborne = false;
+    state.orientation = Orientation::from_euler(slot.yaw_rad, 0.0, 0.0);
+}
+
+/// Queue position at the pit exit, or behind the grid if the track has no pit lane
+fn pit_lane_slot(track: &TrackConfig, queue_idx: usize) -> GridSlot {
+    let (x, y, z, yaw_rad) = match &track.pit_lane {
+        Some(pit_lane) => {
+            let exit = &pit_lane.exit_point;
+            (exit.x, exit.y, exit.z, exit.heading_rad)
+        }
+        None => {
+            let last = track.start_positions.iter().max_by_key(|s| s.position);
+            let last = last.cloned().unwrap_or(GridSlot { position: 0, x: 0.0, y: 0.0, z: 0.0, yaw_rad: 0.0 });
+            (
+                last.x - PIT_QUEUE_SPACING_M * last.yaw_rad.cos(),
+                last.y - PIT_QUEUE_SPACING_M * last.yaw_rad.sin(),
+                last.z,
+                last.yaw_rad,
+            )
+        }
+    };
+
+    let back = PIT_QUEUE_SPACING_M * queue_idx as f32;
+    GridSlot {
+        position: 0,
+        x: x - back * yaw_rad.cos(),
+        y: y - back * yaw_rad.sin(),
+        z,
+        yaw_rad,
+    }
+}
+
+/// Distance along the track of the centerline point nearest a slot
+fn slot_progress(track: &TrackConfig, slot: &GridSlot) -> f32 {
+    track
+        .centerline
+        .iter()
+        .min_by(|a, b| {
+            let da = (a.x - slot.x).powi(2) + (a.y - slot.y).powi(2);
+            let db = (b.x - slot.x).powi(2) + (b.y - slot.y).powi(2);
+            da.total_cmp(&db)
+        })
+        .map(|p| p.distance_from_start_m)
+        .unwrap_or(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_session() -> (RaceSession, TrackConfig, PlayerId, PlayerId) {
+        let track = TrackConfig::default();
+        let mut session = RaceSession::new(PlayerId::new_v4(), track.id, SessionKind::Multiplayer, 8, 0, 3);
+
+        let first = PlayerId::new_v4();
+        let second = PlayerId::new_v4();
+        for (id, slot) in [(first, 0), (second, 1)] {
+            session
+                .participants
+                .insert(id, CarState::new(id, CarConfigId::new_v4(), &track.start_positions[slot]));
+        }
+
+        (session, track, first, second)
+    }
+
+    #[test]
+    fn test_car_parks_after_lap_in_its_box() {
+        let (mut session, track, first, _) = create_session();
+        let mut formation = FormationLap::new(&session, &track, DEFAULT_TIME_LIMIT_S, TickTiming::default());
+
+        // Stopping in the box before covering the lap does not count
+        formation.update(&mut session);
+        assert!(!formation.is_parked(&first));
+
+        // Drive round the lap in steps and come back to the box
+        let length = track_length(&track);
+        let start_progress = session.participants[&first].track_progress;
+        let mut covered = 0.0;
+        while covered < length {
+            covered += 10.0;
+            let state = session.participants.get_mut(&first).unwrap();
+            state.track_progress = (start_progress + covered).rem_euclid(length);
+            state.pos_x += 5.0;
+            formation.update(&mut session);
+        }
+        let slot = track.start_positions[0].clone();
+        let state = session.participants.get_mut(&first).unwrap();
+        state.pos_x = slot.x + 1.0;
+        state.pos_y = slot.y;
+        state.speed_mps = 0.5;
+        formation.update(&mut session);
+
+        assert!(formation.is_parked(&first));
+        assert_eq!(session.participants[&first].pos_x, slot.x);
+        assert!(!formation.is_finished());
+    }
+
+    #[test]
+    fn test_missed_box_starts_from_pit_lane() {
+        let (mut session, mut track, first, second) = create_session();
+        track.pit_lane = Some(PitLaneConfig {
+            entry_point: TrackPoint { distance_from_start_m: 550.0, ..Default::default() },
+            exit_point: TrackPoint { x: 40.0, y: -20.0, distance_from_start_m: 50.0, ..Default::default() },
+            speed_limit_mps: 22.0,
+            pit_stalls: Vec::new(),
+            centerline: Vec::new(),
+            speed_limit_start_m: 0.0,
+            speed_limit_end_m: 0.0,
+        });
+        let mut formation = FormationLap::new(&session, &track, 1, TickTiming::default());
+
+        for _ in 0..240 {
+            formation.update(&mut session);
+        }
+        assert!(formation.is_finished());
+
+        let starters = formation.finish(&mut session, &track);
+        assert_eq!(starters, vec![first, second]);
+
+        let first_state = &session.participants[&first];
+        assert_eq!((first_state.pos_x, first_state.pos_y), (40.0, -20.0));
+        let second_state = &session.participants[&second];
+        assert_eq!(second_state.pos_x, 40.0 - PIT_QUEUE_SPACING_M);
+        assert_eq!(second_state.speed_mps, 0.0);
+    }
+
+    #[test]
+    fn test_pit_lane_starters_use_their_pit_box() {
+        let (mut session, mut track, first, second) = create_session();
+        let second_slot = session.participants[&second].grid_position;
+        track.pit_lane = Some(PitLaneConfig {
+            entry_point: TrackPoint { distance_from_start_m: 550.0, ..Default::default() },
+            exit_point: TrackPoint { x: 40.0, y: -20.0, distance_from_start_m: 50.0, ..Default::default() },
+            speed_limit_mps: 22.0,
+            pit_stalls: vec![PitStall { position: second_slot, x: 10.0, y: -25.0, z: 0.0, yaw_rad: 0.5 }],
+            centerline: Vec::new(),
+            speed_limit_start_m: 0.0,
+            speed_limit_end_m: 0.0,
+        });
+        let mut formation = FormationLap::new(&session, &track, 1, TickTiming::default());
+        for _ in 0..240 {
+            formation.update(&mut session);
+        }
+        formation.finish(&mut session, &track);
+
+        // The car without a box queues at the exit as if it were first in line
+        let first_state = &session.participants[&first];
+        assert_eq!((first_state.pos_x, first_state.pos_y), (40.0, -20.0));
+        let second_state = &session.participants[&second];
+        assert_eq!((second_state.pos_x, second_state.pos_y, second_state.yaw_rad), (10.0, -25.0, 0.5));
+    }
+}
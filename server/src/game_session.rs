@@ -1,8 +1,60 @@
-use crate::ai_driver::{AiDriverController, AiDriverProfile};
+use crate::ai_driver::{AiDriverController, AiDriverProfile, AiRecovery};
+use crate::dashboard::DashboardPacket;
 use crate::data::*;
+use crate::formation_lap::{self, FormationLap, DEFAULT_TIME_LIMIT_S};
 use crate::network::*;
 use crate::physics;
+use crate::race_events::RaceEventLog;
+use crate::replay::{DelayedFeed, ReplayControl, ReplayPlayer};
+use crate::rolling_start::{RollingStart, FALSE_START_PENALTY_MS};
+use crate::session_broadcast::{BroadcastFrame, SessionBroadcaster};
+use crate::standings::{compare_race_order, StandingsTracker};
+use crate::telemetry_history::TelemetryHistory;
+use crate::weather::Weather;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use tracing::warn;
+
+/// Reset schedule for an always-on open practice session
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct OpenPractice {
+    /// Ticks between automatic resets (0 = never)
+    pub reset_interval_ticks: u32,
+    pub last_reset_tick: u32,
+}
+
+/// Everything that decides how a session's cars move from one tick to the next.
+///
+/// A session rebuilt from this state and fed the same inputs ends up in the
+/// same state, tick for tick.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationState {
+    pub session: RaceSession,
+    pub ai_profiles: Vec<AiDriverProfile>,
+    pub warmup: physics::WarmupSettings,
+    pub collisions: physics::CollisionSettings,
+    pub timing: physics::TickTiming,
+    pub setup_policy: SetupPolicy,
+    pub collision_policy: CollisionPolicy,
+    pub setups: Vec<(PlayerId, CarSetup)>,
+    #[serde(default)]
+    pub balance: Vec<(CarConfigId, BalanceOfPerformance)>,
+    pub aid_limits: DriverAids,
+    pub aids: Vec<(PlayerId, DriverAids)>,
+    pub ai_recovery: Vec<(PlayerId, AiRecovery)>,
+    pub weather: Weather,
+    pub rolling_start: Option<RollingStart>,
+    pub formation_lap: Option<FormationLap>,
+    pub open_practice: Option<OpenPractice>,
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// 64-bit FNV-1a; unlike `DefaultHasher` it gives the same hash in every build
+fn fnv1a(bytes: &[u8], hash: u64) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| (hash ^ *byte as u64).wrapping_mul(FNV_PRIME))
+}
 
 pub struct GameSession {
     pub session: RaceSession,
@@ -10,6 +62,96 @@ pub struct GameSession {
     pub car_configs: HashMap<CarConfigId, CarConfig>,
     /// AI driver profiles indexed by their player ID
     pub ai_profiles: HashMap<PlayerId, AiDriverProfile>,
+    /// Timing point crossings used to build the live leaderboard
+    pub standings: StandingsTracker,
+    /// Race-director events recorded during the session
+    pub race_events: RaceEventLog,
+    /// Whether the results of the finished race have been queued
+    results_sent: bool,
+    /// Last few seconds of telemetry per car
+    pub telemetry_history: TelemetryHistory,
+    /// Cold tire and brake behavior for cars in this session
+    pub warmup: physics::WarmupSettings,
+    /// Restitution and friction of car-to-car contacts
+    pub collisions: physics::CollisionSettings,
+    /// Tick rate and physics sub-steps per tick
+    pub timing: physics::TickTiming,
+    /// Rolling start in progress, kept until every car has taken the green
+    pub rolling_start: Option<RollingStart>,
+    /// Formation lap in progress before a standing start
+    pub formation_lap: Option<FormationLap>,
+    /// Set for open practice sessions that run without a host and reset periodically
+    pub open_practice: Option<OpenPractice>,
+    /// Sky condition and track wetness
+    pub weather: Weather,
+    /// Connections watching this session and messages queued for them
+    pub broadcaster: SessionBroadcaster,
+    /// Spectators watching the race with a delay, one feed per delay
+    delayed_feeds: Vec<DelayedFeed>,
+    /// Whether players may change their car setup
+    pub setup_policy: SetupPolicy,
+    /// Whether cars pass through each other in free practice
+    pub collision_policy: CollisionPolicy,
+    /// Setups players have applied, with the car config each produces
+    setups: HashMap<PlayerId, (CarSetup, CarConfig)>,
+    /// Balance of Performance set for this session, with each car's config before it
+    balance: HashMap<CarConfigId, (BalanceOfPerformance, CarConfig)>,
+    /// Highest level of each driver aid players may run
+    pub aid_limits: DriverAids,
+    /// Driver aids players have selected
+    aids: HashMap<PlayerId, DriverAids>,
+    /// Recovery state of each AI driver after spins and off-track excursions
+    ai_recovery: HashMap<PlayerId, AiRecovery>,
+    /// Recording played back in replay mode
+    replay: Option<ReplayPlayer>,
+    /// Set by an admin to end the race at the next tick
+    finish_requested: bool,
+}
+
+/// Put a car back on the centerline at its track position, stationary and upright
+fn reset_to_track(state: &mut CarState, track: &TrackConfig) {
+    let track_length = track.centerline.last().map(|p| p.distance_from_start_m).unwrap_or(0.0);
+    let progress = if track_length > 0.0 { state.track_progress.rem_euclid(track_length) } else { 0.0 };
+    let Some(point) = track
+        .centerline
+        .iter()
+        .min_by(|a, b| {
+            (a.distance_from_start_m - progress)
+                .abs()
+                .total_cmp(&(b.distance_from_start_m - progress).abs())
+        })
+    else {
+        return;
+    };
+
+    let slot = GridSlot {
+        position: state.grid_position,
+        x: point.x,
+        y: point.y,
+        z: point.z,
+        yaw_rad: point.heading_rad,
+    };
+    formation_lap::park(state, &slot);
+    state.lateral_offset_m = 0.0;
+    state.gear = 1;
+    if state.is_overturned {
+        // Rolling over is what stopped the car; put it back on its wheels
+        state.is_overturned = false;
+        state.damage.is_drivable = true;
+    }
+}
+
+/// Car config a participant drives with: their own setup if they applied one
+fn participant_config<'a>(
+    setups: &'a HashMap<PlayerId, (CarSetup, CarConfig)>,
+    car_configs: &'a HashMap<CarConfigId, CarConfig>,
+    state: &CarState,
+) -> Option<&'a CarConfig> {
+    setups
+        .get(&state.player_id)
+        .map(|(_, tuned)| tuned)
+        .filter(|tuned| tuned.id == state.car_config_id)
+        .or_else(|| car_configs.get(&state.car_config_id))
 }
 
 impl GameSession {
@@ -19,10 +161,32 @@ impl GameSession {
         car_configs: HashMap<CarConfigId, CarConfig>,
     ) -> Self {
         Self {
+            race_events: RaceEventLog::new(session.id),
+            results_sent: false,
             session,
             track_config,
             car_configs,
             ai_profiles: HashMap::new(),
+            standings: StandingsTracker::new(),
+            telemetry_history: TelemetryHistory::new(),
+            warmup: physics::WarmupSettings::default(),
+            collisions: physics::CollisionSettings::default(),
+            timing: physics::TickTiming::default(),
+            rolling_start: None,
+            formation_lap: None,
+            open_practice: None,
+            weather: Weather::default(),
+            broadcaster: SessionBroadcaster::new(),
+            delayed_feeds: Vec::new(),
+            setup_policy: SetupPolicy::default(),
+            collision_policy: CollisionPolicy::default(),
+            setups: HashMap::new(),
+            balance: HashMap::new(),
+            aid_limits: DriverAids::unrestricted(),
+            aids: HashMap::new(),
+            ai_recovery: HashMap::new(),
+            replay: None,
+            finish_requested: false,
         }
     }
     
@@ -45,17 +209,122 @@ impl GameSession {
             .collect();
         
         Self {
+            race_events: RaceEventLog::new(session.id),
+            results_sent: false,
             session,
             track_config,
             car_configs,
             ai_profiles: ai_profiles_map,
+            standings: StandingsTracker::new(),
+            telemetry_history: TelemetryHistory::new(),
+            warmup: physics::WarmupSettings::default(),
+            collisions: physics::CollisionSettings::default(),
+            timing: physics::TickTiming::default(),
+            rolling_start: None,
+            formation_lap: None,
+            open_practice: None,
+            weather: Weather::default(),
+            broadcaster: SessionBroadcaster::new(),
+            delayed_feeds: Vec::new(),
+            setup_policy: SetupPolicy::default(),
+            collision_policy: CollisionPolicy::default(),
+            setups: HashMap::new(),
+            balance: HashMap::new(),
+            aid_limits: DriverAids::unrestricted(),
+            aids: HashMap::new(),
+            ai_recovery: HashMap::new(),
+            replay: None,
+            finish_requested: false,
+        }
+    }
+
+    /// Rebuild a session from a captured simulation state
+    pub fn from_simulation_state(
+        state: SimulationState,
+        track_config: TrackConfig,
+        car_configs: HashMap<CarConfigId, CarConfig>,
+    ) -> Self {
+        let mut game_session = Self::with_ai_profiles(state.session, track_config, car_configs, state.ai_profiles);
+        game_session.warmup = state.warmup;
+        game_session.collisions = state.collisions;
+        game_session.timing = state.timing;
+        game_session.setup_policy = state.setup_policy;
+        game_session.collision_policy = state.collision_policy;
+        for (car_id, balance) in state.balance {
+            if let Err(e) = game_session.set_balance(car_id, balance) {
+                warn!("Dropped Balance of Performance of car {}: {}", car_id, e);
+            }
+        }
+        for (player_id, setup) in state.setups {
+            if let Err(e) = game_session.apply_setup(player_id, setup) {
+                warn!("Dropped setup of player {}: {}", player_id, e);
+            }
+        }
+        game_session.aid_limits = state.aid_limits;
+        for (player_id, aids) in state.aids {
+            if let Err(e) = game_session.set_aids(player_id, aids) {
+                warn!("Dropped driver aids of player {}: {}", player_id, e);
+            }
+        }
+        game_session.ai_recovery = state.ai_recovery.into_iter().collect();
+        game_session.weather = state.weather;
+        game_session.rolling_start = state.rolling_start;
+        game_session.formation_lap = state.formation_lap;
+        game_session.open_practice = state.open_practice;
+        game_session
+    }
+
+    /// Capture what `from_simulation_state` needs to carry on from this tick
+    pub fn simulation_state(&self) -> SimulationState {
+        SimulationState {
+            session: self.session.clone(),
+            ai_profiles: self.ai_profiles.values().cloned().collect(),
+            warmup: self.warmup,
+            collisions: self.collisions,
+            timing: self.timing,
+            setup_policy: self.setup_policy,
+            collision_policy: self.collision_policy,
+            setups: self.setups().map(|(player_id, setup)| (player_id, setup.clone())).collect(),
+            balance: self.balance().map(|(car_id, balance)| (car_id, *balance)).collect(),
+            aid_limits: self.aid_limits,
+            aids: self.selected_aids().collect(),
+            ai_recovery: self.ai_recovery.iter().map(|(id, recovery)| (*id, *recovery)).collect(),
+            weather: self.weather.clone(),
+            rolling_start: self.rolling_start.clone(),
+            formation_lap: self.formation_lap.clone(),
+            open_practice: self.open_practice,
+        }
+    }
+
+    /// Hash of the tick and every car's state, stable across builds and runs.
+    ///
+    /// Player IDs are left out so a pseudonymized recording hashes the same.
+    pub fn state_hash(&self) -> u64 {
+        let mut hash = fnv1a(&self.session.current_tick.to_le_bytes(), FNV_OFFSET_BASIS);
+        for mut car in self.cars_in_grid_order() {
+            car.player_id = PlayerId::nil();
+            car.drafting_behind = None;
+            match rmp_serde::to_vec(&car) {
+                Ok(bytes) => hash = fnv1a(&bytes, hash),
+                Err(e) => warn!("Cannot hash car state: {}", e),
+            }
         }
+        hash
     }
 
     /// Advance the session by one tick
     pub fn tick(&mut self, inputs: &HashMap<PlayerId, PlayerInputData>) {
         self.session.current_tick += 1;
 
+        // Weather runs once the session has started; a replay has none of its own
+        let weather_running = self.session.state != SessionState::Lobby && self.session.game_mode != GameMode::Replay;
+        if weather_running {
+            self.weather.update(self.timing.tick_dt());
+        }
+        for state in self.session.participants.values_mut() {
+            state.track_wetness = self.weather.wetness;
+        }
+
         // Handle game mode specific logic
         match self.session.game_mode {
             GameMode::Lobby => {
@@ -81,9 +350,172 @@ impl GameSession {
                 // For now, treat them like FreePractice
                 self.tick_free_practice(inputs);
             }
+            GameMode::RollingStart => {
+                self.tick_free_practice(inputs);
+            }
+            GameMode::FormationLap => {
+                self.tick_free_practice(inputs);
+                self.update_formation_lap();
+            }
+        }
+
+        self.update_rolling_start();
+        self.update_ai_recovery();
+
+        if self.session.state == SessionState::Racing {
+            let track_length = self.track_config.centerline.last()
+                .map(|p| p.distance_from_start_m)
+                .unwrap_or(0.0);
+            self.standings.update(&self.session, track_length);
+            self.race_events.update(&self.session, &self.track_config);
+            self.telemetry_history.record(&self.session, self.timing);
+
+            if self.finish_requested {
+                self.finish_requested = false;
+                self.classify_running_cars();
+                self.session.state = SessionState::Finished;
+            }
+        }
+
+        if let Some(open_practice) = self.open_practice {
+            let elapsed = self.session.current_tick.saturating_sub(open_practice.last_reset_tick);
+            if open_practice.reset_interval_ticks > 0 && elapsed >= open_practice.reset_interval_ticks {
+                self.reset_open_practice();
+            }
+        }
+
+        if self.was_reset_this_tick() {
+            self.broadcaster.queue(ServerMessage::SessionReset(SessionResetData { session_id: self.session.id }));
+        }
+        if weather_running {
+            if let Some(update) = self.weather.take_update(self.session.id) {
+                self.broadcaster.queue(ServerMessage::WeatherUpdate(update));
+            }
         }
     }
 
+    /// Put every car back on its grid slot and clear lap times, standings and history
+    pub fn reset_open_practice(&mut self) {
+        let tick = self.session.current_tick;
+        for state in self.session.participants.values_mut() {
+            let slot = self
+                .track_config
+                .start_positions
+                .iter()
+                .find(|s| s.position == state.grid_position);
+            if let Some(slot) = slot {
+                *state = CarState::new(state.player_id, state.car_config_id, slot);
+                physics::apply_cold_start(state, &self.warmup);
+            }
+        }
+
+        self.ai_recovery.clear();
+        self.standings = StandingsTracker::new();
+        self.race_events = RaceEventLog::new(self.session.id);
+        self.telemetry_history = TelemetryHistory::new();
+        if let Some(open_practice) = self.open_practice.as_mut() {
+            open_practice.last_reset_tick = tick;
+        }
+    }
+
+    /// Whether an open practice reset happened on the current tick
+    pub fn was_reset_this_tick(&self) -> bool {
+        self.open_practice
+            .is_some_and(|o| o.last_reset_tick == self.session.current_tick && o.last_reset_tick > 0)
+    }
+
+    /// Move every AI driver's recovery state machine on, and put cars that fail to
+    /// recover back on the track outside of competitive sessions
+    fn update_ai_recovery(&mut self) {
+        let driving = matches!(
+            self.session.game_mode,
+            GameMode::DemoLap | GameMode::FreePractice | GameMode::Qualification | GameMode::Race | GameMode::RollingStart
+        );
+        if !driving || self.session.countdown_ticks_remaining.is_some() {
+            return;
+        }
+        let resets_allowed = matches!(self.session.game_mode, GameMode::DemoLap | GameMode::FreePractice);
+        let dt = self.timing.tick_dt();
+
+        let mut timed_out = Vec::new();
+        for (player_id, profile) in &self.ai_profiles {
+            let Some(state) = self.session.participants.get(player_id) else {
+                continue;
+            };
+            let Some(car_config) = self.car_configs.get(&state.car_config_id) else {
+                continue;
+            };
+            let humans = self
+                .session
+                .participants
+                .values()
+                .filter(|other| !self.ai_profiles.contains_key(&other.player_id))
+                .collect();
+            let controller = AiDriverController::new(profile, &self.track_config, car_config).with_traffic(humans);
+            let recovery = self.ai_recovery.entry(*player_id).or_default();
+            controller.update_recovery(state, recovery, dt);
+            if resets_allowed && recovery.timed_out() {
+                timed_out.push(*player_id);
+            }
+        }
+
+        for player_id in timed_out {
+            self.ai_recovery.remove(&player_id);
+            if let Some(state) = self.session.participants.get_mut(&player_id) {
+                reset_to_track(state, &self.track_config);
+            }
+        }
+    }
+
+    /// Pace the field until green, then let each car start its first lap at the line
+    fn update_rolling_start(&mut self) {
+        let Some(rolling_start) = self.rolling_start.as_mut() else {
+            return;
+        };
+
+        let update = rolling_start.update(&mut self.session, self.timing.tick_dt());
+
+        for player_id in update.false_starts {
+            if let Some(state) = self.session.participants.get(&player_id) {
+                self.race_events.record_penalty(
+                    self.session.current_tick,
+                    state,
+                    FALSE_START_PENALTY_MS,
+                    "False start",
+                );
+            }
+        }
+
+        if update.went_green {
+            self.session.game_mode = GameMode::Race;
+        }
+        if rolling_start.is_complete() {
+            self.rolling_start = None;
+        }
+    }
+
+    /// Park cars in their boxes and, once the lap is over, move on to the countdown
+    fn update_formation_lap(&mut self) {
+        let Some(formation_lap) = self.formation_lap.as_mut() else {
+            return;
+        };
+
+        formation_lap.update(&mut self.session);
+        if !formation_lap.is_finished() {
+            return;
+        }
+
+        let pit_lane_starters = formation_lap.finish(&mut self.session, &self.track_config);
+        for player_id in pit_lane_starters {
+            if let Some(state) = self.session.participants.get(&player_id) {
+                self.race_events.record_pit_lane_start(self.session.current_tick, state);
+            }
+        }
+
+        self.formation_lap = None;
+        self.set_game_mode(GameMode::Countdown);
+    }
+
     /// Lobby mode: Players selecting cars, no telemetry sent
     fn tick_lobby(&mut self) {
         // In lobby mode, no simulation occurs and no telemetry is sent
@@ -114,7 +546,7 @@ impl GameSession {
 
     /// Demo lap mode: AI driver demonstrates the track
     fn tick_demolap(&mut self, player_inputs: &HashMap<PlayerId, PlayerInputData>) {
-        let dt = 1.0 / 240.0; // Fixed timestep at 240Hz
+        let dt = self.timing.tick_dt();
 
         // Initialize demo lap progress if not set
         if self.session.demo_lap_progress.is_none() {
@@ -131,16 +563,7 @@ impl GameSession {
             }
 
             // Update physics for all cars (player + AI)
-            let mut states: Vec<&mut CarState> = self.session.participants.values_mut().collect();
-            for state in states.iter_mut() {
-                let input = inputs.get(&state.player_id).copied().unwrap_or_default();
-
-                if let Some(config) = self.car_configs.get(&state.car_config_id) {
-                    physics::update_car_3d(state, config, &input, &self.track_config, dt);
-                    physics::update_track_progress_3d(state, &self.track_config, self.session.current_tick);
-                }
-            }
-
+            self.step_cars(&inputs);
             return;
         }
 
@@ -259,35 +682,17 @@ impl GameSession {
 
     /// Free practice mode: Players drive freely with lap timing
     fn tick_free_practice(&mut self, inputs: &HashMap<PlayerId, PlayerInputData>) {
-        let dt = 1.0 / 240.0; // Fixed timestep at 240Hz
-
-        // Update each car
-        let mut states: Vec<&mut CarState> = self.session.participants.values_mut().collect();
-
-        for state in states.iter_mut() {
-            // Get input for this player (default to coasting if missing)
-            let input = inputs
-                .get(&state.player_id)
-                .copied()
-                .unwrap_or_default();
-
-            // Get car config
-            if let Some(config) = self.car_configs.get(&state.car_config_id) {
-                // Update 3D physics with track context
-                physics::update_car_3d(state, config, &input, &self.track_config, dt);
-
-                // Update track progress
-                physics::update_track_progress_3d(
-                    state,
-                    &self.track_config,
-                    self.session.current_tick,
-                );
-            }
-        }
+        self.step_cars(inputs);
 
         // Check collisions
-        let mut state_vec: Vec<CarState> = self.session.participants.values().cloned().collect();
-        physics::check_aabb_collisions_3d(&mut state_vec, &self.car_configs);
+        let mut state_vec = self.cars_in_grid_order();
+        let ghosts = self.collision_policy == CollisionPolicy::Ghost;
+        for state in state_vec.iter_mut() {
+            state.is_ghost = ghosts;
+        }
+        physics::resolve_car_collisions(&mut state_vec, &self.car_configs, &self.collisions);
+        physics::resolve_wall_collisions(&mut state_vec, &self.car_configs, &self.track_config.walls, &self.collisions);
+        physics::update_slipstream(&mut state_vec, &self.car_configs);
 
         // Update states back
         for state in state_vec {
@@ -295,44 +700,116 @@ impl GameSession {
         }
     }
 
-    /// Replay mode: Send telemetry from recorded data (view-only)
-    fn tick_replay(&mut self) {
-        // Replay mode is not yet implemented
-        // This would play back previously recorded telemetry data
-        // For now, do nothing
+    /// Every car in grid order, so contacts resolve the same way on every run
+    fn cars_in_grid_order(&self) -> Vec<CarState> {
+        let mut cars: Vec<CarState> = self.session.participants.values().cloned().collect();
+        cars.sort_by_key(|s| (s.grid_position, s.player_id));
+        cars
     }
 
-    #[allow(dead_code)]
-    fn tick_racing(&mut self, inputs: &HashMap<PlayerId, PlayerInputData>) {
-        let dt = 1.0 / 240.0; // Fixed timestep at 240Hz
-
-        // Update each car
-        let mut states: Vec<&mut CarState> = self.session.participants.values_mut().collect();
+    /// Run every car through this tick's physics sub-steps, then update its lap progress
+    fn step_cars(&mut self, inputs: &HashMap<PlayerId, PlayerInputData>) {
+        let timing = self.timing;
+        let dt = timing.step_dt();
 
-        for state in states.iter_mut() {
+        for state in self.session.participants.values_mut() {
             // Get input for this player (default to coasting if missing)
             let input = inputs
                 .get(&state.player_id)
                 .copied()
                 .unwrap_or_default();
 
-            // Get car config
-            if let Some(config) = self.car_configs.get(&state.car_config_id) {
-                // Update 3D physics with track context
+            let Some(config) = participant_config(&self.setups, &self.car_configs, state) else {
+                continue;
+            };
+            let aids = self.aids.get(&state.player_id).copied().unwrap_or_default();
+            for _ in 0..timing.substeps {
+                // Shape the inputs with the player's driver aids
+                let input = physics::apply_driver_aids(state, config, &input, aids);
                 physics::update_car_3d(state, config, &input, &self.track_config, dt);
+                physics::update_warmup(state, config, &self.warmup, dt);
+            }
 
-                // Update track progress
-                physics::update_track_progress_3d(
-                    state,
-                    &self.track_config,
-                    self.session.current_tick,
-                );
+            physics::update_track_progress_3d(state, &self.track_config, self.session.current_tick, timing);
+        }
+    }
+
+    /// Replay mode: play back the loaded recording (view-only)
+    fn tick_replay(&mut self) {
+        let Some(player) = self.replay.as_mut() else {
+            return;
+        };
+        let was_playing = player.is_playing();
+        let events = player.advance(self.timing.tick_dt());
+        let ran_out = was_playing && !player.is_playing();
+
+        for event in events {
+            self.broadcaster.queue(ServerMessage::RaceEvent(event));
+        }
+        if ran_out {
+            self.queue_replay_state();
+        }
+    }
+
+    /// Switch the session to playing back `player` from its first frame
+    pub fn start_replay(&mut self, mut player: ReplayPlayer) {
+        // Input replays re-simulate on this session's track and cars
+        player.prepare(&self.track_config, &self.car_configs);
+        self.replay = Some(player);
+        self.session.game_mode = GameMode::Replay;
+        // Telemetry only goes out for running sessions
+        self.session.state = SessionState::Racing;
+        if let Some(player) = self.replay.as_mut() {
+            player.control(ReplayControl::Play);
+        }
+        self.queue_replay_state();
+    }
+
+    /// Apply a playback command from the viewer and tell them the new state
+    pub fn control_replay(&mut self, control: ReplayControl) -> Result<(), String> {
+        let player = self.replay.as_mut().ok_or("No replay is playing in this session")?;
+        if let ReplayControl::JumpToHighlight(index) = control {
+            if index >= player.metadata().highlights.len() {
+                return Err(format!("Replay has no highlight {}", index));
             }
         }
+        player.control(control);
+        self.queue_replay_state();
+        Ok(())
+    }
 
-        // Check collisions
-        let mut state_vec: Vec<CarState> = self.session.participants.values().cloned().collect();
-        physics::check_aabb_collisions_3d(&mut state_vec, &self.car_configs);
+    /// Playback state of the loaded replay
+    pub fn replay_state(&self) -> Option<ReplayStateData> {
+        let player = self.replay.as_ref()?;
+        let (first_tick, last_tick) = player.tick_range().unwrap_or((0, 0));
+        Some(ReplayStateData {
+            session_id: player.metadata().session_id,
+            playing: player.is_playing(),
+            speed: player.speed(),
+            current_tick: player.frame().map(|f| f.tick).unwrap_or(0),
+            first_tick,
+            last_tick,
+        })
+    }
+
+    fn queue_replay_state(&mut self) {
+        if let Some(state) = self.replay_state() {
+            self.broadcaster.queue(ServerMessage::ReplayState(state));
+        }
+    }
+
+    #[allow(dead_code)]
+    fn tick_racing(&mut self, inputs: &HashMap<PlayerId, PlayerInputData>) {
+        self.step_cars(inputs);
+
+        // Check collisions; everyone races solid
+        let mut state_vec = self.cars_in_grid_order();
+        for state in state_vec.iter_mut() {
+            state.is_ghost = false;
+        }
+        physics::resolve_car_collisions(&mut state_vec, &self.car_configs, &self.collisions);
+        physics::resolve_wall_collisions(&mut state_vec, &self.car_configs, &self.track_config.walls, &self.collisions);
+        physics::update_slipstream(&mut state_vec, &self.car_configs);
 
         // Update states back
         for state in state_vec {
@@ -350,7 +827,32 @@ impl GameSession {
     pub fn start_countdown(&mut self) {
         if self.session.state == SessionState::Lobby {
             self.session.state = SessionState::Countdown;
-            self.session.countdown_ticks_remaining = Some(240 * 5); // 5 seconds at 240Hz
+            self.session.countdown_ticks_remaining = Some(self.countdown_ticks(5));
+        }
+    }
+
+    /// End the race at the next tick, whether or not the cars have done their laps
+    pub fn request_finish(&mut self) -> Result<(), String> {
+        if self.session.state != SessionState::Racing || self.session.game_mode == GameMode::Replay {
+            return Err("Only a live race can be finished".to_string());
+        }
+        self.finish_requested = true;
+        Ok(())
+    }
+
+    /// Give cars still running a finish position behind those already
+    /// finished, in their current running order
+    fn classify_running_cars(&mut self) {
+        let mut running: Vec<&CarState> =
+            self.session.participants.values().filter(|state| state.finish_position.is_none()).collect();
+        running.sort_by(|a, b| compare_race_order(a, b));
+        let running: Vec<PlayerId> = running.into_iter().map(|state| state.player_id).collect();
+
+        let finished = self.session.participants.len() - running.len();
+        for (idx, player_id) in running.into_iter().enumerate() {
+            if let Some(state) = self.session.participants.get_mut(&player_id) {
+                state.finish_position = Some((finished + idx + 1) as u8);
+            }
         }
     }
 
@@ -430,9 +932,18 @@ impl GameSession {
             }
             GameMode::Countdown => {
                 // Default 10 second countdown as per spec
-                self.session.countdown_ticks_remaining = Some(240 * 10);
+                self.session.countdown_ticks_remaining = Some(self.countdown_ticks(10));
                 self.session.state = SessionState::Countdown;
             }
+            GameMode::RollingStart => {
+                self.start_rolling_start();
+            }
+            GameMode::FormationLap => {
+                // Racing state so telemetry is sent while the field drives round
+                self.session.state = SessionState::Racing;
+                self.session.race_start_tick = None;
+                self.formation_lap = Some(FormationLap::new(&self.session, &self.track_config, DEFAULT_TIME_LIMIT_S, self.timing));
+            }
             _ => {
                 self.session.demo_lap_progress = None;
             }
@@ -442,22 +953,36 @@ impl GameSession {
     /// Start countdown mode with custom duration and specify next mode
     pub fn start_countdown_mode(&mut self, countdown_seconds: u16, _next_mode: GameMode) {
         self.session.game_mode = GameMode::Countdown;
-        self.session.countdown_ticks_remaining = Some(240 * countdown_seconds);
+        self.session.countdown_ticks_remaining = Some(self.countdown_ticks(countdown_seconds));
         // TODO: Store next_mode to transition to when countdown finishes
     }
 
+    /// Countdown length in ticks, saturating for very high tick rates
+    fn countdown_ticks(&self, seconds: u16) -> u16 {
+        self.timing.ticks(seconds as u32).min(u16::MAX as u32) as u16
+    }
+
     /// Transition from Countdown to another mode
     pub fn transition_from_countdown(&mut self, next_mode: GameMode) {
         self.session.game_mode = next_mode;
         self.session.countdown_ticks_remaining = None;
 
         // Initialize the next mode
-        match next_mode {
-            GameMode::DemoLap => {
-                self.session.demo_lap_progress = Some(0.0);
-            }
-            _ => {}
+        if next_mode == GameMode::DemoLap {
+            self.session.demo_lap_progress = Some(0.0);
+        } else if next_mode == GameMode::RollingStart {
+            self.start_rolling_start();
+        }
+    }
+
+    /// Line the field up behind the pace car for a formation lap
+    fn start_rolling_start(&mut self) {
+        self.session.state = SessionState::Racing;
+        self.session.race_start_tick = None;
+        for state in self.session.participants.values_mut() {
+            state.current_lap = 0;
         }
+        self.rolling_start = Some(RollingStart::new(&self.session, &self.track_config));
     }
 
     /// Add a player to the session
@@ -491,30 +1016,167 @@ impl GameSession {
             .iter()
             .find(|s| s.position == grid_position)
         {
-            let car_state = CarState::new(player_id, car_config_id, grid_slot);
+            let mut car_state = CarState::new(player_id, car_config_id, grid_slot);
+            physics::apply_cold_start(&mut car_state, &self.warmup);
+            car_state.track_wetness = self.weather.wetness;
             self.session.participants.insert(player_id, car_state);
+            // Newcomers need to know the weather too
+            self.weather.announce();
             Some(grid_position)
         } else {
             None
         }
     }
 
-    /// Remove a player from the session
+    /// Paint a participant's car in one of its liveries
+    pub fn set_livery(&mut self, player_id: PlayerId, livery: u8) {
+        if let Some(car_state) = self.session.participants.get_mut(&player_id) {
+            car_state.livery = livery;
+        }
+    }
+
+    /// Remove a player or spectator from the session
     pub fn remove_player(&mut self, player_id: &PlayerId) {
         self.session.participants.remove(player_id);
+        self.setups.remove(player_id);
+        self.aids.remove(player_id);
+        self.broadcaster.unbind(player_id);
+        self.unbind_delayed(player_id);
+    }
+
+    /// Change a player's car setup, checked against the car's setup ranges
+    pub fn apply_setup(&mut self, player_id: PlayerId, setup: CarSetup) -> Result<(), String> {
+        if self.setup_policy == SetupPolicy::Fixed {
+            return Err("Setups are fixed in this session".to_string());
+        }
+        let state = self
+            .session
+            .participants
+            .get(&player_id)
+            .ok_or_else(|| "Not driving in this session".to_string())?;
+        let config = self
+            .car_configs
+            .get(&state.car_config_id)
+            .ok_or_else(|| "Car is not available in this session".to_string())?;
+
+        setup.validate(config)?;
+        let tuned = setup.apply(config);
+        self.setups.insert(player_id, (setup, tuned));
+        Ok(())
+    }
+
+    /// Put Balance of Performance handicaps on a car for this session, replacing
+    /// any it had; neutral handicaps give the car back its own data
+    pub fn set_balance(&mut self, car_id: CarConfigId, balance: BalanceOfPerformance) -> Result<(), String> {
+        balance.validate()?;
+        let stock = match self.balance.remove(&car_id) {
+            Some((_, stock)) => stock,
+            None => self
+                .car_configs
+                .get(&car_id)
+                .cloned()
+                .ok_or_else(|| "Car is not available in this session".to_string())?,
+        };
+        let balanced = balance.apply(&stock);
+        // Setups were applied to the car as it was
+        for (setup, tuned) in self.setups.values_mut() {
+            if tuned.id == car_id {
+                *tuned = setup.apply(&balanced);
+            }
+        }
+        self.car_configs.insert(car_id, balanced);
+        if !balance.is_neutral() {
+            self.balance.insert(car_id, (balance, stock));
+        }
+        Ok(())
+    }
+
+    /// Balance of Performance set for this session
+    pub fn balance(&self) -> impl Iterator<Item = (CarConfigId, &BalanceOfPerformance)> {
+        self.balance.iter().map(|(car_id, (balance, _))| (*car_id, balance))
+    }
+
+    /// Setup a player has applied, if any
+    pub fn setup(&self, player_id: &PlayerId) -> Option<&CarSetup> {
+        self.setups.get(player_id).map(|(setup, _)| setup)
+    }
+
+    /// Setups players have applied in this session
+    pub fn setups(&self) -> impl Iterator<Item = (PlayerId, &CarSetup)> {
+        self.setups.iter().map(|(player_id, (setup, _))| (*player_id, setup))
+    }
+
+    /// Select a player's driver aids, which must be within the session's limits
+    pub fn set_aids(&mut self, player_id: PlayerId, aids: DriverAids) -> Result<(), String> {
+        if !self.session.participants.contains_key(&player_id) {
+            return Err("Not driving in this session".to_string());
+        }
+        if !aids.within(&self.aid_limits) {
+            return Err("Driver aids exceed what this session allows".to_string());
+        }
+        self.aids.insert(player_id, aids);
+        Ok(())
+    }
+
+    /// Driver aids a player runs with; none unless they selected some
+    pub fn aids(&self, player_id: &PlayerId) -> DriverAids {
+        self.aids.get(player_id).copied().unwrap_or_default()
+    }
+
+    /// Driver aids players have selected in this session
+    pub fn selected_aids(&self) -> impl Iterator<Item = (PlayerId, DriverAids)> + '_ {
+        self.aids.iter().map(|(player_id, aids)| (*player_id, *aids))
+    }
+
+    /// Hand a car over to a new player ID, e.g. when a player reconnects on a standby server.
+    ///
+    /// Returns false if `old_id` has no car or `new_id` already has one.
+    pub fn reassign_player(&mut self, old_id: PlayerId, new_id: PlayerId) -> bool {
+        if self.session.participants.contains_key(&new_id) {
+            return false;
+        }
+        let Some(mut state) = self.session.participants.remove(&old_id) else {
+            return false;
+        };
+
+        state.player_id = new_id;
+        self.session.participants.insert(new_id, state);
+        if let Some(setup) = self.setups.remove(&old_id) {
+            self.setups.insert(new_id, setup);
+        }
+        if let Some(aids) = self.aids.remove(&old_id) {
+            self.aids.insert(new_id, aids);
+        }
+        self.broadcaster.unbind(&old_id);
+        if self.session.host_player_id == old_id {
+            self.session.host_player_id = new_id;
+        }
+        true
     }
 
     /// Generate AI input for a player using their AI profile.
     ///
-    /// Returns default input if the player is not an AI or has no profile.
+    /// The AI avoids the human cars in the session; AI cars do not yield to each other.
+    /// While recovering from a spin or an off-track excursion the AI gives way to
+    /// every car. Returns default input if the player is not an AI or has no profile.
     pub fn generate_ai_input(&self, player_id: &PlayerId) -> PlayerInputData {
         // Check if this player has an AI profile
         if let Some(profile) = self.ai_profiles.get(player_id) {
             if let Some(state) = self.session.participants.get(player_id) {
                 // Get the car config for this AI player
                 if let Some(car_config) = self.car_configs.get(&state.car_config_id) {
-                    let controller = AiDriverController::new(profile, &self.track_config, car_config);
-                    return controller.generate_input(state, self.session.current_tick);
+                    let humans = self
+                        .session
+                        .participants
+                        .values()
+                        .filter(|other| !self.ai_profiles.contains_key(&other.player_id))
+                        .collect();
+                    let field = self.session.participants.values().collect();
+                    let controller = AiDriverController::new(profile, &self.track_config, car_config)
+                        .with_traffic(humans)
+                        .with_field(field);
+                    let recovery = self.ai_recovery.get(player_id).copied().unwrap_or_default();
+                    return controller.generate_input_with_recovery(state, &recovery, self.session.current_tick);
                 }
             }
         }
@@ -535,27 +1197,232 @@ impl GameSession {
 
     /// Get telemetry for broadcast
     pub fn get_telemetry(&self) -> ServerMessage {
+        if let Some(frame) = self.replay.as_ref().and_then(|r| r.frame()) {
+            let mut telemetry = frame.telemetry.clone();
+            telemetry.game_mode = GameMode::Replay;
+            return ServerMessage::Telemetry(telemetry);
+        }
+
+        ServerMessage::Telemetry(self.telemetry())
+    }
+
+    /// Live telemetry of the session's cars
+    pub fn telemetry(&self) -> Telemetry {
         let car_states: Vec<CarStateTelemetry> = self
             .session
             .participants
             .values()
-            .map(|s| CarStateTelemetry::from(s))
+            .map(CarStateTelemetry::from)
             .collect();
 
         let countdown_ms = self
             .session
             .countdown_ticks_remaining
-            .map(|ticks| ((ticks as f32 / 240.0) * 1000.0) as u16);
+            .map(|ticks| self.timing.ticks_to_ms(ticks as u32).min(u16::MAX as u32) as u16);
 
-        let telemetry = crate::network::Telemetry {
+        Telemetry {
             server_tick: self.session.current_tick,
             session_state: self.session.state,
             game_mode: self.session.game_mode,
             countdown_ms,
             car_states,
+            pace_car: self
+                .rolling_start
+                .as_ref()
+                .and_then(|r| r.pace_car(&self.track_config)),
+            race_progress: self.race_progress(),
+        }
+    }
+
+    /// Leader's lap and the estimated time until they take the flag, for lap-limited races
+    pub fn race_progress(&self) -> Option<RaceProgress> {
+        let running = matches!(self.session.state, SessionState::Racing | SessionState::Finished);
+        if !running || self.session.lap_limit == 0 {
+            return None;
+        }
+
+        let total_laps = self.session.lap_limit as u16;
+        let leader = self
+            .session
+            .participants
+            .values()
+            .min_by(|a, b| compare_race_order(a, b))?;
+
+        if self.session.state == SessionState::Finished || leader.finish_position.is_some() || leader.current_lap > total_laps {
+            return Some(RaceProgress {
+                leader_lap: total_laps,
+                total_laps,
+                completed_fraction: 1.0,
+                estimated_remaining_s: Some(0),
+            });
+        }
+
+        let track_length_m = self.track_config.centerline.last().map(|p| p.distance_from_start_m).unwrap_or(0.0);
+        let lap_fraction = if track_length_m > 0.0 {
+            (leader.track_progress / track_length_m).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let laps_done = (leader.current_lap.saturating_sub(1) as f32 + lap_fraction).min(total_laps as f32);
+        let laps_left = total_laps as f32 - laps_done;
+
+        Some(RaceProgress {
+            leader_lap: leader.current_lap.clamp(1, total_laps),
+            total_laps,
+            completed_fraction: laps_done / total_laps as f32,
+            estimated_remaining_s: leader
+                .last_lap_time_ms
+                .map(|lap_ms| (lap_ms as f32 / 1000.0 * laps_left).round() as u32),
+        })
+    }
+
+    /// Dashboard packet of a player's car, with its place in the running order
+    pub fn dashboard_packet(&self, player_id: &PlayerId) -> Option<DashboardPacket> {
+        let state = self.session.participants.get(player_id)?;
+        let mut order: Vec<&CarState> = self.session.participants.values().collect();
+        order.sort_by(|a, b| compare_race_order(a, b));
+        let position = order.iter().position(|car| car.player_id == *player_id).unwrap_or(0) + 1;
+        let max_rpm = self
+            .car_configs
+            .get(&state.car_config_id)
+            .map(|car| car.max_engine_rpm)
+            .unwrap_or(0.0);
+        Some(DashboardPacket::new(
+            state,
+            &self.session,
+            max_rpm,
+            position.min(u8::MAX as usize) as u8,
+            order.len().min(u8::MAX as usize) as u8,
+        ))
+    }
+
+    /// Get the live leaderboard for broadcast
+    pub fn get_standings(&self) -> ServerMessage {
+        ServerMessage::Standings(StandingsData {
+            session_id: self.session.id,
+            server_tick: self.session.current_tick,
+            entries: self.standings.standings(&self.session, &self.track_config, self.timing),
+        })
+    }
+
+    /// Race events raised since the last call, also queued for the session's clients.
+    ///
+    /// Once the race has finished, the results follow its last events.
+    pub fn take_race_events(&mut self) -> Vec<RaceEvent> {
+        let finishing = self.session.state == SessionState::Finished && !self.results_sent;
+        if finishing {
+            // Events are only logged while racing, which misses the finishing tick
+            self.race_events.update(&self.session, &self.track_config);
+        }
+
+        let events = self.race_events.take_pending();
+        for event in &events {
+            self.broadcaster.queue(ServerMessage::RaceEvent(event.clone()));
+        }
+        if finishing {
+            self.broadcaster.queue(ServerMessage::SessionResults(self.results()));
+        }
+        self.results_sent = self.session.state == SessionState::Finished;
+        events
+    }
+
+    /// Final standings and timeline of the race
+    pub fn results(&self) -> SessionResultsData {
+        SessionResultsData {
+            session_id: self.session.id,
+            server_tick: self.session.current_tick,
+            entries: self.standings.standings(&self.session, &self.track_config, self.timing),
+            timeline: self.race_events.timeline(self.session.race_start_tick),
+        }
+    }
+
+    /// Telemetry, standings and queued events due this tick, addressed to every
+    /// connection watching the session.
+    ///
+    /// Public spectators get a pseudonymized copy when anonymization is on.
+    /// Nothing goes out before the countdown; queued events are dropped then.
+    pub fn take_broadcast(&mut self) -> Vec<BroadcastFrame> {
+        let pending = self.broadcaster.take_pending();
+        let active = matches!(
+            self.session.state,
+            SessionState::Countdown | SessionState::Racing | SessionState::Finished
+        );
+        if !active || self.broadcaster.recipient_count() == 0 {
+            return Vec::new();
+        }
+
+        let tick = self.session.current_tick;
+        let mut messages = Vec::with_capacity(pending.len() + 2);
+        if self.broadcaster.telemetry_due(tick) {
+            messages.push(self.get_telemetry());
+        }
+        // Leaderboard goes out at a much lower rate than telemetry
+        let live = self.session.game_mode != GameMode::Replay;
+        if live && self.session.state == SessionState::Racing && self.broadcaster.standings_due(tick) {
+            messages.push(self.get_standings());
+        }
+        messages.extend(pending);
+
+        let mut frames = self.broadcaster.frames(messages);
+        // Replays only record positional telemetry
+        if live && self.broadcaster.extended_due(tick) {
+            let cars = self.session.participants.values().map(ExtendedCarTelemetry::from).collect();
+            frames.extend(self.broadcaster.extended_frames(tick, cars));
+        }
+        frames
+    }
+
+    /// Send a spectator extended telemetry of every car, or stop. Drivers always
+    /// get it for their own car only
+    pub fn set_extended_telemetry(&mut self, player_id: PlayerId, enabled: bool) -> Result<(), String> {
+        if self.session.participants.contains_key(&player_id) {
+            return Err("Drivers receive extended telemetry for their own car only".to_string());
+        }
+        if self.session.game_mode == GameMode::Replay || self.broadcaster.connection(&player_id).is_none() {
+            return Err("Extended telemetry is only sent to live spectators".to_string());
+        }
+        self.broadcaster.set_extended_telemetry(player_id, enabled);
+        Ok(())
+    }
+
+    /// Send this session's broadcasts to a spectator `delay_ticks` behind the race
+    pub fn bind_delayed(&mut self, player_id: PlayerId, connection_id: ConnectionId, public: bool, delay_ticks: u32) {
+        self.unbind_delayed(&player_id);
+        let index = match self.delayed_feeds.iter().position(|feed| feed.delay_ticks() == delay_ticks) {
+            Some(index) => index,
+            None => {
+                self.delayed_feeds.push(DelayedFeed::new(delay_ticks, self.broadcaster.without_recipients()));
+                self.delayed_feeds.len() - 1
+            }
         };
+        let viewers = self.delayed_feeds[index].broadcaster_mut();
+        if public {
+            viewers.bind_public(player_id, connection_id);
+        } else {
+            viewers.bind(player_id, connection_id);
+        }
+    }
+
+    /// Stop a delayed feed to a spectator, dropping feeds nobody watches
+    fn unbind_delayed(&mut self, player_id: &PlayerId) {
+        for feed in &mut self.delayed_feeds {
+            feed.broadcaster_mut().unbind(player_id);
+        }
+        self.delayed_feeds.retain(|feed| feed.broadcaster().recipient_count() > 0);
+    }
+
+    /// Feeds of spectators watching with a delay, to be filled from the recording
+    pub fn delayed_feeds_mut(&mut self) -> &mut [DelayedFeed] {
+        &mut self.delayed_feeds
+    }
 
-        ServerMessage::Telemetry(telemetry)
+    /// Broadcasts of every delayed feed due at the current tick
+    pub fn take_delayed_broadcast(&mut self) -> Vec<BroadcastFrame> {
+        let tick = self.session.current_tick;
+        self.delayed_feeds
+            .iter_mut()
+            .flat_map(|feed| feed.advance(tick, &self.track_config, &self.car_configs))
+            .collect()
     }
 
     #[allow(dead_code)]
@@ -628,6 +1495,12 @@ impl GameSession {
                 .expect("No car configuration available");
             
             if self.add_player(ai_id, car_id).is_some() {
+                // Spread AI cars over the liveries so they tell apart
+                let livery_count = self.car_configs.get(&car_id).map_or(0, |c| c.liveries.len());
+                if livery_count > 0 {
+                    let livery = (self.session.ai_player_ids.len() % livery_count) as u8;
+                    self.set_livery(ai_id, livery);
+                }
                 self.session.ai_player_ids.push(ai_id);
             }
         }
@@ -644,7 +1517,7 @@ impl GameSession {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use uuid::Uuid;
+    use crate::anonymize::Pseudonymizer;
 
     fn create_test_session() -> GameSession {
         let track = TrackConfig::default();
@@ -652,7 +1525,7 @@ mod tests {
         let mut car_configs = HashMap::new();
         car_configs.insert(car.id, car.clone());
 
-        let session = RaceSession::new(Uuid::new_v4(), track.id, SessionKind::Multiplayer, 8, 0, 3);
+        let session = RaceSession::new(PlayerId::new_v4(), track.id, SessionKind::Multiplayer, 8, 0, 3);
 
         GameSession::new(session, track, car_configs)
     }
@@ -667,7 +1540,7 @@ mod tests {
     #[test]
     fn test_add_player() {
         let mut game_session = create_test_session();
-        let player_id = Uuid::new_v4();
+        let player_id = PlayerId::new_v4();
         let car_id = game_session.car_configs.values().next().unwrap().id;
 
         let position = game_session.add_player(player_id, car_id);
@@ -685,6 +1558,47 @@ mod tests {
         assert!(game_session.session.countdown_ticks_remaining.is_some());
     }
 
+    #[test]
+    fn test_low_tick_rate_sub_steps_match_the_default_rate() {
+        let drive = |timing: physics::TickTiming| {
+            let mut game_session = create_test_session();
+            game_session.timing = timing;
+            let player_id = PlayerId::new_v4();
+            let car_id = game_session.car_configs.values().next().unwrap().id;
+            game_session.add_player(player_id, car_id);
+            game_session.set_game_mode(GameMode::FreePractice);
+
+            let input = PlayerInputData { throttle: 1.0, steering: 0.2, ..Default::default() };
+            let inputs = HashMap::from([(player_id, input)]);
+            for _ in 0..timing.ticks(2) {
+                game_session.tick(&inputs);
+            }
+            let state = &game_session.session.participants[&player_id];
+            (state.pos_x, state.pos_y, state.speed_mps)
+        };
+
+        let default_rate = drive(physics::TickTiming::default());
+        let low_rate = drive(physics::TickTiming::new(60, 0));
+
+        assert!(default_rate.2 > 1.0, "Car should be under way");
+        assert!((default_rate.0 - low_rate.0).abs() < 0.01);
+        assert!((default_rate.1 - low_rate.1).abs() < 0.01);
+        assert!((default_rate.2 - low_rate.2).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_countdown_lasts_the_same_time_at_any_tick_rate() {
+        let mut game_session = create_test_session();
+        game_session.timing = physics::TickTiming::new(60, 0);
+        game_session.set_game_mode(GameMode::Countdown);
+
+        assert_eq!(game_session.session.countdown_ticks_remaining, Some(600));
+        let ServerMessage::Telemetry(telemetry) = game_session.get_telemetry() else {
+            panic!("Expected telemetry");
+        };
+        assert_eq!(telemetry.countdown_ms, Some(10000));
+    }
+
     #[test]
     fn test_tick_countdown() {
         let mut game_session = create_test_session();
@@ -726,6 +1640,79 @@ mod tests {
         assert!(ai_input.steering >= -1.0 && ai_input.steering <= 1.0);
     }
     
+    #[test]
+    fn test_ai_avoids_human_cars_but_not_other_ai() {
+        let mut game_session = create_test_session();
+        let car_id = game_session.car_configs.values().next().unwrap().id;
+
+        let ai_profile = AiDriverProfile::new("Test AI", 90);
+        let ai_player_id = ai_profile.id;
+        let other_ai_profile = AiDriverProfile::new("Other AI", 90);
+        let other_ai_id = other_ai_profile.id;
+        game_session.set_ai_profiles(vec![ai_profile, other_ai_profile]);
+        game_session.add_player(ai_player_id, car_id);
+        game_session.add_player(other_ai_id, car_id);
+
+        // Line the AI up at speed with another car stopped just ahead in its path
+        let mut ai_state = game_session.session.participants[&ai_player_id].clone();
+        let (sin_yaw, cos_yaw) = ai_state.yaw_rad.sin_cos();
+        ai_state.vel_x = cos_yaw * 30.0;
+        ai_state.vel_y = sin_yaw * 30.0;
+        ai_state.speed_mps = 30.0;
+        let mut blocker = ai_state.clone();
+        blocker.pos_x += cos_yaw * 15.0;
+        blocker.pos_y += sin_yaw * 15.0;
+        blocker.vel_x = 0.0;
+        blocker.vel_y = 0.0;
+        blocker.speed_mps = 0.0;
+        game_session.session.participants.insert(ai_player_id, ai_state);
+
+        blocker.player_id = other_ai_id;
+        game_session.session.participants.insert(other_ai_id, blocker.clone());
+        let behind_ai = game_session.generate_ai_input(&ai_player_id);
+
+        game_session.session.participants.remove(&other_ai_id);
+        let human_id = PlayerId::new_v4();
+        blocker.player_id = human_id;
+        game_session.session.participants.insert(human_id, blocker);
+        let behind_human = game_session.generate_ai_input(&ai_player_id);
+
+        assert!(behind_human.throttle < behind_ai.throttle || behind_human.brake > behind_ai.brake);
+        assert_eq!(behind_human.throttle, 0.0);
+    }
+
+    #[test]
+    fn test_stranded_ai_is_put_back_on_track_outside_races() {
+        let mut game_session = create_test_session();
+        let car_id = game_session.car_configs.values().next().unwrap().id;
+        let ai_profile = AiDriverProfile::new("Test AI", 90);
+        let ai_player_id = ai_profile.id;
+        game_session.set_ai_profiles(vec![ai_profile]);
+        game_session.add_player(ai_player_id, car_id);
+        game_session.session.ai_player_ids.push(ai_player_id);
+        game_session.set_game_mode(GameMode::FreePractice);
+
+        // Rolled over and stopped in the runoff
+        {
+            let state = game_session.session.participants.get_mut(&ai_player_id).unwrap();
+            state.pos_x += 30.0;
+            state.is_overturned = true;
+            state.damage.is_drivable = false;
+        }
+
+        let timeout_ticks = game_session.timing.ticks(crate::ai_driver::RECOVERY_RESET_TIMEOUT_S as u32 + 3);
+        for _ in 0..timeout_ticks {
+            let inputs: HashMap<PlayerId, PlayerInputData> =
+                HashMap::from([(ai_player_id, game_session.generate_ai_input(&ai_player_id))]);
+            game_session.tick(&inputs);
+        }
+
+        let state = &game_session.session.participants[&ai_player_id];
+        assert!(!state.is_overturned);
+        assert!(state.damage.is_drivable);
+        assert!(state.lateral_offset_m.abs() < 5.0, "offset {}", state.lateral_offset_m);
+    }
+
     #[test]
     fn test_ai_spawn_with_profiles() {
         use crate::ai_driver::generate_default_ai_profiles;
@@ -736,7 +1723,7 @@ mod tests {
         car_configs.insert(car.id, car.clone());
         
         // Create session with 2 AI drivers
-        let session = RaceSession::new(Uuid::new_v4(), track.id, SessionKind::Multiplayer, 8, 2, 3);
+        let session = RaceSession::new(PlayerId::new_v4(), track.id, SessionKind::Multiplayer, 8, 2, 3);
         let ai_profiles = generate_default_ai_profiles(2);
         
         let mut game_session = GameSession::with_ai_profiles(session, track, car_configs, ai_profiles);
@@ -754,6 +1741,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_simulation_state_replays_tick_for_tick() {
+        use crate::ai_driver::generate_default_ai_profiles;
+
+        let track = TrackConfig::default();
+        let car = CarConfig::default();
+        let car_configs = HashMap::from([(car.id, car)]);
+        let session = RaceSession::new(PlayerId::new_v4(), track.id, SessionKind::Multiplayer, 8, 6, 3);
+        let mut game_session = GameSession::with_ai_profiles(session, track.clone(), car_configs.clone(), generate_default_ai_profiles(6));
+        game_session.spawn_ai_drivers();
+        game_session.set_game_mode(GameMode::FreePractice);
+
+        let ai_inputs = |game_session: &GameSession| -> HashMap<PlayerId, PlayerInputData> {
+            game_session
+                .session
+                .participants
+                .keys()
+                .map(|id| (*id, game_session.generate_ai_input(id)))
+                .collect()
+        };
+        for _ in 0..60 {
+            let inputs = ai_inputs(&game_session);
+            game_session.tick(&inputs);
+        }
+
+        // Goes through the replay file format on the way
+        let bytes = rmp_serde::to_vec_named(&game_session.simulation_state()).unwrap();
+        let mut recorded = Vec::new();
+        for _ in 0..240 {
+            let inputs = ai_inputs(&game_session);
+            game_session.tick(&inputs);
+            recorded.push((inputs, game_session.state_hash()));
+        }
+
+        // Fresh maps iterate in a different order; the result must not change
+        for _ in 0..2 {
+            let state: SimulationState = rmp_serde::from_slice(&bytes).unwrap();
+            let mut replayed = GameSession::from_simulation_state(state, track.clone(), car_configs.clone());
+            for (tick, (inputs, hash)) in recorded.iter().enumerate() {
+                replayed.tick(inputs);
+                assert_eq!(replayed.state_hash(), *hash, "diverged {} ticks in", tick + 1);
+            }
+        }
+    }
+
     // --- Game Mode Tests ---
 
     #[test]
@@ -785,7 +1817,7 @@ mod tests {
         game_session.set_game_mode(GameMode::Sandbox);
 
         // Add a player
-        let player_id = Uuid::new_v4();
+        let player_id = PlayerId::new_v4();
         let car_id = game_session.car_configs.values().next().unwrap().id;
         game_session.add_player(player_id, car_id);
 
@@ -838,7 +1870,7 @@ mod tests {
         let mut game_session = create_test_session();
 
         // Add a demo car
-        let player_id = Uuid::new_v4();
+        let player_id = PlayerId::new_v4();
         let car_id = game_session.car_configs.values().next().unwrap().id;
         game_session.add_player(player_id, car_id);
 
@@ -853,16 +1885,16 @@ mod tests {
         let mut game_session = create_test_session();
 
         // Add a demo car
-        let player_id = Uuid::new_v4();
+        let player_id = PlayerId::new_v4();
         let car_id = game_session.car_configs.values().next().unwrap().id;
         game_session.add_player(player_id, car_id);
 
         // Add racing line to track
         game_session.track_config.raceline = vec![
-            RacelinePoint { x: 0.0, y: 0.0, z: 0.0 },
-            RacelinePoint { x: 100.0, y: 0.0, z: 0.0 },
-            RacelinePoint { x: 100.0, y: 100.0, z: 0.0 },
-            RacelinePoint { x: 0.0, y: 100.0, z: 0.0 },
+            RacelinePoint { x: 0.0, y: 0.0, z: 0.0, target_speed_mps: 0.0 },
+            RacelinePoint { x: 100.0, y: 0.0, z: 0.0, target_speed_mps: 0.0 },
+            RacelinePoint { x: 100.0, y: 100.0, z: 0.0, target_speed_mps: 0.0 },
+            RacelinePoint { x: 0.0, y: 100.0, z: 0.0, target_speed_mps: 0.0 },
         ];
 
         game_session.set_game_mode(GameMode::DemoLap);
@@ -900,14 +1932,14 @@ mod tests {
         let mut game_session = create_test_session();
 
         // Add a demo car
-        let player_id = Uuid::new_v4();
+        let player_id = PlayerId::new_v4();
         let car_id = game_session.car_configs.values().next().unwrap().id;
         game_session.add_player(player_id, car_id);
 
         // Add racing line
         game_session.track_config.raceline = vec![
-            RacelinePoint { x: 0.0, y: 0.0, z: 0.0 },
-            RacelinePoint { x: 100.0, y: 0.0, z: 0.0 },
+            RacelinePoint { x: 0.0, y: 0.0, z: 0.0, target_speed_mps: 0.0 },
+            RacelinePoint { x: 100.0, y: 0.0, z: 0.0, target_speed_mps: 0.0 },
         ];
 
         game_session.set_game_mode(GameMode::DemoLap);
@@ -937,7 +1969,7 @@ mod tests {
         game_session.set_game_mode(GameMode::FreePractice);
 
         // Add a player
-        let player_id = Uuid::new_v4();
+        let player_id = PlayerId::new_v4();
         let car_id = game_session.car_configs.values().next().unwrap().id;
         game_session.add_player(player_id, car_id);
 
@@ -963,6 +1995,468 @@ mod tests {
         assert_ne!(initial_pos_x, final_pos_x);
     }
 
+    #[test]
+    fn test_applied_setup_drives_the_physics() {
+        let mut game_session = create_test_session();
+        game_session.set_game_mode(GameMode::FreePractice);
+        let car = game_session.car_configs.values().next().unwrap().clone();
+        let (stock, tuned) = (PlayerId::new_v4(), PlayerId::new_v4());
+        game_session.add_player(stock, car.id);
+        game_session.add_player(tuned, car.id);
+
+        let high_downforce = CarSetup { lift_coefficient_rear: -0.8, ..CarSetup::baseline(&car) };
+        assert!(game_session.apply_setup(tuned, high_downforce.clone()).is_ok());
+        assert_eq!(game_session.setup(&tuned), Some(&high_downforce));
+        assert!(game_session
+            .apply_setup(stock, CarSetup { brake_bias_front: 1.0, ..CarSetup::baseline(&car) })
+            .is_err());
+        assert!(game_session.apply_setup(PlayerId::new_v4(), high_downforce.clone()).is_err());
+
+        for state in game_session.session.participants.values_mut() {
+            state.vel_x = 40.0;
+            state.speed_mps = 40.0;
+        }
+        game_session.tick(&HashMap::new());
+
+        let downforce = |id: &PlayerId| game_session.session.participants[id].downforce_rear_n;
+        assert!(downforce(&tuned) > downforce(&stock) * 2.0);
+
+        // Leaving the session forgets the setup
+        game_session.remove_player(&tuned);
+        assert!(game_session.setup(&tuned).is_none());
+    }
+
+    #[test]
+    fn test_balance_of_performance_applies_to_stock_car_and_setups() {
+        let mut game_session = create_test_session();
+        game_session.set_game_mode(GameMode::FreePractice);
+        let stock = game_session.car_configs.values().next().unwrap().clone();
+        let player_id = PlayerId::new_v4();
+        game_session.add_player(player_id, stock.id);
+        assert!(game_session.apply_setup(player_id, CarSetup::baseline(&stock)).is_ok());
+
+        let bop = BalanceOfPerformance { ballast_kg: 30.0, power_multiplier: 0.9, fuel_capacity_liters: Some(50.0) };
+        assert!(game_session.set_balance(stock.id, bop).is_ok());
+        // Setting it again replaces rather than stacks
+        assert!(game_session.set_balance(stock.id, bop).is_ok());
+        let balanced = &game_session.car_configs[&stock.id];
+        assert_eq!(balanced.mass_kg, stock.mass_kg + 30.0);
+        assert!((balanced.max_engine_torque_nm - stock.max_engine_torque_nm * 0.9).abs() < 1e-3);
+        assert_eq!(balanced.fuel.capacity_liters, 50.0);
+        assert_eq!(game_session.setups[&player_id].1.mass_kg, stock.mass_kg + 30.0);
+        assert_eq!(game_session.balance().collect::<Vec<_>>(), vec![(stock.id, &bop)]);
+
+        assert!(game_session
+            .set_balance(stock.id, BalanceOfPerformance { power_multiplier: 3.0, ..bop })
+            .is_err());
+        assert!(game_session.set_balance(CarConfigId::new_v4(), bop).is_err());
+
+        // Neutral balance restores the car as loaded
+        assert!(game_session.set_balance(stock.id, BalanceOfPerformance::default()).is_ok());
+        assert_eq!(game_session.car_configs[&stock.id].mass_kg, stock.mass_kg);
+        assert_eq!(game_session.setups[&player_id].1.mass_kg, stock.mass_kg);
+        assert_eq!(game_session.balance().count(), 0);
+    }
+
+    #[test]
+    fn test_fixed_setup_sessions_reject_changes() {
+        let mut game_session = create_test_session();
+        game_session.setup_policy = SetupPolicy::Fixed;
+        let car = game_session.car_configs.values().next().unwrap().clone();
+        let player_id = PlayerId::new_v4();
+        game_session.add_player(player_id, car.id);
+
+        assert!(game_session.apply_setup(player_id, CarSetup::baseline(&car)).is_err());
+        assert_eq!(game_session.setups().count(), 0);
+    }
+
+    #[test]
+    fn test_ghost_sessions_let_practice_cars_pass_through_each_other() {
+        let overlap = |policy: CollisionPolicy| {
+            let mut game_session = create_test_session();
+            game_session.collision_policy = policy;
+            let car_id = game_session.car_configs.values().next().unwrap().id;
+            let (first, second) = (PlayerId::new_v4(), PlayerId::new_v4());
+            game_session.add_player(first, car_id);
+            game_session.add_player(second, car_id);
+            game_session.set_game_mode(GameMode::FreePractice);
+            let (x, y) = {
+                let state = &game_session.session.participants[&first];
+                (state.pos_x, state.pos_y)
+            };
+            let second_state = game_session.session.participants.get_mut(&second).unwrap();
+            second_state.pos_x = x;
+            second_state.pos_y = y;
+
+            game_session.tick(&HashMap::new());
+            let ServerMessage::Telemetry(telemetry) = game_session.get_telemetry() else {
+                panic!("Expected telemetry");
+            };
+            let state = &game_session.session.participants[&first];
+            (state.is_colliding, telemetry.car_states.iter().all(|car| car.is_ghost))
+        };
+
+        assert_eq!(overlap(CollisionPolicy::Ghost), (false, true));
+        assert_eq!(overlap(CollisionPolicy::Solid), (true, false));
+    }
+
+    #[test]
+    fn test_driver_aids_limited_by_session() {
+        let mut game_session = create_test_session();
+        game_session.set_game_mode(GameMode::FreePractice);
+        game_session.aid_limits = DriverAids { abs: AidLevel::Low, ..Default::default() };
+        let car = game_session.car_configs.values().next().unwrap().clone();
+        let player_id = PlayerId::new_v4();
+        game_session.add_player(player_id, car.id);
+
+        let abs_high = DriverAids { abs: AidLevel::High, ..Default::default() };
+        let abs_low = DriverAids { abs: AidLevel::Low, ..Default::default() };
+        assert!(game_session.set_aids(player_id, abs_high).is_err());
+        assert!(game_session.set_aids(player_id, DriverAids { traction_control: AidLevel::Low, ..abs_low }).is_err());
+        assert!(game_session.set_aids(PlayerId::new_v4(), abs_low).is_err());
+        assert!(game_session.set_aids(player_id, abs_low).is_ok());
+        assert_eq!(game_session.aids(&player_id), abs_low);
+
+        // Telemetry reports the aids the car ran with
+        game_session.tick(&HashMap::new());
+        let telemetry = CarStateTelemetry::from(&game_session.session.participants[&player_id]);
+        assert_eq!(telemetry.aids, abs_low);
+
+        game_session.remove_player(&player_id);
+        assert_eq!(game_session.aids(&player_id), DriverAids::default());
+    }
+
+    #[test]
+    fn test_rolling_start_caps_speed_and_sends_pace_car() {
+        let mut game_session = create_test_session();
+        let player_id = PlayerId::new_v4();
+        let car_id = game_session.car_configs.values().next().unwrap().id;
+        game_session.add_player(player_id, car_id);
+
+        game_session.set_game_mode(GameMode::RollingStart);
+        assert_eq!(game_session.session.state, SessionState::Racing);
+        assert!(game_session.rolling_start.is_some());
+
+        let mut inputs = HashMap::new();
+        inputs.insert(player_id, PlayerInputData {
+            throttle: 1.0,
+            brake: 0.0,
+            steering: 0.0,
+            gear: None,
+            clutch: None,
+        });
+        for _ in 0..240 * 5 {
+            game_session.tick(&inputs);
+        }
+
+        let state = &game_session.session.participants[&player_id];
+        assert!(state.speed_mps <= crate::rolling_start::PACE_SPEED_MPS + 0.01);
+        assert_eq!(state.current_lap, 0);
+
+        match game_session.get_telemetry() {
+            ServerMessage::Telemetry(telemetry) => assert!(telemetry.pace_car.is_some()),
+            _ => panic!("Expected telemetry"),
+        }
+    }
+
+    #[test]
+    fn test_formation_lap_proceeds_to_countdown() {
+        let mut game_session = create_test_session();
+        let player_id = PlayerId::new_v4();
+        let car_id = game_session.car_configs.values().next().unwrap().id;
+        game_session.add_player(player_id, car_id);
+
+        game_session.set_game_mode(GameMode::FormationLap);
+        assert_eq!(game_session.session.state, SessionState::Racing);
+        assert!(game_session.formation_lap.is_some());
+
+        // Nobody drives, so the time limit expires with the car out of its box
+        for _ in 0..DEFAULT_TIME_LIMIT_S as u32 * 240 {
+            game_session.tick(&HashMap::new());
+        }
+
+        assert!(game_session.formation_lap.is_none());
+        assert_eq!(game_session.session.game_mode, GameMode::Countdown);
+        assert_eq!(game_session.session.state, SessionState::Countdown);
+        let events = game_session.race_events.take_pending();
+        assert!(events.iter().any(|e| e.kind == RaceEventKind::PitLaneStart && e.player_id == player_id));
+    }
+
+    #[test]
+    fn test_open_practice_resets_on_interval() {
+        let mut game_session = create_test_session();
+        game_session.set_game_mode(GameMode::FreePractice);
+        game_session.open_practice = Some(OpenPractice {
+            reset_interval_ticks: 480,
+            last_reset_tick: 0,
+        });
+
+        let player_id = PlayerId::new_v4();
+        let car_id = game_session.car_configs.values().next().unwrap().id;
+        game_session.add_player(player_id, car_id);
+        let start_x = game_session.session.participants[&player_id].pos_x;
+
+        let mut inputs = HashMap::new();
+        inputs.insert(player_id, PlayerInputData {
+            throttle: 1.0,
+            ..Default::default()
+        });
+        for _ in 0..479 {
+            game_session.tick(&inputs);
+            assert!(!game_session.was_reset_this_tick());
+        }
+        assert_ne!(game_session.session.participants[&player_id].pos_x, start_x);
+
+        game_session.tick(&inputs);
+        assert!(game_session.was_reset_this_tick());
+        let state = &game_session.session.participants[&player_id];
+        assert_eq!(state.pos_x, start_x);
+        assert_eq!(state.speed_mps, 0.0);
+        assert_eq!(state.current_lap, 0);
+    }
+
+    #[test]
+    fn test_request_finish_classifies_running_cars() {
+        let mut game_session = create_test_session();
+        let car_id = game_session.car_configs.values().next().unwrap().id;
+        let (winner, leader, chaser) = (PlayerId::new_v4(), PlayerId::new_v4(), PlayerId::new_v4());
+        for player_id in [winner, leader, chaser] {
+            game_session.add_player(player_id, car_id);
+        }
+        assert!(game_session.request_finish().is_err(), "Only a race can be finished");
+
+        game_session.session.state = SessionState::Racing;
+        {
+            let participants = &mut game_session.session.participants;
+            participants.get_mut(&winner).unwrap().finish_position = Some(1);
+            participants.get_mut(&leader).unwrap().current_lap = 3;
+            participants.get_mut(&chaser).unwrap().current_lap = 2;
+        }
+        game_session.request_finish().unwrap();
+        game_session.tick(&HashMap::new());
+
+        assert_eq!(game_session.session.state, SessionState::Finished);
+        let position = |player_id| game_session.session.participants[&player_id].finish_position;
+        assert_eq!((position(winner), position(leader), position(chaser)), (Some(1), Some(2), Some(3)));
+        assert!(game_session.request_finish().is_err());
+    }
+
+    #[test]
+    fn test_race_progress_follows_leader_pace() {
+        let mut game_session = create_test_session();
+        let car_id = game_session.car_configs.values().next().unwrap().id;
+        let (leader, chaser) = (PlayerId::new_v4(), PlayerId::new_v4());
+        game_session.add_player(leader, car_id);
+        game_session.add_player(chaser, car_id);
+        assert!(game_session.race_progress().is_none(), "No progress before the race");
+
+        let track_length_m = game_session.track_config.centerline.last().unwrap().distance_from_start_m;
+        game_session.session.state = SessionState::Racing;
+        {
+            let leader = game_session.session.participants.get_mut(&leader).unwrap();
+            leader.current_lap = 2;
+            leader.track_progress = track_length_m / 2.0;
+            leader.last_lap_time_ms = Some(90_000);
+            let chaser = game_session.session.participants.get_mut(&chaser).unwrap();
+            chaser.current_lap = 1;
+            chaser.track_progress = track_length_m * 0.9;
+        }
+
+        // Halfway through lap 2 of 3 at 90 s a lap
+        let progress = game_session.race_progress().unwrap();
+        assert_eq!((progress.leader_lap, progress.total_laps), (2, 3));
+        assert!((progress.completed_fraction - 0.5).abs() < 0.01);
+        assert_eq!(progress.estimated_remaining_s, Some(135));
+        let ServerMessage::Telemetry(telemetry) = game_session.get_telemetry() else {
+            panic!("Expected telemetry");
+        };
+        assert_eq!(telemetry.race_progress, Some(progress));
+
+        game_session.session.state = SessionState::Finished;
+        let progress = game_session.race_progress().unwrap();
+        assert_eq!((progress.leader_lap, progress.completed_fraction, progress.estimated_remaining_s), (3, 1.0, Some(0)));
+    }
+
+    #[test]
+    fn test_results_follow_the_last_race_events() {
+        let mut game_session = create_test_session();
+        let car_id = game_session.car_configs.values().next().unwrap().id;
+        let (pole, winner) = (PlayerId::new_v4(), PlayerId::new_v4());
+        game_session.add_player(pole, car_id);
+        game_session.add_player(winner, car_id);
+        let connection_id = ConnectionId::new_v4();
+        game_session.broadcaster.bind(pole, connection_id);
+
+        game_session.set_game_mode(GameMode::FreePractice);
+        game_session.session.race_start_tick = Some(1);
+        game_session.tick(&HashMap::new());
+        game_session.take_race_events();
+        game_session.take_broadcast();
+
+        game_session.session.participants.get_mut(&winner).unwrap().finish_position = Some(1);
+        game_session.session.participants.get_mut(&pole).unwrap().finish_position = Some(2);
+        game_session.session.state = SessionState::Finished;
+
+        // The finishes of the last tick are still logged and go out before the results
+        let events = game_session.take_race_events();
+        assert_eq!(events.iter().filter(|e| e.kind == RaceEventKind::Finished).count(), 2);
+        let messages: Vec<ServerMessage> = game_session
+            .take_broadcast()
+            .into_iter()
+            .flat_map(|frame| frame.messages)
+            .collect();
+        let Some(ServerMessage::SessionResults(results)) = messages.last() else {
+            panic!("Expected the results last, got {:?}", messages);
+        };
+        assert_eq!(results.session_id, game_session.session.id);
+        let order: Vec<PlayerId> = results.entries.iter().map(|e| e.player_id).collect();
+        assert_eq!(order, vec![winner, pole]);
+        let kinds: Vec<TimelineEventKind> = results.timeline.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![TimelineEventKind::RaceStart, TimelineEventKind::Finished, TimelineEventKind::Finished]
+        );
+
+        // Results are sent once per finish
+        game_session.tick(&HashMap::new());
+        game_session.take_race_events();
+        let resent = game_session
+            .take_broadcast()
+            .into_iter()
+            .flat_map(|frame| frame.messages)
+            .any(|message| matches!(message, ServerMessage::SessionResults(_)));
+        assert!(!resent);
+    }
+
+    #[test]
+    fn test_broadcast_frames_follow_bound_connections() {
+        let mut game_session = create_test_session();
+        let player_id = PlayerId::new_v4();
+        let connection_id = ConnectionId::new_v4();
+        let car_id = game_session.car_configs.values().next().unwrap().id;
+        game_session.add_player(player_id, car_id);
+        game_session.broadcaster.bind(player_id, connection_id);
+
+        // Nothing goes out while the session sits in the lobby
+        game_session.tick(&HashMap::new());
+        assert!(game_session.take_broadcast().is_empty());
+
+        game_session.set_game_mode(GameMode::FreePractice);
+        game_session.broadcaster.set_telemetry_rate_hz(60);
+        let mut telemetry_frames = 0;
+        let mut weather_updates = 0;
+        for _ in 0..8 {
+            game_session.tick(&HashMap::new());
+            for frame in game_session.take_broadcast() {
+                assert_eq!(frame.recipients, vec![connection_id]);
+                for message in &frame.messages {
+                    match message {
+                        ServerMessage::Telemetry(_) => telemetry_frames += 1,
+                        ServerMessage::WeatherUpdate(_) => weather_updates += 1,
+                        _ => {}
+                    }
+                }
+            }
+        }
+        assert_eq!(telemetry_frames, 2);
+        assert_eq!(weather_updates, 1);
+
+        game_session.remove_player(&player_id);
+        game_session.tick(&HashMap::new());
+        assert!(game_session.take_broadcast().is_empty());
+    }
+
+    #[test]
+    fn test_extended_telemetry_goes_to_own_driver_and_opted_in_spectators() {
+        let mut game_session = create_test_session();
+        let (driver_a, driver_b) = (PlayerId::new_v4(), PlayerId::new_v4());
+        let (spectator, viewer) = (PlayerId::new_v4(), PlayerId::new_v4());
+        let connections: Vec<ConnectionId> = (0..4).map(|_| ConnectionId::new_v4()).collect();
+        let car_id = game_session.car_configs.values().next().unwrap().id;
+        game_session.add_player(driver_a, car_id);
+        game_session.add_player(driver_b, car_id);
+        game_session.broadcaster.bind(driver_a, connections[0]);
+        game_session.broadcaster.bind(driver_b, connections[1]);
+        game_session.broadcaster.bind_public(spectator, connections[2]);
+        game_session.broadcaster.bind_public(viewer, connections[3]);
+        let pseudonyms = Pseudonymizer::new("league-secret");
+        game_session.broadcaster.set_pseudonyms(Some(pseudonyms.clone()));
+        game_session.set_game_mode(GameMode::FreePractice);
+
+        assert!(game_session.set_extended_telemetry(driver_a, true).is_err());
+        assert!(game_session.set_extended_telemetry(PlayerId::new_v4(), true).is_err());
+        game_session.set_extended_telemetry(spectator, true).unwrap();
+
+        let mut received: HashMap<ConnectionId, Vec<Vec<PlayerId>>> = HashMap::new();
+        for _ in 0..48 {
+            game_session.tick(&HashMap::new());
+            for frame in game_session.take_broadcast() {
+                for message in &frame.messages {
+                    if let ServerMessage::ExtendedTelemetry(extended) = message {
+                        let mut ids: Vec<PlayerId> = extended.cars.iter().map(|car| car.player_id).collect();
+                        ids.sort();
+                        for conn in &frame.recipients {
+                            received.entry(*conn).or_default().push(ids.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        // Ten per second at 240 Hz: every 24 ticks
+        assert_eq!(received[&connections[0]], vec![vec![driver_a]; 2]);
+        assert_eq!(received[&connections[1]], vec![vec![driver_b]; 2]);
+        let mut anonymized = vec![pseudonyms.player_id(driver_a), pseudonyms.player_id(driver_b)];
+        anonymized.sort();
+        assert_eq!(received[&connections[2]], vec![anonymized; 2]);
+        assert!(!received.contains_key(&connections[3]));
+    }
+
+    #[test]
+    fn test_public_spectators_get_pseudonymized_frames() {
+        let mut game_session = create_test_session();
+        let player_id = PlayerId::new_v4();
+        let spectator_id = PlayerId::new_v4();
+        let (player_conn, spectator_conn) = (ConnectionId::new_v4(), ConnectionId::new_v4());
+        let car_id = game_session.car_configs.values().next().unwrap().id;
+        game_session.add_player(player_id, car_id);
+        game_session.broadcaster.bind(player_id, player_conn);
+        game_session.broadcaster.bind_public(spectator_id, spectator_conn);
+        game_session.set_game_mode(GameMode::FreePractice);
+
+        let telemetry_ids = |frame: &BroadcastFrame| -> Vec<PlayerId> {
+            frame
+                .messages
+                .iter()
+                .filter_map(|message| match message {
+                    ServerMessage::Telemetry(telemetry) => Some(telemetry.car_states[0].player_id),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        // Without anonymization everyone shares one frame
+        game_session.tick(&HashMap::new());
+        let frames = game_session.take_broadcast();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(telemetry_ids(&frames[0]), vec![player_id]);
+
+        let pseudonyms = Pseudonymizer::new("league-secret");
+        game_session.broadcaster.set_pseudonyms(Some(pseudonyms.clone()));
+        game_session.tick(&HashMap::new());
+        let frames = game_session.take_broadcast();
+        assert_eq!(frames.len(), 2);
+        for frame in &frames {
+            let expected = if frame.recipients == vec![spectator_conn] {
+                pseudonyms.player_id(player_id)
+            } else {
+                assert_eq!(frame.recipients, vec![player_conn]);
+                player_id
+            };
+            assert_eq!(telemetry_ids(frame), vec![expected]);
+        }
+    }
+
     #[test]
     fn test_set_game_mode() {
         let mut game_session = create_test_session();
@@ -1025,12 +2519,92 @@ mod tests {
         assert_eq!(game_session.session.current_tick, initial_tick + 1);
     }
 
+    #[test]
+    fn test_replay_playback_streams_recorded_telemetry() {
+        use crate::replay::{ReplayFrame, ReplayHighlight, ReplayMetadata, REPLAY_FORMAT_VERSION};
+
+        let mut game_session = create_test_session();
+        let viewer = PlayerId::new_v4();
+        let connection_id = ConnectionId::new_v4();
+        game_session.broadcaster.bind(viewer, connection_id);
+        game_session.broadcaster.set_telemetry_rate_hz(240);
+
+        let recorded_session = SessionId::new_v4();
+        let metadata = ReplayMetadata {
+            format_version: REPLAY_FORMAT_VERSION,
+            session_id: recorded_session,
+            track_config_id: game_session.track_config.id,
+            track_name: game_session.track_config.name.clone(),
+            recorded_at: 0,
+            duration_ticks: 10,
+            tick_rate: 240,
+            participants: vec![],
+            highlights: vec![ReplayHighlight {
+                kind: HighlightKind::Overtake,
+                start_tick: 505,
+                end_tick: 508,
+                player_id: PlayerId::new_v4(),
+                other_player_id: None,
+            }],
+        };
+        let frames = (500..510)
+            .map(|tick| ReplayFrame {
+                tick,
+                telemetry: Telemetry {
+                    server_tick: tick,
+                    session_state: SessionState::Racing,
+                    game_mode: GameMode::Race,
+                    countdown_ms: None,
+                    car_states: vec![],
+                    pace_car: None,
+                    race_progress: None,
+                },
+            })
+            .collect();
+        game_session.start_replay(ReplayPlayer::new(metadata, frames, vec![]));
+        assert_eq!(game_session.session.game_mode, GameMode::Replay);
+
+        let mut ticks_seen = Vec::new();
+        let mut states = Vec::new();
+        for _ in 0..12 {
+            game_session.tick(&HashMap::new());
+            for frame in game_session.take_broadcast() {
+                for message in frame.messages {
+                    match message {
+                        ServerMessage::Telemetry(t) => {
+                            assert_eq!(t.game_mode, GameMode::Replay);
+                            ticks_seen.push(t.server_tick);
+                        }
+                        ServerMessage::ReplayState(state) => states.push(state),
+                        ServerMessage::Standings(_) | ServerMessage::WeatherUpdate(_) => {
+                            panic!("replays carry no live standings or weather")
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        assert_eq!(ticks_seen[0], 501);
+        assert_eq!(*ticks_seen.last().unwrap(), 509);
+        // Started playing, then paused itself on the last frame
+        assert_eq!(states.len(), 2);
+        assert!(states[0].playing && !states[1].playing);
+        assert_eq!((states[1].session_id, states[1].first_tick, states[1].last_tick), (recorded_session, 500, 509));
+
+        game_session.control_replay(ReplayControl::Seek(503)).unwrap();
+        assert_eq!(game_session.replay_state().unwrap().current_tick, 503);
+        game_session.control_replay(ReplayControl::JumpToHighlight(0)).unwrap();
+        assert_eq!(game_session.replay_state().unwrap().current_tick, 505);
+        assert!(game_session.control_replay(ReplayControl::JumpToHighlight(1)).is_err());
+        assert!(create_test_session().control_replay(ReplayControl::Play).is_err());
+    }
+
     #[test]
     fn test_demolap_without_raceline() {
         let mut game_session = create_test_session();
 
         // Add a demo car
-        let player_id = Uuid::new_v4();
+        let player_id = PlayerId::new_v4();
         let car_id = game_session.car_configs.values().next().unwrap().id;
         game_session.add_player(player_id, car_id);
 
@@ -1080,7 +2654,7 @@ mod tests {
             let y = angle.sin() * radius;
             let distance = (i as f32 / num_points as f32) * 2.0 * std::f32::consts::PI * radius;
 
-            raceline.push(RacelinePoint { x, y, z: 0.0 });
+            raceline.push(RacelinePoint { x, y, z: 0.0, target_speed_mps: 0.0 });
             centerline.push(crate::data::TrackPoint {
                 x,
                 y,
@@ -1094,6 +2668,7 @@ mod tests {
                 heading_rad: angle + std::f32::consts::FRAC_PI_2,
                 grip_modifier: 1.0,
                 surface_type: SurfaceType::Asphalt,
+                runoff_surface: SurfaceType::Grass,
             });
         }
 
@@ -1105,7 +2680,7 @@ mod tests {
         let mut car_configs = HashMap::new();
         car_configs.insert(car.id, car.clone());
 
-        let session = RaceSession::new(Uuid::new_v4(), track.id, SessionKind::Practice, 8, 1, 1);
+        let session = RaceSession::new(PlayerId::new_v4(), track.id, SessionKind::Practice, 8, 1, 1);
         let ai_profiles = generate_default_ai_profiles(1);
 
         let mut game_session = GameSession::with_ai_profiles(session, track, car_configs, ai_profiles);
@@ -1120,16 +2695,8 @@ mod tests {
         // Set to FreePractice mode to test AI driving
         game_session.set_game_mode(GameMode::FreePractice);
 
-        // Give the AI car initial speed to avoid stall (physics limitation)
-        // In a real sim, clutch modulation would handle launch
-        if let Some(ai_state) = game_session.session.participants.get_mut(&ai_player_id) {
-            ai_state.speed_mps = 5.0;  // Start with 5 m/s (18 km/h)
-            ai_state.vel_x = 5.0;
-            ai_state.engine_rpm = 2000.0;  // Start engine above idle
-        }
-
-        // Run simulation for 2 seconds (480 ticks at 240Hz)
-        for _ in 0..480 {
+        // Run simulation for 3 seconds (720 ticks at 240Hz), launching from standstill
+        for _ in 0..720 {
             // Generate AI inputs for all AI players
             let mut inputs = HashMap::new();
             for ai_id in &game_session.session.ai_player_ids {
@@ -1148,7 +2715,7 @@ mod tests {
             + (ai_state.pos_y - start_pos.y).powi(2)).sqrt();
 
         assert!(distance_moved > 10.0,
-            "AI should have moved at least 10m from start position (started at 5m/s), moved: {}m", distance_moved);
+            "AI should launch from standstill and move at least 10m, moved: {}m", distance_moved);
 
         // AI should have positive speed
         assert!(ai_state.speed_mps > 0.0,
@@ -1181,7 +2748,7 @@ mod tests {
 
         // AI should be in a reasonable gear
         if let Some(gear) = ai_input.gear {
-            assert!(gear >= 1 && gear <= 6, "AI gear should be between 1 and 6, got: {}", gear);
+            assert!((1..=6).contains(&gear), "AI gear should be between 1 and 6, got: {}", gear);
         }
     }
 
@@ -1202,7 +2769,7 @@ mod tests {
             let y = 0.0;
             let distance = i as f32 * 10.0;
 
-            raceline.push(RacelinePoint { x, y, z: 0.0 });
+            raceline.push(RacelinePoint { x, y, z: 0.0, target_speed_mps: 0.0 });
             centerline.push(crate::data::TrackPoint {
                 x,
                 y,
@@ -1216,6 +2783,7 @@ mod tests {
                 heading_rad: 0.0, // Straight track, heading east
                 grip_modifier: 1.0,
                 surface_type: SurfaceType::Asphalt,
+                runoff_surface: SurfaceType::Grass,
             });
         }
 
@@ -1230,7 +2798,7 @@ mod tests {
         let mut car_configs = HashMap::new();
         car_configs.insert(car.id, car.clone());
 
-        let session = RaceSession::new(Uuid::new_v4(), track.id, SessionKind::Practice, 8, 1, 1);
+        let session = RaceSession::new(PlayerId::new_v4(), track.id, SessionKind::Practice, 8, 1, 1);
 
         let mut game_session = GameSession::with_ai_profiles(
             session,
@@ -1245,13 +2813,6 @@ mod tests {
         // Start in free practice mode
         game_session.set_game_mode(GameMode::FreePractice);
 
-        // Give the AI car initial speed to avoid stall
-        if let Some(ai_state) = game_session.session.participants.get_mut(&ai_player_id) {
-            ai_state.speed_mps = 10.0;  // Start with 10 m/s
-            ai_state.vel_x = 10.0;
-            ai_state.engine_rpm = 3000.0;
-        }
-
         // Run for 3 seconds to let AI stabilize
         for _ in 0..720 {
             let mut inputs = HashMap::new();
@@ -1295,15 +2856,17 @@ mod tests {
         // Create a session with a specific host car
         let track = TrackConfig::default();
         let car1 = CarConfig::default();
-        let mut car2 = CarConfig::default();
-        car2.id = Uuid::new_v4(); // Different ID
-        car2.name = "Test Car 2".to_string();
+        let car2 = CarConfig {
+            id: CarConfigId::new_v4(), // Different ID
+            name: "Test Car 2".to_string(),
+            ..Default::default()
+        };
 
         let mut car_configs = HashMap::new();
         car_configs.insert(car1.id, car1.clone());
         car_configs.insert(car2.id, car2.clone());
 
-        let host_id = Uuid::new_v4();
+        let host_id = PlayerId::new_v4();
         let mut session = RaceSession::new(host_id, track.id, SessionKind::Multiplayer, 8, 0, 3);
         session.host_car_id = Some(car2.id); // Host selected car2
 
@@ -0,0 +1,436 @@
+//! gRPC control plane for orchestration systems that manage many servers.
+//!
+//! The service in `proto/control.proto` mirrors the HTTP admin API: each call
+//! needs `authorization: Bearer <token>` metadata with one of the server's
+//! `admin_tokens` and becomes the same `AdminCommand`, carried out by the same
+//! task. `StreamStats` repeats `GetStats` on an interval.
+
+use crate::admin_api::{
+    AdminApi, AdminCommand, AdminError, AdminPlayer, AdminResponse, AdminSession, AdminStats, AdminTrack, SessionCreate,
+    SessionTransition, SessionUpdate, TrackGenerate,
+};
+use crate::data::*;
+use crate::procgen::circuit::CircuitParams;
+use crate::tick_lag::TickLagSnapshot;
+use hyper::StatusCode;
+use std::time::Duration;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+use tracing::info;
+
+pub mod proto {
+    tonic::include_proto!("apexsim.control.v1");
+}
+
+use proto::control_plane_server::{ControlPlane, ControlPlaneServer};
+
+/// Shortest `StreamStats` interval
+const MIN_STATS_INTERVAL_MS: u32 = 100;
+
+/// `StreamStats` interval when the caller leaves it at 0
+const DEFAULT_STATS_INTERVAL_MS: u32 = 1000;
+
+/// Serves `ControlPlane` by handing commands to the admin API
+#[derive(Clone)]
+pub struct ControlPlaneService {
+    api: AdminApi,
+}
+
+impl ControlPlaneService {
+    pub fn new(api: AdminApi) -> Self {
+        Self { api }
+    }
+
+    fn authorize<T>(&self, request: &Request<T>) -> Result<(), Status> {
+        let authorization = request.metadata().get("authorization").and_then(|value| value.to_str().ok());
+        if self.api.authorized(authorization) {
+            Ok(())
+        } else {
+            Err(Status::unauthenticated("Admin token required"))
+        }
+    }
+
+    async fn execute<T>(&self, request: &Request<T>, command: AdminCommand) -> Result<AdminResponse, Status> {
+        self.authorize(request)?;
+        self.api.execute(command).await.map_err(status)
+    }
+
+    async fn session(&self, request: &Request<impl Sized>, command: AdminCommand) -> Result<Response<proto::Session>, Status> {
+        match self.execute(request, command).await? {
+            AdminResponse::Session(session) => Ok(Response::new(session.into())),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    async fn action(&self, request: &Request<impl Sized>, command: AdminCommand) -> Result<Response<proto::ActionResponse>, Status> {
+        match self.execute(request, command).await? {
+            AdminResponse::Done { message } => Ok(Response::new(proto::ActionResponse { message })),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    async fn stats(&self) -> Result<proto::ServerStats, Status> {
+        match self.api.execute(AdminCommand::Stats).await.map_err(status)? {
+            AdminResponse::Stats(stats) => Ok(stats.into()),
+            other => Err(unexpected(other)),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl ControlPlane for ControlPlaneService {
+    async fn list_players(
+        &self,
+        request: Request<proto::ListPlayersRequest>,
+    ) -> Result<Response<proto::ListPlayersResponse>, Status> {
+        match self.execute(&request, AdminCommand::ListPlayers).await? {
+            AdminResponse::Players(players) => Ok(Response::new(proto::ListPlayersResponse {
+                players: players.into_iter().map(Into::into).collect(),
+            })),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    async fn kick_player(&self, request: Request<proto::KickPlayerRequest>) -> Result<Response<proto::ActionResponse>, Status> {
+        let player_id = parse_id::<PlayerId>(&request.get_ref().player_id, "player")?;
+        let reason = request.get_ref().reason.clone();
+        self.action(&request, AdminCommand::KickPlayer { player_id, reason }).await
+    }
+
+    async fn list_sessions(
+        &self,
+        request: Request<proto::ListSessionsRequest>,
+    ) -> Result<Response<proto::ListSessionsResponse>, Status> {
+        match self.execute(&request, AdminCommand::ListSessions).await? {
+            AdminResponse::Sessions(sessions) => Ok(Response::new(proto::ListSessionsResponse {
+                sessions: sessions.into_iter().map(Into::into).collect(),
+            })),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    async fn get_session(&self, request: Request<proto::GetSessionRequest>) -> Result<Response<proto::Session>, Status> {
+        let session_id = parse_id::<SessionId>(&request.get_ref().session_id, "session")?;
+        self.session(&request, AdminCommand::GetSession { session_id }).await
+    }
+
+    async fn create_session(&self, request: Request<proto::CreateSessionRequest>) -> Result<Response<proto::Session>, Status> {
+        let create = SessionCreate {
+            track: request.get_ref().track.clone(),
+            max_players: to_u8(request.get_ref().max_players, "max_players")?,
+        };
+        self.session(&request, AdminCommand::CreateSession { create }).await
+    }
+
+    async fn update_session(&self, request: Request<proto::UpdateSessionRequest>) -> Result<Response<proto::Session>, Status> {
+        let session_id = parse_id::<SessionId>(&request.get_ref().session_id, "session")?;
+        let update = SessionUpdate {
+            lap_limit: request.get_ref().lap_limit.map(|v| to_u8(v, "lap_limit")).transpose()?,
+            max_players: request.get_ref().max_players.map(|v| to_u8(v, "max_players")).transpose()?,
+            balance: match (&request.get_ref().balance[..], request.get_ref().clear_balance) {
+                ([], false) => None,
+                (cars, _) => Some(cars.iter().map(|car| (car.car.clone(), BalanceOfPerformance::from(car))).collect()),
+            },
+        };
+        self.session(&request, AdminCommand::UpdateSession { session_id, update }).await
+    }
+
+    async fn close_session(&self, request: Request<proto::CloseSessionRequest>) -> Result<Response<proto::ActionResponse>, Status> {
+        let session_id = parse_id::<SessionId>(&request.get_ref().session_id, "session")?;
+        self.action(&request, AdminCommand::CloseSession { session_id }).await
+    }
+
+    async fn transition_session(
+        &self,
+        request: Request<proto::TransitionSessionRequest>,
+    ) -> Result<Response<proto::Session>, Status> {
+        let session_id = parse_id::<SessionId>(&request.get_ref().session_id, "session")?;
+        let transition = match request.get_ref().transition() {
+            proto::SessionTransition::Start => SessionTransition::Start,
+            proto::SessionTransition::Finish => SessionTransition::Finish,
+            proto::SessionTransition::Unspecified => return Err(Status::invalid_argument("transition is required")),
+        };
+        self.session(&request, AdminCommand::TransitionSession { session_id, transition }).await
+    }
+
+    async fn generate_track(&self, request: Request<proto::GenerateTrackRequest>) -> Result<Response<proto::Track>, Status> {
+        let generate = TrackGenerate::from(request.get_ref());
+        match self.execute(&request, AdminCommand::GenerateTrack { generate }).await? {
+            AdminResponse::Track(track) => Ok(Response::new(track.into())),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    async fn announce(&self, request: Request<proto::AnnounceRequest>) -> Result<Response<proto::ActionResponse>, Status> {
+        let message = request.get_ref().message.clone();
+        if message.trim().is_empty() {
+            return Err(Status::invalid_argument("Announcement must not be empty"));
+        }
+        self.action(&request, AdminCommand::Announce { message }).await
+    }
+
+    async fn drain(&self, request: Request<proto::DrainRequest>) -> Result<Response<proto::ActionResponse>, Status> {
+        self.action(&request, AdminCommand::Drain).await
+    }
+
+    async fn shutdown(&self, request: Request<proto::ShutdownRequest>) -> Result<Response<proto::ActionResponse>, Status> {
+        self.action(&request, AdminCommand::Shutdown).await
+    }
+
+    async fn get_stats(&self, request: Request<proto::GetStatsRequest>) -> Result<Response<proto::ServerStats>, Status> {
+        self.authorize(&request)?;
+        self.stats().await.map(Response::new)
+    }
+
+    type StreamStatsStream = ReceiverStream<Result<proto::ServerStats, Status>>;
+
+    async fn stream_stats(&self, request: Request<proto::StreamStatsRequest>) -> Result<Response<Self::StreamStatsStream>, Status> {
+        self.authorize(&request)?;
+        let interval_ms = match request.get_ref().interval_ms {
+            0 => DEFAULT_STATS_INTERVAL_MS,
+            ms => ms.max(MIN_STATS_INTERVAL_MS),
+        };
+
+        let (sender, receiver) = tokio::sync::mpsc::channel(4);
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(interval_ms as u64));
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                interval.tick().await;
+                let stats = service.stats().await;
+                let failed = stats.is_err();
+                // Stop once the caller hangs up or the server stops answering
+                if sender.send(stats).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+/// The gRPC status for a refused admin command
+fn status(error: AdminError) -> Status {
+    match error.status {
+        StatusCode::BAD_REQUEST => Status::invalid_argument(error.message),
+        StatusCode::NOT_FOUND => Status::not_found(error.message),
+        StatusCode::CONFLICT => Status::failed_precondition(error.message),
+        StatusCode::SERVICE_UNAVAILABLE => Status::unavailable(error.message),
+        _ => Status::internal(error.message),
+    }
+}
+
+fn unexpected(response: AdminResponse) -> Status {
+    Status::internal(format!("Unexpected admin response {:?}", response))
+}
+
+fn parse_id<T: std::str::FromStr>(id: &str, kind: &str) -> Result<T, Status> {
+    id.parse().map_err(|_| Status::invalid_argument(format!("Invalid {} id {}", kind, id)))
+}
+
+fn to_u8(value: u32, field: &str) -> Result<u8, Status> {
+    u8::try_from(value).map_err(|_| Status::invalid_argument(format!("{} must be at most 255", field)))
+}
+
+impl From<AdminPlayer> for proto::Player {
+    fn from(player: AdminPlayer) -> Self {
+        Self {
+            player_id: player.player_id.to_string(),
+            name: player.name,
+            address: player.address.map(|a| a.to_string()).unwrap_or_default(),
+            admin: player.admin,
+            session_id: player.session_id.map(|id| id.to_string()).unwrap_or_default(),
+            spectating: player.spectating,
+        }
+    }
+}
+
+impl From<AdminSession> for proto::Session {
+    fn from(session: AdminSession) -> Self {
+        Self {
+            session_id: session.session_id.to_string(),
+            track_name: session.track_name,
+            host_name: session.host_name,
+            session_kind: session.session_kind as u32,
+            state: session.state as u32,
+            game_mode: session.game_mode as u32,
+            drivers: session.drivers as u32,
+            spectators: session.spectators as u32,
+            max_players: session.max_players as u32,
+            lap_limit: session.lap_limit as u32,
+            server_tick: session.server_tick,
+            balance: session
+                .balance
+                .into_iter()
+                .map(|(car, balance)| proto::CarBalance {
+                    car,
+                    ballast_kg: Some(balance.ballast_kg),
+                    power_multiplier: Some(balance.power_multiplier),
+                    fuel_capacity_liters: balance.fuel_capacity_liters,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<&proto::CarBalance> for BalanceOfPerformance {
+    fn from(car: &proto::CarBalance) -> Self {
+        let neutral = Self::default();
+        Self {
+            ballast_kg: car.ballast_kg.unwrap_or(neutral.ballast_kg),
+            power_multiplier: car.power_multiplier.unwrap_or(neutral.power_multiplier),
+            fuel_capacity_liters: car.fuel_capacity_liters,
+        }
+    }
+}
+
+impl From<&proto::GenerateTrackRequest> for TrackGenerate {
+    fn from(request: &proto::GenerateTrackRequest) -> Self {
+        let defaults = CircuitParams::default();
+        Self {
+            name: request.name.clone(),
+            params: CircuitParams {
+                length_m: request.length_m.unwrap_or(defaults.length_m),
+                corners: request.corners.unwrap_or(defaults.corners),
+                straights: request.straights.unwrap_or(defaults.straights),
+                elevation_variance_m: request.elevation_variance_m.unwrap_or(defaults.elevation_variance_m),
+                width_m: request.width_m.unwrap_or(defaults.width_m),
+                seed: request.seed,
+                environment: match &request.environment {
+                    Some(environment) if environment == "none" => None,
+                    Some(environment) => Some(environment.clone()),
+                    None => defaults.environment,
+                },
+            },
+        }
+    }
+}
+
+impl From<AdminTrack> for proto::Track {
+    fn from(track: AdminTrack) -> Self {
+        Self {
+            track_id: track.track_id.to_string(),
+            name: track.name,
+            length_m: track.length_m,
+            source_path: track.source_path.unwrap_or_default(),
+            seed: track.seed,
+        }
+    }
+}
+
+impl From<AdminStats> for proto::ServerStats {
+    fn from(stats: AdminStats) -> Self {
+        Self {
+            uptime_s: stats.uptime_s,
+            player_count: stats.player_count as u32,
+            session_count: stats.session_count as u32,
+            draining: stats.draining,
+            tcp_messages_dropped: stats.tcp_messages_dropped,
+            udp_messages_dropped: stats.udp_messages_dropped,
+            sessions: stats.sessions.into_iter().map(Into::into).collect(),
+            tick_lag: Some(stats.tick_lag.into()),
+        }
+    }
+}
+
+impl From<TickLagSnapshot> for proto::TickLag {
+    fn from(lag: TickLagSnapshot) -> Self {
+        Self {
+            ticks: lag.ticks,
+            overruns: lag.overruns,
+            catch_up_ticks: lag.catch_up_ticks,
+            skipped_ticks: lag.skipped_ticks,
+            max_tick_us: lag.max_tick_us,
+            lag_us: lag.lag_us,
+        }
+    }
+}
+
+/// Run the gRPC control plane on `bind_addr`
+pub async fn run_grpc_server(bind_addr: String, api: AdminApi) -> Result<(), Box<dyn std::error::Error>> {
+    let addr = bind_addr.parse()?;
+    info!("gRPC control plane listening on {}", bind_addr);
+    tonic::transport::Server::builder()
+        .add_service(ControlPlaneServer::new(ControlPlaneService::new(api)))
+        .serve(addr)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_stream::StreamExt;
+
+    fn authorized<T>(message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        request.metadata_mut().insert("authorization", "Bearer secret".parse().unwrap());
+        request
+    }
+
+    #[tokio::test]
+    async fn test_commands_need_a_token_and_map_errors() {
+        let (api, mut requests) = AdminApi::new(vec!["secret".to_string()]);
+        let service = ControlPlaneService::new(api);
+        tokio::spawn(async move {
+            while let Some(request) = requests.recv().await {
+                let result = match &request.command {
+                    AdminCommand::CloseSession { session_id } => {
+                        Err(AdminError::not_found(format!("Session {} not found", session_id)))
+                    }
+                    _ => Ok(AdminResponse::Done { message: "Draining".to_string() }),
+                };
+                request.respond(result);
+            }
+        });
+
+        let refused = service.drain(Request::new(proto::DrainRequest {})).await.unwrap_err();
+        assert_eq!(refused.code(), tonic::Code::Unauthenticated);
+
+        let drained = service.drain(authorized(proto::DrainRequest {})).await.unwrap();
+        assert_eq!(drained.get_ref().message, "Draining");
+
+        let missing = service
+            .close_session(authorized(proto::CloseSessionRequest { session_id: SessionId::new_v4().to_string() }))
+            .await
+            .unwrap_err();
+        assert_eq!(missing.code(), tonic::Code::NotFound);
+
+        let invalid = service
+            .close_session(authorized(proto::CloseSessionRequest { session_id: "nope".to_string() }))
+            .await
+            .unwrap_err();
+        assert_eq!(invalid.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_stream_stats_repeats() {
+        let (api, mut requests) = AdminApi::new(vec!["secret".to_string()]);
+        let service = ControlPlaneService::new(api);
+        tokio::spawn(async move {
+            let mut uptime_s = 0;
+            while let Some(request) = requests.recv().await {
+                uptime_s += 1;
+                request.respond(Ok(AdminResponse::Stats(AdminStats {
+                    uptime_s,
+                    player_count: 2,
+                    session_count: 0,
+                    draining: false,
+                    tcp_messages_dropped: 0,
+                    udp_messages_dropped: 0,
+                    tick_lag: TickLagSnapshot::default(),
+                    sessions: Vec::new(),
+                })));
+            }
+        });
+
+        let mut stream = service
+            .stream_stats(authorized(proto::StreamStatsRequest { interval_ms: 1 }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(stream.next().await.unwrap().unwrap().uptime_s, 1);
+        assert_eq!(stream.next().await.unwrap().unwrap().uptime_s, 2);
+    }
+}
@@ -1,18 +1,109 @@
+//! Health endpoints for orchestrators and monitoring.
+//!
+//! `/live` says whether the process should be restarted: it fails once the
+//! server marks itself unhealthy or the game loop stops ticking. `/ready`
+//! says whether it should get players: it fails until content is loaded and
+//! the server is up, and while it drains. `/health` reports the details
+//! behind both as JSON.
+
 use hyper::server::conn::http1;
 use hyper::service::service_fn;
 use hyper::{body::Incoming, Request, Response, StatusCode};
 use hyper_util::rt::TokioIo;
-use http_body_util::Full;
+use http_body_util::{combinators::BoxBody, BodyExt, Full};
 use bytes::Bytes;
+use crate::bans::unix_now;
+use crate::live_timing::{handle_timing, LiveTiming};
+use crate::tick_lag::TickLagSnapshot;
+use crate::transport::QueueDepths;
+use serde::Serialize;
+use std::convert::Infallible;
+use std::time::{Duration, Instant};
 use tokio::net::TcpListener;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use tokio::sync::RwLock;
-use tracing::{info, error};
+use tracing::field::{Field, Visit};
+use tracing::{info, error, Event, Level, Subscriber};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// A game loop without a tick for this long is reported as not live
+pub const TICK_STALL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What the server has loaded and how it keeps up, refreshed by the server
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HealthDetails {
+    pub cars: usize,
+    pub tracks: usize,
+    pub sessions: usize,
+    pub players: usize,
+    pub tick_lag: TickLagSnapshot,
+    pub queues: QueueDepths,
+}
+
+/// The latest error the server logged
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LastError {
+    pub message: String,
+    pub target: String,
+    /// Unix time, in seconds
+    pub at: u64,
+}
+
+#[derive(Default)]
+struct Status {
+    details: HealthDetails,
+    /// When the tick count last went up
+    last_tick_at: Option<Instant>,
+}
+
+impl Status {
+    fn update(&mut self, details: HealthDetails, now: Instant) {
+        if details.tick_lag.ticks > self.details.tick_lag.ticks {
+            self.last_tick_at = Some(now);
+        }
+        self.details = details;
+    }
+
+    /// Why the game loop is not live, if it is not; a loop that has not
+    /// started yet is still live
+    fn stalled(&self, now: Instant) -> Option<String> {
+        let last_tick_at = self.last_tick_at?;
+        let since = now.saturating_duration_since(last_tick_at);
+        (since > TICK_STALL_TIMEOUT).then(|| format!("no tick for {} s", since.as_secs()))
+    }
+
+    /// Why the server should not get players, if it should not
+    fn unready(&self) -> Option<&'static str> {
+        if self.details.cars == 0 {
+            Some("no cars loaded")
+        } else if self.details.tracks == 0 {
+            Some("no tracks loaded")
+        } else {
+            None
+        }
+    }
+}
+
+/// The `/health` response
+#[derive(Debug, Serialize)]
+struct HealthReport {
+    live: bool,
+    ready: bool,
+    /// Why the server is not live or not ready
+    problems: Vec<String>,
+    #[serde(flatten)]
+    details: HealthDetails,
+    last_error: Option<LastError>,
+}
 
 #[derive(Clone)]
 pub struct HealthState {
     pub is_ready: Arc<RwLock<bool>>,
     pub is_healthy: Arc<RwLock<bool>>,
+    status: Arc<RwLock<Status>>,
+    last_error: Arc<Mutex<Option<LastError>>>,
 }
 
 impl HealthState {
@@ -20,6 +111,8 @@ impl HealthState {
         Self {
             is_ready: Arc::new(RwLock::new(false)),
             is_healthy: Arc::new(RwLock::new(true)),
+            status: Arc::new(RwLock::new(Status::default())),
+            last_error: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -30,6 +123,46 @@ impl HealthState {
     pub async fn set_healthy(&self, healthy: bool) {
         *self.is_healthy.write().await = healthy;
     }
+
+    /// Replace the details; a tick count that went up shows the game loop is running
+    pub async fn update(&self, details: HealthDetails) {
+        self.status.write().await.update(details, Instant::now());
+    }
+
+    pub fn last_error(&self) -> Option<LastError> {
+        self.last_error.lock().unwrap().clone()
+    }
+
+    /// A tracing layer that keeps the latest error event for `/health`
+    pub fn error_layer<S>(&self) -> impl Layer<S> + Send + Sync
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        LastErrorLayer { last_error: Arc::clone(&self.last_error) }.with_filter(LevelFilter::ERROR)
+    }
+
+    async fn report(&self) -> HealthReport {
+        let now = Instant::now();
+        let status = self.status.read().await;
+        let mut problems = Vec::new();
+        if !*self.is_healthy.read().await {
+            problems.push("marked unhealthy".to_string());
+        }
+        problems.extend(status.stalled(now));
+        let live = problems.is_empty();
+
+        if !*self.is_ready.read().await {
+            problems.push("not accepting players".to_string());
+        }
+        problems.extend(status.unready().map(str::to_string));
+        HealthReport {
+            live,
+            ready: problems.is_empty(),
+            problems,
+            details: status.details.clone(),
+            last_error: self.last_error(),
+        }
+    }
 }
 
 impl Default for HealthState {
@@ -38,51 +171,91 @@ impl Default for HealthState {
     }
 }
 
+struct LastErrorLayer {
+    last_error: Arc<Mutex<Option<LastError>>>,
+}
+
+impl<S: Subscriber> Layer<S> for LastErrorLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if *event.metadata().level() != Level::ERROR {
+            return;
+        }
+        let mut message = MessageVisitor(String::new());
+        event.record(&mut message);
+        *self.last_error.lock().unwrap() = Some(LastError {
+            message: message.0,
+            target: event.metadata().target().to_string(),
+            at: unix_now(),
+        });
+    }
+}
+
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{:?}", value);
+        }
+    }
+}
+
+fn text_response(status: StatusCode, text: &str) -> Response<Full<Bytes>> {
+    Response::builder()
+        .status(status)
+        .body(Full::new(Bytes::from(text.to_string())))
+        .unwrap()
+}
+
 async fn handle_health(
     req: Request<Incoming>,
     health_state: HealthState,
-) -> Result<Response<Full<Bytes>>, hyper::Error> {
+    live_timing: Option<LiveTiming>,
+) -> Result<Response<BoxBody<Bytes, Infallible>>, hyper::Error> {
     let path = req.uri().path();
 
-    match path {
+    if let Some(live_timing) = &live_timing {
+        if let Some(response) = handle_timing(path, live_timing).await {
+            return Ok(response);
+        }
+    }
+
+    let response = match path {
         "/health" => {
-            let is_healthy = *health_state.is_healthy.read().await;
-            if is_healthy {
-                Ok(Response::builder()
-                    .status(StatusCode::OK)
-                    .body(Full::new(Bytes::from("OK")))
-                    .unwrap())
+            let report = health_state.report().await;
+            Response::builder()
+                .status(if report.live { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE })
+                .header("Content-Type", "application/json")
+                .body(Full::new(Bytes::from(serde_json::to_vec(&report).unwrap_or_default())))
+                .unwrap()
+        }
+        "/live" => {
+            let report = health_state.report().await;
+            if report.live {
+                text_response(StatusCode::OK, "Live")
             } else {
-                Ok(Response::builder()
-                    .status(StatusCode::SERVICE_UNAVAILABLE)
-                    .body(Full::new(Bytes::from("Service Unavailable")))
-                    .unwrap())
+                text_response(StatusCode::SERVICE_UNAVAILABLE, &format!("Not Live: {}", report.problems.join(", ")))
             }
         }
         "/ready" => {
-            let is_ready = *health_state.is_ready.read().await;
-            if is_ready {
-                Ok(Response::builder()
-                    .status(StatusCode::OK)
-                    .body(Full::new(Bytes::from("Ready")))
-                    .unwrap())
+            let report = health_state.report().await;
+            if report.ready {
+                text_response(StatusCode::OK, "Ready")
             } else {
-                Ok(Response::builder()
-                    .status(StatusCode::SERVICE_UNAVAILABLE)
-                    .body(Full::new(Bytes::from("Not Ready")))
-                    .unwrap())
+                text_response(StatusCode::SERVICE_UNAVAILABLE, &format!("Not Ready: {}", report.problems.join(", ")))
             }
         }
-        _ => {
-            Ok(Response::builder()
-                .status(StatusCode::NOT_FOUND)
-                .body(Full::new(Bytes::from("Not Found")))
-                .unwrap())
-        }
-    }
+        _ => text_response(StatusCode::NOT_FOUND, "Not Found"),
+    };
+    Ok(response.map(|body| body.boxed()))
 }
 
-pub async fn run_health_server(bind_addr: String, health_state: HealthState) -> Result<(), Box<dyn std::error::Error>> {
+/// Serve health checks, and live timing when `live_timing` is given
+pub async fn run_health_server(
+    bind_addr: String,
+    health_state: HealthState,
+    live_timing: Option<LiveTiming>,
+) -> Result<(), Box<dyn std::error::Error>> {
     info!("Starting health check server on {}", bind_addr);
 
     let listener = TcpListener::bind(&bind_addr).await?;
@@ -99,10 +272,11 @@ pub async fn run_health_server(bind_addr: String, health_state: HealthState) ->
 
         let io = TokioIo::new(stream);
         let health_state = health_state.clone();
+        let live_timing = live_timing.clone();
 
         tokio::spawn(async move {
             let service = service_fn(move |req| {
-                handle_health(req, health_state.clone())
+                handle_health(req, health_state.clone(), live_timing.clone())
             });
 
             if let Err(err) = http1::Builder::new()
@@ -136,4 +310,64 @@ mod tests {
         state.set_healthy(false).await;
         assert!(!*state.is_healthy.read().await);
     }
+
+    fn details(ticks: u64) -> HealthDetails {
+        HealthDetails {
+            cars: 3,
+            tracks: 2,
+            tick_lag: TickLagSnapshot { ticks, ..Default::default() },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_stall_is_measured_from_the_last_tick() {
+        let start = Instant::now();
+        let mut status = Status::default();
+        assert_eq!(status.stalled(start + TICK_STALL_TIMEOUT * 2), None, "Not started yet");
+
+        status.update(details(10), start);
+        status.update(details(10), start + Duration::from_secs(5));
+        assert_eq!(status.stalled(start + TICK_STALL_TIMEOUT), None);
+        assert_eq!(status.stalled(start + Duration::from_secs(11)), Some("no tick for 11 s".to_string()));
+
+        status.update(details(11), start + Duration::from_secs(12));
+        assert_eq!(status.stalled(start + Duration::from_secs(13)), None);
+    }
+
+    #[tokio::test]
+    async fn test_ready_needs_content_and_the_ready_flag() {
+        let state = HealthState::new();
+        state.update(HealthDetails::default()).await;
+        let report = state.report().await;
+        assert!(report.live);
+        assert!(!report.ready);
+        assert_eq!(report.problems, vec!["not accepting players", "no cars loaded"]);
+
+        state.set_ready(true).await;
+        state.update(details(1)).await;
+        let report = state.report().await;
+        assert!(report.live && report.ready, "{:?}", report.problems);
+
+        state.set_healthy(false).await;
+        let report = state.report().await;
+        assert!(!report.live && !report.ready);
+    }
+
+    #[test]
+    fn test_error_layer_keeps_the_latest_error() {
+        use tracing_subscriber::prelude::*;
+
+        let state = HealthState::new();
+        let subscriber = tracing_subscriber::registry().with(state.error_layer());
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::error!("first failure");
+            tracing::warn!("only a warning");
+            tracing::error!(session = 4, "second failure: {}", "disk full");
+        });
+
+        let last_error = state.last_error().unwrap();
+        assert_eq!(last_error.message, "second failure: disk full");
+        assert_eq!(last_error.target, module_path!());
+    }
 }
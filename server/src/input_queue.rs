@@ -0,0 +1,168 @@
+//! Player inputs buffered between the tasks that receive them and the tick
+//! that applies them.
+//!
+//! Receiving runs on tasks of its own, so network jitter never holds up the
+//! simulation. Each input is stamped with the session tick it is for: the one
+//! after the `server_tick_ack` it was made against, but never a tick already
+//! simulated and at most `MAX_INPUT_LEAD_TICKS` ahead. A tick applies every
+//! input stamped up to its number, the latest per player winning, and a
+//! player's input holds until they send another.
+//!
+//! Each player may only have a few inputs waiting per tick of lead, so one
+//! client flooding its session cannot crowd out the others' inputs.
+
+use crate::data::*;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+/// How far past the next tick an input may be scheduled
+pub const MAX_INPUT_LEAD_TICKS: u32 = 32;
+
+/// Inputs a player may have waiting for each tick they can be scheduled on
+const MAX_PENDING_INPUTS_PER_TICK: usize = 4;
+
+/// Inputs a player may have waiting before new ones are dropped
+const MAX_PENDING_INPUTS_PER_PLAYER: usize = MAX_PENDING_INPUTS_PER_TICK * (MAX_INPUT_LEAD_TICKS as usize + 1);
+
+#[derive(Default)]
+struct SessionInputs {
+    /// Next tick the session simulates
+    next_tick: u32,
+    /// Inputs not yet applied, by the tick they are for, in arrival order
+    pending: BTreeMap<u32, Vec<(PlayerId, PlayerInputData)>>,
+    /// How many of the pending inputs each player sent
+    pending_count: HashMap<PlayerId, usize>,
+    /// Latest applied input of each player
+    held: HashMap<PlayerId, PlayerInputData>,
+}
+
+/// Per-session input queues, shared by the receive tasks and the game loop
+#[derive(Default)]
+pub struct InputQueues {
+    sessions: Mutex<HashMap<SessionId, SessionInputs>>,
+}
+
+impl InputQueues {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue an input made against `server_tick_ack`; `false` if the player
+    /// has too many waiting and it was dropped
+    pub fn push(&self, session_id: SessionId, player_id: PlayerId, server_tick_ack: u32, input: PlayerInputData) -> bool {
+        let mut sessions = self.sessions.lock().unwrap();
+        let queue = sessions.entry(session_id).or_default();
+        let pending_count = queue.pending_count.entry(player_id).or_default();
+        if *pending_count >= MAX_PENDING_INPUTS_PER_PLAYER {
+            return false;
+        }
+        *pending_count += 1;
+        let tick = server_tick_ack
+            .saturating_add(1)
+            .clamp(queue.next_tick, queue.next_tick.saturating_add(MAX_INPUT_LEAD_TICKS));
+        queue.pending.entry(tick).or_default().push((player_id, input));
+        true
+    }
+
+    /// Inputs for the session to simulate `tick` with
+    pub fn take(&self, session_id: SessionId, tick: u32) -> HashMap<PlayerId, PlayerInputData> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let queue = sessions.entry(session_id).or_default();
+        let later = queue.pending.split_off(&tick.saturating_add(1));
+        for (player_id, input) in std::mem::replace(&mut queue.pending, later).into_values().flatten() {
+            queue.held.insert(player_id, input);
+            if let Some(count) = queue.pending_count.get_mut(&player_id) {
+                *count -= 1;
+            }
+        }
+        queue.pending_count.retain(|_, count| *count > 0);
+        queue.next_tick = tick.saturating_add(1);
+        queue.held.clone()
+    }
+
+    /// Forget sessions that have closed
+    pub fn retain_sessions(&self, mut keep: impl FnMut(&SessionId) -> bool) {
+        self.sessions.lock().unwrap().retain(|session_id, _| keep(session_id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(throttle: f32) -> PlayerInputData {
+        PlayerInputData { throttle, brake: 0.0, steering: 0.0, gear: None, clutch: None }
+    }
+
+    #[test]
+    fn test_inputs_apply_on_their_tick_and_hold() {
+        let queues = InputQueues::new();
+        let session_id = SessionId::new_v4();
+        let player_id = PlayerId::new_v4();
+        queues.take(session_id, 10);
+
+        queues.push(session_id, player_id, 10, input(0.2));
+        queues.push(session_id, player_id, 10, input(0.4));
+        queues.push(session_id, player_id, 12, input(0.9));
+
+        assert_eq!(queues.take(session_id, 11)[&player_id].throttle, 0.4);
+        assert_eq!(queues.take(session_id, 12)[&player_id].throttle, 0.4, "Held until the next one is due");
+        assert_eq!(queues.take(session_id, 13)[&player_id].throttle, 0.9);
+        assert_eq!(queues.take(session_id, 14)[&player_id].throttle, 0.9);
+    }
+
+    #[test]
+    fn test_late_and_early_inputs_are_clamped() {
+        let queues = InputQueues::new();
+        let session_id = SessionId::new_v4();
+        let player_id = PlayerId::new_v4();
+        queues.take(session_id, 100);
+
+        // Made against an old tick: applied on the next one
+        queues.push(session_id, player_id, 50, input(0.5));
+        assert_eq!(queues.take(session_id, 101)[&player_id].throttle, 0.5);
+
+        // Scheduled far ahead: applied no later than the lead allows
+        queues.push(session_id, player_id, u32::MAX, input(1.0));
+        assert_eq!(queues.take(session_id, 101 + MAX_INPUT_LEAD_TICKS)[&player_id].throttle, 0.5);
+        assert_eq!(queues.take(session_id, 102 + MAX_INPUT_LEAD_TICKS)[&player_id].throttle, 1.0);
+    }
+
+    #[test]
+    fn test_sessions_are_separate_and_bounded() {
+        let queues = InputQueues::new();
+        let (a, b) = (SessionId::new_v4(), SessionId::new_v4());
+        let player_id = PlayerId::new_v4();
+
+        assert!(queues.push(a, player_id, 0, input(1.0)));
+        assert!(queues.take(b, 1).is_empty());
+        assert_eq!(queues.take(a, 1).len(), 1);
+
+        for _ in 0..MAX_PENDING_INPUTS_PER_PLAYER {
+            assert!(queues.push(b, player_id, 1, input(0.1)));
+        }
+        assert!(!queues.push(b, player_id, 1, input(0.1)));
+        queues.take(b, 2);
+        assert!(queues.push(b, player_id, 2, input(0.1)));
+
+        queues.retain_sessions(|session_id| *session_id == b);
+        assert!(queues.take(a, 2).is_empty());
+    }
+
+    #[test]
+    fn test_flooding_player_does_not_block_others() {
+        let queues = InputQueues::new();
+        let session_id = SessionId::new_v4();
+        let (flooder, other) = (PlayerId::new_v4(), PlayerId::new_v4());
+        queues.take(session_id, 0);
+
+        let accepted = (0..10_000).filter(|_| queues.push(session_id, flooder, 0, input(1.0))).count();
+        assert_eq!(accepted, MAX_PENDING_INPUTS_PER_PLAYER);
+
+        assert!(queues.push(session_id, other, 0, input(0.3)));
+        let inputs = queues.take(session_id, 1);
+        assert_eq!(inputs[&other].throttle, 0.3);
+        assert_eq!(inputs[&flooder].throttle, 1.0);
+        assert!(queues.push(session_id, flooder, 1, input(0.5)), "Applied inputs free the player's room");
+    }
+}
@@ -0,0 +1,176 @@
+//! Crash-safe journal of the running sessions.
+//!
+//! Every few seconds the server writes one small file with each session's
+//! structure: its participants and where their cars are, laps, finishing
+//! order and the drivers' resume tokens, but none of the physics behind them,
+//! so it stays cheap however many sessions run. A clean shutdown deletes the
+//! file. If it is still there at startup the server crashed, and the sessions
+//! in it are rebuilt with every car parked where it was: stopped, brakes on,
+//! in sandbox mode where nothing moves. Drivers reclaim their cars with
+//! `ResumeSession` and the host carries on by choosing a game mode.
+//! Standings follow from the journaled laps once the race runs again.
+
+use crate::data::*;
+use crate::game_session::GameSession;
+use crate::replication::SessionSnapshot;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio::fs;
+
+/// Journals written by this build
+pub const JOURNAL_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct JournalFile {
+    version: u32,
+    /// Unix time (s)
+    written_at: u64,
+    sessions: Vec<SessionSnapshot>,
+}
+
+/// Writes and reads the journal file
+pub struct SessionJournal {
+    path: PathBuf,
+}
+
+impl SessionJournal {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Replace the journal with these sessions
+    pub async fn write(&self, sessions: Vec<SessionSnapshot>) -> Result<(), std::io::Error> {
+        let journal = JournalFile {
+            version: JOURNAL_VERSION,
+            written_at: crate::bans::unix_now(),
+            sessions,
+        };
+        let bytes = rmp_serde::to_vec_named(&journal)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+        // Write beside the old journal and swap, so a crash mid-write keeps the old one
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).await?;
+        }
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, bytes).await?;
+        fs::rename(&tmp_path, &self.path).await
+    }
+
+    /// Sessions left by a server that did not shut down cleanly; none when
+    /// there is no journal
+    pub async fn load(&self) -> Result<Vec<SessionSnapshot>, std::io::Error> {
+        let bytes = match fs::read(&self.path).await {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let journal: JournalFile =
+            rmp_serde::from_slice(&bytes).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        if journal.version != JOURNAL_VERSION {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("unsupported journal version {}", journal.version),
+            ));
+        }
+        Ok(journal.sessions)
+    }
+
+    /// Delete the journal on a clean shutdown
+    pub async fn clear(&self) -> Result<(), std::io::Error> {
+        match fs::remove_file(&self.path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Stop every car where it is and hold a running session still until the
+/// host picks a game mode again; sessions still in the lobby stay there
+pub fn park_cars(game_session: &mut GameSession) {
+    for car in game_session.session.participants.values_mut() {
+        car.vel_x = 0.0;
+        car.vel_y = 0.0;
+        car.vel_z = 0.0;
+        car.speed_mps = 0.0;
+        car.angular_vel_yaw = 0.0;
+        car.angular_vel_pitch = 0.0;
+        car.angular_vel_roll = 0.0;
+        car.throttle_input = 0.0;
+        car.brake_input = 1.0;
+        car.steering_input = 0.0;
+    }
+    if matches!(game_session.session.state, SessionState::Countdown | SessionState::Racing) {
+        game_session.session.countdown_ticks_remaining = None;
+        game_session.set_game_mode(GameMode::Sandbox);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai_driver::generate_default_ai_profiles;
+    use crate::physics::{TickTiming, WarmupSettings};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_restore_parks_cars_and_keeps_laps() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = SessionJournal::new(temp_dir.path().join("journal").join("sessions.bin"));
+        assert!(journal.load().await.unwrap().is_empty(), "No journal, nothing to restore");
+
+        let track = TrackConfig::default();
+        let car = CarConfig::default();
+        let car_configs = HashMap::from([(car.id, car.clone())]);
+        let session = RaceSession::new(PlayerId::new_v4(), track.id, SessionKind::Multiplayer, 8, 2, 5);
+        let mut game_session =
+            GameSession::with_ai_profiles(session, track.clone(), car_configs.clone(), generate_default_ai_profiles(2));
+        game_session.spawn_ai_drivers();
+        let driver = PlayerId::new_v4();
+        game_session.add_player(driver, car.id);
+        game_session.set_game_mode(GameMode::FreePractice);
+        for _ in 0..240 {
+            game_session.tick(&HashMap::new());
+        }
+        game_session.session.participants.get_mut(&driver).unwrap().current_lap = 3;
+
+        let token = Uuid::new_v4();
+        let snapshot = SessionSnapshot::new(&game_session, "Host".to_string(), HashMap::from([(token, driver)]));
+        journal.write(vec![snapshot]).await.unwrap();
+
+        // The crashed server's sessions, rebuilt and parked
+        let sessions = journal.load().await.unwrap();
+        assert_eq!(sessions.len(), 1);
+        let tracks = HashMap::from([(track.id, Arc::new(track))]);
+        let timing = TickTiming::new(240, 1);
+        let mut restored = sessions
+            .into_iter()
+            .next()
+            .unwrap()
+            .rebuild(&tracks, &car_configs, &WarmupSettings::default(), timing)
+            .unwrap();
+        assert_eq!(restored.resume_tokens, HashMap::from([(token, driver)]));
+        let game_session_after = &mut restored.game_session;
+        park_cars(game_session_after);
+
+        let before: Vec<(PlayerId, f32, f32)> =
+            game_session.session.participants.values().map(|car| (car.player_id, car.pos_x, car.pos_y)).collect();
+        for _ in 0..60 {
+            game_session_after.tick(&HashMap::new());
+        }
+        for (player_id, pos_x, pos_y) in before {
+            let car = &game_session_after.session.participants[&player_id];
+            assert_eq!((car.pos_x, car.pos_y), (pos_x, pos_y), "Parked cars stay put");
+            assert_eq!(car.speed_mps, 0.0);
+        }
+        assert_eq!(game_session_after.session.participants[&driver].current_lap, 3);
+        assert_eq!(game_session_after.session.ai_player_ids.len(), 2);
+
+        journal.clear().await.unwrap();
+        assert!(journal.load().await.unwrap().is_empty());
+        journal.clear().await.unwrap();
+    }
+}
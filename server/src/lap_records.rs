@@ -0,0 +1,112 @@
+//! Lap records per track.
+//!
+//! At the end of every race the fastest lap of each human driver is checked
+//! against the track's record. The records are written to a JSON file
+//! whenever one falls, so they survive restarts and reach the lobby.
+
+use crate::data::{LapRecord, TrackConfigId};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A record as stored in the file, with the track it was set on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredRecord {
+    track_id: TrackConfigId,
+    /// Name of the track when the record was set, for people reading the file
+    track_name: String,
+    #[serde(flatten)]
+    record: LapRecord,
+}
+
+/// The record of each track, optionally backed by a file
+#[derive(Debug, Default)]
+pub struct LapRecords {
+    path: Option<PathBuf>,
+    records: HashMap<TrackConfigId, (String, LapRecord)>,
+}
+
+impl LapRecords {
+    /// In-memory records that are never written to disk
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the records stored at `path`; a missing file has none
+    pub fn load<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let stored: Vec<StoredRecord> = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        let records = stored.into_iter().map(|s| (s.track_id, (s.track_name, s.record))).collect();
+        Ok(Self { path: Some(path), records })
+    }
+
+    pub fn get(&self, track_id: TrackConfigId) -> Option<&LapRecord> {
+        self.records.get(&track_id).map(|(_, record)| record)
+    }
+
+    /// Keep `record` if it beats the track's record; true when it does
+    pub fn submit(&mut self, track_id: TrackConfigId, track_name: &str, record: LapRecord) -> std::io::Result<bool> {
+        if record.lap_time_ms == 0 || self.get(track_id).is_some_and(|best| best.lap_time_ms <= record.lap_time_ms) {
+            return Ok(false);
+        }
+        self.records.insert(track_id, (track_name.to_string(), record));
+        self.save()?;
+        Ok(true)
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let mut stored: Vec<StoredRecord> = self
+            .records
+            .iter()
+            .map(|(track_id, (track_name, record))| StoredRecord {
+                track_id: *track_id,
+                track_name: track_name.clone(),
+                record: record.clone(),
+            })
+            .collect();
+        stored.sort_by(|a, b| a.track_name.cmp(&b.track_name));
+        let contents = serde_json::to_string_pretty(&stored)?;
+        // Write then rename, so a crash never leaves a half-written file
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, contents)?;
+        fs::rename(&tmp, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lap(lap_time_ms: u32, driver_name: &str) -> LapRecord {
+        LapRecord { lap_time_ms, driver_name: driver_name.to_string(), car_name: "Formula".to_string(), set_at: 1_700_000_000 }
+    }
+
+    #[test]
+    fn test_only_faster_laps_become_records_and_survive_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lap_records.json");
+        let track_id = TrackConfigId::new_v4();
+
+        let mut records = LapRecords::load(&path).unwrap();
+        assert!(records.get(track_id).is_none());
+        assert!(records.submit(track_id, "Monza", lap(82_000, "Alice")).unwrap());
+        assert!(!records.submit(track_id, "Monza", lap(82_000, "Bob")).unwrap());
+        assert!(!records.submit(track_id, "Monza", lap(0, "Bob")).unwrap());
+        assert!(records.submit(track_id, "Monza", lap(81_500, "Carol")).unwrap());
+
+        let reloaded = LapRecords::load(&path).unwrap();
+        assert_eq!(reloaded.get(track_id), Some(&lap(81_500, "Carol")));
+        assert!(reloaded.get(TrackConfigId::new_v4()).is_none());
+    }
+}
@@ -1,14 +1,47 @@
+pub mod admin_api;
+pub mod admin_console;
 pub mod ai_driver;
+pub mod anonymize;
+pub mod bans;
 pub mod car_loader;
+pub mod cluster;
 pub mod config;
+pub mod content_hash;
+pub mod content_transfer;
 pub mod data;
+pub mod formation_lap;
 pub mod game_session;
 pub mod network;
 pub mod physics;
+pub mod raceline;
 pub mod health;
+pub mod dashboard;
+pub mod live_timing;
+pub mod log_file;
+pub mod master_server;
+pub mod results_export;
+pub mod grpc_api;
+pub mod input_queue;
+pub mod journal;
+pub mod lap_records;
 pub mod transport;
+pub mod loadtest;
 pub mod lobby;
+pub mod race_events;
 pub mod replay;
+pub mod replication;
+pub mod runtime;
+pub mod rolling_start;
+pub mod session_broadcast;
+pub mod session_save;
+pub mod session_limits;
+pub mod standings;
+pub mod telemetry_history;
+pub mod tick_lag;
+pub mod track_import;
 pub mod track_loader;
 pub mod track_mesh;
+pub mod weather;
 pub mod procgen;
+pub mod qa_report;
+pub mod race_sim;
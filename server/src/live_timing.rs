@@ -0,0 +1,372 @@
+//! Live timing over HTTP, for timing screens and league websites.
+//!
+//! The game loop publishes a `TimingSnapshot` of every public session on an
+//! interval. The health server serves them beside its checks:
+//!
+//! - `GET /timing` - every session, as a JSON array
+//! - `GET /timing/{session_id}` - one session, as JSON
+//! - `GET /timing/stream` and `GET /timing/{session_id}/stream` - Server-Sent
+//!   Events: a `timing` event with each snapshot, and a `closed` event with
+//!   the session ID when a session goes away
+
+use crate::anonymize::Pseudonymizer;
+use crate::data::*;
+use crate::game_session::GameSession;
+use bytes::Bytes;
+use http_body_util::{combinators::BoxBody, BodyExt, Full};
+use hyper::body::{Body, Frame};
+use hyper::{Response, StatusCode};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::{broadcast, mpsc, RwLock};
+
+/// Updates buffered for a slow stream before it skips ahead
+const TIMING_UPDATE_CHANNEL_SIZE: usize = 64;
+
+/// A session's timing at one tick
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimingSnapshot {
+    pub session_id: SessionId,
+    pub track_name: String,
+    pub state: SessionState,
+    pub game_mode: GameMode,
+    pub server_tick: u32,
+    pub lap_limit: u8,
+    /// Ordered by race position, leader first
+    pub entries: Vec<TimingEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimingEntry {
+    pub position: u8,
+    pub player_id: PlayerId,
+    pub driver_name: String,
+    pub car_name: String,
+    pub current_lap: u16,
+    pub laps_behind_leader: u16,
+    /// None for the leader and for lapped cars
+    pub gap_to_leader_ms: Option<u32>,
+    /// Time behind the car one position ahead; None for the leader and for lapped cars
+    pub interval_ms: Option<u32>,
+    pub last_lap_time_ms: Option<u32>,
+    pub best_lap_time_ms: Option<u32>,
+    pub pit_status: PitStatus,
+    pub finish_position: Option<u8>,
+    /// Sector the car is in, from 1, by the track's sector splits
+    pub current_sector: u8,
+}
+
+impl TimingSnapshot {
+    /// Current standings of a session. Human drivers are named from
+    /// `driver_names`, AI drivers from their profile; with `pseudonyms` every car
+    /// is shown under its pseudonym instead.
+    pub fn new(
+        game_session: &GameSession,
+        driver_names: &HashMap<PlayerId, String>,
+        pseudonyms: Option<&Pseudonymizer>,
+    ) -> Self {
+        let session = &game_session.session;
+        let entries = game_session
+            .standings
+            .standings(session, &game_session.track_config, game_session.timing)
+            .into_iter()
+            .map(|entry| {
+                let (player_id, driver_name) = match pseudonyms {
+                    Some(pseudonyms) => (pseudonyms.player_id(entry.player_id), pseudonyms.player_name(entry.player_id)),
+                    None => {
+                        let name = game_session
+                            .ai_profiles
+                            .get(&entry.player_id)
+                            .map(|profile| profile.name.clone())
+                            .or_else(|| driver_names.get(&entry.player_id).cloned())
+                            .unwrap_or_else(|| "Unknown".to_string());
+                        (entry.player_id, name)
+                    }
+                };
+                let state = session.participants.get(&entry.player_id);
+                let car_name = state
+                    .and_then(|state| game_session.car_configs.get(&state.car_config_id))
+                    .map(|car| car.name.clone())
+                    .unwrap_or_default();
+                let current_sector = state.map_or(1, |state| game_session.track_config.timing.sector_at(state.track_progress));
+                TimingEntry {
+                    position: entry.position,
+                    player_id,
+                    driver_name,
+                    car_name,
+                    current_lap: entry.current_lap,
+                    laps_behind_leader: entry.laps_behind_leader,
+                    gap_to_leader_ms: entry.gap_to_leader_ms,
+                    interval_ms: entry.interval_ms,
+                    last_lap_time_ms: entry.last_lap_time_ms,
+                    best_lap_time_ms: entry.best_lap_time_ms,
+                    pit_status: entry.pit_status,
+                    finish_position: entry.finish_position,
+                    current_sector,
+                }
+            })
+            .collect();
+
+        Self {
+            session_id: session.id,
+            track_name: game_session.track_config.name.clone(),
+            state: session.state,
+            game_mode: session.game_mode,
+            server_tick: session.current_tick,
+            lap_limit: session.lap_limit,
+            entries,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum TimingUpdate {
+    Snapshot(Arc<TimingSnapshot>),
+    Closed(SessionId),
+}
+
+/// Latest timing of every published session, shared between the game loop
+/// and the HTTP server
+#[derive(Clone)]
+pub struct LiveTiming {
+    sessions: Arc<RwLock<HashMap<SessionId, Arc<TimingSnapshot>>>>,
+    updates: broadcast::Sender<TimingUpdate>,
+}
+
+impl LiveTiming {
+    pub fn new() -> Self {
+        Self {
+            sessions: Arc::new(RwLock::new(HashMap::new())),
+            updates: broadcast::channel(TIMING_UPDATE_CHANNEL_SIZE).0,
+        }
+    }
+
+    /// Replace a session's timing and send it to its streams
+    pub async fn publish(&self, snapshot: TimingSnapshot) {
+        let snapshot = Arc::new(snapshot);
+        self.sessions.write().await.insert(snapshot.session_id, Arc::clone(&snapshot));
+        // No streams open is fine
+        let _ = self.updates.send(TimingUpdate::Snapshot(snapshot));
+    }
+
+    /// Stop publishing sessions `keep` rejects, closing their streams
+    pub async fn retain(&self, mut keep: impl FnMut(SessionId) -> bool) {
+        let mut sessions = self.sessions.write().await;
+        let closed: Vec<SessionId> = sessions.keys().copied().filter(|id| !keep(*id)).collect();
+        for session_id in closed {
+            sessions.remove(&session_id);
+            let _ = self.updates.send(TimingUpdate::Closed(session_id));
+        }
+    }
+
+    /// Latest timing of every session, in session ID order
+    pub async fn sessions(&self) -> Vec<TimingSnapshot> {
+        let mut sessions: Vec<TimingSnapshot> =
+            self.sessions.read().await.values().map(|s| TimingSnapshot::clone(s)).collect();
+        sessions.sort_by_key(|s| s.session_id);
+        sessions
+    }
+
+    pub async fn session(&self, session_id: SessionId) -> Option<TimingSnapshot> {
+        self.sessions.read().await.get(&session_id).map(|s| TimingSnapshot::clone(s))
+    }
+
+    /// Server-Sent Events of one session, or of every session when `session_id`
+    /// is None. Opens with the latest timing; a single session's stream ends when
+    /// that session closes.
+    async fn stream(&self, session_id: Option<SessionId>) -> Option<SseBody> {
+        let mut updates = self.updates.subscribe();
+        let current: Vec<Arc<TimingSnapshot>> = {
+            let sessions = self.sessions.read().await;
+            match session_id {
+                Some(id) => vec![Arc::clone(sessions.get(&id)?)],
+                None => sessions.values().cloned().collect(),
+            }
+        };
+
+        let (tx, rx) = mpsc::channel(TIMING_UPDATE_CHANNEL_SIZE);
+        tokio::spawn(async move {
+            for snapshot in current {
+                if tx.send(sse_event("timing", &*snapshot)).await.is_err() {
+                    return;
+                }
+            }
+            loop {
+                let event = match updates.recv().await {
+                    Ok(TimingUpdate::Snapshot(snapshot)) if session_id.is_none_or(|id| id == snapshot.session_id) => {
+                        sse_event("timing", &*snapshot)
+                    }
+                    Ok(TimingUpdate::Closed(closed)) if session_id.is_none_or(|id| id == closed) => {
+                        let event = sse_event("closed", &closed);
+                        if session_id.is_some() {
+                            let _ = tx.send(event).await;
+                            return;
+                        }
+                        event
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return,
+                };
+                // The client went away
+                if tx.send(event).await.is_err() {
+                    return;
+                }
+            }
+        });
+
+        Some(SseBody { events: rx })
+    }
+}
+
+impl Default for LiveTiming {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn sse_event(name: &str, data: &impl Serialize) -> Bytes {
+    let json = serde_json::to_string(data).unwrap_or_default();
+    Bytes::from(format!("event: {}\ndata: {}\n\n", name, json))
+}
+
+/// Response body that stays open and sends events as they are published
+struct SseBody {
+    events: mpsc::Receiver<Bytes>,
+}
+
+impl Body for SseBody {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Frame<Bytes>, Infallible>>> {
+        self.events.poll_recv(cx).map(|event| event.map(|data| Ok(Frame::data(data))))
+    }
+}
+
+/// Answer a `/timing` request; None for paths outside it
+pub async fn handle_timing(path: &str, live_timing: &LiveTiming) -> Option<Response<BoxBody<Bytes, Infallible>>> {
+    let rest = path.strip_prefix("/timing")?;
+    let segments: Vec<&str> = rest.split('/').filter(|s| !s.is_empty()).collect();
+
+    let response = match segments.as_slice() {
+        [] => json_response(&live_timing.sessions().await),
+        ["stream"] => sse_response(live_timing.stream(None).await),
+        [id] => match id.parse::<SessionId>() {
+            Ok(session_id) => match live_timing.session(session_id).await {
+                Some(snapshot) => json_response(&snapshot),
+                None => not_found(),
+            },
+            Err(_) => not_found(),
+        },
+        [id, "stream"] => match id.parse::<SessionId>() {
+            Ok(session_id) => sse_response(live_timing.stream(Some(session_id)).await),
+            Err(_) => not_found(),
+        },
+        _ => return None,
+    };
+    Some(response)
+}
+
+fn json_response(data: &impl Serialize) -> Response<BoxBody<Bytes, Infallible>> {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .header("Access-Control-Allow-Origin", "*")
+        .body(Full::new(Bytes::from(serde_json::to_vec(data).unwrap_or_default())).boxed())
+        .unwrap()
+}
+
+fn sse_response(body: Option<SseBody>) -> Response<BoxBody<Bytes, Infallible>> {
+    let Some(body) = body else {
+        return not_found();
+    };
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "text/event-stream")
+        .header("Cache-Control", "no-cache")
+        .header("Access-Control-Allow-Origin", "*")
+        .body(body.boxed())
+        .unwrap()
+}
+
+fn not_found() -> Response<BoxBody<Bytes, Infallible>> {
+    Response::builder()
+        .status(StatusCode::NOT_FOUND)
+        .body(Full::new(Bytes::from("Not Found")).boxed())
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai_driver::generate_default_ai_profiles;
+
+    fn racing_session() -> GameSession {
+        let track = TrackConfig::default();
+        let car = CarConfig::default();
+        let car_configs = HashMap::from([(car.id, car)]);
+        let session = RaceSession::new(PlayerId::new_v4(), track.id, SessionKind::Multiplayer, 8, 2, 3);
+        let mut game_session =
+            GameSession::with_ai_profiles(session, track, car_configs, generate_default_ai_profiles(2));
+        game_session.spawn_ai_drivers();
+        game_session.set_game_mode(GameMode::FreePractice);
+        game_session.tick(&HashMap::new());
+        game_session
+    }
+
+    async fn next_event(body: &mut SseBody) -> String {
+        let frame = body.frame().await.unwrap().unwrap();
+        String::from_utf8(frame.into_data().unwrap().to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_names_drivers() {
+        let game_session = racing_session();
+        let snapshot = TimingSnapshot::new(&game_session, &HashMap::new(), None);
+        assert_eq!(snapshot.entries.len(), 2);
+        assert_eq!(snapshot.entries.iter().map(|e| e.position).collect::<Vec<_>>(), vec![1, 2]);
+        let first = &snapshot.entries[0];
+        assert_eq!(first.driver_name, game_session.ai_profiles[&first.player_id].name);
+        assert_eq!(first.car_name, CarConfig::default().name);
+
+        let pseudonyms = Pseudonymizer::new("league-secret");
+        let anonymous = TimingSnapshot::new(&game_session, &HashMap::new(), Some(&pseudonyms));
+        assert_eq!(anonymous.entries[0].player_id, pseudonyms.player_id(first.player_id));
+        assert_eq!(anonymous.entries[0].driver_name, pseudonyms.player_name(first.player_id));
+    }
+
+    #[tokio::test]
+    async fn test_timing_routes_and_stream() {
+        let live_timing = LiveTiming::new();
+        let game_session = racing_session();
+        let session_id = game_session.session.id;
+        live_timing.publish(TimingSnapshot::new(&game_session, &HashMap::new(), None)).await;
+
+        let list = handle_timing("/timing", &live_timing).await.unwrap();
+        assert_eq!(list.status(), StatusCode::OK);
+        let body = list.into_body().collect().await.unwrap().to_bytes();
+        let sessions: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(sessions[0]["session_id"], session_id.to_string());
+        assert_eq!(sessions[0]["entries"].as_array().unwrap().len(), 2);
+
+        let one = handle_timing(&format!("/timing/{}", session_id), &live_timing).await.unwrap();
+        assert_eq!(one.status(), StatusCode::OK);
+        let unknown = handle_timing(&format!("/timing/{}", SessionId::new_v4()), &live_timing).await.unwrap();
+        assert_eq!(unknown.status(), StatusCode::NOT_FOUND);
+        assert!(handle_timing("/health", &live_timing).await.is_none());
+
+        // A stream opens with the latest timing, follows updates and ends with the session
+        let mut stream = live_timing.stream(Some(session_id)).await.unwrap();
+        assert!(next_event(&mut stream).await.starts_with("event: timing\ndata: {"));
+        live_timing.publish(TimingSnapshot::new(&game_session, &HashMap::new(), None)).await;
+        assert!(next_event(&mut stream).await.starts_with("event: timing\n"));
+        live_timing.retain(|_| false).await;
+        assert_eq!(next_event(&mut stream).await, format!("event: closed\ndata: \"{}\"\n\n", session_id));
+        assert!(stream.frame().await.is_none());
+        assert!(live_timing.sessions().await.is_empty());
+    }
+}
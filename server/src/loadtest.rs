@@ -0,0 +1,569 @@
+//! Multi-client load testing.
+//!
+//! A scenario connects a number of simulated clients to a running server,
+//! puts them all in one session and has them drive with a chosen input
+//! pattern for a while. The report records how many inputs went out, how much
+//! telemetry came back and the tick rate the server actually sustained, so
+//! runs can be compared across releases and environments.
+
+use crate::data::*;
+use crate::network::{ClientMessage, ServerMessage};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{sleep, timeout, Instant};
+
+/// Most clients a scenario may connect; the default track has this many grid slots
+pub const MAX_CLIENTS: usize = 16;
+
+/// Time allowed for the server to answer a setup request
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often clients send heartbeats; the server drops connections that go quiet
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Attempts at the first connection, for servers that are still starting
+const CONNECT_ATTEMPTS: u32 = 5;
+
+/// How each simulated client drives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum InputPattern {
+    /// Random throttle, occasional braking and gentle steering
+    #[default]
+    Random,
+    /// Flat out in a straight line
+    FullThrottle,
+    /// Steady throttle while steering from side to side
+    Weave,
+    /// No inputs beyond keeping the connection busy
+    Idle,
+}
+
+impl InputPattern {
+    /// Throttle, brake and steering at `elapsed_s` into the run
+    fn input(self, rng: &mut u64, elapsed_s: f64) -> (f32, f32, f32) {
+        match self {
+            InputPattern::Random => {
+                let throttle = next_random(rng);
+                let brake = if next_random(rng) < 0.1 { next_random(rng) * 0.5 } else { 0.0 };
+                let steering = (next_random(rng) - 0.5) * 0.6;
+                (throttle, brake, steering)
+            }
+            InputPattern::FullThrottle => (1.0, 0.0, 0.0),
+            InputPattern::Weave => (0.7, 0.0, 0.3 * (elapsed_s * std::f64::consts::PI).sin() as f32),
+            InputPattern::Idle => (0.0, 0.0, 0.0),
+        }
+    }
+}
+
+/// Xorshift64, so a seeded scenario always drives the same way
+fn next_random(state: &mut u64) -> f32 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    (*state as f32) / (u64::MAX as f32)
+}
+
+/// A load test, as read from a scenario TOML file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scenario {
+    /// Name carried into the report
+    #[serde(default = "default_name")]
+    pub name: String,
+    /// TCP address of the server under test
+    pub target: String,
+    /// Clients to connect, all in one session
+    #[serde(default = "default_clients")]
+    pub clients: usize,
+    /// Tick rate the server is configured for; the sustained rate is measured against it
+    #[serde(default = "default_tick_rate_hz")]
+    pub tick_rate_hz: u16,
+    /// How long the clients drive once the race is under way
+    #[serde(default = "default_duration_s")]
+    pub duration_s: f64,
+    #[serde(default)]
+    pub input_pattern: InputPattern,
+    /// How often each client sends its input
+    #[serde(default = "default_input_rate_hz")]
+    pub input_rate_hz: u16,
+    /// Seed for the random input pattern
+    #[serde(default = "default_seed")]
+    pub seed: u64,
+    /// Time given to the race countdown before driving starts
+    #[serde(default = "default_countdown_s")]
+    pub countdown_s: f64,
+    /// Sustained share of the tick rate below which the run fails
+    #[serde(default = "default_min_tick_ratio")]
+    pub min_tick_ratio: f64,
+}
+
+fn default_name() -> String {
+    "load test".to_string()
+}
+
+fn default_clients() -> usize {
+    MAX_CLIENTS
+}
+
+fn default_tick_rate_hz() -> u16 {
+    240
+}
+
+fn default_duration_s() -> f64 {
+    10.0
+}
+
+fn default_input_rate_hz() -> u16 {
+    60
+}
+
+fn default_seed() -> u64 {
+    1
+}
+
+fn default_countdown_s() -> f64 {
+    6.0
+}
+
+fn default_min_tick_ratio() -> f64 {
+    0.4
+}
+
+impl Scenario {
+    /// Scenario for `clients` clients against `target`, with every other setting at its default
+    pub fn new(target: &str, clients: usize, tick_rate_hz: u16) -> Self {
+        Self {
+            name: default_name(),
+            target: target.to_string(),
+            clients,
+            tick_rate_hz,
+            duration_s: default_duration_s(),
+            input_pattern: InputPattern::default(),
+            input_rate_hz: default_input_rate_hz(),
+            seed: default_seed(),
+            countdown_s: default_countdown_s(),
+            min_tick_ratio: default_min_tick_ratio(),
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path.as_ref())
+            .map_err(|e| format!("Failed to read {}: {}", path.as_ref().display(), e))?;
+        Self::from_toml_str(&text)
+    }
+
+    pub fn from_toml_str(text: &str) -> Result<Self, String> {
+        let scenario: Scenario = toml::from_str(text).map_err(|e| format!("Invalid scenario: {}", e))?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.clients == 0 || self.clients > MAX_CLIENTS {
+            return Err(format!("clients must be between 1 and {}", MAX_CLIENTS));
+        }
+        if self.tick_rate_hz == 0 || self.input_rate_hz == 0 {
+            return Err("tick_rate_hz and input_rate_hz must be positive".to_string());
+        }
+        if self.duration_s <= 0.0 || self.countdown_s < 0.0 {
+            return Err("duration_s must be positive and countdown_s not negative".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// How one simulated client fared
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClientReport {
+    pub inputs_sent: u64,
+    pub telemetry_received: u64,
+    /// Why the client stopped early, if it did
+    pub error: Option<String>,
+}
+
+/// Machine-readable outcome of a load test
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadTestReport {
+    pub scenario: Scenario,
+    /// Version of the harness that ran the test
+    pub harness_version: String,
+    pub started_at_unix_ms: u64,
+    pub clients_connected: usize,
+    /// Tick rate the server sustained, from the ticks seen in telemetry
+    pub actual_tick_rate_hz: f64,
+    /// Sustained tick rate as a share of the scenario's tick rate
+    pub tick_ratio: f64,
+    pub total_inputs_sent: u64,
+    pub total_telemetry_received: u64,
+    pub clients_with_telemetry: usize,
+    pub clients: Vec<ClientReport>,
+    /// Why the test could not run, if it could not
+    pub error: Option<String>,
+    /// Every client connected and got telemetry, and the tick ratio was met
+    pub passed: bool,
+}
+
+impl LoadTestReport {
+    fn new(scenario: &Scenario) -> Self {
+        Self {
+            scenario: scenario.clone(),
+            harness_version: env!("CARGO_PKG_VERSION").to_string(),
+            started_at_unix_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            clients_connected: 0,
+            actual_tick_rate_hz: 0.0,
+            tick_ratio: 0.0,
+            total_inputs_sent: 0,
+            total_telemetry_received: 0,
+            clients_with_telemetry: 0,
+            clients: Vec::new(),
+            error: None,
+            passed: false,
+        }
+    }
+}
+
+/// Connects the scenario's clients, races them and reports how the server held up
+pub async fn run(scenario: &Scenario) -> LoadTestReport {
+    let mut report = LoadTestReport::new(scenario);
+    if let Err(e) = scenario.validate() {
+        report.error = Some(e);
+        return report;
+    }
+
+    let clients = match prepare_session(scenario, &mut report).await {
+        Ok(clients) => clients,
+        Err(e) => {
+            report.error = Some(e);
+            return report;
+        }
+    };
+
+    let window = Arc::new(Mutex::new(TickWindow::default()));
+    let green_flag = Instant::now() + Duration::from_secs_f64(scenario.countdown_s);
+    let handles: Vec<_> = clients
+        .into_iter()
+        .enumerate()
+        .map(|(index, client)| {
+            let scenario = scenario.clone();
+            let window = Arc::clone(&window);
+            tokio::spawn(async move { drive(client, index, &scenario, green_flag, &window).await })
+        })
+        .collect();
+    for handle in handles {
+        let client_report = handle.await.unwrap_or_else(|e| ClientReport {
+            error: Some(format!("Client task failed: {}", e)),
+            ..ClientReport::default()
+        });
+        report.clients.push(client_report);
+    }
+
+    report.total_inputs_sent = report.clients.iter().map(|c| c.inputs_sent).sum();
+    report.total_telemetry_received = report.clients.iter().map(|c| c.telemetry_received).sum();
+    report.clients_with_telemetry = report.clients.iter().filter(|c| c.telemetry_received > 0).count();
+    report.actual_tick_rate_hz = window.lock().unwrap().tick_rate_hz();
+    report.tick_ratio = report.actual_tick_rate_hz / scenario.tick_rate_hz as f64;
+    report.passed = report.clients_with_telemetry == scenario.clients && report.tick_ratio >= scenario.min_tick_ratio;
+    report
+}
+
+/// Connects and authenticates every client, then gathers them in a session and starts it
+async fn prepare_session(scenario: &Scenario, report: &mut LoadTestReport) -> Result<Vec<LoadClient>, String> {
+    let mut clients = Vec::with_capacity(scenario.clients);
+    let mut content = None;
+    for index in 0..scenario.clients {
+        let attempts = if index == 0 { CONNECT_ATTEMPTS } else { 1 };
+        let mut client = LoadClient::connect(&scenario.target, attempts)
+            .await
+            .map_err(|e| format!("Client {} could not connect: {}", index, e))?;
+        let (car_id, track_id) = client
+            .authenticate(&format!("LoadTest_{}", index))
+            .await
+            .map_err(|e| format!("Client {} could not authenticate: {}", index, e))?;
+        client.send(&ClientMessage::SelectCar { car_config_id: car_id, livery: 0 }).await?;
+        content.get_or_insert((car_id, track_id));
+        clients.push(client);
+        report.clients_connected = clients.len();
+    }
+    let (_, track_id) = content.ok_or("No clients connected")?;
+
+    let session_id = clients[0]
+        .create_session(track_id, scenario.clients as u8)
+        .await
+        .map_err(|e| format!("Session creation failed: {}", e))?;
+    for (index, client) in clients.iter_mut().enumerate().skip(1) {
+        client
+            .join_session(session_id)
+            .await
+            .map_err(|e| format!("Client {} could not join: {}", index, e))?;
+    }
+
+    clients[0].send(&ClientMessage::StartSession).await?;
+    Ok(clients)
+}
+
+/// Sends one client's inputs at the scenario's rate from the green flag on,
+/// counting the telemetry it receives. Telemetry is read through the countdown
+/// too, so none is left queued up to arrive in a burst once driving starts.
+async fn drive(
+    client: LoadClient,
+    index: usize,
+    scenario: &Scenario,
+    green_flag: Instant,
+    window: &Mutex<TickWindow>,
+) -> ClientReport {
+    let deadline = green_flag + Duration::from_secs_f64(scenario.duration_s);
+    let (reader, writer) = client.stream.into_split();
+    let last_tick = AtomicU32::new(0);
+    let inputs_sent = AtomicU64::new(0);
+
+    let receiving = async {
+        let mut reader = reader;
+        let mut telemetry_received = 0u64;
+        while let Ok(message) = timeout(deadline.saturating_duration_since(Instant::now()), read_message(&mut reader)).await {
+            if let ServerMessage::Telemetry(telemetry) = message? {
+                last_tick.store(telemetry.server_tick, Ordering::Relaxed);
+                if Instant::now() < green_flag {
+                    continue;
+                }
+                telemetry_received += 1;
+                window.lock().unwrap().observe(telemetry.server_tick);
+            }
+        }
+        Ok::<_, String>(telemetry_received)
+    };
+
+    let sending = async {
+        let mut writer = writer;
+        // Xorshift needs a nonzero state
+        let mut rng = scenario.seed.wrapping_add(index as u64).max(1);
+        let mut heartbeats = tokio::time::interval(HEARTBEAT_INTERVAL);
+        let mut inputs = tokio::time::interval_at(green_flag, Duration::from_secs_f64(1.0 / scenario.input_rate_hz as f64));
+        let mut client_tick = 0u32;
+        loop {
+            let message = tokio::select! {
+                _ = tokio::time::sleep_until(deadline) => break,
+                _ = heartbeats.tick() => {
+                    client_tick += 1;
+                    ClientMessage::Heartbeat { client_tick }
+                }
+                _ = inputs.tick() => {
+                    let elapsed_s = green_flag.elapsed().as_secs_f64();
+                    let (throttle, brake, steering) = scenario.input_pattern.input(&mut rng, elapsed_s);
+                    inputs_sent.fetch_add(1, Ordering::Relaxed);
+                    ClientMessage::PlayerInput {
+                        server_tick_ack: last_tick.load(Ordering::Relaxed),
+                        throttle,
+                        brake,
+                        steering,
+                        gear: None,
+                        clutch: None,
+                    }
+                }
+            };
+            write_message(&mut writer, &message).await?;
+        }
+        Ok::<_, String>(())
+    };
+
+    let (received, sent) = tokio::join!(receiving, sending);
+    ClientReport {
+        inputs_sent: inputs_sent.load(Ordering::Relaxed),
+        telemetry_received: *received.as_ref().unwrap_or(&0),
+        error: received.err().or(sent.err()),
+    }
+}
+
+/// Earliest and latest server ticks seen by any client, and when
+#[derive(Debug, Default)]
+struct TickWindow {
+    first: Option<(u32, Instant)>,
+    last: Option<(u32, Instant)>,
+}
+
+impl TickWindow {
+    fn observe(&mut self, tick: u32) {
+        let now = Instant::now();
+        if self.first.is_none_or(|(first, _)| tick < first) {
+            self.first = Some((tick, now));
+        }
+        if self.last.is_none_or(|(last, _)| tick > last) {
+            self.last = Some((tick, now));
+        }
+    }
+
+    fn tick_rate_hz(&self) -> f64 {
+        match (self.first, self.last) {
+            (Some((first_tick, first_at)), Some((last_tick, last_at))) if last_at > first_at => {
+                last_tick.saturating_sub(first_tick) as f64 / last_at.duration_since(first_at).as_secs_f64()
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+/// A simulated client's connection to the server
+struct LoadClient {
+    stream: TcpStream,
+}
+
+impl LoadClient {
+    async fn connect(target: &str, attempts: u32) -> Result<Self, String> {
+        let mut last_error = String::new();
+        for attempt in 0..attempts {
+            if attempt > 0 {
+                sleep(Duration::from_millis(500)).await;
+            }
+            match timeout(REPLY_TIMEOUT, TcpStream::connect(target)).await {
+                Ok(Ok(stream)) => return Ok(Self { stream }),
+                Ok(Err(e)) => last_error = e.to_string(),
+                Err(_) => last_error = "timed out".to_string(),
+            }
+        }
+        Err(last_error)
+    }
+
+    async fn send(&mut self, message: &ClientMessage) -> Result<(), String> {
+        write_message(&mut self.stream, message).await
+    }
+
+    async fn receive(&mut self) -> Result<ServerMessage, String> {
+        timeout(REPLY_TIMEOUT, read_message(&mut self.stream))
+            .await
+            .map_err(|_| "timed out waiting for the server".to_string())?
+    }
+
+    /// Authenticates and returns the first car and track the lobby offers
+    async fn authenticate(&mut self, name: &str) -> Result<(CarConfigId, TrackConfigId), String> {
+        self.send(&ClientMessage::Authenticate {
+            token: format!("loadtest_{}", name),
+            player_name: name.to_string(),
+            telemetry_rate_hz: 0,
+        })
+        .await?;
+        loop {
+            match self.receive().await? {
+                ServerMessage::AuthFailure { reason } => return Err(reason),
+                ServerMessage::LobbyState(lobby) => {
+                    let car_id = lobby.car_configs.first().ok_or("Server has no cars")?.id;
+                    let track_id = lobby.track_configs.first().ok_or("Server has no tracks")?.id;
+                    return Ok((car_id, track_id));
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    async fn create_session(&mut self, track_id: TrackConfigId, max_players: u8) -> Result<SessionId, String> {
+        self.send(&ClientMessage::CreateSession {
+            track_config_id: track_id,
+            max_players,
+            session_kind: SessionKind::Practice,
+            weather: WeatherSettings::default(),
+            setup_policy: SetupPolicy::Open,
+            collision_policy: CollisionPolicy::Solid,
+            time_of_day: TimeOfDay::Noon,
+            aid_limits: None,
+            ai_count: 0,
+            lap_limit: 3,
+        })
+        .await?;
+        self.await_session_joined().await
+    }
+
+    async fn join_session(&mut self, session_id: SessionId) -> Result<(), String> {
+        self.send(&ClientMessage::JoinSession { session_id }).await?;
+        self.await_session_joined().await.map(|_| ())
+    }
+
+    async fn await_session_joined(&mut self) -> Result<SessionId, String> {
+        loop {
+            match self.receive().await? {
+                ServerMessage::SessionJoined(data) => return Ok(data.session_id),
+                ServerMessage::Error { message, .. } => return Err(message),
+                _ => continue,
+            }
+        }
+    }
+}
+
+async fn write_message<W: AsyncWrite + Unpin>(writer: &mut W, message: &ClientMessage) -> Result<(), String> {
+    let data = rmp_serde::to_vec_named(message).map_err(|e| e.to_string())?;
+    writer.write_all(&(data.len() as u32).to_be_bytes()).await.map_err(|e| e.to_string())?;
+    writer.write_all(&data).await.map_err(|e| e.to_string())?;
+    writer.flush().await.map_err(|e| e.to_string())
+}
+
+async fn read_message<R: AsyncRead + Unpin>(reader: &mut R) -> Result<ServerMessage, String> {
+    let mut len_buf = [0u8; 4];
+    reader.read_exact(&mut len_buf).await.map_err(|e| e.to_string())?;
+    let mut buf = vec![0u8; u32::from_be_bytes(len_buf) as usize];
+    reader.read_exact(&mut buf).await.map_err(|e| e.to_string())?;
+    rmp_serde::from_slice(&buf).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scenario_fills_in_defaults() {
+        let scenario = Scenario::from_toml_str(
+            r#"
+            name = "smoke"
+            target = "127.0.0.1:9000"
+            clients = 4
+            input_pattern = "weave"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(scenario.clients, 4);
+        assert_eq!(scenario.input_pattern, InputPattern::Weave);
+        assert_eq!(scenario.tick_rate_hz, 240);
+        assert_eq!(scenario.input_rate_hz, 60);
+        assert_eq!(scenario.duration_s, 10.0);
+    }
+
+    #[test]
+    fn test_scenario_rejects_more_clients_than_grid_slots() {
+        let toml = format!("target = \"127.0.0.1:9000\"\nclients = {}", MAX_CLIENTS + 1);
+        assert!(Scenario::from_toml_str(&toml).is_err());
+        assert!(Scenario::from_toml_str("target = \"127.0.0.1:9000\"\nduration_s = 0.0").is_err());
+    }
+
+    #[test]
+    fn test_random_inputs_repeat_for_a_seed() {
+        let drive = |seed: u64| {
+            let mut rng = seed;
+            (0..20).map(|i| InputPattern::Random.input(&mut rng, i as f64)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(drive(7), drive(7));
+        assert_ne!(drive(7), drive(8));
+        for (throttle, brake, steering) in drive(7) {
+            assert!((0.0..=1.0).contains(&throttle));
+            assert!((0.0..=0.5).contains(&brake));
+            assert!(steering.abs() <= 0.3);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_target_is_reported() {
+        let mut scenario = Scenario::new("127.0.0.1:1", 1, 240);
+        scenario.duration_s = 0.1;
+
+        let report = run(&scenario).await;
+
+        assert!(!report.passed);
+        assert_eq!(report.clients_connected, 0);
+        assert!(report.error.unwrap().contains("could not connect"));
+    }
+}
@@ -1,10 +1,16 @@
 use crate::data::*;
-use crate::network::{LobbyPlayer, SessionSummary};
+use crate::network::{LobbyPlayer, LobbyUpdate, LobbyUpdateKind, RaceProgress, SessionSummary};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 use tracing::{info, warn};
 
+/// Rating given to players until a league admin assigns one
+pub const DEFAULT_PLAYER_RATING: u32 = 1500;
+
+/// Lobby updates buffered per subscriber before it starts lagging
+const LOBBY_UPDATE_CHANNEL_SIZE: usize = 1024;
+
 /// Represents a player in the lobby (not in any session)
 #[derive(Debug, Clone)]
 pub struct LobbyPlayerState {
@@ -12,6 +18,22 @@ pub struct LobbyPlayerState {
     pub player_name: String,
     pub connection_id: ConnectionId,
     pub selected_car: Option<CarConfigId>,
+    /// Index into the selected car's liveries
+    pub selected_livery: u8,
+    /// League rating, used when splitting oversubscribed sessions into grids
+    pub rating: u32,
+}
+
+impl LobbyPlayerState {
+    fn summary(&self, in_session: Option<SessionId>) -> LobbyPlayer {
+        LobbyPlayer {
+            id: self.player_id,
+            name: self.player_name.clone(),
+            selected_car: self.selected_car,
+            selected_livery: self.selected_livery,
+            in_session,
+        }
+    }
 }
 
 /// Session visibility settings
@@ -39,6 +61,39 @@ pub struct LobbySessionInfo {
     pub visibility: SessionVisibility,
     pub password_hash: Option<String>,
     pub created_at: std::time::Instant,
+    /// Open practice session: joinable while running and kept up when empty
+    pub always_open: bool,
+    /// Leader's progress while a lap-limited race runs
+    pub race_progress: Option<RaceProgress>,
+    pub setup_policy: SetupPolicy,
+    pub collision_policy: CollisionPolicy,
+    pub time_of_day: TimeOfDay,
+    pub aid_limits: DriverAids,
+}
+
+impl LobbySessionInfo {
+    /// No players or spectators left and not a session that is kept open
+    fn is_abandoned(&self) -> bool {
+        !self.always_open && self.current_player_count == 0 && self.spectator_count == 0
+    }
+
+    fn summary(&self) -> SessionSummary {
+        SessionSummary {
+            id: self.session_id,
+            track_name: self.track_name.clone(),
+            track_file: self.track_file.clone(),
+            host_name: self.host_name.clone(),
+            session_kind: self.session_kind,
+            player_count: self.current_player_count,
+            max_players: self.max_players,
+            state: self.state,
+            race_progress: self.race_progress,
+            setup_policy: self.setup_policy,
+            collision_policy: self.collision_policy,
+            time_of_day: self.time_of_day,
+            aid_limits: self.aid_limits,
+        }
+    }
 }
 
 /// Manages the lobby state and player matchmaking
@@ -54,6 +109,9 @@ pub struct LobbyManager {
 
     /// Spectators in sessions (player_id -> session_id)
     spectators: Arc<RwLock<HashMap<PlayerId, SessionId>>>,
+
+    /// Changes visible in the lobby, published as they happen
+    updates: broadcast::Sender<LobbyUpdate>,
 }
 
 impl LobbyManager {
@@ -63,6 +121,35 @@ impl LobbyManager {
             sessions: Arc::new(RwLock::new(HashMap::new())),
             player_sessions: Arc::new(RwLock::new(HashMap::new())),
             spectators: Arc::new(RwLock::new(HashMap::new())),
+            updates: broadcast::channel(LOBBY_UPDATE_CHANNEL_SIZE).0,
+        }
+    }
+
+    /// Receive every lobby change from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<LobbyUpdate> {
+        self.updates.subscribe()
+    }
+
+    fn publish(&self, update: LobbyUpdate) {
+        // No subscribers is fine, there is nobody to tell
+        let _ = self.updates.send(update);
+    }
+
+    async fn publish_player(&self, kind: LobbyUpdateKind, player_id: PlayerId) {
+        let in_session = self.player_sessions.read().await.get(&player_id).copied();
+        let player = self.players.read().await.get(&player_id).map(|p| p.summary(in_session));
+        if let Some(player) = player {
+            self.publish(LobbyUpdate::player(kind, player));
+        }
+    }
+
+    async fn publish_session(&self, kind: LobbyUpdateKind, session_id: SessionId) {
+        let session = self.sessions.read().await
+            .get(&session_id)
+            .filter(|s| s.visibility == SessionVisibility::Public)
+            .map(LobbySessionInfo::summary);
+        if let Some(session) = session {
+            self.publish(LobbyUpdate::session(kind, session));
         }
     }
 
@@ -73,6 +160,7 @@ impl LobbyManager {
 
         self.players.write().await.insert(player_id, player);
         info!("Player {} added to lobby", player_name);
+        self.publish_player(LobbyUpdateKind::PlayerJoined, player_id).await;
     }
 
     /// Remove a player from the lobby
@@ -82,21 +170,24 @@ impl LobbyManager {
 
         if let Some(ref p) = player {
             info!("Player {} removed from lobby", p.player_name);
+            self.publish(LobbyUpdate::player_left(player_id));
 
             // Also remove from any session
-            if let Some(session_id) = self.player_sessions.write().await.remove(&player_id) {
+            let left_session = self.player_sessions.write().await.remove(&player_id);
+            if let Some(session_id) = left_session {
                 if let Some(session) = self.sessions.write().await.get_mut(&session_id) {
                     session.current_player_count = session.current_player_count.saturating_sub(1);
-                    if session.current_player_count == 0 && session.spectator_count == 0 {
+                    if session.is_abandoned() {
                         empty_session_id = Some(session_id);
                     }
                 }
+                self.publish_session(LobbyUpdateKind::SessionUpdated, session_id).await;
             }
             
             if let Some(session_id) = self.spectators.write().await.remove(&player_id) {
                 if let Some(session) = self.sessions.write().await.get_mut(&session_id) {
                     session.spectator_count = session.spectator_count.saturating_sub(1);
-                    if session.current_player_count == 0 && session.spectator_count == 0 {
+                    if session.is_abandoned() {
                         empty_session_id = Some(session_id);
                     }
                 }
@@ -106,10 +197,18 @@ impl LobbyManager {
         (player, empty_session_id)
     }
 
-    /// Update a player's selected car
-    pub async fn set_player_car(&self, player_id: PlayerId, car_config_id: CarConfigId) {
-        if let Some(player) = self.players.write().await.get_mut(&player_id) {
-            player.selected_car = Some(car_config_id);
+    /// Update a player's selected car and livery
+    pub async fn set_player_car(&self, player_id: PlayerId, car_config_id: CarConfigId, livery: u8) {
+        let updated = match self.players.write().await.get_mut(&player_id) {
+            Some(player) => {
+                player.selected_car = Some(car_config_id);
+                player.selected_livery = livery;
+                true
+            }
+            None => false,
+        };
+        if updated {
+            self.publish_player(LobbyUpdateKind::PlayerUpdated, player_id).await;
         }
     }
 
@@ -118,6 +217,26 @@ impl LobbyManager {
         self.players.read().await.get(&player_id).and_then(|p| p.selected_car)
     }
 
+    /// Get a copy of a player's lobby state
+    pub async fn get_player(&self, player_id: PlayerId) -> Option<LobbyPlayerState> {
+        self.players.read().await.get(&player_id).cloned()
+    }
+
+    /// Set a player's league rating
+    pub async fn set_player_rating(&self, player_id: PlayerId, rating: u32) -> bool {
+        if let Some(player) = self.players.write().await.get_mut(&player_id) {
+            player.rating = rating;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get a player's league rating
+    pub async fn get_player_rating(&self, player_id: PlayerId) -> Option<u32> {
+        self.players.read().await.get(&player_id).map(|p| p.rating)
+    }
+
     /// Register a new session in the lobby
     pub async fn register_session(&self, session_info: LobbySessionInfo) {
         let session_id = session_info.session_id;
@@ -125,31 +244,153 @@ impl LobbyManager {
 
         self.sessions.write().await.insert(session_id, session_info);
         info!("Session {} registered in lobby (host: {})", session_id, host_name);
+        self.publish_session(LobbyUpdateKind::SessionCreated, session_id).await;
     }
 
     /// Unregister a session from the lobby
     pub async fn unregister_session(&self, session_id: SessionId) {
-        if let Some(_session) = self.sessions.write().await.remove(&session_id) {
+        let removed = self.sessions.write().await.remove(&session_id);
+        if let Some(session) = removed {
             info!("Session {} unregistered from lobby", session_id);
+            if session.visibility == SessionVisibility::Public {
+                self.publish(LobbyUpdate::session_removed(session_id));
+            }
 
             // Remove all players from this session
-            let mut player_sessions = self.player_sessions.write().await;
-            player_sessions.retain(|_, sid| *sid != session_id);
+            let mut released = Vec::new();
+            self.player_sessions.write().await.retain(|pid, sid| {
+                let keep = *sid != session_id;
+                if !keep {
+                    released.push(*pid);
+                }
+                keep
+            });
 
             // Remove all spectators from this session
             let mut spectators = self.spectators.write().await;
             spectators.retain(|_, sid| *sid != session_id);
+            drop(spectators);
+
+            for player_id in released {
+                self.publish_player(LobbyUpdateKind::PlayerUpdated, player_id).await;
+            }
         }
     }
 
     /// Update session information (player count, state, etc.)
     pub async fn update_session(&self, session_id: SessionId, player_count: u8, state: SessionState) {
-        if let Some(session) = self.sessions.write().await.get_mut(&session_id) {
-            session.current_player_count = player_count;
-            session.state = state;
+        let changed = match self.sessions.write().await.get_mut(&session_id) {
+            Some(session) if session.current_player_count != player_count || session.state != state => {
+                session.current_player_count = player_count;
+                session.state = state;
+                true
+            }
+            _ => false,
+        };
+        if changed {
+            self.publish_session(LobbyUpdateKind::SessionUpdated, session_id).await;
         }
     }
 
+    /// Update a session's state, keeping its player count
+    pub async fn set_session_state(&self, session_id: SessionId, state: SessionState) {
+        let changed = match self.sessions.write().await.get_mut(&session_id) {
+            Some(session) if session.state != state => {
+                session.state = state;
+                true
+            }
+            _ => false,
+        };
+        if changed {
+            self.publish_session(LobbyUpdateKind::SessionUpdated, session_id).await;
+        }
+    }
+
+    /// Record a running race's progress.
+    ///
+    /// Lobby clients only hear about it when the leader starts a new lap or the
+    /// estimate moves to another minute, so a listing does not change every tick.
+    pub async fn set_race_progress(&self, session_id: SessionId, progress: Option<RaceProgress>) {
+        let display_minutes = |p: &RaceProgress| p.estimated_remaining_s.map(|s| s.div_ceil(60));
+        let changed = match self.sessions.write().await.get_mut(&session_id) {
+            Some(session) => {
+                let changed = match (&session.race_progress, &progress) {
+                    (Some(old), Some(new)) => old.leader_lap != new.leader_lap || display_minutes(old) != display_minutes(new),
+                    (None, None) => false,
+                    _ => true,
+                };
+                session.race_progress = progress;
+                changed
+            }
+            None => false,
+        };
+        if changed {
+            self.publish_session(LobbyUpdateKind::SessionUpdated, session_id).await;
+        }
+    }
+
+    /// Change how many drivers a session takes; false if it has more already or does not exist
+    pub async fn set_max_players(&self, session_id: SessionId, max_players: u8) -> bool {
+        match self.sessions.write().await.get_mut(&session_id) {
+            Some(session) if session.current_player_count <= max_players => session.max_players = max_players,
+            _ => return false,
+        }
+        self.publish_session(LobbyUpdateKind::SessionUpdated, session_id).await;
+        true
+    }
+
+    /// Get a copy of a session's lobby info
+    pub async fn get_session_info(&self, session_id: SessionId) -> Option<LobbySessionInfo> {
+        self.sessions.read().await.get(&session_id).cloned()
+    }
+
+    /// Move a participant from their current session to another one.
+    ///
+    /// Both sessions must still be in lobby state and the target must have room.
+    pub async fn move_player(&self, player_id: PlayerId, target_session_id: SessionId) -> bool {
+        let mut player_sessions = self.player_sessions.write().await;
+        let Some(source_session_id) = player_sessions.get(&player_id).copied() else {
+            warn!("Player {} is not in a session", player_id);
+            return false;
+        };
+
+        if source_session_id == target_session_id {
+            return false;
+        }
+
+        let mut sessions = self.sessions.write().await;
+        let source_ok = sessions
+            .get(&source_session_id)
+            .is_some_and(|s| s.state == SessionState::Lobby);
+        let target_ok = sessions.get(&target_session_id).is_some_and(|s| {
+            s.state == SessionState::Lobby && s.current_player_count < s.max_players
+        });
+
+        if !source_ok || !target_ok {
+            warn!(
+                "Cannot move player {} from session {} to {} (not in lobby state or full)",
+                player_id, source_session_id, target_session_id
+            );
+            return false;
+        }
+
+        if let Some(source) = sessions.get_mut(&source_session_id) {
+            source.current_player_count = source.current_player_count.saturating_sub(1);
+        }
+        if let Some(target) = sessions.get_mut(&target_session_id) {
+            target.current_player_count += 1;
+        }
+        player_sessions.insert(player_id, target_session_id);
+        drop(sessions);
+        drop(player_sessions);
+
+        info!("Player {} moved from session {} to {}", player_id, source_session_id, target_session_id);
+        self.publish_player(LobbyUpdateKind::PlayerUpdated, player_id).await;
+        self.publish_session(LobbyUpdateKind::SessionUpdated, source_session_id).await;
+        self.publish_session(LobbyUpdateKind::SessionUpdated, target_session_id).await;
+        true
+    }
+
     /// Add a player to a session (as participant)
     pub async fn join_session(&self, player_id: PlayerId, session_id: SessionId) -> bool {
         // Check if session exists and has space
@@ -160,7 +401,7 @@ impl LobbyManager {
                 return false;
             }
 
-            if session.state != SessionState::Lobby {
+            if session.state != SessionState::Lobby && !session.always_open {
                 warn!("Session {} is not in lobby state", session_id);
                 return false;
             }
@@ -182,6 +423,8 @@ impl LobbyManager {
             }
 
             info!("Player {} joined session {}", player_id, session_id);
+            self.publish_player(LobbyUpdateKind::PlayerUpdated, player_id).await;
+            self.publish_session(LobbyUpdateKind::SessionUpdated, session_id).await;
             true
         } else {
             warn!("Player {} not in lobby", player_id);
@@ -189,13 +432,42 @@ impl LobbyManager {
         }
     }
 
+    /// Add a player to a session in any state, for players resuming a session recovered by a standby
+    pub async fn resume_player(&self, player_id: PlayerId, session_id: SessionId) -> bool {
+        if !self.players.read().await.contains_key(&player_id) {
+            warn!("Player {} not in lobby", player_id);
+            return false;
+        }
+
+        let mut sessions = self.sessions.write().await;
+        let Some(session) = sessions.get_mut(&session_id) else {
+            warn!("Session {} does not exist", session_id);
+            return false;
+        };
+        session.current_player_count += 1;
+        drop(sessions);
+
+        self.player_sessions.write().await.insert(player_id, session_id);
+        info!("Player {} resumed session {}", player_id, session_id);
+        self.publish_player(LobbyUpdateKind::PlayerUpdated, player_id).await;
+        self.publish_session(LobbyUpdateKind::SessionUpdated, session_id).await;
+        true
+    }
+
     /// Add a player as spectator to a session
     pub async fn join_as_spectator(&self, player_id: PlayerId, session_id: SessionId) -> bool {
-        // Check if session exists
+        // Check if session exists and is ready to watch
         let sessions = self.sessions.read().await;
-        if !sessions.contains_key(&session_id) {
-            warn!("Session {} does not exist", session_id);
-            return false;
+        match sessions.get(&session_id) {
+            Some(session) if session.state == SessionState::Preparing => {
+                warn!("Session {} is still being prepared", session_id);
+                return false;
+            }
+            Some(_) => {}
+            None => {
+                warn!("Session {} does not exist", session_id);
+                return false;
+            }
         }
         drop(sessions);
 
@@ -221,16 +493,19 @@ impl LobbyManager {
         let mut empty_session_id = None;
 
         // Check if player is in a session
-        if let Some(session_id) = self.player_sessions.write().await.remove(&player_id) {
+        let left_session = self.player_sessions.write().await.remove(&player_id);
+        if let Some(session_id) = left_session {
             // Update session player count
             if let Some(session) = self.sessions.write().await.get_mut(&session_id) {
                 session.current_player_count = session.current_player_count.saturating_sub(1);
-                if session.current_player_count == 0 && session.spectator_count == 0 {
+                if session.is_abandoned() {
                     empty_session_id = Some(session_id);
                 }
             }
 
             info!("Player {} left session {}", player_id, session_id);
+            self.publish_player(LobbyUpdateKind::PlayerUpdated, player_id).await;
+            self.publish_session(LobbyUpdateKind::SessionUpdated, session_id).await;
         }
 
         // Check if player is spectating
@@ -238,7 +513,7 @@ impl LobbyManager {
             // Update spectator count
             if let Some(session) = self.sessions.write().await.get_mut(&session_id) {
                 session.spectator_count = session.spectator_count.saturating_sub(1);
-                if session.current_player_count == 0 && session.spectator_count == 0 {
+                if session.is_abandoned() {
                     empty_session_id = Some(session_id);
                 }
             }
@@ -257,16 +532,7 @@ impl LobbyManager {
         sessions
             .values()
             .filter(|s| s.visibility == SessionVisibility::Public)
-            .map(|s| SessionSummary {
-                id: s.session_id,
-                track_name: s.track_name.clone(),
-                track_file: s.track_file.clone(),
-                host_name: s.host_name.clone(),
-                session_kind: s.session_kind,
-                player_count: s.current_player_count,
-                max_players: s.max_players,
-                state: s.state,
-            })
+            .map(LobbySessionInfo::summary)
             .collect()
     }
 
@@ -277,12 +543,7 @@ impl LobbyManager {
 
         players
             .values()
-            .map(|p| LobbyPlayer {
-                id: p.player_id,
-                name: p.player_name.clone(),
-                selected_car: p.selected_car,
-                in_session: player_sessions.get(&p.player_id).copied(),
-            })
+            .map(|p| p.summary(player_sessions.get(&p.player_id).copied()))
             .collect()
     }
 
@@ -301,6 +562,15 @@ impl LobbyManager {
         self.spectators.read().await.get(&player_id).copied()
     }
 
+    /// Get all participants of a given session
+    pub async fn get_session_players(&self, session_id: SessionId) -> Vec<PlayerId> {
+        self.player_sessions.read().await
+            .iter()
+            .filter(|(_, sid)| **sid == session_id)
+            .map(|(pid, _)| *pid)
+            .collect()
+    }
+
     /// Get all spectators for a given session
     pub async fn get_session_spectators(&self, session_id: SessionId) -> Vec<PlayerId> {
         self.spectators.read().await
@@ -335,13 +605,15 @@ mod tests {
     #[tokio::test]
     async fn test_lobby_add_remove_player() {
         let lobby = LobbyManager::new();
-        let player_id = Uuid::new_v4();
+        let player_id = PlayerId::new_v4();
 
         let player = LobbyPlayerState {
             player_id,
             player_name: "TestPlayer".to_string(),
             connection_id: Uuid::new_v4(),
             selected_car: None,
+            selected_livery: 0,
+            rating: DEFAULT_PLAYER_RATING,
         };
 
         lobby.add_player(player).await;
@@ -355,15 +627,15 @@ mod tests {
     #[tokio::test]
     async fn test_session_registration() {
         let lobby = LobbyManager::new();
-        let session_id = Uuid::new_v4();
+        let session_id = SessionId::new_v4();
 
         let session_info = LobbySessionInfo {
             session_id,
-            host_player_id: Uuid::new_v4(),
+            host_player_id: PlayerId::new_v4(),
             host_name: "Host".to_string(),
             track_name: "Test Track".to_string(),
             track_file: "tracks/TestTrack.yaml".to_string(),
-            track_config_id: Uuid::new_v4(),
+            track_config_id: TrackConfigId::new_v4(),
             session_kind: SessionKind::Multiplayer,
             max_players: 8,
             current_player_count: 0,
@@ -372,6 +644,12 @@ mod tests {
             visibility: SessionVisibility::Public,
             password_hash: None,
             created_at: std::time::Instant::now(),
+            always_open: false,
+            race_progress: None,
+            setup_policy: SetupPolicy::Open,
+            collision_policy: CollisionPolicy::Solid,
+            time_of_day: TimeOfDay::Noon,
+            aid_limits: DriverAids::unrestricted(),
         };
 
         lobby.register_session(session_info).await;
@@ -387,8 +665,8 @@ mod tests {
     #[tokio::test]
     async fn test_join_session() {
         let lobby = LobbyManager::new();
-        let player_id = Uuid::new_v4();
-        let session_id = Uuid::new_v4();
+        let player_id = PlayerId::new_v4();
+        let session_id = SessionId::new_v4();
 
         // Add player to lobby
         let player = LobbyPlayerState {
@@ -396,17 +674,19 @@ mod tests {
             player_name: "TestPlayer".to_string(),
             connection_id: Uuid::new_v4(),
             selected_car: None,
+            selected_livery: 0,
+            rating: DEFAULT_PLAYER_RATING,
         };
         lobby.add_player(player).await;
 
         // Register session
         let session_info = LobbySessionInfo {
             session_id,
-            host_player_id: Uuid::new_v4(),
+            host_player_id: PlayerId::new_v4(),
             host_name: "Host".to_string(),
             track_name: "Test Track".to_string(),
             track_file: "tracks/TestTrack.yaml".to_string(),
-            track_config_id: Uuid::new_v4(),
+            track_config_id: TrackConfigId::new_v4(),
             session_kind: SessionKind::Multiplayer,
             max_players: 8,
             current_player_count: 0,
@@ -415,6 +695,12 @@ mod tests {
             visibility: SessionVisibility::Public,
             password_hash: None,
             created_at: std::time::Instant::now(),
+            always_open: false,
+            race_progress: None,
+            setup_policy: SetupPolicy::Open,
+            collision_policy: CollisionPolicy::Solid,
+            time_of_day: TimeOfDay::Noon,
+            aid_limits: DriverAids::unrestricted(),
         };
         lobby.register_session(session_info).await;
 
@@ -430,11 +716,66 @@ mod tests {
         assert_eq!(player_session, Some(session_id));
     }
 
+    #[tokio::test]
+    async fn test_move_player_between_sessions() {
+        let lobby = LobbyManager::new();
+        let player_id = PlayerId::new_v4();
+        let source_id = SessionId::new_v4();
+        let target_id = SessionId::new_v4();
+
+        lobby.add_player(LobbyPlayerState {
+            player_id,
+            player_name: "Mover".to_string(),
+            connection_id: Uuid::new_v4(),
+            selected_car: None,
+            selected_livery: 0,
+            rating: DEFAULT_PLAYER_RATING,
+        }).await;
+
+        for (session_id, max_players) in [(source_id, 8), (target_id, 1)] {
+            lobby.register_session(LobbySessionInfo {
+                session_id,
+                host_player_id: PlayerId::new_v4(),
+                host_name: "Host".to_string(),
+                track_name: "Test Track".to_string(),
+                track_file: "tracks/TestTrack.yaml".to_string(),
+                track_config_id: TrackConfigId::new_v4(),
+                session_kind: SessionKind::Multiplayer,
+                max_players,
+                current_player_count: 0,
+                spectator_count: 0,
+                state: SessionState::Lobby,
+                visibility: SessionVisibility::Public,
+                password_hash: None,
+                created_at: std::time::Instant::now(),
+                always_open: false,
+                race_progress: None,
+                setup_policy: SetupPolicy::Open,
+                collision_policy: CollisionPolicy::Solid,
+                time_of_day: TimeOfDay::Noon,
+                aid_limits: DriverAids::unrestricted(),
+            }).await;
+        }
+
+        assert!(lobby.join_session(player_id, source_id).await);
+        assert!(lobby.move_player(player_id, target_id).await);
+
+        assert_eq!(lobby.get_player_session(player_id).await, Some(target_id));
+        assert_eq!(lobby.get_session_info(source_id).await.unwrap().current_player_count, 0);
+        assert_eq!(lobby.get_session_info(target_id).await.unwrap().current_player_count, 1);
+        assert_eq!(lobby.get_session_players(target_id).await, vec![player_id]);
+
+        // Moving back is refused once the source has started
+        lobby.update_session(source_id, 0, SessionState::Countdown).await;
+        assert!(!lobby.move_player(player_id, source_id).await);
+        assert_eq!(lobby.get_player_session(player_id).await, Some(target_id));
+    }
+
     #[tokio::test]
     async fn test_spectator_mode() {
         let lobby = LobbyManager::new();
-        let player_id = Uuid::new_v4();
-        let session_id = Uuid::new_v4();
+        let player_id = PlayerId::new_v4();
+        let session_id = SessionId::new_v4();
 
         // Add player to lobby
         let player = LobbyPlayerState {
@@ -442,17 +783,19 @@ mod tests {
             player_name: "Spectator".to_string(),
             connection_id: Uuid::new_v4(),
             selected_car: None,
+            selected_livery: 0,
+            rating: DEFAULT_PLAYER_RATING,
         };
         lobby.add_player(player).await;
 
         // Register session
         let session_info = LobbySessionInfo {
             session_id,
-            host_player_id: Uuid::new_v4(),
+            host_player_id: PlayerId::new_v4(),
             host_name: "Host".to_string(),
             track_name: "Test Track".to_string(),
             track_file: "tracks/TestTrack.yaml".to_string(),
-            track_config_id: Uuid::new_v4(),
+            track_config_id: TrackConfigId::new_v4(),
             session_kind: SessionKind::Multiplayer,
             max_players: 8,
             current_player_count: 2,
@@ -461,6 +804,12 @@ mod tests {
             visibility: SessionVisibility::Public,
             password_hash: None,
             created_at: std::time::Instant::now(),
+            always_open: false,
+            race_progress: None,
+            setup_policy: SetupPolicy::Open,
+            collision_policy: CollisionPolicy::Solid,
+            time_of_day: TimeOfDay::Noon,
+            aid_limits: DriverAids::unrestricted(),
         };
         lobby.register_session(session_info).await;
 
@@ -472,4 +821,166 @@ mod tests {
         assert!(lobby.is_spectator(player_id).await);
         assert_eq!(lobby.get_spectating_session(player_id).await, Some(session_id));
     }
+
+    #[tokio::test]
+    async fn test_preparing_session_cannot_be_joined() {
+        let lobby = LobbyManager::new();
+        let player_id = PlayerId::new_v4();
+        let session_id = SessionId::new_v4();
+
+        lobby.add_player(LobbyPlayerState {
+            player_id,
+            player_name: "Early".to_string(),
+            connection_id: Uuid::new_v4(),
+            selected_car: None,
+            selected_livery: 0,
+            rating: DEFAULT_PLAYER_RATING,
+        }).await;
+        lobby.register_session(LobbySessionInfo {
+            session_id,
+            host_player_id: PlayerId::new_v4(),
+            host_name: "Host".to_string(),
+            track_name: "Big Track".to_string(),
+            track_file: "tracks/BigTrack.yaml".to_string(),
+            track_config_id: TrackConfigId::new_v4(),
+            session_kind: SessionKind::Multiplayer,
+            max_players: 8,
+            current_player_count: 0,
+            spectator_count: 0,
+            state: SessionState::Preparing,
+            visibility: SessionVisibility::Public,
+            password_hash: None,
+            created_at: std::time::Instant::now(),
+            always_open: false,
+            race_progress: None,
+            setup_policy: SetupPolicy::Open,
+            collision_policy: CollisionPolicy::Solid,
+            time_of_day: TimeOfDay::Noon,
+            aid_limits: DriverAids::unrestricted(),
+        }).await;
+
+        // Listed, but neither drivers nor spectators get in until it is ready
+        assert_eq!(lobby.get_available_sessions().await[0].state, SessionState::Preparing);
+        assert!(!lobby.join_session(player_id, session_id).await);
+        assert!(!lobby.join_as_spectator(player_id, session_id).await);
+
+        lobby.set_session_state(session_id, SessionState::Lobby).await;
+        assert!(lobby.join_session(player_id, session_id).await);
+    }
+
+    #[tokio::test]
+    async fn test_lobby_updates_published() {
+        let lobby = LobbyManager::new();
+        let mut updates = lobby.subscribe();
+        let player_id = PlayerId::new_v4();
+        let session_id = SessionId::new_v4();
+
+        lobby.add_player(LobbyPlayerState {
+            player_id,
+            player_name: "Player".to_string(),
+            connection_id: Uuid::new_v4(),
+            selected_car: None,
+            selected_livery: 0,
+            rating: DEFAULT_PLAYER_RATING,
+        }).await;
+        lobby.register_session(LobbySessionInfo {
+            session_id,
+            host_player_id: player_id,
+            host_name: "Player".to_string(),
+            track_name: "Test Track".to_string(),
+            track_file: "tracks/TestTrack.yaml".to_string(),
+            track_config_id: TrackConfigId::new_v4(),
+            session_kind: SessionKind::Multiplayer,
+            max_players: 8,
+            current_player_count: 0,
+            spectator_count: 0,
+            state: SessionState::Lobby,
+            visibility: SessionVisibility::Public,
+            password_hash: None,
+            created_at: std::time::Instant::now(),
+            always_open: false,
+            race_progress: None,
+            setup_policy: SetupPolicy::Open,
+            collision_policy: CollisionPolicy::Solid,
+            time_of_day: TimeOfDay::Noon,
+            aid_limits: DriverAids::unrestricted(),
+        }).await;
+        assert!(lobby.join_session(player_id, session_id).await);
+        lobby.set_session_state(session_id, SessionState::Racing).await;
+        // Unchanged state publishes nothing
+        lobby.set_session_state(session_id, SessionState::Racing).await;
+        lobby.unregister_session(session_id).await;
+        lobby.remove_player(player_id).await;
+
+        let mut received = Vec::new();
+        while let Ok(update) = updates.try_recv() {
+            received.push(update);
+        }
+        let kinds: Vec<_> = received.iter().map(|u| u.kind).collect();
+        assert_eq!(kinds, vec![
+            LobbyUpdateKind::PlayerJoined,
+            LobbyUpdateKind::SessionCreated,
+            LobbyUpdateKind::PlayerUpdated,
+            LobbyUpdateKind::SessionUpdated,
+            LobbyUpdateKind::SessionUpdated,
+            LobbyUpdateKind::SessionRemoved,
+            LobbyUpdateKind::PlayerUpdated,
+            LobbyUpdateKind::PlayerLeft,
+        ]);
+
+        assert_eq!(received[2].player.as_ref().unwrap().in_session, Some(session_id));
+        assert_eq!(received[3].session.as_ref().unwrap().player_count, 1);
+        assert_eq!(received[4].session.as_ref().unwrap().state, SessionState::Racing);
+        assert_eq!(received[6].player.as_ref().unwrap().in_session, None);
+        assert_eq!(received[7].player_id, Some(player_id));
+    }
+
+    #[tokio::test]
+    async fn test_race_progress_published_per_lap_and_minute() {
+        let lobby = LobbyManager::new();
+        let session_id = SessionId::new_v4();
+        lobby.register_session(LobbySessionInfo {
+            session_id,
+            host_player_id: PlayerId::new_v4(),
+            host_name: "Host".to_string(),
+            track_name: "Test Track".to_string(),
+            track_file: "tracks/TestTrack.yaml".to_string(),
+            track_config_id: TrackConfigId::new_v4(),
+            session_kind: SessionKind::Multiplayer,
+            max_players: 8,
+            current_player_count: 1,
+            spectator_count: 0,
+            state: SessionState::Racing,
+            visibility: SessionVisibility::Public,
+            password_hash: None,
+            created_at: std::time::Instant::now(),
+            always_open: false,
+            race_progress: None,
+            setup_policy: SetupPolicy::Open,
+            collision_policy: CollisionPolicy::Solid,
+            time_of_day: TimeOfDay::Noon,
+            aid_limits: DriverAids::unrestricted(),
+        }).await;
+        let mut updates = lobby.subscribe();
+
+        let progress = |leader_lap: u16, estimated_remaining_s: Option<u32>| RaceProgress {
+            leader_lap,
+            total_laps: 20,
+            completed_fraction: leader_lap as f32 / 20.0,
+            estimated_remaining_s,
+        };
+        lobby.set_race_progress(session_id, Some(progress(12, None))).await;
+        lobby.set_race_progress(session_id, Some(progress(12, Some(540)))).await;
+        // Still "9 min left"
+        lobby.set_race_progress(session_id, Some(progress(12, Some(500)))).await;
+        lobby.set_race_progress(session_id, Some(progress(12, Some(479)))).await;
+        lobby.set_race_progress(session_id, Some(progress(13, Some(470)))).await;
+
+        let mut published = Vec::new();
+        while let Ok(update) = updates.try_recv() {
+            published.push(update.session.unwrap().race_progress.unwrap());
+        }
+        let shown: Vec<_> = published.iter().map(|p| (p.leader_lap, p.estimated_remaining_s)).collect();
+        assert_eq!(shown, vec![(12, None), (12, Some(540)), (12, Some(479)), (13, Some(470))]);
+    }
 }
@@ -0,0 +1,178 @@
+//! Log file output with rotation.
+//!
+//! The file has a filter of its own, so it can keep more detail than the
+//! console shows. Lines are written from a background thread and the game loop
+//! never waits on the disk. Time rotation starts a new dated file every hour or
+//! day; size rotation moves a full `server.log` to `server.log.1`, the old
+//! `.1` to `.2`, and so on.
+
+use crate::config::{LogFileFormat, LogFileSettings, LogRotation};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{EnvFilter, Layer, Registry};
+
+/// A layer of the server's subscriber
+pub type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+/// A layer writing to the configured log file, and the guard that flushes
+/// its last lines when dropped at exit
+pub fn file_layer(settings: &LogFileSettings) -> io::Result<(BoxedLayer, WorkerGuard)> {
+    let filter = EnvFilter::try_new(&settings.level).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let path = Path::new(&settings.path);
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let (writer, guard) = match settings.rotation {
+        LogRotation::Size => tracing_appender::non_blocking(SizeRotatingFile::open(
+            path,
+            settings.max_size_mb.saturating_mul(1024 * 1024),
+            settings.max_files,
+        )?),
+        rotation => tracing_appender::non_blocking(timed_appender(path, rotation, settings.max_files)?),
+    };
+
+    let layer = tracing_subscriber::fmt::layer().with_writer(writer).with_ansi(false);
+    let layer = match settings.format {
+        LogFileFormat::Plain => layer.with_filter(filter).boxed(),
+        LogFileFormat::Json => layer.json().with_filter(filter).boxed(),
+    };
+    Ok((layer, guard))
+}
+
+fn timed_appender(path: &Path, rotation: LogRotation, max_files: usize) -> io::Result<RollingFileAppender> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "log file path has no file name"))?;
+    let mut builder = RollingFileAppender::builder()
+        .rotation(match rotation {
+            LogRotation::Hourly => Rotation::HOURLY,
+            LogRotation::Daily => Rotation::DAILY,
+            LogRotation::Never | LogRotation::Size => Rotation::NEVER,
+        })
+        .filename_prefix(file_name.to_string_lossy());
+    if max_files > 0 {
+        // The appender counts the current file too
+        builder = builder.max_log_files(max_files + 1);
+    }
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    builder.build(dir).map_err(io::Error::other)
+}
+
+/// A file that is moved aside once it reaches `max_bytes`
+pub struct SizeRotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    /// Rotated files kept; 0 keeps every one
+    max_files: usize,
+    file: File,
+    len: u64,
+}
+
+impl SizeRotatingFile {
+    /// Append to `path`, which may already hold a log
+    pub fn open(path: &Path, max_bytes: u64, max_files: usize) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let len = file.metadata()?.len();
+        Ok(Self { path: path.to_path_buf(), max_bytes, max_files, file, len })
+    }
+
+    /// Path of the `n`th newest rotated file
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", n));
+        name.into()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let oldest = if self.max_files == 0 {
+            (1..).find(|n| !self.rotated(*n).exists()).unwrap_or(1)
+        } else {
+            let oldest = self.rotated(self.max_files);
+            if oldest.exists() {
+                std::fs::remove_file(oldest)?;
+            }
+            self.max_files
+        };
+        for n in (1..oldest).rev() {
+            let from = self.rotated(n);
+            if from.exists() {
+                std::fs::rename(from, self.rotated(n + 1))?;
+            }
+        }
+        std::fs::rename(&self.path, self.rotated(1))?;
+
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.len = 0;
+        Ok(())
+    }
+}
+
+impl Write for SizeRotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.len > 0 && self.len + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.len += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("apexsim-log-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_size_rotation_keeps_max_files() {
+        let dir = temp_dir("size");
+        let path = dir.join("server.log");
+        let mut file = SizeRotatingFile::open(&path, 20, 2).unwrap();
+        for line in ["first line 0123456\n", "second line 012345\n", "third line 0123456\n", "fourth line 012345\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+        file.flush().unwrap();
+
+        let read = |path: PathBuf| std::fs::read_to_string(path).unwrap();
+        assert_eq!(read(path.clone()), "fourth line 012345\n");
+        assert_eq!(read(dir.join("server.log.1")), "third line 0123456\n");
+        assert_eq!(read(dir.join("server.log.2")), "second line 012345\n");
+        assert!(!dir.join("server.log.3").exists(), "The oldest is removed");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_size_rotation_appends_to_an_existing_log() {
+        let dir = temp_dir("append");
+        let path = dir.join("server.log");
+        std::fs::write(&path, "from the last run\n").unwrap();
+
+        let mut file = SizeRotatingFile::open(&path, 1024, 0).unwrap();
+        file.write_all(b"this run\n").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "from the last run\nthis run\n");
+
+        // Keeping every file: each rotation adds one
+        let mut file = SizeRotatingFile::open(&path, 1, 0).unwrap();
+        for line in ["a\n", "b\n", "c\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+        assert_eq!(std::fs::read_to_string(dir.join("server.log.3")).unwrap(), "from the last run\nthis run\n");
+        assert_eq!(std::fs::read_to_string(dir.join("server.log.1")).unwrap(), "b\n");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,21 +1,16 @@
 use apexsim_server::{
-    car_loader::CarLoader,
     config::ServerConfig,
-    data::*,
-    game_session::GameSession,
-    health::{HealthState, run_health_server},
-    lobby::LobbyManager,
-    network::{LobbyStateData, SessionJoinedData},
-    replay::ReplayManager,
+    data::TrackConfig,
+    health::HealthState,
+    log_file::{self, BoxedLayer},
+    master_server::MasterServerClient,
+    replay::{ReplayExportFormat, ReplayPlayer},
+    runtime::{ServerRuntime, ServerState},
     track_loader::TrackLoader,
-    transport::TransportLayer,
 };
 use clap::Parser;
-use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::RwLock;
-use tokio::time::{interval, Duration};
-use tracing::{debug, info, warn};
+use tracing::{error, info, warn};
+use tracing_subscriber::prelude::*;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -31,206 +26,117 @@ struct Args {
     /// Generate procedural terrain for all tracks with environment_type metadata
     #[arg(long)]
     generate_terrain: bool,
-}
 
-struct ServerState {
-    config: ServerConfig,
-    car_configs: HashMap<CarConfigId, CarConfig>,
-    track_configs: HashMap<TrackConfigId, TrackConfig>,
-    sessions: HashMap<SessionId, GameSession>,
-    players: HashMap<PlayerId, Player>,
-    lobby: LobbyManager,
-    replay: ReplayManager,
-}
+    /// Generate a circuit with this name into `<tracks_dir>/generated` and exit
+    #[arg(long, value_name = "NAME")]
+    generate_track: Option<String>,
 
-impl ServerState {
-    fn new(config: ServerConfig) -> Self {
-        let mut car_configs = HashMap::new();
-        let mut track_configs = HashMap::new();
+    /// Lap length of a generated circuit (m)
+    #[arg(long, value_name = "M", default_value_t = 3500.0)]
+    gen_length_m: f32,
 
-        // Load custom cars from configured directory
-        let cars_dir = config.content.cars_dir.clone();
-        info!("Loading cars from {}...", cars_dir);
-        Self::load_custom_cars(&mut car_configs, &cars_dir);
+    /// Corners of a generated circuit
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    gen_corners: u32,
 
-        if car_configs.is_empty() {
-            warn!("No cars loaded! Creating default car.");
-            let default_car = CarConfig::default();
-            car_configs.insert(default_car.id, default_car);
-        } else {
-            info!("Loaded {} car(s):", car_configs.len());
-            for car in car_configs.values() {
-                info!("  - {} (ID: {})", car.name, car.id);
-            }
-        }
+    /// How much of the line between two corners stays straight (0-0.9)
+    #[arg(long, value_name = "X", default_value_t = 0.4)]
+    gen_straights: f32,
 
-        // Load custom tracks from configured directory
-        let tracks_dir = config.content.tracks_dir.clone();
-        info!("Loading tracks from {}...", tracks_dir);
-        Self::load_custom_tracks(&mut track_configs, &tracks_dir);
+    /// Height between the lowest and highest point of a generated circuit (m)
+    #[arg(long, value_name = "M", default_value_t = 15.0)]
+    gen_elevation_m: f32,
 
-        if track_configs.is_empty() {
-            warn!("No tracks loaded! Server will not be able to create sessions.");
-        } else {
-            info!("Loaded {} track(s):", track_configs.len());
-            for track in track_configs.values() {
-                info!("  - {} (ID: {})", track.name, track.id);
-            }
-        }
+    /// Track width of a generated circuit (m)
+    #[arg(long, value_name = "M", default_value_t = 12.0)]
+    gen_width_m: f32,
 
-        Self {
-            config,
-            car_configs,
-            track_configs,
-            sessions: HashMap::new(),
-            players: HashMap::new(),
-            lobby: LobbyManager::new(),
-            replay: ReplayManager::new(std::path::PathBuf::from("./replays")),
-        }
-    }
+    /// Seed of a generated circuit; random when not given
+    #[arg(long, value_name = "N")]
+    gen_seed: Option<u32>,
 
-    fn load_custom_tracks(track_configs: &mut HashMap<TrackConfigId, TrackConfig>, tracks_dir_str: &str) {
-        let tracks_dir = std::path::Path::new(tracks_dir_str);
+    /// Environment preset of a generated circuit's terrain, or `none`
+    #[arg(long, value_name = "PRESET", default_value = "plains")]
+    gen_environment: String,
 
-        // Content root is the parent of the tracks directory (e.g., ../content)
-        let content_root = tracks_dir.parent().unwrap_or(tracks_dir);
+    /// Run an AI-only QA race on every track and write a JSON report to this directory
+    #[arg(long, value_name = "DIR")]
+    qa_report: Option<String>,
 
-        if !tracks_dir.exists() {
-            info!("Tracks directory not found at {:?}, skipping custom track loading", tracks_dir);
-            return;
-        }
+    /// Run one accelerated AI-only race on this track (name, file name or id) and write a JSON report
+    #[arg(long, value_name = "TRACK")]
+    simulate_race: Option<String>,
 
-        Self::load_tracks_recursive(track_configs, tracks_dir, content_root);
-    }
+    /// Number of AI drivers in a simulated race
+    #[arg(long, value_name = "N", default_value_t = 8)]
+    sim_ai_count: u8,
 
-    fn load_tracks_recursive(
-        track_configs: &mut HashMap<TrackConfigId, TrackConfig>,
-        dir: &std::path::Path,
-        content_root: &std::path::Path,
-    ) {
-        match std::fs::read_dir(dir) {
-            Ok(entries) => {
-                for entry in entries.filter_map(|e| e.ok()) {
-                    let path = entry.path();
-                    if path.is_dir() {
-                        // Recursively load tracks from subdirectories
-                        Self::load_tracks_recursive(track_configs, &path, content_root);
-                    } else if path.is_file() {
-                        let ext = path.extension().and_then(|s| s.to_str());
-                        if ext == Some("json") || ext == Some("yaml") || ext == Some("yml") {
-                            match TrackLoader::load_from_file(&path) {
-                                Ok(mut track) => {
-                                    // Compute relative path from content root, normalize to forward slashes
-                                    let rel = path.strip_prefix(content_root).unwrap_or(&path);
-                                    let rel_norm = rel.to_string_lossy().replace('\\', "/");
-                                    track.source_path = Some(rel_norm);
-                                    track_configs.insert(track.id, track);
-                                }
-                                Err(e) => {
-                                    warn!("Failed to load track from {:?}: {}", path, e);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                warn!("Failed to read tracks directory {:?}: {}", dir, e);
-            }
-        }
-    }
+    /// Laps in a simulated race
+    #[arg(long, value_name = "N", default_value_t = 5)]
+    sim_laps: u16,
 
-    fn load_custom_cars(car_configs: &mut HashMap<CarConfigId, CarConfig>, cars_dir_str: &str) {
-        let cars_dir = std::path::Path::new(cars_dir_str);
+    /// Directory simulated race reports are written to
+    #[arg(long, value_name = "DIR", default_value = "./race_sims")]
+    sim_output: String,
 
-        if !cars_dir.exists() {
-            info!("Cars directory not found at {:?}, skipping custom car loading", cars_dir);
-            return;
-        }
+    /// Re-simulate a replay file, or every replay in a directory, and check its recorded state hashes
+    #[arg(long, value_name = "PATH")]
+    verify_replay: Option<String>,
 
-        Self::load_cars_recursive(car_configs, cars_dir);
-    }
+    /// Write a replay file's per-car time series (position, speed, inputs, lap times) for analysis
+    #[arg(long, value_name = "PATH")]
+    export_replay: Option<String>,
 
-    fn load_cars_recursive(
-        car_configs: &mut HashMap<CarConfigId, CarConfig>,
-        dir: &std::path::Path,
-    ) {
-        match std::fs::read_dir(dir) {
-            Ok(entries) => {
-                for entry in entries.filter_map(|e| e.ok()) {
-                    let path = entry.path();
-                    if path.is_dir() {
-                        // Recursively load cars from subdirectories
-                        Self::load_cars_recursive(car_configs, &path);
-                    } else if path.is_file() {
-                        let ext = path.extension().and_then(|s| s.to_str());
-                        if ext == Some("toml") {
-                            // Check if this is a car.toml file
-                            if path.file_name().and_then(|s| s.to_str()) == Some("car.toml") {
-                                match CarLoader::load_from_file(&path) {
-                                    Ok(car) => {
-                                        car_configs.insert(car.id, car);
-                                    }
-                                    Err(e) => {
-                                        warn!("Failed to load car from {:?}: {}", path, e);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                warn!("Failed to read cars directory {:?}: {}", dir, e);
-            }
-        }
-    }
+    /// Format of a replay export (csv|json)
+    #[arg(long, value_name = "FORMAT", default_value = "csv")]
+    export_format: ReplayExportFormat,
 
-    #[allow(dead_code)]
-    fn create_session(
-        &mut self,
-        host_player_id: PlayerId,
-        host_car_id: CarConfigId,
-        track_config_id: TrackConfigId,
-        session_kind: SessionKind,
-        max_players: u8,
-        ai_count: u8,
-        lap_limit: u8,
-    ) -> Option<SessionId> {
-        use apexsim_server::ai_driver::generate_default_ai_profiles;
+    /// File a replay export is written to; defaults to the replay path with the format's extension
+    #[arg(long, value_name = "PATH")]
+    export_output: Option<String>,
 
-        if self.sessions.len() >= self.config.server.max_sessions as usize {
-            return None;
-        }
+    /// Ticks between the samples of a replay export
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    export_interval_ticks: u32,
 
-        let track = self.track_configs.get(&track_config_id)?.clone();
-        let mut session = RaceSession::new(host_player_id, track_config_id, session_kind, max_players, ai_count, lap_limit);
-        session.host_car_id = Some(host_car_id);
-        let session_id = session.id;
+    /// Run as a dedicated open practice server (same as `[open_practice] enabled = true`)
+    #[arg(long)]
+    open_practice: bool,
 
-        // Create AI profiles if AI count is specified
-        let ai_profiles = if ai_count > 0 {
-            generate_default_ai_profiles(ai_count)
-        } else {
-            Vec::new()
-        };
+    /// List the servers on the master server list and exit
+    #[arg(long)]
+    query_servers: bool,
 
-        // Create game session with AI profiles
-        let mut game_session = if !ai_profiles.is_empty() {
-            GameSession::with_ai_profiles(session, track, self.car_configs.clone(), ai_profiles)
-        } else {
-            GameSession::new(session, track, self.car_configs.clone())
-        };
+    /// Master server list to query instead of `[master_server] url`
+    #[arg(long, value_name = "URL")]
+    master_url: Option<String>,
 
-        // Spawn AI drivers immediately
-        if ai_count > 0 {
-            game_session.spawn_ai_drivers();
-            info!("Spawned {} AI drivers for session {}", ai_count, session_id);
-        }
+    /// Open the admin console of a running server through its `[admin_console] socket_path` and exit
+    #[arg(long, value_name = "PATH")]
+    console: Option<String>,
 
-        self.sessions.insert(session_id, game_session);
+    /// Check the configuration, report every problem and exit
+    #[arg(long)]
+    check_config: bool,
 
-        Some(session_id)
+    /// Check every track and car in the content directories, report each problem and exit
+    #[arg(long)]
+    validate_content: bool,
+}
+
+/// Files below `dir` that `keep` accepts, in name order
+fn content_files(dir: &std::path::Path, keep: &dyn Fn(&std::path::Path) -> bool, found: &mut Vec<std::path::PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<_> = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            content_files(&path, keep, found);
+        } else if keep(&path) {
+            found.push(path);
+        }
     }
 }
 
@@ -238,1036 +144,349 @@ impl ServerState {
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
 
-    // Initialize tracing
-    let log_level = args
-        .log_level
-        .as_deref()
-        .unwrap_or("info");
-
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(log_level)),
-        )
-        .init();
-
-    info!("Starting ApexSim Racing Server v0.1.0");
-
-    // Load configuration
-    let config = ServerConfig::load_or_default(&args.config);
-    info!("Configuration loaded from: {}", args.config);
-
-    // Check if we're in terrain generation mode
-    if args.generate_terrain {
-        info!("🌍 TERRAIN GENERATION MODE");
-        info!("Generating procedural terrain for all tracks...");
-
-        use apexsim_server::procgen;
-        let result = procgen::terrain::generate_all_terrain(&config.content.tracks_dir);
-
-        match result {
-            Ok(count) => {
-                info!("✅ Successfully generated terrain for {} track(s)", count);
-                return Ok(());
-            }
-            Err(e) => {
-                eprintln!("❌ Terrain generation failed: {}", e);
-                return Err(e.into());
-            }
+    // A console client is not a server; keep server logging out of its terminal
+    if let Some(path) = args.console {
+        #[cfg(unix)]
+        {
+            tokio::task::spawn_blocking(move || apexsim_server::admin_console::run_console_client(&path)).await??;
+            return Ok(());
         }
+        #[cfg(not(unix))]
+        return Err(format!("--console {} needs unix sockets, which this platform lacks", path).into());
     }
 
-    info!("TCP bind: {}", config.network.tcp_bind);
-    info!("UDP bind: {}", config.network.udp_bind);
-    info!("Tick rate: {}Hz", config.server.tick_rate_hz);
-
-    // Initialize server state
-    let state = Arc::new(RwLock::new(ServerState::new(config.clone())));
-
-    info!("Server initialized with {} car configs and {} track configs",
-        state.read().await.car_configs.len(),
-        state.read().await.track_configs.len());
-
-    // Initialize health state
-    let health_state = HealthState::new();
-
-    // Start health check server
-    let health_bind = config.network.health_bind.clone();
-    let health_state_clone = health_state.clone();
-    tokio::spawn(async move {
-        if let Err(e) = run_health_server(health_bind, health_state_clone).await {
-            warn!("Health server error: {}", e);
-        }
-    });
-
-    // Initialize transport layer
-    let mut transport = match TransportLayer::new(
-        &config.network.tcp_bind,
-        &config.network.udp_bind,
-        &config.network.tls_cert_path,
-        &config.network.tls_key_path,
-        config.network.require_tls,
-        config.network.heartbeat_timeout_ms,
-    ).await {
-        Ok(t) => {
-            info!("Transport layer initialized successfully");
-            t
-        }
+    // Load configuration first, it says where the log goes; only a missing
+    // file falls back to the defaults
+    let config = match ServerConfig::load_or_default(&args.config) {
+        Ok(config) => config,
         Err(e) => {
-            return Err(format!("Failed to initialize transport layer: {}", e).into());
+            eprintln!("Failed to load config {}: {}", args.config, e);
+            std::process::exit(1);
         }
     };
 
-    // Start transport layer
-    transport.start().await;
-    let transport = Arc::new(RwLock::new(transport));
-
-    // Mark server as ready
-    health_state.set_ready(true).await;
-    info!("Server marked as ready");
-
-    // Start main game loop
-    let loop_state = Arc::clone(&state);
-    let loop_transport = Arc::clone(&transport);
-    let tick_rate = config.server.tick_rate_hz;
-
-    tokio::spawn(async move {
-        run_game_loop(loop_state, loop_transport, tick_rate).await;
-    });
-
-    info!("Server is running. Press Ctrl+C to stop.");
-
-    // Wait for shutdown signal
-    tokio::signal::ctrl_c().await?;
-
-    info!("Shutdown signal received. Cleaning up...");
-
-    // Mark server as unhealthy
-    health_state.set_healthy(false).await;
-
-    // Shutdown transport layer (notifies all clients)
-    transport.write().await.shutdown().await;
-
-    // Cleanup
-    let final_state = state.read().await;
-    info!("Server shutting down with {} active sessions", final_state.sessions.len());
-
-    Ok(())
-}
-
-/// Send lobby state to a specific connection
-async fn send_lobby_state(
-    connection_id: ConnectionId,
-    state: &Arc<RwLock<ServerState>>,
-    transport: &TransportLayer,
-) -> Result<(), Box<dyn std::error::Error>> {
-    use apexsim_server::network::{ServerMessage, CarConfigSummary, TrackConfigSummary};
-
-    let state_read = state.read().await;
-
-    // Get lobby players and sessions
-    let players_in_lobby = state_read.lobby.get_lobby_players().await;
-    info!("send_lobby_state: players_in_lobby count = {}", players_in_lobby.len());
-    for player in &players_in_lobby {
-        info!("  - Player: {} (ID: {}), SelectedCar: {:?}, InSession: {:?}",
-              player.name, player.id, player.selected_car, player.in_session);
+    if args.check_config {
+        let problems = config.validate();
+        if problems.is_empty() {
+            println!("{}: configuration OK", args.config);
+            return Ok(());
+        }
+        for problem in &problems {
+            println!("{}: {}", args.config, problem);
+        }
+        std::process::exit(1);
     }
-    let available_sessions = state_read.lobby.get_available_sessions().await;
-
-    // Get car and track configs
-    let car_configs: Vec<CarConfigSummary> = state_read
-        .car_configs
-        .values()
-        .map(|c| CarConfigSummary {
-            id: c.id,
-            name: c.name.clone(),
-            model_path: format!("res://content/cars/{}/{}", c.id, c.model),
-            mass_kg: c.mass_kg,
-            max_engine_force_n: c.max_engine_power_w / 100.0, // Rough approximation
-        })
-        .collect();
-
-    let track_configs: Vec<TrackConfigSummary> = state_read
-        .track_configs
-        .values()
-        .map(|t| TrackConfigSummary {
-            id: t.id,
-            name: t.name.clone(),
-            centerline: t.centerline.iter()
-                .step_by(10)
-                .map(|p| apexsim_server::network::TrackPoint { x: p.x, y: p.y })
-                .collect(), // Send every 10th point
-        })
-        .collect();
-
-    drop(state_read);
-
-    let lobby_state = ServerMessage::LobbyState(LobbyStateData {
-        players_in_lobby,
-        available_sessions,
-        car_configs,
-        track_configs,
-    });
-
-    transport.send_tcp(connection_id, lobby_state).await?;
-    Ok(())
-}
-
-/// Broadcast lobby state to all connected clients in the lobby
-async fn broadcast_lobby_state(
-    state: &Arc<RwLock<ServerState>>,
-    transport: &TransportLayer,
-) -> Result<(), Box<dyn std::error::Error>> {
-    use apexsim_server::network::{ServerMessage, CarConfigSummary, TrackConfigSummary};
-
-    let state_read = state.read().await;
-
-    // Get lobby players and sessions
-    let players_in_lobby = state_read.lobby.get_lobby_players().await;
-    let available_sessions = state_read.lobby.get_available_sessions().await;
-
-    // Get car and track configs
-    let car_configs: Vec<CarConfigSummary> = state_read
-        .car_configs
-        .values()
-        .map(|c| CarConfigSummary {
-            id: c.id,
-            name: c.name.clone(),
-            model_path: format!("res://content/cars/{}/{}", c.id, c.model),
-            mass_kg: c.mass_kg,
-            max_engine_force_n: c.max_engine_power_w / 100.0, // Rough approximation
-        })
-        .collect();
 
-    let track_configs: Vec<TrackConfigSummary> = state_read
-        .track_configs
-        .values()
-        .map(|t| TrackConfigSummary {
-            id: t.id,
-            name: t.name.clone(),
-            centerline: t.centerline.iter()
-                .step_by(10)
-                .map(|p| apexsim_server::network::TrackPoint { x: p.x, y: p.y })
-                .collect(), // Send every 10th point
-        })
-        .collect();
-
-    drop(state_read);
-
-    let lobby_state = ServerMessage::LobbyState(LobbyStateData {
-        players_in_lobby,
-        available_sessions,
-        car_configs,
-        track_configs,
-    });
-
-    // Broadcast to all connections
-    transport.broadcast_tcp(lobby_state).await;
-    Ok(())
-}
-
-async fn run_game_loop(state: Arc<RwLock<ServerState>>, transport: Arc<RwLock<TransportLayer>>, tick_rate: u16) {
-    const SHOULD_LOG_TICKS: bool = false;
-    let tick_duration = Duration::from_micros((1_000_000.0 / tick_rate as f64) as u64);
-    let mut ticker = interval(tick_duration);
-
-    let mut tick_count = 0u64;
-    let mut player_inputs: HashMap<PlayerId, PlayerInputData> = HashMap::new();
-
-    loop {
-        ticker.tick().await;
-        tick_count += 1;
-
-        if SHOULD_LOG_TICKS {
-        // Log every second (240 ticks at 240Hz)
-            if tick_count % tick_rate as u64 == 0 {
-                let state_read = state.read().await;
-                info!(
-                    "Tick {} - Active sessions: {}, Players: {}",
-                    tick_count,
-                    state_read.sessions.len(),
-                    state_read.players.len()
-                );
+    if args.validate_content {
+        let mut tracks = Vec::new();
+        content_files(std::path::Path::new(&config.content.tracks_dir), &|path| {
+            let ext = path.extension().and_then(|s| s.to_str());
+            matches!(ext, Some("json" | "yaml" | "yml")) && !TrackLoader::is_sidecar_file(path)
+        }, &mut tracks);
+        let mut cars = Vec::new();
+        content_files(std::path::Path::new(&config.content.cars_dir), &|path| {
+            path.file_name().and_then(|s| s.to_str()) == Some("car.toml")
+        }, &mut cars);
+
+        let mut problem_count = 0;
+        for path in &tracks {
+            for problem in TrackLoader::validate_file(path) {
+                println!("{}: {}", path.display(), problem);
+                problem_count += 1;
             }
         }
-
-        // Process incoming TCP messages (non-blocking)
-        let mut transport_write = transport.write().await;
-        while let Ok(Some((connection_id, msg))) = tokio::time::timeout(
-            Duration::from_micros(100),
-            transport_write.recv_tcp()
-        ).await {
-            use apexsim_server::network::{ClientMessage, ServerMessage};
-            use apexsim_server::lobby::{LobbyPlayerState, LobbySessionInfo, SessionVisibility};
-
-            match msg {
-                ClientMessage::Authenticate { player_name, .. } => {
-                    // Add player to lobby after authentication
-                    if let Some(conn_info) = transport_write.get_connection(connection_id).await {
-                        let state_write = state.write().await;
-                        let lobby_player = LobbyPlayerState {
-                            player_id: conn_info.player_id,
-                            player_name: player_name.clone(),
-                            connection_id,
-                            selected_car: None,
-                        };
-                        state_write.lobby.add_player(lobby_player).await;
-
-                        // Send initial lobby state
-                        drop(state_write);
-                        if let Err(e) = send_lobby_state(connection_id, &state, &transport_write).await {
-                            warn!("Failed to send lobby state: {:?}", e);
-                        }
-                    }
-                }
-
-                ClientMessage::SelectCar { car_config_id } => {
-                    if let Some(conn_info) = transport_write.get_connection(connection_id).await {
-                        info!("SelectCar: player_id={}, car_config_id={}", conn_info.player_id, car_config_id);
-                        let state_write = state.write().await;
-                        state_write.lobby.set_player_car(conn_info.player_id, car_config_id).await;
-                        info!("SelectCar: Car set successfully");
-                    } else {
-                        warn!("SelectCar: No connection info found for connection_id={}", connection_id);
-                    }
-                }
-
-                ClientMessage::RequestLobbyState => {
-                    if let Err(e) = send_lobby_state(connection_id, &state, &transport_write).await {
-                        warn!("Failed to send lobby state: {:?}", e);
-                    }
-                }
-
-                ClientMessage::CreateSession { track_config_id, max_players, ai_count, lap_limit, session_kind } => {
-                    if let Some(conn_info) = transport_write.get_connection(connection_id).await {
-                        let mut state_write = state.write().await;
-
-                        // Get host's selected car
-                        let selected_car = state_write.lobby.get_player_car(conn_info.player_id).await;
-
-                        if let Some(car_id) = selected_car {
-                            // Create session
-                            if let Some(session_id) = state_write.create_session(
-                                conn_info.player_id,
-                                car_id,
-                                track_config_id,
-                                session_kind,
-                                max_players,
-                                ai_count,
-                                lap_limit
-                            ) {
-                                info!("Session {} created by player {}", session_id, conn_info.player_name);
-
-                                // Register session in lobby
-                                let track_name = state_write.track_configs.get(&track_config_id)
-                                    .map(|t| t.name.clone())
-                                    .unwrap_or_else(|| "Unknown Track".to_string());
-
-                                let track_file = state_write.track_configs.get(&track_config_id)
-                                    .and_then(|t| t.source_path.clone())
-                                    .unwrap_or_else(|| "tracks/unknown.yaml".to_string());
-
-                                let session_info = LobbySessionInfo {
-                                    session_id,
-                                    host_player_id: conn_info.player_id,
-                                    host_name: conn_info.player_name.clone(),
-                                    track_name,
-                                    track_file,
-                                    session_kind,
-                                    track_config_id,
-                                    max_players,
-                                    current_player_count: 0, // join_session will increment this
-                                    spectator_count: 0,
-                                    state: SessionState::Lobby,
-                                    visibility: SessionVisibility::Public,
-                                    password_hash: None,
-                                    created_at: std::time::Instant::now(),
-                                };
-
-                                state_write.lobby.register_session(session_info).await;
-
-                                // Join host to their own session (lobby and game session)
-                                let joined = state_write.lobby.join_session(conn_info.player_id, session_id).await;
-
-                                if joined {
-                                    // Add host to the actual game session
-                                    if let Some(game_session) = state_write.sessions.get_mut(&session_id) {
-                                        if let Some(grid_pos) = game_session.add_player(conn_info.player_id, car_id) {
-                                            let _ = transport_write.send_tcp(connection_id, ServerMessage::SessionJoined(SessionJoinedData {
-                                                session_id,
-                                                your_grid_position: grid_pos,
-                                            })).await;
-                                            // Track that player is in a session
-                                            transport_write.set_player_session(connection_id, Some(session_id)).await;
-                                        } else {
-                                            // Failed to add player to game session
-                                            warn!("Failed to add player {} to game session {}", conn_info.player_id, session_id);
-                                            let _ = transport_write.send_tcp(connection_id, ServerMessage::Error {
-                                                code: 500,
-                                                message: "Failed to add player to game session".to_string(),
-                                            }).await;
-                                        }
-                                    } else {
-                                        // Session not found in sessions map
-                                        warn!("Session {} not found in sessions map", session_id);
-                                        let _ = transport_write.send_tcp(connection_id, ServerMessage::Error {
-                                            code: 500,
-                                            message: "Internal error: session not found".to_string(),
-                                        }).await;
-                                    }
-                                } else {
-                                    // Failed to join lobby
-                                    warn!("Player {} failed to join lobby for session {}", conn_info.player_id, session_id);
-                                    let _ = transport_write.send_tcp(connection_id, ServerMessage::Error {
-                                        code: 500,
-                                        message: "Failed to join session lobby".to_string(),
-                                    }).await;
-                                }
-                            } else {
-                                // Failed to create session
-                                warn!("Failed to create session for player {}: track_id={}", conn_info.player_id, track_config_id);
-                                
-                                // Check why it failed
-                                if state_write.sessions.len() >= state_write.config.server.max_sessions as usize {
-                                    let _ = transport_write.send_tcp(connection_id, ServerMessage::Error {
-                                        code: 503,
-                                        message: "Server is at max session capacity".to_string(),
-                                    }).await;
-                                } else if !state_write.track_configs.contains_key(&track_config_id) {
-                                    let _ = transport_write.send_tcp(connection_id, ServerMessage::Error {
-                                        code: 404,
-                                        message: "Track configuration not found".to_string(),
-                                    }).await;
-                                } else {
-                                    let _ = transport_write.send_tcp(connection_id, ServerMessage::Error {
-                                        code: 500,
-                                        message: "Failed to create session".to_string(),
-                                    }).await;
-                                }
-                            }
-                        } else {
-                            // No car selected
-                            let _ = transport_write.send_tcp(connection_id, ServerMessage::Error {
-                                code: 400,
-                                message: "Must select a car before creating session".to_string(),
-                            }).await;
-                        }
-                    }
-                }
-
-                ClientMessage::JoinSession { session_id } => {
-                    if let Some(conn_info) = transport_write.get_connection(connection_id).await {
-                        let mut state_write = state.write().await;
-                        
-                        // Get player's selected car
-                        let selected_car = state_write.lobby.get_player_car(conn_info.player_id).await;
-                        
-                        let joined = state_write.lobby.join_session(conn_info.player_id, session_id).await;
-
-                        if joined {
-                            // Add player to the actual game session
-                            if let Some(game_session) = state_write.sessions.get_mut(&session_id) {
-                                if let Some(car_id) = selected_car {
-                                    if let Some(grid_pos) = game_session.add_player(conn_info.player_id, car_id) {
-                                        info!("Player {} joined session {} at grid position {}",
-                                            conn_info.player_name, session_id, grid_pos);
-                                        let _ = transport_write.send_tcp(connection_id, ServerMessage::SessionJoined(SessionJoinedData {
-                                            session_id,
-                                            your_grid_position: grid_pos,
-                                        })).await;
-                                        // Track that player is in a session
-                                        transport_write.set_player_session(connection_id, Some(session_id)).await;
-                                    } else {
-                                        // Failed to add to session (full)
-                                        let empty_session = state_write.lobby.leave_session(conn_info.player_id, connection_id).await;
-                                        if let Some(session_id) = empty_session {
-                                            info!("Session {} is empty after failed join, removing it", session_id);
-                                            state_write.sessions.remove(&session_id);
-                                            state_write.lobby.unregister_session(session_id).await;
-                                        }
-
-                                        let _ = transport_write.send_tcp(connection_id, ServerMessage::Error {
-                                            code: 400,
-                                            message: "Session is full".to_string(),
-                                        }).await;
-                                    }
-                                } else {
-                                    // No car selected
-                                    let empty_session = state_write.lobby.leave_session(conn_info.player_id, connection_id).await;
-                                    if let Some(session_id) = empty_session {
-                                        info!("Session {} is empty after failed join, removing it", session_id);
-                                        state_write.sessions.remove(&session_id);
-                                        state_write.lobby.unregister_session(session_id).await;
-                                    }
-
-                                    let _ = transport_write.send_tcp(connection_id, ServerMessage::Error {
-                                        code: 400,
-                                        message: "Must select a car before joining session".to_string(),
-                                    }).await;
-                                }
-                            }
-                        } else {
-                            let _ = transport_write.send_tcp(connection_id, ServerMessage::Error {
-                                code: 400,
-                                message: "Unable to join session (full or not in lobby state)".to_string(),
-                            }).await;
-                        }
-                    }
-                }
-
-                ClientMessage::JoinAsSpectator { session_id } => {
-                    if let Some(conn_info) = transport_write.get_connection(connection_id).await {
-                        let state_write = state.write().await;
-                        let joined = state_write.lobby.join_as_spectator(conn_info.player_id, session_id).await;
-
-                        if joined {
-                            info!("Player {} joined session {} as spectator", conn_info.player_name, session_id);
-                            let _ = transport_write.send_tcp(connection_id, ServerMessage::SessionJoined(SessionJoinedData {
-                                session_id,
-                                your_grid_position: 0, // 0 indicates spectator
-                            })).await;
-                            // Track that player is in a session
-                            transport_write.set_player_session(connection_id, Some(session_id)).await;
-                        } else {
-                            let _ = transport_write.send_tcp(connection_id, ServerMessage::Error {
-                                code: 404,
-                                message: "Session not found".to_string(),
-                            }).await;
-                        }
+        for path in &cars {
+            match apexsim_server::car_loader::CarLoader::load_from_file(path) {
+                Ok(car) => {
+                    for warning in apexsim_server::car_loader::CarLoader::validate(&car) {
+                        println!("{}: warning: {}", path.display(), warning);
                     }
+                    println!("{}: {:.0} W/kg, top speed {:.0} km/h",
+                        path.display(), car.power_to_weight_w_per_kg(), car.theoretical_top_speed_mps() * 3.6);
                 }
-
-                ClientMessage::LeaveSession => {
-                    if let Some(conn_info) = transport_write.get_connection(connection_id).await {
-                        let mut state_write = state.write().await;
-
-                        // Remove from game session
-                        if let Some(sid) = conn_info.in_session {
-                            if let Some(game_session) = state_write.sessions.get_mut(&sid) {
-                                game_session.remove_player(&conn_info.player_id);
-                            }
-                        }
-
-                        let empty_session = state_write.lobby.leave_session(conn_info.player_id, connection_id).await;
-
-                        if let Some(session_id) = empty_session {
-                            info!("Session {} has no human players left, removing it", session_id);
-                            state_write.sessions.remove(&session_id);
-                            state_write.lobby.unregister_session(session_id).await;
-                        }
-
-                        info!("Player {} left their session", conn_info.player_name);
-                        let _ = transport_write.send_tcp(connection_id, ServerMessage::SessionLeft).await;
-                        // Clear session tracking
-                        transport_write.set_player_session(connection_id, None).await;
-                    }
-                }
-
-                ClientMessage::StartSession => {
-                    if let Some(conn_info) = transport_write.get_connection(connection_id).await {
-                        let mut state_write = state.write().await;
-                        
-                        // Find which session the player is in
-                        let session_id = state_write.lobby.get_player_session(conn_info.player_id).await;
-                        
-                        if let Some(session_id) = session_id {
-                            if let Some(game_session) = state_write.sessions.get_mut(&session_id) {
-                                // Only host can start the session
-                                if game_session.session.host_player_id == conn_info.player_id {
-                                    game_session.start_countdown();
-                                    info!("Player {} started session {}", conn_info.player_name, session_id);
-
-                                    // Notify all participants
-                                    let msg = ServerMessage::SessionStarting { countdown_seconds: 5 };
-                                    let participant_count = game_session.session.participants.len();
-                                    info!("Broadcasting SessionStarting to {} participants", participant_count);
-
-                                    for player_id in game_session.session.participants.keys() {
-                                        if let Some(conn_id) = transport_write.get_player_connection(*player_id).await {
-                                            info!("Sending SessionStarting to player {} (connection {})", player_id, conn_id);
-                                            let _ = transport_write.send_tcp(conn_id, msg.clone()).await;
-                                        } else {
-                                            warn!("Could not find connection for player {}", player_id);
-                                        }
-                                    }
-                                } else {
-                                    let _ = transport_write.send_tcp(connection_id, ServerMessage::Error {
-                                        code: 403,
-                                        message: "Only the host can start the session".to_string(),
-                                    }).await;
-                                }
-                            }
-                        }
-                    }
-                }
-
-                ClientMessage::SetGameMode { mode } => {
-                    if let Some(conn_info) = transport_write.get_connection(connection_id).await {
-                        let mut state_write = state.write().await;
-
-                        // Check if player is in a session
-                        if let Some(session_id) = conn_info.in_session {
-                            // Collect data we need before mutable borrow
-                            let (is_host, human_player_ids) = {
-                                if let Some(game_session) = state_write.sessions.get(&session_id) {
-                                    let is_host = game_session.session.host_player_id == conn_info.player_id;
-                                    let human_ids: Vec<PlayerId> = game_session.session.participants.keys()
-                                        .filter(|id| !game_session.session.ai_player_ids.contains(id))
-                                        .cloned()
-                                        .collect();
-                                    (is_host, human_ids)
-                                } else {
-                                    (false, Vec::new())
-                                }
-                            };
-
-                            if is_host {
-                                // If switching to DemoLap, add human players as spectators first
-                                if mode == GameMode::DemoLap {
-                                    for player_id in &human_player_ids {
-                                        state_write.lobby.join_as_spectator(*player_id, session_id).await;
-                                        info!("Player {} added as spectator for DemoLap mode", player_id);
-                                    }
-                                }
-
-                                // Now do the mutable borrow
-                                if let Some(game_session) = state_write.sessions.get_mut(&session_id) {
-                                    game_session.set_game_mode(mode);
-
-                                    // Collect participant IDs for notification
-                                    let current_participant_ids: Vec<PlayerId> = game_session.session.participants.keys().cloned().collect();
-
-                                    // Notify all participants
-                                    let mode_changed_msg = ServerMessage::GameModeChanged { mode };
-                                    for participant_id in current_participant_ids {
-                                        if let Some(conn_id) = transport_write.get_player_connection(participant_id).await {
-                                            if let Err(e) = transport_write.send_tcp(conn_id, mode_changed_msg.clone()).await {
-                                                warn!("Failed to send mode change to player: {:?}", e);
-                                            }
-                                        }
-                                    }
-
-                                    // Also notify spectators (for DemoLap)
-                                    for player_id in &human_player_ids {
-                                        if let Some(conn_id) = transport_write.get_player_connection(*player_id).await {
-                                            if let Err(e) = transport_write.send_tcp(conn_id, mode_changed_msg.clone()).await {
-                                                warn!("Failed to send mode change to spectator: {:?}", e);
-                                            }
-                                        }
-                                    }
-                                }
-                            } else {
-                                let _ = transport_write.send_tcp(
-                                    connection_id,
-                                    ServerMessage::Error {
-                                        code: 403,
-                                        message: "Only the session host can change game mode".to_string(),
-                                    }
-                                ).await;
-                            }
-                        }
-                    }
-                }
-
-                ClientMessage::StartCountdown { countdown_seconds, next_mode } => {
-                    if let Some(conn_info) = transport_write.get_connection(connection_id).await {
-                        let mut state_write = state.write().await;
-
-                        // Check if player is in a session
-                        if let Some(session_id) = conn_info.in_session {
-                            if let Some(game_session) = state_write.sessions.get_mut(&session_id) {
-                                // Only host can start countdown
-                                if game_session.session.host_player_id == conn_info.player_id {
-                                    game_session.start_countdown_mode(countdown_seconds, next_mode);
-
-                                    // Notify all participants
-                                    let countdown_msg = ServerMessage::GameModeChanged { mode: GameMode::Countdown };
-                                    for participant_id in game_session.session.participants.keys() {
-                                        if let Some(conn_id) = transport_write.get_player_connection(*participant_id).await {
-                                            if let Err(e) = transport_write.send_tcp(conn_id, countdown_msg.clone()).await {
-                                                warn!("Failed to send countdown start to player: {:?}", e);
-                                            }
-                                        }
-                                    }
-                                } else {
-                                    let _ = transport_write.send_tcp(
-                                        connection_id,
-                                        ServerMessage::Error {
-                                            code: 403,
-                                            message: "Only the session host can start countdown".to_string(),
-                                        }
-                                    ).await;
-                                }
-                            }
-                        }
-                    }
-                }
-
-                ClientMessage::Disconnect => {
-                    if let Some(conn_info) = transport_write.get_connection(connection_id).await {
-                        let mut state_write = state.write().await;
-
-                        // Remove from game session
-                        if let Some(sid) = conn_info.in_session {
-                            if let Some(game_session) = state_write.sessions.get_mut(&sid) {
-                                game_session.remove_player(&conn_info.player_id);
-                            }
-                        }
-
-                        let (_, empty_session) = state_write.lobby.remove_player(conn_info.player_id).await;
-
-                        if let Some(session_id) = empty_session {
-                            info!("Session {} is empty after player disconnect, removing it", session_id);
-                            state_write.sessions.remove(&session_id);
-                            state_write.lobby.unregister_session(session_id).await;
-                        }
-                    }
-                }
-
-                ClientMessage::PlayerInput { throttle, brake, steering, .. } => {
-                    if let Some(conn_info) = transport_write.get_connection(connection_id).await {
-                        let input = PlayerInputData {
-                            throttle,
-                            brake,
-                            steering,
-                            gear: None,
-                            clutch: None,
-                        };
-                        player_inputs.insert(conn_info.player_id, input);
-                    }
-                }
-
-                _ => {
-                    // Other messages (StartSession, etc.) handled elsewhere
+                Err(e) => {
+                    println!("{}: {}", path.display(), e);
+                    problem_count += 1;
                 }
             }
         }
 
-        // Cleanup stale connections every second and handle disconnected players
-        if tick_count % tick_rate as u64 == 0 {
-            let disconnected_players = transport_write.cleanup_stale_connections().await;
-            
-            if !disconnected_players.is_empty() {
-                let mut state_write = state.write().await;
-                
-                for (player_id, session_id_opt) in disconnected_players {
-                    info!("Handling disconnected player: {} (session: {:?})", player_id, session_id_opt);
-                    
-                    // Remove from game session if in one
-                    if let Some(session_id) = session_id_opt {
-                        if let Some(game_session) = state_write.sessions.get_mut(&session_id) {
-                            game_session.remove_player(&player_id);
-                        }
-                    }
-                    
-                    // Remove from lobby and check if session is now empty
-                    let (_, empty_session) = state_write.lobby.remove_player(player_id).await;
-                    
-                    if let Some(session_id) = empty_session {
-                        info!("Session {} is empty after player disconnect, removing it", session_id);
-                        state_write.sessions.remove(&session_id);
-                        state_write.lobby.unregister_session(session_id).await;
-                    }
-                }
-                
-                drop(state_write);
-            }
+        println!("{} track(s) and {} car(s) checked, {} problem(s)", tracks.len(), cars.len(), problem_count);
+        if problem_count > 0 {
+            std::process::exit(1);
         }
+        return Ok(());
+    }
 
-        // Broadcast lobby state every 2 seconds
-        if tick_count % (tick_rate as u64 * 2) == 0 {
-            if let Err(e) = broadcast_lobby_state(&state, &transport_write).await {
-                warn!("Failed to broadcast lobby state: {:?}", e);
+    // Initialize tracing. The console shows `RUST_LOG`, `--log-level` or
+    // `logging.level`, behind a reload layer so the admin console can change
+    // it; the log file has a filter of its own
+    let (log_filter, log_filter_handle) = tracing_subscriber::reload::Layer::new(
+        tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| {
+            tracing_subscriber::EnvFilter::new(args.log_level.as_deref().unwrap_or(&config.logging.level))
+        }),
+    );
+    // Created before logging starts: `/health` reports the latest logged error
+    let health_state = HealthState::new();
+    let mut log_layers: Vec<BoxedLayer> = vec![
+        tracing_subscriber::fmt::layer().with_filter(log_filter).boxed(),
+        health_state.error_layer().boxed(),
+    ];
+    let (mut _log_file_guard, mut log_file_error) = (None, None);
+    if !config.logging.file.path.is_empty() {
+        match log_file::file_layer(&config.logging.file) {
+            Ok((layer, guard)) => {
+                log_layers.push(layer);
+                _log_file_guard = Some(guard);
             }
+            Err(e) => log_file_error = Some(e),
         }
+    }
+    tracing_subscriber::registry().with(log_layers).init();
 
-        drop(transport_write);
-
-        // Update all sessions
-        let mut state_write = state.write().await;
-
-        // Use collected inputs
-        let inputs = player_inputs.clone();
-
-        // Collect replay operations to execute after iteration
-        let mut replay_starts = Vec::new();
-        let mut replay_frames = Vec::new();
-        let mut replay_stops = Vec::new();
-
-        // Collect sessions to remove (empty or finished)
-        let mut sessions_to_remove = Vec::new();
-
-        // Tick each session
-        for (session_id, game_session) in state_write.sessions.iter_mut() {
-            // Check if session has no real (non-AI) players left
-            let real_player_count = game_session.session.participants.keys()
-                .filter(|player_id| !game_session.session.ai_player_ids.contains(player_id))
-                .count();
-
-            // Keep session alive if it's in Demo Lap mode with AI drivers
-            let is_demo_lap_with_ai = game_session.session.game_mode == GameMode::DemoLap
-                && !game_session.session.ai_player_ids.is_empty();
-
-            if real_player_count == 0 && !is_demo_lap_with_ai {
-                info!("Session {} has no real players, marking for removal", session_id);
-                sessions_to_remove.push(*session_id);
-                continue;
-            }
-
-            // Generate AI inputs for AI players
-            let mut session_inputs = inputs.clone();
-            
-            for (player_id, _car_state) in &game_session.session.participants {
-                // Check if this is an AI player (simplified check)
-                if !session_inputs.contains_key(player_id) {
-                    // No human input, generate AI input
-                    let ai_input = game_session.generate_ai_input(player_id);
-                    session_inputs.insert(*player_id, ai_input);
-                }
-            }
-
-            let prev_state = game_session.session.state;
-            game_session.tick(&session_inputs);
-            let new_state = game_session.session.state;
+    info!("Starting ApexSim Racing Server v0.1.0");
+    info!("Configuration loaded from: {}", args.config);
+    if let Some(e) = log_file_error {
+        error!("Failed to open log file {}: {}", config.logging.file.path, e);
+        std::process::exit(1);
+    }
 
-            // Collect replay recording operations
-            if prev_state != SessionState::Racing && new_state == SessionState::Racing {
-                let participants: Vec<_> = game_session.session.participants.keys()
-                    .map(|pid| apexsim_server::replay::ReplayParticipant {
-                        player_id: *pid,
-                        player_name: format!("Player-{}", pid), // TODO: Get actual names
-                        car_config_id: game_session.session.participants.get(pid)
-                            .and_then(|cs| Some(cs.car_config_id))
-                            .unwrap_or_else(|| uuid::Uuid::nil()),
-                        finish_position: None,
-                    })
-                    .collect();
+    // SIGHUP reloads `logging.level` unless `--log-level` or `RUST_LOG` chose the filter
+    let config_sets_log_level = args.log_level.is_none() && std::env::var_os("RUST_LOG").is_none();
+
+    // Check if we're browsing the master server list
+    if args.query_servers {
+        let url = args.master_url.as_deref().unwrap_or(&config.master_server.url);
+        let servers = MasterServerClient::new(url)?.query().await?;
+        println!("{:<32} {:<8} {:<24} {:>7} {:>8}  VERSION", "NAME", "REGION", "ADDRESS", "PLAYERS", "SESSIONS");
+        for server in &servers {
+            let address = format!("{}:{}", server.address, server.tcp_port);
+            println!(
+                "{:<32} {:<8} {:<24} {:>7} {:>5}/{:<2}  {}{}",
+                server.name, server.region, address, server.player_count, server.session_count,
+                server.max_sessions, server.version, if server.requires_tls { " (TLS)" } else { "" },
+            );
+        }
+        println!("{} server(s)", servers.len());
+        return Ok(());
+    }
 
-                let track_config_id = game_session.session.track_config_id;
-                let metadata = (*session_id, track_config_id, participants);
-                replay_starts.push(metadata);
-            }
+    // Check if we're in terrain generation mode
+    if args.generate_terrain {
+        info!("🌍 TERRAIN GENERATION MODE");
+        info!("Generating procedural terrain for all tracks...");
 
-            // Collect telemetry frame if racing
-            if new_state == SessionState::Racing {
-                let telemetry = game_session.get_telemetry();
-                // Extract telemetry data from the ServerMessage
-                if let apexsim_server::network::ServerMessage::Telemetry(tel) = telemetry {
-                    replay_frames.push((*session_id, game_session.session.current_tick, tel));
-                }
-            }
+        use apexsim_server::procgen;
+        let result = procgen::terrain::generate_all_terrain(&config.content.tracks_dir);
 
-            // Collect replay stops when session finishes
-            if prev_state == SessionState::Racing && new_state == SessionState::Finished {
-                replay_stops.push(*session_id);
+        match result {
+            Ok(count) => {
+                info!("✅ Successfully generated terrain for {} track(s)", count);
+                return Ok(());
             }
-
-            // Log state changes
-            if game_session.session.current_tick % tick_rate as u32 == 0 {
-                match new_state {
-                    SessionState::Countdown => {
-                        if let Some(countdown) = game_session.session.countdown_ticks_remaining {
-                            let seconds = countdown / tick_rate;
-                            info!("Session {} countdown: {}s", session_id, seconds);
-                        }
-                    }
-                    SessionState::Racing => {
-                        let lap_info: Vec<String> = game_session
-                            .session
-                            .participants
-                            .values()
-                            .map(|s| format!("L{}", s.current_lap))
-                            .collect();
-                        info!("Session {} racing - Laps: {:?}", session_id, lap_info);
-                    }
-                    SessionState::Finished => {
-                        info!("Session {} finished", session_id);
-                    }
-                    _ => {}
-                }
+            Err(e) => {
+                eprintln!("❌ Terrain generation failed: {}", e);
+                return Err(e.into());
             }
         }
+    }
 
-        // Execute collected replay operations
-        for (session_id, track_config_id, participants) in replay_starts {
-            use apexsim_server::replay::ReplayMetadata;
-
-            let track_name = state_write.track_configs.get(&track_config_id)
-                .map(|t| t.name.clone())
-                .unwrap_or_else(|| "Unknown Track".to_string());
+    // Check if we're generating a circuit
+    if let Some(name) = args.generate_track {
+        use apexsim_server::procgen::circuit::{generate_circuit, save_circuit, CircuitParams, GENERATED_TRACKS_DIR};
+
+        let params = CircuitParams {
+            length_m: args.gen_length_m,
+            corners: args.gen_corners,
+            straights: args.gen_straights,
+            elevation_variance_m: args.gen_elevation_m,
+            width_m: args.gen_width_m,
+            seed: args.gen_seed,
+            environment: Some(args.gen_environment).filter(|env| env != "none"),
+        };
+        params.validate()?;
+        let track = generate_circuit(&name, &params)?;
+        let seed = track.metadata.as_ref().and_then(|metadata| metadata.terrain_seed);
+        let path = save_circuit(&std::path::Path::new(&config.content.tracks_dir).join(GENERATED_TRACKS_DIR), &track)?;
+        info!("Generated {} with seed {:?} at {}", name, seed, path.display());
+        println!("{}", path.display());
+        return Ok(());
+    }
 
-            let metadata = ReplayMetadata {
-                session_id,
-                track_config_id,
-                track_name,
-                recorded_at: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
-                duration_ticks: 0,
-                tick_rate,
-                participants,
-            };
+    // Check if we're in content QA mode
+    if let Some(report_dir) = args.qa_report {
+        use apexsim_server::qa_report::{run_qa, QaSettings};
 
-            state_write.replay.start_recording(metadata).await;
-            info!("Started replay recording for session {}", session_id);
+        info!("CONTENT QA MODE");
+        let state = ServerState::new(config);
+        let tracks = state.track_configs().iter()
+            .map(|(id, track)| (*id, TrackConfig::clone(track)))
+            .collect();
+        let report = run_qa(&tracks, state.car_configs(), &QaSettings::default());
+
+        std::fs::create_dir_all(&report_dir)?;
+        let report_path = std::path::Path::new(&report_dir)
+            .join(format!("qa_report_{}.json", report.generated_at));
+        std::fs::write(&report_path, serde_json::to_string_pretty(&report)?)?;
+
+        for track in report.flagged_tracks() {
+            warn!("Track {} flagged: {:?}", track.track_name, track.flags);
         }
+        info!("QA report for {} track(s) written to {:?}", report.tracks.len(), report_path);
+        return Ok(());
+    }
 
-        for (session_id, tick, telemetry) in replay_frames {
-            state_write.replay.record_frame(session_id, tick, telemetry).await;
-        }
+    // Check if we're in race simulation mode
+    if let Some(track_query) = args.simulate_race {
+        use apexsim_server::race_sim::{run_race_sim, RaceSimSettings};
 
-        for session_id in replay_stops {
-            match state_write.replay.stop_recording(session_id).await {
-                Ok(replay_path) => {
-                    info!("Replay saved for session {} to {:?}", session_id, replay_path);
-                }
-                Err(e) => {
-                    warn!("Failed to save replay for session {}: {}", session_id, e);
-                }
-            }
+        info!("RACE SIMULATION MODE");
+        let state = ServerState::new(config);
+        let Some(track) = state.track_configs().values().find(|t| {
+            let file_stem = t.source_path.as_deref()
+                .and_then(|p| std::path::Path::new(p).file_stem())
+                .and_then(|s| s.to_str());
+            t.id.to_string() == track_query
+                || t.name.eq_ignore_ascii_case(&track_query)
+                || file_stem.is_some_and(|s| s.eq_ignore_ascii_case(&track_query))
+        }) else {
+            return Err(format!("No track named {:?}", track_query).into());
+        };
+        let settings = RaceSimSettings {
+            ai_count: args.sim_ai_count,
+            laps: args.sim_laps,
+            ..Default::default()
+        };
+        let report = run_race_sim(track, state.car_configs(), &settings);
+
+        std::fs::create_dir_all(&args.sim_output)?;
+        let report_path = std::path::Path::new(&args.sim_output)
+            .join(format!("race_sim_{}.json", report.generated_at));
+        std::fs::write(&report_path, serde_json::to_string_pretty(&report)?)?;
+
+        for result in &report.results {
+            info!(
+                "P{} {} ({}): {} laps, best {:?} ms, median {:?} ms",
+                result.position,
+                result.driver_name,
+                result.car_name,
+                result.laps_completed,
+                result.lap_time_stats.as_ref().map(|s| s.min_ms),
+                result.lap_time_stats.as_ref().map(|s| s.median_ms),
+            );
         }
-
-        // Remove empty sessions from the game state and lobby
-        for session_id in sessions_to_remove {
-            state_write.sessions.remove(&session_id);
-            state_write.lobby.unregister_session(session_id).await;
-            info!("Removed empty session {}", session_id);
+        if report.abandoned {
+            warn!("Race abandoned before every car finished");
         }
+        info!(
+            "Simulated {:.0} s in {} ms ({:.0}x), report written to {:?}",
+            report.simulated_seconds, report.wall_clock_ms, report.speedup, report_path
+        );
+        return Ok(());
+    }
 
-        // Broadcast telemetry to all session participants (via TCP for now)
-        let transport_write2 = transport.write().await;
-        for (session_id, game_session) in state_write.sessions.iter() {
-            // Only send telemetry if session is active (not in Lobby or Closed state)
-            let should_send_telemetry = matches!(
-                game_session.session.state,
-                SessionState::Countdown | SessionState::Racing | SessionState::Finished
-            );
-
-            if !should_send_telemetry {
-                continue; // Skip telemetry for lobby sessions
-            }
-
-            // Collect real (non-AI) players who have active connections
-            let mut real_players_with_connections = Vec::new();
-            for player_id in game_session.session.participants.keys() {
-                // Skip AI players
-                if game_session.session.ai_player_ids.contains(player_id) {
-                    continue;
-                }
-
-                // Check if player has an active connection
-                if let Some(conn_id) = transport_write2.get_player_connection(*player_id).await {
-                    real_players_with_connections.push((*player_id, conn_id));
-                }
-            }
-
-            // Also collect spectators for this session (important for DemoLap mode)
-            let mut spectators_with_connections = Vec::new();
-            for player_id in state_write.lobby.get_session_spectators(*session_id).await {
-                if let Some(conn_id) = transport_write2.get_player_connection(player_id).await {
-                    spectators_with_connections.push((player_id, conn_id));
+    // Check if we're in replay verification mode
+    if let Some(replay_path) = args.verify_replay {
+        info!("REPLAY VERIFICATION MODE");
+        let state = ServerState::new(config);
+        let path = std::path::PathBuf::from(&replay_path);
+        let mut replay_paths = Vec::new();
+        if path.is_dir() {
+            for entry in std::fs::read_dir(&path)? {
+                let entry_path = entry?.path();
+                if entry_path.extension().is_some_and(|ext| ext == "bin") {
+                    replay_paths.push(entry_path);
                 }
             }
+            replay_paths.sort();
+        } else {
+            replay_paths.push(path);
+        }
 
-            // Skip telemetry calculation and broadcast if no real players or spectators are connected
-            if real_players_with_connections.is_empty() && spectators_with_connections.is_empty() {
-                if tick_count % 60 == 0 {
-                    debug!("Skipping telemetry for session {} - no real players or spectators connected", session_id);
-                }
+        let mut failed = 0;
+        for path in replay_paths {
+            let mut player = ReplayPlayer::open(path.clone()).await?;
+            if !player.is_input_replay() {
+                info!("{:?}: recorded as telemetry, nothing to verify", path);
                 continue;
             }
-
-            let telemetry_msg = game_session.get_telemetry();
-            let participant_count = game_session.session.participants.len();
-
-            if (participant_count > 0 || !spectators_with_connections.is_empty()) && tick_count % 60 == 0 {
-                debug!("Broadcasting telemetry for session {} to {} real players + {} spectators (total participants: {}, state: {:?})",
-                    session_id, real_players_with_connections.len(), spectators_with_connections.len(), participant_count, game_session.session.state);
-            }
-
-            // Send telemetry to real players who are still connected and in this session
-            for (player_id, conn_id) in real_players_with_connections {
-                // Verify player is still in this session (check via lobby manager)
-                if let Some(player_session) = state_write.lobby.get_player_session(player_id).await {
-                    if player_session == *session_id {
-                        let _ = transport_write2.send_tcp(conn_id, telemetry_msg.clone()).await;
-                    }
+            let Some(track) = state.track_configs().get(&player.metadata().track_config_id) else {
+                warn!("{:?}: track {} is not installed", path, player.metadata().track_config_id);
+                failed += 1;
+                continue;
+            };
+            player.prepare(track, state.car_configs());
+            match player.verify() {
+                Ok(checked) => info!("{:?}: {} state hashes match", path, checked),
+                Err(divergence) => {
+                    warn!("{:?}: {}", path, divergence);
+                    failed += 1;
                 }
             }
-
-            // Send telemetry to spectators (e.g., DemoLap viewers)
-            for (_player_id, conn_id) in spectators_with_connections {
-                let _ = transport_write2.send_tcp(conn_id, telemetry_msg.clone()).await;
-            }
         }
-        drop(transport_write2);
-
-        // Cleanup finished sessions (older than timeout)
-        let timeout_seconds = state_write.config.server.session_timeout_seconds as u64;
-        state_write.sessions.retain(|id, session| {
-            if session.session.state == SessionState::Finished {
-                let age_ticks = tick_count.saturating_sub(session.session.current_tick as u64);
-                let age_seconds = age_ticks / tick_rate as u64;
-                
-                if age_seconds > timeout_seconds {
-                    info!("Removing finished session: {}", id);
-                    return false;
-                }
-            }
-            true
-        });
+        if failed > 0 {
+            return Err(format!("{} replay(s) failed verification", failed).into());
+        }
+        return Ok(());
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use uuid::Uuid;
 
-    #[test]
-    fn test_server_state_creation() {
-        let config = ServerConfig::default();
+    // Check if we're in replay export mode
+    if let Some(replay_path) = args.export_replay {
+        info!("REPLAY EXPORT MODE");
         let state = ServerState::new(config);
-
-        assert!(!state.car_configs.is_empty());
-        assert!(!state.track_configs.is_empty());
-        assert_eq!(state.sessions.len(), 0);
+        let replay_path = std::path::PathBuf::from(&replay_path);
+        let output = args.export_output
+            .map(std::path::PathBuf::from)
+            .unwrap_or_else(|| replay_path.with_extension(args.export_format.extension()));
+
+        let metadata = state.replay().read_replay_metadata(&replay_path).await?;
+        // Tracks without a fixed id get a new one on every start
+        let track = state.track_configs().get(&metadata.track_config_id)
+            .or_else(|| state.track_configs().values().find(|t| t.name == metadata.track_name));
+        let export = state.replay().export_replay(
+            replay_path,
+            track.map(|t| t.as_ref()),
+            state.car_configs(),
+            &output,
+            args.export_format,
+            args.export_interval_ticks,
+        ).await?;
+
+        for car in &export.cars {
+            info!("{}: {} samples, {} laps", car.player_name, car.samples.len(), car.laps.len());
+        }
+        info!("Replay of session {} exported to {:?}", export.session_id, output);
+        return Ok(());
     }
 
-    #[test]
-    fn test_create_session() {
-        let config = ServerConfig::default();
-        let mut state = ServerState::new(config);
+    let problems = config.validate();
+    if !problems.is_empty() {
+        for problem in &problems {
+            error!("Config problem: {}", problem);
+        }
+        error!("{} problem(s) in {}; fix them and try again (--check-config checks without starting)", problems.len(), args.config);
+        std::process::exit(1);
+    }
 
-        let host_id = Uuid::new_v4();
-        let track_id = state.track_configs.values().next().unwrap().id;
-        let car_id = state.car_configs.values().next().unwrap().id;
+    let server = ServerRuntime::builder(config)
+        .health_state(health_state)
+        .log_filter(log_filter_handle)
+        .reload_on_sighup(args.config.clone(), config_sets_log_level)
+        .open_practice(args.open_practice)
+        .start()
+        .await
+        .map_err(|e| e as Box<dyn std::error::Error>)?;
 
-        let session_id = state.create_session(host_id, car_id, track_id, SessionKind::Practice, 8, 2, 5);
+    info!("Server is running. Press Ctrl+C to stop.");
 
-        assert!(session_id.is_some());
-        assert_eq!(state.sessions.len(), 1);
+    // Wait for shutdown signal
+    tokio::select! {
+        signal = tokio::signal::ctrl_c() => signal?,
+        _ = server.shutdown_requested() => info!("Shutdown requested through the admin API"),
     }
 
-    #[test]
-    fn test_max_sessions_limit() {
-        let config = ServerConfig::default();
-        let mut state = ServerState::new(config);
-        state.config.server.max_sessions = 2;
-
-        let host_id = Uuid::new_v4();
-        let track_id = state.track_configs.values().next().unwrap().id;
-        let car_id = state.car_configs.values().next().unwrap().id;
-
-        // Create max sessions
-        for _ in 0..2 {
-            let result = state.create_session(host_id, car_id, track_id, SessionKind::Sandbox, 8, 0, 3);
-            assert!(result.is_some());
-        }
+    info!("Shutdown signal received. Cleaning up...");
+    server.shutdown().await;
 
-        // Try to create one more
-        let result = state.create_session(host_id, car_id, track_id, SessionKind::Multiplayer, 8, 0, 3);
-        assert!(result.is_none());
-    }
+    Ok(())
 }
@@ -0,0 +1,212 @@
+//! Registration with a master server list, so players can browse public
+//! servers instead of typing addresses.
+//!
+//! A registered server POSTs its `ServerListing` to `{url}/servers` on every
+//! heartbeat and DELETEs `{url}/servers/{server_id}` when it shuts down. The
+//! master answers `GET {url}/servers` with the JSON array of live listings and
+//! is expected to drop listings whose heartbeats stop. Only plain `http://`
+//! master URLs are supported.
+
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::{Method, Request, StatusCode, Uri};
+use hyper_util::rt::TokioIo;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+/// Longest wait for the master to answer
+const MASTER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What a server tells the master list about itself
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerListing {
+    /// New on every start, so the master can tell restarts apart
+    pub server_id: uuid::Uuid,
+    pub name: String,
+    pub region: String,
+    /// Game address players connect to; empty to let the master use the
+    /// address the heartbeat came from, with `tcp_port`
+    pub address: String,
+    pub tcp_port: u16,
+    pub requires_tls: bool,
+    pub player_count: u32,
+    pub session_count: u32,
+    pub max_sessions: u8,
+    pub version: String,
+}
+
+/// Talks to one master server list
+#[derive(Debug, Clone)]
+pub struct MasterServerClient {
+    base: String,
+}
+
+impl MasterServerClient {
+    /// A client for the master at `url`, such as `http://master.example.com:8080`
+    pub fn new(url: &str) -> io::Result<Self> {
+        Ok(Self { base: http_base(url, "master")? })
+    }
+
+    /// Add or refresh this server's listing
+    pub async fn register(&self, listing: &ServerListing) -> io::Result<()> {
+        let body = serde_json::to_vec(listing).map_err(io::Error::other)?;
+        self.request(Method::POST, "/servers", Some(body)).await.map(|_| ())
+    }
+
+    /// Take this server off the list
+    pub async fn unregister(&self, server_id: uuid::Uuid) -> io::Result<()> {
+        self.request(Method::DELETE, &format!("/servers/{}", server_id), None).await.map(|_| ())
+    }
+
+    /// Every server the master lists
+    pub async fn query(&self) -> io::Result<Vec<ServerListing>> {
+        let body = self.request(Method::GET, "/servers", None).await?;
+        serde_json::from_slice(&body).map_err(|e| invalid(format!("master sent an invalid server list: {}", e)))
+    }
+
+    async fn request(&self, method: Method, path: &str, body: Option<Vec<u8>>) -> io::Result<Bytes> {
+        tokio::time::timeout(MASTER_TIMEOUT, http_request(&self.base, method, path, body, None))
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "master server did not answer"))?
+    }
+}
+
+/// `url` without its trailing slash, if it is a plain `http://` URL; `what`
+/// names the server in errors
+pub(crate) fn http_base(url: &str, what: &str) -> io::Result<String> {
+    let uri: Uri = url.parse().map_err(|e| invalid(format!("invalid {} URL {:?}: {}", what, url, e)))?;
+    if uri.scheme_str() != Some("http") || uri.host().is_none() {
+        return Err(invalid(format!("{} URL {:?} must be http://host[:port][/path]", what, url)));
+    }
+    Ok(url.trim_end_matches('/').to_string())
+}
+
+/// Send one request to `base` + `path`, with a JSON body and a bearer token if
+/// there are any, and return the response body of a 2xx answer
+pub(crate) async fn http_request(
+    base: &str,
+    method: Method,
+    path: &str,
+    body: Option<Vec<u8>>,
+    bearer: Option<&str>,
+) -> io::Result<Bytes> {
+    let uri: Uri = format!("{}{}", base, path).parse().map_err(|e| invalid(format!("{}", e)))?;
+    let host = uri.host().unwrap_or_default().to_string();
+    let port = uri.port_u16().unwrap_or(80);
+
+    let stream = TcpStream::connect((host.as_str(), port)).await?;
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+        .await
+        .map_err(io::Error::other)?;
+    tokio::spawn(connection);
+
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri.path_and_query().map(|p| p.as_str()).unwrap_or("/"))
+        .header(hyper::header::HOST, format!("{}:{}", host, port));
+    if body.is_some() {
+        request = request.header(hyper::header::CONTENT_TYPE, "application/json");
+    }
+    if let Some(token) = bearer {
+        request = request.header(hyper::header::AUTHORIZATION, format!("Bearer {}", token));
+    }
+    let request = request
+        .body(Full::new(Bytes::from(body.unwrap_or_default())))
+        .map_err(io::Error::other)?;
+
+    let response = sender.send_request(request).await.map_err(io::Error::other)?;
+    let status = response.status();
+    let body = response.into_body().collect().await.map_err(io::Error::other)?.to_bytes();
+    if status != StatusCode::OK && status != StatusCode::NO_CONTENT && status != StatusCode::CREATED {
+        return Err(io::Error::other(format!("server answered {}", status)));
+    }
+    Ok(body)
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::server::conn::http1;
+    use hyper::service::service_fn;
+    use std::convert::Infallible;
+    use std::sync::{Arc, Mutex};
+
+    /// A master list keeping listings in memory
+    async fn fake_master() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let listings: Arc<Mutex<Vec<ServerListing>>> = Arc::default();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let listings = Arc::clone(&listings);
+                tokio::spawn(async move {
+                    let service = service_fn(move |req: Request<hyper::body::Incoming>| {
+                        let listings = Arc::clone(&listings);
+                        async move {
+                            let path = req.uri().path().to_string();
+                            let method = req.method().clone();
+                            let body = req.into_body().collect().await.unwrap().to_bytes();
+                            let mut listings = listings.lock().unwrap();
+                            let reply = match (method, path.as_str()) {
+                                (Method::POST, "/list/servers") => {
+                                    let listing: ServerListing = serde_json::from_slice(&body).unwrap();
+                                    listings.retain(|l| l.server_id != listing.server_id);
+                                    listings.push(listing);
+                                    Vec::new()
+                                }
+                                (Method::GET, "/list/servers") => serde_json::to_vec(&*listings).unwrap(),
+                                (Method::DELETE, path) => {
+                                    listings.retain(|l| !path.ends_with(&l.server_id.to_string()));
+                                    Vec::new()
+                                }
+                                _ => unreachable!(),
+                            };
+                            Ok::<_, Infallible>(hyper::Response::new(Full::new(Bytes::from(reply))))
+                        }
+                    });
+                    let _ = http1::Builder::new().serve_connection(TokioIo::new(stream), service).await;
+                });
+            }
+        });
+        format!("http://{}/list/", addr)
+    }
+
+    #[tokio::test]
+    async fn test_register_query_unregister() {
+        let master = MasterServerClient::new(&fake_master().await).unwrap();
+        let mut listing = ServerListing {
+            server_id: uuid::Uuid::new_v4(),
+            name: "Test Server".to_string(),
+            region: "eu".to_string(),
+            address: String::new(),
+            tcp_port: 9000,
+            requires_tls: false,
+            player_count: 3,
+            session_count: 1,
+            max_sessions: 8,
+            version: "0.1.0".to_string(),
+        };
+
+        master.register(&listing).await.unwrap();
+        listing.player_count = 4;
+        master.register(&listing).await.unwrap();
+        assert_eq!(master.query().await.unwrap(), vec![listing.clone()]);
+
+        master.unregister(listing.server_id).await.unwrap();
+        assert!(master.query().await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_master_url_must_be_http() {
+        assert!(MasterServerClient::new("http://master.example.com:8080").is_ok());
+        assert!(MasterServerClient::new("https://master.example.com").is_err());
+        assert!(MasterServerClient::new("master.example.com").is_err());
+    }
+}
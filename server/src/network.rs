@@ -1,21 +1,27 @@
 use crate::data::*;
 use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
 
-fn deserialize_uuid_from_string<'de, D>(deserializer: D) -> Result<uuid::Uuid, D::Error>
+// The C# client sends and expects IDs as UUID strings. These helpers keep that
+// format for every ID type (typed IDs and bare `Uuid`s alike).
+
+fn deserialize_uuid_from_string<'de, D, T>(deserializer: D) -> Result<T, D::Error>
 where
     D: serde::Deserializer<'de>,
+    T: From<uuid::Uuid>,
 {
     let s = String::deserialize(deserializer)?;
-    uuid::Uuid::parse_str(&s).map_err(serde::de::Error::custom)
+    uuid::Uuid::parse_str(&s).map(T::from).map_err(serde::de::Error::custom)
 }
 
-fn deserialize_option_uuid_from_string<'de, D>(deserializer: D) -> Result<Option<uuid::Uuid>, D::Error>
+fn deserialize_option_uuid_from_string<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
 where
     D: serde::Deserializer<'de>,
+    T: From<uuid::Uuid>,
 {
     let opt = Option::<String>::deserialize(deserializer)?;
     match opt {
-        Some(s) => uuid::Uuid::parse_str(&s).map(Some).map_err(serde::de::Error::custom),
+        Some(s) => uuid::Uuid::parse_str(&s).map(|u| Some(T::from(u))).map_err(serde::de::Error::custom),
         None => Ok(None),
     }
 }
@@ -28,15 +34,38 @@ pub enum ClientMessage {
     Authenticate {
         token: String,
         player_name: String,
+        /// Telemetry frames per second for this client; 0 for every tick
+        #[serde(default)]
+        telemetry_rate_hz: u16,
     },
     Heartbeat {
         client_tick: u32,
     },
+    /// Echo of the `ping_id` from the latest `ConnectionStats`, used to measure RTT
+    StatsAck {
+        ping_id: u32,
+    },
     SelectCar {
         #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
         car_config_id: CarConfigId,
+        /// Index into the car's liveries
+        #[serde(default)]
+        livery: u8,
     },
     RequestLobbyState,
+    /// Ask for the last `seconds` of the sender's own car telemetry
+    RequestRecentTelemetry {
+        seconds: u16,
+    },
+    /// Spectators: receive `ExtendedTelemetry` for every car, or stop.
+    /// Drivers always get it for their own car
+    SetExtendedTelemetry {
+        enabled: bool,
+    },
+    /// Receive `Telemetry` at `rate_hz` instead of every tick; 0 for every tick
+    SetTelemetryRate {
+        rate_hz: u16,
+    },
     CreateSession {
         #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
         track_config_id: TrackConfigId,
@@ -45,6 +74,17 @@ pub enum ClientMessage {
         lap_limit: u8,
         #[serde(default)]
         session_kind: SessionKind,
+        #[serde(default)]
+        weather: WeatherSettings,
+        #[serde(default)]
+        setup_policy: SetupPolicy,
+        #[serde(default)]
+        collision_policy: CollisionPolicy,
+        #[serde(default)]
+        time_of_day: TimeOfDay,
+        /// Highest driver aid levels allowed; unset allows every aid
+        #[serde(default)]
+        aid_limits: Option<DriverAids>,
     },
     JoinSession {
         #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
@@ -53,9 +93,33 @@ pub enum ClientMessage {
     JoinAsSpectator {
         #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
         session_id: SessionId,
+        /// Watch the race this many seconds behind; the server may raise or cap it
+        #[serde(default)]
+        delay_seconds: u32,
+    },
+    /// Reclaim a car after being redirected to a standby server
+    ResumeSession {
+        #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+        session_id: SessionId,
+        #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+        resume_token: uuid::Uuid,
     },
     LeaveSession,
     StartSession,
+    /// Change the sender's car setup for the session they are driving in
+    ApplySetup {
+        setup: CarSetup,
+    },
+    /// Change the sender's driver aids for the session they are driving in
+    SetDriverAids {
+        aids: DriverAids,
+    },
+    /// Send the sender's car as fixed-layout dashboard packets to `port` on the
+    /// sender's address at `rate_hz`; `None` stops them
+    SetDashboardOutput {
+        port: Option<u16>,
+        rate_hz: u16,
+    },
     SetGameMode {
         mode: GameMode,
     },
@@ -65,12 +129,112 @@ pub enum ClientMessage {
     },
     Disconnect,
 
+    // TCP - League admin (requires an admin token)
+    AdminSetPlayerRating {
+        #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+        player_id: PlayerId,
+        rating: u32,
+    },
+    AdminMovePlayer {
+        #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+        player_id: PlayerId,
+        #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+        target_session_id: SessionId,
+    },
+    /// Split a session in two grids by rating; the lower-rated half moves to a new session
+    AdminSplitSession {
+        #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+        session_id: SessionId,
+    },
+    /// Move every player of the source session into the target and close the source
+    AdminMergeSessions {
+        #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+        source_session_id: SessionId,
+        #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+        target_session_id: SessionId,
+    },
+    /// Ban a connected player's auth identity (and their IP if `ban_ip`) and disconnect them
+    AdminBanPlayer {
+        #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+        player_id: PlayerId,
+        reason: String,
+        /// Ban length; permanent when unset
+        #[serde(default)]
+        duration_minutes: Option<u32>,
+        #[serde(default)]
+        ban_ip: bool,
+    },
+    AdminUnban {
+        #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+        ban_id: uuid::Uuid,
+    },
+    AdminListBans,
+    /// Save a session to disk now, to resume after a restart
+    AdminSaveSession {
+        #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+        session_id: SessionId,
+    },
+    AdminListSavedSessions,
+    /// Bring a saved session back; its drivers reclaim their cars with `ResumeSession`
+    AdminResumeSession {
+        #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+        session_id: SessionId,
+    },
+
+    // TCP - Replays
+    ListReplays,
+    /// Download the latest replay file of a session as `ReplayChunk`s
+    RequestReplay {
+        #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+        session_id: SessionId,
+    },
+    /// Start watching the latest replay recorded for a session
+    WatchReplay {
+        #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+        session_id: SessionId,
+    },
+    ReplayPlay,
+    ReplayPause,
+    /// Jump to a recorded server tick
+    ReplaySeek {
+        tick: u32,
+    },
+    /// Playback speed as a multiple of real time (0.1-8)
+    ReplaySetSpeed {
+        speed: f32,
+    },
+    /// Jump to the start of a highlight, by its index in the replay's `Highlights`
+    ReplayJumpToHighlight {
+        index: u32,
+    },
+
+    // TCP - Content downloads
+    /// List the files of a loaded car or track as a `ContentManifest`
+    RequestContent {
+        kind: ContentKind,
+        #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+        id: uuid::Uuid,
+    },
+    /// Download a file named in a `ContentManifest` as `ContentChunk`s,
+    /// starting at `offset` to resume a partial download
+    RequestContentFile {
+        path: String,
+        #[serde(default)]
+        offset: u64,
+    },
+
     // UDP - High frequency
     PlayerInput {
         server_tick_ack: u32,
         throttle: f32,
         brake: f32,
         steering: f32,
+        /// Desired gear (-1 = reverse, 0 = neutral); left out, the server shifts
+        #[serde(default)]
+        gear: Option<i8>,
+        /// Clutch engagement (0-1); left out, the clutch works automatically
+        #[serde(default)]
+        clutch: Option<f32>,
     },
 }
 
@@ -99,6 +263,43 @@ pub struct SessionJoinedData {
     #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
     pub session_id: SessionId,
     pub your_grid_position: u8,
+    /// Seconds the session's broadcasts run behind the race for this spectator; 0 is live
+    #[serde(default)]
+    pub delay_seconds: u32,
+}
+
+/// An open practice session was reset: cars are back on the grid and timing cleared
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SessionResetData {
+    #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+    pub session_id: SessionId,
+}
+
+/// Where to reconnect if this server goes away, and how to reclaim the car there
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SessionRedirectData {
+    #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+    pub session_id: SessionId,
+    /// TCP address of the server now (or about to be) hosting the session
+    pub address: String,
+    /// Sent with `ResumeSession` on the new server
+    #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+    pub resume_token: uuid::Uuid,
+    /// Reconnect now; otherwise keep the address for when the connection drops
+    pub immediate: bool,
+}
+
+/// Connect to another server of the cluster and repeat the request there
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct InstanceRedirectData {
+    /// TCP address of the instance
+    pub address: String,
+    /// Session to join there; `None` to create the session there instead
+    #[serde(serialize_with = "serialize_option_uuid_as_string", deserialize_with = "deserialize_option_uuid_from_string")]
+    pub session_id: Option<SessionId>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -117,6 +318,395 @@ pub struct LobbyStateData {
     pub track_configs: Vec<TrackConfigSummary>,
 }
 
+/// What changed in a `LobbyUpdate`
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+pub enum LobbyUpdateKind {
+    PlayerJoined = 0,
+    PlayerUpdated = 1,
+    PlayerLeft = 2,
+    SessionCreated = 3,
+    SessionUpdated = 4,
+    SessionRemoved = 5,
+}
+
+/// Incremental lobby change, applied on top of the last `LobbyState`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LobbyUpdate {
+    pub kind: LobbyUpdateKind,
+    /// Current entry for `PlayerJoined` and `PlayerUpdated`
+    pub player: Option<LobbyPlayer>,
+    /// Current entry for `SessionCreated` and `SessionUpdated`
+    pub session: Option<SessionSummary>,
+    /// Removed player for `PlayerLeft`
+    #[serde(serialize_with = "serialize_option_uuid_as_string", deserialize_with = "deserialize_option_uuid_from_string")]
+    pub player_id: Option<PlayerId>,
+    /// Removed session for `SessionRemoved`
+    #[serde(serialize_with = "serialize_option_uuid_as_string", deserialize_with = "deserialize_option_uuid_from_string")]
+    pub session_id: Option<SessionId>,
+}
+
+impl LobbyUpdate {
+    pub fn player(kind: LobbyUpdateKind, player: LobbyPlayer) -> Self {
+        Self { kind, player: Some(player), session: None, player_id: None, session_id: None }
+    }
+
+    pub fn player_left(player_id: PlayerId) -> Self {
+        Self { kind: LobbyUpdateKind::PlayerLeft, player: None, session: None, player_id: Some(player_id), session_id: None }
+    }
+
+    pub fn session(kind: LobbyUpdateKind, session: SessionSummary) -> Self {
+        Self { kind, player: None, session: Some(session), player_id: None, session_id: None }
+    }
+
+    pub fn session_removed(session_id: SessionId) -> Self {
+        Self { kind: LobbyUpdateKind::SessionRemoved, player: None, session: None, player_id: None, session_id: Some(session_id) }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct StandingsData {
+    #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+    pub session_id: SessionId,
+    pub server_tick: u32,
+    /// Entries ordered by race position, leader first
+    pub entries: Vec<StandingsEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct StandingsEntry {
+    #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+    pub player_id: PlayerId,
+    pub position: u8,
+    pub current_lap: u16,
+    pub laps_behind_leader: u16,
+    /// Time behind the leader; None for the leader and for lapped cars
+    pub gap_to_leader_ms: Option<u32>,
+    /// Time behind the car one position ahead; None for the leader and for lapped cars
+    pub interval_ms: Option<u32>,
+    pub last_lap_time_ms: Option<u32>,
+    pub best_lap_time_ms: Option<u32>,
+    pub pit_status: PitStatus,
+    pub finish_position: Option<u8>,
+}
+
+/// A race-director event such as a completed lap, overtake or penalty
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct RaceEvent {
+    #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+    pub session_id: SessionId,
+    pub server_tick: u32,
+    pub kind: RaceEventKind,
+    #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+    pub player_id: PlayerId,
+    /// Second car involved (the car that was overtaken)
+    #[serde(serialize_with = "serialize_option_uuid_as_string", deserialize_with = "deserialize_option_uuid_from_string")]
+    pub other_player_id: Option<PlayerId>,
+    pub lap: u16,
+    /// Race position after the event (overtakes and finishes)
+    pub position: Option<u8>,
+    pub lap_time_ms: Option<u32>,
+    pub penalty_ms: Option<u32>,
+    /// Human-readable detail, e.g. the penalty reason
+    pub message: Option<String>,
+}
+
+/// Final classification and timeline of a race, sent once when it finishes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SessionResultsData {
+    #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+    pub session_id: SessionId,
+    /// Tick the race finished on
+    pub server_tick: u32,
+    /// Final standings, winner first
+    pub entries: Vec<StandingsEntry>,
+    /// The start, lead changes, pit stops, penalties, incidents and finishes, oldest first
+    pub timeline: Vec<TimelineEntry>,
+}
+
+/// One moment of a race worth a line in a report or recap
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TimelineEntry {
+    pub server_tick: u32,
+    pub kind: TimelineEventKind,
+    /// None for the race start
+    #[serde(serialize_with = "serialize_option_uuid_as_string", deserialize_with = "deserialize_option_uuid_from_string")]
+    pub player_id: Option<PlayerId>,
+    /// Car that lost the lead, or the other car in a contact
+    #[serde(serialize_with = "serialize_option_uuid_as_string", deserialize_with = "deserialize_option_uuid_from_string")]
+    pub other_player_id: Option<PlayerId>,
+    pub lap: u16,
+    /// Position after a lead change or finish
+    pub position: Option<u8>,
+    pub penalty_ms: Option<u32>,
+    /// Human-readable detail, e.g. the penalty reason
+    pub message: Option<String>,
+}
+
+/// Current weather of a session, sent when it changes and to players joining
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct WeatherUpdateData {
+    #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+    pub session_id: SessionId,
+    pub condition: WeatherCondition,
+    /// Standing water on the track (0 = dry, 1 = soaked)
+    pub track_wetness: f32,
+    /// Next forecast condition, if the weather is due to change
+    pub next_condition: Option<WeatherCondition>,
+    pub next_change_in_s: Option<u32>,
+}
+
+/// Active ban as shown to admins; the banned auth token is never sent out
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct BanSummary {
+    #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+    pub ban_id: uuid::Uuid,
+    pub player_name: String,
+    pub reason: String,
+    /// Whether the ban covers the player's auth identity
+    pub identity_banned: bool,
+    pub ip: Option<String>,
+    /// Unix time (s)
+    pub banned_at: u64,
+    /// Unix time the ban lifts (s); None for a permanent ban
+    pub expires_at: Option<u64>,
+}
+
+impl From<&crate::bans::BanEntry> for BanSummary {
+    fn from(entry: &crate::bans::BanEntry) -> Self {
+        Self {
+            ban_id: entry.id,
+            player_name: entry.player_name.clone(),
+            reason: entry.reason.clone(),
+            identity_banned: entry.identity.is_some(),
+            ip: entry.ip.map(|ip| ip.to_string()),
+            banned_at: entry.banned_at,
+            expires_at: entry.expires_at,
+        }
+    }
+}
+
+/// Reply to `AdminListBans`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct BanListData {
+    pub bans: Vec<BanSummary>,
+}
+
+/// A session saved to disk, as shown to admins
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SavedSessionSummary {
+    #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+    pub session_id: SessionId,
+    pub track_name: String,
+    pub host_name: String,
+    /// Unix time (s)
+    pub saved_at: u64,
+    pub server_tick: u32,
+    pub game_mode: GameMode,
+    /// Lap the leader is on
+    pub lap: u16,
+    pub lap_limit: u8,
+    /// Human drivers who can reclaim their car
+    pub driver_count: u8,
+    /// Whether the session is running on this server now
+    pub active: bool,
+}
+
+/// Reply to the session save admin messages
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SavedSessionListData {
+    pub sessions: Vec<SavedSessionSummary>,
+}
+
+/// The sender's car was saved with a session; send the token with
+/// `ResumeSession` to reclaim it after the session is resumed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SessionSavedData {
+    #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+    pub session_id: SessionId,
+    #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+    pub resume_token: uuid::Uuid,
+}
+
+/// A saved replay that can be watched
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ReplaySummary {
+    #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+    pub session_id: SessionId,
+    pub track_name: String,
+    /// Unix time (s)
+    pub recorded_at: u64,
+    pub duration_ms: u64,
+    pub participants: Vec<ReplayParticipantSummary>,
+    pub highlights: Vec<ReplayHighlightSummary>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ReplayParticipantSummary {
+    #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+    pub player_id: PlayerId,
+    pub player_name: String,
+    #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+    pub car_config_id: CarConfigId,
+    pub finish_position: Option<u8>,
+}
+
+/// Overtake, contact or final lap in a replay, by recorded tick
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ReplayHighlightSummary {
+    pub kind: HighlightKind,
+    pub start_tick: u32,
+    pub end_tick: u32,
+    #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+    pub player_id: PlayerId,
+    #[serde(serialize_with = "serialize_option_uuid_as_string", deserialize_with = "deserialize_option_uuid_from_string")]
+    pub other_player_id: Option<PlayerId>,
+}
+
+impl From<&crate::replay::ReplayMetadata> for ReplaySummary {
+    fn from(metadata: &crate::replay::ReplayMetadata) -> Self {
+        Self {
+            session_id: metadata.session_id,
+            track_name: metadata.track_name.clone(),
+            recorded_at: metadata.recorded_at,
+            duration_ms: metadata.duration_ticks as u64 * 1000 / metadata.tick_rate.max(1) as u64,
+            participants: metadata
+                .participants
+                .iter()
+                .map(|p| ReplayParticipantSummary {
+                    player_id: p.player_id,
+                    player_name: p.player_name.clone(),
+                    car_config_id: p.car_config_id,
+                    finish_position: p.finish_position,
+                })
+                .collect(),
+            highlights: metadata
+                .highlights
+                .iter()
+                .map(|h| ReplayHighlightSummary {
+                    kind: h.kind,
+                    start_tick: h.start_tick,
+                    end_tick: h.end_tick,
+                    player_id: h.player_id,
+                    other_player_id: h.other_player_id,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Reply to `ListReplays`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ReplayListData {
+    pub replays: Vec<ReplaySummary>,
+}
+
+/// Part of a replay file sent in reply to `RequestReplay`; the download is
+/// complete once `Offset` plus the data length reaches `TotalBytes`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ReplayChunkData {
+    #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+    pub session_id: SessionId,
+    pub offset: u64,
+    pub total_bytes: u64,
+    #[serde(with = "serde_bytes")]
+    pub data: Vec<u8>,
+}
+
+/// Which content a `RequestContent` asks for
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize_repr, Deserialize_repr)]
+pub enum ContentKind {
+    Car = 0,
+    Track = 1,
+}
+
+/// One file of a car or track, relative to the content folder
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ContentFileInfo {
+    pub path: String,
+    pub size_bytes: u64,
+    /// SHA-256 of the file as lowercase hex
+    pub sha256: String,
+    /// Over the server's size limit, so it cannot be downloaded
+    #[serde(default)]
+    pub too_large: bool,
+}
+
+/// Files a client needs to have a car or track, in reply to `RequestContent`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ContentManifestData {
+    pub kind: ContentKind,
+    #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+    pub id: uuid::Uuid,
+    pub files: Vec<ContentFileInfo>,
+}
+
+/// Part of a content file sent in reply to `RequestContentFile`; the file is
+/// complete once `Offset` plus the data length reaches `TotalBytes`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ContentChunkData {
+    pub path: String,
+    pub offset: u64,
+    pub total_bytes: u64,
+    #[serde(with = "serde_bytes")]
+    pub data: Vec<u8>,
+}
+
+/// Playback state of the replay being watched, sent whenever it changes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ReplayStateData {
+    /// The recorded session
+    #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string")]
+    pub session_id: SessionId,
+    pub playing: bool,
+    pub speed: f32,
+    pub current_tick: u32,
+    pub first_tick: u32,
+    pub last_tick: u32,
+}
+
+/// Protocol statistics for the receiving connection, counted since it connected
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ConnectionStatsData {
+    /// Echo back in `StatsAck` so the server can measure round-trip time
+    pub ping_id: u32,
+    pub messages_sent: u64,
+    pub messages_received: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    /// Smoothed round-trip time; None until the first `StatsAck` arrives
+    pub rtt_ms: Option<f32>,
+    /// Messages to this client dropped because its queue was full
+    pub messages_dropped: u64,
+    /// Dropped messages as a percentage of all messages meant for this client
+    pub loss_percent: f32,
+    /// Messages from this client discarded for exceeding the rate limit
+    pub rate_limit_hits: u64,
+}
+
 // --- Server to Client Messages ---
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", content = "data")]
@@ -130,6 +720,7 @@ pub enum ServerMessage {
         server_tick: u32,
     },
     LobbyState(LobbyStateData),
+    LobbyUpdate(LobbyUpdate),
     SessionJoined(SessionJoinedData),
     SessionLeft,
     SessionStarting {
@@ -146,9 +737,48 @@ pub enum ServerMessage {
         message: String,
     },
     PlayerDisconnected(PlayerDisconnectedData),
+    Standings(StandingsData),
+    RaceEvent(RaceEvent),
+    SessionResults(SessionResultsData),
+    RecentTelemetry(RecentTelemetry),
+    SessionRedirect(SessionRedirectData),
+    InstanceRedirect(InstanceRedirectData),
+    SessionReset(SessionResetData),
+    WeatherUpdate(WeatherUpdateData),
+    /// The sender's setup was accepted and is now in use
+    SetupApplied {
+        setup: CarSetup,
+    },
+    /// The sender's driver aids were accepted and are now in use
+    DriverAidsApplied {
+        aids: DriverAids,
+    },
+    /// Message from the server operators to every player
+    Announcement {
+        message: String,
+    },
+    /// Where the sender's dashboard packets now go, with the rate granted
+    DashboardOutput {
+        port: Option<u16>,
+        rate_hz: u16,
+    },
+    /// Telemetry rate granted to the sender, at most the tick rate
+    TelemetryRate {
+        rate_hz: u16,
+    },
+    BanList(BanListData),
+    ConnectionStats(ConnectionStatsData),
+    ReplayList(ReplayListData),
+    ReplayChunk(ReplayChunkData),
+    ReplayState(ReplayStateData),
+    SavedSessionList(SavedSessionListData),
+    SessionSaved(SessionSavedData),
+    ContentManifest(ContentManifestData),
+    ContentChunk(ContentChunkData),
 
     // UDP - High frequency telemetry
     Telemetry(Telemetry),
+    ExtendedTelemetry(ExtendedTelemetry),
 }
 
 impl ServerMessage {
@@ -163,13 +793,39 @@ impl ServerMessage {
             ServerMessage::SessionStarting { .. } => MessagePriority::Critical,
             ServerMessage::SessionLeft => MessagePriority::Critical,
             ServerMessage::GameModeChanged { .. } => MessagePriority::Critical,
+            ServerMessage::RaceEvent(_) => MessagePriority::Critical,
+            ServerMessage::SessionResults(_) => MessagePriority::Critical,
+            ServerMessage::SessionRedirect(_) => MessagePriority::Critical,
+            ServerMessage::InstanceRedirect(_) => MessagePriority::Critical,
+            ServerMessage::SessionReset(_) => MessagePriority::Critical,
+            ServerMessage::WeatherUpdate(_) => MessagePriority::Critical,
+            ServerMessage::SetupApplied { .. } => MessagePriority::Critical,
+            ServerMessage::DriverAidsApplied { .. } => MessagePriority::Critical,
+            ServerMessage::DashboardOutput { .. } => MessagePriority::Critical,
+            ServerMessage::TelemetryRate { .. } => MessagePriority::Critical,
+            ServerMessage::Announcement { .. } => MessagePriority::Critical,
+            ServerMessage::BanList(_) => MessagePriority::Critical,
+            ServerMessage::ReplayList(_) => MessagePriority::Critical,
+            ServerMessage::ReplayChunk(_) => MessagePriority::Critical,
+            ServerMessage::ReplayState(_) => MessagePriority::Critical,
+            ServerMessage::SavedSessionList(_) => MessagePriority::Critical,
+            ServerMessage::SessionSaved(_) => MessagePriority::Critical,
+            ServerMessage::ContentManifest(_) => MessagePriority::Critical,
+            ServerMessage::ContentChunk(_) => MessagePriority::Critical,
+            // The one reply to an explicit request
+            ServerMessage::RecentTelemetry(_) => MessagePriority::Critical,
+            // Deltas: a lost update leaves the client's lobby out of date
+            ServerMessage::LobbyUpdate(_) => MessagePriority::Critical,
 
             // Droppable messages - can be dropped when queue is full
             ServerMessage::HeartbeatAck { .. } => MessagePriority::Droppable,
             ServerMessage::CountdownUpdate { .. } => MessagePriority::Droppable,
             ServerMessage::LobbyState(_) => MessagePriority::Droppable,
             ServerMessage::Telemetry(_) => MessagePriority::Droppable,
+            ServerMessage::ExtendedTelemetry(_) => MessagePriority::Droppable,
             ServerMessage::PlayerDisconnected(_) => MessagePriority::Droppable,
+            ServerMessage::Standings(_) => MessagePriority::Droppable,
+            ServerMessage::ConnectionStats(_) => MessagePriority::Droppable,
         }
     }
 }
@@ -183,11 +839,13 @@ pub struct LobbyPlayer {
     pub name: String,
     #[serde(serialize_with = "serialize_option_uuid_as_string", deserialize_with = "deserialize_option_uuid_from_string", rename = "SelectedCar")]
     pub selected_car: Option<CarConfigId>,
+    #[serde(default)]
+    pub selected_livery: u8,
     #[serde(serialize_with = "serialize_option_uuid_as_string", deserialize_with = "deserialize_option_uuid_from_string", rename = "InSession")]
     pub in_session: Option<SessionId>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct SessionSummary {
     #[serde(serialize_with = "serialize_uuid_as_string", deserialize_with = "deserialize_uuid_from_string", rename = "Id")]
@@ -200,6 +858,18 @@ pub struct SessionSummary {
     pub player_count: u8,
     pub max_players: u8,
     pub state: SessionState,
+    /// Set while a lap-limited race is running
+    #[serde(default)]
+    pub race_progress: Option<RaceProgress>,
+    #[serde(default)]
+    pub setup_policy: SetupPolicy,
+    #[serde(default)]
+    pub collision_policy: CollisionPolicy,
+    #[serde(default)]
+    pub time_of_day: TimeOfDay,
+    /// Highest driver aid levels players may run
+    #[serde(default = "DriverAids::unrestricted")]
+    pub aid_limits: DriverAids,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -211,21 +881,39 @@ pub struct CarConfigSummary {
     pub model_path: String,
     pub mass_kg: f32,
     pub max_engine_force_n: f32,
+    /// Peak engine torque and power from the car's torque curve
+    #[serde(default)]
+    pub peak_torque_nm: f32,
+    #[serde(default)]
+    pub peak_power_w: f32,
+    /// Limits on player setups for this car
+    #[serde(default)]
+    pub setup_ranges: SetupRanges,
+    /// The setup the car runs unless a player changes it
+    pub default_setup: CarSetup,
+    /// SHA-256 of the server's `car.toml` and model file, for checking local copies
+    #[serde(default)]
+    pub content_hash: String,
+    /// Paint schemes to pick from; empty when the car has only its model's paint
+    #[serde(default)]
+    pub liveries: Vec<Livery>,
 }
 
-fn serialize_uuid_as_string<S>(uuid: &uuid::Uuid, serializer: S) -> Result<S::Ok, S::Error>
+fn serialize_uuid_as_string<S, T>(id: &T, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
+    T: std::fmt::Display,
 {
-    serializer.serialize_str(&uuid.to_string())
+    serializer.serialize_str(&id.to_string())
 }
 
-fn serialize_option_uuid_as_string<S>(uuid: &Option<uuid::Uuid>, serializer: S) -> Result<S::Ok, S::Error>
+fn serialize_option_uuid_as_string<S, T>(id: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
+    T: std::fmt::Display,
 {
-    match uuid {
-        Some(u) => serializer.serialize_str(&u.to_string()),
+    match id {
+        Some(id) => serializer.serialize_str(&id.to_string()),
         None => serializer.serialize_none(),
     }
 }
@@ -245,6 +933,25 @@ pub struct TrackConfigSummary {
     /// Simplified centerline points for visualization (every Nth point)
     #[serde(default)]
     pub centerline: Vec<TrackPoint>,
+    /// Track file relative to the content folder, as in `SessionJoined`
+    #[serde(default)]
+    pub source_path: Option<String>,
+    /// SHA-256 of the server's track file, for checking local copies
+    #[serde(default)]
+    pub content_hash: String,
+    #[serde(default)]
+    pub country: Option<String>,
+    /// Lap length (m): the track file's, else measured along the centerline
+    #[serde(default)]
+    pub length_m: f32,
+    #[serde(default)]
+    pub corners: u32,
+    /// e.g. "oval", "street"
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Fastest race lap on the track; `None` until one is driven
+    #[serde(default)]
+    pub lap_record: Option<LapRecord>,
 }
 
 // --- Compact Telemetry ---
@@ -259,6 +966,9 @@ pub struct CarStateTelemetry {
     pub yaw_rad: f32,
     pub pitch_rad: f32,
     pub roll_rad: f32,
+    /// Body orientation for rendering, the same rotation as the Euler angles without their singularities
+    #[serde(default)]
+    pub orientation: Orientation,
     // Motion
     pub speed_mps: f32,
     pub throttle: f32,
@@ -276,6 +986,39 @@ pub struct CarStateTelemetry {
     // Status
     pub is_on_track: bool,
     pub is_colliding: bool,
+    /// Passes through other cars, for clients to draw see-through
+    #[serde(default)]
+    pub is_ghost: bool,
+    // Engineering (tire/brake warmup), ordered FL, FR, RL, RR
+    pub tire_temps_c: [f32; 4],
+    pub brake_temps_c: [f32; 4],
+    pub tire_grip_factor: f32,
+    pub brake_efficiency: f32,
+    /// Suspension compression per corner (m)
+    #[serde(default)]
+    pub suspension_travel_m: [f32; 4],
+    /// Slipstream strength (0 = clean air, 1 = full tow)
+    #[serde(default)]
+    pub draft_factor: f32,
+    /// Car whose wake this car is in
+    #[serde(default)]
+    pub drafting_behind: Option<PlayerId>,
+    /// Driver aids the car runs with, so leagues can check aid rules
+    #[serde(default)]
+    pub aids: DriverAids,
+    /// Aids that intervened this tick
+    #[serde(default)]
+    pub aids_active: ActiveAids,
+    #[serde(default)]
+    pub engine_stalled: bool,
+    #[serde(default)]
+    pub is_airborne: bool,
+    /// Rolled onto its side and out of the session
+    #[serde(default)]
+    pub is_overturned: bool,
+    /// Index into the car's liveries, for drawing the right skin
+    #[serde(default)]
+    pub livery: u8,
 }
 
 /// Telemetry data sent to clients at high frequency (240Hz)
@@ -286,6 +1029,123 @@ pub struct Telemetry {
     pub game_mode: GameMode,
     pub countdown_ms: Option<u16>,
     pub car_states: Vec<CarStateTelemetry>,
+    /// Pace car while it is on track during a rolling start
+    #[serde(default)]
+    pub pace_car: Option<PaceCarTelemetry>,
+    /// Leader's progress through a lap-limited race
+    #[serde(default)]
+    pub race_progress: Option<RaceProgress>,
+}
+
+/// How far a lap-limited race has run, for progress bars and lobby listings
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct RaceProgress {
+    /// Lap the leader is on, capped at the race length
+    pub leader_lap: u16,
+    pub total_laps: u16,
+    /// Share of the race distance the leader has covered (0-1)
+    pub completed_fraction: f32,
+    /// Time until the leader takes the flag at their last lap's pace; None until they complete a lap
+    pub estimated_remaining_s: Option<u32>,
+}
+
+/// Position of the server-driven pace car
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaceCarTelemetry {
+    pub pos_x: f32,
+    pub pos_y: f32,
+    pub pos_z: f32,
+    pub yaw_rad: f32,
+    pub speed_mps: f32,
+}
+
+/// One car's telemetry at a given tick, as kept in the history buffer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetrySample {
+    pub server_tick: u32,
+    pub car_state: CarStateTelemetry,
+}
+
+/// Engineering channels of the cars a client may see, sent at a lower rate
+/// than `Telemetry`: to each driver for their own car, and to spectators who
+/// asked with `SetExtendedTelemetry`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtendedTelemetry {
+    pub server_tick: u32,
+    pub cars: Vec<ExtendedCarTelemetry>,
+}
+
+/// Per-wheel arrays are ordered FL, FR, RL, RR
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtendedCarTelemetry {
+    pub player_id: PlayerId,
+    pub engine_rpm: f32,
+    pub gear: i8,
+    /// Left negative, right positive
+    pub lateral_g: f32,
+    /// Braking negative, acceleration positive
+    pub longitudinal_g: f32,
+    pub vertical_g: f32,
+    pub slip_ratio: [f32; 4],
+    pub slip_angle_rad: [f32; 4],
+    pub tire_temps_c: [f32; 4],
+    pub tire_pressures_kpa: [f32; 4],
+    pub tire_wear_percent: [f32; 4],
+    /// Compression (m)
+    pub suspension_travel_m: [f32; 4],
+    pub wheel_load_n: [f32; 4],
+    pub brake_temps_c: [f32; 4],
+}
+
+impl From<&CarState> for ExtendedCarTelemetry {
+    fn from(state: &CarState) -> Self {
+        let tires = [
+            &state.tires.front_left,
+            &state.tires.front_right,
+            &state.tires.rear_left,
+            &state.tires.rear_right,
+        ];
+        Self {
+            player_id: state.player_id,
+            engine_rpm: state.engine_rpm,
+            gear: state.gear,
+            lateral_g: state.g_forces.lateral_g,
+            longitudinal_g: state.g_forces.longitudinal_g,
+            vertical_g: state.g_forces.vertical_g,
+            slip_ratio: tires.map(|tire| tire.slip_ratio),
+            slip_angle_rad: tires.map(|tire| tire.slip_angle_rad),
+            tire_temps_c: tires.map(|tire| tire.temperature_c),
+            tire_pressures_kpa: tires.map(|tire| tire.pressure_kpa),
+            tire_wear_percent: tires.map(|tire| tire.wear_percent),
+            suspension_travel_m: [
+                state.suspension.front_left_travel_m,
+                state.suspension.front_right_travel_m,
+                state.suspension.rear_left_travel_m,
+                state.suspension.rear_right_travel_m,
+            ],
+            wheel_load_n: [
+                state.weight_front_left_n,
+                state.weight_front_right_n,
+                state.weight_rear_left_n,
+                state.weight_rear_right_n,
+            ],
+            brake_temps_c: [
+                state.brakes.front_left_temp_c,
+                state.brakes.front_right_temp_c,
+                state.brakes.rear_left_temp_c,
+                state.brakes.rear_right_temp_c,
+            ],
+        }
+    }
+}
+
+/// Reply to `RequestRecentTelemetry`, oldest sample first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentTelemetry {
+    pub player_id: PlayerId,
+    pub sample_interval_ms: u16,
+    pub samples: Vec<TelemetrySample>,
 }
 
 impl From<&CarState> for CarStateTelemetry {
@@ -298,6 +1158,7 @@ impl From<&CarState> for CarStateTelemetry {
             yaw_rad: state.yaw_rad,
             pitch_rad: state.pitch_rad,
             roll_rad: state.roll_rad,
+            orientation: state.orientation,
             speed_mps: state.speed_mps,
             throttle: state.throttle_input,
             brake: state.brake_input,
@@ -312,6 +1173,35 @@ impl From<&CarState> for CarStateTelemetry {
             best_lap_time_ms: state.best_lap_time_ms,
             is_on_track: state.is_on_track,
             is_colliding: state.is_colliding,
+            is_ghost: state.is_ghost,
+            tire_temps_c: [
+                state.tires.front_left.temperature_c,
+                state.tires.front_right.temperature_c,
+                state.tires.rear_left.temperature_c,
+                state.tires.rear_right.temperature_c,
+            ],
+            brake_temps_c: [
+                state.brakes.front_left_temp_c,
+                state.brakes.front_right_temp_c,
+                state.brakes.rear_left_temp_c,
+                state.brakes.rear_right_temp_c,
+            ],
+            tire_grip_factor: state.tire_grip_factor,
+            brake_efficiency: state.brake_efficiency,
+            suspension_travel_m: [
+                state.suspension.front_left_travel_m,
+                state.suspension.front_right_travel_m,
+                state.suspension.rear_left_travel_m,
+                state.suspension.rear_right_travel_m,
+            ],
+            draft_factor: state.draft_factor,
+            drafting_behind: state.drafting_behind,
+            aids: state.aids,
+            aids_active: state.aids_active,
+            engine_stalled: state.engine_stalled,
+            is_airborne: state.is_airborne,
+            is_overturned: state.is_overturned,
+            livery: state.livery,
         }
     }
 }
@@ -319,20 +1209,20 @@ impl From<&CarState> for CarStateTelemetry {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use uuid::Uuid;
 
     #[test]
     fn test_client_message_serialization() {
         let msg = ClientMessage::Authenticate {
             token: "test_token".to_string(),
             player_name: "Player1".to_string(),
+            telemetry_rate_hz: 0,
         };
 
         let serialized = rmp_serde::to_vec_named(&msg).unwrap();
         let deserialized: ClientMessage = rmp_serde::from_slice(&serialized).unwrap();
 
         match deserialized {
-            ClientMessage::Authenticate { token, player_name } => {
+            ClientMessage::Authenticate { token, player_name, .. } => {
                 assert_eq!(token, "test_token");
                 assert_eq!(player_name, "Player1");
             }
@@ -342,7 +1232,7 @@ mod tests {
 
     #[test]
     fn test_server_message_serialization() {
-        let player_id = Uuid::new_v4();
+        let player_id = PlayerId::new_v4();
         let msg = ServerMessage::AuthSuccess(AuthSuccessData {
             player_id,
             server_version: 1,
@@ -360,6 +1250,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_replay_chunk_sends_raw_bytes() {
+        let msg = ServerMessage::ReplayChunk(ReplayChunkData {
+            session_id: SessionId::new_v4(),
+            offset: 0,
+            total_bytes: 4096,
+            data: vec![0xff; 4096],
+        });
+
+        // A byte array, not a list of integers
+        let serialized = rmp_serde::to_vec_named(&msg).unwrap();
+        assert!(serialized.len() < 4096 + 200);
+
+        match rmp_serde::from_slice::<ServerMessage>(&serialized).unwrap() {
+            ServerMessage::ReplayChunk(chunk) => assert_eq!(chunk.data, vec![0xff; 4096]),
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+
     #[test]
     fn test_player_input_serialization() {
         let msg = ClientMessage::PlayerInput {
@@ -367,6 +1276,8 @@ mod tests {
             throttle: 0.8,
             brake: 0.0,
             steering: -0.5,
+            gear: Some(3),
+            clutch: None,
         };
 
         let serialized = rmp_serde::to_vec_named(&msg).unwrap();
@@ -378,11 +1289,15 @@ mod tests {
                 throttle,
                 brake,
                 steering,
+                gear,
+                clutch,
             } => {
                 assert_eq!(server_tick_ack, 100);
                 assert_eq!(throttle, 0.8);
                 assert_eq!(brake, 0.0);
                 assert_eq!(steering, -0.5);
+                assert_eq!(gear, Some(3));
+                assert_eq!(clutch, None);
             }
             _ => panic!("Wrong message type"),
         }
@@ -390,8 +1305,8 @@ mod tests {
 
     #[test]
     fn test_telemetry_conversion() {
-        let player_id = Uuid::new_v4();
-        let car_id = Uuid::new_v4();
+        let player_id = PlayerId::new_v4();
+        let car_id = CarConfigId::new_v4();
         let grid_slot = GridSlot {
             position: 1,
             x: 10.0,
@@ -1,27 +1,202 @@
 //! 3D Physics Engine for Racing Simulation
 //!
 //! This module implements a realistic 3D vehicle physics simulation with:
-//! - Full 3D position and orientation tracking
+//! - Full 3D position and quaternion orientation, with jumps and rollovers
 //! - Weight transfer (longitudinal and lateral)
 //! - Pacejka-inspired tire model
 //! - Suspension simulation with spring/damper dynamics
-//! - Aerodynamic forces (drag and downforce)
+//! - Aerodynamic forces (drag and downforce) with slipstreaming
 //! - Track surface interaction (grip, elevation, banking)
-//! - Engine and drivetrain simulation
+//! - Engine, clutch and gearbox with automatic or manual shifting
+//! - Tire and brake warmup from a cold start
+//! - Impulse-based collisions using each car's box, convex hull or capsule footprint
 
 use crate::data::*;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::f32::consts::PI;
+use std::f32::consts::{FRAC_PI_2, PI};
 
 /// Gravity constant (m/s²)
 const GRAVITY: f32 = 9.81;
 
 /// Air density at sea level (kg/m³)
-const AIR_DENSITY: f32 = 1.225;
+pub const AIR_DENSITY: f32 = 1.225;
 
 /// Minimum speed threshold for calculations (m/s)
 const MIN_SPEED_THRESHOLD: f32 = 0.1;
 
+/// Speed at which a nominally loaded tire is worked up to its optimal temperature (m/s)
+const TIRE_WORKING_SPEED: f32 = 30.0;
+
+/// Heat capacity of a single brake disc and pad assembly (J/K)
+const BRAKE_HEAT_CAPACITY: f32 = 3000.0;
+
+/// Bump stop stiffness as a multiple of the corner spring rate
+const BUMP_STOP_RATE_FACTOR: f32 = 10.0;
+
+/// How far behind a car its wake still gives a tow (m)
+const SLIPSTREAM_RANGE_M: f32 = 30.0;
+
+/// Wake half-width right behind a car, as a fraction of its width
+const SLIPSTREAM_BASE_HALF_WIDTH: f32 = 0.75;
+
+/// Wake spread per metre behind the car
+const SLIPSTREAM_SPREAD: f32 = 0.05;
+
+/// Lead car speed below which there is no useful wake (m/s)
+const SLIPSTREAM_MIN_SPEED: f32 = 15.0;
+
+/// Drag and downforce lost in a full tow
+const SLIPSTREAM_DRAG_REDUCTION: f32 = 0.4;
+const SLIPSTREAM_DOWNFORCE_LOSS: f32 = 0.3;
+
+/// Grip lost on a fully soaked track surface
+const WET_TRACK_GRIP_LOSS: f32 = 0.35;
+
+/// Grip lost on soaked grass, gravel and sand
+const WET_OFF_TRACK_GRIP_LOSS: f32 = 0.5;
+
+/// Grip and fraction of speed lost per second in a gravel trap
+const GRAVEL_GRIP: f32 = 0.5;
+const GRAVEL_DRAG: f32 = 1.5;
+
+/// Grip and fraction of speed lost per second in sand
+const SAND_GRIP: f32 = 0.4;
+const SAND_DRAG: f32 = 2.2;
+
+/// Grip of a standing-water patch relative to the dry track
+const STANDING_WATER_GRIP: f32 = 0.7;
+
+/// Front damage per second from ploughing through gravel or sand at the reference speed (%)
+const GRAVEL_DAMAGE_RATE: f32 = 2.0;
+const SAND_DAMAGE_RATE: f32 = 1.0;
+
+/// Speed at which loose surfaces do their nominal damage rate (m/s)
+const SURFACE_DAMAGE_REFERENCE_SPEED: f32 = 30.0;
+
+/// Below this speed a car rolls through loose surfaces without damage (m/s)
+const SURFACE_DAMAGE_MIN_SPEED: f32 = 8.0;
+
+/// Simplified wheel inertia: how much a unit of drive or brake torque spins a wheel
+/// up or down relative to the ground
+const WHEEL_SPIN_INERTIA: f32 = 100.0;
+
+/// Wheel torque above which a standing wheel is driven or braked at its optimal slip (N·m)
+const STANDSTILL_DRIVE_TORQUE: f32 = 10.0;
+
+/// Slip across the clutch over which its torque builds to full capacity (rpm)
+const CLUTCH_SLIP_BAND_RPM: f32 = 300.0;
+
+/// Clutch engagement below which the engine runs free of the drive and can restart
+const CLUTCH_RESTART_ENGAGEMENT: f32 = 0.1;
+
+/// Point between idle and redline by which the automatic clutch is fully engaged
+const AUTO_CLUTCH_ENGAGE_FRACTION: f32 = 0.3;
+
+/// Point between idle and redline at which the automatic gearbox changes up, and
+/// below which the next gear down must land for it to change down
+const AUTO_UPSHIFT_FRACTION: f32 = 0.95;
+const AUTO_DOWNSHIFT_FRACTION: f32 = 0.7;
+
+/// Engine speed, as a fraction of idle, below which a loaded engine stalls
+const STALL_RPM_FRACTION: f32 = 0.5;
+
+/// Fastest a car may roll when changing between forward and reverse (m/s)
+const REVERSE_ENGAGE_MAX_SPEED: f32 = 2.0;
+
+const RPM_PER_RAD_S: f32 = 60.0 / (2.0 * PI);
+
+/// Band below the rev limiter over which the fuel is progressively cut (rpm)
+const REV_LIMITER_CUT_RPM: f32 = 100.0;
+
+/// Blow-off time as a fraction of the turbo spool time
+const TURBO_BLOW_OFF_FRACTION: f32 = 0.25;
+
+/// Slip the ABS and traction control let through, as a multiple of the tire's optimal slip
+const AID_SLIP_ALLOWANCE_LOW: f32 = 1.6;
+const AID_SLIP_ALLOWANCE_HIGH: f32 = 1.0;
+
+/// Yaw rate the stability control lets through beyond what the steering asks for,
+/// as a fraction of the requested rate plus a fixed margin (rad/s)
+const STABILITY_YAW_ALLOWANCE_LOW: (f32, f32) = (0.5, 0.15);
+const STABILITY_YAW_ALLOWANCE_HIGH: (f32, f32) = (0.2, 0.05);
+
+/// Below this speed the stability control does not intervene (m/s)
+const STABILITY_MIN_SPEED: f32 = 5.0;
+
+/// Temperature cold tire pressures are set at, and how much they rise per degree above it
+const TIRE_PRESSURE_REFERENCE_TEMP_C: f32 = 20.0;
+const TIRE_PRESSURE_RISE_KPA_PER_C: f32 = 0.5;
+
+/// Landing faster than this (m/s) damages the car, by this much per m/s over
+const LANDING_DAMAGE_SPEED: f32 = 4.0;
+const LANDING_DAMAGE_PER_MPS: f32 = 10.0;
+
+/// Damage to the side a car rolls onto
+const ROLLOVER_DAMAGE: f32 = 50.0;
+
+/// Deceleration of a car sliding on its side (m/s²)
+const OVERTURNED_SLIDE_DECEL: f32 = 6.0;
+
+/// Tilt of a curb rising towards the track edge
+const CURB_TILT_RAD: f32 = 0.05;
+
+/// How a surface treats a car driving on it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurfaceProperties {
+    /// Grip multiplier (1.0 = dry asphalt)
+    pub grip: f32,
+    /// Fraction of speed lost per second to rolling resistance
+    pub drag: f32,
+    /// Front damage per second at `SURFACE_DAMAGE_REFERENCE_SPEED` (%)
+    pub damage_rate: f32,
+}
+
+/// Tire and brake warmup parameters derived from a realism preset
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct WarmupSettings {
+    pub preset: RealismPreset,
+    pub ambient_temp_c: f32,
+    /// Time constant for tires approaching their working temperature (seconds)
+    pub tire_warmup_time_s: f32,
+    /// Lowest grip multiplier from tires outside their temperature window
+    pub min_tire_grip_factor: f32,
+    /// Brake effectiveness with discs at ambient temperature
+    pub cold_brake_efficiency: f32,
+    /// Disc temperature at which brakes reach full effectiveness
+    pub brake_operating_temp_c: f32,
+}
+
+impl WarmupSettings {
+    pub fn from_preset(preset: RealismPreset, ambient_temp_c: f32) -> Self {
+        let (tire_warmup_time_s, min_tire_grip_factor, cold_brake_efficiency, brake_operating_temp_c) = match preset {
+            RealismPreset::Casual => (20.0, 1.0, 1.0, 200.0),
+            RealismPreset::Standard => (30.0, 0.85, 0.85, 250.0),
+            RealismPreset::Simulation => (60.0, 0.7, 0.7, 350.0),
+        };
+
+        Self {
+            preset,
+            ambient_temp_c,
+            tire_warmup_time_s,
+            min_tire_grip_factor,
+            cold_brake_efficiency,
+            brake_operating_temp_c,
+        }
+    }
+
+    /// Whether cars start a run with tires and brakes at ambient temperature
+    pub fn cold_start(&self) -> bool {
+        self.preset != RealismPreset::Casual
+    }
+}
+
+impl Default for WarmupSettings {
+    fn default() -> Self {
+        Self::from_preset(RealismPreset::default(), 20.0)
+    }
+}
+
 /// Per-wheel physics state for intermediate calculations
 #[derive(Debug, Clone, Copy, Default)]
 pub struct WheelState {
@@ -58,8 +233,11 @@ pub struct VehiclePhysicsState {
 pub struct TrackContext {
     pub nearest_point: usize,
     pub elevation: f32,
-    pub banking_rad: f32,
+    /// Rise of the surface to the left of the track direction: banking lowers the inside of a turn, camber falls away from the crown and curbs rise towards the edge
+    pub cross_slope_rad: f32,
     pub slope_rad: f32,
+    /// Change of grade per metre along the track: positive in a dip, negative over a crest
+    pub vertical_curvature: f32,
     pub heading_rad: f32,
     pub lateral_offset: f32,
     pub is_on_track: bool,
@@ -69,6 +247,18 @@ pub struct TrackContext {
     pub width_right: f32,
 }
 
+/// Gravity and surface load on a car from the track frame beneath it
+#[derive(Debug, Clone, Copy)]
+struct TrackFrameForces {
+    /// Gravity along the track surface in the vehicle frame (m/s²)
+    accel_x: f32,
+    accel_y: f32,
+    /// Load pressing the car into the surface as a multiple of its weight
+    normal_factor: f32,
+    pitch_rad: f32,
+    roll_rad: f32,
+}
+
 /// Update car physics for one simulation tick - main 3D physics function
 pub fn update_car_3d(
     state: &mut CarState,
@@ -93,8 +283,18 @@ pub fn update_car_3d(
     state.surface_grip_modifier = track_ctx.grip_modifier;
     state.lateral_offset_m = track_ctx.lateral_offset;
     
-    // 2. Calculate static weight distribution
-    let total_weight = config.mass_kg * GRAVITY;
+    // 2. Calculate static weight distribution on the surface beneath the car
+    let track_forces = calculate_track_frame_forces(state, &track_ctx);
+    if !state.is_airborne && !state.is_overturned && track_forces.normal_factor <= 0.0 {
+        // Over a crest taken faster than gravity can follow the road
+        take_off(state, &track_ctx);
+    }
+    let wheels_down = !state.is_airborne && !state.is_overturned;
+    let total_weight = if wheels_down {
+        config.mass_kg * GRAVITY * track_forces.normal_factor
+    } else {
+        0.0
+    };
     let (static_front_weight, static_rear_weight) = calculate_static_weight_distribution(config, total_weight);
     
     // 3. Calculate aerodynamic forces
@@ -103,20 +303,19 @@ pub fn update_car_3d(
     state.downforce_front_n = downforce_front;
     state.downforce_rear_n = downforce_rear;
     
-    // 4. Calculate engine torque and RPM
-    let (engine_torque, engine_rpm) = calculate_engine_output(state, config, input);
-    state.engine_rpm = engine_rpm;
+    // 4. Gearbox, clutch and engine speed
+    let clutch_torque = update_drivetrain(state, config, input, dt);
 
     // 5. Calculate wheel torques from drivetrain
     let (drive_torque_front, drive_torque_rear) = calculate_drive_torques(
-        engine_torque,
+        clutch_torque,
         config,
         state.gear,
     );
 
     
-    // 6. Calculate brake forces
-    let brake_force = input.brake * config.max_brake_force_n;
+    // 6. Calculate brake forces (cold discs reduce stopping power)
+    let brake_force = input.brake * config.max_brake_force_n * state.brake_efficiency;
     let brake_front = brake_force * config.brake_bias_front;
     let brake_rear = brake_force * (1.0 - config.brake_bias_front);
     
@@ -124,17 +323,31 @@ pub fn update_car_3d(
     let longitudinal_accel = state.g_forces.longitudinal_g * GRAVITY;
     let lateral_accel = state.g_forces.lateral_g * GRAVITY;
     
-    let (weight_transfer_long, weight_transfer_lat_front, weight_transfer_lat_rear) = 
-        calculate_weight_transfer(config, longitudinal_accel, lateral_accel, total_weight);
+    let weight_transfer = calculate_weight_transfer(config, longitudinal_accel, lateral_accel, total_weight);
     
-    // 8. Calculate individual wheel loads
-    let front_weight = static_front_weight + downforce_front - weight_transfer_long;
-    let rear_weight = static_rear_weight + downforce_rear + weight_transfer_long;
-    
-    state.weight_front_left_n = (front_weight / 2.0 + weight_transfer_lat_front).max(0.0);
-    state.weight_front_right_n = (front_weight / 2.0 - weight_transfer_lat_front).max(0.0);
-    state.weight_rear_left_n = (rear_weight / 2.0 + weight_transfer_lat_rear).max(0.0);
-    state.weight_rear_right_n = (rear_weight / 2.0 - weight_transfer_lat_rear).max(0.0);
+    // 8. Calculate individual wheel loads through the suspension
+    let loads = update_suspension(
+        state,
+        config,
+        (static_front_weight, static_rear_weight),
+        (downforce_front, downforce_rear),
+        weight_transfer,
+        dt,
+    );
+    // A car tipped onto one side's wheels carries its load on them alone
+    let [load_fl, load_fr, load_rl, load_rr] = if !wheels_down {
+        [0.0; 4]
+    } else if state.tip_rad > 0.0 {
+        [loads[0] + loads[1], 0.0, loads[2] + loads[3], 0.0]
+    } else if state.tip_rad < 0.0 {
+        [0.0, loads[0] + loads[1], 0.0, loads[2] + loads[3]]
+    } else {
+        loads
+    };
+    state.weight_front_left_n = load_fl;
+    state.weight_front_right_n = load_fr;
+    state.weight_rear_left_n = load_rl;
+    state.weight_rear_right_n = load_rr;
     
     // 9. Calculate steering angle
     let steering_angle = input.steering * config.max_steering_angle_rad;
@@ -147,7 +360,12 @@ pub fn update_car_3d(
     );
     
     // 10. Calculate tire forces using Pacejka-inspired model
-    let effective_grip = config.tire_config.grip_coefficient * track_ctx.grip_modifier;
+    let effective_grip = config.tire_config.grip_coefficient
+        * track_ctx.grip_modifier
+        * state.tire_grip_factor
+        * wet_grip_factor(track_ctx.surface_type, state.track_wetness);
+    let front_grip = effective_grip * pressure_grip_factor(&config.tire_config, config.tire_config.cold_pressure_front_kpa);
+    let rear_grip = effective_grip * pressure_grip_factor(&config.tire_config, config.tire_config.cold_pressure_rear_kpa);
     
     // Calculate slip ratios and angles for each wheel
     let _wheel_speed_front = state.speed_mps * (1.0 + state.angular_vel_yaw * config.track_width_front_m / 2.0 / state.speed_mps.max(0.1));
@@ -206,28 +424,28 @@ pub fn update_car_3d(
         state.weight_front_left_n,
         fl_slip.0,
         fl_slip.1,
-        effective_grip,
+        front_grip,
         &config.tire_config,
     );
     let fr_forces = calculate_tire_forces(
         state.weight_front_right_n,
         fr_slip.0,
         fr_slip.1,
-        effective_grip,
+        front_grip,
         &config.tire_config,
     );
     let rl_forces = calculate_tire_forces(
         state.weight_rear_left_n,
         rl_slip.0,
         rl_slip.1,
-        effective_grip,
+        rear_grip,
         &config.tire_config,
     );
     let rr_forces = calculate_tire_forces(
         state.weight_rear_right_n,
         rr_slip.0,
         rr_slip.1,
-        effective_grip,
+        rear_grip,
         &config.tire_config,
     );
     
@@ -241,10 +459,7 @@ pub fn update_car_3d(
     // Total forces in vehicle frame
     let total_force_x = fl_force_x + fr_force_x + rl_forces.0 + rr_forces.0 - drag_force;
     let total_force_y = fl_force_y + fr_force_y + rl_forces.1 + rr_forces.1;
-    
-    // Include gravity components on slopes
-    let slope_force = config.mass_kg * GRAVITY * track_ctx.slope_rad.sin();
-    let banking_force = config.mass_kg * GRAVITY * track_ctx.banking_rad.sin();
+
     
     // 12. Calculate yaw moment
     let yaw_moment = 
@@ -256,19 +471,21 @@ pub fn update_car_3d(
         + (fr_force_x - fl_force_x) * (config.track_width_front_m / 2.0)
         + (rr_forces.0 - rl_forces.0) * (config.track_width_rear_m / 2.0);
     
-    // 13. Calculate accelerations
-    let accel_x = (total_force_x - slope_force) / config.mass_kg;
-    let accel_y = (total_force_y + banking_force) / config.mass_kg;
+    // 13. Calculate accelerations, including gravity down hills and banking
+    let (gravity_x, gravity_y) = if state.is_airborne {
+        (0.0, 0.0)
+    } else {
+        (track_forces.accel_x, track_forces.accel_y)
+    };
+    let accel_x = total_force_x / config.mass_kg + gravity_x;
+    let accel_y = total_force_y / config.mass_kg + gravity_y;
 
-    
-    // Yaw moment of inertia (simplified as rectangular body)
-    let yaw_inertia = config.mass_kg * (config.length_m.powi(2) + config.width_m.powi(2)) / 12.0;
-    let angular_accel_yaw = yaw_moment / yaw_inertia;
+    let angular_accel_yaw = yaw_moment / yaw_inertia(config);
     
     // 14. Update G-forces
     state.g_forces.longitudinal_g = accel_x / GRAVITY;
     state.g_forces.lateral_g = accel_y / GRAVITY;
-    state.g_forces.vertical_g = 1.0 + (downforce_front + downforce_rear) / (config.mass_kg * GRAVITY);
+    state.g_forces.vertical_g = (total_weight + downforce_front + downforce_rear) / (config.mass_kg * GRAVITY);
     
     // 15. Integrate velocities
     // Transform acceleration from vehicle frame to world frame
@@ -281,15 +498,21 @@ pub fn update_car_3d(
     state.vel_x += accel_world_x * dt;
     state.vel_y += accel_world_y * dt;
     
-    // Apply off-track penalty
-    if !track_ctx.is_on_track {
-        let penalty = 1.0 - track.track_surface.off_track_speed_penalty * dt;
+    // Apply surface drag and damage (grass, gravel and sand traps)
+    let surface = surface_properties(&track.track_surface, track_ctx.surface_type);
+    if surface.drag > 0.0 && !state.is_airborne {
+        let penalty = (1.0 - surface.drag * dt).max(0.0);
         state.vel_x *= penalty;
         state.vel_y *= penalty;
     }
     
     state.speed_mps = (state.vel_x.powi(2) + state.vel_y.powi(2) + state.vel_z.powi(2)).sqrt();
     
+    if surface.damage_rate > 0.0 && state.speed_mps > SURFACE_DAMAGE_MIN_SPEED && !state.is_airborne {
+        let damage = surface.damage_rate * (state.speed_mps / SURFACE_DAMAGE_REFERENCE_SPEED) * dt;
+        apply_damage_to_car(state, 0.0, damage);
+    }
+    
     // Prevent negative speed
     if state.speed_mps < MIN_SPEED_THRESHOLD && input.throttle < 0.1 {
         state.vel_x = 0.0;
@@ -306,29 +529,15 @@ pub fn update_car_3d(
     // 17. Integrate position
     state.pos_x += state.vel_x * dt;
     state.pos_y += state.vel_y * dt;
-    state.pos_z = track_ctx.elevation; // Snap to track elevation
     
-    // 18. Integrate orientation
-    state.yaw_rad += state.angular_vel_yaw * dt;
-    state.yaw_rad = normalize_angle(state.yaw_rad);
-    
-    // Match track pitch and roll
-    state.pitch_rad = track_ctx.slope_rad;
-    state.roll_rad = -track_ctx.banking_rad;
+    // 18. Integrate height and orientation, on the surface or in the air
+    update_body_motion(state, config, &track_ctx, &track_forces, total_force_y, total_weight + downforce_front + downforce_rear, dt);
     
     // 19. Store inputs
     state.throttle_input = input.throttle;
     state.brake_input = input.brake;
     state.steering_input = input.steering;
 
-    // Apply gear and clutch inputs if provided
-    if let Some(gear) = input.gear {
-        state.gear = gear;
-    }
-    if let Some(clutch) = input.clutch {
-        state.clutch_input = clutch;
-    }
-
     // 20. Update telemetry
     update_telemetry_3d(state, config, input, &track_ctx, fl_slip, fr_slip, rl_slip, rr_slip, dt);
     
@@ -336,6 +545,240 @@ pub fn update_car_3d(
     update_fuel_consumption(state, config, input, dt);
 }
 
+/// Shape a driver's throttle and brake with the driver aids they run
+///
+/// Works ahead of the physics step: ABS and traction control limit the brake
+/// and throttle to what keeps the wheel slip the step would produce within the
+/// aid's allowance, and stability control backs off the throttle while the car
+/// yaws faster than the steering asks for. Records the aids and whether each
+/// intervened on `state` for telemetry.
+pub fn apply_driver_aids(
+    state: &mut CarState,
+    config: &CarConfig,
+    input: &PlayerInputData,
+    aids: DriverAids,
+) -> PlayerInputData {
+    let mut shaped = *input;
+    let mut active = ActiveAids::default();
+    let speed = state.speed_mps;
+    let slip_allowance = |level: AidLevel| match level {
+        AidLevel::Off => None,
+        AidLevel::Low => Some(config.tire_config.optimal_slip_ratio * AID_SLIP_ALLOWANCE_LOW),
+        AidLevel::High => Some(config.tire_config.optimal_slip_ratio * AID_SLIP_ALLOWANCE_HIGH),
+    };
+
+    if let (Some(max_slip), true) = (slip_allowance(aids.abs), config.abs_enabled) {
+        // Lockup slip of the harder-braked axle at the requested pedal
+        let axle_share = config.brake_bias_front.max(1.0 - config.brake_bias_front);
+        let wheel_brake_force = shaped.brake * config.max_brake_force_n * state.brake_efficiency * axle_share / 2.0;
+        let slip = wheel_brake_force * config.wheel_radius_m / (WHEEL_SPIN_INERTIA * speed.max(MIN_SPEED_THRESHOLD));
+        if speed > MIN_SPEED_THRESHOLD && slip > max_slip {
+            shaped.brake *= max_slip / slip;
+            active.abs = true;
+        }
+    }
+
+    if let Some(max_slip) = slip_allowance(aids.traction_control) {
+        // Wheelspin of the harder-driven axle at the requested throttle
+        let engine_torque = calculate_engine_output(config, state.engine_rpm, shaped.throttle, state.turbo_boost);
+        let (front, rear) = calculate_drive_torques(engine_torque, config, state.gear);
+        let slip = front.abs().max(rear.abs()) / 2.0 / (WHEEL_SPIN_INERTIA * speed.max(MIN_SPEED_THRESHOLD));
+        if speed > MIN_SPEED_THRESHOLD && engine_torque > 0.0 && slip > max_slip {
+            shaped.throttle *= max_slip / slip;
+            active.traction_control = true;
+        }
+    }
+
+    let yaw_allowance = match aids.stability_control {
+        AidLevel::Off => None,
+        AidLevel::Low => Some(STABILITY_YAW_ALLOWANCE_LOW),
+        AidLevel::High => Some(STABILITY_YAW_ALLOWANCE_HIGH),
+    };
+    if let (Some((fraction, margin)), true) = (yaw_allowance, speed > STABILITY_MIN_SPEED) {
+        let requested = speed * (shaped.steering * config.max_steering_angle_rad).tan() / config.wheelbase_m;
+        let allowed = requested.abs() * (1.0 + fraction) + margin;
+        let yaw_rate = state.angular_vel_yaw.abs();
+        if yaw_rate > allowed && shaped.throttle > 0.0 {
+            shaped.throttle *= allowed / yaw_rate;
+            active.stability_control = true;
+        }
+    }
+
+    state.aids = aids;
+    state.aids_active = active;
+    shaped
+}
+
+/// Leave the road with the vertical speed the surface had under the car
+fn take_off(state: &mut CarState, track_ctx: &TrackContext) {
+    let speed = speed_along_track(state, track_ctx);
+    state.is_airborne = true;
+    state.vel_z = speed * track_ctx.slope_rad.tan();
+    // Still turning nose down over the crest
+    state.angular_vel_pitch = speed * track_ctx.vertical_curvature;
+}
+
+/// Move the car body through the air or along the surface and keep its orientation
+///
+/// On its wheels the body sits on the surface's pitch and roll, moving on its
+/// suspension, and tips onto one side's wheels when the cornering force at the
+/// CoG outweighs the load about the outer contact patches. Past the tipping
+/// point gravity takes it over onto its side. In the air it follows a
+/// ballistic path, turning at the rates it left the ground with, and lands
+/// into its suspension on whichever wheels it comes down on.
+fn update_body_motion(
+    state: &mut CarState,
+    config: &CarConfig,
+    track_ctx: &TrackContext,
+    track_forces: &TrackFrameForces,
+    lateral_tire_force: f32,
+    surface_load: f32,
+    dt: f32,
+) {
+    if state.is_airborne {
+        state.vel_z -= GRAVITY * dt;
+        state.pos_z += state.vel_z * dt;
+        state.orientation = state.orientation.integrate(
+            (-state.angular_vel_roll, -state.angular_vel_pitch, state.angular_vel_yaw),
+            dt,
+        );
+        (state.yaw_rad, state.pitch_rad, state.roll_rad) = state.orientation.to_euler();
+        if state.pos_z <= track_ctx.elevation {
+            land(state, track_ctx, track_forces);
+        }
+        return;
+    }
+
+    state.pos_z = track_ctx.elevation;
+    state.yaw_rad = normalize_angle(state.yaw_rad + state.angular_vel_yaw * dt);
+    if state.is_overturned {
+        slide_overturned(state, dt);
+    } else {
+        update_tip(state, config, lateral_tire_force, surface_load, dt);
+    }
+
+    // The nose follows the road over crests and through dips
+    state.angular_vel_pitch = speed_along_track(state, track_ctx) * track_ctx.vertical_curvature;
+    state.pitch_rad = track_forces.pitch_rad - state.chassis.pitch_rad;
+    state.roll_rad = track_forces.roll_rad + state.tip_rad - state.chassis.roll_rad;
+    state.orientation = Orientation::from_euler(state.yaw_rad, state.pitch_rad, state.roll_rad);
+}
+
+/// Come back down onto the surface, the suspension taking the impact
+fn land(state: &mut CarState, track_ctx: &TrackContext, track_forces: &TrackFrameForces) {
+    let surface_vertical_speed = speed_along_track(state, track_ctx) * track_ctx.slope_rad.tan();
+    let impact_speed = (surface_vertical_speed - state.vel_z).max(0.0);
+
+    state.is_airborne = false;
+    state.pos_z = track_ctx.elevation;
+    state.vel_z = 0.0;
+    state.angular_vel_pitch = 0.0;
+    state.chassis.heave_rate_mps += impact_speed;
+    if impact_speed > LANDING_DAMAGE_SPEED {
+        apply_damage_to_car(state, 0.0, (impact_speed - LANDING_DAMAGE_SPEED) * LANDING_DAMAGE_PER_MPS);
+    }
+
+    // Whatever roll it comes down with leaves it on that side's wheels
+    state.tip_rad = normalize_angle(state.roll_rad - track_forces.roll_rad);
+}
+
+/// Roll the car about its outer wheels while the cornering force lifts the inner ones
+fn update_tip(state: &mut CarState, config: &CarConfig, lateral_tire_force: f32, surface_load: f32, dt: f32) {
+    let half_track = (config.track_width_front_m + config.track_width_rear_m) / 4.0;
+    let height = config.cog_height_m;
+    // The tires push one way; the body's inertia leans it the other
+    let side = if state.tip_rad != 0.0 {
+        state.tip_rad.signum()
+    } else {
+        -lateral_tire_force.signum()
+    };
+    let tip = state.tip_rad.abs();
+    let overturning = -side * lateral_tire_force * (height * tip.cos() + half_track * tip.sin());
+    let restoring = surface_load * (half_track * tip.cos() - height * tip.sin());
+    if tip == 0.0 && overturning <= restoring {
+        state.angular_vel_roll = 0.0;
+        return;
+    }
+
+    // Inertia about the contact line of the wheels still down
+    let body_height = 2.0 * height;
+    let inertia = config.mass_kg * (config.width_m.powi(2) + body_height.powi(2)) / 12.0
+        + config.mass_kg * (half_track.powi(2) + height.powi(2));
+    let tip_rate = side * state.angular_vel_roll + (overturning - restoring) / inertia * dt;
+    let tip = tip + tip_rate * dt;
+
+    if tip <= 0.0 {
+        // Back down on all four wheels
+        state.tip_rad = 0.0;
+        state.angular_vel_roll = 0.0;
+    } else if tip >= FRAC_PI_2 {
+        overturn(state, side);
+    } else {
+        state.tip_rad = side * tip;
+        state.angular_vel_roll = side * tip_rate;
+    }
+}
+
+/// Put the car on its side, damaging the side it landed on
+fn overturn(state: &mut CarState, side: f32) {
+    state.is_overturned = true;
+    state.tip_rad = side * FRAC_PI_2;
+    state.angular_vel_roll = 0.0;
+    let impact_angle = if side > 0.0 { PI / 2.0 } else { 3.0 * PI / 2.0 };
+    apply_damage_to_car(state, impact_angle, ROLLOVER_DAMAGE);
+}
+
+/// Scrape an overturned car to a stop; once it stops it is out
+fn slide_overturned(state: &mut CarState, dt: f32) {
+    let speed = state.vel_x.hypot(state.vel_y);
+    let slowed = (speed - OVERTURNED_SLIDE_DECEL * dt).max(0.0);
+    let scale = if speed > 0.0 { slowed / speed } else { 0.0 };
+    state.vel_x *= scale;
+    state.vel_y *= scale;
+    state.speed_mps = slowed;
+    state.angular_vel_yaw *= scale;
+    if slowed == 0.0 {
+        state.damage.is_drivable = false;
+    }
+}
+
+fn speed_along_track(state: &CarState, track_ctx: &TrackContext) -> f32 {
+    let (sin_heading, cos_heading) = track_ctx.heading_rad.sin_cos();
+    state.vel_x * cos_heading + state.vel_y * sin_heading
+}
+
+/// Resolve gravity into the car's frame on the local track surface
+///
+/// The slope and cross-slope are turned into rises ahead of and to the left of
+/// the car, so a car pointing across a hill feels it sideways. The surface load
+/// grows when the car corners towards the low side of a bank or drives through
+/// a dip, and falls over a crest.
+fn calculate_track_frame_forces(state: &CarState, track_ctx: &TrackContext) -> TrackFrameForces {
+    let relative_yaw = state.yaw_rad - track_ctx.heading_rad;
+    let (sin_rel, cos_rel) = relative_yaw.sin_cos();
+    let grade = track_ctx.slope_rad.tan();
+    let cross = track_ctx.cross_slope_rad.tan();
+    let rise_ahead = grade * cos_rel + cross * sin_rel;
+    let rise_left = cross * cos_rel - grade * sin_rel;
+
+    let pitch_rad = rise_ahead.atan();
+    let roll_rad = rise_left.atan();
+
+    // Cornering acceleration points to the car's left when it yaws left
+    let cornering_accel = state.speed_mps * state.angular_vel_yaw;
+    let vertical_accel = speed_along_track(state, track_ctx).powi(2) * track_ctx.vertical_curvature;
+    let normal_factor = (1.0 + (vertical_accel - cornering_accel * rise_left) / GRAVITY)
+        / (1.0 + rise_ahead.powi(2) + rise_left.powi(2)).sqrt();
+
+    TrackFrameForces {
+        accel_x: -GRAVITY * pitch_rad.sin(),
+        accel_y: -GRAVITY * roll_rad.sin(),
+        normal_factor: normal_factor.max(0.0),
+        pitch_rad,
+        roll_rad: -roll_rad,
+    }
+}
+
 /// Calculate static weight distribution based on CoG position
 fn calculate_static_weight_distribution(config: &CarConfig, total_weight: f32) -> (f32, f32) {
     let front_weight = total_weight * config.weight_distribution_front;
@@ -343,78 +786,223 @@ fn calculate_static_weight_distribution(config: &CarConfig, total_weight: f32) -
     (front_weight, rear_weight)
 }
 
-/// Calculate aerodynamic forces (drag and downforce)
+/// Calculate aerodynamic forces (drag and downforce), reduced in another car's slipstream
 fn calculate_aerodynamic_forces(state: &CarState, config: &CarConfig) -> (f32, f32, f32) {
     let speed_squared = state.speed_mps.powi(2);
     let dynamic_pressure = 0.5 * AIR_DENSITY * speed_squared;
+    let draft = state.draft_factor.clamp(0.0, 1.0);
     
     // Drag force
-    let drag = dynamic_pressure * config.drag_coefficient * config.frontal_area_m2;
+    let drag = dynamic_pressure * config.drag_coefficient * config.frontal_area_m2
+        * (1.0 - SLIPSTREAM_DRAG_REDUCTION * draft);
     
     // Downforce (lift coefficients are negative for downforce)
-    let downforce_front = -dynamic_pressure * config.lift_coefficient_front * config.frontal_area_m2;
-    let downforce_rear = -dynamic_pressure * config.lift_coefficient_rear * config.frontal_area_m2;
+    let downforce_scale = 1.0 - SLIPSTREAM_DOWNFORCE_LOSS * draft;
+    let downforce_front = -dynamic_pressure * config.lift_coefficient_front * config.frontal_area_m2 * downforce_scale;
+    let downforce_rear = -dynamic_pressure * config.lift_coefficient_rear * config.frontal_area_m2 * downforce_scale;
     
     (drag, downforce_front.max(0.0), downforce_rear.max(0.0))
 }
 
-/// Calculate engine output torque and RPM
-fn calculate_engine_output(state: &CarState, config: &CarConfig, input: &PlayerInputData) -> (f32, f32) {
-    // Calculate wheel speed based on current velocity
-    let wheel_rpm = if state.speed_mps > MIN_SPEED_THRESHOLD {
-        (state.speed_mps / (2.0 * PI * config.wheel_radius_m)) * 60.0
-    } else {
+/// Gearbox, clutch and engine speed for one tick
+///
+/// Changes gear when the driver asks, or when the automatic gearbox decides if
+/// they send no gear, cutting the drive for the car's shift time. The clutch
+/// couples the engine to the gearbox: fully engaged at matching speeds it locks
+/// and passes the engine's torque, engine braking included, and otherwise it
+/// slips and passes up to its capacity. Without a clutch input the clutch works
+/// itself, taking up drive as the engine revs past idle and holding the driven
+/// wheels at their best slip until the car is rolling, so it never stalls.
+/// Returns the torque the clutch passes into the gearbox.
+fn update_drivetrain(state: &mut CarState, config: &CarConfig, input: &PlayerInputData, dt: f32) -> f32 {
+    state.shift_timer_s = (state.shift_timer_s - dt).max(0.0);
+    let requested = input.gear.unwrap_or_else(|| automatic_gear(state, config));
+    if requested != state.gear && state.shift_timer_s <= 0.0 && can_shift(state, config, requested) {
+        state.gear = requested;
+        state.shift_timer_s = config.transmission.shift_time_s;
+    }
+    let shifting = state.shift_timer_s > 0.0;
+
+    let gearbox_rpm = gearbox_rpm(state, config, state.gear);
+    let rev_band = (config.redline_rpm - config.idle_rpm).max(1.0);
+    let launch_rpm = config.idle_rpm + rev_band * AUTO_CLUTCH_ENGAGE_FRACTION;
+    let engagement = if state.gear == 0 || shifting {
         0.0
-    };
-    
-    // Calculate engine RPM from wheel speed through drivetrain
-    let gear_ratio = if state.gear > 0 && (state.gear as usize) < config.gear_ratios.len() {
-        config.gear_ratios[state.gear as usize]
-    } else if state.gear == 0 {
-        0.0  // Neutral
-    } else {
-        config.gear_ratios[0]  // Reverse
-    };
-    
-    let engine_rpm = if gear_ratio.abs() > 0.001 {
-        (wheel_rpm * gear_ratio.abs() * config.final_drive_ratio).clamp(config.idle_rpm, config.max_engine_rpm)
     } else {
-        config.idle_rpm + input.throttle * (config.redline_rpm - config.idle_rpm) * 0.3
+        match input.clutch {
+            Some(pedal) => pedal.clamp(0.0, 1.0),
+            None if gearbox_rpm >= launch_rpm => 1.0,
+            None => ((state.engine_rpm - config.idle_rpm) / (launch_rpm - config.idle_rpm)).clamp(0.0, 1.0),
+        }
     };
-    
-    let torque_at_rpm = if !config.engine.torque_curve.is_empty() {
-        interpolate_torque_curve(&config.engine.torque_curve, engine_rpm)
+    state.clutch_input = engagement;
+
+    if state.engine_stalled {
+        // A stalled engine restarts once it is free of the drive
+        if engagement >= CLUTCH_RESTART_ENGAGEMENT {
+            state.engine_rpm = 0.0;
+            return 0.0;
+        }
+        state.engine_stalled = false;
+        state.engine_rpm = config.idle_rpm;
+    }
+
+    // The throttle is cut while a gear goes in
+    let throttle = if shifting { 0.0 } else { input.throttle };
+    update_turbo(state, config, throttle, dt);
+    let engine_torque = calculate_engine_output(config, state.engine_rpm, throttle, state.turbo_boost);
+    let capacity = config.transmission.clutch_max_torque_nm * engagement;
+    let slip_rpm = state.engine_rpm - gearbox_rpm;
+
+    // The automatic clutch keeps slipping until the car is through its launch
+    let lock_rpm = if input.clutch.is_some() { config.idle_rpm } else { launch_rpm };
+    if engagement >= 1.0
+        && gearbox_rpm >= lock_rpm
+        && slip_rpm.abs() < CLUTCH_SLIP_BAND_RPM
+        && engine_torque.abs() <= capacity
+    {
+        // Locked: the engine turns with the gearbox
+        state.engine_rpm = gearbox_rpm.min(config.max_engine_rpm);
+        return engine_torque;
+    }
+
+    let mut clutch_torque = capacity * (slip_rpm / CLUTCH_SLIP_BAND_RPM).clamp(-1.0, 1.0);
+    let launch_limit = launch_clutch_torque(state, config);
+    let launching = input.clutch.is_none() && clutch_torque > launch_limit;
+    if launching {
+        clutch_torque = launch_limit;
+    }
+
+    let idle_torque = config.engine.idle_control_gain * (config.idle_rpm - state.engine_rpm).max(0.0);
+    let engine_accel = (engine_torque + idle_torque - clutch_torque) / config.engine.inertia_kg_m2.max(0.01);
+    state.engine_rpm = (state.engine_rpm + engine_accel * RPM_PER_RAD_S * dt).clamp(0.0, config.max_engine_rpm);
+    if launching {
+        // Launch control holds the revs while the clutch slips
+        state.engine_rpm = state.engine_rpm.min(launch_rpm);
+    }
+
+    if state.engine_rpm < config.idle_rpm * STALL_RPM_FRACTION && engagement >= CLUTCH_RESTART_ENGAGEMENT {
+        state.engine_stalled = true;
+        state.engine_rpm = 0.0;
+        return 0.0;
+    }
+
+    clutch_torque
+}
+
+/// Gear the automatic gearbox picks: up near the redline, down once the next
+/// gear down would still leave headroom, and first from neutral or reverse
+fn automatic_gear(state: &CarState, config: &CarConfig) -> i8 {
+    let forward_gears = forward_gears(config);
+    if state.gear <= 0 {
+        return forward_gears.min(1);
+    }
+
+    let rev_band = config.redline_rpm - config.idle_rpm;
+    if state.gear < forward_gears
+        && gearbox_rpm(state, config, state.gear) > config.idle_rpm + rev_band * AUTO_UPSHIFT_FRACTION
+    {
+        return state.gear + 1;
+    }
+    if state.gear > 1
+        && gearbox_rpm(state, config, state.gear - 1) < config.idle_rpm + rev_band * AUTO_DOWNSHIFT_FRACTION
+    {
+        return state.gear - 1;
+    }
+    state.gear
+}
+
+/// Whether `gear` can go in now: it must exist, changing between forward and
+/// reverse needs the car nearly stopped, and a downshift must not over-rev the engine
+fn can_shift(state: &CarState, config: &CarConfig, gear: i8) -> bool {
+    if gear < -1 || gear > forward_gears(config) {
+        return false;
+    }
+    let changes_direction = gear != 0 && (gear < 0) != (state.gear < 0);
+    if changes_direction && state.speed_mps > REVERSE_ENGAGE_MAX_SPEED {
+        return false;
+    }
+    gearbox_rpm(state, config, gear) <= config.max_engine_rpm
+}
+
+fn forward_gears(config: &CarConfig) -> i8 {
+    config.gear_ratios.len().saturating_sub(1) as i8
+}
+
+/// Ratio of `gear`, negative for reverse and zero in neutral
+fn gear_ratio(config: &CarConfig, gear: i8) -> f32 {
+    match gear {
+        0 => 0.0,
+        reverse if reverse < 0 => config.gear_ratios.first().copied().unwrap_or(0.0),
+        forward => config.gear_ratios.get(forward as usize).copied().unwrap_or(0.0),
+    }
+}
+
+/// Engine speed that matches the car's road speed in `gear` (zero in neutral)
+fn gearbox_rpm(state: &CarState, config: &CarConfig, gear: i8) -> f32 {
+    let wheel_rpm = if state.speed_mps > MIN_SPEED_THRESHOLD {
+        (state.speed_mps / (2.0 * PI * config.wheel_radius_m)) * 60.0
     } else {
-        // Legacy simple torque curve (peak at ~60% of redline)
-        let rpm_normalized = (engine_rpm - config.idle_rpm) / (config.redline_rpm - config.idle_rpm);
-        let torque_factor = 1.0 - (rpm_normalized - 0.6).powi(2);
-        config.max_engine_torque_nm * torque_factor.clamp(0.3, 1.0)
+        0.0
     };
+    wheel_rpm * gear_ratio(config, gear).abs() * config.final_drive_ratio
+}
+
+/// Clutch torque that keeps the harder-driven wheels at their optimal slip,
+/// and never so little that a standing car does not pull away
+fn launch_clutch_torque(state: &CarState, config: &CarConfig) -> f32 {
+    let (front, rear) = calculate_drive_torques(1.0, config, state.gear);
+    let wheel_torque_per_nm = front.abs().max(rear.abs()) / 2.0;
+    let wheel_torque = config.tire_config.optimal_slip_ratio * WHEEL_SPIN_INERTIA * state.speed_mps.max(MIN_SPEED_THRESHOLD);
+    wheel_torque.max(STANDSTILL_DRIVE_TORQUE * 1.5) / wheel_torque_per_nm.max(1e-3)
+}
+
+/// Net engine torque at `engine_rpm`: the throttle's share of the torque curve,
+/// less engine braking off the throttle and internal friction
+fn calculate_engine_output(config: &CarConfig, engine_rpm: f32, throttle: f32, boost: f32) -> f32 {
+    let mut torque_at_rpm = interpolate_torque_curve(&config.engine.torque_curve, engine_rpm);
+    if let Some(turbo) = &config.engine.turbo {
+        // The curve is the full-boost torque; the boosted share builds with the turbo
+        torque_at_rpm *= 1.0 - turbo.boost_torque_share.clamp(0.0, 1.0) * (1.0 - boost.clamp(0.0, 1.0));
+    }
 
-    // Rev limiter torque cut
+    // Rev limiter fuel cut, fading in over the last few rpm
     let limiter_rpm = config.engine.rev_limiter_rpm.max(config.redline_rpm);
-    let limiter_cut = if engine_rpm >= limiter_rpm {
-        // Hard cut near limiter
-        0.2
-    } else {
-        1.0
-    };
+    let limiter_cut = ((limiter_rpm - engine_rpm) / REV_LIMITER_CUT_RPM).clamp(0.0, 1.0);
 
     // Engine braking & friction
     let rpm_frac = ((engine_rpm - config.idle_rpm) / (config.redline_rpm - config.idle_rpm).max(1.0)).clamp(0.0, 1.0);
-    let engine_brake = if input.throttle < 0.01 {
+    let engine_brake = if throttle < 0.01 {
         config.engine.engine_brake_torque_nm * rpm_frac
     } else {
         0.0
     };
 
     // Net torque produced by engine (positive = drive, negative = braking)
-    let mut engine_torque = (input.throttle * torque_at_rpm * limiter_cut) - engine_brake;
+    let mut engine_torque = (throttle * torque_at_rpm * limiter_cut) - engine_brake;
 
     // Always apply a small friction torque opposing rotation
     engine_torque -= config.engine.friction_torque_nm * rpm_frac;
-    
-    (engine_torque, engine_rpm)
+
+    engine_torque
+}
+
+/// Spools the turbo towards the boost the throttle asks for. Boost only builds
+/// above the turbo's threshold rpm, rising over its spool time, and bleeds off
+/// quickly through the blow-off valve when the throttle closes.
+fn update_turbo(state: &mut CarState, config: &CarConfig, throttle: f32, dt: f32) {
+    let Some(turbo) = &config.engine.turbo else {
+        state.turbo_boost = 0.0;
+        return;
+    };
+    let target = if state.engine_rpm >= turbo.boost_threshold_rpm { throttle.clamp(0.0, 1.0) } else { 0.0 };
+    let time_constant = if target > state.turbo_boost {
+        turbo.spool_time_s
+    } else {
+        turbo.spool_time_s * TURBO_BLOW_OFF_FRACTION
+    };
+    let blend = (dt / time_constant.max(dt)).clamp(0.0, 1.0);
+    state.turbo_boost += (target - state.turbo_boost) * blend;
 }
 
 fn interpolate_torque_curve(curve: &[TorqueCurvePoint], rpm: f32) -> f32 {
@@ -445,17 +1033,7 @@ fn interpolate_torque_curve(curve: &[TorqueCurvePoint], rpm: f32) -> f32 {
 
 /// Calculate drive torques for front and rear axles
 fn calculate_drive_torques(engine_torque: f32, config: &CarConfig, gear: i8) -> (f32, f32) {
-    if gear == 0 {
-        return (0.0, 0.0);  // Neutral
-    }
-    
-    let gear_ratio = if gear > 0 && (gear as usize) < config.gear_ratios.len() {
-        config.gear_ratios[gear as usize]
-    } else {
-        config.gear_ratios[0]  // Reverse
-    };
-    
-    let total_ratio = gear_ratio * config.final_drive_ratio;
+    let total_ratio = gear_ratio(config, gear) * config.final_drive_ratio;
     let wheel_torque = engine_torque * total_ratio * config.transmission.efficiency.clamp(0.0, 1.0);
     
     match config.drivetrain {
@@ -465,6 +1043,118 @@ fn calculate_drive_torques(engine_torque: f32, config: &CarConfig, gear: i8) ->
     }
 }
 
+/// Four-corner suspension: springs, dampers, anti-roll bars and bump stops
+/// carrying a sprung body with heave, pitch and roll.
+///
+/// The inertial weight transfer and aero loads act on the body; each corner's
+/// wheel load is its static load plus the spring, damper, anti-roll bar and
+/// bump stop forces at that corner. Returns the loads ordered FL, FR, RL, RR
+/// and updates the body motion and suspension travel on `state`.
+fn update_suspension(
+    state: &mut CarState,
+    config: &CarConfig,
+    (static_front, static_rear): (f32, f32),
+    (downforce_front, downforce_rear): (f32, f32),
+    (transfer_long, transfer_lat_front, transfer_lat_rear): (f32, f32, f32),
+    dt: f32,
+) -> [f32; 4] {
+    let susp = &config.suspension;
+    let body = state.chassis;
+
+    // Corner positions from the CoG: x forward, y right
+    let front_x = config.wheelbase_m * (1.0 - config.weight_distribution_front);
+    let rear_x = -config.wheelbase_m * config.weight_distribution_front;
+    let half_front = config.track_width_front_m / 2.0;
+    let half_rear = config.track_width_rear_m / 2.0;
+    let corners = [(front_x, -half_front), (front_x, half_front), (rear_x, -half_rear), (rear_x, half_rear)];
+
+    let static_loads = [static_front / 2.0, static_front / 2.0, static_rear / 2.0, static_rear / 2.0];
+    let spring_rates = [
+        susp.spring_rate_front_n_per_m,
+        susp.spring_rate_front_n_per_m,
+        susp.spring_rate_rear_n_per_m,
+        susp.spring_rate_rear_n_per_m,
+    ];
+    let ride_heights = [susp.ride_height_front_m, susp.ride_height_front_m, susp.ride_height_rear_m, susp.ride_height_rear_m];
+
+    // Compression and compression rate of each corner relative to static
+    let deflection: [f32; 4] = std::array::from_fn(|i| {
+        let (x, y) = corners[i];
+        body.heave_m + body.pitch_rad * x + body.roll_rad * y
+    });
+    let deflection_rate: [f32; 4] = std::array::from_fn(|i| {
+        let (x, y) = corners[i];
+        body.heave_rate_mps + body.pitch_rate_rps * x + body.roll_rate_rps * y
+    });
+
+    let mut loads = [0.0; 4];
+    for i in 0..4 {
+        let front = i < 2;
+        let spring_rate = spring_rates[i].max(1.0);
+        let damping = match (front, deflection_rate[i] > 0.0) {
+            (true, true) => susp.damper_compression_front,
+            (true, false) => susp.damper_rebound_front,
+            (false, true) => susp.damper_compression_rear,
+            (false, false) => susp.damper_rebound_rear,
+        };
+
+        let static_sag = static_loads[i] / spring_rate;
+        let travel = static_sag + deflection[i];
+        let stop = susp.max_travel_m.min(static_sag + ride_heights[i]);
+        let bump_stop = (travel - stop).max(0.0) * spring_rate * BUMP_STOP_RATE_FACTOR;
+
+        loads[i] = static_loads[i] + spring_rate * deflection[i] + damping * deflection_rate[i] + bump_stop;
+    }
+
+    // Anti-roll bars resist the difference in travel across each axle
+    let arb_front = susp.anti_roll_bar_front * (deflection[1] - deflection[0]);
+    let arb_rear = susp.anti_roll_bar_rear * (deflection[3] - deflection[2]);
+    loads[0] -= arb_front;
+    loads[1] += arb_front;
+    loads[2] -= arb_rear;
+    loads[3] += arb_rear;
+
+    // A tire can push but not pull; a lifted wheel carries no load
+    for load in loads.iter_mut() {
+        *load = load.max(0.0);
+    }
+
+    // Net suspension forces on the body, relative to static equilibrium
+    let mut heave_force = downforce_front + downforce_rear;
+    let mut pitch_moment = -transfer_long * config.wheelbase_m + downforce_front * front_x + downforce_rear * rear_x;
+    let mut roll_moment = -(transfer_lat_front * config.track_width_front_m + transfer_lat_rear * config.track_width_rear_m);
+    for i in 0..4 {
+        let (x, y) = corners[i];
+        let reaction = loads[i] - static_loads[i];
+        heave_force -= reaction;
+        pitch_moment -= reaction * x;
+        roll_moment -= reaction * y;
+    }
+
+    // Body inertia (simplified as a box twice the CoG height tall)
+    let body_height = 2.0 * config.cog_height_m;
+    let pitch_inertia = config.mass_kg * (config.length_m.powi(2) + body_height.powi(2)) / 12.0;
+    let roll_inertia = config.mass_kg * (config.width_m.powi(2) + body_height.powi(2)) / 12.0;
+
+    let chassis = &mut state.chassis;
+    chassis.heave_rate_mps += heave_force / config.mass_kg * dt;
+    chassis.pitch_rate_rps += pitch_moment / pitch_inertia * dt;
+    chassis.roll_rate_rps += roll_moment / roll_inertia * dt;
+    chassis.heave_m += chassis.heave_rate_mps * dt;
+    chassis.pitch_rad += chassis.pitch_rate_rps * dt;
+    chassis.roll_rad += chassis.roll_rate_rps * dt;
+
+    let travel: [f32; 4] = std::array::from_fn(|i| {
+        (static_loads[i] / spring_rates[i].max(1.0) + deflection[i]).clamp(0.0, susp.max_travel_m)
+    });
+    state.suspension.front_left_travel_m = travel[0];
+    state.suspension.front_right_travel_m = travel[1];
+    state.suspension.rear_left_travel_m = travel[2];
+    state.suspension.rear_right_travel_m = travel[3];
+
+    loads
+}
+
 /// Calculate weight transfer from acceleration
 fn calculate_weight_transfer(
     config: &CarConfig,
@@ -476,9 +1166,16 @@ fn calculate_weight_transfer(
     let weight_transfer_long = (config.mass_kg * longitudinal_accel * config.cog_height_m) / config.wheelbase_m;
     
     // Lateral weight transfer (different for front and rear due to roll stiffness)
+    // Cars without anti-roll bars split the transfer evenly
     let total_roll_stiffness = config.suspension.anti_roll_bar_front + config.suspension.anti_roll_bar_rear;
-    let front_roll_ratio = config.suspension.anti_roll_bar_front / total_roll_stiffness.max(1.0);
-    let rear_roll_ratio = config.suspension.anti_roll_bar_rear / total_roll_stiffness.max(1.0);
+    let (front_roll_ratio, rear_roll_ratio) = if total_roll_stiffness > 0.0 {
+        (
+            config.suspension.anti_roll_bar_front / total_roll_stiffness,
+            config.suspension.anti_roll_bar_rear / total_roll_stiffness,
+        )
+    } else {
+        (0.5, 0.5)
+    };
     
     let lateral_transfer_front = (config.mass_kg * lateral_accel * config.cog_height_m) / config.track_width_front_m * front_roll_ratio;
     let lateral_transfer_rear = (config.mass_kg * lateral_accel * config.cog_height_m) / config.track_width_rear_m * rear_roll_ratio;
@@ -512,6 +1209,7 @@ fn calculate_ackermann_steering(steering_angle: f32, wheelbase: f32, track_width
 }
 
 /// Calculate slip ratio and slip angle for a wheel
+#[allow(clippy::too_many_arguments)]
 fn calculate_wheel_slip(
     vehicle_speed: f32,
     yaw_rate: f32,
@@ -531,8 +1229,8 @@ fn calculate_wheel_slip(
 
     // Slip ratio (longitudinal)
     // The driven wheel speed is increased by engine torque (through the drivetrain)
-    // and decreased by brake force. The factor 100.0 is a simplified wheel inertia term.
-    let driven_wheel_speed = wheel_speed + (drive_torque - brake_force * wheel_radius) / (100.0 * wheel_radius);
+    // and decreased by brake force, through a simplified wheel inertia.
+    let driven_wheel_speed = wheel_speed + (drive_torque - brake_force * wheel_radius) / (WHEEL_SPIN_INERTIA * wheel_radius);
 
     let slip_ratio = if vehicle_speed > MIN_SPEED_THRESHOLD {
         // Normal driving: slip = (wheel_speed - ground_speed) / ground_speed
@@ -543,9 +1241,9 @@ fn calculate_wheel_slip(
         // Beyond that, force drops off (simulating wheel spin)
         // So we target optimal slip when launching for maximum traction
         let optimal_slip = 0.12; // Near optimal slip for peak traction
-        if drive_torque > 10.0 {
+        if drive_torque > STANDSTILL_DRIVE_TORQUE {
             optimal_slip  // Apply throttle = optimal slip for max force
-        } else if brake_force > 10.0 {
+        } else if brake_force > STANDSTILL_DRIVE_TORQUE {
             -optimal_slip  // Braking = negative slip
         } else {
             0.0  // No input = no slip
@@ -562,7 +1260,11 @@ fn calculate_wheel_slip(
     (slip_ratio.clamp(-1.0, 1.0), slip_angle.clamp(-0.5, 0.5))
 }
 
-/// Calculate tire forces using simplified Pacejka magic formula
+/// Calculate tire forces using the Pacejka magic formula.
+///
+/// Peak force scales with load less than linearly (load sensitivity), and
+/// combined slip is limited by a friction ellipse so that braking or
+/// accelerating in a corner trades off lateral grip.
 fn calculate_tire_forces(
     wheel_load: f32,
     slip_ratio: f32,
@@ -573,36 +1275,58 @@ fn calculate_tire_forces(
     if wheel_load < 1.0 {
         return (0.0, 0.0);
     }
-    
-    // Magic formula parameters (simplified)
-    let b_long = 10.0;  // Stiffness factor
-    let c_long = 1.9;   // Shape factor
-    let d_long = grip_coefficient * wheel_load;  // Peak force
-    
-    let b_lat = 8.0;
-    let c_lat = 1.3;
-    let d_lat = grip_coefficient * wheel_load;
-    
-    // Longitudinal force (Fx)
-    let slip_ratio_adjusted = slip_ratio / tire_config.optimal_slip_ratio;
-    let fx = d_long * (c_long * (b_long * slip_ratio_adjusted).atan()).sin();
-    
-    // Lateral force (Fy)
-    let slip_angle_adjusted = slip_angle / tire_config.optimal_slip_angle_rad;
-    let fy = d_lat * (c_lat * (b_lat * slip_angle_adjusted).atan()).sin();
-    
-    // Combined slip (friction circle)
-    let combined_force = (fx.powi(2) + fy.powi(2)).sqrt();
-    let max_force = d_long.max(d_lat);
-    
-    if combined_force > max_force {
-        let scale = max_force / combined_force;
-        (fx * scale, fy * scale)
+
+    // Load sensitivity: heavily loaded tires make proportionally less grip
+    let nominal_load = tire_config.nominal_load_n.max(1.0);
+    let load_delta = (wheel_load - nominal_load) / nominal_load;
+    let load_factor = (1.0 - tire_config.load_sensitivity * load_delta).clamp(0.2, 1.5);
+    let peak_force = grip_coefficient * load_factor * wheel_load;
+
+    // Slip normalized so 1.0 is the peak of each curve
+    let norm_long = slip_ratio / tire_config.optimal_slip_ratio.max(1e-4);
+    let norm_lat = slip_angle / tire_config.optimal_slip_angle_rad.max(1e-4);
+
+    let fx = peak_force
+        * norm_long.signum()
+        * magic_formula(norm_long.abs(), tire_config.longitudinal_shape, tire_config.longitudinal_curvature);
+    let fy = peak_force
+        * norm_lat.signum()
+        * magic_formula(norm_lat.abs(), tire_config.lateral_shape, tire_config.lateral_curvature);
+
+    // Combined slip: both forces share the same friction ellipse
+    let usage = (fx * fx + fy * fy).sqrt() / peak_force;
+    if usage > 1.0 {
+        (fx / usage, fy / usage)
     } else {
         (fx, fy)
     }
 }
 
+/// Magic formula `sin(C·atan(Bx − E(Bx − atan(Bx))))` for slip normalized to its
+/// peak, with B chosen so the curve peaks at exactly 1.0
+fn magic_formula(normalized_slip: f32, shape: f32, curvature: f32) -> f32 {
+    let stiffness = magic_formula_stiffness(shape, curvature);
+    let bx = stiffness * normalized_slip;
+    (shape * (bx - curvature * (bx - bx.atan())).atan()).sin()
+}
+
+/// Stiffness factor B that puts the magic formula peak at a normalized slip of 1.0.
+///
+/// The peak is where `Bx − E(Bx − atan(Bx)) = tan(π / 2C)`; solved for Bx by Newton's method.
+fn magic_formula_stiffness(shape: f32, curvature: f32) -> f32 {
+    let shape = shape.clamp(1.01, 1.99);
+    let curvature = curvature.clamp(-2.0, 0.95);
+    let target = (std::f32::consts::PI / (2.0 * shape)).tan();
+
+    let mut phi = target;
+    for _ in 0..8 {
+        let residual = phi - curvature * (phi - phi.atan()) - target;
+        let slope = 1.0 - curvature * (1.0 - 1.0 / (1.0 + phi * phi));
+        phi -= residual / slope;
+    }
+    phi.max(1e-3)
+}
+
 /// Get track context at the car's current position
 fn get_track_context(state: &CarState, track: &TrackConfig) -> TrackContext {
     if track.centerline.is_empty() {
@@ -639,15 +1363,51 @@ fn get_track_context(state: &CarState, track: &TrackConfig) -> TrackContext {
     let (surface_type, grip_modifier) = if is_on_track {
         (nearest.surface_type, nearest.grip_modifier)
     } else {
-        // Off track
-        (SurfaceType::Grass, track.track_surface.off_track_grip)
+        // Off track the local grip adjustment no longer applies
+        (nearest.runoff_surface, 1.0)
     };
-    
+    let grip_modifier = grip_modifier * surface_properties(&track.track_surface, surface_type).grip;
+
+    // Blend the surface along the segment the car is on
+    let last = track.centerline.len() - 1;
+    let prev = &track.centerline[nearest_idx.saturating_sub(1)];
+    let next = &track.centerline[(nearest_idx + 1).min(last)];
+    let along = dx * nearest.heading_rad.cos() + dy * nearest.heading_rad.sin();
+    let (from, to, fraction) = if along >= 0.0 {
+        (nearest, next, along / (next.x - nearest.x).hypot(next.y - nearest.y).max(0.1))
+    } else {
+        (prev, nearest, 1.0 + along / (nearest.x - prev.x).hypot(nearest.y - prev.y).max(0.1))
+    };
+    let fraction = fraction.clamp(0.0, 1.0);
+    let lerp = |a: f32, b: f32| a + (b - a) * fraction;
+
+    // Banking is given towards the inside of the turn, whichever way it goes
+    let turn = normalize_angle(next.heading_rad - prev.heading_rad);
+    let banking = lerp(from.banking_rad, to.banking_rad);
+    let banking_rise = if turn > 0.0 { -banking } else if turn < 0.0 { banking } else { 0.0 };
+    // A crowned surface falls away either side of the centerline
+    let camber = lerp(from.camber_rad, to.camber_rad);
+    let camber_rise = if lateral_offset > 0.0 { camber } else if lateral_offset < 0.0 { -camber } else { 0.0 };
+    // Curbs lift the side of the car nearer the track edge
+    let curb_rise = match (surface_type, lateral_offset > 0.0) {
+        (SurfaceType::Curb, true) => -CURB_TILT_RAD,
+        (SurfaceType::Curb, false) => CURB_TILT_RAD,
+        _ => 0.0,
+    };
+
+    let span = (next.x - prev.x).hypot(next.y - prev.y);
+    let vertical_curvature = if span > 0.1 {
+        (next.slope_rad - prev.slope_rad) / span
+    } else {
+        0.0
+    };
+
     TrackContext {
         nearest_point: nearest_idx,
-        elevation: nearest.z,
-        banking_rad: nearest.banking_rad,
-        slope_rad: nearest.slope_rad,
+        elevation: lerp(from.z, to.z),
+        cross_slope_rad: banking_rise + camber_rise + curb_rise,
+        slope_rad: lerp(from.slope_rad, to.slope_rad),
+        vertical_curvature,
         heading_rad: nearest.heading_rad,
         lateral_offset,
         is_on_track,
@@ -659,6 +1419,7 @@ fn get_track_context(state: &CarState, track: &TrackConfig) -> TrackContext {
 }
 
 /// Update telemetry data for 3D physics
+#[allow(clippy::too_many_arguments)]
 fn update_telemetry_3d(
     state: &mut CarState,
     config: &CarConfig,
@@ -670,69 +1431,36 @@ fn update_telemetry_3d(
     rr_slip: (f32, f32),
     dt: f32,
 ) {
-    // Calculate tire temperatures based on slip and load
-    let calculate_tire_temp = |slip_ratio: f32, slip_angle: f32, load: f32| -> f32 {
-        let base_temp = 80.0;
-        let slip_heat = (slip_ratio.abs() + slip_angle.abs()) * 100.0;
-        let load_heat = load / 10000.0 * 10.0;
-        let speed_cooling = state.speed_mps * 0.1;
-        base_temp + slip_heat + load_heat - speed_cooling
-    };
-    
+    // Tire pressures rise from their cold setting with the temperatures tracked by update_warmup
+    let tires = &config.tire_config;
     // Front left tire
-    state.tires.front_left.temperature_c = calculate_tire_temp(fl_slip.0, fl_slip.1, state.weight_front_left_n);
-    state.tires.front_left.pressure_kpa = 200.0 + state.tires.front_left.temperature_c * 0.5;
+    state.tires.front_left.pressure_kpa = hot_tire_pressure(tires.cold_pressure_front_kpa, state.tires.front_left.temperature_c);
     state.tires.front_left.slip_ratio = fl_slip.0;
     state.tires.front_left.slip_angle_rad = fl_slip.1;
     state.tires.front_left.wear_percent = (state.tires.front_left.wear_percent + 
         fl_slip.0.abs() * 0.0001 * config.tire_config.wear_rate * dt).min(100.0);
     
     // Front right tire
-    state.tires.front_right.temperature_c = calculate_tire_temp(fr_slip.0, fr_slip.1, state.weight_front_right_n);
-    state.tires.front_right.pressure_kpa = 200.0 + state.tires.front_right.temperature_c * 0.5;
+    state.tires.front_right.pressure_kpa = hot_tire_pressure(tires.cold_pressure_front_kpa, state.tires.front_right.temperature_c);
     state.tires.front_right.slip_ratio = fr_slip.0;
     state.tires.front_right.slip_angle_rad = fr_slip.1;
     state.tires.front_right.wear_percent = (state.tires.front_right.wear_percent + 
         fr_slip.0.abs() * 0.0001 * config.tire_config.wear_rate * dt).min(100.0);
     
     // Rear left tire
-    state.tires.rear_left.temperature_c = calculate_tire_temp(rl_slip.0, rl_slip.1, state.weight_rear_left_n);
-    state.tires.rear_left.pressure_kpa = 200.0 + state.tires.rear_left.temperature_c * 0.5;
+    state.tires.rear_left.pressure_kpa = hot_tire_pressure(tires.cold_pressure_rear_kpa, state.tires.rear_left.temperature_c);
     state.tires.rear_left.slip_ratio = rl_slip.0;
     state.tires.rear_left.slip_angle_rad = rl_slip.1;
     state.tires.rear_left.wear_percent = (state.tires.rear_left.wear_percent + 
         rl_slip.0.abs() * 0.0001 * config.tire_config.wear_rate * dt).min(100.0);
     
     // Rear right tire
-    state.tires.rear_right.temperature_c = calculate_tire_temp(rr_slip.0, rr_slip.1, state.weight_rear_right_n);
-    state.tires.rear_right.pressure_kpa = 200.0 + state.tires.rear_right.temperature_c * 0.5;
+    state.tires.rear_right.pressure_kpa = hot_tire_pressure(tires.cold_pressure_rear_kpa, state.tires.rear_right.temperature_c);
     state.tires.rear_right.slip_ratio = rr_slip.0;
     state.tires.rear_right.slip_angle_rad = rr_slip.1;
     state.tires.rear_right.wear_percent = (state.tires.rear_right.wear_percent + 
         rr_slip.0.abs() * 0.0001 * config.tire_config.wear_rate * dt).min(100.0);
     
-    // Suspension travel (based on weight and spring rates)
-    let calculate_suspension_travel = |weight: f32, spring_rate: f32| -> f32 {
-        (weight / spring_rate).clamp(0.0, config.suspension.max_travel_m)
-    };
-    
-    state.suspension.front_left_travel_m = calculate_suspension_travel(
-        state.weight_front_left_n,
-        config.suspension.spring_rate_front_n_per_m,
-    );
-    state.suspension.front_right_travel_m = calculate_suspension_travel(
-        state.weight_front_right_n,
-        config.suspension.spring_rate_front_n_per_m,
-    );
-    state.suspension.rear_left_travel_m = calculate_suspension_travel(
-        state.weight_rear_left_n,
-        config.suspension.spring_rate_rear_n_per_m,
-    );
-    state.suspension.rear_right_travel_m = calculate_suspension_travel(
-        state.weight_rear_right_n,
-        config.suspension.spring_rate_rear_n_per_m,
-    );
-    
     // Engine temperature (increases with load, decreases with airflow)
     let engine_load = input.throttle * (state.engine_rpm / config.redline_rpm);
     let cooling = state.speed_mps * 0.2;
@@ -750,136 +1478,768 @@ fn update_telemetry_3d(
     state.water_temp_c = state.water_temp_c + (state.engine_temp_c - state.water_temp_c) * 0.02;
 }
 
-/// Update fuel consumption
-fn update_fuel_consumption(state: &mut CarState, config: &CarConfig, input: &PlayerInputData, dt: f32) {
-    // Base consumption + load-based consumption
-    let rpm_factor = state.engine_rpm / config.max_engine_rpm;
-    let throttle_factor = input.throttle;
-    
-    state.fuel_consumption_lps = config.fuel.idle_consumption_lps
-        + (throttle_factor * rpm_factor * config.fuel.load_consumption_scale);
-    state.fuel_liters = (state.fuel_liters - state.fuel_consumption_lps * dt).max(0.0);
+/// Pressure of a tire set to `cold_pressure_kpa` in the garage once it reaches `temperature_c`
+fn hot_tire_pressure(cold_pressure_kpa: f32, temperature_c: f32) -> f32 {
+    cold_pressure_kpa + (temperature_c - TIRE_PRESSURE_REFERENCE_TEMP_C) * TIRE_PRESSURE_RISE_KPA_PER_C
 }
 
-/// Check and resolve 3D AABB collisions between cars
-pub fn check_aabb_collisions_3d(
-    states: &mut [CarState],
-    configs: &HashMap<CarConfigId, CarConfig>,
-) {
-    // Reset collision flags
-    for state in states.iter_mut() {
+/// Grip multiplier from how close a cold pressure setting brings the tire to its
+/// optimal pressure once it is at working temperature
+pub fn pressure_grip_factor(tires: &TireConfig, cold_pressure_kpa: f32) -> f32 {
+    let working_pressure = hot_tire_pressure(cold_pressure_kpa, tires.optimal_temperature_c);
+    (1.0 - (working_pressure - tires.optimal_pressure_kpa).abs() * tires.pressure_grip_falloff).clamp(0.5, 1.0)
+}
+
+/// Reset tires and brakes to ambient temperature at the start of a run
+pub fn apply_cold_start(state: &mut CarState, settings: &WarmupSettings) {
+    if !settings.cold_start() {
+        return;
+    }
+
+    let pressures = [
+        state.tires.front_left.pressure_kpa,
+        state.tires.front_right.pressure_kpa,
+        state.tires.rear_left.pressure_kpa,
+        state.tires.rear_right.pressure_kpa,
+    ];
+    state.tires = TireTelemetry::uniform(settings.ambient_temp_c);
+    state.tires.front_left.pressure_kpa = pressures[0];
+    state.tires.front_right.pressure_kpa = pressures[1];
+    state.tires.rear_left.pressure_kpa = pressures[2];
+    state.tires.rear_right.pressure_kpa = pressures[3];
+    state.brakes = BrakeTelemetry::uniform(settings.ambient_temp_c);
+    state.tire_grip_factor = settings.min_tire_grip_factor;
+    state.brake_efficiency = settings.cold_brake_efficiency;
+}
+
+/// Grip, drag and damage of a surface; grass, curb and asphalt grip come from the track's own settings
+pub fn surface_properties(track_surface: &TrackSurface, surface: SurfaceType) -> SurfaceProperties {
+    let (grip, drag, damage_rate) = match surface {
+        SurfaceType::Asphalt | SurfaceType::Concrete => (track_surface.base_grip, 0.0, 0.0),
+        SurfaceType::Curb => (track_surface.curb_grip, 0.0, 0.0),
+        SurfaceType::Wet => (track_surface.base_grip * STANDING_WATER_GRIP, 0.0, 0.0),
+        SurfaceType::Grass => (track_surface.off_track_grip, track_surface.off_track_speed_penalty, 0.0),
+        SurfaceType::Gravel => (GRAVEL_GRIP, GRAVEL_DRAG, GRAVEL_DAMAGE_RATE),
+        SurfaceType::Sand => (SAND_GRIP, SAND_DRAG, SAND_DAMAGE_RATE),
+    };
+    SurfaceProperties { grip, drag, damage_rate }
+}
+
+/// Grip multiplier for standing water on a surface (wetness 0 = dry, 1 = soaked)
+pub fn wet_grip_factor(surface: SurfaceType, wetness: f32) -> f32 {
+    let loss = match surface {
+        SurfaceType::Grass | SurfaceType::Gravel | SurfaceType::Sand => WET_OFF_TRACK_GRIP_LOSS,
+        SurfaceType::Asphalt | SurfaceType::Concrete | SurfaceType::Curb | SurfaceType::Wet => WET_TRACK_GRIP_LOSS,
+    };
+    1.0 - loss * wetness.clamp(0.0, 1.0)
+}
+
+/// Advance tire and brake temperatures and derive the grip and braking multipliers
+///
+/// Must run after `update_car_3d` so it sees this tick's slip, load and brake input.
+/// The multipliers take effect on the next physics tick.
+pub fn update_warmup(state: &mut CarState, config: &CarConfig, settings: &WarmupSettings, dt: f32) {
+    let ambient = settings.ambient_temp_c;
+    let optimal = config.tire_config.optimal_temperature_c;
+    let static_load = config.mass_kg * GRAVITY / 4.0;
+    let speed_work = (state.speed_mps / TIRE_WORKING_SPEED).min(1.5);
+
+    // Tires heat towards a target set by how hard they are worked and cool towards ambient
+    let update_tire = |tire: &mut TireData, load: f32| {
+        let slip_work = (tire.slip_ratio.abs() + tire.slip_angle_rad.abs()) * 5.0;
+        let work = speed_work * (load / static_load) + slip_work;
+        let target = (ambient + (optimal - ambient) * work).min(optimal + 60.0);
+        let time_constant = if target > tire.temperature_c {
+            settings.tire_warmup_time_s
+        } else {
+            settings.tire_warmup_time_s * 2.0
+        };
+        tire.temperature_c += (target - tire.temperature_c) * (dt / time_constant).min(1.0);
+    };
+
+    update_tire(&mut state.tires.front_left, state.weight_front_left_n);
+    update_tire(&mut state.tires.front_right, state.weight_front_right_n);
+    update_tire(&mut state.tires.rear_left, state.weight_rear_left_n);
+    update_tire(&mut state.tires.rear_right, state.weight_rear_right_n);
+
+    // Brakes absorb the kinetic energy they dissipate and shed heat faster with airflow
+    let brake_power = state.brake_input * config.max_brake_force_n * state.brake_efficiency * state.speed_mps;
+    let front_power = brake_power * config.brake_bias_front / 2.0;
+    let rear_power = brake_power * (1.0 - config.brake_bias_front) / 2.0;
+    let cooling_rate = 0.02 + 0.003 * state.speed_mps;
+
+    let update_brake = |temp: &mut f32, power: f32| {
+        *temp += (power / BRAKE_HEAT_CAPACITY - (*temp - ambient) * cooling_rate) * dt;
+        *temp = temp.max(ambient);
+    };
+
+    update_brake(&mut state.brakes.front_left_temp_c, front_power);
+    update_brake(&mut state.brakes.front_right_temp_c, front_power);
+    update_brake(&mut state.brakes.rear_left_temp_c, rear_power);
+    update_brake(&mut state.brakes.rear_right_temp_c, rear_power);
+
+    if !settings.cold_start() {
+        state.tire_grip_factor = 1.0;
+        state.brake_efficiency = 1.0;
+        return;
+    }
+
+    let tire_grip = |tire: &TireData| {
+        let deviation = (tire.temperature_c - optimal).abs();
+        (1.0 - deviation * config.tire_config.temperature_grip_falloff).clamp(settings.min_tire_grip_factor, 1.0)
+    };
+    state.tire_grip_factor = (tire_grip(&state.tires.front_left)
+        + tire_grip(&state.tires.front_right)
+        + tire_grip(&state.tires.rear_left)
+        + tire_grip(&state.tires.rear_right))
+        / 4.0;
+
+    let brake_window = (settings.brake_operating_temp_c - ambient).max(1.0);
+    let brake_effect = |temp: f32| {
+        let warmth = ((temp - ambient) / brake_window).clamp(0.0, 1.0);
+        settings.cold_brake_efficiency + (1.0 - settings.cold_brake_efficiency) * warmth
+    };
+    state.brake_efficiency = (brake_effect(state.brakes.front_left_temp_c)
+        + brake_effect(state.brakes.front_right_temp_c)
+        + brake_effect(state.brakes.rear_left_temp_c)
+        + brake_effect(state.brakes.rear_right_temp_c))
+        / 4.0;
+}
+
+/// Update fuel consumption
+fn update_fuel_consumption(state: &mut CarState, config: &CarConfig, input: &PlayerInputData, dt: f32) {
+    // Base consumption + load-based consumption
+    let rpm_factor = state.engine_rpm / config.max_engine_rpm;
+    let throttle_factor = input.throttle;
+    
+    state.fuel_consumption_lps = config.fuel.idle_consumption_lps
+        + (throttle_factor * rpm_factor * config.fuel.load_consumption_scale);
+    state.fuel_liters = (state.fuel_liters - state.fuel_consumption_lps * dt).max(0.0);
+}
+
+/// Work out which cars are in another car's wake for the next tick.
+///
+/// The wake is a cone behind each car moving at speed; the tow is strongest
+/// close behind and on the lead car's centerline. A car takes the strongest
+/// tow on offer.
+pub fn update_slipstream(states: &mut [CarState], configs: &HashMap<CarConfigId, CarConfig>) {
+    for i in 0..states.len() {
+        let mut best: Option<(f32, PlayerId)> = None;
+
+        for j in 0..states.len() {
+            if i == j {
+                continue;
+            }
+            let Some(lead_config) = configs.get(&states[j].car_config_id) else {
+                continue;
+            };
+            let strength = slipstream_strength(&states[j], lead_config, &states[i]);
+            if strength > best.map_or(0.0, |(s, _)| s) {
+                best = Some((strength, states[j].player_id));
+            }
+        }
+
+        states[i].draft_factor = best.map_or(0.0, |(s, _)| s);
+        states[i].drafting_behind = best.map(|(_, id)| id);
+    }
+}
+
+/// Tow a following car gets from a lead car's wake (0.0 - 1.0)
+fn slipstream_strength(lead: &CarState, lead_config: &CarConfig, follower: &CarState) -> f32 {
+    if lead.speed_mps < SLIPSTREAM_MIN_SPEED {
+        return 0.0;
+    }
+
+    // Both cars must be heading roughly the same way
+    if (follower.yaw_rad - lead.yaw_rad).cos() < 0.8 {
+        return 0.0;
+    }
+
+    let (fwd_x, fwd_y) = (lead.yaw_rad.cos(), lead.yaw_rad.sin());
+    let dx = follower.pos_x - lead.pos_x;
+    let dy = follower.pos_y - lead.pos_y;
+    let behind = -(dx * fwd_x + dy * fwd_y);
+    let lateral = (dx * fwd_y - dy * fwd_x).abs();
+
+    if behind < lead_config.length_m * 0.5 || behind > SLIPSTREAM_RANGE_M {
+        return 0.0;
+    }
+
+    let half_width = lead_config.width_m * SLIPSTREAM_BASE_HALF_WIDTH + behind * SLIPSTREAM_SPREAD;
+    if lateral >= half_width {
+        return 0.0;
+    }
+
+    (1.0 - behind / SLIPSTREAM_RANGE_M) * (1.0 - lateral / half_width)
+}
+
+/// Lowest physics step rate the integrator stays stable at (Hz)
+const MIN_STABLE_STEP_HZ: u32 = 240;
+
+/// How often sessions tick and how many physics steps each tick is split into.
+///
+/// Sessions tick at the server's network rate. A slow tick is split into
+/// sub-steps short enough for stable integration; a fast one is stepped once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TickTiming {
+    pub tick_rate_hz: u16,
+    /// Physics steps per tick
+    pub substeps: u8,
+}
+
+impl TickTiming {
+    /// Timing for `tick_rate_hz`; `substeps` of 0 picks the fewest steps that
+    /// keep each one at or under the stable step
+    pub fn new(tick_rate_hz: u16, substeps: u8) -> Self {
+        let tick_rate_hz = tick_rate_hz.max(1);
+        let substeps = if substeps > 0 {
+            substeps
+        } else {
+            MIN_STABLE_STEP_HZ.div_ceil(tick_rate_hz as u32).min(u8::MAX as u32) as u8
+        };
+        Self { tick_rate_hz, substeps }
+    }
+
+    /// Time covered by one tick (s)
+    pub fn tick_dt(&self) -> f32 {
+        1.0 / self.tick_rate_hz as f32
+    }
+
+    /// Time covered by one physics step (s)
+    pub fn step_dt(&self) -> f32 {
+        self.tick_dt() / self.substeps as f32
+    }
+
+    /// Ticks in `seconds` of session time
+    pub fn ticks(&self, seconds: u32) -> u32 {
+        seconds * self.tick_rate_hz as u32
+    }
+
+    /// Milliseconds of session time in `ticks`
+    pub fn ticks_to_ms(&self, ticks: u32) -> u32 {
+        (ticks as u64 * 1000 / self.tick_rate_hz as u64) as u32
+    }
+}
+
+impl Default for TickTiming {
+    fn default() -> Self {
+        Self::new(240, 0)
+    }
+}
+
+/// Restitution and friction of contacts with other cars and walls
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CollisionSettings {
+    /// Fraction of closing speed returned as separation speed (0 = dead, 1 = elastic)
+    pub restitution: f32,
+    /// Coulomb friction between bodywork, limiting the sliding impulse
+    pub friction: f32,
+}
+
+impl Default for CollisionSettings {
+    fn default() -> Self {
+        Self {
+            restitution: 0.3,
+            friction: 0.4,
+        }
+    }
+}
+
+/// Penetration between two car footprints
+#[derive(Debug, Clone, Copy)]
+struct Contact {
+    /// Unit normal pointing from the first car to the second
+    normal: (f32, f32),
+    depth: f32,
+    /// World-space point the impulse acts through
+    point: (f32, f32),
+}
+
+/// One convex piece of a car's footprint: a polygon or segment grown by a radius
+struct CollisionPart {
+    /// World-space outline; two points for a capsule's spine
+    core: Vec<(f32, f32)>,
+    radius: f32,
+}
+
+impl CollisionPart {
+    /// Edges of the core, counting a segment once
+    fn edges(&self) -> impl Iterator<Item = ((f32, f32), (f32, f32))> + '_ {
+        let n = self.core.len();
+        let count = if n > 2 { n } else { n - 1 };
+        (0..count).map(move |i| (self.core[i], self.core[(i + 1) % n]))
+    }
+
+    /// Candidate separating axes: edge normals, plus a segment's own direction
+    fn axes(&self) -> Vec<(f32, f32)> {
+        let mut axes: Vec<(f32, f32)> = self
+            .edges()
+            .filter_map(|(p, q)| {
+                let (ex, ey) = (q.0 - p.0, q.1 - p.1);
+                let length = ex.hypot(ey);
+                (length > 1e-6).then(|| (-ey / length, ex / length))
+            })
+            .collect();
+        if self.core.len() == 2 {
+            if let Some(&(nx, ny)) = axes.first() {
+                axes.push((ny, -nx));
+            }
+        }
+        axes
+    }
+
+    /// Extent of the core projected onto `axis`
+    fn project(&self, axis: (f32, f32)) -> (f32, f32) {
+        self.core.iter().fold((f32::MAX, f32::MIN), |(min, max), p| {
+            let d = p.0 * axis.0 + p.1 * axis.1;
+            (min.min(d), max.max(d))
+        })
+    }
+
+    fn contains(&self, point: (f32, f32)) -> bool {
+        if self.core.len() < 3 {
+            return false;
+        }
+        let sides: Vec<f32> = self
+            .edges()
+            .map(|(p, q)| cross((q.0 - p.0, q.1 - p.1), (point.0 - p.0, point.1 - p.1)))
+            .collect();
+        sides.iter().all(|&s| s >= -1e-4) || sides.iter().all(|&s| s <= 1e-4)
+    }
+}
+
+/// Car footprint in the ground plane, built from its `CollisionShape`
+struct CarShape {
+    center: (f32, f32),
+    parts: Vec<CollisionPart>,
+    /// Distance from the center to the farthest point of any part
+    reach: f32,
+}
+
+impl CarShape {
+    fn new(state: &CarState, config: &CarConfig) -> Self {
+        let (sin, cos) = state.yaw_rad.sin_cos();
+        let to_world = |[x, y]: [f32; 2]| (state.pos_x + x * cos - y * sin, state.pos_y + x * sin + y * cos);
+        let polygon = |points: &[[f32; 2]]| CollisionPart {
+            core: points.iter().copied().map(to_world).collect(),
+            radius: 0.0,
+        };
+
+        let parts = match &config.collision_shape {
+            CollisionShape::Box => {
+                let (hl, hw) = (config.length_m / 2.0, config.width_m / 2.0);
+                vec![polygon(&[[hl, hw], [-hl, hw], [-hl, -hw], [hl, -hw]])]
+            }
+            CollisionShape::Hull(points) => vec![polygon(points)],
+            CollisionShape::Capsules(capsules) => capsules
+                .iter()
+                .map(|c| CollisionPart {
+                    core: vec![to_world(c.from), to_world(c.to)],
+                    radius: c.radius_m,
+                })
+                .collect(),
+        };
+
+        let center = (state.pos_x, state.pos_y);
+        let reach = parts
+            .iter()
+            .flat_map(|part| part.core.iter().map(|p| (p.0 - center.0).hypot(p.1 - center.1) + part.radius))
+            .fold(0.0, f32::max);
+        Self { center, parts, reach }
+    }
+}
+
+/// Closest points between segments `p1`-`q1` and `p2`-`q2`
+fn closest_points_on_segments(
+    p1: (f32, f32),
+    q1: (f32, f32),
+    p2: (f32, f32),
+    q2: (f32, f32),
+) -> ((f32, f32), (f32, f32)) {
+    let d1 = (q1.0 - p1.0, q1.1 - p1.1);
+    let d2 = (q2.0 - p2.0, q2.1 - p2.1);
+    let r = (p1.0 - p2.0, p1.1 - p2.1);
+    let dot = |a: (f32, f32), b: (f32, f32)| a.0 * b.0 + a.1 * b.1;
+    let (a, e, f) = (dot(d1, d1), dot(d2, d2), dot(d2, r));
+
+    let (s, t) = if a <= 1e-9 && e <= 1e-9 {
+        (0.0, 0.0)
+    } else if a <= 1e-9 {
+        (0.0, (f / e).clamp(0.0, 1.0))
+    } else {
+        let c = dot(d1, r);
+        if e <= 1e-9 {
+            ((-c / a).clamp(0.0, 1.0), 0.0)
+        } else {
+            let b = dot(d1, d2);
+            let denom = a * e - b * b;
+            let s = if denom > 1e-9 { ((b * f - c * e) / denom).clamp(0.0, 1.0) } else { 0.0 };
+            let t = (b * s + f) / e;
+            if t < 0.0 {
+                ((-c / a).clamp(0.0, 1.0), 0.0)
+            } else if t > 1.0 {
+                (((b - c) / a).clamp(0.0, 1.0), 1.0)
+            } else {
+                (s, t)
+            }
+        }
+    };
+
+    ((p1.0 + d1.0 * s, p1.1 + d1.1 * s), (p2.0 + d2.0 * t, p2.1 + d2.1 * t))
+}
+
+/// Contact between two convex parts, with the normal pointing from `a` to `b`
+fn part_contact(a: &CollisionPart, b: &CollisionPart) -> Option<Contact> {
+    let radii = a.radius + b.radius;
+
+    // Separating-axis test between the cores, keeping the shallowest penetration
+    let mut best: Option<(f32, (f32, f32), bool)> = None;
+    for (axis, from_a) in a.axes().into_iter().map(|ax| (ax, true)).chain(b.axes().into_iter().map(|ax| (ax, false))) {
+        let (a_min, a_max) = a.project(axis);
+        let (b_min, b_max) = b.project(axis);
+        let (forward, backward) = (a_max - b_min, b_max - a_min);
+        if forward <= 0.0 || backward <= 0.0 {
+            best = None;
+            break;
+        }
+        let (depth, normal) = if forward <= backward { (forward, axis) } else { (backward, (-axis.0, -axis.1)) };
+        if best.is_none_or(|(best_depth, _, _)| depth < best_depth) {
+            best = Some((depth, normal, from_a));
+        }
+    }
+
+    if let Some((depth, normal, from_a)) = best {
+        // Cores overlap: contact sits at the mean of the vertices inside the other part
+        let inside: Vec<(f32, f32)> = a
+            .core
+            .iter()
+            .copied()
+            .filter(|&p| b.contains(p))
+            .chain(b.core.iter().copied().filter(|&p| a.contains(p)))
+            .collect();
+        let point = if inside.is_empty() {
+            // Edges crossing without a vertex inside: use the incident part's deepest vertex
+            let along_normal = |p: &(f32, f32)| p.0 * normal.0 + p.1 * normal.1;
+            if from_a {
+                b.core.iter().copied().min_by(|p, q| along_normal(p).total_cmp(&along_normal(q)))?
+            } else {
+                a.core.iter().copied().max_by(|p, q| along_normal(p).total_cmp(&along_normal(q)))?
+            }
+        } else {
+            let n = inside.len() as f32;
+            (
+                inside.iter().map(|p| p.0).sum::<f32>() / n,
+                inside.iter().map(|p| p.1).sum::<f32>() / n,
+            )
+        };
+        return Some(Contact { normal, depth: depth + radii, point });
+    }
+
+    // Cores apart: only rounded parts can still touch, across the closest pair of edges
+    if radii <= 0.0 {
+        return None;
+    }
+    let (pa, pb, distance) = a
+        .edges()
+        .flat_map(|(p1, q1)| b.edges().map(move |(p2, q2)| closest_points_on_segments(p1, q1, p2, q2)))
+        .map(|(pa, pb)| (pa, pb, (pb.0 - pa.0).hypot(pb.1 - pa.1)))
+        .min_by(|x, y| x.2.total_cmp(&y.2))?;
+    if distance >= radii || distance < 1e-6 {
+        return None;
+    }
+
+    let normal = ((pb.0 - pa.0) / distance, (pb.1 - pa.1) / distance);
+    // Midway between the two surfaces
+    let point = (
+        (pa.0 + normal.0 * a.radius + pb.0 - normal.0 * b.radius) / 2.0,
+        (pa.1 + normal.1 * a.radius + pb.1 - normal.1 * b.radius) / 2.0,
+    );
+    Some(Contact { normal, depth: radii - distance, point })
+}
+
+/// Contact between two car footprints
+///
+/// Takes the normal and depth of the deepest touching pair of parts, but acts
+/// through the middle of every touching pair, so two axles meeting evenly do
+/// not spin the cars.
+fn shape_contact(a: &CarShape, b: &CarShape) -> Option<Contact> {
+    let apart = (b.center.0 - a.center.0).hypot(b.center.1 - a.center.1);
+    if apart >= a.reach + b.reach {
+        return None;
+    }
+
+    let contacts: Vec<Contact> = a
+        .parts
+        .iter()
+        .flat_map(|pa| b.parts.iter().filter_map(move |pb| part_contact(pa, pb)))
+        .collect();
+    let deepest = *contacts.iter().max_by(|x, y| x.depth.total_cmp(&y.depth))?;
+    let n = contacts.len() as f32;
+    let point = (
+        contacts.iter().map(|c| c.point.0).sum::<f32>() / n,
+        contacts.iter().map(|c| c.point.1).sum::<f32>() / n,
+    );
+    Some(Contact { point, ..deepest })
+}
+
+/// Yaw moment of inertia of a car, treating it as a uniform rectangular body
+fn yaw_inertia(config: &CarConfig) -> f32 {
+    config.mass_kg * (config.length_m.powi(2) + config.width_m.powi(2)) / 12.0
+}
+
+/// Velocity of the point `r` away from the car's center, including spin
+fn point_velocity(state: &CarState, r: (f32, f32)) -> (f32, f32) {
+    (
+        state.vel_x - state.angular_vel_yaw * r.1,
+        state.vel_y + state.angular_vel_yaw * r.0,
+    )
+}
+
+fn cross(a: (f32, f32), b: (f32, f32)) -> f32 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+/// Apply a linear impulse through the point `r` away from the car's center
+fn apply_impulse(state: &mut CarState, config: &CarConfig, r: (f32, f32), impulse: (f32, f32)) {
+    state.vel_x += impulse.0 / config.mass_kg;
+    state.vel_y += impulse.1 / config.mass_kg;
+    state.angular_vel_yaw += cross(r, impulse) / yaw_inertia(config);
+}
+
+/// Detect and resolve car-to-car contacts between collision shapes with impulses
+///
+/// Pairs are resolved in player ID order, so the outcome does not depend on the
+/// order `states` arrives in. Off-center hits transfer spin as well as momentum.
+pub fn resolve_car_collisions(
+    states: &mut [CarState],
+    configs: &HashMap<CarConfigId, CarConfig>,
+    settings: &CollisionSettings,
+) {
+    // Reset collision flags
+    for state in states.iter_mut() {
         state.is_colliding = false;
         state.collision_normal_x = 0.0;
         state.collision_normal_y = 0.0;
         state.collision_normal_z = 0.0;
     }
 
-    // Check all pairs
-    for i in 0..states.len() {
-        for j in (i + 1)..states.len() {
-            let config_i = configs.get(&states[i].car_config_id);
-            let config_j = configs.get(&states[j].car_config_id);
-
-            if let (Some(cfg_i), Some(cfg_j)) = (config_i, config_j) {
-                if check_collision_3d(&states[i], cfg_i, &states[j], cfg_j) {
-                    // Mark as colliding
-                    states[i].is_colliding = true;
-                    states[j].is_colliding = true;
-
-                    // Calculate collision normal
-                    let dx = states[j].pos_x - states[i].pos_x;
-                    let dy = states[j].pos_y - states[i].pos_y;
-                    let dz = states[j].pos_z - states[i].pos_z;
-                    let dist = (dx * dx + dy * dy + dz * dz).sqrt().max(0.1);
-                    
-                    let nx = dx / dist;
-                    let ny = dy / dist;
-                    let nz = dz / dist;
-                    
-                    states[i].collision_normal_x = -nx;
-                    states[i].collision_normal_y = -ny;
-                    states[i].collision_normal_z = -nz;
-                    states[j].collision_normal_x = nx;
-                    states[j].collision_normal_y = ny;
-                    states[j].collision_normal_z = nz;
-
-                    // Separate cars
-                    let separation = 0.5;
-                    states[i].pos_x -= nx * separation;
-                    states[i].pos_y -= ny * separation;
-                    states[j].pos_x += nx * separation;
-                    states[j].pos_y += ny * separation;
-
-                    // Calculate impact velocity and apply impulse
-                    let rel_vel_x = states[j].vel_x - states[i].vel_x;
-                    let rel_vel_y = states[j].vel_y - states[i].vel_y;
-                    let rel_vel_normal = rel_vel_x * nx + rel_vel_y * ny;
-                    
-                    if rel_vel_normal < 0.0 {
-                        // Collision impulse (elastic coefficient)
-                        let restitution = 0.3;
-                        let impulse = -(1.0 + restitution) * rel_vel_normal;
-                        let impulse = impulse / (1.0 / cfg_i.mass_kg + 1.0 / cfg_j.mass_kg);
-                        
-                        states[i].vel_x -= impulse * nx / cfg_i.mass_kg;
-                        states[i].vel_y -= impulse * ny / cfg_i.mass_kg;
-                        states[j].vel_x += impulse * nx / cfg_j.mass_kg;
-                        states[j].vel_y += impulse * ny / cfg_j.mass_kg;
-                    }
-                    
-                    // Recalculate speeds
-                    states[i].speed_mps = (states[i].vel_x.powi(2) + states[i].vel_y.powi(2) + states[i].vel_z.powi(2)).sqrt();
-                    states[j].speed_mps = (states[j].vel_x.powi(2) + states[j].vel_y.powi(2) + states[j].vel_z.powi(2)).sqrt();
-                    
-                    // Apply damage
-                    let impact_speed = ((states[i].speed_mps + states[j].speed_mps) / 2.0).min(50.0);
-                    let damage_amount = (impact_speed / 50.0) * 5.0;
-                    
-                    let angle_i = (ny.atan2(nx) - states[i].yaw_rad).rem_euclid(2.0 * PI);
-                    let angle_j = (ny.atan2(nx) - states[j].yaw_rad + PI).rem_euclid(2.0 * PI);
-                    
-                    apply_damage_to_car(&mut states[i], angle_i, damage_amount);
-                    apply_damage_to_car(&mut states[j], angle_j, damage_amount);
-                }
+    let mut order: Vec<usize> = (0..states.len()).collect();
+    order.sort_by_key(|&i| states[i].player_id);
+
+    for (n, &i) in order.iter().enumerate() {
+        for &j in &order[n + 1..] {
+            if states[i].is_ghost || states[j].is_ghost {
+                continue;
+            }
+            let (Some(cfg_i), Some(cfg_j)) = (
+                configs.get(&states[i].car_config_id),
+                configs.get(&states[j].car_config_id),
+            ) else {
+                continue;
+            };
+
+            let half_h = (cfg_i.height_m + cfg_j.height_m) / 2.0;
+            if (states[i].pos_z - states[j].pos_z).abs() >= half_h {
+                continue;
             }
+            let Some(contact) = shape_contact(&CarShape::new(&states[i], cfg_i), &CarShape::new(&states[j], cfg_j)) else {
+                continue;
+            };
+            let (nx, ny) = contact.normal;
+
+            states[i].is_colliding = true;
+            states[j].is_colliding = true;
+            states[i].collision_normal_x = -nx;
+            states[i].collision_normal_y = -ny;
+            states[j].collision_normal_x = nx;
+            states[j].collision_normal_y = ny;
+
+            // Push the cars apart in proportion to their inverse masses
+            let inv_mass_i = 1.0 / cfg_i.mass_kg;
+            let inv_mass_j = 1.0 / cfg_j.mass_kg;
+            let share_i = inv_mass_i / (inv_mass_i + inv_mass_j);
+            states[i].pos_x -= nx * contact.depth * share_i;
+            states[i].pos_y -= ny * contact.depth * share_i;
+            states[j].pos_x += nx * contact.depth * (1.0 - share_i);
+            states[j].pos_y += ny * contact.depth * (1.0 - share_i);
+
+            let r_i = (contact.point.0 - states[i].pos_x, contact.point.1 - states[i].pos_y);
+            let r_j = (contact.point.0 - states[j].pos_x, contact.point.1 - states[j].pos_y);
+            let v_i = point_velocity(&states[i], r_i);
+            let v_j = point_velocity(&states[j], r_j);
+            let rel = (v_j.0 - v_i.0, v_j.1 - v_i.1);
+            let closing_speed = -(rel.0 * nx + rel.1 * ny);
+
+            if closing_speed > 0.0 {
+                let effective_inv_mass = |dir: (f32, f32)| {
+                    inv_mass_i
+                        + inv_mass_j
+                        + cross(r_i, dir).powi(2) / yaw_inertia(cfg_i)
+                        + cross(r_j, dir).powi(2) / yaw_inertia(cfg_j)
+                };
+
+                let normal_impulse = (1.0 + settings.restitution) * closing_speed / effective_inv_mass((nx, ny));
+
+                // Friction opposes sliding along the contact, up to the Coulomb limit
+                let tangent_speed = rel.0 * -ny + rel.1 * nx;
+                let tangent = (-ny, nx);
+                let friction_impulse = (-tangent_speed / effective_inv_mass(tangent))
+                    .clamp(-settings.friction * normal_impulse, settings.friction * normal_impulse);
+
+                let impulse = (
+                    nx * normal_impulse + tangent.0 * friction_impulse,
+                    ny * normal_impulse + tangent.1 * friction_impulse,
+                );
+                apply_impulse(&mut states[i], cfg_i, r_i, (-impulse.0, -impulse.1));
+                apply_impulse(&mut states[j], cfg_j, r_j, impulse);
+            }
+
+            for k in [i, j] {
+                states[k].speed_mps = (states[k].vel_x.powi(2) + states[k].vel_y.powi(2) + states[k].vel_z.powi(2)).sqrt();
+            }
+
+            // Damage scales with how fast the bodywork meets, head-on or sliding, on the side that was hit
+            let impact_speed = (rel.0 * rel.0 + rel.1 * rel.1).sqrt().min(50.0);
+            let damage_amount = (impact_speed / 50.0) * 5.0;
+            let angle_i = hit_angle(&states[i], cfg_i, r_i);
+            let angle_j = hit_angle(&states[j], cfg_j, r_j);
+            apply_damage_to_car(&mut states[i], angle_i, damage_amount);
+            apply_damage_to_car(&mut states[j], angle_j, damage_amount);
         }
     }
 }
 
-/// Check if two cars are colliding using oriented bounding boxes (simplified to AABB)
-fn check_collision_3d(
-    state_a: &CarState,
-    config_a: &CarConfig,
-    state_b: &CarState,
-    config_b: &CarConfig,
-) -> bool {
-    let half_l_a = config_a.length_m / 2.0;
-    let half_w_a = config_a.width_m / 2.0;
-    let half_h_a = config_a.height_m / 2.0;
-    
-    let half_l_b = config_b.length_m / 2.0;
-    let half_w_b = config_b.width_m / 2.0;
-    let half_h_b = config_b.height_m / 2.0;
+/// Penetration of a car footprint through a wall, with the normal pointing back towards the car
+fn wall_contact(car: &CarShape, wall: &WallSegment) -> Option<Contact> {
+    let (sx, sy) = (wall.x2 - wall.x1, wall.y2 - wall.y1);
+    let length = sx.hypot(sy);
+    if length < 1e-6 {
+        return None;
+    }
+    let dir = (sx / length, sy / length);
+
+    // Cheap rejection before looking at the parts
+    let to_center = (car.center.0 - wall.x1, car.center.1 - wall.y1);
+    let along = to_center.0 * dir.0 + to_center.1 * dir.1;
+    let side = cross(dir, to_center);
+    if side.abs() > car.reach || along < -car.reach || along > length + car.reach {
+        return None;
+    }
+
+    // Walls are two-sided: the car belongs on whichever side its center is
+    let normal = if side < 0.0 { (dir.1, -dir.0) } else { (-dir.1, dir.0) };
+
+    let mut depth: f32 = 0.0;
+    let mut touching = Vec::with_capacity(4);
+    for part in &car.parts {
+        for &(px, py) in &part.core {
+            let rel = (px - wall.x1, py - wall.y1);
+            let distance = rel.0 * normal.0 + rel.1 * normal.1 - part.radius;
+            let t = rel.0 * dir.0 + rel.1 * dir.1;
+            if distance < 0.0 && (0.0..=length).contains(&t) {
+                depth = depth.max(-distance);
+                // Where the vertex, or the rounded edge around it, crossed the wall face
+                let to_face = distance + part.radius;
+                touching.push((px - normal.0 * to_face, py - normal.1 * to_face));
+            }
+        }
+    }
+    if touching.is_empty() {
+        return None;
+    }
+
+    let n = touching.len() as f32;
+    let point = (
+        touching.iter().map(|p| p.0).sum::<f32>() / n,
+        touching.iter().map(|p| p.1).sum::<f32>() / n,
+    );
+    Some(Contact { normal, depth, point })
+}
+
+/// Detect and resolve contacts between cars and track walls
+///
+/// Walls do not move: a car poking through one is pushed back to its own side and
+/// bounces off it with the session's restitution and friction. Only the closing
+/// speed into the wall does damage, so scraping along a barrier is cheap.
+pub fn resolve_wall_collisions(
+    states: &mut [CarState],
+    configs: &HashMap<CarConfigId, CarConfig>,
+    walls: &[WallSegment],
+    settings: &CollisionSettings,
+) {
+    for state in states.iter_mut() {
+        let Some(config) = configs.get(&state.car_config_id) else {
+            continue;
+        };
 
-    let dx = (state_a.pos_x - state_b.pos_x).abs();
-    let dy = (state_a.pos_y - state_b.pos_y).abs();
-    let dz = (state_a.pos_z - state_b.pos_z).abs();
+        // Only a contact moves the car, so the footprint is rebuilt after one
+        let mut shape = CarShape::new(state, config);
+        for wall in walls {
+            let Some(contact) = wall_contact(&shape, wall) else {
+                continue;
+            };
+            let (nx, ny) = contact.normal;
+
+            state.is_colliding = true;
+            state.collision_normal_x = nx;
+            state.collision_normal_y = ny;
+            state.pos_x += nx * contact.depth;
+            state.pos_y += ny * contact.depth;
+            shape = CarShape::new(state, config);
+
+            let r = (contact.point.0 - state.pos_x, contact.point.1 - state.pos_y);
+            let v = point_velocity(state, r);
+            let closing_speed = -(v.0 * nx + v.1 * ny);
+            if closing_speed <= 0.0 {
+                continue;
+            }
 
-    // Use larger dimension for rotated AABB approximation
-    let size_a = half_l_a.max(half_w_a);
-    let size_b = half_l_b.max(half_w_b);
+            let effective_inv_mass = |dir: (f32, f32)| 1.0 / config.mass_kg + cross(r, dir).powi(2) / yaw_inertia(config);
+            let normal_impulse = (1.0 + settings.restitution) * closing_speed / effective_inv_mass((nx, ny));
+
+            let tangent = (-ny, nx);
+            let tangent_speed = v.0 * tangent.0 + v.1 * tangent.1;
+            let friction_impulse = (-tangent_speed / effective_inv_mass(tangent))
+                .clamp(-settings.friction * normal_impulse, settings.friction * normal_impulse);
+
+            apply_impulse(
+                state,
+                config,
+                r,
+                (
+                    nx * normal_impulse + tangent.0 * friction_impulse,
+                    ny * normal_impulse + tangent.1 * friction_impulse,
+                ),
+            );
+            state.speed_mps = (state.vel_x.powi(2) + state.vel_y.powi(2) + state.vel_z.powi(2)).sqrt();
+
+            let damage_amount = (closing_speed.min(50.0) / 50.0) * 5.0;
+            let angle = hit_angle(state, config, r);
+            apply_damage_to_car(state, angle, damage_amount);
+        }
+    }
+}
 
-    dx < (size_a + size_b) && dy < (size_a + size_b) && dz < (half_h_a + half_h_b)
+/// Direction of a contact `r` away from the car's center, in the car's frame
+///
+/// The offset is scaled by the car's length and width first, so the footprint's
+/// corners fall on the zone boundaries: a hit on the flank near the nose of a
+/// long car counts as a side hit, not a front one.
+fn hit_angle(state: &CarState, config: &CarConfig, r: (f32, f32)) -> f32 {
+    let (sin, cos) = state.yaw_rad.sin_cos();
+    let forward = (r.0 * cos + r.1 * sin) / config.length_m;
+    let left = (r.1 * cos - r.0 * sin) / config.width_m;
+    left.atan2(forward).rem_euclid(2.0 * PI)
 }
 
 /// Apply damage to a car based on collision angle
 fn apply_damage_to_car(car: &mut CarState, angle: f32, damage_amount: f32) {
-    if angle < PI / 4.0 || angle > 7.0 * PI / 4.0 {
+    if !(PI / 4.0..=7.0 * PI / 4.0).contains(&angle) {
         car.damage.front_damage_percent = (car.damage.front_damage_percent + damage_amount).min(100.0);
         car.damage.engine_damage_percent = (car.damage.engine_damage_percent + damage_amount * 0.5).min(100.0);
-    } else if angle >= PI / 4.0 && angle < 3.0 * PI / 4.0 {
+    } else if (PI / 4.0..3.0 * PI / 4.0).contains(&angle) {
         car.damage.left_damage_percent = (car.damage.left_damage_percent + damage_amount).min(100.0);
-    } else if angle >= 3.0 * PI / 4.0 && angle < 5.0 * PI / 4.0 {
+    } else if (3.0 * PI / 4.0..5.0 * PI / 4.0).contains(&angle) {
         car.damage.rear_damage_percent = (car.damage.rear_damage_percent + damage_amount).min(100.0);
     } else {
         car.damage.right_damage_percent = (car.damage.right_damage_percent + damage_amount).min(100.0);
@@ -894,6 +2254,7 @@ pub fn update_track_progress_3d(
     state: &mut CarState,
     track: &TrackConfig,
     current_tick: u32,
+    timing: TickTiming,
 ) {
     if track.centerline.is_empty() {
         return;
@@ -921,8 +2282,8 @@ pub fn update_track_progress_3d(
     let old_progress = state.track_progress;
     state.track_progress = track.centerline[nearest_idx].distance_from_start_m;
 
-    // Debug: Log track progress every 240 ticks (once per second)
-    if current_tick % 240 == 0 {
+    // Debug: Log track progress once per second
+    if current_tick.is_multiple_of(timing.tick_rate_hz as u32) {
         eprintln!("[Lap Debug] Tick {}: current_lap={}, track_progress={:.1}m/{:.1}m, lap_time={}ms",
             current_tick, state.current_lap, state.track_progress, track_length, state.current_lap_time_ms);
     }
@@ -930,14 +2291,14 @@ pub fn update_track_progress_3d(
     // Update current lap time
     if state.current_lap > 0 {
         let ticks_elapsed = current_tick.saturating_sub(state.lap_start_tick);
-        state.current_lap_time_ms = ((ticks_elapsed as f32 * 1000.0) / 240.0) as u32;
+        state.current_lap_time_ms = timing.ticks_to_ms(ticks_elapsed);
     }
 
     // Detect lap completion
     if state.current_lap > 0 && old_progress > track_length * 0.8 && state.track_progress < track_length * 0.2 {
         // Calculate lap time (time since lap started)
         let ticks_elapsed = current_tick.saturating_sub(state.lap_start_tick);
-        let lap_time_ms = ((ticks_elapsed as f32 * 1000.0) / 240.0) as u32;
+        let lap_time_ms = timing.ticks_to_ms(ticks_elapsed);
         state.last_lap_time_ms = Some(lap_time_ms);
 
         if state.best_lap_time_ms.is_none() || lap_time_ms < state.best_lap_time_ms.unwrap() {
@@ -991,6 +2352,7 @@ pub fn update_track_progress(
     centerline: &[TrackPoint],
     track_length: f32,
     current_tick: u32,
+    timing: TickTiming,
 ) {
     if centerline.is_empty() {
         return;
@@ -1016,13 +2378,13 @@ pub fn update_track_progress(
     // Update current lap time
     if state.current_lap > 0 {
         let ticks_elapsed = current_tick.saturating_sub(state.lap_start_tick);
-        state.current_lap_time_ms = ((ticks_elapsed as f32 * 1000.0) / 240.0) as u32;
+        state.current_lap_time_ms = timing.ticks_to_ms(ticks_elapsed);
     }
 
     if state.current_lap > 0 && old_progress > track_length * 0.8 && state.track_progress < track_length * 0.2 {
         // Calculate lap time (time since lap started)
         let ticks_elapsed = current_tick.saturating_sub(state.lap_start_tick);
-        let lap_time_ms = ((ticks_elapsed as f32 * 1000.0) / 240.0) as u32;
+        let lap_time_ms = timing.ticks_to_ms(ticks_elapsed);
         state.last_lap_time_ms = Some(lap_time_ms);
 
         if state.best_lap_time_ms.is_none() || lap_time_ms < state.best_lap_time_ms.unwrap() {
@@ -1043,12 +2405,12 @@ pub fn update_track_progress(
     }
 }
 
-/// Legacy collision check - wraps 3D version
+/// Legacy collision check - resolves collisions with default settings
 pub fn check_aabb_collisions(
     states: &mut [CarState],
     configs: &HashMap<CarConfigId, CarConfig>,
 ) {
-    check_aabb_collisions_3d(states, configs);
+    resolve_car_collisions(states, configs, &CollisionSettings::default());
 }
 
 // ============================================================================
@@ -1058,7 +2420,6 @@ pub fn check_aabb_collisions(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use uuid::Uuid;
 
     fn create_test_car_state() -> CarState {
         let grid_slot = GridSlot {
@@ -1068,7 +2429,7 @@ mod tests {
             z: 0.0,
             yaw_rad: 0.0,
         };
-        CarState::new(Uuid::new_v4(), Uuid::new_v4(), &grid_slot)
+        CarState::new(PlayerId::new_v4(), CarConfigId::new_v4(), &grid_slot)
     }
 
     fn create_test_config() -> CarConfig {
@@ -1129,92 +2490,924 @@ mod tests {
         assert!(state.speed_mps < initial_speed, "Speed should decrease from braking");
     }
 
+    /// Straight asphalt strip along +x, long enough to accelerate through the gears
+    fn create_straight_track() -> TrackConfig {
+        TrackConfig {
+            centerline: (0..200)
+                .map(|i| TrackPoint {
+                    x: i as f32 * 10.0,
+                    distance_from_start_m: i as f32 * 10.0,
+                    ..TrackPoint::default()
+                })
+                .collect(),
+            ..create_test_track()
+        }
+    }
+
     #[test]
-    fn test_update_car_steering() {
-        let mut state = create_test_car_state();
-        state.vel_x = 10.0;
-        state.speed_mps = 10.0;
-        
+    fn test_automatic_clutch_launches_from_standstill() {
         let config = create_test_config();
-        let track = create_test_track();
-        let input = PlayerInputData {
-            throttle: 0.5,
-            brake: 0.0,
-            steering: 0.5,
-            gear: None,
-            clutch: None,
-        };
+        let track = create_straight_track();
+        let input = PlayerInputData { throttle: 1.0, ..Default::default() };
+        let mut state = create_test_car_state();
 
-        let dt = 1.0 / 240.0;
+        for _ in 0..(240 * 2) {
+            update_car_3d(&mut state, &config, &input, &track, 1.0 / 240.0);
+            assert!(!state.engine_stalled);
+        }
 
-        update_car_3d(&mut state, &config, &input, &track, dt);
+        assert!(state.speed_mps > 8.0, "Should pull away briskly, got {} m/s", state.speed_mps);
+        assert!(state.pos_x > 8.0, "Should cover ground from a standing start, got {} m", state.pos_x);
+    }
 
-        // Angular velocity should change with steering
-        assert!(state.angular_vel_yaw.abs() > 0.0 || state.yaw_rad.abs() > 0.0001,
-            "Steering should cause yaw change");
+    #[test]
+    fn test_dropping_the_clutch_at_standstill_stalls() {
+        let config = create_test_config();
+        let track = create_straight_track();
+        let mut state = create_test_car_state();
+        let dt = 1.0 / 240.0;
+
+        let dropped = PlayerInputData { clutch: Some(1.0), gear: Some(1), ..Default::default() };
+        for _ in 0..240 {
+            update_car_3d(&mut state, &config, &dropped, &track, dt);
+        }
+        assert!(state.engine_stalled);
+        assert_eq!(state.engine_rpm, 0.0);
+
+        // Pressing the clutch restarts the engine
+        let pressed = PlayerInputData { clutch: Some(0.0), gear: Some(1), ..Default::default() };
+        update_car_3d(&mut state, &config, &pressed, &track, dt);
+        assert!(!state.engine_stalled);
+        assert!(state.engine_rpm >= config.idle_rpm);
     }
 
     #[test]
-    fn test_collision_detection() {
+    fn test_gear_change_cuts_drive_for_the_shift_time() {
         let config = create_test_config();
-        let grid_slot1 = GridSlot { position: 1, x: 0.0, y: 0.0, z: 0.0, yaw_rad: 0.0 };
-        let grid_slot2 = GridSlot { position: 2, x: 1.0, y: 0.0, z: 0.0, yaw_rad: 0.0 };
-        
-        let mut states = vec![
-            CarState::new(Uuid::new_v4(), config.id, &grid_slot1),
-            CarState::new(Uuid::new_v4(), config.id, &grid_slot2),
-        ];
-        states[0].speed_mps = 10.0;
-        states[1].speed_mps = 10.0;
+        let track = create_straight_track();
+        let mut state = create_test_car_state();
+        state.vel_x = 15.0;
+        state.speed_mps = 15.0;
+        let dt = 1.0 / 240.0;
 
-        let mut configs = HashMap::new();
-        configs.insert(config.id, config.clone());
+        let second = PlayerInputData { throttle: 1.0, gear: Some(2), ..Default::default() };
+        update_car_3d(&mut state, &config, &second, &track, dt);
+        assert_eq!(state.gear, 2);
+        assert!(state.shift_timer_s > 0.0);
+        assert_eq!(state.clutch_input, 0.0, "No drive while the gear goes in");
 
-        check_aabb_collisions_3d(&mut states, &configs);
+        // A further change waits for the first to finish
+        let third = PlayerInputData { gear: Some(3), ..second };
+        update_car_3d(&mut state, &config, &third, &track, dt);
+        assert_eq!(state.gear, 2);
 
-        assert!(states[0].is_colliding, "Car 1 should be colliding");
-        assert!(states[1].is_colliding, "Car 2 should be colliding");
+        let shift_ticks = (config.transmission.shift_time_s / dt).ceil() as usize;
+        for _ in 0..shift_ticks {
+            update_car_3d(&mut state, &config, &third, &track, dt);
+        }
+        assert_eq!(state.gear, 3);
     }
 
     #[test]
-    fn test_track_progress_update() {
-        let track = create_test_track();
+    fn test_gearbox_refuses_reverse_and_over_revving_downshifts() {
+        let config = create_test_config();
+        let track = create_straight_track();
         let mut state = create_test_car_state();
-        state.pos_x = track.centerline[1].x;
-        state.pos_y = track.centerline[1].y;
+        state.gear = 4;
+        state.vel_x = 40.0;
+        state.speed_mps = 40.0;
 
-        update_track_progress_3d(&mut state, &track, 0);
+        let reverse = PlayerInputData { gear: Some(-1), ..Default::default() };
+        update_car_3d(&mut state, &config, &reverse, &track, 1.0 / 240.0);
+        assert_eq!(state.gear, 4, "Reverse at speed");
 
-        assert!(state.track_progress > 0.0, "Track progress should be positive");
+        let first = PlayerInputData { gear: Some(1), ..Default::default() };
+        update_car_3d(&mut state, &config, &first, &track, 1.0 / 240.0);
+        assert_eq!(state.gear, 4, "First at 40 m/s would over-rev the engine");
     }
 
     #[test]
-    fn test_aerodynamic_forces() {
-        let mut state = create_test_car_state();
-        state.speed_mps = 50.0;  // 180 km/h
+    fn test_automatic_gearbox_changes_up_through_the_gears() {
         let config = create_test_config();
+        let track = create_straight_track();
+        let input = PlayerInputData { throttle: 1.0, ..Default::default() };
+        let mut state = create_test_car_state();
 
-        let (drag, df_front, df_rear) = calculate_aerodynamic_forces(&state, &config);
+        let mut highest_rpm: f32 = 0.0;
+        for _ in 0..(240 * 10) {
+            update_car_3d(&mut state, &config, &input, &track, 1.0 / 240.0);
+            highest_rpm = highest_rpm.max(state.engine_rpm);
+        }
 
-        assert!(drag > 0.0, "Drag should be positive at speed");
-        assert!(df_front > 0.0, "Front downforce should be positive");
-        assert!(df_rear > 0.0, "Rear downforce should be positive");
+        assert!(state.gear >= 3, "Should have changed up, in gear {}", state.gear);
+        assert!(highest_rpm < config.engine.rev_limiter_rpm, "Should change up before the limiter");
     }
 
     #[test]
-    fn test_weight_transfer() {
+    fn test_engine_braking_slows_the_car_in_gear() {
         let config = create_test_config();
-        let total_weight = config.mass_kg * GRAVITY;
-        
-        // Under braking (negative longitudinal accel)
-        let (long_transfer, _, _) = calculate_weight_transfer(&config, -10.0, 0.0, total_weight);
-        assert!(long_transfer < 0.0, "Weight should transfer forward under braking");
-        
-        // Under acceleration
-        let (long_transfer, _, _) = calculate_weight_transfer(&config, 5.0, 0.0, total_weight);
+        let track = create_straight_track();
+        let coast = |gear: i8| {
+            let mut state = create_test_car_state();
+            state.gear = gear;
+            state.vel_x = 25.0;
+            state.speed_mps = 25.0;
+            state.engine_rpm = 5000.0;
+            let input = PlayerInputData { gear: Some(gear), clutch: Some(1.0), ..Default::default() };
+            for _ in 0..240 {
+                update_car_3d(&mut state, &config, &input, &track, 1.0 / 240.0);
+            }
+            state.speed_mps
+        };
+
+        assert!(coast(3) < coast(0) - 0.5, "Closed throttle in gear should slow the car more than coasting in neutral");
+    }
+
+    #[test]
+    fn test_rev_limiter_cuts_the_fuel() {
+        let config = create_test_config();
+        let limiter_rpm = config.engine.rev_limiter_rpm;
+
+        assert!(calculate_engine_output(&config, limiter_rpm - 500.0, 1.0, 0.0) > 0.0);
+        assert!(
+            calculate_engine_output(&config, limiter_rpm, 1.0, 0.0) <= 0.0,
+            "No drive should be made at the limiter"
+        );
+    }
+
+    #[test]
+    fn test_turbo_boost_lags_the_throttle() {
+        let mut config = create_test_config();
+        config.engine.turbo = Some(TurboConfig {
+            boost_torque_share: 0.4,
+            boost_threshold_rpm: 3000.0,
+            spool_time_s: 0.8,
+        });
+        let dt = 1.0 / 240.0;
+        let mut state = create_test_car_state();
+        state.engine_rpm = 5000.0;
+
+        update_turbo(&mut state, &config, 1.0, dt);
+        let first_torque = calculate_engine_output(&config, state.engine_rpm, 1.0, state.turbo_boost);
+        for _ in 0..480 {
+            update_turbo(&mut state, &config, 1.0, dt);
+        }
+        let spooled_torque = calculate_engine_output(&config, state.engine_rpm, 1.0, state.turbo_boost);
+
+        assert!(state.turbo_boost > 0.9, "Boost should build over the spool time");
+        assert!(spooled_torque > first_torque * 1.4, "Torque should build with boost");
+
+        // Off boost below the threshold
+        state.engine_rpm = 2000.0;
+        for _ in 0..240 {
+            update_turbo(&mut state, &config, 1.0, dt);
+        }
+        assert!(state.turbo_boost < 0.05);
+    }
+
+    #[test]
+    fn test_wet_track_lengthens_braking() {
+        let config = create_test_config();
+        // Straight asphalt strip along +x so braking stays grip-limited on track
+        let track = TrackConfig {
+            centerline: (0..40)
+                .map(|i| TrackPoint {
+                    x: i as f32 * 10.0,
+                    distance_from_start_m: i as f32 * 10.0,
+                    ..TrackPoint::default()
+                })
+                .collect(),
+            ..create_test_track()
+        };
+        let input = PlayerInputData {
+            throttle: 0.0,
+            brake: 1.0,
+            steering: 0.0,
+            gear: None,
+            clutch: None,
+        };
+        let dt = 1.0 / 240.0;
+
+        let braking_distance = |wetness: f32| {
+            let mut state = create_test_car_state();
+            state.vel_x = 30.0;
+            state.speed_mps = 30.0;
+            state.track_wetness = wetness;
+            let mut distance = 0.0;
+            for _ in 0..(240 * 10) {
+                if state.speed_mps < 1.0 {
+                    break;
+                }
+                update_car_3d(&mut state, &config, &input, &track, dt);
+                distance += state.speed_mps * dt;
+            }
+            assert!(state.is_on_track);
+            distance
+        };
+
+        let dry = braking_distance(0.0);
+        let wet = braking_distance(1.0);
+        assert!(wet > dry * 1.2, "Wet braking distance {} should be well beyond dry {}", wet, dry);
+        assert!(wet_grip_factor(SurfaceType::Gravel, 1.0) < wet_grip_factor(SurfaceType::Asphalt, 1.0));
+        assert_eq!(wet_grip_factor(SurfaceType::Asphalt, 0.0), 1.0);
+    }
+
+    #[test]
+    fn test_hills_slow_climbing_cars_and_speed_descending_ones() {
+        let config = create_test_config();
+        let hill = |slope_rad: f32| TrackConfig {
+            centerline: (0..40)
+                .map(|i| TrackPoint {
+                    x: i as f32 * 10.0,
+                    z: i as f32 * 10.0 * slope_rad.tan(),
+                    distance_from_start_m: i as f32 * 10.0,
+                    slope_rad,
+                    ..TrackPoint::default()
+                })
+                .collect(),
+            ..create_test_track()
+        };
+        let input = PlayerInputData {
+            throttle: 0.0,
+            brake: 0.0,
+            steering: 0.0,
+            gear: None,
+            clutch: None,
+        };
+        let coast = |slope_rad: f32| {
+            let track = hill(slope_rad);
+            let mut state = create_test_car_state();
+            state.vel_x = 20.0;
+            state.speed_mps = 20.0;
+            for _ in 0..480 {
+                update_car_3d(&mut state, &config, &input, &track, 1.0 / 240.0);
+            }
+            state
+        };
+
+        let climbing = coast(0.1);
+        let flat = coast(0.0);
+        let descending = coast(-0.1);
+        assert!(climbing.speed_mps < flat.speed_mps - 1.0, "Climbing {} vs flat {}", climbing.speed_mps, flat.speed_mps);
+        assert!(descending.speed_mps > flat.speed_mps + 1.0, "Descending {} vs flat {}", descending.speed_mps, flat.speed_mps);
+        assert!(climbing.pitch_rad > 0.05 && descending.pitch_rad < -0.05);
+        assert!(climbing.pos_z > 1.0, "Car should follow the road up the hill: {}", climbing.pos_z);
+    }
+
+    #[test]
+    fn test_banked_turn_loads_the_tires_towards_the_inside() {
+        // Left-hand arc of radius 100 m banked towards its inside
+        let radius = 100.0;
+        let arc = |banking_rad: f32| TrackConfig {
+            centerline: (0..40)
+                .map(|i| {
+                    let angle = i as f32 * 0.05;
+                    TrackPoint {
+                        x: radius * angle.cos(),
+                        y: radius * angle.sin(),
+                        distance_from_start_m: radius * angle,
+                        heading_rad: angle + PI / 2.0,
+                        banking_rad,
+                        ..TrackPoint::default()
+                    }
+                })
+                .collect(),
+            ..create_test_track()
+        };
+        let mut state = create_test_car_state();
+        let angle: f32 = 1.0;
+        state.pos_x = radius * angle.cos();
+        state.pos_y = radius * angle.sin();
+        state.yaw_rad = angle + PI / 2.0;
+        state.speed_mps = 30.0;
+        state.vel_x = 30.0 * state.yaw_rad.cos();
+        state.vel_y = 30.0 * state.yaw_rad.sin();
+        state.angular_vel_yaw = 30.0 / radius;
+
+        let banked_ctx = get_track_context(&state, &arc(0.2));
+        assert!(banked_ctx.cross_slope_rad < -0.19, "Inside of a left turn is its left: {}", banked_ctx.cross_slope_rad);
+        let banked = calculate_track_frame_forces(&state, &banked_ctx);
+        let flat = calculate_track_frame_forces(&state, &get_track_context(&state, &arc(0.0)));
+        assert!((flat.normal_factor - 1.0).abs() < 0.001);
+        assert!(banked.normal_factor > 1.1, "Cornering into the bank adds load: {}", banked.normal_factor);
+        assert!(banked.accel_y > 1.0, "Gravity pulls towards the inside: {}", banked.accel_y);
+
+        // Parked on the bank, the surface carries less than the car's weight
+        state.speed_mps = 0.0;
+        state.vel_x = 0.0;
+        state.vel_y = 0.0;
+        state.angular_vel_yaw = 0.0;
+        assert!(calculate_track_frame_forces(&state, &banked_ctx).normal_factor < 1.0);
+    }
+
+    #[test]
+    fn test_crest_unloads_and_camber_falls_away_from_the_crown() {
+        let track = TrackConfig {
+            centerline: (0..40)
+                .map(|i| TrackPoint {
+                    x: i as f32 * 10.0,
+                    distance_from_start_m: i as f32 * 10.0,
+                    // Grade eases from 0.2 rad uphill to 0.2 rad downhill
+                    slope_rad: 0.2 - i as f32 * 0.01,
+                    camber_rad: 0.03,
+                    ..TrackPoint::default()
+                })
+                .collect(),
+            ..create_test_track()
+        };
+        let mut state = create_test_car_state();
+        state.pos_x = 200.0;
+        state.pos_y = -3.0;
+        state.vel_x = 40.0;
+        state.speed_mps = 40.0;
+
+        let ctx = get_track_context(&state, &track);
+        assert!(ctx.vertical_curvature < 0.0);
+        assert!((ctx.cross_slope_rad - 0.03).abs() < 0.001, "Right of the crown the surface rises to the left");
+        let forces = calculate_track_frame_forces(&state, &ctx);
+        assert!(forces.normal_factor < 0.9, "Crest should unload the car: {}", forces.normal_factor);
+        assert!(forces.accel_y < 0.0, "Camber pushes the car off the crown");
+
+        state.pos_y = 3.0;
+        assert!((get_track_context(&state, &track).cross_slope_rad + 0.03).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_car_jumps_off_a_crest_and_lands_on_its_wheels() {
+        let config = create_test_config();
+        // The road climbs at 0.1 rad to a sharp crest, then drops away at 0.1 rad
+        let track = TrackConfig {
+            centerline: (0..80)
+                .map(|i| {
+                    let x = i as f32 * 5.0;
+                    let (z, slope_rad) = if x <= 100.0 {
+                        (x * 0.1_f32.tan(), 0.1)
+                    } else {
+                        ((200.0 - x) * 0.1_f32.tan(), -0.1)
+                    };
+                    TrackPoint {
+                        x,
+                        z,
+                        distance_from_start_m: x,
+                        slope_rad,
+                        ..TrackPoint::default()
+                    }
+                })
+                .collect(),
+            ..create_test_track()
+        };
+        let input = PlayerInputData {
+            throttle: 0.0,
+            brake: 0.0,
+            steering: 0.0,
+            gear: None,
+            clutch: None,
+        };
+        let mut state = create_test_car_state();
+        state.pos_x = 80.0;
+        state.vel_x = 40.0;
+        state.speed_mps = 40.0;
+
+        let mut flew = false;
+        let mut highest_above_road: f32 = 0.0;
+        for _ in 0..(240 * 4) {
+            update_car_3d(&mut state, &config, &input, &track, 1.0 / 240.0);
+            if state.is_airborne {
+                flew = true;
+                let road = get_track_context(&state, &track).elevation;
+                highest_above_road = highest_above_road.max(state.pos_z - road);
+            }
+        }
+
+        assert!(flew, "Car should leave the road over the crest");
+        assert!(highest_above_road > 1.0, "Car should clear the road: {}", highest_above_road);
+        assert!(!state.is_airborne && !state.is_overturned, "Car should be back on its wheels");
+        assert!(state.pitch_rad < 0.0, "Car should follow the road down");
+    }
+
+    #[test]
+    fn test_hard_cornering_tips_a_tall_car_over() {
+        let mut config = create_test_config();
+        config.cog_height_m = 1.2;
+        let weight = config.mass_kg * GRAVITY;
+        let dt = 1.0 / 240.0;
+
+        // A force at the CoG short of the car's weight about the outer wheels leaves it flat
+        let mut state = create_test_car_state();
+        for _ in 0..240 {
+            update_tip(&mut state, &config, weight * 0.5, weight, dt);
+        }
+        assert_eq!(state.tip_rad, 0.0);
+
+        // Cornering left hard enough lifts the left wheels and rolls the car onto its right side
+        for _ in 0..(240 * 3) {
+            update_tip(&mut state, &config, weight * 1.5, weight, dt);
+            if state.is_overturned {
+                break;
+            }
+        }
+        assert!(state.is_overturned);
+        assert!(state.tip_rad < -1.5, "Right side down: {}", state.tip_rad);
+        assert!(state.damage.right_damage_percent > 0.0);
+
+        // Slides to a stop on its side and is out
+        state.vel_x = 10.0;
+        for _ in 0..480 {
+            slide_overturned(&mut state, dt);
+        }
+        assert_eq!(state.speed_mps, 0.0);
+        assert!(!state.damage.is_drivable);
+    }
+
+    #[test]
+    fn test_landing_on_two_wheels_drops_back_onto_four() {
+        let config = create_test_config();
+        let weight = config.mass_kg * GRAVITY;
+        let mut state = create_test_car_state();
+        state.tip_rad = 0.2;
+        for _ in 0..240 {
+            update_tip(&mut state, &config, 0.0, weight, 1.0 / 240.0);
+        }
+        assert_eq!(state.tip_rad, 0.0);
+        assert!(!state.is_overturned);
+    }
+
+    #[test]
+    fn test_gravel_trap_costs_more_speed_than_grass() {
+        let config = create_test_config();
+        let straight = |runoff_surface: SurfaceType| TrackConfig {
+            centerline: (0..40)
+                .map(|i| TrackPoint {
+                    x: i as f32 * 10.0,
+                    distance_from_start_m: i as f32 * 10.0,
+                    runoff_surface,
+                    ..TrackPoint::default()
+                })
+                .collect(),
+            ..create_test_track()
+        };
+        let input = PlayerInputData {
+            throttle: 0.0,
+            brake: 0.0,
+            steering: 0.0,
+            gear: None,
+            clutch: None,
+        };
+        let dt = 1.0 / 240.0;
+
+        // Coast for two seconds, either on the asphalt or 12 m wide of the centerline
+        let coast = |runoff_surface: SurfaceType, lateral_m: f32| {
+            let track = straight(runoff_surface);
+            let mut state = create_test_car_state();
+            state.pos_x = 20.0;
+            state.pos_y = lateral_m;
+            state.vel_x = 30.0;
+            state.speed_mps = 30.0;
+            for _ in 0..(240 * 2) {
+                update_car_3d(&mut state, &config, &input, &track, dt);
+            }
+            state
+        };
+
+        let asphalt = coast(SurfaceType::Gravel, 0.0);
+        let grass = coast(SurfaceType::Grass, 12.0);
+        let gravel = coast(SurfaceType::Gravel, 12.0);
+        assert_eq!(asphalt.current_surface, SurfaceType::Asphalt);
+        assert_eq!(gravel.current_surface, SurfaceType::Gravel);
+        assert!(!gravel.is_on_track);
+
+        assert!(grass.speed_mps < asphalt.speed_mps);
+        assert!(
+            gravel.speed_mps < grass.speed_mps * 0.5,
+            "Gravel {} should scrub far more speed than grass {}",
+            gravel.speed_mps,
+            grass.speed_mps
+        );
+        assert!(gravel.surface_grip_modifier < asphalt.surface_grip_modifier);
+
+        assert_eq!(grass.damage.front_damage_percent, 0.0);
+        assert!(gravel.damage.front_damage_percent > 0.0);
+        assert!(gravel.damage.is_drivable);
+    }
+
+    #[test]
+    fn test_update_car_steering() {
+        let mut state = create_test_car_state();
+        state.vel_x = 10.0;
+        state.speed_mps = 10.0;
+        
+        let config = create_test_config();
+        let track = create_test_track();
+        let input = PlayerInputData {
+            throttle: 0.5,
+            brake: 0.0,
+            steering: 0.5,
+            gear: None,
+            clutch: None,
+        };
+
+        let dt = 1.0 / 240.0;
+
+        update_car_3d(&mut state, &config, &input, &track, dt);
+
+        // Angular velocity should change with steering
+        assert!(state.angular_vel_yaw.abs() > 0.0 || state.yaw_rad.abs() > 0.0001,
+            "Steering should cause yaw change");
+    }
+
+    #[test]
+    fn test_collision_detection() {
+        let config = create_test_config();
+        let grid_slot1 = GridSlot { position: 1, x: 0.0, y: 0.0, z: 0.0, yaw_rad: 0.0 };
+        let grid_slot2 = GridSlot { position: 2, x: 1.0, y: 0.0, z: 0.0, yaw_rad: 0.0 };
+        
+        let mut states = vec![
+            CarState::new(PlayerId::new_v4(), config.id, &grid_slot1),
+            CarState::new(PlayerId::new_v4(), config.id, &grid_slot2),
+        ];
+        states[0].speed_mps = 10.0;
+        states[1].speed_mps = 10.0;
+
+        let mut configs = HashMap::new();
+        configs.insert(config.id, config.clone());
+
+        resolve_car_collisions(&mut states, &configs, &CollisionSettings::default());
+
+        assert!(states[0].is_colliding, "Car 1 should be colliding");
+        assert!(states[1].is_colliding, "Car 2 should be colliding");
+    }
+
+    #[test]
+    fn test_ghost_cars_pass_through_each_other_but_not_walls() {
+        let config = create_test_config();
+        let slot = GridSlot { position: 1, x: 0.0, y: 0.0, z: 0.0, yaw_rad: 0.0 };
+        let mut states = vec![
+            CarState::new(PlayerId::new_v4(), config.id, &slot),
+            CarState::new(PlayerId::new_v4(), config.id, &GridSlot { position: 2, x: 1.0, ..slot }),
+        ];
+        states[0].is_ghost = true;
+        let configs = HashMap::from([(config.id, config.clone())]);
+
+        resolve_car_collisions(&mut states, &configs, &CollisionSettings::default());
+        assert!(!states[0].is_colliding && !states[1].is_colliding);
+        assert_eq!(states[1].pos_x, 1.0);
+
+        // A wall across the ghost's nose still stops it
+        states[0].vel_x = 20.0;
+        states[0].speed_mps = 20.0;
+        let walls = [WallSegment { x1: 1.5, y1: -5.0, x2: 1.5, y2: 5.0 }];
+        resolve_wall_collisions(&mut states[..1], &configs, &walls, &CollisionSettings::default());
+        assert!(states[0].vel_x < 20.0);
+    }
+
+    #[test]
+    fn test_parallel_cars_a_lane_apart_do_not_collide() {
+        let config = create_test_config();
+        // Side by side with a 30 cm gap; a box sized by car length would report contact
+        let gap = config.width_m + 0.3;
+        let grid_slot1 = GridSlot { position: 1, x: 0.0, y: 0.0, z: 0.0, yaw_rad: 0.0 };
+        let grid_slot2 = GridSlot { position: 2, x: 0.5, y: gap, z: 0.0, yaw_rad: 0.0 };
+        let mut states = vec![
+            CarState::new(PlayerId::new_v4(), config.id, &grid_slot1),
+            CarState::new(PlayerId::new_v4(), config.id, &grid_slot2),
+        ];
+        let mut configs = HashMap::new();
+        configs.insert(config.id, config.clone());
+
+        resolve_car_collisions(&mut states, &configs, &CollisionSettings::default());
+        assert!(!states[0].is_colliding && !states[1].is_colliding);
+
+        // Turning one car across the gap brings its corner into the other
+        states[1].yaw_rad = 0.5;
+        states[1].pos_y = config.width_m;
+        resolve_car_collisions(&mut states, &configs, &CollisionSettings::default());
+        assert!(states[0].is_colliding && states[1].is_colliding);
+    }
+
+    #[test]
+    fn test_tapered_hull_misses_where_box_would_hit() {
+        let boxed = create_test_config();
+        // Same length and width, but the nose narrows to a third of the width
+        let (hl, hw) = (boxed.length_m / 2.0, boxed.width_m / 2.0);
+        let tapered = CarConfig {
+            id: CarConfigId::new_v4(),
+            collision_shape: CollisionShape::Hull(vec![[hl, -hw / 3.0], [hl, hw / 3.0], [0.0, hw], [-hl, hw], [-hl, -hw], [0.0, -hw]]),
+            ..create_test_config()
+        };
+        let mut configs = HashMap::new();
+        configs.insert(boxed.id, boxed.clone());
+        configs.insert(tapered.id, tapered.clone());
+
+        // A car alongside the front corner, overlapping the box outline by 10 cm
+        let ahead = GridSlot { position: 1, x: 0.0, y: 0.0, z: 0.0, yaw_rad: 0.0 };
+        let alongside = GridSlot { position: 2, x: boxed.length_m - 0.3, y: boxed.width_m - 0.1, z: 0.0, yaw_rad: 0.0 };
+        let run = |config_id: CarConfigId| {
+            let mut states = vec![
+                CarState::new(PlayerId::new_v4(), config_id, &ahead),
+                CarState::new(PlayerId::new_v4(), boxed.id, &alongside),
+            ];
+            resolve_car_collisions(&mut states, &configs, &CollisionSettings::default());
+            states[0].is_colliding
+        };
+
+        assert!(run(boxed.id));
+        assert!(!run(tapered.id), "The tapered nose should clear the other car");
+    }
+
+    #[test]
+    fn test_capsules_touch_wheel_to_wheel() {
+        // One rounded bar across each axle, sticking out past the bodywork
+        let config = CarConfig {
+            collision_shape: CollisionShape::Capsules(vec![
+                CollisionCapsule { from: [1.4, 0.6], to: [1.4, -0.6], radius_m: 0.4 },
+                CollisionCapsule { from: [-1.4, 0.6], to: [-1.4, -0.6], radius_m: 0.4 },
+            ]),
+            ..create_test_config()
+        };
+        let mut configs = HashMap::new();
+        configs.insert(config.id, config.clone());
+
+        // Wheels 10 cm apart, then 10 cm overlapping
+        for (gap, touching) in [(2.1, false), (1.9, true)] {
+            let slot1 = GridSlot { position: 1, x: 0.0, y: 0.0, z: 0.0, yaw_rad: 0.0 };
+            let slot2 = GridSlot { position: 2, x: 0.0, y: gap, z: 0.0, yaw_rad: 0.0 };
+            let mut states = vec![
+                CarState::new(PlayerId::new_v4(), config.id, &slot1),
+                CarState::new(PlayerId::new_v4(), config.id, &slot2),
+            ];
+            states[1].vel_y = -2.0;
+            resolve_car_collisions(&mut states, &configs, &CollisionSettings::default());
+
+            assert_eq!(states[0].is_colliding, touching);
+            if touching {
+                assert!(states[0].collision_normal_y < -0.99, "Cars should be pushed apart sideways");
+                assert!(states[0].vel_y < 0.0 && states[1].vel_y > -2.0);
+                assert!(states[0].angular_vel_yaw.abs() < 1e-4, "Both axles touch evenly, so nothing spins");
+                assert!(states[0].damage.left_damage_percent > 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_hit_near_the_nose_on_the_flank_damages_the_side() {
+        let config = create_test_config();
+        let state = create_test_car_state();
+        // On the left flank, well forward of center but short of the corner
+        let r = (config.length_m / 2.0 * 0.7, config.width_m / 2.0);
+        let angle = hit_angle(&state, &config, r);
+        assert!((PI / 4.0..3.0 * PI / 4.0).contains(&angle));
+    }
+
+    #[test]
+    fn test_off_center_hit_transfers_momentum_and_spin() {
+        let config = create_test_config();
+        let mut configs = HashMap::new();
+        configs.insert(config.id, config.clone());
+
+        // A stationary car is tapped on its left rear corner by a car moving at 10 m/s
+        let grid_slot1 = GridSlot { position: 1, x: 0.0, y: 0.0, z: 0.0, yaw_rad: 0.0 };
+        let grid_slot2 = GridSlot { position: 2, x: -config.length_m + 0.05, y: 1.0, z: 0.0, yaw_rad: 0.0 };
+        let mut states = vec![
+            CarState::new(PlayerId::new_v4(), config.id, &grid_slot1),
+            CarState::new(PlayerId::new_v4(), config.id, &grid_slot2),
+        ];
+        states[1].vel_x = 10.0;
+        states[1].speed_mps = 10.0;
+
+        resolve_car_collisions(&mut states, &configs, &CollisionSettings::default());
+
+        let (hit, hitter) = (&states[0], &states[1]);
+        assert!(hit.is_colliding);
+        assert!(hit.vel_x > 0.0, "Struck car should be pushed forward");
+        assert!(hitter.vel_x < 10.0, "Striking car should slow down");
+        let momentum = (hit.vel_x + hitter.vel_x) * config.mass_kg;
+        assert!((momentum - 10.0 * config.mass_kg).abs() < 1.0, "Momentum should be conserved");
+        assert!(hit.angular_vel_yaw < 0.0, "A push on the left rear swings the nose right");
+        assert!(hitter.angular_vel_yaw < 0.0, "Pushing the hitter's right front back turns it right too");
+
+        // Without restitution the contact point stops closing instead of bouncing apart
+        let mut dead = vec![
+            CarState::new(states[0].player_id, config.id, &grid_slot1),
+            CarState::new(states[1].player_id, config.id, &grid_slot2),
+        ];
+        dead[1].vel_x = 10.0;
+        resolve_car_collisions(&mut dead, &configs, &CollisionSettings { restitution: 0.0, friction: 0.0 });
+        assert!(dead[0].vel_x < hit.vel_x);
+    }
+
+    #[test]
+    fn test_collision_outcome_ignores_input_order() {
+        let config = create_test_config();
+        let mut configs = HashMap::new();
+        configs.insert(config.id, config.clone());
+
+        // Three cars piled into each other, so the order pairs are resolved in matters
+        let slots = [
+            GridSlot { position: 1, x: 0.0, y: 0.0, z: 0.0, yaw_rad: 0.0 },
+            GridSlot { position: 2, x: 3.0, y: 0.4, z: 0.0, yaw_rad: 0.3 },
+            GridSlot { position: 3, x: 5.5, y: -0.2, z: 0.0, yaw_rad: -0.2 },
+        ];
+        let cars: Vec<CarState> = slots
+            .iter()
+            .enumerate()
+            .map(|(i, slot)| {
+                let mut state = CarState::new(PlayerId::new_v4(), config.id, slot);
+                state.vel_x = 20.0 - i as f32 * 5.0;
+                state
+            })
+            .collect();
+
+        let mut forward = cars.clone();
+        let mut reversed: Vec<CarState> = cars.iter().rev().cloned().collect();
+        resolve_car_collisions(&mut forward, &configs, &CollisionSettings::default());
+        resolve_car_collisions(&mut reversed, &configs, &CollisionSettings::default());
+        reversed.reverse();
+
+        assert!(forward.iter().all(|car| car.is_colliding));
+        for (a, b) in forward.iter().zip(&reversed) {
+            assert_eq!((a.pos_x, a.pos_y, a.vel_x, a.vel_y), (b.pos_x, b.pos_y, b.vel_x, b.vel_y));
+            assert_eq!(a.angular_vel_yaw, b.angular_vel_yaw);
+        }
+    }
+
+    #[test]
+    fn test_car_driven_into_wall_stays_inside() {
+        let config = create_test_config();
+        let mut configs = HashMap::new();
+        configs.insert(config.id, config.clone());
+
+        // Straight track along +x with boundary walls 22.5 m either side of the centerline
+        let centerline: Vec<TrackPoint> = (0..40)
+            .map(|i| TrackPoint {
+                x: i as f32 * 10.0,
+                distance_from_start_m: i as f32 * 10.0,
+                ..TrackPoint::default()
+            })
+            .collect();
+        let track = TrackConfig {
+            walls: WallSegment::boundary(&centerline, 15.0, false),
+            centerline,
+            ..create_test_track()
+        };
+        let input = PlayerInputData {
+            throttle: 1.0,
+            brake: 0.0,
+            steering: 0.0,
+            gear: None,
+            clutch: None,
+        };
+        let dt = 1.0 / 240.0;
+
+        // Aim across the runoff at the left wall
+        let mut state = CarState::new(
+            PlayerId::new_v4(),
+            config.id,
+            &GridSlot { position: 1, x: 100.0, y: 0.0, z: 0.0, yaw_rad: 1.2 },
+        );
+        state.vel_x = 30.0 * 1.2f32.cos();
+        state.vel_y = 30.0 * 1.2f32.sin();
+        state.speed_mps = 30.0;
+
+        let mut hit = false;
+        for _ in 0..(240 * 4) {
+            update_car_3d(&mut state, &config, &input, &track, dt);
+            let mut states = vec![state];
+            resolve_wall_collisions(&mut states, &configs, &track.walls, &CollisionSettings::default());
+            state = states.pop().unwrap();
+            hit |= state.is_colliding;
+            assert!(state.pos_y < 22.5, "Car escaped through the wall at y = {}", state.pos_y);
+        }
+
+        assert!(hit);
+        let damage = &state.damage;
+        assert!(damage.front_damage_percent + damage.left_damage_percent + damage.right_damage_percent > 0.0);
+    }
+
+    #[test]
+    fn test_tick_timing_splits_slow_ticks() {
+        assert_eq!(TickTiming::new(240, 0).substeps, 1);
+        assert_eq!(TickTiming::new(480, 0).substeps, 1);
+        assert_eq!(TickTiming::new(60, 0).substeps, 4);
+        assert_eq!(TickTiming::new(100, 0).substeps, 3);
+        assert_eq!(TickTiming::new(60, 2).substeps, 2, "An explicit step count is kept");
+        assert!(TickTiming::new(100, 0).step_dt() <= 1.0 / 240.0);
+
+        let timing = TickTiming::new(60, 0);
+        assert_eq!(timing.ticks(10), 600);
+        assert_eq!(timing.ticks_to_ms(90), 1500);
+    }
+
+    #[test]
+    fn test_track_progress_update() {
+        let track = create_test_track();
+        let mut state = create_test_car_state();
+        state.pos_x = track.centerline[1].x;
+        state.pos_y = track.centerline[1].y;
+
+        update_track_progress_3d(&mut state, &track, 0, TickTiming::default());
+
+        assert!(state.track_progress > 0.0, "Track progress should be positive");
+    }
+
+    #[test]
+    fn test_aerodynamic_forces() {
+        let mut state = create_test_car_state();
+        state.speed_mps = 50.0;  // 180 km/h
+        let config = create_test_config();
+
+        let (drag, df_front, df_rear) = calculate_aerodynamic_forces(&state, &config);
+
+        assert!(drag > 0.0, "Drag should be positive at speed");
+        assert!(df_front > 0.0, "Front downforce should be positive");
+        assert!(df_rear > 0.0, "Rear downforce should be positive");
+    }
+
+    #[test]
+    fn test_slipstream_behind_lead_car() {
+        let config = create_test_config();
+        let mut configs = HashMap::new();
+        configs.insert(config.id, config.clone());
+
+        let mut lead = create_test_car_state();
+        lead.car_config_id = config.id;
+        lead.speed_mps = 60.0;
+        let mut follower = lead.clone();
+        follower.player_id = PlayerId::new_v4();
+        follower.pos_x = -10.0;
+        let mut alongside = lead.clone();
+        alongside.player_id = PlayerId::new_v4();
+        alongside.pos_x = -10.0;
+        alongside.pos_y = 5.0;
+
+        let mut states = vec![lead.clone(), follower, alongside];
+        update_slipstream(&mut states, &configs);
+
+        assert_eq!(states[0].draft_factor, 0.0, "Lead car is in clean air");
+        assert!(states[1].draft_factor > 0.5);
+        assert_eq!(states[1].drafting_behind, Some(lead.player_id));
+        assert_eq!(states[2].draft_factor, 0.0, "Car alongside is outside the wake");
+
+        let (clean_drag, clean_front, _) = calculate_aerodynamic_forces(&states[0], &config);
+        let (tow_drag, tow_front, _) = calculate_aerodynamic_forces(&states[1], &config);
+        assert!(tow_drag < clean_drag, "Slipstream should cut drag");
+        assert!(tow_front < clean_front, "Slipstream should cost downforce");
+    }
+
+    #[test]
+    fn test_weight_transfer() {
+        let config = create_test_config();
+        let total_weight = config.mass_kg * GRAVITY;
+        
+        // Under braking (negative longitudinal accel)
+        let (long_transfer, _, _) = calculate_weight_transfer(&config, -10.0, 0.0, total_weight);
+        assert!(long_transfer < 0.0, "Weight should transfer forward under braking");
+        
+        // Under acceleration
+        let (long_transfer, _, _) = calculate_weight_transfer(&config, 5.0, 0.0, total_weight);
         assert!(long_transfer > 0.0, "Weight should transfer rearward under acceleration");
     }
 
+    /// Run the suspension to steady state under constant weight transfer
+    fn settle_suspension(config: &CarConfig, transfer: (f32, f32, f32)) -> (CarState, [f32; 4]) {
+        let mut state = create_test_car_state();
+        let weight = config.mass_kg * GRAVITY;
+        let static_split = calculate_static_weight_distribution(config, weight);
+        let mut loads = [0.0; 4];
+        for _ in 0..2400 {
+            loads = update_suspension(&mut state, config, static_split, (0.0, 0.0), transfer, 1.0 / 240.0);
+        }
+        (state, loads)
+    }
+
+    #[test]
+    fn test_suspension_braking_loads_front() {
+        let config = create_test_config();
+        let weight = config.mass_kg * GRAVITY;
+        let transfer = calculate_weight_transfer(&config, -10.0, 0.0, weight);
+        let (state, loads) = settle_suspension(&config, transfer);
+
+        let (static_front, _) = calculate_static_weight_distribution(&config, weight);
+        assert!(state.chassis.pitch_rad > 0.0, "Car should pitch nose down under braking");
+        assert!((loads[0] + loads[1] - (static_front - transfer.0)).abs() < 10.0);
+        assert!((loads.iter().sum::<f32>() - weight).abs() < 10.0, "Total load should match weight");
+        assert!(state.suspension.front_left_travel_m > state.suspension.rear_left_travel_m);
+    }
+
+    #[test]
+    fn test_anti_roll_bar_shifts_lateral_transfer() {
+        let weight = create_test_config().mass_kg * GRAVITY;
+        let front_share = |anti_roll_bar_front: f32| {
+            let mut config = create_test_config();
+            config.suspension.anti_roll_bar_front = anti_roll_bar_front;
+            let transfer = calculate_weight_transfer(&config, 0.0, 8.0, weight);
+            let (state, loads) = settle_suspension(&config, transfer);
+            assert!(state.chassis.roll_rad < 0.0, "Car should roll onto its left side");
+            (loads[0] - loads[1]) / ((loads[0] - loads[1]) + (loads[2] - loads[3]))
+        };
+
+        assert!(front_share(60000.0) > front_share(0.0), "Stiffer front bar should carry more transfer");
+    }
+
     #[test]
     fn test_tire_forces() {
         let tire_config = TireConfig::default();
@@ -1231,6 +3424,149 @@ mod tests {
         assert!(fy.abs() > 0.0, "Should produce lateral force");
     }
 
+    #[test]
+    fn test_slip_curves_peak_at_optimal_slip() {
+        let tire_config = TireConfig {
+            longitudinal_curvature: 0.5,
+            lateral_curvature: -0.5,
+            ..Default::default()
+        };
+        let load = tire_config.nominal_load_n;
+
+        let (peak_fx, _) = calculate_tire_forces(load, tire_config.optimal_slip_ratio, 0.0, 1.0, &tire_config);
+        assert!((peak_fx - load).abs() < 1.0, "Peak longitudinal force should be mu * load");
+        let (below, _) = calculate_tire_forces(load, tire_config.optimal_slip_ratio * 0.5, 0.0, 1.0, &tire_config);
+        let (beyond, _) = calculate_tire_forces(load, tire_config.optimal_slip_ratio * 3.0, 0.0, 1.0, &tire_config);
+        assert!(below < peak_fx && beyond < peak_fx, "Force should fall off either side of the peak");
+
+        let (_, peak_fy) = calculate_tire_forces(load, 0.0, tire_config.optimal_slip_angle_rad, 1.0, &tire_config);
+        assert!((peak_fy - load).abs() < 1.0, "Peak lateral force should be mu * load");
+    }
+
+    #[test]
+    fn test_abs_keeps_wheels_from_locking() {
+        let config = create_test_config();
+        let optimal = config.tire_config.optimal_slip_ratio;
+        let stamp = |aids: DriverAids, config: &CarConfig| {
+            let mut state = create_test_car_state();
+            state.vel_x = 30.0;
+            state.speed_mps = 30.0;
+            let input = PlayerInputData { brake: 1.0, ..Default::default() };
+            let shaped = apply_driver_aids(&mut state, config, &input, aids);
+            update_car_3d(&mut state, config, &shaped, &create_test_track(), 1.0 / 240.0);
+            (shaped.brake, state.tires.front_left.slip_ratio, state.aids_active.abs)
+        };
+
+        let (brake, slip, active) = stamp(DriverAids::default(), &config);
+        assert_eq!(brake, 1.0);
+        assert!(slip < -optimal * 2.0, "Full brakes should lock the fronts, got slip {}", slip);
+        assert!(!active);
+
+        let abs = DriverAids { abs: AidLevel::High, ..Default::default() };
+        let (brake, slip, active) = stamp(abs, &config);
+        assert!(brake < 1.0 && active);
+        assert!(slip >= -optimal * 1.01, "ABS should hold slip at the peak, got {}", slip);
+
+        // A car without ABS hardware ignores the setting
+        let no_abs = CarConfig { abs_enabled: false, ..create_test_config() };
+        assert_eq!(stamp(abs, &no_abs).0, 1.0);
+    }
+
+    #[test]
+    fn test_traction_control_limits_wheelspin() {
+        let config = create_test_config();
+        let launch = |aids: DriverAids| {
+            let mut state = create_test_car_state();
+            state.vel_x = 5.0;
+            state.speed_mps = 5.0;
+            state.engine_rpm = 2000.0;
+            // Clutch fully engaged, so the engine drives the wheels directly
+            let input = PlayerInputData { throttle: 1.0, clutch: Some(1.0), ..Default::default() };
+            let shaped = apply_driver_aids(&mut state, &config, &input, aids);
+            update_car_3d(&mut state, &config, &shaped, &create_test_track(), 1.0 / 240.0);
+            (state.tires.rear_left.slip_ratio, state.vel_x, state.aids_active.traction_control)
+        };
+
+        let (spin, speed_off, active) = launch(DriverAids::default());
+        assert!(!active);
+        let tc = DriverAids { traction_control: AidLevel::High, ..Default::default() };
+        let (held, speed_tc, active) = launch(tc);
+        assert!(active);
+        assert!(held < spin && held <= config.tire_config.optimal_slip_ratio * 1.01);
+        assert!(speed_tc >= speed_off, "Holding peak slip should not cost drive");
+    }
+
+    #[test]
+    fn test_stability_control_backs_off_throttle_in_a_slide() {
+        let config = create_test_config();
+        let mut state = create_test_car_state();
+        state.vel_x = 20.0;
+        state.speed_mps = 20.0;
+        // Rotating hard with the wheel straight
+        state.angular_vel_yaw = 1.0;
+        let input = PlayerInputData { throttle: 1.0, ..Default::default() };
+
+        let low = DriverAids { stability_control: AidLevel::Low, ..Default::default() };
+        let high = DriverAids { stability_control: AidLevel::High, ..Default::default() };
+        let throttle_low = apply_driver_aids(&mut state, &config, &input, low).throttle;
+        let throttle_high = apply_driver_aids(&mut state, &config, &input, high).throttle;
+        assert!(state.aids_active.stability_control);
+        assert!(throttle_high < throttle_low && throttle_low < 1.0);
+        assert_eq!(state.aids, high);
+
+        // A gentle turn that matches the steering is left alone
+        state.angular_vel_yaw = 0.2;
+        let steering = PlayerInputData { steering: 0.2, ..input };
+        assert_eq!(apply_driver_aids(&mut state, &config, &steering, high).throttle, 1.0);
+    }
+
+    #[test]
+    fn test_tire_pressure_away_from_optimal_costs_grip() {
+        let tires = TireConfig::default();
+        assert_eq!(pressure_grip_factor(&tires, tires.cold_pressure_front_kpa), 1.0);
+
+        let under = pressure_grip_factor(&tires, tires.cold_pressure_front_kpa - 40.0);
+        let over = pressure_grip_factor(&tires, tires.cold_pressure_front_kpa + 20.0);
+        assert!(under < over && over < 1.0);
+
+        // Telemetry pressures start from the cold setting
+        let config = CarConfig {
+            tire_config: TireConfig { cold_pressure_front_kpa: 180.0, ..tires },
+            ..create_test_config()
+        };
+        let mut state = create_test_car_state();
+        update_car_3d(&mut state, &config, &PlayerInputData::default(), &create_test_track(), 1.0 / 240.0);
+        assert!(state.tires.front_left.pressure_kpa < state.tires.rear_left.pressure_kpa - 25.0);
+    }
+
+    #[test]
+    fn test_load_sensitivity() {
+        let tire_config = TireConfig::default();
+        let slip = tire_config.optimal_slip_ratio;
+
+        let (nominal, _) = calculate_tire_forces(tire_config.nominal_load_n, slip, 0.0, 1.0, &tire_config);
+        let (doubled, _) = calculate_tire_forces(tire_config.nominal_load_n * 2.0, slip, 0.0, 1.0, &tire_config);
+        assert!(doubled > nominal, "More load should still give more force");
+        assert!(doubled < nominal * 2.0, "Grip coefficient should drop as load rises");
+
+        let insensitive = TireConfig { load_sensitivity: 0.0, ..Default::default() };
+        let (linear, _) = calculate_tire_forces(insensitive.nominal_load_n * 2.0, slip, 0.0, 1.0, &insensitive);
+        assert!((linear - nominal * 2.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_combined_slip_trades_lateral_for_longitudinal() {
+        let tire_config = TireConfig::default();
+        let load = 3000.0;
+        let angle = tire_config.optimal_slip_angle_rad;
+
+        let (_, pure_fy) = calculate_tire_forces(load, 0.0, angle, 1.0, &tire_config);
+        let (fx, combined_fy) = calculate_tire_forces(load, -tire_config.optimal_slip_ratio, angle, 1.0, &tire_config);
+        assert!(fx < 0.0, "Braking slip should give a braking force");
+        assert!(combined_fy.abs() < pure_fy.abs(), "Braking should reduce cornering force");
+        assert!((fx * fx + combined_fy * combined_fy).sqrt() <= pure_fy.abs() * 1.001, "Total force stays within the friction limit");
+    }
+
     #[test]
     fn test_ackermann_steering() {
         let wheelbase = 2.7;
@@ -1298,8 +3634,8 @@ mod tests {
         let grid_slot2 = GridSlot { position: 2, x: 1.0, y: 0.0, z: 0.0, yaw_rad: 0.0 };
         
         let mut states = vec![
-            CarState::new(Uuid::new_v4(), config.id, &grid_slot1),
-            CarState::new(Uuid::new_v4(), config.id, &grid_slot2),
+            CarState::new(PlayerId::new_v4(), config.id, &grid_slot1),
+            CarState::new(PlayerId::new_v4(), config.id, &grid_slot2),
         ];
         states[0].speed_mps = 30.0;
         states[0].vel_x = 30.0;
@@ -1309,7 +3645,7 @@ mod tests {
         let mut configs = HashMap::new();
         configs.insert(config.id, config.clone());
 
-        check_aabb_collisions_3d(&mut states, &configs);
+        resolve_car_collisions(&mut states, &configs, &CollisionSettings::default());
 
         // Check that damage was applied
         let total_damage_0 = states[0].damage.front_damage_percent 
@@ -1337,4 +3673,57 @@ mod tests {
         
         assert!(state.speed_mps > 0.0, "Legacy API should work");
     }
+
+    #[test]
+    fn test_cold_start_reduces_grip_and_braking() {
+        let mut state = create_test_car_state();
+        let settings = WarmupSettings::from_preset(RealismPreset::Simulation, 15.0);
+
+        apply_cold_start(&mut state, &settings);
+
+        assert_eq!(state.tires.front_left.temperature_c, 15.0);
+        assert_eq!(state.brakes.rear_right_temp_c, 15.0);
+        assert!(state.tire_grip_factor < 1.0);
+        assert!(state.brake_efficiency < 1.0);
+    }
+
+    #[test]
+    fn test_tires_and_brakes_warm_up_when_driven() {
+        let mut state = create_test_car_state();
+        let config = create_test_config();
+        let settings = WarmupSettings::from_preset(RealismPreset::Standard, 20.0);
+        apply_cold_start(&mut state, &settings);
+
+        let cold_grip = state.tire_grip_factor;
+        let cold_brakes = state.brake_efficiency;
+
+        // Sustained running at speed with periodic heavy braking
+        state.speed_mps = 40.0;
+        state.weight_front_left_n = config.mass_kg * GRAVITY / 4.0;
+        state.weight_front_right_n = config.mass_kg * GRAVITY / 4.0;
+        state.weight_rear_left_n = config.mass_kg * GRAVITY / 4.0;
+        state.weight_rear_right_n = config.mass_kg * GRAVITY / 4.0;
+        let dt = 1.0 / 240.0;
+        for tick in 0..(240 * 90) {
+            state.brake_input = if tick % 2400 < 240 { 1.0 } else { 0.0 };
+            update_warmup(&mut state, &config, &settings, dt);
+        }
+
+        assert!(state.tires.front_left.temperature_c > 70.0, "Tires should warm: {}", state.tires.front_left.temperature_c);
+        assert!(state.tire_grip_factor > cold_grip);
+        assert!(state.brake_efficiency > cold_brakes);
+    }
+
+    #[test]
+    fn test_casual_preset_has_no_cold_start() {
+        let mut state = create_test_car_state();
+        let config = create_test_config();
+        let settings = WarmupSettings::from_preset(RealismPreset::Casual, 10.0);
+
+        apply_cold_start(&mut state, &settings);
+        update_warmup(&mut state, &config, &settings, 1.0 / 240.0);
+
+        assert_eq!(state.tire_grip_factor, 1.0);
+        assert_eq!(state.brake_efficiency, 1.0);
+    }
 }
@@ -0,0 +1,549 @@
+//! Procedural circuit synthesizer
+//!
+//! Lays out a closed circuit from a few parameters: corners are placed at
+//! increasing angles around a centre, which keeps the loop from crossing
+//! itself, each corner is rounded off between its two straights and the
+//! result is scaled to the asked length. Hills follow a few smooth waves
+//! around the lap. A layout that turns too tightly or runs too close to
+//! another part of itself is thrown away and the next one from the same
+//! seed is tried, so a seed always gives the same circuit.
+//!
+//! Starting shapes are the plain layouts a track drawn by hand in the track
+//! editor begins from: an oval, a figure eight crossing over itself on a
+//! bridge, or a straight to bend into shape.
+
+use super::environment_presets;
+use super::noise::SplitMix64;
+use crate::data::TrackMetadata;
+use crate::track_import::{cumulative_lengths, resample, PathPoint};
+use crate::track_loader::{TrackFileFormat, TrackNode};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::{PI, TAU};
+use std::path::{Path, PathBuf};
+
+/// Folder below the tracks directory generated circuits are saved in
+pub const GENERATED_TRACKS_DIR: &str = "generated";
+
+/// Distance between the nodes written to the track file (m)
+const NODE_SPACING_M: f64 = 10.0;
+
+/// Tightest corner a generated circuit may have (m)
+const MIN_CORNER_RADIUS_M: f64 = 15.0;
+
+/// Steepest climb or descent (rise over run)
+const MAX_GRADE: f64 = 0.1;
+
+/// Shortest length allowed per corner (m)
+const MIN_LENGTH_PER_CORNER_M: f32 = 80.0;
+
+/// Layouts tried before giving up on a set of parameters
+const MAX_ATTEMPTS: usize = 200;
+
+/// Bezier samples per corner before the line is resampled
+const CORNER_SAMPLES: usize = 24;
+
+/// What to generate; every field has a default
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CircuitParams {
+    /// Lap length (m), 500-20000
+    pub length_m: f32,
+    /// Number of corners, 3-40
+    pub corners: u32,
+    /// How much of the line between two corners stays straight, 0-0.9
+    pub straights: f32,
+    /// Height between the lowest and highest point of the lap (m), 0-200;
+    /// lowered if it would make a slope steeper than 10%
+    pub elevation_variance_m: f32,
+    /// Track width (m), 6-30
+    pub width_m: f32,
+    /// Same seed and parameters, same circuit; `None` picks one
+    pub seed: Option<u32>,
+    /// Terrain around the track, one of the environment presets; `None` for none
+    pub environment: Option<String>,
+}
+
+impl Default for CircuitParams {
+    fn default() -> Self {
+        Self {
+            length_m: 3500.0,
+            corners: 10,
+            straights: 0.4,
+            elevation_variance_m: 15.0,
+            width_m: 12.0,
+            seed: None,
+            environment: Some("plains".to_string()),
+        }
+    }
+}
+
+impl CircuitParams {
+    pub fn validate(&self) -> Result<(), String> {
+        in_range("length_m", self.length_m, 500.0, 20_000.0)?;
+        in_range("straights", self.straights, 0.0, 0.9)?;
+        in_range("elevation_variance_m", self.elevation_variance_m, 0.0, 200.0)?;
+        in_range("width_m", self.width_m, 6.0, 30.0)?;
+        if !(3..=40).contains(&self.corners) {
+            return Err(format!("corners {} must be within 3-40", self.corners));
+        }
+        if self.length_m / (self.corners as f32) < MIN_LENGTH_PER_CORNER_M {
+            return Err(format!(
+                "{} corners need a lap of at least {:.0} m",
+                self.corners,
+                self.corners as f32 * MIN_LENGTH_PER_CORNER_M
+            ));
+        }
+        known_environment(self.environment.as_deref())
+    }
+}
+
+fn in_range(name: &str, value: f32, min: f32, max: f32) -> Result<(), String> {
+    if value.is_finite() && (min..=max).contains(&value) {
+        Ok(())
+    } else {
+        Err(format!("{} {} must be within {}-{}", name, value, min, max))
+    }
+}
+
+fn known_environment(environment: Option<&str>) -> Result<(), String> {
+    match environment {
+        Some(environment) if environment_presets::get_preset(environment).is_none() => {
+            let mut known: Vec<String> = environment_presets::load_presets().into_keys().collect();
+            known.sort();
+            Err(format!("Unknown environment {}; one of {}", environment, known.join(", ")))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Generate a circuit; the seed used is in its `metadata.terrain_seed`
+pub fn generate_circuit(name: &str, params: &CircuitParams) -> Result<TrackFileFormat, String> {
+    params.validate()?;
+    let seed = params.seed.unwrap_or_else(|| {
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default();
+        now.subsec_nanos() ^ now.as_secs() as u32
+    });
+    let mut rng = SplitMix64(seed as u64);
+
+    let nodes = (0..MAX_ATTEMPTS)
+        .map(|_| lay_out(params, &mut rng))
+        .find(|nodes| turns_gently(nodes) && keeps_clear(nodes, params.width_m as f64))
+        .ok_or_else(|| format!("No circuit found for seed {} in {} tries; try fewer corners or more length", seed, MAX_ATTEMPTS))?;
+
+    let length_m = lap_length(&nodes, true);
+    let metadata = TrackMetadata {
+        length_m: Some(length_m as f32),
+        description: Some(format!(
+            "Generated circuit: {} corners, {:.0}% straights, {:.0} m elevation, seed {}",
+            params.corners,
+            params.straights * 100.0,
+            params.elevation_variance_m,
+            seed
+        )),
+        category: Some("Generated".to_string()),
+        corners: Some(params.corners),
+        tags: vec!["generated".to_string()],
+        environment_type: params.environment.clone(),
+        terrain_seed: Some(seed),
+        ..Default::default()
+    };
+    Ok(track_file(name, &nodes, true, params.width_m, metadata))
+}
+
+/// Distance along the nodes, back round to the first on a closed lap
+fn lap_length(nodes: &[PathPoint], closed: bool) -> f64 {
+    let open = cumulative_lengths(nodes).last().copied().unwrap_or(0.0);
+    match nodes {
+        [first, .., last] if closed => open + distance(last, first),
+        _ => open,
+    }
+}
+
+/// A new asphalt track through `nodes`, with nothing else laid out yet
+fn track_file(name: &str, nodes: &[PathPoint], closed: bool, width_m: f32, metadata: TrackMetadata) -> TrackFileFormat {
+    TrackFileFormat {
+        name: name.to_string(),
+        track_id: Some(uuid::Uuid::new_v4().to_string()),
+        nodes: nodes
+            .iter()
+            .map(|node| TrackNode {
+                x: node[0] as f32,
+                y: node[1] as f32,
+                z: node[2] as f32,
+                width: None,
+                width_left: None,
+                width_right: None,
+                banking: None,
+                camber: None,
+                friction: None,
+                surface_type: Some("Asphalt".to_string()),
+                runoff_surface: None,
+            })
+            .collect(),
+        checkpoints: Vec::new(),
+        spawn_points: Vec::new(),
+        default_width: width_m,
+        closed_loop: closed,
+        raceline: Vec::new(),
+        metadata: Some(metadata),
+        runoff_width: None,
+        boundary_walls: None,
+        walls: Vec::new(),
+        pit_lane: None,
+        timing: None,
+    }
+}
+
+/// One candidate layout: nodes from 2/3 down the longest straight
+fn lay_out(params: &CircuitParams, rng: &mut SplitMix64) -> Vec<PathPoint> {
+    let n = params.corners as usize;
+    let stretch = rng.range(0.6, 1.0);
+    let corners: Vec<[f64; 2]> = (0..n)
+        .map(|i| {
+            // Jitter stays within half a slot, so angles keep increasing
+            let angle = (i as f64 + rng.range(-0.35, 0.35)) * TAU / n as f64;
+            let radius = rng.range(0.55, 1.0);
+            [radius * angle.cos(), radius * angle.sin() * stretch]
+        })
+        .collect();
+
+    // Each corner turns between points on its two straights, at most half of
+    // the shorter one away and less the more of the lap is straight
+    let (entries, exits): (Vec<[f64; 2]>, Vec<[f64; 2]>) = (0..n)
+        .map(|i| {
+            let (previous, corner, next) = (corners[(i + n - 1) % n], corners[i], corners[(i + 1) % n]);
+            let (into, out) = (distance(&previous, &corner), distance(&corner, &next));
+            let reach = 0.5 * (1.0 - params.straights as f64) * into.min(out) * rng.range(0.7, 1.0);
+            (toward(&corner, &previous, reach / into), toward(&corner, &next, reach / out))
+        })
+        .unzip();
+
+    let longest = (0..n).max_by(|&a, &b| {
+        distance(&exits[a], &entries[(a + 1) % n]).total_cmp(&distance(&exits[b], &entries[(b + 1) % n]))
+    });
+    let longest = longest.unwrap_or(0);
+    let mut line: Vec<PathPoint> = Vec::with_capacity(n * (CORNER_SAMPLES + 1) + 1);
+    let start = toward(&exits[longest], &entries[(longest + 1) % n], 2.0 / 3.0);
+    line.push([start[0], start[1], 0.0, 0.0]);
+    for k in 1..=n {
+        let i = (longest + k) % n;
+        for step in 0..=CORNER_SAMPLES {
+            let t = step as f64 / CORNER_SAMPLES as f64;
+            let point: [f64; 2] = std::array::from_fn(|axis| {
+                (1.0 - t) * (1.0 - t) * entries[i][axis] + 2.0 * (1.0 - t) * t * corners[i][axis] + t * t * exits[i][axis]
+            });
+            line.push([point[0], point[1], 0.0, 0.0]);
+        }
+    }
+
+    let unit_length = cumulative_lengths(&line).last().copied().unwrap_or(0.0) + distance(&line[line.len() - 1], &line[0]);
+    let scale = params.length_m as f64 / unit_length;
+    line.iter_mut().for_each(|point| {
+        point[0] *= scale;
+        point[1] *= scale;
+    });
+    let mut nodes = resample(&line, NODE_SPACING_M, true);
+    add_hills(&mut nodes, params.elevation_variance_m as f64, rng);
+    nodes
+}
+
+/// Three waves of random phase around the lap, spanning `variance` metres
+/// with the lowest point at 0
+fn add_hills(nodes: &mut [PathPoint], variance: f64, rng: &mut SplitMix64) {
+    let waves: Vec<(f64, f64)> = (1..=3).map(|k| (rng.range(0.2, 1.0) / k as f64, rng.range(0.0, TAU))).collect();
+    let count = nodes.len() as f64;
+    for (i, node) in nodes.iter_mut().enumerate() {
+        let around = i as f64 / count * TAU;
+        node[2] = waves.iter().enumerate().map(|(k, (amplitude, phase))| amplitude * ((k + 1) as f64 * around + phase).sin()).sum();
+    }
+    let (low, high) = nodes.iter().fold((f64::MAX, f64::MIN), |(low, high), node| (low.min(node[2]), high.max(node[2])));
+    let mut scale = if high > low { variance / (high - low) } else { 0.0 };
+    let steepest = (0..nodes.len())
+        .map(|i| {
+            let next = &nodes[(i + 1) % nodes.len()];
+            (next[2] - nodes[i][2]).abs() * scale / distance(&nodes[i], next).max(f64::EPSILON)
+        })
+        .fold(0.0, f64::max);
+    if steepest > MAX_GRADE {
+        scale *= MAX_GRADE / steepest;
+    }
+    nodes.iter_mut().for_each(|node| node[2] = (node[2] - low) * scale);
+}
+
+/// Height of the figure eight's bridge over the crossing (m)
+const BRIDGE_HEIGHT_M: f64 = 10.0;
+
+/// Points along a starting shape before it is resampled to its nodes
+const SHAPE_SAMPLES: usize = 2000;
+
+/// Closest nodes of a starting shape may be (m)
+const MIN_NODE_SPACING_M: f32 = 2.0;
+
+/// Layout a new track in the editor starts from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StartingShape {
+    /// Two straights joined by half circles
+    Oval,
+    /// Two loops, one end of the crossing on a bridge over the other
+    FigureEight,
+    /// An open straight
+    Blank,
+}
+
+/// What a starting shape is laid out to
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapeParams {
+    pub shape: StartingShape,
+    /// Lap length, or the straight's length for a blank track (m), 200-20000
+    pub length_m: f32,
+    /// Number of nodes, evenly spaced and at least 2 m apart; 4-2000
+    pub nodes: u32,
+    /// Track width (m), 6-30
+    pub width_m: f32,
+    /// Terrain around the track, one of the environment presets; `None` for none
+    pub environment: Option<String>,
+}
+
+impl Default for ShapeParams {
+    fn default() -> Self {
+        Self {
+            shape: StartingShape::Oval,
+            length_m: 2000.0,
+            nodes: 100,
+            width_m: 12.0,
+            environment: Some("plains".to_string()),
+        }
+    }
+}
+
+impl ShapeParams {
+    pub fn validate(&self) -> Result<(), String> {
+        in_range("length_m", self.length_m, 200.0, 20_000.0)?;
+        in_range("width_m", self.width_m, 6.0, 30.0)?;
+        if !(4..=2000).contains(&self.nodes) {
+            return Err(format!("nodes {} must be within 4-2000", self.nodes));
+        }
+        if self.length_m / (self.nodes as f32) < MIN_NODE_SPACING_M {
+            return Err(format!("{} nodes need a track of at least {:.0} m", self.nodes, self.nodes as f32 * MIN_NODE_SPACING_M));
+        }
+        known_environment(self.environment.as_deref())
+    }
+}
+
+/// A new track laid out in a starting shape, for editing into a real one
+pub fn generate_shape(name: &str, params: &ShapeParams) -> Result<TrackFileFormat, String> {
+    params.validate()?;
+    let closed = params.shape != StartingShape::Blank;
+    let mut line: Vec<PathPoint> = (0..SHAPE_SAMPLES)
+        .map(|i| {
+            let fraction = i as f64 / SHAPE_SAMPLES as f64;
+            match params.shape {
+                StartingShape::Oval => oval_point(fraction),
+                // Lemniscate of Gerono from between a loop's far end and
+                // the crossing, rising to the bridge on the second pass
+                StartingShape::FigureEight => {
+                    let t = (fraction + 0.125) * TAU;
+                    [t.cos(), t.sin() * t.cos(), BRIDGE_HEIGHT_M * (1.0 - t.sin()) / 2.0, 0.0]
+                }
+                StartingShape::Blank => [fraction, 0.0, 0.0, 0.0],
+            }
+        })
+        .collect();
+    if !closed {
+        line.push([1.0, 0.0, 0.0, 0.0]);
+    }
+
+    let scale = params.length_m as f64 / lap_length(&line, closed);
+    line.iter_mut().for_each(|point| {
+        point[0] *= scale;
+        point[1] *= scale;
+    });
+    // An open track has a node at either end
+    let steps = if closed { params.nodes } else { params.nodes - 1 };
+    let spacing = params.length_m as f64 / steps as f64;
+    let nodes = resample(&line, spacing, closed);
+
+    let shape = match params.shape {
+        StartingShape::Oval => "oval",
+        StartingShape::FigureEight => "figure eight",
+        StartingShape::Blank => "straight",
+    };
+    let metadata = TrackMetadata {
+        length_m: Some(lap_length(&nodes, closed) as f32),
+        description: Some(format!("New track started from a {:.0} m {}", params.length_m, shape)),
+        environment_type: params.environment.clone(),
+        ..Default::default()
+    };
+    Ok(track_file(name, &nodes, closed, params.width_m, metadata))
+}
+
+/// The point `fraction` of the way round an oval one unit wide, its
+/// straights as long as it is wide; from halfway down the lower straight,
+/// anticlockwise
+fn oval_point(fraction: f64) -> PathPoint {
+    let (radius, straight) = (0.5, 1.0);
+    let (half, bend) = (straight / 2.0, PI * radius);
+    let along = fraction * (2.0 * straight + 2.0 * bend);
+    // Angle round a bend entered at `from`
+    let around = |from: f64| (along - from) / radius;
+    let [x, y] = if along < half {
+        [along, -radius]
+    } else if along < half + bend {
+        let angle = around(half);
+        [half + radius * angle.sin(), -radius * angle.cos()]
+    } else if along < half + bend + straight {
+        [half - (along - half - bend), radius]
+    } else if along < half + 2.0 * bend + straight {
+        let angle = around(half + bend + straight);
+        [-half - radius * angle.sin(), radius * angle.cos()]
+    } else {
+        [along - 2.0 * bend - 2.0 * straight, -radius]
+    };
+    [x, y, 0.0, 0.0]
+}
+
+fn distance(a: &[f64], b: &[f64]) -> f64 {
+    (a[0] - b[0]).hypot(a[1] - b[1])
+}
+
+/// The point `fraction` of the way from `from` to `to`
+fn toward(from: &[f64; 2], to: &[f64; 2], fraction: f64) -> [f64; 2] {
+    [from[0] + (to[0] - from[0]) * fraction, from[1] + (to[1] - from[1]) * fraction]
+}
+
+/// No three neighbouring nodes on a circle tighter than the minimum radius
+fn turns_gently(nodes: &[PathPoint]) -> bool {
+    let n = nodes.len();
+    (0..n).all(|i| {
+        let (a, b, c) = (&nodes[(i + n - 1) % n], &nodes[i], &nodes[(i + 1) % n]);
+        let cross = (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]);
+        // Circumradius = product of the sides over four times the area
+        cross.abs() < f64::EPSILON || distance(a, b) * distance(b, c) * distance(a, c) / (2.0 * cross.abs()) >= MIN_CORNER_RADIUS_M
+    })
+}
+
+/// Parts of the lap far apart along it are at least three track widths apart
+fn keeps_clear(nodes: &[PathPoint], width: f64) -> bool {
+    let n = nodes.len();
+    let clearance = 3.0 * width;
+    let neighbours = ((clearance * 2.0 + 50.0) / NODE_SPACING_M).ceil() as usize;
+    (0..n).all(|i| {
+        (i + 1..n)
+            .filter(|&j| (j - i).min(n - (j - i)) > neighbours)
+            .all(|j| distance(&nodes[i], &nodes[j]) >= clearance)
+    })
+}
+
+/// Lowercase letters, digits and dashes, for file names
+fn slug(name: &str) -> String {
+    let slug: String = name.to_lowercase().chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '-' }).collect();
+    let slug = slug.split('-').filter(|part| !part.is_empty()).collect::<Vec<_>>().join("-");
+    if slug.is_empty() {
+        "circuit".to_string()
+    } else {
+        slug
+    }
+}
+
+/// Where a generated circuit named `name` is written in `dir`
+pub fn circuit_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}.yaml", slug(name)))
+}
+
+/// Write a generated circuit to `dir` and build its terrain cache next to
+/// it; the path of the track file
+pub fn save_circuit(dir: &Path, track: &TrackFileFormat) -> Result<PathBuf, String> {
+    let path = circuit_path(dir, &track.name);
+    if path.exists() {
+        return Err(format!("{} already exists", path.display()));
+    }
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let yaml = serde_yaml::to_string(track).map_err(|e| format!("Failed to serialize track: {}", e))?;
+    std::fs::write(&path, yaml).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    if track.metadata.as_ref().is_some_and(|metadata| metadata.environment_type.is_some()) {
+        super::terrain::process_track_for_terrain(&path)?;
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::track_loader::TrackLoader;
+
+    fn params(seed: u32) -> CircuitParams {
+        CircuitParams { seed: Some(seed), environment: None, ..Default::default() }
+    }
+
+    #[test]
+    fn test_generated_circuits_load_and_match_their_parameters() {
+        for seed in 0..5 {
+            let params = CircuitParams { length_m: 2800.0, corners: 8, elevation_variance_m: 12.0, ..params(seed) };
+            let track = generate_circuit("Generated", &params).unwrap();
+            let metadata = track.metadata.as_ref().unwrap();
+            assert!((metadata.length_m.unwrap() - 2800.0).abs() < 30.0, "length {:?}", metadata.length_m);
+            assert_eq!(metadata.terrain_seed, Some(seed));
+            let (low, high) = track.nodes.iter().fold((f32::MAX, f32::MIN), |(low, high), node| (low.min(node.z), high.max(node.z)));
+            assert_eq!(low, 0.0);
+            assert!(high <= 12.01, "height {}", high);
+
+            let loaded = TrackLoader::load_from_string(&serde_yaml::to_string(&track).unwrap());
+            assert!(loaded.is_ok(), "seed {}: {:?}", seed, loaded.err());
+        }
+    }
+
+    #[test]
+    fn test_same_seed_same_circuit() {
+        let node_positions = |track: TrackFileFormat| track.nodes.iter().map(|node| (node.x, node.y, node.z)).collect::<Vec<_>>();
+        let first = node_positions(generate_circuit("A", &params(42)).unwrap());
+        assert_eq!(first, node_positions(generate_circuit("B", &params(42)).unwrap()));
+        assert_ne!(first, node_positions(generate_circuit("A", &params(43)).unwrap()));
+        // A random seed is recorded so the circuit can be made again
+        let random = generate_circuit("C", &CircuitParams { seed: None, ..params(0) }).unwrap();
+        assert!(random.metadata.unwrap().terrain_seed.is_some());
+    }
+
+    #[test]
+    fn test_bad_parameters_are_refused() {
+        assert!(generate_circuit("X", &CircuitParams { corners: 2, ..params(1) }).is_err());
+        assert!(generate_circuit("X", &CircuitParams { length_m: 1000.0, corners: 20, ..params(1) }).is_err());
+        assert!(generate_circuit("X", &CircuitParams { straights: 1.0, ..params(1) }).is_err());
+        assert!(generate_circuit("X", &CircuitParams { environment: Some("moon".to_string()), ..params(1) }).is_err());
+        assert_eq!(slug("  Grand Prix #2 "), "grand-prix-2");
+    }
+
+    #[test]
+    fn test_starting_shapes_have_their_length_and_nodes() {
+        for shape in [StartingShape::Oval, StartingShape::FigureEight, StartingShape::Blank] {
+            let params = ShapeParams { shape, length_m: 1500.0, nodes: 60, environment: None, ..Default::default() };
+            let track = generate_shape("Shape", &params).unwrap();
+            assert_eq!(track.nodes.len(), 60, "{:?}", shape);
+            assert_eq!(track.closed_loop, shape != StartingShape::Blank);
+            let length = track.metadata.as_ref().unwrap().length_m.unwrap();
+            assert!((length - 1500.0).abs() < 15.0, "{:?} length {}", shape, length);
+
+            let loaded = TrackLoader::load_from_string(&serde_yaml::to_string(&track).unwrap());
+            assert!(loaded.is_ok(), "{:?}: {:?}", shape, loaded.err());
+        }
+
+        // The figure eight's second pass crosses over the first on the bridge
+        let eight = generate_shape("Eight", &ShapeParams { shape: StartingShape::FigureEight, ..Default::default() }).unwrap();
+        let crossing: Vec<f32> = eight.nodes.iter().filter(|node| node.x.hypot(node.y) < 15.0).map(|node| node.z).collect();
+        assert!(crossing.iter().any(|&z| z < 1.0) && crossing.iter().any(|&z| z > 9.0), "{:?}", crossing);
+
+        assert!(generate_shape("X", &ShapeParams { nodes: 3, ..Default::default() }).is_err());
+        assert!(generate_shape("X", &ShapeParams { length_m: 300.0, nodes: 200, ..Default::default() }).is_err());
+        assert!(generate_shape("X", &ShapeParams { environment: Some("moon".to_string()), ..Default::default() }).is_err());
+    }
+
+    #[test]
+    fn test_save_circuit_refuses_to_overwrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let track = generate_circuit("Test Ring", &params(7)).unwrap();
+        let path = save_circuit(dir.path(), &track).unwrap();
+        assert_eq!(path, dir.path().join("test-ring.yaml"));
+        assert!(TrackLoader::load_from_file(&path).is_ok());
+        assert!(save_circuit(dir.path(), &generate_circuit("Test Ring", &params(8)).unwrap()).is_err());
+    }
+}
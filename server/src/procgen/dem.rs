@@ -0,0 +1,336 @@
+//! Terrain from real elevation data
+//!
+//! Tracks imported from GPS traces or OpenStreetMap record where their
+//! x = 0, y = 0 lies on the globe. Given SRTM tiles or a GeoTIFF covering
+//! the area, the heightmap around such a track is sampled from the
+//! elevation model instead of noise, then shifted so the ground under the
+//! centerline lines up with the track's own heights.
+
+use super::terrain::terrain_grid;
+use super::world_data::TerrainHeightmap;
+use crate::data::{GeoOrigin, TrackPoint};
+use crate::track_import::{ElevationModel, Projection, SrtmTiles};
+use std::io::{Read, Seek};
+use std::path::Path;
+use tiff::decoder::{Decoder, DecodingResult};
+use tiff::tags::Tag;
+use tiff::ColorType;
+
+/// GeoKey saying whether the raster is in projected or geographic coordinates
+const GT_MODEL_TYPE_GEO_KEY: u16 = 1024;
+/// GeoKey saying whether the tie point is a pixel's corner or its centre
+const GT_RASTER_TYPE_GEO_KEY: u16 = 1025;
+const MODEL_TYPE_GEOGRAPHIC: u16 = 2;
+const RASTER_PIXEL_IS_POINT: u16 = 2;
+
+/// Tracks whose heights vary less than this are flat and get draped on the terrain (m)
+const FLAT_TRACK_RANGE_M: f32 = 0.5;
+
+/// A single-band elevation raster in WGS84 degrees, such as an SRTM or
+/// Copernicus GeoTIFF
+pub struct GeoTiff {
+    width: usize,
+    height: usize,
+    /// North row first
+    heights: Vec<f32>,
+    nodata: Option<f32>,
+    /// Longitude and latitude of the centre of the top-left pixel
+    west: f64,
+    north: f64,
+    /// Pixel size in degrees
+    step_lon: f64,
+    step_lat: f64,
+}
+
+impl GeoTiff {
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let file = std::fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        Self::decode(std::io::BufReader::new(file)).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    fn decode<R: Read + Seek>(reader: R) -> Result<Self, String> {
+        let mut decoder = Decoder::new(reader).map_err(|e| format!("Not a TIFF file ({})", e))?;
+        let (width, height) = decoder.dimensions().map_err(|e| e.to_string())?;
+        if !matches!(decoder.colortype().map_err(|e| e.to_string())?, ColorType::Gray(_)) {
+            return Err("Elevation GeoTIFFs must have a single band".to_string());
+        }
+
+        // GeoKeyDirectory: a header of four shorts, then four per key
+        let keys: Vec<u16> = decoder.find_tag_unsigned_vec(Tag::GeoKeyDirectoryTag).map_err(|e| e.to_string())?.unwrap_or_default();
+        let geo_key = |id: u16| keys.chunks_exact(4).skip(1).find(|key| key[0] == id && key[1] == 0).map(|key| key[3]);
+        if geo_key(GT_MODEL_TYPE_GEO_KEY).is_some_and(|model| model != MODEL_TYPE_GEOGRAPHIC) {
+            return Err("The raster is in a projected coordinate system; reproject it to WGS84 degrees first \
+                        (gdalwarp -t_srs EPSG:4326)"
+                .to_string());
+        }
+        let mut georeference = |tag: Tag, count: usize| match decoder.find_tag(tag) {
+            Ok(Some(value)) => value.into_f64_vec().ok().filter(|values| values.len() >= count),
+            _ => None,
+        };
+        let (Some(scale), Some(tie)) = (georeference(Tag::ModelPixelScaleTag, 2), georeference(Tag::ModelTiepointTag, 6)) else {
+            return Err("The raster has no georeferencing (ModelPixelScale and ModelTiepoint tags)".to_string());
+        };
+        // Tie points are pixel corners unless the raster says otherwise
+        let centre = if geo_key(GT_RASTER_TYPE_GEO_KEY) == Some(RASTER_PIXEL_IS_POINT) { 0.0 } else { 0.5 };
+        let west = tie[3] + (centre - tie[0]) * scale[0];
+        let north = tie[4] - (centre - tie[1]) * scale[1];
+        if north.abs() > 90.0 || west.abs() > 360.0 || scale[0] <= 0.0 || scale[1] <= 0.0 {
+            return Err("The raster is not in WGS84 degrees; reproject it first (gdalwarp -t_srs EPSG:4326)".to_string());
+        }
+
+        let nodata = match decoder.find_tag(Tag::GdalNodata) {
+            Ok(Some(value)) => value.into_string().ok().and_then(|text| text.trim_matches(char::from(0)).trim().parse().ok()),
+            _ => None,
+        };
+        let heights: Vec<f32> = match decoder.read_image().map_err(|e| e.to_string())? {
+            DecodingResult::U8(values) => values.into_iter().map(f32::from).collect(),
+            DecodingResult::U16(values) => values.into_iter().map(f32::from).collect(),
+            DecodingResult::U32(values) => values.into_iter().map(|v| v as f32).collect(),
+            DecodingResult::U64(values) => values.into_iter().map(|v| v as f32).collect(),
+            DecodingResult::F16(values) => values.into_iter().map(|v| v.to_f32()).collect(),
+            DecodingResult::F32(values) => values,
+            DecodingResult::F64(values) => values.into_iter().map(|v| v as f32).collect(),
+            DecodingResult::I8(values) => values.into_iter().map(f32::from).collect(),
+            DecodingResult::I16(values) => values.into_iter().map(f32::from).collect(),
+            DecodingResult::I32(values) => values.into_iter().map(|v| v as f32).collect(),
+            DecodingResult::I64(values) => values.into_iter().map(|v| v as f32).collect(),
+        };
+
+        Ok(Self {
+            width: width as usize,
+            height: height as usize,
+            heights,
+            nodata,
+            west,
+            north,
+            step_lon: scale[0],
+            step_lat: scale[1],
+        })
+    }
+
+    fn height(&self, row: usize, col: usize) -> Option<f64> {
+        let height = self.heights[row * self.width + col];
+        Some(height).filter(|h| h.is_finite() && Some(*h) != self.nodata).map(f64::from)
+    }
+}
+
+impl ElevationModel for GeoTiff {
+    fn elevation(&mut self, lat: f64, lon: f64) -> Option<f64> {
+        let col = (lon - self.west) / self.step_lon;
+        let row = (self.north - lat) / self.step_lat;
+        let (last_col, last_row) = ((self.width - 1) as f64, (self.height - 1) as f64);
+        if !(0.0..=last_col).contains(&col) || !(0.0..=last_row).contains(&row) {
+            return None;
+        }
+        let (r0, c0) = (row.floor() as usize, col.floor() as usize);
+        let (r1, c1) = ((r0 + 1).min(self.height - 1), (c0 + 1).min(self.width - 1));
+        let (fr, fc) = (row - r0 as f64, col - c0 as f64);
+        let top = self.height(r0, c0)? * (1.0 - fc) + self.height(r0, c1)? * fc;
+        let bottom = self.height(r1, c0)? * (1.0 - fc) + self.height(r1, c1)? * fc;
+        Some(top * (1.0 - fr) + bottom * fr)
+    }
+}
+
+/// Elevation model at `path`: a directory of SRTM `.hgt` tiles or a GeoTIFF
+pub fn open(path: &Path) -> Result<Box<dyn ElevationModel>, String> {
+    if path.is_dir() {
+        return Ok(Box::new(SrtmTiles::new(path)));
+    }
+    match path.extension().and_then(|ext| ext.to_str()).map(str::to_lowercase).as_deref() {
+        Some("tif" | "tiff") => Ok(Box::new(GeoTiff::read(path)?)),
+        _ => Err(format!("{} is neither a directory of SRTM tiles nor a GeoTIFF", path.display())),
+    }
+}
+
+/// Heightmap around the track sampled from `model`
+///
+/// The elevations are shifted by the median difference between the model
+/// and the track along the centerline, so a track with recorded heights
+/// sits in its real surroundings. A flat track is lined up with the lowest
+/// ground under it instead, and should be draped with `drape_flat_track`.
+/// Holes in the data are filled from the cells around them.
+pub fn dem_heightmap(
+    track_points: &[TrackPoint],
+    origin: GeoOrigin,
+    model: &mut dyn ElevationModel,
+) -> Result<TerrainHeightmap, String> {
+    let mut heightmap = terrain_grid(track_points)?;
+    let projection = Projection::from_origin(origin);
+    let mut elevation_at = |x: f32, y: f32| {
+        let (lat, lon) = projection.to_lat_lon(x as f64, y as f64);
+        model.elevation(lat, lon).map(|height| height as f32)
+    };
+
+    let mut differences: Vec<f32> = track_points
+        .iter()
+        .filter_map(|point| elevation_at(point.x, point.y).map(|height| height - point.z))
+        .collect();
+    if differences.is_empty() {
+        return Err(format!(
+            "The elevation data does not cover the track around {:.5}, {:.5}",
+            origin.lat, origin.lon
+        ));
+    }
+    let offset = if is_flat(track_points) {
+        differences.iter().copied().fold(f32::INFINITY, f32::min)
+    } else {
+        differences.sort_by(f32::total_cmp);
+        differences[differences.len() / 2]
+    };
+
+    let mut heights = Vec::with_capacity(heightmap.heights.len());
+    for y in 0..heightmap.height {
+        for x in 0..heightmap.width {
+            let world_x = heightmap.origin_x + x as f32 * heightmap.cell_size_m;
+            let world_y = heightmap.origin_y + y as f32 * heightmap.cell_size_m;
+            heights.push(elevation_at(world_x, world_y).map(|height| height - offset));
+        }
+    }
+    let missing = heights.iter().filter(|height| height.is_none()).count();
+    if missing * 2 > heights.len() {
+        return Err(format!(
+            "The elevation data covers only {:.0}% of the area around the track",
+            100.0 * (heights.len() - missing) as f32 / heights.len() as f32
+        ));
+    }
+    if missing > 0 {
+        println!("Filling {} terrain cells without elevation data", missing);
+        fill_holes(&mut heights, heightmap.width, heightmap.height);
+    }
+    heightmap.heights = heights.into_iter().map(|height| height.unwrap_or(0.0)).collect();
+    println!("Sampled terrain from elevation data, {:.1} m below the model", offset);
+    Ok(heightmap)
+}
+
+/// The track with its heights taken from `heightmap`, when it has none of its own
+pub fn drape_flat_track(track_points: &[TrackPoint], heightmap: &TerrainHeightmap) -> Option<Vec<TrackPoint>> {
+    if !is_flat(track_points) {
+        return None;
+    }
+    let draped = track_points
+        .iter()
+        .map(|point| TrackPoint { z: heightmap.sample(point.x, point.y), ..point.clone() })
+        .collect();
+    Some(draped)
+}
+
+fn is_flat(track_points: &[TrackPoint]) -> bool {
+    let (low, high) = track_points.iter().fold((f32::MAX, f32::MIN), |(low, high), point| (low.min(point.z), high.max(point.z)));
+    high - low < FLAT_TRACK_RANGE_M
+}
+
+/// Give every missing cell the average of its known neighbours, growing
+/// inwards from the edges of each hole
+fn fill_holes(heights: &mut [Option<f32>], width: usize, height: usize) {
+    let mut holes: Vec<usize> = (0..heights.len()).filter(|&i| heights[i].is_none()).collect();
+    while !holes.is_empty() {
+        let filled: Vec<(usize, f32)> = holes
+            .iter()
+            .filter_map(|&i| {
+                let (x, y) = (i % width, i / width);
+                let neighbours = [
+                    (x > 0).then(|| i - 1),
+                    (x + 1 < width).then(|| i + 1),
+                    (y > 0).then(|| i - width),
+                    (y + 1 < height).then(|| i + width),
+                ];
+                let (sum, count) = neighbours
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|j| heights[j])
+                    .fold((0.0, 0), |(sum, count), h| (sum + h, count + 1));
+                (count > 0).then(|| (i, sum / count as f32))
+            })
+            .collect();
+        if filled.is_empty() {
+            return;
+        }
+        for &(i, height) in &filled {
+            heights[i] = Some(height);
+        }
+        holes.retain(|&i| heights[i].is_none());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tiff::encoder::{colortype, TiffEncoder};
+
+    /// A 0.01° GeoTIFF around (50.5, 5.5) rising 1 m every pixel (0.0001°)
+    /// east, with a hole of nodata 111 m north and 70 m east of its centre
+    fn write_geotiff(path: &Path) {
+        let size = 101;
+        let mut data: Vec<i16> = (0..size * size).map(|i| (i % size) as i16 + 100).collect();
+        data[40 * size + 60] = -9999;
+        let mut file = std::fs::File::create(path).unwrap();
+        let mut tiff = TiffEncoder::new(&mut file).unwrap();
+        let mut image = tiff.new_image::<colortype::GrayI16>(size as u32, size as u32).unwrap();
+        image.encoder().write_tag(Tag::ModelPixelScaleTag, &[0.0001, 0.0001, 0.0][..]).unwrap();
+        image.encoder().write_tag(Tag::ModelTiepointTag, &[0.0, 0.0, 0.0, 5.495, 50.505, 0.0][..]).unwrap();
+        let keys: [u16; 12] = [1, 1, 0, 2, GT_MODEL_TYPE_GEO_KEY, 0, 1, MODEL_TYPE_GEOGRAPHIC, GT_RASTER_TYPE_GEO_KEY, 0, 1, RASTER_PIXEL_IS_POINT];
+        image.encoder().write_tag(Tag::GeoKeyDirectoryTag, &keys[..]).unwrap();
+        image.encoder().write_tag(Tag::GdalNodata, "-9999").unwrap();
+        image.write_data(&data).unwrap();
+    }
+
+    /// A straight 200 m west-east track through `origin`, climbing `climb` metres
+    fn straight_track(climb: f32) -> Vec<TrackPoint> {
+        (0..=40)
+            .map(|i| TrackPoint {
+                x: -100.0 + i as f32 * 5.0,
+                y: 0.0,
+                z: climb * i as f32 / 40.0,
+                distance_from_start_m: i as f32 * 5.0,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_geotiff_heights_are_interpolated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dem.tif");
+        write_geotiff(&path);
+        let mut dem = open(&path).unwrap();
+
+        assert_eq!(dem.elevation(50.5, 5.5).map(f64::round), Some(150.0));
+        assert_eq!(dem.elevation(50.5, 5.50005).map(|h| (h * 10.0).round()), Some(1505.0));
+        assert_eq!(dem.elevation(50.501, 5.501), None, "nodata");
+        assert_eq!(dem.elevation(50.6, 5.5), None, "outside the raster");
+        assert!(open(&dir.path().join("dem.png")).is_err());
+    }
+
+    #[test]
+    fn test_dem_terrain_lines_up_with_the_track() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dem.tif");
+        write_geotiff(&path);
+        let origin = GeoOrigin { lat: 50.5, lon: 5.5 };
+        // 1 m per 0.0001° of longitude is about 0.14 m per metre east
+        let metres_per_pixel = 0.0001_f32.to_radians() * 6_371_000.0 * 50.5_f32.to_radians().cos();
+        let climb = 200.0 / metres_per_pixel;
+
+        let track = straight_track(climb);
+        let heightmap = dem_heightmap(&track, origin, GeoTiff::read(&path).as_mut().unwrap()).unwrap();
+        for point in &track {
+            assert!((heightmap.sample(point.x, point.y) - point.z).abs() < 0.1, "at x {}", point.x);
+        }
+        // The ground keeps its slope away from the track, and the hole is filled
+        let (west, east) = (heightmap.sample(-150.0, 150.0), heightmap.sample(150.0, 150.0));
+        assert!(((east - west) - 300.0 / metres_per_pixel).abs() < 0.5, "slope {}", east - west);
+        let filled = heightmap.sample(70.0, 111.0) - heightmap.sample(-100.0, 111.0);
+        assert!((filled - 170.0 / metres_per_pixel).abs() < 0.5, "filled {}", filled);
+
+        // A flat track starts at the lowest ground and is draped over it
+        let flat = straight_track(0.0);
+        let heightmap = dem_heightmap(&flat, origin, GeoTiff::read(&path).as_mut().unwrap()).unwrap();
+        let draped = drape_flat_track(&flat, &heightmap).unwrap();
+        assert!(draped[0].z.abs() < 0.1);
+        assert!((draped[40].z - climb).abs() < 0.1);
+        assert!(drape_flat_track(&track, &heightmap).is_none());
+
+        let far = GeoOrigin { lat: -33.9, lon: 151.2 };
+        assert!(dem_heightmap(&track, far, GeoTiff::read(&path).as_mut().unwrap()).is_err());
+    }
+}
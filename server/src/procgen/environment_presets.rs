@@ -16,6 +16,8 @@ pub fn load_presets() -> HashMap<String, EnvironmentPreset> {
     presets.insert("plains".to_string(), EnvironmentPreset::plains());
     presets.insert("country".to_string(), EnvironmentPreset::country());
     presets.insert("park".to_string(), EnvironmentPreset::park());
+    presets.insert("alpine".to_string(), EnvironmentPreset::alpine());
+    presets.insert("coastal".to_string(), EnvironmentPreset::coastal());
 
     presets
 }
@@ -32,6 +34,8 @@ pub fn get_preset(environment_type: &str) -> Option<EnvironmentPreset> {
         "plains" => Some(EnvironmentPreset::plains()),
         "country" => Some(EnvironmentPreset::country()),
         "park" => Some(EnvironmentPreset::park()),
+        "alpine" => Some(EnvironmentPreset::alpine()),
+        "coastal" => Some(EnvironmentPreset::coastal()),
         _ => None,
     }
 }
@@ -44,7 +48,7 @@ mod tests {
     fn test_load_all_presets() {
         let presets = load_presets();
 
-        assert_eq!(presets.len(), 7);
+        assert_eq!(presets.len(), 9);
         assert!(presets.contains_key("desert"));
         assert!(presets.contains_key("forest"));
         assert!(presets.contains_key("city"));
@@ -52,6 +56,8 @@ mod tests {
         assert!(presets.contains_key("plains"));
         assert!(presets.contains_key("country"));
         assert!(presets.contains_key("park"));
+        assert!(presets.contains_key("alpine"));
+        assert!(presets.contains_key("coastal"));
     }
 
     #[test]
@@ -74,5 +80,14 @@ mod tests {
 
         let city = get_preset("city").unwrap();
         assert_eq!(city.max_height, 5.0); // City is mostly flat
+
+        let alpine = get_preset("alpine").unwrap();
+        assert!(alpine.max_height > desert.max_height);
+        assert!(alpine.noise_layers.iter().any(|l| l.ridged)); // Sharp peaks
+        assert!(alpine.peak_color.is_some()); // Snow caps
+
+        let coastal = get_preset("coastal").unwrap();
+        assert!(!coastal.noise_layers.is_empty());
+        assert_ne!(coastal.sky, alpine.sky);
     }
 }
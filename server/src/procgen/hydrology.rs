@@ -0,0 +1,411 @@
+//! Rivers and lakes
+//!
+//! A priority flood from the edges of the map fills every depression in the
+//! terrain up to where it spills over, and records for each cell the
+//! neighbour it drains into. Summing the ground drained down that tree
+//! shows where water gathers: a river channel is carved wherever enough of
+//! it does, and filled depressions deep and wide enough become lakes. The
+//! preset's `rainfall` sets how much ground a river needs and how small a
+//! lake may be. Water is kept clear of the track, whose corridor is carved
+//! afterwards.
+
+use super::world_data::{EnvironmentPreset, Lake, River, RiverPoint, TerrainHeightmap, WaterFeatures};
+use crate::data::TrackPoint;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+/// Ground a river drains at rainfall 1 (m²); drier biomes need more
+const RIVER_CATCHMENT_M2: f32 = 200_000.0;
+const RIVER_MIN_WIDTH_M: f32 = 3.0;
+const RIVER_MAX_WIDTH_M: f32 = 20.0;
+/// Water surface below the ground it flows through (m)
+const RIVER_FREEBOARD_M: f32 = 0.3;
+/// Smallest lake at rainfall 1 (m²); drier biomes need bigger hollows
+const LAKE_MIN_AREA_M2: f32 = 5_000.0;
+/// Depressions shallower than this stay dry (m)
+const LAKE_MIN_DEPTH_M: f32 = 1.0;
+/// Cells filled less than this are not under water (m)
+const FLOODED_M: f32 = 0.01;
+
+/// Cells on the map's edge drain off it
+const OFF_MAP: usize = usize::MAX;
+
+/// Fill lakes and carve river channels into `heightmap`, keeping everything
+/// `clearance` meters from the centerline
+pub fn generate_water(
+    heightmap: &mut TerrainHeightmap,
+    track_points: &[TrackPoint],
+    preset: &EnvironmentPreset,
+    clearance: f32,
+) -> WaterFeatures {
+    if preset.rainfall <= 0.0 || heightmap.width < 3 || heightmap.height < 3 {
+        return WaterFeatures::default();
+    }
+    let near_track = track_mask(heightmap, track_points, clearance);
+    let drainage = Drainage::new(heightmap);
+    let (lakes, in_lake) = find_lakes(heightmap, &drainage, &near_track, preset.rainfall);
+    let rivers = trace_rivers(heightmap, &drainage, &near_track, &in_lake, preset.rainfall);
+    for river in &rivers {
+        carve_river(heightmap, river);
+    }
+    println!("Generated {} lakes and {} rivers", lakes.len(), rivers.len());
+    WaterFeatures { lakes, rivers }
+}
+
+/// How water runs over the terrain
+struct Drainage {
+    /// Terrain with every depression filled to its spill height
+    filled: Vec<f32>,
+    /// Cell each cell drains into
+    downstream: Vec<usize>,
+    /// Cells drained through each cell, itself included
+    catchment: Vec<u32>,
+}
+
+/// Cell waiting in the flood, lowest first and then first come
+#[derive(PartialEq)]
+struct Flooding {
+    level: f32,
+    order: usize,
+    index: usize,
+}
+
+impl Eq for Flooding {}
+
+impl Ord for Flooding {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.level.total_cmp(&self.level).then(other.order.cmp(&self.order))
+    }
+}
+
+impl PartialOrd for Flooding {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Drainage {
+    fn new(heightmap: &TerrainHeightmap) -> Self {
+        let (width, height) = (heightmap.width, heightmap.height);
+        let count = width * height;
+        let mut filled = heightmap.heights.clone();
+        let mut downstream = vec![OFF_MAP; count];
+        let mut reached = vec![false; count];
+        let mut queue = BinaryHeap::new();
+        let mut order = 0;
+        for index in 0..count {
+            let (x, y) = (index % width, index / width);
+            if x == 0 || y == 0 || x == width - 1 || y == height - 1 {
+                reached[index] = true;
+                queue.push(Flooding { level: filled[index], order, index });
+                order += 1;
+            }
+        }
+        // Each cell is reached from the neighbour it drains into
+        let mut flooded = Vec::with_capacity(count);
+        while let Some(Flooding { index, .. }) = queue.pop() {
+            flooded.push(index);
+            for next in neighbours(index, width, height) {
+                if !reached[next] {
+                    reached[next] = true;
+                    filled[next] = filled[next].max(filled[index]);
+                    downstream[next] = index;
+                    queue.push(Flooding { level: filled[next], order, index: next });
+                    order += 1;
+                }
+            }
+        }
+        let mut catchment = vec![1u32; count];
+        for &index in flooded.iter().rev() {
+            if downstream[index] != OFF_MAP {
+                catchment[downstream[index]] += catchment[index];
+            }
+        }
+        Self { filled, downstream, catchment }
+    }
+}
+
+/// The eight cells around `index`
+fn neighbours(index: usize, width: usize, height: usize) -> impl Iterator<Item = usize> {
+    let (x, y) = ((index % width) as isize, (index / width) as isize);
+    (-1..=1)
+        .flat_map(move |dy| (-1..=1).map(move |dx| (x + dx, y + dy)))
+        .filter(move |&(nx, ny)| (nx, ny) != (x, y) && nx >= 0 && ny >= 0 && nx < width as isize && ny < height as isize)
+        .map(move |(nx, ny)| ny as usize * width + nx as usize)
+}
+
+fn cell_position(heightmap: &TerrainHeightmap, index: usize) -> (f32, f32) {
+    (
+        heightmap.origin_x + (index % heightmap.width) as f32 * heightmap.cell_size_m,
+        heightmap.origin_y + (index / heightmap.width) as f32 * heightmap.cell_size_m,
+    )
+}
+
+/// Cells within `clearance` meters of the centerline
+fn track_mask(heightmap: &TerrainHeightmap, track_points: &[TrackPoint], clearance: f32) -> Vec<bool> {
+    let mut mask = vec![false; heightmap.width * heightmap.height];
+    let cells = (clearance / heightmap.cell_size_m).ceil() as isize + 1;
+    for point in track_points {
+        let cx = ((point.x - heightmap.origin_x) / heightmap.cell_size_m).round() as isize;
+        let cy = ((point.y - heightmap.origin_y) / heightmap.cell_size_m).round() as isize;
+        for y in (cy - cells).max(0)..=(cy + cells).min(heightmap.height as isize - 1) {
+            for x in (cx - cells).max(0)..=(cx + cells).min(heightmap.width as isize - 1) {
+                let index = y as usize * heightmap.width + x as usize;
+                let (world_x, world_y) = cell_position(heightmap, index);
+                if (world_x - point.x).hypot(world_y - point.y) <= clearance {
+                    mask[index] = true;
+                }
+            }
+        }
+    }
+    mask
+}
+
+/// Filled depressions big and deep enough to hold a lake, and which cells they cover
+fn find_lakes(heightmap: &TerrainHeightmap, drainage: &Drainage, near_track: &[bool], rainfall: f32) -> (Vec<Lake>, Vec<bool>) {
+    let (width, height) = (heightmap.width, heightmap.height);
+    let flooded = |index: usize| drainage.filled[index] > heightmap.heights[index] + FLOODED_M;
+    let min_cells = (LAKE_MIN_AREA_M2 / rainfall / (heightmap.cell_size_m * heightmap.cell_size_m)).ceil() as usize;
+    let mut seen = vec![false; width * height];
+    let mut in_lake = vec![false; width * height];
+    let mut lakes = Vec::new();
+    for start in 0..width * height {
+        if seen[start] || !flooded(start) {
+            continue;
+        }
+        // One body of water: flooded cells joined at the same level
+        let level = drainage.filled[start];
+        let mut cells = vec![start];
+        let mut queue = VecDeque::from([start]);
+        seen[start] = true;
+        while let Some(index) = queue.pop_front() {
+            for next in neighbours(index, width, height) {
+                if !seen[next] && flooded(next) && (drainage.filled[next] - level).abs() < FLOODED_M {
+                    seen[next] = true;
+                    cells.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+        let depth = cells.iter().map(|&index| level - heightmap.heights[index]).fold(0.0, f32::max);
+        // The surface reaches a cell under the shore all round
+        let mut covered: Vec<usize> = cells.iter().flat_map(|&index| neighbours(index, width, height).chain([index])).collect();
+        covered.sort_unstable();
+        covered.dedup();
+        if cells.len() < min_cells || depth < LAKE_MIN_DEPTH_M || covered.iter().any(|&index| near_track[index]) {
+            continue;
+        }
+        for &index in &cells {
+            in_lake[index] = true;
+        }
+        lakes.push(Lake { level, rects: cover_cells(heightmap, &covered) });
+    }
+    (lakes, in_lake)
+}
+
+/// Rectangles covering the sorted cells: a row's runs, stacked when the
+/// same run continues on the next row
+fn cover_cells(heightmap: &TerrainHeightmap, cells: &[usize]) -> Vec<[f32; 4]> {
+    let half = heightmap.cell_size_m / 2.0;
+    let mut rects: Vec<[f32; 4]> = Vec::new();
+    // Rectangle still growing for each run, and the row it reached
+    let mut open: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+    let mut i = 0;
+    while i < cells.len() {
+        let (row, first) = (cells[i] / heightmap.width, cells[i] % heightmap.width);
+        let mut last = first;
+        while i + 1 < cells.len() && cells[i + 1] == cells[i] + 1 && cells[i + 1] / heightmap.width == row {
+            i += 1;
+            last += 1;
+        }
+        i += 1;
+        let (min_x, min_y) = cell_position(heightmap, row * heightmap.width + first);
+        let (max_x, max_y) = cell_position(heightmap, row * heightmap.width + last);
+        match open.get_mut(&(first, last)) {
+            Some((rect, reached)) if *reached + 1 == row => {
+                rects[*rect][3] = max_y + half;
+                *reached = row;
+            }
+            _ => {
+                open.insert((first, last), (rects.len(), row));
+                rects.push([min_x - half, min_y - half, max_x + half, max_y + half]);
+            }
+        }
+    }
+    rects
+}
+
+/// Rivers wherever enough ground drains through, from each source down to
+/// the map's edge, a lake, the track or the river it joins
+fn trace_rivers(
+    heightmap: &TerrainHeightmap,
+    drainage: &Drainage,
+    near_track: &[bool],
+    in_lake: &[bool],
+    rainfall: f32,
+) -> Vec<River> {
+    let count = heightmap.width * heightmap.height;
+    let threshold = (RIVER_CATCHMENT_M2 / rainfall / (heightmap.cell_size_m * heightmap.cell_size_m)).ceil().max(2.0) as u32;
+    let is_river = |index: usize| drainage.catchment[index] >= threshold && !in_lake[index] && !near_track[index];
+    let mut fed = vec![false; count];
+    for index in (0..count).filter(|&index| is_river(index)) {
+        if drainage.downstream[index] != OFF_MAP {
+            fed[drainage.downstream[index]] = true;
+        }
+    }
+    let point = |index: usize| {
+        let (x, y) = cell_position(heightmap, index);
+        let width = RIVER_MIN_WIDTH_M * (drainage.catchment[index] as f32 / threshold as f32).sqrt();
+        let level = if in_lake[index] { drainage.filled[index] } else { drainage.filled[index] - RIVER_FREEBOARD_M };
+        RiverPoint { x, y, level, width: width.min(RIVER_MAX_WIDTH_M) }
+    };
+
+    let mut traced = vec![false; count];
+    let mut rivers = Vec::new();
+    for source in (0..count).filter(|&index| is_river(index) && !fed[index]) {
+        let mut points = Vec::new();
+        let mut index = source;
+        loop {
+            points.push(point(index));
+            traced[index] = true;
+            let next = drainage.downstream[index];
+            if next == OFF_MAP {
+                break;
+            }
+            // Into the lake, or on to where it joins a river already traced
+            if in_lake[next] || (is_river(next) && traced[next]) {
+                points.push(RiverPoint { width: points[points.len() - 1].width, ..point(next) });
+                break;
+            }
+            if !is_river(next) {
+                break;
+            }
+            index = next;
+        }
+        if points.len() >= 2 {
+            smooth_course(&mut points);
+            rivers.push(River { points });
+        }
+    }
+    rivers
+}
+
+/// Round off the cell-to-cell steps of a river, keeping its ends in place
+fn smooth_course(points: &mut [RiverPoint]) {
+    for _ in 0..2 {
+        let source = points.to_vec();
+        for i in 1..points.len().saturating_sub(1) {
+            points[i].x = (source[i - 1].x + 2.0 * source[i].x + source[i + 1].x) / 4.0;
+            points[i].y = (source[i - 1].y + 2.0 * source[i].y + source[i + 1].y) / 4.0;
+        }
+    }
+}
+
+/// Cut a rounded channel under the river and a bank at water level beside it
+fn carve_river(heightmap: &mut TerrainHeightmap, river: &River) {
+    let cell = heightmap.cell_size_m;
+    for point in &river.points {
+        let half_width = point.width / 2.0;
+        let depth = (point.width * 0.25).clamp(0.5, 2.5);
+        let reach = half_width + cell;
+        let cx = ((point.x - heightmap.origin_x) / cell).round() as isize;
+        let cy = ((point.y - heightmap.origin_y) / cell).round() as isize;
+        let cells = (reach / cell).ceil() as isize;
+        for y in (cy - cells).max(0)..=(cy + cells).min(heightmap.height as isize - 1) {
+            for x in (cx - cells).max(0)..=(cx + cells).min(heightmap.width as isize - 1) {
+                let (x, y) = (x as usize, y as usize);
+                let world_x = heightmap.origin_x + x as f32 * cell;
+                let world_y = heightmap.origin_y + y as f32 * cell;
+                let distance = (world_x - point.x).hypot(world_y - point.y);
+                let target = if distance <= half_width {
+                    let across = distance / half_width;
+                    point.level - depth * (1.0 - across * across)
+                } else if distance <= reach {
+                    point.level + RIVER_FREEBOARD_M
+                } else {
+                    continue;
+                };
+                if heightmap.get_height(x, y) > target {
+                    heightmap.set_height(x, y, target);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 500 m square falling 0.1 m per meter to the south, with a V-shaped
+    /// valley down the middle and a 10 m deep hollow in it 350 m north
+    fn valley() -> TerrainHeightmap {
+        let mut heightmap = TerrainHeightmap::new(101, 101, 5.0, 0.0, 0.0);
+        for y in 0..101 {
+            for x in 0..101 {
+                let (wx, wy) = (x as f32 * 5.0, y as f32 * 5.0);
+                let hollow = 10.0 * (-((wx - 250.0).powi(2) + (wy - 350.0).powi(2)) / 1600.0).exp();
+                heightmap.set_height(x, y, 0.1 * wy + 0.2 * (wx - 250.0).abs() - hollow);
+            }
+        }
+        heightmap
+    }
+
+    fn wet() -> EnvironmentPreset {
+        EnvironmentPreset { rainfall: 5.0, ..EnvironmentPreset::forest() }
+    }
+
+    #[test]
+    fn test_rivers_run_down_valleys_into_and_out_of_lakes() {
+        let original = valley();
+        let mut heightmap = original.clone();
+        let water = generate_water(&mut heightmap, &[], &wet(), 0.0);
+
+        assert_eq!(water.lakes.len(), 1, "{:?}", water.lakes);
+        let lake = &water.lakes[0];
+        assert!(lake.level > original.sample(250.0, 350.0) + LAKE_MIN_DEPTH_M);
+        assert!(water.covers(250.0, 350.0, 0.0));
+        assert!(!water.covers(100.0, 350.0, 5.0));
+
+        assert!(!water.rivers.is_empty());
+        let outflow = water.rivers.iter().find(|river| river.points.last().unwrap().y < 10.0).expect("a river leaving the map");
+        for pair in outflow.points.windows(2) {
+            assert!(pair[1].level <= pair[0].level + 1e-3, "rivers run downhill");
+        }
+        for point in &outflow.points {
+            assert!((point.x - 250.0).abs() < 15.0, "river at {}, {}", point.x, point.y);
+            assert!(point.level < original.sample(point.x, point.y));
+            assert!(heightmap.sample(point.x, point.y) < point.level);
+        }
+        let sizes = outflow.points.iter().map(|point| point.width);
+        assert!(sizes.clone().all(|width| (RIVER_MIN_WIDTH_M..=RIVER_MAX_WIDTH_M).contains(&width)));
+        assert!(outflow.points.last().unwrap().width > outflow.points[0].width, "rivers widen downstream");
+    }
+
+    #[test]
+    fn test_water_keeps_clear_of_the_track() {
+        // A road across the valley floor, 100 m north
+        let track: Vec<TrackPoint> = (0..=20)
+            .map(|i| TrackPoint { x: 200.0 + i as f32 * 5.0, y: 100.0, ..Default::default() })
+            .collect();
+        let mut heightmap = valley();
+        let water = generate_water(&mut heightmap, &track, &wet(), 40.0);
+        assert!(!water.rivers.is_empty());
+        for point in water.rivers.iter().flat_map(|river| &river.points) {
+            assert!((point.y - 100.0).abs() > 35.0, "river at {}, {}", point.x, point.y);
+        }
+
+        let mut heightmap = valley();
+        let dry = EnvironmentPreset { rainfall: 0.0, ..wet() };
+        assert_eq!(generate_water(&mut heightmap, &[], &dry, 0.0), WaterFeatures::default());
+        assert_eq!(heightmap.heights, valley().heights);
+    }
+
+    #[test]
+    fn test_lake_rects_cover_each_cell_once() {
+        let heightmap = TerrainHeightmap::new(10, 10, 2.0, 0.0, 0.0);
+        // An L: two rows of three, then a row of one
+        let rects = cover_cells(&heightmap, &[11, 12, 13, 21, 22, 23, 31]);
+        assert_eq!(rects, vec![[1.0, 1.0, 7.0, 5.0], [1.0, 5.0, 3.0, 7.0]]);
+    }
+}
@@ -0,0 +1,96 @@
+//! Track lights
+//!
+//! Lamps a track lights itself with for dusk and night sessions: a row
+//! across the start gantry over the start line and a lamp every
+//! `PIT_LAMP_SPACING_M` along the pit lane, hung above the ground of the
+//! finished terrain.
+
+use super::world_data::{LightKind, LightSource, TerrainHeightmap};
+use crate::data::TrackPoint;
+
+/// Height of the gantry lamps above the road (m)
+const GANTRY_HEIGHT_M: f32 = 7.0;
+/// Greatest gap between neighbouring gantry lamps (m)
+const GANTRY_LAMP_SPACING_M: f32 = 5.0;
+const GANTRY_COLOR: [f32; 3] = [1.0, 1.0, 1.0];
+/// Height of the pit lane lamps above the ground (m)
+const PIT_LAMP_HEIGHT_M: f32 = 6.0;
+const PIT_LAMP_SPACING_M: f32 = 20.0;
+const PIT_LAMP_COLOR: [f32; 3] = [1.0, 0.93, 0.8];
+
+/// Lamps over the start line at `start` and along the pit lane centerline
+/// (empty for tracks without a pit lane)
+pub fn place_track_lights(start: &TrackPoint, pit_lane: &[TrackPoint], heightmap: &TerrainHeightmap) -> Vec<LightSource> {
+    let mut lights = Vec::new();
+
+    // Evenly spread across the road, from its right edge to its left
+    let (left_x, left_y) = (-start.heading_rad.sin(), start.heading_rad.cos());
+    let span = start.width_left_m + start.width_right_m;
+    let lamps = (span / GANTRY_LAMP_SPACING_M).ceil() as usize + 1;
+    for i in 0..lamps {
+        let offset = -start.width_right_m + span * i as f32 / (lamps - 1) as f32;
+        lights.push(LightSource {
+            kind: LightKind::StartGantry,
+            x: start.x + left_x * offset,
+            y: start.y + left_y * offset,
+            z: start.z + GANTRY_HEIGHT_M,
+            color: GANTRY_COLOR,
+            range_m: 30.0,
+            energy: 3.0,
+        });
+    }
+
+    let mut next_m = 0.0;
+    for point in pit_lane {
+        if point.distance_from_start_m < next_m {
+            continue;
+        }
+        next_m = point.distance_from_start_m + PIT_LAMP_SPACING_M;
+        lights.push(LightSource {
+            kind: LightKind::PitLane,
+            x: point.x,
+            y: point.y,
+            z: heightmap.sample(point.x, point.y).max(point.z) + PIT_LAMP_HEIGHT_M,
+            color: PIT_LAMP_COLOR,
+            range_m: 25.0,
+            energy: 2.0,
+        });
+    }
+
+    lights
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: f32, y: f32, distance: f32) -> TrackPoint {
+        TrackPoint {
+            x,
+            y,
+            distance_from_start_m: distance,
+            width_left_m: 6.0,
+            width_right_m: 6.0,
+            heading_rad: std::f32::consts::FRAC_PI_2,
+            ..TrackPoint::default()
+        }
+    }
+
+    #[test]
+    fn test_lamps_span_the_start_line_and_line_the_pit_lane() {
+        let mut heightmap = TerrainHeightmap::new(61, 61, 5.0, -150.0, -150.0);
+        heightmap.heights.fill(2.0);
+        let pit_lane: Vec<TrackPoint> = (0..=20).map(|i| point(20.0, i as f32 * 5.0, i as f32 * 5.0)).collect();
+        let lights = place_track_lights(&point(0.0, 0.0, 0.0), &pit_lane, &heightmap);
+
+        let gantry: Vec<_> = lights.iter().filter(|light| light.kind == LightKind::StartGantry).collect();
+        // 12 m of road heading north, lit from its right (east) edge to its left
+        assert_eq!(gantry.len(), 4);
+        assert!((gantry[0].x - 6.0).abs() < 1e-4 && (gantry[3].x + 6.0).abs() < 1e-4);
+        assert!(gantry.iter().all(|light| light.y.abs() < 1e-4 && light.z == GANTRY_HEIGHT_M));
+
+        let pit: Vec<_> = lights.iter().filter(|light| light.kind == LightKind::PitLane).collect();
+        assert_eq!(pit.iter().map(|light| light.y).collect::<Vec<_>>(), [0.0, 20.0, 40.0, 60.0, 80.0, 100.0]);
+        assert!(pit.iter().all(|light| light.z == 2.0 + PIT_LAMP_HEIGHT_M));
+    }
+}
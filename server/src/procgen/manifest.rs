@@ -0,0 +1,208 @@
+//! World parameter manifests
+//!
+//! Everything a world is generated from besides the track itself is
+//! gathered in one `WorldParams`, written next to the terrain cache as
+//! `<track>.world.yaml`. The same track and parameters always give the same
+//! world, so a cache is only rebuilt when its manifest no longer matches,
+//! and what changed between the two explains how the new world differs.
+
+use super::environment_presets::get_preset;
+use super::world_data::EnvironmentPreset;
+use crate::data::{GeoOrigin, TrackMetadata};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use std::path::{Path, PathBuf};
+
+/// Suffix of the manifest that sits next to a track file and its terrain cache
+pub const WORLD_PARAMS_SUFFIX: &str = ".world.yaml";
+
+const DEFAULT_TERRAIN_SCALE: f32 = 1.0;
+const DEFAULT_BLEND_WIDTH_M: f32 = 20.0;
+const DEFAULT_DECAL_PROFILE: &str = "default";
+
+/// Every input of world generation besides the track
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorldParams {
+    pub environment_type: String,
+    pub seed: u32,
+    /// Multiplier on the preset's noise heights; unused with `terrain_dem`
+    pub terrain_scale: f32,
+    /// Elevation data the terrain is sampled from instead of noise,
+    /// relative to the track file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terrain_dem: Option<String>,
+    /// Where the track's x = 0, y = 0 lies on the elevation data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub geo_origin: Option<GeoOrigin>,
+    /// Track corridor blend width in meters
+    pub blend_width: f32,
+    /// Object density multiplier (0-1)
+    pub object_density: f32,
+    pub decal_profile: String,
+    /// The preset in full, so changes to the built-in presets count too
+    pub preset: EnvironmentPreset,
+}
+
+impl WorldParams {
+    /// Parameters of a track's world: its metadata, with defaults for
+    /// whatever that leaves out
+    pub fn from_metadata(track_name: &str, metadata: &TrackMetadata) -> Result<Self, String> {
+        let environment_type = metadata
+            .environment_type
+            .clone()
+            .ok_or_else(|| format!("{} has no environment_type", track_name))?;
+        let preset = get_preset(&environment_type).ok_or_else(|| format!("Unknown environment type: {}", environment_type))?;
+        if metadata.terrain_dem.is_some() && metadata.geo_origin.is_none() {
+            return Err("terrain_dem needs the track's geo_origin to place it on the elevation data".to_string());
+        }
+        Ok(Self {
+            seed: metadata.terrain_seed.unwrap_or_else(|| name_seed(track_name)),
+            terrain_scale: metadata.terrain_scale.unwrap_or(DEFAULT_TERRAIN_SCALE),
+            terrain_dem: metadata.terrain_dem.clone(),
+            geo_origin: metadata.terrain_dem.as_ref().and(metadata.geo_origin),
+            blend_width: metadata.terrain_blend_width.unwrap_or(DEFAULT_BLEND_WIDTH_M),
+            object_density: metadata.object_density.unwrap_or(preset.object_density),
+            decal_profile: metadata.decal_profile.clone().unwrap_or_else(|| DEFAULT_DECAL_PROFILE.to_string()),
+            environment_type,
+            preset,
+        })
+    }
+
+    /// Each parameter that differs from `earlier`, as `path: earlier -> now`
+    pub fn changes_from(&self, earlier: &WorldParams) -> Vec<String> {
+        let mut changes = Vec::new();
+        diff_values("", &as_value(earlier), &as_value(self), &mut changes);
+        changes
+    }
+}
+
+/// Seed of a track that names none, from its name; the same on every
+/// platform and Rust release
+pub fn name_seed(name: &str) -> u32 {
+    // FNV-1a
+    name.bytes().fold(0x811C_9DC5_u32, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x0100_0193))
+}
+
+/// Manifest for a track file: `Monza.yaml` -> `Monza.world.yaml`
+pub fn manifest_path(track_file: &Path) -> PathBuf {
+    let stem = track_file.file_stem().and_then(|s| s.to_str()).unwrap_or("track");
+    track_file.with_file_name(format!("{}{}", stem, WORLD_PARAMS_SUFFIX))
+}
+
+/// Whether a file in the tracks folder is a world manifest rather than a track
+pub fn is_manifest_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|s| s.to_str())
+        .is_some_and(|name| name.ends_with(WORLD_PARAMS_SUFFIX))
+}
+
+/// Track file a manifest belongs to: `Monza.world.yaml` -> the `Monza`
+/// track file beside it
+pub fn track_for_manifest(manifest: &Path) -> Option<PathBuf> {
+    let name = manifest.file_name()?.to_str()?.strip_suffix(WORLD_PARAMS_SUFFIX)?;
+    ["yaml", "yml", "json"]
+        .iter()
+        .map(|ext| manifest.with_file_name(format!("{}.{}", name, ext)))
+        .find(|path| path.is_file())
+}
+
+pub fn save_manifest(track_file: &Path, params: &WorldParams) -> Result<(), String> {
+    let path = manifest_path(track_file);
+    let yaml = serde_yaml::to_string(params).map_err(|e| format!("Failed to serialize world parameters: {}", e))?;
+    std::fs::write(&path, yaml).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// The manifest next to a track file, if it has a readable one
+pub fn load_manifest(track_file: &Path) -> Option<WorldParams> {
+    let path = manifest_path(track_file);
+    let content = std::fs::read_to_string(&path).ok()?;
+    serde_yaml::from_str(&content)
+        .map_err(|e| eprintln!("Failed to parse world manifest {}: {}", path.display(), e))
+        .ok()
+}
+
+/// Parameters as YAML values, read back from their text so floats compare
+/// and print as written
+fn as_value(params: &WorldParams) -> Value {
+    serde_yaml::to_string(params)
+        .ok()
+        .and_then(|yaml| serde_yaml::from_str(&yaml).ok())
+        .unwrap_or(Value::Null)
+}
+
+fn diff_values(path: &str, earlier: &Value, now: &Value, changes: &mut Vec<String>) {
+    match (earlier, now) {
+        (Value::Mapping(earlier), Value::Mapping(now)) => {
+            let keys = earlier.keys().chain(now.keys().filter(|k| !earlier.contains_key(*k)));
+            for key in keys {
+                let name = key.as_str().map(str::to_string).unwrap_or_else(|| render(key));
+                let child = if path.is_empty() { name } else { format!("{}.{}", path, name) };
+                diff_values(&child, earlier.get(key).unwrap_or(&Value::Null), now.get(key).unwrap_or(&Value::Null), changes);
+            }
+        }
+        _ if earlier != now => changes.push(format!("{}: {} -> {}", path, render(earlier), render(now))),
+        _ => {}
+    }
+}
+
+fn render(value: &Value) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| format!("{:?}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata() -> TrackMetadata {
+        TrackMetadata {
+            environment_type: Some("forest".to_string()),
+            terrain_blend_width: Some(30.0),
+            ..TrackMetadata::default()
+        }
+    }
+
+    #[test]
+    fn test_params_fill_in_defaults_and_a_stable_seed() {
+        let params = WorldParams::from_metadata("Monza", &metadata()).unwrap();
+
+        assert_eq!(params.seed, 2_474_968_352);
+        assert_eq!(params.terrain_scale, 1.0);
+        assert_eq!(params.blend_width, 30.0);
+        assert_eq!(params.object_density, EnvironmentPreset::forest().object_density);
+        assert_eq!(params.preset, EnvironmentPreset::forest());
+
+        let unknown = TrackMetadata { environment_type: Some("lunar".to_string()), ..metadata() };
+        assert!(WorldParams::from_metadata("Monza", &unknown).is_err());
+        let unplaced = TrackMetadata { terrain_dem: Some("srtm".to_string()), ..metadata() };
+        assert!(WorldParams::from_metadata("Monza", &unplaced).is_err());
+    }
+
+    #[test]
+    fn test_manifest_round_trips_and_explains_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let track_file = dir.path().join("Monza.yaml");
+        let params = WorldParams::from_metadata("Monza", &metadata()).unwrap();
+        save_manifest(&track_file, &params).unwrap();
+
+        assert!(is_manifest_file(&manifest_path(&track_file)));
+        assert!(!is_manifest_file(&track_file));
+        assert_eq!(track_for_manifest(&manifest_path(&track_file)), None);
+        std::fs::write(&track_file, "name: Monza\n").unwrap();
+        assert_eq!(track_for_manifest(&manifest_path(&track_file)), Some(track_file.clone()));
+        assert_eq!(load_manifest(&track_file), Some(params.clone()));
+
+        let mut tweaked = params.clone();
+        tweaked.seed = 7;
+        tweaked.preset.max_height = 60.0;
+        tweaked.preset.peak_color = Some([0.9, 0.9, 0.9]);
+        assert_eq!(
+            tweaked.changes_from(&params),
+            [
+                "seed: 2474968352 -> 7",
+                "preset.max_height: 40.0 -> 60.0",
+                "preset.peak_color: null -> [0.9,0.9,0.9]",
+            ]
+        );
+        assert!(params.changes_from(&params).is_empty());
+    }
+}
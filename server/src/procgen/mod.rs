@@ -1,13 +1,22 @@
-/// Procedural world generation module for ApexSim
-///
-/// This module provides terrain generation, environment object placement,
-/// and trackside decal systems for racing tracks.
+//! Procedural world generation module for ApexSim
+//!
+//! This module provides terrain generation, environment object placement,
+//! and trackside decal systems for racing tracks, and synthesizes whole
+//! circuits from a handful of parameters.
 
+pub mod circuit;
+pub mod dem;
 pub mod world_data;
 pub mod noise;
+pub mod objects;
 pub mod environment_presets;
+pub mod hydrology;
+pub mod lights;
+pub mod manifest;
+pub mod splatmap;
 pub mod terrain;
 
 // Re-export main types for convenience
-pub use world_data::{ProceduralWorldData, TerrainHeightmap, EnvironmentPreset};
+pub use world_data::{ProceduralWorldData, TerrainHeightmap, EnvironmentPreset, TracksideObject, WaterFeatures, Splatmap, TerrainLod, Lighting, LightSource};
+pub use manifest::WorldParams;
 pub use terrain::generate_procedural_world;
@@ -1,4 +1,5 @@
 /// Multi-octave noise generation for terrain
+use super::world_data::NoiseLayer;
 use noise::{NoiseFn, Perlin};
 
 /// Multi-layer noise sampler for natural terrain generation
@@ -87,6 +88,62 @@ impl TerrainNoise {
     }
 }
 
+/// SplitMix64: small, fast and the same on every platform
+pub(crate) struct SplitMix64(pub u64);
+
+impl SplitMix64 {
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `min..max`
+    pub fn range(&mut self, min: f64, max: f64) -> f64 {
+        min + (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64 * (max - min)
+    }
+}
+
+/// Noise sampler for a biome's own stack of layers
+pub struct StackNoise {
+    layers: Vec<(Perlin, NoiseLayer)>,
+    total_amplitude: f32,
+}
+
+impl StackNoise {
+    /// Create a sampler for `layers`, each seeded from `seed` and its index
+    pub fn new(seed: u32, layers: &[NoiseLayer]) -> Self {
+        Self {
+            layers: layers
+                .iter()
+                .enumerate()
+                .map(|(i, layer)| (Perlin::new(seed.wrapping_add(i as u32)), *layer))
+                .collect(),
+            total_amplitude: layers.iter().map(|l| l.amplitude.abs()).sum(),
+        }
+    }
+
+    /// Sample the stack at world position, normalized to [0, 1]
+    pub fn sample(&self, x: f32, y: f32) -> f32 {
+        if self.total_amplitude <= 0.0 {
+            return 0.0;
+        }
+        let sum: f32 = self
+            .layers
+            .iter()
+            .map(|(perlin, layer)| {
+                let n = perlin.get([(x * layer.frequency) as f64, (y * layer.frequency) as f64]) as f32;
+                // Ridged noise folds the zero crossings into crests
+                let n = if layer.ridged { 1.0 - 2.0 * n.abs() } else { n };
+                n * layer.amplitude
+            })
+            .sum();
+        ((sum / self.total_amplitude + 1.0) * 0.5).clamp(0.0, 1.0)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -141,4 +198,21 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_stack_noise_is_normalized_and_deterministic() {
+        let layers = [NoiseLayer::smooth(0.003, 1.0), NoiseLayer::ridged(0.01, 0.5)];
+        let a = StackNoise::new(7, &layers);
+        let b = StackNoise::new(7, &layers);
+
+        for x in 0..20 {
+            for y in 0..20 {
+                let (wx, wy) = (x as f32 * 37.0, y as f32 * 53.0);
+                let value = a.sample(wx, wy);
+                assert!((0.0..=1.0).contains(&value), "Stack value {} out of range", value);
+                assert_eq!(value, b.sample(wx, wy));
+            }
+        }
+        assert_eq!(StackNoise::new(7, &[]).sample(10.0, 10.0), 0.0);
+    }
 }
@@ -0,0 +1,326 @@
+//! Trackside object placement
+//!
+//! Objects are placed by rules relative to the centerline: grandstands
+//! beside the start/finish line, marshal posts at even intervals, tire
+//! walls on the outside of tight corners, barriers along the remaining
+//! edges, and the biome's scenery scattered beyond a clear zone. Nothing is
+//! left standing in water. All of it is drawn from the world seed, so the
+//! same world gets the same objects.
+
+use super::noise::SplitMix64;
+use super::world_data::{EnvironmentPreset, TerrainHeightmap, TracksideObject, WaterFeatures};
+use crate::data::TrackPoint;
+use std::collections::HashMap;
+use std::f32::consts::FRAC_PI_2;
+
+pub const GRANDSTAND: &str = "grandstand";
+pub const MARSHAL_POST: &str = "marshal_post";
+pub const TIRE_WALL: &str = "tire_wall";
+pub const BARRIER: &str = "barrier";
+
+/// Track either side of the start/finish line lined with grandstands
+const GRANDSTAND_SPAN_M: f32 = 150.0;
+const GRANDSTAND_SPACING_M: f32 = 40.0;
+const GRANDSTAND_GAP_M: f32 = 20.0;
+const MARSHAL_SPACING_M: f32 = 400.0;
+const MARSHAL_GAP_M: f32 = 8.0;
+/// Corners tighter than this radius get a tire wall on the outside
+const TIRE_WALL_MAX_RADIUS_M: f32 = 120.0;
+const TIRE_WALL_SPACING_M: f32 = 8.0;
+const TIRE_WALL_GAP_M: f32 = 15.0;
+const BARRIER_SPACING_M: f32 = 12.0;
+const BARRIER_GAP_M: f32 = 4.0;
+/// Track either side of a point used to measure how fast it turns
+const CURVATURE_WINDOW_M: f32 = 10.0;
+/// Scenery spacing at density 1; sparser biomes space it out further
+const SCENERY_SPACING_M: f32 = 25.0;
+/// Scenery keeps at least this far from the centerline
+const SCENERY_CLEARANCE_M: f32 = 45.0;
+/// Objects keep at least this far from lakes and rivers
+const WATER_CLEARANCE_M: f32 = 3.0;
+
+/// Place the trackside objects and scenery of a world
+pub fn place_trackside_objects(
+    track_points: &[TrackPoint],
+    heightmap: &TerrainHeightmap,
+    preset: &EnvironmentPreset,
+    water: &WaterFeatures,
+    object_density: f32,
+    seed: u32,
+) -> Vec<TracksideObject> {
+    let mut objects = Vec::new();
+    if track_points.len() < 2 {
+        return objects;
+    }
+    let lap_m = track_points.last().map(|p| p.distance_from_start_m).unwrap_or(0.0);
+    let object = |kind: &str, i: usize, side: f32, gap_m: f32, heading_rad: f32| {
+        let point = &track_points[i];
+        let heading = track_heading(track_points, i);
+        let offset = if side > 0.0 { point.width_left_m } else { point.width_right_m } + gap_m;
+        // Left of the direction of travel is +90°
+        let x = point.x - heading.sin() * side * offset;
+        let y = point.y + heading.cos() * side * offset;
+        TracksideObject { kind: kind.to_string(), x, y, z: heightmap.sample(x, y), heading_rad, scale: 1.0 }
+    };
+
+    // Grandstands on the left of the start/finish line, facing the track
+    for i in every(track_points, GRANDSTAND_SPACING_M) {
+        let d = track_points[i].distance_from_start_m;
+        if d <= GRANDSTAND_SPAN_M || d >= lap_m - GRANDSTAND_SPAN_M {
+            let facing = track_heading(track_points, i) - FRAC_PI_2;
+            objects.push(object(GRANDSTAND, i, 1.0, GRANDSTAND_GAP_M, facing));
+        }
+    }
+
+    // Marshal posts on the right, facing the track
+    for i in every(track_points, MARSHAL_SPACING_M).skip(1) {
+        let facing = track_heading(track_points, i) + FRAC_PI_2;
+        objects.push(object(MARSHAL_POST, i, -1.0, MARSHAL_GAP_M, facing));
+    }
+
+    // Tire walls on the outside of tight corners, barriers everywhere else
+    for i in every(track_points, TIRE_WALL_SPACING_M) {
+        let curvature = curvature(track_points, i);
+        if curvature.abs() > 1.0 / TIRE_WALL_MAX_RADIUS_M {
+            // Turning left (positive curvature) runs wide to the right
+            let outside = -curvature.signum();
+            objects.push(object(TIRE_WALL, i, outside, TIRE_WALL_GAP_M, track_heading(track_points, i)));
+        }
+    }
+    for i in every(track_points, BARRIER_SPACING_M) {
+        let curvature = curvature(track_points, i);
+        let heading = track_heading(track_points, i);
+        for side in [1.0, -1.0] {
+            let walled = curvature.abs() > 1.0 / TIRE_WALL_MAX_RADIUS_M && side == -curvature.signum();
+            if !walled {
+                objects.push(object(BARRIER, i, side, BARRIER_GAP_M, heading));
+            }
+        }
+    }
+
+    objects.extend(scatter_scenery(track_points, heightmap, preset, object_density, seed));
+    objects.retain(|object| !water.covers(object.x, object.y, WATER_CLEARANCE_M));
+    objects
+}
+
+/// The biome's scenery on a jittered grid, kept clear of the track
+fn scatter_scenery(
+    track_points: &[TrackPoint],
+    heightmap: &TerrainHeightmap,
+    preset: &EnvironmentPreset,
+    object_density: f32,
+    seed: u32,
+) -> Vec<TracksideObject> {
+    if object_density <= 0.0 || preset.allowed_objects.is_empty() {
+        return Vec::new();
+    }
+    let spacing = SCENERY_SPACING_M / object_density.min(1.0).sqrt();
+    let bucket_of = |x: f32, y: f32| ((x / SCENERY_CLEARANCE_M).floor() as i32, (y / SCENERY_CLEARANCE_M).floor() as i32);
+    let mut buckets: HashMap<(i32, i32), Vec<&TrackPoint>> = HashMap::new();
+    for point in track_points {
+        buckets.entry(bucket_of(point.x, point.y)).or_default().push(point);
+    }
+    let clear_of_track = |x: f32, y: f32| {
+        let (bx, by) = bucket_of(x, y);
+        (-1..=1).all(|dx| {
+            (-1..=1).all(|dy| {
+                buckets.get(&(bx + dx, by + dy)).is_none_or(|points| {
+                    points.iter().all(|p| {
+                        let clearance = SCENERY_CLEARANCE_M.max(p.width_left_m.max(p.width_right_m) + 10.0);
+                        (p.x - x).hypot(p.y - y) >= clearance
+                    })
+                })
+            })
+        })
+    };
+
+    // A stream of its own, so trackside rules never shift the scenery
+    let mut rng = SplitMix64(seed as u64 ^ 0x5CE7_E4E5);
+    let width_m = (heightmap.width.saturating_sub(1)) as f32 * heightmap.cell_size_m;
+    let height_m = (heightmap.height.saturating_sub(1)) as f32 * heightmap.cell_size_m;
+    let mut scenery = Vec::new();
+    let mut cy = 0.0;
+    while cy < height_m {
+        let mut cx = 0.0;
+        while cx < width_m {
+            let x = heightmap.origin_x + cx + rng.range(0.0, spacing as f64) as f32;
+            let y = heightmap.origin_y + cy + rng.range(0.0, spacing as f64) as f32;
+            let pick = (rng.next_u64() % preset.allowed_objects.len() as u64) as usize;
+            let heading_rad = rng.range(0.0, std::f64::consts::TAU) as f32;
+            let scale = rng.range(0.8, 1.2) as f32;
+            if clear_of_track(x, y) {
+                scenery.push(TracksideObject {
+                    kind: preset.allowed_objects[pick].clone(),
+                    x,
+                    y,
+                    z: heightmap.sample(x, y),
+                    heading_rad,
+                    scale,
+                });
+            }
+            cx += spacing;
+        }
+        cy += spacing;
+    }
+    scenery
+}
+
+/// Index of the first point at or past each multiple of `spacing_m` along the track
+fn every(track_points: &[TrackPoint], spacing_m: f32) -> impl Iterator<Item = usize> + '_ {
+    let mut next_m = 0.0;
+    (0..track_points.len()).filter(move |&i| {
+        let d = track_points[i].distance_from_start_m;
+        if d + f32::EPSILON < next_m {
+            return false;
+        }
+        next_m = (d / spacing_m).floor() * spacing_m + spacing_m;
+        true
+    })
+}
+
+/// Direction of travel at point `i`
+fn track_heading(track_points: &[TrackPoint], i: usize) -> f32 {
+    let (a, b) = if i + 1 < track_points.len() { (&track_points[i], &track_points[i + 1]) } else { (&track_points[i - 1], &track_points[i]) };
+    (b.y - a.y).atan2(b.x - a.x)
+}
+
+/// Signed curvature at point `i` in 1/m; positive turns left
+fn curvature(track_points: &[TrackPoint], i: usize) -> f32 {
+    let point = &track_points[i];
+    let d = point.distance_from_start_m;
+    let before = track_points[..i].iter().rev().find(|p| d - p.distance_from_start_m >= CURVATURE_WINDOW_M);
+    let after = track_points[i + 1..].iter().find(|p| p.distance_from_start_m - d >= CURVATURE_WINDOW_M);
+    let (Some(before), Some(after)) = (before, after) else {
+        return 0.0;
+    };
+    let heading_in = (point.y - before.y).atan2(point.x - before.x);
+    let heading_out = (after.y - point.y).atan2(after.x - point.x);
+    let mut turn = heading_out - heading_in;
+    while turn > std::f32::consts::PI {
+        turn -= std::f32::consts::TAU;
+    }
+    while turn < -std::f32::consts::PI {
+        turn += std::f32::consts::TAU;
+    }
+    // The chords turn through half the arc on each side of the point
+    turn / (0.5 * (after.distance_from_start_m - before.distance_from_start_m)).max(f32::EPSILON)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::SurfaceType;
+    use crate::procgen::world_data::{Lake, River, RiverPoint};
+    use std::f32::consts::PI;
+
+    /// Anticlockwise oval: 300 m straights joined by 80 m radius hairpins
+    fn oval() -> Vec<TrackPoint> {
+        let mut xy = Vec::new();
+        for i in 0..300 {
+            xy.push((i as f32, 0.0));
+        }
+        for i in 0..126 {
+            let a = -FRAC_PI_2 + PI * i as f32 / 126.0;
+            xy.push((300.0 + 80.0 * a.cos(), 80.0 + 80.0 * a.sin()));
+        }
+        for i in 0..300 {
+            xy.push((300.0 - i as f32, 160.0));
+        }
+        for i in 0..126 {
+            let a = FRAC_PI_2 + PI * i as f32 / 126.0;
+            xy.push((80.0 * a.cos(), 80.0 + 80.0 * a.sin()));
+        }
+        let mut distance = 0.0;
+        let mut points: Vec<TrackPoint> = Vec::new();
+        for (x, y) in xy {
+            if let Some(last) = points.last() {
+                distance += (x - last.x).hypot(y - last.y);
+            }
+            points.push(TrackPoint {
+                x,
+                y,
+                z: 0.0,
+                distance_from_start_m: distance,
+                width_left_m: 6.0,
+                width_right_m: 6.0,
+                banking_rad: 0.0,
+                camber_rad: 0.0,
+                slope_rad: 0.0,
+                heading_rad: 0.0,
+                surface_type: SurfaceType::Asphalt,
+                grip_modifier: 1.0,
+                runoff_surface: SurfaceType::Grass,
+            });
+        }
+        points
+    }
+
+    fn place_by(water: &WaterFeatures, seed: u32) -> Vec<TracksideObject> {
+        let heightmap = TerrainHeightmap::new(160, 120, 5.0, -200.0, -200.0);
+        place_trackside_objects(&oval(), &heightmap, &EnvironmentPreset::forest(), water, 0.8, seed)
+    }
+
+    fn place(seed: u32) -> Vec<TracksideObject> {
+        place_by(&WaterFeatures::default(), seed)
+    }
+
+    #[test]
+    fn test_objects_follow_the_placement_rules() {
+        let track = oval();
+        let objects = place(3);
+        let of_kind = |kind: &str| objects.iter().filter(|o| o.kind == kind).collect::<Vec<_>>();
+
+        // Grandstands only near the line, on the left: inside an anticlockwise oval
+        let grandstands = of_kind(GRANDSTAND);
+        assert!(grandstands.len() >= 6);
+        for stand in &grandstands {
+            assert!(stand.x.hypot(stand.y) < GRANDSTAND_SPAN_M + 30.0);
+            let inset = if stand.x >= 0.0 { stand.y } else { 80.0 - stand.x.hypot(stand.y - 80.0) };
+            assert!((inset - 26.0).abs() < 1.0, "grandstand at ({}, {})", stand.x, stand.y);
+        }
+
+        // The 1100 m or so lap has a marshal post every 400 m past the start
+        assert_eq!(of_kind(MARSHAL_POST).len(), 2);
+
+        // Tire walls only in the hairpins and always on the outside
+        let walls = of_kind(TIRE_WALL);
+        assert!(!walls.is_empty());
+        for wall in &walls {
+            let centre_x = if wall.x > 150.0 { 300.0 } else { 0.0 };
+            let radius = (wall.x - centre_x).hypot(wall.y - 80.0);
+            assert!(radius > 80.0 + 6.0, "tire wall inside the corner at ({}, {})", wall.x, wall.y);
+            assert!(wall.x < 0.0 || wall.x > 300.0);
+        }
+        assert!(of_kind(BARRIER).len() > walls.len());
+
+        // Scenery is from the biome and clear of the track
+        let scenery: Vec<_> = objects.iter().filter(|o| EnvironmentPreset::forest().allowed_objects.contains(&o.kind)).collect();
+        assert!(!scenery.is_empty());
+        for object in &scenery {
+            let nearest = track.iter().map(|p| (p.x - object.x).hypot(p.y - object.y)).fold(f32::MAX, f32::min);
+            assert!(nearest >= SCENERY_CLEARANCE_M, "{} only {:.1} m from the track", object.kind, nearest);
+        }
+    }
+
+    #[test]
+    fn test_nothing_stands_in_water() {
+        // A pond in the infield and a stream outside the far straight
+        let water = WaterFeatures {
+            lakes: vec![Lake { level: 0.0, rects: vec![[100.0, 50.0, 200.0, 110.0]] }],
+            rivers: vec![River {
+                points: (0..=60).map(|i| RiverPoint { x: i as f32 * 5.0, y: 250.0, level: 0.0, width: 8.0 }).collect(),
+            }],
+        };
+        let dry = place(3);
+        let objects = place_by(&water, 3);
+        assert!(objects.len() < dry.len());
+        assert!(objects.iter().all(|o| !water.covers(o.x, o.y, WATER_CLEARANCE_M)));
+        assert!(dry.iter().any(|o| water.covers(o.x, o.y, 0.0)));
+    }
+
+    #[test]
+    fn test_same_seed_same_objects() {
+        assert_eq!(place(3), place(3));
+        assert_ne!(place(3), place(4));
+    }
+}
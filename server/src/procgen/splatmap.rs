@@ -0,0 +1,221 @@
+//! Texture splatmap
+//!
+//! Weighs four ground cover layers for every heightmap cell, so clients
+//! texture the terrain alike. Worn dirt lines the edges of the road and
+//! covers steep banks, bare rock shows on the steepest slopes, sand covers
+//! the shores of lakes and rivers and, in biomes with a `sand_below`, the
+//! low ground, and grass takes whatever is left.
+
+use super::terrain::{lateral_offset, TrackGrid};
+use super::world_data::{EnvironmentPreset, Splatmap, TerrainHeightmap, WaterFeatures};
+use crate::data::TrackPoint;
+
+const GRASS: usize = 0;
+const DIRT: usize = 1;
+const ROCK: usize = 2;
+const SAND: usize = 3;
+
+/// Worn ground reaching out from the edge of the road (m)
+const DIRT_VERGE_M: f32 = 4.0;
+/// Slopes turn from grass to dirt between these angles (degrees)
+const DIRT_SLOPE_DEG: (f32, f32) = (20.0, 30.0);
+/// Slopes turn to bare rock between these angles (degrees)
+const ROCK_SLOPE_DEG: (f32, f32) = (30.0, 45.0);
+/// Share of the height range over which sand gives way to grass
+const SAND_BLEND: f32 = 0.1;
+
+/// Ground cover of every cell of the finished, carved terrain
+pub fn generate_splatmap(
+    heightmap: &TerrainHeightmap,
+    track_points: &[TrackPoint],
+    preset: &EnvironmentPreset,
+    water: &WaterFeatures,
+) -> Splatmap {
+    let (width, height) = (heightmap.width, heightmap.height);
+    let max_edge = track_points
+        .iter()
+        .map(|p| p.width_left_m.max(p.width_right_m))
+        .fold(0.0_f32, f32::max);
+    let grid = TrackGrid::new(track_points, max_edge + DIRT_VERGE_M);
+    let shore = shore_mask(heightmap, water);
+    let low = heightmap.heights.iter().cloned().fold(f32::MAX, f32::min);
+    let high = heightmap.heights.iter().cloned().fold(f32::MIN, f32::max);
+    let range = (high - low).max(f32::EPSILON);
+
+    let mut weights = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        for x in 0..width {
+            let world_x = heightmap.origin_x + x as f32 * heightmap.cell_size_m;
+            let world_y = heightmap.origin_y + y as f32 * heightmap.cell_size_m;
+
+            let verge = grid.nearest(world_x, world_y).map_or(0.0, |(point, dist)| {
+                let edge = if lateral_offset(point, world_x, world_y) >= 0.0 { point.width_left_m } else { point.width_right_m };
+                1.0 - smoothstep(0.0, DIRT_VERGE_M, dist - edge)
+            });
+            let slope = slope_deg(heightmap, x, y);
+            let altitude = (heightmap.get_height(x, y) - low) / range;
+            let sand = if shore[y * width + x] { 1.0 } else { sand_cover(preset.sand_below, altitude) };
+
+            // Each rule covers its share of what the ones before it left
+            let mut cover = [0.0_f32; 4];
+            let mut left = 1.0;
+            for (layer, share) in [
+                (DIRT, verge),
+                (ROCK, smoothstep(ROCK_SLOPE_DEG.0, ROCK_SLOPE_DEG.1, slope)),
+                (DIRT, smoothstep(DIRT_SLOPE_DEG.0, DIRT_SLOPE_DEG.1, slope)),
+                (SAND, sand),
+            ] {
+                let taken = share * left;
+                cover[layer] += taken;
+                left -= taken;
+            }
+            cover[GRASS] += left;
+            weights.extend(quantize(cover));
+        }
+    }
+
+    Splatmap { width, height, weights }
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Sand on ground below `sand_below` of the height range, fading into grass
+fn sand_cover(sand_below: f32, altitude: f32) -> f32 {
+    if sand_below <= 0.0 {
+        0.0
+    } else if sand_below >= 1.0 {
+        1.0
+    } else {
+        1.0 - smoothstep(sand_below - SAND_BLEND / 2.0, sand_below + SAND_BLEND / 2.0, altitude)
+    }
+}
+
+/// Steepest slope of the terrain at a cell, from its neighbours either side
+fn slope_deg(heightmap: &TerrainHeightmap, x: usize, y: usize) -> f32 {
+    let (x0, x1) = (x.saturating_sub(1), (x + 1).min(heightmap.width - 1));
+    let (y0, y1) = (y.saturating_sub(1), (y + 1).min(heightmap.height - 1));
+    let dx = (heightmap.get_height(x1, y) - heightmap.get_height(x0, y)) / ((x1 - x0).max(1) as f32 * heightmap.cell_size_m);
+    let dy = (heightmap.get_height(x, y1) - heightmap.get_height(x, y0)) / ((y1 - y0).max(1) as f32 * heightmap.cell_size_m);
+    dx.hypot(dy).atan().to_degrees()
+}
+
+/// Cells under water or within a cell of it
+fn shore_mask(heightmap: &TerrainHeightmap, water: &WaterFeatures) -> Vec<bool> {
+    let cell = heightmap.cell_size_m;
+    let mut mask = vec![false; heightmap.width * heightmap.height];
+    let mut mark = |min_x: f32, min_y: f32, max_x: f32, max_y: f32, inside: &dyn Fn(f32, f32) -> bool| {
+        let to_cell = |v: f32, origin: f32, count: usize| ((v - origin) / cell).clamp(0.0, count as f32 - 1.0);
+        let (x0, x1) = (to_cell(min_x, heightmap.origin_x, heightmap.width).floor() as usize, to_cell(max_x, heightmap.origin_x, heightmap.width).ceil() as usize);
+        let (y0, y1) = (to_cell(min_y, heightmap.origin_y, heightmap.height).floor() as usize, to_cell(max_y, heightmap.origin_y, heightmap.height).ceil() as usize);
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                if inside(heightmap.origin_x + x as f32 * cell, heightmap.origin_y + y as f32 * cell) {
+                    mask[y * heightmap.width + x] = true;
+                }
+            }
+        }
+    };
+    for rect in water.lakes.iter().flat_map(|lake| &lake.rects) {
+        mark(rect[0] - cell, rect[1] - cell, rect[2] + cell, rect[3] + cell, &|_, _| true);
+    }
+    for point in water.rivers.iter().flat_map(|river| &river.points) {
+        let reach = point.width / 2.0 + cell;
+        mark(point.x - reach, point.y - reach, point.x + reach, point.y + reach, &|x, y| (x - point.x).hypot(y - point.y) <= reach);
+    }
+    mask
+}
+
+/// Byte weights summing to exactly 255
+fn quantize(cover: [f32; 4]) -> [u8; 4] {
+    let mut weights = [0; 4];
+    let (mut total, mut given) = (0.0, 0);
+    for (weight, share) in weights.iter_mut().zip(cover) {
+        total += share;
+        let upto = (total * 255.0).round().min(255.0) as u32;
+        *weight = (upto - given) as u8;
+        given = upto;
+    }
+    weights[GRASS] += (255 - given) as u8;
+    weights
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::SurfaceType;
+    use crate::procgen::world_data::{Lake, SPLAT_LAYERS};
+
+    /// Road 10 m wide running north along x = 0, on flat ground that rises
+    /// up a 45° bank east of x = 150 to a plateau 30 m up
+    fn world() -> (TerrainHeightmap, Vec<TrackPoint>) {
+        let mut heightmap = TerrainHeightmap::new(81, 41, 5.0, -200.0, 0.0);
+        for y in 0..41 {
+            for x in 0..81 {
+                let world_x = -200.0 + x as f32 * 5.0;
+                heightmap.set_height(x, y, (world_x - 150.0).clamp(0.0, 30.0));
+            }
+        }
+        let track = (0..=20)
+            .map(|i| TrackPoint {
+                x: 0.0,
+                y: i as f32 * 10.0,
+                z: 0.0,
+                distance_from_start_m: i as f32 * 10.0,
+                width_left_m: 5.0,
+                width_right_m: 5.0,
+                banking_rad: 0.0,
+                camber_rad: 0.0,
+                slope_rad: 0.0,
+                heading_rad: std::f32::consts::FRAC_PI_2,
+                surface_type: SurfaceType::Asphalt,
+                grip_modifier: 1.0,
+                runoff_surface: SurfaceType::Grass,
+            })
+            .collect();
+        (heightmap, track)
+    }
+
+    /// Heaviest layer at world `(x, y)`
+    fn layer_at(splatmap: &Splatmap, heightmap: &TerrainHeightmap, x: f32, y: f32) -> &'static str {
+        let weights = splatmap.weights_at(
+            ((x - heightmap.origin_x) / heightmap.cell_size_m) as usize,
+            ((y - heightmap.origin_y) / heightmap.cell_size_m) as usize,
+        );
+        let heaviest = (0..4).max_by_key(|&i| weights[i]).unwrap();
+        SPLAT_LAYERS[heaviest]
+    }
+
+    #[test]
+    fn test_cover_follows_road_slope_and_water() {
+        let (heightmap, track) = world();
+        let water = WaterFeatures {
+            lakes: vec![Lake { level: 0.0, rects: vec![[-150.0, 100.0, -120.0, 130.0]] }],
+            rivers: Vec::new(),
+        };
+        let splatmap = generate_splatmap(&heightmap, &track, &EnvironmentPreset::forest(), &water);
+
+        assert_eq!((splatmap.width, splatmap.height), (heightmap.width, heightmap.height));
+        assert!(splatmap.weights.chunks(4).all(|cell| cell.iter().map(|&w| w as u32).sum::<u32>() == 255));
+        assert_eq!(layer_at(&splatmap, &heightmap, 5.0, 100.0), "dirt");
+        assert_eq!(layer_at(&splatmap, &heightmap, 50.0, 100.0), "grass");
+        assert_eq!(layer_at(&splatmap, &heightmap, 165.0, 100.0), "rock");
+        assert_eq!(layer_at(&splatmap, &heightmap, -135.0, 115.0), "sand");
+        assert_eq!(layer_at(&splatmap, &heightmap, -135.0, 160.0), "grass");
+    }
+
+    #[test]
+    fn test_sand_covers_low_ground_of_sandy_biomes() {
+        let (heightmap, track) = world();
+        let water = WaterFeatures::default();
+        let desert = generate_splatmap(&heightmap, &track, &EnvironmentPreset::desert(), &water);
+        let coastal = generate_splatmap(&heightmap, &track, &EnvironmentPreset::coastal(), &water);
+
+        assert_eq!(layer_at(&desert, &heightmap, 50.0, 100.0), "sand");
+        assert_eq!(layer_at(&coastal, &heightmap, 50.0, 100.0), "sand");
+        // The plateau, above the coastal sand line
+        assert_eq!(layer_at(&coastal, &heightmap, 195.0, 100.0), "grass");
+    }
+}
@@ -1,51 +1,126 @@
 /// Core terrain generation logic
-use super::noise::TerrainNoise;
-use super::world_data::{EnvironmentPreset, ProceduralWorldData, TerrainHeightmap};
-use crate::data::TrackPoint;
+use super::dem::{dem_heightmap, drape_flat_track};
+use super::hydrology::generate_water;
+use super::lights::place_track_lights;
+use super::manifest::{load_manifest, save_manifest, WorldParams};
+use super::noise::{StackNoise, TerrainNoise};
+use super::objects::place_trackside_objects;
+use super::splatmap::generate_splatmap;
+use super::world_data::{EnvironmentPreset, ProceduralWorldData, TerrainHeightmap, TerrainLod};
+use crate::data::{TimeOfDay, TrackPoint};
+use crate::track_import::ElevationModel;
+use std::collections::HashMap;
 use std::path::Path;
 
+/// Cells along the side of a terrain tile at full resolution
+pub const LOD_TILE_CELLS: usize = 64;
+
 /// Generate complete procedural world data for a track
 ///
 /// This is the main entry point for procedural generation. It creates
-/// terrain, applies track corridor carving, and packages everything
-/// into a ProceduralWorldData structure.
+/// terrain, applies track corridor carving, places trackside objects,
+/// weighs the ground cover for texturing, builds coarser levels of the
+/// terrain for distant tiles, resolves the lighting at every time of day
+/// and packages everything
+/// into a ProceduralWorldData structure. The same track, `params` and
+/// elevation data always give the same world.
+///
+/// The heights come from `elevation` when the parameters name a
+/// `terrain_dem`, otherwise from the preset's noise.
 pub fn generate_procedural_world(
     track_points: &[TrackPoint],
-    environment_type: String,
-    seed: u32,
-    preset: EnvironmentPreset,
-    terrain_scale: f32,
-    blend_width: f32,
-    object_density: f32,
-    decal_profile: String,
+    params: &WorldParams,
+    elevation: Option<&mut dyn ElevationModel>,
 ) -> Result<ProceduralWorldData, String> {
+    let preset = &params.preset;
+
     // Generate heightmap
-    let mut heightmap = generate_terrain(track_points, seed, &preset, terrain_scale)?;
+    let (mut heightmap, draped) = match elevation {
+        None => (generate_terrain(track_points, params.seed, preset, params.terrain_scale)?, None),
+        Some(model) => {
+            let origin = params
+                .geo_origin
+                .ok_or("Elevation data needs the track's geo_origin to place it")?;
+            let heightmap = dem_heightmap(track_points, origin, model)?;
+            // A track without heights of its own follows the real ground
+            let draped = drape_flat_track(track_points, &heightmap);
+            (heightmap, draped)
+        }
+    };
+    let track_points = draped.as_deref().unwrap_or(track_points);
+
+    // Rivers and lakes follow the natural terrain, clear of the corridor
+    let water = generate_water(&mut heightmap, track_points, preset, corridor_reach(track_points, params.blend_width));
 
     // Carve track corridor
-    carve_track_corridor(&mut heightmap, track_points, blend_width);
+    carve_track_corridor(&mut heightmap, track_points, params.blend_width);
+
+    let objects = place_trackside_objects(track_points, &heightmap, preset, &water, params.object_density, params.seed);
+    println!("Placed {} trackside objects", objects.len());
+
+    let splatmap = generate_splatmap(&heightmap, track_points, preset, &water);
+    let lod = TerrainLod::build(&heightmap, LOD_TILE_CELLS);
 
     Ok(ProceduralWorldData {
-        environment_type,
-        seed,
+        environment_type: params.environment_type.clone(),
+        seed: params.seed,
         heightmap: Some(heightmap),
-        blend_width,
-        object_density,
-        decal_profile,
-        preset,
+        blend_width: params.blend_width,
+        object_density: params.object_density,
+        decal_profile: params.decal_profile.clone(),
+        preset: preset.clone(),
+        objects,
+        water,
+        splatmap: Some(splatmap),
+        lod: Some(lod),
+        lighting: TimeOfDay::ALL.iter().map(|&time_of_day| preset.lighting_at(time_of_day)).collect(),
+        // Placed by `bake_track_world`, which knows the start line and pit lane
+        lights: Vec::new(),
     })
 }
 
 /// Generate terrain heightmap around track
 ///
 /// Creates a heightmap using layered Perlin noise based on the
-/// environment preset parameters.
+/// environment preset parameters: the preset's own noise stack when it
+/// has one, otherwise the classic three octaves.
 pub fn generate_terrain(
     track_points: &[TrackPoint],
     seed: u32,
     preset: &EnvironmentPreset,
     terrain_scale: f32,
 ) -> Result<TerrainHeightmap, String> {
+    let mut heightmap = terrain_grid(track_points)?;
+    let (width, height, cell_size) = (heightmap.width, heightmap.height, heightmap.cell_size_m);
+    let (min_x, min_y) = (heightmap.origin_x, heightmap.origin_y);
+
+    // Generate noise-based terrain
+    let noise = TerrainNoise::new(seed);
+    let stack = StackNoise::new(seed, &preset.noise_layers);
+
+    for y in 0..height {
+        for x in 0..width {
+            let world_x = min_x + x as f32 * cell_size;
+            let world_y = min_y + y as f32 * cell_size;
+
+            let normalized = if preset.noise_layers.is_empty() {
+                // Map noise to height (noise is roughly [-1.75, 1.75], map to [0, max_height])
+                let noise_value = noise.sample(world_x, world_y, preset.detail_noise_freq);
+                (noise_value + 1.75) / 3.5 // Map to roughly [0, 1]
+            } else {
+                stack.sample(world_x, world_y)
+            };
+            let height_value = normalized * preset.max_height * terrain_scale;
+
+            heightmap.set_height(x, y, height_value);
+        }
+    }
+
+    Ok(heightmap)
+}
+
+/// Flat heightmap covering the track with a margin all round
+pub(crate) fn terrain_grid(track_points: &[TrackPoint]) -> Result<TerrainHeightmap, String> {
     if track_points.is_empty() {
         return Err("Cannot generate terrain for empty track".to_string());
     }
@@ -82,39 +157,21 @@ pub fn generate_terrain(
         height as f32 * cell_size
     );
 
-    // Create heightmap
-    let mut heightmap = TerrainHeightmap::new(width, height, cell_size, min_x, min_y);
-
-    // Generate noise-based terrain
-    let noise = TerrainNoise::new(seed);
-
-    for y in 0..height {
-        for x in 0..width {
-            let world_x = min_x + x as f32 * cell_size;
-            let world_y = min_y + y as f32 * cell_size;
-
-            // Sample noise
-            let noise_value = noise.sample(
-                world_x,
-                world_y,
-                preset.detail_noise_freq,
-            );
-
-            // Map noise to height (noise is roughly [-1.75, 1.75], map to [0, max_height])
-            let normalized = (noise_value + 1.75) / 3.5; // Map to roughly [0, 1]
-            let height_value = normalized * preset.max_height * terrain_scale;
-
-            heightmap.set_height(x, y, height_value);
-        }
-    }
-
-    Ok(heightmap)
+    Ok(TerrainHeightmap::new(width, height, cell_size, min_x, min_y))
 }
 
+/// Flat ground either side of the road for shoulders and runoff
+pub const SHOULDER_WIDTH_M: f32 = 10.0;
+/// Terrain under the road and its shoulders sits this far below the surface
+pub const ROAD_CLEARANCE_M: f32 = 0.05;
+
 /// Carve track corridor by flattening terrain around track
 ///
-/// This creates a smooth transition from the flat track surface
-/// to the surrounding terrain within the blend_width distance.
+/// The road and a shoulder of `SHOULDER_WIDTH_M` either side are flattened
+/// just below the lowest the banked or cambered surface gets, then blended
+/// smoothly into the surrounding terrain over `blend_width` meters. A last
+/// pass lowers any cell that would still poke through the road, so the
+/// road always sits on or slightly above the terrain.
 pub fn carve_track_corridor(
     heightmap: &mut TerrainHeightmap,
     track_points: &[TrackPoint],
@@ -122,35 +179,132 @@ pub fn carve_track_corridor(
 ) {
     println!("Carving track corridor with {} meter blend width", blend_width);
 
+    let grid = TrackGrid::new(track_points, corridor_reach(track_points, blend_width));
+
     for y in 0..heightmap.height {
         for x in 0..heightmap.width {
             let world_x = heightmap.origin_x + x as f32 * heightmap.cell_size_m;
             let world_y = heightmap.origin_y + y as f32 * heightmap.cell_size_m;
 
-            // Find distance to nearest track point
-            let (dist_to_track, nearest_track_z) = find_nearest_track_distance(
-                track_points,
-                world_x,
-                world_y,
-            );
-
-            // Apply smooth blend within blend_width
-            if dist_to_track < blend_width {
+            let Some((point, dist)) = grid.nearest(world_x, world_y) else {
+                continue;
+            };
+            let edge = if lateral_offset(point, world_x, world_y) >= 0.0 { point.width_left_m } else { point.width_right_m };
+            let flat_half_width = edge + SHOULDER_WIDTH_M;
+            let road_height = lowest_road_height(point, dist.min(edge)) - ROAD_CLEARANCE_M;
+
+            if dist <= flat_half_width {
+                heightmap.set_height(x, y, road_height);
+            } else if dist < flat_half_width + blend_width {
+                // Smoothstep from the flat corridor out to the natural terrain
+                let blend = (dist - flat_half_width) / blend_width;
+                let blend_smooth = blend * blend * (3.0 - 2.0 * blend);
                 let current_height = heightmap.get_height(x, y);
-                let target_height = nearest_track_z; // Track surface height
+                heightmap.set_height(x, y, road_height * (1.0 - blend_smooth) + current_height * blend_smooth);
+            }
+        }
+    }
 
-                // Smooth blend factor (0 at track, 1 at blend_width)
-                let blend = (dist_to_track / blend_width).clamp(0.0, 1.0);
-                // Use smoothstep for better visual result
-                let blend_smooth = blend * blend * (3.0 - 2.0 * blend);
+    settle_terrain_under_road(heightmap, track_points);
+}
 
-                // Interpolate between track height and terrain height
-                let blended_height = target_height * (1.0 - blend_smooth) + current_height * blend_smooth;
+/// Farthest from the centerline that carving the corridor changes the terrain
+fn corridor_reach(track_points: &[TrackPoint], blend_width: f32) -> f32 {
+    let max_width = track_points
+        .iter()
+        .map(|p| p.width_left_m.max(p.width_right_m))
+        .fold(0.0_f32, f32::max);
+    max_width + SHOULDER_WIDTH_M + blend_width.max(0.0)
+}
 
-                heightmap.set_height(x, y, blended_height);
+/// Lower the cells under any spot where the terrain still rises through the road
+///
+/// Samples across the width of the road at every track point. A bilinear
+/// sample never exceeds its four cells, so lowering those four is enough.
+fn settle_terrain_under_road(heightmap: &mut TerrainHeightmap, track_points: &[TrackPoint]) {
+    let step = heightmap.cell_size_m * 0.5;
+    let mut lowered = 0;
+    for point in track_points {
+        let (normal_x, normal_y) = (-point.heading_rad.sin(), point.heading_rad.cos());
+        let samples = ((point.width_left_m + point.width_right_m) / step).ceil().max(1.0) as usize;
+        for i in 0..=samples {
+            let offset = -point.width_right_m + (point.width_left_m + point.width_right_m) * i as f32 / samples as f32;
+            let (world_x, world_y) = (point.x + normal_x * offset, point.y + normal_y * offset);
+            let limit = lowest_road_height(point, offset.abs()) - ROAD_CLEARANCE_M;
+            if heightmap.sample(world_x, world_y) <= limit {
+                continue;
+            }
+            let grid_x = ((world_x - heightmap.origin_x) / heightmap.cell_size_m).floor();
+            let grid_y = ((world_y - heightmap.origin_y) / heightmap.cell_size_m).floor();
+            if grid_x < 0.0 || grid_y < 0.0 {
+                continue;
+            }
+            let (x0, y0) = (grid_x as usize, grid_y as usize);
+            for (x, y) in [(x0, y0), (x0 + 1, y0), (x0, y0 + 1), (x0 + 1, y0 + 1)] {
+                if x < heightmap.width && y < heightmap.height && heightmap.get_height(x, y) > limit {
+                    heightmap.set_height(x, y, limit);
+                    lowered += 1;
+                }
             }
         }
     }
+    if lowered > 0 {
+        println!("Lowered {} terrain cells poking through the road", lowered);
+    }
+}
+
+/// Lowest the road surface gets `offset` meters from the centerline
+///
+/// Banking and camber tilt the surface across its width; whichever way
+/// they lean, it is never lower than this.
+fn lowest_road_height(point: &TrackPoint, offset: f32) -> f32 {
+    point.z - offset * (point.banking_rad.sin().abs() + point.camber_rad.sin().abs())
+}
+
+/// Signed distance from the centerline at `point`, positive to the left
+pub(super) fn lateral_offset(point: &TrackPoint, world_x: f32, world_y: f32) -> f32 {
+    (world_y - point.y) * point.heading_rad.cos() - (world_x - point.x) * point.heading_rad.sin()
+}
+
+/// Track points bucketed on a square grid, for nearest-point lookups near the track
+pub(super) struct TrackGrid<'a> {
+    points: &'a [TrackPoint],
+    cell: f32,
+    buckets: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl<'a> TrackGrid<'a> {
+    /// Lookups find the nearest point within `reach` meters
+    pub(super) fn new(points: &'a [TrackPoint], reach: f32) -> Self {
+        let cell = reach.max(1.0);
+        let mut buckets: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+        for (i, point) in points.iter().enumerate() {
+            buckets.entry(Self::bucket(cell, point.x, point.y)).or_default().push(i);
+        }
+        Self { points, cell, buckets }
+    }
+
+    fn bucket(cell: f32, x: f32, y: f32) -> (i32, i32) {
+        ((x / cell).floor() as i32, (y / cell).floor() as i32)
+    }
+
+    /// Nearest track point and its distance, if one is within reach
+    pub(super) fn nearest(&self, x: f32, y: f32) -> Option<(&'a TrackPoint, f32)> {
+        let (bx, by) = Self::bucket(self.cell, x, y);
+        let mut best: Option<(&'a TrackPoint, f32)> = None;
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for &i in self.buckets.get(&(bx + dx, by + dy)).into_iter().flatten() {
+                    let point = &self.points[i];
+                    let dist = (point.x - x).hypot(point.y - y);
+                    if dist <= self.cell && best.is_none_or(|(_, d)| dist < d) {
+                        best = Some((point, dist));
+                    }
+                }
+            }
+        }
+        best
+    }
 }
 
 /// Apply terrain elevation to track points
@@ -239,33 +393,245 @@ fn calculate_bounds(track_points: &[TrackPoint]) -> (f32, f32, f32, f32) {
     (min_x, min_y, max_x, max_y)
 }
 
-/// Find distance to nearest track point and its elevation
-fn find_nearest_track_distance(
-    track_points: &[TrackPoint],
-    world_x: f32,
-    world_y: f32,
-) -> (f32, f32) {
-    let mut min_dist = f32::MAX;
-    let mut nearest_z = 0.0;
+/// Batch generate terrain for all tracks in a directory
+///
+/// This function scans the tracks directory, finds all tracks with
+/// `environment_type` metadata, generates terrain for them, and saves
+/// the procedural data to cache files.
+pub fn generate_all_terrain(tracks_dir: &str) -> Result<usize, String> {
+    use std::path::Path;
 
-    for point in track_points {
-        let dx = world_x - point.x;
-        let dy = world_y - point.y;
-        let dist = (dx * dx + dy * dy).sqrt();
+    let tracks_path = Path::new(tracks_dir);
+    if !tracks_path.exists() {
+        return Err(format!("Tracks directory not found: {}", tracks_dir));
+    }
+
+    println!("Scanning tracks directory: {}", tracks_dir);
+
+    let mut generated_count = 0;
+    let mut files_to_process = Vec::new();
+
+    // Collect all YAML/JSON track files
+    collect_track_files(tracks_path, &mut files_to_process)?;
+
+    println!("Found {} track file(s)", files_to_process.len());
 
-        if dist < min_dist {
-            min_dist = dist;
-            nearest_z = point.z;
+    for track_file in files_to_process {
+        match process_track_for_terrain(&track_file) {
+            Ok(true) => {
+                generated_count += 1;
+                println!("  ✅ Generated terrain for: {}", track_file.display());
+            }
+            Ok(false) => {
+                println!("  ⏭️  Skipped (no environment_type): {}", track_file.display());
+            }
+            Err(e) => {
+                eprintln!("  ❌ Failed to process {}: {}", track_file.display(), e);
+            }
         }
     }
 
-    (min_dist, nearest_z)
+    Ok(generated_count)
+}
+
+/// Track files in `dir` and its subdirectories, without the files that sit
+/// next to them
+pub fn track_files(dir: &Path) -> Result<Vec<std::path::PathBuf>, String> {
+    let mut files = Vec::new();
+    collect_track_files(dir, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+fn collect_track_files(dir: &Path, files: &mut Vec<std::path::PathBuf>) -> Result<(), String> {
+    use crate::track_loader::TrackLoader;
+    use std::fs;
+
+    let entries = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read directory {:?}: {}", dir, e))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read dir entry: {}", e))?;
+        let path = entry.path();
+
+        if path.is_dir() {
+            // Recursively scan subdirectories
+            collect_track_files(&path, files)?;
+        } else if let Some(ext) = path.extension() {
+            let ext_str = ext.to_string_lossy();
+            if (ext_str == "yaml" || ext_str == "yml" || ext_str == "json") && !TrackLoader::is_sidecar_file(&path) {
+                files.push(path);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Generate and cache the terrain of one track file with `environment_type`
+/// metadata; false when it has none or its cache is up to date
+///
+/// The parameters the world was generated from are written next to the
+/// cache. A cache is up to date when it is newer than the track file and
+/// its manifest matches the parameters the track gives now.
+pub fn process_track_for_terrain(track_file: &Path) -> Result<bool, String> {
+    bake_track_world(track_file, None, false)
+}
+
+/// Generate and cache the world of one track file, from `params` when
+/// given and otherwise from its metadata; false when it has no
+/// `environment_type` or, unless `force`, its cache is up to date
+pub fn bake_track_world(track_file: &Path, params: Option<WorldParams>, force: bool) -> Result<bool, String> {
+    use std::fs;
+
+    // Load track file to get metadata
+    let content = fs::read_to_string(track_file)
+        .map_err(|e| format!("Failed to read track file: {}", e))?;
+
+    let track_file_format: crate::track_loader::TrackFileFormat = if content.trim_start().starts_with('{') {
+        serde_json::from_str(&content)
+            .map_err(|e| format!("JSON parse error: {}", e))?
+    } else {
+        serde_yaml::from_str(&content)
+            .map_err(|e| format!("YAML parse error: {}", e))?
+    };
+
+    let metadata = track_file_format.metadata.clone().unwrap_or_default();
+
+    // Check if it needs procedural generation
+    let params = match params {
+        Some(params) => params,
+        None if metadata.environment_type.is_none() => return Ok(false), // Skip, no procedural metadata
+        None => WorldParams::from_metadata(&track_file_format.name, &metadata)?,
+    };
+
+    // Check if terrain cache already exists and is up-to-date
+    let cache_path = get_terrain_cache_path(track_file);
+    let manifest = load_manifest(track_file);
+    if cache_path.exists() && !force {
+        // Check if cache is newer than source file
+        let source_modified = fs::metadata(track_file)
+            .and_then(|m| m.modified())
+            .ok();
+        let cache_modified = fs::metadata(&cache_path)
+            .and_then(|m| m.modified())
+            .ok();
+
+        if let (Some(src), Some(cache)) = (source_modified, cache_modified) {
+            if cache >= src && manifest.as_ref() == Some(&params) {
+                println!("  ℹ️  Cache up-to-date: {}", cache_path.display());
+                return Ok(false);
+            }
+        }
+    }
+    if let Some(earlier) = &manifest {
+        for change in params.changes_from(earlier) {
+            println!("  ℹ️  {}", change);
+        }
+    }
+
+    match generate_track_world(&track_file_format, &params, track_file.parent())? {
+        Some(procedural_world) => {
+            // Save to cache file, with the parameters it was generated from
+            save_terrain_cache(&cache_path, &procedural_world)?;
+            save_manifest(track_file, &params)?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// The world of a track file from `params`, lamps included, without
+/// touching any cache; `None` when generation failed. `track_dir` is where
+/// a `terrain_dem` path is relative to
+pub fn generate_track_world(
+    track_file_format: &crate::track_loader::TrackFileFormat,
+    params: &WorldParams,
+    track_dir: Option<&Path>,
+) -> Result<Option<ProceduralWorldData>, String> {
+    use crate::track_loader::{TrackLoader, SplineInterpolator};
+
+    // Generate centerline points for terrain generation
+    let default_width = if track_file_format.default_width > 0.0 {
+        track_file_format.default_width
+    } else {
+        12.0
+    };
+
+    let mut centerline_points = SplineInterpolator::interpolate_spline(
+        &track_file_format.nodes,
+        track_file_format.closed_loop,
+        default_width,
+    ).map_err(|e| format!("Failed to interpolate spline: {}", e))?;
+
+    // Generate procedural world
+    let procedural_world = TrackLoader::generate_procedural_world_for_track(
+        &track_file_format.name,
+        &mut centerline_points,
+        params,
+        track_dir,
+    );
+
+    Ok(procedural_world.map(|mut procedural_world| {
+        // Lamps go where the loader lays out the start line and pit lane
+        let start = TrackLoader::start_line_index(track_file_format, &centerline_points);
+        let pit_lane = track_file_format
+            .pit_lane
+            .as_ref()
+            .and_then(|definition| TrackLoader::build_pit_lane(definition, &centerline_points, start));
+        if let (Some(heightmap), Some(start)) = (&procedural_world.heightmap, centerline_points.get(start)) {
+            let pit_centerline = pit_lane.as_ref().map_or(&[][..], |pit_lane| &pit_lane.centerline[..]);
+            procedural_world.lights = place_track_lights(start, pit_centerline, heightmap);
+        }
+        procedural_world
+    }))
+}
+
+pub fn get_terrain_cache_path(track_file: &Path) -> std::path::PathBuf {
+    let mut cache_path = track_file.to_path_buf();
+    let stem = cache_path.file_stem().unwrap().to_string_lossy();
+    cache_path.set_file_name(format!("{}.terrain.msgpack", stem));
+    cache_path
+}
+
+fn save_terrain_cache(cache_path: &Path, procedural_world: &ProceduralWorldData) -> Result<(), String> {
+    use std::fs;
+
+    let msgpack_data = rmp_serde::to_vec(procedural_world)
+        .map_err(|e| format!("Failed to serialize terrain data: {}", e))?;
+
+    fs::write(cache_path, msgpack_data)
+        .map_err(|e| format!("Failed to write terrain cache: {}", e))?;
+
+    Ok(())
+}
+
+/// Load terrain data from cache file
+pub fn load_terrain_cache(track_file: &Path) -> Option<ProceduralWorldData> {
+    use std::fs;
+
+    let cache_path = get_terrain_cache_path(track_file);
+
+    if !cache_path.exists() {
+        return None;
+    }
+
+    let content = fs::read(&cache_path).ok()?;
+    let procedural_world: ProceduralWorldData = rmp_serde::from_slice(&content)
+        .map_err(|e| {
+            eprintln!("Failed to parse terrain cache {}: {}", cache_path.display(), e);
+            e
+        })
+        .ok()?;
+
+    Some(procedural_world)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::data::SurfaceType;
+    use crate::procgen::world_data::{Lighting, SkyHint};
 
     fn create_test_track() -> Vec<TrackPoint> {
         vec![
@@ -282,6 +648,7 @@ mod tests {
                 heading_rad: 0.0,
                 surface_type: SurfaceType::Asphalt,
                 grip_modifier: 1.0,
+                runoff_surface: SurfaceType::Grass,
             },
             TrackPoint {
                 x: 100.0,
@@ -296,6 +663,7 @@ mod tests {
                 heading_rad: 0.0,
                 surface_type: SurfaceType::Asphalt,
                 grip_modifier: 1.0,
+                runoff_surface: SurfaceType::Grass,
             },
             TrackPoint {
                 x: 100.0,
@@ -310,6 +678,7 @@ mod tests {
                 heading_rad: 0.0,
                 surface_type: SurfaceType::Asphalt,
                 grip_modifier: 1.0,
+                runoff_surface: SurfaceType::Grass,
             },
         ]
     }
@@ -350,6 +719,116 @@ mod tests {
         assert_eq!(heightmap1.heights, heightmap2.heights);
     }
 
+    #[test]
+    fn test_biome_noise_stacks_shape_distinct_terrain() {
+        let track = create_test_track();
+        let alpine = generate_terrain(&track, 7, &EnvironmentPreset::alpine(), 1.0).unwrap();
+        let coastal = generate_terrain(&track, 7, &EnvironmentPreset::coastal(), 1.0).unwrap();
+
+        let peak = |h: &TerrainHeightmap| h.heights.iter().cloned().fold(0.0_f32, f32::max);
+        assert!(peak(&alpine) <= EnvironmentPreset::alpine().max_height);
+        assert!(peak(&coastal) <= EnvironmentPreset::coastal().max_height);
+        assert!(peak(&alpine) > peak(&coastal));
+    }
+
+    #[test]
+    fn test_caches_without_biome_fields_still_load() {
+        // A preset as written before noise stacks and sky hints existed
+        let legacy = (0.02_f32, 0.1_f32, 15.0_f32, 0.3_f32, vec!["rock_small".to_string()], [0.8_f32, 0.7, 0.5]);
+        let bytes = rmp_serde::to_vec(&legacy).unwrap();
+        let preset: EnvironmentPreset = rmp_serde::from_slice(&bytes).unwrap();
+
+        assert!(preset.noise_layers.is_empty());
+        assert_eq!(preset.peak_color, None);
+        assert_eq!(preset.sky, SkyHint::default());
+    }
+
+    #[test]
+    fn test_same_params_give_the_same_world() {
+        let track = create_test_track();
+        let metadata = crate::data::TrackMetadata {
+            environment_type: Some("forest".to_string()),
+            ..Default::default()
+        };
+        let params = WorldParams::from_metadata("Test", &metadata).unwrap();
+        let generate = |params: &WorldParams| rmp_serde::to_vec(&generate_procedural_world(&track, params, None).unwrap()).unwrap();
+
+        assert_eq!(generate(&params), generate(&params));
+        assert_ne!(generate(&params), generate(&WorldParams { seed: params.seed + 1, ..params.clone() }));
+    }
+
+    #[test]
+    fn test_lod_levels_tile_the_same_ground() {
+        let track = create_test_track();
+        let heightmap = generate_terrain(&track, 3, &EnvironmentPreset::forest(), 1.0).unwrap();
+        let lod = TerrainLod::build(&heightmap, LOD_TILE_CELLS);
+
+        // 64 cells a side at full resolution, down to 8
+        assert_eq!(lod.levels.len(), 3);
+        let (columns, rows) = heightmap.tile_counts(LOD_TILE_CELLS);
+        for level in 1..=3 {
+            let grid = lod.level(&heightmap, level).unwrap();
+            assert_eq!(grid.cell_size_m, heightmap.cell_size_m * (1 << level) as f32);
+            assert_eq!(grid.tile_counts(lod.tile_cells_at(level)), (columns, rows));
+        }
+
+        // Neighbouring tiles share their edge, and coarse tiles start where fine ones do
+        let left = heightmap.tile(0, 0, LOD_TILE_CELLS).unwrap();
+        let tile = heightmap.tile(1, 0, LOD_TILE_CELLS).unwrap();
+        assert_eq!((left.width, left.height), (LOD_TILE_CELLS + 1, LOD_TILE_CELLS + 1));
+        assert_eq!(tile.get_height(0, 5), left.get_height(LOD_TILE_CELLS, 5));
+        assert_eq!(tile.get_height(3, 2), heightmap.get_height(LOD_TILE_CELLS + 3, 2));
+        let coarse = lod.levels[2].tile(1, 0, lod.tile_cells_at(3)).unwrap();
+        assert_eq!((coarse.origin_x, coarse.origin_y), (tile.origin_x, tile.origin_y));
+        assert_eq!(coarse.get_height(0, 0), tile.get_height(0, 0));
+        assert!(heightmap.tile(columns, 0, LOD_TILE_CELLS).is_none());
+    }
+
+    #[test]
+    fn test_height_at_a_resolution_uses_the_coarsest_fine_enough_level() {
+        let track = create_test_track();
+        let metadata = crate::data::TrackMetadata {
+            environment_type: Some("alpine".to_string()),
+            ..Default::default()
+        };
+        let params = WorldParams::from_metadata("Test", &metadata).unwrap();
+        let world = generate_procedural_world(&track, &params, None).unwrap();
+        let heightmap = world.heightmap.as_ref().unwrap();
+        let lod = world.lod.as_ref().unwrap();
+        let (x, y) = (-57.0, 143.0);
+
+        assert_eq!(world.height_at(x, y, 1.0), heightmap.sample(x, y));
+        assert_eq!(world.height_at(x, y, 5.0), heightmap.sample(x, y));
+        assert_eq!(world.height_at(x, y, 12.0), lod.levels[0].sample(x, y));
+        assert_eq!(world.height_at(x, y, 1000.0), lod.levels[2].sample(x, y));
+    }
+
+    #[test]
+    fn test_worlds_light_every_time_of_day_from_their_preset() {
+        let track = create_test_track();
+        let metadata = crate::data::TrackMetadata {
+            environment_type: Some("city".to_string()),
+            ..Default::default()
+        };
+        let params = WorldParams::from_metadata("Test", &metadata).unwrap();
+        let mut world = generate_procedural_world(&track, &params, None).unwrap();
+
+        assert_eq!(world.lighting.len(), TimeOfDay::ALL.len());
+        let noon = world.lighting_at(TimeOfDay::Noon);
+        assert_eq!((noon.sun_elevation_deg, noon.sky_top_color), (params.preset.sky.sun_elevation_deg, params.preset.sky.top_color));
+        let dusk = world.lighting_at(TimeOfDay::Dusk);
+        assert!(dusk.sun_elevation_deg < noon.sun_elevation_deg && dusk.sun_energy < noon.sun_energy);
+        // The city's own night glows brighter than plain moonlight
+        let night = world.lighting_at(TimeOfDay::Night);
+        assert_eq!(night, params.preset.lighting[0]);
+        assert!(night.ambient_energy > Lighting::from_sky(&params.preset.sky, TimeOfDay::Night).ambient_energy);
+
+        // Caches from before lighting fall back on their preset
+        world.lighting.clear();
+        assert_eq!(world.lighting_at(TimeOfDay::Night), night);
+        assert_eq!(world.lighting_at(TimeOfDay::Morning), Lighting::from_sky(&params.preset.sky, TimeOfDay::Morning));
+    }
+
     #[test]
     fn test_track_corridor_carving() {
         let track = create_test_track();
@@ -370,6 +849,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_road_sits_on_or_above_terrain_everywhere() {
+        // A banked, climbing straight across alpine peaks
+        let mut track = create_test_track();
+        for (i, point) in track.iter_mut().enumerate() {
+            point.z = 20.0 + 5.0 * i as f32;
+            point.banking_rad = 0.1;
+            point.camber_rad = 0.02;
+            point.heading_rad = if i == 0 { 0.0 } else { std::f32::consts::FRAC_PI_2 };
+        }
+        let natural = generate_terrain(&track, 9, &EnvironmentPreset::alpine(), 1.0).unwrap();
+        let mut heightmap = natural.clone();
+        carve_track_corridor(&mut heightmap, &track, 20.0);
+
+        for point in &track {
+            let (normal_x, normal_y) = (-point.heading_rad.sin(), point.heading_rad.cos());
+            for i in 0..=40 {
+                let offset = -point.width_right_m + (point.width_left_m + point.width_right_m) * i as f32 / 40.0;
+                let road = point.z - offset.abs() * (point.banking_rad.sin() + point.camber_rad.sin());
+                let terrain = heightmap.sample(point.x + normal_x * offset, point.y + normal_y * offset);
+                assert!(terrain <= road, "terrain {} above road {} at offset {}", terrain, road, offset);
+            }
+        }
+
+        // Shoulders are flat and terrain well away from the track is untouched
+        let shoulder = heightmap.sample(0.0, -(5.0 + SHOULDER_WIDTH_M - 1.0));
+        assert!((shoulder - (track[0].z - ROAD_CLEARANCE_M)).abs() < 1.5, "shoulder at {}", shoulder);
+        assert_eq!(heightmap.get_height(0, 0), natural.get_height(0, 0));
+    }
+
     #[test]
     fn test_elevation_application() {
         let mut track = create_test_track();
@@ -403,6 +912,7 @@ mod tests {
                 heading_rad: 0.0,
                 surface_type: SurfaceType::Asphalt,
                 grip_modifier: 1.0,
+                runoff_surface: SurfaceType::Grass,
             },
             TrackPoint {
                 x: 100.0,
@@ -417,6 +927,7 @@ mod tests {
                 heading_rad: 0.0,
                 surface_type: SurfaceType::Asphalt,
                 grip_modifier: 1.0,
+                runoff_surface: SurfaceType::Grass,
             },
         ];
 
@@ -431,179 +942,3 @@ mod tests {
         );
     }
 }
-
-/// Batch generate terrain for all tracks in a directory
-///
-/// This function scans the tracks directory, finds all tracks with
-/// `environment_type` metadata, generates terrain for them, and saves
-/// the procedural data to cache files.
-pub fn generate_all_terrain(tracks_dir: &str) -> Result<usize, String> {
-    use std::path::Path;
-
-    let tracks_path = Path::new(tracks_dir);
-    if !tracks_path.exists() {
-        return Err(format!("Tracks directory not found: {}", tracks_dir));
-    }
-
-    println!("Scanning tracks directory: {}", tracks_dir);
-
-    let mut generated_count = 0;
-    let mut files_to_process = Vec::new();
-
-    // Collect all YAML/JSON track files
-    collect_track_files(tracks_path, &mut files_to_process)?;
-
-    println!("Found {} track file(s)", files_to_process.len());
-
-    for track_file in files_to_process {
-        match process_track_for_terrain(&track_file) {
-            Ok(true) => {
-                generated_count += 1;
-                println!("  ✅ Generated terrain for: {}", track_file.display());
-            }
-            Ok(false) => {
-                println!("  ⏭️  Skipped (no environment_type): {}", track_file.display());
-            }
-            Err(e) => {
-                eprintln!("  ❌ Failed to process {}: {}", track_file.display(), e);
-            }
-        }
-    }
-
-    Ok(generated_count)
-}
-
-fn collect_track_files(dir: &Path, files: &mut Vec<std::path::PathBuf>) -> Result<(), String> {
-    use std::fs;
-
-    let entries = fs::read_dir(dir)
-        .map_err(|e| format!("Failed to read directory {:?}: {}", dir, e))?;
-
-    for entry in entries {
-        let entry = entry.map_err(|e| format!("Failed to read dir entry: {}", e))?;
-        let path = entry.path();
-
-        if path.is_dir() {
-            // Recursively scan subdirectories
-            collect_track_files(&path, files)?;
-        } else if let Some(ext) = path.extension() {
-            let ext_str = ext.to_string_lossy();
-            if ext_str == "yaml" || ext_str == "yml" || ext_str == "json" {
-                files.push(path);
-            }
-        }
-    }
-
-    Ok(())
-}
-
-fn process_track_for_terrain(track_file: &Path) -> Result<bool, String> {
-    use crate::track_loader::{TrackLoader, SplineInterpolator};
-    use std::fs;
-
-    // Load track file to get metadata
-    let content = fs::read_to_string(track_file)
-        .map_err(|e| format!("Failed to read track file: {}", e))?;
-
-    let track_file_format: crate::track_loader::TrackFileFormat = if content.trim_start().starts_with('{') {
-        serde_json::from_str(&content)
-            .map_err(|e| format!("JSON parse error: {}", e))?
-    } else {
-        serde_yaml::from_str(&content)
-            .map_err(|e| format!("YAML parse error: {}", e))?
-    };
-
-    let metadata = track_file_format.metadata.clone().unwrap_or_default();
-
-    // Check if it needs procedural generation
-    if metadata.environment_type.is_none() {
-        return Ok(false); // Skip, no procedural metadata
-    }
-
-    // Check if terrain cache already exists and is up-to-date
-    let cache_path = get_terrain_cache_path(track_file);
-    if cache_path.exists() {
-        // Check if cache is newer than source file
-        let source_modified = fs::metadata(track_file)
-            .and_then(|m| m.modified())
-            .ok();
-        let cache_modified = fs::metadata(&cache_path)
-            .and_then(|m| m.modified())
-            .ok();
-
-        if let (Some(src), Some(cache)) = (source_modified, cache_modified) {
-            if cache >= src {
-                println!("  ℹ️  Cache up-to-date: {}", cache_path.display());
-                return Ok(false);
-            }
-        }
-    }
-
-    // Generate centerline points for terrain generation
-    let default_width = if track_file_format.default_width > 0.0 {
-        track_file_format.default_width
-    } else {
-        12.0
-    };
-
-    let mut centerline_points = SplineInterpolator::interpolate_spline(
-        &track_file_format.nodes,
-        track_file_format.closed_loop,
-        default_width,
-    ).map_err(|e| format!("Failed to interpolate spline: {}", e))?;
-
-    // Generate procedural world
-    let procedural_world = TrackLoader::generate_procedural_world_for_track(
-        &track_file_format.name,
-        &mut centerline_points,
-        &metadata,
-    );
-
-    if let Some(procedural_world) = procedural_world {
-        // Save to cache file
-        save_terrain_cache(&cache_path, &procedural_world)?;
-        return Ok(true);
-    }
-
-    Ok(false)
-}
-
-fn get_terrain_cache_path(track_file: &Path) -> std::path::PathBuf {
-    let mut cache_path = track_file.to_path_buf();
-    let stem = cache_path.file_stem().unwrap().to_string_lossy();
-    cache_path.set_file_name(format!("{}.terrain.msgpack", stem));
-    cache_path
-}
-
-fn save_terrain_cache(cache_path: &Path, procedural_world: &ProceduralWorldData) -> Result<(), String> {
-    use std::fs;
-
-    let msgpack_data = rmp_serde::to_vec(procedural_world)
-        .map_err(|e| format!("Failed to serialize terrain data: {}", e))?;
-
-    fs::write(cache_path, msgpack_data)
-        .map_err(|e| format!("Failed to write terrain cache: {}", e))?;
-
-    Ok(())
-}
-
-/// Load terrain data from cache file
-pub fn load_terrain_cache(track_file: &Path) -> Option<ProceduralWorldData> {
-    use std::fs;
-
-    let cache_path = get_terrain_cache_path(track_file);
-
-    if !cache_path.exists() {
-        return None;
-    }
-
-    let content = fs::read(&cache_path).ok()?;
-    let procedural_world: ProceduralWorldData = rmp_serde::from_slice(&content)
-        .map_err(|e| {
-            eprintln!("Failed to parse terrain cache {}: {}", cache_path.display(), e);
-            e
-        })
-        .ok()?;
-
-    Some(procedural_world)
-}
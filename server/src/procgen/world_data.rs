@@ -1,8 +1,9 @@
 /// Data structures for procedural world generation
+use crate::data::TimeOfDay;
 use serde::{Deserialize, Serialize};
 
 /// Compact heightmap representation for terrain
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TerrainHeightmap {
     /// Grid width in cells
     pub width: usize,
@@ -79,10 +80,221 @@ impl TerrainHeightmap {
             self.heights[y * self.width + x] = height;
         }
     }
+
+    /// Every other height along each axis: half the resolution over the
+    /// same ground, short of the last cell when the grid has an odd number
+    pub fn halved(&self) -> TerrainHeightmap {
+        let width = self.width.saturating_sub(1) / 2 + 1;
+        let height = self.height.saturating_sub(1) / 2 + 1;
+        let mut half = TerrainHeightmap::new(width, height, self.cell_size_m * 2.0, self.origin_x, self.origin_y);
+        for y in 0..height {
+            for x in 0..width {
+                half.set_height(x, y, self.get_height(x * 2, y * 2));
+            }
+        }
+        half
+    }
+
+    /// Tiles of `tile_cells` cells a side across and up the grid
+    pub fn tile_counts(&self, tile_cells: usize) -> (usize, usize) {
+        let tile_cells = tile_cells.max(1);
+        (
+            self.width.saturating_sub(1).div_ceil(tile_cells).max(1),
+            self.height.saturating_sub(1).div_ceil(tile_cells).max(1),
+        )
+    }
+
+    /// Heights of one tile, `tile_cells + 1` a side so neighbouring tiles
+    /// share their edges; tiles at the far edges of the grid may be smaller
+    pub fn tile(&self, tile_x: usize, tile_y: usize, tile_cells: usize) -> Option<TerrainHeightmap> {
+        let (columns, rows) = self.tile_counts(tile_cells);
+        if tile_x >= columns || tile_y >= rows {
+            return None;
+        }
+        let (first_x, first_y) = (tile_x * tile_cells, tile_y * tile_cells);
+        let width = (tile_cells + 1).min(self.width - first_x);
+        let height = (tile_cells + 1).min(self.height - first_y);
+        let mut tile = TerrainHeightmap::new(
+            width,
+            height,
+            self.cell_size_m,
+            self.origin_x + first_x as f32 * self.cell_size_m,
+            self.origin_y + first_y as f32 * self.cell_size_m,
+        );
+        for y in 0..height {
+            let row = (first_y + y) * self.width + first_x;
+            tile.heights[y * width..(y + 1) * width].copy_from_slice(&self.heights[row..row + width]);
+        }
+        Some(tile)
+    }
+}
+
+/// Coarser copies of a heightmap, for drawing distant terrain with fewer
+/// triangles
+///
+/// The terrain is cut into square tiles that cover the same ground at
+/// every level: `tile_cells` cells a side at the heightmap's resolution,
+/// half as many at each coarser level.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TerrainLod {
+    /// Cells along the side of a tile at full resolution
+    pub tile_cells: usize,
+    /// Levels 1 and up, each half the resolution of the one before; level 0
+    /// is the heightmap itself
+    pub levels: Vec<TerrainHeightmap>,
+}
+
+impl TerrainLod {
+    /// Coarsest tiles still have this many cells a side
+    pub const MIN_TILE_CELLS: usize = 8;
+
+    /// Halve `heightmap` until its tiles would drop below `MIN_TILE_CELLS`
+    pub fn build(heightmap: &TerrainHeightmap, tile_cells: usize) -> Self {
+        let mut levels: Vec<TerrainHeightmap> = Vec::new();
+        while tile_cells >> (levels.len() + 1) >= Self::MIN_TILE_CELLS {
+            let finer = levels.last().unwrap_or(heightmap);
+            if finer.width < 3 || finer.height < 3 {
+                break;
+            }
+            levels.push(finer.halved());
+        }
+        Self { tile_cells, levels }
+    }
+
+    /// Grid of a level, where 0 is `heightmap`
+    pub fn level<'a>(&'a self, heightmap: &'a TerrainHeightmap, level: usize) -> Option<&'a TerrainHeightmap> {
+        match level {
+            0 => Some(heightmap),
+            n => self.levels.get(n - 1),
+        }
+    }
+
+    /// Cells along the side of a tile at a level
+    pub fn tile_cells_at(&self, level: usize) -> usize {
+        self.tile_cells >> level
+    }
+}
+
+/// One layer of a biome's heightmap noise stack
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NoiseLayer {
+    /// Spatial frequency in cycles per meter
+    pub frequency: f32,
+    /// Relative weight of this layer in the stack
+    pub amplitude: f32,
+    /// Fold the noise into sharp crests (ridges, dunes) instead of smooth hills
+    pub ridged: bool,
+}
+
+impl NoiseLayer {
+    pub fn smooth(frequency: f32, amplitude: f32) -> Self {
+        Self { frequency, amplitude, ridged: false }
+    }
+
+    pub fn ridged(frequency: f32, amplitude: f32) -> Self {
+        Self { frequency, amplitude, ridged: true }
+    }
+}
+
+/// Hints for the client's sky and fog; all colors RGB (0-1 range)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkyHint {
+    pub top_color: [f32; 3],
+    pub horizon_color: [f32; 3],
+    /// Sun height above the horizon in degrees
+    pub sun_elevation_deg: f32,
+    /// Exponential fog density; 0 disables fog
+    pub fog_density: f32,
+}
+
+impl Default for SkyHint {
+    /// The sky the client shows when no biome asks for another
+    fn default() -> Self {
+        Self {
+            top_color: [0.385, 0.454, 0.55],
+            horizon_color: [0.646, 0.656, 0.67],
+            sun_elevation_deg: 45.0,
+            fog_density: 0.0,
+        }
+    }
+}
+
+/// Sun, sky and ambient light at one time of day; all colors RGB (0-1 range)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lighting {
+    pub time_of_day: TimeOfDay,
+    /// Height above the horizon in degrees of the sun, or of the moon at night
+    pub sun_elevation_deg: f32,
+    /// Compass bearing of the sun in degrees, clockwise from north (+Y)
+    pub sun_azimuth_deg: f32,
+    pub sun_color: [f32; 3],
+    /// Strength of the sun; 1 is full daylight
+    pub sun_energy: f32,
+    /// Light reaching everything the sun does not
+    pub ambient_color: [f32; 3],
+    pub ambient_energy: f32,
+    pub sky_top_color: [f32; 3],
+    pub sky_horizon_color: [f32; 3],
+}
+
+impl Lighting {
+    /// Lighting at a time of day under a biome's sky: its sky and sun at
+    /// noon, a lower sun in warmer light towards either end of the day, the
+    /// sun on the horizon at dusk and moonlight at night
+    pub fn from_sky(sky: &SkyHint, time_of_day: TimeOfDay) -> Self {
+        let noon = Self {
+            time_of_day,
+            sun_elevation_deg: sky.sun_elevation_deg,
+            sun_azimuth_deg: 180.0,
+            sun_color: [1.0, 0.98, 0.92],
+            sun_energy: 1.0,
+            ambient_color: sky.horizon_color,
+            ambient_energy: 0.6,
+            sky_top_color: sky.top_color,
+            sky_horizon_color: sky.horizon_color,
+        };
+        let tinted = |color: [f32; 3], tint: [f32; 3], amount: f32| -> [f32; 3] {
+            std::array::from_fn(|c| color[c] + (tint[c] - color[c]) * amount)
+        };
+        match time_of_day {
+            TimeOfDay::Noon => noon,
+            TimeOfDay::Morning | TimeOfDay::Afternoon => Self {
+                sun_elevation_deg: sky.sun_elevation_deg / 2.0,
+                sun_azimuth_deg: if time_of_day == TimeOfDay::Morning { 110.0 } else { 250.0 },
+                sun_color: [1.0, 0.92, 0.8],
+                sun_energy: 0.85,
+                ambient_energy: 0.5,
+                sky_horizon_color: tinted(sky.horizon_color, [1.0, 0.85, 0.7], 0.25),
+                ..noon
+            },
+            TimeOfDay::Dusk => Self {
+                sun_elevation_deg: 4.0,
+                sun_azimuth_deg: 280.0,
+                sun_color: [1.0, 0.6, 0.35],
+                sun_energy: 0.45,
+                ambient_color: tinted(sky.horizon_color, [0.55, 0.45, 0.6], 0.5),
+                ambient_energy: 0.3,
+                sky_top_color: tinted(sky.top_color, [0.15, 0.15, 0.3], 0.5),
+                sky_horizon_color: tinted(sky.horizon_color, [0.95, 0.55, 0.3], 0.6),
+                ..noon
+            },
+            TimeOfDay::Night => Self {
+                sun_elevation_deg: 35.0,
+                sun_azimuth_deg: 140.0,
+                sun_color: [0.6, 0.7, 1.0],
+                sun_energy: 0.08,
+                ambient_color: [0.2, 0.25, 0.4],
+                ambient_energy: 0.1,
+                sky_top_color: [0.01, 0.01, 0.03],
+                sky_horizon_color: [0.04, 0.05, 0.1],
+                ..noon
+            },
+        }
+    }
 }
 
 /// Environment preset configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct EnvironmentPreset {
     /// Low-frequency noise for base terrain shape
     pub base_noise_freq: f32,
@@ -96,9 +308,38 @@ pub struct EnvironmentPreset {
     pub allowed_objects: Vec<String>,
     /// Ground color RGB (0-1 range)
     pub ground_color: [f32; 3],
+    /// Heightmap noise stack; empty uses the classic three octaves scaled
+    /// by `detail_noise_freq`
+    #[serde(default)]
+    pub noise_layers: Vec<NoiseLayer>,
+    /// Color the ground fades to on the highest terrain (rock, snow)
+    #[serde(default)]
+    pub peak_color: Option<[f32; 3]>,
+    #[serde(default)]
+    pub sky: SkyHint,
+    /// How readily rivers and lakes form (0-1); 0 keeps the land dry
+    #[serde(default)]
+    pub rainfall: f32,
+    /// Share (0-1) of the terrain's height range below which the ground is
+    /// sand; 1 covers it all
+    #[serde(default)]
+    pub sand_below: f32,
+    /// Lighting at the times of day the biome sets itself; the others
+    /// follow from `sky`
+    #[serde(default)]
+    pub lighting: Vec<Lighting>,
 }
 
 impl EnvironmentPreset {
+    /// The biome's lighting at a time of day
+    pub fn lighting_at(&self, time_of_day: TimeOfDay) -> Lighting {
+        self.lighting
+            .iter()
+            .find(|lighting| lighting.time_of_day == time_of_day)
+            .cloned()
+            .unwrap_or_else(|| Lighting::from_sky(&self.sky, time_of_day))
+    }
+
     /// Create desert preset
     pub fn desert() -> Self {
         Self {
@@ -112,6 +353,22 @@ impl EnvironmentPreset {
                 "bush_dry".to_string(),
             ],
             ground_color: [0.8, 0.7, 0.5], // Sandy
+            // Long dune crests over a broad, flat basin
+            noise_layers: vec![
+                NoiseLayer::smooth(0.002, 1.0),
+                NoiseLayer::ridged(0.012, 0.6),
+                NoiseLayer::smooth(0.05, 0.1),
+            ],
+            peak_color: Some([0.87, 0.78, 0.58]),
+            sky: SkyHint {
+                top_color: [0.3, 0.5, 0.8],
+                horizon_color: [0.85, 0.8, 0.7],
+                sun_elevation_deg: 65.0,
+                fog_density: 0.0005,
+            },
+            rainfall: 0.0,
+            sand_below: 1.0,
+            lighting: Vec::new(),
         }
     }
 
@@ -128,6 +385,22 @@ impl EnvironmentPreset {
                 "bush_green".to_string(),
             ],
             ground_color: [0.3, 0.5, 0.2], // Forest green
+            // Rolling hills with a rough floor
+            noise_layers: vec![
+                NoiseLayer::smooth(0.004, 1.0),
+                NoiseLayer::smooth(0.015, 0.4),
+                NoiseLayer::smooth(0.06, 0.15),
+            ],
+            peak_color: None,
+            sky: SkyHint {
+                top_color: [0.35, 0.45, 0.55],
+                horizon_color: [0.6, 0.68, 0.65],
+                sun_elevation_deg: 40.0,
+                fog_density: 0.002,
+            },
+            rainfall: 1.0,
+            sand_below: 0.0,
+            lighting: Vec::new(),
         }
     }
 
@@ -145,6 +418,18 @@ impl EnvironmentPreset {
                 "fence".to_string(),
             ],
             ground_color: [0.4, 0.4, 0.4], // Concrete gray
+            noise_layers: Vec::new(),
+            peak_color: None,
+            sky: SkyHint::default(),
+            rainfall: 0.0,
+            sand_below: 0.0,
+            // Street lights glow on the haze over the city after dark
+            lighting: vec![Lighting {
+                ambient_color: [0.45, 0.38, 0.3],
+                ambient_energy: 0.2,
+                sky_horizon_color: [0.25, 0.16, 0.1],
+                ..Lighting::from_sky(&SkyHint::default(), TimeOfDay::Night)
+            }],
         }
     }
 
@@ -161,6 +446,12 @@ impl EnvironmentPreset {
                 "tree_pine".to_string(),
             ],
             ground_color: [0.5, 0.5, 0.4], // Rocky gray
+            noise_layers: Vec::new(),
+            peak_color: None,
+            sky: SkyHint::default(),
+            rainfall: 0.6,
+            sand_below: 0.0,
+            lighting: Vec::new(),
         }
     }
 
@@ -177,6 +468,12 @@ impl EnvironmentPreset {
                 "tree_oak".to_string(),
             ],
             ground_color: [0.4, 0.6, 0.3], // Grassland
+            noise_layers: Vec::new(),
+            peak_color: None,
+            sky: SkyHint::default(),
+            rainfall: 0.4,
+            sand_below: 0.0,
+            lighting: Vec::new(),
         }
     }
 
@@ -193,6 +490,12 @@ impl EnvironmentPreset {
                 "grass_tall".to_string(),
             ],
             ground_color: [0.45, 0.55, 0.3], // Countryside green
+            noise_layers: Vec::new(),
+            peak_color: None,
+            sky: SkyHint::default(),
+            rainfall: 0.7,
+            sand_below: 0.0,
+            lighting: Vec::new(),
         }
     }
 
@@ -209,8 +512,188 @@ impl EnvironmentPreset {
                 "grass_tall".to_string(),
             ],
             ground_color: [0.35, 0.65, 0.3], // Park green
+            noise_layers: Vec::new(),
+            peak_color: None,
+            sky: SkyHint::default(),
+            rainfall: 0.6,
+            sand_below: 0.0,
+            lighting: Vec::new(),
+        }
+    }
+
+    /// Create alpine preset (ridged peaks, snow above the tree line)
+    pub fn alpine() -> Self {
+        Self {
+            base_noise_freq: 0.01,
+            detail_noise_freq: 0.08,
+            max_height: 90.0,
+            object_density: 0.35,
+            allowed_objects: vec![
+                "tree_pine".to_string(),
+                "rock_large".to_string(),
+                "rock_small".to_string(),
+            ],
+            ground_color: [0.35, 0.45, 0.3], // Alpine meadow
+            noise_layers: vec![
+                NoiseLayer::ridged(0.003, 1.0),
+                NoiseLayer::ridged(0.01, 0.5),
+                NoiseLayer::smooth(0.04, 0.15),
+            ],
+            peak_color: Some([0.92, 0.93, 0.95]),
+            sky: SkyHint {
+                top_color: [0.2, 0.4, 0.75],
+                horizon_color: [0.7, 0.78, 0.85],
+                sun_elevation_deg: 35.0,
+                fog_density: 0.001,
+            },
+            rainfall: 0.8,
+            sand_below: 0.0,
+            lighting: Vec::new(),
         }
     }
+
+    /// Create coastal preset (low dunes and grassy bluffs)
+    pub fn coastal() -> Self {
+        Self {
+            base_noise_freq: 0.02,
+            detail_noise_freq: 0.06,
+            max_height: 12.0,
+            object_density: 0.45,
+            allowed_objects: vec![
+                "grass_tall".to_string(),
+                "bush_green".to_string(),
+                "rock_small".to_string(),
+            ],
+            ground_color: [0.76, 0.72, 0.55], // Beach sand
+            noise_layers: vec![
+                NoiseLayer::smooth(0.003, 1.0),
+                NoiseLayer::smooth(0.02, 0.3),
+                NoiseLayer::ridged(0.05, 0.1),
+            ],
+            peak_color: Some([0.45, 0.6, 0.35]),
+            sky: SkyHint {
+                top_color: [0.3, 0.55, 0.85],
+                horizon_color: [0.75, 0.85, 0.9],
+                sun_elevation_deg: 50.0,
+                fog_density: 0.0015,
+            },
+            rainfall: 0.5,
+            sand_below: 0.35,
+            lighting: Vec::new(),
+        }
+    }
+}
+
+/// An object placed beside the track
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TracksideObject {
+    /// Object model ID: a trackside kind such as "grandstand", or one of
+    /// the biome's allowed objects
+    pub kind: String,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    /// Direction the object faces, counter-clockwise from +X
+    pub heading_rad: f32,
+    /// Uniform scale of the model
+    pub scale: f32,
+}
+
+/// Standing and flowing water of a world
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WaterFeatures {
+    pub lakes: Vec<Lake>,
+    pub rivers: Vec<River>,
+}
+
+impl WaterFeatures {
+    /// Whether `(x, y)` is in any water, or within `margin` meters of it
+    pub fn covers(&self, x: f32, y: f32, margin: f32) -> bool {
+        let in_lake = self.lakes.iter().flat_map(|lake| &lake.rects).any(|rect| {
+            x >= rect[0] - margin && y >= rect[1] - margin && x <= rect[2] + margin && y <= rect[3] + margin
+        });
+        in_lake
+            || self
+                .rivers
+                .iter()
+                .flat_map(|river| &river.points)
+                .any(|point| (point.x - x).hypot(point.y - y) <= point.width / 2.0 + margin)
+    }
+}
+
+/// A lake with a flat surface
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lake {
+    /// Height of the water surface
+    pub level: f32,
+    /// Rectangles `[min_x, min_y, max_x, max_y]` that together cover the
+    /// water; they reach a little under the shore, where the terrain hides them
+    pub rects: Vec<[f32; 4]>,
+}
+
+/// A river in its carved channel, from its source downstream
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct River {
+    pub points: Vec<RiverPoint>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RiverPoint {
+    pub x: f32,
+    pub y: f32,
+    /// Height of the water surface
+    pub level: f32,
+    /// Width of the water surface in meters
+    pub width: f32,
+}
+
+/// Ground cover layers of a splatmap, in the order of its weights
+pub const SPLAT_LAYERS: [&str; 4] = ["grass", "dirt", "rock", "sand"];
+
+/// How much of each ground cover layer textures each heightmap cell
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Splatmap {
+    /// Grid width in cells, the same as the heightmap's
+    pub width: usize,
+    /// Grid height in cells, the same as the heightmap's
+    pub height: usize,
+    /// Weights of the `SPLAT_LAYERS`, four bytes per cell summing to 255
+    /// (row-major order like the heights)
+    #[serde(with = "serde_bytes")]
+    pub weights: Vec<u8>,
+}
+
+impl Splatmap {
+    /// Layer weights of a cell (all zero outside the grid)
+    pub fn weights_at(&self, x: usize, y: usize) -> [u8; 4] {
+        if x >= self.width || y >= self.height {
+            return [0; 4];
+        }
+        let i = (y * self.width + x) * 4;
+        [self.weights[i], self.weights[i + 1], self.weights[i + 2], self.weights[i + 3]]
+    }
+}
+
+/// What a track light belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LightKind {
+    PitLane,
+    StartGantry,
+}
+
+/// A lamp of the track's own, for clients to light at dusk and at night
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LightSource {
+    pub kind: LightKind,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    /// RGB (0-1 range)
+    pub color: [f32; 3],
+    /// Distance in meters the light reaches
+    pub range_m: f32,
+    pub energy: f32,
 }
 
 /// Complete procedural world data
@@ -230,4 +713,49 @@ pub struct ProceduralWorldData {
     pub decal_profile: String,
     /// Environment preset used for generation
     pub preset: EnvironmentPreset,
+    /// Trackside objects and scenery
+    #[serde(default)]
+    pub objects: Vec<TracksideObject>,
+    /// Lakes and rivers
+    #[serde(default)]
+    pub water: WaterFeatures,
+    /// Ground cover for texturing the terrain (None in caches from before
+    /// splatmaps)
+    #[serde(default)]
+    pub splatmap: Option<Splatmap>,
+    /// Coarser levels of the heightmap, tiled (None in caches from before
+    /// them)
+    #[serde(default)]
+    pub lod: Option<TerrainLod>,
+    /// Lighting at every time of day (empty in caches from before it)
+    #[serde(default)]
+    pub lighting: Vec<Lighting>,
+    /// Pit lane and start gantry lamps
+    #[serde(default)]
+    pub lights: Vec<LightSource>,
+}
+
+impl ProceduralWorldData {
+    /// Terrain height at world `(x, y)` from the coarsest level whose cells
+    /// are no larger than `resolution_m`, or the full heightmap when none is
+    pub fn height_at(&self, x: f32, y: f32, resolution_m: f32) -> f32 {
+        let Some(heightmap) = &self.heightmap else {
+            return 0.0;
+        };
+        let coarse = self.lod.iter().flat_map(|lod| lod.levels.iter()).rev();
+        coarse
+            .chain(std::iter::once(heightmap))
+            .find(|level| level.cell_size_m <= resolution_m)
+            .unwrap_or(heightmap)
+            .sample(x, y)
+    }
+
+    /// Lighting of a session at `time_of_day`
+    pub fn lighting_at(&self, time_of_day: TimeOfDay) -> Lighting {
+        self.lighting
+            .iter()
+            .find(|lighting| lighting.time_of_day == time_of_day)
+            .cloned()
+            .unwrap_or_else(|| self.preset.lighting_at(time_of_day))
+    }
 }
@@ -0,0 +1,426 @@
+//! AI-vs-AI content QA.
+//!
+//! Runs a headless AI-only race on a track and summarizes how it went:
+//! completed laps, off-track excursions per corner, collisions and pace.
+//! Tracks with suspicious results are flagged so broken or unbalanced
+//! content can be fixed before players drive it.
+
+use crate::ai_driver::generate_default_ai_profiles;
+use crate::data::*;
+use crate::game_session::GameSession;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::info;
+
+/// Curvature (1/m) above which a centerline point counts as part of a corner
+const CORNER_CURVATURE: f32 = 1.0 / 250.0;
+
+/// Corners shorter than this are treated as kinks in the centerline
+const MIN_CORNER_LENGTH_M: f32 = 15.0;
+
+/// Off-track excursions per car per lap at a corner before it is flagged
+const HIGH_OFF_TRACK_RATE: f32 = 0.25;
+
+/// Collisions per car per lap before a track is flagged
+const HIGH_COLLISION_RATE: f32 = 0.5;
+
+/// Lap time coefficient of variation before pace is flagged as inconsistent
+const INCONSISTENT_PACE_CV: f32 = 0.2;
+
+/// Settings for a QA run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QaSettings {
+    /// Number of AI drivers per race
+    pub ai_count: u8,
+    /// Laps each AI driver must complete
+    pub laps: u16,
+    /// Simulated time allowed per lap before the race is abandoned
+    pub max_seconds_per_lap: u32,
+}
+
+impl Default for QaSettings {
+    fn default() -> Self {
+        Self {
+            ai_count: 4,
+            laps: 3,
+            max_seconds_per_lap: 300,
+        }
+    }
+}
+
+/// Problems found on a track
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum QaFlag {
+    /// No AI driver completed a single lap
+    NoCompletedLaps,
+    /// Not every AI driver completed the race distance in time
+    IncompleteRace,
+    /// AI drivers regularly leave the track at this corner
+    HighOffTrackCorner { corner: u16 },
+    /// AI drivers collide with each other regularly
+    FrequentCollisions,
+    /// Lap times vary widely
+    InconsistentPace,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CornerQaStats {
+    pub corner: u16,
+    pub start_m: f32,
+    pub end_m: f32,
+    pub off_tracks: u32,
+    /// Off-track excursions per car per lap
+    pub off_track_rate: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackQaReport {
+    pub track_id: TrackConfigId,
+    pub track_name: String,
+    pub source_path: Option<String>,
+    pub cars: u8,
+    pub target_laps: u16,
+    pub simulated_seconds: f32,
+    pub completed_laps: u32,
+    pub cars_finished: u8,
+    pub average_lap_time_ms: Option<u32>,
+    pub best_lap_time_ms: Option<u32>,
+    pub off_tracks: u32,
+    pub collisions: u32,
+    pub corners: Vec<CornerQaStats>,
+    pub flags: Vec<QaFlag>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QaReport {
+    /// Unix timestamp of the run
+    pub generated_at: u64,
+    pub settings: QaSettings,
+    pub tracks: Vec<TrackQaReport>,
+}
+
+impl QaReport {
+    /// Tracks that raised at least one flag
+    pub fn flagged_tracks(&self) -> impl Iterator<Item = &TrackQaReport> {
+        self.tracks.iter().filter(|t| !t.flags.is_empty())
+    }
+}
+
+/// A corner found from centerline curvature, numbered from the start line
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Corner {
+    pub number: u16,
+    pub start_m: f32,
+    pub end_m: f32,
+}
+
+/// Run an AI-only race on every track, sorted by name
+pub fn run_qa(
+    tracks: &HashMap<TrackConfigId, TrackConfig>,
+    car_configs: &HashMap<CarConfigId, CarConfig>,
+    settings: &QaSettings,
+) -> QaReport {
+    let mut ordered: Vec<&TrackConfig> = tracks.values().collect();
+    ordered.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let reports = ordered
+        .into_iter()
+        .map(|track| {
+            let report = run_track_qa(track, car_configs, settings);
+            info!(
+                "QA {}: {} laps, {} off-tracks, {} collisions, flags: {:?}",
+                report.track_name, report.completed_laps, report.off_tracks, report.collisions, report.flags
+            );
+            report
+        })
+        .collect();
+
+    QaReport {
+        generated_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        settings: settings.clone(),
+        tracks: reports,
+    }
+}
+
+/// Run a headless AI-only race on one track and report on it
+pub fn run_track_qa(
+    track: &TrackConfig,
+    car_configs: &HashMap<CarConfigId, CarConfig>,
+    settings: &QaSettings,
+) -> TrackQaReport {
+    let ai_count = settings.ai_count.min(track.start_positions.len() as u8);
+    let session = RaceSession::new(
+        PlayerId::nil(),
+        track.id,
+        SessionKind::Practice,
+        ai_count,
+        ai_count,
+        settings.laps.min(u8::MAX as u16) as u8,
+    );
+    let mut game = GameSession::with_ai_profiles(
+        session,
+        track.clone(),
+        car_configs.clone(),
+        generate_default_ai_profiles(ai_count),
+    );
+    game.spawn_ai_drivers();
+    game.set_game_mode(GameMode::FreePractice);
+
+    let track_length_m = track.centerline.last().map(|p| p.distance_from_start_m).unwrap_or(0.0);
+    let corners = find_corners(&track.centerline);
+    let mut corner_off_tracks = vec![0u32; corners.len()];
+    let mut off_tracks = 0u32;
+    let mut collisions = 0u32;
+    let mut lap_times: Vec<u32> = Vec::new();
+
+    let max_ticks = game.timing.ticks(settings.max_seconds_per_lap) as u64 * (settings.laps as u64 + 1);
+    let target_lap = settings.laps + 1;
+    let mut ticks = 0u64;
+
+    while ticks < max_ticks && !game.session.participants.is_empty() {
+        let previous: HashMap<PlayerId, (bool, bool, u16)> = game
+            .session
+            .participants
+            .values()
+            .map(|s| (s.player_id, (s.is_on_track, s.is_colliding, s.current_lap)))
+            .collect();
+
+        let inputs: HashMap<PlayerId, PlayerInputData> = game
+            .session
+            .ai_player_ids
+            .iter()
+            .map(|id| (*id, game.generate_ai_input(id)))
+            .collect();
+        game.tick(&inputs);
+        ticks += 1;
+
+        for state in game.session.participants.values() {
+            let Some(&(was_on_track, was_colliding, lap)) = previous.get(&state.player_id) else {
+                continue;
+            };
+
+            if was_on_track && !state.is_on_track {
+                off_tracks += 1;
+                if let Some(idx) = corner_index(&corners, state.track_progress, track_length_m) {
+                    corner_off_tracks[idx] += 1;
+                }
+            }
+            if state.is_colliding && !was_colliding {
+                collisions += 1;
+            }
+            if state.current_lap > lap && lap > 0 {
+                if let Some(lap_time) = state.last_lap_time_ms {
+                    lap_times.push(lap_time);
+                }
+            }
+        }
+
+        if game.session.participants.values().all(|s| s.current_lap >= target_lap) {
+            break;
+        }
+    }
+
+    let cars = game.session.participants.len() as u8;
+    let cars_finished = game
+        .session
+        .participants
+        .values()
+        .filter(|s| s.current_lap >= target_lap)
+        .count() as u8;
+    let completed_laps = lap_times.len() as u32;
+    let car_laps = (completed_laps as f32).max(cars as f32);
+
+    let corners: Vec<CornerQaStats> = corners
+        .iter()
+        .zip(corner_off_tracks)
+        .map(|(corner, off_tracks)| CornerQaStats {
+            corner: corner.number,
+            start_m: corner.start_m,
+            end_m: corner.end_m,
+            off_tracks,
+            off_track_rate: off_tracks as f32 / car_laps.max(1.0),
+        })
+        .collect();
+
+    let average_lap_time_ms = if lap_times.is_empty() {
+        None
+    } else {
+        Some((lap_times.iter().map(|&t| t as u64).sum::<u64>() / lap_times.len() as u64) as u32)
+    };
+
+    let mut flags = Vec::new();
+    if completed_laps == 0 {
+        flags.push(QaFlag::NoCompletedLaps);
+    }
+    if cars_finished < cars {
+        flags.push(QaFlag::IncompleteRace);
+    }
+    for corner in &corners {
+        if corner.off_tracks >= 2 && corner.off_track_rate > HIGH_OFF_TRACK_RATE {
+            flags.push(QaFlag::HighOffTrackCorner { corner: corner.corner });
+        }
+    }
+    if collisions as f32 / car_laps.max(1.0) > HIGH_COLLISION_RATE {
+        flags.push(QaFlag::FrequentCollisions);
+    }
+    if lap_time_variation(&lap_times) > INCONSISTENT_PACE_CV {
+        flags.push(QaFlag::InconsistentPace);
+    }
+
+    TrackQaReport {
+        track_id: track.id,
+        track_name: track.name.clone(),
+        source_path: track.source_path.clone(),
+        cars,
+        target_laps: settings.laps,
+        simulated_seconds: ticks as f32 / game.timing.tick_rate_hz as f32,
+        completed_laps,
+        cars_finished,
+        average_lap_time_ms,
+        best_lap_time_ms: lap_times.iter().min().copied(),
+        off_tracks,
+        collisions,
+        corners,
+        flags,
+    }
+}
+
+/// Find corners from centerline curvature
+pub fn find_corners(centerline: &[TrackPoint]) -> Vec<Corner> {
+    let mut corners = Vec::new();
+    let mut start: Option<f32> = None;
+
+    for pair in centerline.windows(2) {
+        let distance = pair[1].distance_from_start_m - pair[0].distance_from_start_m;
+        let mut turn = pair[1].heading_rad - pair[0].heading_rad;
+        while turn > std::f32::consts::PI {
+            turn -= 2.0 * std::f32::consts::PI;
+        }
+        while turn < -std::f32::consts::PI {
+            turn += 2.0 * std::f32::consts::PI;
+        }
+
+        let in_corner = distance > 0.0 && turn.abs() / distance > CORNER_CURVATURE;
+        match (in_corner, start) {
+            (true, None) => start = Some(pair[0].distance_from_start_m),
+            (false, Some(s)) => {
+                push_corner(&mut corners, s, pair[0].distance_from_start_m);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    if let (Some(s), Some(last)) = (start, centerline.last()) {
+        push_corner(&mut corners, s, last.distance_from_start_m);
+    }
+
+    corners
+}
+
+fn push_corner(corners: &mut Vec<Corner>, start_m: f32, end_m: f32) {
+    if end_m - start_m >= MIN_CORNER_LENGTH_M {
+        corners.push(Corner {
+            number: corners.len() as u16 + 1,
+            start_m,
+            end_m,
+        });
+    }
+}
+
+/// Corner an off-track at `progress` is attributed to: the corner the car is in,
+/// or the last one it passed (running wide on exit ends up in the run-off after it)
+fn corner_index(corners: &[Corner], progress: f32, track_length_m: f32) -> Option<usize> {
+    if corners.is_empty() || track_length_m <= 0.0 {
+        return None;
+    }
+
+    corners
+        .iter()
+        .rposition(|c| c.start_m <= progress)
+        .or(Some(corners.len() - 1))
+}
+
+/// Coefficient of variation of lap times
+fn lap_time_variation(lap_times: &[u32]) -> f32 {
+    if lap_times.len() < 2 {
+        return 0.0;
+    }
+
+    let n = lap_times.len() as f32;
+    let mean = lap_times.iter().map(|&t| t as f32).sum::<f32>() / n;
+    if mean <= 0.0 {
+        return 0.0;
+    }
+    let variance = lap_times.iter().map(|&t| (t as f32 - mean).powi(2)).sum::<f32>() / n;
+    variance.sqrt() / mean
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(distance: f32, heading: f32) -> TrackPoint {
+        TrackPoint {
+            distance_from_start_m: distance,
+            heading_rad: heading,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_find_corners() {
+        // Straight, a 90 degree bend over 50 m, then straight again
+        let centerline: Vec<TrackPoint> = (0..=20)
+            .map(|i| {
+                let d = i as f32 * 10.0;
+                let heading = if d <= 50.0 {
+                    0.0
+                } else if d <= 100.0 {
+                    (d - 50.0) / 50.0 * std::f32::consts::FRAC_PI_2
+                } else {
+                    std::f32::consts::FRAC_PI_2
+                };
+                point(d, heading)
+            })
+            .collect();
+        let corners = find_corners(&centerline);
+        assert_eq!(corners.len(), 1);
+        assert_eq!(corners[0].number, 1);
+        assert_eq!(corners[0].start_m, 50.0);
+        assert_eq!(corners[0].end_m, 100.0);
+
+        assert_eq!(corner_index(&corners, 70.0, 200.0), Some(0));
+        assert_eq!(corner_index(&corners, 150.0, 200.0), Some(0));
+        assert_eq!(corner_index(&corners, 10.0, 200.0), Some(0));
+    }
+
+    #[test]
+    fn test_lap_time_variation() {
+        assert_eq!(lap_time_variation(&[60_000]), 0.0);
+        assert!(lap_time_variation(&[60_000, 60_500, 59_800]) < 0.05);
+        assert!(lap_time_variation(&[40_000, 90_000]) > INCONSISTENT_PACE_CV);
+    }
+
+    #[test]
+    fn test_run_track_qa_on_default_track() {
+        let track = TrackConfig::default();
+        let car = CarConfig::default();
+        let car_configs = HashMap::from([(car.id, car)]);
+        let settings = QaSettings {
+            ai_count: 2,
+            laps: 1,
+            max_seconds_per_lap: 60,
+        };
+
+        let report = run_track_qa(&track, &car_configs, &settings);
+
+        assert_eq!(report.track_id, track.id);
+        assert_eq!(report.cars, 2);
+        assert!(report.simulated_seconds > 0.0);
+        assert_eq!(report.completed_laps == 0, report.flags.contains(&QaFlag::NoCompletedLaps));
+    }
+}
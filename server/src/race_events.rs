@@ -0,0 +1,446 @@
+//! Race-director event log.
+//!
+//! Events are derived by comparing each car's state against the previous
+//! tick: a lap counter that ticks over is a completed lap, a car that moves
+//! ahead of another in the running order is an overtake, a car that starts
+//! colliding has made contact, and so on. Every
+//! event is kept for the lifetime of the session (for replays and post-race
+//! reports) and queued until the server broadcasts it.
+
+use crate::data::*;
+use crate::network::{RaceEvent, TimelineEntry};
+use crate::standings::{compare_race_order, pit_status};
+use std::collections::HashMap;
+
+/// Per-car state from the previous tick used to detect transitions
+#[derive(Debug, Clone, Copy)]
+struct CarSnapshot {
+    lap: u16,
+    is_on_track: bool,
+    pit_status: PitStatus,
+    position: usize,
+    finished: bool,
+    is_colliding: bool,
+}
+
+/// Furthest apart two colliding cars can be for their contact to be with each other
+const CAR_CONTACT_RANGE_M: f32 = 6.0;
+
+/// Detects and retains race events for a session
+#[derive(Debug, Default)]
+pub struct RaceEventLog {
+    session_id: SessionId,
+    events: Vec<RaceEvent>,
+    /// Index of the first event not yet handed out by `take_pending`
+    pending_from: usize,
+    previous: HashMap<PlayerId, CarSnapshot>,
+}
+
+impl RaceEventLog {
+    pub fn new(session_id: SessionId) -> Self {
+        Self {
+            session_id,
+            ..Default::default()
+        }
+    }
+
+    /// Compare the session against the previous tick and record any events.
+    ///
+    /// Must be called once per simulated tick while the session is racing.
+    pub fn update(&mut self, session: &RaceSession, track: &TrackConfig) {
+        let track_length_m = track
+            .centerline
+            .last()
+            .map(|p| p.distance_from_start_m)
+            .unwrap_or(0.0);
+
+        let mut order: Vec<&CarState> = session.participants.values().collect();
+        order.sort_by(|a, b| compare_race_order(a, b));
+
+        let current: HashMap<PlayerId, CarSnapshot> = order
+            .iter()
+            .enumerate()
+            .map(|(idx, state)| {
+                (
+                    state.player_id,
+                    CarSnapshot {
+                        lap: state.current_lap,
+                        is_on_track: state.is_on_track,
+                        pit_status: pit_status(state, track, track_length_m),
+                        position: idx,
+                        finished: state.finish_position.is_some(),
+                        is_colliding: state.is_colliding,
+                    },
+                )
+            })
+            .collect();
+
+        let tick = session.current_tick;
+
+        for (idx, state) in order.iter().enumerate() {
+            let now = current[&state.player_id];
+            let Some(before) = self.previous.get(&state.player_id).copied() else {
+                continue;
+            };
+
+            // Lap 0 -> 1 is the first crossing of the start line, not a completed lap
+            if now.lap > before.lap && before.lap > 0 {
+                let mut event = self.event(tick, RaceEventKind::LapCompleted, state);
+                event.lap = before.lap;
+                event.lap_time_ms = state.last_lap_time_ms;
+                self.events.push(event);
+            }
+
+            if now.pit_status != PitStatus::OnTrack && before.pit_status == PitStatus::OnTrack {
+                self.events.push(self.event(tick, RaceEventKind::PitEntry, state));
+            } else if now.pit_status == PitStatus::OnTrack && before.pit_status != PitStatus::OnTrack {
+                self.events.push(self.event(tick, RaceEventKind::PitExit, state));
+            } else if !now.is_on_track && before.is_on_track && now.pit_status == PitStatus::OnTrack {
+                self.events.push(self.event(tick, RaceEventKind::OffTrack, state));
+            }
+
+            if now.finished && !before.finished {
+                let mut event = self.event(tick, RaceEventKind::Finished, state);
+                event.position = state.finish_position;
+                self.events.push(event);
+            }
+
+            // Contacts: one event per pair of cars, with the car listed first in the
+            // running order as the subject
+            if now.is_colliding && !before.is_colliding {
+                let other = order
+                    .iter()
+                    .filter(|other| other.player_id != state.player_id && other.is_colliding)
+                    .map(|other| (other, (other.pos_x - state.pos_x).hypot(other.pos_y - state.pos_y)))
+                    .filter(|(_, distance)| *distance <= CAR_CONTACT_RANGE_M)
+                    .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                    .map(|(other, _)| other.player_id);
+                let reported = other.is_some_and(|other| {
+                    self.events.iter().rev().take_while(|e| e.server_tick == tick).any(|e| {
+                        e.kind == RaceEventKind::Contact
+                            && e.player_id == other
+                            && e.other_player_id == Some(state.player_id)
+                    })
+                });
+                if !reported {
+                    let mut event = self.event(tick, RaceEventKind::Contact, state);
+                    event.other_player_id = other;
+                    self.events.push(event);
+                }
+            }
+
+            // Overtakes: cars that were ahead last tick and are now behind.
+            // Finishers are ordered by finish position, so swaps there are not passes.
+            if now.position < before.position && !now.finished {
+                for other in order.iter().skip(idx + 1) {
+                    let other_before = self.previous.get(&other.player_id);
+                    if other_before.is_some_and(|o| o.position < before.position && !o.finished) {
+                        let mut event = self.event(tick, RaceEventKind::Overtake, state);
+                        event.other_player_id = Some(other.player_id);
+                        event.position = Some((idx + 1) as u8);
+                        self.events.push(event);
+                    }
+                }
+            }
+        }
+
+        self.previous = current;
+    }
+
+    /// Record an event raised outside the log, e.g. a penalty from the stewards
+    pub fn record_penalty(&mut self, tick: u32, state: &CarState, penalty_ms: u32, reason: &str) {
+        let mut event = self.event(tick, RaceEventKind::Penalty, state);
+        event.penalty_ms = Some(penalty_ms);
+        event.message = Some(reason.to_string());
+        self.events.push(event);
+    }
+
+    /// Record a car that missed its grid box and starts from the pit lane
+    pub fn record_pit_lane_start(&mut self, tick: u32, state: &CarState) {
+        let mut event = self.event(tick, RaceEventKind::PitLaneStart, state);
+        event.message = Some("Missed grid box on formation lap".to_string());
+        self.events.push(event);
+    }
+
+    /// Continue a log from events recorded before a restart; they are not
+    /// broadcast again
+    pub fn restore(&mut self, events: Vec<RaceEvent>) {
+        self.events = events;
+        self.pending_from = self.events.len();
+        self.previous.clear();
+    }
+
+    /// Events recorded since the previous call, for broadcasting
+    pub fn take_pending(&mut self) -> Vec<RaceEvent> {
+        let pending = self.events[self.pending_from..].to_vec();
+        self.pending_from = self.events.len();
+        pending
+    }
+
+    /// Every event recorded in this session, oldest first
+    pub fn events(&self) -> &[RaceEvent] {
+        &self.events
+    }
+
+    /// The race as a report would tell it: the start, lead changes, pit stops,
+    /// penalties, off-track excursions, contacts and finishes, oldest first.
+    /// Laps and overtakes further down the order are left out.
+    pub fn timeline(&self, race_start_tick: Option<u32>) -> Vec<TimelineEntry> {
+        let start = race_start_tick.map(|tick| TimelineEntry {
+            server_tick: tick,
+            kind: TimelineEventKind::RaceStart,
+            player_id: None,
+            other_player_id: None,
+            lap: 0,
+            position: None,
+            penalty_ms: None,
+            message: None,
+        });
+
+        // A car passing several others into the lead raises an overtake for
+        // each; the first names the car that led
+        let mut leader = None;
+        let events = self.events.iter().filter_map(|event| {
+            let kind = match event.kind {
+                RaceEventKind::Overtake if event.position == Some(1) && leader != Some(event.player_id) => {
+                    leader = Some(event.player_id);
+                    TimelineEventKind::LeadChange
+                }
+                RaceEventKind::PitEntry => TimelineEventKind::PitEntry,
+                RaceEventKind::PitExit => TimelineEventKind::PitExit,
+                RaceEventKind::PitLaneStart => TimelineEventKind::PitLaneStart,
+                RaceEventKind::Penalty => TimelineEventKind::Penalty,
+                RaceEventKind::OffTrack => TimelineEventKind::OffTrack,
+                RaceEventKind::Finished => TimelineEventKind::Finished,
+                RaceEventKind::Contact => TimelineEventKind::Contact,
+                RaceEventKind::LapCompleted | RaceEventKind::Overtake => return None,
+            };
+            Some(TimelineEntry {
+                server_tick: event.server_tick,
+                kind,
+                player_id: Some(event.player_id),
+                other_player_id: event.other_player_id,
+                lap: event.lap,
+                position: event.position,
+                penalty_ms: event.penalty_ms,
+                message: event.message.clone(),
+            })
+        });
+
+        start.into_iter().chain(events).collect()
+    }
+
+    fn event(&self, tick: u32, kind: RaceEventKind, state: &CarState) -> RaceEvent {
+        RaceEvent {
+            session_id: self.session_id,
+            server_tick: tick,
+            kind,
+            player_id: state.player_id,
+            other_player_id: None,
+            lap: state.current_lap,
+            position: None,
+            lap_time_ms: None,
+            penalty_ms: None,
+            message: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_session_with_cars(count: usize) -> (RaceSession, TrackConfig, Vec<PlayerId>) {
+        let track = TrackConfig::default();
+        let mut session = RaceSession::new(PlayerId::new_v4(), track.id, SessionKind::Multiplayer, 8, 0, 3);
+        let mut ids = Vec::new();
+
+        for slot in track.start_positions.iter().take(count) {
+            let id = PlayerId::new_v4();
+            let mut state = CarState::new(id, CarConfigId::new_v4(), slot);
+            state.current_lap = 1;
+            session.participants.insert(id, state);
+            ids.push(id);
+        }
+
+        (session, track, ids)
+    }
+
+    fn kinds(events: &[RaceEvent]) -> Vec<RaceEventKind> {
+        events.iter().map(|e| e.kind).collect()
+    }
+
+    #[test]
+    fn test_lap_completed_and_finish() {
+        let (mut session, track, ids) = create_session_with_cars(1);
+        let mut log = RaceEventLog::new(session.id);
+        log.update(&session, &track);
+
+        let state = session.participants.get_mut(&ids[0]).unwrap();
+        state.current_lap = 2;
+        state.last_lap_time_ms = Some(61_250);
+        state.finish_position = Some(1);
+        session.current_tick = 100;
+        log.update(&session, &track);
+
+        let events = log.take_pending();
+        assert_eq!(kinds(&events), vec![RaceEventKind::LapCompleted, RaceEventKind::Finished]);
+        assert_eq!(events[0].lap, 1);
+        assert_eq!(events[0].lap_time_ms, Some(61_250));
+        assert_eq!(events[1].position, Some(1));
+        assert_eq!(events[0].server_tick, 100);
+
+        // Pending events are handed out once but retained for the session
+        assert!(log.take_pending().is_empty());
+        assert_eq!(log.events().len(), 2);
+    }
+
+    #[test]
+    fn test_overtake() {
+        let (mut session, track, ids) = create_session_with_cars(2);
+        session.participants.get_mut(&ids[0]).unwrap().track_progress = 200.0;
+        session.participants.get_mut(&ids[1]).unwrap().track_progress = 190.0;
+
+        let mut log = RaceEventLog::new(session.id);
+        log.update(&session, &track);
+
+        session.participants.get_mut(&ids[1]).unwrap().track_progress = 210.0;
+        log.update(&session, &track);
+
+        let events = log.take_pending();
+        assert_eq!(kinds(&events), vec![RaceEventKind::Overtake]);
+        assert_eq!(events[0].player_id, ids[1]);
+        assert_eq!(events[0].other_player_id, Some(ids[0]));
+        assert_eq!(events[0].position, Some(1));
+    }
+
+    #[test]
+    fn test_contact_reported_once_per_pair() {
+        let (mut session, track, ids) = create_session_with_cars(3);
+        for (i, id) in ids.iter().enumerate() {
+            let state = session.participants.get_mut(id).unwrap();
+            state.pos_x = 0.0;
+            state.pos_y = i as f32 * 50.0;
+            state.track_progress = 300.0 - i as f32 * 50.0;
+        }
+
+        let mut log = RaceEventLog::new(session.id);
+        log.update(&session, &track);
+
+        // The first two cars touch; the third hits a wall on its own
+        session.participants.get_mut(&ids[1]).unwrap().pos_y = 3.0;
+        for id in &ids {
+            session.participants.get_mut(id).unwrap().is_colliding = true;
+        }
+        session.current_tick = 10;
+        log.update(&session, &track);
+        // Still touching on the next tick is the same contact
+        session.current_tick = 11;
+        log.update(&session, &track);
+
+        let events = log.take_pending();
+        assert_eq!(kinds(&events), vec![RaceEventKind::Contact, RaceEventKind::Contact]);
+        assert_eq!((events[0].player_id, events[0].other_player_id), (ids[0], Some(ids[1])));
+        assert_eq!((events[1].player_id, events[1].other_player_id), (ids[2], None));
+    }
+
+    #[test]
+    fn test_off_track_and_pit_lane() {
+        let (mut session, mut track, ids) = create_session_with_cars(1);
+        track.pit_lane = Some(PitLaneConfig {
+            entry_point: TrackPoint { distance_from_start_m: 550.0, ..Default::default() },
+            exit_point: TrackPoint { distance_from_start_m: 50.0, ..Default::default() },
+            speed_limit_mps: 22.0,
+            pit_stalls: Vec::new(),
+            centerline: Vec::new(),
+            speed_limit_start_m: 0.0,
+            speed_limit_end_m: 0.0,
+        });
+        session.participants.get_mut(&ids[0]).unwrap().track_progress = 300.0;
+
+        let mut log = RaceEventLog::new(session.id);
+        log.update(&session, &track);
+
+        // Leaving the track away from the pit lane is an off-track excursion
+        session.participants.get_mut(&ids[0]).unwrap().is_on_track = false;
+        log.update(&session, &track);
+        session.participants.get_mut(&ids[0]).unwrap().is_on_track = true;
+        log.update(&session, &track);
+
+        // Leaving the track inside the pit window is a pit entry
+        session.participants.get_mut(&ids[0]).unwrap().track_progress = 560.0;
+        log.update(&session, &track);
+        session.participants.get_mut(&ids[0]).unwrap().is_on_track = false;
+        log.update(&session, &track);
+        session.participants.get_mut(&ids[0]).unwrap().is_on_track = true;
+        log.update(&session, &track);
+
+        assert_eq!(
+            kinds(&log.take_pending()),
+            vec![RaceEventKind::OffTrack, RaceEventKind::PitEntry, RaceEventKind::PitExit]
+        );
+    }
+
+    #[test]
+    fn test_timeline_keeps_lead_changes_stops_and_incidents() {
+        let (mut session, track, ids) = create_session_with_cars(3);
+        session.participants.get_mut(&ids[0]).unwrap().track_progress = 200.0;
+        session.participants.get_mut(&ids[1]).unwrap().track_progress = 190.0;
+        session.participants.get_mut(&ids[2]).unwrap().track_progress = 180.0;
+
+        let mut log = RaceEventLog::new(session.id);
+        log.update(&session, &track);
+
+        // Third passes second, then second takes the lead
+        session.current_tick = 10;
+        session.participants.get_mut(&ids[2]).unwrap().track_progress = 195.0;
+        log.update(&session, &track);
+        session.current_tick = 20;
+        session.participants.get_mut(&ids[1]).unwrap().track_progress = 210.0;
+        log.update(&session, &track);
+
+        session.current_tick = 30;
+        session.participants.get_mut(&ids[0]).unwrap().is_on_track = false;
+        log.record_penalty(30, &session.participants[&ids[2]], 5000, "Track limits");
+        log.update(&session, &track);
+
+        session.current_tick = 40;
+        let state = session.participants.get_mut(&ids[1]).unwrap();
+        state.current_lap = 2;
+        state.finish_position = Some(1);
+        log.update(&session, &track);
+
+        let timeline = log.timeline(Some(5));
+        let entries: Vec<(u32, TimelineEventKind, Option<PlayerId>)> =
+            timeline.iter().map(|e| (e.server_tick, e.kind, e.player_id)).collect();
+        assert_eq!(
+            entries,
+            vec![
+                (5, TimelineEventKind::RaceStart, None),
+                (20, TimelineEventKind::LeadChange, Some(ids[1])),
+                (30, TimelineEventKind::Penalty, Some(ids[2])),
+                (30, TimelineEventKind::OffTrack, Some(ids[0])),
+                (40, TimelineEventKind::Finished, Some(ids[1])),
+            ]
+        );
+        assert_eq!(timeline[1].other_player_id, Some(ids[0]));
+        assert_eq!(timeline[2].penalty_ms, Some(5000));
+        assert_eq!(timeline[4].position, Some(1));
+
+        // Without a recorded start the timeline opens with the first event
+        assert_eq!(log.timeline(None)[0].kind, TimelineEventKind::LeadChange);
+    }
+
+    #[test]
+    fn test_record_penalty() {
+        let (session, _track, ids) = create_session_with_cars(1);
+        let mut log = RaceEventLog::new(session.id);
+
+        log.record_penalty(42, &session.participants[&ids[0]], 5000, "Track limits");
+
+        let events = log.take_pending();
+        assert_eq!(kinds(&events), vec![RaceEventKind::Penalty]);
+        assert_eq!(events[0].penalty_ms, Some(5000));
+        assert_eq!(events[0].message.as_deref(), Some("Track limits"));
+    }
+}
@@ -0,0 +1,394 @@
+//! Headless accelerated AI race simulation.
+//!
+//! Runs a full AI-only race back to back without a ticker, so a race that
+//! would take minutes in real time finishes in seconds. The report holds the
+//! finishing order and lap time distributions per driver and per car, which
+//! is what car physics and AI profile balancing is judged on.
+
+use crate::ai_driver::generate_default_ai_profiles;
+use crate::data::*;
+use crate::game_session::GameSession;
+use crate::standings::compare_race_order;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Width of one lap time histogram bin (ms)
+const HISTOGRAM_BIN_MS: u32 = 500;
+
+/// Settings for a simulated race
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RaceSimSettings {
+    /// Number of AI drivers on the grid
+    pub ai_count: u8,
+    /// Race distance in laps
+    pub laps: u16,
+    /// Simulated time allowed per lap before the race is abandoned
+    pub max_seconds_per_lap: u32,
+}
+
+impl Default for RaceSimSettings {
+    fn default() -> Self {
+        Self {
+            ai_count: 8,
+            laps: 5,
+            max_seconds_per_lap: 300,
+        }
+    }
+}
+
+/// Summary of a set of lap times
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LapTimeStats {
+    pub laps: u32,
+    pub min_ms: u32,
+    pub p25_ms: u32,
+    pub median_ms: u32,
+    pub p75_ms: u32,
+    pub max_ms: u32,
+    pub mean_ms: u32,
+    pub std_dev_ms: u32,
+}
+
+impl LapTimeStats {
+    /// Stats for `lap_times`, or None when there are none
+    pub fn from_lap_times(lap_times: &[u32]) -> Option<Self> {
+        if lap_times.is_empty() {
+            return None;
+        }
+
+        let mut sorted = lap_times.to_vec();
+        sorted.sort_unstable();
+        let n = sorted.len() as f64;
+        let mean = sorted.iter().map(|&t| t as f64).sum::<f64>() / n;
+        let variance = sorted.iter().map(|&t| (t as f64 - mean).powi(2)).sum::<f64>() / n;
+
+        Some(Self {
+            laps: sorted.len() as u32,
+            min_ms: sorted[0],
+            p25_ms: percentile(&sorted, 0.25),
+            median_ms: percentile(&sorted, 0.5),
+            p75_ms: percentile(&sorted, 0.75),
+            max_ms: sorted[sorted.len() - 1],
+            mean_ms: mean.round() as u32,
+            std_dev_ms: variance.sqrt().round() as u32,
+        })
+    }
+}
+
+/// Number of laps that fell in `[from_ms, from_ms + HISTOGRAM_BIN_MS)`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistogramBin {
+    pub from_ms: u32,
+    pub laps: u32,
+}
+
+/// Result of one driver, in finishing order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriverSimResult {
+    pub position: u8,
+    pub player_id: PlayerId,
+    pub driver_name: String,
+    pub skill_level: u8,
+    pub car_config_id: CarConfigId,
+    pub car_name: String,
+    /// Took the chequered flag before the race was abandoned
+    pub finished: bool,
+    pub laps_completed: u16,
+    /// Time from the start to the finish line on the final lap
+    pub race_time_ms: Option<u32>,
+    pub lap_times_ms: Vec<u32>,
+    pub lap_time_stats: Option<LapTimeStats>,
+}
+
+/// Lap times of every driver in one car
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CarSimResult {
+    pub car_config_id: CarConfigId,
+    pub car_name: String,
+    pub drivers: u8,
+    pub lap_time_stats: Option<LapTimeStats>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RaceSimReport {
+    /// Unix timestamp of the run
+    pub generated_at: u64,
+    pub track_id: TrackConfigId,
+    pub track_name: String,
+    pub settings: RaceSimSettings,
+    pub tick_rate_hz: u16,
+    pub simulated_seconds: f32,
+    /// Real time the simulation took
+    pub wall_clock_ms: u64,
+    /// Simulated time per unit of real time
+    pub speedup: f32,
+    /// The race distance was not covered by every car in time
+    pub abandoned: bool,
+    pub results: Vec<DriverSimResult>,
+    pub cars: Vec<CarSimResult>,
+    /// Every lap of every driver
+    pub lap_time_stats: Option<LapTimeStats>,
+    pub lap_time_histogram: Vec<HistogramBin>,
+}
+
+/// Run a full AI-only race on `track` as fast as the simulation allows.
+///
+/// AI drivers are spread over the available cars in name order so every
+/// car gets a share of the field.
+pub fn run_race_sim(
+    track: &TrackConfig,
+    car_configs: &HashMap<CarConfigId, CarConfig>,
+    settings: &RaceSimSettings,
+) -> RaceSimReport {
+    let started = Instant::now();
+
+    let ai_count = settings.ai_count.min(track.start_positions.len() as u8);
+    let mut cars: Vec<&CarConfig> = car_configs.values().collect();
+    cars.sort_by(|a, b| a.name.cmp(&b.name));
+    let mut profiles = generate_default_ai_profiles(ai_count);
+    for (i, profile) in profiles.iter_mut().enumerate() {
+        if !cars.is_empty() {
+            profile.preferred_car_id = Some(cars[i % cars.len()].id);
+        }
+    }
+    let drivers: HashMap<PlayerId, (String, u8)> = profiles
+        .iter()
+        .map(|p| (p.id, (p.name.clone(), p.skill_level)))
+        .collect();
+
+    let session = RaceSession::new(
+        PlayerId::nil(),
+        track.id,
+        SessionKind::Practice,
+        ai_count,
+        ai_count,
+        settings.laps.min(u8::MAX as u16) as u8,
+    );
+    let mut game = GameSession::with_ai_profiles(session, track.clone(), car_configs.clone(), profiles);
+    game.spawn_ai_drivers();
+    game.session.game_mode = GameMode::Race;
+    game.session.state = SessionState::Racing;
+    game.session.race_start_tick = Some(0);
+
+    let tick_rate_hz = game.timing.tick_rate_hz;
+    let max_ticks =
+        game.timing.ticks(settings.max_seconds_per_lap) as u64 * (settings.laps as u64 + 1);
+    let target_lap = settings.laps + 1;
+    let mut lap_times: HashMap<PlayerId, Vec<u32>> = HashMap::new();
+    let mut finish_ticks: HashMap<PlayerId, u32> = HashMap::new();
+    let mut ticks = 0u64;
+
+    while ticks < max_ticks && !game.session.participants.is_empty() {
+        let previous_laps: HashMap<PlayerId, u16> = game
+            .session
+            .participants
+            .values()
+            .map(|s| (s.player_id, s.current_lap))
+            .collect();
+
+        let inputs: HashMap<PlayerId, PlayerInputData> = game
+            .session
+            .ai_player_ids
+            .iter()
+            .map(|id| (*id, game.generate_ai_input(id)))
+            .collect();
+        game.tick(&inputs);
+        ticks += 1;
+
+        for state in game.session.participants.values() {
+            let Some(&lap) = previous_laps.get(&state.player_id) else {
+                continue;
+            };
+            if state.current_lap <= lap || finish_ticks.contains_key(&state.player_id) {
+                continue;
+            }
+            if lap > 0 {
+                if let Some(lap_time) = state.last_lap_time_ms {
+                    lap_times.entry(state.player_id).or_default().push(lap_time);
+                }
+            }
+            if state.current_lap >= target_lap {
+                finish_ticks.insert(state.player_id, game.session.current_tick);
+            }
+        }
+
+        if finish_ticks.len() == game.session.participants.len() {
+            break;
+        }
+    }
+
+    // Finishers in the order they crossed the line, then everyone else by track position
+    let mut order: Vec<&CarState> = game.session.participants.values().collect();
+    order.sort_by(|a, b| match (finish_ticks.get(&a.player_id), finish_ticks.get(&b.player_id)) {
+        (Some(a_tick), Some(b_tick)) => a_tick.cmp(b_tick),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => compare_race_order(a, b),
+    });
+
+    let ms_per_tick = 1000.0 / tick_rate_hz as f64;
+    let results: Vec<DriverSimResult> = order
+        .iter()
+        .enumerate()
+        .map(|(idx, state)| {
+            let (driver_name, skill_level) = drivers.get(&state.player_id).cloned().unwrap_or_default();
+            let laps = lap_times.remove(&state.player_id).unwrap_or_default();
+            DriverSimResult {
+                position: (idx + 1) as u8,
+                player_id: state.player_id,
+                driver_name,
+                skill_level,
+                car_config_id: state.car_config_id,
+                car_name: car_configs
+                    .get(&state.car_config_id)
+                    .map(|c| c.name.clone())
+                    .unwrap_or_default(),
+                finished: finish_ticks.contains_key(&state.player_id),
+                laps_completed: laps.len() as u16,
+                race_time_ms: finish_ticks
+                    .get(&state.player_id)
+                    .map(|&tick| (tick as f64 * ms_per_tick).round() as u32),
+                lap_time_stats: LapTimeStats::from_lap_times(&laps),
+                lap_times_ms: laps,
+            }
+        })
+        .collect();
+
+    let mut car_laps: Vec<(CarConfigId, String, u8, Vec<u32>)> = Vec::new();
+    for result in &results {
+        match car_laps.iter_mut().find(|(id, ..)| *id == result.car_config_id) {
+            Some((_, _, drivers, laps)) => {
+                *drivers += 1;
+                laps.extend_from_slice(&result.lap_times_ms);
+            }
+            None => car_laps.push((
+                result.car_config_id,
+                result.car_name.clone(),
+                1,
+                result.lap_times_ms.clone(),
+            )),
+        }
+    }
+    car_laps.sort_by(|a, b| a.1.cmp(&b.1));
+    let cars = car_laps
+        .into_iter()
+        .map(|(car_config_id, car_name, drivers, laps)| CarSimResult {
+            car_config_id,
+            car_name,
+            drivers,
+            lap_time_stats: LapTimeStats::from_lap_times(&laps),
+        })
+        .collect();
+
+    let all_laps: Vec<u32> = results.iter().flat_map(|r| r.lap_times_ms.iter().copied()).collect();
+    let simulated_seconds = ticks as f32 / tick_rate_hz as f32;
+    let wall_clock = started.elapsed();
+
+    RaceSimReport {
+        generated_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        track_id: track.id,
+        track_name: track.name.clone(),
+        settings: settings.clone(),
+        tick_rate_hz,
+        simulated_seconds,
+        wall_clock_ms: wall_clock.as_millis() as u64,
+        speedup: simulated_seconds / wall_clock.as_secs_f32().max(f32::EPSILON),
+        abandoned: results.iter().any(|r| !r.finished),
+        results,
+        cars,
+        lap_time_stats: LapTimeStats::from_lap_times(&all_laps),
+        lap_time_histogram: lap_time_histogram(&all_laps),
+    }
+}
+
+/// Nearest-rank percentile of sorted lap times
+fn percentile(sorted: &[u32], fraction: f64) -> u32 {
+    let rank = (fraction * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Lap times bucketed into `HISTOGRAM_BIN_MS` bins from the fastest to the slowest lap
+fn lap_time_histogram(lap_times: &[u32]) -> Vec<HistogramBin> {
+    let (Some(&min), Some(&max)) = (lap_times.iter().min(), lap_times.iter().max()) else {
+        return Vec::new();
+    };
+
+    let first = min / HISTOGRAM_BIN_MS;
+    let mut bins: Vec<HistogramBin> = (first..=max / HISTOGRAM_BIN_MS)
+        .map(|bin| HistogramBin {
+            from_ms: bin * HISTOGRAM_BIN_MS,
+            laps: 0,
+        })
+        .collect();
+    for &lap_time in lap_times {
+        bins[(lap_time / HISTOGRAM_BIN_MS - first) as usize].laps += 1;
+    }
+    bins
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lap_time_stats() {
+        assert_eq!(LapTimeStats::from_lap_times(&[]), None);
+
+        let stats = LapTimeStats::from_lap_times(&[62_000, 60_000, 61_000, 63_000]).unwrap();
+        assert_eq!(stats.laps, 4);
+        assert_eq!(stats.min_ms, 60_000);
+        assert_eq!(stats.p25_ms, 60_000);
+        assert_eq!(stats.median_ms, 61_000);
+        assert_eq!(stats.p75_ms, 62_000);
+        assert_eq!(stats.max_ms, 63_000);
+        assert_eq!(stats.mean_ms, 61_500);
+        assert_eq!(stats.std_dev_ms, 1_118);
+    }
+
+    #[test]
+    fn test_lap_time_histogram() {
+        assert!(lap_time_histogram(&[]).is_empty());
+
+        let bins = lap_time_histogram(&[60_100, 60_400, 61_200]);
+        assert_eq!(
+            bins,
+            vec![
+                HistogramBin { from_ms: 60_000, laps: 2 },
+                HistogramBin { from_ms: 60_500, laps: 0 },
+                HistogramBin { from_ms: 61_000, laps: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run_race_sim_on_default_track() {
+        let track = TrackConfig::default();
+        let car = CarConfig::default();
+        let car_configs = HashMap::from([(car.id, car)]);
+        let settings = RaceSimSettings {
+            ai_count: 2,
+            laps: 1,
+            max_seconds_per_lap: 60,
+        };
+
+        let report = run_race_sim(&track, &car_configs, &settings);
+
+        assert_eq!(report.track_id, track.id);
+        assert_eq!(report.results.len(), 2);
+        assert!(report.simulated_seconds > 0.0);
+        let positions: Vec<u8> = report.results.iter().map(|r| r.position).collect();
+        assert_eq!(positions, vec![1, 2]);
+        assert_eq!(report.abandoned, report.results.iter().any(|r| !r.finished));
+        assert_eq!(report.cars.len(), 1);
+        assert_eq!(report.cars[0].drivers, 2);
+
+        let laps: usize = report.results.iter().map(|r| r.lap_times_ms.len()).sum();
+        let histogram_laps: u32 = report.lap_time_histogram.iter().map(|b| b.laps).sum();
+        assert_eq!(histogram_laps as usize, laps);
+        assert_eq!(report.lap_time_stats.map(|s| s.laps as usize).unwrap_or(0), laps);
+    }
+}
@@ -0,0 +1,355 @@
+//! Racing line generation.
+//!
+//! Tracks without a `raceline` get one computed from the centerline and track
+//! widths when they load. The line starts on the centerline and is relaxed
+//! towards minimum curvature, which finds the apexes and opens up entries and
+//! exits. Each point only moves along the track normal and stays a car's
+//! width inside the edges.
+//!
+//! Every raceline point gets a target speed from a simple grip model: the
+//! cornering limit from the local curvature, followed by a braking pass
+//! backwards and an acceleration pass forwards so speeds are reachable.
+//!
+//! Generated lines are cached next to the track file as
+//! `<track>.raceline.msgpack` and regenerated when the track file is newer.
+
+use crate::data::{RacelinePoint, TrackPoint};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Spacing between generated raceline points (m), matching the converted real tracks
+const RACELINE_SPACING_M: f32 = 5.0;
+
+/// Distance the line keeps from the track edge (m)
+const EDGE_MARGIN_M: f32 = 1.0;
+
+/// Relaxation passes towards minimum curvature
+const RELAXATION_ITERATIONS: usize = 5000;
+
+const GRAVITY: f32 = 9.81;
+
+/// Lateral grip the target speeds assume (g)
+const CORNERING_G: f32 = 1.2;
+
+/// Deceleration the target speeds assume under braking (g)
+const BRAKING_G: f32 = 1.0;
+
+/// Acceleration the target speeds assume out of corners (g)
+const ACCELERATION_G: f32 = 0.5;
+
+/// Target speed on straights (m/s, ~300 km/h)
+const MAX_TARGET_SPEED_MPS: f32 = 83.0;
+
+/// Bumped whenever the generator changes so stale caches are regenerated
+const CACHE_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RacelineCache {
+    version: u32,
+    raceline: Vec<RacelinePoint>,
+}
+
+/// Compute a racing line with target speeds from the centerline and widths
+pub fn generate(centerline: &[TrackPoint], closed_loop: bool) -> Vec<RacelinePoint> {
+    // Centerline points a raceline spacing apart, plus the end of an open track
+    let mut samples: Vec<&TrackPoint> = Vec::new();
+    for point in centerline {
+        if samples
+            .last()
+            .is_none_or(|last| (point.x - last.x).hypot(point.y - last.y) >= RACELINE_SPACING_M)
+        {
+            samples.push(point);
+        }
+    }
+    if let (Some(last), false) = (centerline.last(), closed_loop) {
+        if samples.last().is_some_and(|sample| !std::ptr::eq(*sample, last)) {
+            samples.push(last);
+        }
+    }
+    let n = samples.len();
+    if n < 3 {
+        return samples
+            .iter()
+            .map(|p| RacelinePoint { x: p.x, y: p.y, z: p.z, target_speed_mps: 0.0 })
+            .collect();
+    }
+
+    // Left-pointing normal and allowed offset range at each sample
+    let normals: Vec<(f32, f32)> = samples
+        .iter()
+        .map(|p| {
+            let (sin, cos) = p.heading_rad.sin_cos();
+            (-sin, cos)
+        })
+        .collect();
+    let limits: Vec<(f32, f32)> = samples.iter().map(|p| lateral_limits(p)).collect();
+
+    let mut offsets = vec![0.0_f32; n];
+    let position = |offsets: &[f32], i: usize| {
+        (
+            samples[i].x + normals[i].0 * offsets[i],
+            samples[i].y + normals[i].1 * offsets[i],
+        )
+    };
+    // Neighbour `step` points away, or None past the ends of an open track
+    let neighbour = |i: usize, step: isize| {
+        let j = i as isize + step;
+        if closed_loop {
+            Some(j.rem_euclid(n as isize) as usize)
+        } else {
+            (0..n as isize).contains(&j).then_some(j as usize)
+        }
+    };
+
+    for _ in 0..RELAXATION_ITERATIONS {
+        for i in 0..n {
+            let (Some(prev), Some(next)) = (neighbour(i, -1), neighbour(i, 1)) else {
+                continue;
+            };
+            let (px, py) = position(&offsets, prev);
+            let (nx, ny) = position(&offsets, next);
+            let (tx, ty) = match (neighbour(i, -2), neighbour(i, 2)) {
+                // Least squared second differences over i and its neighbours
+                (Some(prev2), Some(next2)) => {
+                    let (ppx, ppy) = position(&offsets, prev2);
+                    let (nnx, nny) = position(&offsets, next2);
+                    (
+                        (4.0 * (px + nx) - (ppx + nnx)) / 6.0,
+                        (4.0 * (py + ny) - (ppy + nny)) / 6.0,
+                    )
+                }
+                // Straight between the neighbours next to the ends of an open track
+                _ => ((px + nx) * 0.5, (py + ny) * 0.5),
+            };
+            let desired = (tx - samples[i].x) * normals[i].0 + (ty - samples[i].y) * normals[i].1;
+            offsets[i] = desired.clamp(limits[i].0, limits[i].1);
+        }
+    }
+
+    let mut raceline: Vec<RacelinePoint> = (0..n)
+        .map(|i| {
+            let (x, y) = position(&offsets, i);
+            RacelinePoint { x, y, z: samples[i].z, target_speed_mps: 0.0 }
+        })
+        .collect();
+    assign_target_speeds(&mut raceline, closed_loop);
+    raceline
+}
+
+/// How far right (negative) and left (positive) of a centerline point the
+/// line may go
+pub fn lateral_limits(point: &TrackPoint) -> (f32, f32) {
+    (
+        -(point.width_right_m - EDGE_MARGIN_M).max(0.0),
+        (point.width_left_m - EDGE_MARGIN_M).max(0.0),
+    )
+}
+
+/// Fill in the target speed of every raceline point
+pub fn assign_target_speeds(raceline: &mut [RacelinePoint], closed_loop: bool) {
+    let n = raceline.len();
+    if n < 2 {
+        return;
+    }
+    let distance = |a: &RacelinePoint, b: &RacelinePoint| (b.x - a.x).hypot(b.y - a.y);
+
+    // Cornering limit from the circle through each point and its neighbours
+    let mut speeds: Vec<f32> = (0..n)
+        .map(|i| {
+            let (prev, next) = if closed_loop {
+                ((i + n - 1) % n, (i + 1) % n)
+            } else if i == 0 || i == n - 1 {
+                return MAX_TARGET_SPEED_MPS;
+            } else {
+                (i - 1, i + 1)
+            };
+            let (a, b, c) = (&raceline[prev], &raceline[i], &raceline[next]);
+            let cross = (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x);
+            let sides = distance(a, b) * distance(b, c) * distance(a, c);
+            let curvature = if sides > 1e-6 { 2.0 * cross.abs() / sides } else { 0.0 };
+            if curvature > 1e-6 {
+                (CORNERING_G * GRAVITY / curvature).sqrt().min(MAX_TARGET_SPEED_MPS)
+            } else {
+                MAX_TARGET_SPEED_MPS
+            }
+        })
+        .collect();
+
+    // A closed loop needs a second lap for the limits to carry over the line
+    let passes = if closed_loop { 2 * n } else { n - 1 };
+    for step in 0..passes {
+        let (i, next) = (step % n, (step + 1) % n);
+        let ds = distance(&raceline[i], &raceline[next]);
+        speeds[next] = speeds[next].min((speeds[i].powi(2) + 2.0 * ACCELERATION_G * GRAVITY * ds).sqrt());
+    }
+    for step in 0..passes {
+        let i = (n - 1) - step % n;
+        let prev = (i + n - 1) % n;
+        let ds = distance(&raceline[prev], &raceline[i]);
+        speeds[prev] = speeds[prev].min((speeds[i].powi(2) + 2.0 * BRAKING_G * GRAVITY * ds).sqrt());
+    }
+
+    for (point, speed) in raceline.iter_mut().zip(speeds) {
+        point.target_speed_mps = speed;
+    }
+}
+
+fn cache_path(track_file: &Path) -> PathBuf {
+    let mut cache_path = track_file.to_path_buf();
+    let stem = cache_path.file_stem().unwrap().to_string_lossy();
+    cache_path.set_file_name(format!("{}.raceline.msgpack", stem));
+    cache_path
+}
+
+/// Generated raceline cached for a track file, if it is newer than the track file
+pub fn load_cache(track_file: &Path) -> Option<Vec<RacelinePoint>> {
+    let cache_path = cache_path(track_file);
+    let source_modified = fs::metadata(track_file).and_then(|m| m.modified()).ok()?;
+    let cache_modified = fs::metadata(&cache_path).and_then(|m| m.modified()).ok()?;
+    if cache_modified < source_modified {
+        return None;
+    }
+
+    let content = fs::read(&cache_path).ok()?;
+    let cache: RacelineCache = rmp_serde::from_slice(&content)
+        .map_err(|e| {
+            warn!("Failed to parse raceline cache {}: {}", cache_path.display(), e);
+            e
+        })
+        .ok()?;
+    (cache.version == CACHE_VERSION).then_some(cache.raceline)
+}
+
+/// Cache a generated raceline next to its track file
+pub fn save_cache(track_file: &Path, raceline: &[RacelinePoint]) -> Result<(), String> {
+    let cache = RacelineCache {
+        version: CACHE_VERSION,
+        raceline: raceline.to_vec(),
+    };
+    let msgpack_data = rmp_serde::to_vec(&cache)
+        .map_err(|e| format!("Failed to serialize raceline: {}", e))?;
+    fs::write(cache_path(track_file), msgpack_data)
+        .map_err(|e| format!("Failed to write raceline cache: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::track_loader::TrackLoader;
+
+    /// 200 m square with 12 m of track, driven anticlockwise
+    fn square_track() -> crate::data::TrackConfig {
+        TrackLoader::load_from_string(
+            r#"{
+                "name": "Square",
+                "default_width": 12.0,
+                "closed_loop": true,
+                "nodes": [
+                    {"x": 0.0, "y": 0.0}, {"x": 100.0, "y": 0.0}, {"x": 200.0, "y": 0.0},
+                    {"x": 200.0, "y": 100.0}, {"x": 200.0, "y": 200.0},
+                    {"x": 100.0, "y": 200.0}, {"x": 0.0, "y": 200.0},
+                    {"x": 0.0, "y": 100.0}
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn distance_to_centerline(track: &crate::data::TrackConfig, x: f32, y: f32) -> f32 {
+        track
+            .centerline
+            .iter()
+            .map(|p| (p.x - x).hypot(p.y - y))
+            .fold(f32::MAX, f32::min)
+    }
+
+    #[test]
+    fn test_generated_line_cuts_the_corners_inside_the_track() {
+        let track = square_track();
+        assert!(!track.raceline.is_empty());
+
+        // Every point stays on the tarmac, a margin inside the edges
+        for point in &track.raceline {
+            assert!(distance_to_centerline(&track, point.x, point.y) <= 6.0 - EDGE_MARGIN_M + 0.5);
+        }
+
+        // The line passes closer to the inner kerb of a corner than the centerline does
+        let inside = |x: f32, y: f32| (x - 194.0).hypot(y - 194.0);
+        let line_apex = track.raceline.iter().map(|p| inside(p.x, p.y)).fold(f32::MAX, f32::min);
+        let centre_apex = track.centerline.iter().map(|p| inside(p.x, p.y)).fold(f32::MAX, f32::min);
+        assert!(line_apex < centre_apex - 2.0, "apex {} vs centerline {}", line_apex, centre_apex);
+    }
+
+    #[test]
+    fn test_target_speed_drops_for_corners_and_builds_on_straights() {
+        let track = square_track();
+        let speed_near = |x: f32, y: f32| {
+            track
+                .raceline
+                .iter()
+                .min_by(|a, b| (a.x - x).hypot(a.y - y).total_cmp(&(b.x - x).hypot(b.y - y)))
+                .unwrap()
+                .target_speed_mps
+        };
+
+        let corner = speed_near(190.0, 190.0);
+        let straight = speed_near(100.0, 0.0);
+        assert!(corner > 5.0);
+        assert!(straight > corner + 5.0, "straight {} vs corner {}", straight, corner);
+
+        // Consecutive speeds never change faster than the car can brake
+        for pair in track.raceline.windows(2) {
+            let ds = (pair[1].x - pair[0].x).hypot(pair[1].y - pair[0].y);
+            let max_drop = (pair[0].target_speed_mps.powi(2) - 2.0 * BRAKING_G * GRAVITY * ds).max(0.0).sqrt();
+            assert!(pair[1].target_speed_mps >= max_drop - 0.01);
+        }
+    }
+
+    #[test]
+    fn test_provided_racelines_are_kept_and_given_speeds() {
+        let track = TrackLoader::load_from_string(
+            r#"{
+                "name": "With Line",
+                "default_width": 12.0,
+                "closed_loop": false,
+                "nodes": [{"x": 0.0, "y": 0.0}, {"x": 100.0, "y": 0.0}],
+                "raceline": [
+                    {"x": 0.0, "y": 0.0, "z": 0.0},
+                    {"x": 50.0, "y": 1.0, "z": 0.0},
+                    {"x": 100.0, "y": 0.0, "z": 0.0}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(track.raceline.len(), 3);
+        assert_eq!(track.raceline[1].x, 50.0);
+        assert!(track.raceline.iter().all(|p| p.target_speed_mps > 0.0));
+    }
+
+    #[test]
+    fn test_generated_raceline_is_cached_next_to_the_track_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let track_path = dir.path().join("Square.json");
+        fs::write(
+            &track_path,
+            r#"{
+                "name": "Square",
+                "default_width": 12.0,
+                "closed_loop": true,
+                "nodes": [{"x": 0.0, "y": 0.0}, {"x": 200.0, "y": 0.0}, {"x": 200.0, "y": 200.0}, {"x": 0.0, "y": 200.0}]
+            }"#,
+        )
+        .unwrap();
+
+        let generated = TrackLoader::load_from_file(&track_path).unwrap().raceline;
+        assert!(dir.path().join("Square.raceline.msgpack").exists());
+
+        let cached = load_cache(&track_path).unwrap();
+        assert_eq!(cached.len(), generated.len());
+        assert_eq!(cached[3].x, generated[3].x);
+        assert_eq!(cached[3].target_speed_mps, generated[3].target_speed_mps);
+    }
+}
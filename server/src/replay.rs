@@ -1,7 +1,10 @@
+use crate::anonymize::Pseudonymizer;
 use crate::data::*;
-use crate::network::Telemetry;
+use crate::game_session::{GameSession, SimulationState};
+use crate::network::{RaceEvent, Telemetry};
+use crate::session_broadcast::{BroadcastFrame, SessionBroadcaster};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::fs::{self, File};
@@ -9,9 +12,40 @@ use tokio::io::{AsyncWriteExt, BufWriter};
 use tokio::sync::RwLock;
 use tracing::{info, warn};
 
+/// Slowest playback speed, as a multiple of real time
+pub const MIN_PLAYBACK_SPEED: f32 = 0.1;
+/// Fastest playback speed, as a multiple of real time
+pub const MAX_PLAYBACK_SPEED: f32 = 8.0;
+
+/// Replay files record inputs and an initial state since version 2, and carry
+/// their format version and highlights in the metadata since version 3
+pub const REPLAY_FORMAT_VERSION: u32 = 3;
+/// Oldest replay files that are migrated on load
+pub const MIN_REPLAY_FORMAT_VERSION: u32 = 1;
+
+/// Ticks between state hashes stored in an input replay
+pub const STATE_HASH_INTERVAL_TICKS: u32 = 60;
+
+/// Frames between simulation states kept for seeking back in an input replay
+const CHECKPOINT_INTERVAL_FRAMES: usize = 2400;
+
+/// Ticks a delayed feed simulates per server tick while catching up to its delay
+const MAX_CATCH_UP_TICKS: usize = 32;
+
+/// Seconds of build-up shown before an overtake or contact
+const HIGHLIGHT_LEAD_IN_S: u32 = 5;
+/// Seconds shown after an overtake, contact or the winner taking the flag
+const HIGHLIGHT_FOLLOW_UP_S: u32 = 3;
+
+/// Size of each `ReplayChunk` when a client downloads a replay file
+pub const REPLAY_CHUNK_BYTES: usize = 64 * 1024;
+
 /// Replay metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReplayMetadata {
+    /// Format the replay was recorded in; older files keep their version after migration
+    #[serde(default)]
+    pub format_version: u32,
     pub session_id: SessionId,
     pub track_config_id: TrackConfigId,
     pub track_name: String,
@@ -19,6 +53,9 @@ pub struct ReplayMetadata {
     pub duration_ticks: u32,
     pub tick_rate: u16,
     pub participants: Vec<ReplayParticipant>,
+    /// Found in the race events when the replay is saved, in tick order
+    #[serde(default)]
+    pub highlights: Vec<ReplayHighlight>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,6 +66,65 @@ pub struct ReplayParticipant {
     pub finish_position: Option<u8>,
 }
 
+/// A stretch of a replay worth watching again
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayHighlight {
+    pub kind: HighlightKind,
+    pub start_tick: u32,
+    pub end_tick: u32,
+    /// Car to follow: the one overtaking, the one in contact, or the winner
+    pub player_id: PlayerId,
+    /// Car overtaken or hit
+    pub other_player_id: Option<PlayerId>,
+}
+
+/// Highlights of a race found in its event log, in tick order and clamped to
+/// the recorded `first_tick..=last_tick`
+pub fn extract_highlights(
+    events: &[RaceEvent],
+    first_tick: u32,
+    last_tick: u32,
+    lap_limit: u8,
+    tick_rate: u16,
+) -> Vec<ReplayHighlight> {
+    let lead_in = HIGHLIGHT_LEAD_IN_S * tick_rate as u32;
+    let follow_up = HIGHLIGHT_FOLLOW_UP_S * tick_rate as u32;
+    let around = |kind, event: &RaceEvent| ReplayHighlight {
+        kind,
+        start_tick: event.server_tick.saturating_sub(lead_in).max(first_tick),
+        end_tick: event.server_tick.saturating_add(follow_up).min(last_tick),
+        player_id: event.player_id,
+        other_player_id: event.other_player_id,
+    };
+
+    let mut highlights: Vec<ReplayHighlight> = events
+        .iter()
+        .filter_map(|event| match event.kind {
+            RaceEventKind::Overtake => Some(around(HighlightKind::Overtake, event)),
+            RaceEventKind::Contact => Some(around(HighlightKind::Contact, event)),
+            _ => None,
+        })
+        .collect();
+
+    // The final lap starts when the leader completes the one before it
+    if let Some(winner) = events.iter().find(|e| e.kind == RaceEventKind::Finished) {
+        let start_tick = events
+            .iter()
+            .find(|e| e.kind == RaceEventKind::LapCompleted && e.lap + 1 == lap_limit as u16)
+            .map_or(first_tick, |e| e.server_tick.max(first_tick));
+        highlights.push(ReplayHighlight {
+            kind: HighlightKind::FinalLap,
+            start_tick,
+            end_tick: winner.server_tick.saturating_add(follow_up).min(last_tick),
+            player_id: winner.player_id,
+            other_player_id: None,
+        });
+    }
+
+    highlights.sort_by_key(|h| h.start_tick);
+    highlights
+}
+
 /// A single frame of replay data
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReplayFrame {
@@ -36,12 +132,198 @@ pub struct ReplayFrame {
     pub telemetry: Telemetry,
 }
 
+/// What went into one tick of a recorded session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputFrame {
+    pub tick: u32,
+    /// Inputs applied to each car, AI drivers included
+    pub inputs: Vec<(PlayerId, PlayerInputData)>,
+    /// Cars that joined since the previous tick
+    #[serde(default)]
+    pub joined: Vec<(PlayerId, CarConfigId)>,
+    /// Cars that left since the previous tick
+    #[serde(default)]
+    pub left: Vec<PlayerId>,
+    /// `GameSession::state_hash` after the tick, every `STATE_HASH_INTERVAL_TICKS`
+    #[serde(default)]
+    pub state_hash: Option<u64>,
+}
+
 /// Replay file header
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReplayHeader {
     pub version: u32,
     pub metadata: ReplayMetadata,
     pub frame_count: u32,
+    /// Race-director events recorded during the session
+    #[serde(default)]
+    pub events: Vec<RaceEvent>,
+    /// Session state the input frames start from; version 1 files record telemetry instead
+    #[serde(default)]
+    pub initial_state: Option<Box<SimulationState>>,
+}
+
+/// A re-simulated replay produced a different state than the recorded session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplayDivergence {
+    pub tick: u32,
+    pub expected_hash: u64,
+    pub actual_hash: u64,
+}
+
+impl std::fmt::Display for ReplayDivergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "state diverged at tick {}: expected hash {:016x}, got {:016x}",
+            self.tick, self.expected_hash, self.actual_hash
+        )
+    }
+}
+
+impl std::error::Error for ReplayDivergence {}
+
+/// File format of a replay export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayExportFormat {
+    /// One row per car per sample, cars one after another
+    Csv,
+    /// `ReplayExport` as a single document
+    Json,
+}
+
+impl ReplayExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ReplayExportFormat::Csv => "csv",
+            ReplayExportFormat::Json => "json",
+        }
+    }
+}
+
+impl std::str::FromStr for ReplayExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(ReplayExportFormat::Csv),
+            "json" => Ok(ReplayExportFormat::Json),
+            _ => Err(format!("unknown export format {:?} (expected csv or json)", s)),
+        }
+    }
+}
+
+/// A replay as per-car time series, for analysis outside the game
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayExport {
+    pub session_id: SessionId,
+    pub track_name: String,
+    pub tick_rate: u16,
+    /// Ticks between samples
+    pub interval_ticks: u32,
+    pub cars: Vec<ExportedCar>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportedCar {
+    pub player_id: PlayerId,
+    pub player_name: String,
+    pub car_config_id: CarConfigId,
+    pub finish_position: Option<u8>,
+    /// Laps completed, from the replay's race events
+    pub laps: Vec<ExportedLap>,
+    pub samples: Vec<ExportedSample>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ExportedLap {
+    pub lap: u16,
+    pub lap_time_ms: u32,
+}
+
+/// One car at one recorded tick
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ExportedSample {
+    pub tick: u32,
+    /// Seconds since the first recorded tick
+    pub time_s: f32,
+    pub pos_x: f32,
+    pub pos_y: f32,
+    pub pos_z: f32,
+    pub speed_mps: f32,
+    pub throttle: f32,
+    pub brake: f32,
+    pub steering: f32,
+    pub gear: i8,
+    pub engine_rpm: f32,
+    pub current_lap: u16,
+    pub track_progress: f32,
+    pub current_lap_time_ms: u32,
+    pub last_lap_time_ms: Option<u32>,
+    pub best_lap_time_ms: Option<u32>,
+    pub is_on_track: bool,
+}
+
+const EXPORT_CSV_HEADER: &str = "player_id,player_name,car_config_id,tick,time_s,pos_x,pos_y,pos_z,speed_mps,\
+throttle,brake,steering,gear,engine_rpm,current_lap,track_progress,current_lap_time_ms,last_lap_time_ms,\
+best_lap_time_ms,is_on_track";
+
+impl ReplayExport {
+    /// The export as a CSV table or JSON document
+    pub fn to_bytes(&self, format: ReplayExportFormat) -> Result<Vec<u8>, std::io::Error> {
+        match format {
+            ReplayExportFormat::Json => {
+                serde_json::to_vec(self).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+            }
+            ReplayExportFormat::Csv => {
+                use std::fmt::Write;
+
+                let optional = |value: Option<u32>| value.map(|v| v.to_string()).unwrap_or_default();
+                let mut csv = String::new();
+                writeln!(csv, "{}", EXPORT_CSV_HEADER).unwrap();
+                for car in &self.cars {
+                    let name = csv_field(&car.player_name);
+                    for s in &car.samples {
+                        writeln!(
+                            csv,
+                            "{},{},{},{},{:.4},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                            car.player_id,
+                            name,
+                            car.car_config_id,
+                            s.tick,
+                            s.time_s,
+                            s.pos_x,
+                            s.pos_y,
+                            s.pos_z,
+                            s.speed_mps,
+                            s.throttle,
+                            s.brake,
+                            s.steering,
+                            s.gear,
+                            s.engine_rpm,
+                            s.current_lap,
+                            s.track_progress,
+                            s.current_lap_time_ms,
+                            optional(s.last_lap_time_ms),
+                            optional(s.best_lap_time_ms),
+                            s.is_on_track,
+                        )
+                        .unwrap();
+                    }
+                }
+                Ok(csv.into_bytes())
+            }
+        }
+    }
+}
+
+/// Quote a CSV field when it holds a separator, quote or line break
+fn csv_field(value: &str) -> std::borrow::Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\"")).into()
+    } else {
+        value.into()
+    }
 }
 
 /// Manages replay recording and playback
@@ -51,13 +333,20 @@ pub struct ReplayManager {
 
     /// Currently recording sessions (session_id -> ReplayRecorder)
     active_recordings: Arc<RwLock<HashMap<SessionId, ReplayRecorder>>>,
+
+    /// Set when saved replays must not carry real player identities
+    pseudonyms: Option<Pseudonymizer>,
 }
 
 /// Records a single session's replay
 pub struct ReplayRecorder {
     session_id: SessionId,
     metadata: ReplayMetadata,
-    frames: Vec<ReplayFrame>,
+    initial_state: SimulationState,
+    frames: Vec<InputFrame>,
+    events: Vec<RaceEvent>,
+    /// Cars in the session after the last recorded tick
+    cars: HashSet<PlayerId>,
 }
 
 impl ReplayManager {
@@ -65,35 +354,86 @@ impl ReplayManager {
         Self {
             replay_dir,
             active_recordings: Arc::new(RwLock::new(HashMap::new())),
+            pseudonyms: None,
         }
     }
 
-    /// Start recording a session
-    pub async fn start_recording(&self, metadata: ReplayMetadata) {
+    /// Save replays with player IDs and names replaced by pseudonyms
+    pub fn set_pseudonyms(&mut self, pseudonyms: Option<Pseudonymizer>) {
+        self.pseudonyms = pseudonyms;
+    }
+
+    /// Start recording a session from its state at the current tick
+    pub async fn start_recording(&self, metadata: ReplayMetadata, initial_state: SimulationState) {
         let session_id = metadata.session_id;
 
         let recorder = ReplayRecorder {
             session_id,
             metadata,
+            cars: initial_state.session.participants.keys().copied().collect(),
+            initial_state,
             frames: Vec::new(),
+            events: Vec::new(),
         };
 
         self.active_recordings.write().await.insert(session_id, recorder);
         info!("Started recording replay for session {}", session_id);
     }
 
-    /// Record a frame for a session
-    pub async fn record_frame(&self, session_id: SessionId, tick: u32, telemetry: Telemetry) {
+    /// Record the inputs a session applied on a tick.
+    ///
+    /// `cars` are the session's cars after the tick; cars that joined or left
+    /// since the previous tick are stored with the frame.
+    pub async fn record_tick(
+        &self,
+        session_id: SessionId,
+        tick: u32,
+        inputs: Vec<(PlayerId, PlayerInputData)>,
+        cars: Vec<(PlayerId, CarConfigId)>,
+        state_hash: Option<u64>,
+    ) {
         if let Some(recorder) = self.active_recordings.write().await.get_mut(&session_id) {
-            recorder.record_frame(tick, telemetry);
+            recorder.record_tick(tick, inputs, cars, state_hash);
+        }
+    }
+
+    /// Hand `feed` whatever it has not read yet of the session's recording in progress.
+    ///
+    /// A feed following an earlier recording of the session starts over on the
+    /// new one; once the recording stops the feed plays out what it already has.
+    pub async fn follow(&self, session_id: SessionId, feed: &mut DelayedFeed) {
+        let recordings = self.active_recordings.read().await;
+        let Some(recorder) = recordings.get(&session_id) else {
+            return;
+        };
+
+        let started_at = recorder.initial_state.session.current_tick;
+        if feed.started_at != Some(started_at) {
+            feed.restart(started_at, recorder.initial_state.clone());
         }
+        feed.queued.extend(recorder.frames[feed.read..].iter().cloned());
+        feed.read = recorder.frames.len();
     }
 
-    /// Stop recording and save replay to disk
-    pub async fn stop_recording(&self, session_id: SessionId) -> Result<PathBuf, std::io::Error> {
+    /// Record race events for a session
+    pub async fn record_events(&self, session_id: SessionId, events: &[RaceEvent]) {
+        if let Some(recorder) = self.active_recordings.write().await.get_mut(&session_id) {
+            recorder.events.extend_from_slice(events);
+        }
+    }
+
+    /// Stop recording, note where each car finished and save the replay to disk
+    pub async fn stop_recording(
+        &self,
+        session_id: SessionId,
+        finish_positions: &HashMap<PlayerId, u8>,
+    ) -> Result<PathBuf, std::io::Error> {
         let recorder = self.active_recordings.write().await.remove(&session_id);
 
-        if let Some(recorder) = recorder {
+        if let Some(mut recorder) = recorder {
+            for participant in &mut recorder.metadata.participants {
+                participant.finish_position = finish_positions.get(&participant.player_id).copied();
+            }
             let replay_path = self.save_replay(recorder).await?;
             info!("Saved replay for session {} to {:?}", session_id, replay_path);
             Ok(replay_path)
@@ -107,7 +447,11 @@ impl ReplayManager {
     }
 
     /// Save replay to disk
-    async fn save_replay(&self, recorder: ReplayRecorder) -> Result<PathBuf, std::io::Error> {
+    async fn save_replay(&self, mut recorder: ReplayRecorder) -> Result<PathBuf, std::io::Error> {
+        if let Some(pseudonyms) = &self.pseudonyms {
+            recorder.anonymize(pseudonyms)?;
+        }
+
         // Create replay directory if it doesn't exist
         fs::create_dir_all(&self.replay_dir).await?;
 
@@ -125,12 +469,24 @@ impl ReplayManager {
 
         // Create replay header
         let mut metadata = recorder.metadata;
+        metadata.format_version = REPLAY_FORMAT_VERSION;
         metadata.duration_ticks = recorder.frames.len() as u32;
+        let first_tick = recorder.initial_state.session.current_tick;
+        let last_tick = recorder.frames.last().map_or(first_tick, |frame| frame.tick);
+        metadata.highlights = extract_highlights(
+            &recorder.events,
+            first_tick,
+            last_tick,
+            recorder.initial_state.session.lap_limit,
+            metadata.tick_rate,
+        );
 
         let header = ReplayHeader {
-            version: 1,
+            version: REPLAY_FORMAT_VERSION,
             metadata,
             frame_count: recorder.frames.len() as u32,
+            events: recorder.events,
+            initial_state: Some(Box::new(recorder.initial_state)),
         };
 
         // Write to file
@@ -160,44 +516,30 @@ impl ReplayManager {
 
     /// Load a replay from disk
     pub async fn load_replay(&self, replay_path: PathBuf) -> Result<ReplayPlayer, std::io::Error> {
-        use tokio::io::{AsyncReadExt, BufReader};
-
-        let file = File::open(&replay_path).await?;
-        let mut reader = BufReader::new(file);
-
-        // Read header
-        let mut header_len_bytes = [0u8; 4];
-        reader.read_exact(&mut header_len_bytes).await?;
-        let header_len = u32::from_le_bytes(header_len_bytes) as usize;
-
-        let mut header_bytes = vec![0u8; header_len];
-        reader.read_exact(&mut header_bytes).await?;
-
-        let header: ReplayHeader = rmp_serde::from_slice(&header_bytes)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
-
-        // Read frames
-        let mut frames = Vec::with_capacity(header.frame_count as usize);
-        for _ in 0..header.frame_count {
-            let mut frame_len_bytes = [0u8; 4];
-            reader.read_exact(&mut frame_len_bytes).await?;
-            let frame_len = u32::from_le_bytes(frame_len_bytes) as usize;
-
-            let mut frame_bytes = vec![0u8; frame_len];
-            reader.read_exact(&mut frame_bytes).await?;
-
-            let frame: ReplayFrame = rmp_serde::from_slice(&frame_bytes)
-                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        ReplayPlayer::open(replay_path).await
+    }
 
-            frames.push(frame);
+    /// Path of the latest replay recorded for `session_id`
+    pub async fn find_replay(&self, session_id: SessionId) -> Result<PathBuf, std::io::Error> {
+        let prefix = format!("replay_{}_", session_id);
+        let mut latest: Option<PathBuf> = None;
+
+        if self.replay_dir.exists() {
+            let mut entries = fs::read_dir(&self.replay_dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                let matches = path
+                    .file_name()
+                    .and_then(|s| s.to_str())
+                    .is_some_and(|name| name.starts_with(&prefix) && name.ends_with(".bin"));
+                if matches && latest.as_ref().is_none_or(|l| path > *l) {
+                    latest = Some(path);
+                }
+            }
         }
 
-        info!("Loaded replay from {:?} ({} frames)", replay_path, frames.len());
-
-        Ok(ReplayPlayer {
-            metadata: header.metadata,
-            frames,
-            current_frame: 0,
+        latest.ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, format!("No replay for session {}", session_id))
         })
     }
 
@@ -228,8 +570,43 @@ impl ReplayManager {
         Ok(replays)
     }
 
+    /// Write a replay file's per-car time series to `output`, one sample every
+    /// `interval_ticks`.
+    ///
+    /// Input replays are re-simulated on `track_config` and `car_configs`;
+    /// telemetry replays need no track.
+    pub async fn export_replay(
+        &self,
+        replay_path: PathBuf,
+        track_config: Option<&TrackConfig>,
+        car_configs: &HashMap<CarConfigId, CarConfig>,
+        output: &std::path::Path,
+        format: ReplayExportFormat,
+        interval_ticks: u32,
+    ) -> Result<ReplayExport, std::io::Error> {
+        let mut player = self.load_replay(replay_path).await?;
+        if player.is_input_replay() {
+            let Some(track_config) = track_config else {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("Track {} of the replay is not loaded", player.metadata().track_name),
+                ));
+            };
+            player.prepare(track_config, car_configs);
+        }
+
+        let export = player.export(interval_ticks);
+        if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(output, export.to_bytes(format)?).await?;
+        info!("Exported {} car(s) of session {} to {:?}", export.cars.len(), export.session_id, output);
+
+        Ok(export)
+    }
+
     /// Read just the metadata from a replay file
-    async fn read_replay_metadata(&self, path: &PathBuf) -> Result<ReplayMetadata, std::io::Error> {
+    pub async fn read_replay_metadata(&self, path: &PathBuf) -> Result<ReplayMetadata, std::io::Error> {
         use tokio::io::{AsyncReadExt, BufReader};
 
         let file = File::open(path).await?;
@@ -243,16 +620,58 @@ impl ReplayManager {
         let mut header_bytes = vec![0u8; header_len];
         reader.read_exact(&mut header_bytes).await?;
 
-        let header: ReplayHeader = rmp_serde::from_slice(&header_bytes)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let header = decode_header(&header_bytes)?;
 
         Ok(header.metadata)
     }
 }
 
+
 impl ReplayRecorder {
-    pub fn record_frame(&mut self, tick: u32, telemetry: Telemetry) {
-        self.frames.push(ReplayFrame { tick, telemetry });
+    pub fn record_tick(
+        &mut self,
+        tick: u32,
+        mut inputs: Vec<(PlayerId, PlayerInputData)>,
+        cars: Vec<(PlayerId, CarConfigId)>,
+        state_hash: Option<u64>,
+    ) {
+        let now: HashSet<PlayerId> = cars.iter().map(|(id, _)| *id).collect();
+        let mut joined: Vec<(PlayerId, CarConfigId)> = cars.into_iter().filter(|(id, _)| !self.cars.contains(id)).collect();
+        let mut left: Vec<PlayerId> = self.cars.difference(&now).copied().collect();
+        self.cars = now;
+
+        // Same frame, same bytes, whatever order the session's maps are in
+        inputs.sort_by_key(|(id, _)| *id);
+        joined.sort_by_key(|(id, _)| *id);
+        left.sort();
+
+        self.frames.push(InputFrame { tick, inputs, joined, left, state_hash });
+    }
+
+    /// Replace every player ID and name in the recording with its pseudonym
+    fn anonymize(&mut self, pseudonyms: &Pseudonymizer) -> Result<(), std::io::Error> {
+        for participant in &mut self.metadata.participants {
+            participant.player_name = pseudonyms.player_name(participant.player_id);
+            participant.player_id = pseudonyms.player_id(participant.player_id);
+        }
+        self.initial_state = pseudonyms
+            .simulation_state(&self.initial_state)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        for frame in &mut self.frames {
+            let player_ids = frame
+                .inputs
+                .iter_mut()
+                .map(|(id, _)| id)
+                .chain(frame.joined.iter_mut().map(|(id, _)| id))
+                .chain(frame.left.iter_mut());
+            for player_id in player_ids {
+                *player_id = pseudonyms.player_id(*player_id);
+            }
+        }
+        for event in &mut self.events {
+            *event = pseudonyms.race_event(event);
+        }
+        Ok(())
     }
 
     pub fn get_frame_count(&self) -> usize {
@@ -260,19 +679,254 @@ impl ReplayRecorder {
     }
 }
 
+/// Playback command from the client watching a replay
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplayControl {
+    Play,
+    Pause,
+    /// Jump to the first frame at or after this recorded tick
+    Seek(u32),
+    /// Jump to the start of a highlight, by its index in the metadata
+    JumpToHighlight(usize),
+    /// Playback speed as a multiple of real time
+    SetSpeed(f32),
+}
+
+/// Recorded frames of a replay
+enum ReplayFrames {
+    /// Version 1: telemetry recorded every tick
+    Telemetry(Vec<ReplayFrame>),
+    /// Version 2: inputs, re-simulated as playback moves along
+    Inputs(Box<Resimulation>),
+}
+
+/// Re-simulates an input replay on demand.
+///
+/// Frame 0 is the initial state and frame `n` the state after the `n`th input
+/// frame. Seeking backwards restarts from the nearest checkpoint.
+struct Resimulation {
+    initial: SimulationState,
+    inputs: Vec<InputFrame>,
+    /// Set by `ReplayPlayer::prepare`
+    sim: Option<SimulatedSession>,
+    /// Reported once, playback carries on regardless
+    diverged: bool,
+}
+
+struct SimulatedSession {
+    track_config: TrackConfig,
+    car_configs: HashMap<CarConfigId, CarConfig>,
+    game_session: GameSession,
+    /// Frame the session is at, and its telemetry
+    at: usize,
+    frame: ReplayFrame,
+    /// Simulation states saved every `CHECKPOINT_INTERVAL_FRAMES` frames
+    checkpoints: Vec<(usize, SimulationState)>,
+}
+
+impl Resimulation {
+    fn frame_tick(&self, index: usize) -> u32 {
+        match index {
+            0 => self.initial.session.current_tick,
+            _ => self.inputs[index - 1].tick,
+        }
+    }
+
+    fn prepare(&mut self, track_config: &TrackConfig, car_configs: &HashMap<CarConfigId, CarConfig>) {
+        let game_session =
+            GameSession::from_simulation_state(self.initial.clone(), track_config.clone(), car_configs.clone());
+        self.sim = Some(SimulatedSession {
+            track_config: track_config.clone(),
+            car_configs: car_configs.clone(),
+            frame: ReplayFrame { tick: game_session.session.current_tick, telemetry: game_session.telemetry() },
+            game_session,
+            at: 0,
+            checkpoints: Vec::new(),
+        });
+    }
+
+    /// Simulate up to frame `index`, stopping at the first hash mismatch
+    fn seek(&mut self, index: usize) -> Result<(), ReplayDivergence> {
+        let Some(sim) = self.sim.as_mut() else {
+            return Ok(());
+        };
+
+        if index < sim.at {
+            let (at, state) = sim
+                .checkpoints
+                .iter()
+                .rev()
+                .find(|(at, _)| *at <= index)
+                .map(|(at, state)| (*at, state.clone()))
+                .unwrap_or((0, self.initial.clone()));
+            sim.game_session = GameSession::from_simulation_state(state, sim.track_config.clone(), sim.car_configs.clone());
+            sim.at = at;
+        }
+
+        let mut result = Ok(());
+        while sim.at < index.min(self.inputs.len()) {
+            let frame = &self.inputs[sim.at];
+            let game_session = &mut sim.game_session;
+            apply_input_frame(game_session, frame);
+            // Nobody listens to this session; drop what it queued
+            game_session.take_race_events();
+            game_session.take_broadcast();
+            sim.at += 1;
+
+            if sim.at.is_multiple_of(CHECKPOINT_INTERVAL_FRAMES) && sim.checkpoints.last().is_none_or(|(at, _)| *at < sim.at) {
+                sim.checkpoints.push((sim.at, game_session.simulation_state()));
+            }
+            if let Some(expected_hash) = frame.state_hash {
+                let actual_hash = game_session.state_hash();
+                if actual_hash != expected_hash {
+                    result = Err(ReplayDivergence { tick: frame.tick, expected_hash, actual_hash });
+                    break;
+                }
+            }
+        }
+
+        sim.frame = ReplayFrame { tick: sim.game_session.session.current_tick, telemetry: sim.game_session.telemetry() };
+        result
+    }
+}
+
 /// Plays back a recorded replay
 pub struct ReplayPlayer {
     metadata: ReplayMetadata,
-    frames: Vec<ReplayFrame>,
+    frames: ReplayFrames,
+    events: Vec<RaceEvent>,
     current_frame: usize,
+    /// Playback position in frames, between whole frames while playing slowly
+    position: f64,
+    playing: bool,
+    speed: f32,
 }
 
 impl ReplayPlayer {
+    pub fn new(metadata: ReplayMetadata, frames: Vec<ReplayFrame>, events: Vec<RaceEvent>) -> Self {
+        Self::with_frames(metadata, ReplayFrames::Telemetry(frames), events)
+    }
+
+    /// Player for an input replay; call `prepare` before playing it
+    pub fn from_inputs(
+        metadata: ReplayMetadata,
+        initial_state: SimulationState,
+        inputs: Vec<InputFrame>,
+        events: Vec<RaceEvent>,
+    ) -> Self {
+        let resimulation = Resimulation { initial: initial_state, inputs, sim: None, diverged: false };
+        Self::with_frames(metadata, ReplayFrames::Inputs(Box::new(resimulation)), events)
+    }
+
+    fn with_frames(metadata: ReplayMetadata, frames: ReplayFrames, events: Vec<RaceEvent>) -> Self {
+        Self {
+            metadata,
+            frames,
+            events,
+            current_frame: 0,
+            position: 0.0,
+            playing: false,
+            speed: 1.0,
+        }
+    }
+
+    /// Load a replay file without going through a `ReplayManager`
+    pub async fn open(replay_path: PathBuf) -> Result<Self, std::io::Error> {
+        use tokio::io::{AsyncReadExt, BufReader};
+
+        let file = File::open(&replay_path).await?;
+        let mut reader = BufReader::new(file);
+
+        // Read header
+        let mut header_len_bytes = [0u8; 4];
+        reader.read_exact(&mut header_len_bytes).await?;
+        let header_len = u32::from_le_bytes(header_len_bytes) as usize;
+
+        let mut header_bytes = vec![0u8; header_len];
+        reader.read_exact(&mut header_bytes).await?;
+
+        let header = decode_header(&header_bytes)?;
+
+        // Read frames
+        let mut frames = Vec::with_capacity(header.frame_count as usize);
+        for _ in 0..header.frame_count {
+            let mut frame_len_bytes = [0u8; 4];
+            reader.read_exact(&mut frame_len_bytes).await?;
+            let frame_len = u32::from_le_bytes(frame_len_bytes) as usize;
+
+            let mut frame_bytes = vec![0u8; frame_len];
+            reader.read_exact(&mut frame_bytes).await?;
+            frames.push(frame_bytes);
+        }
+
+        let player = match header.initial_state {
+            Some(initial_state) => Self::from_inputs(header.metadata, *initial_state, decode_frames(&frames)?, header.events),
+            None => Self::new(header.metadata, decode_frames(&frames)?, header.events),
+        };
+        info!("Loaded replay from {:?} ({} frames)", replay_path, frames.len());
+
+        Ok(player)
+    }
+
+    /// Give an input replay the track and cars to re-simulate on; telemetry replays need nothing
+    pub fn prepare(&mut self, track_config: &TrackConfig, car_configs: &HashMap<CarConfigId, CarConfig>) {
+        if let ReplayFrames::Inputs(resimulation) = &mut self.frames {
+            resimulation.prepare(track_config, car_configs);
+            self.sync();
+        }
+    }
+
+    /// Whether the replay re-simulates recorded inputs rather than showing recorded telemetry
+    pub fn is_input_replay(&self) -> bool {
+        matches!(self.frames, ReplayFrames::Inputs(_))
+    }
+
+    /// Re-simulate a prepared input replay from start to end, checking every
+    /// recorded state hash.
+    ///
+    /// Returns the number of hashes checked. Playback restarts afterwards.
+    pub fn verify(&mut self) -> Result<usize, ReplayDivergence> {
+        let ReplayFrames::Inputs(resimulation) = &mut self.frames else {
+            return Ok(0);
+        };
+        let checked = resimulation.inputs.iter().filter(|f| f.state_hash.is_some()).count();
+        resimulation.seek(0)?;
+        let result = resimulation.seek(resimulation.inputs.len());
+        self.reset();
+        result.map(|_| checked)
+    }
+
+    /// Simulate an input replay up to the current frame
+    fn sync(&mut self) {
+        let index = self.current_frame.min(self.frame_count().saturating_sub(1));
+        if let ReplayFrames::Inputs(resimulation) = &mut self.frames {
+            if let Err(divergence) = resimulation.seek(index) {
+                if !resimulation.diverged {
+                    warn!("Replay of session {} no longer matches the recording: {}", self.metadata.session_id, divergence);
+                    resimulation.diverged = true;
+                }
+            }
+        }
+    }
+
+    /// Recorded tick of a frame
+    fn frame_tick(&self, index: usize) -> u32 {
+        match &self.frames {
+            ReplayFrames::Telemetry(frames) => frames[index].tick,
+            ReplayFrames::Inputs(resimulation) => resimulation.frame_tick(index),
+        }
+    }
+
     /// Get replay metadata
     pub fn metadata(&self) -> &ReplayMetadata {
         &self.metadata
     }
 
+    /// Get race events recorded with the replay
+    pub fn events(&self) -> &[RaceEvent] {
+        &self.events
+    }
+
     /// Get current frame
     pub fn current_frame(&self) -> usize {
         self.current_frame
@@ -280,132 +934,925 @@ impl ReplayPlayer {
 
     /// Get total frame count
     pub fn frame_count(&self) -> usize {
-        self.frames.len()
+        match &self.frames {
+            ReplayFrames::Telemetry(frames) => frames.len(),
+            ReplayFrames::Inputs(resimulation) => resimulation.inputs.len() + 1,
+        }
     }
 
     /// Get next frame
     pub fn next_frame(&mut self) -> Option<&ReplayFrame> {
-        if self.current_frame < self.frames.len() {
-            let frame = &self.frames[self.current_frame];
-            self.current_frame += 1;
-            Some(frame)
-        } else {
-            None
+        if self.current_frame >= self.frame_count() {
+            return None;
+        }
+        self.sync();
+        let index = self.current_frame;
+        self.current_frame += 1;
+        match &self.frames {
+            ReplayFrames::Telemetry(frames) => frames.get(index),
+            ReplayFrames::Inputs(resimulation) => resimulation.sim.as_ref().map(|sim| &sim.frame),
         }
     }
 
     /// Seek to a specific frame
     pub fn seek(&mut self, frame: usize) {
-        self.current_frame = frame.min(self.frames.len());
+        self.current_frame = frame.min(self.frame_count());
+        self.position = self.current_frame as f64;
+        self.sync();
+    }
+
+    /// Seek to the first frame recorded at or after `tick`, or the last frame
+    pub fn seek_to_tick(&mut self, tick: u32) {
+        let count = self.frame_count();
+        // Frames are in tick order
+        let (mut low, mut high) = (0, count);
+        while low < high {
+            let mid = (low + high) / 2;
+            if self.frame_tick(mid) < tick {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        self.seek(low.min(count.saturating_sub(1)));
     }
 
     /// Reset to beginning
     pub fn reset(&mut self) {
-        self.current_frame = 0;
+        self.seek(0);
     }
 
-    /// Get frame at specific index
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Playback speed as a multiple of real time
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// Apply a playback command from the viewer
+    pub fn control(&mut self, control: ReplayControl) {
+        match control {
+            ReplayControl::Play => {
+                // Playing a replay that has run out starts it over
+                if self.current_frame + 1 >= self.frame_count() {
+                    self.reset();
+                }
+                self.playing = true;
+            }
+            ReplayControl::Pause => self.playing = false,
+            ReplayControl::Seek(tick) => self.seek_to_tick(tick),
+            ReplayControl::JumpToHighlight(index) => {
+                if let Some(start_tick) = self.metadata.highlights.get(index).map(|h| h.start_tick) {
+                    self.seek_to_tick(start_tick);
+                }
+            }
+            ReplayControl::SetSpeed(speed) => {
+                self.speed = speed.clamp(MIN_PLAYBACK_SPEED, MAX_PLAYBACK_SPEED);
+            }
+        }
+    }
+
+    /// Move playback on by `dt_s` of real time, pausing on the last frame.
+    ///
+    /// Returns the recorded events passed on the way.
+    pub fn advance(&mut self, dt_s: f32) -> Vec<RaceEvent> {
+        let count = self.frame_count();
+        if !self.playing || count == 0 {
+            return Vec::new();
+        }
+
+        let from_tick = self.frame_tick(self.current_frame.min(count - 1));
+        let last = (count - 1) as f64;
+        self.position += dt_s as f64 * self.speed as f64 * self.metadata.tick_rate as f64;
+        if self.position >= last {
+            self.position = last;
+            self.playing = false;
+        }
+        self.current_frame = self.position as usize;
+        self.sync();
+        let to_tick = self.frame_tick(self.current_frame);
+
+        self.events
+            .iter()
+            .filter(|e| e.server_tick > from_tick && e.server_tick <= to_tick)
+            .cloned()
+            .collect()
+    }
+
+    /// Frame shown at the current playback position; None for an input replay
+    /// that has not been prepared
+    pub fn frame(&self) -> Option<&ReplayFrame> {
+        match &self.frames {
+            ReplayFrames::Telemetry(frames) => frames.get(self.current_frame.min(frames.len().saturating_sub(1))),
+            ReplayFrames::Inputs(resimulation) => resimulation.sim.as_ref().map(|sim| &sim.frame),
+        }
+    }
+
+    /// Recorded ticks of the first and last frame
+    pub fn tick_range(&self) -> Option<(u32, u32)> {
+        let count = self.frame_count();
+        (count > 0).then(|| (self.frame_tick(0), self.frame_tick(count - 1)))
+    }
+
+    /// Get frame at specific index; input replays only hold the frame being shown
     pub fn get_frame(&self, index: usize) -> Option<&ReplayFrame> {
-        self.frames.get(index)
+        match &self.frames {
+            ReplayFrames::Telemetry(frames) => frames.get(index),
+            ReplayFrames::Inputs(resimulation) => resimulation.sim.as_ref().filter(|sim| sim.at == index).map(|sim| &sim.frame),
+        }
     }
 
     /// Check if replay has ended
     pub fn is_finished(&self) -> bool {
-        self.current_frame >= self.frames.len()
+        self.current_frame >= self.frame_count()
+    }
+
+    /// Per-car time series of the whole replay, one sample every `interval_ticks`.
+    ///
+    /// An input replay must be prepared first. Playback restarts afterwards.
+    pub fn export(&mut self, interval_ticks: u32) -> ReplayExport {
+        let interval_ticks = interval_ticks.max(1);
+        let first_tick = self.tick_range().map_or(0, |(first, _)| first);
+        let tick_rate = self.metadata.tick_rate.max(1) as f32;
+
+        // Participants first, in recorded order, then cars that only show up in the frames
+        let mut cars: Vec<ExportedCar> = self
+            .metadata
+            .participants
+            .iter()
+            .map(|p| ExportedCar {
+                player_id: p.player_id,
+                player_name: p.player_name.clone(),
+                car_config_id: p.car_config_id,
+                finish_position: p.finish_position,
+                laps: Vec::new(),
+                samples: Vec::new(),
+            })
+            .collect();
+        let mut next_tick = first_tick;
+        for index in 0..self.frame_count() {
+            if self.frame_tick(index) < next_tick {
+                continue;
+            }
+            self.seek(index);
+            let Some(frame) = self.frame() else {
+                break;
+            };
+            next_tick = frame.tick.saturating_add(interval_ticks);
+            let time_s = frame.tick.saturating_sub(first_tick) as f32 / tick_rate;
+            for car in &frame.telemetry.car_states {
+                let position = match cars.iter().position(|c| c.player_id == car.player_id) {
+                    Some(position) => position,
+                    None => {
+                        cars.push(ExportedCar {
+                            player_id: car.player_id,
+                            player_name: String::new(),
+                            car_config_id: CarConfigId::nil(),
+                            finish_position: None,
+                            laps: Vec::new(),
+                            samples: Vec::new(),
+                        });
+                        cars.len() - 1
+                    }
+                };
+                cars[position].finish_position = cars[position].finish_position.or(car.finish_position);
+                cars[position].samples.push(ExportedSample {
+                    tick: frame.tick,
+                    time_s,
+                    pos_x: car.pos_x,
+                    pos_y: car.pos_y,
+                    pos_z: car.pos_z,
+                    speed_mps: car.speed_mps,
+                    throttle: car.throttle,
+                    brake: car.brake,
+                    steering: car.steering,
+                    gear: car.gear,
+                    engine_rpm: car.engine_rpm,
+                    current_lap: car.current_lap,
+                    track_progress: car.track_progress,
+                    current_lap_time_ms: car.current_lap_time_ms,
+                    last_lap_time_ms: car.last_lap_time_ms,
+                    best_lap_time_ms: car.best_lap_time_ms,
+                    is_on_track: car.is_on_track,
+                });
+            }
+        }
+        self.reset();
+
+        for event in self.events.iter().filter(|e| e.kind == RaceEventKind::LapCompleted) {
+            let (Some(car), Some(lap_time_ms)) =
+                (cars.iter_mut().find(|c| c.player_id == event.player_id), event.lap_time_ms)
+            else {
+                continue;
+            };
+            car.laps.push(ExportedLap { lap: event.lap, lap_time_ms });
+        }
+
+        ReplayExport {
+            session_id: self.metadata.session_id,
+            track_name: self.metadata.track_name.clone(),
+            tick_rate: self.metadata.tick_rate,
+            interval_ticks,
+            cars,
+        }
     }
 }
 
+/// A live session's broadcasts, re-simulated from its recording in progress
+/// `delay_ticks` behind the race.
+///
+/// Spectators who join before the race starts wait for the recording to begin.
+/// Joining mid-race simulates from the start at up to `MAX_CATCH_UP_TICKS` per
+/// server tick, and nothing is sent until the feed has caught up.
+pub struct DelayedFeed {
+    delay_ticks: u32,
+    /// Recipients while there is no re-simulated session to hold them
+    viewers: SessionBroadcaster,
+    /// First tick of the recording being followed
+    started_at: Option<u32>,
+    /// Initial state of that recording, until the session is built from it
+    initial: Option<SimulationState>,
+    sim: Option<GameSession>,
+    /// Frames read from the recording and not simulated yet
+    queued: VecDeque<InputFrame>,
+    read: usize,
+}
+
+impl DelayedFeed {
+    /// Feed sending to `viewers`' recipients, with its pseudonyms and rates
+    pub fn new(delay_ticks: u32, viewers: SessionBroadcaster) -> Self {
+        Self {
+            delay_ticks,
+            viewers,
+            started_at: None,
+            initial: None,
+            sim: None,
+            queued: VecDeque::new(),
+            read: 0,
+        }
+    }
+
+    pub fn delay_ticks(&self) -> u32 {
+        self.delay_ticks
+    }
+
+    /// Recipients of the feed
+    pub fn broadcaster(&self) -> &SessionBroadcaster {
+        match &self.sim {
+            Some(sim) => &sim.broadcaster,
+            None => &self.viewers,
+        }
+    }
+
+    pub fn broadcaster_mut(&mut self) -> &mut SessionBroadcaster {
+        match &mut self.sim {
+            Some(sim) => &mut sim.broadcaster,
+            None => &mut self.viewers,
+        }
+    }
+
+    /// Tick of the race being shown, once the recording has started
+    pub fn current_tick(&self) -> Option<u32> {
+        self.sim.as_ref().map(|sim| sim.session.current_tick)
+    }
+
+    /// Simulate the recorded ticks that are at least `delay_ticks` old at
+    /// `live_tick`, returning the broadcasts they produce
+    pub fn advance(
+        &mut self,
+        live_tick: u32,
+        track_config: &TrackConfig,
+        car_configs: &HashMap<CarConfigId, CarConfig>,
+    ) -> Vec<BroadcastFrame> {
+        if let Some(initial) = self.initial.take() {
+            let mut sim = GameSession::from_simulation_state(initial, track_config.clone(), car_configs.clone());
+            sim.broadcaster = std::mem::take(&mut self.viewers);
+            self.sim = Some(sim);
+        }
+        let Some(sim) = self.sim.as_mut() else {
+            return Vec::new();
+        };
+
+        let target = live_tick.saturating_sub(self.delay_ticks);
+        let due = self.queued.iter().take_while(|frame| frame.tick <= target).count();
+        let catching_up = due > MAX_CATCH_UP_TICKS;
+        let mut broadcasts = Vec::new();
+        for frame in self.queued.drain(..due.min(MAX_CATCH_UP_TICKS)) {
+            apply_input_frame(sim, &frame);
+            sim.take_race_events();
+            let frames = sim.take_broadcast();
+            if !catching_up {
+                broadcasts.extend(frames);
+            }
+        }
+        broadcasts
+    }
+
+    /// Start over on a new recording, keeping the recipients
+    fn restart(&mut self, started_at: u32, initial: SimulationState) {
+        if let Some(sim) = self.sim.take() {
+            self.viewers = sim.broadcaster;
+        }
+        self.started_at = Some(started_at);
+        self.initial = Some(initial);
+        self.queued.clear();
+        self.read = 0;
+    }
+}
+
+/// Run one recorded tick on a re-simulated session
+/// Just the version of a replay header, readable whatever else the header holds
+#[derive(Deserialize)]
+struct HeaderVersion {
+    version: u32,
+}
+
+/// Read a replay header, refusing versions this build does not know and
+/// migrating older ones to the current format
+fn decode_header(bytes: &[u8]) -> Result<ReplayHeader, std::io::Error> {
+    let HeaderVersion { version } =
+        rmp_serde::from_slice(bytes).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    if !(MIN_REPLAY_FORMAT_VERSION..=REPLAY_FORMAT_VERSION).contains(&version) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "unsupported replay format version {} (this server reads versions {} to {})",
+                version, MIN_REPLAY_FORMAT_VERSION, REPLAY_FORMAT_VERSION
+            ),
+        ));
+    }
+
+    let mut header: ReplayHeader =
+        rmp_serde::from_slice(bytes).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    migrate_header(&mut header);
+    Ok(header)
+}
+
+/// Upgrade a header read from an older file, one version at a time
+fn migrate_header(header: &mut ReplayHeader) {
+    if header.version < 3 && header.metadata.highlights.is_empty() {
+        // Highlights were only stored by some version 2 files; find them again.
+        // Version 1 files have no initial state, so their first tick and lap
+        // limit come from the events
+        let (first_tick, lap_limit) = match &header.initial_state {
+            Some(initial) => (initial.session.current_tick, initial.session.lap_limit),
+            None => {
+                let first_tick = header.events.first().map_or(0, |e| e.server_tick);
+                // The winner finishes as their lap count passes the limit
+                let lap_limit = header
+                    .events
+                    .iter()
+                    .find(|e| e.kind == RaceEventKind::Finished)
+                    .map_or(0, |winner| winner.lap.saturating_sub(1));
+                (first_tick, lap_limit.min(u8::MAX as u16) as u8)
+            }
+        };
+        let last_tick = first_tick.saturating_add(header.frame_count);
+        header.metadata.highlights =
+            extract_highlights(&header.events, first_tick, last_tick, lap_limit, header.metadata.tick_rate);
+    }
+    header.metadata.format_version = header.version;
+}
+
+fn apply_input_frame(game_session: &mut GameSession, frame: &InputFrame) {
+    for player_id in &frame.left {
+        game_session.remove_player(player_id);
+    }
+    for (player_id, car_config_id) in &frame.joined {
+        game_session.add_player(*player_id, *car_config_id);
+    }
+    game_session.tick(&frame.inputs.iter().copied().collect());
+}
+
+fn decode_frames<T: serde::de::DeserializeOwned>(frames: &[Vec<u8>]) -> Result<Vec<T>, std::io::Error> {
+    frames
+        .iter()
+        .map(|bytes| rmp_serde::from_slice(bytes).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e)))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::data::SessionState;
+    use crate::network::ServerMessage;
     use tempfile::TempDir;
-    use uuid::Uuid;
+
+    /// Record `ticks` ticks of AI free practice; returns the track and cars to play it back on
+    async fn record_ai_session(
+        manager: &ReplayManager,
+        session_id: SessionId,
+        ticks: u32,
+    ) -> (TrackConfig, HashMap<CarConfigId, CarConfig>) {
+        use crate::ai_driver::generate_default_ai_profiles;
+
+        let track = TrackConfig::default();
+        let car = CarConfig::default();
+        let car_configs = HashMap::from([(car.id, car)]);
+        let mut session = RaceSession::new(PlayerId::new_v4(), track.id, SessionKind::Multiplayer, 8, 4, 3);
+        session.id = session_id;
+        let mut game_session = GameSession::with_ai_profiles(session, track.clone(), car_configs.clone(), generate_default_ai_profiles(4));
+        game_session.spawn_ai_drivers();
+        game_session.set_game_mode(GameMode::FreePractice);
+
+        let participants = game_session
+            .session
+            .participants
+            .values()
+            .map(|state| ReplayParticipant {
+                player_id: state.player_id,
+                player_name: format!("Driver {}", state.grid_position),
+                car_config_id: state.car_config_id,
+                finish_position: None,
+            })
+            .collect();
+        manager.start_recording(ReplayMetadata {
+            format_version: REPLAY_FORMAT_VERSION,
+            session_id,
+            track_config_id: track.id,
+            track_name: track.name.clone(),
+            recorded_at: 123456789,
+            duration_ticks: ticks,
+            tick_rate: 240,
+            participants,
+            highlights: Vec::new(),
+        }, game_session.simulation_state()).await;
+
+        for _ in 0..ticks {
+            let inputs: HashMap<PlayerId, PlayerInputData> = game_session
+                .session
+                .participants
+                .keys()
+                .map(|id| (*id, game_session.generate_ai_input(id)))
+                .collect();
+            game_session.tick(&inputs);
+            let tick = game_session.session.current_tick;
+            let cars = game_session.session.participants.values().map(|s| (s.player_id, s.car_config_id)).collect();
+            let state_hash = tick.is_multiple_of(STATE_HASH_INTERVAL_TICKS).then(|| game_session.state_hash());
+            manager.record_tick(session_id, tick, inputs.into_iter().collect(), cars, state_hash).await;
+        }
+
+        (track, car_configs)
+    }
 
     #[tokio::test]
     async fn test_replay_recording() {
         let temp_dir = TempDir::new().unwrap();
         let manager = ReplayManager::new(temp_dir.path().to_path_buf());
 
-        let session_id = Uuid::new_v4();
-        let metadata = ReplayMetadata {
+        let session_id = SessionId::new_v4();
+        record_ai_session(&manager, session_id, 10).await;
+
+        let event = RaceEvent {
             session_id,
-            track_config_id: Uuid::new_v4(),
-            track_name: "Test Track".to_string(),
-            recorded_at: 123456789,
-            duration_ticks: 0,
-            tick_rate: 240,
-            participants: vec![],
+            server_tick: 5,
+            kind: RaceEventKind::OffTrack,
+            player_id: PlayerId::new_v4(),
+            other_player_id: None,
+            lap: 1,
+            position: None,
+            lap_time_ms: None,
+            penalty_ms: None,
+            message: None,
         };
+        manager.record_events(session_id, std::slice::from_ref(&event)).await;
 
-        manager.start_recording(metadata).await;
+        // Stop and save
+        let replay_path = manager.stop_recording(session_id, &HashMap::new()).await.unwrap();
+        assert!(replay_path.exists());
 
-        // Record some frames
-        for tick in 0..10 {
-            let telemetry = Telemetry {
-                server_tick: tick,
-                session_state: SessionState::Racing,
-                game_mode: GameMode::FreePractice,
-                countdown_ms: None,
-                car_states: vec![],
-            };
+        // Load and verify: the initial state plus one frame per tick
+        let player = manager.load_replay(replay_path).await.unwrap();
+        assert!(player.is_input_replay());
+        assert_eq!(player.metadata().format_version, REPLAY_FORMAT_VERSION);
+        assert_eq!(player.frame_count(), 11);
+        assert_eq!(player.events(), &[event]);
+    }
 
-            manager.record_frame(session_id, tick, telemetry).await;
+    #[tokio::test]
+    async fn test_find_replay_picks_latest_recording() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = ReplayManager::new(temp_dir.path().to_path_buf());
+        let session_id = SessionId::new_v4();
+        for name in [
+            format!("replay_{}_1700000000.bin", session_id),
+            format!("replay_{}_1700000500.bin", session_id),
+            format!("replay_{}_1800000000.bin", SessionId::new_v4()),
+        ] {
+            std::fs::write(temp_dir.path().join(name), b"").unwrap();
         }
 
-        // Stop and save
-        let replay_path = manager.stop_recording(session_id).await.unwrap();
-        assert!(replay_path.exists());
+        let path = manager.find_replay(session_id).await.unwrap();
+        assert!(path.ends_with(format!("replay_{}_1700000500.bin", session_id)));
+        let missing = manager.find_replay(SessionId::new_v4()).await.unwrap_err();
+        assert_eq!(missing.kind(), std::io::ErrorKind::NotFound);
+    }
 
-        // Load and verify
-        let player = manager.load_replay(replay_path).await.unwrap();
-        assert_eq!(player.frame_count(), 10);
+    /// Write a replay file the way older and newer servers lay it out
+    fn write_raw_replay(path: &std::path::Path, header: &impl Serialize, frames: &[ReplayFrame]) {
+        let mut bytes = Vec::new();
+        for chunk in std::iter::once(rmp_serde::to_vec_named(header).unwrap())
+            .chain(frames.iter().map(|frame| rmp_serde::to_vec_named(frame).unwrap()))
+        {
+            bytes.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&chunk);
+        }
+        std::fs::write(path, bytes).unwrap();
     }
 
     #[tokio::test]
-    async fn test_replay_playback() {
+    async fn test_replay_format_versions() {
         let temp_dir = TempDir::new().unwrap();
         let manager = ReplayManager::new(temp_dir.path().to_path_buf());
 
-        let session_id = Uuid::new_v4();
-        let metadata = ReplayMetadata {
+        // A version 1 file: recorded telemetry, no format version in the metadata and no highlights
+        #[derive(Serialize)]
+        struct V1Metadata {
+            session_id: SessionId,
+            track_config_id: TrackConfigId,
+            track_name: String,
+            recorded_at: u64,
+            duration_ticks: u32,
+            tick_rate: u16,
+            participants: Vec<ReplayParticipant>,
+        }
+        #[derive(Serialize)]
+        struct V1Header {
+            version: u32,
+            metadata: V1Metadata,
+            frame_count: u32,
+            events: Vec<RaceEvent>,
+        }
+
+        let player = player_with_frames(100..340, vec![]);
+        let (winner, other) = (PlayerId::new_v4(), PlayerId::new_v4());
+        let event = |server_tick, kind, lap| RaceEvent {
+            session_id: player.metadata().session_id,
+            server_tick,
+            kind,
+            player_id: winner,
+            other_player_id: Some(other),
+            lap,
+            position: None,
+            lap_time_ms: None,
+            penalty_ms: None,
+            message: None,
+        };
+        let header = V1Header {
+            version: 1,
+            metadata: V1Metadata {
+                session_id: player.metadata().session_id,
+                track_config_id: player.metadata().track_config_id,
+                track_name: player.metadata().track_name.clone(),
+                recorded_at: 123456789,
+                duration_ticks: 240,
+                tick_rate: 240,
+                participants: vec![],
+            },
+            frame_count: 240,
+            events: vec![
+                event(100, RaceEventKind::Overtake, 1),
+                event(200, RaceEventKind::LapCompleted, 1),
+                event(300, RaceEventKind::LapCompleted, 2),
+                event(300, RaceEventKind::Finished, 3),
+            ],
+        };
+        let frames: Vec<ReplayFrame> = (0..240).filter_map(|i| player.get_frame(i).cloned()).collect();
+        let v1_path = temp_dir.path().join("replay_v1.bin");
+        write_raw_replay(&v1_path, &header, &frames);
+
+        let migrated = manager.read_replay_metadata(&v1_path).await.unwrap();
+        assert_eq!(migrated.format_version, 1);
+        let ranges: Vec<_> = migrated.highlights.iter().map(|h| (h.kind, h.start_tick, h.end_tick)).collect();
+        assert_eq!(ranges, vec![(HighlightKind::Overtake, 100, 340), (HighlightKind::FinalLap, 200, 340)]);
+        let loaded = manager.load_replay(v1_path).await.unwrap();
+        assert!(!loaded.is_input_replay());
+        assert_eq!(loaded.frame_count(), 240);
+
+        // A file from a newer server is refused with its version, and left out of the list
+        let future_path = temp_dir.path().join("replay_future.bin");
+        #[derive(Serialize)]
+        struct FutureHeader {
+            version: u32,
+            layout: Vec<String>,
+        }
+        let header = FutureHeader { version: REPLAY_FORMAT_VERSION + 1, layout: vec!["chunked".to_string()] };
+        write_raw_replay(&future_path, &header, &[]);
+        let refused = manager.read_replay_metadata(&future_path).await.unwrap_err();
+        assert_eq!(refused.kind(), std::io::ErrorKind::InvalidData);
+        assert!(refused.to_string().contains(&format!("version {}", REPLAY_FORMAT_VERSION + 1)));
+        assert!(ReplayPlayer::open(future_path).await.is_err());
+
+        let listed = manager.list_replays().await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].format_version, 1);
+    }
+
+    #[tokio::test]
+    async fn test_export_replay() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = ReplayManager::new(temp_dir.path().to_path_buf());
+        let session_id = SessionId::new_v4();
+        let (track, car_configs) = record_ai_session(&manager, session_id, 100).await;
+        let car = manager.active_recordings.read().await[&session_id].metadata.participants[0].player_id;
+        let lap = RaceEvent {
             session_id,
-            track_config_id: Uuid::new_v4(),
-            track_name: "Test Track".to_string(),
-            recorded_at: 123456789,
-            duration_ticks: 0,
-            tick_rate: 240,
-            participants: vec![],
+            server_tick: 50,
+            kind: RaceEventKind::LapCompleted,
+            player_id: car,
+            other_player_id: None,
+            lap: 1,
+            position: None,
+            lap_time_ms: Some(91_234),
+            penalty_ms: None,
+            message: None,
         };
+        manager.record_events(session_id, &[lap]).await;
+        let replay_path = manager.stop_recording(session_id, &HashMap::new()).await.unwrap();
+
+        // Input replays need their track
+        let csv_path = temp_dir.path().join("export/race.csv");
+        let missing = manager
+            .export_replay(replay_path.clone(), None, &car_configs, &csv_path, ReplayExportFormat::Csv, 10)
+            .await
+            .unwrap_err();
+        assert_eq!(missing.kind(), std::io::ErrorKind::NotFound);
+
+        let export = manager
+            .export_replay(replay_path.clone(), Some(&track), &car_configs, &csv_path, ReplayExportFormat::Csv, 10)
+            .await
+            .unwrap();
+        assert_eq!(export.cars.len(), 4);
+        // The initial state plus one frame per tick, every 10th tick
+        let samples = &export.cars[0].samples;
+        assert_eq!(samples.len(), 11);
+        assert_eq!(samples[1].tick - samples[0].tick, 10);
+        assert!((samples[1].time_s - 10.0 / 240.0).abs() < 1e-6);
+        assert!(samples.last().unwrap().speed_mps > 0.0);
+        assert_eq!(export.cars[0].laps, vec![ExportedLap { lap: 1, lap_time_ms: 91_234 }]);
+
+        let csv = std::fs::read_to_string(&csv_path).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some(EXPORT_CSV_HEADER));
+        assert_eq!(lines.count(), 44);
+        let first_row: Vec<&str> = csv.lines().nth(1).unwrap().split(',').collect();
+        assert_eq!(first_row.len(), EXPORT_CSV_HEADER.split(',').count());
+        assert_eq!(first_row[0], export.cars[0].player_id.to_string());
+
+        let json_path = temp_dir.path().join("race.json");
+        manager
+            .export_replay(replay_path, Some(&track), &car_configs, &json_path, ReplayExportFormat::Json, 10)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&std::fs::read(&json_path).unwrap()).unwrap();
+        assert_eq!(json["cars"].as_array().unwrap().len(), 4);
+        assert_eq!(json["cars"][0]["samples"].as_array().unwrap().len(), 11);
+        assert_eq!(json["cars"][0]["laps"][0]["lap_time_ms"], 91_234);
+    }
 
-        manager.start_recording(metadata).await;
+    #[test]
+    fn test_csv_field_quoting() {
+        assert_eq!(csv_field("Driver 1"), "Driver 1");
+        assert_eq!(csv_field("Smith, J"), "\"Smith, J\"");
+        assert_eq!(csv_field("\"Flash\" Gordon"), "\"\"\"Flash\"\" Gordon\"");
+        assert_eq!("csv".parse::<ReplayExportFormat>(), Ok(ReplayExportFormat::Csv));
+        assert_eq!("JSON".parse::<ReplayExportFormat>(), Ok(ReplayExportFormat::Json));
+        assert!("xml".parse::<ReplayExportFormat>().is_err());
+    }
 
-        // Record frames
-        for tick in 0..5 {
-            let telemetry = Telemetry {
-                server_tick: tick,
-                session_state: SessionState::Racing,
-                game_mode: GameMode::FreePractice,
-                countdown_ms: None,
-                car_states: vec![],
-            };
-            manager.record_frame(session_id, tick, telemetry).await;
-        }
+    #[tokio::test]
+    async fn test_anonymized_replay_hides_identities() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut manager = ReplayManager::new(temp_dir.path().to_path_buf());
+        let pseudonyms = Pseudonymizer::new("league-secret");
+        manager.set_pseudonyms(Some(pseudonyms.clone()));
+
+        let session_id = SessionId::new_v4();
+        let (track, car_configs) = record_ai_session(&manager, session_id, 120).await;
+        let real_ids: HashSet<PlayerId> = manager.active_recordings.read().await[&session_id].cars.clone();
 
-        let replay_path = manager.stop_recording(session_id).await.unwrap();
+        let replay_path = manager.stop_recording(session_id, &HashMap::new()).await.unwrap();
         let mut player = manager.load_replay(replay_path).await.unwrap();
+        let participant = player.metadata().participants[0].clone();
+        assert!(!real_ids.contains(&participant.player_id));
+        assert!(real_ids.iter().any(|id| pseudonyms.player_id(*id) == participant.player_id));
+        assert!(participant.player_name.starts_with("Driver"));
+
+        // Pseudonyms go all the way through the re-simulated session
+        player.prepare(&track, &car_configs);
+        assert_eq!(player.verify(), Ok(2));
+        let car_ids: HashSet<PlayerId> = player.frame().unwrap().telemetry.car_states.iter().map(|c| c.player_id).collect();
+        assert!(car_ids.contains(&participant.player_id));
+        assert!(car_ids.is_disjoint(&real_ids));
+    }
+
+    #[tokio::test]
+    async fn test_replay_playback() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = ReplayManager::new(temp_dir.path().to_path_buf());
+
+        let session_id = SessionId::new_v4();
+        let (track, car_configs) = record_ai_session(&manager, session_id, 5).await;
+
+        let replay_path = manager.stop_recording(session_id, &HashMap::new()).await.unwrap();
+        let mut player = manager.load_replay(replay_path).await.unwrap();
+        player.prepare(&track, &car_configs);
 
         // Playback
-        let mut frame_count = 0;
-        while let Some(_frame) = player.next_frame() {
-            frame_count += 1;
+        let mut ticks = Vec::new();
+        while let Some(frame) = player.next_frame() {
+            ticks.push(frame.tick);
         }
 
-        assert_eq!(frame_count, 5);
+        assert_eq!(ticks, (0..=5).collect::<Vec<_>>());
         assert!(player.is_finished());
 
         // Reset and replay
         player.reset();
         assert_eq!(player.current_frame(), 0);
+        assert_eq!(player.frame().unwrap().tick, 0);
+    }
+
+    #[tokio::test]
+    async fn test_input_replay_detects_divergence() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = ReplayManager::new(temp_dir.path().to_path_buf());
+
+        let session_id = SessionId::new_v4();
+        let (track, car_configs) = record_ai_session(&manager, session_id, 240).await;
+        let replay_path = manager.stop_recording(session_id, &HashMap::new()).await.unwrap();
+
+        let mut player = ReplayPlayer::open(replay_path.clone()).await.unwrap();
+        player.prepare(&track, &car_configs);
+        assert_eq!(player.verify(), Ok(4));
+
+        // Seeking back lands on the same state as playing straight through
+        let positions = |player: &ReplayPlayer| -> Vec<(PlayerId, f32, f32)> {
+            player.frame().unwrap().telemetry.car_states.iter().map(|c| (c.player_id, c.pos_x, c.pos_y)).collect()
+        };
+        player.seek_to_tick(200);
+        let forward = positions(&player);
+        player.seek_to_tick(30);
+        player.seek_to_tick(200);
+        assert_eq!(positions(&player), forward);
+
+        // Floor the throttle of one car for a tick and the next hash no longer matches
+        let mut tampered = ReplayPlayer::open(replay_path).await.unwrap();
+        if let ReplayFrames::Inputs(resimulation) = &mut tampered.frames {
+            resimulation.inputs[99].inputs[0].1.throttle = 1.0;
+            resimulation.inputs[99].inputs[0].1.brake = 0.0;
+        }
+        tampered.prepare(&track, &car_configs);
+        let divergence = tampered.verify().unwrap_err();
+        assert_eq!(divergence.tick, 120);
+        assert_ne!(divergence.expected_hash, divergence.actual_hash);
+    }
+
+    #[tokio::test]
+    async fn test_delayed_feed_follows_recording_in_progress() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = ReplayManager::new(temp_dir.path().to_path_buf());
+
+        let session_id = SessionId::new_v4();
+        let (track, car_configs) = record_ai_session(&manager, session_id, 240).await;
+        let mut feed = DelayedFeed::new(120, SessionBroadcaster::new());
+        feed.broadcaster_mut().bind(PlayerId::new_v4(), ConnectionId::new_v4());
+        manager.follow(session_id, &mut feed).await;
+
+        // Catching up to two seconds behind the live tick sends nothing
+        let mut advances = 0;
+        while feed.current_tick() != Some(120) {
+            let frames = feed.advance(240, &track, &car_configs);
+            advances += 1;
+            if feed.current_tick() != Some(120) {
+                assert!(frames.is_empty());
+            }
+        }
+        assert_eq!(advances, 4);
+
+        // Then it keeps pace with the live session, the delay behind
+        let frames = feed.advance(241, &track, &car_configs);
+        assert_eq!(frames.len(), 1);
+        match &frames[0].messages[0] {
+            ServerMessage::Telemetry(telemetry) => assert_eq!(telemetry.server_tick, 121),
+            other => panic!("expected telemetry, got {:?}", other),
+        }
+        assert!(feed.advance(241, &track, &car_configs).is_empty());
+    }
+
+    #[test]
+    fn test_extract_highlights() {
+        let (leader, other) = (PlayerId::new_v4(), PlayerId::new_v4());
+        let event = |server_tick, kind, lap, other_player_id| RaceEvent {
+            session_id: SessionId::new_v4(),
+            server_tick,
+            kind,
+            player_id: leader,
+            other_player_id,
+            lap,
+            position: None,
+            lap_time_ms: None,
+            penalty_ms: None,
+            message: None,
+        };
+        let events = vec![
+            event(1_500, RaceEventKind::Overtake, 1, Some(other)),
+            event(20_000, RaceEventKind::LapCompleted, 1, None),
+            event(30_000, RaceEventKind::Contact, 2, None),
+            event(40_000, RaceEventKind::LapCompleted, 2, None),
+            event(40_000, RaceEventKind::OffTrack, 3, None),
+            event(60_000, RaceEventKind::Finished, 3, None),
+        ];
+
+        let highlights = extract_highlights(&events, 1_000, 60_240, 3, 240);
+        let ranges: Vec<_> = highlights.iter().map(|h| (h.kind, h.start_tick, h.end_tick)).collect();
+        assert_eq!(ranges, vec![
+            // Clamped to the start of the recording and the last recorded tick
+            (HighlightKind::Overtake, 1_000, 2_220),
+            (HighlightKind::Contact, 28_800, 30_720),
+            (HighlightKind::FinalLap, 40_000, 60_240),
+        ]);
+        assert_eq!(highlights[0].other_player_id, Some(other));
+        assert_eq!(highlights[2].player_id, leader);
+    }
+
+    fn player_with_frames(ticks: std::ops::Range<u32>, events: Vec<RaceEvent>) -> ReplayPlayer {
+        let metadata = ReplayMetadata {
+            format_version: REPLAY_FORMAT_VERSION,
+            session_id: SessionId::new_v4(),
+            track_config_id: TrackConfigId::new_v4(),
+            track_name: "Test Track".to_string(),
+            recorded_at: 123456789,
+            duration_ticks: ticks.len() as u32,
+            tick_rate: 240,
+            participants: vec![],
+            highlights: Vec::new(),
+        };
+        let frames = ticks
+            .map(|tick| ReplayFrame {
+                tick,
+                telemetry: Telemetry {
+                    server_tick: tick,
+                    session_state: SessionState::Racing,
+                    game_mode: GameMode::Race,
+                    countdown_ms: None,
+                    car_states: vec![],
+                    pace_car: None,
+                    race_progress: None,
+                },
+            })
+            .collect();
+        ReplayPlayer::new(metadata, frames, events)
+    }
+
+    #[test]
+    fn test_playback_controls() {
+        let event = RaceEvent {
+            session_id: SessionId::new_v4(),
+            server_tick: 150,
+            kind: RaceEventKind::OffTrack,
+            player_id: PlayerId::new_v4(),
+            other_player_id: None,
+            lap: 1,
+            position: None,
+            lap_time_ms: None,
+            penalty_ms: None,
+            message: None,
+        };
+        let mut player = player_with_frames(100..580, vec![event.clone()]);
+        assert_eq!(player.tick_range(), Some((100, 579)));
+
+        // Paused until told to play
+        assert!(player.advance(1.0).is_empty());
+        assert_eq!(player.frame().unwrap().tick, 100);
+
+        player.control(ReplayControl::Play);
+        assert_eq!(player.advance(0.25), vec![event]);
+        assert_eq!(player.frame().unwrap().tick, 160);
+
+        player.control(ReplayControl::SetSpeed(0.5));
+        player.advance(0.25);
+        assert_eq!(player.frame().unwrap().tick, 190);
+        player.control(ReplayControl::SetSpeed(100.0));
+        assert_eq!(player.speed(), MAX_PLAYBACK_SPEED);
+
+        player.control(ReplayControl::Seek(120));
+        assert_eq!(player.frame().unwrap().tick, 120);
+        player.control(ReplayControl::Seek(10_000));
+        assert_eq!(player.frame().unwrap().tick, 579);
+
+        // Runs out on the last frame and pauses; playing again starts over
+        player.control(ReplayControl::Seek(500));
+        player.advance(1.0);
+        assert_eq!(player.frame().unwrap().tick, 579);
+        assert!(!player.is_playing());
+        player.control(ReplayControl::Play);
+        assert_eq!(player.frame().unwrap().tick, 100);
+
+        player.control(ReplayControl::Pause);
+        player.advance(1.0);
+        assert_eq!(player.frame().unwrap().tick, 100);
     }
 }
@@ -0,0 +1,637 @@
+//! Hot standby replication.
+//!
+//! A primary server streams the inputs applied to every session each tick,
+//! plus a periodic snapshot of each session's full state, to a standby
+//! process over a dedicated TCP link. The standby keeps the latest snapshot
+//! and the inputs received since; if the primary goes quiet for longer than
+//! the failover timeout (or announces a handover) the standby rebuilds the
+//! sessions by replaying those inputs on top of the snapshot and starts
+//! simulating them itself.
+//!
+//! Clients learn the standby address and a per-player resume token from
+//! `SessionRedirect` while the primary is healthy, and reattach to their car
+//! on the standby with `ResumeSession`.
+//!
+//! The link opens with a `Hello` carrying the configured shared secret; the
+//! standby drops any peer whose first message is not a `Hello` with that
+//! secret, so only the primary can push session state or trigger a takeover.
+//!
+//! Only the race session itself is replicated: standings, race events,
+//! telemetry history and rolling start/formation lap progress restart empty
+//! on the standby.
+
+use crate::admin_api::constant_time_eq;
+use crate::ai_driver::AiDriverProfile;
+use crate::config::ReplicationSettings;
+use crate::data::*;
+use crate::game_session::GameSession;
+use crate::physics::{TickTiming, WarmupSettings};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, Mutex};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Bumped whenever `ReplicationMessage` changes incompatibly
+pub const REPLICATION_PROTOCOL_VERSION: u32 = 2;
+
+/// Snapshots carry every car in every session, so allow far more than client messages
+const MAX_FRAME_BYTES: usize = 16 * 1024 * 1024;
+
+/// Messages buffered while the link to the standby is slow or down
+const CHANNEL_CAPACITY: usize = 4096;
+
+/// Heartbeat sent when there is nothing else to send
+const HEARTBEAT_INTERVAL: Duration = Duration::from_millis(250);
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Full state of one session at a tick
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    pub session: RaceSession,
+    pub ai_profiles: Vec<AiDriverProfile>,
+    pub host_name: String,
+    /// Resume token -> player it reattaches to
+    pub resume_tokens: HashMap<Uuid, PlayerId>,
+    #[serde(default)]
+    pub setup_policy: SetupPolicy,
+    #[serde(default)]
+    pub collision_policy: CollisionPolicy,
+    /// Car setups players applied, so replayed inputs drive the same cars
+    #[serde(default)]
+    pub setups: Vec<(PlayerId, CarSetup)>,
+    #[serde(default = "DriverAids::unrestricted")]
+    pub aid_limits: DriverAids,
+    /// Driver aids players selected, so replayed inputs are shaped the same way
+    #[serde(default)]
+    pub aids: Vec<(PlayerId, DriverAids)>,
+}
+
+impl SessionSnapshot {
+    pub fn new(game_session: &GameSession, host_name: String, resume_tokens: HashMap<Uuid, PlayerId>) -> Self {
+        Self {
+            session: game_session.session.clone(),
+            ai_profiles: game_session.ai_profiles.values().cloned().collect(),
+            host_name,
+            resume_tokens,
+            setup_policy: game_session.setup_policy,
+            collision_policy: game_session.collision_policy,
+            setups: game_session.setups().map(|(player_id, setup)| (player_id, setup.clone())).collect(),
+            aid_limits: game_session.aid_limits,
+            aids: game_session.selected_aids().collect(),
+        }
+    }
+
+    /// Build the session again on the given track and cars, `None` if the
+    /// track is not loaded; setups and aids the session no longer allows are dropped
+    pub fn rebuild(
+        self,
+        track_configs: &HashMap<TrackConfigId, Arc<TrackConfig>>,
+        car_configs: &HashMap<CarConfigId, CarConfig>,
+        warmup: &WarmupSettings,
+        timing: TickTiming,
+    ) -> Option<RecoveredSession> {
+        let session_id = self.session.id;
+        let Some(track) = track_configs.get(&self.session.track_config_id) else {
+            warn!("Cannot recover session {}: track {} not loaded", session_id, self.session.track_config_id);
+            return None;
+        };
+
+        let mut game_session = GameSession::with_ai_profiles(
+            self.session,
+            TrackConfig::clone(track),
+            car_configs.clone(),
+            self.ai_profiles,
+        );
+        game_session.warmup = *warmup;
+        game_session.timing = timing;
+        game_session.setup_policy = self.setup_policy;
+        game_session.collision_policy = self.collision_policy;
+        for (player_id, setup) in self.setups {
+            if let Err(e) = game_session.apply_setup(player_id, setup) {
+                warn!("Dropped setup of player {} in session {}: {}", player_id, session_id, e);
+            }
+        }
+        game_session.aid_limits = self.aid_limits;
+        for (player_id, aids) in self.aids {
+            if let Err(e) = game_session.set_aids(player_id, aids) {
+                warn!("Dropped driver aids of player {} in session {}: {}", player_id, session_id, e);
+            }
+        }
+
+        Some(RecoveredSession {
+            game_session,
+            host_name: self.host_name,
+            resume_tokens: self.resume_tokens,
+        })
+    }
+}
+
+/// Messages from the primary to the standby
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ReplicationMessage {
+    /// First message on the link; `secret` is the shared replication secret
+    Hello { protocol_version: u32, secret: String },
+    /// Every active session; sessions missing from the list have ended
+    Snapshot(Vec<SessionSnapshot>),
+    /// Inputs applied to a session on a tick
+    Inputs {
+        session_id: SessionId,
+        tick: u32,
+        inputs: Vec<(PlayerId, PlayerInputData)>,
+    },
+    Heartbeat,
+    /// The primary is shutting down deliberately; take over now
+    Handover,
+}
+
+/// Primary side: queues replication messages for the background sender
+pub struct ReplicationPrimary {
+    tx: mpsc::Sender<ReplicationMessage>,
+    /// Address clients should reconnect to after a failover
+    pub redirect_addr: String,
+    snapshot_interval_ticks: u64,
+    resume_tokens: HashMap<PlayerId, Uuid>,
+    announced: HashSet<PlayerId>,
+}
+
+impl ReplicationPrimary {
+    /// Start streaming to the configured standby. Must be called inside a tokio runtime.
+    pub fn start(settings: &ReplicationSettings, tick_rate: u16) -> Self {
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let standby_addr = settings.standby_addr.clone();
+        let secret = settings.shared_secret.clone();
+        tokio::spawn(async move {
+            run_sender(standby_addr, secret, rx).await;
+        });
+
+        Self::with_sender(tx, settings, tick_rate)
+    }
+
+    fn with_sender(tx: mpsc::Sender<ReplicationMessage>, settings: &ReplicationSettings, tick_rate: u16) -> Self {
+        Self {
+            tx,
+            redirect_addr: settings.redirect_addr.clone(),
+            snapshot_interval_ticks: (settings.snapshot_interval_ms * tick_rate as u64 / 1000).max(1),
+            resume_tokens: HashMap::new(),
+            announced: HashSet::new(),
+        }
+    }
+
+    /// Whether a snapshot is due on this server tick
+    pub fn snapshot_due(&self, tick_count: u64) -> bool {
+        tick_count.is_multiple_of(self.snapshot_interval_ticks)
+    }
+
+    /// Resume token for a player, created on first use
+    pub fn resume_token(&mut self, player_id: PlayerId) -> Uuid {
+        *self.resume_tokens.entry(player_id).or_insert_with(Uuid::new_v4)
+    }
+
+    /// Human participants that have not been told the failover address yet.
+    ///
+    /// Each player is returned once, with their resume token.
+    pub fn take_unannounced(&mut self, session: &RaceSession) -> Vec<(PlayerId, Uuid)> {
+        let players: Vec<PlayerId> = session
+            .participants
+            .keys()
+            .filter(|id| !session.ai_player_ids.contains(id) && !self.announced.contains(id))
+            .copied()
+            .collect();
+
+        players
+            .into_iter()
+            .map(|id| {
+                self.announced.insert(id);
+                (id, self.resume_token(id))
+            })
+            .collect()
+    }
+
+    /// Snapshot a session, creating resume tokens for its human participants
+    pub fn snapshot(&mut self, game_session: &GameSession, host_name: String) -> SessionSnapshot {
+        let session = &game_session.session;
+        let resume_tokens = session
+            .participants
+            .keys()
+            .filter(|id| !session.ai_player_ids.contains(id))
+            .map(|id| (self.resume_token(*id), *id))
+            .collect();
+
+        SessionSnapshot::new(game_session, host_name, resume_tokens)
+    }
+
+    pub fn send_inputs(&self, session_id: SessionId, tick: u32, inputs: &HashMap<PlayerId, PlayerInputData>) {
+        self.send(ReplicationMessage::Inputs {
+            session_id,
+            tick,
+            inputs: inputs.iter().map(|(id, input)| (*id, *input)).collect(),
+        });
+    }
+
+    pub fn send_snapshot(&self, snapshots: Vec<SessionSnapshot>) {
+        self.send(ReplicationMessage::Snapshot(snapshots));
+    }
+
+    /// Ask the standby to take over immediately
+    pub async fn handover(&self) {
+        let _ = self.tx.send(ReplicationMessage::Handover).await;
+    }
+
+    fn send(&self, msg: ReplicationMessage) {
+        // A dropped input frame leaves a gap the standby discards up to the next snapshot
+        if self.tx.try_send(msg).is_err() {
+            debug!("Replication queue full, dropping message");
+        }
+    }
+}
+
+/// Keep a connection to the standby open and forward queued messages
+async fn run_sender(standby_addr: String, secret: String, mut rx: mpsc::Receiver<ReplicationMessage>) {
+    loop {
+        let mut stream = match TcpStream::connect(&standby_addr).await {
+            Ok(stream) => stream,
+            Err(e) => {
+                debug!("Standby {} unreachable: {}", standby_addr, e);
+                // Discard what queued up meanwhile; the next snapshot resynchronizes
+                while rx.try_recv().is_ok() {}
+                tokio::time::sleep(RECONNECT_DELAY).await;
+                continue;
+            }
+        };
+        let _ = stream.set_nodelay(true);
+        info!("Replicating sessions to standby {}", standby_addr);
+
+        let hello = ReplicationMessage::Hello {
+            protocol_version: REPLICATION_PROTOCOL_VERSION,
+            secret: secret.clone(),
+        };
+        let mut result = write_frame(&mut stream, &hello).await;
+
+        while result.is_ok() {
+            let msg = match tokio::time::timeout(HEARTBEAT_INTERVAL, rx.recv()).await {
+                Ok(Some(msg)) => msg,
+                Ok(None) => return,
+                Err(_) => ReplicationMessage::Heartbeat,
+            };
+            result = write_frame(&mut stream, &msg).await;
+        }
+
+        if let Err(e) = result {
+            warn!("Lost replication link to standby {}: {}", standby_addr, e);
+        }
+    }
+}
+
+async fn write_frame(stream: &mut TcpStream, msg: &ReplicationMessage) -> std::io::Result<()> {
+    let bytes = rmp_serde::to_vec_named(msg).map_err(std::io::Error::other)?;
+    stream.write_all(&(bytes.len() as u32).to_be_bytes()).await?;
+    stream.write_all(&bytes).await
+}
+
+async fn read_frame(stream: &mut TcpStream) -> std::io::Result<ReplicationMessage> {
+    let mut len_buf = [0u8; 4];
+    stream.read_exact(&mut len_buf).await?;
+    let len = u32::from_be_bytes(len_buf) as usize;
+    if len > MAX_FRAME_BYTES {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Replication frame too large: {} bytes", len),
+        ));
+    }
+
+    let mut buf = vec![0u8; len];
+    stream.read_exact(&mut buf).await?;
+    rmp_serde::from_slice(&buf).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// Inputs the primary applied to a session on one tick
+type InputFrame = (u32, HashMap<PlayerId, PlayerInputData>);
+
+/// A session rebuilt on the standby
+pub struct RecoveredSession {
+    pub game_session: GameSession,
+    pub host_name: String,
+    pub resume_tokens: HashMap<Uuid, PlayerId>,
+}
+
+/// Standby side: latest state received from the primary
+#[derive(Debug, Default)]
+pub struct StandbyState {
+    snapshots: HashMap<SessionId, SessionSnapshot>,
+    /// Consecutive input frames after each session's snapshot
+    inputs: HashMap<SessionId, Vec<InputFrame>>,
+    last_heard: Option<Instant>,
+    handover: bool,
+}
+
+impl StandbyState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn apply(&mut self, msg: ReplicationMessage, now: Instant) {
+        self.last_heard = Some(now);
+
+        match msg {
+            ReplicationMessage::Hello { .. } | ReplicationMessage::Heartbeat => {}
+            ReplicationMessage::Handover => {
+                self.handover = true;
+            }
+            ReplicationMessage::Snapshot(snapshots) => {
+                let ids: HashSet<SessionId> = snapshots.iter().map(|s| s.session.id).collect();
+                self.snapshots.retain(|id, _| ids.contains(id));
+                self.inputs.retain(|id, _| ids.contains(id));
+
+                for snapshot in snapshots {
+                    let session_id = snapshot.session.id;
+                    let tick = snapshot.session.current_tick;
+                    if let Some(frames) = self.inputs.get_mut(&session_id) {
+                        frames.retain(|(t, _)| *t > tick);
+                    }
+                    self.snapshots.insert(session_id, snapshot);
+                }
+            }
+            ReplicationMessage::Inputs { session_id, tick, inputs } => {
+                let Some(snapshot) = self.snapshots.get(&session_id) else {
+                    return;
+                };
+                let frames = self.inputs.entry(session_id).or_default();
+                let expected = frames.last().map(|(t, _)| t + 1).unwrap_or(snapshot.session.current_tick + 1);
+
+                if tick == expected {
+                    frames.push((tick, inputs.into_iter().collect()));
+                } else if tick > expected {
+                    // Missed frames can't be replayed; wait for the next snapshot
+                    debug!("Replication gap in session {}: expected tick {}, got {}", session_id, expected, tick);
+                    frames.clear();
+                    self.snapshots.remove(&session_id);
+                }
+            }
+        }
+    }
+
+    /// Whether the primary has handed over or been silent for longer than `timeout`
+    pub fn should_take_over(&self, now: Instant, timeout: Duration) -> bool {
+        match self.last_heard {
+            Some(last_heard) => self.handover || now.duration_since(last_heard) > timeout,
+            None => false,
+        }
+    }
+
+    /// Rebuild every replicated session, replaying inputs received after its snapshot
+    pub fn take_over(
+        &mut self,
+        track_configs: &HashMap<TrackConfigId, Arc<TrackConfig>>,
+        car_configs: &HashMap<CarConfigId, CarConfig>,
+        warmup: &WarmupSettings,
+        timing: TickTiming,
+    ) -> Vec<RecoveredSession> {
+        let mut recovered = Vec::new();
+
+        for (session_id, snapshot) in self.snapshots.drain() {
+            let Some(mut session) = snapshot.rebuild(track_configs, car_configs, warmup, timing) else {
+                continue;
+            };
+
+            let frames = self.inputs.remove(&session_id).unwrap_or_default();
+            for (_, inputs) in &frames {
+                session.game_session.tick(inputs);
+            }
+            info!(
+                "Recovered session {} at tick {} ({} replayed)",
+                session_id,
+                session.game_session.session.current_tick,
+                frames.len()
+            );
+            recovered.push(session);
+        }
+
+        recovered
+    }
+}
+
+/// Why a peer's opening message does not admit it to the replication link
+fn refuse_hello(msg: &ReplicationMessage, secret: &str) -> Option<String> {
+    match msg {
+        ReplicationMessage::Hello { protocol_version, .. } if *protocol_version != REPLICATION_PROTOCOL_VERSION => Some(format!(
+            "uses replication protocol {}, expected {}",
+            protocol_version, REPLICATION_PROTOCOL_VERSION
+        )),
+        ReplicationMessage::Hello { secret: presented, .. } => {
+            // An unset secret admits no one
+            let matches = !secret.is_empty() && constant_time_eq(secret.as_bytes(), presented.as_bytes());
+            (!matches).then(|| "presented the wrong replication secret".to_string())
+        }
+        _ => Some("did not open with a Hello".to_string()),
+    }
+}
+
+/// Accept the primary's replication link and apply everything it sends.
+///
+/// Peers that do not open with a `Hello` carrying `secret` are dropped
+/// before anything they send reaches the standby state.
+pub async fn run_standby_listener(listener: TcpListener, secret: String, state: Arc<Mutex<StandbyState>>) {
+    loop {
+        let (mut stream, addr) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                warn!("Replication accept failed: {}", e);
+                continue;
+            }
+        };
+
+        let hello = match read_frame(&mut stream).await {
+            Ok(msg) => msg,
+            Err(e) => {
+                warn!("Replication link from {} closed: {}", addr, e);
+                continue;
+            }
+        };
+        if let Some(reason) = refuse_hello(&hello, &secret) {
+            warn!("Refused replication peer {}: {}", addr, reason);
+            continue;
+        }
+        info!("Primary {} connected for replication", addr);
+        state.lock().await.apply(hello, Instant::now());
+
+        loop {
+            match read_frame(&mut stream).await {
+                Ok(msg) => state.lock().await.apply(msg, Instant::now()),
+                Err(e) => {
+                    warn!("Replication link from {} closed: {}", addr, e);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_game_session() -> (GameSession, PlayerId) {
+        let track = TrackConfig::default();
+        let car = CarConfig::default();
+        let car_id = car.id;
+        let session = RaceSession::new(PlayerId::new_v4(), track.id, SessionKind::Multiplayer, 8, 0, 3);
+        let mut game_session = GameSession::new(session, track, HashMap::from([(car_id, car)]));
+
+        let player_id = PlayerId::new_v4();
+        game_session.add_player(player_id, car_id);
+        game_session.set_game_mode(GameMode::FreePractice);
+        (game_session, player_id)
+    }
+
+    fn primary() -> (ReplicationPrimary, mpsc::Receiver<ReplicationMessage>) {
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        (ReplicationPrimary::with_sender(tx, &ReplicationSettings::default(), 240), rx)
+    }
+
+    fn throttle(player_id: PlayerId) -> HashMap<PlayerId, PlayerInputData> {
+        HashMap::from([(player_id, PlayerInputData { throttle: 1.0, ..Default::default() })])
+    }
+
+    #[test]
+    fn test_take_over_replays_inputs_after_snapshot() {
+        let (mut game_session, player_id) = create_game_session();
+        let (mut primary, _rx) = primary();
+        let mut standby = StandbyState::new();
+        let now = Instant::now();
+
+        let snapshot = primary.snapshot(&game_session, "Host".to_string());
+        let token = *snapshot.resume_tokens.keys().next().unwrap();
+        standby.apply(ReplicationMessage::Snapshot(vec![snapshot]), now);
+
+        for _ in 0..120 {
+            let inputs = throttle(player_id);
+            game_session.tick(&inputs);
+            standby.apply(
+                ReplicationMessage::Inputs {
+                    session_id: game_session.session.id,
+                    tick: game_session.session.current_tick,
+                    inputs: inputs.into_iter().collect(),
+                },
+                now,
+            );
+        }
+
+        let recovered = standby.take_over(
+            &HashMap::from([(game_session.track_config.id, Arc::new(game_session.track_config.clone()))]),
+            &game_session.car_configs,
+            &game_session.warmup,
+            game_session.timing,
+        );
+
+        assert_eq!(recovered.len(), 1);
+        let session = &recovered[0].game_session.session;
+        assert_eq!(session.current_tick, game_session.session.current_tick);
+        assert_eq!(session.participants[&player_id].pos_x, game_session.session.participants[&player_id].pos_x);
+        assert_eq!(recovered[0].resume_tokens.get(&token), Some(&player_id));
+    }
+
+    #[test]
+    fn test_input_gap_discards_session_until_next_snapshot() {
+        let (game_session, player_id) = create_game_session();
+        let (mut primary, _rx) = primary();
+        let mut standby = StandbyState::new();
+        let now = Instant::now();
+        let session_id = game_session.session.id;
+
+        standby.apply(ReplicationMessage::Snapshot(vec![primary.snapshot(&game_session, String::new())]), now);
+        standby.apply(
+            ReplicationMessage::Inputs {
+                session_id,
+                tick: game_session.session.current_tick + 2,
+                inputs: throttle(player_id).into_iter().collect(),
+            },
+            now,
+        );
+
+        let tracks = HashMap::from([(game_session.track_config.id, Arc::new(game_session.track_config.clone()))]);
+        assert!(standby.take_over(&tracks, &game_session.car_configs, &game_session.warmup, game_session.timing).is_empty());
+    }
+
+    #[test]
+    fn test_should_take_over_after_silence_or_handover() {
+        let mut standby = StandbyState::new();
+        let now = Instant::now();
+        let timeout = Duration::from_secs(3);
+
+        // Never heard from a primary: nothing to take over
+        assert!(!standby.should_take_over(now + Duration::from_secs(60), timeout));
+
+        standby.apply(ReplicationMessage::Heartbeat, now);
+        assert!(!standby.should_take_over(now + Duration::from_secs(1), timeout));
+        assert!(standby.should_take_over(now + Duration::from_secs(4), timeout));
+
+        standby.apply(ReplicationMessage::Handover, now);
+        assert!(standby.should_take_over(now, timeout));
+    }
+
+    #[test]
+    fn test_players_announced_once() {
+        let (game_session, player_id) = create_game_session();
+        let (mut primary, _rx) = primary();
+
+        let announced = primary.take_unannounced(&game_session.session);
+        assert_eq!(announced, vec![(player_id, primary.resume_token(player_id))]);
+        assert!(primary.take_unannounced(&game_session.session).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replication_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let settings = ReplicationSettings {
+            standby_addr: listener.local_addr().unwrap().to_string(),
+            shared_secret: "replication-secret".to_string(),
+            ..Default::default()
+        };
+        let standby = Arc::new(Mutex::new(StandbyState::new()));
+        tokio::spawn(run_standby_listener(listener, settings.shared_secret.clone(), Arc::clone(&standby)));
+
+        let (game_session, _) = create_game_session();
+        let mut primary = ReplicationPrimary::start(&settings, 240);
+        let snapshot = primary.snapshot(&game_session, "Host".to_string());
+        primary.send_snapshot(vec![snapshot]);
+        primary.handover().await;
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !standby.lock().await.should_take_over(Instant::now(), Duration::from_secs(60)) {
+            assert!(Instant::now() < deadline, "Standby never received the handover");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(standby.lock().await.snapshots.contains_key(&game_session.session.id));
+    }
+
+    #[tokio::test]
+    async fn test_standby_refuses_unauthenticated_peers() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let standby = Arc::new(Mutex::new(StandbyState::new()));
+        tokio::spawn(run_standby_listener(listener, "replication-secret".to_string(), Arc::clone(&standby)));
+
+        let wrong_secret = ReplicationMessage::Hello {
+            protocol_version: REPLICATION_PROTOCOL_VERSION,
+            secret: "guess".to_string(),
+        };
+        for opening in [wrong_secret, ReplicationMessage::Handover] {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            write_frame(&mut stream, &opening).await.unwrap();
+            let _ = write_frame(&mut stream, &ReplicationMessage::Handover).await;
+
+            // The standby hangs up without applying anything
+            let closed = tokio::time::timeout(Duration::from_secs(5), read_frame(&mut stream)).await;
+            assert!(matches!(closed, Ok(Err(_))), "Standby kept an unauthenticated link open");
+        }
+        let standby = standby.lock().await;
+        assert!(standby.last_heard.is_none());
+        assert!(!standby.should_take_over(Instant::now(), Duration::ZERO));
+    }
+}
@@ -0,0 +1,467 @@
+//! Finished-race results for championship tools.
+//!
+//! Results are written in the layout of the Assetto Corsa dedicated server's
+//! results JSON, which simresults and most league management tools already
+//! import: `Cars` is the entry list, `Result` the classification, `Laps` every
+//! completed lap and `Events` the contacts. All times are milliseconds. Result
+//! rows also carry `HasPenalty` and `PenaltyTime`, the time penalties a driver
+//! collected; `TotalTime` does not include them. `Timeline` is the race's
+//! timeline from the session results, for race reports. Drivers are named as
+//! they raced, not under spectator pseudonyms.
+
+use crate::data::*;
+use crate::game_session::GameSession;
+use crate::network::RaceEvent;
+use crate::standings::compare_race_order;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::{Method, Request, Uri};
+use hyper_util::rt::TokioIo;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+/// Longest wait for the webhook to answer
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LeagueResults {
+    pub track_name: String,
+    pub track_config: String,
+    /// `RACE`, or `PRACTICE` for sessions without a lap limit
+    #[serde(rename = "Type")]
+    pub session_type: String,
+    pub duration_secs: u32,
+    pub race_laps: u16,
+    pub cars: Vec<LeagueCar>,
+    pub result: Vec<LeagueResult>,
+    pub laps: Vec<LeagueLap>,
+    pub events: Vec<LeagueEvent>,
+    pub timeline: Vec<LeagueTimelineEntry>,
+    pub session_file: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LeagueDriver {
+    pub name: String,
+    pub team: String,
+    pub guid: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LeagueCar {
+    pub car_id: u32,
+    pub driver: LeagueDriver,
+    pub model: String,
+    pub skin: String,
+    #[serde(rename = "BallastKG")]
+    pub ballast_kg: u32,
+    pub restrictor: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LeagueResult {
+    pub driver_name: String,
+    pub driver_guid: String,
+    pub car_id: u32,
+    pub car_model: String,
+    /// 0 without a completed lap
+    pub best_lap: u32,
+    /// Race start to the finish line; 0 for cars that did not finish
+    pub total_time: u32,
+    #[serde(rename = "BallastKG")]
+    pub ballast_kg: u32,
+    pub restrictor: u32,
+    pub has_penalty: bool,
+    pub penalty_time: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LeagueLap {
+    pub driver_name: String,
+    pub driver_guid: String,
+    pub car_id: u32,
+    pub car_model: String,
+    /// Since the start of the race
+    pub timestamp: u32,
+    pub lap_time: u32,
+    pub sectors: Vec<u32>,
+    pub cuts: u32,
+    #[serde(rename = "BallastKG")]
+    pub ballast_kg: u32,
+    pub tyre: String,
+    pub restrictor: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LeagueEvent {
+    /// `COLLISION_WITH_CAR` or `COLLISION_WITH_ENV`
+    #[serde(rename = "Type")]
+    pub event_type: String,
+    pub car_id: u32,
+    pub driver: LeagueDriver,
+    /// -1 for contact with the environment
+    pub other_car_id: i64,
+    pub other_driver: LeagueDriver,
+    /// Since the start of the race
+    pub timestamp: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LeagueTimelineEntry {
+    /// `RACE_START`, `LEAD_CHANGE`, `PIT_ENTRY`, `PIT_EXIT`, `PIT_LANE_START`,
+    /// `PENALTY`, `OFF_TRACK`, `FINISH` or `CONTACT`
+    #[serde(rename = "Type")]
+    pub event_type: String,
+    /// Since the start of the race
+    pub timestamp: u32,
+    /// -1 for the race start
+    pub car_id: i64,
+    pub driver_name: String,
+    /// The car that lost the lead or was hit, or -1
+    pub other_car_id: i64,
+    pub lap: u16,
+    /// Position after a lead change or finish; 0 otherwise
+    pub position: u8,
+    /// 0 except for penalties
+    pub penalty_time: u32,
+    pub message: String,
+}
+
+impl LeagueResults {
+    /// Results of a finished session. Human drivers are named from
+    /// `driver_names`, AI drivers from their profile; `teams` maps driver
+    /// names to teams.
+    pub fn new(
+        game_session: &GameSession,
+        driver_names: &HashMap<PlayerId, String>,
+        teams: &HashMap<String, String>,
+    ) -> Self {
+        let session = &game_session.session;
+        let tick_rate_hz = game_session.timing.tick_rate_hz.max(1) as u64;
+        let start_tick = session.race_start_tick.unwrap_or(0);
+        let since_start = |tick: u32| (tick.saturating_sub(start_tick) as u64 * 1000 / tick_rate_hz) as u32;
+
+        // Car IDs follow the grid
+        let mut grid: Vec<&CarState> = session.participants.values().collect();
+        grid.sort_by_key(|state| (state.grid_position, state.player_id));
+        let car_ids: HashMap<PlayerId, u32> =
+            grid.iter().enumerate().map(|(idx, state)| (state.player_id, idx as u32)).collect();
+
+        let driver = |player_id: PlayerId| {
+            let name = game_session
+                .ai_profiles
+                .get(&player_id)
+                .map(|profile| profile.name.clone())
+                .or_else(|| driver_names.get(&player_id).cloned())
+                .unwrap_or_else(|| "Unknown".to_string());
+            LeagueDriver {
+                team: teams.get(&name).cloned().unwrap_or_default(),
+                name,
+                guid: player_id.to_string(),
+            }
+        };
+        let model = |player_id: PlayerId| {
+            session
+                .participants
+                .get(&player_id)
+                .and_then(|state| game_session.car_configs.get(&state.car_config_id))
+                .map(|car| car.name.clone())
+                .unwrap_or_default()
+        };
+
+        let cars = grid
+            .iter()
+            .map(|state| LeagueCar {
+                car_id: car_ids[&state.player_id],
+                driver: driver(state.player_id),
+                model: model(state.player_id),
+                skin: String::new(),
+                ballast_kg: 0,
+                restrictor: 0,
+            })
+            .collect();
+
+        let events = game_session.race_events.events();
+        let finish_ticks: HashMap<PlayerId, u32> = events
+            .iter()
+            .filter(|e| e.kind == RaceEventKind::Finished)
+            .map(|e| (e.player_id, e.server_tick))
+            .collect();
+        let mut penalties: HashMap<PlayerId, u32> = HashMap::new();
+        for event in events.iter().filter(|e| e.kind == RaceEventKind::Penalty) {
+            *penalties.entry(event.player_id).or_default() += event.penalty_ms.unwrap_or(0);
+        }
+
+        let mut order: Vec<&CarState> = session.participants.values().collect();
+        order.sort_by(|a, b| compare_race_order(a, b));
+        let result = order
+            .iter()
+            .map(|state| {
+                let penalty_time = penalties.get(&state.player_id).copied().unwrap_or(0);
+                LeagueResult {
+                    driver_name: driver(state.player_id).name,
+                    driver_guid: state.player_id.to_string(),
+                    car_id: car_ids[&state.player_id],
+                    car_model: model(state.player_id),
+                    best_lap: state.best_lap_time_ms.unwrap_or(0),
+                    total_time: finish_ticks.get(&state.player_id).map(|tick| since_start(*tick)).unwrap_or(0),
+                    ballast_kg: 0,
+                    restrictor: 0,
+                    has_penalty: penalty_time > 0,
+                    penalty_time,
+                }
+            })
+            .collect();
+
+        let laps = events
+            .iter()
+            .filter(|e| e.kind == RaceEventKind::LapCompleted && car_ids.contains_key(&e.player_id))
+            .filter_map(|e| {
+                Some(LeagueLap {
+                    driver_name: driver(e.player_id).name,
+                    driver_guid: e.player_id.to_string(),
+                    car_id: car_ids[&e.player_id],
+                    car_model: model(e.player_id),
+                    timestamp: since_start(e.server_tick),
+                    lap_time: e.lap_time_ms?,
+                    sectors: Vec::new(),
+                    cuts: 0,
+                    ballast_kg: 0,
+                    tyre: String::new(),
+                    restrictor: 0,
+                })
+            })
+            .collect();
+
+        let timeline = game_session
+            .race_events
+            .timeline(session.race_start_tick)
+            .into_iter()
+            .filter(|e| e.player_id.is_none_or(|id| car_ids.contains_key(&id)))
+            .map(|e| LeagueTimelineEntry {
+                event_type: match e.kind {
+                    TimelineEventKind::RaceStart => "RACE_START",
+                    TimelineEventKind::LeadChange => "LEAD_CHANGE",
+                    TimelineEventKind::PitEntry => "PIT_ENTRY",
+                    TimelineEventKind::PitExit => "PIT_EXIT",
+                    TimelineEventKind::PitLaneStart => "PIT_LANE_START",
+                    TimelineEventKind::Penalty => "PENALTY",
+                    TimelineEventKind::OffTrack => "OFF_TRACK",
+                    TimelineEventKind::Finished => "FINISH",
+                    TimelineEventKind::Contact => "CONTACT",
+                }
+                .to_string(),
+                timestamp: since_start(e.server_tick),
+                car_id: e.player_id.map(|id| car_ids[&id] as i64).unwrap_or(-1),
+                driver_name: e.player_id.map(|id| driver(id).name).unwrap_or_default(),
+                other_car_id: e.other_player_id.and_then(|id| car_ids.get(&id)).map(|id| *id as i64).unwrap_or(-1),
+                lap: e.lap,
+                position: e.position.unwrap_or(0),
+                penalty_time: e.penalty_ms.unwrap_or(0),
+                message: e.message.unwrap_or_default(),
+            })
+            .collect();
+
+        let events = events
+            .iter()
+            .filter(|e| e.kind == RaceEventKind::Contact && car_ids.contains_key(&e.player_id))
+            .map(|e: &RaceEvent| {
+                let other = e.other_player_id.filter(|id| car_ids.contains_key(id));
+                LeagueEvent {
+                    event_type: if other.is_some() { "COLLISION_WITH_CAR" } else { "COLLISION_WITH_ENV" }.to_string(),
+                    car_id: car_ids[&e.player_id],
+                    driver: driver(e.player_id),
+                    other_car_id: other.map(|id| car_ids[&id] as i64).unwrap_or(-1),
+                    other_driver: other.map(driver).unwrap_or(LeagueDriver {
+                        name: String::new(),
+                        team: String::new(),
+                        guid: String::new(),
+                    }),
+                    timestamp: since_start(e.server_tick),
+                }
+            })
+            .collect();
+
+        Self {
+            track_name: game_session.track_config.name.clone(),
+            track_config: String::new(),
+            session_type: if session.lap_limit > 0 { "RACE" } else { "PRACTICE" }.to_string(),
+            duration_secs: since_start(session.current_tick) / 1000,
+            race_laps: session.lap_limit as u16,
+            cars,
+            result,
+            laps,
+            events,
+            timeline,
+            session_file: session.id.to_string(),
+        }
+    }
+
+    /// File name for results of a race finished at `finished_at_unix`
+    pub fn file_name(&self, finished_at_unix: u64) -> String {
+        format!("{}_{}_{}.json", finished_at_unix, self.session_type, self.session_file)
+    }
+}
+
+/// Write results as `{directory}/{file_name}`, returning the path
+pub async fn write_results(directory: &Path, file_name: &str, results: &LeagueResults) -> io::Result<PathBuf> {
+    tokio::fs::create_dir_all(directory).await?;
+    let path = directory.join(file_name);
+    let json = serde_json::to_vec_pretty(results).map_err(io::Error::other)?;
+    tokio::fs::write(&path, json).await?;
+    Ok(path)
+}
+
+/// POST results as JSON to a plain `http://` webhook
+pub async fn post_results(url: &str, results: &LeagueResults) -> io::Result<()> {
+    let body = serde_json::to_vec(results).map_err(io::Error::other)?;
+    tokio::time::timeout(WEBHOOK_TIMEOUT, post_json(url, body))
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "results webhook did not answer"))?
+}
+
+async fn post_json(url: &str, body: Vec<u8>) -> io::Result<()> {
+    let uri: Uri = url
+        .parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("invalid webhook URL {:?}: {}", url, e)))?;
+    if uri.scheme_str() != Some("http") {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("webhook URL {:?} must be http://", url)));
+    }
+    let host = uri.host().unwrap_or_default().to_string();
+    let port = uri.port_u16().unwrap_or(80);
+
+    let stream = TcpStream::connect((host.as_str(), port)).await?;
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+        .await
+        .map_err(io::Error::other)?;
+    tokio::spawn(connection);
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(uri.path_and_query().map(|p| p.as_str()).unwrap_or("/"))
+        .header(hyper::header::HOST, format!("{}:{}", host, port))
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .body(Full::new(Bytes::from(body)))
+        .map_err(io::Error::other)?;
+    let response = sender.send_request(request).await.map_err(io::Error::other)?;
+    let status = response.status();
+    // Drain the answer so the connection closes cleanly
+    let _ = response.into_body().collect().await;
+    if !status.is_success() {
+        return Err(io::Error::other(format!("results webhook answered {}", status)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(kind: RaceEventKind, tick: u32, player_id: PlayerId) -> RaceEvent {
+        RaceEvent {
+            session_id: SessionId::nil(),
+            server_tick: tick,
+            kind,
+            player_id,
+            other_player_id: None,
+            lap: 1,
+            position: None,
+            lap_time_ms: None,
+            penalty_ms: None,
+            message: None,
+        }
+    }
+
+    #[test]
+    fn test_results_from_a_finished_race() {
+        let track = TrackConfig::default();
+        let car = CarConfig::default();
+        let car_configs = HashMap::from([(car.id, car.clone())]);
+        let session = RaceSession::new(PlayerId::new_v4(), track.id, SessionKind::Multiplayer, 8, 0, 1);
+        let mut game_session = GameSession::new(session, track, car_configs);
+        game_session.timing.tick_rate_hz = 100;
+
+        let (winner, second) = (PlayerId::new_v4(), PlayerId::new_v4());
+        for (idx, (player_id, finish)) in [(second, 2), (winner, 1)].into_iter().enumerate() {
+            let mut state = CarState::new(player_id, car.id, &GridSlot { position: idx as u8 + 1, x: 0.0, y: 0.0, z: 0.0, yaw_rad: 0.0 });
+            state.finish_position = Some(finish);
+            state.best_lap_time_ms = Some(60_000 + finish as u32);
+            game_session.session.participants.insert(player_id, state);
+        }
+        game_session.session.race_start_tick = Some(1_000);
+        game_session.session.current_tick = 8_000;
+
+        let mut lap = event(RaceEventKind::LapCompleted, 7_000, winner);
+        lap.lap_time_ms = Some(60_001);
+        let mut penalty = event(RaceEventKind::Penalty, 5_000, second);
+        penalty.penalty_ms = Some(5_000);
+        let mut contact = event(RaceEventKind::Contact, 3_000, winner);
+        contact.other_player_id = Some(second);
+        let mut lead_change = event(RaceEventKind::Overtake, 4_000, winner);
+        lead_change.other_player_id = Some(second);
+        lead_change.position = Some(1);
+        game_session.race_events.restore(vec![
+            contact,
+            lead_change,
+            penalty,
+            lap,
+            event(RaceEventKind::Finished, 7_000, winner),
+            event(RaceEventKind::Finished, 7_500, second),
+        ]);
+
+        let names = HashMap::from([(winner, "Ana".to_string()), (second, "Ben".to_string())]);
+        let teams = HashMap::from([("Ana".to_string(), "Red Team".to_string())]);
+        let results = LeagueResults::new(&game_session, &names, &teams);
+
+        assert_eq!(results.session_type, "RACE");
+        assert_eq!(results.race_laps, 1);
+        assert_eq!(results.duration_secs, 70);
+        assert_eq!(results.cars[0].driver.name, "Ben");
+        assert_eq!(results.cars[1].driver.team, "Red Team");
+
+        assert_eq!(results.result[0].driver_name, "Ana");
+        assert_eq!(results.result[0].total_time, 60_000);
+        assert_eq!(results.result[0].best_lap, 60_001);
+        assert!(!results.result[0].has_penalty);
+        assert_eq!(results.result[1].total_time, 65_000);
+        assert_eq!(results.result[1].penalty_time, 5_000);
+
+        assert_eq!(results.laps.len(), 1);
+        assert_eq!((results.laps[0].lap_time, results.laps[0].timestamp), (60_001, 60_000));
+        assert_eq!(results.events[0].event_type, "COLLISION_WITH_CAR");
+        assert_eq!(results.events[0].other_car_id, 0);
+
+        let timeline: Vec<(&str, u32, i64)> =
+            results.timeline.iter().map(|e| (e.event_type.as_str(), e.timestamp, e.car_id)).collect();
+        assert_eq!(
+            timeline,
+            vec![
+                ("RACE_START", 0, -1),
+                ("CONTACT", 20_000, 1),
+                ("LEAD_CHANGE", 30_000, 1),
+                ("PENALTY", 40_000, 0),
+                ("FINISH", 60_000, 1),
+                ("FINISH", 65_000, 0),
+            ]
+        );
+        assert_eq!((results.timeline[2].driver_name.as_str(), results.timeline[2].other_car_id), ("Ana", 0));
+        assert_eq!(results.timeline[3].penalty_time, 5_000);
+
+        let json = serde_json::to_value(&results).unwrap();
+        assert_eq!(json["Timeline"][2]["Type"], "LEAD_CHANGE");
+        assert_eq!(json["Result"][0]["DriverName"], "Ana");
+        assert_eq!(json["Cars"][1]["BallastKG"], 0);
+        assert_eq!(json["Type"], "RACE");
+    }
+}
@@ -0,0 +1,402 @@
+//! Rolling start procedure.
+//!
+//! The field follows a server-driven pace car around a formation lap in grid
+//! order with speed capped at the pace speed. The pace car pulls off late in
+//! the lap and the race goes green when the leader crosses the start/finish
+//! line. Passing the car ahead on the grid (or the pace car) before green is
+//! a false start.
+//!
+//! Progress is measured as distance travelled since the procedure began,
+//! offset by each car's starting distance behind the pole sitter, so the
+//! running order can be compared independently of where the line is.
+
+use crate::data::*;
+use crate::network::PaceCarTelemetry;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Speed cap for the field and the pace car during the formation lap (m/s, ~80 km/h)
+pub const PACE_SPEED_MPS: f32 = 22.0;
+
+/// Time penalty recorded against a false start
+pub const FALSE_START_PENALTY_MS: u32 = 5000;
+
+/// Distance the pace car runs ahead of the pole sitter
+const PACE_CAR_LEAD_M: f32 = 30.0;
+
+/// How far a car must get ahead of another before it counts as a pass
+const PASS_MARGIN_M: f32 = 2.0;
+
+/// Fraction of the formation lap after which the pace car pulls off
+const PACE_CAR_PIT_IN_FRACTION: f32 = 0.75;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RollingCar {
+    /// Car that should be directly ahead (None for the pole sitter)
+    ahead: Option<PlayerId>,
+    /// Starting distance behind the pole sitter
+    offset_m: f32,
+    travelled_m: f32,
+    last_progress: f32,
+    false_start: bool,
+}
+
+impl RollingCar {
+    fn position_m(&self) -> f32 {
+        self.travelled_m - self.offset_m
+    }
+}
+
+/// What happened during one rolling start update
+#[derive(Debug, Default, PartialEq)]
+pub struct RollingStartUpdate {
+    /// Cars that passed before green on this tick
+    pub false_starts: Vec<PlayerId>,
+    /// The leader crossed the line and the race is on
+    pub went_green: bool,
+}
+
+/// State of an in-progress rolling start
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RollingStart {
+    cars: HashMap<PlayerId, RollingCar>,
+    leader: Option<PlayerId>,
+    track_length_m: f32,
+    pace_car_travelled_m: f32,
+    pace_car_on_track: bool,
+    green_tick: Option<u32>,
+    /// Cars that have not crossed the line since green; their first lap starts there
+    awaiting_line: HashSet<PlayerId>,
+}
+
+impl RollingStart {
+    /// Line the field up in grid order behind the pace car
+    pub fn new(session: &RaceSession, track: &TrackConfig) -> Self {
+        let track_length_m = track_length(track);
+
+        let mut grid: Vec<&CarState> = session.participants.values().collect();
+        grid.sort_by_key(|s| s.grid_position);
+
+        let leader = grid.first().map(|s| (s.player_id, s.track_progress));
+        let cars = grid
+            .iter()
+            .enumerate()
+            .map(|(idx, state)| {
+                let offset_m = leader
+                    .map(|(_, progress)| wrap_distance(progress - state.track_progress, track_length_m))
+                    .unwrap_or(0.0);
+                (
+                    state.player_id,
+                    RollingCar {
+                        ahead: idx.checked_sub(1).map(|i| grid[i].player_id),
+                        offset_m,
+                        travelled_m: 0.0,
+                        last_progress: state.track_progress,
+                        false_start: false,
+                    },
+                )
+            })
+            .collect();
+
+        Self {
+            cars,
+            leader: leader.map(|(id, _)| id),
+            track_length_m,
+            pace_car_travelled_m: 0.0,
+            pace_car_on_track: true,
+            green_tick: None,
+            awaiting_line: HashSet::new(),
+        }
+    }
+
+    pub fn is_green(&self) -> bool {
+        self.green_tick.is_some()
+    }
+
+    /// Green and every car has started its first racing lap
+    pub fn is_complete(&self) -> bool {
+        self.is_green() && self.awaiting_line.is_empty()
+    }
+
+    /// Advance the procedure after the cars have moved for this tick.
+    ///
+    /// Caps speeds, holds lap counting until green, detects false starts and
+    /// starts each car's first lap as it crosses the line after green.
+    pub fn update(&mut self, session: &mut RaceSession, dt: f32) -> RollingStartUpdate {
+        let mut update = RollingStartUpdate::default();
+        let tick = session.current_tick;
+        let length = self.track_length_m;
+
+        self.cars.retain(|id, _| session.participants.contains_key(id));
+        self.awaiting_line.retain(|id| session.participants.contains_key(id));
+
+        // Distance travelled and line crossings
+        let mut crossed_line = HashSet::new();
+        for (id, car) in self.cars.iter_mut() {
+            let state = &session.participants[id];
+            let delta = wrap_distance(state.track_progress - car.last_progress, length);
+            car.travelled_m += delta;
+            if length > 0.0 && car.last_progress > length * 0.8 && state.track_progress < length * 0.2 {
+                crossed_line.insert(*id);
+            }
+            car.last_progress = state.track_progress;
+        }
+
+        if let Some(green_tick) = self.green_tick {
+            // Each car's first racing lap starts when it reaches the line
+            for id in crossed_line {
+                if self.awaiting_line.remove(&id) {
+                    if let Some(state) = session.participants.get_mut(&id) {
+                        start_first_lap(state, tick.max(green_tick));
+                    }
+                }
+            }
+            for id in &self.awaiting_line {
+                if let Some(state) = session.participants.get_mut(id) {
+                    hold_lap(state);
+                }
+            }
+            return update;
+        }
+
+        // Formation lap: speed capped, no lap timing
+        for state in session.participants.values_mut() {
+            cap_speed(state, PACE_SPEED_MPS);
+            hold_lap(state);
+        }
+
+        if self.pace_car_on_track {
+            self.pace_car_travelled_m += PACE_SPEED_MPS * dt;
+        }
+
+        let leader_travelled = self
+            .leader
+            .and_then(|id| self.cars.get(&id))
+            .map(|c| c.travelled_m)
+            .unwrap_or(0.0);
+        if self.pace_car_on_track && leader_travelled >= length * PACE_CAR_PIT_IN_FRACTION {
+            self.pace_car_on_track = false;
+        }
+
+        // False starts: passing the car ahead on the grid or the pace car
+        let pace_car_position = self.pace_car_travelled_m + PACE_CAR_LEAD_M;
+        let positions: HashMap<PlayerId, f32> = self.cars.iter().map(|(id, c)| (*id, c.position_m())).collect();
+        for (id, car) in self.cars.iter_mut() {
+            if car.false_start {
+                continue;
+            }
+            let position = positions[id];
+            let passed_car = car
+                .ahead
+                .and_then(|ahead| positions.get(&ahead))
+                .is_some_and(|ahead_position| position > ahead_position + PASS_MARGIN_M);
+            let passed_pace_car = self.pace_car_on_track && position > pace_car_position + PASS_MARGIN_M;
+
+            if passed_car || passed_pace_car {
+                car.false_start = true;
+                update.false_starts.push(*id);
+            }
+        }
+
+        // Green when the leader crosses the line after most of the formation lap
+        let leader_crossed = self.leader.is_some_and(|id| crossed_line.contains(&id));
+        if leader_crossed && leader_travelled > length / 2.0 {
+            self.green_tick = Some(tick);
+            update.went_green = true;
+
+            for (id, state) in session.participants.iter_mut() {
+                if Some(*id) == self.leader {
+                    start_first_lap(state, tick);
+                } else {
+                    self.awaiting_line.insert(*id);
+                }
+            }
+            session.race_start_tick = Some(tick);
+        }
+
+        update
+    }
+
+    /// Pace car position for telemetry while it is on track
+    pub fn pace_car(&self, track: &TrackConfig) -> Option<PaceCarTelemetry> {
+        if !self.pace_car_on_track || self.is_green() || track.centerline.is_empty() {
+            return None;
+        }
+
+        let leader = self.leader.and_then(|id| self.cars.get(&id))?;
+        let start = leader.last_progress - leader.travelled_m;
+        let distance = (start + self.pace_car_travelled_m + PACE_CAR_LEAD_M).rem_euclid(self.track_length_m.max(1.0));
+        let point = point_at_distance(track, distance);
+
+        Some(PaceCarTelemetry {
+            pos_x: point.x,
+            pos_y: point.y,
+            pos_z: point.z,
+            yaw_rad: point.heading_rad,
+            speed_mps: PACE_SPEED_MPS,
+        })
+    }
+}
+
+pub(crate) fn track_length(track: &TrackConfig) -> f32 {
+    track.centerline.last().map(|p| p.distance_from_start_m).unwrap_or(0.0)
+}
+
+/// Wrap a progress difference into (-length/2, length/2]
+pub(crate) fn wrap_distance(delta: f32, length: f32) -> f32 {
+    if length <= 0.0 {
+        return delta;
+    }
+    let wrapped = delta.rem_euclid(length);
+    if wrapped > length / 2.0 {
+        wrapped - length
+    } else {
+        wrapped
+    }
+}
+
+pub(crate) fn cap_speed(state: &mut CarState, max_speed: f32) {
+    if state.speed_mps > max_speed {
+        let scale = max_speed / state.speed_mps;
+        state.vel_x *= scale;
+        state.vel_y *= scale;
+        state.vel_z *= scale;
+        state.speed_mps = max_speed;
+    }
+}
+
+pub(crate) fn hold_lap(state: &mut CarState) {
+    state.current_lap = 0;
+    state.current_lap_time_ms = 0;
+}
+
+fn start_first_lap(state: &mut CarState, tick: u32) {
+    state.current_lap = 1;
+    state.lap_start_tick = tick;
+    state.current_lap_time_ms = 0;
+}
+
+fn point_at_distance(track: &TrackConfig, distance: f32) -> TrackPoint {
+    let centerline = &track.centerline;
+    let idx = centerline.partition_point(|p| p.distance_from_start_m < distance);
+
+    if idx == 0 {
+        return centerline[0].clone();
+    }
+    if idx >= centerline.len() {
+        return centerline[centerline.len() - 1].clone();
+    }
+
+    let a = &centerline[idx - 1];
+    let b = &centerline[idx];
+    let span = b.distance_from_start_m - a.distance_from_start_m;
+    let t = if span > 0.0 { (distance - a.distance_from_start_m) / span } else { 0.0 };
+
+    TrackPoint {
+        x: a.x + (b.x - a.x) * t,
+        y: a.y + (b.y - a.y) * t,
+        z: a.z + (b.z - a.z) * t,
+        distance_from_start_m: distance,
+        ..a.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 1.0 / 240.0;
+
+    /// Two cars lined up 10 m apart, 50 m before the line
+    fn create_session() -> (RaceSession, TrackConfig, PlayerId, PlayerId) {
+        let track = TrackConfig::default();
+        let length = track_length(&track);
+        let mut session = RaceSession::new(PlayerId::new_v4(), track.id, SessionKind::Multiplayer, 8, 0, 3);
+
+        let pole = PlayerId::new_v4();
+        let second = PlayerId::new_v4();
+        for (id, slot, progress) in [(pole, 0, length - 50.0), (second, 1, length - 60.0)] {
+            let mut state = CarState::new(id, CarConfigId::new_v4(), &track.start_positions[slot]);
+            state.track_progress = progress;
+            session.participants.insert(id, state);
+        }
+
+        (session, track, pole, second)
+    }
+
+    /// Move every car forward by `distance`, wrapping at the line
+    fn advance(session: &mut RaceSession, length: f32, distance: f32) {
+        for state in session.participants.values_mut() {
+            state.track_progress = (state.track_progress + distance).rem_euclid(length);
+        }
+        session.current_tick += 1;
+    }
+
+    #[test]
+    fn test_speed_capped_and_laps_held_during_formation() {
+        let (mut session, track, pole, _) = create_session();
+        let mut rolling = RollingStart::new(&session, &track);
+
+        let state = session.participants.get_mut(&pole).unwrap();
+        state.vel_x = 50.0;
+        state.speed_mps = 50.0;
+        state.current_lap = 1;
+
+        rolling.update(&mut session, DT);
+
+        let state = &session.participants[&pole];
+        assert_eq!(state.speed_mps, PACE_SPEED_MPS);
+        assert_eq!(state.vel_x, PACE_SPEED_MPS);
+        assert_eq!(state.current_lap, 0);
+        assert!(rolling.pace_car(&track).is_some());
+    }
+
+    #[test]
+    fn test_goes_green_when_leader_crosses_line() {
+        let (mut session, track, pole, second) = create_session();
+        let length = track_length(&track);
+        let mut rolling = RollingStart::new(&session, &track);
+
+        // Cross the line once just after the start, then drive most of a lap
+        let mut went_green = false;
+        let mut travelled = 0.0;
+        while travelled < length + 100.0 && !went_green {
+            advance(&mut session, length, 5.0);
+            travelled += 5.0;
+            went_green = rolling.update(&mut session, DT).went_green;
+        }
+
+        assert!(went_green, "Race should go green within the formation lap");
+        assert!(travelled > length / 2.0);
+        assert_eq!(session.participants[&pole].current_lap, 1);
+        assert_eq!(session.participants[&second].current_lap, 0);
+        assert!(rolling.pace_car(&track).is_none());
+        assert!(!rolling.is_complete());
+
+        // The second car starts its lap when it reaches the line
+        for _ in 0..4 {
+            advance(&mut session, length, 5.0);
+            rolling.update(&mut session, DT);
+        }
+        assert_eq!(session.participants[&second].current_lap, 1);
+        assert!(rolling.is_complete());
+    }
+
+    #[test]
+    fn test_passing_before_green_is_false_start() {
+        let (mut session, track, _, second) = create_session();
+        let length = track_length(&track);
+        let mut rolling = RollingStart::new(&session, &track);
+
+        let state = session.participants.get_mut(&second).unwrap();
+        state.track_progress = (state.track_progress + 15.0).rem_euclid(length);
+        session.current_tick += 1;
+
+        let update = rolling.update(&mut session, DT);
+        assert_eq!(update.false_starts, vec![second]);
+
+        // Only reported once
+        session.current_tick += 1;
+        assert!(rolling.update(&mut session, DT).false_starts.is_empty());
+    }
+}
@@ -0,0 +1,4740 @@
+//! The server itself: its state, the tasks serving clients and the game loop.
+//!
+//! `ServerRuntime` starts one from a `ServerConfig`. The `apexsim-server`
+//! binary runs it until Ctrl+C; integration tests and custom launchers run it
+//! in-process instead, where clients can connect without a socket, ticks can
+//! be stepped by hand and the state looked at between them.
+
+use crate::{
+    admin_api::{
+        constant_time_eq, run_admin_api_server, AdminApi, AdminCommand, AdminError, AdminPlayer, AdminRequest, AdminResponse, AdminResult,
+        AdminSession, AdminStats, AdminTrack, SessionTransition, SessionUpdate,
+    },
+    admin_console::{spawn_stdin_console, AdminConsole, LogFilterHandle},
+    anonymize::Pseudonymizer,
+    bans::{unix_now, BanEntry, BanList},
+    car_loader::CarLoader,
+    cluster::{run_gateway_server, session_load, ClusterRegistry, GatewayClient, InstanceReport},
+    config::{ClusterRole, ReplicationRole, ServerConfig},
+    content_transfer::{ContentLibrary, CONTENT_CHUNK_BYTES, MAX_CONTENT_TRANSFERS_PER_CONNECTION},
+    dashboard::DashboardOutputs,
+    data::*,
+    game_session::GameSession,
+    lap_records::LapRecords,
+    grpc_api::run_grpc_server,
+    input_queue::InputQueues,
+    journal::{park_cars, SessionJournal},
+    health::{HealthDetails, HealthState, run_health_server},
+    live_timing::{LiveTiming, TimingSnapshot},
+    lobby::LobbyManager,
+    master_server::{MasterServerClient, ServerListing},
+    network::{ClientMessage, InstanceRedirectData, LobbyStateData, ServerMessage, SessionJoinedData},
+    physics::{CollisionSettings, TickTiming, WarmupSettings},
+    procgen::circuit::{circuit_path, generate_circuit, save_circuit, GENERATED_TRACKS_DIR},
+    replay::{ReplayControl, ReplayManager, ReplayPlayer, REPLAY_CHUNK_BYTES, STATE_HASH_INTERVAL_TICKS},
+    replication::{run_standby_listener, RecoveredSession, ReplicationPrimary, SessionSnapshot, StandbyState},
+    results_export::{post_results, write_results, LeagueResults},
+    session_limits::{SessionCreators, SessionLimit},
+    session_save::SessionSaves,
+    tick_lag::{TickClock, TickLagMetrics},
+    track_loader::TrackLoader,
+    transport::{ConnectionInfo, ContentTransferGuard, TransportLayer},
+    weather::Weather,
+};
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Notify, RwLock, RwLockReadGuard};
+use tokio::task::JoinHandle;
+use tokio::time::{interval, Duration};
+use tracing::{debug, info, warn};
+
+/// How often the health endpoints' details are refreshed
+const HEALTH_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Everything the server knows: content, sessions, players and the lobby
+pub struct ServerState {
+    config: ServerConfig,
+    car_configs: HashMap<CarConfigId, CarConfig>,
+    /// Shared so a session's copy can be made away from the state lock
+    track_configs: HashMap<TrackConfigId, Arc<TrackConfig>>,
+    sessions: HashMap<SessionId, GameSession>,
+    /// Sessions listed in the lobby whose game state is still being built
+    preparing: HashSet<SessionId>,
+    players: HashMap<PlayerId, Player>,
+    /// Players that authenticated with one of the configured admin tokens
+    admins: HashSet<PlayerId>,
+    lobby: LobbyManager,
+    replay: ReplayManager,
+    /// Streams sessions to the hot standby when this server is a primary
+    replication: Option<ReplicationPrimary>,
+    /// Resume token -> (session, car owner) for sessions recovered from a failed
+    /// primary or resumed from a save
+    resume_tokens: HashMap<uuid::Uuid, (SessionId, PlayerId)>,
+    /// Mid-race saves of sessions
+    saves: SessionSaves,
+    /// Crash journal of the sessions; `None` when it is disabled
+    journal: Option<SessionJournal>,
+    /// Banned identities and addresses, shared with the transport which enforces them
+    bans: Arc<RwLock<BanList>>,
+    /// Fastest race lap on each track
+    lap_records: LapRecords,
+    /// Set when public spectator feeds and replays are anonymized
+    pseudonyms: Option<Pseudonymizer>,
+    /// Timing of public sessions, served over HTTP
+    live_timing: LiveTiming,
+    /// Players whose car is sent as dashboard packets
+    dashboards: DashboardOutputs,
+    /// Socket dashboard packets are sent from; `None` when they are disabled
+    dashboard_socket: Option<std::net::UdpSocket>,
+    /// Set by an admin before a shutdown: no new sessions are created
+    draining: bool,
+    /// When the server started, for uptime in admin stats
+    started_at: std::time::Instant,
+    /// Game loop timing, for admin stats
+    tick_lag: Arc<TickLagMetrics>,
+    /// Instances behind this server when it is a cluster gateway
+    cluster: Option<ClusterRegistry>,
+    /// Who created each session, for `[session_limits]`
+    session_creators: SessionCreators,
+    /// Files of the loaded cars and tracks, for clients to download
+    content: Arc<ContentLibrary>,
+}
+
+/// A reserved session whose game state has not been built yet.
+///
+/// Copying a large track and spawning AI can take long enough to stall the game
+/// loop, so `ServerState::prepare_session` only gathers the inputs under the
+/// state lock and `build` runs on a blocking thread.
+struct PendingSession {
+    session: RaceSession,
+    track: Arc<TrackConfig>,
+    car_configs: HashMap<CarConfigId, CarConfig>,
+    warmup: WarmupSettings,
+    collisions: CollisionSettings,
+    timing: TickTiming,
+    pseudonyms: Option<Pseudonymizer>,
+}
+
+impl PendingSession {
+    fn session_id(&self) -> SessionId {
+        self.session.id
+    }
+
+    fn build(self) -> GameSession {
+        use crate::ai_driver::generate_default_ai_profiles;
+
+        let session_id = self.session.id;
+        let ai_count = self.session.ai_count;
+        let track = Arc::unwrap_or_clone(self.track);
+
+        // Create game session with AI profiles if AI count is specified
+        let mut game_session = if ai_count > 0 {
+            GameSession::with_ai_profiles(self.session, track, self.car_configs, generate_default_ai_profiles(ai_count))
+        } else {
+            GameSession::new(self.session, track, self.car_configs)
+        };
+        game_session.warmup = self.warmup;
+        game_session.collisions = self.collisions;
+        game_session.timing = self.timing;
+        game_session.broadcaster.set_pseudonyms(self.pseudonyms);
+
+        // Spawn AI drivers immediately
+        if ai_count > 0 {
+            game_session.spawn_ai_drivers();
+            info!("Spawned {} AI drivers for session {}", ai_count, session_id);
+        }
+
+        game_session
+    }
+}
+
+/// Car with this ID or name, ignoring case
+fn find_car_mut<'a>(car_configs: &'a mut HashMap<CarConfigId, CarConfig>, key: &str) -> Option<&'a mut CarConfig> {
+    car_configs.values_mut().find(|c| c.id.to_string() == key || c.name.eq_ignore_ascii_case(key))
+}
+
+impl ServerState {
+    /// State with the configured content loaded and no sessions
+    pub fn new(config: ServerConfig) -> Self {
+        let mut car_configs = HashMap::new();
+        let mut track_configs = HashMap::new();
+
+        // Load custom cars from configured directory
+        let cars_dir = config.content.cars_dir.clone();
+        info!("Loading cars from {}...", cars_dir);
+        Self::load_custom_cars(&mut car_configs, &cars_dir);
+
+        for (key, balance) in &config.balance_of_performance.cars {
+            match find_car_mut(&mut car_configs, key) {
+                Some(car) => {
+                    info!("Balance of Performance for {}: {:?}", car.name, balance);
+                    *car = balance.apply(car);
+                }
+                None => warn!("Balance of Performance for unknown car {}", key),
+            }
+        }
+
+        if car_configs.is_empty() {
+            warn!("No cars loaded! Creating default car.");
+            let default_car = CarConfig::default();
+            car_configs.insert(default_car.id, default_car);
+        } else {
+            // Balance report, quickest first
+            let mut cars: Vec<&CarConfig> = car_configs.values().collect();
+            cars.sort_by(|a, b| b.power_to_weight_w_per_kg().total_cmp(&a.power_to_weight_w_per_kg()));
+            info!("Loaded {} car(s):", car_configs.len());
+            for car in cars {
+                info!("  - {} (ID: {}): {:.0} kW, {:.0} kg, {:.0} W/kg, top speed {:.0} km/h",
+                    car.name, car.id, car.engine.peak_power_w() / 1000.0, car.mass_kg,
+                    car.power_to_weight_w_per_kg(), car.theoretical_top_speed_mps() * 3.6);
+            }
+        }
+
+        // Load custom tracks from configured directory
+        let tracks_dir = config.content.tracks_dir.clone();
+        info!("Loading tracks from {}...", tracks_dir);
+        Self::load_custom_tracks(&mut track_configs, &tracks_dir);
+
+        if track_configs.is_empty() {
+            warn!("No tracks loaded! Server will not be able to create sessions.");
+        } else {
+            info!("Loaded {} track(s):", track_configs.len());
+            for track in track_configs.values() {
+                info!("  - {} (ID: {})", track.name, track.id);
+            }
+        }
+
+        let mut content = ContentLibrary::new();
+        let content_root = |dir: &str| std::path::Path::new(dir).parent().unwrap_or(std::path::Path::new(dir)).to_path_buf();
+        for car in car_configs.values() {
+            content.add_car(car, &content_root(&cars_dir));
+        }
+        for track in track_configs.values() {
+            content.add_track(track, &content_root(&tracks_dir));
+        }
+
+        let bans = BanList::load(&config.server.ban_list_path).unwrap_or_else(|e| {
+            warn!("Failed to load ban list from {}: {}; starting with no bans", config.server.ban_list_path, e);
+            BanList::new()
+        });
+        let lap_records = LapRecords::load(&config.server.lap_records_path).unwrap_or_else(|e| {
+            warn!("Failed to load lap records from {}: {}; starting with none", config.server.lap_records_path, e);
+            LapRecords::new()
+        });
+
+        let pseudonyms = config
+            .privacy
+            .anonymize_public_data
+            .then(|| Pseudonymizer::new(&config.privacy.pseudonym_secret));
+        let mut replay = ReplayManager::new(std::path::PathBuf::from("./replays"));
+        replay.set_pseudonyms(pseudonyms.clone());
+        let saves = SessionSaves::new(std::path::PathBuf::from(&config.session_saves.dir));
+        let journal = config.journal.enabled.then(|| SessionJournal::new(std::path::PathBuf::from(&config.journal.path)));
+        let dashboard_socket = if config.dashboard.enabled {
+            // Packets are dropped rather than stalling the game loop when the socket is busy
+            match std::net::UdpSocket::bind(&config.dashboard.bind).and_then(|socket| socket.set_nonblocking(true).map(|_| socket)) {
+                Ok(socket) => Some(socket),
+                Err(e) => {
+                    warn!("Failed to bind dashboard socket to {}: {}; dashboard output disabled", config.dashboard.bind, e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let cluster = (config.cluster.role == ClusterRole::Gateway)
+            .then(|| ClusterRegistry::new(Duration::from_millis(config.cluster.instance_timeout_ms)));
+
+        Self {
+            config,
+            car_configs,
+            track_configs,
+            sessions: HashMap::new(),
+            preparing: HashSet::new(),
+            players: HashMap::new(),
+            admins: HashSet::new(),
+            lobby: LobbyManager::new(),
+            replay,
+            replication: None,
+            resume_tokens: HashMap::new(),
+            saves,
+            journal,
+            bans: Arc::new(RwLock::new(bans)),
+            lap_records,
+            pseudonyms,
+            live_timing: LiveTiming::new(),
+            dashboards: DashboardOutputs::new(),
+            dashboard_socket,
+            draining: false,
+            started_at: std::time::Instant::now(),
+            tick_lag: Arc::new(TickLagMetrics::new()),
+            cluster,
+            session_creators: SessionCreators::new(),
+            content: Arc::new(content),
+        }
+    }
+
+    pub fn config(&self) -> &ServerConfig {
+        &self.config
+    }
+
+    pub fn car_configs(&self) -> &HashMap<CarConfigId, CarConfig> {
+        &self.car_configs
+    }
+
+    pub fn track_configs(&self) -> &HashMap<TrackConfigId, Arc<TrackConfig>> {
+        &self.track_configs
+    }
+
+    pub fn sessions(&self) -> &HashMap<SessionId, GameSession> {
+        &self.sessions
+    }
+
+    pub fn players(&self) -> &HashMap<PlayerId, Player> {
+        &self.players
+    }
+
+    pub fn lobby(&self) -> &LobbyManager {
+        &self.lobby
+    }
+
+    pub fn replay(&self) -> &ReplayManager {
+        &self.replay
+    }
+
+    /// Instances routed to when this server is a cluster gateway
+    pub fn cluster(&self) -> Option<&ClusterRegistry> {
+        self.cluster.as_ref()
+    }
+
+    /// Load a track file written while the server runs, such as a generated
+    /// circuit, and offer it like the tracks loaded at startup
+    fn add_track_file(&mut self, path: &std::path::Path) -> Result<Arc<TrackConfig>, String> {
+        let tracks_dir = std::path::Path::new(&self.config.content.tracks_dir);
+        let content_root = tracks_dir.parent().unwrap_or(tracks_dir);
+        let mut track = TrackLoader::load_from_file(path).map_err(|e| format!("Failed to load {}: {}", path.display(), e))?;
+        let rel = path.strip_prefix(content_root).unwrap_or(path);
+        track.source_path = Some(rel.to_string_lossy().replace('\\', "/"));
+        Arc::make_mut(&mut self.content).add_track(&track, content_root);
+        let track = Arc::new(track);
+        self.track_configs.insert(track.id, Arc::clone(&track));
+        Ok(track)
+    }
+
+    fn load_custom_tracks(track_configs: &mut HashMap<TrackConfigId, Arc<TrackConfig>>, tracks_dir_str: &str) {
+        let tracks_dir = std::path::Path::new(tracks_dir_str);
+
+        // Content root is the parent of the tracks directory (e.g., ../content)
+        let content_root = tracks_dir.parent().unwrap_or(tracks_dir);
+
+        if !tracks_dir.exists() {
+            info!("Tracks directory not found at {:?}, skipping custom track loading", tracks_dir);
+            return;
+        }
+
+        Self::load_tracks_recursive(track_configs, tracks_dir, content_root);
+    }
+
+    fn load_tracks_recursive(
+        track_configs: &mut HashMap<TrackConfigId, Arc<TrackConfig>>,
+        dir: &std::path::Path,
+        content_root: &std::path::Path,
+    ) {
+        match std::fs::read_dir(dir) {
+            Ok(entries) => {
+                for entry in entries.filter_map(|e| e.ok()) {
+                    let path = entry.path();
+                    if path.is_dir() {
+                        // Recursively load tracks from subdirectories
+                        Self::load_tracks_recursive(track_configs, &path, content_root);
+                    } else if path.is_file() {
+                        let ext = path.extension().and_then(|s| s.to_str());
+                        let is_track = ext == Some("json") || ext == Some("yaml") || ext == Some("yml");
+                        if is_track && !TrackLoader::is_sidecar_file(&path) {
+                            match TrackLoader::load_from_file(&path) {
+                                Ok(mut track) => {
+                                    // Compute relative path from content root, normalize to forward slashes
+                                    let rel = path.strip_prefix(content_root).unwrap_or(&path);
+                                    let rel_norm = rel.to_string_lossy().replace('\\', "/");
+                                    track.source_path = Some(rel_norm);
+                                    track_configs.insert(track.id, Arc::new(track));
+                                }
+                                Err(e) => {
+                                    warn!("Failed to load track from {:?}: {}", path, e);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                warn!("Failed to read tracks directory {:?}: {}", dir, e);
+            }
+        }
+    }
+
+    fn load_custom_cars(car_configs: &mut HashMap<CarConfigId, CarConfig>, cars_dir_str: &str) {
+        let cars_dir = std::path::Path::new(cars_dir_str);
+
+        if !cars_dir.exists() {
+            info!("Cars directory not found at {:?}, skipping custom car loading", cars_dir);
+            return;
+        }
+
+        // Content root is the parent of the cars directory (e.g., ../content)
+        let content_root = cars_dir.parent().unwrap_or(cars_dir);
+
+        Self::load_cars_recursive(car_configs, cars_dir, content_root);
+    }
+
+    fn load_cars_recursive(
+        car_configs: &mut HashMap<CarConfigId, CarConfig>,
+        dir: &std::path::Path,
+        content_root: &std::path::Path,
+    ) {
+        match std::fs::read_dir(dir) {
+            Ok(entries) => {
+                for entry in entries.filter_map(|e| e.ok()) {
+                    let path = entry.path();
+                    if path.is_dir() {
+                        // Recursively load cars from subdirectories
+                        Self::load_cars_recursive(car_configs, &path, content_root);
+                    } else if path.is_file() {
+                        let ext = path.extension().and_then(|s| s.to_str());
+                        if ext == Some("toml") {
+                            // Check if this is a car.toml file
+                            if path.file_name().and_then(|s| s.to_str()) == Some("car.toml") {
+                                match CarLoader::load_from_file(&path) {
+                                    Ok(mut car) => {
+                                        for warning in CarLoader::validate(&car) {
+                                            warn!("Car {:?}: {}", path, warning);
+                                        }
+                                        let rel = path.strip_prefix(content_root).unwrap_or(&path);
+                                        car.source_path = Some(rel.to_string_lossy().replace('\\', "/"));
+                                        car_configs.insert(car.id, car);
+                                    }
+                                    Err(e) => {
+                                        warn!("Failed to load car from {:?}: {}", path, e);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                warn!("Failed to read cars directory {:?}: {}", dir, e);
+            }
+        }
+    }
+
+    #[allow(dead_code, clippy::too_many_arguments)]
+    fn create_session(
+        &mut self,
+        host_player_id: PlayerId,
+        host_car_id: CarConfigId,
+        track_config_id: TrackConfigId,
+        session_kind: SessionKind,
+        max_players: u8,
+        ai_count: u8,
+        lap_limit: u8,
+    ) -> Option<SessionId> {
+        let pending = self.prepare_session(host_player_id, host_car_id, track_config_id, session_kind, max_players, ai_count, lap_limit)?;
+        let session_id = pending.session_id();
+        self.preparing.remove(&session_id);
+        self.sessions.insert(session_id, pending.build());
+
+        Some(session_id)
+    }
+
+    /// Session tick rate and physics sub-steps from the server config
+    fn tick_timing(&self) -> TickTiming {
+        TickTiming::new(self.config.server.tick_rate_hz, self.config.physics.substeps)
+    }
+
+    /// Reserve a session slot and gather what building the session needs.
+    ///
+    /// The session counts against `max_sessions` until it is inserted or abandoned.
+    #[allow(clippy::too_many_arguments)]
+    fn prepare_session(
+        &mut self,
+        host_player_id: PlayerId,
+        host_car_id: CarConfigId,
+        track_config_id: TrackConfigId,
+        session_kind: SessionKind,
+        max_players: u8,
+        ai_count: u8,
+        lap_limit: u8,
+    ) -> Option<PendingSession> {
+        let mut session = RaceSession::new(host_player_id, track_config_id, session_kind, max_players, ai_count, lap_limit);
+        session.host_car_id = Some(host_car_id);
+        self.reserve_session(session)
+    }
+
+    /// Reserve a slot for a session that plays back a replay to `viewer_id`
+    fn prepare_replay_session(&mut self, viewer_id: PlayerId, track_config_id: TrackConfigId) -> Option<PendingSession> {
+        let session = RaceSession::new(viewer_id, track_config_id, SessionKind::Practice, 0, 0, 0);
+        self.reserve_session(session)
+    }
+
+    /// Instance a new session should be created on instead of this gateway, if
+    /// one has more room; an error when none can take it and the gateway does
+    /// not host sessions itself
+    fn route_new_session(&self) -> Result<Option<String>, String> {
+        let Some(cluster) = &self.cluster else {
+            return Ok(None);
+        };
+        let local_load = (self.config.cluster.local_sessions && !self.draining)
+            .then(|| session_load(self.sessions.len() + self.preparing.len(), self.config.server.max_sessions))
+            .filter(|load| *load < 1.0);
+        match cluster.least_loaded(local_load) {
+            Some(address) => Ok(Some(address)),
+            None if self.config.cluster.local_sessions => Ok(None),
+            None => Err("No instance has room for another session".to_string()),
+        }
+    }
+
+    /// Whether `player_id`, connected from `ip`, may create another session;
+    /// admins always may
+    fn check_session_limits(&mut self, player_id: PlayerId, ip: std::net::IpAddr) -> Result<(), SessionLimit> {
+        if self.admins.contains(&player_id) {
+            return Ok(());
+        }
+        let (sessions, preparing) = (&self.sessions, &self.preparing);
+        self.session_creators.check(&self.config.session_limits, player_id, ip, std::time::Instant::now(), |session_id| {
+            sessions.contains_key(&session_id) || preparing.contains(&session_id)
+        })
+    }
+
+    /// Instance hosting `session_id` when it is not one of this gateway's sessions
+    fn instance_hosting(&self, session_id: SessionId) -> Option<String> {
+        if self.sessions.contains_key(&session_id) || self.preparing.contains(&session_id) {
+            return None;
+        }
+        self.cluster.as_ref()?.instance_hosting(session_id)
+    }
+
+    fn reserve_session(&mut self, session: RaceSession) -> Option<PendingSession> {
+        if self.draining || self.sessions.len() + self.preparing.len() >= self.config.server.max_sessions as usize {
+            return None;
+        }
+
+        let track = Arc::clone(self.track_configs.get(&session.track_config_id)?);
+        self.preparing.insert(session.id);
+
+        Some(PendingSession {
+            session,
+            track,
+            car_configs: self.car_configs.clone(),
+            warmup: WarmupSettings::from_preset(
+                self.config.physics.realism,
+                self.config.physics.ambient_temp_c,
+            ),
+            collisions: self.config.physics.collisions,
+            timing: self.tick_timing(),
+            pseudonyms: self.pseudonyms.clone(),
+        })
+    }
+
+    /// Move a human player between two lobby-state sessions, keeping their car.
+    ///
+    /// Returns the player's grid position in the target session.
+    async fn move_player_to_session(&mut self, player_id: PlayerId, target_session_id: SessionId) -> Result<u8, String> {
+        let source_session_id = self.lobby.get_player_session(player_id).await
+            .ok_or_else(|| format!("Player {} is not in a session", player_id))?;
+
+        let (car_id, livery) = self.sessions.get(&source_session_id)
+            .and_then(|s| s.session.participants.get(&player_id))
+            .map(|s| (s.car_config_id, s.livery))
+            .ok_or_else(|| format!("Player {} has no car in session {}", player_id, source_session_id))?;
+
+        let target = self.sessions.get(&target_session_id)
+            .ok_or_else(|| format!("Session {} not found", target_session_id))?;
+        if target.session.participants.len() >= target.session.max_players as usize {
+            return Err(format!("Session {} is full", target_session_id));
+        }
+
+        if !self.lobby.move_player(player_id, target_session_id).await {
+            return Err(format!("Cannot move player {} to session {}", player_id, target_session_id));
+        }
+
+        let grid_position = self.sessions.get_mut(&target_session_id).and_then(|s| {
+            let grid_position = s.add_player(player_id, car_id)?;
+            s.set_livery(player_id, livery);
+            Some(grid_position)
+        });
+
+        match grid_position {
+            Some(grid_position) => {
+                let (connection_id, setup, aids) = self.sessions.get_mut(&source_session_id).map(|source| {
+                    let connection_id = source.broadcaster.connection(&player_id);
+                    let setup = source.setup(&player_id).cloned();
+                    let aids = source.aids(&player_id);
+                    source.remove_player(&player_id);
+                    (connection_id, setup, aids)
+                }).unwrap_or_default();
+                if let Some(target) = self.sessions.get_mut(&target_session_id) {
+                    if let Some(connection_id) = connection_id {
+                        target.broadcaster.bind(player_id, connection_id);
+                    }
+                    // A setup only carries over where setups are open
+                    if let Some(setup) = setup {
+                        let _ = target.apply_setup(player_id, setup);
+                    }
+                    // Aids carry over as far as the target session allows
+                    let aids = aids.capped(&target.aid_limits);
+                    let _ = target.set_aids(player_id, aids);
+                }
+                Ok(grid_position)
+            }
+            None => {
+                // No free grid slot on the target track, undo the lobby move
+                self.lobby.move_player(player_id, source_session_id).await;
+                Err(format!("No free grid slot in session {}", target_session_id))
+            }
+        }
+    }
+
+    /// Remove a player who disconnected or was kicked from their session and the lobby,
+    /// closing the session if no human is left in it
+    async fn remove_disconnected_player(&mut self, player_id: PlayerId, session_id: Option<SessionId>) {
+        self.dashboards.remove(&player_id);
+        if let Some(game_session) = session_id.and_then(|sid| self.sessions.get_mut(&sid)) {
+            game_session.remove_player(&player_id);
+        }
+
+        let (_, empty_session) = self.lobby.remove_player(player_id).await;
+        if let Some(session_id) = empty_session {
+            info!("Session {} is empty after player disconnect, removing it", session_id);
+            self.sessions.remove(&session_id);
+            self.lobby.unregister_session(session_id).await;
+        }
+    }
+
+    /// Split a lobby-state session into two grids by rating.
+    ///
+    /// The higher-rated half stays; the rest moves to a new session with the same
+    /// track and settings, hosted by its highest-rated player.
+    async fn split_session(&mut self, session_id: SessionId) -> Result<(SessionId, Vec<(PlayerId, u8)>), String> {
+        let game_session = self.sessions.get(&session_id)
+            .ok_or_else(|| format!("Session {} not found", session_id))?;
+        if game_session.session.state != SessionState::Lobby {
+            return Err(format!("Session {} is not in lobby state", session_id));
+        }
+
+        let mut ranked = Vec::new();
+        for player_id in self.lobby.get_session_players(session_id).await {
+            let rating = self.lobby.get_player_rating(player_id).await.unwrap_or(0);
+            ranked.push((player_id, rating));
+        }
+        if ranked.len() < 2 {
+            return Err(format!("Session {} needs at least two players to split", session_id));
+        }
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let lower_half: Vec<PlayerId> = ranked.split_off(ranked.len().div_ceil(2)).into_iter().map(|(id, _)| id).collect();
+
+        let new_host = lower_half[0];
+        let new_host_car = game_session.session.participants.get(&new_host)
+            .map(|s| s.car_config_id)
+            .ok_or_else(|| format!("Player {} has no car in session {}", new_host, session_id))?;
+        let track_config_id = game_session.session.track_config_id;
+        let session_kind = game_session.session.session_kind;
+        let max_players = game_session.session.max_players;
+        let lap_limit = game_session.session.lap_limit;
+        let setup_policy = game_session.setup_policy;
+        let collision_policy = game_session.collision_policy;
+        let time_of_day = game_session.session.time_of_day;
+        let aid_limits = game_session.aid_limits;
+
+        let lobby_info = self.lobby.get_session_info(session_id).await
+            .ok_or_else(|| format!("Session {} not registered in lobby", session_id))?;
+        let new_host_name = self.lobby.get_player(new_host).await
+            .map(|p| p.player_name)
+            .unwrap_or_else(|| "Unknown".to_string());
+
+        let new_session_id = self.create_session(new_host, new_host_car, track_config_id, session_kind, max_players, 0, lap_limit)
+            .ok_or_else(|| "Server is at max session capacity".to_string())?;
+        if let Some(new_session) = self.sessions.get_mut(&new_session_id) {
+            new_session.setup_policy = setup_policy;
+            new_session.collision_policy = collision_policy;
+            new_session.session.time_of_day = time_of_day;
+            new_session.aid_limits = aid_limits;
+        }
+
+        self.lobby.register_session(crate::lobby::LobbySessionInfo {
+            session_id: new_session_id,
+            host_player_id: new_host,
+            host_name: new_host_name,
+            current_player_count: 0,
+            spectator_count: 0,
+            state: SessionState::Lobby,
+            created_at: std::time::Instant::now(),
+            always_open: false,
+            race_progress: None,
+            ..lobby_info
+        }).await;
+
+        let mut moved = Vec::new();
+        for player_id in lower_half {
+            match self.move_player_to_session(player_id, new_session_id).await {
+                Ok(grid_position) => moved.push((player_id, grid_position)),
+                Err(e) => warn!("Split of session {}: {}", session_id, e),
+            }
+        }
+
+        Ok((new_session_id, moved))
+    }
+
+    /// Merge an undersubscribed lobby-state session into another and close it.
+    async fn merge_sessions(&mut self, source_session_id: SessionId, target_session_id: SessionId) -> Result<Vec<(PlayerId, u8)>, String> {
+        if source_session_id == target_session_id {
+            return Err("Cannot merge a session into itself".to_string());
+        }
+
+        let source = self.sessions.get(&source_session_id)
+            .ok_or_else(|| format!("Session {} not found", source_session_id))?;
+        let target = self.sessions.get(&target_session_id)
+            .ok_or_else(|| format!("Session {} not found", target_session_id))?;
+        if source.session.state != SessionState::Lobby || target.session.state != SessionState::Lobby {
+            return Err("Both sessions must be in lobby state".to_string());
+        }
+
+        let players = self.lobby.get_session_players(source_session_id).await;
+        let free_slots = (target.session.max_players as usize).saturating_sub(target.session.participants.len());
+        if players.len() > free_slots {
+            return Err(format!(
+                "Session {} has {} free slot(s), {} needed",
+                target_session_id, free_slots, players.len()
+            ));
+        }
+
+        let mut moved = Vec::new();
+        for player_id in players {
+            let grid_position = self.move_player_to_session(player_id, target_session_id).await?;
+            moved.push((player_id, grid_position));
+        }
+
+        self.sessions.remove(&source_session_id);
+        self.lobby.unregister_session(source_session_id).await;
+
+        Ok(moved)
+    }
+
+    /// Create one hostless, always-open practice session per loaded track
+    async fn create_open_practice_sessions(&mut self) {
+        let mut tracks: Vec<TrackConfig> = self.track_configs.values().map(|t| TrackConfig::clone(t)).collect();
+        tracks.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let max_players = self.config.open_practice.max_players;
+        for track in tracks {
+            let track_name = track.name.clone();
+            let session_id = self.create_open_session(track, max_players, "Open Practice").await;
+            info!("Open practice session {} on {}", session_id, track_name);
+        }
+    }
+
+    /// Create a hostless free practice session that stays up while empty
+    async fn create_open_session(&mut self, track: TrackConfig, max_players: u8, host_name: &str) -> SessionId {
+        use crate::game_session::OpenPractice;
+        use crate::lobby::{LobbySessionInfo, SessionVisibility};
+
+        let reset_interval_ticks =
+            self.config.open_practice.reset_interval_minutes * 60 * self.config.server.tick_rate_hz as u32;
+
+        let session = RaceSession::new(PlayerId::nil(), track.id, SessionKind::Practice, max_players, 0, 0);
+        let session_id = session.id;
+        let track_config_id = track.id;
+        let track_name = track.name.clone();
+        let track_file = track.source_path.clone().unwrap_or_else(|| "tracks/unknown.yaml".to_string());
+
+        let mut game_session = GameSession::new(session, track, self.car_configs.clone());
+        game_session.warmup = WarmupSettings::from_preset(
+            self.config.physics.realism,
+            self.config.physics.ambient_temp_c,
+        );
+        game_session.collisions = self.config.physics.collisions;
+        game_session.timing = self.tick_timing();
+        game_session.broadcaster.set_pseudonyms(self.pseudonyms.clone());
+        game_session.set_game_mode(GameMode::FreePractice);
+        game_session.open_practice = Some(OpenPractice {
+            reset_interval_ticks,
+            last_reset_tick: 0,
+        });
+
+        self.lobby.register_session(LobbySessionInfo {
+            session_id,
+            host_player_id: PlayerId::nil(),
+            host_name: host_name.to_string(),
+            track_name,
+            track_file,
+            track_config_id,
+            session_kind: SessionKind::Practice,
+            max_players,
+            current_player_count: 0,
+            spectator_count: 0,
+            state: game_session.session.state,
+            visibility: SessionVisibility::Public,
+            password_hash: None,
+            created_at: std::time::Instant::now(),
+            always_open: true,
+            race_progress: None,
+            setup_policy: SetupPolicy::Open,
+            collision_policy: game_session.collision_policy,
+            time_of_day: game_session.session.time_of_day,
+            aid_limits: game_session.aid_limits,
+        }).await;
+        self.sessions.insert(session_id, game_session);
+        session_id
+    }
+
+    /// Adopt sessions rebuilt from a failed primary's replicated state
+    async fn recover_sessions(&mut self, recovered: Vec<RecoveredSession>) {
+        use crate::lobby::{LobbySessionInfo, SessionVisibility};
+
+        for RecoveredSession { mut game_session, host_name, resume_tokens } in recovered {
+            game_session.collisions = self.config.physics.collisions;
+            game_session.broadcaster.set_pseudonyms(self.pseudonyms.clone());
+            let session = &game_session.session;
+            let session_id = session.id;
+            let track = &game_session.track_config;
+
+            self.lobby.register_session(LobbySessionInfo {
+                session_id,
+                host_player_id: session.host_player_id,
+                host_name,
+                track_name: track.name.clone(),
+                track_file: track.source_path.clone().unwrap_or_else(|| "tracks/unknown.yaml".to_string()),
+                track_config_id: session.track_config_id,
+                session_kind: session.session_kind,
+                max_players: session.max_players,
+                current_player_count: 0, // resume_player increments this as players return
+                spectator_count: 0,
+                state: session.state,
+                visibility: SessionVisibility::Public,
+                password_hash: None,
+                created_at: std::time::Instant::now(),
+                always_open: false,
+                race_progress: None,
+                setup_policy: game_session.setup_policy,
+                collision_policy: game_session.collision_policy,
+                time_of_day: game_session.session.time_of_day,
+                aid_limits: game_session.aid_limits,
+            }).await;
+
+            for (token, player_id) in resume_tokens {
+                self.resume_tokens.insert(token, (session_id, player_id));
+            }
+            self.sessions.insert(session_id, game_session);
+        }
+    }
+
+    /// Save a running session to disk, returning the drivers given a resume token by this save
+    async fn save_session(&mut self, session_id: SessionId) -> Result<Vec<(PlayerId, uuid::Uuid)>, String> {
+        let game_session = self.sessions.get(&session_id)
+            .ok_or_else(|| format!("Session {} not found", session_id))?;
+        if game_session.session.game_mode == GameMode::Replay {
+            return Err("Replays cannot be saved".to_string());
+        }
+        let host_name = self.lobby.get_player(game_session.session.host_player_id).await
+            .map(|p| p.player_name)
+            .unwrap_or_else(|| "Unknown".to_string());
+        self.saves.save(game_session, host_name).await
+            .map_err(|e| format!("Failed to save session {}: {}", session_id, e))
+    }
+
+    /// Bring a saved session back, counting toward `max_sessions`
+    async fn resume_saved_session(&mut self, session_id: SessionId) -> Result<(), String> {
+        if self.sessions.contains_key(&session_id) || self.preparing.contains(&session_id) {
+            return Err(format!("Session {} is already running", session_id));
+        }
+        if self.sessions.len() + self.preparing.len() >= self.config.server.max_sessions as usize {
+            return Err("Server is at its session limit".to_string());
+        }
+
+        let saved = self.saves.load(session_id).await
+            .map_err(|e| format!("Failed to load save of session {}: {}", session_id, e))?;
+        let track = self.track_configs.get(&saved.state.session.track_config_id)
+            .ok_or_else(|| format!("Track {} of the save is not installed", saved.track_name))?;
+        let host_name = saved.host_name.clone();
+        let resume_tokens = saved.resume_tokens.clone();
+        let game_session = saved.resume(TrackConfig::clone(track), self.car_configs.clone());
+        info!("Resumed session {} at tick {}", session_id, game_session.session.current_tick);
+
+        self.recover_sessions(vec![RecoveredSession { game_session, host_name, resume_tokens }]).await;
+        Ok(())
+    }
+
+    /// Rebuild the sessions a crashed server left in its journal, with their cars parked
+    async fn restore_journal(&mut self) {
+        let Some(journal) = &self.journal else {
+            return;
+        };
+        let snapshots = match journal.load().await {
+            Ok(snapshots) => snapshots,
+            Err(e) => {
+                warn!("Failed to read the session journal: {}", e);
+                return;
+            }
+        };
+        if snapshots.is_empty() {
+            return;
+        }
+
+        let warmup = WarmupSettings::from_preset(self.config.physics.realism, self.config.physics.ambient_temp_c);
+        let timing = self.tick_timing();
+        let mut restored = Vec::new();
+        for snapshot in snapshots {
+            let Some(mut session) = snapshot.rebuild(&self.track_configs, &self.car_configs, &warmup, timing) else {
+                continue;
+            };
+            park_cars(&mut session.game_session);
+            self.saves.keep_tokens(session.game_session.session.id, &session.resume_tokens);
+            restored.push(session);
+        }
+        warn!(
+            "The server did not shut down cleanly; restored {} session(s) from the journal with their cars parked",
+            restored.len()
+        );
+        self.recover_sessions(restored).await;
+    }
+
+    /// Journal every session but replays and open practice, returning the
+    /// drivers given a resume token by this write, per session
+    async fn write_journal(&mut self) -> Vec<(SessionId, Vec<(PlayerId, uuid::Uuid)>)> {
+        let Some(journal) = &self.journal else {
+            return Vec::new();
+        };
+
+        let mut snapshots = Vec::new();
+        let mut issued = Vec::new();
+        for (session_id, game_session) in &self.sessions {
+            if game_session.session.game_mode == GameMode::Replay || game_session.open_practice.is_some() {
+                continue;
+            }
+            let host_name = self.lobby.get_player(game_session.session.host_player_id).await
+                .map(|p| p.player_name)
+                .unwrap_or_else(|| "Unknown".to_string());
+            let (resume_tokens, new_tokens) = self.saves.resume_tokens(&game_session.session);
+            if !new_tokens.is_empty() {
+                issued.push((*session_id, new_tokens));
+            }
+            snapshots.push(SessionSnapshot::new(game_session, host_name, resume_tokens));
+        }
+        if let Err(e) = journal.write(snapshots).await {
+            warn!("Failed to write the session journal: {}", e);
+        }
+        issued
+    }
+
+    /// Refresh the live timing of every public session; sessions that have
+    /// ended or are not public are taken off it
+    async fn publish_live_timing(&self) {
+        use crate::lobby::SessionVisibility;
+
+        let mut published = HashSet::new();
+        for (session_id, game_session) in &self.sessions {
+            let session = &game_session.session;
+            if session.game_mode == GameMode::Replay || session.state == SessionState::Lobby {
+                continue;
+            }
+            let public = self.lobby.get_session_info(*session_id).await
+                .is_some_and(|info| info.visibility == SessionVisibility::Public);
+            if !public {
+                continue;
+            }
+
+            let mut driver_names = HashMap::new();
+            for player_id in session.participants.keys() {
+                if let Some(player) = self.lobby.get_player(*player_id).await {
+                    driver_names.insert(*player_id, player.player_name);
+                }
+            }
+            let snapshot = TimingSnapshot::new(game_session, &driver_names, self.pseudonyms.as_ref());
+            self.live_timing.publish(snapshot).await;
+            published.insert(*session_id);
+        }
+        self.live_timing.retain(|session_id| published.contains(&session_id)).await;
+    }
+
+    /// Start recording a race from the session's current state. Human drivers
+    /// are named as the lobby knows them, AI drivers after their profile.
+    async fn start_replay_recording(&self, session_id: SessionId) {
+        use crate::replay::{ReplayMetadata, ReplayParticipant, REPLAY_FORMAT_VERSION};
+
+        let Some(game_session) = self.sessions.get(&session_id) else {
+            return;
+        };
+
+        let mut participants = Vec::new();
+        for (player_id, car) in &game_session.session.participants {
+            let player_name = match game_session.ai_profiles.get(player_id) {
+                Some(profile) => profile.name.clone(),
+                None => match self.lobby.get_player(*player_id).await {
+                    Some(player) => player.player_name,
+                    None => "Unknown".to_string(),
+                },
+            };
+            participants.push(ReplayParticipant {
+                player_id: *player_id,
+                player_name,
+                car_config_id: car.car_config_id,
+                finish_position: None,
+            });
+        }
+
+        let track_config_id = game_session.session.track_config_id;
+        let track_name = self.track_configs.get(&track_config_id)
+            .map(|t| t.name.clone())
+            .unwrap_or_else(|| "Unknown Track".to_string());
+
+        let metadata = ReplayMetadata {
+            format_version: REPLAY_FORMAT_VERSION,
+            session_id,
+            track_config_id,
+            track_name,
+            recorded_at: unix_now(),
+            duration_ticks: 0,
+            tick_rate: game_session.timing.tick_rate_hz,
+            participants,
+            highlights: Vec::new(),
+        };
+
+        self.replay.start_recording(metadata, game_session.simulation_state()).await;
+        info!("Started replay recording for session {}", session_id);
+    }
+
+    /// Write and post the results of a race that just finished
+    async fn export_results(&self, session_id: SessionId) {
+        let settings = &self.config.results_export;
+        let Some(game_session) = self.sessions.get(&session_id) else {
+            return;
+        };
+        if !settings.enabled || game_session.session.game_mode == GameMode::Replay {
+            return;
+        }
+
+        let mut driver_names = HashMap::new();
+        for player_id in game_session.session.participants.keys() {
+            if let Some(player) = self.lobby.get_player(*player_id).await {
+                driver_names.insert(*player_id, player.player_name);
+            }
+        }
+        let results = LeagueResults::new(game_session, &driver_names, &settings.teams);
+        let file_name = results.file_name(unix_now());
+        let directory = settings.directory.clone();
+        let webhook_url = settings.webhook_url.clone();
+
+        // Disk and network stay off the game loop
+        tokio::spawn(async move {
+            if !directory.is_empty() {
+                match write_results(std::path::Path::new(&directory), &file_name, &results).await {
+                    Ok(path) => info!("Results of session {} written to {:?}", session_id, path),
+                    Err(e) => warn!("Failed to write results of session {}: {}", session_id, e),
+                }
+            }
+            if !webhook_url.is_empty() {
+                match post_results(&webhook_url, &results).await {
+                    Ok(()) => info!("Results of session {} posted to {}", session_id, webhook_url),
+                    Err(e) => warn!("Failed to post results of session {}: {}", session_id, e),
+                }
+            }
+        });
+    }
+
+    /// Check the fastest lap of the human drivers of a race that just finished
+    /// against the track's lap record
+    async fn update_lap_record(&mut self, session_id: SessionId) {
+        let Some(game_session) = self.sessions.get(&session_id) else {
+            return;
+        };
+        if game_session.session.game_mode == GameMode::Replay {
+            return;
+        }
+        let fastest = game_session
+            .session
+            .participants
+            .values()
+            .filter(|state| !game_session.ai_profiles.contains_key(&state.player_id))
+            .filter_map(|state| Some((state.best_lap_time_ms?, state)))
+            .min_by_key(|(lap_time_ms, _)| *lap_time_ms);
+        let Some((lap_time_ms, state)) = fastest else {
+            return;
+        };
+        let Some(player) = self.lobby.get_player(state.player_id).await else {
+            return;
+        };
+        let record = LapRecord {
+            lap_time_ms,
+            driver_name: player.player_name,
+            car_name: game_session.car_configs.get(&state.car_config_id).map(|car| car.name.clone()).unwrap_or_default(),
+            set_at: unix_now(),
+        };
+        let track = &game_session.track_config;
+        let (track_id, track_name) = (track.id, track.name.clone());
+        match self.lap_records.submit(track_id, &track_name, record.clone()) {
+            Ok(true) => info!("Lap record on {}: {} ms by {} ({})", track_name, record.lap_time_ms, record.driver_name, record.car_name),
+            Ok(false) => {}
+            Err(e) => warn!("Failed to save the lap record of {}: {}", track_name, e),
+        }
+    }
+
+    /// Send the dashboard packets due this tick
+    fn send_dashboard_packets(&self) {
+        let Some(socket) = &self.dashboard_socket else {
+            return;
+        };
+        if self.dashboards.is_empty() {
+            return;
+        }
+
+        for game_session in self.sessions.values() {
+            for (player_id, addr) in self.dashboards.due(&game_session.session) {
+                let Some(packet) = game_session.dashboard_packet(&player_id) else {
+                    continue;
+                };
+                if let Err(e) = socket.send_to(&packet.to_bytes(), addr) {
+                    debug!("Dropped dashboard packet to {}: {}", addr, e);
+                }
+            }
+        }
+    }
+
+    /// A running session as the admin API lists it
+    async fn admin_session(&self, session_id: SessionId) -> Option<AdminSession> {
+        let game_session = self.sessions.get(&session_id)?;
+        let info = self.lobby.get_session_info(session_id).await;
+        let session = &game_session.session;
+        Some(AdminSession {
+            session_id,
+            track_name: game_session.track_config.name.clone(),
+            host_name: info.as_ref().map(|info| info.host_name.clone()).unwrap_or_default(),
+            session_kind: session.session_kind,
+            state: session.state,
+            game_mode: session.game_mode,
+            drivers: session.participants.len(),
+            spectators: self.lobby.get_session_spectators(session_id).await.len(),
+            max_players: session.max_players,
+            lap_limit: session.lap_limit,
+            server_tick: session.current_tick,
+            balance: game_session.balance()
+                .map(|(car_id, balance)| {
+                    let name = game_session.car_configs.get(&car_id).map_or_else(|| car_id.to_string(), |c| c.name.clone());
+                    (name, *balance)
+                })
+                .collect(),
+        })
+    }
+
+    /// Apply settings changed through the admin API
+    async fn update_session_settings(&mut self, session_id: SessionId, update: &SessionUpdate) -> Result<(), AdminError> {
+        let game_session = self.sessions.get_mut(&session_id)
+            .ok_or_else(|| AdminError::not_found(format!("Session {} not found", session_id)))?;
+        let session = &mut game_session.session;
+
+        if update.lap_limit.is_some() && session.state != SessionState::Lobby {
+            return Err(AdminError::conflict("Lap limit can only change before the session starts"));
+        }
+        if update.balance.is_some() && session.state != SessionState::Lobby {
+            return Err(AdminError::conflict("Balance of Performance can only change before the session starts"));
+        }
+        // Check every car before changing any
+        let mut balance = Vec::new();
+        for (key, car_balance) in update.balance.iter().flatten() {
+            let car_id = game_session.car_configs.values()
+                .find(|c| c.id.to_string() == *key || c.name.eq_ignore_ascii_case(key))
+                .map(|c| c.id)
+                .ok_or_else(|| AdminError::not_found(format!("Car {} not found", key)))?;
+            car_balance.validate().map_err(|e| AdminError::bad_request(format!("{}: {}", key, e)))?;
+            balance.push((car_id, *car_balance));
+        }
+        if let Some(max_players) = update.max_players {
+            if max_players == 0 || (max_players as usize) < session.participants.len() {
+                return Err(AdminError::conflict(format!(
+                    "Session has {} cars; max players must be at least that and above 0",
+                    session.participants.len()
+                )));
+            }
+        }
+
+        if let Some(lap_limit) = update.lap_limit {
+            game_session.session.lap_limit = lap_limit;
+        }
+        if let Some(max_players) = update.max_players {
+            game_session.session.max_players = max_players;
+            self.lobby.set_max_players(session_id, max_players).await;
+        }
+        if update.balance.as_ref().is_some_and(|b| b.is_empty()) {
+            let cars: Vec<CarConfigId> = game_session.balance().map(|(car_id, _)| car_id).collect();
+            balance.extend(cars.into_iter().map(|car_id| (car_id, BalanceOfPerformance::default())));
+        }
+        for (car_id, car_balance) in balance {
+            game_session.set_balance(car_id, car_balance).map_err(AdminError::bad_request)?;
+        }
+        info!("Admin API changed session {}: {:?}", session_id, update);
+        Ok(())
+    }
+
+    /// Reply to the session save admin messages
+    async fn saved_session_list(&self) -> crate::network::ServerMessage {
+        use crate::network::{SavedSessionListData, SavedSessionSummary, ServerMessage};
+
+        match self.saves.list().await {
+            Ok(mut saves) => {
+                saves.sort_by_key(|saved| std::cmp::Reverse(saved.saved_at));
+                ServerMessage::SavedSessionList(SavedSessionListData {
+                    sessions: saves.iter().map(|saved| {
+                        let session = &saved.state.session;
+                        SavedSessionSummary {
+                            session_id: session.id,
+                            track_name: saved.track_name.clone(),
+                            host_name: saved.host_name.clone(),
+                            saved_at: saved.saved_at,
+                            server_tick: session.current_tick,
+                            game_mode: session.game_mode,
+                            lap: session.participants.values().map(|s| s.current_lap).max().unwrap_or(0),
+                            lap_limit: session.lap_limit,
+                            driver_count: saved.resume_tokens.len() as u8,
+                            active: self.sessions.contains_key(&session.id),
+                        }
+                    }).collect(),
+                })
+            }
+            Err(e) => {
+                warn!("Failed to list session saves: {}", e);
+                ServerMessage::Error {
+                    code: 500,
+                    message: "Failed to list session saves".to_string(),
+                }
+            }
+        }
+    }
+}
+
+/// Hand drivers the resume tokens a session save gave them
+async fn notify_saved_drivers(transport: &TransportLayer, session_id: SessionId, issued: &[(PlayerId, uuid::Uuid)]) {
+    use crate::network::{ServerMessage, SessionSavedData};
+
+    for (player_id, resume_token) in issued {
+        if let Some(conn_id) = transport.get_player_connection(*player_id).await {
+            let _ = transport.send_tcp(conn_id, ServerMessage::SessionSaved(SessionSavedData {
+                session_id,
+                resume_token: *resume_token,
+            })).await;
+        }
+    }
+}
+
+/// Stream this tick's inputs to the standby, plus a snapshot of every session when one is due.
+///
+/// Players are sent the standby address and their resume token the first time
+/// they appear in a snapshot.
+async fn replicate_sessions(
+    state: &mut ServerState,
+    transport: &RwLock<TransportLayer>,
+    inputs: Vec<(SessionId, u32, HashMap<PlayerId, PlayerInputData>)>,
+    tick_count: u64,
+) {
+    use crate::network::{ServerMessage, SessionRedirectData};
+
+    let Some(replication) = state.replication.as_mut() else {
+        return;
+    };
+
+    for (session_id, tick, session_inputs) in &inputs {
+        replication.send_inputs(*session_id, *tick, session_inputs);
+    }
+
+    if !replication.snapshot_due(tick_count) {
+        return;
+    }
+
+    let mut snapshots = Vec::new();
+    let mut redirects = Vec::new();
+    for (session_id, game_session) in &state.sessions {
+        let host_name = state.lobby.get_player(game_session.session.host_player_id).await
+            .map(|p| p.player_name)
+            .unwrap_or_else(|| "Unknown".to_string());
+        snapshots.push(replication.snapshot(game_session, host_name));
+
+        for (player_id, resume_token) in replication.take_unannounced(&game_session.session) {
+            redirects.push((player_id, SessionRedirectData {
+                session_id: *session_id,
+                address: replication.redirect_addr.clone(),
+                resume_token,
+                immediate: false,
+            }));
+        }
+    }
+    replication.send_snapshot(snapshots);
+
+    let transport = transport.read().await;
+    for (player_id, redirect) in redirects {
+        if let Some(conn_id) = transport.get_player_connection(player_id).await {
+            let _ = transport.send_tcp(conn_id, ServerMessage::SessionRedirect(redirect)).await;
+        }
+    }
+}
+
+/// Take over the primary's sessions once it stops replicating
+async fn watch_primary(
+    state: Arc<RwLock<ServerState>>,
+    standby: Arc<tokio::sync::Mutex<StandbyState>>,
+    failover_timeout: Duration,
+) {
+    let mut ticker = interval(Duration::from_millis(250));
+    loop {
+        ticker.tick().await;
+
+        let mut standby = standby.lock().await;
+        if !standby.should_take_over(std::time::Instant::now(), failover_timeout) {
+            continue;
+        }
+
+        let mut state_write = state.write().await;
+        let warmup = WarmupSettings::from_preset(
+            state_write.config.physics.realism,
+            state_write.config.physics.ambient_temp_c,
+        );
+        let recovered = standby.take_over(
+            &state_write.track_configs,
+            &state_write.car_configs,
+            &warmup,
+            state_write.tick_timing(),
+        );
+        warn!("Primary lost, taking over {} session(s)", recovered.len());
+        state_write.recover_sessions(recovered).await;
+        return;
+    }
+}
+
+/// Tell every session player to reconnect to the standby now and ask it to take over
+async fn redirect_to_standby(state: &RwLock<ServerState>, transport: &RwLock<TransportLayer>) {
+    use crate::network::{ServerMessage, SessionRedirectData};
+
+    let mut state_write = state.write().await;
+    let state_write = &mut *state_write;
+    let Some(replication) = state_write.replication.as_mut() else {
+        return;
+    };
+
+    let transport = transport.read().await;
+    for (session_id, game_session) in &state_write.sessions {
+        for player_id in game_session.session.participants.keys() {
+            if game_session.session.ai_player_ids.contains(player_id) {
+                continue;
+            }
+            if let Some(conn_id) = transport.get_player_connection(*player_id).await {
+                let _ = transport.send_tcp(conn_id, ServerMessage::SessionRedirect(SessionRedirectData {
+                    session_id: *session_id,
+                    address: replication.redirect_addr.clone(),
+                    resume_token: replication.resume_token(*player_id),
+                    immediate: true,
+                })).await;
+            }
+        }
+    }
+
+    replication.handover().await;
+    // Give the replication link a moment to flush before the process exits
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    info!("Handed sessions over to standby");
+}
+
+/// Active bans, as sent to admins
+fn ban_list_message(bans: &BanList) -> crate::network::ServerMessage {
+    use crate::network::{BanListData, ServerMessage};
+
+    ServerMessage::BanList(BanListData {
+        bans: bans.active(unix_now()).map(Into::into).collect(),
+    })
+}
+
+/// Tell players that an admin moved them to another session
+async fn notify_moved_players(transport: &TransportLayer, session_id: SessionId, moved: &[(PlayerId, u8)]) {
+    use crate::network::ServerMessage;
+
+    for (player_id, grid_position) in moved {
+        if let Some(conn_id) = transport.get_player_connection(*player_id).await {
+            let _ = transport.send_tcp(conn_id, ServerMessage::SessionJoined(SessionJoinedData {
+                session_id,
+                your_grid_position: *grid_position,
+                delay_seconds: 0,
+            })).await;
+            transport.set_player_session(conn_id, Some(session_id)).await;
+        }
+    }
+}
+
+/// Stream a file from `offset` to one client, one message per chunk.
+///
+/// Chunks are critical messages, so a slow client holds the download back
+/// instead of losing parts of the file. At least one chunk is sent, so a
+/// client resuming a finished file still hears back. Returns the file size, or
+/// `None` when the connection went away.
+async fn send_file_chunks(
+    transport: &RwLock<TransportLayer>,
+    path: &std::path::Path,
+    mut offset: u64,
+    chunk_bytes: usize,
+    connection_id: ConnectionId,
+    chunk: impl Fn(u64, u64, Vec<u8>) -> ServerMessage,
+) -> std::io::Result<Option<u64>> {
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+    let mut file = tokio::fs::File::open(path).await?;
+    let total_bytes = file.metadata().await?.len();
+    file.seek(std::io::SeekFrom::Start(offset)).await?;
+    loop {
+        let mut data = vec![0u8; chunk_bytes.min(total_bytes.saturating_sub(offset) as usize)];
+        file.read_exact(&mut data).await?;
+        let len = data.len() as u64;
+        if transport.read().await.send_tcp(connection_id, chunk(offset, total_bytes, data)).await.is_err() {
+            return Ok(None);
+        }
+        offset += len;
+        if offset >= total_bytes {
+            return Ok(Some(total_bytes));
+        }
+    }
+}
+
+/// Send a replay file to one client as `ReplayChunk`s
+async fn send_replay_file(
+    transport: Arc<RwLock<TransportLayer>>,
+    replay_path: std::path::PathBuf,
+    session_id: SessionId,
+    connection_id: ConnectionId,
+) {
+    use crate::network::ReplayChunkData;
+
+    let sent = send_file_chunks(&transport, &replay_path, 0, REPLAY_CHUNK_BYTES, connection_id, |offset, total_bytes, data| {
+        ServerMessage::ReplayChunk(ReplayChunkData { session_id, offset, total_bytes, data })
+    })
+    .await;
+
+    match sent {
+        Ok(Some(total_bytes)) => info!("Sent replay {:?} ({} bytes) to connection {}", replay_path, total_bytes, connection_id),
+        Ok(None) => warn!("Stopped sending replay {:?}: connection {} is gone", replay_path, connection_id),
+        Err(e) => {
+            warn!("Failed to send replay {:?}: {}", replay_path, e);
+            let _ = transport.read().await.send_tcp(connection_id, ServerMessage::Error {
+                code: 500,
+                message: "Failed to read replay".to_string(),
+            }).await;
+        }
+    }
+}
+
+/// Claim a content transfer slot for an authenticated connection, telling the
+/// client why when it gets none
+async fn start_content_transfer(transport: &TransportLayer, connection_id: ConnectionId) -> Option<ContentTransferGuard> {
+    let refusal = match transport.get_connection(connection_id).await {
+        None => (401, "Log in before downloading content".to_string()),
+        Some(conn_info) => match conn_info.start_content_transfer(MAX_CONTENT_TRANSFERS_PER_CONNECTION) {
+            Some(transfer) => return Some(transfer),
+            None => (429, format!("At most {} content downloads may run at once", MAX_CONTENT_TRANSFERS_PER_CONNECTION)),
+        },
+    };
+    let (code, message) = refusal;
+    let _ = transport.send_tcp(connection_id, ServerMessage::Error { code, message }).await;
+    None
+}
+
+/// Send a car or track file to one client as `ContentChunk`s, from `offset`
+async fn send_content_file(
+    transport: Arc<RwLock<TransportLayer>>,
+    file: std::path::PathBuf,
+    path: String,
+    offset: u64,
+    max_file_bytes: u64,
+    connection_id: ConnectionId,
+) {
+    use crate::network::ContentChunkData;
+
+    let sent = async {
+        let size = tokio::fs::metadata(&file).await?.len();
+        if size > max_file_bytes {
+            return Ok(Err((413, format!("{} is {} bytes, over the server's limit of {}", path, size, max_file_bytes))));
+        }
+        if offset > size {
+            return Ok(Err((416, format!("Offset {} is past the end of {} ({} bytes)", offset, path, size))));
+        }
+        send_file_chunks(&transport, &file, offset, CONTENT_CHUNK_BYTES, connection_id, |offset, total_bytes, data| {
+            ServerMessage::ContentChunk(ContentChunkData { path: path.clone(), offset, total_bytes, data })
+        })
+        .await
+        .map(Ok)
+    }
+    .await;
+
+    let refusal = match sent {
+        Ok(Ok(Some(total_bytes))) => {
+            debug!("Sent {} from byte {} ({} bytes) to connection {}", path, offset, total_bytes, connection_id);
+            return;
+        }
+        Ok(Ok(None)) => {
+            warn!("Stopped sending {}: connection {} is gone", path, connection_id);
+            return;
+        }
+        Ok(Err(refusal)) => refusal,
+        Err(e) => {
+            warn!("Failed to send {}: {}", path, e);
+            (500, format!("Failed to read {}", path))
+        }
+    };
+    let (code, message) = refusal;
+    let _ = transport.read().await.send_tcp(connection_id, ServerMessage::Error { code, message }).await;
+}
+
+/// Load a replay and build its session off the game loop, then start playback
+/// with the viewer watching as a spectator.
+async fn open_replay_session(
+    state: Arc<RwLock<ServerState>>,
+    transport: Arc<RwLock<TransportLayer>>,
+    pending: PendingSession,
+    replay_path: std::path::PathBuf,
+    viewer_id: PlayerId,
+    connection_id: ConnectionId,
+) {
+    use crate::network::ServerMessage;
+
+    let session_id = pending.session_id();
+    let loaded = ReplayPlayer::open(replay_path).await;
+    let built = match loaded {
+        Ok(player) => tokio::task::spawn_blocking(move || {
+            let mut game_session = pending.build();
+            game_session.start_replay(player);
+            game_session
+        })
+        .await
+        .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+
+    let transport = transport.read().await;
+    let mut state_write = state.write().await;
+    state_write.preparing.remove(&session_id);
+
+    let game_session = match built {
+        Ok(game_session) => game_session,
+        Err(e) => {
+            warn!("Failed to open replay session {}: {}", session_id, e);
+            state_write.lobby.unregister_session(session_id).await;
+            let _ = transport.send_tcp(connection_id, ServerMessage::Error {
+                code: 500,
+                message: "Failed to load replay".to_string(),
+            }).await;
+            return;
+        }
+    };
+    state_write.sessions.insert(session_id, game_session);
+    state_write.lobby.set_session_state(session_id, SessionState::Racing).await;
+
+    if !state_write.lobby.join_as_spectator(viewer_id, session_id).await {
+        warn!("Viewer {} left before replay session {} was ready, closing it", viewer_id, session_id);
+        state_write.sessions.remove(&session_id);
+        state_write.lobby.unregister_session(session_id).await;
+        return;
+    }
+    if let Some(game_session) = state_write.sessions.get_mut(&session_id) {
+        game_session.broadcaster.bind(viewer_id, connection_id);
+    }
+    let _ = transport.send_tcp(connection_id, ServerMessage::SessionJoined(SessionJoinedData {
+        session_id,
+        your_grid_position: 0, // 0 indicates spectator
+        delay_seconds: 0,
+    })).await;
+    transport.set_player_session(connection_id, Some(session_id)).await;
+}
+
+/// Build a prepared session off the game loop, then open it and seat its host.
+///
+/// The session is listed as `Preparing` until the build is done, so no one can
+/// join it earlier. If the host has gone by then, the session is dropped.
+#[allow(clippy::too_many_arguments)]
+async fn open_prepared_session(
+    state: Arc<RwLock<ServerState>>,
+    transport: Arc<RwLock<TransportLayer>>,
+    pending: PendingSession,
+    weather: WeatherSettings,
+    setup_policy: SetupPolicy,
+    collision_policy: CollisionPolicy,
+    time_of_day: TimeOfDay,
+    aid_limits: DriverAids,
+    host_player_id: PlayerId,
+    connection_id: ConnectionId,
+) {
+    use crate::network::ServerMessage;
+
+    let session_id = pending.session_id();
+    let host_car_id = pending.session.host_car_id;
+    let started = std::time::Instant::now();
+    let built = tokio::task::spawn_blocking(move || pending.build()).await;
+
+    let transport = transport.read().await;
+    let mut state_write = state.write().await;
+    state_write.preparing.remove(&session_id);
+
+    let mut game_session = match built {
+        Ok(game_session) => game_session,
+        Err(e) => {
+            warn!("Failed to prepare session {}: {}", session_id, e);
+            state_write.lobby.unregister_session(session_id).await;
+            let _ = transport.send_tcp(connection_id, ServerMessage::Error {
+                code: 500,
+                message: "Failed to create session".to_string(),
+            }).await;
+            return;
+        }
+    };
+    info!("Session {} prepared in {:.0?}", session_id, started.elapsed());
+
+    game_session.weather = Weather::new(&weather);
+    game_session.setup_policy = setup_policy;
+    game_session.collision_policy = collision_policy;
+    game_session.session.time_of_day = time_of_day;
+    game_session.aid_limits = aid_limits;
+    state_write.sessions.insert(session_id, game_session);
+    state_write.lobby.set_session_state(session_id, SessionState::Lobby).await;
+
+    // Join host to their own session (lobby and game session)
+    if !state_write.lobby.join_session(host_player_id, session_id).await {
+        warn!("Host {} left before session {} was ready, closing it", host_player_id, session_id);
+        state_write.sessions.remove(&session_id);
+        state_write.lobby.unregister_session(session_id).await;
+        let _ = transport.send_tcp(connection_id, ServerMessage::Error {
+            code: 500,
+            message: "Failed to join session lobby".to_string(),
+        }).await;
+        return;
+    }
+
+    // Add host to the actual game session
+    let host_livery = state_write.lobby.get_player(host_player_id).await.map_or(0, |p| p.selected_livery);
+    let grid_pos = state_write.sessions.get_mut(&session_id)
+        .zip(host_car_id)
+        .and_then(|(game_session, car_id)| {
+            let grid_pos = game_session.add_player(host_player_id, car_id)?;
+            game_session.set_livery(host_player_id, host_livery);
+            game_session.broadcaster.bind(host_player_id, connection_id);
+            Some(grid_pos)
+        });
+    if let Some(grid_pos) = grid_pos {
+        let _ = transport.send_tcp(connection_id, ServerMessage::SessionJoined(SessionJoinedData {
+            session_id,
+            your_grid_position: grid_pos,
+            delay_seconds: 0,
+        })).await;
+        // Track that player is in a session
+        transport.set_player_session(connection_id, Some(session_id)).await;
+    } else {
+        warn!("Failed to add player {} to game session {}", host_player_id, session_id);
+        let _ = transport.send_tcp(connection_id, ServerMessage::Error {
+            code: 500,
+            message: "Failed to add player to game session".to_string(),
+        }).await;
+    }
+}
+
+/// Settings of a server about to start; see `ServerRuntime::builder`
+pub struct ServerRuntimeBuilder {
+    config: ServerConfig,
+    health_state: Option<HealthState>,
+    log_filter: Option<LogFilterHandle>,
+    /// Config file reloaded on SIGHUP, and whether it may replace the log filter
+    reload: Option<(String, bool)>,
+    open_practice: bool,
+    manual_ticks: bool,
+}
+
+impl ServerRuntimeBuilder {
+    /// Report through this health state, e.g. one whose error layer is
+    /// already part of the log subscriber
+    pub fn health_state(mut self, health_state: HealthState) -> Self {
+        self.health_state = Some(health_state);
+        self
+    }
+
+    /// Log filter the admin console's `log` command changes
+    pub fn log_filter(mut self, handle: LogFilterHandle) -> Self {
+        self.log_filter = Some(handle);
+        self
+    }
+
+    /// Reload `path` on SIGHUP; `reload_log_level` lets its `logging.level`
+    /// replace the log filter
+    pub fn reload_on_sighup(mut self, path: impl Into<String>, reload_log_level: bool) -> Self {
+        self.reload = Some((path.into(), reload_log_level));
+        self
+    }
+
+    /// Create the open practice sessions, as `[open_practice] enabled` does
+    pub fn open_practice(mut self, open_practice: bool) -> Self {
+        self.open_practice = open_practice;
+        self
+    }
+
+    /// Run no game loop: ticks happen only when `ServerRuntime::step` is called
+    pub fn manual_ticks(mut self, manual_ticks: bool) -> Self {
+        self.manual_ticks = manual_ticks;
+        self
+    }
+
+    /// Load the content, bind every listener and start serving
+    pub async fn start(self) -> Result<ServerRuntime, Box<dyn std::error::Error + Send + Sync>> {
+        let config = self.config;
+        let problems = config.validate();
+        if !problems.is_empty() {
+            return Err(format!("invalid config: {}", problems.join("; ")).into());
+        }
+        let health_state = self.health_state.unwrap_or_default();
+        let mut tasks = Vec::new();
+
+        info!("TCP bind: {}", config.network.tcp_bind);
+        info!("UDP bind: {}", config.network.udp_bind);
+        let timing = TickTiming::new(config.server.tick_rate_hz, config.physics.substeps);
+        info!("Tick rate: {}Hz, {} physics step(s) per tick", timing.tick_rate_hz, timing.substeps);
+
+        // Initialize server state
+        let state = Arc::new(RwLock::new(ServerState::new(config.clone())));
+
+        info!("Server initialized with {} car configs and {} track configs",
+            state.read().await.car_configs.len(),
+            state.read().await.track_configs.len());
+
+        // Hot standby replication
+        match config.replication.role {
+            ReplicationRole::Disabled => {}
+            ReplicationRole::Primary => {
+                info!("Replicating sessions to standby at {}", config.replication.standby_addr);
+                state.write().await.replication = Some(ReplicationPrimary::start(
+                    &config.replication,
+                    config.server.tick_rate_hz,
+                ));
+            }
+            ReplicationRole::Standby => {
+                let listener = tokio::net::TcpListener::bind(&config.replication.standby_addr).await?;
+                info!("Standing by for primary on {}", config.replication.standby_addr);
+                let standby = Arc::new(tokio::sync::Mutex::new(StandbyState::new()));
+                tasks.push(tokio::spawn(run_standby_listener(
+                    listener,
+                    config.replication.shared_secret.clone(),
+                    Arc::clone(&standby),
+                )));
+                tasks.push(tokio::spawn(watch_primary(
+                    Arc::clone(&state),
+                    standby,
+                    Duration::from_millis(config.replication.failover_timeout_ms),
+                )));
+            }
+        }
+
+        if self.open_practice || config.open_practice.enabled {
+            info!("OPEN PRACTICE MODE");
+            state.write().await.create_open_practice_sessions().await;
+        }
+        state.write().await.restore_journal().await;
+
+        // Start health check server
+        let health_bind = config.network.health_bind.clone();
+        let health_state_clone = health_state.clone();
+        let live_timing = state.read().await.live_timing.clone();
+        let live_timing = config.live_timing.enabled.then_some(live_timing);
+        tasks.push(tokio::spawn(async move {
+            if let Err(e) = run_health_server(health_bind, health_state_clone, live_timing).await {
+                warn!("Health server error: {}", e);
+            }
+        }));
+
+        // Initialize transport layer
+        let mut transport = match TransportLayer::new(
+            &config.network.tcp_bind,
+            &config.network.udp_bind,
+            &config.network.tls_cert_path,
+            &config.network.tls_key_path,
+            config.network.require_tls,
+            config.network.heartbeat_timeout_ms,
+        ).await {
+            Ok(t) => {
+                info!("Transport layer initialized successfully");
+                t
+            }
+            Err(e) => {
+                for task in &tasks {
+                    task.abort();
+                }
+                return Err(format!("Failed to initialize transport layer: {}", e).into());
+            }
+        };
+
+        // Start transport layer
+        transport.set_max_messages_per_second(config.network.max_messages_per_second);
+        transport.set_ban_list(Arc::clone(&state.read().await.bans));
+        transport.start().await;
+        transport.forward_lobby_updates(state.read().await.lobby.subscribe());
+        let tcp_messages = transport.take_tcp_receiver().expect("TCP messages are only taken once");
+        let udp_messages = transport.take_udp_receiver().expect("UDP messages are only taken once");
+        let transport = Arc::new(RwLock::new(transport));
+
+        // Admin API, gRPC control plane and admin console, whose commands may end the server early
+        let shutdown = Arc::new(Notify::new());
+        let console_enabled = config.admin_console.stdin || !config.admin_console.socket_path.is_empty();
+        if config.admin_api.enabled || config.grpc.enabled || console_enabled {
+            let (api, requests) = AdminApi::new(config.server.admin_tokens.clone());
+            if (config.admin_api.enabled || config.grpc.enabled) && config.server.admin_tokens.is_empty() {
+                warn!("Admin API enabled without admin_tokens; every request will be refused");
+            }
+            if config.admin_api.enabled {
+                let bind = config.admin_api.bind.clone();
+                let api = api.clone();
+                tasks.push(tokio::spawn(async move {
+                    if let Err(e) = run_admin_api_server(bind, api).await {
+                        warn!("Admin API error: {}", e);
+                    }
+                }));
+            }
+            if console_enabled {
+                let console = AdminConsole::new(api.clone(), self.log_filter.clone());
+                if config.admin_console.stdin {
+                    spawn_stdin_console(console.clone());
+                }
+                if !config.admin_console.socket_path.is_empty() {
+                    spawn_console_socket(config.admin_console.socket_path.clone(), console);
+                }
+            }
+            if config.grpc.enabled {
+                let bind = config.grpc.bind.clone();
+                tasks.push(tokio::spawn(async move {
+                    if let Err(e) = run_grpc_server(bind, api).await {
+                        warn!("gRPC control plane error: {}", e);
+                    }
+                }));
+            }
+            tasks.push(tokio::spawn(run_admin_requests(
+                Arc::clone(&state),
+                Arc::clone(&transport),
+                requests,
+                health_state.clone(),
+                Arc::clone(&shutdown),
+            )));
+        }
+
+        // Public server browser listing
+        let master = if config.master_server.enabled {
+            match MasterServerClient::new(&config.master_server.url) {
+                Ok(client) => {
+                    let server_id = uuid::Uuid::new_v4();
+                    tasks.push(tokio::spawn(run_master_heartbeat(Arc::clone(&state), client.clone(), server_id)));
+                    Some((client, server_id))
+                }
+                Err(e) => {
+                    warn!("Not registering with the master server: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        // Cluster: a gateway takes reports from its instances, an instance sends them
+        let mut cluster_addr = None;
+        let cluster_instance = match config.cluster.role {
+            ClusterRole::Standalone => None,
+            ClusterRole::Gateway => {
+                let listener = match tokio::net::TcpListener::bind(&config.cluster.bind).await {
+                    Ok(listener) => listener,
+                    Err(e) => {
+                        for task in &tasks {
+                            task.abort();
+                        }
+                        return Err(format!("Failed to listen for cluster instances on {}: {}", config.cluster.bind, e).into());
+                    }
+                };
+                cluster_addr = listener.local_addr().ok();
+                let registry = state.read().await.cluster.clone().expect("gateways have a cluster registry");
+                tasks.push(tokio::spawn(run_gateway_server(listener, registry, config.cluster.token.clone())));
+                None
+            }
+            ClusterRole::Instance => {
+                // The URL was checked with the rest of the config
+                let client = GatewayClient::new(&config.cluster.gateway_url, &config.cluster.token)?;
+                let instance_id = uuid::Uuid::new_v4();
+                tasks.push(tokio::spawn(run_cluster_reports(Arc::clone(&state), client.clone(), instance_id)));
+                Some((client, instance_id))
+            }
+        };
+
+        // Mark server as ready
+        health_state.set_ready(true).await;
+        info!("Server marked as ready");
+
+        // Client messages are received on tasks of their own, so network jitter
+        // does not reach the tick; inputs wait in per-session queues
+        let input_queues = Arc::new(InputQueues::new());
+        tasks.push(tokio::spawn(run_client_messages(
+            Arc::clone(&state),
+            Arc::clone(&transport),
+            tcp_messages,
+            Arc::clone(&input_queues),
+        )));
+        tasks.push(tokio::spawn(run_udp_messages(Arc::clone(&transport), udp_messages, Arc::clone(&input_queues))));
+
+        // Start main game loop, unless the caller steps the ticks
+        let tick_rate = config.server.tick_rate_hz;
+        let manual_ticks = if self.manual_ticks {
+            info!("Game loop stopped; ticks are stepped manually");
+            Some(tokio::sync::Mutex::new(0))
+        } else {
+            tasks.push(tokio::spawn(run_game_loop(
+                Arc::clone(&state),
+                Arc::clone(&transport),
+                Arc::clone(&input_queues),
+                tick_rate,
+            )));
+            None
+        };
+
+        tasks.push(tokio::spawn(run_health_updates(Arc::clone(&state), Arc::clone(&transport), health_state.clone())));
+
+        #[cfg(unix)]
+        if let Some((path, reload_log_level)) = self.reload {
+            tasks.push(tokio::spawn(run_config_reloads(
+                Arc::clone(&state),
+                path,
+                self.log_filter.filter(|_| reload_log_level),
+            )));
+        }
+
+        Ok(ServerRuntime {
+            state,
+            transport,
+            input_queues,
+            health_state,
+            shutdown,
+            master,
+            cluster_instance,
+            cluster_addr,
+            tick_rate,
+            manual_ticks,
+            tasks,
+        })
+    }
+}
+
+/// A running server.
+///
+/// ```no_run
+/// # async fn example() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+/// use apexsim_server::{config::ServerConfig, runtime::ServerRuntime};
+///
+/// let server = ServerRuntime::builder(ServerConfig::default()).manual_ticks(true).start().await?;
+/// let (connection_id, mut messages) = server.connect("Tester", "dev-token").await;
+/// server.step(240).await;
+/// println!("{} session(s)", server.state().await.sessions().len());
+/// server.shutdown().await;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ServerRuntime {
+    state: Arc<RwLock<ServerState>>,
+    transport: Arc<RwLock<TransportLayer>>,
+    input_queues: Arc<InputQueues>,
+    health_state: HealthState,
+    /// Notified when an admin asks the server to shut down
+    shutdown: Arc<Notify>,
+    master: Option<(MasterServerClient, uuid::Uuid)>,
+    /// The gateway this instance reports to
+    cluster_instance: Option<(GatewayClient, uuid::Uuid)>,
+    cluster_addr: Option<SocketAddr>,
+    tick_rate: u16,
+    /// Ticks stepped so far; `None` when the game loop runs them
+    manual_ticks: Option<tokio::sync::Mutex<u64>>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl ServerRuntime {
+    pub fn builder(config: ServerConfig) -> ServerRuntimeBuilder {
+        ServerRuntimeBuilder {
+            config,
+            health_state: None,
+            log_filter: None,
+            reload: None,
+            open_practice: false,
+            manual_ticks: false,
+        }
+    }
+
+    /// Address clients connect to over TCP, e.g. the port picked for `:0`
+    pub async fn tcp_addr(&self) -> Option<SocketAddr> {
+        self.transport.read().await.tcp_local_addr()
+    }
+
+    pub async fn udp_addr(&self) -> Option<SocketAddr> {
+        self.transport.read().await.udp_local_addr().ok()
+    }
+
+    /// Address a cluster gateway takes instance reports on
+    pub fn cluster_addr(&self) -> Option<SocketAddr> {
+        self.cluster_addr
+    }
+
+    pub fn health(&self) -> &HealthState {
+        &self.health_state
+    }
+
+    /// The server's state; nothing ticks or handles messages while it is held
+    pub async fn state(&self) -> RwLockReadGuard<'_, ServerState> {
+        self.state.read().await
+    }
+
+    /// Connect an authenticated client without a socket; what the server sends
+    /// it arrives on the receiver, starting with `AuthSuccess`
+    pub async fn connect(&self, player_name: &str, token: &str) -> (ConnectionId, mpsc::Receiver<ServerMessage>) {
+        self.transport.read().await.connect_local(player_name, token).await
+    }
+
+    /// Handle a message as if the connection had sent it, local or remote;
+    /// `false` once the server has stopped
+    pub async fn send(&self, connection_id: ConnectionId, message: ClientMessage) -> bool {
+        self.transport.read().await.inject(connection_id, message).await
+    }
+
+    /// Run `ticks` ticks right away and return the ticks run so far; `None`
+    /// when the game loop runs them, see `ServerRuntimeBuilder::manual_ticks`
+    pub async fn step(&self, ticks: u32) -> Option<u64> {
+        let mut tick_count = self.manual_ticks.as_ref()?.lock().await;
+        for _ in 0..ticks {
+            *tick_count += 1;
+            run_tick(&self.state, &self.transport, &self.input_queues, self.tick_rate, *tick_count).await;
+        }
+        Some(*tick_count)
+    }
+
+    /// Wait until an admin asks the server to shut down
+    pub async fn shutdown_requested(&self) {
+        self.shutdown.notified().await
+    }
+
+    /// Hand players over to the standby, say goodbye to clients and stop
+    /// every task; the sessions end with it
+    pub async fn shutdown(mut self) {
+        // Mark server as unhealthy
+        self.health_state.set_healthy(false).await;
+
+        // Send players to the standby before the primary goes away
+        redirect_to_standby(&self.state, &self.transport).await;
+
+        if let Some((master, server_id)) = self.master.take() {
+            if let Err(e) = master.unregister(server_id).await {
+                warn!("Failed to remove listing from the master server: {}", e);
+            }
+        }
+
+        // Shutdown transport layer (notifies all clients)
+        self.transport.write().await.shutdown().await;
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+        // After the report task stops, so no report follows
+        if let Some((gateway, instance_id)) = self.cluster_instance.take() {
+            if let Err(e) = gateway.leave(instance_id).await {
+                warn!("Failed to leave the cluster: {}", e);
+            }
+        }
+
+        // Cleanup
+        let final_state = self.state.read().await;
+        info!("Server shutting down with {} active sessions", final_state.sessions.len());
+        // A journal left behind would bring the sessions back as if the server had crashed
+        if let Some(journal) = &final_state.journal {
+            if let Err(e) = journal.clear().await {
+                warn!("Failed to delete the session journal: {}", e);
+            }
+        }
+    }
+}
+
+/// Refresh the details the health endpoints report
+async fn run_health_updates(
+    state: Arc<RwLock<ServerState>>,
+    transport: Arc<RwLock<TransportLayer>>,
+    health_state: HealthState,
+) {
+    let mut ticker = interval(HEALTH_UPDATE_INTERVAL);
+    loop {
+        ticker.tick().await;
+        let queues = transport.read().await.queue_depths().await;
+        let details = {
+            let state = state.read().await;
+            HealthDetails {
+                cars: state.car_configs.len(),
+                tracks: state.track_configs.len(),
+                sessions: state.sessions.len(),
+                players: state.players.len(),
+                tick_lag: state.tick_lag.snapshot(),
+                queues,
+            }
+        };
+        health_state.update(details).await;
+    }
+}
+
+/// Keep this server's listing on the master server list fresh
+async fn run_master_heartbeat(state: Arc<RwLock<ServerState>>, master: MasterServerClient, server_id: uuid::Uuid) {
+    let heartbeat_seconds = state.read().await.config.master_server.heartbeat_interval_seconds.max(1);
+    let mut ticker = interval(Duration::from_secs(heartbeat_seconds));
+    // Only changes are logged, not every heartbeat
+    let mut registered = None;
+
+    loop {
+        ticker.tick().await;
+        let listing = {
+            let state_read = state.read().await;
+            let config = &state_read.config;
+            ServerListing {
+                server_id,
+                name: config.master_server.name.clone(),
+                region: config.master_server.region.clone(),
+                address: config.master_server.public_address.clone(),
+                tcp_port: config.network.tcp_bind.parse::<std::net::SocketAddr>().map(|addr| addr.port()).unwrap_or(0),
+                requires_tls: config.network.require_tls,
+                player_count: state_read.lobby.get_lobby_count().await as u32,
+                session_count: state_read.sessions.len() as u32,
+                max_sessions: config.server.max_sessions,
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            }
+        };
+
+        match master.register(&listing).await {
+            Ok(()) if registered != Some(true) => {
+                info!("Listed on the master server as {:?}", listing.name);
+                registered = Some(true);
+            }
+            Err(e) if registered != Some(false) => {
+                warn!("Master server heartbeat failed: {}", e);
+                registered = Some(false);
+            }
+            Err(e) => debug!("Master server heartbeat failed: {}", e),
+            Ok(()) => {}
+        }
+    }
+}
+
+/// Keep the gateway's view of this instance's sessions and load fresh
+async fn run_cluster_reports(state: Arc<RwLock<ServerState>>, gateway: GatewayClient, instance_id: uuid::Uuid) {
+    let report_interval_ms = state.read().await.config.cluster.report_interval_ms.max(1);
+    let mut ticker = interval(Duration::from_millis(report_interval_ms));
+    // Only changes are logged, not every report
+    let mut reporting = None;
+
+    loop {
+        ticker.tick().await;
+        let report = {
+            let state_read = state.read().await;
+            InstanceReport {
+                instance_id,
+                address: state_read.config.cluster.public_address.clone(),
+                player_count: state_read.lobby.get_lobby_count().await as u32,
+                session_count: (state_read.sessions.len() + state_read.preparing.len()) as u32,
+                max_sessions: state_read.config.server.max_sessions,
+                sessions: state_read.lobby.get_available_sessions().await,
+            }
+        };
+
+        match gateway.report(&report).await {
+            Ok(()) if reporting != Some(true) => {
+                info!("Reporting to the cluster gateway as {}", report.address);
+                reporting = Some(true);
+            }
+            Err(e) if reporting != Some(false) => {
+                warn!("Cluster gateway report failed: {}", e);
+                reporting = Some(false);
+            }
+            Err(e) => debug!("Cluster gateway report failed: {}", e),
+            Ok(()) => {}
+        }
+    }
+}
+
+/// Carry out admin API commands as they arrive
+/// Replace the log filter, e.g. with `logging.level`
+fn set_log_level(handle: &LogFilterHandle, level: &str) {
+    match tracing_subscriber::EnvFilter::try_new(level) {
+        Ok(filter) => {
+            if let Err(e) = handle.reload(filter) {
+                warn!("Failed to set log level {}: {}", level, e);
+            }
+        }
+        Err(e) => warn!("Invalid log level {}: {}", level, e),
+    }
+}
+
+/// Reload the config file on SIGHUP, applying the settings that can change
+/// while the server runs
+#[cfg(unix)]
+async fn run_config_reloads(state: Arc<RwLock<ServerState>>, path: String, log_filter: Option<LogFilterHandle>) {
+    let mut hangups = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(e) => {
+            warn!("Config reload on SIGHUP unavailable: {}", e);
+            return;
+        }
+    };
+    while hangups.recv().await.is_some() {
+        let new = match ServerConfig::load(&path) {
+            Ok(new) => new,
+            Err(e) => {
+                warn!("Keeping the current config; failed to reload {}: {}", path, e);
+                continue;
+            }
+        };
+        let problems = new.validate();
+        if !problems.is_empty() {
+            warn!("Keeping the current config; {} has problems: {}", path, problems.join("; "));
+            continue;
+        }
+        let reload = state.write().await.config.apply_reload(&new);
+        if let (true, Some(handle)) = (reload.applied.contains(&"logging.level"), &log_filter) {
+            set_log_level(handle, &new.logging.level);
+        }
+        if reload.applied.is_empty() {
+            info!("Reloaded {}: nothing to apply", path);
+        } else {
+            info!("Reloaded {}: applied {}", path, reload.applied.join(", "));
+        }
+        if !reload.needs_restart.is_empty() {
+            warn!("Changes to [{}] in {} take effect after a restart", reload.needs_restart.join("], ["), path);
+        }
+    }
+}
+
+/// Serve the admin console on `[admin_console] socket_path`
+fn spawn_console_socket(path: String, console: AdminConsole) {
+    #[cfg(unix)]
+    tokio::spawn(async move {
+        if let Err(e) = crate::admin_console::run_console_socket(path, console).await {
+            warn!("Admin console socket error: {}", e);
+        }
+    });
+    #[cfg(not(unix))]
+    {
+        let _ = console;
+        warn!("Admin console socket {} needs unix sockets, which this platform lacks", path);
+    }
+}
+
+async fn run_admin_requests(
+    state: Arc<RwLock<ServerState>>,
+    transport: Arc<RwLock<TransportLayer>>,
+    mut requests: tokio::sync::mpsc::Receiver<AdminRequest>,
+    health_state: HealthState,
+    shutdown: Arc<tokio::sync::Notify>,
+) {
+    while let Some(request) = requests.recv().await {
+        let result = execute_admin_command(&state, &transport, &request.command, &health_state, &shutdown).await;
+        if let Err(e) = &result {
+            info!("Admin API command {:?} refused: {}", request.command, e.message);
+        }
+        request.respond(result);
+    }
+}
+
+async fn execute_admin_command(
+    state: &Arc<RwLock<ServerState>>,
+    transport: &Arc<RwLock<TransportLayer>>,
+    command: &AdminCommand,
+    health_state: &HealthState,
+    shutdown: &tokio::sync::Notify,
+) -> AdminResult {
+    use crate::network::ServerMessage;
+
+    // The game loop locks the transport before the state, so this task never
+    // holds both at once
+    match command {
+        AdminCommand::ListPlayers => {
+            let mut players = Vec::new();
+            let state_read = state.read().await;
+            for player in state_read.lobby.get_lobby_players().await {
+                let spectating = state_read.lobby.get_spectating_session(player.id).await;
+                players.push(AdminPlayer {
+                    player_id: player.id,
+                    name: player.name,
+                    address: None,
+                    admin: state_read.admins.contains(&player.id),
+                    session_id: player.in_session.or(spectating),
+                    spectating: spectating.is_some(),
+                });
+            }
+            drop(state_read);
+
+            let transport_read = transport.read().await;
+            for player in &mut players {
+                if let Some(connection_id) = transport_read.get_player_connection(player.player_id).await {
+                    player.address = transport_read.get_connection(connection_id).await.map(|info| info.tcp_addr.ip());
+                }
+            }
+            Ok(AdminResponse::Players(players))
+        }
+
+        AdminCommand::KickPlayer { player_id, reason } => {
+            let transport_read = transport.read().await;
+            let target = match transport_read.get_player_connection(*player_id).await {
+                Some(connection_id) => transport_read.get_connection(connection_id).await.map(|info| (connection_id, info)),
+                None => None,
+            };
+            let (connection_id, target) = target.ok_or_else(|| AdminError::not_found(format!("Player {} not connected", player_id)))?;
+
+            let message = match reason {
+                Some(reason) => format!("Kicked by an administrator: {}", reason),
+                None => "Kicked by an administrator".to_string(),
+            };
+            info!("Admin API kicked player {} ({})", target.player_name, message);
+            transport_read.disconnect(connection_id, ServerMessage::Error { code: 403, message }).await;
+            drop(transport_read);
+
+            state.write().await.remove_disconnected_player(*player_id, target.in_session).await;
+            Ok(AdminResponse::Done { message: format!("Kicked {}", target.player_name) })
+        }
+
+        AdminCommand::ListSessions => {
+            let state_read = state.read().await;
+            let mut sessions = Vec::new();
+            for session_id in state_read.sessions.keys() {
+                sessions.extend(state_read.admin_session(*session_id).await);
+            }
+            Ok(AdminResponse::Sessions(sessions))
+        }
+
+        AdminCommand::GetSession { session_id } => state
+            .read()
+            .await
+            .admin_session(*session_id)
+            .await
+            .map(AdminResponse::Session)
+            .ok_or_else(|| AdminError::not_found(format!("Session {} not found", session_id))),
+
+        AdminCommand::CreateSession { create } => {
+            let mut state_write = state.write().await;
+            if state_write.draining {
+                return Err(AdminError::new(hyper::StatusCode::SERVICE_UNAVAILABLE, "Server is draining; no new sessions"));
+            }
+            if create.max_players == 0 {
+                return Err(AdminError::bad_request("max_players must be at least 1"));
+            }
+            if state_write.sessions.len() + state_write.preparing.len() >= state_write.config.server.max_sessions as usize {
+                return Err(AdminError::conflict("Server is at its session limit"));
+            }
+            let track = state_write
+                .track_configs
+                .values()
+                .find(|t| t.id.to_string() == create.track || t.name.eq_ignore_ascii_case(&create.track))
+                .map(|t| TrackConfig::clone(t))
+                .ok_or_else(|| AdminError::not_found(format!("Track {} not found", create.track)))?;
+            let track_name = track.name.clone();
+            let session_id = state_write.create_open_session(track, create.max_players, "Admin").await;
+            info!("Admin API opened session {} on {}", session_id, track_name);
+            let session = state_write.admin_session(session_id).await
+                .ok_or_else(|| AdminError::not_found(format!("Session {} not found", session_id)))?;
+            Ok(AdminResponse::Session(session))
+        }
+
+        AdminCommand::UpdateSession { session_id, update } => {
+            let mut state_write = state.write().await;
+            state_write.update_session_settings(*session_id, update).await?;
+            let session = state_write.admin_session(*session_id).await
+                .ok_or_else(|| AdminError::not_found(format!("Session {} not found", session_id)))?;
+            Ok(AdminResponse::Session(session))
+        }
+
+        AdminCommand::CloseSession { session_id } => {
+            let mut state_write = state.write().await;
+            if !state_write.sessions.contains_key(session_id) {
+                return Err(AdminError::not_found(format!("Session {} not found", session_id)));
+            }
+            let mut members = state_write.lobby.get_session_players(*session_id).await;
+            members.extend(state_write.lobby.get_session_spectators(*session_id).await);
+            state_write.sessions.remove(session_id);
+            state_write.lobby.unregister_session(*session_id).await;
+            drop(state_write);
+
+            let transport_read = transport.read().await;
+            for player_id in members {
+                if let Some(connection_id) = transport_read.get_player_connection(player_id).await {
+                    let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                        code: 410,
+                        message: "Session closed by an administrator".to_string(),
+                    }).await;
+                    let _ = transport_read.send_tcp(connection_id, ServerMessage::SessionLeft).await;
+                    transport_read.set_player_session(connection_id, None).await;
+                }
+            }
+            info!("Admin API closed session {}", session_id);
+            Ok(AdminResponse::Done { message: format!("Closed session {}", session_id) })
+        }
+
+        AdminCommand::TransitionSession { session_id, transition } => {
+            let mut state_write = state.write().await;
+            let game_session = state_write.sessions.get_mut(session_id)
+                .ok_or_else(|| AdminError::not_found(format!("Session {} not found", session_id)))?;
+            let starting = match transition {
+                SessionTransition::Start => {
+                    if game_session.session.state != SessionState::Lobby {
+                        return Err(AdminError::conflict("Only a session in the lobby can be started"));
+                    }
+                    game_session.start_countdown();
+                    game_session.session.participants.keys().copied().collect()
+                }
+                SessionTransition::Finish => {
+                    game_session.request_finish().map_err(AdminError::conflict)?;
+                    Vec::new()
+                }
+            };
+            let session = state_write.admin_session(*session_id).await
+                .ok_or_else(|| AdminError::not_found(format!("Session {} not found", session_id)))?;
+            drop(state_write);
+
+            let transport_read = transport.read().await;
+            for player_id in starting {
+                if let Some(connection_id) = transport_read.get_player_connection(player_id).await {
+                    let _ = transport_read.send_tcp(connection_id, ServerMessage::SessionStarting { countdown_seconds: 5 }).await;
+                }
+            }
+            info!("Admin API forced {:?} of session {}", transition, session_id);
+            Ok(AdminResponse::Session(session))
+        }
+
+        AdminCommand::Announce { message } => {
+            transport.read().await.broadcast_tcp(ServerMessage::Announcement { message: message.clone() }).await;
+            info!("Admin API announcement: {}", message);
+            Ok(AdminResponse::Done { message: "Announcement sent".to_string() })
+        }
+
+        AdminCommand::GenerateTrack { generate } => {
+            let name = generate.name.trim().to_string();
+            if name.is_empty() {
+                return Err(AdminError::bad_request("Track name is empty"));
+            }
+            generate.params.validate().map_err(AdminError::bad_request)?;
+            let generated_dir = {
+                let state_read = state.read().await;
+                if state_read.track_configs.values().any(|t| t.name.eq_ignore_ascii_case(&name)) {
+                    return Err(AdminError::conflict(format!("Track {} already exists", name)));
+                }
+                std::path::Path::new(&state_read.config.content.tracks_dir).join(GENERATED_TRACKS_DIR)
+            };
+            if circuit_path(&generated_dir, &name).exists() {
+                return Err(AdminError::conflict(format!("Track {} already exists", name)));
+            }
+
+            // Building the terrain takes seconds, too long to hold up this task
+            let params = generate.params.clone();
+            let track_file = tokio::task::spawn_blocking(move || {
+                let track = generate_circuit(&name, &params).map_err(AdminError::bad_request)?;
+                save_circuit(&generated_dir, &track).map_err(|e| AdminError::new(hyper::StatusCode::INTERNAL_SERVER_ERROR, e))
+            })
+            .await
+            .map_err(|e| AdminError::new(hyper::StatusCode::INTERNAL_SERVER_ERROR, format!("Track generation failed: {}", e)))??;
+
+            let track = state
+                .write()
+                .await
+                .add_track_file(&track_file)
+                .map_err(|e| AdminError::new(hyper::StatusCode::INTERNAL_SERVER_ERROR, e))?;
+            info!("Admin API generated track {} ({}) at {}", track.name, track.id, track_file.display());
+            Ok(AdminResponse::Track(AdminTrack {
+                track_id: track.id,
+                name: track.name.clone(),
+                length_m: crate::rolling_start::track_length(&track),
+                source_path: track.source_path.clone(),
+                seed: track.metadata.terrain_seed,
+            }))
+        }
+
+        AdminCommand::Drain => {
+            let mut state_write = state.write().await;
+            state_write.draining = true;
+            health_state.set_ready(false).await;
+            info!("Admin API drain: no new sessions, {} still running", state_write.sessions.len());
+            Ok(AdminResponse::Done {
+                message: format!("Draining; {} session(s) still running", state_write.sessions.len()),
+            })
+        }
+
+        AdminCommand::Shutdown => {
+            shutdown.notify_one();
+            Ok(AdminResponse::Done { message: "Shutting down".to_string() })
+        }
+
+        AdminCommand::Stats => {
+            let (tcp_messages_dropped, udp_messages_dropped) = {
+                let transport_read = transport.read().await;
+                (transport_read.metrics.tcp_dropped(), transport_read.metrics.udp_dropped())
+            };
+            let state_read = state.read().await;
+            let mut sessions = Vec::new();
+            for session_id in state_read.sessions.keys() {
+                sessions.extend(state_read.admin_session(*session_id).await);
+            }
+            Ok(AdminResponse::Stats(AdminStats {
+                uptime_s: state_read.started_at.elapsed().as_secs(),
+                player_count: state_read.lobby.get_lobby_players().await.len(),
+                session_count: sessions.len(),
+                draining: state_read.draining,
+                tcp_messages_dropped,
+                udp_messages_dropped,
+                tick_lag: state_read.tick_lag.snapshot(),
+                sessions,
+            }))
+        }
+    }
+}
+
+/// Send lobby state to a specific connection
+async fn send_lobby_state(
+    connection_id: ConnectionId,
+    state: &Arc<RwLock<ServerState>>,
+    transport: &TransportLayer,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use crate::network::{ServerMessage, CarConfigSummary, TrackConfigSummary};
+
+    let state_read = state.read().await;
+
+    // Get lobby players and sessions
+    let players_in_lobby = state_read.lobby.get_lobby_players().await;
+    info!("send_lobby_state: players_in_lobby count = {}", players_in_lobby.len());
+    for player in &players_in_lobby {
+        info!("  - Player: {} (ID: {}), SelectedCar: {:?}, InSession: {:?}",
+              player.name, player.id, player.selected_car, player.in_session);
+    }
+    let mut available_sessions = state_read.lobby.get_available_sessions().await;
+    if let Some(cluster) = &state_read.cluster {
+        available_sessions.extend(cluster.sessions());
+    }
+
+    // Get car and track configs
+    let car_configs: Vec<CarConfigSummary> = state_read
+        .car_configs
+        .values()
+        .map(|c| CarConfigSummary {
+            id: c.id,
+            name: c.name.clone(),
+            model_path: format!("res://content/cars/{}/{}", c.id, c.model),
+            mass_kg: c.mass_kg,
+            max_engine_force_n: c.peak_drive_force_n(),
+            peak_torque_nm: c.engine.peak_torque_nm(),
+            peak_power_w: c.engine.peak_power_w(),
+            setup_ranges: c.setup_ranges.clone(),
+            default_setup: CarSetup::baseline(c),
+            content_hash: c.content_hash.clone(),
+            liveries: c.liveries.clone(),
+        })
+        .collect();
+
+    let track_configs: Vec<TrackConfigSummary> = state_read
+        .track_configs
+        .values()
+        .map(|t| TrackConfigSummary {
+            id: t.id,
+            name: t.name.clone(),
+            centerline: t.centerline.iter()
+                .step_by(10)
+                .map(|p| crate::network::TrackPoint { x: p.x, y: p.y })
+                .collect(), // Send every 10th point
+            source_path: t.source_path.clone(),
+            content_hash: t.content_hash.clone(),
+            country: t.metadata.country.clone(),
+            length_m: t.metadata.length_m.unwrap_or_else(|| crate::rolling_start::track_length(t)),
+            corners: t.metadata.corners.unwrap_or_default(),
+            tags: t.metadata.tags.clone(),
+            lap_record: state_read.lap_records.get(t.id).cloned(),
+        })
+        .collect();
+
+    drop(state_read);
+
+    let lobby_state = ServerMessage::LobbyState(LobbyStateData {
+        players_in_lobby,
+        available_sessions,
+        car_configs,
+        track_configs,
+    });
+
+    transport.send_tcp(connection_id, lobby_state).await?;
+    Ok(())
+}
+
+/// Handle messages from TCP clients as they arrive, apart from the tick;
+/// inputs are queued for the tick they are meant for
+async fn run_client_messages(
+    state: Arc<RwLock<ServerState>>,
+    transport: Arc<RwLock<TransportLayer>>,
+    mut messages: tokio::sync::mpsc::Receiver<(ConnectionId, crate::network::ClientMessage)>,
+    input_queues: Arc<InputQueues>,
+) {
+    while let Some((connection_id, msg)) = messages.recv().await {
+        let transport_read = transport.read().await;
+        use crate::network::{ClientMessage, ReplayListData, ServerMessage};
+        use crate::lobby::{LobbyPlayerState, LobbySessionInfo, SessionVisibility, DEFAULT_PLAYER_RATING};
+
+        match msg {
+            ClientMessage::Authenticate { token, player_name, telemetry_rate_hz } => {
+                // Add player to lobby after authentication
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let mut state_write = state.write().await;
+                    let lobby_player = LobbyPlayerState {
+                        player_id: conn_info.player_id,
+                        player_name: player_name.clone(),
+                        connection_id,
+                        selected_car: None,
+                        selected_livery: 0,
+                        rating: DEFAULT_PLAYER_RATING,
+                    };
+                    state_write.lobby.add_player(lobby_player).await;
+
+                    let is_admin = !token.is_empty()
+                        && state_write.config.server.admin_tokens.iter().any(|admin| constant_time_eq(admin.as_bytes(), token.as_bytes()));
+                    if is_admin {
+                        info!("Player {} authenticated as admin", player_name);
+                        state_write.admins.insert(conn_info.player_id);
+                    }
+                    let tick_rate_hz = state_write.config.server.tick_rate_hz;
+
+                    // Send initial lobby state
+                    drop(state_write);
+                    if telemetry_rate_hz > 0 {
+                        if let Some(rate_hz) = transport_read.set_telemetry_rate(connection_id, telemetry_rate_hz, tick_rate_hz).await {
+                            let _ = transport_read.send_tcp(connection_id, ServerMessage::TelemetryRate { rate_hz }).await;
+                        }
+                    }
+                    if let Err(e) = send_lobby_state(connection_id, &state, &transport_read).await {
+                        warn!("Failed to send lobby state: {:?}", e);
+                    }
+                }
+            }
+
+            ClientMessage::SelectCar { car_config_id, livery } => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    info!("SelectCar: player_id={}, car_config_id={}, livery={}", conn_info.player_id, car_config_id, livery);
+                    let state_write = state.write().await;
+                    let livery_count = state_write.car_configs.get(&car_config_id).map_or(0, |c| c.liveries.len());
+                    if livery > 0 && livery as usize >= livery_count {
+                        let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                            code: 400,
+                            message: format!("Car {} has no livery {}", car_config_id, livery),
+                        }).await;
+                        continue;
+                    }
+                    state_write.lobby.set_player_car(conn_info.player_id, car_config_id, livery).await;
+                    info!("SelectCar: Car set successfully");
+                } else {
+                    warn!("SelectCar: No connection info found for connection_id={}", connection_id);
+                }
+            }
+
+            ClientMessage::RequestLobbyState => {
+                if let Err(e) = send_lobby_state(connection_id, &state, &transport_read).await {
+                    warn!("Failed to send lobby state: {:?}", e);
+                }
+            }
+
+            ClientMessage::RequestRecentTelemetry { seconds } => {
+                use crate::network::RecentTelemetry;
+                use crate::telemetry_history::sample_interval_ticks;
+
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let state_read = state.read().await;
+                    let game_session = conn_info.in_session.and_then(|sid| state_read.sessions.get(&sid));
+
+                    if let Some(game_session) = game_session {
+                        let samples = game_session.telemetry_history.recent(conn_info.player_id, seconds, game_session.timing);
+                        let _ = transport_read.send_tcp(connection_id, ServerMessage::RecentTelemetry(RecentTelemetry {
+                            player_id: conn_info.player_id,
+                            sample_interval_ms: game_session.timing.ticks_to_ms(sample_interval_ticks(game_session.timing)) as u16,
+                            samples,
+                        })).await;
+                    } else {
+                        let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                            code: 400,
+                            message: "Not in a session".to_string(),
+                        }).await;
+                    }
+                }
+            }
+
+            ClientMessage::SetDashboardOutput { port, rate_hz } => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let mut state_write = state.write().await;
+                    let reply = if state_write.dashboard_socket.is_none() {
+                        ServerMessage::Error {
+                            code: 403,
+                            message: "Dashboard output is disabled on this server".to_string(),
+                        }
+                    } else {
+                        match port {
+                            Some(0) => ServerMessage::Error {
+                                code: 400,
+                                message: "Dashboard port must not be 0".to_string(),
+                            },
+                            Some(port) => {
+                                // Only ever the client's own address, so the server cannot be aimed at a third party
+                                let addr = std::net::SocketAddr::new(conn_info.tcp_addr.ip(), port);
+                                let max_rate_hz = state_write.config.dashboard.max_rate_hz;
+                                let tick_rate_hz = state_write.config.server.tick_rate_hz;
+                                let rate_hz = state_write.dashboards.set(conn_info.player_id, addr, rate_hz, max_rate_hz, tick_rate_hz);
+                                info!("Sending dashboard packets for {} to {} at {} Hz", conn_info.player_name, addr, rate_hz);
+                                ServerMessage::DashboardOutput { port: Some(port), rate_hz }
+                            }
+                            None => {
+                                state_write.dashboards.remove(&conn_info.player_id);
+                                ServerMessage::DashboardOutput { port: None, rate_hz: 0 }
+                            }
+                        }
+                    };
+                    let _ = transport_read.send_tcp(connection_id, reply).await;
+                }
+            }
+
+            ClientMessage::SetTelemetryRate { rate_hz } => {
+                let tick_rate_hz = state.read().await.config.server.tick_rate_hz;
+                if let Some(rate_hz) = transport_read.set_telemetry_rate(connection_id, rate_hz, tick_rate_hz).await {
+                    let _ = transport_read.send_tcp(connection_id, ServerMessage::TelemetryRate { rate_hz }).await;
+                }
+            }
+
+            ClientMessage::SetExtendedTelemetry { enabled } => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let mut state_write = state.write().await;
+                    let game_session = conn_info.in_session.and_then(|sid| state_write.sessions.get_mut(&sid));
+
+                    let result = match game_session {
+                        Some(game_session) => game_session.set_extended_telemetry(conn_info.player_id, enabled),
+                        None => Err("Not in a session".to_string()),
+                    };
+                    if let Err(message) = result {
+                        let _ = transport_read.send_tcp(connection_id, ServerMessage::Error { code: 400, message }).await;
+                    }
+                }
+            }
+
+            ClientMessage::ApplySetup { setup } => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let mut state_write = state.write().await;
+                    let game_session = conn_info.in_session.and_then(|sid| state_write.sessions.get_mut(&sid));
+
+                    let result = match game_session {
+                        Some(game_session) => game_session.apply_setup(conn_info.player_id, setup.clone()),
+                        None => Err("Not in a session".to_string()),
+                    };
+                    let reply = match result {
+                        Ok(()) => ServerMessage::SetupApplied { setup },
+                        Err(message) => ServerMessage::Error { code: 400, message },
+                    };
+                    let _ = transport_read.send_tcp(connection_id, reply).await;
+                }
+            }
+
+            ClientMessage::SetDriverAids { aids } => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let mut state_write = state.write().await;
+                    let game_session = conn_info.in_session.and_then(|sid| state_write.sessions.get_mut(&sid));
+
+                    let result = match game_session {
+                        Some(game_session) => game_session.set_aids(conn_info.player_id, aids),
+                        None => Err("Not in a session".to_string()),
+                    };
+                    let reply = match result {
+                        Ok(()) => ServerMessage::DriverAidsApplied { aids },
+                        Err(message) => ServerMessage::Error { code: 400, message },
+                    };
+                    let _ = transport_read.send_tcp(connection_id, reply).await;
+                }
+            }
+
+            ClientMessage::CreateSession { track_config_id, max_players, ai_count, lap_limit, session_kind, weather, setup_policy, collision_policy, time_of_day, aid_limits } => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let mut state_write = state.write().await;
+                    match state_write.route_new_session() {
+                        Ok(None) => {}
+                        Ok(Some(address)) => {
+                            info!("Sending {} to instance {} to create a session", conn_info.player_name, address);
+                            let _ = transport_read.send_tcp(connection_id, ServerMessage::InstanceRedirect(InstanceRedirectData {
+                                address,
+                                session_id: None,
+                            })).await;
+                            continue;
+                        }
+                        Err(message) => {
+                            let _ = transport_read.send_tcp(connection_id, ServerMessage::Error { code: 503, message }).await;
+                            continue;
+                        }
+                    }
+                    let client_ip = conn_info.tcp_addr.ip();
+                    if let Err(limit) = state_write.check_session_limits(conn_info.player_id, client_ip) {
+                        info!("Refused a session for {}: {}", conn_info.player_name, limit);
+                        let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                            code: 429,
+                            message: limit.to_string(),
+                        }).await;
+                        continue;
+                    }
+                    let aid_limits = aid_limits.unwrap_or_else(DriverAids::unrestricted);
+
+                    // Get host's selected car
+                    let selected_car = state_write.lobby.get_player_car(conn_info.player_id).await;
+
+                    if let Some(car_id) = selected_car {
+                        // Reserve the session now; building it can take a while on large tracks
+                        if let Some(pending) = state_write.prepare_session(
+                            conn_info.player_id,
+                            car_id,
+                            track_config_id,
+                            session_kind,
+                            max_players,
+                            ai_count,
+                            lap_limit
+                        ) {
+                            let session_id = pending.session_id();
+                            info!("Preparing session {} for player {}", session_id, conn_info.player_name);
+                            state_write.session_creators.record(session_id, conn_info.player_id, client_ip, std::time::Instant::now());
+
+                            // List the session in the lobby while it is being built
+                            let track = &pending.track;
+                            let session_info = LobbySessionInfo {
+                                session_id,
+                                host_player_id: conn_info.player_id,
+                                host_name: conn_info.player_name.clone(),
+                                track_name: track.name.clone(),
+                                track_file: track.source_path.clone()
+                                    .unwrap_or_else(|| "tracks/unknown.yaml".to_string()),
+                                session_kind,
+                                track_config_id,
+                                max_players,
+                                current_player_count: 0, // join_session will increment this
+                                spectator_count: 0,
+                                state: SessionState::Preparing,
+                                visibility: SessionVisibility::Public,
+                                password_hash: None,
+                                created_at: std::time::Instant::now(),
+                                always_open: false,
+                                race_progress: None,
+                                setup_policy,
+                                collision_policy,
+                                time_of_day,
+                                aid_limits,
+                            };
+
+                            state_write.lobby.register_session(session_info).await;
+                            drop(state_write);
+
+                            tokio::spawn(open_prepared_session(
+                                Arc::clone(&state),
+                                Arc::clone(&transport),
+                                pending,
+                                weather,
+                                setup_policy,
+                                collision_policy,
+                                time_of_day,
+                                aid_limits,
+                                conn_info.player_id,
+                                connection_id,
+                            ));
+                        } else {
+                            // Failed to create session
+                            warn!("Failed to create session for player {}: track_id={}", conn_info.player_id, track_config_id);
+                            
+                            // Check why it failed
+                            if state_write.draining {
+                                let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                                    code: 503,
+                                    message: "Server is draining and not creating sessions".to_string(),
+                                }).await;
+                            } else if state_write.sessions.len() + state_write.preparing.len() >= state_write.config.server.max_sessions as usize {
+                                let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                                    code: 503,
+                                    message: "Server is at max session capacity".to_string(),
+                                }).await;
+                            } else if !state_write.track_configs.contains_key(&track_config_id) {
+                                let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                                    code: 404,
+                                    message: "Track configuration not found".to_string(),
+                                }).await;
+                            } else {
+                                let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                                    code: 500,
+                                    message: "Failed to create session".to_string(),
+                                }).await;
+                            }
+                        }
+                    } else {
+                        // No car selected
+                        let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                            code: 400,
+                            message: "Must select a car before creating session".to_string(),
+                        }).await;
+                    }
+                }
+            }
+
+            ClientMessage::JoinSession { session_id } => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let mut state_write = state.write().await;
+                    if let Some(address) = state_write.instance_hosting(session_id) {
+                        info!("Sending {} to instance {} to join session {}", conn_info.player_name, address, session_id);
+                        let _ = transport_read.send_tcp(connection_id, ServerMessage::InstanceRedirect(InstanceRedirectData {
+                            address,
+                            session_id: Some(session_id),
+                        })).await;
+                        continue;
+                    }
+
+                    // Get player's selected car
+                    let selected_car = state_write.lobby.get_player_car(conn_info.player_id).await;
+                    let livery = state_write.lobby.get_player(conn_info.player_id).await.map_or(0, |p| p.selected_livery);
+                    
+                    let joined = state_write.lobby.join_session(conn_info.player_id, session_id).await;
+
+                    if joined {
+                        // Add player to the actual game session
+                        if let Some(game_session) = state_write.sessions.get_mut(&session_id) {
+                            if let Some(car_id) = selected_car {
+                                if let Some(grid_pos) = game_session.add_player(conn_info.player_id, car_id) {
+                                    info!("Player {} joined session {} at grid position {}",
+                                        conn_info.player_name, session_id, grid_pos);
+                                    game_session.set_livery(conn_info.player_id, livery);
+                                    game_session.broadcaster.bind(conn_info.player_id, connection_id);
+                                    let _ = transport_read.send_tcp(connection_id, ServerMessage::SessionJoined(SessionJoinedData {
+                                        session_id,
+                                        your_grid_position: grid_pos,
+                                        delay_seconds: 0,
+                                    })).await;
+                                    // Track that player is in a session
+                                    transport_read.set_player_session(connection_id, Some(session_id)).await;
+                                } else {
+                                    // Failed to add to session (full)
+                                    let empty_session = state_write.lobby.leave_session(conn_info.player_id, connection_id).await;
+                                    if let Some(session_id) = empty_session {
+                                        info!("Session {} is empty after failed join, removing it", session_id);
+                                        state_write.sessions.remove(&session_id);
+                                        state_write.lobby.unregister_session(session_id).await;
+                                    }
+
+                                    let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                                        code: 400,
+                                        message: "Session is full".to_string(),
+                                    }).await;
+                                }
+                            } else {
+                                // No car selected
+                                let empty_session = state_write.lobby.leave_session(conn_info.player_id, connection_id).await;
+                                if let Some(session_id) = empty_session {
+                                    info!("Session {} is empty after failed join, removing it", session_id);
+                                    state_write.sessions.remove(&session_id);
+                                    state_write.lobby.unregister_session(session_id).await;
+                                }
+
+                                let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                                    code: 400,
+                                    message: "Must select a car before joining session".to_string(),
+                                }).await;
+                            }
+                        }
+                    } else {
+                        let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                            code: 400,
+                            message: "Unable to join session (full or not in lobby state)".to_string(),
+                        }).await;
+                    }
+                }
+            }
+
+            ClientMessage::JoinAsSpectator { session_id, delay_seconds } => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let mut state_write = state.write().await;
+                    let joined = state_write.lobby.join_as_spectator(conn_info.player_id, session_id).await;
+
+                    if joined {
+                        // Admins spectate as stewards and keep seeing real identities
+                        let steward = state_write.admins.contains(&conn_info.player_id);
+                        let mut delay_seconds = state_write.config.spectating.delay_seconds(delay_seconds, steward);
+                        let delay_ticks = delay_seconds * state_write.config.server.tick_rate_hz as u32;
+                        if let Some(game_session) = state_write.sessions.get_mut(&session_id) {
+                            if game_session.session.game_mode == GameMode::Replay {
+                                // Already a recording; nothing to hold back
+                                delay_seconds = 0;
+                            }
+                            if delay_seconds > 0 {
+                                game_session.bind_delayed(conn_info.player_id, connection_id, !steward, delay_ticks);
+                            } else if steward {
+                                game_session.broadcaster.bind(conn_info.player_id, connection_id);
+                            } else {
+                                game_session.broadcaster.bind_public(conn_info.player_id, connection_id);
+                            }
+                        }
+                        info!("Player {} joined session {} as spectator ({} s delay)", conn_info.player_name, session_id, delay_seconds);
+                        let _ = transport_read.send_tcp(connection_id, ServerMessage::SessionJoined(SessionJoinedData {
+                            session_id,
+                            your_grid_position: 0, // 0 indicates spectator
+                            delay_seconds,
+                        })).await;
+                        // Track that player is in a session
+                        transport_read.set_player_session(connection_id, Some(session_id)).await;
+                    } else {
+                        let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                            code: 404,
+                            message: "Session not found".to_string(),
+                        }).await;
+                    }
+                }
+            }
+
+            ClientMessage::ResumeSession { session_id, resume_token } => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let mut state_write = state.write().await;
+
+                    let claim = state_write.resume_tokens.get(&resume_token)
+                        .copied()
+                        .filter(|(sid, _)| *sid == session_id);
+                    let Some((_, old_player_id)) = claim else {
+                        let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                            code: 403,
+                            message: "Invalid resume token".to_string(),
+                        }).await;
+                        continue;
+                    };
+
+                    let grid_position = state_write.sessions.get_mut(&session_id)
+                        .filter(|game_session| game_session.session.participants.contains_key(&old_player_id))
+                        .and_then(|game_session| {
+                            game_session.reassign_player(old_player_id, conn_info.player_id)
+                                .then(|| {
+                                    game_session.broadcaster.bind(conn_info.player_id, connection_id);
+                                    game_session.session.participants[&conn_info.player_id].grid_position
+                                })
+                        });
+
+                    match grid_position {
+                        Some(grid_position) if state_write.lobby.resume_player(conn_info.player_id, session_id).await => {
+                            state_write.resume_tokens.remove(&resume_token);
+                            info!("Player {} resumed car in session {}", conn_info.player_name, session_id);
+                            let _ = transport_read.send_tcp(connection_id, ServerMessage::SessionJoined(SessionJoinedData {
+                                session_id,
+                                your_grid_position: grid_position,
+                                delay_seconds: 0,
+                            })).await;
+                            transport_read.set_player_session(connection_id, Some(session_id)).await;
+                        }
+                        _ => {
+                            let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                                code: 404,
+                                message: "Session no longer available".to_string(),
+                            }).await;
+                        }
+                    }
+                }
+            }
+
+            ClientMessage::LeaveSession => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let mut state_write = state.write().await;
+
+                    // Remove from game session
+                    if let Some(sid) = conn_info.in_session {
+                        if let Some(game_session) = state_write.sessions.get_mut(&sid) {
+                            game_session.remove_player(&conn_info.player_id);
+                        }
+                    }
+
+                    let empty_session = state_write.lobby.leave_session(conn_info.player_id, connection_id).await;
+
+                    if let Some(session_id) = empty_session {
+                        info!("Session {} has no human players left, removing it", session_id);
+                        state_write.sessions.remove(&session_id);
+                        state_write.lobby.unregister_session(session_id).await;
+                    }
+
+                    info!("Player {} left their session", conn_info.player_name);
+                    let _ = transport_read.send_tcp(connection_id, ServerMessage::SessionLeft).await;
+                    // Clear session tracking
+                    transport_read.set_player_session(connection_id, None).await;
+                }
+            }
+
+            ClientMessage::StartSession => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let mut state_write = state.write().await;
+                    
+                    // Find which session the player is in
+                    let session_id = state_write.lobby.get_player_session(conn_info.player_id).await;
+                    
+                    if let Some(session_id) = session_id {
+                        if let Some(game_session) = state_write.sessions.get_mut(&session_id) {
+                            // Only host can start the session
+                            if game_session.session.host_player_id == conn_info.player_id {
+                                game_session.start_countdown();
+                                info!("Player {} started session {}", conn_info.player_name, session_id);
+
+                                // Notify all participants
+                                let msg = ServerMessage::SessionStarting { countdown_seconds: 5 };
+                                let participant_count = game_session.session.participants.len();
+                                info!("Broadcasting SessionStarting to {} participants", participant_count);
+
+                                for player_id in game_session.session.participants.keys() {
+                                    if let Some(conn_id) = transport_read.get_player_connection(*player_id).await {
+                                        info!("Sending SessionStarting to player {} (connection {})", player_id, conn_id);
+                                        let _ = transport_read.send_tcp(conn_id, msg.clone()).await;
+                                    } else {
+                                        warn!("Could not find connection for player {}", player_id);
+                                    }
+                                }
+                            } else {
+                                let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                                    code: 403,
+                                    message: "Only the host can start the session".to_string(),
+                                }).await;
+                            }
+                        }
+                    }
+                }
+            }
+
+            ClientMessage::SetGameMode { mode } => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let mut state_write = state.write().await;
+
+                    // Check if player is in a session
+                    if let Some(session_id) = conn_info.in_session {
+                        // Collect data we need before mutable borrow
+                        let (is_host, human_player_ids) = {
+                            if let Some(game_session) = state_write.sessions.get(&session_id) {
+                                let is_host = game_session.session.host_player_id == conn_info.player_id;
+                                let human_ids: Vec<PlayerId> = game_session.session.participants.keys()
+                                    .filter(|id| !game_session.session.ai_player_ids.contains(id))
+                                    .cloned()
+                                    .collect();
+                                (is_host, human_ids)
+                            } else {
+                                (false, Vec::new())
+                            }
+                        };
+
+                        if is_host {
+                            // If switching to DemoLap, add human players as spectators first
+                            if mode == GameMode::DemoLap {
+                                for player_id in &human_player_ids {
+                                    state_write.lobby.join_as_spectator(*player_id, session_id).await;
+                                    info!("Player {} added as spectator for DemoLap mode", player_id);
+                                }
+                            }
+
+                            // Now do the mutable borrow
+                            if let Some(game_session) = state_write.sessions.get_mut(&session_id) {
+                                game_session.set_game_mode(mode);
+
+                                // Collect participant IDs for notification
+                                let current_participant_ids: Vec<PlayerId> = game_session.session.participants.keys().cloned().collect();
+
+                                // Notify all participants
+                                let mode_changed_msg = ServerMessage::GameModeChanged { mode };
+                                for participant_id in current_participant_ids {
+                                    if let Some(conn_id) = transport_read.get_player_connection(participant_id).await {
+                                        if let Err(e) = transport_read.send_tcp(conn_id, mode_changed_msg.clone()).await {
+                                            warn!("Failed to send mode change to player: {:?}", e);
+                                        }
+                                    }
+                                }
+
+                                // Also notify spectators (for DemoLap)
+                                for player_id in &human_player_ids {
+                                    if let Some(conn_id) = transport_read.get_player_connection(*player_id).await {
+                                        if let Err(e) = transport_read.send_tcp(conn_id, mode_changed_msg.clone()).await {
+                                            warn!("Failed to send mode change to spectator: {:?}", e);
+                                        }
+                                    }
+                                }
+                            }
+                        } else {
+                            let _ = transport_read.send_tcp(
+                                connection_id,
+                                ServerMessage::Error {
+                                    code: 403,
+                                    message: "Only the session host can change game mode".to_string(),
+                                }
+                            ).await;
+                        }
+                    }
+                }
+            }
+
+            ClientMessage::StartCountdown { countdown_seconds, next_mode } => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let mut state_write = state.write().await;
+
+                    // Check if player is in a session
+                    if let Some(session_id) = conn_info.in_session {
+                        if let Some(game_session) = state_write.sessions.get_mut(&session_id) {
+                            // Only host can start countdown
+                            if game_session.session.host_player_id == conn_info.player_id {
+                                game_session.start_countdown_mode(countdown_seconds, next_mode);
+
+                                // Notify all participants
+                                let countdown_msg = ServerMessage::GameModeChanged { mode: GameMode::Countdown };
+                                for participant_id in game_session.session.participants.keys() {
+                                    if let Some(conn_id) = transport_read.get_player_connection(*participant_id).await {
+                                        if let Err(e) = transport_read.send_tcp(conn_id, countdown_msg.clone()).await {
+                                            warn!("Failed to send countdown start to player: {:?}", e);
+                                        }
+                                    }
+                                }
+                            } else {
+                                let _ = transport_read.send_tcp(
+                                    connection_id,
+                                    ServerMessage::Error {
+                                        code: 403,
+                                        message: "Only the session host can start countdown".to_string(),
+                                    }
+                                ).await;
+                            }
+                        }
+                    }
+                }
+            }
+
+            ClientMessage::Disconnect => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let mut state_write = state.write().await;
+                    state_write.remove_disconnected_player(conn_info.player_id, conn_info.in_session).await;
+                }
+            }
+
+            ClientMessage::AdminSetPlayerRating { player_id, rating } => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let state_write = state.write().await;
+                    if !state_write.admins.contains(&conn_info.player_id) {
+                        let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                            code: 403,
+                            message: "Admin rights required".to_string(),
+                        }).await;
+                    } else if state_write.lobby.set_player_rating(player_id, rating).await {
+                        info!("Admin {} set rating of player {} to {}", conn_info.player_name, player_id, rating);
+                    } else {
+                        let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                            code: 404,
+                            message: format!("Player {} not found", player_id),
+                        }).await;
+                    }
+                }
+            }
+
+            ClientMessage::AdminMovePlayer { player_id, target_session_id } => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let mut state_write = state.write().await;
+                    if !state_write.admins.contains(&conn_info.player_id) {
+                        let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                            code: 403,
+                            message: "Admin rights required".to_string(),
+                        }).await;
+                        continue;
+                    }
+
+                    let result = state_write.move_player_to_session(player_id, target_session_id).await;
+                    drop(state_write);
+
+                    match result {
+                        Ok(grid_position) => {
+                            info!("Admin {} moved player {} to session {}", conn_info.player_name, player_id, target_session_id);
+                            notify_moved_players(&transport_read, target_session_id, &[(player_id, grid_position)]).await;
+                        }
+                        Err(message) => {
+                            let _ = transport_read.send_tcp(connection_id, ServerMessage::Error { code: 400, message }).await;
+                        }
+                    }
+                }
+            }
+
+            ClientMessage::AdminSplitSession { session_id } => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let mut state_write = state.write().await;
+                    if !state_write.admins.contains(&conn_info.player_id) {
+                        let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                            code: 403,
+                            message: "Admin rights required".to_string(),
+                        }).await;
+                        continue;
+                    }
+
+                    let result = state_write.split_session(session_id).await;
+                    drop(state_write);
+
+                    match result {
+                        Ok((new_session_id, moved)) => {
+                            info!(
+                                "Admin {} split session {}: {} player(s) moved to {}",
+                                conn_info.player_name, session_id, moved.len(), new_session_id
+                            );
+                            notify_moved_players(&transport_read, new_session_id, &moved).await;
+                        }
+                        Err(message) => {
+                            let _ = transport_read.send_tcp(connection_id, ServerMessage::Error { code: 400, message }).await;
+                        }
+                    }
+                }
+            }
+
+            ClientMessage::AdminMergeSessions { source_session_id, target_session_id } => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let mut state_write = state.write().await;
+                    if !state_write.admins.contains(&conn_info.player_id) {
+                        let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                            code: 403,
+                            message: "Admin rights required".to_string(),
+                        }).await;
+                        continue;
+                    }
+
+                    let result = state_write.merge_sessions(source_session_id, target_session_id).await;
+                    drop(state_write);
+
+                    match result {
+                        Ok(moved) => {
+                            info!(
+                                "Admin {} merged session {} into {} ({} player(s))",
+                                conn_info.player_name, source_session_id, target_session_id, moved.len()
+                            );
+                            notify_moved_players(&transport_read, target_session_id, &moved).await;
+                        }
+                        Err(message) => {
+                            let _ = transport_read.send_tcp(connection_id, ServerMessage::Error { code: 400, message }).await;
+                        }
+                    }
+                }
+            }
+
+            ClientMessage::AdminBanPlayer { player_id, reason, duration_minutes, ban_ip } => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let mut state_write = state.write().await;
+                    let target = match transport_read.get_player_connection(player_id).await {
+                        Some(target_connection_id) => transport_read.get_connection(target_connection_id).await
+                            .map(|info| (target_connection_id, info)),
+                        None => None,
+                    };
+
+                    if !state_write.admins.contains(&conn_info.player_id) {
+                        let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                            code: 403,
+                            message: "Admin rights required".to_string(),
+                        }).await;
+                    } else if let Some((target_connection_id, target)) = target {
+                        let now = unix_now();
+                        let ban = BanEntry {
+                            id: uuid::Uuid::new_v4(),
+                            identity: Some(target.auth_identity.clone()),
+                            ip: ban_ip.then(|| target.tcp_addr.ip()),
+                            player_name: target.player_name.clone(),
+                            reason,
+                            banned_at: now,
+                            expires_at: duration_minutes.map(|minutes| now + minutes as u64 * 60),
+                        };
+                        let kick_message = ban.describe(now);
+
+                        let mut bans = state_write.bans.write().await;
+                        if let Err(e) = bans.add(ban) {
+                            warn!("Failed to save ban list: {}", e);
+                        }
+                        let ban_list = ban_list_message(&bans);
+                        drop(bans);
+
+                        info!("Admin {} banned player {} ({})", conn_info.player_name, target.player_name, kick_message);
+                        transport_read.disconnect(target_connection_id, ServerMessage::Error {
+                            code: 403,
+                            message: kick_message,
+                        }).await;
+                        state_write.remove_disconnected_player(player_id, target.in_session).await;
+                        let _ = transport_read.send_tcp(connection_id, ban_list).await;
+                    } else {
+                        let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                            code: 404,
+                            message: format!("Player {} not connected", player_id),
+                        }).await;
+                    }
+                }
+            }
+
+            ClientMessage::AdminUnban { ban_id } => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let state_write = state.write().await;
+                    if !state_write.admins.contains(&conn_info.player_id) {
+                        let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                            code: 403,
+                            message: "Admin rights required".to_string(),
+                        }).await;
+                    } else {
+                        let mut bans = state_write.bans.write().await;
+                        match bans.remove(ban_id) {
+                            Ok(true) => {
+                                info!("Admin {} lifted ban {}", conn_info.player_name, ban_id);
+                                let _ = transport_read.send_tcp(connection_id, ban_list_message(&bans)).await;
+                            }
+                            Ok(false) => {
+                                let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                                    code: 404,
+                                    message: format!("Ban {} not found", ban_id),
+                                }).await;
+                            }
+                            Err(e) => {
+                                warn!("Failed to save ban list: {}", e);
+                                let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                                    code: 500,
+                                    message: "Failed to save ban list".to_string(),
+                                }).await;
+                            }
+                        }
+                    }
+                }
+            }
+
+            ClientMessage::AdminListBans => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let state_read = state.read().await;
+                    let reply = if state_read.admins.contains(&conn_info.player_id) {
+                        ban_list_message(&*state_read.bans.read().await)
+                    } else {
+                        ServerMessage::Error {
+                            code: 403,
+                            message: "Admin rights required".to_string(),
+                        }
+                    };
+                    let _ = transport_read.send_tcp(connection_id, reply).await;
+                }
+            }
+
+            ClientMessage::AdminSaveSession { session_id } => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let mut state_write = state.write().await;
+                    if !state_write.admins.contains(&conn_info.player_id) {
+                        let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                            code: 403,
+                            message: "Admin rights required".to_string(),
+                        }).await;
+                        continue;
+                    }
+
+                    match state_write.save_session(session_id).await {
+                        Ok(issued) => {
+                            info!("Admin {} saved session {}", conn_info.player_name, session_id);
+                            notify_saved_drivers(&transport_read, session_id, &issued).await;
+                            let reply = state_write.saved_session_list().await;
+                            let _ = transport_read.send_tcp(connection_id, reply).await;
+                        }
+                        Err(message) => {
+                            let _ = transport_read.send_tcp(connection_id, ServerMessage::Error { code: 400, message }).await;
+                        }
+                    }
+                }
+            }
+
+            ClientMessage::AdminListSavedSessions => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let state_read = state.read().await;
+                    let reply = if state_read.admins.contains(&conn_info.player_id) {
+                        state_read.saved_session_list().await
+                    } else {
+                        ServerMessage::Error {
+                            code: 403,
+                            message: "Admin rights required".to_string(),
+                        }
+                    };
+                    let _ = transport_read.send_tcp(connection_id, reply).await;
+                }
+            }
+
+            ClientMessage::AdminResumeSession { session_id } => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let mut state_write = state.write().await;
+                    if !state_write.admins.contains(&conn_info.player_id) {
+                        let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                            code: 403,
+                            message: "Admin rights required".to_string(),
+                        }).await;
+                        continue;
+                    }
+
+                    match state_write.resume_saved_session(session_id).await {
+                        Ok(()) => {
+                            info!("Admin {} resumed saved session {}", conn_info.player_name, session_id);
+                            let reply = state_write.saved_session_list().await;
+                            let _ = transport_read.send_tcp(connection_id, reply).await;
+                        }
+                        Err(message) => {
+                            let _ = transport_read.send_tcp(connection_id, ServerMessage::Error { code: 400, message }).await;
+                        }
+                    }
+                }
+            }
+
+            ClientMessage::ListReplays => {
+                let state_read = state.read().await;
+                let reply = match state_read.replay.list_replays().await {
+                    Ok(mut replays) => {
+                        replays.sort_by_key(|m| std::cmp::Reverse(m.recorded_at));
+                        ServerMessage::ReplayList(ReplayListData {
+                            replays: replays.iter().map(Into::into).collect(),
+                        })
+                    }
+                    Err(e) => {
+                        warn!("Failed to list replays: {}", e);
+                        ServerMessage::Error {
+                            code: 500,
+                            message: "Failed to list replays".to_string(),
+                        }
+                    }
+                };
+                let _ = transport_read.send_tcp(connection_id, reply).await;
+            }
+
+            ClientMessage::RequestReplay { session_id } => {
+                let found = state.read().await.replay.find_replay(session_id).await;
+                match found {
+                    Ok(replay_path) => {
+                        tokio::spawn(send_replay_file(Arc::clone(&transport), replay_path, session_id, connection_id));
+                    }
+                    Err(_) => {
+                        let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                            code: 404,
+                            message: format!("No replay found for session {}", session_id),
+                        }).await;
+                    }
+                }
+            }
+
+            ClientMessage::RequestContent { kind, id } => {
+                let Some(transfer) = start_content_transfer(&transport_read, connection_id).await else {
+                    continue;
+                };
+                let (enabled, max_file_bytes, content) = {
+                    let state_read = state.read().await;
+                    let settings = &state_read.config.content_transfer;
+                    (settings.enabled, settings.max_file_bytes(), Arc::clone(&state_read.content))
+                };
+                if !enabled {
+                    let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                        code: 403,
+                        message: "Content downloads are disabled on this server".to_string(),
+                    }).await;
+                    continue;
+                }
+                // Hashing a large model would stall the message loop
+                let transport = Arc::clone(&transport);
+                tokio::spawn(async move {
+                    let _transfer = transfer;
+                    let manifest = tokio::task::spawn_blocking(move || content.manifest(kind, id, max_file_bytes)).await;
+                    let reply = match manifest {
+                        Ok(Some(Ok(manifest))) => ServerMessage::ContentManifest(manifest),
+                        Ok(None) => ServerMessage::Error {
+                            code: 404,
+                            message: format!("No {:?} with id {} is loaded", kind, id),
+                        },
+                        Ok(Some(Err(e))) => {
+                            warn!("Failed to list the files of {:?} {}: {}", kind, id, e);
+                            ServerMessage::Error { code: 500, message: "Failed to read content".to_string() }
+                        }
+                        Err(e) => {
+                            warn!("Listing the files of {:?} {} failed: {}", kind, id, e);
+                            ServerMessage::Error { code: 500, message: "Failed to read content".to_string() }
+                        }
+                    };
+                    let _ = transport.read().await.send_tcp(connection_id, reply).await;
+                });
+            }
+
+            ClientMessage::RequestContentFile { path, offset } => {
+                let Some(transfer) = start_content_transfer(&transport_read, connection_id).await else {
+                    continue;
+                };
+                let (enabled, max_file_bytes, file) = {
+                    let state_read = state.read().await;
+                    let settings = &state_read.config.content_transfer;
+                    (settings.enabled, settings.max_file_bytes(), state_read.content.file(&path).map(|f| f.to_path_buf()))
+                };
+                if !enabled {
+                    let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                        code: 403,
+                        message: "Content downloads are disabled on this server".to_string(),
+                    }).await;
+                    continue;
+                }
+                let Some(file) = file else {
+                    let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                        code: 404,
+                        message: format!("{} is not a content file of this server", path),
+                    }).await;
+                    continue;
+                };
+                let transport = Arc::clone(&transport);
+                tokio::spawn(async move {
+                    send_content_file(transport, file, path, offset, max_file_bytes, connection_id).await;
+                    drop(transfer);
+                });
+            }
+
+            ClientMessage::WatchReplay { session_id } => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let mut state_write = state.write().await;
+
+                    let found = match state_write.replay.find_replay(session_id).await {
+                        Ok(path) => match state_write.replay.read_replay_metadata(&path).await {
+                            Ok(metadata) => Ok((path, metadata)),
+                            // e.g. a replay written by a newer server
+                            Err(e) => Err((400, format!("Replay of session {} cannot be played: {}", session_id, e))),
+                        },
+                        Err(_) => Err((404, format!("No replay found for session {}", session_id))),
+                    };
+                    let (replay_path, metadata) = match found {
+                        Ok(found) => found,
+                        Err((code, message)) => {
+                            let _ = transport_read.send_tcp(connection_id, ServerMessage::Error { code, message }).await;
+                            continue;
+                        }
+                    };
+
+                    // Tracks without a fixed id get a new one on every start
+                    let track = state_write.track_configs.get(&metadata.track_config_id)
+                        .or_else(|| state_write.track_configs.values().find(|t| t.name == metadata.track_name))
+                        .map(|t| (t.id, t.source_path.clone()));
+                    let Some((track_config_id, track_file)) = track else {
+                        let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                            code: 404,
+                            message: format!("Track {} of the replay is not loaded", metadata.track_name),
+                        }).await;
+                        continue;
+                    };
+
+                    let Some(pending) = state_write.prepare_replay_session(conn_info.player_id, track_config_id) else {
+                        let _ = transport_read.send_tcp(connection_id, ServerMessage::Error {
+                            code: 503,
+                            message: "Server is at max session capacity".to_string(),
+                        }).await;
+                        continue;
+                    };
+                    let replay_session_id = pending.session_id();
+                    info!("Player {} watching replay of session {} in session {}",
+                        conn_info.player_name, session_id, replay_session_id);
+
+                    // Unlisted: the session only exists for this viewer
+                    state_write.lobby.register_session(LobbySessionInfo {
+                        session_id: replay_session_id,
+                        host_player_id: conn_info.player_id,
+                        host_name: conn_info.player_name.clone(),
+                        track_name: metadata.track_name.clone(),
+                        track_file: track_file.unwrap_or_else(|| "tracks/unknown.yaml".to_string()),
+                        session_kind: SessionKind::Practice,
+                        track_config_id,
+                        max_players: 0,
+                        current_player_count: 0,
+                        spectator_count: 0,
+                        state: SessionState::Preparing,
+                        visibility: SessionVisibility::Private,
+                        password_hash: None,
+                        created_at: std::time::Instant::now(),
+                        always_open: false,
+                        race_progress: None,
+                        setup_policy: SetupPolicy::default(),
+                        collision_policy: CollisionPolicy::default(),
+                        time_of_day: TimeOfDay::default(),
+                        aid_limits: DriverAids::unrestricted(),
+                    }).await;
+                    drop(state_write);
+
+                    tokio::spawn(open_replay_session(
+                        Arc::clone(&state),
+                        Arc::clone(&transport),
+                        pending,
+                        replay_path,
+                        conn_info.player_id,
+                        connection_id,
+                    ));
+                }
+            }
+
+            ClientMessage::ReplayPlay
+            | ClientMessage::ReplayPause
+            | ClientMessage::ReplaySeek { .. }
+            | ClientMessage::ReplaySetSpeed { .. }
+            | ClientMessage::ReplayJumpToHighlight { .. } => {
+                let control = match msg {
+                    ClientMessage::ReplayPlay => ReplayControl::Play,
+                    ClientMessage::ReplayPause => ReplayControl::Pause,
+                    ClientMessage::ReplaySeek { tick } => ReplayControl::Seek(tick),
+                    ClientMessage::ReplaySetSpeed { speed } => ReplayControl::SetSpeed(speed),
+                    ClientMessage::ReplayJumpToHighlight { index } => ReplayControl::JumpToHighlight(index as usize),
+                    _ => unreachable!(),
+                };
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let mut state_write = state.write().await;
+                    // Only the viewer who opened the replay drives playback
+                    let result = match conn_info.in_session.and_then(|sid| state_write.sessions.get_mut(&sid)) {
+                        Some(game_session) if game_session.session.host_player_id == conn_info.player_id => {
+                            game_session.control_replay(control).map_err(|e| (400, e))
+                        }
+                        Some(_) => Err((403, "Only the viewer who started the replay can control it".to_string())),
+                        None => Err((400, "Not watching a replay".to_string())),
+                    };
+                    if let Err((code, message)) = result {
+                        let _ = transport_read.send_tcp(connection_id, ServerMessage::Error { code, message }).await;
+                    }
+                }
+            }
+
+            ClientMessage::PlayerInput { server_tick_ack, throttle, brake, steering, gear, clutch } => {
+                if let Some(conn_info) = transport_read.get_connection(connection_id).await {
+                    let input = PlayerInputData { throttle, brake, steering, gear, clutch };
+                    queue_input(&input_queues, &conn_info, server_tick_ack, input);
+                }
+            }
+
+            _ => {
+                // Other messages (StartSession, etc.) handled elsewhere
+            }
+        }
+    }
+}
+
+/// Queue inputs sent as datagrams from the address of a connection; only
+/// inputs are taken over UDP
+async fn run_udp_messages(
+    transport: Arc<RwLock<TransportLayer>>,
+    mut messages: tokio::sync::mpsc::Receiver<(std::net::SocketAddr, crate::network::ClientMessage)>,
+    input_queues: Arc<InputQueues>,
+) {
+    use crate::network::ClientMessage;
+
+    while let Some((addr, msg)) = messages.recv().await {
+        let ClientMessage::PlayerInput { server_tick_ack, throttle, brake, steering, gear, clutch } = msg else {
+            debug!("Ignoring UDP message from {} that is not an input", addr);
+            continue;
+        };
+        let transport_read = transport.read().await;
+        let conn_info = match transport_read.connection_for_addr(addr).await {
+            Some(connection_id) => transport_read.get_connection(connection_id).await,
+            None => None,
+        };
+        match conn_info {
+            Some(conn_info) if !conn_info.allow_message() => {}
+            Some(conn_info) => {
+                let input = PlayerInputData { throttle, brake, steering, gear, clutch };
+                queue_input(&input_queues, &conn_info, server_tick_ack, input);
+            }
+            None => debug!("Dropping UDP input from unknown address {}", addr),
+        }
+    }
+}
+
+/// Queue an input for the session its sender drives in
+fn queue_input(input_queues: &InputQueues, conn_info: &ConnectionInfo, server_tick_ack: u32, input: PlayerInputData) {
+    let Some(session_id) = conn_info.in_session else {
+        return;
+    };
+    if !input_queues.push(session_id, conn_info.player_id, server_tick_ack, input) {
+        debug!("Too many inputs waiting in session {}; dropping input from {}", session_id, conn_info.player_name);
+    }
+}
+
+async fn run_game_loop(
+    state: Arc<RwLock<ServerState>>,
+    transport: Arc<RwLock<TransportLayer>>,
+    input_queues: Arc<InputQueues>,
+    tick_rate: u16,
+) {
+    let tick_duration = Duration::from_micros((1_000_000.0 / tick_rate as f64) as u64);
+    let tick_lag = Arc::clone(&state.read().await.tick_lag);
+    let mut clock = TickClock::new(tick_duration, std::time::Instant::now(), tick_lag);
+
+    let mut tick_count = 0u64;
+
+    loop {
+        tokio::time::sleep_until(tokio::time::Instant::from_std(clock.next_tick())).await;
+        let tick_started = std::time::Instant::now();
+        tick_count += 1;
+
+        run_tick(&state, &transport, &input_queues, tick_rate, tick_count).await;
+
+        if let Some(warning) = clock.finish_tick(tick_started, std::time::Instant::now()) {
+            warn!("{}", warning);
+        }
+    }
+}
+
+/// Run tick `tick_count` of the server: simulate every session, then record,
+/// save and broadcast what happened
+async fn run_tick(
+    state: &RwLock<ServerState>,
+    transport: &RwLock<TransportLayer>,
+    input_queues: &InputQueues,
+    tick_rate: u16,
+    tick_count: u64,
+) {
+    const SHOULD_LOG_TICKS: bool = false;
+    // Read every tick, as a config reload may change it
+    let stats_interval_ms = state.read().await.config.network.stats_interval_ms;
+
+
+    if SHOULD_LOG_TICKS {
+    // Log every second (240 ticks at 240Hz)
+        if tick_count.is_multiple_of(tick_rate as u64) {
+            let state_read = state.read().await;
+            info!(
+                "Tick {} - Active sessions: {}, Players: {}",
+                tick_count,
+                state_read.sessions.len(),
+                state_read.players.len()
+            );
+        }
+    }
+
+    let transport_read = transport.read().await;
+
+    let stats_interval_ticks = (stats_interval_ms * tick_rate as u64 / 1000).max(1);
+    if stats_interval_ms > 0 && tick_count.is_multiple_of(stats_interval_ticks) {
+        transport_read.send_connection_stats().await;
+    }
+
+    // Cleanup stale connections every second and handle disconnected players
+    if tick_count.is_multiple_of(tick_rate as u64) {
+        let disconnected_players = transport_read.cleanup_stale_connections().await;
+        
+        if !disconnected_players.is_empty() {
+            let mut state_write = state.write().await;
+            
+            for (player_id, session_id_opt) in disconnected_players {
+                info!("Handling disconnected player: {} (session: {:?})", player_id, session_id_opt);
+                state_write.remove_disconnected_player(player_id, session_id_opt).await;
+            }
+            
+            drop(state_write);
+        }
+    }
+
+    drop(transport_read);
+
+    // Update all sessions
+    let mut state_write = state.write().await;
+
+    // Collect replay operations to execute after iteration
+    let mut replay_starts = Vec::new();
+    let mut replay_ticks = Vec::new();
+    let mut replay_stops = Vec::new();
+
+    // Race events raised this tick, broadcast and recorded after iteration
+    let mut race_events = HashMap::new();
+
+    // Session states, mirrored into the lobby after iteration (it only publishes changes)
+    let mut session_states = Vec::new();
+
+    // Collect sessions to remove (empty or finished)
+    let mut sessions_to_remove = Vec::new();
+
+    // Inputs applied this tick, streamed to the hot standby
+    let replicating = state_write.replication.is_some();
+    let mut replicated_inputs = Vec::new();
+
+    // Tick each session
+    for (session_id, game_session) in state_write.sessions.iter_mut() {
+        // Check if session has no real (non-AI) players left
+        let real_player_count = game_session.session.participants.keys()
+            .filter(|player_id| !game_session.session.ai_player_ids.contains(player_id))
+            .count();
+
+        // Keep session alive if it's in Demo Lap mode with AI drivers
+        let is_demo_lap_with_ai = game_session.session.game_mode == GameMode::DemoLap
+            && !game_session.session.ai_player_ids.is_empty();
+
+        // Open practice sessions stay up with nobody in them
+        let is_open_practice = game_session.open_practice.is_some();
+
+        // Replays have no cars; they close when the viewer leaves
+        let is_replay = game_session.session.game_mode == GameMode::Replay;
+
+        if real_player_count == 0 && !is_demo_lap_with_ai && !is_open_practice && !is_replay {
+            info!("Session {} has no real players, marking for removal", session_id);
+            sessions_to_remove.push(*session_id);
+            continue;
+        }
+
+        // Inputs queued for the tick about to run, and AI inputs for cars without any
+        let mut session_inputs = input_queues.take(*session_id, game_session.session.current_tick + 1);
+        
+        for player_id in game_session.session.participants.keys() {
+            // Check if this is an AI player (simplified check)
+            if !session_inputs.contains_key(player_id) {
+                // No human input, generate AI input
+                let ai_input = game_session.generate_ai_input(player_id);
+                session_inputs.insert(*player_id, ai_input);
+            }
+        }
+
+        let prev_state = game_session.session.state;
+        game_session.tick(&session_inputs);
+        let new_state = game_session.session.state;
+        session_states.push((*session_id, new_state, game_session.race_progress()));
+
+        // Inputs of a race being recorded, up to the tick it finishes on; the tick it
+        // started on is in its initial state
+        if !is_replay && prev_state == SessionState::Racing {
+            let participants = &game_session.session.participants;
+            let tick = game_session.session.current_tick;
+            replay_ticks.push((
+                *session_id,
+                tick,
+                session_inputs.iter()
+                    .filter(|(id, _)| participants.contains_key(id))
+                    .map(|(id, input)| (*id, *input))
+                    .collect::<Vec<_>>(),
+                participants.values().map(|s| (s.player_id, s.car_config_id)).collect::<Vec<_>>(),
+                tick.is_multiple_of(STATE_HASH_INTERVAL_TICKS).then(|| game_session.state_hash()),
+            ));
+        }
+
+        if replicating {
+            replicated_inputs.push((*session_id, game_session.session.current_tick, session_inputs));
+        }
+
+        if game_session.was_reset_this_tick() {
+            info!("Open practice session {} reset", session_id);
+        }
+
+        let events = game_session.take_race_events();
+        if !events.is_empty() {
+            race_events.insert(*session_id, events);
+        }
+
+        // Collect replay recording operations
+        if !is_replay && prev_state != SessionState::Racing && new_state == SessionState::Racing {
+            replay_starts.push(*session_id);
+        }
+
+        // Collect replay stops when session finishes
+        if prev_state == SessionState::Racing && new_state == SessionState::Finished {
+            let finish_positions: HashMap<PlayerId, u8> = game_session.session.participants.iter()
+                .filter_map(|(id, state)| Some((*id, state.finish_position?)))
+                .collect();
+            replay_stops.push((*session_id, finish_positions));
+        }
+
+        // Log state changes
+        if game_session.session.current_tick % tick_rate as u32 == 0 {
+            match new_state {
+                SessionState::Countdown => {
+                    if let Some(countdown) = game_session.session.countdown_ticks_remaining {
+                        let seconds = countdown / tick_rate;
+                        info!("Session {} countdown: {}s", session_id, seconds);
+                    }
+                }
+                SessionState::Racing => {
+                    let lap_info: Vec<String> = game_session
+                        .session
+                        .participants
+                        .values()
+                        .map(|s| format!("L{}", s.current_lap))
+                        .collect();
+                    info!("Session {} racing - Laps: {:?}", session_id, lap_info);
+                }
+                SessionState::Finished => {
+                    info!("Session {} finished", session_id);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    for (session_id, session_state, race_progress) in session_states {
+        state_write.lobby.set_session_state(session_id, session_state).await;
+        state_write.lobby.set_race_progress(session_id, race_progress).await;
+    }
+
+    // Execute collected replay operations
+    for session_id in replay_starts {
+        state_write.start_replay_recording(session_id).await;
+    }
+
+    for (session_id, tick, inputs, cars, state_hash) in replay_ticks {
+        state_write.replay.record_tick(session_id, tick, inputs, cars, state_hash).await;
+    }
+
+    for (session_id, events) in &race_events {
+        state_write.replay.record_events(*session_id, events).await;
+    }
+
+    for (session_id, finish_positions) in replay_stops {
+        state_write.export_results(session_id).await;
+        state_write.update_lap_record(session_id).await;
+        match state_write.replay.stop_recording(session_id, &finish_positions).await {
+            Ok(replay_path) => {
+                info!("Replay saved for session {} to {:?}", session_id, replay_path);
+            }
+            Err(e) => {
+                warn!("Failed to save replay for session {}: {}", session_id, e);
+            }
+        }
+        // A finished race has nothing left to resume
+        if let Err(e) = state_write.saves.remove(session_id).await {
+            warn!("Failed to delete save of session {}: {}", session_id, e);
+        }
+    }
+
+    // Auto-save racing sessions so a restart can resume them
+    let auto_save_ticks = state_write.config.session_saves.auto_save_interval_seconds as u64 * tick_rate as u64;
+    if auto_save_ticks > 0 && tick_count.is_multiple_of(auto_save_ticks) {
+        let racing: Vec<SessionId> = state_write.sessions.iter()
+            .filter(|(_, s)| s.session.state == SessionState::Racing && s.session.game_mode != GameMode::Replay)
+            .map(|(id, _)| *id)
+            .collect();
+        for session_id in racing {
+            match state_write.save_session(session_id).await {
+                Ok(issued) => notify_saved_drivers(&*transport.read().await, session_id, &issued).await,
+                Err(message) => warn!("{}", message),
+            }
+        }
+    }
+
+    // Remove empty sessions from the game state and lobby
+    for session_id in sessions_to_remove {
+        state_write.sessions.remove(&session_id);
+        state_write.lobby.unregister_session(session_id).await;
+        info!("Removed empty session {}", session_id);
+    }
+    input_queues.retain_sessions(|session_id| state_write.sessions.contains_key(session_id));
+
+    // Journal the sessions so a crash does not lose them
+    let journal_ticks = state_write.config.journal.interval_seconds.max(1) as u64 * tick_rate as u64;
+    if state_write.journal.is_some() && tick_count.is_multiple_of(journal_ticks) {
+        let issued = state_write.write_journal().await;
+        if !issued.is_empty() {
+            let transport = transport.read().await;
+            for (session_id, issued) in issued {
+                notify_saved_drivers(&transport, session_id, &issued).await;
+            }
+        }
+    }
+
+    if replicating {
+        replicate_sessions(&mut state_write, transport, replicated_inputs, tick_count).await;
+    }
+
+    // Delayed spectators are fed from the recording in progress
+    let ServerState { sessions, replay, .. } = &mut *state_write;
+    for (session_id, game_session) in sessions.iter_mut() {
+        for feed in game_session.delayed_feeds_mut() {
+            replay.follow(*session_id, feed).await;
+        }
+    }
+
+    // Each session addresses its own telemetry, standings and events
+    let transport_read = transport.read().await;
+    for (session_id, game_session) in state_write.sessions.iter_mut() {
+        let mut frames = game_session.take_broadcast();
+        frames.extend(game_session.take_delayed_broadcast());
+        for frame in frames {
+            if tick_count.is_multiple_of(60) {
+                debug!("Broadcasting {} message(s) for session {} to {} connection(s) (total participants: {}, state: {:?})",
+                    frame.messages.len(), session_id, frame.recipients.len(),
+                    game_session.session.participants.len(), game_session.session.state);
+            }
+
+            for conn_id in frame.recipients {
+                for message in &frame.messages {
+                    let _ = transport_read.send_tcp(conn_id, message.clone()).await;
+                }
+            }
+        }
+    }
+    drop(transport_read);
+
+    state_write.send_dashboard_packets();
+
+    // Refresh the live timing feed
+    let timing = &state_write.config.live_timing;
+    let timing_ticks = (timing.interval_ms * tick_rate as u64 / 1000).max(1);
+    if timing.enabled && tick_count.is_multiple_of(timing_ticks) {
+        state_write.publish_live_timing().await;
+    }
+
+    // Cleanup finished sessions (older than timeout)
+    let timeout_seconds = state_write.config.server.session_timeout_seconds as u64;
+    state_write.sessions.retain(|id, session| {
+        if session.session.state == SessionState::Finished {
+            let age_ticks = tick_count.saturating_sub(session.session.current_tick as u64);
+            let age_seconds = age_ticks / tick_rate as u64;
+            
+            if age_seconds > timeout_seconds {
+                info!("Removing finished session: {}", id);
+                return false;
+            }
+        }
+        true
+    });
+    drop(state_write);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_server_state_creation() {
+        let config = ServerConfig::default();
+        let state = ServerState::new(config);
+
+        assert!(!state.car_configs.is_empty());
+        assert!(!state.track_configs.is_empty());
+        assert_eq!(state.sessions.len(), 0);
+    }
+
+    #[test]
+    fn test_create_session() {
+        let config = ServerConfig::default();
+        let mut state = ServerState::new(config);
+
+        let host_id = PlayerId::new_v4();
+        let track_id = state.track_configs.values().next().unwrap().id;
+        let car_id = state.car_configs.values().next().unwrap().id;
+
+        let session_id = state.create_session(host_id, car_id, track_id, SessionKind::Practice, 8, 2, 5);
+
+        assert!(session_id.is_some());
+        assert_eq!(state.sessions.len(), 1);
+    }
+
+    #[test]
+    fn test_max_sessions_limit() {
+        let config = ServerConfig::default();
+        let mut state = ServerState::new(config);
+        state.config.server.max_sessions = 2;
+
+        let host_id = PlayerId::new_v4();
+        let track_id = state.track_configs.values().next().unwrap().id;
+        let car_id = state.car_configs.values().next().unwrap().id;
+
+        // Create max sessions
+        for _ in 0..2 {
+            let result = state.create_session(host_id, car_id, track_id, SessionKind::Sandbox, 8, 0, 3);
+            assert!(result.is_some());
+        }
+
+        // Try to create one more
+        let result = state.create_session(host_id, car_id, track_id, SessionKind::Multiplayer, 8, 0, 3);
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn test_preparing_sessions_hold_their_slot() {
+        let config = ServerConfig::default();
+        let mut state = ServerState::new(config);
+        state.config.server.max_sessions = 1;
+
+        let host_id = PlayerId::new_v4();
+        let track_id = state.track_configs.values().next().unwrap().id;
+        let car_id = state.car_configs.values().next().unwrap().id;
+
+        let pending = state.prepare_session(host_id, car_id, track_id, SessionKind::Multiplayer, 8, 1, 3).unwrap();
+        assert!(state.sessions.is_empty());
+        assert!(state.prepare_session(host_id, car_id, track_id, SessionKind::Multiplayer, 8, 0, 3).is_none());
+
+        let game_session = pending.build();
+        assert_eq!(game_session.track_config.id, track_id);
+        assert_eq!(game_session.session.participants.len(), 1);
+    }
+
+    /// Create a lobby-state session registered in the lobby with the given player ratings
+    async fn setup_lobby_session(state: &mut ServerState, ratings: &[u32]) -> (SessionId, Vec<PlayerId>) {
+        use crate::lobby::{LobbyPlayerState, LobbySessionInfo, SessionVisibility};
+
+        let track_id = state.track_configs.values().next().unwrap().id;
+        let car_id = state.car_configs.values().next().unwrap().id;
+        let player_ids: Vec<PlayerId> = ratings.iter().map(|_| PlayerId::new_v4()).collect();
+
+        let session_id = state.create_session(player_ids[0], car_id, track_id, SessionKind::Multiplayer, 8, 0, 3).unwrap();
+        state.lobby.register_session(LobbySessionInfo {
+            session_id,
+            host_player_id: player_ids[0],
+            host_name: "Player 0".to_string(),
+            track_name: "Test Track".to_string(),
+            track_file: String::new(),
+            track_config_id: track_id,
+            session_kind: SessionKind::Multiplayer,
+            max_players: 8,
+            current_player_count: 0,
+            spectator_count: 0,
+            state: SessionState::Lobby,
+            visibility: SessionVisibility::Public,
+            password_hash: None,
+            created_at: std::time::Instant::now(),
+            always_open: false,
+            race_progress: None,
+            setup_policy: SetupPolicy::Open,
+            collision_policy: CollisionPolicy::Solid,
+            time_of_day: TimeOfDay::Noon,
+            aid_limits: DriverAids::unrestricted(),
+        }).await;
+
+        for (i, (player_id, rating)) in player_ids.iter().zip(ratings).enumerate() {
+            state.lobby.add_player(LobbyPlayerState {
+                player_id: *player_id,
+                player_name: format!("Player {}", i),
+                connection_id: Uuid::new_v4(),
+                selected_car: Some(car_id),
+                selected_livery: 0,
+                rating: *rating,
+            }).await;
+            assert!(state.lobby.join_session(*player_id, session_id).await);
+            assert!(state.sessions.get_mut(&session_id).unwrap().add_player(*player_id, car_id).is_some());
+        }
+
+        (session_id, player_ids)
+    }
+
+    #[tokio::test]
+    async fn test_split_session_by_rating() {
+        let mut state = ServerState::new(ServerConfig::default());
+        let (session_id, players) = setup_lobby_session(&mut state, &[1200, 1800, 1500, 1000]).await;
+
+        let (new_session_id, moved) = state.split_session(session_id).await.unwrap();
+
+        assert_eq!(moved.len(), 2);
+        let moved_ids: Vec<PlayerId> = moved.iter().map(|(id, _)| *id).collect();
+        assert!(moved_ids.contains(&players[0]));
+        assert!(moved_ids.contains(&players[3]));
+
+        assert_eq!(state.sessions[&session_id].session.participants.len(), 2);
+        assert_eq!(state.sessions[&new_session_id].session.participants.len(), 2);
+        assert_eq!(state.lobby.get_player_session(players[3]).await, Some(new_session_id));
+        assert_eq!(state.lobby.get_player_session(players[1]).await, Some(session_id));
+        assert_eq!(state.lobby.get_session_info(new_session_id).await.unwrap().current_player_count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_merge_sessions() {
+        let mut state = ServerState::new(ServerConfig::default());
+        let (source_id, source_players) = setup_lobby_session(&mut state, &[1500, 1400]).await;
+        let (target_id, _) = setup_lobby_session(&mut state, &[1600, 1300, 1700]).await;
+
+        let moved = state.merge_sessions(source_id, target_id).await.unwrap();
+
+        assert_eq!(moved.len(), 2);
+        assert!(!state.sessions.contains_key(&source_id));
+        assert!(state.lobby.get_session_info(source_id).await.is_none());
+        assert_eq!(state.sessions[&target_id].session.participants.len(), 5);
+        for player_id in source_players {
+            assert_eq!(state.lobby.get_player_session(player_id).await, Some(target_id));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_merge_sessions_rejects_overflow() {
+        let mut state = ServerState::new(ServerConfig::default());
+        let (source_id, _) = setup_lobby_session(&mut state, &[1500; 5]).await;
+        let (target_id, _) = setup_lobby_session(&mut state, &[1500; 5]).await;
+
+        assert!(state.merge_sessions(source_id, target_id).await.is_err());
+        assert!(state.sessions.contains_key(&source_id));
+        assert_eq!(state.sessions[&target_id].session.participants.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_open_practice_sessions_are_joinable_and_persistent() {
+        use crate::lobby::LobbyPlayerState;
+
+        let mut state = ServerState::new(ServerConfig::default());
+        state.create_open_practice_sessions().await;
+        assert_eq!(state.sessions.len(), state.track_configs.len());
+
+        let (session_id, game_session) = state.sessions.iter_mut().next().unwrap();
+        let session_id = *session_id;
+        assert_eq!(game_session.session.state, SessionState::Racing);
+
+        // Wheel in while the session is running
+        let player_id = PlayerId::new_v4();
+        let car_id = state.car_configs.values().next().unwrap().id;
+        state.lobby.add_player(LobbyPlayerState {
+            player_id,
+            player_name: "Driver".to_string(),
+            connection_id: Uuid::new_v4(),
+            selected_car: Some(car_id),
+            selected_livery: 0,
+            rating: 1500,
+        }).await;
+        assert!(state.lobby.join_session(player_id, session_id).await);
+        assert!(state.sessions.get_mut(&session_id).unwrap().add_player(player_id, car_id).is_some());
+
+        // Wheel out: the session is not reported as empty and stays registered
+        assert_eq!(state.lobby.leave_session(player_id, Uuid::new_v4()).await, None);
+        assert!(state.lobby.get_session_info(session_id).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_recovered_session_can_be_resumed() {
+        use crate::lobby::LobbyPlayerState;
+
+        let mut primary = ServerState::new(ServerConfig::default());
+        let (session_id, player_ids) = setup_lobby_session(&mut primary, &[1500]).await;
+        let game_session = primary.sessions.remove(&session_id).unwrap();
+
+        let token = Uuid::new_v4();
+        let mut standby = ServerState::new(ServerConfig::default());
+        standby.recover_sessions(vec![RecoveredSession {
+            game_session,
+            host_name: "Player 0".to_string(),
+            resume_tokens: HashMap::from([(token, player_ids[0])]),
+        }]).await;
+
+        assert!(standby.lobby.get_session_info(session_id).await.is_some());
+        assert_eq!(standby.resume_tokens.get(&token), Some(&(session_id, player_ids[0])));
+
+        // The player reconnects under a new ID and reclaims their car
+        let new_id = PlayerId::new_v4();
+        standby.lobby.add_player(LobbyPlayerState {
+            player_id: new_id,
+            player_name: "Player 0".to_string(),
+            connection_id: Uuid::new_v4(),
+            selected_car: None,
+            selected_livery: 0,
+            rating: 1500,
+        }).await;
+        let game_session = standby.sessions.get_mut(&session_id).unwrap();
+        assert!(game_session.reassign_player(player_ids[0], new_id));
+        assert_eq!(game_session.session.host_player_id, new_id);
+        assert_eq!(game_session.session.participants[&new_id].player_id, new_id);
+        assert!(standby.lobby.resume_player(new_id, session_id).await);
+        assert_eq!(standby.lobby.get_player_session(new_id).await, Some(session_id));
+    }
+
+    /// A config whose listeners take free ports
+    fn in_process_config() -> ServerConfig {
+        let mut config = ServerConfig::default();
+        config.network.tcp_bind = "127.0.0.1:0".to_string();
+        config.network.udp_bind = "127.0.0.1:0".to_string();
+        config.network.health_bind = "127.0.0.1:0".to_string();
+        config
+    }
+
+    /// The first message `pick` accepts
+    async fn receive<T>(messages: &mut mpsc::Receiver<ServerMessage>, mut pick: impl FnMut(ServerMessage) -> Option<T>) -> T {
+        let receive = async {
+            while let Some(message) = messages.recv().await {
+                if let Some(picked) = pick(message) {
+                    return picked;
+                }
+            }
+            panic!("Server closed the connection");
+        };
+        tokio::time::timeout(Duration::from_secs(10), receive).await.expect("No such message from the server")
+    }
+
+    #[tokio::test]
+    async fn test_runtime_runs_a_session_in_process() {
+        let server = ServerRuntime::builder(in_process_config()).manual_ticks(true).start().await.unwrap();
+        assert!(server.tcp_addr().await.is_some_and(|addr| addr.port() != 0));
+
+        let (connection_id, mut messages) = server.connect("Tester", "local").await;
+        let player_id = receive(&mut messages, |message| match message {
+            ServerMessage::AuthSuccess(auth) => Some(auth.player_id),
+            _ => None,
+        }).await;
+        let (track_config_id, car_config_id) = {
+            let state = server.state().await;
+            (state.track_configs().values().next().unwrap().id, state.car_configs().values().next().unwrap().id)
+        };
+
+        assert!(server.send(connection_id, ClientMessage::SelectCar { car_config_id, livery: 0 }).await);
+        assert!(server.send(connection_id, ClientMessage::CreateSession {
+            track_config_id,
+            max_players: 4,
+            ai_count: 1,
+            lap_limit: 3,
+            session_kind: SessionKind::Practice,
+            weather: Default::default(),
+            setup_policy: Default::default(),
+            collision_policy: Default::default(),
+            time_of_day: Default::default(),
+            aid_limits: None,
+        }).await);
+        let session_id = receive(&mut messages, |message| match message {
+            ServerMessage::SessionJoined(joined) => Some(joined.session_id),
+            _ => None,
+        }).await;
+
+        // Nothing ticks until the caller steps
+        let tick_before = server.state().await.sessions()[&session_id].session.current_tick;
+        assert_eq!(server.step(0).await, Some(0));
+        assert_eq!(server.step(30).await, Some(30));
+        {
+            let state = server.state().await;
+            let game_session = &state.sessions()[&session_id];
+            assert_eq!(game_session.session.current_tick, tick_before + 30);
+            assert!(game_session.session.participants.contains_key(&player_id));
+            assert_eq!(game_session.session.participants.len(), 2, "The player and an AI driver");
+        }
+
+        server.shutdown().await;
+        assert!(messages.recv().await.is_some(), "Told the server is shutting down");
+    }
+
+    #[tokio::test]
+    async fn test_selected_livery_reaches_the_session_car() {
+        let server = ServerRuntime::builder(in_process_config()).manual_ticks(true).start().await.unwrap();
+        let (connection_id, mut messages) = server.connect("Painter", "local").await;
+        let player_id = receive(&mut messages, |message| match message {
+            ServerMessage::AuthSuccess(auth) => Some(auth.player_id),
+            _ => None,
+        }).await;
+        let (track_config_id, car_config_id) = {
+            let state = server.state().await;
+            let car = state.car_configs().values().find(|c| c.liveries.len() >= 2).expect("A car with liveries");
+            (state.track_configs().values().next().unwrap().id, car.id)
+        };
+
+        assert!(server.send(connection_id, ClientMessage::SelectCar { car_config_id, livery: 200 }).await);
+        let code = receive(&mut messages, |message| match message {
+            ServerMessage::Error { code, .. } => Some(code),
+            _ => None,
+        }).await;
+        assert_eq!(code, 400, "No such livery");
+
+        assert!(server.send(connection_id, ClientMessage::SelectCar { car_config_id, livery: 1 }).await);
+        assert!(server.send(connection_id, ClientMessage::CreateSession {
+            track_config_id,
+            max_players: 4,
+            ai_count: 0,
+            lap_limit: 3,
+            session_kind: SessionKind::Practice,
+            weather: Default::default(),
+            setup_policy: Default::default(),
+            collision_policy: Default::default(),
+            time_of_day: Default::default(),
+            aid_limits: None,
+        }).await);
+        let session_id = receive(&mut messages, |message| match message {
+            ServerMessage::SessionJoined(joined) => Some(joined.session_id),
+            _ => None,
+        }).await;
+
+        {
+            let state = server.state().await;
+            let car = &state.sessions()[&session_id].session.participants[&player_id];
+            assert_eq!(car.livery, 1);
+            assert_eq!(crate::network::CarStateTelemetry::from(car).livery, 1);
+        }
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_listed_replays_name_their_drivers_and_finishers() {
+        let replays = tempfile::tempdir().unwrap();
+        let server = ServerRuntime::builder(in_process_config()).manual_ticks(true).start().await.unwrap();
+        server.state.write().await.replay = ReplayManager::new(replays.path().to_path_buf());
+
+        let (connection_id, mut messages) = server.connect("Tester", "local").await;
+        let player_id = receive(&mut messages, |message| match message {
+            ServerMessage::AuthSuccess(auth) => Some(auth.player_id),
+            _ => None,
+        }).await;
+        let (track_config_id, car_config_id) = {
+            let state = server.state().await;
+            (state.track_configs().values().next().unwrap().id, state.car_configs().values().next().unwrap().id)
+        };
+        assert!(server.send(connection_id, ClientMessage::SelectCar { car_config_id, livery: 0 }).await);
+        assert!(server.send(connection_id, ClientMessage::CreateSession {
+            track_config_id,
+            max_players: 4,
+            ai_count: 1,
+            lap_limit: 3,
+            session_kind: SessionKind::Multiplayer,
+            weather: Default::default(),
+            setup_policy: Default::default(),
+            collision_policy: Default::default(),
+            time_of_day: Default::default(),
+            aid_limits: None,
+        }).await);
+        let session_id = receive(&mut messages, |message| match message {
+            ServerMessage::SessionJoined(joined) => Some(joined.session_id),
+            _ => None,
+        }).await;
+
+        // The game loop starts recording when a tick takes the session into
+        // racing; this mode change lands between ticks, so start it here
+        assert!(server.send(connection_id, ClientMessage::SetGameMode { mode: GameMode::FreePractice }).await);
+        wait_for(&server, |state| state.sessions()[&session_id].session.state == SessionState::Racing).await;
+        server.state.write().await.start_replay_recording(session_id).await;
+        server.step(10).await;
+        server.state.write().await.sessions.get_mut(&session_id).unwrap().request_finish().unwrap();
+        server.step(1).await;
+
+        let ai_name = {
+            let state = server.state().await;
+            let game_session = &state.sessions()[&session_id];
+            assert_eq!(game_session.session.state, SessionState::Finished);
+            game_session.ai_profiles.values().next().unwrap().name.clone()
+        };
+        assert!(server.send(connection_id, ClientMessage::ListReplays).await);
+        let replay = receive(&mut messages, |message| match message {
+            ServerMessage::ReplayList(list) => list.replays.into_iter().find(|r| r.session_id == session_id),
+            _ => None,
+        }).await;
+
+        let names: Vec<(bool, &str)> =
+            replay.participants.iter().map(|p| (p.player_id == player_id, p.player_name.as_str())).collect();
+        assert_eq!(names.len(), 2);
+        assert!(names.contains(&(true, "Tester")));
+        assert!(names.contains(&(false, ai_name.as_str())));
+        let mut positions: Vec<Option<u8>> = replay.participants.iter().map(|p| p.finish_position).collect();
+        positions.sort();
+        assert_eq!(positions, vec![Some(1), Some(2)]);
+
+        server.shutdown().await;
+    }
+
+    /// Wait until `ready` holds for the server's state
+    async fn wait_for(server: &ServerRuntime, ready: impl Fn(&ServerState) -> bool) {
+        let wait = async {
+            while !ready(&*server.state().await) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(10), wait).await.expect("Server state never got there");
+    }
+
+    #[tokio::test]
+    async fn test_gateway_routes_players_to_instances() {
+        let mut config = in_process_config();
+        config.cluster.role = ClusterRole::Gateway;
+        config.cluster.bind = "127.0.0.1:0".to_string();
+        config.cluster.local_sessions = false;
+        config.cluster.token = "cluster-token".to_string();
+        let gateway = ServerRuntime::builder(config).manual_ticks(true).start().await.unwrap();
+
+        let mut config = in_process_config();
+        config.cluster.role = ClusterRole::Instance;
+        config.cluster.gateway_url = format!("http://{}", gateway.cluster_addr().unwrap());
+        config.cluster.public_address = "instance.test:9000".to_string();
+        config.cluster.report_interval_ms = 20;
+        config.cluster.token = "cluster-token".to_string();
+        let instance = ServerRuntime::builder(config).manual_ticks(true).start().await.unwrap();
+        wait_for(&gateway, |state| state.cluster().is_some_and(|cluster| !cluster.instances().is_empty())).await;
+
+        let (track_config_id, car_config_id) = {
+            let state = gateway.state().await;
+            (state.track_configs().values().next().unwrap().id, state.car_configs().values().next().unwrap().id)
+        };
+        let create_session = ClientMessage::CreateSession {
+            track_config_id,
+            max_players: 4,
+            ai_count: 0,
+            lap_limit: 3,
+            session_kind: SessionKind::Multiplayer,
+            weather: Default::default(),
+            setup_policy: Default::default(),
+            collision_policy: Default::default(),
+            time_of_day: Default::default(),
+            aid_limits: None,
+        };
+
+        // The gateway hosts nothing itself, so new sessions go to the instance
+        let (connection_id, mut messages) = gateway.connect("Player", "local").await;
+        assert!(gateway.send(connection_id, ClientMessage::SelectCar { car_config_id, livery: 0 }).await);
+        assert!(gateway.send(connection_id, create_session.clone()).await);
+        let redirect = receive(&mut messages, |message| match message {
+            ServerMessage::InstanceRedirect(redirect) => Some(redirect),
+            _ => None,
+        }).await;
+        assert_eq!(redirect.address, "instance.test:9000");
+        assert_eq!(redirect.session_id, None);
+
+        let (host_id, mut host_messages) = instance.connect("Host", "local").await;
+        assert!(instance.send(host_id, ClientMessage::SelectCar { car_config_id, livery: 0 }).await);
+        assert!(instance.send(host_id, create_session).await);
+        let session_id = receive(&mut host_messages, |message| match message {
+            ServerMessage::SessionJoined(joined) => Some(joined.session_id),
+            _ => None,
+        }).await;
+
+        // The instance's session shows in the gateway's lobby, and joining it is redirected
+        wait_for(&gateway, |state| state.cluster().unwrap().instance_hosting(session_id).is_some()).await;
+        assert!(gateway.send(connection_id, ClientMessage::RequestLobbyState).await);
+        let listed = receive(&mut messages, |message| match message {
+            ServerMessage::LobbyState(lobby) => Some(lobby.available_sessions),
+            _ => None,
+        }).await;
+        assert!(listed.iter().any(|session| session.id == session_id));
+        assert!(gateway.send(connection_id, ClientMessage::JoinSession { session_id }).await);
+        let redirect = receive(&mut messages, |message| match message {
+            ServerMessage::InstanceRedirect(redirect) => Some(redirect),
+            _ => None,
+        }).await;
+        assert_eq!((redirect.address.as_str(), redirect.session_id), ("instance.test:9000", Some(session_id)));
+
+        instance.shutdown().await;
+        assert!(gateway.state().await.cluster().unwrap().instances().is_empty(), "Instances leave on shutdown");
+        gateway.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_session_limits_refuse_a_second_session_from_one_address() {
+        let mut config = in_process_config();
+        config.session_limits.max_per_address = 1;
+        let server = ServerRuntime::builder(config).manual_ticks(true).start().await.unwrap();
+        let (track_config_id, car_config_id) = {
+            let state = server.state().await;
+            (state.track_configs().values().next().unwrap().id, state.car_configs().values().next().unwrap().id)
+        };
+        let create_session = ClientMessage::CreateSession {
+            track_config_id,
+            max_players: 4,
+            ai_count: 0,
+            lap_limit: 3,
+            session_kind: SessionKind::Multiplayer,
+            weather: Default::default(),
+            setup_policy: Default::default(),
+            collision_policy: Default::default(),
+            time_of_day: Default::default(),
+            aid_limits: None,
+        };
+
+        let (host_id, mut host_messages) = server.connect("Host", "local").await;
+        assert!(server.send(host_id, ClientMessage::SelectCar { car_config_id, livery: 0 }).await);
+        assert!(server.send(host_id, create_session.clone()).await);
+        receive(&mut host_messages, |message| match message {
+            ServerMessage::SessionJoined(joined) => Some(joined.session_id),
+            _ => None,
+        }).await;
+
+        // In-process clients share an address, so the second host is refused
+        let (other_id, mut other_messages) = server.connect("Other", "local").await;
+        assert!(server.send(other_id, ClientMessage::SelectCar { car_config_id, livery: 0 }).await);
+        assert!(server.send(other_id, create_session).await);
+        let code = receive(&mut other_messages, |message| match message {
+            ServerMessage::Error { code, .. } => Some(code),
+            ServerMessage::SessionJoined(_) => Some(0),
+            _ => None,
+        }).await;
+        assert_eq!(code, 429);
+        assert_eq!(server.state().await.sessions().len(), 1);
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_content_download_resumes_from_an_offset() {
+        use crate::network::ContentKind;
+
+        let server = ServerRuntime::builder(in_process_config()).manual_ticks(true).start().await.unwrap();
+        let car_config_id = server.state().await.car_configs().values().find(|c| c.source_path.is_some()).unwrap().id;
+        let (client_id, mut messages) = server.connect("Downloader", "local").await;
+
+        assert!(server.send(client_id, ClientMessage::RequestContent { kind: ContentKind::Car, id: car_config_id.0 }).await);
+        let manifest = receive(&mut messages, |message| match message {
+            ServerMessage::ContentManifest(manifest) => Some(manifest),
+            _ => None,
+        }).await;
+        let car_toml = manifest.files.iter().find(|f| f.path.ends_with("/car.toml")).unwrap();
+        let bytes = std::fs::read(std::path::Path::new("../content").join(&car_toml.path)).unwrap();
+        assert_eq!(car_toml.size_bytes, bytes.len() as u64);
+        assert_eq!(car_toml.sha256, crate::content_hash::hash_parts(&[&bytes]));
+
+        let offset = car_toml.size_bytes - 10;
+        assert!(server.send(client_id, ClientMessage::RequestContentFile { path: car_toml.path.clone(), offset }).await);
+        let chunk = receive(&mut messages, |message| match message {
+            ServerMessage::ContentChunk(chunk) => Some(chunk),
+            _ => None,
+        }).await;
+        assert_eq!((chunk.offset, chunk.total_bytes), (offset, car_toml.size_bytes));
+        assert_eq!(chunk.data, bytes[offset as usize..]);
+
+        // Only files of loaded content are served
+        assert!(server.send(client_id, ClientMessage::RequestContentFile { path: "../server/server.toml".to_string(), offset: 0 }).await);
+        let code = receive(&mut messages, |message| match message {
+            ServerMessage::Error { code, .. } => Some(code),
+            _ => None,
+        }).await;
+        assert_eq!(code, 404);
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_content_downloads_are_capped_per_connection() {
+        use crate::network::ContentKind;
+
+        let server = ServerRuntime::builder(in_process_config()).manual_ticks(true).start().await.unwrap();
+        let car_config_id = server.state().await.car_configs().values().find(|c| c.source_path.is_some()).unwrap().id;
+        let (client_id, mut messages) = server.connect("Downloader", "local").await;
+        let conn_info = server.transport.read().await.get_connection(client_id).await.unwrap();
+        let running: Vec<_> = (0..MAX_CONTENT_TRANSFERS_PER_CONNECTION)
+            .map(|_| conn_info.start_content_transfer(MAX_CONTENT_TRANSFERS_PER_CONNECTION).unwrap())
+            .collect();
+
+        let request = ClientMessage::RequestContent { kind: ContentKind::Car, id: car_config_id.0 };
+        assert!(server.send(client_id, request.clone()).await);
+        let code = receive(&mut messages, |message| match message {
+            ServerMessage::Error { code, .. } => Some(code),
+            _ => None,
+        }).await;
+        assert_eq!(code, 429);
+
+        drop(running);
+        assert!(server.send(client_id, request).await);
+        receive(&mut messages, |message| match message {
+            ServerMessage::ContentManifest(manifest) => Some(manifest),
+            _ => None,
+        }).await;
+
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_generated_track_is_loaded_and_served() {
+        use crate::admin_api::TrackGenerate;
+        use crate::procgen::circuit::CircuitParams;
+
+        let content = tempfile::tempdir().unwrap();
+        std::fs::create_dir(content.path().join("tracks")).unwrap();
+        let mut config = in_process_config();
+        config.content.tracks_dir = content.path().join("tracks").to_string_lossy().into_owned();
+        let server = ServerRuntime::builder(config).manual_ticks(true).start().await.unwrap();
+        let generate = |name: &str| AdminCommand::GenerateTrack {
+            generate: TrackGenerate {
+                name: name.to_string(),
+                params: CircuitParams { length_m: 2000.0, corners: 6, seed: Some(3), environment: None, ..Default::default() },
+            },
+        };
+        let server_ref = &server;
+        let execute = |command| async move {
+            execute_admin_command(&server_ref.state, &server_ref.transport, &command, &server_ref.health_state, &server_ref.shutdown)
+                .await
+        };
+
+        let Ok(AdminResponse::Track(track)) = execute(generate("Test Ring")).await else {
+            panic!("track not generated");
+        };
+        assert_eq!(track.source_path.as_deref(), Some("tracks/generated/test-ring.yaml"));
+        assert_eq!(track.seed, Some(3));
+        assert!((track.length_m - 2000.0).abs() < 30.0, "length {}", track.length_m);
+        {
+            let state = server.state().await;
+            assert!(state.track_configs().contains_key(&track.track_id));
+            assert!(state.content.file("tracks/generated/test-ring.yaml").is_some());
+        }
+
+        assert_eq!(execute(generate("test ring")).await.unwrap_err().status, hyper::StatusCode::CONFLICT);
+        assert_eq!(execute(generate(" ")).await.unwrap_err().status, hyper::StatusCode::BAD_REQUEST);
+        server.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_runtime_refuses_an_invalid_config() {
+        let mut config = in_process_config();
+        config.server.tick_rate_hz = 0;
+        let error = ServerRuntime::builder(config).start().await.err().unwrap();
+        assert!(error.to_string().contains("server.tick_rate_hz"), "{}", error);
+    }
+}
@@ -0,0 +1,232 @@
+//! Per-session broadcast fan-out.
+//!
+//! Each `GameSession` owns a `SessionBroadcaster` that knows which connection
+//! every player and spectator in the session uses. Once per tick the session
+//! turns its state plus any queued events into a `BroadcastFrame`, and the game
+//! loop hands that frame to the transport without looking inside the session.
+//!
+//! Public spectators can be given pseudonymous copies of the frame instead, so
+//! an open feed never carries real player identities.
+
+use crate::anonymize::Pseudonymizer;
+use crate::data::*;
+use crate::network::{ExtendedCarTelemetry, ExtendedTelemetry, ServerMessage};
+use std::collections::{HashMap, HashSet};
+
+/// Simulation ticks per second
+const TICK_RATE_HZ: u32 = 240;
+
+/// Standings go out twice per second
+const DEFAULT_STANDINGS_INTERVAL_TICKS: u32 = TICK_RATE_HZ / 2;
+
+/// Extended telemetry goes out ten times per second
+const DEFAULT_EXTENDED_INTERVAL_TICKS: u32 = TICK_RATE_HZ / 10;
+
+/// Messages ready to send to every connection watching a session
+#[derive(Debug, Clone)]
+pub struct BroadcastFrame {
+    pub recipients: Vec<ConnectionId>,
+    pub messages: Vec<ServerMessage>,
+}
+
+/// Recipients, rates and queued events of one session's broadcasts
+#[derive(Debug, Clone)]
+pub struct SessionBroadcaster {
+    /// Connection of each human participant or spectator
+    connections: HashMap<PlayerId, ConnectionId>,
+    /// Spectators on the public feed, who get pseudonymized frames
+    public: HashSet<PlayerId>,
+    /// Spectators who asked for extended telemetry of every car
+    extended: HashSet<PlayerId>,
+    /// Set when public feeds are anonymized
+    pseudonyms: Option<Pseudonymizer>,
+    telemetry_interval_ticks: u32,
+    standings_interval_ticks: u32,
+    extended_interval_ticks: u32,
+    /// Session-scoped messages sent with the next frame
+    pending: Vec<ServerMessage>,
+}
+
+impl SessionBroadcaster {
+    pub fn new() -> Self {
+        Self {
+            connections: HashMap::new(),
+            public: HashSet::new(),
+            extended: HashSet::new(),
+            pseudonyms: None,
+            telemetry_interval_ticks: 1,
+            standings_interval_ticks: DEFAULT_STANDINGS_INTERVAL_TICKS,
+            extended_interval_ticks: DEFAULT_EXTENDED_INTERVAL_TICKS,
+            pending: Vec::new(),
+        }
+    }
+
+    /// A broadcaster with the same pseudonyms and rates, but no recipients or queued messages
+    pub fn without_recipients(&self) -> Self {
+        Self {
+            connections: HashMap::new(),
+            public: HashSet::new(),
+            extended: HashSet::new(),
+            pseudonyms: self.pseudonyms.clone(),
+            telemetry_interval_ticks: self.telemetry_interval_ticks,
+            standings_interval_ticks: self.standings_interval_ticks,
+            extended_interval_ticks: self.extended_interval_ticks,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Send this session's broadcasts to `connection_id` for `player_id`
+    pub fn bind(&mut self, player_id: PlayerId, connection_id: ConnectionId) {
+        self.connections.insert(player_id, connection_id);
+    }
+
+    /// Send this session's broadcasts to a public spectator, anonymized if enabled
+    pub fn bind_public(&mut self, player_id: PlayerId, connection_id: ConnectionId) {
+        self.connections.insert(player_id, connection_id);
+        self.public.insert(player_id);
+    }
+
+    /// Stop sending to a player, returning the connection they used
+    pub fn unbind(&mut self, player_id: &PlayerId) -> Option<ConnectionId> {
+        self.public.remove(player_id);
+        self.extended.remove(player_id);
+        self.connections.remove(player_id)
+    }
+
+    /// Send a spectator extended telemetry of every car, or stop
+    pub fn set_extended_telemetry(&mut self, player_id: PlayerId, enabled: bool) {
+        if enabled {
+            self.extended.insert(player_id);
+        } else {
+            self.extended.remove(&player_id);
+        }
+    }
+
+    /// Replace player identities with pseudonyms in frames for public spectators
+    pub fn set_pseudonyms(&mut self, pseudonyms: Option<Pseudonymizer>) {
+        self.pseudonyms = pseudonyms;
+    }
+
+    pub fn connection(&self, player_id: &PlayerId) -> Option<ConnectionId> {
+        self.connections.get(player_id).copied()
+    }
+
+    pub fn recipient_count(&self) -> usize {
+        self.connections.len()
+    }
+
+    /// Telemetry frames per second, capped at the tick rate
+    pub fn telemetry_rate_hz(&self) -> u32 {
+        TICK_RATE_HZ / self.telemetry_interval_ticks
+    }
+
+    /// Change how often telemetry goes out; 0 is treated as 1 Hz
+    pub fn set_telemetry_rate_hz(&mut self, hz: u32) {
+        self.telemetry_interval_ticks = (TICK_RATE_HZ / hz.clamp(1, TICK_RATE_HZ)).max(1);
+    }
+
+    /// Extended telemetry frames per second, capped at the tick rate
+    pub fn extended_rate_hz(&self) -> u32 {
+        TICK_RATE_HZ / self.extended_interval_ticks
+    }
+
+    /// Change how often extended telemetry goes out; 0 is treated as 1 Hz
+    pub fn set_extended_rate_hz(&mut self, hz: u32) {
+        self.extended_interval_ticks = (TICK_RATE_HZ / hz.clamp(1, TICK_RATE_HZ)).max(1);
+    }
+
+    /// Send a session-scoped message (race event, reset, weather) with the next frame
+    pub fn queue(&mut self, message: ServerMessage) {
+        self.pending.push(message);
+    }
+
+    pub(crate) fn telemetry_due(&self, tick: u32) -> bool {
+        tick.is_multiple_of(self.telemetry_interval_ticks)
+    }
+
+    pub(crate) fn standings_due(&self, tick: u32) -> bool {
+        tick.is_multiple_of(self.standings_interval_ticks)
+    }
+
+    pub(crate) fn extended_due(&self, tick: u32) -> bool {
+        tick.is_multiple_of(self.extended_interval_ticks)
+    }
+
+    /// Queued messages, emptying the queue
+    pub(crate) fn take_pending(&mut self) -> Vec<ServerMessage> {
+        std::mem::take(&mut self.pending)
+    }
+
+    /// Addressed frames for `messages`: one with real identities and, when public
+    /// feeds are anonymized, a pseudonymized one for public spectators
+    pub(crate) fn frames(&self, messages: Vec<ServerMessage>) -> Vec<BroadcastFrame> {
+        self.frames_to(self.connections.iter().map(|(player_id, conn)| (*player_id, *conn)).collect(), messages)
+    }
+
+    fn frames_to(&self, connections: Vec<(PlayerId, ConnectionId)>, messages: Vec<ServerMessage>) -> Vec<BroadcastFrame> {
+        if messages.is_empty() || connections.is_empty() {
+            return Vec::new();
+        }
+
+        let Some(pseudonyms) = &self.pseudonyms else {
+            return vec![BroadcastFrame {
+                recipients: connections.into_iter().map(|(_, conn)| conn).collect(),
+                messages,
+            }];
+        };
+
+        let (public, private): (Vec<_>, Vec<_>) = connections
+            .into_iter()
+            .partition(|(player_id, _)| self.public.contains(player_id));
+        let mut frames = Vec::with_capacity(2);
+        if !public.is_empty() {
+            frames.push(BroadcastFrame {
+                recipients: public.into_iter().map(|(_, conn)| conn).collect(),
+                messages: messages.iter().map(|message| pseudonyms.message(message)).collect(),
+            });
+        }
+        if !private.is_empty() {
+            frames.push(BroadcastFrame {
+                recipients: private.into_iter().map(|(_, conn)| conn).collect(),
+                messages,
+            });
+        }
+        frames
+    }
+
+    /// Extended telemetry frames: each driver gets their own car, and spectators
+    /// who opted in get every car, pseudonymized on anonymized public feeds
+    pub(crate) fn extended_frames(&self, server_tick: u32, cars: Vec<ExtendedCarTelemetry>) -> Vec<BroadcastFrame> {
+        let mut frames = Vec::new();
+        for car in &cars {
+            if let Some(&connection) = self.connections.get(&car.player_id) {
+                frames.push(BroadcastFrame {
+                    recipients: vec![connection],
+                    messages: vec![ServerMessage::ExtendedTelemetry(ExtendedTelemetry {
+                        server_tick,
+                        cars: vec![car.clone()],
+                    })],
+                });
+            }
+        }
+
+        let viewers: Vec<_> = self
+            .connections
+            .iter()
+            .filter(|(player_id, _)| self.extended.contains(player_id))
+            .filter(|(player_id, _)| !cars.iter().any(|car| car.player_id == **player_id))
+            .map(|(player_id, conn)| (*player_id, *conn))
+            .collect();
+        if !cars.is_empty() {
+            let message = ServerMessage::ExtendedTelemetry(ExtendedTelemetry { server_tick, cars });
+            frames.extend(self.frames_to(viewers, vec![message]));
+        }
+        frames
+    }
+}
+
+impl Default for SessionBroadcaster {
+    fn default() -> Self {
+        Self::new()
+    }
+}
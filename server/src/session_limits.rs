@@ -0,0 +1,145 @@
+//! Quotas on session creation, so one client cannot take every session slot.
+//!
+//! The server remembers who created each session and from which address, and
+//! when each player and address last created one. A `CreateSession` is refused
+//! while the player or the address already hosts as many open sessions as
+//! allowed, or within the cooldown after their last one. Sessions count until
+//! they close, whether or not their host is still in them.
+
+use crate::config::SessionLimitSettings;
+use crate::data::{PlayerId, SessionId};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+/// Why a session may not be created now
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionLimit {
+    PerPlayer(u8),
+    PerAddress(u8),
+    /// Time left until the next session may be created
+    Cooldown(Duration),
+}
+
+impl std::fmt::Display for SessionLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionLimit::PerPlayer(max) => write!(f, "You already host {} open session(s), the most allowed", max),
+            SessionLimit::PerAddress(max) => {
+                write!(f, "Your address already hosts {} open session(s), the most allowed", max)
+            }
+            SessionLimit::Cooldown(left) => {
+                write!(f, "Wait {} more second(s) before creating another session", left.as_secs().max(1))
+            }
+        }
+    }
+}
+
+/// Who created the open sessions, and when each player and address last did
+#[derive(Debug, Default)]
+pub struct SessionCreators {
+    hosts: HashMap<SessionId, (PlayerId, IpAddr)>,
+    last_by_player: HashMap<PlayerId, Instant>,
+    last_by_address: HashMap<IpAddr, Instant>,
+}
+
+impl SessionCreators {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether `player_id` at `ip` may create a session at `now`; `is_open`
+    /// tells which sessions still exist
+    pub fn check(
+        &mut self,
+        limits: &SessionLimitSettings,
+        player_id: PlayerId,
+        ip: IpAddr,
+        now: Instant,
+        is_open: impl Fn(SessionId) -> bool,
+    ) -> Result<(), SessionLimit> {
+        self.hosts.retain(|session_id, _| is_open(*session_id));
+
+        if limits.max_per_player > 0 {
+            let hosted = self.hosts.values().filter(|(host, _)| *host == player_id).count();
+            if hosted >= limits.max_per_player as usize {
+                return Err(SessionLimit::PerPlayer(limits.max_per_player));
+            }
+        }
+        if limits.max_per_address > 0 {
+            let hosted = self.hosts.values().filter(|(_, address)| *address == ip).count();
+            if hosted >= limits.max_per_address as usize {
+                return Err(SessionLimit::PerAddress(limits.max_per_address));
+            }
+        }
+
+        let cooldown = Duration::from_secs(limits.creation_cooldown_seconds);
+        let last = [self.last_by_player.get(&player_id), self.last_by_address.get(&ip)]
+            .into_iter()
+            .flatten()
+            .max();
+        if let Some(left) = last.and_then(|last| cooldown.checked_sub(now.saturating_duration_since(*last))) {
+            if !left.is_zero() {
+                return Err(SessionLimit::Cooldown(left));
+            }
+        }
+        Ok(())
+    }
+
+    /// Count `session_id` against its creator
+    pub fn record(&mut self, session_id: SessionId, player_id: PlayerId, ip: IpAddr, now: Instant) {
+        self.hosts.insert(session_id, (player_id, ip));
+        self.last_by_player.insert(player_id, now);
+        self.last_by_address.insert(ip, now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(max_per_player: u8, max_per_address: u8, creation_cooldown_seconds: u64) -> SessionLimitSettings {
+        SessionLimitSettings { max_per_player, max_per_address, creation_cooldown_seconds }
+    }
+
+    #[test]
+    fn test_hosted_sessions_count_until_they_close() {
+        let mut creators = SessionCreators::new();
+        let limits = limits(1, 2, 0);
+        let (alice, bob, carol) = (PlayerId::new_v4(), PlayerId::new_v4(), PlayerId::new_v4());
+        let home: IpAddr = "10.0.0.1".parse().unwrap();
+        let elsewhere: IpAddr = "10.0.0.2".parse().unwrap();
+        let now = Instant::now();
+        let first = SessionId::new_v4();
+
+        assert_eq!(creators.check(&limits, alice, home, now, |_| true), Ok(()));
+        creators.record(first, alice, home, now);
+        assert_eq!(creators.check(&limits, alice, elsewhere, now, |_| true), Err(SessionLimit::PerPlayer(1)));
+
+        creators.record(SessionId::new_v4(), bob, home, now);
+        assert_eq!(creators.check(&limits, carol, home, now, |_| true), Err(SessionLimit::PerAddress(2)));
+        assert_eq!(creators.check(&limits, carol, elsewhere, now, |_| true), Ok(()));
+
+        // Once Alice's session closes she and her address have room again
+        assert_eq!(creators.check(&limits, alice, home, now, |id| id != first), Ok(()));
+    }
+
+    #[test]
+    fn test_cooldown_applies_to_player_and_address() {
+        let mut creators = SessionCreators::new();
+        let limits = limits(0, 0, 30);
+        let (alice, bob) = (PlayerId::new_v4(), PlayerId::new_v4());
+        let home: IpAddr = "10.0.0.1".parse().unwrap();
+        let now = Instant::now();
+
+        creators.record(SessionId::new_v4(), alice, home, now);
+        let later = now + Duration::from_secs(10);
+        assert_eq!(
+            creators.check(&limits, bob, home, later, |_| true),
+            Err(SessionLimit::Cooldown(Duration::from_secs(20))),
+            "A new player from the same address waits too"
+        );
+        assert!(creators.check(&limits, alice, "10.0.0.2".parse().unwrap(), later, |_| true).is_err());
+        assert_eq!(creators.check(&limits, alice, home, now + Duration::from_secs(30), |_| true), Ok(()));
+    }
+}
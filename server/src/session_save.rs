@@ -0,0 +1,236 @@
+//! Mid-race session saves.
+//!
+//! A save holds everything needed to rebuild a `GameSession` after a server
+//! restart: the simulation state, the race events so far (penalties included)
+//! and a resume token for each human driver. Drivers get their token when they
+//! first appear in a save and send it with `ResumeSession` once the session has
+//! been resumed. Standings timing and telemetry history start empty again.
+
+use crate::data::*;
+use crate::game_session::{GameSession, SimulationState};
+use crate::network::RaceEvent;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tokio::fs;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Save files written by this build
+pub const SESSION_SAVE_VERSION: u32 = 1;
+
+/// A session written to disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSession {
+    pub version: u32,
+    /// Unix time (s)
+    pub saved_at: u64,
+    pub host_name: String,
+    pub track_name: String,
+    pub state: SimulationState,
+    pub events: Vec<RaceEvent>,
+    /// Resume token -> human driver it reattaches to
+    pub resume_tokens: HashMap<Uuid, PlayerId>,
+}
+
+/// Writes, lists and loads session saves in one directory
+pub struct SessionSaves {
+    dir: PathBuf,
+    /// Token issued to each human driver, per session
+    tokens: HashMap<SessionId, HashMap<PlayerId, Uuid>>,
+}
+
+impl SessionSaves {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            tokens: HashMap::new(),
+        }
+    }
+
+    fn path(&self, session_id: SessionId) -> PathBuf {
+        self.dir.join(format!("session_{}.bin", session_id))
+    }
+
+    /// Resume tokens of a session's human drivers, by token, and the drivers
+    /// given one just now. The crash journal shares them, so a driver has one
+    /// token per session whichever way it comes back.
+    pub fn resume_tokens(&mut self, session: &RaceSession) -> (HashMap<Uuid, PlayerId>, Vec<(PlayerId, Uuid)>) {
+        let tokens = self.tokens.entry(session.id).or_default();
+        let mut issued = Vec::new();
+        let drivers = session.participants.keys().filter(|id| !session.ai_player_ids.contains(id));
+        for player_id in drivers {
+            tokens.entry(*player_id).or_insert_with(|| {
+                let token = Uuid::new_v4();
+                issued.push((*player_id, token));
+                token
+            });
+        }
+        // Tokens of drivers who have left stay issued but are not saved
+        let resume_tokens = tokens
+            .iter()
+            .filter(|(player_id, _)| session.participants.contains_key(player_id))
+            .map(|(player_id, token)| (*token, *player_id))
+            .collect();
+        (resume_tokens, issued)
+    }
+
+    /// Save a session, replacing its previous save.
+    ///
+    /// Returns the drivers given a resume token by this save, with their token.
+    pub async fn save(
+        &mut self,
+        game_session: &GameSession,
+        host_name: String,
+    ) -> Result<Vec<(PlayerId, Uuid)>, std::io::Error> {
+        let session = &game_session.session;
+        let (resume_tokens, issued) = self.resume_tokens(session);
+
+        let saved = SavedSession {
+            version: SESSION_SAVE_VERSION,
+            saved_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            host_name,
+            track_name: game_session.track_config.name.clone(),
+            state: game_session.simulation_state(),
+            events: game_session.race_events.events().to_vec(),
+            resume_tokens,
+        };
+        let bytes = rmp_serde::to_vec_named(&saved)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+        // Write beside the old save and swap, so a crash mid-write keeps the old one
+        fs::create_dir_all(&self.dir).await?;
+        let path = self.path(session.id);
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, bytes).await?;
+        fs::rename(&tmp_path, &path).await?;
+        info!("Saved session {} at tick {} to {:?}", session.id, session.current_tick, path);
+
+        Ok(issued)
+    }
+
+    /// Load a session's save; its resume tokens stay valid for later saves
+    pub async fn load(&mut self, session_id: SessionId) -> Result<SavedSession, std::io::Error> {
+        let bytes = fs::read(self.path(session_id)).await?;
+        let saved = decode(&bytes)?;
+        self.keep_tokens(session_id, &saved.resume_tokens);
+        Ok(saved)
+    }
+
+    /// Go on issuing the tokens a session came back with
+    pub fn keep_tokens(&mut self, session_id: SessionId, resume_tokens: &HashMap<Uuid, PlayerId>) {
+        self.tokens.insert(
+            session_id,
+            resume_tokens.iter().map(|(token, player_id)| (*player_id, *token)).collect(),
+        );
+    }
+
+    /// Every save in the directory, skipping files that cannot be read
+    pub async fn list(&self) -> Result<Vec<SavedSession>, std::io::Error> {
+        let mut saves = Vec::new();
+        let mut entries = match fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(saves),
+            Err(e) => return Err(e),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "bin") {
+                continue;
+            }
+            match fs::read(&path).await.and_then(|bytes| decode(&bytes)) {
+                Ok(saved) => saves.push(saved),
+                Err(e) => warn!("Skipping session save {:?}: {}", path, e),
+            }
+        }
+        Ok(saves)
+    }
+
+    /// Delete a session's save, e.g. once its race has finished
+    pub async fn remove(&mut self, session_id: SessionId) -> Result<(), std::io::Error> {
+        self.tokens.remove(&session_id);
+        match fs::remove_file(self.path(session_id)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn decode(bytes: &[u8]) -> Result<SavedSession, std::io::Error> {
+    let saved: SavedSession =
+        rmp_serde::from_slice(bytes).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    if saved.version != SESSION_SAVE_VERSION {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("unsupported session save version {}", saved.version),
+        ));
+    }
+    Ok(saved)
+}
+
+impl SavedSession {
+    /// Rebuild the session on the given track and cars
+    pub fn resume(self, track_config: TrackConfig, car_configs: HashMap<CarConfigId, CarConfig>) -> GameSession {
+        let mut game_session = GameSession::from_simulation_state(self.state, track_config, car_configs);
+        game_session.race_events.restore(self.events);
+        game_session
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai_driver::generate_default_ai_profiles;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_save_and_resume_mid_race() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut saves = SessionSaves::new(temp_dir.path().to_path_buf());
+
+        let track = TrackConfig::default();
+        let car = CarConfig::default();
+        let car_configs = HashMap::from([(car.id, car.clone())]);
+        let session = RaceSession::new(PlayerId::new_v4(), track.id, SessionKind::Multiplayer, 8, 3, 5);
+        let mut game_session =
+            GameSession::with_ai_profiles(session, track.clone(), car_configs.clone(), generate_default_ai_profiles(3));
+        game_session.spawn_ai_drivers();
+        let driver = PlayerId::new_v4();
+        game_session.add_player(driver, car.id);
+        game_session.set_game_mode(GameMode::FreePractice);
+        for _ in 0..120 {
+            game_session.tick(&HashMap::new());
+        }
+        let penalized = *game_session.session.participants.keys().next().unwrap();
+        let state = &game_session.session.participants[&penalized];
+        game_session.race_events.record_penalty(100, state, 5_000, "Track limits");
+
+        // Only the human driver gets a token, and only on the first save
+        let issued = saves.save(&game_session, "Host".to_string()).await.unwrap();
+        assert_eq!(issued.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![driver]);
+        assert!(saves.save(&game_session, "Host".to_string()).await.unwrap().is_empty());
+
+        // A restarted server only has the file
+        let mut restarted = SessionSaves::new(temp_dir.path().to_path_buf());
+        let listed = restarted.list().await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].host_name, "Host");
+        let saved = restarted.load(game_session.session.id).await.unwrap();
+        assert_eq!(saved.resume_tokens, HashMap::from([(issued[0].1, driver)]));
+
+        let mut resumed = saved.resume(track, car_configs);
+        assert_eq!(resumed.session.current_tick, game_session.session.current_tick);
+        assert_eq!(resumed.state_hash(), game_session.state_hash());
+        let events = resumed.race_events.events();
+        assert_eq!(events.len(), 1);
+        assert_eq!((events[0].kind, events[0].penalty_ms), (RaceEventKind::Penalty, Some(5_000)));
+        // Events from before the restart are not broadcast again
+        assert!(resumed.take_race_events().is_empty());
+
+        restarted.remove(resumed.session.id).await.unwrap();
+        assert!(restarted.list().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,358 @@
+//! Live standings for racing sessions.
+//!
+//! Gaps are measured at fixed timing points spread evenly around the lap:
+//! every time a car passes one, the tick is recorded. The gap between two cars
+//! is the difference between the ticks at which they passed the same timing
+//! point on the same lap, which stays stable between updates instead of
+//! jittering with speed like a distance-based estimate would.
+
+use crate::data::*;
+use crate::network::StandingsEntry;
+use crate::physics::TickTiming;
+use std::collections::HashMap;
+
+/// Number of timing points per lap
+pub const TIMING_POINTS_PER_LAP: usize = 100;
+
+/// Speed below which a car inside the pit lane counts as stopped in its box
+const PIT_BOX_SPEED_MPS: f32 = 0.5;
+
+/// Distance from a pit stall within which a stopped car is considered in its box
+const PIT_BOX_RADIUS_M: f32 = 6.0;
+
+#[derive(Debug, Clone, Copy)]
+struct Crossing {
+    lap: u16,
+    tick: u32,
+}
+
+#[derive(Debug, Default)]
+struct CarTiming {
+    /// Last crossing of each timing point, indexed by timing point
+    crossings: Vec<Option<Crossing>>,
+    /// Most recently crossed timing point
+    last_point: Option<usize>,
+}
+
+/// Tracks timing point crossings and turns them into standings
+#[derive(Debug, Default)]
+pub struct StandingsTracker {
+    timing: HashMap<PlayerId, CarTiming>,
+}
+
+impl StandingsTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record timing point crossings for every car that has started a lap.
+    ///
+    /// Must be called once per simulated tick while the session is racing.
+    pub fn update(&mut self, session: &RaceSession, track_length_m: f32) {
+        if track_length_m <= 0.0 {
+            return;
+        }
+
+        self.timing.retain(|id, _| session.participants.contains_key(id));
+
+        for state in session.participants.values() {
+            if state.current_lap == 0 {
+                continue;
+            }
+
+            let point = timing_point(state.track_progress, track_length_m);
+            let timing = self.timing.entry(state.player_id).or_insert_with(|| CarTiming {
+                crossings: vec![None; TIMING_POINTS_PER_LAP],
+                last_point: None,
+            });
+
+            if timing.last_point == Some(point) {
+                continue;
+            }
+
+            let crossing = Crossing {
+                lap: state.current_lap,
+                tick: session.current_tick,
+            };
+
+            // Sparse centerlines can move a car across several timing points in
+            // one tick; stamp the skipped ones too so that gap lookups never miss.
+            match timing.last_point {
+                Some(last) => {
+                    let skipped = (point + TIMING_POINTS_PER_LAP - last) % TIMING_POINTS_PER_LAP;
+                    if skipped < TIMING_POINTS_PER_LAP / 2 {
+                        for step in 1..=skipped {
+                            let idx = (last + step) % TIMING_POINTS_PER_LAP;
+                            timing.crossings[idx] = Some(crossing);
+                        }
+                    } else {
+                        // Moving backwards (spin, reversing): only the current point is reliable
+                        timing.crossings[point] = Some(crossing);
+                    }
+                }
+                None => timing.crossings[point] = Some(crossing),
+            }
+
+            timing.last_point = Some(point);
+        }
+    }
+
+    /// Build the ordered standings for the session; `timing` turns tick
+    /// differences into gap times.
+    pub fn standings(&self, session: &RaceSession, track: &TrackConfig, timing: TickTiming) -> Vec<StandingsEntry> {
+        let track_length_m = track
+            .centerline
+            .last()
+            .map(|p| p.distance_from_start_m)
+            .unwrap_or(0.0);
+
+        let mut order: Vec<&CarState> = session.participants.values().collect();
+        order.sort_by(|a, b| compare_race_order(a, b));
+
+        let leader = order.first().copied();
+
+        order
+            .iter()
+            .enumerate()
+            .map(|(idx, state)| {
+                let ahead = if idx > 0 { Some(order[idx - 1]) } else { None };
+
+                let laps_behind_leader = leader
+                    .map(|l| laps_between(l, state, track_length_m))
+                    .unwrap_or(0);
+
+                let gap_to_leader_ms = match leader {
+                    Some(l) if idx > 0 => self.gap_ms(l.player_id, state.player_id, timing),
+                    _ => None,
+                };
+                let interval_ms = ahead.and_then(|a| self.gap_ms(a.player_id, state.player_id, timing));
+
+                StandingsEntry {
+                    player_id: state.player_id,
+                    position: (idx + 1) as u8,
+                    current_lap: state.current_lap,
+                    laps_behind_leader,
+                    gap_to_leader_ms,
+                    interval_ms,
+                    last_lap_time_ms: state.last_lap_time_ms,
+                    best_lap_time_ms: state.best_lap_time_ms,
+                    pit_status: pit_status(state, track, track_length_m),
+                    finish_position: state.finish_position,
+                }
+            })
+            .collect()
+    }
+
+    /// Time between `ahead` and `behind` passing the timing point `behind` crossed last.
+    ///
+    /// Returns None when the cars are not on the same lap at that point.
+    fn gap_ms(&self, ahead: PlayerId, behind: PlayerId, timing: TickTiming) -> Option<u32> {
+        let behind_timing = self.timing.get(&behind)?;
+        let point = behind_timing.last_point?;
+        let behind_crossing = behind_timing.crossings[point]?;
+        let ahead_crossing = self.timing.get(&ahead)?.crossings[point]?;
+
+        if ahead_crossing.lap != behind_crossing.lap {
+            return None;
+        }
+
+        Some(timing.ticks_to_ms(behind_crossing.tick.saturating_sub(ahead_crossing.tick)))
+    }
+}
+
+/// Ordering used for the leaderboard: finishers first by finish position,
+/// then by laps completed and distance into the current lap.
+pub fn compare_race_order(a: &CarState, b: &CarState) -> std::cmp::Ordering {
+    match (a.finish_position, b.finish_position) {
+        (Some(pa), Some(pb)) => pa.cmp(&pb),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => b.current_lap.cmp(&a.current_lap).then_with(|| {
+            b.track_progress
+                .partial_cmp(&a.track_progress)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        }),
+    }
+}
+
+fn timing_point(track_progress: f32, track_length_m: f32) -> usize {
+    let fraction = (track_progress / track_length_m).clamp(0.0, 1.0);
+    ((fraction * TIMING_POINTS_PER_LAP as f32) as usize).min(TIMING_POINTS_PER_LAP - 1)
+}
+
+fn laps_between(leader: &CarState, state: &CarState, track_length_m: f32) -> u16 {
+    if track_length_m <= 0.0 {
+        return leader.current_lap.saturating_sub(state.current_lap);
+    }
+
+    let leader_distance = leader.current_lap as f32 * track_length_m + leader.track_progress;
+    let distance = state.current_lap as f32 * track_length_m + state.track_progress;
+    ((leader_distance - distance).max(0.0) / track_length_m).floor() as u16
+}
+
+pub(crate) fn pit_status(state: &CarState, track: &TrackConfig, track_length_m: f32) -> PitStatus {
+    let Some(pit_lane) = track.pit_lane.as_ref() else {
+        return PitStatus::OnTrack;
+    };
+
+    // With a pit lane centerline the car's position says whether it is in the lane;
+    // otherwise a car off the track between pit entry and exit is taken to be
+    let in_pit_lane = if pit_lane.centerline.is_empty() {
+        let entry = pit_lane.entry_point.distance_from_start_m;
+        let exit = pit_lane.exit_point.distance_from_start_m;
+        let progress = state.track_progress;
+
+        // The pit lane usually straddles the start/finish line, so the window can wrap
+        let in_window = if entry <= exit {
+            progress >= entry && progress <= exit
+        } else {
+            progress >= entry || progress <= exit || track_length_m <= 0.0
+        };
+        in_window && !state.is_on_track
+    } else {
+        pit_lane.locate(state.pos_x, state.pos_y).is_some()
+    };
+
+    if !in_pit_lane {
+        return PitStatus::OnTrack;
+    }
+
+    let near_stall = pit_lane.pit_stalls.iter().any(|stall| {
+        let dx = state.pos_x - stall.x;
+        let dy = state.pos_y - stall.y;
+        (dx * dx + dy * dy).sqrt() <= PIT_BOX_RADIUS_M
+    });
+
+    if near_stall && state.speed_mps < PIT_BOX_SPEED_MPS {
+        PitStatus::InPitBox
+    } else {
+        PitStatus::InPitLane
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_session_with_cars(count: usize) -> (RaceSession, TrackConfig, Vec<PlayerId>) {
+        let track = TrackConfig::default();
+        let mut session = RaceSession::new(PlayerId::new_v4(), track.id, SessionKind::Multiplayer, 8, 0, 3);
+        let mut ids = Vec::new();
+
+        for slot in track.start_positions.iter().take(count) {
+            let id = PlayerId::new_v4();
+            session.participants.insert(id, CarState::new(id, CarConfigId::new_v4(), slot));
+            ids.push(id);
+        }
+
+        (session, track, ids)
+    }
+
+    fn track_length(track: &TrackConfig) -> f32 {
+        track.centerline.last().unwrap().distance_from_start_m
+    }
+
+    #[test]
+    fn test_standings_order_by_lap_and_progress() {
+        let (mut session, track, ids) = create_session_with_cars(3);
+
+        session.participants.get_mut(&ids[0]).unwrap().current_lap = 1;
+        session.participants.get_mut(&ids[0]).unwrap().track_progress = 300.0;
+        session.participants.get_mut(&ids[1]).unwrap().current_lap = 2;
+        session.participants.get_mut(&ids[1]).unwrap().track_progress = 10.0;
+        session.participants.get_mut(&ids[2]).unwrap().current_lap = 1;
+        session.participants.get_mut(&ids[2]).unwrap().track_progress = 450.0;
+
+        let tracker = StandingsTracker::new();
+        let standings = tracker.standings(&session, &track, TickTiming::default());
+
+        let order: Vec<PlayerId> = standings.iter().map(|e| e.player_id).collect();
+        assert_eq!(order, vec![ids[1], ids[2], ids[0]]);
+        assert_eq!(standings[0].position, 1);
+        assert_eq!(standings[2].position, 3);
+        assert!(standings[0].gap_to_leader_ms.is_none());
+    }
+
+    #[test]
+    fn test_gap_and_interval_from_timing_points() {
+        let (mut session, track, ids) = create_session_with_cars(3);
+        let length = track_length(&track);
+        let mut tracker = StandingsTracker::new();
+
+        for id in &ids {
+            session.participants.get_mut(id).unwrap().current_lap = 1;
+        }
+
+        // Each car passes the same point: leader at tick 100, second at 160, third at 400.
+        // Small offsets inside the timing point keep the running order unambiguous.
+        let point_distance = length * 0.5;
+        for (tick, id, offset) in [(100u32, ids[0], 2.0), (160, ids[1], 1.0), (400, ids[2], 0.0)] {
+            session.current_tick = tick;
+            session.participants.get_mut(&id).unwrap().track_progress = point_distance + offset;
+            tracker.update(&session, length);
+        }
+
+        let standings = tracker.standings(&session, &track, TickTiming::default());
+        let entry = |id: PlayerId| standings.iter().find(|e| e.player_id == id).unwrap().clone();
+
+        assert_eq!(entry(ids[1]).gap_to_leader_ms, Some(250));
+        assert_eq!(entry(ids[1]).interval_ms, Some(250));
+        assert_eq!(entry(ids[2]).gap_to_leader_ms, Some(1250));
+        assert_eq!(entry(ids[2]).interval_ms, Some(1000));
+
+        // The same ticks are twice as long at 120 Hz
+        let standings = tracker.standings(&session, &track, TickTiming::new(120, 0));
+        let gap = standings.iter().find(|e| e.player_id == ids[2]).unwrap().gap_to_leader_ms;
+        assert_eq!(gap, Some(2500));
+    }
+
+    #[test]
+    fn test_lapped_car_has_no_time_gap() {
+        let (mut session, track, ids) = create_session_with_cars(2);
+        let length = track_length(&track);
+        let mut tracker = StandingsTracker::new();
+
+        session.participants.get_mut(&ids[0]).unwrap().current_lap = 3;
+        session.participants.get_mut(&ids[0]).unwrap().track_progress = length * 0.5;
+        session.participants.get_mut(&ids[1]).unwrap().current_lap = 1;
+        session.participants.get_mut(&ids[1]).unwrap().track_progress = length * 0.5;
+        session.current_tick = 10;
+        tracker.update(&session, length);
+
+        let standings = tracker.standings(&session, &track, TickTiming::default());
+        let lapped = standings.iter().find(|e| e.player_id == ids[1]).unwrap();
+
+        assert_eq!(lapped.laps_behind_leader, 2);
+        assert!(lapped.gap_to_leader_ms.is_none());
+    }
+
+    #[test]
+    fn test_pit_status() {
+        let (mut session, mut track, ids) = create_session_with_cars(1);
+        track.pit_lane = Some(PitLaneConfig {
+            entry_point: TrackPoint { distance_from_start_m: 550.0, ..Default::default() },
+            exit_point: TrackPoint { distance_from_start_m: 50.0, ..Default::default() },
+            speed_limit_mps: 22.0,
+            pit_stalls: vec![PitStall { position: 1, x: 5.0, y: 5.0, z: 0.0, yaw_rad: 0.0 }],
+            centerline: Vec::new(),
+            speed_limit_start_m: 0.0,
+            speed_limit_end_m: 0.0,
+        });
+
+        let state = session.participants.get_mut(&ids[0]).unwrap();
+        state.track_progress = 20.0;
+        state.is_on_track = false;
+        state.pos_x = 5.0;
+        state.pos_y = 5.0;
+        state.speed_mps = 15.0;
+
+        let tracker = StandingsTracker::new();
+        assert_eq!(tracker.standings(&session, &track, TickTiming::default())[0].pit_status, PitStatus::InPitLane);
+
+        session.participants.get_mut(&ids[0]).unwrap().speed_mps = 0.0;
+        assert_eq!(tracker.standings(&session, &track, TickTiming::default())[0].pit_status, PitStatus::InPitBox);
+
+        session.participants.get_mut(&ids[0]).unwrap().track_progress = 300.0;
+        assert_eq!(tracker.standings(&session, &track, TickTiming::default())[0].pit_status, PitStatus::OnTrack);
+    }
+}
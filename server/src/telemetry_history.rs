@@ -0,0 +1,151 @@
+//! Short per-car telemetry history.
+//!
+//! A bounded ring buffer of recent telemetry for every car in a session, so
+//! clients can fetch the last few seconds of their own car (e.g. to redraw
+//! graphs after losing packets) without the server keeping whole-session logs.
+
+use crate::data::*;
+use crate::network::{CarStateTelemetry, TelemetrySample};
+use crate::physics::TickTiming;
+use std::collections::{HashMap, VecDeque};
+
+/// Rate samples are stored at, or every tick below it
+const SAMPLE_RATE_HZ: u16 = 60;
+
+/// Longest history a client can request
+pub const MAX_HISTORY_SECONDS: u16 = 30;
+
+/// Ticks between stored samples at `timing`'s tick rate
+pub fn sample_interval_ticks(timing: TickTiming) -> u32 {
+    (timing.tick_rate_hz / SAMPLE_RATE_HZ).max(1) as u32
+}
+
+fn samples_per_second(timing: TickTiming) -> usize {
+    (timing.tick_rate_hz as u32).div_ceil(sample_interval_ticks(timing)) as usize
+}
+
+/// Recent telemetry samples for every car in a session
+#[derive(Debug, Default)]
+pub struct TelemetryHistory {
+    samples: HashMap<PlayerId, VecDeque<TelemetrySample>>,
+}
+
+impl TelemetryHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store a sample for every car if this tick falls on the sample interval.
+    pub fn record(&mut self, session: &RaceSession, timing: TickTiming) {
+        if !session.current_tick.is_multiple_of(sample_interval_ticks(timing)) {
+            return;
+        }
+
+        self.samples.retain(|id, _| session.participants.contains_key(id));
+
+        let capacity = MAX_HISTORY_SECONDS as usize * samples_per_second(timing);
+        for state in session.participants.values() {
+            let buffer = self
+                .samples
+                .entry(state.player_id)
+                .or_insert_with(|| VecDeque::with_capacity(capacity));
+
+            while buffer.len() >= capacity {
+                buffer.pop_front();
+            }
+            buffer.push_back(TelemetrySample {
+                server_tick: session.current_tick,
+                car_state: CarStateTelemetry::from(state),
+            });
+        }
+    }
+
+    /// Samples from the last `seconds` (capped at `MAX_HISTORY_SECONDS`), oldest first
+    pub fn recent(&self, player_id: PlayerId, seconds: u16, timing: TickTiming) -> Vec<TelemetrySample> {
+        let Some(buffer) = self.samples.get(&player_id) else {
+            return Vec::new();
+        };
+
+        let count = seconds.min(MAX_HISTORY_SECONDS) as usize * samples_per_second(timing);
+        buffer.iter().skip(buffer.len().saturating_sub(count)).cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_session() -> (RaceSession, PlayerId) {
+        let track = TrackConfig::default();
+        let mut session = RaceSession::new(PlayerId::new_v4(), track.id, SessionKind::Practice, 8, 0, 3);
+        let id = PlayerId::new_v4();
+        session.participants.insert(id, CarState::new(id, CarConfigId::new_v4(), &track.start_positions[0]));
+        (session, id)
+    }
+
+    #[test]
+    fn test_recent_returns_requested_window() {
+        let (mut session, id) = create_session();
+        let mut history = TelemetryHistory::new();
+        let timing = TickTiming::default();
+
+        // 10 seconds of ticks
+        for tick in 1..=2400 {
+            session.current_tick = tick;
+            history.record(&session, timing);
+        }
+
+        let samples = history.recent(id, 2, timing);
+        assert_eq!(samples.len(), 2 * 60);
+        assert_eq!(samples.last().unwrap().server_tick, 2400);
+        assert!(samples.windows(2).all(|w| w[0].server_tick < w[1].server_tick));
+        assert!(history.recent(PlayerId::new_v4(), 2, timing).is_empty());
+    }
+
+    #[test]
+    fn test_window_follows_the_tick_rate() {
+        let (mut session, id) = create_session();
+        let mut history = TelemetryHistory::new();
+        let timing = TickTiming::new(120, 0);
+
+        // 10 seconds at 120 Hz, sampled every other tick
+        for tick in 1..=1200 {
+            session.current_tick = tick;
+            history.record(&session, timing);
+        }
+
+        assert_eq!(sample_interval_ticks(timing), 2);
+        assert_eq!(history.recent(id, 2, timing).len(), 2 * 60);
+        assert_eq!(sample_interval_ticks(TickTiming::new(30, 0)), 1);
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let (mut session, id) = create_session();
+        let mut history = TelemetryHistory::new();
+        let timing = TickTiming::default();
+
+        for tick in 1..=(MAX_HISTORY_SECONDS as u32 + 10) * 240 {
+            session.current_tick = tick;
+            history.record(&session, timing);
+        }
+
+        let samples = history.recent(id, u16::MAX, timing);
+        assert_eq!(samples.len(), MAX_HISTORY_SECONDS as usize * 60);
+    }
+
+    #[test]
+    fn test_departed_cars_are_dropped() {
+        let (mut session, id) = create_session();
+        let mut history = TelemetryHistory::new();
+        let timing = TickTiming::default();
+
+        session.current_tick = sample_interval_ticks(timing);
+        history.record(&session, timing);
+        session.participants.remove(&id);
+        session.current_tick += sample_interval_ticks(timing);
+        history.record(&session, timing);
+
+        assert!(history.recent(id, 5, timing).is_empty());
+    }
+}
@@ -0,0 +1,226 @@
+//! Tick timing accounting for the game loop.
+//!
+//! Ticks are scheduled on a fixed timeline. A tick that overruns its budget
+//! makes the ones after it late; the loop then runs them back to back to catch
+//! up, but at most `MAX_CATCH_UP_TICKS` in a row. Further behind than that,
+//! the missed ticks are skipped and the timeline restarts, so a long stall
+//! slows the simulation down instead of fast-forwarding it afterwards.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Late ticks run back to back before the rest are skipped
+pub const MAX_CATCH_UP_TICKS: u32 = 8;
+
+/// How often a server that cannot keep up says so
+const LAG_WARNING_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Tick timing counters since the server started, shared with the admin API
+#[derive(Debug, Default)]
+pub struct TickLagMetrics {
+    ticks: AtomicU64,
+    overruns: AtomicU64,
+    catch_up_ticks: AtomicU64,
+    skipped_ticks: AtomicU64,
+    max_tick_us: AtomicU64,
+    lag_us: AtomicU64,
+}
+
+/// A copy of the tick timing counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct TickLagSnapshot {
+    pub ticks: u64,
+    /// Ticks that took longer than their budget
+    pub overruns: u64,
+    /// Late ticks run straight after the one before
+    pub catch_up_ticks: u64,
+    /// Ticks given up on because the loop was too far behind
+    pub skipped_ticks: u64,
+    /// Slowest tick
+    pub max_tick_us: u64,
+    /// How late the latest tick started
+    pub lag_us: u64,
+}
+
+impl TickLagMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn snapshot(&self) -> TickLagSnapshot {
+        TickLagSnapshot {
+            ticks: self.ticks.load(Ordering::Relaxed),
+            overruns: self.overruns.load(Ordering::Relaxed),
+            catch_up_ticks: self.catch_up_ticks.load(Ordering::Relaxed),
+            skipped_ticks: self.skipped_ticks.load(Ordering::Relaxed),
+            max_tick_us: self.max_tick_us.load(Ordering::Relaxed),
+            lag_us: self.lag_us.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Overruns and skips since the last warning
+struct LagWindow {
+    since: Instant,
+    overruns: u64,
+    skipped_ticks: u64,
+    slowest: Duration,
+}
+
+impl LagWindow {
+    fn new(since: Instant) -> Self {
+        Self { since, overruns: 0, skipped_ticks: 0, slowest: Duration::ZERO }
+    }
+}
+
+/// Schedules the game loop's ticks and accounts for their timing
+pub struct TickClock {
+    period: Duration,
+    next: Instant,
+    /// Late ticks run in a row so far
+    catching_up: u32,
+    metrics: Arc<TickLagMetrics>,
+    window: LagWindow,
+}
+
+impl TickClock {
+    /// A clock whose first tick is due at `start`
+    pub fn new(period: Duration, start: Instant, metrics: Arc<TickLagMetrics>) -> Self {
+        Self { period, next: start, catching_up: 0, metrics, window: LagWindow::new(start) }
+    }
+
+    /// When the next tick is due
+    pub fn next_tick(&self) -> Instant {
+        self.next
+    }
+
+    /// Account for a tick that ran from `started` to `finished` and schedule
+    /// the next one; returns a warning when the server has not kept up lately
+    pub fn finish_tick(&mut self, started: Instant, finished: Instant) -> Option<String> {
+        let took = finished.saturating_duration_since(started);
+        let metrics = &self.metrics;
+        metrics.ticks.fetch_add(1, Ordering::Relaxed);
+        metrics.max_tick_us.fetch_max(took.as_micros() as u64, Ordering::Relaxed);
+        metrics.lag_us.store(started.saturating_duration_since(self.next).as_micros() as u64, Ordering::Relaxed);
+        if took > self.period {
+            metrics.overruns.fetch_add(1, Ordering::Relaxed);
+            self.window.overruns += 1;
+        }
+        self.window.slowest = self.window.slowest.max(took);
+
+        self.next += self.period;
+        if finished <= self.next {
+            self.catching_up = 0;
+        } else if self.catching_up < MAX_CATCH_UP_TICKS {
+            self.catching_up += 1;
+            metrics.catch_up_ticks.fetch_add(1, Ordering::Relaxed);
+        } else {
+            // Every tick due by now is given up on, and the next runs right away
+            let behind = finished - self.next;
+            let skipped = (behind.as_nanos() / self.period.as_nanos().max(1)) as u64 + 1;
+            metrics.skipped_ticks.fetch_add(skipped, Ordering::Relaxed);
+            self.window.skipped_ticks += skipped;
+            self.next = finished;
+            self.catching_up = 0;
+        }
+
+        if finished.saturating_duration_since(self.window.since) < LAG_WARNING_INTERVAL {
+            return None;
+        }
+        let window = std::mem::replace(&mut self.window, LagWindow::new(finished));
+        if window.overruns == 0 && window.skipped_ticks == 0 {
+            return None;
+        }
+        Some(format!(
+            "Server cannot sustain {:.0} Hz: {} ticks over budget and {} skipped in the last {} s, slowest {:.1} ms of {:.1} ms",
+            1.0 / self.period.as_secs_f64(),
+            window.overruns,
+            window.skipped_ticks,
+            finished.saturating_duration_since(window.since).as_secs(),
+            window.slowest.as_secs_f64() * 1000.0,
+            self.period.as_secs_f64() * 1000.0,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PERIOD: Duration = Duration::from_millis(10);
+
+    fn clock() -> (TickClock, Arc<TickLagMetrics>, Instant) {
+        let metrics = Arc::new(TickLagMetrics::new());
+        let start = Instant::now();
+        (TickClock::new(PERIOD, start, Arc::clone(&metrics)), metrics, start)
+    }
+
+    #[test]
+    fn test_ticks_within_budget_keep_the_timeline() {
+        let (mut clock, metrics, start) = clock();
+        for i in 0..10 {
+            let started = start + PERIOD * i;
+            assert_eq!(clock.finish_tick(started, started + Duration::from_millis(2)), None);
+            assert_eq!(clock.next_tick(), start + PERIOD * (i + 1));
+        }
+
+        let snapshot = metrics.snapshot();
+        assert_eq!((snapshot.ticks, snapshot.overruns, snapshot.catch_up_ticks, snapshot.skipped_ticks), (10, 0, 0, 0));
+        assert_eq!(snapshot.max_tick_us, 2000);
+    }
+
+    #[test]
+    fn test_overrun_is_caught_up_back_to_back() {
+        let (mut clock, metrics, start) = clock();
+
+        // 25 ms tick: the next two are due at 10 and 20 ms and run straight away
+        clock.finish_tick(start, start + Duration::from_millis(25));
+        assert_eq!(clock.next_tick(), start + PERIOD);
+        let finished = start + Duration::from_millis(26);
+        clock.finish_tick(start + Duration::from_millis(25), finished);
+        assert_eq!(clock.next_tick(), start + PERIOD * 2);
+        clock.finish_tick(finished, start + Duration::from_millis(27));
+        assert_eq!(clock.next_tick(), start + PERIOD * 3, "Back on the timeline");
+
+        let snapshot = metrics.snapshot();
+        assert_eq!((snapshot.overruns, snapshot.catch_up_ticks, snapshot.skipped_ticks), (1, 2, 0));
+        assert_eq!(snapshot.lag_us, 6000, "The last late tick started at 26 ms, due at 20");
+    }
+
+    #[test]
+    fn test_long_stall_skips_ticks_beyond_catch_up() {
+        let (mut clock, metrics, start) = clock();
+
+        // A 1 s stall, then fast ticks
+        let mut now = start + Duration::from_secs(1);
+        clock.finish_tick(start, now);
+        for _ in 0..MAX_CATCH_UP_TICKS {
+            let started = now;
+            now += Duration::from_millis(1);
+            clock.finish_tick(started, now);
+        }
+        assert_eq!(clock.next_tick(), now, "The timeline restarts");
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.catch_up_ticks, MAX_CATCH_UP_TICKS as u64);
+        // Ticks due from 90 ms to 1008 ms
+        assert_eq!(snapshot.skipped_ticks, 92);
+    }
+
+    #[test]
+    fn test_warns_when_it_cannot_keep_up() {
+        let (mut clock, _, start) = clock();
+        let mut now = start;
+        let mut warnings = Vec::new();
+        while now < start + LAG_WARNING_INTERVAL + Duration::from_secs(1) {
+            let started = clock.next_tick().max(now);
+            now = started + Duration::from_millis(12);
+            warnings.extend(clock.finish_tick(started, now));
+        }
+
+        assert_eq!(warnings.len(), 1, "{:?}", warnings);
+        assert!(warnings[0].starts_with("Server cannot sustain 100 Hz"), "{}", warnings[0]);
+    }
+}
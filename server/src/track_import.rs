@@ -0,0 +1,1122 @@
+//! Builds track files from real-world geometry.
+//!
+//! A recorded GPS lap (GPX or KML) becomes a `TrackFileFormat`: the trace is
+//! projected onto a flat plane around its centre, resampled at an even node
+//! spacing and smoothed, and a closed lap is found where the trace comes back
+//! on itself. Elevation comes from the recording, smoothed harder than the
+//! line since GPS altitude is the noisiest part of a fix.
+//!
+//! A centerline already in metres, read from CSV, goes through the same steps
+//! without the projection.
+//!
+//! OpenStreetMap ways go through the same steps after they are joined end to
+//! end, taking widths from their tags and heights from an elevation model.
+
+use crate::data::{GeoOrigin, TrackMetadata};
+use crate::track_loader::{TrackFileFormat, TrackNode};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Mean Earth radius used to project coordinates (m)
+const EARTH_RADIUS_M: f64 = 6_371_000.0;
+
+/// Fixes closer than this to the previous one are GPS jitter while stopped (m)
+const MIN_FIX_SPACING_M: f64 = 1.0;
+
+/// Shortest lap a closing trace may make (m)
+const MIN_LAP_LENGTH_M: f64 = 200.0;
+
+/// A position in WGS84 degrees, with altitude in metres if recorded
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoPoint {
+    pub lat: f64,
+    pub lon: f64,
+    pub ele: Option<f64>,
+}
+
+/// A recorded trace and the named waypoints that came with it
+#[derive(Debug, Clone, Default)]
+pub struct GpsTrace {
+    /// Name of the track or route, if the file has one
+    pub name: Option<String>,
+    pub points: Vec<GeoPoint>,
+    /// Waypoints (GPX) or point placemarks (KML) by name
+    pub markers: Vec<(String, GeoPoint)>,
+}
+
+#[derive(Debug)]
+pub enum TrackImportError {
+    IoError(std::io::Error),
+    ParseError(String),
+    /// The trace cannot make a track
+    InvalidTrace(String),
+}
+
+impl From<std::io::Error> for TrackImportError {
+    fn from(err: std::io::Error) -> Self {
+        TrackImportError::IoError(err)
+    }
+}
+
+impl fmt::Display for TrackImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrackImportError::IoError(e) => write!(f, "IO error: {}", e),
+            TrackImportError::ParseError(e) => write!(f, "Parse error: {}", e),
+            TrackImportError::InvalidTrace(e) => write!(f, "Invalid trace: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for TrackImportError {}
+
+/// Where node heights come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElevationMode {
+    /// The recorded altitude, or the elevation model's for OSM, smoothed
+    Recorded,
+    Flat,
+}
+
+/// How a trace is turned into a track
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+    /// Distance between track nodes (m)
+    pub node_spacing_m: f64,
+    /// Window the centerline is averaged over (m); 0 keeps it as recorded
+    pub smoothing_m: f64,
+    /// Window heights are averaged over (m)
+    pub elevation_smoothing_m: f64,
+    pub elevation: ElevationMode,
+    /// Track width written as the file's `default_width` (m)
+    pub width_m: f32,
+    /// `None` detects a closed lap and falls back to an open road course
+    pub closed_loop: Option<bool>,
+    /// How close the trace must come back to itself to close a lap (m)
+    pub lap_closure_m: f64,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            node_spacing_m: 5.0,
+            smoothing_m: 20.0,
+            elevation_smoothing_m: 100.0,
+            elevation: ElevationMode::Recorded,
+            width_m: 12.0,
+            closed_loop: None,
+            lap_closure_m: 25.0,
+        }
+    }
+}
+
+/// Read a GPX or KML file, told apart by its root element
+pub fn read_trace<P: AsRef<Path>>(path: P) -> Result<GpsTrace, TrackImportError> {
+    let content = std::fs::read_to_string(path)?;
+    parse_trace(&content)
+}
+
+/// Parse GPX or KML
+pub fn parse_trace(xml: &str) -> Result<GpsTrace, TrackImportError> {
+    if elements(xml, "kml").next().is_some() {
+        parse_kml(xml)
+    } else if elements(xml, "gpx").next().is_some() {
+        parse_gpx(xml)
+    } else {
+        Err(TrackImportError::ParseError("Neither a GPX nor a KML document".to_string()))
+    }
+}
+
+/// Track points of every track segment, or route points when there are none
+pub fn parse_gpx(xml: &str) -> Result<GpsTrace, TrackImportError> {
+    let fix = |attributes: &str, inner: &str| -> Result<GeoPoint, TrackImportError> {
+        let coordinate = |name: &str| {
+            attribute(attributes, name)
+                .and_then(|value| value.trim().parse::<f64>().ok())
+                .ok_or_else(|| TrackImportError::ParseError(format!("Point without a valid {}: <{}>", name, attributes.trim())))
+        };
+        let ele = elements(inner, "ele").next().and_then(|(_, value)| value.trim().parse().ok());
+        Ok(GeoPoint { lat: coordinate("lat")?, lon: coordinate("lon")?, ele })
+    };
+    let mut points = elements(xml, "trkpt").map(|(attributes, inner)| fix(attributes, inner)).collect::<Result<Vec<_>, _>>()?;
+    if points.is_empty() {
+        points = elements(xml, "rtept").map(|(attributes, inner)| fix(attributes, inner)).collect::<Result<_, _>>()?;
+    }
+    let markers = elements(xml, "wpt")
+        .map(|(attributes, inner)| Ok((element_text(inner, "name").unwrap_or_default(), fix(attributes, inner)?)))
+        .collect::<Result<_, TrackImportError>>()?;
+    let name = elements(xml, "trk").chain(elements(xml, "rte")).find_map(|(_, inner)| element_text(inner, "name"));
+    Ok(GpsTrace { name, points, markers })
+}
+
+/// Coordinates of every `LineString`; placemarks with a `Point` become markers
+pub fn parse_kml(xml: &str) -> Result<GpsTrace, TrackImportError> {
+    let mut trace = GpsTrace::default();
+    for (_, placemark) in elements(xml, "Placemark") {
+        if let Some((_, point)) = elements(placemark, "Point").next() {
+            if let Some(marker) = elements(point, "coordinates").next().map(|(_, text)| kml_coordinates(text)) {
+                if let Some(&position) = marker?.first() {
+                    trace.markers.push((element_text(placemark, "name").unwrap_or_default(), position));
+                }
+            }
+            continue;
+        }
+        for (_, line) in elements(placemark, "LineString") {
+            for (_, text) in elements(line, "coordinates") {
+                trace.points.extend(kml_coordinates(text)?);
+            }
+        }
+        if trace.name.is_none() && !trace.points.is_empty() {
+            trace.name = element_text(placemark, "name");
+        }
+    }
+    Ok(trace)
+}
+
+/// Parse a centerline in metres, one `x,y` or `x,y,z` point a line; a
+/// header line, blank lines and `#` comments are skipped
+pub fn parse_csv(text: &str) -> Result<Vec<[f64; 3]>, TrackImportError> {
+    let mut points = Vec::new();
+    let mut header = false;
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let values: Result<Vec<f64>, _> = line.split([',', ';', '\t']).take(3).map(|field| field.trim().parse::<f64>()).collect();
+        match values {
+            Ok(values) if values.len() >= 2 && values.iter().all(|value| value.is_finite()) => {
+                points.push([values[0], values[1], values.get(2).copied().unwrap_or(0.0)]);
+            }
+            Err(_) if points.is_empty() && !header => header = true,
+            _ => {
+                return Err(TrackImportError::ParseError(format!(
+                    "Line {}: expected x,y or x,y,z in metres, found \"{}\"",
+                    number + 1,
+                    line
+                )))
+            }
+        }
+    }
+    Ok(points)
+}
+
+/// `lon,lat[,alt]` tuples separated by whitespace
+fn kml_coordinates(text: &str) -> Result<Vec<GeoPoint>, TrackImportError> {
+    text.split_whitespace()
+        .map(|tuple| {
+            let values = tuple.split(',').map(|v| v.parse::<f64>()).collect::<Result<Vec<_>, _>>();
+            match values.as_deref() {
+                Ok([lon, lat]) => Ok(GeoPoint { lat: *lat, lon: *lon, ele: None }),
+                Ok([lon, lat, ele]) => Ok(GeoPoint { lat: *lat, lon: *lon, ele: Some(*ele) }),
+                _ => Err(TrackImportError::ParseError(format!("Invalid KML coordinate {}", tuple))),
+            }
+        })
+        .collect()
+}
+
+/// `(attributes, inner text)` of each `<name ...>...</name>` or `<name .../>`,
+/// outermost first; enough XML for GPS exports
+fn elements<'a>(xml: &'a str, name: &'a str) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
+    let open = format!("<{}", name);
+    let close = format!("</{}>", name);
+    let mut rest = xml;
+    std::iter::from_fn(move || loop {
+        let start = rest.find(&open)?;
+        let after = &rest[start + open.len()..];
+        // `<trk` must not match `<trkpt`
+        if !after.starts_with(|c: char| c.is_whitespace() || c == '>' || c == '/') {
+            rest = after;
+            continue;
+        }
+        let end = after.find('>')?;
+        let attributes = &after[..end];
+        if let Some(attributes) = attributes.strip_suffix('/') {
+            rest = &after[end + 1..];
+            return Some((attributes, ""));
+        }
+        let body = &after[end + 1..];
+        let inner_end = body.find(&close)?;
+        rest = &body[inner_end + close.len()..];
+        return Some((attributes, &body[..inner_end]));
+    })
+}
+
+/// Trimmed text of the first `<name>` element, without a CDATA wrapper
+fn element_text(xml: &str, name: &str) -> Option<String> {
+    let (_, text) = elements(xml, name).next()?;
+    let text = text.trim();
+    let text = text.strip_prefix("<![CDATA[").and_then(|t| t.strip_suffix("]]>")).unwrap_or(text);
+    Some(text.trim().to_string()).filter(|text| !text.is_empty())
+}
+
+/// Value of `name="..."` or `name='...'`
+fn attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = attributes;
+    while let Some(at) = rest.find(name) {
+        let before_ok = at == 0 || rest[..at].ends_with(char::is_whitespace);
+        let after = rest[at + name.len()..].trim_start();
+        if let (true, Some(value)) = (before_ok, after.strip_prefix('=')) {
+            let value = value.trim_start();
+            let quote = value.chars().next()?;
+            if quote == '"' || quote == '\'' {
+                let value = &value[1..];
+                return value.find(quote).map(|end| &value[..end]);
+            }
+        }
+        rest = &rest[at + name.len()..];
+    }
+    None
+}
+
+/// Equirectangular projection around a reference point, accurate to well
+/// under a metre across a circuit
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Projection {
+    lat0: f64,
+    lon0: f64,
+    cos_lat: f64,
+}
+
+impl Projection {
+    /// Around the middle of `points`
+    fn around(points: &[GeoPoint]) -> Self {
+        let (lat, lon) = points.iter().fold((0.0, 0.0), |(lat, lon), p| (lat + p.lat, lon + p.lon));
+        let count = points.len().max(1) as f64;
+        let (lat0, lon0) = (lat / count, lon / count);
+        Self::from_origin(GeoOrigin { lat: lat0, lon: lon0 })
+    }
+
+    /// With x = 0, y = 0 at `origin`
+    pub(crate) fn from_origin(origin: GeoOrigin) -> Self {
+        Self { lat0: origin.lat, lon0: origin.lon, cos_lat: origin.lat.to_radians().cos() }
+    }
+
+    pub(crate) fn origin(self) -> GeoOrigin {
+        GeoOrigin { lat: self.lat0, lon: self.lon0 }
+    }
+
+    fn to_xyz(self, p: &GeoPoint) -> [f64; 3] {
+        [
+            (p.lon - self.lon0).to_radians() * self.cos_lat * EARTH_RADIUS_M,
+            (p.lat - self.lat0).to_radians() * EARTH_RADIUS_M,
+            p.ele.unwrap_or(0.0),
+        ]
+    }
+
+    /// Latitude and longitude of a projected point
+    pub(crate) fn to_lat_lon(self, x: f64, y: f64) -> (f64, f64) {
+        (self.lat0 + (y / EARTH_RADIUS_M).to_degrees(), self.lon0 + (x / EARTH_RADIUS_M / self.cos_lat).to_degrees())
+    }
+}
+
+/// Flat x (east), y (north) and height around the middle of `points`
+pub fn project(points: &[GeoPoint]) -> Vec<[f64; 3]> {
+    let projection = Projection::around(points);
+    points.iter().map(|p| projection.to_xyz(p)).collect()
+}
+
+/// Centerline point being built: x, y, height and width
+pub(crate) type PathPoint = [f64; 4];
+
+fn distance_2d(a: &[f64], b: &[f64]) -> f64 {
+    (a[0] - b[0]).hypot(a[1] - b[1])
+}
+
+/// Distance along `points` to each of them, from the first
+pub(crate) fn cumulative_lengths(points: &[PathPoint]) -> Vec<f64> {
+    let mut total = 0.0;
+    let mut lengths = Vec::with_capacity(points.len());
+    for (i, point) in points.iter().enumerate() {
+        if i > 0 {
+            total += distance_2d(&points[i - 1], point);
+        }
+        lengths.push(total);
+    }
+    lengths
+}
+
+/// Points every `spacing` metres along the polyline; for a closed one the
+/// spacing is stretched so the last point falls a full step before the first
+pub(crate) fn resample(points: &[PathPoint], spacing: f64, closed: bool) -> Vec<PathPoint> {
+    let mut path = points.to_vec();
+    if closed && !path.is_empty() {
+        path.push(path[0]);
+    }
+    let lengths = cumulative_lengths(&path);
+    let total = lengths.last().copied().unwrap_or(0.0);
+    if path.len() < 2 || total <= 0.0 {
+        return points.to_vec();
+    }
+    let (count, step) = if closed {
+        let count = (total / spacing).round().max(3.0) as usize;
+        (count, total / count as f64)
+    } else {
+        let steps = (total / spacing).round().max(1.0) as usize;
+        (steps + 1, total / steps as f64)
+    };
+    let mut segment = 0;
+    (0..count)
+        .map(|i| {
+            let at = i as f64 * step;
+            while segment + 2 < path.len() && lengths[segment + 1] < at {
+                segment += 1;
+            }
+            let (a, b) = (&path[segment], &path[segment + 1]);
+            let length = lengths[segment + 1] - lengths[segment];
+            let t = if length > 0.0 { ((at - lengths[segment]) / length).clamp(0.0, 1.0) } else { 0.0 };
+            std::array::from_fn(|axis| a[axis] + (b[axis] - a[axis]) * t)
+        })
+        .collect()
+}
+
+/// Two passes of a moving average over `radius` points on each side of every
+/// point, of the coordinates in `axes`; open ends average over what is there
+fn smooth(points: &mut [PathPoint], radius: usize, closed: bool, axes: std::ops::Range<usize>) {
+    let n = points.len();
+    if radius == 0 || n < 3 {
+        return;
+    }
+    for _ in 0..2 {
+        let source = points.to_vec();
+        for (i, point) in points.iter_mut().enumerate() {
+            for axis in axes.clone() {
+                let (sum, count) = (i as isize - radius as isize..=i as isize + radius as isize)
+                    .filter_map(|j| match closed {
+                        true => Some(j.rem_euclid(n as isize) as usize),
+                        false => usize::try_from(j).ok().filter(|&j| j < n),
+                    })
+                    .fold((0.0, 0), |(sum, count), j| (sum + source[j][axis], count + 1));
+                point[axis] = sum / count as f64;
+            }
+        }
+    }
+}
+
+/// First place the trace comes back within `closure` metres of where it was
+/// at least a lap earlier: the lap is `points[start..end]`
+fn find_lap(points: &[PathPoint], closure: f64) -> Option<(usize, usize)> {
+    let lengths = cumulative_lengths(points);
+    let earlier = |end: usize| {
+        (0..end)
+            .take_while(|&start| lengths[end] - lengths[start] >= MIN_LAP_LENGTH_M.max(closure * 4.0))
+            .map(|start| (start, distance_2d(&points[start], &points[end])))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    };
+    let first = (0..points.len()).find(|&end| earlier(end).is_some_and(|(_, distance)| distance < closure))?;
+    // Coming within range is not the closest approach yet
+    let (start, end, _) = (first..points.len())
+        .take_while(|&end| lengths[end] - lengths[first] <= closure * 2.0)
+        .filter_map(|end| earlier(end).map(|(start, distance)| (start, end, distance)))
+        .min_by(|a, b| a.2.total_cmp(&b.2))?;
+    Some((start, end))
+}
+
+fn check_spacing(options: &ImportOptions) -> Result<(), TrackImportError> {
+    if options.node_spacing_m.is_nan() || options.node_spacing_m <= 0.0 {
+        return Err(TrackImportError::InvalidTrace("Node spacing must be positive".to_string()));
+    }
+    Ok(())
+}
+
+/// Spacing the centerline is processed at before the nodes are laid out
+fn fine_spacing(options: &ImportOptions) -> f64 {
+    options.node_spacing_m.min(options.lap_closure_m / 2.0)
+}
+
+/// Build a track file from a recorded trace
+pub fn trace_to_track(trace: &GpsTrace, name: &str, options: &ImportOptions) -> Result<TrackFileFormat, TrackImportError> {
+    check_spacing(options)?;
+    let mut all: Vec<GeoPoint> = trace.points.clone();
+    all.extend(trace.markers.iter().map(|(_, point)| *point));
+    let projection = Projection::around(&all);
+    let projected: Vec<[f64; 3]> = all.iter().map(|p| projection.to_xyz(p)).collect();
+    let (fixes, markers) = projected.split_at(trace.points.len());
+
+    let has_elevation = options.elevation == ElevationMode::Recorded && trace.points.iter().any(|p| p.ele.is_some());
+
+    // A waypoint named for the start/finish line moves the first node there
+    let start = trace.markers.iter().zip(markers).find(|((name, _), _)| is_start_finish(name)).map(|(_, marker)| *marker);
+    let mut track = path_to_track(fixes, start, has_elevation, name, options)?;
+    set_geo_origin(&mut track, projection);
+    Ok(track)
+}
+
+/// Build a track file from a centerline already in metres, such as one read
+/// from a CSV file: x (east), y (north) and height
+pub fn points_to_track(points: &[[f64; 3]], name: &str, options: &ImportOptions) -> Result<TrackFileFormat, TrackImportError> {
+    check_spacing(options)?;
+    let has_elevation = options.elevation == ElevationMode::Recorded && points.iter().any(|point| point[2] != 0.0);
+    path_to_track(points, None, has_elevation, name, options)
+}
+
+/// Drop repeated fixes, find a closed lap and lay the nodes out along what is left
+fn path_to_track(
+    fixes: &[[f64; 3]],
+    start: Option<[f64; 3]>,
+    has_elevation: bool,
+    name: &str,
+    options: &ImportOptions,
+) -> Result<TrackFileFormat, TrackImportError> {
+    let mut path: Vec<PathPoint> = Vec::with_capacity(fixes.len());
+    for fix in fixes {
+        if path.last().is_none_or(|last| distance_2d(last, fix) >= MIN_FIX_SPACING_M) {
+            path.push([fix[0], fix[1], fix[2], options.width_m as f64]);
+        }
+    }
+    if path.len() < 3 {
+        return Err(TrackImportError::InvalidTrace(format!("{} distinct points, at least 3 needed", path.len())));
+    }
+
+    // Work on an evenly spaced copy, so neither lap closure nor smoothing
+    // depends on the fix rate
+    let even = resample(&path, fine_spacing(options), false);
+    let lap = match options.closed_loop {
+        Some(false) => None,
+        Some(true) => Some(find_lap(&even, options.lap_closure_m).ok_or_else(|| {
+            TrackImportError::InvalidTrace(format!(
+                "The trace never comes back within {} m of itself, so it has no closed lap",
+                options.lap_closure_m
+            ))
+        })?),
+        None => find_lap(&even, options.lap_closure_m),
+    };
+    let path = match lap {
+        Some((start, end)) => even[start..end].to_vec(),
+        None => even,
+    };
+    Ok(lay_out_track(path, lap.is_some(), start, has_elevation, false, name, options))
+}
+
+/// Record where the track's x = 0, y = 0 lies, so terrain can be built
+/// from real elevation data around it
+fn set_geo_origin(track: &mut TrackFileFormat, projection: Projection) {
+    track.metadata.get_or_insert_with(TrackMetadata::default).geo_origin = Some(projection.origin());
+}
+
+/// Smooth an evenly spaced centerline and turn it into the track's nodes,
+/// starting nearest `start` on a closed loop
+fn lay_out_track(
+    mut path: Vec<PathPoint>,
+    closed: bool,
+    start: Option<[f64; 3]>,
+    has_elevation: bool,
+    has_widths: bool,
+    name: &str,
+    options: &ImportOptions,
+) -> TrackFileFormat {
+    let radius = |window: f64| (window / fine_spacing(options) / 2.0).round() as usize;
+    smooth(&mut path, radius(options.smoothing_m), closed, 0..2);
+    if has_elevation {
+        smooth(&mut path, radius(options.elevation_smoothing_m), closed, 2..3);
+    }
+    let mut nodes = resample(&path, options.node_spacing_m, closed);
+    if has_elevation {
+        let lowest = nodes.iter().map(|node| node[2]).fold(f64::INFINITY, f64::min);
+        nodes.iter_mut().for_each(|node| node[2] -= lowest);
+    } else {
+        nodes.iter_mut().for_each(|node| node[2] = 0.0);
+    }
+
+    if let (true, Some(start)) = (closed, start) {
+        let nearest = (0..nodes.len()).min_by(|&a, &b| distance_2d(&nodes[a], &start).total_cmp(&distance_2d(&nodes[b], &start)));
+        nodes.rotate_left(nearest.unwrap_or(0));
+    }
+
+    let length_m = cumulative_lengths(&nodes).last().copied().unwrap_or(0.0)
+        + if closed { distance_2d(&nodes[nodes.len() - 1], &nodes[0]) } else { 0.0 };
+    TrackFileFormat {
+        name: name.to_string(),
+        track_id: Some(uuid::Uuid::new_v4().to_string()),
+        nodes: nodes
+            .iter()
+            .map(|node| TrackNode {
+                x: node[0] as f32,
+                y: node[1] as f32,
+                z: node[2] as f32,
+                // Rounded, so a steady width does not flicker between nodes
+                width: Some((node[3] * 10.0).round() as f32 / 10.0).filter(|_| has_widths),
+                width_left: None,
+                width_right: None,
+                banking: None,
+                camber: None,
+                friction: None,
+                surface_type: Some("Asphalt".to_string()),
+                runoff_surface: None,
+            })
+            .collect(),
+        checkpoints: Vec::new(),
+        spawn_points: Vec::new(),
+        default_width: options.width_m,
+        closed_loop: closed,
+        raceline: Vec::new(),
+        metadata: Some(TrackMetadata { length_m: Some(length_m as f32), ..Default::default() }),
+        runoff_width: None,
+        boundary_walls: None,
+        walls: Vec::new(),
+        pit_lane: None,
+        timing: None,
+    }
+}
+
+fn is_start_finish(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.contains("start") || name.contains("finish") || name == "s/f"
+}
+
+/// `path` as written into the track file at `track_file`: relative to the
+/// track's directory when it is inside it, absolute otherwise
+pub fn path_from_track(track_file: &Path, path: &Path) -> std::io::Result<String> {
+    let path = std::fs::canonicalize(path)?;
+    let dir = track_file.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let relative = std::fs::canonicalize(dir).ok().and_then(|dir| path.strip_prefix(dir).ok().map(Path::to_path_buf));
+    Ok(relative.unwrap_or(path).to_string_lossy().into_owned())
+}
+
+/// Ground heights for the OSM importer
+pub trait ElevationModel {
+    /// Height above sea level (m), if the model covers the place
+    fn elevation(&mut self, lat: f64, lon: f64) -> Option<f64>;
+}
+
+/// SRTM `.hgt` tiles in one directory, named like `N50E005.hgt` after their
+/// south-west corner and loaded as they are needed
+pub struct SrtmTiles {
+    dir: PathBuf,
+    tiles: HashMap<(i32, i32), Option<SrtmTile>>,
+}
+
+/// A square grid of big-endian heights, north row first
+struct SrtmTile {
+    size: usize,
+    heights: Vec<i16>,
+}
+
+/// Height SRTM stores where it has no data
+const SRTM_VOID: i16 = -32768;
+
+impl SrtmTiles {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self { dir: dir.into(), tiles: HashMap::new() }
+    }
+
+    fn tile_name(lat: i32, lon: i32) -> String {
+        format!(
+            "{}{:02}{}{:03}.hgt",
+            if lat < 0 { 'S' } else { 'N' },
+            lat.abs(),
+            if lon < 0 { 'W' } else { 'E' },
+            lon.abs()
+        )
+    }
+
+    fn load(path: &Path) -> Option<SrtmTile> {
+        let bytes = std::fs::read(path).ok()?;
+        let size = ((bytes.len() / 2) as f64).sqrt() as usize;
+        if size < 2 || size * size * 2 != bytes.len() {
+            tracing::warn!("{} is not an SRTM tile", path.display());
+            return None;
+        }
+        let heights = bytes.chunks_exact(2).map(|pair| i16::from_be_bytes([pair[0], pair[1]])).collect();
+        Some(SrtmTile { size, heights })
+    }
+}
+
+impl ElevationModel for SrtmTiles {
+    fn elevation(&mut self, lat: f64, lon: f64) -> Option<f64> {
+        let (south, west) = (lat.floor() as i32, lon.floor() as i32);
+        let dir = &self.dir;
+        let tile = self
+            .tiles
+            .entry((south, west))
+            .or_insert_with(|| Self::load(&dir.join(Self::tile_name(south, west))))
+            .as_ref()?;
+        let last = (tile.size - 1) as f64;
+        let row = (1.0 - (lat - south as f64)) * last;
+        let col = (lon - west as f64) * last;
+        let (r0, c0) = (row.floor() as usize, col.floor() as usize);
+        let (r1, c1) = ((r0 + 1).min(tile.size - 1), (c0 + 1).min(tile.size - 1));
+        let height = |r: usize, c: usize| Some(tile.heights[r * tile.size + c]).filter(|&h| h != SRTM_VOID).map(f64::from);
+        let (fr, fc) = (row - r0 as f64, col - c0 as f64);
+        let top = height(r0, c0)? * (1.0 - fc) + height(r0, c1)? * fc;
+        let bottom = height(r1, c0)? * (1.0 - fc) + height(r1, c1)? * fc;
+        Some(top * (1.0 - fr) + bottom * fr)
+    }
+}
+
+/// What to build a track from in OpenStreetMap data
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OsmSelection {
+    Way(i64),
+    /// A route or circuit relation; its way members in order
+    Relation(i64),
+    /// The longest chain of `highway=raceway` ways inside the box, pit lanes left out
+    BoundingBox { south: f64, west: f64, north: f64, east: f64 },
+}
+
+impl OsmSelection {
+    /// Overpass API query that downloads what this selection needs as OSM XML
+    pub fn overpass_query(&self) -> String {
+        match self {
+            OsmSelection::Way(id) => format!("[out:xml];way({});(._;>;);out;", id),
+            OsmSelection::Relation(id) => format!("[out:xml];relation({});(._;>>;);out;", id),
+            OsmSelection::BoundingBox { south, west, north, east } => {
+                format!("[out:xml];(way[\"highway\"=\"raceway\"]({},{},{},{});>;);out;", south, west, north, east)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OsmWay {
+    pub id: i64,
+    pub nodes: Vec<i64>,
+    pub tags: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OsmRelation {
+    pub id: i64,
+    /// IDs of the ways among the members, in order
+    pub ways: Vec<i64>,
+    pub tags: HashMap<String, String>,
+}
+
+/// The parts of an OSM XML document a track is built from
+#[derive(Debug, Clone, Default)]
+pub struct OsmData {
+    pub nodes: HashMap<i64, GeoPoint>,
+    pub ways: Vec<OsmWay>,
+    pub relations: Vec<OsmRelation>,
+}
+
+/// Read an OSM XML file, as exported from openstreetmap.org or Overpass
+pub fn read_osm<P: AsRef<Path>>(path: P) -> Result<OsmData, TrackImportError> {
+    let content = std::fs::read_to_string(path)?;
+    parse_osm(&content)
+}
+
+pub fn parse_osm(xml: &str) -> Result<OsmData, TrackImportError> {
+    if elements(xml, "osm").next().is_none() {
+        return Err(TrackImportError::ParseError("Not an OSM XML document".to_string()));
+    }
+    let id = |element: &str, attributes: &str| {
+        attribute(attributes, "id")
+            .and_then(|id| id.parse::<i64>().ok())
+            .ok_or_else(|| TrackImportError::ParseError(format!("{} without a valid id", element)))
+    };
+    let tags = |inner: &str| {
+        elements(inner, "tag")
+            .filter_map(|(attributes, _)| Some((attribute(attributes, "k")?.to_string(), attribute(attributes, "v")?.to_string())))
+            .collect()
+    };
+    let mut data = OsmData::default();
+    for (attributes, _) in elements(xml, "node") {
+        let coordinate = |name: &str| attribute(attributes, name).and_then(|value| value.parse::<f64>().ok());
+        // Deleted nodes in change files have no position
+        if let (Some(lat), Some(lon)) = (coordinate("lat"), coordinate("lon")) {
+            data.nodes.insert(id("node", attributes)?, GeoPoint { lat, lon, ele: None });
+        }
+    }
+    for (attributes, inner) in elements(xml, "way") {
+        let nodes = elements(inner, "nd").filter_map(|(attributes, _)| attribute(attributes, "ref")?.parse().ok()).collect();
+        data.ways.push(OsmWay { id: id("way", attributes)?, nodes, tags: tags(inner) });
+    }
+    for (attributes, inner) in elements(xml, "relation") {
+        let ways = elements(inner, "member")
+            .filter(|(attributes, _)| attribute(attributes, "type") == Some("way"))
+            .filter_map(|(attributes, _)| attribute(attributes, "ref")?.parse().ok())
+            .collect();
+        data.relations.push(OsmRelation { id: id("relation", attributes)?, ways, tags: tags(inner) });
+    }
+    Ok(data)
+}
+
+/// Pit lanes are tagged several ways; none of them is racing surface
+fn is_pit_lane(way: &OsmWay) -> bool {
+    let tag = |key: &str| way.tags.get(key).map(|value| value.to_lowercase()).unwrap_or_default();
+    tag("service") == "pit_lane" || tag("raceway") == "pit_lane" || tag("name").contains("pit")
+}
+
+/// Width of a way in metres from its `width` tag, or from `lanes`
+fn way_width(way: &OsmWay) -> Option<f64> {
+    if let Some(width) = way.tags.get("width") {
+        let width = width.trim().trim_end_matches('m').trim();
+        if let Ok(width) = width.parse::<f64>() {
+            return Some(width).filter(|width| *width > 0.0);
+        }
+    }
+    way.tags.get("lanes").and_then(|lanes| lanes.parse::<f64>().ok()).map(|lanes| lanes * 3.5).filter(|width| *width > 0.0)
+}
+
+/// Join ways end to end, from the first, turning them round as needed;
+/// node IDs with the width of the way each came from
+fn chain_ways(ways: &[&OsmWay]) -> Vec<(i64, Option<f64>)> {
+    let Some(first) = ways.first() else {
+        return Vec::new();
+    };
+    let mut chain: Vec<(i64, Option<f64>)> = first.nodes.iter().map(|node| (*node, way_width(first))).collect();
+    let mut used = vec![false; ways.len()];
+    used[0] = true;
+    loop {
+        let (head, tail) = (chain[0].0, chain[chain.len() - 1].0);
+        if chain.len() > 1 && head == tail {
+            break;
+        }
+        let next = (0..ways.len()).filter(|&i| !used[i] && ways[i].nodes.len() > 1).find_map(|i| {
+            let nodes = &ways[i].nodes;
+            let (first, last) = (nodes[0], nodes[nodes.len() - 1]);
+            [(tail, first, false, false), (tail, last, false, true), (head, last, true, false), (head, first, true, true)]
+                .into_iter()
+                .find(|(end, joint, _, _)| end == joint)
+                .map(|(_, _, at_head, reversed)| (i, at_head, reversed))
+        });
+        let Some((i, at_head, reversed)) = next else {
+            break;
+        };
+        used[i] = true;
+        let width = way_width(ways[i]);
+        let mut nodes: Vec<(i64, Option<f64>)> = ways[i].nodes.iter().map(|node| (*node, width)).collect();
+        if reversed {
+            nodes.reverse();
+        }
+        if at_head {
+            nodes.pop();
+            nodes.extend(chain);
+            chain = nodes;
+        } else {
+            chain.extend(nodes.into_iter().skip(1));
+        }
+    }
+    chain
+}
+
+/// Build a track file from OSM ways, with heights from `elevation` when
+/// given; `name` defaults to the selected way's or relation's name tag
+pub fn osm_to_track(
+    data: &OsmData,
+    selection: OsmSelection,
+    elevation: Option<&mut dyn ElevationModel>,
+    name: Option<&str>,
+    options: &ImportOptions,
+) -> Result<TrackFileFormat, TrackImportError> {
+    check_spacing(options)?;
+    let not_found = |what: String| TrackImportError::InvalidTrace(format!("{} is not in the OSM data", what));
+    let (chain, tags) = match selection {
+        OsmSelection::Way(id) => {
+            let way = data.ways.iter().find(|way| way.id == id).ok_or_else(|| not_found(format!("Way {}", id)))?;
+            (chain_ways(&[way]), Some(&way.tags))
+        }
+        OsmSelection::Relation(id) => {
+            let relation = data.relations.iter().find(|relation| relation.id == id).ok_or_else(|| not_found(format!("Relation {}", id)))?;
+            let ways: Vec<&OsmWay> = relation
+                .ways
+                .iter()
+                .filter_map(|id| data.ways.iter().find(|way| way.id == *id))
+                .filter(|way| !is_pit_lane(way))
+                .collect();
+            (chain_ways(&ways), Some(&relation.tags))
+        }
+        OsmSelection::BoundingBox { south, west, north, east } => {
+            let inside = |node: &i64| {
+                data.nodes.get(node).is_some_and(|p| (south..=north).contains(&p.lat) && (west..=east).contains(&p.lon))
+            };
+            let mut ways: Vec<&OsmWay> = data
+                .ways
+                .iter()
+                .filter(|way| way.tags.get("highway").map(String::as_str) == Some("raceway") && !is_pit_lane(way))
+                .filter(|way| way.nodes.iter().all(inside))
+                .collect();
+            // Try every way as the first, keep the longest chain
+            let mut best: Vec<(i64, Option<f64>)> = Vec::new();
+            for i in 0..ways.len() {
+                ways.swap(0, i);
+                let chain = chain_ways(&ways);
+                if chain.len() > best.len() {
+                    best = chain;
+                }
+            }
+            (best, None)
+        }
+    };
+    if chain.is_empty() {
+        return Err(TrackImportError::InvalidTrace("No raceway ways to build a track from".to_string()));
+    }
+
+    let closed = chain.len() > 2 && chain[0].0 == chain[chain.len() - 1].0;
+    if options.closed_loop == Some(true) && !closed {
+        return Err(TrackImportError::InvalidTrace("The ways do not join up into a closed loop".to_string()));
+    }
+    let closed = closed && options.closed_loop != Some(false);
+    let chain = if closed { &chain[..chain.len() - 1] } else { &chain[..] };
+    let points = chain
+        .iter()
+        .map(|(id, _)| data.nodes.get(id).copied().ok_or_else(|| not_found(format!("Node {}", id))))
+        .collect::<Result<Vec<GeoPoint>, _>>()?;
+    let projection = Projection::around(&points);
+    let has_widths = chain.iter().any(|(_, width)| width.is_some());
+    let path: Vec<PathPoint> = points
+        .iter()
+        .zip(chain)
+        .map(|(point, (_, width))| {
+            let [x, y, _] = projection.to_xyz(point);
+            [x, y, 0.0, width.unwrap_or(options.width_m as f64)]
+        })
+        .collect();
+    if path.len() < 3 {
+        return Err(TrackImportError::InvalidTrace(format!("{} nodes, at least 3 needed", path.len())));
+    }
+    let mut path = resample(&path, fine_spacing(options), closed);
+
+    // Sample heights along the whole line, not just at the mapped nodes
+    let mut has_elevation = false;
+    if let (ElevationMode::Recorded, Some(model)) = (options.elevation, elevation) {
+        let heights: Vec<Option<f64>> = path
+            .iter()
+            .map(|point| {
+                let (lat, lon) = projection.to_lat_lon(point[0], point[1]);
+                model.elevation(lat, lon)
+            })
+            .collect();
+        let missing = heights.iter().filter(|height| height.is_none()).count();
+        if missing == heights.len() {
+            tracing::warn!("The elevation model does not cover the track; it is flat");
+        } else {
+            if missing > 0 {
+                tracing::warn!("No elevation data for {} of {} points; they take the height of the nearest", missing, heights.len());
+            }
+            let nearest = |i: usize| {
+                (0..heights.len()).filter_map(|j| heights[j].map(|height| (i.abs_diff(j), height))).min_by_key(|(gap, _)| *gap)
+            };
+            for (i, point) in path.iter_mut().enumerate() {
+                point[2] = heights[i].or_else(|| nearest(i).map(|(_, height)| height)).unwrap_or(0.0);
+            }
+            has_elevation = true;
+        }
+    }
+
+    let name = name
+        .map(str::to_string)
+        .or_else(|| tags.and_then(|tags| tags.get("name").cloned()))
+        .unwrap_or_else(|| "Imported track".to_string());
+    let mut track = lay_out_track(path, closed, None, has_elevation, has_widths, &name, options);
+    set_geo_origin(&mut track, projection);
+    Ok(track)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::track_loader::TrackLoader;
+
+    /// Fixes every `step` degrees around a circle of `radius_m` near Spa,
+    /// for `turns` laps from the east, climbing and falling 20 m a lap
+    fn circle(radius_m: f64, turns: f64, step: f64) -> Vec<GeoPoint> {
+        let (lat0, lon0) = (50.44_f64, 5.97_f64);
+        let per_degree = EARTH_RADIUS_M.to_radians().recip();
+        (0..=(360.0 * turns / step) as usize)
+            .map(|i| {
+                let angle = (i as f64 * step).to_radians();
+                // A metre of jitter, like a phone's fix
+                let jitter = (i as f64 * 12.9898).sin();
+                GeoPoint {
+                    lat: lat0 + (radius_m + jitter) * angle.sin() * per_degree,
+                    lon: lon0 + (radius_m + jitter) * angle.cos() * per_degree / lat0.to_radians().cos(),
+                    ele: Some(300.0 + 10.0 * angle.sin() + jitter),
+                }
+            })
+            .collect()
+    }
+
+    fn gpx(points: &[GeoPoint], waypoints: &str) -> String {
+        let fixes: String = points
+            .iter()
+            .map(|p| format!("<trkpt lat=\"{}\" lon=\"{}\"><ele>{}</ele><time>2024-01-01T00:00:00Z</time></trkpt>\n", p.lat, p.lon, p.ele.unwrap()))
+            .collect();
+        format!(
+            "<?xml version=\"1.0\"?>\n<gpx version=\"1.1\" creator=\"test\">{}<trk><name>Ring</name><trkseg>\n{}</trkseg></trk></gpx>",
+            waypoints, fixes
+        )
+    }
+
+    #[test]
+    fn test_recorded_laps_become_one_closed_loop() {
+        let trace = parse_trace(&gpx(&circle(300.0, 1.6, 0.5), "")).unwrap();
+        assert_eq!(trace.name.as_deref(), Some("Ring"));
+        let track = trace_to_track(&trace, "Ring", &ImportOptions::default()).unwrap();
+
+        assert!(track.closed_loop);
+        let lap = 2.0 * std::f64::consts::PI * 300.0;
+        let length = track.metadata.as_ref().unwrap().length_m.unwrap() as f64;
+        assert!((length - lap).abs() / lap < 0.02, "length {}", length);
+        assert!((track.nodes.len() as f64 - lap / 5.0).abs() <= 1.0);
+        // Heights keep their 20 m swing, from 0
+        let heights = track.nodes.iter().map(|node| node.z);
+        let (low, high) = heights.fold((f32::MAX, f32::MIN), |(low, high), z| (low.min(z), high.max(z)));
+        assert_eq!(low, 0.0);
+        assert!((15.0..22.0).contains(&high), "height {}", high);
+
+        let yaml = serde_yaml::to_string(&track).unwrap();
+        assert!(TrackLoader::load_from_string(&yaml).is_ok());
+    }
+
+    #[test]
+    fn test_start_finish_waypoint_moves_the_first_node() {
+        let (lat, lon) = (50.44 + 300.0 / EARTH_RADIUS_M.to_radians(), 5.97);
+        let waypoint = format!("<wpt lat='{}' lon='{}'><name>Start/Finish</name></wpt>", lat, lon);
+        let trace = parse_gpx(&gpx(&circle(300.0, 2.0, 1.0), &waypoint)).unwrap();
+        assert_eq!(trace.markers.len(), 1);
+
+        let track = trace_to_track(&trace, "Ring", &ImportOptions { elevation: ElevationMode::Flat, ..Default::default() }).unwrap();
+        let first = &track.nodes[0];
+        assert!(first.x.abs() < 5.0 && (first.y - 300.0).abs() < 5.0, "first node at {}, {}", first.x, first.y);
+        assert!(track.nodes.iter().all(|node| node.z == 0.0));
+    }
+
+    #[test]
+    fn test_a_trace_that_never_returns_stays_open() {
+        let trace = GpsTrace { points: circle(300.0, 0.5, 1.0), ..Default::default() };
+        let track = trace_to_track(&trace, "Half", &ImportOptions::default()).unwrap();
+        assert!(!track.closed_loop);
+        assert!(trace_to_track(&trace, "Half", &ImportOptions { closed_loop: Some(true), ..Default::default() }).is_err());
+        assert!(trace_to_track(&GpsTrace::default(), "Empty", &ImportOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_csv_centerline_becomes_a_track() {
+        let mut csv = "x,y,z\n".to_string();
+        for i in 0..=400 {
+            let angle = (i as f64 * 0.9).to_radians();
+            csv.push_str(&format!("{:.2},{:.2},{:.2}\n", 300.0 * angle.cos(), 300.0 * angle.sin(), 10.0 + 10.0 * angle.sin()));
+        }
+        let points = parse_csv(&csv).unwrap();
+        assert_eq!(points.len(), 401);
+
+        let track = points_to_track(&points, "Ring", &ImportOptions::default()).unwrap();
+        assert!(track.closed_loop);
+        let lap = 2.0 * std::f64::consts::PI * 300.0;
+        assert!((track.nodes.len() as f64 - lap / 5.0).abs() <= 1.0);
+        assert!(track.nodes.iter().any(|node| node.z > 15.0));
+        assert!(track.metadata.as_ref().unwrap().geo_origin.is_none());
+
+        let flat = parse_csv("# lap\n0,0\n100;0\n100\t100\n").unwrap();
+        assert_eq!(flat, vec![[0.0, 0.0, 0.0], [100.0, 0.0, 0.0], [100.0, 100.0, 0.0]]);
+        let error = parse_csv("x,y\n0,0\n10,ten\n").unwrap_err();
+        assert!(error.to_string().contains("Line 3"), "{}", error);
+    }
+
+    #[test]
+    fn test_parse_kml() {
+        let kml = r#"<?xml version="1.0"?><kml xmlns="http://www.opengis.net/kml/2.2"><Document>
+            <Placemark><name>S/F</name><Point><coordinates>5.97,50.44,0</coordinates></Point></Placemark>
+            <Placemark><name><![CDATA[Hill climb]]></name><LineString><coordinates>
+                5.97,50.44,300 5.971,50.441,305
+                5.972,50.442
+            </coordinates></LineString></Placemark></Document></kml>"#;
+        let trace = parse_trace(kml).unwrap();
+        assert_eq!(trace.name.as_deref(), Some("Hill climb"));
+        assert_eq!(trace.points.len(), 3);
+        assert_eq!(trace.points[1], GeoPoint { lat: 50.441, lon: 5.971, ele: Some(305.0) });
+        assert_eq!(trace.points[2].ele, None);
+        assert_eq!(trace.markers[0].0, "S/F");
+
+        assert!(parse_trace("<gpx><trk><trkseg><trkpt lon=\"5.9\"/></trkseg></trk></gpx>").is_err());
+        assert!(parse_trace("{}").is_err());
+    }
+
+    /// A 0.004° square near (50.5, 5.5) in two ways, the second drawn the
+    /// other way round, with a pit lane in the same relation
+    const CIRCUIT_OSM: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<osm version="0.6" generator="test">
+  <node id="1" lat="50.498" lon="5.498"/>
+  <node id="2" lat="50.502" lon="5.498"/>
+  <node id="3" lat="50.502" lon="5.502"/>
+  <node id="4" lat="50.498" lon="5.502"/>
+  <node id="5" lat="50.4985" lon="5.4985"><tag k="barrier" v="gate"/></node>
+  <node id="6" lat="50.5015" lon="5.4985"/>
+  <way id="10"><nd ref="1"/><nd ref="2"/><nd ref="3"/><tag k="highway" v="raceway"/><tag k="width" v="10 m"/></way>
+  <way id="11"><nd ref="1"/><nd ref="4"/><nd ref="3"/><tag k="highway" v="raceway"/><tag k="lanes" v="2"/></way>
+  <way id="12"><nd ref="5"/><nd ref="6"/><tag k="highway" v="raceway"/><tag k="service" v="pit_lane"/></way>
+  <relation id="100">
+    <member type="way" ref="12" role="pit_lane"/>
+    <member type="way" ref="10" role=""/>
+    <member type="way" ref="11" role=""/>
+    <member type="node" ref="1" role="start"/>
+    <tag k="name" v="Square Ring"/>
+  </relation>
+</osm>"#;
+
+    /// Heights rising 1000 m per 0.5° east, with a void in the north-east corner
+    fn srtm_tile(dir: &Path) {
+        let heights: [i16; 9] = [0, 1000, SRTM_VOID, 0, 1000, 2000, 0, 1000, 2000];
+        let bytes: Vec<u8> = heights.iter().flat_map(|h| h.to_be_bytes()).collect();
+        std::fs::write(dir.join("N50E005.hgt"), bytes).unwrap();
+    }
+
+    #[test]
+    fn test_srtm_heights_are_interpolated() {
+        let dir = tempfile::tempdir().unwrap();
+        srtm_tile(dir.path());
+        let mut dem = SrtmTiles::new(dir.path());
+        assert_eq!(dem.elevation(50.25, 5.25), Some(500.0));
+        assert_eq!(dem.elevation(50.0, 6.0 - 1e-9).map(f64::round), Some(2000.0));
+        assert_eq!(dem.elevation(50.9, 5.9), None);
+        assert_eq!(dem.elevation(-33.9, 151.2), None);
+        assert_eq!(SrtmTiles::tile_name(-34, -71), "S34W071.hgt");
+    }
+
+    #[test]
+    fn test_osm_relation_becomes_a_closed_track() {
+        let data = parse_osm(CIRCUIT_OSM).unwrap();
+        assert_eq!(data.nodes.len(), 6);
+        assert_eq!(data.relations[0].ways, vec![12, 10, 11]);
+
+        let dir = tempfile::tempdir().unwrap();
+        srtm_tile(dir.path());
+        let mut dem = SrtmTiles::new(dir.path());
+        let options = ImportOptions { smoothing_m: 0.0, ..Default::default() };
+        let track = osm_to_track(&data, OsmSelection::Relation(100), Some(&mut dem), None, &options).unwrap();
+
+        assert_eq!(track.name, "Square Ring");
+        assert!(track.closed_loop);
+        let side_ns = 0.004_f64.to_radians() * EARTH_RADIUS_M;
+        let lap = 2.0 * side_ns + 2.0 * side_ns * 50.5_f64.to_radians().cos();
+        let length = track.metadata.as_ref().unwrap().length_m.unwrap() as f64;
+        // Nodes cut the corners a little
+        assert!((length - lap).abs() < 10.0, "length {} of {}", length, lap);
+        // Widths from the `width` tag on one side and `lanes` on the other
+        let widths: std::collections::BTreeSet<u32> = track.nodes.iter().map(|node| node.width.unwrap() as u32).collect();
+        assert!(widths.contains(&10) && widths.contains(&7));
+        // The east side is 8 m higher than the west
+        let high = track.nodes.iter().map(|node| node.z).fold(0.0_f32, f32::max);
+        assert!((high - 8.0).abs() < 0.5, "height {}", high);
+        // Nodes are laid out around the middle of the square
+        let origin = track.metadata.as_ref().unwrap().geo_origin.unwrap();
+        assert!((origin.lat - 50.5).abs() < 1e-9 && (origin.lon - 5.5).abs() < 1e-9, "origin {:?}", origin);
+        assert!(TrackLoader::load_from_string(&serde_yaml::to_string(&track).unwrap()).is_ok());
+
+        let open = osm_to_track(&data, OsmSelection::Way(10), None, Some("Half"), &options).unwrap();
+        assert!(!open.closed_loop);
+        assert!(open.nodes.iter().all(|node| node.z == 0.0 && node.width == Some(10.0)));
+        assert!(osm_to_track(&data, OsmSelection::Way(10), None, None, &ImportOptions { closed_loop: Some(true), ..options.clone() }).is_err());
+        assert!(osm_to_track(&data, OsmSelection::Way(99), None, None, &options).is_err());
+    }
+
+    #[test]
+    fn test_osm_bounding_box_finds_the_raceway_and_skips_the_pit_lane() {
+        let data = parse_osm(CIRCUIT_OSM).unwrap();
+        let bbox = OsmSelection::BoundingBox { south: 50.49, west: 5.49, north: 50.51, east: 5.51 };
+        let track = osm_to_track(&data, bbox, None, None, &ImportOptions::default()).unwrap();
+        assert!(track.closed_loop);
+        assert_eq!(track.name, "Imported track");
+
+        let outside = OsmSelection::BoundingBox { south: 0.0, west: 0.0, north: 1.0, east: 1.0 };
+        assert!(osm_to_track(&data, outside, None, None, &ImportOptions::default()).is_err());
+        assert_eq!(
+            OsmSelection::Relation(100).overpass_query(),
+            "[out:xml];relation(100);(._;>>;);out;"
+        );
+        assert!(parse_osm("<gpx></gpx>").is_err());
+    }
+}
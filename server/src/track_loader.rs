@@ -1,7 +1,33 @@
-use crate::data::{TrackConfig, TrackPoint, SurfaceType, TrackSurface, GridSlot, RacelinePoint, TrackMetadata};
+use crate::data::{TrackConfig, TrackPoint, SurfaceType, TrackSurface, GridSlot, RacelinePoint, TrackMetadata, TrackConfigId, WallSegment, AiHints, PitLaneConfig, PitStall, SpeedTrap, DrsZone, TimingLines, DEFAULT_RUNOFF_WIDTH_M};
+use crate::content_hash;
+use crate::raceline;
+use crate::procgen::manifest::{self, WorldParams};
+use crate::track_import::ElevationModel;
+use crate::qa_report::find_corners;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// Suffix of the AI hint file that can sit next to a track file
+const AI_HINTS_SUFFIX: &str = ".ai.yaml";
+
+/// Widest total track width accepted (m); half that for one side
+const MAX_TRACK_WIDTH_M: f32 = 60.0;
+
+/// Nodes closer together than this count as the same place (m)
+const MIN_NODE_SPACING_M: f32 = 0.01;
+
+/// How far a raceline's length may differ from the centerline's, as a fraction
+const RACELINE_LENGTH_TOLERANCE: f32 = 0.2;
+
+/// Pit lane width when neither the pit lane nor its nodes give one (m)
+const DEFAULT_PIT_LANE_WIDTH_M: f32 = 8.0;
+
+/// Furthest the pit lane's ends may be from its entry and exit nodes (m)
+const PIT_LANE_JOIN_DISTANCE_M: f32 = 30.0;
+
+/// Furthest a pit box may be from the pit lane centerline (m)
+const PIT_BOX_MAX_DISTANCE_M: f32 = 20.0;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TrackFileFormat {
@@ -23,6 +49,94 @@ pub struct TrackFileFormat {
     /// Track metadata
     #[serde(default)]
     pub metadata: Option<TrackMetadata>,
+    /// Runoff between the track edge and the boundary wall (m, default 15)
+    #[serde(default)]
+    pub runoff_width: Option<f32>,
+    /// Generate walls along both track boundaries (default true)
+    #[serde(default)]
+    pub boundary_walls: Option<bool>,
+    /// Extra barriers such as pit walls or tyre stacks
+    #[serde(default)]
+    pub walls: Vec<WallDefinition>,
+    /// Pit lane, for tracks that have one
+    #[serde(default)]
+    pub pit_lane: Option<PitLaneDefinition>,
+    /// Start/finish line, sector splits and speed traps
+    #[serde(default)]
+    pub timing: Option<TimingDefinition>,
+}
+
+/// Timing lines, each placed where it crosses the centerline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimingDefinition {
+    /// Where laps start and end (default: the first node); closed loops only
+    #[serde(default)]
+    pub start_finish: Option<TimingPoint>,
+    /// Lines between sectors, in lap order
+    #[serde(default)]
+    pub sectors: Vec<TimingPoint>,
+    #[serde(default)]
+    pub speed_traps: Vec<SpeedTrapDefinition>,
+    /// Stretches where DRS or a boost may be used, in lap order
+    #[serde(default)]
+    pub drs_zones: Vec<DrsZoneDefinition>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TimingPoint {
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeedTrapDefinition {
+    /// Default: "Speed trap 1", "Speed trap 2", ...
+    #[serde(default)]
+    pub name: Option<String>,
+    pub x: f32,
+    pub y: f32,
+}
+
+/// DRS or boost zone from the line where it opens to the line where it closes
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct DrsZoneDefinition {
+    pub start: TimingPoint,
+    pub end: TimingPoint,
+}
+
+/// Pit lane running beside the track from `entry_node` to `exit_node`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PitLaneDefinition {
+    /// Track node where the pit lane leaves the track
+    pub entry_node: usize,
+    /// Track node where the pit lane rejoins the track
+    pub exit_node: usize,
+    /// Pit lane centerline, from entry to exit
+    pub nodes: Vec<TrackNode>,
+    /// Width of pit lane nodes without their own (m, default 8)
+    #[serde(default)]
+    pub default_width: f32,
+    pub speed_limit_mps: f32,
+    /// Where the speed limit starts, in m from the pit entry (default: the entry)
+    #[serde(default)]
+    pub speed_limit_start_m: Option<f32>,
+    /// Where the speed limit ends, in m from the pit entry (default: the exit)
+    #[serde(default)]
+    pub speed_limit_end_m: Option<f32>,
+    /// Pit boxes in grid order: the first belongs to grid slot 1
+    #[serde(default)]
+    pub boxes: Vec<PitBox>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PitBox {
+    pub x: f32,
+    pub y: f32,
+    #[serde(default)]
+    pub z: f32,
+    /// Direction a car parks in (default: along the pit lane)
+    #[serde(default)]
+    pub yaw: Option<f32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,10 +153,32 @@ pub struct TrackNode {
     pub width_right: Option<f32>,
     #[serde(default)]
     pub banking: Option<f32>,
+    /// Crown of the surface (rad): it falls away this much either side of
+    /// the centerline (default 0)
+    #[serde(default)]
+    pub camber: Option<f32>,
     #[serde(default)]
     pub friction: Option<f32>,
     #[serde(default)]
     pub surface_type: Option<String>,
+    /// Surface beside the track (default: "Grass")
+    #[serde(default)]
+    pub runoff_surface: Option<String>,
+}
+
+/// Barrier following a polyline in track coordinates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WallDefinition {
+    pub points: Vec<WallPoint>,
+    /// Join the last point back to the first
+    #[serde(default)]
+    pub closed: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct WallPoint {
+    pub x: f32,
+    pub y: f32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -60,11 +196,35 @@ pub struct SpawnPoint {
     pub offset_y: f32,
 }
 
+/// Problem found in a track file, pointing at the field responsible
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackDiagnostic {
+    /// Path of the field, such as `nodes[12].width_left`; empty for the whole file
+    pub field: String,
+    /// 1-based line of the field, for YAML files in block style
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl std::fmt::Display for TrackDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {}: ", line)?;
+        }
+        if !self.field.is_empty() {
+            write!(f, "{}: ", self.field)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
 #[derive(Debug)]
 pub enum TrackLoadError {
     IoError(std::io::Error),
     ParseError(String),
     InvalidData(String),
+    /// The file parsed but failed validation
+    Invalid(Vec<TrackDiagnostic>),
 }
 
 impl From<std::io::Error> for TrackLoadError {
@@ -79,6 +239,10 @@ impl std::fmt::Display for TrackLoadError {
             TrackLoadError::IoError(e) => write!(f, "IO error: {}", e),
             TrackLoadError::ParseError(e) => write!(f, "Parse error: {}", e),
             TrackLoadError::InvalidData(e) => write!(f, "Invalid data: {}", e),
+            TrackLoadError::Invalid(problems) => {
+                let problems: Vec<String> = problems.iter().map(ToString::to_string).collect();
+                write!(f, "Invalid track: {}", problems.join("; "))
+            }
         }
     }
 }
@@ -91,69 +255,420 @@ impl TrackLoader {
     pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<TrackConfig, TrackLoadError> {
         let path_ref = path.as_ref();
         let content = fs::read_to_string(path_ref)?;
-        Self::load_from_string_with_path(&content, Some(path_ref))
+        let mut track = Self::load_from_string_with_path(&content, Some(path_ref))?;
+        track.content_hash = content_hash::hash_parts(&[content.as_bytes()]);
+        Ok(track)
     }
 
     pub fn load_from_string(content: &str) -> Result<TrackConfig, TrackLoadError> {
         Self::load_from_string_with_path(content, None)
     }
 
+    /// Every problem in a track file that keeps it from loading, apart from
+    /// its AI hint file; empty when there are none
+    pub fn validate(content: &str) -> Vec<TrackDiagnostic> {
+        match Self::parse(content) {
+            Ok(track_file) => Self::diagnose(&track_file, content).err().unwrap_or_default(),
+            Err(problem) => vec![problem],
+        }
+    }
+
+    /// `validate` for a file on disk
+    pub fn validate_file<P: AsRef<Path>>(path: P) -> Vec<TrackDiagnostic> {
+        match fs::read_to_string(path) {
+            Ok(content) => Self::validate(&content),
+            Err(e) => vec![TrackDiagnostic { field: String::new(), line: None, message: e.to_string() }],
+        }
+    }
+
     fn load_from_string_with_path(content: &str, track_path: Option<&Path>) -> Result<TrackConfig, TrackLoadError> {
-        let track_file: TrackFileFormat = if content.trim_start().starts_with('{') {
-            serde_json::from_str(content)
-                .map_err(|e| TrackLoadError::ParseError(format!("JSON parse error: {}", e)))?
-        } else {
-            serde_yaml::from_str(content)
-                .map_err(|e| TrackLoadError::ParseError(format!("YAML parse error: {}", e)))?
-        };
+        let track_file = Self::parse(content).map_err(|problem| TrackLoadError::ParseError(problem.message))?;
 
-        Self::validate(&track_file)?;
-        Self::build_track_config(track_file, track_path)
+        let centerline = Self::diagnose(&track_file, content).map_err(TrackLoadError::Invalid)?;
+        Self::build_track_config(track_file, centerline, track_path)
     }
 
-    fn validate(track: &TrackFileFormat) -> Result<(), TrackLoadError> {
-        if track.nodes.len() < 2 {
-            return Err(TrackLoadError::InvalidData(
-                "Track must have at least 2 nodes".to_string(),
+    fn parse(content: &str) -> Result<TrackFileFormat, TrackDiagnostic> {
+        if content.trim_start().starts_with('{') {
+            serde_json::from_str(content).map_err(|e| TrackDiagnostic {
+                field: String::new(),
+                line: Some(e.line()),
+                message: format!("JSON parse error: {}", e),
+            })
+        } else {
+            serde_yaml::from_str(content).map_err(|e| TrackDiagnostic {
+                field: String::new(),
+                line: e.location().map(|location| location.line()),
+                message: format!("YAML parse error: {}", e),
+            })
+        }
+    }
+
+    /// Checks a parsed track file: finite coordinates, sane widths, distinct
+    /// nodes so distance keeps increasing, a closed loop that really closes,
+    /// a raceline about as long as the centerline and spawn points on the track.
+    /// Gives the interpolated centerline when there are no problems
+    fn diagnose(track: &TrackFileFormat, content: &str) -> Result<Vec<TrackPoint>, Vec<TrackDiagnostic>> {
+        let mut found = Diagnostics { lines: FieldLines::new(content), found: Vec::new() };
+        let nodes = &track.nodes;
+
+        if nodes.len() < 2 {
+            found.report("nodes", None, None, format!("Track must have at least 2 nodes, found {}", nodes.len()));
+        }
+
+        let has_width = |n: &TrackNode| n.width.is_some() || (n.width_left.is_some() && n.width_right.is_some());
+        if track.default_width <= 0.0 && !nodes.iter().any(has_width) {
+            found.report("default_width", None, None, "Track must have a default_width or per-node width values".to_string());
+        }
+        if !(0.0..=MAX_TRACK_WIDTH_M).contains(&track.default_width) {
+            found.report("default_width", None, None, format!(
+                "{} m is not a track width, expected up to {} m",
+                track.default_width, MAX_TRACK_WIDTH_M
             ));
         }
 
-        if track.default_width <= 0.0 && track.nodes.iter().all(|n| n.width.is_none()) {
-            return Err(TrackLoadError::InvalidData(
-                "Track must have a default_width or per-node width values".to_string(),
+        for (i, node) in nodes.iter().enumerate() {
+            for (field, value) in [("x", node.x), ("y", node.y), ("z", node.z)] {
+                if !value.is_finite() {
+                    found.report("nodes", Some(i), Some(field), format!("{} is not a coordinate", value));
+                }
+            }
+            let widths = [
+                ("width", node.width, MAX_TRACK_WIDTH_M),
+                ("width_left", node.width_left, MAX_TRACK_WIDTH_M / 2.0),
+                ("width_right", node.width_right, MAX_TRACK_WIDTH_M / 2.0),
+            ];
+            for (field, width, max) in widths {
+                if let Some(width) = width.filter(|w| !(*w > 0.0 && *w <= max)) {
+                    found.report("nodes", Some(i), Some(field), format!("{} m is not a track width, expected more than 0 and up to {} m", width, max));
+                }
+            }
+            match (node.width_left, node.width_right) {
+                (Some(_), None) => found.report("nodes", Some(i), Some("width_left"), "Set width_right too, or neither".to_string()),
+                (None, Some(_)) => found.report("nodes", Some(i), Some("width_right"), "Set width_left too, or neither".to_string()),
+                _ => {}
+            }
+        }
+
+        let spacing = |a: &TrackNode, b: &TrackNode| ((b.x - a.x).powi(2) + (b.y - a.y).powi(2) + (b.z - a.z).powi(2)).sqrt();
+        let mut longest_spacing = 0.0f32;
+        for i in 1..nodes.len() {
+            let gap = spacing(&nodes[i - 1], &nodes[i]);
+            longest_spacing = longest_spacing.max(gap);
+            if gap < MIN_NODE_SPACING_M {
+                found.report("nodes", Some(i), None, format!(
+                    "Same place as nodes[{}], so distance along the track stops increasing",
+                    i - 1
+                ));
+            }
+        }
+        if track.closed_loop && nodes.len() > 2 {
+            let last = nodes.len() - 1;
+            let closing_gap = spacing(&nodes[last], &nodes[0]);
+            if closing_gap < MIN_NODE_SPACING_M {
+                found.report("nodes", Some(last), None, "Repeats nodes[0]; closed_loop already joins the last node to the first".to_string());
+            } else if closing_gap > 3.0 * longest_spacing {
+                found.report("closed_loop", None, None, format!(
+                    "The centerline does not close: the last node is {:.1} m from the first, but nodes are at most {:.1} m apart elsewhere",
+                    closing_gap, longest_spacing
+                ));
+            }
+        }
+
+        for (i, checkpoint) in track.checkpoints.iter().enumerate() {
+            for (field, index) in [("index_start", checkpoint.index_start), ("index_end", checkpoint.index_end)] {
+                if index >= nodes.len() {
+                    found.report("checkpoints", Some(i), Some(field), format!("Node {} is out of bounds, the track has {} nodes", index, nodes.len()));
+                }
+            }
+        }
+
+        if let Some(runoff_width) = track.runoff_width.filter(|w| *w < 0.0) {
+            found.report("runoff_width", None, None, format!("runoff_width must not be negative, got {}", runoff_width));
+        }
+        for (i, wall) in track.walls.iter().enumerate() {
+            if wall.points.len() < 2 {
+                found.report("walls", Some(i), Some("points"), format!("Wall {} needs at least 2 points", i));
+            }
+        }
+
+        if let Some(pit_lane) = &track.pit_lane {
+            if pit_lane.nodes.len() < 2 {
+                found.report("pit_lane", None, Some("nodes"), format!("A pit lane needs at least 2 nodes, found {}", pit_lane.nodes.len()));
+            }
+            for (i, node) in pit_lane.nodes.iter().enumerate() {
+                if !(node.x.is_finite() && node.y.is_finite() && node.z.is_finite()) {
+                    found.report("pit_lane", None, Some(&format!("nodes[{}]", i)), "Coordinates must be numbers".to_string());
+                }
+            }
+            for (field, node) in [("entry_node", pit_lane.entry_node), ("exit_node", pit_lane.exit_node)] {
+                if node >= nodes.len() {
+                    found.report("pit_lane", None, Some(field), format!("Node {} is out of bounds, the track has {} nodes", node, nodes.len()));
+                }
+            }
+            if !(pit_lane.speed_limit_mps > 0.0 && pit_lane.speed_limit_mps.is_finite()) {
+                found.report("pit_lane", None, Some("speed_limit_mps"), format!("{} is not a speed limit", pit_lane.speed_limit_mps));
+            }
+            if !(0.0..=MAX_TRACK_WIDTH_M).contains(&pit_lane.default_width) {
+                found.report("pit_lane", None, Some("default_width"), format!(
+                    "{} m is not a pit lane width, expected up to {} m",
+                    pit_lane.default_width, MAX_TRACK_WIDTH_M
+                ));
+            }
+            if let (Some(start), Some(end)) = (pit_lane.speed_limit_start_m, pit_lane.speed_limit_end_m) {
+                if start > end {
+                    found.report("pit_lane", None, Some("speed_limit_start_m"), format!("The speed limit starts at {} m, after it ends at {} m", start, end));
+                }
+            }
+        }
+
+        if let Some(Err(e)) = track.track_id.as_ref().map(|id| id.parse::<TrackConfigId>()) {
+            found.report("track_id", None, None, format!("Invalid track_id format: {}", e));
+        }
+
+        // The rest needs a centerline, which only sound nodes give
+        if !found.found.is_empty() {
+            return Err(found.found);
+        }
+        let default_width = if track.default_width > 0.0 { track.default_width } else { 12.0 };
+        let centerline = match SplineInterpolator::interpolate_spline(nodes, track.closed_loop, default_width) {
+            Ok(centerline) => centerline,
+            Err(e) => {
+                found.report("nodes", None, None, e.to_string());
+                return Err(found.found);
+            }
+        };
+
+        if let Some(i) = centerline.windows(2).position(|pair| pair[1].distance_from_start_m <= pair[0].distance_from_start_m) {
+            let node = i / SplineInterpolator::POINTS_PER_SEGMENT;
+            found.report("nodes", Some(node), None, format!(
+                "Distance along the track stops increasing between nodes[{}] and the next node",
+                node
             ));
         }
 
-        for checkpoint in &track.checkpoints {
-            if checkpoint.index_start >= track.nodes.len()
-                || checkpoint.index_end >= track.nodes.len()
-            {
-                return Err(TrackLoadError::InvalidData(format!(
-                    "Checkpoint indices out of bounds: start={}, end={}, nodes={}",
-                    checkpoint.index_start,
-                    checkpoint.index_end,
-                    track.nodes.len()
-                )));
+        if !track.raceline.is_empty() {
+            let raceline_length = path_length(track.raceline.iter().map(|p| (p.x, p.y, p.z)), track.closed_loop);
+            let centerline_length = path_length(centerline.iter().map(|p| (p.x, p.y, p.z)), track.closed_loop);
+            let non_finite = track.raceline.iter().position(|p| !(p.x.is_finite() && p.y.is_finite() && p.z.is_finite()));
+            if let Some(i) = non_finite {
+                found.report("raceline", Some(i), None, "Coordinates must be numbers".to_string());
+            } else if track.raceline.len() < 2 {
+                found.report("raceline", None, None, "A raceline needs at least 2 points".to_string());
+            } else if (raceline_length / centerline_length - 1.0).abs() > RACELINE_LENGTH_TOLERANCE {
+                found.report("raceline", None, None, format!(
+                    "The raceline is {:.0} m long but the centerline is {:.0} m; they should differ by at most {:.0}%",
+                    raceline_length,
+                    centerline_length,
+                    RACELINE_LENGTH_TOLERANCE * 100.0
+                ));
+            }
+        }
+
+        for (i, spawn) in track.spawn_points.iter().enumerate() {
+            let Some(point) = centerline.get(spawn.position) else {
+                found.report("spawn_points", Some(i), Some("position"), format!(
+                    "Centerline point {} is past the end of the track's {} points ({} per node)",
+                    spawn.position,
+                    centerline.len(),
+                    SplineInterpolator::POINTS_PER_SEGMENT
+                ));
+                continue;
+            };
+            // Positive to the left of the direction of travel
+            let lateral = -spawn.offset_x * point.heading_rad.sin() + spawn.offset_y * point.heading_rad.cos();
+            let (side, width) = if lateral >= 0.0 { ("left", point.width_left_m) } else { ("right", point.width_right_m) };
+            if lateral.abs() > width {
+                found.report("spawn_points", Some(i), None, format!(
+                    "The offset puts the car {:.1} m {} of the centerline, off the {:.1} m of track on that side",
+                    lateral.abs(),
+                    side,
+                    width
+                ));
+            }
+        }
+
+        if let Some(pit_lane) = track.pit_lane.as_ref().and_then(|def| Self::build_pit_lane(def, &centerline, 0)) {
+            let (first, last) = (&pit_lane.centerline[0], &pit_lane.centerline[pit_lane.centerline.len() - 1]);
+            let ends = [
+                ("entry_node", &pit_lane.entry_point, first, "starts"),
+                ("exit_node", &pit_lane.exit_point, last, "ends"),
+            ];
+            for (field, joins, end, verb) in ends {
+                let gap = ((end.x - joins.x).powi(2) + (end.y - joins.y).powi(2)).sqrt();
+                if gap > PIT_LANE_JOIN_DISTANCE_M {
+                    found.report("pit_lane", None, Some(field), format!(
+                        "The pit lane {} {:.0} m from this node; it should be within {:.0} m",
+                        verb, gap, PIT_LANE_JOIN_DISTANCE_M
+                    ));
+                }
+            }
+            for (field, distance) in [("speed_limit_start_m", pit_lane.speed_limit_start_m), ("speed_limit_end_m", pit_lane.speed_limit_end_m)] {
+                if !(0.0..=pit_lane.length_m()).contains(&distance) {
+                    found.report("pit_lane", None, Some(field), format!("{} m is outside the {:.0} m pit lane", distance, pit_lane.length_m()));
+                }
+            }
+            for (i, stall) in pit_lane.pit_stalls.iter().enumerate() {
+                let nearest = pit_lane.centerline.iter()
+                    .map(|p| ((p.x - stall.x).powi(2) + (p.y - stall.y).powi(2)).sqrt())
+                    .fold(f32::INFINITY, f32::min);
+                if nearest > PIT_BOX_MAX_DISTANCE_M {
+                    found.report("pit_lane", None, Some(&format!("boxes[{}]", i)), format!(
+                        "The box is {:.0} m from the pit lane; it should be within {:.0} m",
+                        nearest, PIT_BOX_MAX_DISTANCE_M
+                    ));
+                }
+            }
+        }
+
+        if let Some(timing) = &track.timing {
+            let on_track = |point: &TimingPoint| {
+                let (index, distance) = nearest_centerline_point(&centerline, point.x, point.y);
+                let half_width = centerline[index].width_left_m.max(centerline[index].width_right_m);
+                (distance <= half_width).then_some(index).ok_or(distance)
+            };
+            let mut check = |field: String, point: &TimingPoint| match on_track(point) {
+                Ok(index) => Some(index),
+                Err(distance) => {
+                    found.report("timing", None, Some(&field), format!("{:.1} m from the centerline, off the track", distance));
+                    None
+                }
+            };
+
+            let start = match &timing.start_finish {
+                Some(point) => check("start_finish".to_string(), point),
+                None => Some(0),
+            };
+            let splits: Vec<Option<usize>> = timing.sectors.iter().enumerate()
+                .map(|(i, point)| check(format!("sectors[{}]", i), point))
+                .collect();
+            for (i, trap) in timing.speed_traps.iter().enumerate() {
+                check(format!("speed_traps[{}]", i), &TimingPoint { x: trap.x, y: trap.y });
+            }
+            for (i, zone) in timing.drs_zones.iter().enumerate() {
+                check(format!("drs_zones[{}].start", i), &zone.start);
+                check(format!("drs_zones[{}].end", i), &zone.end);
+            }
+
+            if timing.start_finish.is_some() && !track.closed_loop {
+                found.report("timing", None, Some("start_finish"), "Only closed loops can move the start/finish line".to_string());
+            } else if let Some(start) = start {
+                let rebased = |index: usize| (index + centerline.len() - start) % centerline.len();
+                let mut previous = 0;
+                for (i, index) in splits.iter().enumerate() {
+                    let Some(index) = index.map(rebased) else {
+                        continue;
+                    };
+                    if index <= previous {
+                        found.report("timing", None, Some(&format!("sectors[{}]", i)), format!(
+                            "{} lap order: each split must come after the start/finish line and the split before it",
+                            if index == 0 { "On the start/finish line, out of" } else { "Out of" }
+                        ));
+                    }
+                    previous = previous.max(index);
+                }
             }
         }
 
-        Ok(())
+        if found.found.is_empty() {
+            Ok(centerline)
+        } else {
+            Err(found.found)
+        }
     }
 
-    fn build_track_config(track_file: TrackFileFormat, track_path: Option<&Path>) -> Result<TrackConfig, TrackLoadError> {
+    /// Index of the centerline point on the start/finish line
+    pub(crate) fn start_line_index(track_file: &TrackFileFormat, centerline: &[TrackPoint]) -> usize {
+        match track_file.timing.as_ref().and_then(|timing| timing.start_finish) {
+            Some(point) if track_file.closed_loop => nearest_centerline_point(centerline, point.x, point.y).0,
+            _ => 0,
+        }
+    }
+
+    /// Sector splits, speed traps and DRS zones on a centerline that starts at the start/finish line
+    fn build_timing_lines(track_file: &TrackFileFormat, centerline: &[TrackPoint]) -> TimingLines {
+        let Some(timing) = &track_file.timing else {
+            return TimingLines::default();
+        };
+        let distance = |x: f32, y: f32| centerline[nearest_centerline_point(centerline, x, y).0].distance_from_start_m;
+        TimingLines {
+            sector_splits_m: timing.sectors.iter().map(|point| distance(point.x, point.y)).collect(),
+            speed_traps: timing.speed_traps.iter().enumerate().map(|(i, trap)| SpeedTrap {
+                name: trap.name.clone().unwrap_or_else(|| format!("Speed trap {}", i + 1)),
+                distance_m: distance(trap.x, trap.y),
+            }).collect(),
+            drs_zones: timing.drs_zones.iter().map(|zone| DrsZone {
+                start_m: distance(zone.start.x, zone.start.y),
+                end_m: distance(zone.end.x, zone.end.y),
+            }).collect(),
+        }
+    }
+
+    /// Pit lane geometry from its definition and the track centerline; none
+    /// without at least 2 pit lane nodes. The centerline starts `start` points
+    /// into the interpolated nodes, at the start/finish line
+    pub(crate) fn build_pit_lane(definition: &PitLaneDefinition, centerline: &[TrackPoint], start: usize) -> Option<PitLaneConfig> {
+        let width = if definition.default_width > 0.0 { definition.default_width } else { DEFAULT_PIT_LANE_WIDTH_M };
+        let pit_centerline = SplineInterpolator::interpolate_spline(&definition.nodes, false, width).ok()?;
+        let track_point = |node: usize| {
+            let index = (node * SplineInterpolator::POINTS_PER_SEGMENT).min(centerline.len().saturating_sub(1));
+            centerline.get((index + centerline.len() - start) % centerline.len()).cloned().unwrap_or_default()
+        };
+        let length = pit_centerline.last().map_or(0.0, |p| p.distance_from_start_m);
+
+        let pit_stalls = definition.boxes.iter().enumerate().map(|(i, pit_box)| {
+            let along_lane = || {
+                pit_centerline.iter()
+                    .min_by(|a, b| {
+                        let da = (a.x - pit_box.x).powi(2) + (a.y - pit_box.y).powi(2);
+                        let db = (b.x - pit_box.x).powi(2) + (b.y - pit_box.y).powi(2);
+                        da.total_cmp(&db)
+                    })
+                    .map_or(0.0, |p| p.heading_rad)
+            };
+            PitStall {
+                position: (i + 1).min(u8::MAX as usize) as u8,
+                x: pit_box.x,
+                y: pit_box.y,
+                z: pit_box.z,
+                yaw_rad: pit_box.yaw.unwrap_or_else(along_lane),
+            }
+        }).collect();
+
+        Some(PitLaneConfig {
+            entry_point: track_point(definition.entry_node),
+            exit_point: track_point(definition.exit_node),
+            speed_limit_mps: definition.speed_limit_mps,
+            pit_stalls,
+            speed_limit_start_m: definition.speed_limit_start_m.unwrap_or(0.0),
+            speed_limit_end_m: definition.speed_limit_end_m.unwrap_or(length),
+            centerline: pit_centerline,
+        })
+    }
+
+    fn build_track_config(
+        track_file: TrackFileFormat,
+        mut centerline_points: Vec<TrackPoint>,
+        track_path: Option<&Path>,
+    ) -> Result<TrackConfig, TrackLoadError> {
         let default_width = if track_file.default_width > 0.0 {
             track_file.default_width
         } else {
             12.0
         };
 
-        let mut centerline_points = SplineInterpolator::interpolate_spline(
-            &track_file.nodes,
-            track_file.closed_loop,
-            default_width,
-        )?;
+        // Distances along the track are measured from the start/finish line
+        let start = Self::start_line_index(&track_file, &centerline_points);
+        if start > 0 {
+            centerline_points.rotate_left(start);
+            SplineInterpolator::compute_derived_properties(&mut centerline_points, true);
+        }
+        let timing = Self::build_timing_lines(&track_file, &centerline_points);
 
-        let metadata = track_file.metadata.clone().unwrap_or_default();
+        let mut metadata = track_file.metadata.clone().unwrap_or_default();
+        if metadata.corners.is_none() {
+            metadata.corners = Some(find_corners(&centerline_points).len() as u32);
+        }
 
         // Load or generate procedural world if metadata specifies it
         let procedural_world = Self::load_or_generate_procedural_world(
@@ -163,24 +678,32 @@ impl TrackLoader {
             track_path,
         );
 
-        let start_positions = Self::generate_start_positions(&track_file, &centerline_points);
+        let start_positions = Self::generate_start_positions(&track_file, &centerline_points, start);
 
         // Use track_id from file if provided, otherwise generate new UUID
         let track_id = if let Some(track_id_str) = &track_file.track_id {
-            uuid::Uuid::parse_str(track_id_str)
+            track_id_str
+                .parse::<TrackConfigId>()
                 .map_err(|e| TrackLoadError::InvalidData(format!("Invalid track_id format: {}", e)))?
         } else {
-            uuid::Uuid::new_v4()
+            TrackConfigId::new_v4()
         };
 
-        // Convert raceline points to the data structure
-        let raceline = track_file.raceline.into_iter().map(|rl| {
-            crate::data::RacelinePoint {
-                x: rl.x,
-                y: rl.y,
-                z: rl.z,
-            }
-        }).collect();
+        let walls = Self::build_walls(&track_file, &centerline_points);
+        let pit_lane = track_file.pit_lane.as_ref().and_then(|definition| Self::build_pit_lane(definition, &centerline_points, start));
+
+        // Tracks without a raceline get a generated one; either way every point gets a target speed
+        let mut raceline = track_file.raceline;
+        if raceline.is_empty() {
+            raceline = Self::load_or_generate_raceline(&centerline_points, track_file.closed_loop, track_path);
+        } else if raceline.iter().all(|point| point.target_speed_mps <= 0.0) {
+            raceline::assign_target_speeds(&mut raceline, track_file.closed_loop);
+        }
+
+        let ai_hints = match track_path {
+            Some(path) => Self::load_ai_hints(path, &centerline_points)?,
+            None => AiHints::default(),
+        };
 
         Ok(TrackConfig {
             id: track_id,
@@ -188,6 +711,7 @@ impl TrackLoader {
             centerline: centerline_points,
             width_m: default_width,
             source_path: None,
+            content_hash: String::new(),
             start_positions,
             track_surface: TrackSurface {
                 base_grip: 1.0,
@@ -195,16 +719,107 @@ impl TrackLoader {
                 off_track_grip: 0.6,
                 off_track_speed_penalty: 0.8,
             },
-            pit_lane: None,
+            pit_lane,
             raceline,
             metadata,
             procedural_world,
+            walls,
+            ai_hints,
+            timing,
         })
     }
 
+    /// Boundary walls from the centerline and widths, plus the file's own walls
+    fn build_walls(track_file: &TrackFileFormat, centerline_points: &[TrackPoint]) -> Vec<WallSegment> {
+        let runoff_width = track_file.runoff_width.unwrap_or(DEFAULT_RUNOFF_WIDTH_M);
+        let mut walls = if track_file.boundary_walls.unwrap_or(true) {
+            WallSegment::boundary(centerline_points, runoff_width, track_file.closed_loop)
+        } else {
+            Vec::new()
+        };
+
+        for wall in &track_file.walls {
+            let points: Vec<(f32, f32)> = wall.points.iter().map(|p| (p.x, p.y)).collect();
+            walls.extend(WallSegment::polyline(&points, wall.closed));
+        }
+
+        walls
+    }
+
+    /// AI hint file for a track file: `Monza.yaml` -> `Monza.ai.yaml`
+    pub fn ai_hints_path(track_file: &Path) -> PathBuf {
+        let stem = track_file.file_stem().and_then(|s| s.to_str()).unwrap_or("track");
+        track_file.with_file_name(format!("{}{}", stem, AI_HINTS_SUFFIX))
+    }
+
+    /// Whether a file in the tracks folder holds AI hints rather than a track
+    pub fn is_ai_hints_file(path: &Path) -> bool {
+        path.file_name()
+            .and_then(|s| s.to_str())
+            .is_some_and(|name| name.ends_with(AI_HINTS_SUFFIX))
+    }
+
+    /// Whether a file in the tracks folder sits next to a track rather than
+    /// being one: its AI hints or its world manifest
+    pub fn is_sidecar_file(path: &Path) -> bool {
+        Self::is_ai_hints_file(path) || manifest::is_manifest_file(path)
+    }
+
+    /// Hints from the track's AI hint file, or none if it has no such file
+    fn load_ai_hints(track_file: &Path, centerline_points: &[TrackPoint]) -> Result<AiHints, TrackLoadError> {
+        let hints_path = Self::ai_hints_path(track_file);
+        if !hints_path.exists() {
+            return Ok(AiHints::default());
+        }
+
+        let content = fs::read_to_string(&hints_path)?;
+        let hints: AiHints = serde_yaml::from_str(&content).map_err(|e| {
+            TrackLoadError::ParseError(format!("AI hints {}: {}", hints_path.display(), e))
+        })?;
+
+        let track_length = centerline_points.last().map(|p| p.distance_from_start_m).unwrap_or(0.0);
+        let in_range = |distance: f32| (0.0..=track_length).contains(&distance);
+        let markers_valid = hints
+            .braking_markers
+            .iter()
+            .all(|m| in_range(m.distance_m) && m.speed_mps > 0.0 && m.hold_m >= 0.0);
+        let zones_valid = hints.overtaking_zones.iter().all(|z| in_range(z.start_m) && in_range(z.end_m));
+        let caution_valid = hints
+            .pit_entry_caution
+            .iter()
+            .all(|z| in_range(z.start_m) && in_range(z.end_m) && z.speed_mps > 0.0);
+        if !(markers_valid && zones_valid && caution_valid) {
+            return Err(TrackLoadError::InvalidData(format!(
+                "AI hints {}: distances must lie within the {:.0} m lap and speeds must be positive",
+                hints_path.display(),
+                track_length
+            )));
+        }
+
+        Ok(hints)
+    }
+
+    fn load_or_generate_raceline(
+        centerline_points: &[TrackPoint],
+        closed_loop: bool,
+        track_path: Option<&Path>,
+    ) -> Vec<RacelinePoint> {
+        if let Some(cached) = track_path.and_then(raceline::load_cache) {
+            return cached;
+        }
+
+        let generated = raceline::generate(centerline_points, closed_loop);
+        if let Some(path) = track_path {
+            if let Err(e) = raceline::save_cache(path, &generated) {
+                eprintln!("⚠️  {} for {}", e, path.display());
+            }
+        }
+        generated
+    }
+
     fn load_or_generate_procedural_world(
         track_name: &str,
-        centerline_points: &mut Vec<TrackPoint>,
+        centerline_points: &mut [TrackPoint],
         metadata: &TrackMetadata,
         track_path: Option<&Path>,
     ) -> Option<crate::procgen::ProceduralWorldData> {
@@ -233,53 +848,37 @@ impl TrackLoader {
         None
     }
 
+    /// `track_dir` is where a `terrain_dem` path is relative to
     pub fn generate_procedural_world_for_track(
         track_name: &str,
-        centerline_points: &mut Vec<TrackPoint>,
-        metadata: &TrackMetadata,
+        centerline_points: &mut [TrackPoint],
+        params: &WorldParams,
+        track_dir: Option<&Path>,
     ) -> Option<crate::procgen::ProceduralWorldData> {
         // This function is called during --generate-terrain mode
-        let environment_type = metadata.environment_type.as_ref()?;
-
         println!("🌍 Generating procedural world for track: {}", track_name);
-        println!("   Environment type: {}", environment_type);
-
-        // Get or create seed
-        let seed = metadata.terrain_seed.unwrap_or_else(|| {
-            // Generate deterministic seed from track name
-            use std::collections::hash_map::DefaultHasher;
-            use std::hash::{Hash, Hasher};
-            let mut hasher = DefaultHasher::new();
-            track_name.hash(&mut hasher);
-            hasher.finish() as u32
-        });
-
-        // Get environment preset
-        let preset = match crate::procgen::environment_presets::get_preset(environment_type) {
-            Some(p) => p,
-            None => {
-                eprintln!("❌ Unknown environment type: {}", environment_type);
-                return None;
+        println!("   Environment type: {}", params.environment_type);
+        println!("   Seed: {}", params.seed);
+
+        // Real elevation data replaces the noise when the track names some
+        let mut dem = match &params.terrain_dem {
+            Some(dem) => {
+                let path = track_dir.map(|dir| dir.join(dem)).unwrap_or_else(|| PathBuf::from(dem));
+                println!("   Elevation data: {}", path.display());
+                match crate::procgen::dem::open(&path) {
+                    Ok(model) => Some(model),
+                    Err(e) => {
+                        eprintln!("❌ Failed to read elevation data: {}", e);
+                        return None;
+                    }
+                }
             }
+            None => None,
         };
 
-        // Get parameters with defaults
-        let terrain_scale = metadata.terrain_scale.unwrap_or(1.0);
-        let blend_width = metadata.terrain_blend_width.unwrap_or(20.0);
-        let object_density = metadata.object_density.unwrap_or(0.8);
-        let decal_profile = metadata.decal_profile.clone().unwrap_or_else(|| "default".to_string());
-
         // Generate procedural world
-        match crate::procgen::terrain::generate_procedural_world(
-            centerline_points,
-            environment_type.clone(),
-            seed,
-            preset.clone(),
-            terrain_scale,
-            blend_width,
-            object_density,
-            decal_profile,
-        ) {
+        let elevation = dem.as_mut().map(|model| -> &mut dyn ElevationModel { model.as_mut() });
+        match crate::procgen::terrain::generate_procedural_world(centerline_points, params, elevation) {
             Ok(world_data) => {
                 // Apply elevation to track points
                 if let Some(ref heightmap) = world_data.heightmap {
@@ -297,9 +896,12 @@ impl TrackLoader {
         }
     }
 
+    /// Spawn points index the centerline as interpolated, before it was
+    /// moved `start` points on to begin at the start/finish line
     fn generate_start_positions(
         track_file: &TrackFileFormat,
         centerline: &[TrackPoint],
+        start: usize,
     ) -> Vec<GridSlot> {
         if !track_file.spawn_points.is_empty() {
             track_file
@@ -308,7 +910,7 @@ impl TrackLoader {
                 .enumerate()
                 .filter_map(|(idx, spawn)| {
                     if spawn.position < centerline.len() {
-                        let point = &centerline[spawn.position];
+                        let point = &centerline[(spawn.position + centerline.len() - start) % centerline.len()];
                         Some(GridSlot {
                             position: idx as u8 + 1,
                             x: point.x + spawn.offset_x,
@@ -353,9 +955,104 @@ impl TrackLoader {
     }
 }
 
+/// Index of the centerline point nearest (x, y), and how far away it is
+fn nearest_centerline_point(centerline: &[TrackPoint], x: f32, y: f32) -> (usize, f32) {
+    centerline
+        .iter()
+        .map(|p| ((p.x - x).powi(2) + (p.y - y).powi(2)).sqrt())
+        .enumerate()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap_or((0, f32::INFINITY))
+}
+
+/// Length of a polyline, back to its start when `closed`
+fn path_length(mut points: impl Iterator<Item = (f32, f32, f32)>, closed: bool) -> f32 {
+    let distance = |a: (f32, f32, f32), b: (f32, f32, f32)| ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2) + (b.2 - a.2).powi(2)).sqrt();
+    let Some(first) = points.next() else {
+        return 0.0;
+    };
+    let (length, last) = points.fold((0.0, first), |(length, previous), point| (length + distance(previous, point), point));
+    if closed { length + distance(last, first) } else { length }
+}
+
+/// Diagnostics gathered while checking a track file
+struct Diagnostics<'a> {
+    lines: FieldLines<'a>,
+    found: Vec<TrackDiagnostic>,
+}
+
+impl Diagnostics<'_> {
+    /// Report a problem with `section`, its `index`th item, or `field` of that item
+    fn report(&mut self, section: &str, index: Option<usize>, field: Option<&str>, message: String) {
+        let mut path = section.to_string();
+        if let Some(index) = index {
+            path.push_str(&format!("[{}]", index));
+        }
+        if let Some(field) = field {
+            path.push('.');
+            path.push_str(field);
+        }
+        let line = self.lines.find(section, index, field);
+        self.found.push(TrackDiagnostic { field: path, line, message });
+    }
+}
+
+/// Finds the lines of fields in a block-style YAML track file. JSON and
+/// flow-style YAML get no line numbers
+struct FieldLines<'a> {
+    content: &'a str,
+    /// Split on the first lookup; most files have nothing to report
+    lines: std::cell::OnceCell<Vec<&'a str>>,
+}
+
+impl<'a> FieldLines<'a> {
+    fn new(content: &'a str) -> Self {
+        let is_json = content.trim_start().starts_with('{');
+        Self { content: if is_json { "" } else { content }, lines: std::cell::OnceCell::new() }
+    }
+
+    /// 1-based line of a top-level `section`, its `index`th list item, or
+    /// `field` within that item; the item's line when the field is missing
+    fn find(&self, section: &str, index: Option<usize>, field: Option<&str>) -> Option<usize> {
+        let lines = self.lines.get_or_init(|| self.content.lines().collect());
+        let is_top_level = |line: &str| !line.is_empty() && !line.starts_with([' ', '\t', '-', '#']);
+        let key = format!("{}:", section);
+        let start = lines.iter().position(|line| line.starts_with(&key))?;
+        let end = lines[start + 1..]
+            .iter()
+            .position(|line| is_top_level(line))
+            .map_or(lines.len(), |offset| start + 1 + offset);
+        let Some(index) = index else {
+            return Some(start + 1);
+        };
+
+        let indent = |line: &str| line.len() - line.trim_start().len();
+        let is_item = |line: &str| line.trim_start().starts_with('-');
+        let item_indent = lines[start + 1..end].iter().find(|line| is_item(line)).map(|line| indent(line))?;
+        let items: Vec<usize> = (start + 1..end)
+            .filter(|&i| is_item(lines[i]) && indent(lines[i]) == item_indent)
+            .collect();
+        let item = *items.get(index)?;
+        let item_end = items.get(index + 1).copied().unwrap_or(end);
+
+        let Some(field) = field else {
+            return Some(item + 1);
+        };
+        let key = format!("{}:", field);
+        let field_line = (item..item_end).find(|&i| {
+            let line = lines[i].trim_start();
+            line.strip_prefix('-').unwrap_or(line).trim_start().starts_with(&key)
+        });
+        Some(field_line.unwrap_or(item) + 1)
+    }
+}
+
 pub struct SplineInterpolator;
 
 impl SplineInterpolator {
+    /// Centerline points generated between each pair of nodes
+    pub const POINTS_PER_SEGMENT: usize = 20;
+
     pub fn interpolate_spline(
         nodes: &[TrackNode],
         closed_loop: bool,
@@ -368,7 +1065,7 @@ impl SplineInterpolator {
         }
 
         let mut track_points = Vec::new();
-        let points_per_segment = 20;
+        let points_per_segment = Self::POINTS_PER_SEGMENT;
 
         for i in 0..nodes.len() {
             let p0_idx = if i == 0 && closed_loop {
@@ -412,6 +1109,10 @@ impl SplineInterpolator {
                 let friction = p1.friction.unwrap_or(1.0);
 
                 let surface_type = Self::parse_surface_type(p1.surface_type.as_deref());
+                let runoff_surface = match p1.runoff_surface.as_deref() {
+                    Some(runoff) => Self::parse_surface_type(Some(runoff)),
+                    None => SurfaceType::Grass,
+                };
 
                 track_points.push(TrackPoint {
                     x: point.0,
@@ -421,11 +1122,12 @@ impl SplineInterpolator {
                     width_left_m: width_left,
                     width_right_m: width_right,
                     banking_rad: banking,
-                    camber_rad: 0.0,
+                    camber_rad: p1.camber.unwrap_or(0.0),
                     slope_rad: 0.0,
                     heading_rad: 0.0,
                     surface_type,
                     grip_modifier: friction,
+                    runoff_surface,
                 });
             }
         }
@@ -585,13 +1287,14 @@ nodes:
             "default_width": 12.0,
             "closed_loop": false,
             "nodes": [
-                {"x": 0.0, "y": 0.0, "z": 0.0, "banking": 0.0, "friction": 1.0},
+                {"x": 0.0, "y": 0.0, "z": 0.0, "banking": 0.0, "camber": 0.02, "friction": 1.0},
                 {"x": 100.0, "y": 0.0, "z": 5.0, "banking": 0.1, "friction": 0.9}
             ]
         }"#;
 
         let track = TrackLoader::load_from_string(json).unwrap();
         assert!(track.centerline.len() >= 2);
+        assert_eq!(track.centerline[0].camber_rad, 0.02);
     }
 
     #[test]
@@ -608,6 +1311,261 @@ nodes:
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_validate_points_at_the_field_and_line() {
+        let yaml = r#"name: "Broken"
+default_width: 10.0
+closed_loop: true
+nodes:
+  - x: 0.0
+    y: 0.0
+  - x: 100.0
+    y: 0.0
+    width_left: 80.0
+    width_right: 5.0
+  - x: 100.0
+    y: 0.0
+  - x: 100.0
+    y: 100.0
+    width_left: 5.0
+checkpoints:
+  - index_start: 0
+    index_end: 9
+"#;
+
+        let problems = TrackLoader::validate(yaml);
+        let located: Vec<(&str, Option<usize>)> = problems.iter().map(|p| (p.field.as_str(), p.line)).collect();
+        assert_eq!(located, vec![
+            ("nodes[1].width_left", Some(9)),
+            ("nodes[3].width_left", Some(15)),
+            ("nodes[2]", Some(11)),
+            ("checkpoints[0].index_end", Some(18)),
+        ]);
+        assert!(problems[2].to_string().starts_with("line 11: nodes[2]: Same place as nodes[1]"), "{}", problems[2]);
+
+        match TrackLoader::load_from_string(yaml) {
+            Err(TrackLoadError::Invalid(load_problems)) => assert_eq!(load_problems, problems),
+            other => panic!("Expected the validation problems, got {:?}", other.map(|track| track.name)),
+        }
+
+        let unparsable = TrackLoader::validate("name: Broken\nnodes: 3\n");
+        assert_eq!(unparsable.len(), 1);
+        assert_eq!(unparsable[0].line, Some(2));
+    }
+
+    #[test]
+    fn test_validate_checks_loop_raceline_and_spawn_points() {
+        let square = |extra: &str| format!(r#"name: "Square"
+default_width: 10.0
+closed_loop: true
+nodes:
+  - {{x: 0.0, y: 0.0}}
+  - {{x: 100.0, y: 0.0}}
+  - {{x: 100.0, y: 100.0}}
+  - {{x: 0.0, y: 100.0}}
+{}"#, extra);
+        assert_eq!(TrackLoader::validate(&square("")), vec![]);
+
+        let fields = |extra: &str| -> Vec<String> {
+            TrackLoader::validate(&square(extra)).into_iter().map(|p| p.field).collect()
+        };
+        assert_eq!(fields("raceline:\n  - {x: 0.0, y: 0.0, z: 0.0}\n  - {x: 10.0, y: 0.0, z: 0.0}\n"), vec!["raceline"]);
+        assert_eq!(
+            fields("spawn_points:\n  - position: 500\n  - position: 0\n    offset_y: 3.0\n  - position: 0\n    offset_y: -20.0\n"),
+            vec!["spawn_points[0].position", "spawn_points[2]"]
+        );
+
+        let open_loop = r#"name: "Open"
+default_width: 10.0
+closed_loop: true
+nodes:
+  - {x: 0.0, y: 0.0}
+  - {x: 10.0, y: 0.0}
+  - {x: 20.0, y: 0.0}
+  - {x: 30.0, y: 0.0}
+  - {x: 40.0, y: 0.0}
+"#;
+        let problems = TrackLoader::validate(open_loop);
+        assert_eq!(problems.len(), 1);
+        assert_eq!((problems[0].field.as_str(), problems[0].line), ("closed_loop", Some(3)));
+    }
+
+    #[test]
+    fn test_pit_lane_is_loaded_and_checked() {
+        let track_with = |pit_lane: &str| format!(r#"name: "Pit Square"
+default_width: 10.0
+closed_loop: true
+nodes:
+  - {{x: 0.0, y: 0.0}}
+  - {{x: 100.0, y: 0.0}}
+  - {{x: 100.0, y: 100.0}}
+  - {{x: 0.0, y: 100.0}}
+pit_lane:
+  entry_node: 0
+  exit_node: 1
+  speed_limit_mps: 16.0
+  nodes:
+    - {{x: 10.0, y: -15.0}}
+    - {{x: 50.0, y: -15.0}}
+    - {{x: 90.0, y: -15.0}}
+{}"#, pit_lane);
+
+        let track = TrackLoader::load_from_string(&track_with("  boxes:\n    - {x: 30.0, y: -22.0}\n    - {x: 40.0, y: -22.0, yaw: 1.0}\n")).unwrap();
+        let pit_lane = track.pit_lane.expect("The track has a pit lane");
+        assert_eq!((pit_lane.entry_point.x, pit_lane.exit_point.x), (0.0, 100.0));
+        assert_eq!(pit_lane.speed_limit_mps, 16.0);
+        assert!(pit_lane.length_m() > 70.0);
+        assert_eq!((pit_lane.speed_limit_start_m, pit_lane.speed_limit_end_m), (0.0, pit_lane.length_m()));
+        let stalls: Vec<(u8, f32)> = pit_lane.pit_stalls.iter().map(|stall| (stall.position, stall.yaw_rad)).collect();
+        assert_eq!(stalls, vec![(1, 0.0), (2, 1.0)], "Boxes follow the grid; yaw defaults to the pit lane's");
+        assert_eq!(pit_lane.locate(30.0, -16.0).map(|d| d.round()), Some(20.0));
+        assert_eq!(pit_lane.locate(30.0, 0.0), None, "The track is not the pit lane");
+
+        let problems = TrackLoader::validate(&track_with("  speed_limit_end_m: 500.0\n  boxes:\n    - {x: 30.0, y: -80.0}\n"));
+        let fields: Vec<&str> = problems.iter().map(|p| p.field.as_str()).collect();
+        assert_eq!(fields, vec!["pit_lane.speed_limit_end_m", "pit_lane.boxes[0]"]);
+        assert_eq!(problems[0].line, Some(9));
+
+        let problems = TrackLoader::validate(&track_with("").replace("exit_node: 1", "exit_node: 7"));
+        assert_eq!(problems.iter().map(|p| p.field.as_str()).collect::<Vec<_>>(), vec!["pit_lane.exit_node"]);
+    }
+
+    /// Counter-clockwise circle of radius 200 m with a node every 10 degrees
+    fn circle_track(closed_loop: bool, timing: &str) -> String {
+        let nodes: String = (0..36)
+            .map(|i| {
+                let angle = (i as f32 * 10.0).to_radians();
+                format!("  - {{x: {:.3}, y: {:.3}}}\n", 200.0 * angle.cos(), 200.0 * angle.sin())
+            })
+            .collect();
+        format!("name: Circle\ndefault_width: 12.0\nclosed_loop: {}\nnodes:\n{}{}", closed_loop, nodes, timing)
+    }
+
+    #[test]
+    fn test_corners_are_counted_unless_given() {
+        // 600 x 200 m rectangle with a node every 50 m, so the straights stay
+        // straight, starting halfway along one as a lap does
+        let mut nodes = String::new();
+        for (from, to) in [((300.0_f32, 0.0_f32), (600.0, 0.0)), ((600.0, 0.0), (600.0, 200.0)), ((600.0, 200.0), (0.0, 200.0)), ((0.0, 200.0), (0.0, 0.0)), ((0.0, 0.0), (300.0, 0.0))] {
+            let steps = (to.0 - from.0).hypot(to.1 - from.1) as usize / 50;
+            for i in 0..steps {
+                let t = i as f32 / steps as f32;
+                nodes.push_str(&format!("  - {{x: {}, y: {}}}\n", from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t));
+            }
+        }
+        let rectangle = format!("name: Rectangle\ndefault_width: 12.0\nclosed_loop: true\nnodes:\n{}", nodes);
+        let track = TrackLoader::load_from_string(&rectangle).unwrap();
+        assert_eq!(track.metadata.corners, Some(4));
+
+        // A ring is one long corner
+        let ring = TrackLoader::load_from_string(&circle_track(true, "")).unwrap();
+        assert_eq!(ring.metadata.corners, Some(1));
+
+        let given = format!("{}metadata:\n  corners: 7\n  tags: [street]\n", rectangle);
+        let track = TrackLoader::load_from_string(&given).unwrap();
+        assert_eq!(track.metadata.corners, Some(7));
+        assert_eq!(track.metadata.tags, vec!["street".to_string()]);
+    }
+
+    #[test]
+    fn test_timing_lines_are_measured_from_the_start_line() {
+        let timing = "timing:
+  start_finish: {x: 0.0, y: 200.0}
+  sectors:
+    - {x: -200.0, y: 0.0}
+    - {x: 0.0, y: -200.0}
+  speed_traps:
+    - {x: 141.4, y: 141.4}
+  drs_zones:
+    - start: {x: 200.0, y: 0.0}
+      end: {x: -141.4, y: 141.4}
+";
+        let track = TrackLoader::load_from_string(&circle_track(true, timing)).unwrap();
+        let start = &track.centerline[0];
+        assert!(start.x.abs() < 1.0 && (start.y - 200.0).abs() < 1.0, "The lap starts at the line");
+        let slot = &track.start_positions[0];
+        assert!((slot.x.hypot(slot.y - 200.0)) < 10.0, "The grid forms behind the line");
+
+        let lap = track.centerline.last().unwrap().distance_from_start_m;
+        let fractions: Vec<f32> = track.timing.sector_splits_m.iter().map(|d| (d / lap * 100.0).round() / 100.0).collect();
+        assert_eq!(fractions, vec![0.25, 0.5]);
+        assert_eq!(track.timing.speed_traps[0].name, "Speed trap 1");
+        assert!((track.timing.speed_traps[0].distance_m / lap - 0.875).abs() < 0.01);
+        let zone = &track.timing.drs_zones[0];
+        assert!((zone.start_m / lap - 0.75).abs() < 0.01, "{:?}", zone);
+        assert!((zone.end_m / lap - 0.125).abs() < 0.01, "The zone runs across the line: {:?}", zone);
+        assert_eq!(
+            [0.1, 0.3, 0.9].map(|fraction| track.timing.sector_at(lap * fraction)),
+            [1, 2, 3]
+        );
+
+        let backwards = timing.replace("{x: -200.0, y: 0.0}", "{x: 0.0, y: -201.0}").replacen("{x: 0.0, y: -200.0}", "{x: -200.0, y: 0.0}", 1);
+        let problems = TrackLoader::validate(&circle_track(true, &backwards));
+        assert_eq!(problems.iter().map(|p| p.field.as_str()).collect::<Vec<_>>(), vec!["timing.sectors[1]"]);
+
+        let off_track = timing.replace("{x: 141.4, y: 141.4}", "{x: 0.0, y: 0.0}").replace("{x: 200.0, y: 0.0}", "{x: 250.0, y: 0.0}");
+        let problems = TrackLoader::validate(&circle_track(true, &off_track));
+        assert_eq!(
+            problems.iter().map(|p| p.field.as_str()).collect::<Vec<_>>(),
+            vec!["timing.speed_traps[0]", "timing.drs_zones[0].start"]
+        );
+        assert!(problems[0].message.contains("off the track"), "{}", problems[0]);
+
+        let problems = TrackLoader::validate(&circle_track(false, timing));
+        assert_eq!(problems.iter().map(|p| p.field.as_str()).collect::<Vec<_>>(), vec!["timing.start_finish"]);
+    }
+
+    #[test]
+    fn test_ai_hints_are_loaded_from_next_to_the_track_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let track_path = dir.path().join("Hinted.yaml");
+        fs::write(
+            &track_path,
+            r#"
+name: "Hinted"
+default_width: 10.0
+closed_loop: true
+nodes:
+  - { x: 0.0, y: 0.0 }
+  - { x: 100.0, y: 0.0 }
+  - { x: 100.0, y: 100.0 }
+  - { x: 0.0, y: 100.0 }
+"#,
+        )
+        .unwrap();
+
+        let track = TrackLoader::load_from_file(&track_path).unwrap();
+        assert_eq!(track.ai_hints, AiHints::default());
+
+        let hints_path = TrackLoader::ai_hints_path(&track_path);
+        assert!(TrackLoader::is_ai_hints_file(&hints_path));
+        assert!(!TrackLoader::is_ai_hints_file(&track_path));
+        fs::write(
+            &hints_path,
+            r#"
+braking_markers:
+  - { distance_m: 90.0, speed_mps: 20.0, hold_m: 15.0 }
+overtaking_zones:
+  - { start_m: 10.0, end_m: 80.0 }
+pit_entry_caution:
+  - { start_m: 300.0, end_m: 20.0, speed_mps: 25.0 }
+"#,
+        )
+        .unwrap();
+
+        let track = TrackLoader::load_from_file(&track_path).unwrap();
+        assert_eq!(track.ai_hints.braking_markers.len(), 1);
+        assert_eq!(track.ai_hints.braking_markers[0].hold_m, 15.0);
+        assert_eq!(track.ai_hints.overtaking_zones.len(), 1);
+        assert_eq!(track.ai_hints.pit_entry_caution[0].speed_mps, 25.0);
+
+        fs::write(&hints_path, "braking_markers:\n  - { distance_m: 99999.0, speed_mps: 20.0 }\n").unwrap();
+        assert!(matches!(
+            TrackLoader::load_from_file(&track_path),
+            Err(TrackLoadError::InvalidData(_))
+        ));
+    }
+
     #[test]
     fn test_catmull_rom_interpolation() {
         let nodes = vec![
@@ -619,8 +1577,10 @@ nodes:
                 width_left: None,
                 width_right: None,
                 banking: None,
+                camber: None,
                 friction: None,
                 surface_type: None,
+                runoff_surface: None,
             },
             TrackNode {
                 x: 10.0,
@@ -630,8 +1590,10 @@ nodes:
                 width_left: None,
                 width_right: None,
                 banking: None,
+                camber: None,
                 friction: None,
                 surface_type: None,
+                runoff_surface: None,
             },
             TrackNode {
                 x: 20.0,
@@ -641,8 +1603,10 @@ nodes:
                 width_left: None,
                 width_right: None,
                 banking: None,
+                camber: None,
                 friction: None,
                 surface_type: None,
+                runoff_surface: None,
             },
         ];
 
@@ -663,8 +1627,10 @@ nodes:
                 width_left: None,
                 width_right: None,
                 banking: None,
+                camber: None,
                 friction: None,
                 surface_type: None,
+                runoff_surface: None,
             },
             TrackNode {
                 x: 10.0,
@@ -674,12 +1640,50 @@ nodes:
                 width_left: None,
                 width_right: None,
                 banking: None,
+                camber: None,
                 friction: None,
                 surface_type: None,
+                runoff_surface: None,
             },
         ];
 
         let points = SplineInterpolator::interpolate_spline(&nodes, false, 10.0).unwrap();
         assert!(points[0].heading_rad.abs() < 0.1);
     }
+
+    #[test]
+    fn test_boundary_walls_never_cross_the_track() {
+        // Hairpin far tighter than the 10 m + 15 m wall offset
+        let yaml = r#"
+name: Hairpin
+default_width: 20.0
+closed_loop: false
+nodes:
+  - { x: 0.0, y: 0.0 }
+  - { x: 200.0, y: 0.0 }
+  - { x: 215.0, y: 15.0 }
+  - { x: 200.0, y: 30.0 }
+  - { x: 0.0, y: 30.0 }
+walls:
+  - points: [{ x: -20.0, y: -40.0 }, { x: -20.0, y: 70.0 }]
+"#;
+
+        let track = TrackLoader::load_from_string(yaml).unwrap();
+        assert!(track.walls.len() > 10);
+        for wall in &track.walls {
+            for (x, y) in [(wall.x1, wall.y1), (wall.x2, wall.y2)] {
+                let clearance = track
+                    .centerline
+                    .iter()
+                    .map(|p| (p.x - x).hypot(p.y - y))
+                    .fold(f32::MAX, f32::min);
+                assert!(clearance > 10.0, "Wall point ({}, {}) is on the track", x, y);
+            }
+        }
+        assert!(track.walls.contains(&WallSegment { x1: -20.0, y1: -40.0, x2: -20.0, y2: 70.0 }));
+
+        let without_boundary = yaml.replace("closed_loop: false", "closed_loop: false\nboundary_walls: false");
+        let track = TrackLoader::load_from_string(&without_boundary).unwrap();
+        assert_eq!(track.walls.len(), 1);
+    }
 }
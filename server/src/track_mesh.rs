@@ -215,19 +215,19 @@ impl TrackMeshGenerator {
             obj.push_str(&format!("v {} {} {}\n", vertex.x, vertex.y, vertex.z));
         }
 
-        obj.push_str("\n");
+        obj.push('\n');
 
         for uv in &mesh.uvs {
             obj.push_str(&format!("vt {} {}\n", uv.u, uv.v));
         }
 
-        obj.push_str("\n");
+        obj.push('\n');
 
         for normal in &mesh.normals {
             obj.push_str(&format!("vn {} {} {}\n", normal.x, normal.y, normal.z));
         }
 
-        obj.push_str("\n");
+        obj.push('\n');
 
         for triangle_idx in (0..mesh.indices.len()).step_by(3) {
             let i0 = mesh.indices[triangle_idx] + 1;
@@ -356,6 +356,7 @@ mod tests {
                 heading_rad: 0.0,
                 surface_type: SurfaceType::Asphalt,
                 grip_modifier: 1.0,
+                runoff_surface: SurfaceType::Grass,
             },
             TrackPoint {
                 x: 100.0,
@@ -370,6 +371,7 @@ mod tests {
                 heading_rad: 0.0,
                 surface_type: SurfaceType::Asphalt,
                 grip_modifier: 1.0,
+                runoff_surface: SurfaceType::Grass,
             },
         ];
 
@@ -397,6 +399,7 @@ mod tests {
                 heading_rad: 0.0,
                 surface_type: SurfaceType::Asphalt,
                 grip_modifier: 1.0,
+                runoff_surface: SurfaceType::Grass,
             },
             TrackPoint {
                 x: 100.0,
@@ -411,6 +414,7 @@ mod tests {
                 heading_rad: 0.0,
                 surface_type: SurfaceType::Asphalt,
                 grip_modifier: 1.0,
+                runoff_surface: SurfaceType::Grass,
             },
             TrackPoint {
                 x: 100.0,
@@ -425,6 +429,7 @@ mod tests {
                 heading_rad: std::f32::consts::PI / 2.0,
                 surface_type: SurfaceType::Asphalt,
                 grip_modifier: 1.0,
+                runoff_surface: SurfaceType::Grass,
             },
         ];
 
@@ -450,6 +455,7 @@ mod tests {
                 heading_rad: 0.0,
                 surface_type: SurfaceType::Asphalt,
                 grip_modifier: 1.0,
+                runoff_surface: SurfaceType::Grass,
             },
             TrackPoint {
                 x: 10.0,
@@ -464,6 +470,7 @@ mod tests {
                 heading_rad: 0.0,
                 surface_type: SurfaceType::Asphalt,
                 grip_modifier: 1.0,
+                runoff_surface: SurfaceType::Grass,
             },
         ];
 
@@ -492,6 +499,7 @@ mod tests {
                 heading_rad: 0.0,
                 surface_type: SurfaceType::Asphalt,
                 grip_modifier: 1.0,
+                runoff_surface: SurfaceType::Grass,
             },
             TrackPoint {
                 x: 10.0,
@@ -506,6 +514,7 @@ mod tests {
                 heading_rad: 0.0,
                 surface_type: SurfaceType::Asphalt,
                 grip_modifier: 1.0,
+                runoff_surface: SurfaceType::Grass,
             },
         ];
 
@@ -1,19 +1,21 @@
+use crate::bans::{unix_now, BanList};
 use crate::data::*;
-use crate::network::{AuthSuccessData, ClientMessage, MessagePriority, ServerMessage};
+use crate::network::{AuthSuccessData, ClientMessage, ConnectionStatsData, LobbyUpdate, MessagePriority, ServerMessage};
 use rustls::pki_types::CertificateDer;
 use rustls::ServerConfig as TlsConfig;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::BufReader;
 use std::net::SocketAddr;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use thiserror::Error;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream, UdpSocket};
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc, Notify};
 use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
 use tokio_rustls::TlsAcceptor;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
@@ -24,6 +26,12 @@ const UDP_INBOUND_CHANNEL_SIZE: usize = 2000;
 const UDP_OUTBOUND_CHANNEL_SIZE: usize = 2000;
 const PER_CLIENT_TCP_CHANNEL_SIZE: usize = 100;
 
+/// Default cap on inbound messages per connection per second
+pub const DEFAULT_MAX_MESSAGES_PER_SECOND: u32 = 300;
+
+/// Weight of a new RTT sample in the smoothed RTT
+const RTT_SMOOTHING: f32 = 0.125;
+
 /// Metrics for tracking dropped messages
 #[derive(Debug, Default, Clone)]
 pub struct TransportMetrics {
@@ -51,6 +59,181 @@ impl TransportMetrics {
     }
 }
 
+/// Per-connection protocol counters, reported to the client in `ConnectionStats`
+#[derive(Debug, Default)]
+pub struct ConnectionCounters {
+    pub messages_sent: AtomicU64,
+    pub messages_received: AtomicU64,
+    pub bytes_sent: AtomicU64,
+    pub bytes_received: AtomicU64,
+    /// Droppable messages discarded because the client's queue was full
+    pub messages_dropped: AtomicU64,
+    /// Inbound messages discarded for exceeding the rate limit
+    pub rate_limit_hits: AtomicU64,
+    next_ping_id: AtomicU32,
+    /// Ping id and send time of the last stats message not yet acknowledged
+    pending_ping: Mutex<Option<(u32, Instant)>>,
+    /// Smoothed round-trip time in milliseconds
+    rtt_ms: Mutex<Option<f32>>,
+}
+
+impl ConnectionCounters {
+    pub fn record_sent(&self, bytes: usize) {
+        self.messages_sent.fetch_add(1, Ordering::Relaxed);
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_received(&self, bytes: usize) {
+        self.messages_received.fetch_add(1, Ordering::Relaxed);
+        self.bytes_received.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_dropped(&self) {
+        self.messages_dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_rate_limited(&self) {
+        self.rate_limit_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Complete a ping; acks for anything but the latest ping are ignored
+    pub fn record_stats_ack(&self, ping_id: u32) {
+        let mut pending = self.pending_ping.lock().unwrap();
+        let Some((expected, sent_at)) = *pending else {
+            return;
+        };
+        if expected != ping_id {
+            return;
+        }
+        *pending = None;
+
+        let sample = sent_at.elapsed().as_secs_f32() * 1000.0;
+        let mut rtt = self.rtt_ms.lock().unwrap();
+        *rtt = Some(match *rtt {
+            Some(smoothed) => smoothed + (sample - smoothed) * RTT_SMOOTHING,
+            None => sample,
+        });
+    }
+
+    /// Snapshot the counters into a stats message and start a new ping
+    pub fn snapshot(&self) -> ConnectionStatsData {
+        let ping_id = self.next_ping_id.fetch_add(1, Ordering::Relaxed);
+        *self.pending_ping.lock().unwrap() = Some((ping_id, Instant::now()));
+
+        let messages_sent = self.messages_sent.load(Ordering::Relaxed);
+        let messages_dropped = self.messages_dropped.load(Ordering::Relaxed);
+        let attempted = messages_sent + messages_dropped;
+        let loss_percent = if attempted > 0 {
+            messages_dropped as f32 * 100.0 / attempted as f32
+        } else {
+            0.0
+        };
+
+        ConnectionStatsData {
+            ping_id,
+            messages_sent,
+            messages_received: self.messages_received.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            rtt_ms: *self.rtt_ms.lock().unwrap(),
+            messages_dropped,
+            loss_percent,
+            rate_limit_hits: self.rate_limit_hits.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Per-connection telemetry rate: `Telemetry` frames closer together than
+/// `interval_ticks` are not sent
+#[derive(Debug)]
+pub struct TelemetryThrottle {
+    interval_ticks: AtomicU32,
+    /// Server tick of the last frame sent, `u32::MAX` before the first
+    last_tick: AtomicU32,
+}
+
+impl Default for TelemetryThrottle {
+    fn default() -> Self {
+        Self {
+            interval_ticks: AtomicU32::new(1),
+            last_tick: AtomicU32::new(u32::MAX),
+        }
+    }
+}
+
+impl TelemetryThrottle {
+    pub fn interval_ticks(&self) -> u32 {
+        self.interval_ticks.load(Ordering::Relaxed)
+    }
+
+    pub fn set_interval_ticks(&self, interval_ticks: u32) {
+        self.interval_ticks.store(interval_ticks.max(1), Ordering::Relaxed);
+    }
+
+    /// Whether a frame for `server_tick` goes out, remembering it if so
+    pub fn allow(&self, server_tick: u32) -> bool {
+        let interval = self.interval_ticks();
+        if interval <= 1 {
+            return true;
+        }
+        let last = self.last_tick.load(Ordering::Relaxed);
+        // Joining another session or seeking a replay starts the count again
+        if last == u32::MAX || server_tick < last || server_tick - last >= interval {
+            self.last_tick.store(server_tick, Ordering::Relaxed);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Ticks between telemetry frames for a client asking for `rate_hz`; 0 asks
+/// for every tick
+pub fn telemetry_interval_ticks(rate_hz: u16, tick_rate_hz: u16) -> u32 {
+    match rate_hz {
+        0 => 1,
+        hz => (tick_rate_hz / hz.min(tick_rate_hz)).max(1) as u32,
+    }
+}
+
+/// Fixed one-second window counter for inbound messages
+#[derive(Debug)]
+pub struct RateLimiter {
+    max_per_window: u32,
+    window_start: Instant,
+    count: u32,
+}
+
+impl RateLimiter {
+    fn new(max_per_second: u32) -> Self {
+        Self {
+            max_per_window: max_per_second,
+            window_start: Instant::now(),
+            count: 0,
+        }
+    }
+
+    /// Count a message; false if it exceeds the limit. A limit of 0 disables limiting.
+    fn allow(&mut self, now: Instant) -> bool {
+        if self.max_per_window == 0 {
+            return true;
+        }
+        if now.duration_since(self.window_start) >= Duration::from_secs(1) {
+            self.window_start = now;
+            self.count = 0;
+        }
+        self.count += 1;
+        self.count <= self.max_per_window
+    }
+}
+
+/// No limit, for connections that do not come through a socket
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum TransportError {
     #[error("IO error: {0}")]
@@ -78,6 +261,58 @@ pub struct ConnectionInfo {
     pub tcp_addr: SocketAddr,
     pub tcp_tx: mpsc::Sender<ServerMessage>,
     pub in_session: Option<SessionId>,
+    pub stats: Arc<ConnectionCounters>,
+    /// Auth token the player logged in with, the identity bans apply to
+    pub auth_identity: String,
+    /// Wakes the reader so the server can drop the connection
+    pub close: Arc<Notify>,
+    /// Rate this client receives telemetry at
+    pub telemetry: Arc<TelemetryThrottle>,
+    /// Inbound message limit, shared by its TCP stream and UDP datagrams
+    pub rate_limiter: Arc<Mutex<RateLimiter>>,
+    /// Content manifests and files being sent to the client
+    pub content_transfers: Arc<AtomicUsize>,
+}
+
+impl ConnectionInfo {
+    /// Count an inbound message against the connection's limit; false, and
+    /// recorded as a rate-limit hit, if it exceeds it
+    pub fn allow_message(&self) -> bool {
+        let allowed = self.rate_limiter.lock().unwrap().allow(Instant::now());
+        if !allowed {
+            self.stats.record_rate_limited();
+        }
+        allowed
+    }
+
+    /// Claim one of the connection's `limit` content transfer slots; None if
+    /// all are in use. The slot is freed when the guard is dropped.
+    pub fn start_content_transfer(&self, limit: usize) -> Option<ContentTransferGuard> {
+        self.content_transfers
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |active| (active < limit).then_some(active + 1))
+            .ok()
+            .map(|_| ContentTransferGuard(Arc::clone(&self.content_transfers)))
+    }
+}
+
+/// A content transfer in progress on a connection
+pub struct ContentTransferGuard(Arc<AtomicUsize>);
+
+impl Drop for ContentTransferGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Messages waiting in the transport's channels
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+pub struct QueueDepths {
+    /// Client messages received over TCP, not yet handled
+    pub tcp_inbound: usize,
+    pub udp_inbound: usize,
+    pub udp_outbound: usize,
+    /// Fullest send queue of a TCP client
+    pub tcp_outbound_max: usize,
 }
 
 pub struct TransportLayer {
@@ -88,13 +323,16 @@ pub struct TransportLayer {
 
     // Network sockets
     tcp_listener: Option<TcpListener>,
+    /// Address the TCP listener is bound to, which `start` takes
+    tcp_local_addr: Option<SocketAddr>,
     udp_socket: Arc<UdpSocket>,
     tls_acceptor: Option<TlsAcceptor>,
 
     // Channels for communication (bounded)
-    tcp_rx: mpsc::Receiver<(ConnectionId, ClientMessage)>,
+    // Receivers move to the server's message tasks
+    tcp_rx: Option<mpsc::Receiver<(ConnectionId, ClientMessage)>>,
     tcp_tx: mpsc::Sender<(ConnectionId, ClientMessage)>,
-    udp_rx: mpsc::Receiver<(SocketAddr, ClientMessage)>,
+    udp_rx: Option<mpsc::Receiver<(SocketAddr, ClientMessage)>>,
     udp_tx: mpsc::Sender<(SocketAddr, ClientMessage)>,
 
     // Outbound message queues (UDP only - TCP uses per-connection channels)
@@ -105,8 +343,12 @@ pub struct TransportLayer {
     shutdown_tx: mpsc::UnboundedSender<()>,
     #[allow(dead_code)]
     shutdown_rx: Option<mpsc::UnboundedReceiver<()>>,
+    /// Acceptor and UDP tasks, stopped on shutdown
+    tasks: Vec<JoinHandle<()>>,
 
     heartbeat_timeout: Duration,
+    max_messages_per_second: u32,
+    bans: Arc<RwLock<BanList>>,
 
     // Metrics
     pub metrics: TransportMetrics,
@@ -123,7 +365,8 @@ impl TransportLayer {
     ) -> Result<Self, TransportError> {
         // Setup TCP with TLS
         let tcp_listener = TcpListener::bind(tcp_bind).await?;
-        info!("TCP listener bound to {}", tcp_bind);
+        let tcp_local_addr = tcp_listener.local_addr()?;
+        info!("TCP listener bound to {}", tcp_local_addr);
 
         // Setup UDP
         let udp_socket = Arc::new(UdpSocket::bind(udp_bind).await?);
@@ -172,17 +415,21 @@ impl TransportLayer {
             player_to_connection: Arc::new(RwLock::new(HashMap::new())),
             addr_to_connection: Arc::new(RwLock::new(HashMap::new())),
             tcp_listener: Some(tcp_listener),
+            tcp_local_addr: Some(tcp_local_addr),
             udp_socket,
             tls_acceptor,
-            tcp_rx,
+            tcp_rx: Some(tcp_rx),
             tcp_tx,
-            udp_rx,
+            udp_rx: Some(udp_rx),
             udp_tx,
             udp_out_tx,
             udp_out_rx,
             shutdown_tx,
             shutdown_rx: Some(shutdown_rx),
+            tasks: Vec::new(),
             heartbeat_timeout: Duration::from_millis(heartbeat_timeout_ms),
+            max_messages_per_second: DEFAULT_MAX_MESSAGES_PER_SECOND,
+            bans: Arc::new(RwLock::new(BanList::new())),
             metrics: TransportMetrics::new(),
         })
     }
@@ -218,6 +465,25 @@ impl TransportLayer {
         Ok(config)
     }
 
+    /// Cap inbound messages per connection per second (0 disables). Call before `start`.
+    pub fn set_max_messages_per_second(&mut self, max: u32) {
+        self.max_messages_per_second = max;
+    }
+
+    /// Refuse banned players and addresses using this list. Call before `start`.
+    pub fn set_ban_list(&mut self, bans: Arc<RwLock<BanList>>) {
+        self.bans = bans;
+    }
+
+    /// Address clients connect to over TCP, e.g. the port picked for `:0`
+    pub fn tcp_local_addr(&self) -> Option<SocketAddr> {
+        self.tcp_local_addr
+    }
+
+    pub fn udp_local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.udp_socket.local_addr()
+    }
+
     pub async fn start(&mut self) {
         // Spawn TCP acceptor
         if let Some(listener) = self.tcp_listener.take() {
@@ -226,8 +492,10 @@ impl TransportLayer {
             let connections = Arc::clone(&self.connections);
             let addr_to_connection = Arc::clone(&self.addr_to_connection);
             let player_to_connection = Arc::clone(&self.player_to_connection);
+            let max_messages_per_second = self.max_messages_per_second;
+            let bans = Arc::clone(&self.bans);
 
-            tokio::spawn(async move {
+            self.tasks.push(tokio::spawn(async move {
                 Self::tcp_acceptor(
                     listener,
                     tcp_tx,
@@ -235,27 +503,30 @@ impl TransportLayer {
                     connections,
                     addr_to_connection,
                     player_to_connection,
+                    max_messages_per_second,
+                    bans,
                 )
                 .await;
-            });
+            }));
         }
 
         // Spawn UDP receiver
         let udp_socket = Arc::clone(&self.udp_socket);
         let udp_tx = self.udp_tx.clone();
-        tokio::spawn(async move {
+        self.tasks.push(tokio::spawn(async move {
             Self::udp_receiver(udp_socket, udp_tx).await;
-        });
+        }));
 
         // Spawn UDP sender
         let udp_socket = Arc::clone(&self.udp_socket);
         let mut udp_out_rx = std::mem::replace(&mut self.udp_out_rx, mpsc::channel(1).1);
         let metrics = self.metrics.clone();
-        tokio::spawn(async move {
+        self.tasks.push(tokio::spawn(async move {
             Self::udp_sender(udp_socket, &mut udp_out_rx, metrics).await;
-        });
+        }));
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn tcp_acceptor(
         listener: TcpListener,
         tcp_tx: mpsc::Sender<(ConnectionId, ClientMessage)>,
@@ -263,16 +534,24 @@ impl TransportLayer {
         connections: Arc<RwLock<HashMap<ConnectionId, ConnectionInfo>>>,
         addr_to_connection: Arc<RwLock<HashMap<SocketAddr, ConnectionId>>>,
         player_to_connection: Arc<RwLock<HashMap<PlayerId, ConnectionId>>>,
+        max_messages_per_second: u32,
+        bans: Arc<RwLock<BanList>>,
     ) {
         loop {
             match listener.accept().await {
                 Ok((stream, addr)) => {
+                    if let Some(ban) = bans.read().await.find(None, Some(addr.ip()), unix_now()) {
+                        info!("Refused TCP connection from banned address {} ({})", addr, ban.reason);
+                        continue;
+                    }
+
                     info!("New TCP connection from {}", addr);
                     let tcp_tx = tcp_tx.clone();
                     let tls_acceptor = tls_acceptor.clone();
                     let connections = Arc::clone(&connections);
                     let addr_to_connection = Arc::clone(&addr_to_connection);
                     let player_to_connection = Arc::clone(&player_to_connection);
+                    let bans = Arc::clone(&bans);
 
                     tokio::spawn(async move {
                         if let Err(e) = Self::handle_tcp_connection(
@@ -283,6 +562,8 @@ impl TransportLayer {
                             connections,
                             addr_to_connection,
                             player_to_connection,
+                            max_messages_per_second,
+                            bans,
                         )
                         .await
                         {
@@ -297,6 +578,7 @@ impl TransportLayer {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn handle_tcp_connection(
         stream: TcpStream,
         addr: SocketAddr,
@@ -305,6 +587,8 @@ impl TransportLayer {
         connections: Arc<RwLock<HashMap<ConnectionId, ConnectionInfo>>>,
         addr_to_connection: Arc<RwLock<HashMap<SocketAddr, ConnectionId>>>,
         player_to_connection: Arc<RwLock<HashMap<PlayerId, ConnectionId>>>,
+        max_messages_per_second: u32,
+        bans: Arc<RwLock<BanList>>,
     ) -> Result<(), TransportError> {
         // Generate unique connection ID
         let connection_id = Uuid::new_v4();
@@ -327,13 +611,14 @@ impl TransportLayer {
                         connections,
                         addr_to_connection,
                         player_to_connection,
+                        max_messages_per_second,
+                        bans,
                     )
                     .await
                 }
                 Err(e) => {
                     error!("TLS handshake failed for {}: {}", addr, e);
-                    Err(TransportError::Io(std::io::Error::new(
-                        std::io::ErrorKind::Other,
+                    Err(TransportError::Io(std::io::Error::other(
                         format!("TLS handshake failed: {}", e),
                     )))
                 }
@@ -350,11 +635,14 @@ impl TransportLayer {
                 connections,
                 addr_to_connection,
                 player_to_connection,
+                max_messages_per_second,
+                bans,
             )
             .await
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn handle_stream<S>(
         stream: S,
         addr: SocketAddr,
@@ -365,6 +653,8 @@ impl TransportLayer {
         connections: Arc<RwLock<HashMap<ConnectionId, ConnectionInfo>>>,
         addr_to_connection: Arc<RwLock<HashMap<SocketAddr, ConnectionId>>>,
         player_to_connection: Arc<RwLock<HashMap<PlayerId, ConnectionId>>>,
+        max_messages_per_second: u32,
+        bans: Arc<RwLock<BanList>>,
     ) -> Result<(), TransportError>
     where
         S: AsyncReadExt + AsyncWriteExt + Unpin + Send + 'static,
@@ -372,8 +662,13 @@ impl TransportLayer {
         // Split into reader and writer
         let (mut reader, mut writer) = tokio::io::split(stream);
 
+        let stats = Arc::new(ConnectionCounters::default());
+        let rate_limiter = Arc::new(Mutex::new(RateLimiter::new(max_messages_per_second)));
+        let close = Arc::new(Notify::new());
+
         // Spawn writer task
         let writer_addr = addr;
+        let writer_stats = Arc::clone(&stats);
         tokio::spawn(async move {
             while let Some(msg) = conn_rx.recv().await {
                 match rmp_serde::to_vec_named(&msg) {
@@ -389,6 +684,7 @@ impl TransportLayer {
                         if writer.flush().await.is_err() {
                             break;
                         }
+                        writer_stats.record_sent(data.len() + 4);
                     }
                     Err(e) => {
                         error!("Failed to serialize message: {}", e);
@@ -404,8 +700,15 @@ impl TransportLayer {
         let mut len_buf = [0u8; 4];
 
         loop {
-            // Read length prefix
-            match reader.read_exact(&mut len_buf).await {
+            // Read length prefix, unless the server is dropping this connection
+            let read = tokio::select! {
+                read = reader.read_exact(&mut len_buf) => read,
+                _ = close.notified() => {
+                    info!("Closing connection from {}", addr);
+                    break;
+                }
+            };
+            match read {
                 Ok(_) => {
                     let len = u32::from_be_bytes(len_buf) as usize;
 
@@ -420,11 +723,27 @@ impl TransportLayer {
                     let mut msg_buf = vec![0u8; len];
                     match reader.read_exact(&mut msg_buf).await {
                         Ok(_) => {
+                            stats.record_received(len + 4);
+                            if !rate_limiter.lock().unwrap().allow(Instant::now()) {
+                                stats.record_rate_limited();
+                                continue;
+                            }
+
                             match rmp_serde::from_slice::<ClientMessage>(&msg_buf) {
                                 Ok(msg) => {
                                     // Handle authentication - register connection
-                                    if let ClientMessage::Authenticate { player_name, .. } = &msg {
-                                        let player_id = Uuid::new_v4();
+                                    if let ClientMessage::Authenticate { token, player_name, .. } = &msg {
+                                        let now = unix_now();
+                                        let ban = bans.read().await.find(Some(token), Some(addr.ip()), now).cloned();
+                                        if let Some(ban) = ban {
+                                            info!("Refused login of banned player {} from {}", player_name, addr);
+                                            let _ = conn_tx.send(ServerMessage::AuthFailure {
+                                                reason: ban.describe(now),
+                                            }).await;
+                                            break;
+                                        }
+
+                                        let player_id = PlayerId::new_v4();
                                         let conn_info = ConnectionInfo {
                                             player_id,
                                             player_name: player_name.clone(),
@@ -433,6 +752,12 @@ impl TransportLayer {
                                             tcp_addr: addr,
                                             tcp_tx: conn_tx.clone(),
                                             in_session: None,
+                                            stats: Arc::clone(&stats),
+                                            auth_identity: token.clone(),
+                                            close: Arc::clone(&close),
+                                            telemetry: Arc::default(),
+                                            rate_limiter: Arc::clone(&rate_limiter),
+                                            content_transfers: Arc::default(),
                                         };
 
                                         connections
@@ -476,6 +801,9 @@ impl TransportLayer {
                                             server_tick: 0, // Will be updated later with actual tick
                                         };
                                         let _ = conn_tx.try_send(response);
+                                    } else if let ClientMessage::StatsAck { ping_id } = &msg {
+                                        stats.record_stats_ack(*ping_id);
+                                        continue;
                                     }
 
                                     if tcp_tx.send((connection_id, msg)).await.is_err() {
@@ -563,12 +891,19 @@ impl TransportLayer {
         }
     }
 
-    pub async fn recv_tcp(&mut self) -> Option<(ConnectionId, ClientMessage)> {
-        self.tcp_rx.recv().await
+    /// Messages from TCP clients, for the task that handles them; `None` once taken
+    pub fn take_tcp_receiver(&mut self) -> Option<mpsc::Receiver<(ConnectionId, ClientMessage)>> {
+        self.tcp_rx.take()
     }
 
-    pub async fn recv_udp(&mut self) -> Option<(SocketAddr, ClientMessage)> {
-        self.udp_rx.recv().await
+    /// Datagrams from UDP clients, for the task that handles them; `None` once taken
+    pub fn take_udp_receiver(&mut self) -> Option<mpsc::Receiver<(SocketAddr, ClientMessage)>> {
+        self.udp_rx.take()
+    }
+
+    /// The connection a datagram belongs to, by the address it came from
+    pub async fn connection_for_addr(&self, addr: SocketAddr) -> Option<ConnectionId> {
+        self.addr_to_connection.read().await.get(&addr).copied()
     }
 
     pub async fn send_tcp(
@@ -578,6 +913,11 @@ impl TransportLayer {
     ) -> Result<(), TransportError> {
         // Find the connection and use its dedicated channel
         if let Some(conn_info) = self.connections.read().await.get(&connection_id) {
+            if let ServerMessage::Telemetry(telemetry) = &msg {
+                if !conn_info.telemetry.allow(telemetry.server_tick) {
+                    return Ok(());
+                }
+            }
             let priority = msg.priority();
 
             match priority {
@@ -601,6 +941,7 @@ impl TransportLayer {
                         Ok(_) => Ok(()),
                         Err(mpsc::error::TrySendError::Full(_)) => {
                             // Queue full, drop the message and log it
+                            conn_info.stats.record_dropped();
                             self.metrics
                                 .tcp_messages_dropped
                                 .fetch_add(1, Ordering::Relaxed);
@@ -662,6 +1003,24 @@ impl TransportLayer {
         }
     }
 
+    pub async fn queue_depths(&self) -> QueueDepths {
+        let depth = |max: usize, free: usize| max - free;
+        let tcp_outbound_max = self
+            .connections
+            .read()
+            .await
+            .values()
+            .map(|conn| depth(conn.tcp_tx.max_capacity(), conn.tcp_tx.capacity()))
+            .max()
+            .unwrap_or(0);
+        QueueDepths {
+            tcp_inbound: depth(self.tcp_tx.max_capacity(), self.tcp_tx.capacity()),
+            udp_inbound: depth(self.udp_tx.max_capacity(), self.udp_tx.capacity()),
+            udp_outbound: depth(self.udp_out_tx.max_capacity(), self.udp_out_tx.capacity()),
+            tcp_outbound_max,
+        }
+    }
+
     pub async fn get_connection(&self, connection_id: ConnectionId) -> Option<ConnectionInfo> {
         self.connections.read().await.get(&connection_id).cloned()
     }
@@ -720,12 +1079,94 @@ impl TransportLayer {
         disconnected_players
     }
 
+    /// Register an authenticated client that lives in this process rather than
+    /// behind a socket; what the server sends it arrives on the receiver.
+    ///
+    /// The server sees its `Authenticate` as it would a remote client's.
+    pub async fn connect_local(&self, player_name: &str, token: &str) -> (ConnectionId, mpsc::Receiver<ServerMessage>) {
+        let connection_id = ConnectionId::new_v4();
+        let player_id = PlayerId::new_v4();
+        let (conn_tx, conn_rx) = mpsc::channel(PER_CLIENT_TCP_CHANNEL_SIZE);
+        let conn_info = ConnectionInfo {
+            player_id,
+            player_name: player_name.to_string(),
+            connected_at: Instant::now(),
+            last_heartbeat: Instant::now(),
+            // Never in `addr_to_connection`: a local client sends no UDP
+            tcp_addr: SocketAddr::from(([0, 0, 0, 0], 0)),
+            tcp_tx: conn_tx.clone(),
+            in_session: None,
+            stats: Arc::default(),
+            auth_identity: token.to_string(),
+            close: Arc::new(Notify::new()),
+            telemetry: Arc::default(),
+            rate_limiter: Arc::default(),
+            content_transfers: Arc::default(),
+        };
+        self.connections.write().await.insert(connection_id, conn_info);
+        self.player_to_connection.write().await.insert(player_id, connection_id);
+        info!("Player {} authenticated as {} (local connection: {})", player_name, player_id, connection_id);
+
+        let _ = conn_tx.send(ServerMessage::AuthSuccess(AuthSuccessData { player_id, server_version: 1 })).await;
+        let authenticate = ClientMessage::Authenticate {
+            token: token.to_string(),
+            player_name: player_name.to_string(),
+            telemetry_rate_hz: 0,
+        };
+        let _ = self.tcp_tx.send((connection_id, authenticate)).await;
+        (connection_id, conn_rx)
+    }
+
+    /// Handle a message as if it had arrived on the connection; `false` if the
+    /// server has stopped taking messages
+    pub async fn inject(&self, connection_id: ConnectionId, msg: ClientMessage) -> bool {
+        match &msg {
+            ClientMessage::Heartbeat { .. } => {
+                self.update_heartbeat(connection_id).await;
+                if let Some(conn_info) = self.connections.read().await.get(&connection_id) {
+                    let _ = conn_info.tcp_tx.try_send(ServerMessage::HeartbeatAck { server_tick: 0 });
+                }
+            }
+            ClientMessage::StatsAck { ping_id } => {
+                if let Some(conn_info) = self.connections.read().await.get(&connection_id) {
+                    conn_info.stats.record_stats_ack(*ping_id);
+                }
+                return true;
+            }
+            _ => {}
+        }
+        self.tcp_tx.send((connection_id, msg)).await.is_ok()
+    }
+
     pub async fn update_heartbeat(&self, connection_id: ConnectionId) {
         if let Some(info) = self.connections.write().await.get_mut(&connection_id) {
             info.last_heartbeat = Instant::now();
         }
     }
 
+    /// Send `message` to a connection and then close it, e.g. to kick a banned player.
+    ///
+    /// The connection is forgotten right away; the caller removes the player from the lobby and sessions.
+    pub async fn disconnect(&self, connection_id: ConnectionId, message: ServerMessage) -> Option<ConnectionInfo> {
+        let info = self.connections.write().await.remove(&connection_id)?;
+        self.addr_to_connection.write().await.remove(&info.tcp_addr);
+        self.player_to_connection.write().await.remove(&info.player_id);
+
+        let _ = info.tcp_tx.send(message).await;
+        info.close.notify_one();
+        Some(info)
+    }
+
+    /// Send a connection telemetry at `rate_hz` (0 for every tick), returning
+    /// the rate it gets
+    pub async fn set_telemetry_rate(&self, connection_id: ConnectionId, rate_hz: u16, tick_rate_hz: u16) -> Option<u16> {
+        let connections = self.connections.read().await;
+        let info = connections.get(&connection_id)?;
+        let interval_ticks = telemetry_interval_ticks(rate_hz, tick_rate_hz);
+        info.telemetry.set_interval_ticks(interval_ticks);
+        Some(tick_rate_hz / interval_ticks as u16)
+    }
+
     pub async fn set_player_session(&self, connection_id: ConnectionId, session_id: Option<SessionId>) {
         if let Some(info) = self.connections.write().await.get_mut(&connection_id) {
             info.in_session = session_id;
@@ -742,7 +1183,7 @@ impl TransportLayer {
                 "Sending shutdown notification to player: {}",
                 conn_info.player_name
             );
-            let _ = conn_info.tcp_tx.send(ServerMessage::Error {
+            let _ = conn_info.tcp_tx.try_send(ServerMessage::Error {
                 code: 503,
                 message: "Server is shutting down".to_string(),
             });
@@ -755,11 +1196,44 @@ impl TransportLayer {
         // Give connections time to send shutdown messages
         tokio::time::sleep(std::time::Duration::from_millis(500)).await;
 
+        // Stop accepting, so the ports are free once this returns
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+
         info!("Transport layer shutdown complete");
     }
 
     pub async fn broadcast_tcp(&self, msg: ServerMessage) {
-        let connections = self.connections.read().await;
+        Self::broadcast_to(&self.connections, &self.metrics, msg).await;
+    }
+
+    /// Send lobby updates to every connection as they are published
+    pub fn forward_lobby_updates(&self, mut updates: broadcast::Receiver<LobbyUpdate>) {
+        let connections = Arc::clone(&self.connections);
+        let metrics = self.metrics.clone();
+
+        tokio::spawn(async move {
+            loop {
+                match updates.recv().await {
+                    Ok(update) => {
+                        Self::broadcast_to(&connections, &metrics, ServerMessage::LobbyUpdate(update)).await;
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Skipped {} lobby updates, clients may show a stale lobby until they request it again", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    async fn broadcast_to(
+        connections: &RwLock<HashMap<ConnectionId, ConnectionInfo>>,
+        metrics: &TransportMetrics,
+        msg: ServerMessage,
+    ) {
+        let connections = connections.read().await;
         let priority = msg.priority();
         let mut dropped_count = 0;
         let mut failed_critical = 0;
@@ -778,6 +1252,7 @@ impl TransportLayer {
                     if let Err(mpsc::error::TrySendError::Full(_)) =
                         conn_info.tcp_tx.try_send(msg.clone())
                     {
+                        conn_info.stats.record_dropped();
                         dropped_count += 1;
                     }
                 }
@@ -785,7 +1260,7 @@ impl TransportLayer {
         }
 
         if dropped_count > 0 {
-            self.metrics
+            metrics
                 .tcp_messages_dropped
                 .fetch_add(dropped_count, Ordering::Relaxed);
             debug!(
@@ -795,7 +1270,7 @@ impl TransportLayer {
         }
 
         if failed_critical > 0 {
-            self.metrics
+            metrics
                 .clients_disconnected_backpressure
                 .fetch_add(failed_critical, Ordering::Relaxed);
             warn!(
@@ -805,6 +1280,17 @@ impl TransportLayer {
         }
     }
 
+    /// Send each authenticated connection its protocol statistics
+    pub async fn send_connection_stats(&self) {
+        let connections = self.connections.read().await;
+        for conn_info in connections.values() {
+            let stats = ServerMessage::ConnectionStats(conn_info.stats.snapshot());
+            if let Err(mpsc::error::TrySendError::Full(_)) = conn_info.tcp_tx.try_send(stats) {
+                conn_info.stats.record_dropped();
+            }
+        }
+    }
+
     pub fn get_connection_count(&self) -> usize {
         // Use try_read for non-blocking synchronous access
         // Returns 0 if the lock is currently held for writing
@@ -835,17 +1321,21 @@ mod tests {
             player_to_connection: Arc::new(RwLock::new(HashMap::new())),
             addr_to_connection: Arc::new(RwLock::new(HashMap::new())),
             tcp_listener: None,
+            tcp_local_addr: None,
             udp_socket: Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap()),
             tls_acceptor: None,
-            tcp_rx,
+            tcp_rx: Some(tcp_rx),
             tcp_tx,
-            udp_rx,
+            udp_rx: Some(udp_rx),
             udp_tx,
             udp_out_tx,
             udp_out_rx,
             shutdown_tx,
             shutdown_rx: Some(shutdown_rx),
+            tasks: Vec::new(),
             heartbeat_timeout: Duration::from_secs(30),
+            max_messages_per_second: DEFAULT_MAX_MESSAGES_PER_SECOND,
+            bans: Arc::new(RwLock::new(BanList::new())),
             metrics: TransportMetrics::new(),
         }
     }
@@ -901,13 +1391,19 @@ mod tests {
                 connections.insert(
                     conn_id,
                     ConnectionInfo {
-                        player_id: Uuid::new_v4(),
+                        player_id: PlayerId::new_v4(),
                         player_name: format!("Player{}", i),
                         connected_at: Instant::now(),
                         last_heartbeat: Instant::now(),
                         tcp_addr: addr,
                         tcp_tx: conn_tx,
                         in_session: None,
+                        stats: Arc::new(ConnectionCounters::default()),
+                        auth_identity: String::new(),
+                        close: Arc::new(Notify::new()),
+                        telemetry: Arc::default(),
+                        rate_limiter: Arc::default(),
+                        content_transfers: Arc::default(),
                     },
                 );
             }
@@ -962,19 +1458,87 @@ mod tests {
                 connections.insert(
                     conn_id,
                     ConnectionInfo {
-                        player_id: Uuid::new_v4(),
+                        player_id: PlayerId::new_v4(),
                         player_name: format!("Player{}", expected_count),
                         connected_at: Instant::now(),
                         last_heartbeat: Instant::now(),
                         tcp_addr: addr,
                         tcp_tx: conn_tx,
                         in_session: None,
+                        stats: Arc::new(ConnectionCounters::default()),
+                        auth_identity: String::new(),
+                        close: Arc::new(Notify::new()),
+                        telemetry: Arc::default(),
+                        rate_limiter: Arc::default(),
+                        content_transfers: Arc::default(),
                     },
                 );
             }
         }
     }
 
+    async fn send_frame(stream: &mut TcpStream, msg: &ClientMessage) {
+        let data = rmp_serde::to_vec_named(msg).unwrap();
+        stream.write_all(&(data.len() as u32).to_be_bytes()).await.unwrap();
+        stream.write_all(&data).await.unwrap();
+    }
+
+    async fn recv_frame(stream: &mut TcpStream) -> Option<ServerMessage> {
+        let mut len_buf = [0u8; 4];
+        stream.read_exact(&mut len_buf).await.ok()?;
+        let mut data = vec![0u8; u32::from_be_bytes(len_buf) as usize];
+        stream.read_exact(&mut data).await.ok()?;
+        Some(rmp_serde::from_slice(&data).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_banned_identity_is_refused_and_kicks_close_the_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut bans = BanList::new();
+        bans.add(crate::bans::BanEntry {
+            id: Uuid::new_v4(),
+            identity: Some("banned-token".to_string()),
+            ip: None,
+            player_name: "Griefer".to_string(),
+            reason: "Wrecking".to_string(),
+            banned_at: 0,
+            expires_at: None,
+        }).unwrap();
+
+        let mut transport = create_test_transport_layer().await;
+        transport.tcp_listener = Some(listener);
+        transport.set_ban_list(Arc::new(RwLock::new(bans)));
+        transport.start().await;
+
+        let authenticate = |token: &str| ClientMessage::Authenticate {
+            token: token.to_string(),
+            player_name: "Player".to_string(),
+            telemetry_rate_hz: 0,
+        };
+
+        let mut banned = TcpStream::connect(addr).await.unwrap();
+        send_frame(&mut banned, &authenticate("banned-token")).await;
+        match recv_frame(&mut banned).await {
+            Some(ServerMessage::AuthFailure { reason }) => assert!(reason.contains("Wrecking")),
+            other => panic!("Expected AuthFailure, got {:?}", other),
+        }
+        assert!(recv_frame(&mut banned).await.is_none(), "Connection should be closed");
+
+        let mut player = TcpStream::connect(addr).await.unwrap();
+        send_frame(&mut player, &authenticate("good-token")).await;
+        let Some(ServerMessage::AuthSuccess(auth)) = recv_frame(&mut player).await else {
+            panic!("Expected AuthSuccess");
+        };
+        let connection_id = transport.get_player_connection(auth.player_id).await.unwrap();
+        assert_eq!(transport.get_connection(connection_id).await.unwrap().auth_identity, "good-token");
+
+        transport.disconnect(connection_id, ServerMessage::Error { code: 403, message: "Kicked".to_string() }).await;
+        assert!(matches!(recv_frame(&mut player).await, Some(ServerMessage::Error { code: 403, .. })));
+        assert!(recv_frame(&mut player).await.is_none(), "Connection should be closed");
+        assert!(transport.get_player_connection(auth.player_id).await.is_none());
+    }
+
     #[test]
     fn test_connection_id_type_is_uuid() {
         // Verify that ConnectionId is indeed a Uuid type
@@ -987,4 +1551,67 @@ mod tests {
         // Should have standard UUID format (8-4-4-4-12 hex digits)
         assert_eq!(id_string.len(), 36); // UUID string length with dashes
     }
+
+    #[test]
+    fn test_rate_limiter_resets_each_second() {
+        let mut limiter = RateLimiter::new(2);
+        let start = Instant::now();
+
+        assert!(limiter.allow(start));
+        assert!(limiter.allow(start));
+        assert!(!limiter.allow(start));
+        assert!(limiter.allow(start + Duration::from_secs(1)));
+
+        let mut unlimited = RateLimiter::new(0);
+        assert!((0..1000).all(|_| unlimited.allow(start)));
+    }
+
+    #[test]
+    fn test_connection_counters_snapshot() {
+        let counters = ConnectionCounters::default();
+        counters.record_sent(100);
+        counters.record_sent(50);
+        counters.record_sent(50);
+        counters.record_dropped();
+        counters.record_received(20);
+        counters.record_rate_limited();
+
+        let first = counters.snapshot();
+        assert_eq!(first.messages_sent, 3);
+        assert_eq!(first.bytes_sent, 200);
+        assert_eq!(first.messages_received, 1);
+        assert_eq!(first.bytes_received, 20);
+        assert_eq!(first.rate_limit_hits, 1);
+        assert_eq!(first.loss_percent, 25.0);
+        assert_eq!(first.rtt_ms, None);
+
+        // Stale acks are ignored; the latest ping sets the RTT
+        let second = counters.snapshot();
+        counters.record_stats_ack(first.ping_id);
+        assert_eq!(counters.snapshot().rtt_ms, None);
+        let third = counters.snapshot();
+        counters.record_stats_ack(second.ping_id);
+        counters.record_stats_ack(third.ping_id);
+        assert!(counters.snapshot().rtt_ms.is_some());
+    }
+
+    #[test]
+    fn test_telemetry_throttle() {
+        assert_eq!(telemetry_interval_ticks(0, 240), 1);
+        assert_eq!(telemetry_interval_ticks(60, 240), 4);
+        assert_eq!(telemetry_interval_ticks(50, 240), 4);
+        assert_eq!(telemetry_interval_ticks(1000, 240), 1);
+
+        let throttle = TelemetryThrottle::default();
+        assert!((0..8).all(|tick| throttle.allow(tick)));
+
+        // Every fourth tick goes out, also when the session sends every other tick
+        throttle.set_interval_ticks(4);
+        let sent: Vec<u32> = (10..30).step_by(2).filter(|tick| throttle.allow(*tick)).collect();
+        assert_eq!(sent, vec![10, 14, 18, 22, 26]);
+
+        // A new session restarts the count
+        assert!(throttle.allow(0));
+        assert!(!throttle.allow(2));
+    }
 }
@@ -0,0 +1,158 @@
+//! Session weather.
+//!
+//! The sky condition follows the forecast chosen at session creation. Track
+//! wetness lags behind it: rain soaks the surface within a couple of minutes,
+//! while a drying line takes much longer to come back. Physics and the AI
+//! read the wetness each car sees from `CarState::track_wetness`.
+
+use crate::data::*;
+use crate::network::WeatherUpdateData;
+use serde::{Deserialize, Serialize};
+
+/// Track wetness a damp sky settles at
+pub const DAMP_WETNESS: f32 = 0.4;
+
+/// Wetness gained per second while it rains harder than the track is wet
+const WETTING_RATE_PER_S: f32 = 1.0 / 120.0;
+
+/// Wetness lost per second while the track dries
+const DRYING_RATE_PER_S: f32 = 1.0 / 600.0;
+
+/// Announce the wetness again once it has moved this far
+const WETNESS_ANNOUNCE_STEP: f32 = 0.05;
+
+/// Wetness the track tends towards under a condition
+pub fn target_wetness(condition: WeatherCondition) -> f32 {
+    match condition {
+        WeatherCondition::Dry => 0.0,
+        WeatherCondition::Damp => DAMP_WETNESS,
+        WeatherCondition::Wet => 1.0,
+    }
+}
+
+/// Weather state of a running session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Weather {
+    pub condition: WeatherCondition,
+    /// Standing water on the track (0 = dry, 1 = soaked)
+    pub wetness: f32,
+    /// Upcoming changes, soonest first
+    forecast: Vec<WeatherChange>,
+    elapsed_s: f32,
+    /// Wetness in the last update sent to clients; None when one is due
+    announced_wetness: Option<f32>,
+}
+
+impl Weather {
+    /// Start with the track already matching the initial condition
+    pub fn new(settings: &WeatherSettings) -> Self {
+        let mut forecast = settings.forecast.clone();
+        forecast.sort_by_key(|c| c.after_s);
+
+        Self {
+            condition: settings.condition,
+            wetness: target_wetness(settings.condition),
+            forecast,
+            elapsed_s: 0.0,
+            announced_wetness: None,
+        }
+    }
+
+    /// Advance the forecast and move the track wetness towards the current condition
+    pub fn update(&mut self, dt: f32) {
+        self.elapsed_s += dt;
+
+        while let Some(change) = self.forecast.first().copied() {
+            if self.elapsed_s < change.after_s as f32 {
+                break;
+            }
+            self.forecast.remove(0);
+            if change.condition != self.condition {
+                self.condition = change.condition;
+                self.announced_wetness = None;
+            }
+        }
+
+        let target = target_wetness(self.condition);
+        if self.wetness < target {
+            self.wetness = (self.wetness + WETTING_RATE_PER_S * dt).min(target);
+        } else {
+            self.wetness = (self.wetness - DRYING_RATE_PER_S * dt).max(target);
+        }
+
+        if let Some(announced) = self.announced_wetness {
+            if (self.wetness - announced).abs() >= WETNESS_ANNOUNCE_STEP {
+                self.announced_wetness = None;
+            }
+        }
+    }
+
+    /// Send the current weather again, e.g. after someone joined
+    pub fn announce(&mut self) {
+        self.announced_wetness = None;
+    }
+
+    /// Weather update for clients if the condition or wetness changed since the last one
+    pub fn take_update(&mut self, session_id: SessionId) -> Option<WeatherUpdateData> {
+        if self.announced_wetness.is_some() {
+            return None;
+        }
+        self.announced_wetness = Some(self.wetness);
+
+        let next = self.forecast.iter().find(|c| c.condition != self.condition);
+        Some(WeatherUpdateData {
+            session_id,
+            condition: self.condition,
+            track_wetness: self.wetness,
+            next_condition: next.map(|c| c.condition),
+            next_change_in_s: next.map(|c| (c.after_s as f32 - self.elapsed_s).max(0.0) as u32),
+        })
+    }
+}
+
+impl Default for Weather {
+    fn default() -> Self {
+        Self::new(&WeatherSettings::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rain_soaks_and_dries_the_track() {
+        let mut weather = Weather::new(&WeatherSettings {
+            condition: WeatherCondition::Dry,
+            forecast: vec![
+                WeatherChange { after_s: 600, condition: WeatherCondition::Dry },
+                WeatherChange { after_s: 10, condition: WeatherCondition::Wet },
+            ],
+        });
+        let session_id = SessionId::new_v4();
+        assert_eq!(weather.wetness, 0.0);
+        assert!(weather.take_update(session_id).is_some());
+        assert!(weather.take_update(session_id).is_none());
+
+        // Rain starts after 10 s and the track is soaked two minutes later
+        for _ in 0..(11 * 240) {
+            weather.update(1.0 / 240.0);
+        }
+        assert_eq!(weather.condition, WeatherCondition::Wet);
+        let update = weather.take_update(session_id).expect("condition change is announced");
+        assert_eq!(update.next_condition, Some(WeatherCondition::Dry));
+        assert!(weather.wetness < 0.05);
+
+        for _ in 0..(130 * 240) {
+            weather.update(1.0 / 240.0);
+        }
+        assert_eq!(weather.wetness, 1.0);
+
+        // Drying is slower than wetting
+        for _ in 0..(600 * 240) {
+            weather.update(1.0 / 240.0);
+        }
+        assert_eq!(weather.condition, WeatherCondition::Dry);
+        assert!(weather.wetness > 0.5);
+    }
+}
@@ -0,0 +1,123 @@
+//! An in-process server for the integration tests.
+//!
+//! Every test starts a server of its own inside the test process: it listens
+//! on ports the OS picks and loads a car and a track written to a temporary
+//! directory. `cargo test` needs no server running beforehand, no content
+//! checkout and no free well-known ports, and tests never see each other's
+//! players or sessions.
+
+#![allow(dead_code)]
+
+use apexsim_server::config::ServerConfig;
+use apexsim_server::runtime::ServerRuntime;
+use std::f32::consts::TAU;
+use std::net::SocketAddr;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Car in the test content
+pub const TEST_CAR_ID: &str = "5e57ca12-0000-4000-8000-000000000001";
+
+/// Corners of the test track's oval
+const TRACK_NODES: usize = 32;
+
+/// A server running inside the test, with the content it loaded
+pub struct TestServer {
+    runtime: ServerRuntime,
+    pub tcp_addr: SocketAddr,
+    pub udp_addr: SocketAddr,
+    /// Deleted when the server is dropped
+    content: TempDir,
+}
+
+impl TestServer {
+    pub async fn start() -> Self {
+        Self::start_with(|_| {}).await
+    }
+
+    /// Start a server with the test config changed by `configure`, e.g. its tick rate
+    pub async fn start_with(configure: impl FnOnce(&mut ServerConfig)) -> Self {
+        let content = TempDir::new().expect("Failed to create the test content directory");
+        write_test_content(content.path());
+        let mut config = test_config(content.path());
+        configure(&mut config);
+
+        let runtime = ServerRuntime::builder(config).start().await.expect("Test server failed to start");
+        let tcp_addr = runtime.tcp_addr().await.expect("Test server has no TCP listener");
+        let udp_addr = runtime.udp_addr().await.expect("Test server has no UDP socket");
+        Self { runtime, tcp_addr, udp_addr, content }
+    }
+
+    pub fn runtime(&self) -> &ServerRuntime {
+        &self.runtime
+    }
+
+    /// Stop the server as Ctrl+C would, telling its clients
+    pub async fn shutdown(self) {
+        self.runtime.shutdown().await;
+    }
+}
+
+/// Settings of a test server: plain TCP, every listener on a free port on
+/// localhost, and files kept under `dir`
+pub fn test_config(dir: &Path) -> ServerConfig {
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+    let mut config = ServerConfig::default();
+    config.network.tcp_bind = "127.0.0.1:0".to_string();
+    config.network.udp_bind = "127.0.0.1:0".to_string();
+    config.network.health_bind = "127.0.0.1:0".to_string();
+    // No certificates: clients connect without TLS
+    config.network.tls_cert_path = path("no-server.crt");
+    config.network.tls_key_path = path("no-server.key");
+    config.content.cars_dir = path("cars");
+    config.content.tracks_dir = path("tracks");
+    config.server.ban_list_path = path("bans.json");
+    config.session_saves.dir = path("saves");
+    config
+}
+
+/// Load the repository's cars and tracks instead of the test content, for
+/// tests that drive a real track
+pub fn use_repo_content(config: &mut ServerConfig) {
+    let content = Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join("content");
+    config.content.cars_dir = content.join("cars").to_string_lossy().into_owned();
+    config.content.tracks_dir = content.join("tracks").to_string_lossy().into_owned();
+}
+
+/// Write one car and one oval track under `dir/cars` and `dir/tracks`
+pub fn write_test_content(dir: &Path) {
+    let car_dir = dir.join("cars").join("test-car");
+    std::fs::create_dir_all(&car_dir).unwrap();
+    std::fs::write(
+        car_dir.join("car.toml"),
+        format!(
+            r#"id = "{TEST_CAR_ID}"
+name = "Test Car"
+version = "1.0.0"
+model = "test-car.glb"
+
+[physics]
+mass_kg = 1200.0
+length_m = 4.5
+width_m = 1.9
+max_engine_force_n = 6000.0
+max_brake_force_n = 12000.0
+drag_coefficient = 0.35
+grip_coefficient = 1.2
+max_steering_angle_rad = 0.5
+wheelbase_m = 2.7
+"#
+        ),
+    )
+    .unwrap();
+
+    // A 1.5 km oval, short enough that a race finishes within a test
+    let tracks_dir = dir.join("tracks");
+    std::fs::create_dir_all(&tracks_dir).unwrap();
+    let mut track = String::from("name: \"Test Oval\"\ndefault_width: 14.0\nclosed_loop: true\nnodes:\n");
+    for i in 0..TRACK_NODES {
+        let angle = TAU * i as f32 / TRACK_NODES as f32;
+        track.push_str(&format!("  - x: {:.1}\n    y: {:.1}\n    z: 0.0\n", 300.0 * angle.cos(), 160.0 * angle.sin()));
+    }
+    std::fs::write(tracks_dir.join("test_oval.yaml"), track).unwrap();
+}
@@ -1,4 +1,3 @@
-use std::process::{Child, Command, Stdio};
 use std::sync::Arc;
 use std::time::Duration;
 use std::io::Write;
@@ -8,10 +7,12 @@ use tokio::sync::Mutex;
 use tokio::time::{sleep, timeout, Instant};
 
 use apexsim_server::data::*;
+use apexsim_server::loadtest::{self, Scenario};
 use apexsim_server::network::{ClientMessage, ServerMessage};
 
-const SERVER_TCP_ADDR: &str = "127.0.0.1:9000";
-const SERVER_UDP_ADDR: &str = "127.0.0.1:9001";
+mod common;
+use common::TestServer;
+
 const TEST_TIMEOUT: Duration = Duration::from_secs(30);
 
 /// Client simulator that can connect to the server and interact with it
@@ -25,13 +26,13 @@ struct TestClient {
 }
 
 impl TestClient {
-    async fn connect(name: &str) -> Result<Self, Box<dyn std::error::Error>> {
+    async fn connect(server: &TestServer, name: &str) -> Result<Self, Box<dyn std::error::Error>> {
         // Connect TCP (plain, no TLS for testing)
-        let tcp_stream = TcpStream::connect(SERVER_TCP_ADDR).await?;
+        let tcp_stream = TcpStream::connect(server.tcp_addr).await?;
 
         // Create UDP socket
         let udp_socket = UdpSocket::bind("127.0.0.1:0").await?;
-        udp_socket.connect(SERVER_UDP_ADDR).await?;
+        udp_socket.connect(server.udp_addr).await?;
 
         Ok(Self {
             player_id: None,
@@ -56,6 +57,7 @@ impl TestClient {
         let auth_msg = ClientMessage::Authenticate {
             token: format!("test_token_{}", self.name),
             player_name: self.name.clone(),
+            telemetry_rate_hz: 0,
         };
         
         self.send_tcp_message(&auth_msg).await?;
@@ -88,6 +90,7 @@ impl TestClient {
         println!("DEBUG: Sending SelectCar with car_id={}", car_id);
         let msg = ClientMessage::SelectCar {
             car_config_id: car_id,
+            livery: 0,
         };
         self.send_tcp_message(&msg).await?;
         // Give server time to process the car selection
@@ -118,6 +121,11 @@ impl TestClient {
             track_config_id: track_id,
             max_players,
             session_kind,
+            weather: WeatherSettings::default(),
+            setup_policy: SetupPolicy::Open,
+            collision_policy: CollisionPolicy::Solid,
+            time_of_day: TimeOfDay::Noon,
+            aid_limits: None,
             ai_count: 0,
             lap_limit: 3,
         };
@@ -202,6 +210,8 @@ impl TestClient {
             throttle,
             brake,
             steering,
+            gear: None,
+            clutch: None,
         };
         
         // Send via TCP for now (UDP not fully implemented in server)
@@ -239,15 +249,20 @@ impl TestClient {
     }
 
     async fn receive_tcp_message(&mut self) -> Result<ServerMessage, Box<dyn std::error::Error>> {
-        let mut len_buf = [0u8; 4];
-        self.tcp_stream.read_exact(&mut len_buf).await?;
-        let len = u32::from_be_bytes(len_buf);
-        
-        let mut buf = vec![0u8; len as usize];
-        self.tcp_stream.read_exact(&mut buf).await?;
-        let msg: ServerMessage = rmp_serde::from_slice(&buf)?;
-        
-        Ok(msg)
+        // Lobby deltas and connection stats arrive unsolicited; these tests only look at full lobby state
+        loop {
+            let mut len_buf = [0u8; 4];
+            self.tcp_stream.read_exact(&mut len_buf).await?;
+            let len = u32::from_be_bytes(len_buf);
+
+            let mut buf = vec![0u8; len as usize];
+            self.tcp_stream.read_exact(&mut buf).await?;
+            let msg: ServerMessage = rmp_serde::from_slice(&buf)?;
+
+            if !matches!(msg, ServerMessage::LobbyUpdate(_) | ServerMessage::ConnectionStats(_)) {
+                return Ok(msg);
+            }
+        }
     }
 
     // Telemetry listener removed - we'll receive telemetry directly in test loop
@@ -255,31 +270,34 @@ impl TestClient {
 
 #[tokio::test]
 async fn test_server_initialization() {
-    // This test verifies that the server can be initialized with default config
-    // In a real scenario, this would test actual server startup
-    assert!(true);
+    // A client of a freshly started server is offered the test content
+    let server = TestServer::start().await;
+    let mut client = TestClient::connect(&server, "InitPlayer").await.expect("Failed to connect");
+    let (_, lobby_state) = client.authenticate().await.expect("Failed to authenticate");
+    match lobby_state {
+        ServerMessage::LobbyState(lobby) => {
+            assert_eq!(lobby.car_configs.len(), 1);
+            assert_eq!(lobby.track_configs.len(), 1);
+            assert!(lobby.available_sessions.is_empty());
+        }
+        other => panic!("Expected lobby state after authentication, got {:?}", other),
+    }
+    server.shutdown().await;
 }
 
-/// Integration test that requires the server to be running
-/// Start the server using VS Code task: "Start Server"
-/// Stop the server using VS Code task: "Stop Server"
+/// Four clients race together on an in-process server
 #[tokio::test]
-#[ignore] // Run manually: cargo test --test integration_test -- --ignored
 async fn test_multiplayer_race_session() {
     println!("=== Multiplayer Race Session Integration Test ===");
-    println!("NOTE: Server must be running (use VS Code task: 'Start Server')");
-    println!("");
-    
-    // Wait a moment for server to be ready
-    sleep(Duration::from_secs(1)).await;
+    let server = TestServer::start().await;
     
     let result = timeout(TEST_TIMEOUT, async {
         // Create 4 test clients
         println!("Creating test clients...");
-        let mut client1 = TestClient::connect("Player1").await?;
-        let mut client2 = TestClient::connect("Player2").await?;
-        let mut client3 = TestClient::connect("Player3").await?;
-        let mut client4 = TestClient::connect("Player4").await?;
+        let mut client1 = TestClient::connect(&server, "Player1").await?;
+        let mut client2 = TestClient::connect(&server, "Player2").await?;
+        let mut client3 = TestClient::connect(&server, "Player3").await?;
+        let mut client4 = TestClient::connect(&server, "Player4").await?;
         
         // Authenticate all clients
         println!("Authenticating clients...");
@@ -406,10 +424,10 @@ async fn test_multiplayer_race_session() {
         println!("  Client 4: {} packets", telemetry4.len());
         
         // Verify all clients received telemetry
-        assert!(telemetry1.len() > 0, "Client 1 received no telemetry");
-        assert!(telemetry2.len() > 0, "Client 2 received no telemetry");
-        assert!(telemetry3.len() > 0, "Client 3 received no telemetry");
-        assert!(telemetry4.len() > 0, "Client 4 received no telemetry");
+        assert!(!telemetry1.is_empty(), "Client 1 received no telemetry");
+        assert!(!telemetry2.is_empty(), "Client 2 received no telemetry");
+        assert!(!telemetry3.is_empty(), "Client 3 received no telemetry");
+        assert!(!telemetry4.is_empty(), "Client 4 received no telemetry");
         
         // Verify telemetry contains all 4 cars
         if let Some((tick, car_count)) = telemetry1.last() {
@@ -460,22 +478,17 @@ async fn test_multiplayer_race_session() {
 
 /// Integration test to verify telemetry broadcast is working correctly
 /// This test specifically checks that the server broadcasts telemetry to session participants
-/// Run: cargo test --test integration_test test_telemetry_broadcast -- --ignored --nocapture
+/// Run: cargo test --test integration_test test_telemetry_broadcast -- --nocapture
 #[tokio::test]
-#[ignore]
 async fn test_telemetry_broadcast() {
     println!("=== Telemetry Broadcast Integration Test ===");
-    println!("NOTE: Server must be running (use VS Code task: 'Start Server')");
-    println!("");
-    
-    // Wait a moment for server to be ready
-    sleep(Duration::from_secs(1)).await;
+    let server = TestServer::start().await;
     
     let result = timeout(Duration::from_secs(60), async {
         // Create 2 test clients (simpler test case)
         println!("Step 1: Creating test clients...");
-        let mut client1 = TestClient::connect("BroadcastTest1").await?;
-        let mut client2 = TestClient::connect("BroadcastTest2").await?;
+        let mut client1 = TestClient::connect(&server, "BroadcastTest1").await?;
+        let mut client2 = TestClient::connect(&server, "BroadcastTest2").await?;
         println!("  ✓ Both clients connected to server");
         
         // Authenticate both clients
@@ -614,77 +627,6 @@ async fn test_telemetry_broadcast() {
     }
 }
 
-/// Helper struct to manage server process for tick rate testing
-struct ServerProcess {
-    child: Child,
-    config_path: String,
-}
-
-impl ServerProcess {
-    fn start(tick_rate_hz: u16, port_offset: u16) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
-        // Create a temporary config file with the specified tick rate
-        let config_path = format!("/tmp/apexsim_test_{}hz.toml", tick_rate_hz);
-        let tcp_port = 9000 + port_offset;
-        let udp_port = 9001 + port_offset;
-        let health_port = 9002 + port_offset;
-        
-        let config_content = format!(
-            r#"[server]
-tick_rate_hz = {}
-max_sessions = 8
-session_timeout_seconds = 300
-
-[network]
-tcp_bind = "127.0.0.1:{}"
-udp_bind = "127.0.0.1:{}"
-health_bind = "127.0.0.1:{}"
-tls_cert_path = "./certs/server.crt"
-tls_key_path = "./certs/server.key"
-heartbeat_interval_ms = 1000
-heartbeat_timeout_ms = 5000
-
-[content]
-cars_dir = "../content/cars"
-tracks_dir = "../content/tracks"
-
-[logging]
-level = "warn"
-console_enabled = true
-"#,
-            tick_rate_hz, tcp_port, udp_port, health_port
-        );
-        
-        std::fs::write(&config_path, &config_content)?;
-        
-        // Start the server process
-        let child = Command::new("cargo")
-            .args(["run", "--release", "--", "--config", &config_path])
-            .current_dir("/home/guido/apexsim/server")
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .spawn()?;
-        
-        Ok(Self { child, config_path })
-    }
-    
-    fn stop(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        // Try graceful shutdown first
-        let _ = self.child.kill();
-        let _ = self.child.wait();
-        
-        // Clean up config file
-        let _ = std::fs::remove_file(&self.config_path);
-        
-        Ok(())
-    }
-}
-
-impl Drop for ServerProcess {
-    fn drop(&mut self) {
-        let _ = self.stop();
-    }
-}
-
 /// Results from a single tick rate test
 #[derive(Debug)]
 struct TickRateTestResult {
@@ -703,18 +645,18 @@ struct TickRateTestResult {
 
 /// Tick rate stress test - tests server at multiple tick rates to find performance limits
 /// Run: cargo test --test integration_test test_tick_rate_stress -- --ignored --nocapture
-#[tokio::test]
+#[tokio::test(flavor = "multi_thread")]
 #[ignore]
 async fn test_tick_rate_stress() {
     println!("╔══════════════════════════════════════════════════════════════════════════════╗");
     println!("║              TICK RATE STRESS TEST - PERFORMANCE BENCHMARK                   ║");
     println!("╠══════════════════════════════════════════════════════════════════════════════╣");
-    println!("║  Testing server tick rates: 120Hz, 240Hz, 480Hz, 960Hz, 1440Hz               ║");
+    println!("║  Testing server tick rates: 120Hz, 240Hz, 480Hz, 960Hz                       ║");
     println!("║  Each test runs for 10 seconds measuring actual tick rate and timing jitter  ║");
     println!("╚══════════════════════════════════════════════════════════════════════════════╝");
     println!();
 
-    let tick_rates = [120u16, 240, 480, 960, 1440];
+    let tick_rates = [120u16, 240, 480, 960];
     let test_duration_secs = 10.0;
     let mut results: Vec<TickRateTestResult> = Vec::new();
     
@@ -724,35 +666,10 @@ async fn test_tick_rate_stress() {
         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
         
         // Start server with this tick rate
-        let port_offset = (i as u16) * 10;
-        let tcp_addr = format!("127.0.0.1:{}", 9000 + port_offset);
-        
         print!("  Starting server... ");
         std::io::stdout().flush().unwrap();
-        
-        let mut server = match ServerProcess::start(target_hz, port_offset) {
-            Ok(s) => s,
-            Err(e) => {
-                println!("FAILED: {}", e);
-                results.push(TickRateTestResult {
-                    target_hz,
-                    actual_hz: 0.0,
-                    accuracy_percent: 0.0,
-                    tick_count: 0,
-                    duration_secs: 0.0,
-                    avg_tick_interval_us: 0.0,
-                    jitter_us: 0.0,
-                    min_interval_us: 0.0,
-                    max_interval_us: 0.0,
-                    missed_ticks: 0,
-                    passed: false,
-                });
-                continue;
-            }
-        };
-        
-        // Wait for server to start
-        sleep(Duration::from_secs(3)).await;
+        let server = TestServer::start_with(|config| config.server.tick_rate_hz = target_hz).await;
+        let tcp_addr = server.tcp_addr.to_string();
         println!("OK");
         
         // Connect client and run test
@@ -807,8 +724,7 @@ async fn test_tick_rate_stress() {
         // Stop server
         print!("  Stopping server... ");
         std::io::stdout().flush().unwrap();
-        let _ = server.stop();
-        sleep(Duration::from_secs(1)).await;
+        server.shutdown().await;
         println!("OK");
         println!();
     }
@@ -939,7 +855,7 @@ async fn run_tick_rate_test(
         }
         
         // Send input to keep connection alive (occasionally)
-        if packets_received % 20 == 0 {
+        if packets_received.is_multiple_of(20) {
             let _ = client.send_input(0.5, 0.0, 0.0, last_tick_num).await;
         }
     }
@@ -1000,8 +916,7 @@ async fn run_tick_rate_test(
     // For this stress test, we focus on relative consistency rather than absolute accuracy
     // since network batching affects receive rate
     let ratio = actual_hz / target_hz as f64;
-    let passed = ratio >= 0.45  // Allow for network batching reducing effective rate to ~50%
-        && ratio <= 1.10        // But shouldn't exceed target significantly
+    let passed = (0.45..=1.10).contains(&ratio)        // But shouldn't exceed target significantly
         && packet_loss_percent < 5.0
         && jitter_in_ticks < 2.0;  // Gaps should be consistent (mostly 1s)
     
@@ -1030,6 +945,7 @@ impl TestClientMinimal {
         let msg = ClientMessage::Authenticate {
             token: format!("test_token_{}", name),
             player_name: name.to_string(),
+            telemetry_rate_hz: 0,
         };
         self.send_message(&msg).await?;
         sleep(Duration::from_millis(50)).await;
@@ -1056,7 +972,7 @@ impl TestClientMinimal {
     }
     
     async fn select_car(&mut self, car_id: CarConfigId) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let msg = ClientMessage::SelectCar { car_config_id: car_id };
+        let msg = ClientMessage::SelectCar { car_config_id: car_id, livery: 0 };
         self.send_message(&msg).await
     }
     
@@ -1065,6 +981,11 @@ impl TestClientMinimal {
             track_config_id: track_id,
             max_players: 4,
             session_kind: SessionKind::Practice,
+            weather: WeatherSettings::default(),
+            setup_policy: SetupPolicy::Open,
+            collision_policy: CollisionPolicy::Solid,
+            time_of_day: TimeOfDay::Noon,
+            aid_limits: None,
             ai_count: 0,
             lap_limit: 3,
         };
@@ -1089,6 +1010,8 @@ impl TestClientMinimal {
             throttle,
             brake,
             steering,
+            gear: None,
+            clutch: None,
         };
         self.send_message(&msg).await
     }
@@ -1103,56 +1026,27 @@ impl TestClientMinimal {
     }
     
     async fn receive_message(&mut self) -> Result<ServerMessage, Box<dyn std::error::Error + Send + Sync>> {
-        let mut len_buf = [0u8; 4];
-        self.tcp_stream.read_exact(&mut len_buf).await?;
-        let len = u32::from_be_bytes(len_buf);
-        
-        let mut buf = vec![0u8; len as usize];
-        self.tcp_stream.read_exact(&mut buf).await?;
-        let msg: ServerMessage = rmp_serde::from_slice(&buf)?;
-        Ok(msg)
-    }
-    
-    async fn join_session(&mut self, session_id: SessionId) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        // Small delay to let any pending broadcasts arrive
-        sleep(Duration::from_millis(10)).await;
-        
-        let msg = ClientMessage::JoinSession { session_id };
-        self.send_message(&msg).await?;
-        
-        // Read responses until we get SessionJoined (skip LobbyState updates)
-        for _ in 0..10 {
-            let response = self.receive_message().await?;
-            match response {
-                ServerMessage::SessionJoined(_) => return Ok(()),
-                ServerMessage::LobbyState(_) => continue, // Skip lobby updates
-                ServerMessage::Error { message, .. } => return Err(format!("Join failed: {}", message).into()),
-                _ => continue,
+        // Lobby deltas and connection stats arrive unsolicited; these tests only look at full lobby state
+        loop {
+            let mut len_buf = [0u8; 4];
+            self.tcp_stream.read_exact(&mut len_buf).await?;
+            let len = u32::from_be_bytes(len_buf);
+
+            let mut buf = vec![0u8; len as usize];
+            self.tcp_stream.read_exact(&mut buf).await?;
+            let msg: ServerMessage = rmp_serde::from_slice(&buf)?;
+
+            if !matches!(msg, ServerMessage::LobbyUpdate(_) | ServerMessage::ConnectionStats(_)) {
+                return Ok(msg);
             }
         }
-        Err("Join timed out waiting for SessionJoined".into())
     }
 }
 
-/// Results from a multi-client load test
-#[derive(Debug)]
-struct MultiClientTestResult {
-    target_hz: u16,
-    client_count: usize,
-    actual_hz: f64,
-    ratio_percent: f64,
-    total_inputs_sent: u64,
-    total_telemetry_received: u64,
-    avg_telemetry_per_client: f64,
-    clients_with_telemetry: usize,
-    passed: bool,
-}
-
 /// Test the complete CLI client workflow: select car -> create session -> start game -> finish -> return to lobby
 /// This test simulates the exact workflow that a CLI client would go through
-/// Run: cargo test --test integration_test test_cli_client_workflow -- --ignored --nocapture
+/// Run: cargo test --test integration_test test_cli_client_workflow -- --nocapture
 #[tokio::test]
-#[ignore]
 async fn test_cli_client_workflow() {
     println!("╔══════════════════════════════════════════════════════════════════════════════╗");
     println!("║                    CLI CLIENT WORKFLOW INTEGRATION TEST                      ║");
@@ -1167,13 +1061,12 @@ async fn test_cli_client_workflow() {
     println!("╚══════════════════════════════════════════════════════════════════════════════╝");
     println!();
 
-    // Wait a moment for server to be ready
-    sleep(Duration::from_secs(1)).await;
+    let server = TestServer::start().await;
 
     let result = timeout(TEST_TIMEOUT, async {
         println!("Step 1: Connect and authenticate");
         println!("  Creating CLI test client...");
-        let mut client = TestClient::connect("CLI-Player").await?;
+        let mut client = TestClient::connect(&server, "CLI-Player").await?;
 
         println!("  Authenticating...");
         let (player_id, lobby_state) = client.authenticate().await?;
@@ -1211,8 +1104,11 @@ async fn test_cli_client_workflow() {
         client.start_session().await?;
         println!("  ✓ Session starting (waiting for countdown)");
 
-        // Wait for countdown
-        sleep(Duration::from_secs(6)).await;
+        // Wait for countdown, heartbeating so the server keeps the connection
+        for _ in 0..3 {
+            sleep(Duration::from_secs(2)).await;
+            client.send_heartbeat().await?;
+        }
         println!("  ✓ Countdown complete, race should be active");
 
         println!("\nStep 5: Simulate racing");
@@ -1280,16 +1176,17 @@ async fn test_cli_client_workflow() {
             println!("  Leaving session...");
             client.send_tcp_message(&ClientMessage::LeaveSession).await?;
 
-            // Wait for SessionLeft response, skipping telemetry messages
+            // Wait for SessionLeft response, skipping telemetry still queued for us
             let mut left_confirmed = false;
-            for _ in 0..10 {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while Instant::now() < deadline {
                 match timeout(Duration::from_millis(500), client.receive_tcp_message()).await {
                     Ok(Ok(ServerMessage::SessionLeft)) => {
                         println!("  ✓ Left session successfully");
                         left_confirmed = true;
                         break;
                     }
-                    Ok(Ok(ServerMessage::Telemetry(_))) => {
+                    Ok(Ok(ServerMessage::Telemetry(_) | ServerMessage::ExtendedTelemetry(_))) => {
                         // Skip telemetry messages that are still being sent
                         continue;
                     }
@@ -1325,7 +1222,8 @@ async fn test_cli_client_workflow() {
 
         // Wait for LobbyState, skipping other messages
         let mut lobby_confirmed = false;
-        for _ in 0..10 {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
             match timeout(Duration::from_millis(500), client.receive_tcp_message()).await {
                 Ok(Ok(ServerMessage::LobbyState(lobby))) => {
                     println!("  ✓ Back in lobby: {} players, {} sessions",
@@ -1333,7 +1231,7 @@ async fn test_cli_client_workflow() {
                     lobby_confirmed = true;
                     break;
                 }
-                Ok(Ok(ServerMessage::Telemetry(_))) => {
+                Ok(Ok(ServerMessage::Telemetry(_) | ServerMessage::ExtendedTelemetry(_))) => {
                     // Skip telemetry messages
                     continue;
                 }
@@ -1383,7 +1281,7 @@ async fn test_cli_client_workflow() {
 
 /// Multi-client load test - tests server with 16 concurrent clients sending random inputs
 /// Run: cargo test --test integration_test test_multi_client_load -- --ignored --nocapture
-#[tokio::test]
+#[tokio::test(flavor = "multi_thread")]
 #[ignore]
 async fn test_multi_client_load() {
     println!("╔══════════════════════════════════════════════════════════════════════════════════════════╗");
@@ -1398,7 +1296,7 @@ async fn test_multi_client_load() {
     let tick_rates = [120u16, 240, 360, 480];
     let client_count = 16;
     let test_duration_secs = 10.0;
-    let mut results: Vec<MultiClientTestResult> = Vec::new();
+    let mut results = Vec::new();
     
     for (i, &target_hz) in tick_rates.iter().enumerate() {
         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
@@ -1406,77 +1304,42 @@ async fn test_multi_client_load() {
         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
         
         // Start server with this tick rate
-        let port_offset = (i as u16) * 10;
-        let tcp_addr = format!("127.0.0.1:{}", 9000 + port_offset);
-        
         print!("  Starting server at {}Hz... ", target_hz);
         std::io::stdout().flush().unwrap();
-        
-        let mut server = match ServerProcess::start(target_hz, port_offset) {
-            Ok(s) => s,
-            Err(e) => {
-                println!("FAILED: {}", e);
-                results.push(MultiClientTestResult {
-                    target_hz,
-                    client_count,
-                    actual_hz: 0.0,
-                    ratio_percent: 0.0,
-                    total_inputs_sent: 0,
-                    total_telemetry_received: 0,
-                    avg_telemetry_per_client: 0.0,
-                    clients_with_telemetry: 0,
-                    passed: false,
-                });
-                continue;
-            }
-        };
-        
-        // Wait for server to start
-        sleep(Duration::from_secs(3)).await;
+        let server = TestServer::start_with(|config| config.server.tick_rate_hz = target_hz).await;
+        let tcp_addr = server.tcp_addr.to_string();
         println!("OK");
         
         // Run multi-client test
-        let result = run_multi_client_test(&tcp_addr, target_hz, client_count, test_duration_secs).await;
-        
-        match result {
-            Ok(test_result) => {
+        let mut scenario = Scenario::new(&tcp_addr, client_count, target_hz);
+        scenario.name = format!("{}hz-{}-clients", target_hz, client_count);
+        scenario.duration_s = test_duration_secs;
+        let report = loadtest::run(&scenario).await;
+
+        match &report.error {
+            None => {
                 println!("  Results:");
-                println!("    Clients connected: {:>4}", test_result.client_count);
-                println!("    Target tick rate:  {:>4} Hz", test_result.target_hz);
+                println!("    Clients connected: {:>4}", report.clients_connected);
+                println!("    Target tick rate:  {:>4} Hz", target_hz);
                 println!("    Actual tick rate:  {:>6.1} Hz ({:.1}% of target)", 
-                    test_result.actual_hz, test_result.ratio_percent);
-                println!("    Total inputs sent: {:>6}", test_result.total_inputs_sent);
+                    report.actual_tick_rate_hz, report.tick_ratio * 100.0);
+                println!("    Total inputs sent: {:>6}", report.total_inputs_sent);
                 println!("    Telemetry received:{:>6} total ({:.1} avg/client)", 
-                    test_result.total_telemetry_received, test_result.avg_telemetry_per_client);
+                    report.total_telemetry_received, report.total_telemetry_received as f64 / client_count as f64);
                 println!("    Clients w/ telemetry: {}/{}", 
-                    test_result.clients_with_telemetry, test_result.client_count);
+                    report.clients_with_telemetry, client_count);
                 
-                let status = if test_result.passed { "✅ PASS" } else { "⚠️  DEGRADED" };
+                let status = if report.passed { "✅ PASS" } else { "⚠️  DEGRADED" };
                 println!("    Status:            {}", status);
-                
-                results.push(test_result);
-            }
-            Err(e) => {
-                println!("  FAILED: {}", e);
-                results.push(MultiClientTestResult {
-                    target_hz,
-                    client_count,
-                    actual_hz: 0.0,
-                    ratio_percent: 0.0,
-                    total_inputs_sent: 0,
-                    total_telemetry_received: 0,
-                    avg_telemetry_per_client: 0.0,
-                    clients_with_telemetry: 0,
-                    passed: false,
-                });
             }
+            Some(e) => println!("  FAILED: {}", e),
         }
+        results.push((target_hz, Some(report)));
         
         // Stop server
         print!("  Stopping server... ");
         std::io::stdout().flush().unwrap();
-        let _ = server.stop();
-        sleep(Duration::from_secs(1)).await;
+        server.shutdown().await;
         println!("OK");
         println!();
     }
@@ -1490,21 +1353,26 @@ async fn test_multi_client_load() {
     
     let mut max_sustainable_hz = 0u16;
     
-    for result in &results {
-        let status_icon = if result.passed { "✅" } else if result.actual_hz > 0.0 { "⚠️ " } else { "❌" };
+    for (target_hz, report) in &results {
+        let Some(report) = report else {
+            println!("║  {:>5} │ {:>9} │ {:>6} │ {:>11} │ {:>9} │ {:>10} │ ❌                             ║",
+                target_hz, "-", "-", "-", "-", "-");
+            continue;
+        };
+        let status_icon = if report.passed { "✅" } else if report.actual_tick_rate_hz > 0.0 { "⚠️ " } else { "❌" };
         println!("║  {:>5} │ {:>9.1} │ {:>5.1}% │ {:>11} │ {:>9} │ {:>5}/{:<4} │ {}                             ║",
-            result.target_hz,
-            result.actual_hz,
-            result.ratio_percent,
-            result.total_inputs_sent,
-            result.total_telemetry_received,
-            result.clients_with_telemetry,
-            result.client_count,
+            target_hz,
+            report.actual_tick_rate_hz,
+            report.tick_ratio * 100.0,
+            report.total_inputs_sent,
+            report.total_telemetry_received,
+            report.clients_with_telemetry,
+            client_count,
             status_icon
         );
         
-        if result.passed && result.target_hz > max_sustainable_hz {
-            max_sustainable_hz = result.target_hz;
+        if report.passed && *target_hz > max_sustainable_hz {
+            max_sustainable_hz = *target_hz;
         }
     }
     
@@ -1521,279 +1389,15 @@ async fn test_multi_client_load() {
     println!("\n✅ TEST PASSED: Server sustains up to {}Hz with {} concurrent clients", max_sustainable_hz, client_count);
 }
 
-async fn run_multi_client_test(
-    tcp_addr: &str,
-    target_hz: u16,
-    client_count: usize,
-    duration_secs: f64,
-) -> Result<MultiClientTestResult, Box<dyn std::error::Error + Send + Sync>> {
-    print!("  Connecting {} clients... ", client_count);
-    std::io::stdout().flush().unwrap();
-    
-    // Connect all clients
-    let mut clients: Vec<TestClientMinimal> = Vec::new();
-    let mut car_id = None;
-    let mut track_id = None;
-    
-    for i in 0..client_count {
-        // First client may need retries while server is starting
-        let tcp_stream = if i == 0 {
-            let mut attempts = 0;
-            loop {
-                match timeout(Duration::from_secs(2), TcpStream::connect(tcp_addr)).await {
-                    Ok(Ok(s)) => break s,
-                    Ok(Err(_)) | Err(_) => {
-                        attempts += 1;
-                        if attempts >= 5 {
-                            return Err("Client 0 connect failed after 5 attempts".into());
-                        }
-                        sleep(Duration::from_millis(500)).await;
-                    }
-                }
-            }
-        } else {
-            match timeout(Duration::from_secs(5), TcpStream::connect(tcp_addr)).await {
-                Ok(Ok(s)) => s,
-                Ok(Err(e)) => return Err(format!("Client {} connect failed: {}", i, e).into()),
-                Err(_) => return Err(format!("Client {} connect timeout", i).into()),
-            }
-        };
-        
-        let mut client = TestClientMinimal { tcp_stream };
-        
-        // Authenticate
-        let (_player_id, c_id, t_id) = client.authenticate(&format!("LoadTest_{}", i)).await?;
-        
-        if car_id.is_none() {
-            car_id = Some(c_id);
-            track_id = Some(t_id);
-        }
-        
-        // Select car
-        client.select_car(c_id).await?;
-        
-        clients.push(client);
-        
-        // Small delay between connections to avoid overwhelming
-        if i % 5 == 4 {
-            sleep(Duration::from_millis(50)).await;
-        }
-    }
-    println!("OK ({} connected)", clients.len());
-    
-    let _car_id = car_id.ok_or("No car ID")?;
-    let track_id = track_id.ok_or("No track ID")?;
-    
-    // Give server time to process all auth/select messages
-    sleep(Duration::from_millis(200)).await;
-    
-    // First client creates session with max_players = 16 (max grid positions on default track)
-    print!("  Creating session... ");
-    std::io::stdout().flush().unwrap();
-    
-    // Create session with enough slots for all clients
-    let create_msg = ClientMessage::CreateSession {
-        track_config_id: track_id,
-        max_players: 16,
-        session_kind: SessionKind::Practice,
-        ai_count: 0,
-        lap_limit: 3,
-    };
-    clients[0].send_message(&create_msg).await?;
-    
-    // Read responses until we get SessionJoined (skip LobbyState updates)
-    let mut session_id = None;
-    for _ in 0..5 {
-        let response = clients[0].receive_message().await?;
-        match response {
-            ServerMessage::SessionJoined(data) => {
-                session_id = Some(data.session_id);
-                break;
-            }
-            ServerMessage::LobbyState(_) => continue, // Skip lobby updates
-            ServerMessage::Error { message, .. } => return Err(format!("Create failed: {}", message).into()),
-            other => return Err(format!("Unexpected response: {:?}", other).into()),
-        }
-    }
-    let session_id = session_id.ok_or("No session created after retries")?;
-    println!("OK (session: {})", session_id);
-    
-    // Give server time to process session creation
-    sleep(Duration::from_millis(100)).await;
-    
-    // Other clients join the session
-    print!("  Joining {} clients to session... ", client_count - 1);
-    std::io::stdout().flush().unwrap();
-    
-    for i in 1..clients.len() {
-        match clients[i].join_session(session_id).await {
-            Ok(()) => {}
-            Err(e) => {
-                println!("\n    Client {} failed to join: {}", i, e);
-                return Err(e);
-            }
-        }
-        
-        if i % 5 == 0 {
-            sleep(Duration::from_millis(20)).await;
-        }
-    }
-    println!("OK");
-    
-    // Start session
-    print!("  Starting race... ");
-    std::io::stdout().flush().unwrap();
-    clients[0].start_session().await?;
-    
-    // Wait for countdown
-    sleep(Duration::from_secs(6)).await;
-    println!("OK");
-    
-    // Split clients into separate tasks for concurrent operation
-    print!("  Racing for {:.0}s with random inputs... ", duration_secs);
-    std::io::stdout().flush().unwrap();
-    
-    // Use Arc<Mutex> to share state between tasks
-    let inputs_sent = Arc::new(std::sync::atomic::AtomicU64::new(0));
-    let telemetry_received: Arc<Mutex<Vec<u64>>> = Arc::new(Mutex::new(vec![0; client_count]));
-    let first_tick: Arc<Mutex<Option<u32>>> = Arc::new(Mutex::new(None));
-    let last_tick: Arc<Mutex<u32>> = Arc::new(Mutex::new(0));
-    
-    let collection_start = Instant::now();
-    
-    // Spawn tasks for each client
-    let mut handles = Vec::new();
-    
-    for (client_idx, client) in clients.into_iter().enumerate() {
-        let inputs_sent = Arc::clone(&inputs_sent);
-        let telemetry_received = Arc::clone(&telemetry_received);
-        let first_tick = Arc::clone(&first_tick);
-        let last_tick = Arc::clone(&last_tick);
-        let start = collection_start;
-        // Create a seed from current time + client index for deterministic but varied RNG
-        let seed = (std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_nanos() as u64)
-            .wrapping_add(client_idx as u64);
-        
-        let handle = tokio::spawn(async move {
-            let mut client = client;
-            // Use a simple PRNG seeded with time + client index
-            let mut rng_state = seed;
-            let mut local_telemetry_count = 0u64;
-            let mut local_last_tick = 0u32;
-            
-            // Simple xorshift64 PRNG
-            let mut next_random = || -> f32 {
-                rng_state ^= rng_state << 13;
-                rng_state ^= rng_state >> 7;
-                rng_state ^= rng_state << 17;
-                (rng_state as f32) / (u64::MAX as f32)
-            };
-            
-            while start.elapsed().as_secs_f64() < duration_secs {
-                // Send random input
-                let throttle: f32 = next_random();
-                let brake: f32 = if next_random() < 0.1 { next_random() * 0.5 } else { 0.0 };
-                let steering: f32 = (next_random() - 0.5) * 0.6;  // -0.3 to 0.3
-                
-                if client.send_input(throttle, brake, steering, local_last_tick).await.is_ok() {
-                    inputs_sent.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                }
-                
-                // Try to receive telemetry (non-blocking)
-                match timeout(Duration::from_millis(5), client.receive_message()).await {
-                    Ok(Ok(ServerMessage::Telemetry(tel))) => {
-                        local_telemetry_count += 1;
-                        local_last_tick = tel.server_tick;
-                        
-                        // Update shared first/last tick
-                        {
-                            let mut ft = first_tick.lock().await;
-                            if ft.is_none() {
-                                *ft = Some(tel.server_tick);
-                            }
-                        }
-                        {
-                            let mut lt = last_tick.lock().await;
-                            if tel.server_tick > *lt {
-                                *lt = tel.server_tick;
-                            }
-                        }
-                    }
-                    _ => {}
-                }
-                
-                // Small yield to allow other tasks
-                tokio::task::yield_now().await;
-            }
-            
-            // Update telemetry count for this client
-            {
-                let mut tr = telemetry_received.lock().await;
-                tr[client_idx] = local_telemetry_count;
-            }
-        });
-        
-        handles.push(handle);
-    }
-    
-    // Wait for all client tasks to complete
-    for handle in handles {
-        let _ = handle.await;
-    }
-    
-    println!("OK");
-    
-    // Calculate results
-    let total_inputs = inputs_sent.load(std::sync::atomic::Ordering::Relaxed);
-    let telemetry_counts = telemetry_received.lock().await;
-    let total_telemetry: u64 = telemetry_counts.iter().sum();
-    let clients_with_telemetry = telemetry_counts.iter().filter(|&&c| c > 0).count();
-    let avg_telemetry = total_telemetry as f64 / client_count as f64;
-    
-    let first_tick_val = first_tick.lock().await.unwrap_or(0);
-    let last_tick_val = *last_tick.lock().await;
-    let total_server_ticks = last_tick_val.saturating_sub(first_tick_val);
-    
-    let actual_duration = collection_start.elapsed().as_secs_f64();
-    let actual_hz = if actual_duration > 0.0 {
-        total_server_ticks as f64 / actual_duration
-    } else {
-        0.0
-    };
-    let ratio_percent = (actual_hz / target_hz as f64) * 100.0;
-    
-    // Pass criteria: ≥40% ratio (accounting for TCP batching) and all clients got some telemetry
-    let passed = ratio_percent >= 40.0 && clients_with_telemetry == client_count;
-    
-    Ok(MultiClientTestResult {
-        target_hz,
-        client_count,
-        actual_hz,
-        ratio_percent,
-        total_inputs_sent: total_inputs,
-        total_telemetry_received: total_telemetry,
-        avg_telemetry_per_client: avg_telemetry,
-        clients_with_telemetry,
-        passed,
-    })
-}
-
 #[tokio::test]
 async fn test_sandbox_session_workflow() {
     println!("=== Sandbox Session Workflow Test ===");
-    println!("NOTE: Server must be running (use VS Code task: 'Start Server')");
-    println!("");
-    
-    // Wait a moment for server to be ready
-    sleep(Duration::from_secs(1)).await;
+    let server = TestServer::start().await;
     
     let result = timeout(TEST_TIMEOUT, async {
         // Create a test client
         println!("Creating test client...");
-        let mut client = TestClient::connect("SandboxPlayer").await?;
+        let mut client = TestClient::connect(&server, "SandboxPlayer").await?;
         
         // Authenticate
         println!("Authenticating client...");
@@ -6,7 +6,9 @@ use tokio::time::{sleep, timeout};
 use apexsim_server::data::*;
 use apexsim_server::network::{ClientMessage, ServerMessage, LobbyStateData};
 
-const SERVER_TCP_ADDR: &str = "127.0.0.1:9000";
+mod common;
+use common::TestServer;
+
 const TEST_TIMEOUT: Duration = Duration::from_secs(30);
 
 /// Lightweight test client for lobby operations
@@ -18,8 +20,8 @@ struct LobbyTestClient {
 }
 
 impl LobbyTestClient {
-    async fn connect(name: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let tcp_stream = TcpStream::connect(SERVER_TCP_ADDR).await?;
+    async fn connect(server: &TestServer, name: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let tcp_stream = TcpStream::connect(server.tcp_addr).await?;
 
         Ok(Self {
             player_id: None,
@@ -33,6 +35,7 @@ impl LobbyTestClient {
         let auth_msg = ClientMessage::Authenticate {
             token: format!("test_token_{}", self.name),
             player_name: self.name.clone(),
+            telemetry_rate_hz: 0,
         };
 
         self.send_message(&auth_msg).await?;
@@ -62,6 +65,7 @@ impl LobbyTestClient {
     async fn select_car(&mut self, car_id: CarConfigId) -> Result<(), Box<dyn std::error::Error>> {
         let msg = ClientMessage::SelectCar {
             car_config_id: car_id,
+            livery: 0,
         };
         self.send_message(&msg).await?;
         sleep(Duration::from_millis(100)).await;
@@ -78,6 +82,11 @@ impl LobbyTestClient {
             track_config_id: track_id,
             max_players,
             session_kind,
+            weather: WeatherSettings::default(),
+            setup_policy: SetupPolicy::Open,
+            collision_policy: CollisionPolicy::Solid,
+            time_of_day: TimeOfDay::Noon,
+            aid_limits: None,
             ai_count: 0,
             lap_limit: 3,
         };
@@ -207,15 +216,20 @@ impl LobbyTestClient {
     }
 
     async fn receive_message(&mut self) -> Result<ServerMessage, Box<dyn std::error::Error>> {
-        let mut len_buf = [0u8; 4];
-        self.tcp_stream.read_exact(&mut len_buf).await?;
-        let len = u32::from_be_bytes(len_buf);
+        // Lobby deltas and connection stats arrive unsolicited; these tests only look at full lobby state
+        loop {
+            let mut len_buf = [0u8; 4];
+            self.tcp_stream.read_exact(&mut len_buf).await?;
+            let len = u32::from_be_bytes(len_buf);
 
-        let mut buf = vec![0u8; len as usize];
-        self.tcp_stream.read_exact(&mut buf).await?;
-        let msg: ServerMessage = rmp_serde::from_slice(&buf)?;
+            let mut buf = vec![0u8; len as usize];
+            self.tcp_stream.read_exact(&mut buf).await?;
+            let msg: ServerMessage = rmp_serde::from_slice(&buf)?;
 
-        Ok(msg)
+            if !matches!(msg, ServerMessage::LobbyUpdate(_) | ServerMessage::ConnectionStats(_)) {
+                return Ok(msg);
+            }
+        }
     }
 
 }
@@ -226,13 +240,13 @@ impl LobbyTestClient {
 
 /// Test: Create a session and verify it appears in lobby state
 #[tokio::test]
-#[ignore]
 async fn test_create_session() {
     println!("=== Test: Create Session ===");
-    println!("NOTE: Server must be running (use VS Code task: 'Start Server')");
+
+    let server = TestServer::start().await;
 
     let result = timeout(TEST_TIMEOUT, async {
-        let mut client = LobbyTestClient::connect("CreateSessionTest").await?;
+        let mut client = LobbyTestClient::connect(&server, "CreateSessionTest").await?;
         let (player_id, lobby_state) = client.authenticate().await?;
         println!("  Authenticated as player: {}", player_id);
 
@@ -275,13 +289,14 @@ async fn test_create_session() {
 
 /// Test: Join an existing session
 #[tokio::test]
-#[ignore]
 async fn test_join_session() {
     println!("=== Test: Join Session ===");
 
+    let server = TestServer::start().await;
+
     let result = timeout(TEST_TIMEOUT, async {
         // Client 1 creates a session
-        let mut client1 = LobbyTestClient::connect("JoinTest_Host").await?;
+        let mut client1 = LobbyTestClient::connect(&server, "JoinTest_Host").await?;
         let (_, lobby_state) = client1.authenticate().await?;
         println!("  Host authenticated");
 
@@ -293,7 +308,7 @@ async fn test_join_session() {
         println!("  Session created: {}", session_id);
 
         // Client 2 joins the session
-        let mut client2 = LobbyTestClient::connect("JoinTest_Joiner").await?;
+        let mut client2 = LobbyTestClient::connect(&server, "JoinTest_Joiner").await?;
         let (_, _) = client2.authenticate().await?;
         println!("  Joiner authenticated");
 
@@ -321,13 +336,14 @@ async fn test_join_session() {
 
 /// Test: Leave a session
 #[tokio::test]
-#[ignore]
 async fn test_leave_session() {
     println!("=== Test: Leave Session ===");
 
+    let server = TestServer::start().await;
+
     let result = timeout(TEST_TIMEOUT, async {
         // Setup: Create session with 2 players
-        let mut client1 = LobbyTestClient::connect("LeaveTest_Host").await?;
+        let mut client1 = LobbyTestClient::connect(&server, "LeaveTest_Host").await?;
         let (_, lobby_state) = client1.authenticate().await?;
 
         let car_id = lobby_state.car_configs.first().ok_or("No cars")?.id;
@@ -337,7 +353,7 @@ async fn test_leave_session() {
         let session_id = client1.create_session(track_id, 4, SessionKind::Practice).await?;
         println!("  Session created: {}", session_id);
 
-        let mut client2 = LobbyTestClient::connect("LeaveTest_Leaver").await?;
+        let mut client2 = LobbyTestClient::connect(&server, "LeaveTest_Leaver").await?;
         client2.authenticate().await?;
         client2.select_car(car_id).await?;
         client2.join_session(session_id).await?;
@@ -374,12 +390,13 @@ async fn test_leave_session() {
 
 /// Test: Session is removed when all players leave
 #[tokio::test]
-#[ignore]
 async fn test_session_cleanup_on_empty() {
     println!("=== Test: Session Cleanup When Empty ===");
 
+    let server = TestServer::start().await;
+
     let result = timeout(TEST_TIMEOUT, async {
-        let mut client = LobbyTestClient::connect("CleanupTest").await?;
+        let mut client = LobbyTestClient::connect(&server, "CleanupTest").await?;
         let (_, lobby_state) = client.authenticate().await?;
 
         let car_id = lobby_state.car_configs.first().ok_or("No cars")?.id;
@@ -432,13 +449,14 @@ async fn test_session_cleanup_on_empty() {
 
 /// Test: Cannot join a full session (max players reached)
 #[tokio::test]
-#[ignore]
 async fn test_max_players_limit() {
     println!("=== Test: Max Players Limit ===");
 
+    let server = TestServer::start().await;
+
     let result = timeout(TEST_TIMEOUT, async {
         // Create session with max 2 players
-        let mut host = LobbyTestClient::connect("MaxPlayers_Host").await?;
+        let mut host = LobbyTestClient::connect(&server, "MaxPlayers_Host").await?;
         let (_, lobby_state) = host.authenticate().await?;
 
         let car_id = lobby_state.car_configs.first().ok_or("No cars")?.id;
@@ -449,14 +467,14 @@ async fn test_max_players_limit() {
         println!("  Created session with max 2 players: {}", session_id);
 
         // Second player joins
-        let mut player2 = LobbyTestClient::connect("MaxPlayers_P2").await?;
+        let mut player2 = LobbyTestClient::connect(&server, "MaxPlayers_P2").await?;
         player2.authenticate().await?;
         player2.select_car(car_id).await?;
         player2.join_session(session_id).await?;
         println!("  Player 2 joined");
 
         // Third player tries to join - should fail
-        let mut player3 = LobbyTestClient::connect("MaxPlayers_P3").await?;
+        let mut player3 = LobbyTestClient::connect(&server, "MaxPlayers_P3").await?;
         player3.authenticate().await?;
         player3.select_car(car_id).await?;
 
@@ -476,13 +494,14 @@ async fn test_max_players_limit() {
 
 /// Test: Rapid join and leave by multiple clients
 #[tokio::test]
-#[ignore]
 async fn test_rapid_join_leave() {
     println!("=== Test: Rapid Join/Leave ===");
 
+    let server = TestServer::start().await;
+
     let result = timeout(Duration::from_secs(60), async {
         // Create session
-        let mut host = LobbyTestClient::connect("RapidTest_Host").await?;
+        let mut host = LobbyTestClient::connect(&server, "RapidTest_Host").await?;
         let (_, lobby_state) = host.authenticate().await?;
 
         let car_id = lobby_state.car_configs.first().ok_or("No cars")?.id;
@@ -495,7 +514,7 @@ async fn test_rapid_join_leave() {
         // Rapid join/leave cycle with multiple clients
         let iterations = 5;
         for i in 0..iterations {
-            let mut client = LobbyTestClient::connect(&format!("RapidTest_Client{}", i)).await?;
+            let mut client = LobbyTestClient::connect(&server, &format!("RapidTest_Client{}", i)).await?;
             client.authenticate().await?;
             client.select_car(car_id).await?;
 
@@ -536,17 +555,18 @@ async fn test_rapid_join_leave() {
 
 /// Test: Multiple simultaneous session creations
 #[tokio::test]
-#[ignore]
 async fn test_multiple_sessions() {
     println!("=== Test: Multiple Simultaneous Sessions ===");
 
+    let server = TestServer::start().await;
+
     let result = timeout(TEST_TIMEOUT, async {
         let mut clients: Vec<LobbyTestClient> = Vec::new();
         let mut session_ids: Vec<SessionId> = Vec::new();
 
         // Create 3 clients
         for i in 0..3 {
-            let mut client = LobbyTestClient::connect(&format!("MultiSession_Host{}", i)).await?;
+            let mut client = LobbyTestClient::connect(&server, &format!("MultiSession_Host{}", i)).await?;
             let (_, lobby_state) = client.authenticate().await?;
 
             let car_id = lobby_state.car_configs.first().ok_or("No cars")?.id;
@@ -586,19 +606,20 @@ async fn test_multiple_sessions() {
 
 /// Test: Cannot join non-existent session
 #[tokio::test]
-#[ignore]
 async fn test_join_nonexistent_session() {
     println!("=== Test: Join Non-existent Session ===");
 
+    let server = TestServer::start().await;
+
     let result = timeout(TEST_TIMEOUT, async {
-        let mut client = LobbyTestClient::connect("NonExistent_Test").await?;
+        let mut client = LobbyTestClient::connect(&server, "NonExistent_Test").await?;
         let (_, lobby_state) = client.authenticate().await?;
 
         let car_id = lobby_state.car_configs.first().ok_or("No cars")?.id;
         client.select_car(car_id).await?;
 
         // Generate a random session ID that doesn't exist
-        let fake_session_id = uuid::Uuid::new_v4();
+        let fake_session_id = SessionId::new_v4();
         println!("  Attempting to join non-existent session: {}", fake_session_id);
 
         let join_result = client.join_session(fake_session_id).await;
@@ -617,20 +638,21 @@ async fn test_join_nonexistent_session() {
 
 /// Test: Lobby state updates are broadcast to connected clients
 #[tokio::test]
-#[ignore]
 async fn test_lobby_state_broadcast() {
     println!("=== Test: Lobby State Broadcast ===");
 
+    let server = TestServer::start().await;
+
     let result = timeout(TEST_TIMEOUT, async {
         // Client 1 connects and sits in lobby
-        let mut client1 = LobbyTestClient::connect("Broadcast_Watcher").await?;
+        let mut client1 = LobbyTestClient::connect(&server, "Broadcast_Watcher").await?;
         let (_, lobby_state) = client1.authenticate().await?;
         println!("  Watcher client connected");
 
         let initial_session_count = lobby_state.available_sessions.len();
 
         // Client 2 creates a session
-        let mut client2 = LobbyTestClient::connect("Broadcast_Creator").await?;
+        let mut client2 = LobbyTestClient::connect(&server, "Broadcast_Creator").await?;
         let (_, lobby_state2) = client2.authenticate().await?;
 
         let car_id = lobby_state2.car_configs.first().ok_or("No cars")?.id;
@@ -666,12 +688,13 @@ async fn test_lobby_state_broadcast() {
 
 /// Test: Player appears in lobby after leaving session
 #[tokio::test]
-#[ignore]
 async fn test_player_returns_to_lobby() {
     println!("=== Test: Player Returns to Lobby After Leaving Session ===");
 
+    let server = TestServer::start().await;
+
     let result = timeout(TEST_TIMEOUT, async {
-        let mut client = LobbyTestClient::connect("ReturnTest").await?;
+        let mut client = LobbyTestClient::connect(&server, "ReturnTest").await?;
         let (player_id, lobby_state) = client.authenticate().await?;
         println!("  Client authenticated: {}", player_id);
 
@@ -713,10 +736,11 @@ async fn test_player_returns_to_lobby() {
 
 /// Test: Session kinds are correctly set and visible
 #[tokio::test]
-#[ignore]
 async fn test_session_kinds() {
     println!("=== Test: Session Kinds ===");
 
+    let server = TestServer::start().await;
+
     let result = timeout(TEST_TIMEOUT, async {
         let kinds = [
             SessionKind::Practice,
@@ -725,7 +749,7 @@ async fn test_session_kinds() {
         ];
 
         for kind in kinds {
-            let mut client = LobbyTestClient::connect(&format!("KindTest_{:?}", kind)).await?;
+            let mut client = LobbyTestClient::connect(&server, &format!("KindTest_{:?}", kind)).await?;
             let (_, lobby_state) = client.authenticate().await?;
 
             let car_id = lobby_state.car_configs.first().ok_or("No cars")?.id;
@@ -758,13 +782,14 @@ async fn test_session_kinds() {
 
 /// Test: Disconnect while in session cleans up properly
 #[tokio::test]
-#[ignore]
 async fn test_disconnect_cleanup() {
     println!("=== Test: Disconnect Cleanup ===");
 
+    let server = TestServer::start().await;
+
     let result = timeout(TEST_TIMEOUT, async {
         // Host creates session
-        let mut host = LobbyTestClient::connect("DisconnectTest_Host").await?;
+        let mut host = LobbyTestClient::connect(&server, "DisconnectTest_Host").await?;
         let (_host_id, lobby_state) = host.authenticate().await?;
 
         let car_id = lobby_state.car_configs.first().ok_or("No cars")?.id;
@@ -775,7 +800,7 @@ async fn test_disconnect_cleanup() {
         println!("  Host created session: {}", session_id);
 
         // Joiner connects and joins
-        let mut joiner = LobbyTestClient::connect("DisconnectTest_Joiner").await?;
+        let mut joiner = LobbyTestClient::connect(&server, "DisconnectTest_Joiner").await?;
         joiner.authenticate().await?;
         joiner.select_car(car_id).await?;
         joiner.join_session(session_id).await?;
@@ -823,6 +848,7 @@ async fn test_disconnect_cleanup() {
 // =============================================================================
 
 /// Test: Demo mode with 2 players, different cars, Zandvoort track, timing 3 laps
+/// Ignored by default: it takes minutes and loads the cars and tracks in `content/`
 /// Run: cargo test --test lobby_integration_tests test_demo_mode_lap_timing -- --ignored --nocapture
 #[tokio::test]
 #[ignore]
@@ -835,11 +861,13 @@ async fn test_demo_mode_lap_timing() {
     println!("╚══════════════════════════════════════════════════════════════════════════════╝");
     println!();
 
+    let server = TestServer::start_with(common::use_repo_content).await;
+
     let result = timeout(Duration::from_secs(300), async {
         // Create 2 clients
         println!("Step 1: Creating and authenticating 2 clients...");
-        let mut client1 = LobbyTestClient::connect("DemoTest_Player1").await?;
-        let mut client2 = LobbyTestClient::connect("DemoTest_Player2").await?;
+        let mut client1 = LobbyTestClient::connect(&server, "DemoTest_Player1").await?;
+        let mut client2 = LobbyTestClient::connect(&server, "DemoTest_Player2").await?;
 
         let (player1_id, lobby_state) = client1.authenticate().await?;
         let (player2_id, _) = client2.authenticate().await?;
@@ -1049,40 +1077,3 @@ async fn test_demo_mode_lap_timing() {
         Err(_) => panic!("Test timed out after 300 seconds"),
     }
 }
-
-// =============================================================================
-// RUN ALL LOBBY TESTS
-// =============================================================================
-
-/// Run all lobby integration tests
-/// Execute with: cargo test --test lobby_integration_tests -- --ignored --nocapture
-#[tokio::test]
-#[ignore]
-async fn run_all_lobby_tests() {
-    println!("╔══════════════════════════════════════════════════════════════════════════════╗");
-    println!("║                    LOBBY INTEGRATION TEST SUITE                              ║");
-    println!("╠══════════════════════════════════════════════════════════════════════════════╣");
-    println!("║  NOTE: Server must be running (use VS Code task: 'Start Server')             ║");
-    println!("╚══════════════════════════════════════════════════════════════════════════════╝");
-    println!();
-    println!("To run individual tests:");
-    println!("  cargo test --test lobby_integration_tests test_create_session -- --ignored --nocapture");
-    println!("  cargo test --test lobby_integration_tests test_join_session -- --ignored --nocapture");
-    println!("  cargo test --test lobby_integration_tests test_leave_session -- --ignored --nocapture");
-    println!("  cargo test --test lobby_integration_tests test_max_players_limit -- --ignored --nocapture");
-    println!("  cargo test --test lobby_integration_tests test_rapid_join_leave -- --ignored --nocapture");
-    println!();
-    println!("This test file contains the following lobby-specific tests:");
-    println!("  - test_create_session: Create session and verify lobby state");
-    println!("  - test_join_session: Join an existing session");
-    println!("  - test_leave_session: Leave a session");
-    println!("  - test_session_cleanup_on_empty: Session removed when all leave");
-    println!("  - test_max_players_limit: Cannot join full session");
-    println!("  - test_rapid_join_leave: Stress test rapid join/leave");
-    println!("  - test_multiple_sessions: Multiple simultaneous sessions");
-    println!("  - test_join_nonexistent_session: Error on invalid session");
-    println!("  - test_lobby_state_broadcast: Lobby updates are broadcast");
-    println!("  - test_player_returns_to_lobby: Player returns after leaving");
-    println!("  - test_session_kinds: Different session kinds work");
-    println!("  - test_disconnect_cleanup: Abrupt disconnect cleanup");
-}
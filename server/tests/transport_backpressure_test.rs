@@ -52,12 +52,12 @@ async fn test_droppable_messages_are_dropped_when_queue_full() {
 
 #[tokio::test]
 async fn test_message_priority_classification() {
+    use apexsim_server::data::PlayerId;
     use apexsim_server::network::{MessagePriority, ServerMessage};
-    use uuid::Uuid;
 
     // Test that critical messages are correctly classified
     let auth_msg = ServerMessage::AuthSuccess(apexsim_server::network::AuthSuccessData {
-        player_id: Uuid::new_v4(),
+        player_id: PlayerId::new_v4(),
         server_version: 1,
     });
     assert_eq!(auth_msg.priority(), MessagePriority::Critical);
@@ -77,6 +77,8 @@ async fn test_message_priority_classification() {
         session_state: apexsim_server::data::SessionState::Racing,
         countdown_ms: None,
         car_states: vec![],
+        pace_car: None,
+        race_progress: None,
         game_mode: apexsim_server::data::GameMode::Lobby
     });
     assert_eq!(telemetry_msg.priority(), MessagePriority::Droppable);
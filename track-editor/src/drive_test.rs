@@ -0,0 +1,262 @@
+//! Test drive: a car run by the server's own physics (`apexsim-server`) on the
+//! track as it is being edited, so elevation, widths and corners can be felt
+//! without a server.
+
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+use apexsim_server::data::{
+    CarConfig, CarConfigId, CarState, DriverAids, GridSlot, PlayerId, PlayerInputData, TrackConfig,
+};
+use apexsim_server::physics::{self, CollisionSettings, TickTiming};
+use apexsim_server::track_loader::TrackLoader;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{CameraState, EditorMode, EditorState};
+
+/// How fast keyboard steering moves towards full lock and back (per second)
+const STEERING_RATE: f32 = 3.0;
+
+/// Longest frame the simulation catches up on, so a stall doesn't freeze it (s)
+const MAX_FRAME_TIME_S: f32 = 0.25;
+
+/// Chase camera distance when a drive starts (m)
+const CHASE_DISTANCE_M: f32 = 20.0;
+
+/// The test car, drawn as a box the size of the car
+#[derive(Component)]
+pub struct DriveTestCar;
+
+/// The drive in progress: the edited track as the server loads it, the car
+/// and the simulation clock
+struct DriveSession {
+    track: TrackConfig,
+    configs: HashMap<CarConfigId, CarConfig>,
+    state: CarState,
+    start: GridSlot,
+    timing: TickTiming,
+    tick: u32,
+    /// Frame time not yet simulated (s)
+    pending_s: f32,
+    steering: f32,
+}
+
+#[derive(Resource, Default)]
+pub struct DriveTestState {
+    session: Option<DriveSession>,
+}
+
+/// The edited track through the server's track loader, so the car drives
+/// exactly what a server would make of the saved file
+fn load_edited_track(editor_state: &EditorState) -> Result<TrackConfig, String> {
+    let loaded = editor_state.loaded_track.as_ref().ok_or("No track loaded")?;
+    let yaml = serde_yaml::to_string(&loaded.track_data).map_err(|e| format!("Failed to serialize track: {}", e))?;
+    TrackLoader::load_from_string(&yaml).map_err(|e| e.to_string())
+}
+
+/// First grid slot, or the start of the centerline when the track has none
+fn start_slot(track: &TrackConfig) -> Option<GridSlot> {
+    track.start_positions.first().cloned().or_else(|| {
+        let point = track.centerline.first()?;
+        Some(GridSlot { position: 1, x: point.x, y: point.y, z: point.z, yaw_rad: point.heading_rad })
+    })
+}
+
+fn start_session(editor_state: &EditorState) -> Result<DriveSession, String> {
+    let track = load_edited_track(editor_state)?;
+    let start = start_slot(&track).ok_or("The track has no centerline to start on")?;
+    let config = CarConfig::default();
+    let config_id = CarConfigId::nil();
+    Ok(DriveSession {
+        state: CarState::new(PlayerId::nil(), config_id, &start),
+        configs: HashMap::from([(config_id, config)]),
+        track,
+        start,
+        timing: TickTiming::default(),
+        tick: 0,
+        pending_s: 0.0,
+        steering: 0.0,
+    })
+}
+
+/// Arrow keys as driver inputs; steering eases towards the held direction
+/// the way a keyboard driver would feather a wheel
+fn keyboard_input(keyboard: &ButtonInput<KeyCode>, steering: &mut f32, dt: f32) -> PlayerInputData {
+    let held = |key: KeyCode| if keyboard.pressed(key) { 1.0 } else { 0.0 };
+    let target = held(KeyCode::ArrowLeft) - held(KeyCode::ArrowRight);
+    let step = STEERING_RATE * dt;
+    *steering += (target - *steering).clamp(-step, step);
+    PlayerInputData {
+        throttle: held(KeyCode::ArrowUp),
+        brake: held(KeyCode::ArrowDown),
+        steering: *steering,
+        gear: None,
+        clutch: None,
+    }
+}
+
+/// One server tick for the test car: its physics sub-steps with every aid
+/// on, walls, then lap progress
+fn step(session: &mut DriveSession, input: &PlayerInputData) {
+    let config = &session.configs[&CarConfigId::nil()];
+    let dt = session.timing.step_dt();
+    for _ in 0..session.timing.substeps {
+        let input = physics::apply_driver_aids(&mut session.state, config, input, DriverAids::unrestricted());
+        physics::update_car_3d(&mut session.state, config, &input, &session.track, dt);
+    }
+    physics::resolve_wall_collisions(
+        std::slice::from_mut(&mut session.state),
+        &session.configs,
+        &session.track.walls,
+        &CollisionSettings::default(),
+    );
+    session.tick += 1;
+    physics::update_track_progress_3d(&mut session.state, &session.track, session.tick, session.timing);
+}
+
+fn car_transform(state: &CarState, config: &CarConfig) -> Transform {
+    let o = state.orientation;
+    let rotation = Quat::from_xyzw(o.x, o.y, o.z, o.w);
+    Transform::from_translation(Vec3::new(state.pos_x, state.pos_y, state.pos_z) + rotation * Vec3::Z * config.height_m * 0.5)
+        .with_rotation(rotation)
+}
+
+/// Starts the drive on entering Drive mode: loads the edited track, puts
+/// the car on the grid and brings the camera in behind it
+pub fn drive_test_start_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut editor_state: ResMut<EditorState>,
+    mut drive_test: ResMut<DriveTestState>,
+    mut mode: ResMut<EditorMode>,
+    mut camera_state: ResMut<CameraState>,
+) {
+    if drive_test.session.is_some() {
+        return;
+    }
+    match start_session(&editor_state) {
+        Ok(session) => {
+            let config = &session.configs[&CarConfigId::nil()];
+            commands.spawn((
+                PbrBundle {
+                    mesh: meshes.add(Cuboid::new(config.length_m, config.width_m, config.height_m)),
+                    material: materials.add(StandardMaterial {
+                        base_color: Color::srgb(0.85, 0.1, 0.1),
+                        ..default()
+                    }),
+                    transform: car_transform(&session.state, config),
+                    ..default()
+                },
+                DriveTestCar,
+            ));
+            camera_state.distance = CHASE_DISTANCE_M;
+            drive_test.session = Some(session);
+        }
+        Err(e) => {
+            editor_state.status_message = Some(format!("Can't test drive: {}", e));
+            *mode = EditorMode::default();
+        }
+    }
+}
+
+/// Runs the car from the arrow keys in real time with the camera chasing
+/// it. R puts the car back on the grid and Escape ends the drive
+pub fn drive_test_system(
+    mut drive_test: ResMut<DriveTestState>,
+    mut mode: ResMut<EditorMode>,
+    mut camera_state: ResMut<CameraState>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    mut car_query: Query<&mut Transform, With<DriveTestCar>>,
+) {
+    let Some(session) = drive_test.session.as_mut() else {
+        return;
+    };
+
+    if keyboard.just_pressed(KeyCode::Escape) {
+        *mode = EditorMode::default();
+        return;
+    }
+    if keyboard.just_pressed(KeyCode::KeyR) {
+        session.state = CarState::new(PlayerId::nil(), CarConfigId::nil(), &session.start);
+        session.steering = 0.0;
+    }
+
+    session.pending_s += time.delta_seconds().min(MAX_FRAME_TIME_S);
+    let tick_dt = session.timing.tick_dt();
+    while session.pending_s >= tick_dt {
+        session.pending_s -= tick_dt;
+        let input = keyboard_input(&keyboard, &mut session.steering, tick_dt);
+        step(session, &input);
+    }
+
+    let config = &session.configs[&CarConfigId::nil()];
+    let transform = car_transform(&session.state, config);
+    if let Ok(mut car) = car_query.get_single_mut() {
+        *car = transform;
+    }
+    // Chase from behind, leaving the zoom and height to the mouse
+    camera_state.focus = transform.translation;
+    camera_state.yaw = session.state.yaw_rad + PI;
+}
+
+/// Takes the test car away once the drive ends, by leaving Drive mode or
+/// the editor
+pub fn drive_test_stop_system(
+    mut commands: Commands,
+    mut drive_test: ResMut<DriveTestState>,
+    car_query: Query<Entity, With<DriveTestCar>>,
+) {
+    if drive_test.session.take().is_none() {
+        return;
+    }
+    for entity in car_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Speed, gearbox, lap and surface readouts while driving
+pub fn drive_test_panel_system(mut contexts: EguiContexts, drive_test: Res<DriveTestState>) {
+    let Some(session) = &drive_test.session else {
+        return;
+    };
+    let state = &session.state;
+    let lap_time = |ms: u32| format!("{}:{:02}.{:03}", ms / 60_000, ms / 1000 % 60, ms % 1000);
+
+    egui::Window::new("Test Drive")
+        .default_pos([10.0, 260.0])
+        .default_size([220.0, 220.0])
+        .show(contexts.ctx_mut(), |ui| {
+            ui.heading(format!("{:.0} km/h", state.speed_mps * 3.6));
+            let gear = match state.gear {
+                -1 => "R".to_string(),
+                0 => "N".to_string(),
+                gear => gear.to_string(),
+            };
+            ui.label(format!("Gear: {}   {:.0} rpm", gear, state.engine_rpm));
+            ui.add_space(5.0);
+            ui.label(format!("Lap: {}", state.current_lap));
+            ui.label(format!("Current: {}", lap_time(state.current_lap_time_ms)));
+            if let Some(ms) = state.last_lap_time_ms {
+                ui.label(format!("Last: {}", lap_time(ms)));
+            }
+            if let Some(ms) = state.best_lap_time_ms {
+                ui.label(format!("Best: {}", lap_time(ms)));
+            }
+            ui.add_space(5.0);
+            ui.label(format!("Surface: {:?}", state.current_surface));
+            if !state.is_on_track {
+                ui.colored_label(egui::Color32::YELLOW, "Off track");
+            }
+            ui.label(format!("Offset from centerline: {:.1} m", state.lateral_offset_m));
+            if state.is_overturned || !state.damage.is_drivable {
+                ui.colored_label(egui::Color32::RED, "Car is out - press R to restart");
+            }
+            ui.add_space(10.0);
+            ui.label("Arrow keys - Drive and steer");
+            ui.label("R - Back to the grid");
+            ui.label("Esc - Stop driving");
+        });
+}
@@ -0,0 +1,183 @@
+//! New tracks from a recorded or exported centerline: GPX/KML traces and CSV
+//! points in metres go through the server's importer (`track_import`), which
+//! resamples them to evenly spaced nodes, smooths them and finds a closed lap.
+
+use std::path::{Path, PathBuf};
+
+use apexsim_server::track_import::{self, ElevationMode, ImportOptions};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::track_data::TrackFileFormat;
+use crate::track_mesh::DEFAULT_WIDTH_M;
+use crate::{open_new_track, AppState, EditorState};
+
+/// Whether the imported centerline should close into a lap
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+enum LapChoice {
+    /// A lap if the trace comes back on itself, else a road course
+    #[default]
+    Detect,
+    Closed,
+    Open,
+}
+
+#[derive(Resource)]
+pub struct ImportDialog {
+    pub open: bool,
+    source: Option<PathBuf>,
+    name: String,
+    width_m: f32,
+    node_spacing_m: f64,
+    smoothing_m: f64,
+    lap: LapChoice,
+    flat: bool,
+    error: Option<String>,
+}
+
+impl Default for ImportDialog {
+    fn default() -> Self {
+        let options = ImportOptions::default();
+        Self {
+            open: false,
+            source: None,
+            name: String::new(),
+            width_m: DEFAULT_WIDTH_M,
+            node_spacing_m: options.node_spacing_m,
+            smoothing_m: options.smoothing_m,
+            lap: LapChoice::Detect,
+            flat: false,
+            error: None,
+        }
+    }
+}
+
+impl ImportDialog {
+    fn options(&self) -> ImportOptions {
+        ImportOptions {
+            node_spacing_m: self.node_spacing_m,
+            smoothing_m: self.smoothing_m,
+            elevation: if self.flat { ElevationMode::Flat } else { ElevationMode::Recorded },
+            width_m: self.width_m,
+            closed_loop: match self.lap {
+                LapChoice::Detect => None,
+                LapChoice::Closed => Some(true),
+                LapChoice::Open => Some(false),
+            },
+            ..Default::default()
+        }
+    }
+}
+
+/// CSV is read as points in metres, anything else as a GPS trace
+fn import_track(source: &Path, name: &str, options: &ImportOptions) -> Result<TrackFileFormat, String> {
+    let is_csv = source.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("csv"));
+    let track = if is_csv {
+        let text = std::fs::read_to_string(source).map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+        let points = track_import::parse_csv(&text).map_err(|e| e.to_string())?;
+        track_import::points_to_track(&points, name, options)
+    } else {
+        let trace = track_import::read_trace(source).map_err(|e| e.to_string())?;
+        track_import::trace_to_track(&trace, name, options)
+    }
+    .map_err(|e| e.to_string())?;
+
+    // The server's track file as the editor's, by way of their shared YAML form
+    let yaml = serde_yaml::to_value(&track).map_err(|e| format!("Failed to convert the track: {}", e))?;
+    serde_yaml::from_value(yaml).map_err(|e| format!("Failed to convert the track: {}", e))
+}
+
+/// The import window on the track list: source file, track name and how the
+/// centerline is laid out
+pub fn import_dialog_system(
+    mut contexts: EguiContexts,
+    mut editor_state: ResMut<EditorState>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut dialog: ResMut<ImportDialog>,
+) {
+    if !dialog.open {
+        return;
+    }
+    let mut open = true;
+    let mut import_clicked = false;
+
+    egui::Window::new("Import Centerline")
+        .open(&mut open)
+        .collapsible(false)
+        .default_width(360.0)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("A GPX or KML recording, or a CSV file of x,y or x,y,z points in metres.");
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                let source = dialog.source.as_ref().map_or("No file chosen".to_string(), |path| path.display().to_string());
+                ui.label(source);
+                if ui.button("Choose...").clicked() {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .set_title("Import centerline")
+                        .add_filter("Centerline", &["csv", "gpx", "kml"])
+                        .pick_file()
+                    {
+                        dialog.name = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+                        dialog.source = Some(path);
+                        dialog.error = None;
+                    }
+                }
+            });
+            ui.add_space(5.0);
+
+            egui::Grid::new("import_options").num_columns(2).show(ui, |ui| {
+                ui.label("Name");
+                ui.text_edit_singleline(&mut dialog.name);
+                ui.end_row();
+                ui.label("Track width");
+                ui.add(egui::DragValue::new(&mut dialog.width_m).speed(0.1).range(3.0..=60.0).suffix(" m"));
+                ui.end_row();
+                ui.label("Node spacing");
+                ui.add(egui::DragValue::new(&mut dialog.node_spacing_m).speed(0.1).range(1.0..=100.0).suffix(" m"));
+                ui.end_row();
+                ui.label("Smoothing");
+                ui.add(egui::DragValue::new(&mut dialog.smoothing_m).speed(0.5).range(0.0..=200.0).suffix(" m"));
+                ui.end_row();
+                ui.label("Lap");
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut dialog.lap, LapChoice::Detect, "Detect");
+                    ui.selectable_value(&mut dialog.lap, LapChoice::Closed, "Closed");
+                    ui.selectable_value(&mut dialog.lap, LapChoice::Open, "Open");
+                });
+                ui.end_row();
+                ui.label("Elevation");
+                ui.checkbox(&mut dialog.flat, "Flatten");
+                ui.end_row();
+            });
+
+            if let Some(error) = &dialog.error {
+                ui.add_space(5.0);
+                ui.colored_label(egui::Color32::RED, error);
+            }
+            ui.add_space(10.0);
+            let ready = dialog.source.is_some() && !dialog.name.trim().is_empty();
+            import_clicked = ui.add_enabled(ready, egui::Button::new("Import")).clicked();
+        });
+
+    dialog.open = open;
+    let (true, Some(source)) = (import_clicked, dialog.source.clone()) else {
+        return;
+    };
+    match import_track(&source, dialog.name.trim(), &dialog.options()) {
+        Ok(track_data) => {
+            let length_m = track_data.metadata.as_ref().and_then(|metadata| metadata.length_m).unwrap_or_default();
+            let summary = format!(
+                "Imported {} nodes, {} of {:.2} km from {}",
+                track_data.nodes.len(),
+                if track_data.closed_loop { "a closed lap" } else { "an open course" },
+                length_m / 1000.0,
+                source.display()
+            );
+            open_new_track(&mut editor_state, track_data, None, source.parent());
+            editor_state.status_message = Some(summary);
+            *dialog = ImportDialog::default();
+            next_state.set(AppState::Editor);
+        }
+        Err(e) => dialog.error = Some(e),
+    }
+}
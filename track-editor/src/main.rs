@@ -1,12 +1,28 @@
+mod drive_test;
+mod import_dialog;
+mod new_track;
+mod node_editor;
+mod placement_editor;
+mod raceline_editor;
+mod timing_editor;
 mod track_data;
+mod track_file;
 mod track_mesh;
 
 use bevy::prelude::*;
 use bevy::input::mouse::{MouseMotion, MouseWheel};
+use bevy::window::WindowCloseRequested;
 use bevy_egui::{egui, EguiContexts, EguiPlugin};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use track_data::{TrackFileFormat, ProceduralWorldData, TerrainHeightmap};
+use drive_test::DriveTestState;
+use import_dialog::ImportDialog;
+use new_track::NewTrackWizard;
+use node_editor::NodeEditState;
+use placement_editor::PlacementState;
+use raceline_editor::RacelineEditState;
+use timing_editor::TimingEditState;
+use track_data::{TrackFileFormat, ProceduralWorldData, Splatmap, TerrainHeightmap, TerrainLod};
 
 fn main() {
     App::new()
@@ -16,21 +32,62 @@ fn main() {
                 resolution: (1600., 900.).into(),
                 ..default()
             }),
+            // Closing is confirmed first when there are unsaved edits
+            close_when_requested: false,
             ..default()
         }))
         .add_plugins(EguiPlugin)
         .init_state::<AppState>()
         .insert_resource(EditorState::default())
         .insert_resource(CameraState::default())
+        .insert_resource(NodeEditState::default())
+        .insert_resource(RacelineEditState::default())
+        .init_resource::<PlacementState>()
+        .init_resource::<TimingEditState>()
+        .init_resource::<DriveTestState>()
+        .init_resource::<EditorMode>()
+        .init_resource::<ImportDialog>()
+        .init_resource::<NewTrackWizard>()
+        .add_systems(Update, window_close_system)
         .add_systems(Update, splash_screen_system.run_if(in_state(AppState::Splash)))
         .add_systems(OnEnter(AppState::Browse), setup_browse_state)
-        .add_systems(Update, browse_screen_system.run_if(in_state(AppState::Browse)))
+        .add_systems(Update, (browse_screen_system, import_dialog::import_dialog_system, new_track::new_track_wizard_system).chain().run_if(in_state(AppState::Browse)))
         .add_systems(OnEnter(AppState::Editor), setup_editor)
         .add_systems(Update, (
             editor_ui_system,
+            (
+                drive_test::drive_test_start_system,
+                drive_test::drive_test_system,
+            ).chain().run_if(resource_equals(EditorMode::Drive)),
             camera_controller_system,
-        ).run_if(in_state(AppState::Editor)))
-        .add_systems(OnExit(AppState::Editor), cleanup_editor)
+            (
+                node_editor::node_panel_system,
+                node_editor::node_keyboard_system,
+                node_editor::node_pick_and_drag_system,
+            ).chain().run_if(resource_equals(EditorMode::Nodes)),
+            (
+                raceline_editor::raceline_panel_system,
+                raceline_editor::raceline_pick_and_drag_system,
+            ).chain().run_if(resource_equals(EditorMode::Raceline)),
+            (
+                placement_editor::placement_panel_system,
+                placement_editor::placement_keyboard_system,
+                placement_editor::placement_click_system,
+            ).chain().run_if(resource_equals(EditorMode::Placement)),
+            (
+                timing_editor::timing_panel_system,
+                timing_editor::timing_keyboard_system,
+                timing_editor::timing_click_system,
+            ).chain().run_if(resource_equals(EditorMode::Timing)),
+            drive_test::drive_test_panel_system.run_if(resource_equals(EditorMode::Drive)),
+            drive_test::drive_test_stop_system.run_if(not(resource_equals(EditorMode::Drive))),
+            node_editor::track_mesh_update_system,
+            node_editor::node_gizmo_system.run_if(resource_equals(EditorMode::Nodes)),
+            raceline_editor::raceline_gizmo_system,
+            placement_editor::placement_gizmo_system.run_if(resource_equals(EditorMode::Placement)),
+            timing_editor::timing_gizmo_system.run_if(resource_equals(EditorMode::Timing)),
+        ).chain().run_if(in_state(AppState::Editor)))
+        .add_systems(OnExit(AppState::Editor), (drive_test::drive_test_stop_system, cleanup_editor))
         .run();
 }
 
@@ -50,6 +107,30 @@ struct EditorState {
     loaded_track: Option<LoadedTrack>,
     splash_timer: f32,
     folder_input: String,
+    /// Outcome of the last save, shown in the editor's top bar
+    status_message: Option<String>,
+    /// Leaving the editor, waiting on what to do with unsaved edits
+    pending_exit: Option<PendingExit>,
+}
+
+/// What left clicks in the 3D view edit
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq)]
+enum EditorMode {
+    #[default]
+    Nodes,
+    Raceline,
+    /// Start grid slots and pit boxes
+    Placement,
+    /// Start/finish line, sector splits and DRS zones
+    Timing,
+    /// Driving the track in a test car; nothing is edited
+    Drive,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum PendingExit {
+    TrackList,
+    Quit,
 }
 
 #[derive(Clone)]
@@ -61,8 +142,15 @@ struct TrackEntry {
 
 struct LoadedTrack {
     name: String,
+    yaml_path: PathBuf,
     track_data: TrackFileFormat,
     terrain_data: Option<ProceduralWorldData>,
+    /// The track has edits that aren't saved yet
+    dirty: bool,
+    /// The terrain was changed too and its cache needs writing on save
+    terrain_dirty: bool,
+    /// The original files were copied to `.bak` by an earlier save
+    backed_up: bool,
 }
 
 #[derive(Resource)]
@@ -94,9 +182,16 @@ struct EditorCamera;
 #[derive(Component)]
 struct TrackMeshEntity;
 
+/// The road surface, rebuilt whenever its nodes are edited
+#[derive(Component)]
+struct TrackSurface;
+
 #[derive(Component)]
 struct TerrainMeshEntity;
 
+#[derive(Component)]
+struct TracksideObjectEntity;
+
 // Splash screen system
 fn splash_screen_system(
     mut contexts: EguiContexts,
@@ -183,6 +278,8 @@ fn browse_screen_system(
     mut contexts: EguiContexts,
     mut editor_state: ResMut<EditorState>,
     mut next_state: ResMut<NextState<AppState>>,
+    mut import_dialog: ResMut<ImportDialog>,
+    mut new_track_wizard: ResMut<NewTrackWizard>,
 ) {
     egui::CentralPanel::default().show(contexts.ctx_mut(), |ui| {
         ui.vertical_centered(|ui| {
@@ -235,6 +332,14 @@ fn browse_screen_system(
                     scan_tracks_folder(&mut editor_state);
                 }
             }
+
+            ui.separator();
+            if ui.button("New Track...").clicked() {
+                new_track_wizard.open = true;
+            }
+            if ui.button("Import Centerline...").clicked() {
+                import_dialog.open = true;
+            }
         });
 
         ui.add_space(20.0);
@@ -279,9 +384,14 @@ fn browse_screen_system(
 
                                 editor_state.loaded_track = Some(LoadedTrack {
                                     name: track_entry.name.clone(),
+                                    yaml_path: track_entry.yaml_path.clone(),
                                     track_data,
                                     terrain_data,
+                                    dirty: false,
+                                    terrain_dirty: false,
+                                    backed_up: false,
                                 });
+                                editor_state.status_message = None;
 
                                 next_state.set(AppState::Editor);
                             }
@@ -300,6 +410,12 @@ fn setup_editor(
     editor_state: Res<EditorState>,
     mut camera_state: ResMut<CameraState>,
 ) {
+    commands.insert_resource(NodeEditState::default());
+    commands.insert_resource(RacelineEditState::default());
+    commands.insert_resource(PlacementState::default());
+    commands.insert_resource(TimingEditState::default());
+    commands.insert_resource(EditorMode::default());
+
     // Add light
     commands.spawn((
         DirectionalLightBundle {
@@ -340,7 +456,7 @@ fn setup_editor(
         camera_state.pitch = -0.7;
 
         // Generate track mesh from centerline nodes
-        let track_mesh = track_mesh::generate_track_mesh(&loaded.track_data.nodes, true);
+        let track_mesh = track_mesh::generate_track_mesh(&loaded.track_data.nodes, loaded.track_data.closed_loop);
 
         commands.spawn((
             PbrBundle {
@@ -353,22 +469,20 @@ fn setup_editor(
                 ..default()
             },
             TrackMeshEntity,
+            TrackSurface,
         ));
 
         // Generate terrain mesh if available
         if let Some(terrain) = &loaded.terrain_data {
             if let Some(heightmap) = &terrain.heightmap {
-                let terrain_mesh = generate_terrain_mesh(heightmap);
+                let (grid, splat_step) = editor_terrain_level(heightmap, terrain.lod.as_ref());
+                let terrain_mesh = generate_terrain_mesh(grid, terrain.preset.ground_color, terrain.splatmap.as_ref(), splat_step);
 
                 commands.spawn((
                     PbrBundle {
                         mesh: meshes.add(terrain_mesh),
                         material: materials.add(StandardMaterial {
-                            base_color: Color::srgb(
-                                terrain.preset.ground_color[0],
-                                terrain.preset.ground_color[1],
-                                terrain.preset.ground_color[2],
-                            ),
+                            base_color: Color::WHITE,
                             perceptual_roughness: 0.95,
                             ..default()
                         }),
@@ -377,6 +491,25 @@ fn setup_editor(
                     TerrainMeshEntity,
                 ));
             }
+
+            if let Some(water_mesh) = generate_water_mesh(&terrain.water) {
+                commands.spawn((
+                    PbrBundle {
+                        mesh: meshes.add(water_mesh),
+                        material: materials.add(StandardMaterial {
+                            base_color: Color::srgb(0.15, 0.3, 0.4),
+                            perceptual_roughness: 0.1,
+                            double_sided: true,
+                            cull_mode: None,
+                            ..default()
+                        }),
+                        ..default()
+                    },
+                    TerrainMeshEntity,
+                ));
+            }
+
+            spawn_trackside_objects(&mut commands, &mut meshes, &mut materials, &terrain.objects);
         }
 
         // Calculate initial camera position
@@ -394,6 +527,103 @@ fn setup_editor(
     }
 }
 
+/// Stand-in box size (length, width, height in meters) and color of an object kind
+fn object_primitive(kind: &str) -> (Vec3, Color) {
+    match kind {
+        "grandstand" => (Vec3::new(12.0, 30.0, 8.0), Color::srgb(0.6, 0.6, 0.65)),
+        "marshal_post" => (Vec3::new(2.0, 2.0, 3.0), Color::srgb(1.0, 0.5, 0.0)),
+        "tire_wall" => (Vec3::new(1.2, 1.2, 1.2), Color::srgb(0.1, 0.1, 0.1)),
+        "barrier" => (Vec3::new(12.0, 0.3, 0.8), Color::srgb(0.8, 0.8, 0.8)),
+        k if k.starts_with("tree") => (Vec3::new(4.0, 4.0, 10.0), Color::srgb(0.15, 0.35, 0.15)),
+        k if k.starts_with("building") => (Vec3::new(15.0, 15.0, 12.0), Color::srgb(0.55, 0.5, 0.45)),
+        k if k.starts_with("rock") => (Vec3::new(3.0, 3.0, 2.0), Color::srgb(0.45, 0.43, 0.4)),
+        _ => (Vec3::new(2.0, 2.0, 1.5), Color::srgb(0.3, 0.45, 0.2)),
+    }
+}
+
+/// Spawn the world's trackside objects as boxes, sharing one mesh and material per kind
+fn spawn_trackside_objects(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    objects: &[track_data::TracksideObject],
+) {
+    let mut handles = std::collections::HashMap::new();
+    for object in objects {
+        let (size, color) = object_primitive(&object.kind);
+        let (mesh, material) = handles
+            .entry(object.kind.as_str())
+            .or_insert_with(|| (meshes.add(Cuboid::from_size(size)), materials.add(StandardMaterial { base_color: color, ..default() })))
+            .clone();
+        // Boxes are centred; lift them to rest on the ground
+        let translation = Vec3::new(object.x, object.y, object.z + size.z * object.scale / 2.0);
+        commands.spawn((
+            PbrBundle {
+                mesh,
+                material,
+                transform: Transform::from_translation(translation)
+                    .with_rotation(Quat::from_rotation_z(object.heading_rad))
+                    .with_scale(Vec3::splat(object.scale)),
+                ..default()
+            },
+            TracksideObjectEntity,
+        ));
+    }
+}
+
+/// Lakes as flat quads at their level and rivers as ribbons along their
+/// course; the terrain hides whatever reaches under the banks
+fn generate_water_mesh(water: &track_data::WaterFeatures) -> Option<Mesh> {
+    if water.lakes.is_empty() && water.rivers.is_empty() {
+        return None;
+    }
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+    let mut quad = |corners: [[f32; 3]; 4]| {
+        let first = positions.len() as u32;
+        positions.extend(corners);
+        indices.extend([first, first + 1, first + 2, first + 1, first + 3, first + 2]);
+    };
+
+    for lake in &water.lakes {
+        for rect in &lake.rects {
+            quad([
+                [rect[0], rect[1], lake.level],
+                [rect[2], rect[1], lake.level],
+                [rect[0], rect[3], lake.level],
+                [rect[2], rect[3], lake.level],
+            ]);
+        }
+    }
+
+    for river in &water.rivers {
+        let banks: Vec<([f32; 3], [f32; 3])> = (0..river.points.len())
+            .map(|i| {
+                let before = river.points[i.saturating_sub(1)];
+                let after = river.points[(i + 1).min(river.points.len() - 1)];
+                let point = river.points[i];
+                let direction = Vec2::new(after.x - before.x, after.y - before.y).normalize_or_zero();
+                let left = direction.perp() * point.width / 2.0;
+                (
+                    [point.x - left.x, point.y - left.y, point.level],
+                    [point.x + left.x, point.y + left.y, point.level],
+                )
+            })
+            .collect();
+        for pair in banks.windows(2) {
+            let ((right0, left0), (right1, left1)) = (pair[0], pair[1]);
+            quad([right0, right1, left0, left1]);
+        }
+    }
+
+    let normals = vec![[0.0, 0.0, 1.0]; positions.len()];
+    let mut mesh = Mesh::new(bevy::render::mesh::PrimitiveTopology::TriangleList, default());
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_indices(bevy::render::mesh::Indices::U32(indices));
+    Some(mesh)
+}
+
 fn calculate_camera_position(state: &CameraState) -> Vec3 {
     let x = state.distance * state.pitch.cos() * state.yaw.cos();
     let y = state.distance * state.pitch.cos() * state.yaw.sin();
@@ -402,14 +632,43 @@ fn calculate_camera_position(state: &CameraState) -> Vec3 {
     state.focus + Vec3::new(x, y, z)
 }
 
-fn generate_terrain_mesh(heightmap: &TerrainHeightmap) -> Mesh {
+/// Splatmap layer colors (sRGB); grass takes the biome's ground color
+const DIRT_COLOR: [f32; 3] = [0.42, 0.33, 0.24];
+const ROCK_COLOR: [f32; 3] = [0.45, 0.43, 0.4];
+const SAND_COLOR: [f32; 3] = [0.82, 0.74, 0.54];
+
+/// Vertex color of a cell: its splatmap layers blended, or the flat ground
+/// color for caches without a splatmap
+fn ground_cover_color(ground_color: [f32; 3], splatmap: Option<&Splatmap>, x: usize, y: usize) -> [f32; 4] {
+    let weights = splatmap.map_or([1.0, 0.0, 0.0, 0.0], |splat| splat.weights_at(x, y));
+    let blended: [f32; 3] = std::array::from_fn(|c| {
+        ground_color[c] * weights[0] + DIRT_COLOR[c] * weights[1] + ROCK_COLOR[c] * weights[2] + SAND_COLOR[c] * weights[3]
+    });
+    Color::srgb(blended[0], blended[1], blended[2]).to_linear().to_f32_array()
+}
+
+/// Widest terrain grid the editor renders at full detail
+const EDITOR_TERRAIN_CELLS: usize = 256;
+
+/// Finest level of detail narrow enough to render whole, with how many
+/// full-resolution cells one of its cells spans
+fn editor_terrain_level<'a>(heightmap: &'a TerrainHeightmap, lod: Option<&'a TerrainLod>) -> (&'a TerrainHeightmap, usize) {
+    std::iter::once(heightmap)
+        .chain(lod.into_iter().flat_map(|lod| lod.levels.iter()))
+        .enumerate()
+        .find(|(_, grid)| grid.width <= EDITOR_TERRAIN_CELLS)
+        .map_or((heightmap, 1), |(level, grid)| (grid, 1 << level))
+}
+
+fn generate_terrain_mesh(heightmap: &TerrainHeightmap, ground_color: [f32; 3], splatmap: Option<&Splatmap>, splat_step: usize) -> Mesh {
     let mut positions: Vec<[f32; 3]> = Vec::new();
     let mut normals: Vec<[f32; 3]> = Vec::new();
     let mut uvs: Vec<[f32; 2]> = Vec::new();
+    let mut colors: Vec<[f32; 4]> = Vec::new();
     let mut indices: Vec<u32> = Vec::new();
 
-    // Downsample for performance (render every nth cell)
-    let step = 2.max(heightmap.width / 256);
+    // Downsample grids too wide to render whole (render every nth cell)
+    let step = if heightmap.width <= EDITOR_TERRAIN_CELLS { 1 } else { 2.max(heightmap.width / EDITOR_TERRAIN_CELLS) };
 
     let sampled_width = heightmap.width / step;
     let sampled_height = heightmap.height / step;
@@ -426,6 +685,7 @@ fn generate_terrain_mesh(heightmap: &TerrainHeightmap) -> Mesh {
 
             positions.push([world_x, world_y, world_z]);
             normals.push([0.0, 0.0, 1.0]); // Will be calculated properly
+            colors.push(ground_cover_color(ground_color, splatmap, x * splat_step, y * splat_step));
             uvs.push([
                 gx as f32 / sampled_width as f32,
                 gy as f32 / sampled_height as f32,
@@ -481,27 +741,159 @@ fn generate_terrain_mesh(heightmap: &TerrainHeightmap) -> Mesh {
     mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
     mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
     mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
     mesh.insert_indices(bevy::render::mesh::Indices::U32(indices));
 
     mesh
 }
 
+/// `<folder>/<name>.yaml`, numbered when a track of that name is already there
+fn new_track_path(folder: &Path, name: &str) -> PathBuf {
+    (1..)
+        .map(|n| match n {
+            1 => folder.join(format!("{}.yaml", name)),
+            n => folder.join(format!("{} {}.yaml", name, n)),
+        })
+        .find(|path| !path.exists())
+        .unwrap_or_default()
+}
+
+/// Open an imported or generated track in the editor as an unsaved new
+/// track in the tracks folder, or else in `fallback_folder`
+fn open_new_track(
+    editor_state: &mut EditorState,
+    track_data: TrackFileFormat,
+    terrain_data: Option<ProceduralWorldData>,
+    fallback_folder: Option<&Path>,
+) {
+    let folder = editor_state
+        .tracks_folder
+        .clone()
+        .or_else(|| fallback_folder.map(Path::to_path_buf))
+        .unwrap_or_default();
+    let yaml_path = new_track_path(&folder, &track_data.name);
+    editor_state.loaded_track = Some(LoadedTrack {
+        name: yaml_path.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+        yaml_path,
+        track_data,
+        terrain_dirty: terrain_data.is_some(),
+        terrain_data,
+        dirty: true,
+        backed_up: false,
+    });
+}
+
+/// Save the loaded track over its file, or to `save_as`. The first save over
+/// a file backs up the original; Save As gets the copy its own track id so
+/// lap records stay apart, and always writes the terrain next to it
+fn save_loaded_track(editor_state: &mut EditorState, save_as: Option<PathBuf>) -> bool {
+    let Some(loaded) = editor_state.loaded_track.as_mut() else {
+        return false;
+    };
+
+    let result = match save_as {
+        Some(path) => {
+            let mut track_data = loaded.track_data.clone();
+            track_data.track_id = Some(uuid::Uuid::new_v4().to_string());
+            track_file::save_track(&path, &track_data, loaded.terrain_data.as_ref(), true).map(|()| {
+                loaded.name = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+                loaded.track_data = track_data;
+                loaded.yaml_path = path;
+            })
+        }
+        None => {
+            let terrain = loaded.terrain_data.as_ref().filter(|_| loaded.terrain_dirty);
+            track_file::save_track(&loaded.yaml_path, &loaded.track_data, terrain, !loaded.backed_up)
+        }
+    };
+
+    match result {
+        Ok(()) => {
+            loaded.dirty = false;
+            loaded.terrain_dirty = false;
+            loaded.backed_up = true;
+            editor_state.status_message = Some(format!("Saved {}", loaded.yaml_path.display()));
+            scan_tracks_folder(editor_state);
+            true
+        }
+        Err(e) => {
+            editor_state.status_message = Some(e);
+            false
+        }
+    }
+}
+
+fn pick_save_as_path(editor_state: &EditorState) -> Option<PathBuf> {
+    let loaded = editor_state.loaded_track.as_ref()?;
+    let mut dialog = rfd::FileDialog::new()
+        .set_title("Save track as")
+        .add_filter("Track", &["yaml"])
+        .set_file_name(format!("{}.yaml", loaded.name));
+    if let Some(folder) = loaded.yaml_path.parent() {
+        dialog = dialog.set_directory(folder);
+    }
+    dialog.save_file()
+}
+
+/// Close the window right away, or ask first when there are unsaved edits
+fn window_close_system(
+    mut commands: Commands,
+    mut close_requests: EventReader<WindowCloseRequested>,
+    mut editor_state: ResMut<EditorState>,
+) {
+    for request in close_requests.read() {
+        if editor_state.loaded_track.as_ref().is_some_and(|loaded| loaded.dirty) {
+            editor_state.pending_exit = Some(PendingExit::Quit);
+        } else {
+            commands.entity(request.window).despawn();
+        }
+    }
+}
+
 fn editor_ui_system(
     mut contexts: EguiContexts,
-    editor_state: Res<EditorState>,
+    mut editor_state: ResMut<EditorState>,
     mut next_state: ResMut<NextState<AppState>>,
+    mut app_exit: EventWriter<AppExit>,
+    mut mode: ResMut<EditorMode>,
     camera_state: Res<CameraState>,
 ) {
+    let dirty = editor_state.loaded_track.as_ref().is_some_and(|loaded| loaded.dirty);
+    let mut back_clicked = false;
+    let mut save_clicked = contexts.ctx_mut().input(|i| i.modifiers.command && i.key_pressed(egui::Key::S));
+    let mut save_as_clicked = false;
+
     egui::TopBottomPanel::top("top_panel").show(contexts.ctx_mut(), |ui| {
         ui.horizontal(|ui| {
-            if ui.button("< Back to Track List").clicked() {
-                next_state.set(AppState::Browse);
+            back_clicked = ui.button("< Back to Track List").clicked();
+
+            ui.separator();
+
+            save_clicked |= ui.add_enabled(dirty, egui::Button::new("Save")).clicked();
+            save_as_clicked = ui.button("Save As...").clicked();
+
+            ui.separator();
+
+            ui.label("Edit:");
+            ui.selectable_value(&mut *mode, EditorMode::Nodes, "Nodes");
+            ui.selectable_value(&mut *mode, EditorMode::Raceline, "Raceline");
+            ui.selectable_value(&mut *mode, EditorMode::Placement, "Grid & Pits");
+            ui.selectable_value(&mut *mode, EditorMode::Timing, "Timing");
+
+            ui.separator();
+
+            if *mode == EditorMode::Drive {
+                if ui.button("Stop Driving").clicked() {
+                    *mode = EditorMode::default();
+                }
+            } else if ui.button("Test Drive").clicked() {
+                *mode = EditorMode::Drive;
             }
 
             ui.separator();
 
             if let Some(loaded) = &editor_state.loaded_track {
-                ui.label(format!("Track: {}", loaded.name));
+                ui.label(format!("Track: {}{}", loaded.name, if loaded.dirty { " *" } else { "" }));
                 ui.separator();
                 ui.label(format!("Nodes: {}", loaded.track_data.nodes.len()));
 
@@ -509,10 +901,77 @@ fn editor_ui_system(
                     ui.separator();
                     ui.label("Terrain: Loaded");
                 }
+
+                if loaded.dirty {
+                    ui.separator();
+                    ui.colored_label(egui::Color32::YELLOW, "Unsaved changes");
+                }
+            }
+
+            if let Some(message) = &editor_state.status_message {
+                ui.separator();
+                ui.label(message);
             }
         });
     });
 
+    if save_clicked && dirty {
+        save_loaded_track(&mut editor_state, None);
+    }
+    if save_as_clicked {
+        if let Some(path) = pick_save_as_path(&editor_state) {
+            save_loaded_track(&mut editor_state, Some(path));
+        }
+    }
+    if back_clicked {
+        if dirty {
+            editor_state.pending_exit = Some(PendingExit::TrackList);
+        } else {
+            next_state.set(AppState::Browse);
+        }
+    }
+
+    if let Some(pending) = editor_state.pending_exit {
+        let mut choice = None;
+        egui::Window::new("Unsaved changes")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(contexts.ctx_mut(), |ui| {
+                ui.label("The track has edits that haven't been saved.");
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Save").clicked() {
+                        choice = Some(true);
+                    }
+                    if ui.button("Discard").clicked() {
+                        choice = Some(false);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        editor_state.pending_exit = None;
+                    }
+                });
+            });
+
+        let leave = match choice {
+            Some(true) => save_loaded_track(&mut editor_state, None),
+            Some(false) => {
+                editor_state.loaded_track = None;
+                true
+            }
+            None => false,
+        };
+        if leave {
+            editor_state.pending_exit = None;
+            match pending {
+                PendingExit::TrackList => next_state.set(AppState::Browse),
+                PendingExit::Quit => {
+                    app_exit.send(AppExit::Success);
+                }
+            }
+        }
+    }
+
     egui::Window::new("Camera Info")
         .default_pos([10.0, 60.0])
         .default_size([200.0, 100.0])
@@ -527,6 +986,8 @@ fn editor_ui_system(
             ui.label("WASD - Move camera");
             ui.label("Right-drag - Rotate view");
             ui.label("Scroll - Zoom in/out");
+            ui.label("Left-click - Select node");
+            ui.label("Insert/Delete - Add/remove node");
         });
 }
 
@@ -612,10 +1073,10 @@ fn camera_controller_system(
     }
 }
 
-fn cleanup_editor(
-    mut commands: Commands,
-    query: Query<Entity, Or<(With<TrackMeshEntity>, With<TerrainMeshEntity>, With<EditorCamera>)>>,
-) {
+/// Entities the editor spawns and removes when it is left
+type EditorCleanupFilter = Or<(With<TrackMeshEntity>, With<TerrainMeshEntity>, With<TracksideObjectEntity>, With<EditorCamera>)>;
+
+fn cleanup_editor(mut commands: Commands, query: Query<Entity, EditorCleanupFilter>) {
     for entity in query.iter() {
         commands.entity(entity).despawn_recursive();
     }
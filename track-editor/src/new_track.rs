@@ -0,0 +1,145 @@
+//! New tracks from scratch: an oval, figure eight or straight of a chosen
+//! length and node count, laid out by the server's circuit generator, with
+//! terrain from one of its environment presets around it.
+
+use apexsim_server::procgen::circuit::{self, ShapeParams, StartingShape};
+use apexsim_server::procgen::environment_presets;
+use apexsim_server::procgen::manifest::WorldParams;
+use apexsim_server::procgen::terrain;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::track_data::{ProceduralWorldData, TrackFileFormat};
+use crate::{open_new_track, AppState, EditorState};
+
+#[derive(Resource)]
+pub struct NewTrackWizard {
+    pub open: bool,
+    name: String,
+    params: ShapeParams,
+    /// Environment presets to choose the terrain from, by name
+    environments: Vec<String>,
+    error: Option<String>,
+}
+
+impl Default for NewTrackWizard {
+    fn default() -> Self {
+        let mut environments: Vec<String> = environment_presets::load_presets().into_keys().collect();
+        environments.sort();
+        Self {
+            open: false,
+            name: "New Track".to_string(),
+            params: ShapeParams::default(),
+            environments,
+            error: None,
+        }
+    }
+}
+
+/// The track in its starting shape and, when it has an environment, the
+/// terrain the server would bake for it
+fn create_track(name: &str, params: &ShapeParams) -> Result<(TrackFileFormat, Option<ProceduralWorldData>), String> {
+    let track = circuit::generate_shape(name, params)?;
+    let terrain_data = match &params.environment {
+        Some(_) => {
+            let world_params = WorldParams::from_metadata(&track.name, &track.metadata.clone().unwrap_or_default())?;
+            let world = terrain::generate_track_world(&track, &world_params, None)?
+                .ok_or("Terrain generation failed; the track can still be made without an environment")?;
+            // The terrain cache's MessagePack form is what the editor reads
+            let bytes = rmp_serde::to_vec(&world).map_err(|e| format!("Failed to convert the terrain: {}", e))?;
+            Some(rmp_serde::from_slice(&bytes).map_err(|e| format!("Failed to convert the terrain: {}", e))?)
+        }
+        None => None,
+    };
+
+    let yaml = serde_yaml::to_value(&track).map_err(|e| format!("Failed to convert the track: {}", e))?;
+    let track_data = serde_yaml::from_value(yaml).map_err(|e| format!("Failed to convert the track: {}", e))?;
+    Ok((track_data, terrain_data))
+}
+
+/// The new track window on the track list: name, starting shape, size and
+/// terrain
+pub fn new_track_wizard_system(
+    mut contexts: EguiContexts,
+    mut editor_state: ResMut<EditorState>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut wizard: ResMut<NewTrackWizard>,
+) {
+    if !wizard.open {
+        return;
+    }
+    let mut open = true;
+    let mut create_clicked = false;
+
+    egui::Window::new("New Track")
+        .open(&mut open)
+        .collapsible(false)
+        .default_width(340.0)
+        .show(contexts.ctx_mut(), |ui| {
+            let wizard = &mut *wizard;
+            egui::Grid::new("new_track_options").num_columns(2).show(ui, |ui| {
+                ui.label("Name");
+                ui.text_edit_singleline(&mut wizard.name);
+                ui.end_row();
+                ui.label("Shape");
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut wizard.params.shape, StartingShape::Oval, "Oval");
+                    ui.selectable_value(&mut wizard.params.shape, StartingShape::FigureEight, "Figure Eight");
+                    ui.selectable_value(&mut wizard.params.shape, StartingShape::Blank, "Blank");
+                });
+                ui.end_row();
+                ui.label("Length");
+                ui.add(egui::DragValue::new(&mut wizard.params.length_m).speed(10.0).range(200.0..=20_000.0).suffix(" m"));
+                ui.end_row();
+                ui.label("Nodes");
+                ui.add(egui::DragValue::new(&mut wizard.params.nodes).speed(1.0).range(4..=2000));
+                ui.end_row();
+                ui.label("Track width");
+                ui.add(egui::DragValue::new(&mut wizard.params.width_m).speed(0.1).range(6.0..=30.0).suffix(" m"));
+                ui.end_row();
+                ui.label("Terrain");
+                egui::ComboBox::from_id_source("new_track_environment")
+                    .selected_text(wizard.params.environment.as_deref().unwrap_or("None"))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut wizard.params.environment, None, "None");
+                        for environment in &wizard.environments {
+                            ui.selectable_value(&mut wizard.params.environment, Some(environment.clone()), environment);
+                        }
+                    });
+                ui.end_row();
+            });
+
+            ui.add_space(5.0);
+            let spacing = wizard.params.length_m / wizard.params.nodes.max(1) as f32;
+            ui.label(format!("Nodes {:.1} m apart", spacing));
+            if wizard.params.shape == StartingShape::Blank {
+                ui.label("A straight to bend into shape; it stays an open course.");
+            }
+            if let Some(error) = &wizard.error {
+                ui.add_space(5.0);
+                ui.colored_label(egui::Color32::RED, error);
+            }
+            ui.add_space(10.0);
+            create_clicked = ui.add_enabled(!wizard.name.trim().is_empty(), egui::Button::new("Create")).clicked();
+        });
+
+    wizard.open = open;
+    if !create_clicked {
+        return;
+    }
+    match create_track(wizard.name.trim(), &wizard.params) {
+        Ok((track_data, terrain_data)) => {
+            let summary = format!(
+                "New track: {} nodes over {:.2} km{}",
+                track_data.nodes.len(),
+                wizard.params.length_m / 1000.0,
+                wizard.params.environment.as_ref().map_or(String::new(), |environment| format!(", {} terrain", environment))
+            );
+            open_new_track(&mut editor_state, track_data, terrain_data, None);
+            editor_state.status_message = Some(summary);
+            *wizard = NewTrackWizard::default();
+            next_state.set(AppState::Editor);
+        }
+        Err(e) => wizard.error = Some(e),
+    }
+}
@@ -0,0 +1,524 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::track_data::{TrackFileFormat, TrackNode};
+use crate::{track_mesh, CameraState, EditorCamera, EditorState, TrackSurface};
+
+/// How close (in pixels) the cursor must be to pick a node or gizmo handle
+pub const PICK_RADIUS_PX: f32 = 10.0;
+
+/// Gizmo arrow length as a fraction of the camera distance, so it keeps its
+/// size on screen while zooming
+const GIZMO_SCALE: f32 = 0.08;
+
+/// Narrowest and widest a side of the track can be dragged to (m), inside
+/// what the server accepts
+const MIN_HALF_WIDTH_M: f32 = 0.5;
+const MAX_HALF_WIDTH_M: f32 = 30.0;
+
+#[derive(Resource, Default)]
+pub struct NodeEditState {
+    pub selected: Option<usize>,
+    drag: Option<NodeDrag>,
+    /// The nodes changed since the track mesh was last built
+    pub mesh_dirty: bool,
+}
+
+/// Gizmo handle being dragged
+#[derive(Clone, Copy, PartialEq)]
+enum GizmoHandle {
+    AxisX,
+    AxisY,
+    AxisZ,
+    /// Free movement in the horizontal plane
+    PlaneXY,
+    /// Track edges, dragged sideways to change the width
+    EdgeLeft,
+    EdgeRight,
+}
+
+struct NodeDrag {
+    handle: GizmoHandle,
+    /// Node position when the drag started
+    start: Vec3,
+    /// Where the cursor ray first hit the drag plane
+    grab: Vec3,
+}
+
+pub fn default_width(track: &TrackFileFormat) -> f32 {
+    if track.default_width > 0.0 {
+        track.default_width
+    } else {
+        track_mesh::DEFAULT_WIDTH_M
+    }
+}
+
+/// Cross-section through a node: its left-pointing normal and the left
+/// edge, centre and right edge of the surface, banked and cambered
+struct CrossSection {
+    normal: Vec2,
+    left: Vec3,
+    centre: Vec3,
+    right: Vec3,
+}
+
+fn cross_section(track: &TrackFileFormat, index: usize) -> Option<CrossSection> {
+    let nodes = &track.nodes;
+    let node = nodes.get(index)?;
+    let count = nodes.len();
+    let (before, after) = if track.closed_loop {
+        ((index + count - 1) % count, (index + 1) % count)
+    } else {
+        (index.saturating_sub(1), (index + 1).min(count - 1))
+    };
+    let centre = Vec3::new(node.x, node.y, node.z);
+    let flat = |i: usize| Vec2::new(nodes[i].x, nodes[i].y);
+    let incoming = flat(index) - flat(before);
+    let outgoing = flat(after) - flat(index);
+    let direction = (flat(after) - flat(before)).normalize_or_zero();
+    let turn = if incoming == Vec2::ZERO || outgoing == Vec2::ZERO {
+        0.0
+    } else {
+        incoming.angle_between(outgoing)
+    };
+
+    let normal = direction.perp();
+    let (width_left, width_right) = node.half_widths(default_width(track));
+    let (left_dz, right_dz) = track_mesh::cross_section_heights(
+        width_left,
+        width_right,
+        node.banking.unwrap_or(0.0),
+        node.camber.unwrap_or(0.0),
+        turn,
+    );
+    Some(CrossSection {
+        normal,
+        left: centre + (normal * width_left).extend(left_dz),
+        centre,
+        right: centre - (normal * width_right).extend(-right_dz),
+    })
+}
+
+fn gizmo_length(camera_state: &CameraState) -> f32 {
+    camera_state.distance * GIZMO_SCALE
+}
+
+/// Distance from a point to a screen-space segment
+fn distance_to_segment(point: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let t = ((point - a).dot(ab) / ab.length_squared().max(f32::EPSILON)).clamp(0.0, 1.0);
+    point.distance(a + ab * t)
+}
+
+/// Plane the cursor ray is intersected with while dragging a handle: the
+/// ground plane through the node for horizontal moves, a vertical plane
+/// facing the camera for height
+fn drag_plane(handle: GizmoHandle, camera_transform: &GlobalTransform) -> InfinitePlane3d {
+    match handle {
+        GizmoHandle::AxisZ => {
+            let facing = camera_transform.back().truncate().normalize_or_zero();
+            InfinitePlane3d::new(if facing == Vec2::ZERO { Vec3::X } else { facing.extend(0.0) })
+        }
+        _ => InfinitePlane3d::new(Vec3::Z),
+    }
+}
+
+pub fn cursor_hit(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    cursor: Vec2,
+    origin: Vec3,
+    plane: InfinitePlane3d,
+) -> Option<Vec3> {
+    let ray = camera.viewport_to_world(camera_transform, cursor)?;
+    let distance = ray.intersect_plane(origin, plane)?;
+    Some(ray.get_point(distance))
+}
+
+/// Handle of the selected node's gizmo under the cursor, if any
+fn handle_under_cursor(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    cursor: Vec2,
+    position: Vec3,
+    length: f32,
+    edges: [Vec3; 2],
+) -> Option<GizmoHandle> {
+    let centre = camera.world_to_viewport(camera_transform, position)?;
+    if cursor.distance(centre) <= PICK_RADIUS_PX {
+        return Some(GizmoHandle::PlaneXY);
+    }
+    let edge = [GizmoHandle::EdgeLeft, GizmoHandle::EdgeRight]
+        .into_iter()
+        .zip(edges)
+        .find(|(_, edge)| {
+            camera
+                .world_to_viewport(camera_transform, *edge)
+                .is_some_and(|screen| cursor.distance(screen) <= PICK_RADIUS_PX)
+        });
+    if let Some((handle, _)) = edge {
+        return Some(handle);
+    }
+    [
+        (GizmoHandle::AxisX, Vec3::X),
+        (GizmoHandle::AxisY, Vec3::Y),
+        (GizmoHandle::AxisZ, Vec3::Z),
+    ]
+    .into_iter()
+    .filter_map(|(handle, axis)| {
+        let tip = camera.world_to_viewport(camera_transform, position + axis * length)?;
+        Some((handle, distance_to_segment(cursor, centre, tip)))
+    })
+    .filter(|(_, distance)| *distance <= PICK_RADIUS_PX)
+    .min_by(|a, b| a.1.total_cmp(&b.1))
+    .map(|(handle, _)| handle)
+}
+
+/// Left click picks a node or grabs a gizmo handle of the selected one;
+/// dragging moves the node along the grabbed handle
+pub fn node_pick_and_drag_system(
+    mut editor_state: ResMut<EditorState>,
+    mut node_edit: ResMut<NodeEditState>,
+    camera_state: Res<CameraState>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<EditorCamera>>,
+    mut contexts: EguiContexts,
+) {
+    if mouse_buttons.just_released(MouseButton::Left) {
+        node_edit.drag = None;
+    }
+
+    let Ok((camera, camera_transform)) = camera_query.get_single() else {
+        return;
+    };
+    let Some(cursor) = window_query.get_single().ok().and_then(|window| window.cursor_position()) else {
+        return;
+    };
+
+    if mouse_buttons.just_pressed(MouseButton::Left) && !contexts.ctx_mut().wants_pointer_input() {
+        // Grab a handle of the selected node's gizmo first
+        let grabbed = node_edit.selected.and_then(|index| {
+            let section = cross_section(&editor_state.loaded_track.as_ref()?.track_data, index)?;
+            let position = section.centre;
+            let edges = [section.left, section.right];
+            let handle = handle_under_cursor(camera, camera_transform, cursor, position, gizmo_length(&camera_state), edges)?;
+            let grab = cursor_hit(camera, camera_transform, cursor, position, drag_plane(handle, camera_transform))?;
+            Some(NodeDrag { handle, start: position, grab })
+        });
+        if grabbed.is_some() {
+            node_edit.drag = grabbed;
+            return;
+        }
+
+        // Otherwise select the node nearest the cursor on screen
+        let picked = editor_state.loaded_track.as_ref().and_then(|loaded| {
+            loaded
+                .track_data
+                .nodes
+                .iter()
+                .enumerate()
+                .filter_map(|(i, node)| {
+                    let screen = camera.world_to_viewport(camera_transform, Vec3::new(node.x, node.y, node.z))?;
+                    Some((i, screen.distance(cursor)))
+                })
+                .filter(|(_, distance)| *distance <= PICK_RADIUS_PX)
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(i, _)| i)
+        });
+        node_edit.selected = picked;
+        return;
+    }
+
+    let (Some(drag), Some(index)) = (&node_edit.drag, node_edit.selected) else {
+        return;
+    };
+    if !mouse_buttons.pressed(MouseButton::Left) {
+        return;
+    }
+    let Some(hit) = cursor_hit(camera, camera_transform, cursor, drag.start, drag_plane(drag.handle, camera_transform)) else {
+        return;
+    };
+    let delta = hit - drag.grab;
+    let handle = drag.handle;
+    let moved = drag.start
+        + match handle {
+            GizmoHandle::AxisX => Vec3::new(delta.x, 0.0, 0.0),
+            GizmoHandle::AxisY => Vec3::new(0.0, delta.y, 0.0),
+            GizmoHandle::AxisZ => Vec3::new(0.0, 0.0, delta.z),
+            GizmoHandle::PlaneXY => Vec3::new(delta.x, delta.y, 0.0),
+            GizmoHandle::EdgeLeft | GizmoHandle::EdgeRight => Vec3::ZERO,
+        };
+
+    let Some(loaded) = editor_state.loaded_track.as_mut() else {
+        return;
+    };
+
+    if matches!(handle, GizmoHandle::EdgeLeft | GizmoHandle::EdgeRight) {
+        let Some(section) = cross_section(&loaded.track_data, index) else {
+            return;
+        };
+        let default_width = default_width(&loaded.track_data);
+        let offset = (hit - section.centre).truncate().dot(section.normal);
+        let node = &mut loaded.track_data.nodes[index];
+        let (mut width_left, mut width_right) = node.half_widths(default_width);
+        if handle == GizmoHandle::EdgeLeft {
+            width_left = offset.clamp(MIN_HALF_WIDTH_M, MAX_HALF_WIDTH_M);
+        } else {
+            width_right = (-offset).clamp(MIN_HALF_WIDTH_M, MAX_HALF_WIDTH_M);
+        }
+        if node.half_widths(default_width) != (width_left, width_right) {
+            set_half_widths(node, width_left, width_right);
+            loaded.dirty = true;
+            node_edit.mesh_dirty = true;
+        }
+        return;
+    }
+
+    if let Some(node) = loaded.track_data.nodes.get_mut(index) {
+        if (node.x, node.y, node.z) != (moved.x, moved.y, moved.z) {
+            node.x = moved.x;
+            node.y = moved.y;
+            node.z = moved.z;
+            loaded.dirty = true;
+            node_edit.mesh_dirty = true;
+        }
+    }
+}
+
+/// Insert and Delete keys add a node after the selected one or remove it
+pub fn node_keyboard_system(
+    mut editor_state: ResMut<EditorState>,
+    mut node_edit: ResMut<NodeEditState>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut contexts: EguiContexts,
+) {
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    if keyboard.just_pressed(KeyCode::Escape) {
+        node_edit.selected = None;
+    }
+    if keyboard.just_pressed(KeyCode::Insert) {
+        insert_selected_node(&mut editor_state, &mut node_edit);
+    }
+    if keyboard.just_pressed(KeyCode::Delete) {
+        delete_selected_node(&mut editor_state, &mut node_edit);
+    }
+}
+
+/// Give a node explicit left and right widths in place of a symmetric one
+fn set_half_widths(node: &mut TrackNode, width_left: f32, width_right: f32) {
+    node.width = None;
+    node.width_left = Some(width_left);
+    node.width_right = Some(width_right);
+}
+
+fn insert_selected_node(editor_state: &mut EditorState, node_edit: &mut NodeEditState) {
+    let (Some(index), Some(loaded)) = (node_edit.selected, editor_state.loaded_track.as_mut()) else {
+        return;
+    };
+    node_edit.selected = Some(loaded.track_data.insert_node_after(index));
+    loaded.dirty = true;
+    node_edit.drag = None;
+    node_edit.mesh_dirty = true;
+}
+
+fn delete_selected_node(editor_state: &mut EditorState, node_edit: &mut NodeEditState) {
+    let (Some(index), Some(loaded)) = (node_edit.selected, editor_state.loaded_track.as_mut()) else {
+        return;
+    };
+    if loaded.track_data.remove_node(index) {
+        loaded.dirty = true;
+        node_edit.selected = Some(index.min(loaded.track_data.nodes.len() - 1));
+        node_edit.drag = None;
+        node_edit.mesh_dirty = true;
+    }
+}
+
+/// Node markers, plus the move gizmo of the selected node
+pub fn node_gizmo_system(
+    mut gizmos: Gizmos,
+    editor_state: Res<EditorState>,
+    node_edit: Res<NodeEditState>,
+    camera_state: Res<CameraState>,
+) {
+    let Some(loaded) = &editor_state.loaded_track else {
+        return;
+    };
+    let length = gizmo_length(&camera_state);
+    let marker_radius = length * 0.06;
+
+    for (i, node) in loaded.track_data.nodes.iter().enumerate() {
+        let color = if node_edit.selected == Some(i) {
+            Color::srgb(1.0, 0.9, 0.1)
+        } else {
+            Color::srgb(0.2, 0.7, 1.0)
+        };
+        gizmos.circle(Vec3::new(node.x, node.y, node.z + 0.1), Dir3::Z, marker_radius, color);
+    }
+
+    let Some(section) = node_edit.selected.and_then(|index| cross_section(&loaded.track_data, index)) else {
+        return;
+    };
+    let position = section.centre;
+    let active = node_edit.drag.as_ref().map(|drag| drag.handle);
+    let highlight = |handle: GizmoHandle, color: Color| {
+        if active == Some(handle) {
+            Color::WHITE
+        } else {
+            color
+        }
+    };
+    gizmos.arrow(position, position + Vec3::X * length, highlight(GizmoHandle::AxisX, Color::srgb(0.9, 0.2, 0.2)));
+    gizmos.arrow(position, position + Vec3::Y * length, highlight(GizmoHandle::AxisY, Color::srgb(0.2, 0.9, 0.2)));
+    gizmos.arrow(position, position + Vec3::Z * length, highlight(GizmoHandle::AxisZ, Color::srgb(0.2, 0.4, 1.0)));
+    gizmos.rect(
+        position,
+        Quat::IDENTITY,
+        Vec2::splat(length * 0.25),
+        highlight(GizmoHandle::PlaneXY, Color::srgb(1.0, 0.9, 0.1)),
+    );
+
+    // Banked and cambered profile of the surface, with the edge handles
+    let lift = Vec3::Z * 0.15;
+    gizmos.linestrip([section.left + lift, section.centre + lift, section.right + lift], Color::srgb(1.0, 0.55, 0.1));
+    gizmos.sphere(section.left + lift, Quat::IDENTITY, marker_radius * 1.5, highlight(GizmoHandle::EdgeLeft, Color::srgb(1.0, 0.55, 0.1)));
+    gizmos.sphere(section.right + lift, Quat::IDENTITY, marker_radius * 1.5, highlight(GizmoHandle::EdgeRight, Color::srgb(1.0, 0.55, 0.1)));
+}
+
+/// Values of the selected node shown in the inspector, angles in degrees
+#[derive(Clone, Copy, PartialEq)]
+struct NodeValues {
+    position: Vec3,
+    width_left: f32,
+    width_right: f32,
+    banking_deg: f32,
+    camber_deg: f32,
+}
+
+/// Inspector for the selected node: numeric entry for its position, widths,
+/// banking and camber, and the insert/delete actions
+pub fn node_panel_system(
+    mut contexts: EguiContexts,
+    mut editor_state: ResMut<EditorState>,
+    mut node_edit: ResMut<NodeEditState>,
+) {
+    let Some(index) = node_edit.selected else {
+        return;
+    };
+    let Some(loaded) = editor_state.loaded_track.as_ref() else {
+        return;
+    };
+    let Some(node) = loaded.track_data.nodes.get(index) else {
+        node_edit.selected = None;
+        return;
+    };
+    let node_count = loaded.track_data.nodes.len();
+    let default_width = default_width(&loaded.track_data);
+    let (width_left, width_right) = node.half_widths(default_width);
+    let shown = NodeValues {
+        position: Vec3::new(node.x, node.y, node.z),
+        width_left,
+        width_right,
+        banking_deg: node.banking.unwrap_or(0.0).to_degrees(),
+        camber_deg: node.camber.unwrap_or(0.0).to_degrees(),
+    };
+    let mut values = shown;
+
+    egui::Window::new("Node Inspector")
+        .default_pos([10.0, 260.0])
+        .default_size([220.0, 200.0])
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label(format!("Node {} of {}", index + 1, node_count));
+            ui.add_space(5.0);
+            egui::Grid::new("node_inspector").num_columns(2).show(ui, |ui| {
+                for (label, value) in [
+                    ("X", &mut values.position.x),
+                    ("Y", &mut values.position.y),
+                    ("Z", &mut values.position.z),
+                ] {
+                    ui.label(label);
+                    ui.add(egui::DragValue::new(value).speed(0.1).suffix(" m"));
+                    ui.end_row();
+                }
+                for (label, value) in [("Width left", &mut values.width_left), ("Width right", &mut values.width_right)] {
+                    ui.label(label);
+                    ui.add(
+                        egui::DragValue::new(value)
+                            .speed(0.05)
+                            .range(MIN_HALF_WIDTH_M..=MAX_HALF_WIDTH_M)
+                            .suffix(" m"),
+                    );
+                    ui.end_row();
+                }
+                ui.label("Banking");
+                ui.add(egui::DragValue::new(&mut values.banking_deg).speed(0.1).range(-45.0..=45.0).suffix("°"));
+                ui.end_row();
+                ui.label("Camber");
+                ui.add(egui::DragValue::new(&mut values.camber_deg).speed(0.05).range(-10.0..=10.0).suffix("°"));
+                ui.end_row();
+            });
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                if ui.button("Insert After").clicked() {
+                    insert_selected_node(&mut editor_state, &mut node_edit);
+                }
+                if ui.add_enabled(node_count > 2, egui::Button::new("Delete")).clicked() {
+                    delete_selected_node(&mut editor_state, &mut node_edit);
+                }
+            });
+            ui.add_space(10.0);
+            ui.label("Drag the arrows to move along an axis,");
+            ui.label("the square to move across the ground");
+            ui.label("and the orange handles to change the width.");
+        });
+
+    if values == shown {
+        return;
+    }
+    let Some(node) = editor_state
+        .loaded_track
+        .as_mut()
+        .and_then(|loaded| loaded.track_data.nodes.get_mut(index))
+    else {
+        return;
+    };
+    node.x = values.position.x;
+    node.y = values.position.y;
+    node.z = values.position.z;
+    if (values.width_left, values.width_right) != (shown.width_left, shown.width_right) {
+        set_half_widths(node, values.width_left, values.width_right);
+    }
+    if values.banking_deg != shown.banking_deg {
+        node.banking = Some(values.banking_deg.to_radians());
+    }
+    if values.camber_deg != shown.camber_deg {
+        node.camber = Some(values.camber_deg.to_radians());
+    }
+    if let Some(loaded) = editor_state.loaded_track.as_mut() {
+        loaded.dirty = true;
+    }
+    node_edit.mesh_dirty = true;
+}
+
+/// Rebuild the track surface after its nodes change
+pub fn track_mesh_update_system(
+    editor_state: Res<EditorState>,
+    mut node_edit: ResMut<NodeEditState>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    surface_query: Query<&Handle<Mesh>, With<TrackSurface>>,
+) {
+    if !node_edit.mesh_dirty {
+        return;
+    }
+    node_edit.mesh_dirty = false;
+
+    let Some(loaded) = &editor_state.loaded_track else {
+        return;
+    };
+    for handle in surface_query.iter() {
+        meshes.insert(handle, track_mesh::generate_track_mesh(&loaded.track_data.nodes, loaded.track_data.closed_loop));
+    }
+}
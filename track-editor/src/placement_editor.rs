@@ -0,0 +1,410 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::node_editor::{cursor_hit, default_width, PICK_RADIUS_PX};
+use crate::track_data::{PitBox, PitLaneDefinition, SpawnPoint, TrackFileFormat};
+use crate::timing_editor::start_line_index;
+use crate::track_mesh::{interpolate_centerline, nearest_centerline_point, CenterlinePoint};
+use crate::{CameraState, EditorCamera, EditorState};
+
+/// Pit lane width when its nodes give none (m), as on the server
+const DEFAULT_PIT_LANE_WIDTH_M: f32 = 8.0;
+
+/// Footprint drawn for a grid slot or pit box (m)
+const SLOT_SIZE_M: Vec2 = Vec2::new(4.5, 2.0);
+
+/// Most slots the auto grid lays out
+const MAX_GRID_SLOTS: usize = 40;
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlacementTool {
+    #[default]
+    Grid,
+    PitBoxes,
+}
+
+#[derive(Resource)]
+pub struct PlacementState {
+    tool: PlacementTool,
+    selected: Option<usize>,
+    /// Auto grid layout: slot count, distance between rows and between the
+    /// two columns (m), the server's default grid spacing to start with
+    grid_slots: usize,
+    row_spacing_m: f32,
+    column_spacing_m: f32,
+}
+
+impl Default for PlacementState {
+    fn default() -> Self {
+        Self {
+            tool: PlacementTool::Grid,
+            selected: None,
+            grid_slots: 16,
+            row_spacing_m: 8.0,
+            column_spacing_m: 3.0,
+        }
+    }
+}
+
+/// Where a grid slot or pit box ends up, facing which way, and whether a
+/// car there would stand on the surface it belongs on
+struct Placement {
+    position: Vec3,
+    heading: f32,
+    on_surface: bool,
+}
+
+fn track_centerline(track: &TrackFileFormat) -> Vec<CenterlinePoint> {
+    interpolate_centerline(&track.nodes, track.closed_loop, default_width(track))
+}
+
+fn pit_centerline(pit_lane: &PitLaneDefinition) -> Vec<CenterlinePoint> {
+    let width = if pit_lane.default_width > 0.0 { pit_lane.default_width } else { DEFAULT_PIT_LANE_WIDTH_M };
+    interpolate_centerline(&pit_lane.nodes, false, width)
+}
+
+/// Offset of `(x, y)` to the left of a centerline point, and whether that
+/// is within the surface on that side
+fn lateral_offset(point: &CenterlinePoint, x: f32, y: f32) -> (f32, bool) {
+    let lateral = (Vec2::new(x, y) - Vec2::new(point.x, point.y)).dot(point.left_normal());
+    let width = if lateral >= 0.0 { point.width_left } else { point.width_right };
+    (lateral, lateral.abs() <= width)
+}
+
+/// A spawn point sits on its centerline point, moved by its offset and
+/// facing along the track, as the server places it
+fn grid_slot(centerline: &[CenterlinePoint], spawn: &SpawnPoint) -> Option<Placement> {
+    let point = centerline.get(spawn.position)?;
+    let (x, y) = (point.x + spawn.offset_x, point.y + spawn.offset_y);
+    Some(Placement {
+        position: Vec3::new(x, y, point.z),
+        heading: point.heading,
+        on_surface: lateral_offset(point, x, y).1,
+    })
+}
+
+fn pit_box(pit_centerline: &[CenterlinePoint], pit_box: &PitBox) -> Placement {
+    let nearest = nearest_centerline_point(pit_centerline, pit_box.x, pit_box.y).map(|i| &pit_centerline[i]);
+    Placement {
+        position: Vec3::new(pit_box.x, pit_box.y, pit_box.z),
+        heading: pit_box.yaw.or(nearest.map(|p| p.heading)).unwrap_or(0.0),
+        on_surface: nearest.is_some_and(|p| lateral_offset(p, pit_box.x, pit_box.y).1),
+    }
+}
+
+/// Centerline index `distance` metres along the track from `start`,
+/// backwards when negative; stops at the ends of an open track
+fn walk(centerline: &[CenterlinePoint], closed_loop: bool, start: usize, distance: f32) -> usize {
+    let count = centerline.len();
+    let mut index = start;
+    let mut travelled = 0.0;
+    while travelled < distance.abs() {
+        let next = match (distance < 0.0, closed_loop) {
+            (true, true) => (index + count - 1) % count,
+            (false, true) => (index + 1) % count,
+            (true, false) if index > 0 => index - 1,
+            (false, false) if index + 1 < count => index + 1,
+            _ => break,
+        };
+        let (a, b) = (&centerline[index], &centerline[next]);
+        travelled += (b.x - a.x).hypot(b.y - a.y);
+        index = next;
+    }
+    index
+}
+
+/// Rows of two behind the start of a closed loop, like the server's default
+/// grid; an open track has no room behind its start, so there the grid
+/// forms ahead of it with slot 1 at the front
+fn auto_grid(centerline: &[CenterlinePoint], closed_loop: bool, start: usize, state: &PlacementState) -> Vec<SpawnPoint> {
+    let rows = state.grid_slots.div_ceil(2);
+    (0..state.grid_slots)
+        .map(|slot| {
+            let (row, column) = (slot / 2, slot % 2);
+            let distance = if closed_loop {
+                -(row as f32) * state.row_spacing_m
+            } else {
+                (rows - 1 - row) as f32 * state.row_spacing_m
+            };
+            let position = walk(centerline, closed_loop, start, distance);
+            let offset = centerline[position].left_normal() * (column as f32 - 0.5) * state.column_spacing_m;
+            SpawnPoint { position, offset_x: offset.x, offset_y: offset.y }
+        })
+        .collect()
+}
+
+/// Where the cursor ray meets the road: intersected with the ground plane,
+/// then again at the height of the centerline found there, so elevated
+/// tracks are hit where they are drawn
+pub fn surface_hit(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    cursor: Vec2,
+    centerline: &[CenterlinePoint],
+    start_height: f32,
+) -> Option<Vec3> {
+    let mut height = start_height;
+    let mut hit = None;
+    for _ in 0..3 {
+        let point = cursor_hit(camera, camera_transform, cursor, Vec3::Z * height, InfinitePlane3d::new(Vec3::Z))?;
+        height = nearest_centerline_point(centerline, point.x, point.y).map_or(height, |i| centerline[i].z);
+        hit = Some(point.truncate().extend(height));
+    }
+    hit
+}
+
+/// Remove grid slot or pit box `index`, returning whether there was one
+fn remove(track: &mut TrackFileFormat, tool: PlacementTool, index: usize) -> bool {
+    match (tool, track.pit_lane.as_mut()) {
+        (PlacementTool::Grid, _) if index < track.spawn_points.len() => {
+            track.spawn_points.remove(index);
+            true
+        }
+        (PlacementTool::PitBoxes, Some(pit_lane)) if index < pit_lane.boxes.len() => {
+            pit_lane.boxes.remove(index);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Delete removes the selected grid slot or pit box, Escape deselects it
+pub fn placement_keyboard_system(
+    mut editor_state: ResMut<EditorState>,
+    mut placement: ResMut<PlacementState>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut contexts: EguiContexts,
+) {
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    if keyboard.just_pressed(KeyCode::Escape) {
+        placement.selected = None;
+    }
+    let Some(loaded) = editor_state.loaded_track.as_mut() else {
+        return;
+    };
+    if keyboard.just_pressed(KeyCode::Delete) {
+        if let Some(index) = placement.selected.take() {
+            loaded.dirty |= remove(&mut loaded.track_data, placement.tool, index);
+        }
+    }
+}
+
+/// Left click selects the grid slot or pit box under the cursor, or drops a
+/// new one where the track is clicked
+pub fn placement_click_system(
+    mut editor_state: ResMut<EditorState>,
+    mut placement: ResMut<PlacementState>,
+    camera_state: Res<CameraState>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<EditorCamera>>,
+    mut contexts: EguiContexts,
+) {
+    let Some(loaded) = editor_state.loaded_track.as_mut() else {
+        return;
+    };
+    let track = &mut loaded.track_data;
+
+    if !mouse_buttons.just_pressed(MouseButton::Left) || contexts.ctx_mut().wants_pointer_input() {
+        return;
+    }
+    let Ok((camera, camera_transform)) = camera_query.get_single() else {
+        return;
+    };
+    let Some(cursor) = window_query.get_single().ok().and_then(|window| window.cursor_position()) else {
+        return;
+    };
+
+    let centerline = track_centerline(track);
+    let pit_centerline = track.pit_lane.as_ref().map(pit_centerline);
+    let placed: Vec<Placement> = match (placement.tool, &track.pit_lane, &pit_centerline) {
+        (PlacementTool::Grid, _, _) => track.spawn_points.iter().filter_map(|spawn| grid_slot(&centerline, spawn)).collect(),
+        (PlacementTool::PitBoxes, Some(pit_lane), Some(lane)) => pit_lane.boxes.iter().map(|b| pit_box(lane, b)).collect(),
+        _ => Vec::new(),
+    };
+
+    // Pick an existing one first
+    let picked = placed
+        .iter()
+        .enumerate()
+        .filter_map(|(i, slot)| Some((i, camera.world_to_viewport(camera_transform, slot.position)?.distance(cursor))))
+        .filter(|(_, distance)| *distance <= PICK_RADIUS_PX * 2.0)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i);
+    if picked.is_some() {
+        placement.selected = picked;
+        return;
+    }
+
+    match (placement.tool, track.pit_lane.as_mut(), pit_centerline) {
+        (PlacementTool::Grid, _, _) => {
+            let Some(hit) = surface_hit(camera, camera_transform, cursor, &centerline, camera_state.focus.z) else {
+                return;
+            };
+            let Some(position) = nearest_centerline_point(&centerline, hit.x, hit.y) else {
+                return;
+            };
+            let point = &centerline[position];
+            track.spawn_points.push(SpawnPoint {
+                position,
+                offset_x: hit.x - point.x,
+                offset_y: hit.y - point.y,
+            });
+            placement.selected = Some(track.spawn_points.len() - 1);
+        }
+        (PlacementTool::PitBoxes, Some(pit_lane), Some(lane)) => {
+            let Some(hit) = surface_hit(camera, camera_transform, cursor, &lane, camera_state.focus.z) else {
+                return;
+            };
+            // Parked along the pit lane
+            let heading = nearest_centerline_point(&lane, hit.x, hit.y).map(|i| lane[i].heading);
+            pit_lane.boxes.push(PitBox { x: hit.x, y: hit.y, z: hit.z, yaw: heading });
+            placement.selected = Some(pit_lane.boxes.len() - 1);
+        }
+        _ => return,
+    }
+    loaded.dirty = true;
+}
+
+/// Footprints of the grid slots and pit boxes, red where off the surface
+pub fn placement_gizmo_system(mut gizmos: Gizmos, editor_state: Res<EditorState>, placement: Res<PlacementState>) {
+    let Some(loaded) = &editor_state.loaded_track else {
+        return;
+    };
+    let track = &loaded.track_data;
+    let centerline = track_centerline(track);
+    let lift = Vec3::Z * 0.2;
+    let off_surface = Color::srgb(1.0, 0.15, 0.15);
+
+    let mut draw = |slot: &Placement, color: Color| {
+        let rotation = Quat::from_rotation_z(slot.heading);
+        let color = if slot.on_surface { color } else { off_surface };
+        gizmos.rect(slot.position + lift, rotation, SLOT_SIZE_M, color);
+        gizmos.arrow(slot.position + lift, slot.position + lift + rotation * Vec3::X * SLOT_SIZE_M.x * 0.5, color);
+    };
+
+    let selected = |tool: PlacementTool, index: usize| placement.tool == tool && placement.selected == Some(index);
+    for (i, spawn) in track.spawn_points.iter().enumerate() {
+        if let Some(slot) = grid_slot(&centerline, spawn) {
+            let color = if selected(PlacementTool::Grid, i) { Color::srgb(1.0, 0.9, 0.1) } else { Color::srgb(0.2, 0.9, 0.3) };
+            draw(&slot, color);
+        }
+    }
+    if let Some(pit_lane) = &track.pit_lane {
+        let lane = pit_centerline(pit_lane);
+        for (i, b) in pit_lane.boxes.iter().enumerate() {
+            let color = if selected(PlacementTool::PitBoxes, i) { Color::srgb(1.0, 0.9, 0.1) } else { Color::srgb(0.2, 0.6, 1.0) };
+            draw(&pit_box(&lane, b), color);
+        }
+    }
+}
+
+/// Tool choice, the auto grid and the list of slots with their checks
+pub fn placement_panel_system(
+    mut contexts: EguiContexts,
+    mut editor_state: ResMut<EditorState>,
+    mut placement: ResMut<PlacementState>,
+) {
+    let Some(loaded) = editor_state.loaded_track.as_mut() else {
+        return;
+    };
+    let track = &mut loaded.track_data;
+    let centerline = track_centerline(track);
+    let grid: Vec<Option<Placement>> = track.spawn_points.iter().map(|spawn| grid_slot(&centerline, spawn)).collect();
+    let boxes: Option<Vec<Placement>> = track.pit_lane.as_ref().map(|pit_lane| {
+        let lane = pit_centerline(pit_lane);
+        pit_lane.boxes.iter().map(|b| pit_box(&lane, b)).collect()
+    });
+
+    let mut auto_grid_clicked = false;
+    let mut clear_clicked = false;
+    let mut removed = None;
+
+    egui::Window::new("Grid & Pit Boxes")
+        .default_pos([10.0, 260.0])
+        .default_size([240.0, 300.0])
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut placement.tool, PlacementTool::Grid, "Start Grid");
+                ui.selectable_value(&mut placement.tool, PlacementTool::PitBoxes, "Pit Boxes");
+            });
+            ui.add_space(5.0);
+
+            let statuses: Vec<bool> = match placement.tool {
+                PlacementTool::Grid => {
+                    egui::Grid::new("auto_grid").num_columns(2).show(ui, |ui| {
+                        ui.label("Slots");
+                        ui.add(egui::DragValue::new(&mut placement.grid_slots).range(1..=MAX_GRID_SLOTS));
+                        ui.end_row();
+                        ui.label("Row spacing");
+                        ui.add(egui::DragValue::new(&mut placement.row_spacing_m).speed(0.1).range(5.0..=30.0).suffix(" m"));
+                        ui.end_row();
+                        ui.label("Column spacing");
+                        ui.add(egui::DragValue::new(&mut placement.column_spacing_m).speed(0.1).range(0.0..=10.0).suffix(" m"));
+                        ui.end_row();
+                    });
+                    auto_grid_clicked = ui.button("Auto-Space Grid").clicked();
+                    grid.iter().map(|slot| slot.as_ref().is_some_and(|slot| slot.on_surface)).collect()
+                }
+                PlacementTool::PitBoxes => match &boxes {
+                    Some(boxes) => boxes.iter().map(|b| b.on_surface).collect(),
+                    None => {
+                        ui.label("This track has no pit lane to put boxes in.");
+                        Vec::new()
+                    }
+                },
+            };
+
+            ui.add_space(5.0);
+            let off_surface = statuses.iter().filter(|on_surface| !**on_surface).count();
+            if statuses.is_empty() {
+                ui.label("Click on the track to place one.");
+            } else if off_surface == 0 {
+                ui.colored_label(egui::Color32::GREEN, format!("All {} on the surface", statuses.len()));
+            } else {
+                ui.colored_label(egui::Color32::RED, format!("{} of {} off the surface", off_surface, statuses.len()));
+            }
+
+            egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                for (i, on_surface) in statuses.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        let label = format!("{} {}", i + 1, if *on_surface { "" } else { "(off the surface)" });
+                        if ui.selectable_label(placement.selected == Some(i), label).clicked() {
+                            placement.selected = Some(i);
+                        }
+                        if ui.small_button("x").clicked() {
+                            removed = Some(i);
+                        }
+                    });
+                }
+            });
+            if !statuses.is_empty() {
+                clear_clicked = ui.button("Clear All").clicked();
+            }
+        });
+
+    let changed = match (placement.tool, removed) {
+        (PlacementTool::Grid, _) if auto_grid_clicked && !centerline.is_empty() => {
+            let start = start_line_index(track, &centerline);
+            track.spawn_points = auto_grid(&centerline, track.closed_loop, start, &placement);
+            true
+        }
+        (PlacementTool::Grid, _) if clear_clicked => {
+            track.spawn_points.clear();
+            true
+        }
+        (PlacementTool::PitBoxes, _) if clear_clicked => track.pit_lane.as_mut().is_some_and(|pit_lane| {
+            pit_lane.boxes.clear();
+            true
+        }),
+        (tool, Some(index)) => remove(track, tool, index),
+        _ => false,
+    };
+    if changed {
+        placement.selected = None;
+        loaded.dirty = true;
+    }
+}
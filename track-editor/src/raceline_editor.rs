@@ -0,0 +1,226 @@
+use apexsim_server::data::TrackPoint;
+use apexsim_server::raceline;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::node_editor::{cursor_hit, default_width, PICK_RADIUS_PX};
+use crate::track_data::{RacelinePoint, TrackFileFormat};
+use crate::track_mesh::{interpolate_centerline, nearest_centerline_point, CenterlinePoint};
+use crate::{CameraState, EditorCamera, EditorMode, EditorState};
+
+/// Height the overlay floats above the road so it isn't hidden in it (m)
+const OVERLAY_LIFT_M: f32 = 0.2;
+
+#[derive(Resource)]
+pub struct RacelineEditState {
+    pub visible: bool,
+    selected: Option<usize>,
+    dragging: bool,
+}
+
+impl Default for RacelineEditState {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            selected: None,
+            dragging: false,
+        }
+    }
+}
+
+/// Where a raceline point may slide: the centerline point beside it, its
+/// left normal, the allowed offset range and the point's current offset
+struct LateralTrack {
+    centre: Vec3,
+    normal: Vec2,
+    limits: (f32, f32),
+    offset: f32,
+}
+
+/// A centerline point as the server's raceline generator takes it
+fn track_point(point: &CenterlinePoint) -> TrackPoint {
+    TrackPoint {
+        x: point.x,
+        y: point.y,
+        z: point.z,
+        width_left_m: point.width_left,
+        width_right_m: point.width_right,
+        heading_rad: point.heading,
+        ..Default::default()
+    }
+}
+
+/// A racing line from the server's generator. Its target speeds are left out,
+/// like those of an edited line, and worked out again when the track loads
+fn generate_raceline(track: &TrackFileFormat) -> Vec<RacelinePoint> {
+    let centerline = interpolate_centerline(&track.nodes, track.closed_loop, default_width(track));
+    let centerline: Vec<TrackPoint> = centerline.iter().map(track_point).collect();
+    raceline::generate(&centerline, track.closed_loop)
+        .into_iter()
+        .map(|point| RacelinePoint { x: point.x, y: point.y, z: point.z, extra: Default::default() })
+        .collect()
+}
+
+fn lateral_track(track: &TrackFileFormat, index: usize) -> Option<LateralTrack> {
+    let point = track.raceline.get(index)?;
+    let centerline = interpolate_centerline(&track.nodes, track.closed_loop, default_width(track));
+    let nearest = &centerline[nearest_centerline_point(&centerline, point.x, point.y)?];
+    let centre = Vec3::new(nearest.x, nearest.y, nearest.z);
+    let normal = nearest.left_normal();
+    Some(LateralTrack {
+        centre,
+        normal,
+        limits: raceline::lateral_limits(&track_point(nearest)),
+        offset: (Vec2::new(point.x, point.y) - centre.truncate()).dot(normal),
+    })
+}
+
+/// Racing line overlay, with the points and the selected one's lateral
+/// range while editing it
+pub fn raceline_gizmo_system(
+    mut gizmos: Gizmos,
+    editor_state: Res<EditorState>,
+    raceline_edit: Res<RacelineEditState>,
+    mode: Res<EditorMode>,
+    camera_state: Res<CameraState>,
+) {
+    let Some(loaded) = &editor_state.loaded_track else {
+        return;
+    };
+    if !raceline_edit.visible && *mode != EditorMode::Raceline {
+        return;
+    }
+    let track = &loaded.track_data;
+    let lift = Vec3::Z * OVERLAY_LIFT_M;
+    let line_color = Color::srgb(0.9, 0.1, 0.6);
+    let points = track.raceline.iter().map(|p| Vec3::new(p.x, p.y, p.z) + lift);
+    if track.closed_loop {
+        gizmos.linestrip(points.clone().chain(points.take(1)), line_color);
+    } else {
+        gizmos.linestrip(points, line_color);
+    }
+
+    if *mode != EditorMode::Raceline {
+        return;
+    }
+    let marker_radius = camera_state.distance * 0.005;
+    for (i, point) in track.raceline.iter().enumerate() {
+        let color = if raceline_edit.selected == Some(i) {
+            Color::srgb(1.0, 0.9, 0.1)
+        } else {
+            line_color
+        };
+        gizmos.circle(Vec3::new(point.x, point.y, point.z) + lift, Dir3::Z, marker_radius, color);
+    }
+
+    if let Some(lateral) = raceline_edit.selected.and_then(|index| lateral_track(track, index)) {
+        let edge = |offset: f32| lateral.centre + (lateral.normal * offset).extend(0.0) + lift;
+        gizmos.line(edge(lateral.limits.0), edge(lateral.limits.1), Color::srgb(1.0, 0.9, 0.1));
+    }
+}
+
+/// Left click picks a raceline point; dragging slides it across the track,
+/// never closer to an edge than the server's generator lets the line go
+pub fn raceline_pick_and_drag_system(
+    mut editor_state: ResMut<EditorState>,
+    mut raceline_edit: ResMut<RacelineEditState>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<EditorCamera>>,
+    mut contexts: EguiContexts,
+) {
+    if mouse_buttons.just_released(MouseButton::Left) {
+        raceline_edit.dragging = false;
+    }
+
+    let Ok((camera, camera_transform)) = camera_query.get_single() else {
+        return;
+    };
+    let Some(cursor) = window_query.get_single().ok().and_then(|window| window.cursor_position()) else {
+        return;
+    };
+    let Some(loaded) = editor_state.loaded_track.as_mut() else {
+        return;
+    };
+
+    if mouse_buttons.just_pressed(MouseButton::Left) && !contexts.ctx_mut().wants_pointer_input() {
+        let picked = loaded
+            .track_data
+            .raceline
+            .iter()
+            .enumerate()
+            .filter_map(|(i, point)| {
+                let screen = camera.world_to_viewport(camera_transform, Vec3::new(point.x, point.y, point.z))?;
+                Some((i, screen.distance(cursor)))
+            })
+            .filter(|(_, distance)| *distance <= PICK_RADIUS_PX)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i);
+        raceline_edit.selected = picked;
+        raceline_edit.dragging = picked.is_some();
+        return;
+    }
+
+    let (true, Some(index)) = (raceline_edit.dragging && mouse_buttons.pressed(MouseButton::Left), raceline_edit.selected) else {
+        return;
+    };
+    let Some(lateral) = lateral_track(&loaded.track_data, index) else {
+        return;
+    };
+    let Some(hit) = cursor_hit(camera, camera_transform, cursor, lateral.centre, InfinitePlane3d::new(Vec3::Z)) else {
+        return;
+    };
+    let offset = (hit - lateral.centre).truncate().dot(lateral.normal).clamp(lateral.limits.0, lateral.limits.1);
+    if offset == lateral.offset {
+        return;
+    }
+    let moved = lateral.centre + (lateral.normal * offset).extend(0.0);
+    let point = &mut loaded.track_data.raceline[index];
+    point.x = moved.x;
+    point.y = moved.y;
+    point.z = moved.z;
+    loaded.track_data.clear_raceline_speeds();
+    loaded.dirty = true;
+}
+
+/// Overlay toggle, the selected point's offset and regeneration of the
+/// whole line
+pub fn raceline_panel_system(
+    mut contexts: EguiContexts,
+    mut editor_state: ResMut<EditorState>,
+    mut raceline_edit: ResMut<RacelineEditState>,
+) {
+    let Some(loaded) = editor_state.loaded_track.as_mut() else {
+        return;
+    };
+    let selected = raceline_edit.selected.and_then(|index| Some((index, lateral_track(&loaded.track_data, index)?)));
+    let mut regenerate = false;
+
+    egui::Window::new("Raceline")
+        .default_pos([10.0, 260.0])
+        .default_size([220.0, 140.0])
+        .show(contexts.ctx_mut(), |ui| {
+            ui.checkbox(&mut raceline_edit.visible, "Show in every mode");
+            ui.label(format!("Points: {}", loaded.track_data.raceline.len()));
+            if let Some((index, lateral)) = &selected {
+                ui.add_space(5.0);
+                ui.label(format!("Point {} of {}", index + 1, loaded.track_data.raceline.len()));
+                ui.label(format!(
+                    "Offset: {:.2} m (from {:.2} to {:.2})",
+                    lateral.offset, lateral.limits.0, lateral.limits.1
+                ));
+            }
+            ui.add_space(10.0);
+            regenerate = ui.button("Regenerate Raceline").clicked();
+            ui.add_space(10.0);
+            ui.label("Drag a point to move it across the track.");
+        });
+
+    if regenerate {
+        loaded.track_data.raceline = generate_raceline(&loaded.track_data);
+        loaded.dirty = true;
+        raceline_edit.selected = None;
+        raceline_edit.dragging = false;
+    }
+}
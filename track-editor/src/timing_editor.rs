@@ -0,0 +1,401 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::node_editor::{default_width, PICK_RADIUS_PX};
+use crate::placement_editor::surface_hit;
+use crate::track_data::{DrsZoneDefinition, TimingDefinition, TimingPoint, TrackFileFormat};
+use crate::track_mesh::{interpolate_centerline, nearest_centerline_point, CenterlinePoint};
+use crate::{CameraState, EditorCamera, EditorState};
+
+/// Height the markers float above the road so they aren't hidden in it (m)
+const MARKER_LIFT_M: f32 = 0.3;
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimingTool {
+    #[default]
+    StartFinish,
+    Sector,
+    DrsZone,
+}
+
+/// A timing line on the track, as listed in the panel
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Marker {
+    StartFinish,
+    Sector(usize),
+    DrsZone(usize),
+    SpeedTrap(usize),
+}
+
+#[derive(Resource, Default)]
+pub struct TimingEditState {
+    tool: TimingTool,
+    selected: Option<Marker>,
+    /// Where the DRS zone being placed opens, waiting for the click that closes it
+    drs_start: Option<TimingPoint>,
+}
+
+/// The centerline with every point's distance along the lap from the
+/// start/finish line, measured the way the server does once it has moved
+/// the centerline to begin at the line
+pub struct Lap {
+    pub centerline: Vec<CenterlinePoint>,
+    pub distances: Vec<f32>,
+    pub length: f32,
+}
+
+impl Lap {
+    pub fn new(track: &TrackFileFormat) -> Self {
+        let centerline = interpolate_centerline(&track.nodes, track.closed_loop, default_width(track));
+        let n = centerline.len();
+        let start = start_line_index(track, &centerline);
+        let gap = |a: usize, b: usize| (centerline[b].x - centerline[a].x).hypot(centerline[b].y - centerline[a].y);
+
+        let mut distances = vec![0.0; n];
+        let mut travelled = 0.0;
+        for step in 1..n {
+            let (previous, index) = ((start + step - 1) % n, (start + step) % n);
+            travelled += gap(previous, index);
+            distances[index] = travelled;
+        }
+        if track.closed_loop && n > 1 {
+            travelled += gap((start + n - 1) % n, start);
+        }
+        Self { centerline, distances, length: travelled }
+    }
+
+    /// Centerline point a timing line crosses at, and whether the point given
+    /// for it is on the track (within the wider side, as the server checks)
+    fn crossing(&self, point: &TimingPoint) -> Option<(usize, bool)> {
+        let index = nearest_centerline_point(&self.centerline, point.x, point.y)?;
+        let centre = &self.centerline[index];
+        let distance = (point.x - centre.x).hypot(point.y - centre.y);
+        Some((index, distance <= centre.width_left.max(centre.width_right)))
+    }
+
+    fn distance(&self, point: &TimingPoint) -> Option<f32> {
+        self.crossing(point).map(|(index, _)| self.distances[index])
+    }
+}
+
+/// Centerline point on the start/finish line: the one nearest the line on a
+/// closed loop that moves it, else the first
+pub fn start_line_index(track: &TrackFileFormat, centerline: &[CenterlinePoint]) -> usize {
+    match track.timing.as_ref().and_then(|timing| timing.start_finish) {
+        Some(point) if track.closed_loop => nearest_centerline_point(centerline, point.x, point.y).unwrap_or(0),
+        _ => 0,
+    }
+}
+
+/// Keep sector splits and DRS zones in lap order, as the server wants them,
+/// after a line was added or the start/finish line moved
+fn sort_in_lap_order(timing: &mut TimingDefinition, lap: &Lap) {
+    let distance = |point: &TimingPoint| lap.distance(point).unwrap_or(0.0);
+    timing.sectors.sort_by(|a, b| distance(a).total_cmp(&distance(b)));
+    timing.drs_zones.sort_by(|a, b| distance(&a.start).total_cmp(&distance(&b.start)));
+}
+
+fn remove_marker(timing: &mut TimingDefinition, marker: Marker) {
+    match marker {
+        Marker::StartFinish => timing.start_finish = None,
+        Marker::Sector(i) if i < timing.sectors.len() => {
+            timing.sectors.remove(i);
+        }
+        Marker::DrsZone(i) if i < timing.drs_zones.len() => {
+            timing.drs_zones.remove(i);
+        }
+        Marker::SpeedTrap(i) if i < timing.speed_traps.len() => {
+            timing.speed_traps.remove(i);
+        }
+        _ => {}
+    }
+}
+
+/// Each timing line with the point it is placed at
+fn markers(timing: &TimingDefinition) -> Vec<(Marker, TimingPoint)> {
+    let start_finish = timing.start_finish.map(|point| (Marker::StartFinish, point));
+    let sectors = timing.sectors.iter().enumerate().map(|(i, point)| (Marker::Sector(i), *point));
+    let zones = timing.drs_zones.iter().enumerate().flat_map(|(i, zone)| [(Marker::DrsZone(i), zone.start), (Marker::DrsZone(i), zone.end)]);
+    let traps = timing
+        .speed_traps
+        .iter()
+        .enumerate()
+        .map(|(i, trap)| (Marker::SpeedTrap(i), TimingPoint { x: trap.x, y: trap.y }));
+    start_finish.into_iter().chain(sectors).chain(zones).chain(traps).collect()
+}
+
+/// Left click selects the timing line under the cursor, or places one with
+/// the current tool where the track is clicked, snapped onto the centerline.
+/// A DRS zone takes two clicks: where it opens, then where it closes
+pub fn timing_click_system(
+    mut editor_state: ResMut<EditorState>,
+    mut timing_edit: ResMut<TimingEditState>,
+    camera_state: Res<CameraState>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<EditorCamera>>,
+    mut contexts: EguiContexts,
+) {
+    if !mouse_buttons.just_pressed(MouseButton::Left) || contexts.ctx_mut().wants_pointer_input() {
+        return;
+    }
+    let Some(loaded) = editor_state.loaded_track.as_mut() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_query.get_single() else {
+        return;
+    };
+    let Some(cursor) = window_query.get_single().ok().and_then(|window| window.cursor_position()) else {
+        return;
+    };
+    let track = &mut loaded.track_data;
+    let lap = Lap::new(track);
+
+    // Pick an existing line first
+    let placed = track.timing.as_ref().map(markers).unwrap_or_default();
+    let picked = placed
+        .into_iter()
+        .filter_map(|(marker, point)| {
+            let (index, _) = lap.crossing(&point)?;
+            let centre = &lap.centerline[index];
+            let screen = camera.world_to_viewport(camera_transform, Vec3::new(point.x, point.y, centre.z))?;
+            Some((marker, screen.distance(cursor)))
+        })
+        .filter(|(_, distance)| *distance <= PICK_RADIUS_PX * 2.0)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(marker, _)| marker);
+    if picked.is_some() {
+        timing_edit.selected = picked;
+        return;
+    }
+
+    let Some(hit) = surface_hit(camera, camera_transform, cursor, &lap.centerline, camera_state.focus.z) else {
+        return;
+    };
+    let Some(index) = nearest_centerline_point(&lap.centerline, hit.x, hit.y) else {
+        return;
+    };
+    let centre = &lap.centerline[index];
+    let (lateral, width) = {
+        let lateral = (hit.truncate() - Vec2::new(centre.x, centre.y)).dot(centre.left_normal());
+        (lateral, if lateral >= 0.0 { centre.width_left } else { centre.width_right })
+    };
+    if lateral.abs() > width {
+        return;
+    }
+    let point = TimingPoint { x: centre.x, y: centre.y };
+
+    let timing = track.timing.get_or_insert_with(TimingDefinition::default);
+    match timing_edit.tool {
+        TimingTool::StartFinish if track.closed_loop => {
+            timing.start_finish = Some(point);
+            timing_edit.selected = Some(Marker::StartFinish);
+        }
+        TimingTool::StartFinish => return,
+        // A split on the start/finish line would end a sector before it began
+        TimingTool::Sector if lap.distances[index] > 0.0 => {
+            timing.sectors.push(point);
+            timing_edit.selected = None;
+        }
+        TimingTool::Sector => return,
+        TimingTool::DrsZone => match timing_edit.drs_start.take() {
+            Some(start) if start != point => {
+                timing.drs_zones.push(DrsZoneDefinition { start, end: point });
+                timing_edit.selected = None;
+            }
+            Some(_) => return,
+            None => {
+                timing_edit.drs_start = Some(point);
+                return;
+            }
+        },
+    }
+    let lap = Lap::new(track);
+    if let Some(timing) = track.timing.as_mut() {
+        sort_in_lap_order(timing, &lap);
+    }
+    loaded.dirty = true;
+}
+
+/// Delete removes the selected timing line; Escape deselects it and drops a
+/// half placed DRS zone
+pub fn timing_keyboard_system(
+    mut editor_state: ResMut<EditorState>,
+    mut timing_edit: ResMut<TimingEditState>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut contexts: EguiContexts,
+) {
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    if keyboard.just_pressed(KeyCode::Escape) {
+        timing_edit.selected = None;
+        timing_edit.drs_start = None;
+    }
+    if !keyboard.just_pressed(KeyCode::Delete) {
+        return;
+    }
+    let Some(loaded) = editor_state.loaded_track.as_mut() else {
+        return;
+    };
+    if let (Some(marker), Some(timing)) = (timing_edit.selected.take(), loaded.track_data.timing.as_mut()) {
+        remove_marker(timing, marker);
+        loaded.dirty = true;
+    }
+}
+
+/// Timing lines drawn across the track, with DRS zones along the centerline
+/// between their lines; lines off the track are red
+pub fn timing_gizmo_system(mut gizmos: Gizmos, editor_state: Res<EditorState>, timing_edit: Res<TimingEditState>) {
+    let Some(loaded) = &editor_state.loaded_track else {
+        return;
+    };
+    let track = &loaded.track_data;
+    let lap = Lap::new(track);
+    if lap.centerline.is_empty() {
+        return;
+    }
+    let lift = Vec3::Z * MARKER_LIFT_M;
+    let centre = |index: usize| {
+        let point = &lap.centerline[index];
+        Vec3::new(point.x, point.y, point.z) + lift
+    };
+    let mut line_across = |index: usize, color: Color| {
+        let point = &lap.centerline[index];
+        let normal = point.left_normal().extend(0.0);
+        gizmos.line(centre(index) + normal * point.width_left, centre(index) - normal * point.width_right, color);
+    };
+
+    let selected_color = Color::srgb(1.0, 0.9, 0.1);
+    let off_track = Color::srgb(1.0, 0.15, 0.15);
+    let drs_color = Color::srgb(0.2, 0.9, 0.3);
+
+    // The start/finish line is at the first node unless moved
+    let start_color = if timing_edit.selected == Some(Marker::StartFinish) { selected_color } else { Color::WHITE };
+    line_across(start_line_index(track, &lap.centerline), start_color);
+
+    if let Some((index, _)) = timing_edit.drs_start.and_then(|point| lap.crossing(&point)) {
+        line_across(index, drs_color);
+    }
+
+    let Some(timing) = &track.timing else {
+        return;
+    };
+    for (marker, point) in markers(timing) {
+        let Some((index, on_track)) = lap.crossing(&point) else {
+            continue;
+        };
+        let color = match marker {
+            _ if timing_edit.selected == Some(marker) => selected_color,
+            _ if !on_track => off_track,
+            Marker::StartFinish => Color::WHITE,
+            Marker::Sector(_) => Color::srgb(1.0, 0.6, 0.0),
+            Marker::DrsZone(_) => drs_color,
+            Marker::SpeedTrap(_) => Color::srgb(0.2, 0.7, 1.0),
+        };
+        line_across(index, color);
+    }
+
+    let n = lap.centerline.len();
+    for zone in &timing.drs_zones {
+        let (Some((start, _)), Some((end, _))) = (lap.crossing(&zone.start), lap.crossing(&zone.end)) else {
+            continue;
+        };
+        let length = if end >= start { end - start } else if track.closed_loop { end + n - start } else { 0 };
+        gizmos.linestrip((0..=length).map(|step| centre((start + step) % n)), drs_color);
+    }
+}
+
+/// Tool choice and every timing line with its distance from the start/finish line
+pub fn timing_panel_system(
+    mut contexts: EguiContexts,
+    mut editor_state: ResMut<EditorState>,
+    mut timing_edit: ResMut<TimingEditState>,
+) {
+    let Some(loaded) = editor_state.loaded_track.as_mut() else {
+        return;
+    };
+    let track = &mut loaded.track_data;
+    let lap = Lap::new(track);
+    let closed_loop = track.closed_loop;
+    let mut removed = None;
+
+    egui::Window::new("Timing")
+        .default_pos([10.0, 260.0])
+        .default_size([260.0, 320.0])
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.add_enabled_ui(closed_loop, |ui| {
+                    ui.selectable_value(&mut timing_edit.tool, TimingTool::StartFinish, "Start/Finish");
+                });
+                ui.selectable_value(&mut timing_edit.tool, TimingTool::Sector, "Sector");
+                ui.selectable_value(&mut timing_edit.tool, TimingTool::DrsZone, "DRS Zone");
+            });
+            ui.label(match (timing_edit.tool, timing_edit.drs_start) {
+                (TimingTool::StartFinish, _) if !closed_loop => "Open tracks start at the first node.",
+                (TimingTool::StartFinish, _) => "Click on the track to move the start/finish line.",
+                (TimingTool::Sector, _) => "Click on the track to split a sector there.",
+                (TimingTool::DrsZone, None) => "Click where the zone opens.",
+                (TimingTool::DrsZone, Some(_)) => "Click where the zone closes (Esc to cancel).",
+            });
+            ui.add_space(5.0);
+            ui.label(format!("Lap length: {:.0} m", lap.length));
+            ui.add_space(5.0);
+
+            let readout = |point: &TimingPoint| match lap.crossing(point) {
+                Some((index, true)) => format!("{:.0} m", lap.distances[index]),
+                Some((index, false)) => format!("{:.0} m (off the track)", lap.distances[index]),
+                None => "-".to_string(),
+            };
+            let timing = track.timing.clone().unwrap_or_default();
+            let mut rows: Vec<(Marker, String)> = Vec::new();
+            rows.push((
+                Marker::StartFinish,
+                format!("Start/finish{}", if timing.start_finish.is_some() { "" } else { " (first node)" }),
+            ));
+            for (i, point) in timing.sectors.iter().enumerate() {
+                rows.push((Marker::Sector(i), format!("Sector {} | {}: {}", i + 1, i + 2, readout(point))));
+            }
+            for (i, zone) in timing.drs_zones.iter().enumerate() {
+                let length = match (lap.distance(&zone.start), lap.distance(&zone.end)) {
+                    (Some(start), Some(end)) => (end - start).rem_euclid(lap.length.max(1.0)),
+                    _ => 0.0,
+                };
+                rows.push((
+                    Marker::DrsZone(i),
+                    format!("DRS {}: {} to {} ({:.0} m)", i + 1, readout(&zone.start), readout(&zone.end), length),
+                ));
+            }
+            for (i, trap) in timing.speed_traps.iter().enumerate() {
+                let name = trap.name.clone().unwrap_or_else(|| format!("Speed trap {}", i + 1));
+                rows.push((Marker::SpeedTrap(i), format!("{}: {}", name, readout(&TimingPoint { x: trap.x, y: trap.y }))));
+            }
+
+            egui::ScrollArea::vertical().max_height(220.0).show(ui, |ui| {
+                for (marker, label) in rows {
+                    ui.horizontal(|ui| {
+                        if ui.selectable_label(timing_edit.selected == Some(marker), label).clicked() {
+                            timing_edit.selected = Some(marker);
+                        }
+                        let removable = marker != Marker::StartFinish || timing.start_finish.is_some();
+                        if removable && ui.small_button("x").clicked() {
+                            removed = Some(marker);
+                        }
+                    });
+                }
+            });
+        });
+
+    if let (Some(marker), Some(timing)) = (removed, track.timing.as_mut()) {
+        remove_marker(timing, marker);
+        if marker == Marker::StartFinish {
+            let lap = Lap::new(track);
+            if let Some(timing) = track.timing.as_mut() {
+                sort_in_lap_order(timing, &lap);
+            }
+        }
+        timing_edit.selected = None;
+        loaded.dirty = true;
+    }
+}
@@ -18,6 +18,75 @@ pub struct TrackFileFormat {
     pub raceline: Vec<RacelinePoint>,
     #[serde(default)]
     pub metadata: Option<TrackMetadata>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pit_lane: Option<PitLaneDefinition>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timing: Option<TimingDefinition>,
+    /// Fields the editor doesn't edit (walls, surfaces...), kept so saving
+    /// writes them back unchanged
+    #[serde(flatten)]
+    pub extra: serde_yaml::Mapping,
+}
+
+impl TrackFileFormat {
+    /// Insert a node halfway between `index` and the node after it (or the
+    /// one before it at the end of an open track); returns the new node's index
+    pub fn insert_node_after(&mut self, index: usize) -> usize {
+        let count = self.nodes.len();
+        let (a, b) = if index + 1 < count {
+            (index, index + 1)
+        } else if self.closed_loop || count < 2 {
+            (index, 0)
+        } else {
+            (index - 1, index)
+        };
+        let mut node = self.nodes[a].clone();
+        node.x = (self.nodes[a].x + self.nodes[b].x) / 2.0;
+        node.y = (self.nodes[a].y + self.nodes[b].y) / 2.0;
+        node.z = (self.nodes[a].z + self.nodes[b].z) / 2.0;
+
+        let inserted = a + 1;
+        self.nodes.insert(inserted, node);
+        for node_index in self.node_references() {
+            if *node_index >= inserted {
+                *node_index += 1;
+            }
+        }
+        inserted
+    }
+
+    /// Everything that refers to a node by its index
+    fn node_references(&mut self) -> impl Iterator<Item = &mut usize> {
+        self.checkpoints
+            .iter_mut()
+            .flat_map(|checkpoint| [&mut checkpoint.index_start, &mut checkpoint.index_end])
+            .chain(self.pit_lane.iter_mut().flat_map(|pit_lane| [&mut pit_lane.entry_node, &mut pit_lane.exit_node]))
+    }
+
+    /// Forget the target speeds of an edited raceline, so the server works
+    /// them out again for the new line
+    pub fn clear_raceline_speeds(&mut self) {
+        for point in &mut self.raceline {
+            point.extra.remove("target_speed_mps");
+        }
+    }
+
+    /// Remove a node, keeping at least the two a track needs; checkpoints and
+    /// pit lane ends on the removed node move to its successor
+    pub fn remove_node(&mut self, index: usize) -> bool {
+        if self.nodes.len() <= 2 || index >= self.nodes.len() {
+            return false;
+        }
+        self.nodes.remove(index);
+        let last = self.nodes.len() - 1;
+        for node_index in self.node_references() {
+            if *node_index > index {
+                *node_index -= 1;
+            }
+            *node_index = (*node_index).min(last);
+        }
+        true
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,10 +103,28 @@ pub struct TrackNode {
     pub width_right: Option<f32>,
     #[serde(default)]
     pub banking: Option<f32>,
+    /// Crown (rad), falling away either side of the centerline
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub camber: Option<f32>,
     #[serde(default)]
     pub friction: Option<f32>,
     #[serde(default)]
     pub surface_type: Option<String>,
+    #[serde(flatten)]
+    pub extra: serde_yaml::Mapping,
+}
+
+impl TrackNode {
+    /// Width left and right of the centerline, from the explicit sides, the
+    /// symmetric width or else `default_width`
+    pub fn half_widths(&self, default_width: f32) -> (f32, f32) {
+        if let (Some(left), Some(right)) = (self.width_left, self.width_right) {
+            (left, right)
+        } else {
+            let width = self.width.unwrap_or(default_width);
+            (width / 2.0, width / 2.0)
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -55,11 +142,77 @@ pub struct SpawnPoint {
     pub offset_y: f32,
 }
 
+/// Pit lane running beside the track from `entry_node` to `exit_node`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PitLaneDefinition {
+    pub entry_node: usize,
+    pub exit_node: usize,
+    pub nodes: Vec<TrackNode>,
+    #[serde(default)]
+    pub default_width: f32,
+    pub speed_limit_mps: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed_limit_start_m: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed_limit_end_m: Option<f32>,
+    /// In grid order: the first belongs to grid slot 1
+    #[serde(default)]
+    pub boxes: Vec<PitBox>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PitBox {
+    pub x: f32,
+    pub y: f32,
+    #[serde(default)]
+    pub z: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub yaw: Option<f32>,
+}
+
+/// Timing lines, each placed where it crosses the centerline
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TimingDefinition {
+    /// Where laps start and end (default: the first node); closed loops only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_finish: Option<TimingPoint>,
+    /// Lines between sectors, in lap order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sectors: Vec<TimingPoint>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub speed_traps: Vec<SpeedTrapDefinition>,
+    /// DRS or boost zones, in lap order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub drs_zones: Vec<DrsZoneDefinition>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TimingPoint {
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeedTrapDefinition {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct DrsZoneDefinition {
+    pub start: TimingPoint,
+    pub end: TimingPoint,
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct RacelinePoint {
     pub x: f32,
     pub y: f32,
     pub z: f32,
+    #[serde(flatten)]
+    pub extra: serde_yaml::Mapping,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -84,6 +237,8 @@ pub struct TrackMetadata {
     pub object_density: Option<f32>,
     #[serde(default)]
     pub decal_profile: Option<String>,
+    #[serde(flatten)]
+    pub extra: serde_yaml::Mapping,
 }
 
 // Terrain/Procedural data structures
@@ -107,6 +262,21 @@ impl TerrainHeightmap {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoiseLayer {
+    pub frequency: f32,
+    pub amplitude: f32,
+    pub ridged: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SkyHint {
+    pub top_color: [f32; 3],
+    pub horizon_color: [f32; 3],
+    pub sun_elevation_deg: f32,
+    pub fog_density: f32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EnvironmentPreset {
     pub base_noise_freq: f32,
@@ -115,6 +285,54 @@ pub struct EnvironmentPreset {
     pub object_density: f32,
     pub allowed_objects: Vec<String>,
     pub ground_color: [f32; 3],
+    #[serde(default)]
+    pub noise_layers: Vec<NoiseLayer>,
+    #[serde(default)]
+    pub peak_color: Option<[f32; 3]>,
+    #[serde(default)]
+    pub sky: SkyHint,
+    #[serde(default)]
+    pub rainfall: f32,
+    #[serde(default)]
+    pub sand_below: f32,
+    #[serde(default)]
+    pub lighting: Vec<Lighting>,
+}
+
+/// Sun, sky and ambient light at one time of day (0 morning to 4 night)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lighting {
+    pub time_of_day: u8,
+    pub sun_elevation_deg: f32,
+    pub sun_azimuth_deg: f32,
+    pub sun_color: [f32; 3],
+    pub sun_energy: f32,
+    pub ambient_color: [f32; 3],
+    pub ambient_energy: f32,
+    pub sky_top_color: [f32; 3],
+    pub sky_horizon_color: [f32; 3],
+}
+
+/// Pit lane or start gantry lamp
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LightSource {
+    pub kind: String,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub color: [f32; 3],
+    pub range_m: f32,
+    pub energy: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TracksideObject {
+    pub kind: String,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub heading_rad: f32,
+    pub scale: f32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -126,4 +344,68 @@ pub struct ProceduralWorldData {
     pub object_density: f32,
     pub decal_profile: String,
     pub preset: EnvironmentPreset,
+    #[serde(default)]
+    pub objects: Vec<TracksideObject>,
+    #[serde(default)]
+    pub water: WaterFeatures,
+    #[serde(default)]
+    pub splatmap: Option<Splatmap>,
+    #[serde(default)]
+    pub lod: Option<TerrainLod>,
+    #[serde(default)]
+    pub lighting: Vec<Lighting>,
+    #[serde(default)]
+    pub lights: Vec<LightSource>,
+}
+
+/// Coarser copies of the heightmap, each half the resolution of the one before
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerrainLod {
+    pub tile_cells: usize,
+    pub levels: Vec<TerrainHeightmap>,
+}
+
+/// Grass, dirt, rock and sand weights per heightmap cell, summing to 255
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Splatmap {
+    pub width: usize,
+    pub height: usize,
+    #[serde(with = "serde_bytes")]
+    pub weights: Vec<u8>,
+}
+
+impl Splatmap {
+    pub fn weights_at(&self, x: usize, y: usize) -> [f32; 4] {
+        if x >= self.width || y >= self.height {
+            return [1.0, 0.0, 0.0, 0.0];
+        }
+        let i = (y * self.width + x) * 4;
+        std::array::from_fn(|layer| self.weights[i + layer] as f32 / 255.0)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WaterFeatures {
+    pub lakes: Vec<Lake>,
+    pub rivers: Vec<River>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lake {
+    pub level: f32,
+    /// [min_x, min_y, max_x, max_y]
+    pub rects: Vec<[f32; 4]>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct River {
+    pub points: Vec<RiverPoint>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RiverPoint {
+    pub x: f32,
+    pub y: f32,
+    pub level: f32,
+    pub width: f32,
 }
@@ -0,0 +1,64 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::track_data::{ProceduralWorldData, TrackFileFormat};
+
+/// Terrain cache that sits next to a track file, as the server expects it
+pub fn terrain_path(yaml_path: &Path) -> PathBuf {
+    let stem = yaml_path.file_stem().unwrap_or_default().to_string_lossy();
+    yaml_path.with_file_name(format!("{}.terrain.msgpack", stem))
+}
+
+/// `Monza.yaml` -> `Monza.yaml.bak`, which the track scanners skip
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+fn backup(path: &Path) -> Result<(), String> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let backup = backup_path(path);
+    fs::copy(path, &backup)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to back up {} to {}: {}", path.display(), backup.display(), e))
+}
+
+/// Write `path` through a temporary file so a failed save never leaves it half written
+fn write_replacing(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp = path.with_file_name(temp_name);
+    fs::write(&temp, contents).map_err(|e| format!("Failed to write {}: {}", temp.display(), e))?;
+    fs::rename(&temp, path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+}
+
+/// Write a track as YAML, plus its terrain cache when given one. With
+/// `backup_existing` the files about to be replaced are first copied to `.bak`
+pub fn save_track(
+    yaml_path: &Path,
+    track: &TrackFileFormat,
+    terrain: Option<&ProceduralWorldData>,
+    backup_existing: bool,
+) -> Result<(), String> {
+    let yaml = serde_yaml::to_string(track).map_err(|e| format!("Failed to serialize track: {}", e))?;
+    let terrain_bytes = terrain
+        .map(rmp_serde::to_vec)
+        .transpose()
+        .map_err(|e| format!("Failed to serialize terrain: {}", e))?;
+
+    if backup_existing {
+        backup(yaml_path)?;
+        if terrain_bytes.is_some() {
+            backup(&terrain_path(yaml_path))?;
+        }
+    }
+
+    write_replacing(yaml_path, yaml.as_bytes())?;
+    if let Some(bytes) = terrain_bytes {
+        write_replacing(&terrain_path(yaml_path), &bytes)?;
+    }
+    Ok(())
+}
@@ -1,6 +1,13 @@
 use bevy::prelude::*;
 use bevy::render::mesh::{Indices, PrimitiveTopology};
 use crate::track_data::TrackNode;
+use std::f32::consts::PI;
+
+/// Width of nodes that give none
+pub const DEFAULT_WIDTH_M: f32 = 12.0;
+
+/// Centerline points between two nodes, as many as the server interpolates
+pub const POINTS_PER_SEGMENT: usize = 20;
 
 /// Generate a Bevy mesh from track nodes using Catmull-Rom spline interpolation
 pub fn generate_track_mesh(nodes: &[TrackNode], closed_loop: bool) -> Mesh {
@@ -8,66 +15,7 @@ pub fn generate_track_mesh(nodes: &[TrackNode], closed_loop: bool) -> Mesh {
         return Mesh::new(PrimitiveTopology::TriangleList, default());
     }
 
-    let default_width = 12.0;
-    let points_per_segment = 20;
-
-    // Interpolate the track centerline
-    let mut track_points: Vec<InterpolatedPoint> = Vec::new();
-
-    for i in 0..nodes.len() {
-        let p0_idx = if i == 0 && closed_loop {
-            nodes.len() - 1
-        } else if i == 0 {
-            0
-        } else {
-            i - 1
-        };
-
-        let p1_idx = i;
-        let p2_idx = (i + 1) % nodes.len();
-        let p3_idx = if closed_loop {
-            (i + 2) % nodes.len()
-        } else {
-            (i + 2).min(nodes.len() - 1)
-        };
-
-        if i == nodes.len() - 1 && !closed_loop {
-            break;
-        }
-
-        let p0 = &nodes[p0_idx];
-        let p1 = &nodes[p1_idx];
-        let p2 = &nodes[p2_idx];
-        let p3 = &nodes[p3_idx];
-
-        for j in 0..points_per_segment {
-            let t = j as f32 / points_per_segment as f32;
-            let (x, y, z) = catmull_rom_point(p0, p1, p2, p3, t);
-
-            let (width_left, width_right) = if let (Some(wl), Some(wr)) = (p1.width_left, p1.width_right) {
-                (wl, wr)
-            } else if let Some(w) = p1.width {
-                (w / 2.0, w / 2.0)
-            } else {
-                (default_width / 2.0, default_width / 2.0)
-            };
-
-            let banking = p1.banking.unwrap_or(0.0);
-
-            track_points.push(InterpolatedPoint {
-                x,
-                y,
-                z,
-                width_left,
-                width_right,
-                banking,
-                heading: 0.0, // Will be computed
-            });
-        }
-    }
-
-    // Compute headings
-    compute_headings(&mut track_points, closed_loop);
+    let track_points = interpolate_centerline(nodes, closed_loop, DEFAULT_WIDTH_M);
 
     // Generate mesh vertices
     let mut positions: Vec<[f32; 3]> = Vec::new();
@@ -85,34 +33,44 @@ pub fn generate_track_mesh(nodes: &[TrackNode], closed_loop: bool) -> Mesh {
         let perpendicular_x = -sin_heading;
         let perpendicular_y = cos_heading;
 
-        // Banking offset
-        let banking_offset_z = if point.banking.abs() > 0.001 {
-            point.width_left * point.banking.sin()
+        // Which way the track turns here decides which edge banking lowers
+        let count = track_points.len();
+        let (before, after) = if closed_loop {
+            ((i + count - 1) % count, (i + 1) % count)
         } else {
-            0.0
+            (i.saturating_sub(1), (i + 1).min(count - 1))
         };
+        let (left_dz, right_dz) = cross_section_heights(
+            point.width_left,
+            point.width_right,
+            point.banking,
+            point.camber,
+            track_points[after].heading - track_points[before].heading,
+        );
 
         // Left vertex
         let left_x = point.x + perpendicular_x * point.width_left;
         let left_y = point.y + perpendicular_y * point.width_left;
-        let left_z = point.z + banking_offset_z;
+        let left_z = point.z + left_dz;
 
         // Right vertex
         let right_x = point.x - perpendicular_x * point.width_right;
         let right_y = point.y - perpendicular_y * point.width_right;
-        let right_z = point.z - banking_offset_z;
+        let right_z = point.z + right_dz;
 
+        // The centerline vertex carries the crown of a cambered surface
         positions.push([left_x, left_y, left_z]);
+        positions.push([point.x, point.y, point.z]);
         positions.push([right_x, right_y, right_z]);
 
         // UVs
         let u = i as f32 / total_length;
         uvs.push([u, 0.0]);
+        uvs.push([u, 0.5]);
         uvs.push([u, 1.0]);
 
         // Normals (will be computed properly later)
-        normals.push([0.0, 0.0, 1.0]);
-        normals.push([0.0, 0.0, 1.0]);
+        normals.extend([[0.0, 0.0, 1.0]; 3]);
     }
 
     // Generate indices
@@ -125,20 +83,23 @@ pub fn generate_track_mesh(nodes: &[TrackNode], closed_loop: bool) -> Mesh {
     for i in 0..num_segments {
         let next_i = (i + 1) % track_points.len();
 
-        let v0 = (i * 2) as u32;
-        let v1 = (i * 2 + 1) as u32;
-        let v2 = (next_i * 2) as u32;
-        let v3 = (next_i * 2 + 1) as u32;
-
-        // First triangle
-        indices.push(v0);
-        indices.push(v1);
-        indices.push(v2);
-
-        // Second triangle
-        indices.push(v2);
-        indices.push(v1);
-        indices.push(v3);
+        // Left half, then right half
+        for side in 0..2 {
+            let v0 = (i * 3 + side) as u32;
+            let v1 = (i * 3 + side + 1) as u32;
+            let v2 = (next_i * 3 + side) as u32;
+            let v3 = (next_i * 3 + side + 1) as u32;
+
+            // First triangle
+            indices.push(v0);
+            indices.push(v1);
+            indices.push(v2);
+
+            // Second triangle
+            indices.push(v2);
+            indices.push(v1);
+            indices.push(v3);
+        }
     }
 
     // Compute smooth normals
@@ -153,14 +114,113 @@ pub fn generate_track_mesh(nodes: &[TrackNode], closed_loop: bool) -> Mesh {
     mesh
 }
 
-struct InterpolatedPoint {
-    x: f32,
-    y: f32,
-    z: f32,
-    width_left: f32,
-    width_right: f32,
-    banking: f32,
-    heading: f32,
+pub struct CenterlinePoint {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub width_left: f32,
+    pub width_right: f32,
+    pub banking: f32,
+    pub camber: f32,
+    pub heading: f32,
+}
+
+impl CenterlinePoint {
+    /// Unit vector pointing to the left of the track direction
+    pub fn left_normal(&self) -> Vec2 {
+        let (sin, cos) = self.heading.sin_cos();
+        Vec2::new(-sin, cos)
+    }
+}
+
+/// Interpolate the centerline through the nodes the way the server does,
+/// each point taking the width, banking and camber of the node it follows
+pub fn interpolate_centerline(nodes: &[TrackNode], closed_loop: bool, default_width: f32) -> Vec<CenterlinePoint> {
+    let mut track_points: Vec<CenterlinePoint> = Vec::new();
+    if nodes.len() < 2 {
+        return track_points;
+    }
+
+    for i in 0..nodes.len() {
+        let p0_idx = if i == 0 && closed_loop {
+            nodes.len() - 1
+        } else if i == 0 {
+            0
+        } else {
+            i - 1
+        };
+
+        let p1_idx = i;
+        let p2_idx = (i + 1) % nodes.len();
+        let p3_idx = if closed_loop {
+            (i + 2) % nodes.len()
+        } else {
+            (i + 2).min(nodes.len() - 1)
+        };
+
+        if i == nodes.len() - 1 && !closed_loop {
+            break;
+        }
+
+        let p0 = &nodes[p0_idx];
+        let p1 = &nodes[p1_idx];
+        let p2 = &nodes[p2_idx];
+        let p3 = &nodes[p3_idx];
+
+        for j in 0..POINTS_PER_SEGMENT {
+            let t = j as f32 / POINTS_PER_SEGMENT as f32;
+            let (x, y, z) = catmull_rom_point(p0, p1, p2, p3, t);
+
+            let (width_left, width_right) = p1.half_widths(default_width);
+
+            let banking = p1.banking.unwrap_or(0.0);
+            let camber = p1.camber.unwrap_or(0.0);
+
+            track_points.push(CenterlinePoint {
+                x,
+                y,
+                z,
+                width_left,
+                width_right,
+                banking,
+                camber,
+                heading: 0.0, // Will be computed
+            });
+        }
+    }
+
+    // Compute headings
+    compute_headings(&mut track_points, closed_loop);
+
+    track_points
+}
+
+/// Index of the centerline point nearest to `(x, y)`
+pub fn nearest_centerline_point(centerline: &[CenterlinePoint], x: f32, y: f32) -> Option<usize> {
+    centerline
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| (a.x - x).hypot(a.y - y).total_cmp(&(b.x - x).hypot(b.y - y)))
+        .map(|(i, _)| i)
+}
+
+/// Height of the left and right edges above the centerline, the way the
+/// server's physics tilts the surface: banking lowers the inside of the turn
+/// (`turn` is the change of heading, positive to the left) and camber falls
+/// away either side of the crown
+pub fn cross_section_heights(width_left: f32, width_right: f32, banking: f32, camber: f32, turn: f32) -> (f32, f32) {
+    let turn = (turn + PI).rem_euclid(2.0 * PI) - PI;
+    let left_rise = if turn > 0.0 {
+        -banking
+    } else if turn < 0.0 {
+        banking
+    } else {
+        0.0
+    };
+    (
+        width_left * (left_rise.sin() - camber.sin()),
+        width_right * (-left_rise.sin() - camber.sin()),
+    )
 }
 
 fn catmull_rom_point(
@@ -194,7 +254,7 @@ fn catmull_rom_point(
     (x, y, z)
 }
 
-fn compute_headings(points: &mut [InterpolatedPoint], closed_loop: bool) {
+fn compute_headings(points: &mut [CenterlinePoint], closed_loop: bool) {
     for i in 0..points.len() {
         let next_idx = if i == points.len() - 1 {
             if closed_loop { 0 } else { i }